@@ -340,6 +340,22 @@ pub fn path_prebaked_results(name: &MapName, scenario_name: &str) -> String {
     ))
 }
 
+/// KPIs from simulating a scenario many times with different RNG seeds, without map edits
+pub fn path_prebaked_multirun(name: &MapName, scenario_name: &str) -> String {
+    path(format!(
+        "system/{}/{}/prebaked_results/{}/multirun/{}.json",
+        name.city.country, name.city.city, name.map, scenario_name
+    ))
+}
+
+/// Speeds and volumes per road and hour of the day, observed by counters or telematics
+pub fn path_observed_traffic(name: &MapName) -> String {
+    path(format!(
+        "system/{}/{}/observed_traffic/{}.bin",
+        name.city.country, name.city.city, name.map
+    ))
+}
+
 pub fn path_scenario(name: &MapName, scenario_name: &str) -> String {
     // TODO Getting complicated. Sometimes we're trying to load, so we should look for .bin, then
     // .json. But when we're writing a custom scenario, we actually want to write a .bin.
@@ -404,6 +420,60 @@ pub fn path_all_edits(name: &MapName) -> String {
     ))
 }
 
+pub fn path_lesson(name: &MapName, lesson_name: &str) -> String {
+    path(format!(
+        "player/lessons/{}/{}/{}/{}.json",
+        name.city.country, name.city.city, name.map, lesson_name
+    ))
+}
+pub fn path_all_lessons(name: &MapName) -> String {
+    path(format!(
+        "player/lessons/{}/{}/{}",
+        name.city.country, name.city.city, name.map
+    ))
+}
+
+pub fn path_scenario_modifier_preset(name: &MapName, preset_name: &str) -> String {
+    path(format!(
+        "player/scenario_modifiers/{}/{}/{}/{}.json",
+        name.city.country, name.city.city, name.map, preset_name
+    ))
+}
+pub fn path_all_scenario_modifier_presets(name: &MapName) -> String {
+    path(format!(
+        "player/scenario_modifiers/{}/{}/{}",
+        name.city.country, name.city.city, name.map
+    ))
+}
+
+/// Sessions can be on any map, so they're not grouped by map
+/// Junction templates aren't specific to one map, so they can be shared
+pub fn path_junction_template(template_name: &str) -> String {
+    path(format!("player/junction_templates/{}.json", template_name))
+}
+pub fn path_all_junction_templates() -> String {
+    path("player/junction_templates")
+}
+
+/// Site plans of proposed developments, as GeoJSON
+pub fn path_all_developments(name: &MapName) -> String {
+    path(format!(
+        "player/developments/{}/{}/{}",
+        name.city.country, name.city.city, name.map
+    ))
+}
+
+pub fn path_session(session_name: &str) -> String {
+    path(format!("player/sessions/{}.json", session_name))
+}
+pub fn path_all_sessions() -> String {
+    path("player/sessions")
+}
+
+pub fn path_bug_report(report_name: &str) -> String {
+    path(format!("player/bug_reports/{}.zip", report_name))
+}
+
 pub fn path_ltn_proposals(name: &MapName, proposal_name: &str) -> String {
     path(format!(
         "player/ltn_proposals/{}/{}/{}/{}.json.gz",
@@ -430,6 +500,14 @@ pub fn path_all_saves(name: &MapName, edits_name: &str, run_name: &str) -> Strin
     ))
 }
 
+/// Where vehicles were parked at the end of a run of a scenario, to start the next day from
+pub fn path_warm_start(name: &MapName, scenario_name: &str) -> String {
+    path(format!(
+        "player/warm_starts/{}/{}/{}/{}.bin",
+        name.city.country, name.city.city, name.map, scenario_name
+    ))
+}
+
 pub fn path_trips(name: &MapName) -> String {
     path(format!(
         "player/routes/{}/{}/{}.json",
@@ -1,7 +1,14 @@
-use std::sync::Once;
+use std::collections::VecDeque;
+use std::sync::{Mutex, Once};
 
 static SETUP: Once = Once::new();
 
+// How many of the most recent log lines to remember for bug reports
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_RECENT_LOGS: usize = 1000;
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
 /// ## On native: uses env_log
 ///
 /// You can adjust the log level without recompiling with the RUST_LOG env variable.
@@ -51,7 +58,50 @@ pub fn setup() {
         #[cfg(not(target_arch = "wasm32"))]
         {
             use env_logger::{Builder, Env};
-            Builder::from_env(Env::default().default_filter_or("info")).init();
+            let inner = Builder::from_env(Env::default().default_filter_or("info")).build();
+            let max_level = inner.filter();
+            if log::set_boxed_logger(Box::new(RecordingLogger { inner })).is_ok() {
+                log::set_max_level(max_level);
+            }
         }
     });
 }
+
+/// The most recent log lines, oldest first. Only recorded on native.
+pub fn recent_logs() -> Vec<String> {
+    RECENT_LOGS.lock().unwrap().iter().cloned().collect()
+}
+
+/// Passes everything along, but also remembers recent lines.
+#[cfg(not(target_arch = "wasm32"))]
+struct RecordingLogger {
+    inner: env_logger::Logger,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl log::Log for RecordingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        self.inner.log(record);
+        let mut recent = RECENT_LOGS.lock().unwrap();
+        if recent.len() == MAX_RECENT_LOGS {
+            recent.pop_front();
+        }
+        recent.push_back(format!(
+            "[{} {}] {}",
+            record.level(),
+            record.target(),
+            record.args()
+        ));
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
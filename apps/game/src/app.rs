@@ -15,17 +15,20 @@ use map_gui::render::{DrawMap, DrawOptions};
 use map_gui::tools::CameraState;
 use map_model::AreaType;
 use map_model::{BufferType, IntersectionID, LaneType, Map, Traversable};
-use sim::{AgentID, Analytics, Sim, SimCallback, SimFlags, VehicleType};
+use sim::{AgentID, Analytics, MultiRunResults, Sim, SimCallback, SimFlags, VehicleType};
 use synthpop::Scenario;
 use widgetry::mapspace::ToggleZoomed;
-use widgetry::{Cached, Canvas, EventCtx, GfxCtx, Prerender, SharedAppState, State};
+use widgetry::{
+    Cached, Canvas, Drawable, EventCtx, FrameStats, GeomBatch, GfxCtx, Prerender, SharedAppState,
+    State,
+};
 
 use crate::challenges::HighScore;
-use crate::common::Warping;
+use crate::common::{Jobs, PerfTracker, Warping};
 use crate::edit::apply_map_edits;
 use crate::layer::Layer;
 use crate::render::{unzoomed_agent_radius, AgentCache, GameRenderable};
-use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::dashboards::{DashTab, Tournament};
 use crate::sandbox::{GameplayMode, TutorialState};
 
 // Convenient typedef
@@ -232,11 +235,45 @@ impl App {
             }
         }
 
+        self.draw_edited_outlines(g);
+
         if let Some(i) = sample_intersection {
             g.set_screencap_naming_hint(i);
         }
     }
 
+    /// A subtle outline around every road, intersection, and building changed by the current
+    /// proposal
+    fn draw_edited_outlines(&self, g: &mut GfxCtx) {
+        let map = &self.primary.map;
+        let edits = map.get_edits();
+        if edits.commands.is_empty() {
+            return;
+        }
+        let color = self.cs.edits_layer.alpha(0.5);
+        let thickness = Distance::meters(1.0);
+        let mut cache = self.primary.edited_outlines.borrow_mut();
+        cache.update(Some(map.get_edits_change_key()), |_| {
+            let mut batch = GeomBatch::new();
+            for r in edits.original_roads.keys() {
+                batch.push(
+                    color,
+                    map.get_r(*r).get_thick_polygon().to_outline(thickness),
+                );
+            }
+            for i in edits.original_intersections.keys() {
+                batch.push(color, map.get_i(*i).polygon.to_outline(thickness));
+            }
+            for b in &edits.changed_bike_parking {
+                batch.push(color, map.get_b(*b).polygon.to_outline(thickness));
+            }
+            g.upload(batch)
+        });
+        if let Some(draw) = cache.value() {
+            g.redraw(draw);
+        }
+    }
+
     /// Assumes some defaults.
     pub fn recalculate_current_selection(&mut self, ctx: &EventCtx) {
         self.primary.current_selection =
@@ -639,6 +676,8 @@ pub struct PerMap {
     pub draw_map: DrawMap,
     pub sim: Sim,
     pub agents: RefCell<AgentCache>,
+    /// Keyed by the map's edits change key
+    pub edited_outlines: RefCell<Cached<usize, Drawable>>,
 
     pub current_selection: Option<ID>,
     pub current_flags: Flags,
@@ -666,6 +705,12 @@ pub struct PerMap {
     /// Storing this may cost some memory, but otherwise resetting to midnight would require
     /// loading it again from a file. This is particularly painful on the web!
     pub scenario: Option<Scenario>,
+    /// Results from simulating the scenario many times with different RNG seeds, along with the
+    /// map's edits change key when they were calculated. They don't match the current edits if
+    /// the key differs.
+    pub multirun: Option<(usize, MultiRunResults)>,
+    /// Saved proposals being simulated and compared in the background
+    pub tournament: Option<Tournament>,
 
     /// Is this the original "secondary" state, loaded via --diff?
     pub is_secondary: bool,
@@ -696,6 +741,7 @@ impl PerMap {
             draw_map,
             sim,
             agents: RefCell::new(AgentCache::new()),
+            edited_outlines: RefCell::new(Cached::new()),
             current_selection: None,
             current_flags: flags,
             last_warped_from: None,
@@ -707,6 +753,8 @@ impl PerMap {
             suspended_sim: None,
             prebaked: None,
             scenario: None,
+            multirun: None,
+            tournament: None,
             is_secondary: false,
         }
     }
@@ -796,6 +844,15 @@ pub struct SessionState {
     pub last_gmns_timing_csv: Option<(String, Vec<u8>)>,
     pub dash_tab: DashTab,
     pub buffer_lane_type: LaneType,
+    /// The last pipeline of scenario modifiers applied, including disabled steps, per scenario
+    pub scenario_pipeline: Option<(
+        String,
+        Vec<crate::sandbox::gameplay::play_scenario::PipelineStep>,
+    )>,
+
+    /// Frame and simulation timings for developers
+    pub perf: PerfTracker,
+    pub jobs: Jobs,
 
     // Specific to the ungap tool
     pub elevation_contours: Cached<MapName, (FindClosest<Distance>, ToggleZoomed)>,
@@ -820,6 +877,9 @@ impl SessionState {
             last_gmns_timing_csv: None,
             dash_tab: DashTab::TripTable,
             buffer_lane_type: LaneType::Buffer(BufferType::Stripes),
+            scenario_pipeline: None,
+            perf: PerfTracker::new(),
+            jobs: Jobs::new(),
 
             elevation_contours: Cached::new(),
             routing_preferences: crate::ungap::RoutingPreferences::default(),
@@ -874,12 +934,21 @@ impl SimCallback for FindDelayedIntersections {
 impl SharedAppState for App {
     fn before_event(&mut self) {
         self.per_obj.reset();
+        Jobs::poll(self);
     }
 
     fn draw_default(&self, g: &mut GfxCtx) {
         self.draw(g, DrawOptions::new(), &ShowEverything::new());
     }
 
+    fn draw_overlay(&self, g: &mut GfxCtx) {
+        self.session.perf.draw_hud(g);
+    }
+
+    fn frame_finished(&mut self, frame: FrameStats) {
+        self.session.perf.frame_finished(frame);
+    }
+
     fn dump_before_abort(&self, canvas: &Canvas) {
         println!();
         println!(
@@ -0,0 +1,349 @@
+//! Optional online leaderboards for challenges. Nothing is uploaded unless the player chooses to
+//! submit a score, and scores are anonymous unless they enter a name. Along with the score, the
+//! proposal is uploaded, and its checksum lets anybody verify the score by replaying it.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstutil::Timer;
+use geom::{Duration, Polygon};
+use map_gui::tools::grey_out_map;
+use widgetry::tools::{FutureLoader, PopupMsg};
+use widgetry::{
+    Color, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Key, Line, Panel, ScreenDims, SimpleState,
+    State, Text, TextBox, TextExt, TextSpan, Toggle, Widget,
+};
+
+use super::{Challenge, HighScore};
+use crate::app::{App, Transition};
+use crate::common::share::PROPOSAL_HOST_URL;
+
+/// Only show this many of the best scores
+const MAX_ENTRIES: usize = 20;
+
+#[derive(Serialize)]
+struct Submission {
+    challenge: String,
+    goal: String,
+    score_seconds: f64,
+    map: String,
+    edits_checksum: String,
+    name: Option<String>,
+    version: String,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    name: Option<String>,
+    score_seconds: f64,
+    edits_checksum: String,
+}
+
+/// Remembered between sessions, so players don't have to retype their name, and can pick out
+/// their own scores.
+#[derive(Serialize, Deserialize, Debug)]
+struct LeaderboardSettings {
+    name: Option<String>,
+    /// The challenge alias and proposal checksum of every submitted score
+    submitted: BTreeSet<(String, String)>,
+}
+
+impl LeaderboardSettings {
+    fn load() -> LeaderboardSettings {
+        abstio::maybe_read_json::<LeaderboardSettings>(
+            abstio::path_player("leaderboard.json"),
+            &mut Timer::throwaway(),
+        )
+        .unwrap_or_else(|_| LeaderboardSettings {
+            name: None,
+            submitted: BTreeSet::new(),
+        })
+    }
+
+    fn save(&self) {
+        abstio::write_json(abstio::path_player("leaderboard.json"), self);
+    }
+}
+
+pub struct SubmitScore {
+    challenge: Challenge,
+    score: HighScore,
+}
+
+impl SubmitScore {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        challenge: Challenge,
+        score: HighScore,
+    ) -> Box<dyn State<App>> {
+        let settings = LeaderboardSettings::load();
+        let mut txt = Text::new();
+        txt.add_line(Line(format!(
+            "Submit {} for {}?",
+            score.score, challenge.title
+        )));
+        txt.add_line(
+            Line(
+                "Your score and proposal are uploaded anonymously, in the public domain. Others \
+                 can replay the proposal to verify the score.",
+            )
+            .secondary(),
+        );
+        txt.add_line(Line("You can't delete a score after submitting it").secondary());
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Submit to leaderboard")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            txt.wrap_to_pct(ctx, 40).into_widget(ctx),
+            Toggle::checkbox(ctx, "show my name", None, settings.name.is_some()),
+            Widget::row(vec![
+                "Name:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "name", settings.name.unwrap_or_default()),
+            ]),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Submit")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+                ctx.style().btn_plain.text("Cancel").build_def(ctx),
+            ]),
+        ]))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(SubmitScore { challenge, score }))
+    }
+}
+
+impl SimpleState<App> for SubmitScore {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" | "Cancel" => Transition::Pop,
+            "Submit" => {
+                let name = Some(panel.text_box("name").trim().to_string())
+                    .filter(|n| panel.is_checked("show my name") && !n.is_empty());
+                let mut settings = LeaderboardSettings::load();
+                settings.name = name.clone();
+                settings.save();
+
+                let map = &app.primary.map;
+                let edits_checksum = map.get_edits().get_checksum(map);
+                let edits_json = abstutil::to_json(&map.get_edits().to_permanent(map));
+                let submission = abstutil::to_json(&Submission {
+                    challenge: self.challenge.alias.clone(),
+                    goal: self.score.goal.clone(),
+                    score_seconds: self.score.score.inner_seconds(),
+                    map: map.get_name().as_filename(),
+                    edits_checksum: edits_checksum.clone(),
+                    name,
+                    version: map_gui::tools::version().to_string(),
+                });
+                let alias = self.challenge.alias.clone();
+
+                let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+                let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+                Transition::Replace(FutureLoader::<App, ()>::new_state(
+                    ctx,
+                    Box::pin(async move {
+                        // Upload the proposal first, so the score can be verified against it
+                        abstio::http_post(format!("{}/create", PROPOSAL_HOST_URL), edits_json)
+                            .await?;
+                        abstio::http_post(
+                            format!("{}/submit-score", PROPOSAL_HOST_URL),
+                            submission,
+                        )
+                        .await?;
+                        let wrapper: Box<dyn Send + FnOnce(&App)> = Box::new(|_| ());
+                        Ok(wrapper)
+                    }),
+                    outer_progress_rx,
+                    inner_progress_rx,
+                    "Submitting score",
+                    Box::new(move |ctx, _, result| match result {
+                        Ok(()) => {
+                            let mut settings = LeaderboardSettings::load();
+                            settings.submitted.insert((alias.clone(), edits_checksum));
+                            settings.save();
+                            Transition::Replace(Leaderboard::new_state(ctx, alias))
+                        }
+                        Err(err) => Transition::Replace(PopupMsg::new_state(
+                            ctx,
+                            "Failure",
+                            vec![format!("Couldn't submit score: {}", err)],
+                        )),
+                    }),
+                ))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
+
+/// The best scores submitted for one challenge
+pub struct Leaderboard;
+
+impl Leaderboard {
+    /// Downloads the scores first
+    pub fn new_state(ctx: &mut EventCtx, alias: String) -> Box<dyn State<App>> {
+        let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
+        let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
+        let url = format!("{}/get-scores?challenge={}", PROPOSAL_HOST_URL, alias);
+        FutureLoader::<App, Vec<u8>>::new_state(
+            ctx,
+            Box::pin(async move {
+                let bytes = abstio::http_get(url).await?;
+                let wrapper: Box<dyn Send + FnOnce(&App) -> Vec<u8>> = Box::new(move |_| bytes);
+                Ok(wrapper)
+            }),
+            outer_progress_rx,
+            inner_progress_rx,
+            "Downloading leaderboard",
+            Box::new(move |ctx, _, result| {
+                match result.and_then(|bytes| abstutil::from_json::<Vec<Entry>>(&bytes)) {
+                    Ok(entries) => Transition::Replace(Leaderboard::show(ctx, &alias, entries)),
+                    Err(err) => Transition::Replace(PopupMsg::new_state(
+                        ctx,
+                        "Couldn't load leaderboard",
+                        vec![err.to_string()],
+                    )),
+                }
+            }),
+        )
+    }
+
+    fn show(ctx: &mut EventCtx, alias: &str, mut entries: Vec<Entry>) -> Box<dyn State<App>> {
+        let settings = LeaderboardSettings::load();
+        let title = Challenge::all()
+            .into_values()
+            .flatten()
+            .find(|c| c.alias == alias)
+            .map(|c| c.title)
+            .unwrap_or_else(|| alias.to_string());
+
+        // Higher scores are better
+        entries.sort_by(|a, b| b.score_seconds.partial_cmp(&a.score_seconds).unwrap());
+        let mut col = vec![Widget::row(vec![
+            Line(format!("Leaderboard: {}", title))
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        if entries.is_empty() {
+            col.push("Nobody has submitted a score yet".text_widget(ctx));
+        } else {
+            let mut txt = Text::new();
+            for (idx, entry) in entries.iter().take(MAX_ENTRIES).enumerate() {
+                let yours = settings
+                    .submitted
+                    .contains(&(alias.to_string(), entry.edits_checksum.clone()));
+                let line = Line(format!(
+                    "{}) {}: {}{}",
+                    idx + 1,
+                    entry.name.as_deref().unwrap_or("anonymous"),
+                    Duration::seconds(entry.score_seconds),
+                    if yours { " (yours)" } else { "" }
+                ));
+                txt.add_line(if yours {
+                    line.fg(ctx.style().text_hotkey_color)
+                } else {
+                    line
+                });
+            }
+            col.push(txt.into_widget(ctx));
+        }
+
+        let panel = Panel::new_builder(Widget::col(col)).build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(Leaderboard))
+    }
+}
+
+impl SimpleState<App> for Leaderboard {
+    fn on_click(&mut self, _: &mut EventCtx, _: &mut App, x: &str, _: &mut Panel) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+}
+
+/// Renders a small image summarizing a score, to share anywhere. Returns the path written.
+pub fn export_score_card(
+    ctx: &EventCtx,
+    app: &App,
+    challenge: &Challenge,
+    score: &HighScore,
+) -> Result<String> {
+    let dims = ScreenDims::new(1200.0, 630.0);
+    let margin = 60.0;
+    let map = &app.primary.map;
+
+    let mut batch = GeomBatch::new();
+    batch.push(Color::WHITE, Polygon::rectangle(dims.width, dims.height));
+    batch.push(Color::hex("#4CA7E9"), Polygon::rectangle(dims.width, 20.0));
+    let mut y = margin;
+    for (line, height) in [
+        (Line("A/B Street challenge").fg(Color::grey(0.4)), 36.0),
+        (Line(&challenge.title).fg(Color::BLACK), 72.0),
+        (Line(&score.goal).fg(Color::BLACK), 36.0),
+        (
+            Line(format!("Score: {}", score.score)).fg(Color::BLACK),
+            96.0,
+        ),
+        (
+            Line(format!("Proposal: {}", score.edits_name)).fg(Color::grey(0.4)),
+            30.0,
+        ),
+        (
+            Line(format!(
+                "Verify with proposal {}",
+                map.get_edits().get_checksum(map)
+            ))
+            .fg(Color::grey(0.4)),
+            24.0,
+        ),
+    ] {
+        batch.append(text(ctx, line, height).translate(margin, y));
+        y += height * 1.4;
+    }
+
+    let path = format!(
+        "score_card_{}_{}.png",
+        challenge.alias.replace('/', "_"),
+        score.edits_name
+    );
+    abstio::write_raw(path.clone(), &batch.to_png(dims)?)?;
+    Ok(path)
+}
+
+/// Renders text with a height in pixels
+fn text(ctx: &EventCtx, line: TextSpan, height: f64) -> GeomBatch {
+    let batch = Text::from(line).render_autocropped(ctx);
+    let current = batch.get_dims().height;
+    if current == 0.0 {
+        return batch;
+    }
+    batch.scale(height / current)
+}
@@ -10,6 +10,7 @@ use crate::sandbox::gameplay::Tutorial;
 use crate::sandbox::{GameplayMode, SandboxMode};
 
 pub mod cutscene;
+pub mod leaderboard;
 pub mod prebake;
 
 // TODO Also have some kind of screenshot to display for each challenge
@@ -21,6 +22,7 @@ pub struct Challenge {
     pub cutscene: Option<fn(&mut EventCtx, &App, &GameplayMode) -> Box<dyn State<App>>>,
 }
 
+#[derive(Clone)]
 pub struct HighScore {
     // TODO This should be tied to the GameplayMode
     pub goal: String,
@@ -74,6 +76,55 @@ impl Challenge {
                 },
             ],
         );
+        tree.insert(
+            "Carbon budget".to_string(),
+            vec![
+                Challenge {
+                    title: "Part 1".to_string(),
+                    description: vec![
+                        "Cut CO2 emissions from driving by 5%, using any edits".to_string()
+                    ],
+                    alias: "carbon/pt1".to_string(),
+                    gameplay: GameplayMode::CarbonBudget(5),
+                    cutscene: Some(
+                        crate::sandbox::gameplay::carbon_budget::CarbonBudget::cutscene_pt1,
+                    ),
+                },
+                Challenge {
+                    title: "Part 2".to_string(),
+                    description: vec!["Now cut emissions by 15%".to_string()],
+                    alias: "carbon/pt2".to_string(),
+                    gameplay: GameplayMode::CarbonBudget(15),
+                    cutscene: Some(
+                        crate::sandbox::gameplay::carbon_budget::CarbonBudget::cutscene_pt2,
+                    ),
+                },
+            ],
+        );
+        // TODO Tune these goals once the scoring has been tried on montlake
+        tree.insert(
+            "School run".to_string(),
+            vec![
+                Challenge {
+                    title: "Part 1".to_string(),
+                    description: vec![
+                        "Give 50% of homes near the school a safe route for children to walk or \
+                         bike"
+                            .to_string(),
+                    ],
+                    alias: "school/pt1".to_string(),
+                    gameplay: GameplayMode::SchoolRun(50),
+                    cutscene: Some(crate::sandbox::gameplay::school_run::SchoolRun::cutscene_pt1),
+                },
+                Challenge {
+                    title: "Part 2".to_string(),
+                    description: vec!["Now reach 80% of homes".to_string()],
+                    alias: "school/pt2".to_string(),
+                    gameplay: GameplayMode::SchoolRun(80),
+                    cutscene: Some(crate::sandbox::gameplay::school_run::SchoolRun::cutscene_pt2),
+                },
+            ],
+        );
         tree.insert(
             "Traffic signal survivor".to_string(),
             vec![Challenge {
@@ -90,6 +141,14 @@ impl Challenge {
         tree
     }
 
+    /// Only some challenges record a score that can be compared on a leaderboard
+    pub fn has_leaderboard(&self) -> bool {
+        matches!(
+            self.gameplay,
+            GameplayMode::OptimizeCommute(_, _) | GameplayMode::FixTrafficSignals
+        )
+    }
+
     // Also returns the next stage, if there is one
     pub fn find(mode: &GameplayMode) -> (Challenge, Option<Challenge>) {
         // Find the next stage
@@ -197,11 +256,18 @@ impl ChallengesPicker {
 
             let mut inner_col = vec![
                 txt.into_widget(ctx),
-                ctx.style()
-                    .btn_outline
-                    .text("Start!")
-                    .hotkey(Key::Enter)
-                    .build_def(ctx),
+                Widget::row(vec![
+                    ctx.style()
+                        .btn_outline
+                        .text("Start!")
+                        .hotkey(Key::Enter)
+                        .build_def(ctx),
+                    if challenge.has_leaderboard() {
+                        ctx.style().btn_plain.text("Leaderboard").build_def(ctx)
+                    } else {
+                        Widget::nothing()
+                    },
+                ]),
             ];
 
             if let Some(scores) = app.session.high_scores.get(&challenge.gameplay) {
@@ -248,6 +314,10 @@ impl SimpleState<App> for ChallengesPicker {
         match x {
             "close" => Transition::Pop,
             "Introduction and tutorial" => Transition::Replace(Tutorial::start(ctx, app)),
+            "Leaderboard" => Transition::Push(leaderboard::Leaderboard::new_state(
+                ctx,
+                self.challenge.as_ref().unwrap().alias.clone(),
+            )),
             "Start!" => {
                 #[cfg(not(target_arch = "wasm32"))]
                 {
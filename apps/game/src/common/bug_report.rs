@@ -0,0 +1,230 @@
+//! Bundle everything needed to reproduce a problem into one zip file: the map, proposal, sim time
+//! and RNG seed, a savestate, recent logs, and a screenshot. Somebody else can load the session or
+//! savestate and see exactly the same crash or gridlock.
+
+use std::io::Write;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use abstio::MapName;
+use geom::Time;
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    DrawBaselayer, EventCtx, GfxCtx, Line, Panel, SimpleState, State, TextBox, TextExt, Toggle,
+    UpdateType, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::gameplay::GameplayMode;
+use crate::sandbox::session::Session;
+
+pub struct BugReport {
+    gameplay: GameplayMode,
+}
+
+impl BugReport {
+    pub fn new_state(ctx: &mut EventCtx, gameplay: GameplayMode) -> Box<dyn State<App>> {
+        if cfg!(target_arch = "wasm32") {
+            return PopupMsg::new_state(
+                ctx,
+                "Not supported on the web",
+                vec!["Bug reports can only be created in the desktop version"],
+            );
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Report a problem").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "What went wrong?".text_widget(ctx),
+            TextBox::default_widget(ctx, "description", String::new()),
+            Toggle::checkbox(ctx, "scrub personal file paths", None, true),
+            Line(
+                "The report includes a screenshot, a savestate of the simulation, your edits, and \
+                 recent logs.",
+            )
+            .secondary()
+            .into_widget(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Create report")
+                .build_def(ctx),
+        ]))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(BugReport { gameplay }))
+    }
+}
+
+impl SimpleState<App> for BugReport {
+    fn on_click(
+        &mut self,
+        _: &mut EventCtx,
+        _: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            "Create report" => Transition::Replace(Box::new(CaptureReport {
+                gameplay: self.gameplay.clone(),
+                description: panel.text_box("description"),
+                scrub_paths: panel.is_checked("scrub personal file paths"),
+                requested_screenshot: false,
+            })),
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
+
+// The dialog has to be gone before taking the screenshot, and widgetry only takes it after this
+// state's event returns, so this takes two rounds.
+struct CaptureReport {
+    gameplay: GameplayMode,
+    description: String,
+    scrub_paths: bool,
+    requested_screenshot: bool,
+}
+
+impl State<App> for CaptureReport {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if !self.requested_screenshot {
+            self.requested_screenshot = true;
+            ctx.request_update(UpdateType::ScreenCaptureCurrentShot {
+                filename: screenshot_path(),
+            });
+            // Make sure there's another event to finish up in, without waiting for input
+            ctx.request_update(UpdateType::Game);
+            return Transition::Keep;
+        }
+
+        let result = ctx.loading_screen("create bug report", |ctx, _| self.write(ctx, app));
+        Transition::Replace(match result {
+            Ok(path) => PopupMsg::new_state(
+                ctx,
+                "Report created",
+                vec![
+                    format!("Wrote {}", path),
+                    "Please attach it to an issue describing the problem.".to_string(),
+                ],
+            ),
+            Err(err) => PopupMsg::new_state(ctx, "Couldn't create report", vec![err.to_string()]),
+        })
+    }
+
+    fn draw(&self, _: &mut GfxCtx, _: &App) {}
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
+
+#[derive(Serialize)]
+struct ReportSummary {
+    description: String,
+    map_name: MapName,
+    edits_name: String,
+    sim_time: Time,
+    rng_seed: u64,
+    platform: String,
+    has_screenshot: bool,
+}
+
+impl CaptureReport {
+    /// Returns the path written
+    fn write(&self, ctx: &EventCtx, app: &mut App) -> Result<String> {
+        let map = &app.primary.map;
+        let name = format!(
+            "{}_{}",
+            map.get_name().as_filename(),
+            app.primary.sim.time().as_filename()
+        );
+        let screenshot = abstio::slurp_file(screenshot_path()).ok();
+        let summary = ReportSummary {
+            description: self.description.clone(),
+            map_name: map.get_name().clone(),
+            edits_name: map.get_edits().edits_name.clone(),
+            sim_time: app.primary.sim.time(),
+            rng_seed: app.primary.current_flags.sim_flags.rng_seed,
+            platform: std::env::consts::OS.to_string(),
+            has_screenshot: screenshot.is_some(),
+        };
+        let session = Session::capture(ctx, app, name.clone(), self.gameplay.clone());
+        let edits = if map.get_edits().commands.is_empty() {
+            None
+        } else {
+            Some(map.get_edits().to_permanent(map))
+        };
+        let savestate_path = app.primary.sim.save();
+        let savestate = abstio::slurp_file(&savestate_path)?;
+
+        let scrub = |text: String| {
+            if self.scrub_paths {
+                scrub_personal_paths(text)
+            } else {
+                text
+            }
+        };
+
+        let path = abstio::path_bug_report(&name);
+        fs_err::create_dir_all(std::path::Path::new(&path).parent().unwrap())?;
+        let mut zip = zip::ZipWriter::new(fs_err::File::create(&path)?);
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        zip.start_file("report.json", options)?;
+        zip.write_all(scrub(abstutil::to_json(&summary)).as_bytes())?;
+        zip.start_file("session.json", options)?;
+        zip.write_all(scrub(abstutil::to_json(&session)).as_bytes())?;
+        if let Some(edits) = edits {
+            zip.start_file("edits.json", options)?;
+            zip.write_all(abstutil::to_json(&edits).as_bytes())?;
+        }
+        zip.start_file("logs.txt", options)?;
+        zip.write_all(scrub(abstutil::recent_logs().join("\n")).as_bytes())?;
+        // Pass the savestate as the input file when launching the game to restore it exactly
+        zip.start_file(
+            format!("savestate_{}.bin", app.primary.sim.time().as_filename()),
+            options,
+        )?;
+        zip.write_all(&savestate)?;
+        if let Some(bytes) = screenshot {
+            zip.start_file("screenshot.png", options)?;
+            zip.write_all(&bytes)?;
+            abstio::delete_file(screenshot_path());
+        }
+        zip.finish()?;
+
+        Ok(path)
+    }
+}
+
+fn screenshot_path() -> String {
+    abstio::path_player("bug_reports/screenshot.png")
+}
+
+/// Replace the home directory and username in logs and file paths, which often identify a person.
+fn scrub_personal_paths(mut text: String) -> String {
+    for var in ["HOME", "USERPROFILE"] {
+        if let Ok(dir) = std::env::var(var) {
+            // Don't replace "/" everywhere
+            if dir.len() > 1 {
+                text = text.replace(&dir, "~");
+            }
+        }
+    }
+    for var in ["USER", "USERNAME"] {
+        if let Ok(user) = std::env::var(var) {
+            // Very short names would clobber unrelated text
+            if user.len() > 2 {
+                text = text.replace(&user, "<user>");
+            }
+        }
+    }
+    text
+}
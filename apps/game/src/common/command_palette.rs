@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+
+use abstutil::Timer;
+use map_gui::tools::grey_out_map;
+use map_model::MapEdits;
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    Choice, DrawBaselayer, EventCtx, GfxCtx, Line, Menu, Outcome, Panel, State, TextBox, TextExt,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::edit::{apply_map_edits, EditMode};
+use crate::layer::{all_layer_names, layer_by_name};
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::GameplayMode;
+use crate::ID;
+
+/// Only show this many of the best matches
+const MAX_RESULTS: usize = 12;
+
+#[derive(Clone)]
+enum Command {
+    Dashboard(DashTab),
+    Layer(&'static str),
+    EditMap,
+    /// Open a saved proposal, by path
+    Proposal(String),
+    /// Many objects might share a name, like segments of one road. Jump to the closest.
+    Jump(Vec<ID>),
+}
+
+/// Opened with Ctrl+K. Fuzzy-searches actions, named map objects, and saved proposals, then runs or
+/// jumps to the chosen one, so players don't have to know where everything lives in the menus.
+pub struct CommandPalette {
+    panel: Panel,
+    /// Labels are unique. Actions come first, so they show up before typing anything.
+    commands: Vec<(String, Command)>,
+    mode: GameplayMode,
+}
+
+impl CommandPalette {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, mode: GameplayMode) -> Box<dyn State<App>> {
+        let commands = all_commands(app);
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Search for anything").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            TextBox::default_widget(ctx, "query", String::new()),
+            results_widget(ctx, &commands, ""),
+        ]))
+        .build(ctx);
+        Box::new(CommandPalette {
+            panel,
+            commands,
+            mode,
+        })
+    }
+
+    fn execute(&self, ctx: &mut EventCtx, app: &mut App, cmd: Command) -> Transition {
+        match cmd {
+            Command::Dashboard(tab) => {
+                app.session.dash_tab = tab;
+                Transition::Replace(tab.launch(ctx, app))
+            }
+            Command::Layer(name) => {
+                app.primary.layer = layer_by_name(ctx, app, name);
+                Transition::Pop
+            }
+            Command::EditMap => {
+                Transition::Replace(EditMode::new_state(ctx, app, self.mode.clone()))
+            }
+            Command::Proposal(path) => {
+                match MapEdits::load_from_file(&app.primary.map, path, &mut Timer::throwaway()) {
+                    Ok(edits) => {
+                        if !self.mode.allows(&edits) {
+                            return Transition::Replace(PopupMsg::new_state(
+                                ctx,
+                                "Can't open proposal",
+                                vec![
+                                    "The current gameplay mode restricts edits. This proposal \
+                                     has a banned command.",
+                                ],
+                            ));
+                        }
+                        // Enter edit mode first, so the proposal counts as a change and the
+                        // simulation restarts with it afterwards.
+                        let edit_mode = EditMode::new_state(ctx, app, self.mode.clone());
+                        apply_map_edits(ctx, app, edits);
+                        Transition::Replace(edit_mode)
+                    }
+                    Err(err) => Transition::Replace(PopupMsg::new_state(
+                        ctx,
+                        "Can't open proposal",
+                        vec![err.to_string()],
+                    )),
+                }
+            }
+            Command::Jump(ids) => {
+                let center = ctx.canvas.center_to_map_pt();
+                let closest = ids
+                    .into_iter()
+                    .filter_map(|id| {
+                        let pt = app.primary.canonical_point(id.clone())?;
+                        Some((pt.dist_to(center), id, pt))
+                    })
+                    .min_by_key(|(dist, _, _)| *dist);
+                match closest {
+                    Some((_, id, pt)) => Transition::Replace(Warping::new_state(
+                        ctx,
+                        pt,
+                        Some(10.0),
+                        Some(id),
+                        &mut app.primary,
+                    )),
+                    None => Transition::Pop,
+                }
+            }
+        }
+    }
+}
+
+impl State<App> for CommandPalette {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if x == "close" {
+                    return Transition::Pop;
+                }
+                if let Some((_, cmd)) = self.commands.iter().find(|(label, _)| *label == x) {
+                    let cmd = cmd.clone();
+                    return self.execute(ctx, app, cmd);
+                }
+            }
+            Outcome::Changed(_) => {
+                let query = self.panel.text_box("query");
+                let results = results_widget(ctx, &self.commands, &query);
+                self.panel.replace(ctx, "results", results);
+            }
+            _ => {
+                if self.panel.clicked_outside(ctx) {
+                    return Transition::Pop;
+                }
+            }
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
+
+fn all_commands(app: &App) -> Vec<(String, Command)> {
+    let map = &app.primary.map;
+    let lang = app.opts.language.as_ref();
+
+    let mut commands = vec![("Edit the map".to_string(), Command::EditMap)];
+    for choice in DashTab::choices(app) {
+        commands.push((
+            format!("Dashboard: {}", choice.label),
+            Command::Dashboard(choice.data),
+        ));
+    }
+    for name in all_layer_names(app) {
+        commands.push((format!("Layer: {}", name), Command::Layer(name)));
+    }
+    for name in abstio::list_all_objects(abstio::path_all_edits(map.get_name())) {
+        commands.push((
+            format!("Proposal: {}", name),
+            Command::Proposal(abstio::path_edits(map.get_name(), &name)),
+        ));
+    }
+
+    let mut jumps: BTreeMap<String, Vec<ID>> = BTreeMap::new();
+    for r in map.all_roads() {
+        if !r.is_light_rail() && !r.is_footway() {
+            jumps
+                .entry(format!("Road: {}", r.get_name(lang)))
+                .or_insert_with(Vec::new)
+                .push(ID::Road(r.id));
+        }
+    }
+    for i in map.all_intersections() {
+        jumps
+            .entry(format!("Intersection #{}: {}", i.id.0, i.name(lang, map)))
+            .or_insert_with(Vec::new)
+            .push(ID::Intersection(i.id));
+    }
+    for b in map.all_buildings() {
+        if !b.address.starts_with("???") {
+            jumps
+                .entry(format!("Building: {}", b.address))
+                .or_insert_with(Vec::new)
+                .push(ID::Building(b.id));
+        }
+        if let Some(ref names) = b.name {
+            jumps
+                .entry(format!("Building: {}", names.get(lang)))
+                .or_insert_with(Vec::new)
+                .push(ID::Building(b.id));
+        }
+    }
+    commands.extend(
+        jumps
+            .into_iter()
+            .map(|(label, ids)| (label, Command::Jump(ids))),
+    );
+    commands
+}
+
+fn results_widget(ctx: &EventCtx, commands: &[(String, Command)], query: &str) -> Widget {
+    let query: Vec<char> = query
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let mut matches: Vec<(usize, &String)> = commands
+        .iter()
+        .filter_map(|(label, _)| {
+            fuzzy_score(&query, &label.to_lowercase()).map(|score| (score, label))
+        })
+        .collect();
+    // Stable, so with no query, actions stay first. Prefer shorter labels between equal matches.
+    matches.sort_by_key(|(score, label)| (std::cmp::Reverse(*score), label.len()));
+    if matches.is_empty() {
+        return "No matches".text_widget(ctx).named("results");
+    }
+    Menu::widget(
+        ctx,
+        matches
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, label)| Choice::new(label, ()))
+            .collect(),
+    )
+    .named("results")
+}
+
+/// Matches if every character of the query appears in order in the text. Both should be
+/// lowercase. Higher scores are better; runs of consecutive characters and matches at the start
+/// of a word count for more.
+fn fuzzy_score(query: &[char], text: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut text = text.chars();
+    let mut prev_char: Option<char> = None;
+    for (idx, q) in query.iter().enumerate() {
+        let mut skipped = false;
+        loop {
+            let c = text.next()?;
+            if c == *q {
+                score += 1;
+                if idx > 0 && !skipped {
+                    score += 5;
+                }
+                if prev_char.map(|p| !p.is_alphanumeric()).unwrap_or(true) {
+                    score += 3;
+                }
+                prev_char = Some(c);
+                break;
+            }
+            skipped = true;
+            prev_char = Some(c);
+        }
+    }
+    Some(score)
+}
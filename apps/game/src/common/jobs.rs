@@ -0,0 +1,296 @@
+//! Some analyses simulate a full day several times over, taking minutes. Instead of freezing the
+//! UI behind a loading screen, run them in a background thread. The sandbox shows a panel with
+//! the progress of each job, lets the player cancel them, and says when they finish.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, TryRecvError};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+
+use geom::Polygon;
+use widgetry::{
+    EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, Text, UpdateType,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Background jobs for the whole session. Lives in `SessionState`, so jobs keep running while
+/// the player moves between dashboards and editing.
+pub struct Jobs {
+    running: Vec<Job>,
+    /// Messages about finished jobs, oldest first, until the player dismisses them
+    notifications: Vec<String>,
+    /// Changes whenever a job starts, finishes, or is cancelled, so UI can tell when to rebuild
+    version: usize,
+}
+
+struct Job {
+    name: String,
+    progress: JobProgress,
+    /// Checks if the work is done, and if so, uses the result. Returns a message for the player.
+    poll: Box<dyn FnMut(&mut App) -> Option<String>>,
+}
+
+/// Shared between a job's background thread and the UI
+#[derive(Clone)]
+pub struct JobProgress {
+    cancelled: Arc<AtomicBool>,
+    /// A description of the current step, and the fraction of all work done
+    status: Arc<Mutex<(String, f64)>>,
+}
+
+impl JobProgress {
+    fn new() -> JobProgress {
+        JobProgress {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            status: Arc::new(Mutex::new((String::new(), 0.0))),
+        }
+    }
+
+    /// `fraction` is between 0 and 1
+    pub fn set<S: Into<String>>(&self, status: S, fraction: f64) {
+        *self.status.lock().unwrap() = (status.into(), fraction.clamp(0.0, 1.0));
+    }
+
+    /// Work should stop as soon as convenient once this is true. Nobody will use the result.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Bails out if the job was cancelled; use between steps of the work
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            bail!("cancelled");
+        }
+        Ok(())
+    }
+
+    fn get(&self) -> (String, f64) {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+impl Jobs {
+    pub fn new() -> Jobs {
+        Jobs {
+            running: Vec::new(),
+            notifications: Vec::new(),
+            version: 0,
+        }
+    }
+
+    /// Run `work` in a background thread. When it succeeds, `on_done` uses the result and returns
+    /// a message for the player. On the web, there are no threads, so the work happens right away
+    /// and blocks the UI.
+    pub fn spawn<T, S, W, D>(&mut self, name: S, work: W, on_done: D)
+    where
+        T: Send + 'static,
+        S: Into<String>,
+        W: FnOnce(&JobProgress) -> Result<T> + Send + 'static,
+        D: FnOnce(&mut App, T) -> String + 'static,
+    {
+        let name = name.into();
+        let progress = JobProgress::new();
+        let (sender, receiver) = channel();
+        if cfg!(target_arch = "wasm32") {
+            let _ = sender.send(work(&progress));
+        } else {
+            let progress = progress.clone();
+            std::thread::spawn(move || {
+                // If the job was cancelled, nobody's listening anymore
+                let _ = sender.send(work(&progress));
+            });
+        }
+
+        let job_name = name.clone();
+        let mut on_done = Some(on_done);
+        self.running.push(Job {
+            name,
+            progress,
+            poll: Box::new(move |app| match receiver.try_recv() {
+                Ok(Ok(result)) => Some((on_done.take().unwrap())(app, result)),
+                Ok(Err(err)) => Some(format!("{} failed: {}", job_name, err)),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => {
+                    Some(format!("{} crashed. Check the logs for details.", job_name))
+                }
+            }),
+        });
+        self.version += 1;
+    }
+
+    /// Handle any jobs that finished. Returns true if anything changed.
+    pub fn poll(app: &mut App) -> bool {
+        if app.session.jobs.running.is_empty() {
+            return false;
+        }
+        // Jobs use the rest of the app when they finish, maybe even to start more jobs
+        let mut running = std::mem::take(&mut app.session.jobs.running);
+        let mut changed = false;
+        let mut idx = 0;
+        while idx < running.len() {
+            if let Some(msg) = (running[idx].poll)(app) {
+                running.remove(idx);
+                app.session.jobs.notifications.push(msg);
+                changed = true;
+            } else {
+                idx += 1;
+            }
+        }
+        running.append(&mut app.session.jobs.running);
+        app.session.jobs.running = running;
+        if changed {
+            app.session.jobs.version += 1;
+        }
+        changed
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    pub fn is_running(&self, name: &str) -> bool {
+        self.running.iter().any(|job| job.name == name)
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    fn cancel(&mut self, idx: usize) {
+        let job = self.running.remove(idx);
+        job.progress.cancelled.store(true, Ordering::Relaxed);
+        self.version += 1;
+    }
+}
+
+/// Shows the progress of running jobs and messages about finished ones
+pub struct JobsPanel {
+    panel: Option<Panel>,
+    version: usize,
+    /// The progress of each running job, as last shown. Rebuilding the panel every frame would
+    /// make the buttons lose hover state.
+    shown: Vec<(String, f64)>,
+}
+
+impl JobsPanel {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> JobsPanel {
+        let mut jobs = JobsPanel {
+            panel: None,
+            version: 0,
+            shown: Vec::new(),
+        };
+        jobs.recreate_panel(ctx, app);
+        jobs
+    }
+
+    pub fn recreate_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.panel = make_panel(ctx, app);
+        self.version = app.session.jobs.version;
+        self.shown = current_progress(app);
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+        if let Some(ref mut panel) = self.panel {
+            if let Outcome::Clicked(x) = panel.event(ctx) {
+                if x == "dismiss" {
+                    app.session.jobs.notifications.clear();
+                    app.session.jobs.version += 1;
+                } else if let Some(idx) = x.strip_prefix("cancel job ") {
+                    app.session.jobs.cancel(idx.parse::<usize>().unwrap());
+                } else {
+                    unreachable!()
+                }
+                self.recreate_panel(ctx, app);
+                return Some(Transition::Keep);
+            }
+        }
+
+        if self.version != app.session.jobs.version || self.shown != current_progress(app) {
+            self.recreate_panel(ctx, app);
+        }
+        // Progress changes without any input
+        if app.session.jobs.is_busy() {
+            ctx.request_update(UpdateType::Game);
+        }
+        None
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if let Some(ref panel) = self.panel {
+            panel.draw(g);
+        }
+    }
+}
+
+fn current_progress(app: &App) -> Vec<(String, f64)> {
+    app.session
+        .jobs
+        .running
+        .iter()
+        .map(|job| job.progress.get())
+        .collect()
+}
+
+fn make_panel(ctx: &mut EventCtx, app: &App) -> Option<Panel> {
+    let jobs = &app.session.jobs;
+    if jobs.running.is_empty() && jobs.notifications.is_empty() {
+        return None;
+    }
+
+    let mut col = Vec::new();
+    for (idx, job) in jobs.running.iter().enumerate() {
+        let (status, fraction) = job.progress.get();
+        let mut txt = Text::from(Line(&job.name).small_heading());
+        if !status.is_empty() {
+            txt.add_line(Line(status).secondary());
+        }
+        col.push(Widget::row(vec![
+            txt.into_widget(ctx),
+            ctx.style()
+                .btn_plain_destructive
+                .text("Cancel")
+                .build_widget(ctx, format!("cancel job {}", idx))
+                .align_right(),
+        ]));
+        col.push(progress_bar(ctx, fraction));
+    }
+    if !jobs.notifications.is_empty() {
+        let mut txt = Text::new();
+        for msg in &jobs.notifications {
+            txt.add_line(msg);
+        }
+        col.push(Widget::row(vec![
+            txt.wrap_to_pct(ctx, 30).into_widget(ctx),
+            ctx.style()
+                .btn_outline
+                .text("Dismiss")
+                .build_widget(ctx, "dismiss")
+                .align_right(),
+        ]));
+    }
+
+    Some(
+        Panel::new_builder(Widget::col(col))
+            .aligned(
+                HorizontalAlignment::Center,
+                VerticalAlignment::BottomAboveOSD,
+            )
+            .build(ctx),
+    )
+}
+
+// Match the time panel's progress bar
+fn progress_bar(ctx: &mut EventCtx, fraction: f64) -> Widget {
+    let width = 300.0;
+    let height = 15.0;
+    let fg = ctx.style().primary_fg;
+    let mut batch = GeomBatch::new();
+    batch.push(fg.tint(0.6).shade(0.2), Polygon::rectangle(width, height));
+    if let Ok(p) = Polygon::maybe_rectangle(fraction * width, height) {
+        batch.push(fg, p);
+    }
+    batch.into_widget(ctx)
+}
@@ -2,12 +2,16 @@ use std::collections::BTreeSet;
 
 use crate::ID;
 use geom::{Duration, Polygon, Time};
+use map_model::AttributeDiff;
 use sim::{AgentType, TripPhaseType};
 use widgetry::{
     lctrl, Color, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Panel, ScreenDims,
     ScreenPt, ScreenRectangle, Text, TextSpan, VerticalAlignment, Widget,
 };
 
+pub use self::command_palette::CommandPalette;
+pub use self::jobs::{JobProgress, Jobs, JobsPanel};
+pub use self::perf::PerfTracker;
 pub use self::route_sketcher::RouteSketcher;
 pub use self::select::RoadSelector;
 pub use self::warp::{warp_to_id, Warping};
@@ -16,6 +20,11 @@ use crate::app::Transition;
 use crate::info::{ContextualActions, InfoPanel, Tab};
 use crate::sandbox::TimeWarpScreen;
 
+pub mod bug_report;
+mod command_palette;
+mod jobs;
+mod perf;
+pub mod poster;
 mod route_sketcher;
 mod select;
 pub mod share;
@@ -119,6 +128,24 @@ impl CommonState {
         }
 
         CommonState::draw_custom_osd(g, app, osd);
+
+        // Explain what the current proposal changed about the hovered object
+        if self.info_panel.is_none() {
+            if let Some(ref id) = app.primary.current_selection {
+                let diffs = edits_diff(app, id);
+                if !diffs.is_empty() {
+                    let mut txt = Text::from(Line(format!(
+                        "Changed by \"{}\"",
+                        app.primary.map.get_edits().edits_name
+                    )));
+                    for diff in diffs {
+                        txt.add_line(Line(format!("{}: ", diff.attribute)).secondary());
+                        txt.append(Line(format!("{} -> {}", diff.before, diff.after)));
+                    }
+                    g.draw_mouse_tooltip(txt);
+                }
+            }
+        }
     }
 
     fn osd_for(app: &App, id: ID) -> Text {
@@ -297,11 +324,19 @@ impl CommonState {
         self.info_panel.as_ref().and_then(|i| i.active_id(app))
     }
 
-    /// Allow toggling of dev mode and warping to an object by ID.
+    /// Allow toggling of dev mode, the performance HUD, and warping to an object by ID.
     pub fn debug_actions(ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
         if ctx.input.pressed(lctrl(Key::S)) {
             app.opts.dev = !app.opts.dev;
         }
+        if ctx.input.pressed(Key::F12) {
+            let memory = if app.session.perf.show_hud {
+                Vec::new()
+            } else {
+                perf::measure_memory(app)
+            };
+            app.session.perf.toggle_hud(memory);
+        }
         if ctx.input.pressed(lctrl(Key::J)) {
             return Some(Transition::Push(warp::DebugWarp::new_state(ctx)));
         }
@@ -330,6 +365,47 @@ pub fn tool_panel(ctx: &mut EventCtx) -> Panel {
     .build(ctx)
 }
 
+/// Like `tool_panel`, plus buttons to save the whole session and report a problem
+pub fn sandbox_tool_panel(ctx: &mut EventCtx) -> Panel {
+    Panel::new_builder(Widget::row(vec![
+        ctx.style()
+            .btn_plain
+            .icon("system/assets/tools/home.svg")
+            .hotkey(Key::Escape)
+            .build_widget(ctx, "back"),
+        ctx.style()
+            .btn_plain
+            .icon("system/assets/tools/settings.svg")
+            .build_widget(ctx, "settings"),
+        ctx.style()
+            .btn_plain
+            .icon("system/assets/tools/save.svg")
+            .tooltip("Save this session, to resume later from the title screen")
+            .build_widget(ctx, "save session"),
+        ctx.style()
+            .btn_plain
+            .icon("system/assets/tools/alert.svg")
+            .tooltip("Report a problem")
+            .build_widget(ctx, "report a problem"),
+    ]))
+    .aligned(HorizontalAlignment::Left, VerticalAlignment::BottomAboveOSD)
+    .build(ctx)
+}
+
+/// How the current proposal changed a road, intersection, or building. Lanes describe their road.
+/// Empty for anything unchanged.
+pub fn edits_diff(app: &App, id: &ID) -> Vec<AttributeDiff> {
+    let map = &app.primary.map;
+    let edits = map.get_edits();
+    match id {
+        ID::Lane(l) => edits.diff_road(map, l.road, &app.opts.units),
+        ID::Road(r) => edits.diff_road(map, *r, &app.opts.units),
+        ID::Intersection(i) => edits.diff_intersection(map, *i, &app.opts.units),
+        ID::Building(b) => edits.diff_building(map, *b),
+        _ => Vec::new(),
+    }
+}
+
 pub fn list_names<F: Fn(TextSpan) -> TextSpan>(txt: &mut Text, styler: F, names: BTreeSet<String>) {
     let len = names.len();
     for (idx, n) in names.into_iter().enumerate() {
@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+use std::io::Write;
+
+use serde::Serialize;
+
+use abstutil::{prettyprint_bytes, serialized_size_bytes};
+use geom::{Duration, Time};
+use widgetry::{FrameStats, GfxCtx, Line, ScreenPt, Text};
+
+use crate::app::App;
+
+/// Average over this many recent frames in the HUD
+const RECENT_FRAMES: usize = 60;
+
+/// Tracks how long frames and simulation steps take, for a performance HUD and optionally a
+/// structured profile written as one JSON object per line.
+pub struct PerfTracker {
+    pub show_hud: bool,
+    /// Oldest first
+    recent_frames: VecDeque<FrameStats>,
+    /// Steps since the last frame
+    sim_steps: Vec<SimStep>,
+    /// The total real time and simulated time of steps over the recent frames
+    recent_sim_steps: VecDeque<(Duration, Duration)>,
+    /// Estimated in bytes, only calculated when the HUD is opened, because it's slow
+    memory: Vec<(&'static str, usize)>,
+
+    num_frames: usize,
+    profile: Option<fs_err::File>,
+}
+
+#[derive(Serialize)]
+struct SimStep {
+    sim_time_before: Time,
+    sim_time_after: Time,
+    real_seconds: f64,
+}
+
+#[derive(Serialize)]
+struct ProfileFrame<'a> {
+    frame: usize,
+    num_events: usize,
+    event_seconds: f64,
+    draw_seconds: f64,
+    num_uploads: usize,
+    num_draw_calls: usize,
+    num_forks: usize,
+    total_bytes_uploaded: usize,
+    sim_steps: &'a Vec<SimStep>,
+}
+
+impl PerfTracker {
+    pub fn new() -> PerfTracker {
+        PerfTracker {
+            show_hud: false,
+            recent_frames: VecDeque::new(),
+            sim_steps: Vec::new(),
+            recent_sim_steps: VecDeque::new(),
+            memory: Vec::new(),
+            num_frames: 0,
+            profile: None,
+        }
+    }
+
+    /// Start writing timings for every frame to a file. No web support.
+    pub fn profile_to(&mut self, path: &str) {
+        match fs_err::File::create(path) {
+            Ok(f) => {
+                info!("Writing per-frame timings to {}", path);
+                self.profile = Some(f);
+            }
+            Err(err) => {
+                error!("Not profiling: {}", err);
+            }
+        }
+    }
+
+    /// Call after every step of the primary simulation.
+    pub fn record_sim_step(&mut self, before: Time, after: Time, real_time: Duration) {
+        self.sim_steps.push(SimStep {
+            sim_time_before: before,
+            sim_time_after: after,
+            real_seconds: real_time.inner_seconds(),
+        });
+    }
+
+    pub fn frame_finished(&mut self, frame: FrameStats) {
+        self.num_frames += 1;
+        if let Some(ref mut f) = self.profile {
+            let line = serde_json::to_string(&ProfileFrame {
+                frame: self.num_frames,
+                num_events: frame.num_events,
+                event_seconds: frame.event_time.inner_seconds(),
+                draw_seconds: frame.draw_time.inner_seconds(),
+                num_uploads: frame.num_uploads,
+                num_draw_calls: frame.num_draw_calls,
+                num_forks: frame.num_forks,
+                total_bytes_uploaded: frame.total_bytes_uploaded,
+                sim_steps: &self.sim_steps,
+            })
+            .unwrap();
+            if let Err(err) = writeln!(f, "{}", line) {
+                error!("Stopped profiling: {}", err);
+                self.profile = None;
+            }
+        }
+
+        let mut real = Duration::ZERO;
+        let mut simulated = Duration::ZERO;
+        for step in self.sim_steps.drain(..) {
+            real += Duration::seconds(step.real_seconds);
+            simulated += step.sim_time_after - step.sim_time_before;
+        }
+        self.recent_sim_steps.push_back((real, simulated));
+        self.recent_frames.push_back(frame);
+        if self.recent_frames.len() > RECENT_FRAMES {
+            self.recent_frames.pop_front();
+            self.recent_sim_steps.pop_front();
+        }
+    }
+
+    pub fn toggle_hud(&mut self, app_memory: Vec<(&'static str, usize)>) {
+        self.show_hud = !self.show_hud;
+        self.memory = app_memory;
+    }
+
+    pub fn draw_hud(&self, g: &mut GfxCtx) {
+        if !self.show_hud || self.recent_frames.is_empty() {
+            return;
+        }
+        let n = self.recent_frames.len() as f64;
+
+        let mut txt = Text::from(
+            Line(format!(
+                "Performance (last {} frames)",
+                self.recent_frames.len()
+            ))
+            .small_heading(),
+        );
+        let event_time = self.avg(|x| x.event_time.inner_seconds());
+        let draw_time = self.avg(|x| x.draw_time.inner_seconds());
+        txt.add_line(format!(
+            "Frame: {:.1}ms ({:.1}ms events, {:.1}ms drawing)",
+            1000.0 * (event_time + draw_time),
+            1000.0 * event_time,
+            1000.0 * draw_time
+        ));
+        let sim_real: f64 = self
+            .recent_sim_steps
+            .iter()
+            .map(|(real, _)| real.inner_seconds())
+            .sum();
+        let sim_simulated: f64 = self
+            .recent_sim_steps
+            .iter()
+            .map(|(_, simulated)| simulated.inner_seconds())
+            .sum();
+        txt.add_line(format!(
+            "Sim steps: {:.1}ms per frame, covering {} of simulated time",
+            1000.0 * sim_real / n,
+            Duration::seconds(sim_simulated)
+        ));
+        txt.add_line(format!(
+            "{:.0} draw calls, {:.0} forks, {:.1} uploads per frame",
+            self.avg(|x| x.num_draw_calls as f64),
+            self.avg(|x| x.num_forks as f64),
+            self.avg(|x| x.num_uploads as f64)
+        ));
+
+        txt.add_line(Line("Memory").small_heading());
+        txt.add_line(format!(
+            "GPU: {} uploaded total",
+            prettyprint_bytes(self.recent_frames.back().unwrap().total_bytes_uploaded as u64)
+        ));
+        for (name, bytes) in &self.memory {
+            txt.add_line(format!("{}: {}", name, prettyprint_bytes(*bytes as u64)));
+        }
+        txt.add_line(Line("Memory is measured when this opens").secondary());
+
+        g.draw_tooltip_at(
+            txt,
+            ScreenPt::new(g.canvas.window_width - 250.0, 0.3 * g.canvas.window_height),
+        );
+    }
+
+    fn avg<F: Fn(&FrameStats) -> f64>(&self, f: F) -> f64 {
+        self.recent_frames.iter().map(f).sum::<f64>() / (self.recent_frames.len() as f64)
+    }
+}
+
+/// Estimate memory per subsystem by how large each would be serialized. Slow for large maps.
+pub fn measure_memory(app: &App) -> Vec<(&'static str, usize)> {
+    let mut memory = vec![
+        ("Map", serialized_size_bytes(&app.primary.map)),
+        // This includes analytics
+        ("Simulation", serialized_size_bytes(&app.primary.sim)),
+        (
+            "Analytics",
+            serialized_size_bytes(app.primary.sim.get_analytics()),
+        ),
+    ];
+    if let Some(ref scenario) = app.primary.scenario {
+        memory.push(("Scenario", serialized_size_bytes(scenario)));
+    }
+    if let Some(ref map) = app.primary.unedited_map {
+        memory.push(("Unedited map", serialized_size_bytes(map)));
+    }
+    if let Some(ref secondary) = app.secondary {
+        memory.push(("Secondary map", serialized_size_bytes(&secondary.map)));
+        memory.push((
+            "Secondary simulation",
+            serialized_size_bytes(&secondary.sim),
+        ));
+    }
+    if app.has_prebaked().is_some() {
+        memory.push(("Prebaked results", serialized_size_bytes(app.prebaked())));
+    }
+    memory
+}
@@ -0,0 +1,390 @@
+//! Export the map as a large poster for printing, like for public consultation boards about a
+//! proposal. The poster has a title, a scale bar, a north arrow, and a legend for the active
+//! layer.
+
+use anyhow::Result;
+
+use geom::{Bounds, Distance, Polygon, Pt2D, Ring};
+use map_gui::render::DrawMap;
+use map_gui::tools::grey_out_map;
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    Choice, Color, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Line, Panel, ScreenDims,
+    SimpleState, Spinner, State, Text, TextBox, TextExt, TextSpan, Toggle, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::layer::map::Static;
+use crate::layer::Layer;
+
+/// Name, and the width and height in millimeters. Posters are always landscape.
+const PAPER_SIZES: [(&str, f64, f64); 5] = [
+    ("A0", 1189.0, 841.0),
+    ("A1", 841.0, 594.0),
+    ("A2", 594.0, 420.0),
+    ("A3", 420.0, 297.0),
+    ("A4", 297.0, 210.0),
+];
+
+// All in millimeters
+const MARGIN: f64 = 10.0;
+const TITLE_HEIGHT: f64 = 25.0;
+const FOOTER_HEIGHT: f64 = 30.0;
+
+pub struct PosterExport;
+
+impl PosterExport {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Export a poster").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::row(vec![
+                "Title:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(
+                    ctx,
+                    "title",
+                    app.primary.map.get_edits().edits_name.clone(),
+                ),
+            ]),
+            Widget::row(vec![
+                "Paper size:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "paper",
+                    "A1".to_string(),
+                    PAPER_SIZES
+                        .iter()
+                        .map(|(name, _, _)| Choice::string(name))
+                        .collect(),
+                ),
+            ]),
+            Widget::row(vec![
+                "Resolution (DPI):".text_widget(ctx).centered_vert(),
+                Spinner::widget(ctx, "dpi", (72, 600), 300, 1),
+            ]),
+            Widget::row(vec![
+                "Area:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "extent",
+                    "current view".to_string(),
+                    Choice::strings(vec!["current view", "whole map"]),
+                ),
+            ]),
+            Toggle::checkbox(ctx, "highlight edits", None, true),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Export PNG")
+                    .build_def(ctx),
+                ctx.style().btn_outline.text("Export SVG").build_def(ctx),
+            ]),
+        ]))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(PosterExport))
+    }
+}
+
+impl SimpleState<App> for PosterExport {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            "Export PNG" | "Export SVG" => {
+                let paper: String = panel.dropdown_value("paper");
+                let (_, width_mm, height_mm) = PAPER_SIZES
+                    .iter()
+                    .find(|(name, _, _)| *name == paper)
+                    .cloned()
+                    .unwrap();
+                let extent = if panel.dropdown_value::<String, _>("extent") == "whole map" {
+                    app.primary.map.get_bounds().clone()
+                } else {
+                    ctx.canvas.get_screen_bounds()
+                };
+                let poster = Poster {
+                    title: panel.text_box("title"),
+                    width_mm,
+                    height_mm,
+                    dpi: panel.spinner::<usize>("dpi") as f64,
+                    extent,
+                    highlight_edits: panel.is_checked("highlight edits"),
+                };
+                let png = x == "Export PNG";
+                let result =
+                    ctx.loading_screen("export poster", |ctx, _| poster.export(ctx, app, png));
+                Transition::Replace(match result {
+                    Ok(path) => {
+                        PopupMsg::new_state(ctx, "Poster exported", vec![format!("Wrote {}", path)])
+                    }
+                    Err(err) => PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()]),
+                })
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
+
+struct Poster {
+    title: String,
+    width_mm: f64,
+    height_mm: f64,
+    dpi: f64,
+    /// In map-space
+    extent: Bounds,
+    highlight_edits: bool,
+}
+
+impl Poster {
+    /// Returns the path written
+    fn export(&self, ctx: &mut EventCtx, app: &App, png: bool) -> Result<String> {
+        let dims = ScreenDims::new(self.mm(self.width_mm), self.mm(self.height_mm));
+        let batch = self.render(ctx, app, dims);
+        let path = format!(
+            "poster_{}_{}.{}",
+            app.primary.map.get_name().as_filename(),
+            app.primary.map.get_edits().edits_name,
+            if png { "png" } else { "svg" }
+        );
+        if png {
+            abstio::write_raw(path.clone(), &batch.to_png(dims)?)?;
+            Ok(path)
+        } else {
+            abstio::write_file(path, batch.to_svg(dims))
+        }
+    }
+
+    /// Millimeters to pixels
+    fn mm(&self, mm: f64) -> f64 {
+        mm / 25.4 * self.dpi
+    }
+
+    fn render(&self, ctx: &mut EventCtx, app: &App, dims: ScreenDims) -> GeomBatch {
+        let margin = self.mm(MARGIN);
+        let frame_top = margin + self.mm(TITLE_HEIGHT);
+        let frame_bottom = dims.height - margin - self.mm(FOOTER_HEIGHT);
+        let frame_width = dims.width - 2.0 * margin;
+        let frame_height = frame_bottom - frame_top;
+
+        // Fit the extent into the frame, keeping the aspect ratio
+        let scale = (frame_width / self.extent.width()).min(frame_height / self.extent.height());
+        let dx = margin + (frame_width - self.extent.width() * scale) / 2.0;
+        let dy = frame_top + (frame_height - self.extent.height() * scale) / 2.0;
+        let to_frame = |batch: GeomBatch| -> GeomBatch {
+            batch
+                .translate(-self.extent.min_x, -self.extent.min_y)
+                .scale(scale)
+                .translate(dx, dy)
+        };
+
+        let mut batch = GeomBatch::new();
+        batch.push(Color::WHITE, Polygon::rectangle(dims.width, dims.height));
+        batch.append(to_frame(DrawMap::zoomed_batch(ctx, app)));
+
+        let mut legend = Vec::new();
+        if let Some((layer, categories)) = app.primary.layer.as_ref().and_then(|l| l.poster()) {
+            batch.append(to_frame(layer).set_z_offset(-0.5));
+            legend.extend(categories);
+        }
+        if self.highlight_edits {
+            if let Some((edits, categories)) = Static::edits(ctx, app).poster() {
+                batch.append(to_frame(edits).set_z_offset(-0.6));
+                legend.extend(categories);
+            }
+        }
+
+        // Cover anything outside the frame
+        let mut decorations = GeomBatch::new();
+        for (x, y, width, height) in [
+            (0.0, 0.0, dims.width, frame_top),
+            (0.0, frame_bottom, dims.width, dims.height - frame_bottom),
+            (0.0, 0.0, margin, dims.height),
+            (dims.width - margin, 0.0, margin, dims.height),
+        ] {
+            decorations.push(
+                Color::WHITE,
+                Polygon::rectangle(width, height).translate(x, y),
+            );
+        }
+        // A thin border around the frame
+        let border = self.mm(0.5);
+        for (x, y, width, height) in [
+            (margin, frame_top - border, frame_width, border),
+            (margin, frame_bottom, frame_width, border),
+            (
+                margin - border,
+                frame_top - border,
+                border,
+                frame_height + 2.0 * border,
+            ),
+            (
+                dims.width - margin,
+                frame_top - border,
+                border,
+                frame_height + 2.0 * border,
+            ),
+        ] {
+            decorations.push(
+                Color::BLACK,
+                Polygon::rectangle(width, height).translate(x, y),
+            );
+        }
+
+        decorations.append(
+            self.text(ctx, Line(&self.title).fg(Color::BLACK), self.mm(12.0))
+                .translate(margin, margin + self.mm(4.0)),
+        );
+        if self.highlight_edits {
+            // Explain the proposal under the title, shrinking the text if it's too long
+            let summary = app
+                .primary
+                .map
+                .get_edits()
+                .summarize(&app.primary.map, &app.opts.units);
+            if !summary.is_empty() {
+                let mut subtitle =
+                    self.text(ctx, Line(summary.join("; ")).fg(Color::BLACK), self.mm(4.0));
+                let width = subtitle.get_dims().width;
+                if width > frame_width {
+                    subtitle = subtitle.scale(frame_width / width);
+                }
+                decorations.append(subtitle.translate(margin, margin + self.mm(18.0)));
+            }
+        }
+
+        let footer_top = frame_bottom + self.mm(6.0);
+        decorations.append(self.scale_bar(ctx, app, scale, frame_width / 5.0, margin, footer_top));
+        decorations.append(self.north_arrow(ctx, dims.width * 0.3, footer_top));
+        decorations.append(self.legend(ctx, legend, dims.width * 0.4, footer_top));
+        let attribution = self.text(
+            ctx,
+            Line("Map data © OpenStreetMap contributors").fg(Color::BLACK),
+            self.mm(3.0),
+        );
+        let attribution_width = attribution.get_dims().width;
+        decorations.append(attribution.translate(
+            dims.width - margin - attribution_width,
+            dims.height - margin - self.mm(3.0),
+        ));
+
+        batch.append(decorations.set_z_offset(-0.9));
+        batch
+    }
+
+    /// Renders text with a height in pixels
+    fn text(&self, ctx: &EventCtx, line: TextSpan, height: f64) -> GeomBatch {
+        let batch = Text::from(line).render_autocropped(ctx);
+        let current = batch.get_dims().height;
+        if current == 0.0 {
+            return batch;
+        }
+        batch.scale(height / current)
+    }
+
+    /// `scale` is pixels per meter. The bar is at most `max_width` pixels long.
+    fn scale_bar(
+        &self,
+        ctx: &EventCtx,
+        app: &App,
+        scale: f64,
+        max_width: f64,
+        x: f64,
+        y: f64,
+    ) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        // Round down to 1, 2, or 5 times a power of 10
+        let max_meters = max_width / scale;
+        let magnitude = 10.0_f64.powf(max_meters.log10().floor());
+        let meters = [5.0, 2.0, 1.0]
+            .into_iter()
+            .map(|x| x * magnitude)
+            .find(|m| *m <= max_meters)
+            .unwrap_or(magnitude);
+
+        let segments = 4;
+        let segment_width = meters * scale / (segments as f64);
+        let height = self.mm(3.0);
+        for i in 0..segments {
+            let color = if i % 2 == 0 {
+                Color::BLACK
+            } else {
+                Color::grey(0.6)
+            };
+            batch.push(
+                color,
+                Polygon::rectangle(segment_width, height)
+                    .translate(x + (i as f64) * segment_width, y),
+            );
+        }
+        batch.append(
+            self.text(
+                ctx,
+                Line(Distance::meters(meters).to_string(&app.opts.units)).fg(Color::BLACK),
+                self.mm(4.0),
+            )
+            .translate(x, y + height + self.mm(2.0)),
+        );
+        batch
+    }
+
+    /// Map-space has north pointing up
+    fn north_arrow(&self, ctx: &EventCtx, x: f64, y: f64) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        let width = self.mm(8.0);
+        let height = self.mm(12.0);
+        batch.push(
+            Color::BLACK,
+            Ring::must_new(vec![
+                Pt2D::new(x + width / 2.0, y),
+                Pt2D::new(x + width, y + height),
+                Pt2D::new(x + width / 2.0, y + height * 0.75),
+                Pt2D::new(x, y + height),
+                Pt2D::new(x + width / 2.0, y),
+            ])
+            .into_polygon(),
+        );
+        batch.append(
+            self.text(ctx, Line("N").fg(Color::BLACK), self.mm(5.0))
+                .translate(x + width + self.mm(2.0), y),
+        );
+        batch
+    }
+
+    /// Lays out swatches in columns of up to 3
+    fn legend(&self, ctx: &EventCtx, entries: Vec<(String, Color)>, x: f64, y: f64) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        let row_height = self.mm(7.0);
+        let swatch = self.mm(5.0);
+        let mut column_x = x;
+        for column in entries.chunks(3) {
+            let mut column_width: f64 = 0.0;
+            for (row, (name, color)) in column.iter().enumerate() {
+                let row_y = y + (row as f64) * row_height;
+                batch.push(
+                    *color,
+                    Polygon::rectangle(swatch, swatch).translate(column_x, row_y),
+                );
+                let label = self.text(ctx, Line(name).fg(Color::BLACK), self.mm(4.0));
+                column_width = column_width.max(label.get_dims().width);
+                batch.append(label.translate(column_x + swatch + self.mm(2.0), row_y));
+            }
+            column_x += swatch + column_width + self.mm(10.0);
+        }
+        batch
+    }
+}
@@ -0,0 +1,193 @@
+use sim::{AgentID, Breakpoint, BreakpointHit, Intent};
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, Widget};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::ID;
+
+/// How many upcoming steps of an agent's route to show
+const ROUTE_PREVIEW: usize = 5;
+
+/// Shown when the simulation pauses because of a breakpoint, describing what happened and the
+/// internal state of the agent involved.
+pub struct BreakpointHits {
+    panel: Panel,
+    agent: Option<AgentID>,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl BreakpointHits {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        hits: Vec<BreakpointHit>,
+    ) -> Box<dyn State<App>> {
+        let agent = hits.iter().find_map(|hit| hit.agent);
+        let mut breakpoints: Vec<Breakpoint> = hits.iter().map(|hit| hit.breakpoint).collect();
+        breakpoints.sort();
+        breakpoints.dedup();
+
+        let mut txt = Text::new();
+        for hit in &hits {
+            txt.add_line(format!(
+                "At {}, {}",
+                hit.time.ampm_tostring(),
+                hit.description
+            ));
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Breakpoint").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            txt.into_widget(ctx),
+        ];
+        if let Some(agent) = agent {
+            col.push(Widget::horiz_separator(ctx, 1.0));
+            col.push(describe_agent(app, agent).into_widget(ctx));
+            col.push(
+                ctx.style()
+                    .btn_outline
+                    .text(format!("inspect {}", agent))
+                    .build_widget(ctx, "inspect"),
+            );
+        }
+        col.push(Widget::row(vec![
+            ctx.style()
+                .btn_outline
+                .text("remove these breakpoints")
+                .build_def(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("continue")
+                .build_def(ctx),
+        ]));
+
+        Box::new(BreakpointHits {
+            panel: Panel::new_builder(Widget::col(col)).build(ctx),
+            agent,
+            breakpoints,
+        })
+    }
+}
+
+impl State<App> for BreakpointHits {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" | "continue" => {
+                    return Transition::Pop;
+                }
+                "remove these breakpoints" => {
+                    for bp in &self.breakpoints {
+                        app.primary.sim.remove_breakpoint(*bp);
+                    }
+                    return Transition::Pop;
+                }
+                "inspect" => {
+                    let id = ID::from_agent(self.agent.unwrap());
+                    if let Some(pt) = app.primary.canonical_point(id.clone()) {
+                        return Transition::Replace(Warping::new_state(
+                            ctx,
+                            pt,
+                            Some(10.0),
+                            Some(id),
+                            &mut app.primary,
+                        ));
+                    }
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+/// The internal state of an agent: where it is, how fast it's going, what it's trying to do, and
+/// where it's going next.
+fn describe_agent(app: &App, agent: AgentID) -> Text {
+    let sim = &app.primary.sim;
+    let map = &app.primary.map;
+
+    let mut txt = Text::from(Line(agent.to_string()).small_heading());
+    let speed = if let Some(speed) = sim.current_speed(agent) {
+        speed
+    } else {
+        txt.add_line("No longer active");
+        return txt;
+    };
+    txt.add_line(format!("Speed: {}", speed.to_string(&app.opts.units)));
+
+    let (on, waiting_for_turn, intent) = match agent {
+        AgentID::Car(id) | AgentID::BusPassenger(_, id) => match sim.get_draw_car(id, map) {
+            Some(draw) => (draw.on, draw.waiting_for_turn, draw.intent),
+            None => {
+                return txt;
+            }
+        },
+        AgentID::Pedestrian(id) => match sim.get_draw_ped(id, map) {
+            Some(draw) => (draw.on, draw.waiting_for_turn, draw.intent),
+            None => {
+                return txt;
+            }
+        },
+    };
+    txt.add_line(format!("On {}", on));
+    if let Some(t) = waiting_for_turn {
+        txt.add_line(format!("Waiting to turn through {}", t.parent));
+    }
+    txt.add_line(format!(
+        "Intent: {}",
+        match intent {
+            Some(Intent::Parking) => "looking for parking",
+            Some(Intent::SteepUphill) => "slowly climbing a steep hill",
+            None => "following its route",
+        }
+    ));
+
+    let props = sim.agent_properties(map, agent);
+    txt.add_line(format!(
+        "Crossed {} of {}. Waiting here for {}, {} in total",
+        props.dist_crossed.to_string(&app.opts.units),
+        props.total_dist.to_string(&app.opts.units),
+        props.waiting_here,
+        props.total_waiting
+    ));
+    if let AgentID::Car(id) = agent {
+        txt.add_line(Line(format!("Internal state: {}", sim.debug_car_ui(id))).secondary());
+    }
+
+    if let Some(path) = sim.get_path(agent) {
+        txt.add_line(format!("Route to {}:", path.get_req().end));
+        for step in path.get_steps().iter().take(ROUTE_PREVIEW) {
+            txt.add_line(Line(format!("- {}", step.as_traversable())).secondary());
+        }
+        if path.get_steps().len() > ROUTE_PREVIEW {
+            txt.add_line(
+                Line(format!(
+                    "... and {} more steps",
+                    path.get_steps().len() - ROUTE_PREVIEW
+                ))
+                .secondary(),
+            );
+        }
+    }
+    txt
+}
+
+/// What breakpoint can be set on an object? None if it's not something the simulation can break
+/// on.
+pub fn breakpoint_for(id: &ID) -> Option<Breakpoint> {
+    match id {
+        ID::Car(_) | ID::Pedestrian(_) => id.agent_id().map(Breakpoint::Agent),
+        ID::Lane(l) => Some(Breakpoint::Road(l.road)),
+        ID::Intersection(i) => Some(Breakpoint::Intersection(*i)),
+        _ => None,
+    }
+}
@@ -31,10 +31,12 @@ pub use self::routes::PathCostDebugger;
 
 mod blocked_by;
 mod blockfinder;
+pub mod breakpoints;
 mod floodfill;
 mod objects;
 pub mod path_counter;
 mod polygons;
+mod quality;
 mod routes;
 mod select_roads;
 mod uber_turns;
@@ -165,6 +167,10 @@ impl DebugMode {
                         .btn_outline
                         .text("find bad intersection polygons")
                         .build_def(ctx),
+                    ctx.style()
+                        .btn_outline
+                        .text("data quality report")
+                        .build_def(ctx),
                 ]),
                 Text::from_all(vec![
                     Line("Hold "),
@@ -401,6 +407,9 @@ impl State<App> for DebugMode {
                     });
                     self.reset_info(ctx);
                 }
+                "data quality report" => {
+                    return Transition::Push(quality::DataQuality::load_state(ctx, app));
+                }
                 _ => unreachable!(),
             },
             Outcome::Changed(_) => {
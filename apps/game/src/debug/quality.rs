@@ -0,0 +1,219 @@
+use geom::Distance;
+use map_model::{MapEdits, Road};
+use raw_map::quality::{find_quality_issues, AutoFix, IssueKind, QualityIssue};
+use raw_map::RawMap;
+use widgetry::tools::{FileLoader, PopupMsg};
+use widgetry::{
+    Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::edit::apply_map_edits;
+
+/// Check the RawMap this map was built from for suspicious data. Only speed limits can be fixed
+/// here, through map edits; other fixes change the RawMap, so they need map_editor.
+pub struct DataQuality {
+    panel: Panel,
+    issues: Vec<QualityIssue>,
+    current: usize,
+    draw: Drawable,
+    highlight: Drawable,
+}
+
+impl DataQuality {
+    pub fn load_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        FileLoader::<App, RawMap>::new_state(
+            ctx,
+            abstio::path_raw_map(app.primary.map.get_name()),
+            Box::new(|ctx, app, timer, raw| match raw {
+                Ok(raw) => {
+                    let issues = find_quality_issues(&raw, timer);
+                    Transition::Replace(DataQuality::new_state(ctx, app, issues))
+                }
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Error",
+                    vec![format!(
+                        "Couldn't load the RawMap to check data quality: {}",
+                        err
+                    )],
+                )),
+            }),
+        )
+    }
+
+    fn new_state(ctx: &mut EventCtx, app: &App, issues: Vec<QualityIssue>) -> Box<dyn State<App>> {
+        let mut txt = Text::new();
+        for kind in IssueKind::all() {
+            txt.add_line(format!(
+                "{} {}",
+                issues.iter().filter(|i| i.kind == kind).count(),
+                kind.plural()
+            ));
+        }
+
+        let mut batch = GeomBatch::new();
+        for issue in &issues {
+            batch.push(Color::RED.alpha(0.5), issue.polygon.clone());
+        }
+
+        let mut state = DataQuality {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Data quality").small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                format!(
+                    "Checked the RawMap for {}",
+                    app.primary.map.get_name().describe()
+                )
+                .text_widget(ctx),
+                txt.into_widget(ctx).section(ctx),
+                Widget::placeholder(ctx, "current issue"),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            issues,
+            current: 0,
+            draw: ctx.upload(batch),
+            highlight: Drawable::empty(ctx),
+        };
+        state.update(ctx);
+        Box::new(state)
+    }
+
+    fn update(&mut self, ctx: &mut EventCtx) {
+        if self.issues.is_empty() {
+            let widget = "No problems found".text_widget(ctx);
+            self.panel.replace(ctx, "current issue", widget);
+            self.highlight = Drawable::empty(ctx);
+            return;
+        }
+
+        let issue = &self.issues[self.current];
+        let mut txt = Text::from(Line(format!("{:?}", issue.kind)));
+        txt.add_line(Line(&issue.details).secondary());
+        let fix = match issue.fix {
+            Some(AutoFix::SetMaxSpeed(_, ref speed)) => ctx
+                .style()
+                .btn_solid_primary
+                .text(format!("Set the speed limit to {}", speed))
+                .build_widget(ctx, "fix"),
+            Some(ref fix) => {
+                txt.add_line(Line(format!("Use map_editor to {}", fix)).secondary());
+                Widget::nothing()
+            }
+            None => Widget::nothing(),
+        };
+
+        let widget = Widget::col(vec![
+            Widget::row(vec![
+                ctx.style()
+                    .btn_prev()
+                    .disabled(self.current == 0)
+                    .hotkey(Key::LeftArrow)
+                    .build_widget(ctx, "previous issue"),
+                Text::from(Line(format!("{}/{}", self.current + 1, self.issues.len())).secondary())
+                    .into_widget(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_next()
+                    .disabled(self.current == self.issues.len() - 1)
+                    .hotkey(Key::RightArrow)
+                    .build_widget(ctx, "next issue"),
+            ]),
+            txt.into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("zoom to")
+                    .hotkey(Key::Z)
+                    .build_def(ctx),
+                fix,
+            ]),
+        ])
+        .section(ctx);
+        self.panel.replace(ctx, "current issue", widget);
+
+        let mut batch = GeomBatch::new();
+        batch.push(Color::CYAN, issue.polygon.to_outline(Distance::meters(1.0)));
+        self.highlight = ctx.upload(batch);
+    }
+
+    fn fix_speed_limit(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        let (ways, speed) = match self.issues[self.current].fix {
+            Some(AutoFix::SetMaxSpeed(ref ways, ref speed)) => (ways.clone(), speed.clone()),
+            _ => unreachable!(),
+        };
+        let speed_limit = match Road::parse_speed_limit(&speed) {
+            Some(x) => x,
+            None => {
+                return;
+            }
+        };
+
+        let map = &app.primary.map;
+        let mut edits: MapEdits = map.get_edits().clone();
+        for r in map.all_roads() {
+            if ways.contains(&r.orig_id.osm_way_id) {
+                edits.commands.push(map.edit_road_cmd(r.id, |new| {
+                    new.speed_limit = speed_limit;
+                }));
+            }
+        }
+        apply_map_edits(ctx, app, edits);
+
+        self.issues.remove(self.current);
+        self.current = self.current.min(self.issues.len().saturating_sub(1));
+        self.update(ctx);
+    }
+}
+
+impl State<App> for DataQuality {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "previous issue" => {
+                    self.current -= 1;
+                    self.update(ctx);
+                }
+                "next issue" => {
+                    self.current += 1;
+                    self.update(ctx);
+                }
+                "zoom to" => {
+                    return Transition::Push(Warping::new_state(
+                        ctx,
+                        self.issues[self.current].polygon.center(),
+                        Some(10.0),
+                        None,
+                        &mut app.primary,
+                    ));
+                }
+                "fix" => {
+                    self.fix_speed_limit(ctx, app);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        g.redraw(&self.highlight);
+        self.panel.draw(g);
+    }
+}
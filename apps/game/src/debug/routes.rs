@@ -6,8 +6,8 @@ use map_gui::colors::ColorSchemeChoice;
 use map_gui::tools::{cmp_count, ColorNetwork};
 use map_gui::AppLike;
 use map_model::{
-    DirectedRoadID, Direction, PathConstraints, PathRequest, PathStepV2, Pathfinder, RoadID,
-    RoutingParams, NORMAL_LANE_THICKNESS,
+    AlternativeRoutes, DirectedRoadID, Direction, PathConstraints, PathRequest, PathStepV2,
+    Pathfinder, RoadID, RoutingParams, NORMAL_LANE_THICKNESS,
 };
 use synthpop::{TripEndpoint, TripMode};
 use widgetry::mapspace::ToggleZoomed;
@@ -20,6 +20,14 @@ use crate::app::{App, Transition};
 use crate::common::CommonState;
 use crate::ID;
 
+const ALTERNATIVE_COLORS: [Color; 5] = [
+    Color::PURPLE,
+    Color::ORANGE,
+    Color::CYAN,
+    Color::PINK,
+    Color::YELLOW,
+];
+
 /// See how live-tuned routing parameters affect a single request.
 pub struct RouteExplorer {
     panel: Panel,
@@ -45,6 +53,11 @@ impl RouteExplorer {
                     .build_def(ctx),
                 params_to_controls(ctx, TripMode::Bike, app.primary.map.routing_params())
                     .named("params"),
+                Widget::row(vec![
+                    "Alternative routes:".text_widget(ctx).margin_right(20),
+                    Spinner::widget(ctx, "alternatives", (1, ALTERNATIVE_COLORS.len()), 1, 1),
+                ]),
+                Text::new().into_widget(ctx).named("alternatives info"),
             ]))
             .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
             .build(ctx),
@@ -53,26 +66,71 @@ impl RouteExplorer {
 
     fn recalc_paths(&mut self, ctx: &mut EventCtx, app: &App) {
         let (mode, params) = controls_to_params(&self.panel);
+        let count: usize = self.panel.spinner("alternatives");
+        let mut txt = Text::new();
 
         if let Some((ref goal, _, ref mut preview)) = self.goal {
             *preview = Drawable::empty(ctx);
-            if let Some(polygon) = TripEndpoint::path_req(self.start, *goal, mode, &app.primary.map)
-                .and_then(|req| {
-                    Pathfinder::new_dijkstra(
-                        &app.primary.map,
-                        params,
-                        vec![req.constraints],
-                        &mut Timer::throwaway(),
-                    )
-                    .pathfind_v2(req, &app.primary.map)
-                })
-                .and_then(|path| path.into_v1(&app.primary.map).ok())
-                .and_then(|path| path.trace(&app.primary.map))
-                .map(|pl| pl.make_polygons(NORMAL_LANE_THICKNESS))
-            {
-                *preview = GeomBatch::from(vec![(Color::PURPLE, polygon)]).upload(ctx);
+            let map = &app.primary.map;
+            if count == 1 {
+                if let Some(polygon) = TripEndpoint::path_req(self.start, *goal, mode, map)
+                    .and_then(|req| {
+                        Pathfinder::new_dijkstra(
+                            map,
+                            params,
+                            vec![req.constraints],
+                            &mut Timer::throwaway(),
+                        )
+                        .pathfind_v2(req, map)
+                    })
+                    .and_then(|path| path.into_v1(map).ok())
+                    .and_then(|path| path.trace(map))
+                    .map(|pl| pl.make_polygons(NORMAL_LANE_THICKNESS))
+                {
+                    *preview = GeomBatch::from(vec![(Color::PURPLE, polygon)]).upload(ctx);
+                }
+            } else if let Some(req) = TripEndpoint::path_req(self.start, *goal, mode, map) {
+                let opts = AlternativeRoutes {
+                    count,
+                    ..Default::default()
+                };
+                match opts.find(req, &params, map) {
+                    Ok(paths) => {
+                        let mut batch = GeomBatch::new();
+                        // Draw the best route last, so it's on top
+                        for (path, color) in paths.iter().zip(ALTERNATIVE_COLORS).rev() {
+                            if let Some(pl) = path
+                                .clone()
+                                .into_v1(map)
+                                .ok()
+                                .and_then(|path| path.trace(map))
+                            {
+                                batch.push(
+                                    color.alpha(0.8),
+                                    pl.make_polygons(NORMAL_LANE_THICKNESS),
+                                );
+                            }
+                        }
+                        *preview = batch.upload(ctx);
+                        for (idx, (path, color)) in paths.iter().zip(ALTERNATIVE_COLORS).enumerate()
+                        {
+                            txt.add_line(
+                                Line(format!("Route {}: {}", idx + 1, path.get_cost())).fg(color),
+                            );
+                        }
+                        if paths.len() < count {
+                            txt.add_line(Line("No more reasonable alternatives found").secondary());
+                        }
+                    }
+                    Err(err) => {
+                        txt.add_line(Line(err.to_string()).fg(Color::RED));
+                    }
+                }
             }
         }
+
+        let info = txt.into_widget(ctx);
+        self.panel.replace(ctx, "alternatives info", info);
     }
 }
 
@@ -278,6 +336,10 @@ fn params_to_controls(ctx: &mut EventCtx, mode: TripMode, params: &RoutingParams
                 0.1,
             ),
         ]));
+        rows.push(Widget::row(vec![
+            "Avoid unlit roads:".text_widget(ctx).margin_right(20),
+            Spinner::f64_widget(ctx, "avoid_unlit", (0.0, 2.0), params.avoid_unlit, 0.1),
+        ]));
     }
     Widget::col(rows)
 }
@@ -298,6 +360,7 @@ fn controls_to_params(panel: &Panel) -> (TripMode, RoutingParams) {
     params.avoid_steep_incline_penalty =
         panel.spinner::<RoundedF64>("avoid_steep_incline_penalty").0;
     params.avoid_high_stress = panel.spinner::<RoundedF64>("avoid_high_stress").0;
+    params.avoid_unlit = panel.spinner::<RoundedF64>("avoid_unlit").0;
     (TripMode::Bike, params)
 }
 
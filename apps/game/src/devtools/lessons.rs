@@ -0,0 +1,493 @@
+use geom::{Duration, Time};
+use widgetry::tools::{ChooseSomething, PopupMsg, PromptInput};
+use widgetry::{
+    lctrl, Choice, DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Panel, SimpleState, Spinner, State, TextBox, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::{
+    CameraTarget, CompletionTrigger, GameplayMode, Highlight, Lesson, LessonStage, SandboxMode,
+    TutorialPointer,
+};
+
+/// Author guided lessons for the current map. Each stage shows some messages, optionally moves
+/// the camera and points at something, then waits for the player to do something.
+pub struct LessonEditor {
+    panel: Panel,
+    lesson: Lesson,
+    dirty: bool,
+}
+
+impl LessonEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        Self::from_lesson(ctx, app, Lesson::new(app.primary.map.get_name().clone()))
+    }
+
+    fn from_lesson(ctx: &mut EventCtx, app: &App, lesson: Lesson) -> Box<dyn State<App>> {
+        let mut state = LessonEditor {
+            panel: Panel::empty(ctx),
+            lesson,
+            dirty: false,
+        };
+        state.rebuild_panel(ctx, app);
+        Box::new(state)
+    }
+
+    fn rebuild_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut scenarios = vec![Choice::new("no traffic", None)];
+        for name in abstio::list_all_objects(abstio::path_all_scenarios(app.primary.map.get_name()))
+        {
+            scenarios.push(Choice::new(name.clone(), Some(name)));
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Lesson editor").small_heading().into_widget(ctx),
+                Widget::vert_separator(ctx, 30.0),
+                ctx.style()
+                    .btn_outline
+                    .popup(&self.lesson.name)
+                    .hotkey(lctrl(Key::L))
+                    .build_widget(ctx, "load"),
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/save.svg")
+                    .hotkey(lctrl(Key::S))
+                    .disabled(!self.dirty)
+                    .build_widget(ctx, "save"),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::row(vec![
+                "Traffic:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(ctx, "scenario", self.lesson.scenario.clone(), scenarios),
+            ]),
+        ];
+        if self.lesson.stages.is_empty() {
+            col.push("This lesson has no stages yet".text_widget(ctx));
+        }
+        for (idx, stage) in self.lesson.stages.iter().enumerate() {
+            col.push(Widget::row(vec![
+                format!("{}) {}", idx + 1, stage.title)
+                    .text_widget(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/pencil.svg")
+                    .build_widget(ctx, format!("edit stage {}", idx))
+                    .align_right(),
+                ctx.style()
+                    .btn_plain_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("delete stage {}", idx)),
+            ]));
+        }
+        col.push(Widget::row(vec![
+            ctx.style()
+                .btn_outline
+                .text("add stage")
+                .hotkey(Key::A)
+                .build_def(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("preview")
+                .hotkey(Key::P)
+                .disabled(self.lesson.stages.is_empty())
+                .build_def(ctx),
+        ]));
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+            .build(ctx);
+    }
+
+    fn save(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.lesson.save();
+        self.dirty = false;
+        self.rebuild_panel(ctx, app);
+    }
+}
+
+impl State<App> for LessonEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "save" => {
+                    if self.lesson.name != "new lesson" {
+                        self.save(ctx, app);
+                        return Transition::Keep;
+                    }
+                    return Transition::Push(PromptInput::new_state(
+                        ctx,
+                        "Name this lesson",
+                        String::new(),
+                        Box::new(|name, _, _| {
+                            Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::ModifyState(Box::new(move |state, ctx, app| {
+                                    let editor = state.downcast_mut::<LessonEditor>().unwrap();
+                                    editor.lesson.name = name;
+                                    editor.save(ctx, app);
+                                })),
+                            ])
+                        }),
+                    ));
+                }
+                "load" => {
+                    let map_name = app.primary.map.get_name().clone();
+                    let mut choices = Vec::new();
+                    for name in Lesson::list_all(&map_name) {
+                        if name == self.lesson.name {
+                            continue;
+                        }
+                        match Lesson::load(&map_name, &name) {
+                            Ok(lesson) => choices.push(Choice::new(name, lesson)),
+                            Err(err) => warn!("Couldn't load lesson {}: {}", name, err),
+                        }
+                    }
+                    choices.push(Choice::new("new lesson", Lesson::new(map_name)));
+
+                    return Transition::Push(ChooseSomething::new_state(
+                        ctx,
+                        "Load lesson",
+                        choices,
+                        Box::new(|lesson, ctx, app| {
+                            Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::Replace(LessonEditor::from_lesson(ctx, app, lesson)),
+                            ])
+                        }),
+                    ));
+                }
+                "add stage" => {
+                    self.lesson.stages.push(LessonStage {
+                        title: format!("Stage {}", self.lesson.stages.len() + 1),
+                        messages: Vec::new(),
+                        camera: None,
+                        highlight: None,
+                        trigger: CompletionTrigger::ReadMessages,
+                    });
+                    self.dirty = true;
+                    self.rebuild_panel(ctx, app);
+                    let idx = self.lesson.stages.len() - 1;
+                    return Transition::Push(StageEditor::new_state(
+                        ctx,
+                        idx,
+                        &self.lesson.stages[idx],
+                    ));
+                }
+                "preview" => {
+                    if self.lesson.name == "new lesson" {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Save first",
+                            vec!["Name and save this lesson before previewing it"],
+                        ));
+                    }
+                    self.save(ctx, app);
+                    return Transition::Push(SandboxMode::simple_new(
+                        app,
+                        GameplayMode::Lesson(
+                            self.lesson.map_name.clone(),
+                            self.lesson.name.clone(),
+                            TutorialPointer::new(0, 0),
+                        ),
+                    ));
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("edit stage ") {
+                        let idx = idx.parse::<usize>().unwrap();
+                        return Transition::Push(StageEditor::new_state(
+                            ctx,
+                            idx,
+                            &self.lesson.stages[idx],
+                        ));
+                    }
+                    if let Some(idx) = x.strip_prefix("delete stage ") {
+                        self.lesson.stages.remove(idx.parse::<usize>().unwrap());
+                        self.dirty = true;
+                        self.rebuild_panel(ctx, app);
+                        return Transition::Keep;
+                    }
+                    unreachable!()
+                }
+            },
+            Outcome::Changed(_) => {
+                self.lesson.scenario = self.panel.dropdown_value("scenario");
+                self.dirty = true;
+                self.rebuild_panel(ctx, app);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum CameraChoice {
+    DontMove,
+    KeepPrevious,
+    CurrentView,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum HighlightChoice {
+    Nothing,
+    KeepPreviousPoint,
+    CurrentView,
+    TimeControls,
+    ToolPanel,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum TriggerChoice {
+    ReadMessages,
+    ReachTime,
+    KeepPreviousObject,
+    InspectCurrentView,
+    EditMap,
+    FinishSimulation,
+}
+
+struct StageEditor {
+    idx: usize,
+    stage: LessonStage,
+}
+
+impl StageEditor {
+    fn new_state(ctx: &mut EventCtx, idx: usize, stage: &LessonStage) -> Box<dyn State<App>> {
+        let panel = StageEditor::make_panel(ctx, stage, stage.messages.len() + 1);
+        <dyn SimpleState<_>>::new_state(
+            panel,
+            Box::new(StageEditor {
+                idx,
+                stage: stage.clone(),
+            }),
+        )
+    }
+
+    fn make_panel(ctx: &mut EventCtx, stage: &LessonStage, num_messages: usize) -> Panel {
+        let mut camera_choices = vec![Choice::new("don't move", CameraChoice::DontMove)];
+        if stage.camera.is_some() {
+            camera_choices.push(Choice::new(
+                "keep the previous view",
+                CameraChoice::KeepPrevious,
+            ));
+        }
+        camera_choices.push(Choice::new("use the current view", CameraChoice::CurrentView));
+        let camera_default = if stage.camera.is_some() {
+            CameraChoice::KeepPrevious
+        } else {
+            CameraChoice::DontMove
+        };
+
+        let mut highlight_choices = vec![Choice::new("nothing", HighlightChoice::Nothing)];
+        if let Some(Highlight::MapPoint(_)) = stage.highlight {
+            highlight_choices.push(Choice::new(
+                "the previous point",
+                HighlightChoice::KeepPreviousPoint,
+            ));
+        }
+        highlight_choices.extend(vec![
+            Choice::new(
+                "the center of the current view",
+                HighlightChoice::CurrentView,
+            ),
+            Choice::new("the time controls", HighlightChoice::TimeControls),
+            Choice::new("the tool panel", HighlightChoice::ToolPanel),
+        ]);
+        let highlight_default = match stage.highlight {
+            None => HighlightChoice::Nothing,
+            Some(Highlight::MapPoint(_)) => HighlightChoice::KeepPreviousPoint,
+            Some(Highlight::TimeControls) => HighlightChoice::TimeControls,
+            Some(Highlight::ToolPanel) => HighlightChoice::ToolPanel,
+        };
+
+        let mut trigger_choices = vec![
+            Choice::new("read the messages", TriggerChoice::ReadMessages),
+            Choice::new("wait until a time", TriggerChoice::ReachTime),
+        ];
+        if let CompletionTrigger::InspectObject(_) = stage.trigger {
+            trigger_choices.push(Choice::new(
+                "inspect the previous object",
+                TriggerChoice::KeepPreviousObject,
+            ));
+        }
+        trigger_choices.extend(vec![
+            Choice::new(
+                "inspect the object at the center of the current view",
+                TriggerChoice::InspectCurrentView,
+            ),
+            Choice::new("edit the map", TriggerChoice::EditMap),
+            Choice::new("finish the simulation", TriggerChoice::FinishSimulation),
+        ]);
+        let (trigger_default, time) = match stage.trigger {
+            CompletionTrigger::ReadMessages => (TriggerChoice::ReadMessages, Duration::hours(1)),
+            CompletionTrigger::ReachTime(t) => (TriggerChoice::ReachTime, t - Time::START_OF_DAY),
+            CompletionTrigger::InspectObject(_) => {
+                (TriggerChoice::KeepPreviousObject, Duration::hours(1))
+            }
+            CompletionTrigger::EditMap => (TriggerChoice::EditMap, Duration::hours(1)),
+            CompletionTrigger::FinishSimulation => {
+                (TriggerChoice::FinishSimulation, Duration::hours(1))
+            }
+        };
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Editing stage").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            "Move the map before opening this to pick the camera or highlighted point"
+                .text_widget(ctx),
+            Widget::row(vec![
+                "Title:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(ctx, "title", stage.title.clone()),
+            ]),
+        ];
+        for idx in 0..num_messages {
+            col.push(Widget::row(vec![
+                format!("Message {}:", idx + 1)
+                    .text_widget(ctx)
+                    .centered_vert(),
+                TextBox::default_widget(
+                    ctx,
+                    format!("message {}", idx),
+                    stage.messages.get(idx).cloned().unwrap_or_default(),
+                ),
+            ]));
+        }
+        col.push(
+            ctx.style()
+                .btn_outline
+                .text("add another message")
+                .build_def(ctx),
+        );
+        col.push(Widget::row(vec![
+            "Camera:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(ctx, "camera", camera_default, camera_choices),
+        ]));
+        col.push(Widget::row(vec![
+            "Point at:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(ctx, "highlight", highlight_default, highlight_choices),
+        ]));
+        col.push(Widget::row(vec![
+            "To finish:".text_widget(ctx).centered_vert(),
+            Widget::dropdown(ctx, "trigger", trigger_default, trigger_choices),
+        ]));
+        col.push(Widget::row(vec![
+            "Time to wait until:".text_widget(ctx).centered_vert(),
+            Spinner::widget(
+                ctx,
+                "time",
+                (Duration::ZERO, Duration::hours(24)),
+                time,
+                Duration::minutes(15),
+            ),
+        ]));
+        col.push(
+            ctx.style()
+                .btn_solid_primary
+                .text("confirm")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+        );
+
+        Panel::new_builder(Widget::col(col)).build(ctx)
+    }
+
+    fn messages(panel: &Panel) -> Vec<String> {
+        let mut messages = Vec::new();
+        let mut idx = 0;
+        while panel
+            .maybe_find_widget(&format!("message {}", idx))
+            .is_some()
+        {
+            let msg = panel.text_box(&format!("message {}", idx));
+            if !msg.is_empty() {
+                messages.push(msg);
+            }
+            idx += 1;
+        }
+        messages
+    }
+}
+
+impl SimpleState<App> for StageEditor {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            "add another message" => {
+                self.stage.title = panel.text_box("title");
+                self.stage.messages = StageEditor::messages(panel);
+                *panel = StageEditor::make_panel(ctx, &self.stage, self.stage.messages.len() + 2);
+                Transition::Keep
+            }
+            "confirm" => {
+                let gps_bounds = app.primary.map.get_gps_bounds();
+                let center = ctx.canvas.center_to_map_pt().to_gps(gps_bounds);
+
+                let mut stage = self.stage.clone();
+                stage.title = panel.text_box("title");
+                stage.messages = StageEditor::messages(panel);
+                stage.camera = match panel.dropdown_value("camera") {
+                    CameraChoice::DontMove => None,
+                    CameraChoice::KeepPrevious => self.stage.camera,
+                    CameraChoice::CurrentView => Some(CameraTarget {
+                        center,
+                        zoom: ctx.canvas.cam_zoom,
+                    }),
+                };
+                stage.highlight = match panel.dropdown_value("highlight") {
+                    HighlightChoice::Nothing => None,
+                    HighlightChoice::KeepPreviousPoint => self.stage.highlight,
+                    HighlightChoice::CurrentView => Some(Highlight::MapPoint(center)),
+                    HighlightChoice::TimeControls => Some(Highlight::TimeControls),
+                    HighlightChoice::ToolPanel => Some(Highlight::ToolPanel),
+                };
+                stage.trigger = match panel.dropdown_value("trigger") {
+                    TriggerChoice::ReadMessages => CompletionTrigger::ReadMessages,
+                    TriggerChoice::ReachTime => CompletionTrigger::ReachTime(
+                        Time::START_OF_DAY + panel.spinner::<Duration>("time"),
+                    ),
+                    TriggerChoice::KeepPreviousObject => self.stage.trigger,
+                    TriggerChoice::InspectCurrentView => CompletionTrigger::InspectObject(center),
+                    TriggerChoice::EditMap => CompletionTrigger::EditMap,
+                    TriggerChoice::FinishSimulation => CompletionTrigger::FinishSimulation,
+                };
+
+                let idx = self.idx;
+                Transition::Multi(vec![
+                    Transition::Pop,
+                    Transition::ModifyState(Box::new(move |state, ctx, app| {
+                        let editor = state.downcast_mut::<LessonEditor>().unwrap();
+                        editor.lesson.stages[idx] = stage;
+                        editor.dirty = true;
+                        editor.rebuild_panel(ctx, app);
+                    })),
+                ])
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
@@ -15,9 +15,12 @@ mod collisions;
 pub mod compare_counts;
 mod destinations;
 pub mod kml;
+mod lessons;
 mod polygon;
 mod scenario;
+mod scenario_editor;
 mod story;
+mod study_area;
 
 pub struct DevToolsMode;
 
@@ -47,6 +50,11 @@ impl DevToolsMode {
                     .text("load scenario")
                     .hotkey(Key::W)
                     .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("edit scenario")
+                    .hotkey(Key::T)
+                    .build_def(ctx),
                 ctx.style()
                     .btn_outline
                     .text("view KML")
@@ -57,6 +65,11 @@ impl DevToolsMode {
                     .text("story maps")
                     .hotkey(Key::S)
                     .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("lessons")
+                    .hotkey(Key::L)
+                    .build_def(ctx),
                 if abstio::file_exists(app.primary.map.get_city_name().input_path("collisions.bin"))
                 {
                     ctx.style()
@@ -82,6 +95,14 @@ impl DevToolsMode {
                 } else {
                     Widget::nothing()
                 },
+                if cfg!(not(target_arch = "wasm32")) {
+                    ctx.style()
+                        .btn_solid_primary
+                        .text("Crop to a study area")
+                        .build_def(ctx)
+                } else {
+                    Widget::nothing()
+                },
                 if abstio::file_exists(abstio::path_raw_map(app.primary.map.get_name())) {
                     ctx.style()
                         .btn_solid_primary
@@ -157,8 +178,35 @@ impl SimpleState<App> for DevToolsMode {
                     Transition::Replace(scenario::ScenarioManager::new_state(scenario, ctx, app))
                 }),
             )),
+            "edit scenario" => {
+                let mut choices = vec![Choice::new("new scenario", None)];
+                for name in
+                    abstio::list_all_objects(abstio::path_all_scenarios(app.primary.map.get_name()))
+                {
+                    choices.push(Choice::new(name.clone(), Some(name)));
+                }
+                Transition::Push(ChooseSomething::new_state(
+                    ctx,
+                    "Edit which scenario?",
+                    choices,
+                    Box::new(|name, ctx, app| {
+                        let scenario = if let Some(name) = name {
+                            abstio::read_binary(
+                                abstio::path_scenario(app.primary.map.get_name(), &name),
+                                &mut Timer::throwaway(),
+                            )
+                        } else {
+                            synthpop::Scenario::empty(&app.primary.map, "new scenario")
+                        };
+                        Transition::Replace(scenario_editor::ScenarioEditor::new_state(
+                            ctx, app, scenario,
+                        ))
+                    }),
+                ))
+            }
             "view KML" => Transition::Push(kml::ViewKML::new_state(ctx, app, None)),
             "story maps" => Transition::Push(story::StoryMapEditor::new_state(ctx)),
+            "lessons" => Transition::Push(lessons::LessonEditor::new_state(ctx, app)),
             "collisions" => Transition::Push(collisions::CollisionsViewer::new_state(ctx, app)),
             "OpenStreetMap viewer" => {
                 map_gui::tools::Executable::OSMViewer.replace_process(ctx, app, vec![])
@@ -166,6 +214,9 @@ impl SimpleState<App> for DevToolsMode {
             "Parking mapper" => {
                 map_gui::tools::Executable::ParkingMapper.replace_process(ctx, app, vec![])
             }
+            "Crop to a study area" => {
+                Transition::Push(study_area::CropToStudyArea::new_state(ctx, app))
+            }
             "RawMap editor" => {
                 map_gui::tools::Executable::RawMapEditor.replace_process(ctx, app, vec![])
             }
@@ -0,0 +1,468 @@
+use geom::{ArrowCap, Distance, Duration, PolyLine, Time};
+use map_model::Map;
+use synthpop::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+use widgetry::tools::{PopupMsg, PromptInput};
+use widgetry::{
+    lctrl, Choice, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Panel, SimpleState, Spinner, State, Text, TextExt,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+use crate::ID;
+
+/// Hand-craft a small scenario by clicking origins and destinations on the map. Useful for
+/// teaching and for studying a few trips in detail.
+pub struct ScenarioEditor {
+    panel: Panel,
+    /// Everything about the scenario besides the flows, including people from a loaded scenario
+    /// with more than one trip. Those are kept as they are.
+    base: Scenario,
+    flows: Vec<Flow>,
+    picking: Picking,
+    draw_flows: Drawable,
+    dirty: bool,
+}
+
+/// Some number of identical people, each taking one trip, departing at regular intervals
+#[derive(Clone)]
+struct Flow {
+    from: TripEndpoint,
+    to: TripEndpoint,
+    mode: TripMode,
+    purpose: TripPurpose,
+    depart: Time,
+    count: usize,
+    every: Duration,
+}
+
+#[derive(Clone, Copy)]
+enum Picking {
+    Nothing,
+    Origin,
+    Destination(TripEndpoint),
+}
+
+impl ScenarioEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, mut base: Scenario) -> Box<dyn State<App>> {
+        let mut flows = Vec::new();
+        let mut other_people = Vec::new();
+        for person in base.people.drain(..) {
+            if person.trips.len() == 1 {
+                let trip = &person.trips[0];
+                flows.push(Flow {
+                    from: trip.origin,
+                    to: trip.destination,
+                    mode: trip.mode,
+                    purpose: trip.purpose,
+                    depart: trip.depart,
+                    count: 1,
+                    every: Duration::ZERO,
+                });
+            } else {
+                other_people.push(person);
+            }
+        }
+        base.people = other_people;
+
+        let mut state = ScenarioEditor {
+            panel: Panel::empty(ctx),
+            base,
+            flows,
+            picking: Picking::Nothing,
+            draw_flows: Drawable::empty(ctx),
+            dirty: false,
+        };
+        state.rebuild(ctx, app);
+        Box::new(state)
+    }
+
+    fn rebuild(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let num_trips: usize = self.flows.iter().map(|f| f.count).sum();
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line(format!("Scenario editor: {}", self.base.scenario_name))
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/save.svg")
+                    .hotkey(lctrl(Key::S))
+                    .disabled(!self.dirty)
+                    .build_widget(ctx, "save"),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            format!("{} flows, {} trips", self.flows.len(), num_trips).text_widget(ctx),
+        ];
+        if !self.base.people.is_empty() {
+            col.push(
+                Text::from(
+                    Line(format!(
+                        "{} people with several trips are kept as they are",
+                        self.base.people.len()
+                    ))
+                    .secondary(),
+                )
+                .into_widget(ctx),
+            );
+        }
+        for (idx, flow) in self.flows.iter().enumerate() {
+            col.push(Widget::row(vec![
+                flow.describe(map).text_widget(ctx).centered_vert(),
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/pencil.svg")
+                    .build_widget(ctx, format!("edit flow {}", idx))
+                    .align_right(),
+                ctx.style()
+                    .btn_plain_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("delete flow {}", idx)),
+            ]));
+        }
+        col.push(match self.picking {
+            Picking::Nothing => ctx
+                .style()
+                .btn_outline
+                .text("add trips")
+                .hotkey(Key::A)
+                .build_def(ctx),
+            Picking::Origin | Picking::Destination(_) => Widget::row(vec![
+                if let Picking::Origin = self.picking {
+                    "Click a building or border to start from"
+                } else {
+                    "Click a building or border to go to"
+                }
+                .text_widget(ctx)
+                .centered_vert(),
+                ctx.style()
+                    .btn_plain
+                    .text("cancel")
+                    .hotkey(Key::Escape)
+                    .build_def(ctx),
+            ]),
+        });
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx);
+
+        let mut batch = GeomBatch::new();
+        for flow in &self.flows {
+            if let Ok(pl) = PolyLine::new(vec![flow.from.pt(map), flow.to.pt(map)]) {
+                batch.push(
+                    Color::PURPLE.alpha(0.8),
+                    pl.make_arrow(Distance::meters(5.0), ArrowCap::Triangle),
+                );
+            }
+        }
+        self.draw_flows = ctx.upload(batch);
+    }
+
+    fn to_scenario(&self) -> Scenario {
+        let mut scenario = self.base.clone();
+        for flow in &self.flows {
+            for idx in 0..flow.count {
+                scenario.people.push(PersonSpec {
+                    orig_id: None,
+                    trips: vec![IndividTrip::new(
+                        flow.depart + flow.every * (idx as f64),
+                        flow.purpose,
+                        flow.from,
+                        flow.to,
+                        flow.mode,
+                    )],
+                    demographics: None,
+                });
+            }
+        }
+        scenario
+    }
+
+    fn save(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.to_scenario().save();
+        self.dirty = false;
+        self.rebuild(ctx, app);
+    }
+}
+
+impl State<App> for ScenarioEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    app.primary.current_selection = None;
+                    return Transition::Pop;
+                }
+                "save" => {
+                    if self.base.scenario_name != "new scenario" {
+                        self.save(ctx, app);
+                        return Transition::Keep;
+                    }
+                    return Transition::Push(PromptInput::new_state(
+                        ctx,
+                        "Name this scenario",
+                        String::new(),
+                        Box::new(|name, _, _| {
+                            Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::ModifyState(Box::new(move |state, ctx, app| {
+                                    let editor = state.downcast_mut::<ScenarioEditor>().unwrap();
+                                    editor.base.scenario_name = name;
+                                    editor.save(ctx, app);
+                                })),
+                            ])
+                        }),
+                    ));
+                }
+                "add trips" => {
+                    self.picking = Picking::Origin;
+                    self.rebuild(ctx, app);
+                }
+                "cancel" => {
+                    self.picking = Picking::Nothing;
+                    app.primary.current_selection = None;
+                    self.rebuild(ctx, app);
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("edit flow ") {
+                        let idx = idx.parse::<usize>().unwrap();
+                        return Transition::Push(FlowEditor::new_state(
+                            ctx,
+                            Some(idx),
+                            self.flows[idx].clone(),
+                        ));
+                    }
+                    if let Some(idx) = x.strip_prefix("delete flow ") {
+                        self.flows.remove(idx.parse::<usize>().unwrap());
+                        self.dirty = true;
+                        self.rebuild(ctx, app);
+                        return Transition::Keep;
+                    }
+                    unreachable!()
+                }
+            }
+        }
+
+        if let Picking::Nothing = self.picking {
+            return Transition::Keep;
+        }
+
+        if ctx.redo_mouseover() {
+            app.primary.current_selection = match app.mouseover_unzoomed_everything(ctx) {
+                Some(ID::Intersection(i)) if app.primary.map.get_i(i).is_border() => {
+                    Some(ID::Intersection(i))
+                }
+                Some(ID::Building(b)) => Some(ID::Building(b)),
+                _ => None,
+            };
+        }
+        let hovering = match app.primary.current_selection {
+            Some(ID::Intersection(i)) => TripEndpoint::Border(i),
+            Some(ID::Building(b)) => TripEndpoint::Building(b),
+            _ => {
+                return Transition::Keep;
+            }
+        };
+        match self.picking {
+            Picking::Origin => {
+                if app.per_obj.left_click(ctx, "start here") {
+                    self.picking = Picking::Destination(hovering);
+                    self.rebuild(ctx, app);
+                }
+            }
+            Picking::Destination(from) => {
+                if from != hovering && app.per_obj.left_click(ctx, "end here") {
+                    self.picking = Picking::Nothing;
+                    app.primary.current_selection = None;
+                    self.rebuild(ctx, app);
+                    return Transition::Push(FlowEditor::new_state(
+                        ctx,
+                        None,
+                        Flow {
+                            from,
+                            to: hovering,
+                            mode: TripMode::Drive,
+                            purpose: TripPurpose::Shopping,
+                            depart: Time::START_OF_DAY + Duration::hours(7),
+                            count: 1,
+                            every: Duration::minutes(1),
+                        },
+                    ));
+                }
+            }
+            Picking::Nothing => unreachable!(),
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw_flows);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+
+        if let Picking::Destination(from) = self.picking {
+            map_gui::tools::start_marker(g, from.pt(&app.primary.map), 2.0).draw(g);
+        }
+    }
+}
+
+impl Flow {
+    fn describe(&self, map: &Map) -> String {
+        let depart = if self.count == 1 {
+            format!("at {}", self.depart.ampm_tostring())
+        } else {
+            format!("from {}, every {}", self.depart.ampm_tostring(), self.every)
+        };
+        format!(
+            "{}x {} from {} to {}, {}",
+            self.count,
+            self.mode.verb(),
+            describe_endpoint(map, self.from),
+            describe_endpoint(map, self.to),
+            depart
+        )
+    }
+}
+
+fn describe_endpoint(map: &Map, endpt: TripEndpoint) -> String {
+    match endpt {
+        TripEndpoint::Building(b) => map.get_b(b).address.clone(),
+        TripEndpoint::Border(i) => format!("the border at {}", i),
+        TripEndpoint::SuddenlyAppear(_) => "somewhere on a road".to_string(),
+    }
+}
+
+/// Change the details of one flow. The origin and destination were already picked.
+struct FlowEditor {
+    idx: Option<usize>,
+    flow: Flow,
+}
+
+impl FlowEditor {
+    fn new_state(ctx: &mut EventCtx, idx: Option<usize>, flow: Flow) -> Box<dyn State<App>> {
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line(if idx.is_some() {
+                    "Edit trips"
+                } else {
+                    "New trips"
+                })
+                .small_heading()
+                .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::row(vec![
+                "Mode:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "mode",
+                    flow.mode,
+                    TripMode::all()
+                        .into_iter()
+                        .map(|m| Choice::new(m.ongoing_verb(), m))
+                        .collect(),
+                ),
+            ]),
+            Widget::row(vec![
+                "Departure time:".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "depart",
+                    (Duration::ZERO, Duration::hours(24)),
+                    flow.depart - Time::START_OF_DAY,
+                    Duration::minutes(5),
+                ),
+            ]),
+            Widget::row(vec![
+                "Number of trips:".text_widget(ctx).centered_vert(),
+                Spinner::widget(ctx, "count", (1, 1000), flow.count, 1),
+            ]),
+            Widget::row(vec![
+                "Time between departures:".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "every",
+                    (Duration::ZERO, Duration::hours(1)),
+                    flow.every,
+                    Duration::seconds(10.0),
+                ),
+            ]),
+            ctx.style()
+                .btn_solid_primary
+                .text("confirm")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+        ]))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(FlowEditor { idx, flow }))
+    }
+}
+
+impl SimpleState<App> for FlowEditor {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            "confirm" => {
+                let mut flow = self.flow.clone();
+                flow.mode = panel.dropdown_value("mode");
+                flow.depart = Time::START_OF_DAY + panel.spinner::<Duration>("depart");
+                flow.count = panel.spinner("count");
+                flow.every = panel.spinner("every");
+
+                let map = &app.primary.map;
+                if TripEndpoint::path_req(flow.from, flow.to, flow.mode, map)
+                    .and_then(|req| map.pathfind(req).ok())
+                    .is_none()
+                {
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Error",
+                        vec![format!(
+                            "Nobody can {} between these two places",
+                            flow.mode.verb()
+                        )],
+                    ));
+                }
+
+                let idx = self.idx;
+                Transition::Multi(vec![
+                    Transition::Pop,
+                    Transition::ModifyState(Box::new(move |state, ctx, app| {
+                        let editor = state.downcast_mut::<ScenarioEditor>().unwrap();
+                        if let Some(idx) = idx {
+                            editor.flows[idx] = flow;
+                        } else {
+                            editor.flows.push(flow);
+                        }
+                        editor.dirty = true;
+                        editor.rebuild(ctx, app);
+                    })),
+                ])
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        let map = &app.primary.map;
+        map_gui::tools::start_marker(g, self.flow.from.pt(map), 2.0).draw(g);
+        map_gui::tools::goal_marker(g, self.flow.to.pt(map), 2.0).draw(g);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
@@ -0,0 +1,172 @@
+use abstio::MapName;
+use geom::{Distance, Polygon};
+use synthpop::Scenario;
+use widgetry::tools::{Lasso, PopupMsg};
+use widgetry::{
+    Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line,
+    Outcome, Panel, State, Text, TextBox, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::pregame::TitleScreen;
+use crate::sandbox::{GameplayMode, SandboxMode};
+
+/// Draw a study area on a large map, then crop the map and its scenario to it, so that simulating
+/// changes with only local effects is much faster.
+pub struct CropToStudyArea {
+    panel: Panel,
+    lasso: Option<Lasso>,
+    boundary: Option<Polygon>,
+    draw_boundary: Drawable,
+}
+
+impl CropToStudyArea {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut state = CropToStudyArea {
+            panel: Panel::empty(ctx),
+            lasso: Some(Lasso::new(Distance::meters(5.0))),
+            boundary: None,
+            draw_boundary: Drawable::empty(ctx),
+        };
+        state.rebuild_panel(ctx, app);
+        Box::new(state)
+    }
+
+    fn rebuild_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut col = vec![Widget::row(vec![
+            Line("Crop to a study area")
+                .small_heading()
+                .into_widget(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+        if self.boundary.is_none() {
+            col.push(
+                Text::from(
+                    "Click and drag to draw the area to keep. Roads crossing it will end at new \
+                     borders, and only trips touching it will be kept.",
+                )
+                .wrap_to_pct(ctx, 30)
+                .into_widget(ctx),
+            );
+        } else {
+            col.push(Widget::row(vec![
+                "Name of the new map:".text_widget(ctx).centered_vert(),
+                TextBox::default_widget(
+                    ctx,
+                    "name",
+                    format!("{}_study_area", app.primary.map.get_name().map),
+                ),
+            ]));
+            col.push(Widget::row(vec![
+                ctx.style().btn_outline.text("redraw").build_def(ctx),
+                ctx.style().btn_solid_primary.text("crop").build_def(ctx),
+            ]));
+        }
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx);
+    }
+
+    fn crop(&self, ctx: &mut EventCtx, app: &App, name: MapName) -> anyhow::Result<String> {
+        let boundary = self.boundary.clone().unwrap();
+        ctx.loading_screen("crop to study area", |_, timer| {
+            let map = &app.primary.map;
+            let new_map = map.clip_to_study_area(&boundary, name, timer)?;
+            new_map.save();
+
+            // Prefer whatever scenario is loaded, but from here, there usually isn't one
+            let scenario = match app.primary.scenario {
+                Some(ref scenario) => Some(scenario.clone()),
+                None => abstio::maybe_read_binary::<Scenario>(
+                    abstio::path_scenario(
+                        map.get_name(),
+                        &Scenario::default_scenario_for_map(map.get_name()),
+                    ),
+                    timer,
+                )
+                .ok(),
+            };
+            Ok(match scenario {
+                Some(scenario) => {
+                    let clipped = scenario.clip_to_study_area(map, &boundary, &new_map, timer);
+                    clipped.save();
+                    clipped.scenario_name
+                }
+                // Generated for any map
+                None => "home_to_work".to_string(),
+            })
+        })
+    }
+}
+
+impl State<App> for CropToStudyArea {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut lasso) = self.lasso {
+            // Dragging draws the boundary instead of panning
+            if let Some(polygon) = lasso.event(ctx) {
+                self.lasso = None;
+                let mut batch = GeomBatch::new();
+                batch.push(Color::BLUE.alpha(0.3), polygon.clone());
+                self.draw_boundary = ctx.upload(batch);
+                self.boundary = Some(polygon);
+                self.rebuild_panel(ctx, app);
+            }
+        } else {
+            ctx.canvas_movement();
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "redraw" => {
+                    self.lasso = Some(Lasso::new(Distance::meters(5.0)));
+                    self.boundary = None;
+                    self.draw_boundary = Drawable::empty(ctx);
+                    self.rebuild_panel(ctx, app);
+                }
+                "crop" => {
+                    let map_name = self.panel.text_box("name");
+                    if map_name.is_empty() || map_name == app.primary.map.get_name().map {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec!["Pick a different name for the new map"],
+                        ));
+                    }
+                    let name = MapName::from_city(app.primary.map.get_city_name(), &map_name);
+                    return match self.crop(ctx, app, name.clone()) {
+                        Ok(scenario_name) => Transition::Clear(vec![
+                            TitleScreen::new_state(ctx, app),
+                            SandboxMode::simple_new(
+                                app,
+                                GameplayMode::PlayScenario(name, scenario_name, Vec::new()),
+                            ),
+                        ]),
+                        Err(err) => Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![format!("Couldn't crop to this area: {}", err)],
+                        )),
+                    };
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::DefaultDraw
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_boundary);
+        if let Some(ref lasso) = self.lasso {
+            lasso.draw(g);
+        }
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,91 @@
+use map_model::{BuildingID, EditCmd};
+use widgetry::{
+    EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner, State, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::edit::apply_map_edits;
+
+/// Change how many bikes can be parked at a building
+pub struct BikeParkingEditor {
+    panel: Panel,
+    b: BuildingID,
+}
+
+impl BikeParkingEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App, b: BuildingID) -> Box<dyn State<App>> {
+        app.primary.current_selection = None;
+
+        let bldg = app.primary.map.get_b(b);
+        let mut txt = Text::from(Line(&bldg.address));
+        if bldg.bldg_type.has_residents() {
+            txt.add_line(
+                Line("People keep bikes inside homes, so this building never runs out of space")
+                    .secondary(),
+            );
+        }
+        if bldg.bike_parking != bldg.orig_bike_parking {
+            txt.add_line(Line(format!("Originally {} spots", bldg.orig_bike_parking)).secondary());
+        }
+
+        Box::new(BikeParkingEditor {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Bike parking").small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                txt.into_widget(ctx),
+                Widget::row(vec![
+                    "Spots".text_widget(ctx).centered_vert(),
+                    Spinner::widget(ctx, "spots", (0, 500), bldg.bike_parking, 2),
+                ]),
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Apply")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            b,
+        })
+    }
+}
+
+impl State<App> for BikeParkingEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    let new: usize = self.panel.spinner("spots");
+                    let old = app.primary.map.get_b(self.b).bike_parking;
+                    if new != old {
+                        let mut edits = app.primary.map.get_edits().clone();
+                        edits.commands.push(EditCmd::ChangeBikeParking {
+                            b: self.b,
+                            old,
+                            new,
+                        });
+                        apply_map_edits(ctx, app, edits);
+                    }
+
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
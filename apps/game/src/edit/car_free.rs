@@ -0,0 +1,76 @@
+use anyhow::Result;
+
+use geom::{Duration, Time};
+use map_model::RoadID;
+use widgetry::tools::{ChooseSomething, PopupMsg};
+use widgetry::{Choice, EventCtx, State};
+
+use crate::app::{App, Transition};
+use crate::edit::transit_stops::update_pathfinding;
+use crate::edit::{apply_map_edits, RoadEditor};
+
+/// Turn a road into a street for walking and cycling, maybe letting delivery vehicles in at some
+/// times. Afterwards, the road editor starts over for the new lanes.
+pub fn make_car_free(ctx: &mut EventCtx, r: RoadID) -> Box<dyn State<App>> {
+    let hour = |h: usize| Time::START_OF_DAY + Duration::hours(h);
+    ChooseSomething::new_state(
+        ctx,
+        "Can delivery vehicles still use this street?",
+        vec![
+            Choice::new("No, only walking and cycling", Vec::new()),
+            Choice::new("Deliveries from 6am to 10am", vec![(hour(6), hour(10))]),
+            Choice::new(
+                "Deliveries from 6am to 10am and 7pm to 10pm",
+                vec![(hour(6), hour(10)), (hour(19), hour(22))],
+            ),
+        ],
+        Box::new(
+            move |delivery_windows, ctx, app| match apply(ctx, app, r, delivery_windows) {
+                Ok(warnings) => {
+                    let mut transitions = vec![Transition::Replace(
+                        RoadEditor::new_state_without_lane(ctx, app, r),
+                    )];
+                    if !warnings.is_empty() {
+                        transitions.push(Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Buses rerouted",
+                            warnings,
+                        )));
+                    }
+                    Transition::Multi(transitions)
+                }
+                Err(err) => Transition::Replace(PopupMsg::new_state(
+                    ctx,
+                    "Couldn't make this street car-free",
+                    vec![err.to_string()],
+                )),
+            },
+        ),
+    )
+}
+
+/// Returns warnings about bus routes that have to detour
+fn apply(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    r: RoadID,
+    delivery_windows: Vec<(Time, Time)>,
+) -> Result<Vec<String>> {
+    let (cmds, warnings) = app.primary.map.pedestrianize_cmds(r, delivery_windows)?;
+    let orig_edits = app.primary.map.get_edits().clone();
+    let mut edits = orig_edits.clone();
+    edits.commands.extend(cmds);
+    apply_map_edits(ctx, app, edits);
+
+    // Make sure buses can find a way around the street
+    update_pathfinding(ctx, app);
+    for id in warnings.keys() {
+        let route = app.primary.map.get_tr(*id);
+        if let Err(err) = route.all_paths(&app.primary.map) {
+            let msg = format!("{} can't get around this street: {}", route.long_name, err);
+            apply_map_edits(ctx, app, orig_edits);
+            bail!(msg);
+        }
+    }
+    Ok(warnings.into_values().collect())
+}
@@ -0,0 +1,127 @@
+use abstutil::Counter;
+use map_model::{BikeConflictType, CornerDesign, IntersectionID};
+use widgetry::{
+    EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text, TextExt, Toggle,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::edit::apply_map_edits;
+
+/// Toggle design elements that protect people cycling from turning vehicles at one intersection,
+/// showing how many conflicts between bike and vehicle movements each one defuses.
+pub struct CornerDesignEditor {
+    id: IntersectionID,
+    panel: Panel,
+}
+
+impl CornerDesignEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App, id: IntersectionID) -> Box<dyn State<App>> {
+        app.primary.current_selection = None;
+        let design = app.primary.map.get_i(id).corner_design.clone();
+
+        Box::new(Self {
+            id,
+            panel: Panel::new_builder(Widget::col(vec![
+                Line("Cycling corner design")
+                    .small_heading()
+                    .into_widget(ctx),
+                Toggle::checkbox(ctx, "protected corners", None, design.protected_corners),
+                Line("Turning vehicles yield to people cycling straight alongside them")
+                    .secondary()
+                    .into_widget(ctx),
+                Toggle::checkbox(
+                    ctx,
+                    "two-stage turn boxes",
+                    None,
+                    design.two_stage_turn_boxes,
+                ),
+                Line("People cycling turn across traffic in two stages, with the cross street")
+                    .secondary()
+                    .into_widget(ctx),
+                Toggle::checkbox(ctx, "setback crossings", None, design.setback_crossings),
+                Line("Crossings sit a car length back, so turning drivers see and yield")
+                    .secondary()
+                    .into_widget(ctx),
+                conflict_summary(ctx, app, id).section(ctx),
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Finish")
+                    .hotkey(Key::Escape)
+                    .build_def(ctx),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+        })
+    }
+}
+
+impl State<App> for CornerDesignEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(ref x) => match x.as_ref() {
+                "Finish" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                let design = CornerDesign {
+                    protected_corners: self.panel.is_checked("protected corners"),
+                    two_stage_turn_boxes: self.panel.is_checked("two-stage turn boxes"),
+                    setback_crossings: self.panel.is_checked("setback crossings"),
+                };
+                let mut edits = app.primary.map.get_edits().clone();
+                edits
+                    .commands
+                    .push(app.primary.map.edit_intersection_cmd(self.id, |new| {
+                        new.corner_design = design;
+                    }));
+                apply_map_edits(ctx, app, edits);
+                return Transition::Replace(Self::new_state(ctx, app, self.id));
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+/// Conflicts between bike and vehicle movements, by type, and how many the current design
+/// defuses
+pub fn conflict_summary(ctx: &EventCtx, app: &App, id: IntersectionID) -> Widget {
+    let map = &app.primary.map;
+    let i = map.get_i(id);
+    let mut counts = Counter::new();
+    for (_, _, conflict) in i.bike_conflicts(map) {
+        counts.inc(conflict);
+    }
+    if counts.sum() == 0 {
+        return "No bike lanes here cross vehicle movements".text_widget(ctx);
+    }
+
+    let mut txt = Text::from(Line("Conflicts between bike and vehicle movements"));
+    for conflict in BikeConflictType::all() {
+        let cnt = counts.get(conflict);
+        if cnt == 0 {
+            continue;
+        }
+        txt.add_line(Line(format!(
+            "{:?}: {}{}",
+            conflict,
+            cnt,
+            if conflict.mitigated_by(&i.corner_design) {
+                " (mitigated)"
+            } else {
+                ""
+            }
+        )));
+    }
+    txt.into_widget(ctx)
+}
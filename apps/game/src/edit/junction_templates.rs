@@ -0,0 +1,127 @@
+use map_model::{IntersectionID, JunctionTemplate};
+use widgetry::tools::{ChooseSomething, PopupMsg, PromptInput};
+use widgetry::{Choice, EventCtx, State};
+
+use crate::app::{App, Transition};
+use crate::edit::{apply_map_edits, check_sidewalk_connectivity};
+
+/// Save the current design of an intersection as a named template. Templates are stored as JSON
+/// files that aren't tied to one map, so they can be shared.
+pub fn save_template(ctx: &mut EventCtx, i: IntersectionID) -> Box<dyn State<App>> {
+    PromptInput::new_state(
+        ctx,
+        "Name this junction template",
+        String::new(),
+        Box::new(move |name, ctx, app| {
+            let result = if name.is_empty() || name.contains('/') {
+                Err(anyhow!("Invalid template name {:?}", name))
+            } else {
+                JunctionTemplate::from_intersection(&app.primary.map, i, name.clone())
+            };
+            Transition::Replace(match result {
+                Ok(template) => {
+                    let path = abstio::path_junction_template(&name);
+                    abstio::write_json(path.clone(), &template);
+                    PopupMsg::new_state(
+                        ctx,
+                        "Template saved",
+                        vec![
+                            format!("Saved {}", path),
+                            "Share this file to use it elsewhere".to_string(),
+                        ],
+                    )
+                }
+                Err(err) => PopupMsg::new_state(ctx, "Error", vec![err.to_string()]),
+            })
+        }),
+    )
+}
+
+/// Pick a saved template and apply it to an intersection. This replaces any editor for the
+/// intersection, since its movements may change completely.
+pub fn apply_template(ctx: &mut EventCtx, i: IntersectionID) -> Box<dyn State<App>> {
+    let choices = Choice::strings(abstio::list_all_objects(
+        abstio::path_all_junction_templates(),
+    ));
+    if choices.is_empty() {
+        return PopupMsg::new_state(
+            ctx,
+            "No junction templates",
+            vec!["Save an intersection as a template first"],
+        );
+    }
+    ChooseSomething::new_state(
+        ctx,
+        "Apply which junction template?",
+        choices,
+        Box::new(move |name, ctx, app| {
+            Transition::Replace(match apply(ctx, app, i, &name) {
+                Ok(lines) => PopupMsg::new_state(ctx, "Template applied", lines),
+                Err(err) => {
+                    PopupMsg::new_state(ctx, "Couldn't apply template", vec![err.to_string()])
+                }
+            })
+        }),
+    )
+}
+
+fn apply(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    i: IntersectionID,
+    name: &str,
+) -> anyhow::Result<Vec<String>> {
+    let template: JunctionTemplate = abstio::maybe_read_json(
+        abstio::path_junction_template(name),
+        &mut abstutil::Timer::throwaway(),
+    )?;
+    let matched = template.match_intersection(&app.primary.map, i)?;
+    let orig_edits = app.primary.map.get_edits().clone();
+
+    // Lanes first, since they determine the movements the signal has to cover
+    let lane_cmds = template.lane_edits(&app.primary.map, i)?;
+    let num_roads = lane_cmds.len();
+    if !lane_cmds.is_empty() {
+        let mut edits = orig_edits.clone();
+        edits.commands.extend(lane_cmds);
+        apply_map_edits(ctx, app, edits);
+    }
+
+    let cmd = match template.control_edit(&app.primary.map, i) {
+        Ok(cmd) => cmd,
+        Err(err) => {
+            apply_map_edits(ctx, app, orig_edits);
+            return Err(err);
+        }
+    };
+    if check_sidewalk_connectivity(ctx, app, cmd.clone()).is_some() {
+        apply_map_edits(ctx, app, orig_edits);
+        bail!("This template would disconnect some sidewalks");
+    }
+    let mut edits = app.primary.map.get_edits().clone();
+    edits.commands.push(cmd);
+    apply_map_edits(ctx, app, edits);
+    app.primary
+        .sim
+        .handle_live_edited_traffic_signals(&app.primary.map);
+
+    let mut lines = vec![format!(
+        "Applied {} and changed the lanes of {} roads",
+        template.name, num_roads
+    )];
+    let num_approaches = matched.approaches.len();
+    if num_approaches != template.approaches.len() {
+        lines.push(format!(
+            "The template has {} approaches, but this intersection has {}",
+            template.approaches.len(),
+            num_approaches
+        ));
+    }
+    if matched.max_angle_error > 30.0 {
+        lines.push(format!(
+            "Some roads are {:.0} degrees away from the template's approaches",
+            matched.max_angle_error
+        ));
+    }
+    Ok(lines)
+}
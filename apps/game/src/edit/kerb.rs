@@ -0,0 +1,307 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use geom::{Distance, UnitFmt};
+use map_model::{Direction, KerbSegment, KerbUseType, Map, Road, RoadID};
+use widgetry::tools::{ColorLegend, PopupMsg};
+use widgetry::{
+    Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Panel, Spinner, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+
+/// Divide up the kerb along each side of one road into parking, loading bays, bus stops, bike
+/// parking, and parklets.
+pub struct KerbEditor {
+    r: RoadID,
+    kerb_uses: Vec<KerbSegment>,
+    panel: Panel,
+    draw: Drawable,
+}
+
+impl KerbEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, r: RoadID) -> Box<dyn State<App>> {
+        let kerb_uses = app.primary.map.get_r(r).kerb_uses.clone();
+        let mut editor = KerbEditor {
+            r,
+            kerb_uses,
+            panel: Panel::empty(ctx),
+            draw: Drawable::empty(ctx),
+        };
+        editor.recalculate(ctx, app);
+        Box::new(editor)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let road = app.primary.map.get_r(self.r);
+
+        let mut col = vec![
+            Line(format!(
+                "Kerb uses along {}",
+                road.get_name(app.opts.language.as_ref())
+            ))
+            .small_heading()
+            .into_widget(ctx),
+            Text::from(
+                Line("Kerb not covered below is used for parking, if there's a parking lane.")
+                    .secondary(),
+            )
+            .wrap_to_pct(ctx, 30)
+            .into_widget(ctx),
+        ];
+        col.push(Widget::col(
+            KerbUseType::all()
+                .into_iter()
+                .map(|t| ColorLegend::row(ctx, kerb_use_color(t), t.to_string()))
+                .collect(),
+        ));
+
+        for (idx, seg) in self.kerb_uses.iter().enumerate() {
+            col.push(Widget::row(vec![
+                format!(
+                    "{} on the {} side, {} to {}",
+                    seg.use_type,
+                    describe_side(seg.side),
+                    seg.start.to_string(&app.opts.units),
+                    seg.end.to_string(&app.opts.units)
+                )
+                .text_widget(ctx)
+                .centered_vert(),
+                ctx.style()
+                    .btn_plain_destructive
+                    .icon("system/assets/tools/trash.svg")
+                    .build_widget(ctx, format!("delete segment {}", idx))
+                    .align_right(),
+            ]));
+        }
+
+        let length = road.length();
+        col.push(
+            Widget::col(vec![
+                Line("Add a segment").small_heading().into_widget(ctx),
+                Widget::row(vec![
+                    Widget::dropdown(
+                        ctx,
+                        "use type",
+                        KerbUseType::LoadingBay,
+                        KerbUseType::all()
+                            .into_iter()
+                            .map(|t| Choice::new(t.to_string(), t))
+                            .collect(),
+                    ),
+                    Widget::dropdown(
+                        ctx,
+                        "side",
+                        Direction::Fwd,
+                        vec![
+                            Choice::new(describe_side(Direction::Fwd), Direction::Fwd),
+                            Choice::new(describe_side(Direction::Back), Direction::Back),
+                        ],
+                    ),
+                ]),
+                Widget::row(vec![
+                    "From".text_widget(ctx).centered_vert(),
+                    distance_spinner(ctx, "start", length, Distance::ZERO),
+                    "to".text_widget(ctx).centered_vert(),
+                    distance_spinner(ctx, "end", length, length.min(Distance::meters(10.0))),
+                ]),
+                ctx.style()
+                    .btn_outline
+                    .text("add segment")
+                    .hotkey(Key::A)
+                    .build_def(ctx),
+            ])
+            .section(ctx),
+        );
+
+        col.push(Widget::row(vec![
+            ctx.style()
+                .btn_solid_primary
+                .text("Apply")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+            ctx.style()
+                .btn_plain
+                .text("Export kerb-use inventory")
+                .build_def(ctx),
+            ctx.style()
+                .btn_plain
+                .text("Cancel")
+                .hotkey(Key::Escape)
+                .build_def(ctx),
+        ]));
+
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx);
+        self.draw = draw_kerb_uses(ctx, road, &self.kerb_uses);
+    }
+}
+
+impl State<App> for KerbEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "Apply" => {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    let kerb_uses = self.kerb_uses.clone();
+                    edits
+                        .commands
+                        .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                            new.kerb_uses = kerb_uses;
+                        }));
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Pop;
+                }
+                "Cancel" => {
+                    return Transition::Pop;
+                }
+                "Export kerb-use inventory" => {
+                    return Transition::Push(match export_inventory(&app.primary.map) {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Inventory exported",
+                            vec![format!("Kerb uses exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                }
+                "add segment" => {
+                    let start: Distance = self.panel.spinner("start");
+                    let end: Distance = self.panel.spinner("end");
+                    let seg = KerbSegment {
+                        side: self.panel.dropdown_value("side"),
+                        start: start.min(end),
+                        end: start.max(end),
+                        use_type: self.panel.dropdown_value("use type"),
+                    };
+                    if seg.length() == Distance::ZERO {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec!["The segment must have some length."],
+                        ));
+                    }
+                    if self.kerb_uses.iter().any(|other| {
+                        other.side == seg.side && other.start < seg.end && seg.start < other.end
+                    }) {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec!["This overlaps another segment. Delete that one first."],
+                        ));
+                    }
+                    self.kerb_uses.push(seg);
+                    self.kerb_uses
+                        .sort_by_key(|seg| (seg.side == Direction::Back, seg.start));
+                    self.recalculate(ctx, app);
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("delete segment ") {
+                        let idx = idx.parse::<usize>().unwrap();
+                        self.kerb_uses.remove(idx);
+                        self.recalculate(ctx, app);
+                    } else {
+                        unreachable!()
+                    }
+                }
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn describe_side(side: Direction) -> &'static str {
+    match side {
+        Direction::Fwd => "forwards",
+        Direction::Back => "backwards",
+    }
+}
+
+fn kerb_use_color(use_type: KerbUseType) -> Color {
+    match use_type {
+        KerbUseType::Parking => Color::grey(0.6),
+        KerbUseType::LoadingBay => Color::YELLOW,
+        KerbUseType::BusStop => Color::RED,
+        KerbUseType::BikeParking => Color::GREEN,
+        KerbUseType::Parklet => Color::PURPLE,
+        KerbUseType::PickUpDropOff => Color::CYAN,
+    }
+}
+
+fn distance_spinner(ctx: &EventCtx, label: &str, length: Distance, current: Distance) -> Widget {
+    Spinner::widget_with_custom_rendering(
+        ctx,
+        label,
+        (Distance::ZERO, length),
+        current,
+        Distance::meters(1.0),
+        // Like the lane width spinner, the step size is in meters, so render in meters
+        Box::new(|x| x.to_string(&UnitFmt::metric())),
+    )
+}
+
+fn draw_kerb_uses(ctx: &EventCtx, road: &Road, kerb_uses: &[KerbSegment]) -> Drawable {
+    let mut batch = GeomBatch::new();
+    for seg in kerb_uses {
+        // Draw on the outermost lane on that side of the road
+        let lane = if road.lanes[0].dir == seg.side {
+            &road.lanes[0]
+        } else {
+            road.lanes.last().unwrap()
+        };
+        let (start, end) = if lane.dir == Direction::Fwd {
+            (seg.start, seg.end)
+        } else {
+            (road.length() - seg.end, road.length() - seg.start)
+        };
+        if let Ok(pl) = lane.lane_center_pts.maybe_exact_slice(start, end) {
+            batch.push(
+                kerb_use_color(seg.use_type).alpha(0.8),
+                pl.make_polygons(lane.width),
+            );
+        }
+    }
+    ctx.upload(batch)
+}
+
+/// Writes every kerb segment in the map to a CSV file, returning the path
+fn export_inventory(map: &Map) -> Result<String> {
+    let path = format!("kerb_uses_{}.csv", map.get_name().as_filename());
+    let mut out = String::new();
+    writeln!(
+        out,
+        "road,osm_way_id,side,use_type,start_meters,end_meters,length_meters"
+    )?;
+    for r in map.all_roads() {
+        for seg in &r.kerb_uses {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                r.id.0,
+                r.orig_id.osm_way_id.0,
+                describe_side(seg.side),
+                seg.use_type,
+                seg.start.inner_meters(),
+                seg.end.inner_meters(),
+                seg.length().inner_meters()
+            )?;
+        }
+    }
+    abstio::write_file(path, out)
+}
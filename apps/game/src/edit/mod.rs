@@ -6,31 +6,43 @@ use geom::Speed;
 use map_gui::options::OptionsPanel;
 use map_gui::render::DrawMap;
 use map_gui::tools::grey_out_map;
-use map_model::{EditCmd, IntersectionID, LaneID, MapEdits};
+use map_model::{EditCmd, IntersectionID, LaneID, MapEdits, PathConstraints, WalkingClosure};
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::tools::{ChooseSomething, ColorLegend, PopupMsg};
 use widgetry::{
     lctrl, Choice, Color, ControlState, EventCtx, GfxCtx, HorizontalAlignment, Image, Key, Line,
-    Menu, Outcome, Panel, State, Text, TextBox, TextExt, VerticalAlignment, Widget,
+    Menu, Outcome, Panel, State, Text, TextBox, TextExt, Toggle, VerticalAlignment, Widget,
 };
 
+pub use self::bike_parking::BikeParkingEditor;
+pub use self::corner_design::conflict_summary;
 pub use self::roads::RoadEditor;
 pub use self::routes::RouteEditor;
 pub use self::stop_signs::StopSignEditor;
 pub use self::traffic_signals::TrafficSignalEditor;
+pub use self::transit_stops::TransitStopEditor;
 pub use self::validate::{check_blackholes, check_sidewalk_connectivity};
+pub use self::walking_closures::{walking_closure_color, WalkingClosureEditor};
 use crate::app::{App, Transition};
 use crate::common::{tool_panel, CommonState, Warping};
 use crate::debug::DebugMode;
 use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
 
+mod bike_parking;
+mod car_free;
+mod corner_design;
 mod crosswalks;
+mod junction_templates;
+mod kerb;
 mod multiple_roads;
+mod reroute;
 mod roads;
 mod routes;
 mod stop_signs;
 mod traffic_signals;
+mod transit_stops;
 mod validate;
+mod walking_closures;
 mod zones;
 
 pub struct EditMode {
@@ -44,6 +56,8 @@ pub struct EditMode {
     mode: GameplayMode,
 
     map_edit_key: usize,
+    /// Set after the player decides to finish despite routing problems
+    quit_requested: bool,
 
     draw: ToggleZoomed,
 }
@@ -62,6 +76,7 @@ impl EditMode {
             orig_dirty,
             mode,
             map_edit_key: app.primary.map.get_edits_change_key(),
+            quit_requested: false,
             draw: layer.draw,
         })
     }
@@ -138,6 +153,10 @@ impl EditMode {
 
 impl State<App> for EditMode {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if self.quit_requested {
+            return self.quit(ctx, app);
+        }
+
         {
             // We would normally use Cached, but so many values depend on one key, so this is more
             // clear.
@@ -165,6 +184,11 @@ impl State<App> for EditMode {
                         && app.primary.map.maybe_get_stop_sign(i).is_some()
                 }
                 Some(ID::Road(_)) => false,
+                Some(ID::Building(_)) => !self.mode.can_edit_roads(),
+                // Only bus stops can be edited
+                Some(ID::TransitStop(ts)) => {
+                    !self.mode.can_edit_roads() || app.primary.map.get_ts(ts).is_train_stop
+                }
                 _ => true,
             } {
                 app.primary.current_selection = None;
@@ -178,7 +202,14 @@ impl State<App> for EditMode {
         if let Outcome::Clicked(x) = self.top_center.event(ctx) {
             match x.as_ref() {
                 "finish editing" => {
-                    return self.quit(ctx, app);
+                    let orig_edits = self.orig_edits.clone();
+                    let problems = ctx.loading_screen("check routing", |_, timer| {
+                        validate::find_routing_problems(app, &orig_edits, timer)
+                    });
+                    if problems.is_empty() {
+                        return self.quit(ctx, app);
+                    }
+                    return Transition::Push(validate::RoutingWarnings::new_state(ctx, problems));
                 }
                 "Fix sidewalk direction errors" => {
                     let new_fixes = validate::fix_sidewalk_direction(&app.primary.map);
@@ -197,6 +228,18 @@ impl State<App> for EditMode {
                         vec![msg],
                     ));
                 }
+                "Add a bus stop" => {
+                    return Transition::Push(transit_stops::PlaceBusStop::new_state(
+                        ctx, app, None,
+                    ));
+                }
+                "Close sidewalks and crossings" => {
+                    return Transition::Push(WalkingClosureEditor::new_state(
+                        ctx,
+                        app,
+                        WalkingClosure::Snow,
+                    ));
+                }
                 _ => unreachable!(),
             }
         }
@@ -214,6 +257,8 @@ impl State<App> for EditMode {
                             Choice::string("save this proposal as..."),
                             // TODO Disable if empty edits
                             Choice::string("share proposal"),
+                            Choice::string("export transit network as GTFS"),
+                            Choice::string("export a poster"),
                             Choice::string("delete this proposal and remove all edits")
                                 .fg(ctx.style().text_destructive_color),
                         ],
@@ -292,6 +337,23 @@ impl State<App> for EditMode {
                                     ctx, app, "--dev",
                                 ))
                             }
+                            "export transit network as GTFS" => {
+                                Transition::Replace(match export_gtfs(app) {
+                                    Ok(paths) => PopupMsg::new_state(
+                                        ctx,
+                                        "GTFS exported",
+                                        vec![format!("Wrote {}", paths.join(", "))],
+                                    ),
+                                    Err(err) => PopupMsg::new_state(
+                                        ctx,
+                                        "Export failed",
+                                        vec![err.to_string()],
+                                    ),
+                                })
+                            }
+                            "export a poster" => Transition::Replace(
+                                crate::common::poster::PosterExport::new_state(ctx, app),
+                            ),
                             "delete this proposal and remove all edits" => {
                                 abstio::delete_file(abstio::path_edits(
                                     app.primary.map.get_name(),
@@ -376,6 +438,16 @@ impl State<App> for EditMode {
                     return Transition::Push(RoadEditor::new_state(ctx, app, l));
                 }
             }
+            if let Some(ID::Building(b)) = app.primary.current_selection {
+                if app.per_obj.left_click(ctx, "edit bike parking") {
+                    return Transition::Push(BikeParkingEditor::new_state(ctx, app, b));
+                }
+            }
+            if let Some(ID::TransitStop(ts)) = app.primary.current_selection {
+                if app.per_obj.left_click(ctx, "edit bus stop") {
+                    return Transition::Push(TransitStopEditor::new_state(ctx, app, ts));
+                }
+            }
         }
 
         match self.tool_panel.event(ctx) {
@@ -403,6 +475,7 @@ pub struct SaveEdits {
     cancel: Option<Transition>,
     on_success: Box<dyn Fn(&mut EventCtx, &mut App)>,
     reset: bool,
+    summary: Vec<String>,
 }
 
 impl SaveEdits {
@@ -419,6 +492,10 @@ impl SaveEdits {
         } else {
             format!("copy of {}", app.primary.map.get_edits().edits_name)
         };
+        let edits = app.primary.map.get_edits();
+        let summary = edits.summarize(&app.primary.map, &app.opts.units);
+        // Don't overwrite a description somebody wrote
+        let offer_summary = edits.proposal_description.is_empty() && !summary.is_empty();
         let mut save = SaveEdits {
             current_name: initial_name.clone(),
             panel: Panel::new_builder(Widget::col(vec![
@@ -430,6 +507,18 @@ impl SaveEdits {
                 // TODO Want this to always consistently be one line high, but it isn't for a blank
                 // line
                 Widget::placeholder(ctx, "warning"),
+                if summary.is_empty() {
+                    Widget::nothing()
+                } else {
+                    Text::from_multiline(summary.iter().map(|x| Line(x).secondary()).collect())
+                        .wrap_to_pct(ctx, 40)
+                        .into_widget(ctx)
+                },
+                if offer_summary {
+                    Toggle::checkbox(ctx, "Include this summary in the proposal", None, true)
+                } else {
+                    Widget::nothing()
+                },
                 Widget::row(vec![
                     if discard {
                         ctx.style()
@@ -460,6 +549,7 @@ impl SaveEdits {
             cancel,
             on_success,
             reset: discard,
+            summary,
         };
         save.recalc_btn(ctx, app);
         Box::new(save)
@@ -521,6 +611,15 @@ impl State<App> for SaveEdits {
                 "Save" => {
                     let mut edits = app.primary.map.get_edits().clone();
                     edits.edits_name = self.current_name.clone();
+                    if self
+                        .panel
+                        .maybe_is_checked("Include this summary in the proposal")
+                        .unwrap_or(false)
+                    {
+                        // The first line is the title
+                        edits.proposal_description = vec![self.current_name.clone()];
+                        edits.proposal_description.extend(self.summary.clone());
+                    }
                     app.primary
                         .map
                         .must_apply_edits(edits, &mut Timer::throwaway());
@@ -710,6 +809,25 @@ fn make_topcenter(ctx: &mut EventCtx, app: &App) -> Panel {
             ))
             .hotkey(Key::Escape)
             .build_widget(ctx, "finish editing"),
+        if app
+            .primary
+            .map
+            .all_transit_routes()
+            .iter()
+            .any(|r| r.route_type == PathConstraints::Bus)
+        {
+            ctx.style()
+                .btn_outline
+                .text("Add a bus stop")
+                .build_def(ctx)
+        } else {
+            Widget::nothing()
+        },
+        ctx.style()
+            .btn_outline
+            .text("Close sidewalks and crossings")
+            .tooltip("Mark sidewalks and crossings unusable after snow or during works")
+            .build_def(ctx),
         if app.opts.dev {
             ctx.style()
                 .btn_outline
@@ -760,13 +878,15 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
         if !effects.changed_roads.is_empty() || !effects.changed_intersections.is_empty() {
             app.primary
                 .draw_map
-                .draw_all_unzoomed_roads_and_intersections = DrawMap::regenerate_unzoomed_layer(
-                ctx,
-                &app.primary.map,
-                &app.cs,
-                &app.opts,
-                timer,
-            );
+                .draw_all_unzoomed_roads_and_intersections =
+                DrawMap::regenerate_unzoomed_layer_styled(
+                    ctx,
+                    &app.primary.map,
+                    &app.cs,
+                    &app.opts,
+                    app.primary.draw_map.get_style(),
+                    timer,
+                );
         }
 
         for r in effects.changed_roads {
@@ -784,6 +904,12 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
             app.primary.draw_map.get_pl(pl).clear_rendering();
         }
 
+        for ts in effects.changed_transit_stops {
+            app.primary
+                .draw_map
+                .recreate_transit_stop(ctx, ts, &app.primary.map, &app.cs);
+        }
+
         if app.primary.layer.as_ref().and_then(|l| l.name()) == Some("map edits") {
             app.primary.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
         }
@@ -869,6 +995,16 @@ pub fn maybe_edit_intersection(
     None
 }
 
+fn export_gtfs(app: &App) -> anyhow::Result<Vec<String>> {
+    let map = &app.primary.map;
+    let dir = format!(
+        "gtfs_{}_{}",
+        map.get_name().as_filename(),
+        map.get_edits().edits_name
+    );
+    map.export_gtfs()?.write(&dir)
+}
+
 fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
     // TODO Support redo. Bit harder here to reset the redo_stack when the edits
     // change, because nested other places modify it too.
@@ -939,7 +1075,12 @@ fn cmd_to_id(cmd: &EditCmd) -> Option<ID> {
     match cmd {
         EditCmd::ChangeRoad { r, .. } => Some(ID::Road(*r)),
         EditCmd::ChangeIntersection { i, .. } => Some(ID::Intersection(*i)),
-        EditCmd::ChangeRouteSchedule { .. } => None,
+        EditCmd::ChangeRouteSchedule { .. }
+        | EditCmd::ChangeRouteStops { .. }
+        | EditCmd::ChangeRouteAlignment { .. } => None,
+        EditCmd::ChangeBikeParking { b, .. } => Some(ID::Building(*b)),
+        // The stop might not exist anymore
+        EditCmd::ChangeTransitStop { id, .. } => Some(ID::Road(id.road)),
     }
 }
 
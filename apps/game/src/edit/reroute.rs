@@ -0,0 +1,303 @@
+use anyhow::Result;
+
+use geom::{Circle, Distance};
+use map_model::{
+    DirectedRoadID, EditCmd, Map, PathConstraints, PathRequest, PathStepV2, TransitRoute,
+    TransitRouteID, TransitStopID,
+};
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    State, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::edit::transit_stops::{apply_stop_edits, update_pathfinding};
+use crate::ID;
+
+/// Send a bus route along different roads, like around a new bus gate or a pedestrianized
+/// street. Click roads in order; buses take the fastest path between each one. The route keeps
+/// its schedule and the stops along the new roads.
+pub struct RerouteBus {
+    panel: Panel,
+    route: TransitRouteID,
+    /// Roads the player clicked, in order. The first is where the route starts.
+    waypoints: Vec<DirectedRoadID>,
+    /// Through every waypoint, then to wherever the route leaves the map. None if buses can't
+    /// make it.
+    alignment: Option<Vec<DirectedRoadID>>,
+    hovering: Option<DirectedRoadID>,
+    draw_route: Drawable,
+    draw_hover: Drawable,
+}
+
+impl RerouteBus {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App, id: TransitRouteID) -> Box<dyn State<App>> {
+        app.primary.current_selection = None;
+        update_pathfinding(ctx, app);
+
+        let map = &app.primary.map;
+        let start = map.get_l(map.get_tr(id).start).get_directed_parent();
+        let mut state = RerouteBus {
+            panel: Panel::empty(ctx),
+            route: id,
+            waypoints: vec![start],
+            alignment: None,
+            hovering: None,
+            draw_route: Drawable::empty(ctx),
+            draw_hover: Drawable::empty(ctx),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let route = map.get_tr(self.route);
+        self.alignment = follow_waypoints(map, route, &self.waypoints).ok();
+
+        let mut batch = GeomBatch::new();
+        let mut status = Text::new();
+        let mut can_apply = false;
+        if let Some(ref alignment) = self.alignment {
+            for dr in alignment {
+                batch.push(
+                    app.cs.route.alpha(0.5),
+                    map.get_r(dr.road).get_thick_polygon(),
+                );
+            }
+            match map.reroute_cmds(self.route, alignment.clone()) {
+                Ok(cmds) => {
+                    let new_stops = new_stops(route, &cmds);
+                    let added_stops = new_stops.iter().filter(|ts| !route.stops.contains(ts));
+                    for ts in route.stops.iter().chain(added_stops) {
+                        let color = if new_stops.contains(ts) {
+                            Color::GREEN
+                        } else {
+                            Color::RED
+                        };
+                        batch.push(
+                            color,
+                            Circle::new(
+                                map.get_ts(*ts).sidewalk_pos.pt(map),
+                                Distance::meters(3.0),
+                            )
+                            .to_polygon(),
+                        );
+                    }
+                    let dropped = route
+                        .stops
+                        .iter()
+                        .filter(|ts| !new_stops.contains(ts))
+                        .count();
+                    let added = new_stops
+                        .iter()
+                        .filter(|ts| !route.stops.contains(ts))
+                        .count();
+                    status.add_line(Line(format!(
+                        "Serves {} stops (was {})",
+                        new_stops.len(),
+                        route.stops.len()
+                    )));
+                    status.add_line(
+                        Line(format!("{} stops dropped, {} picked up", dropped, added)).secondary(),
+                    );
+                    can_apply = true;
+                }
+                Err(err) => {
+                    status.add_line(Line(err.to_string()).fg(Color::RED));
+                }
+            }
+        } else {
+            status.add_line(Line("Buses can't drive through these roads").fg(Color::RED));
+        }
+        for dr in &self.waypoints {
+            batch.push(
+                app.cs.selected,
+                Circle::new(
+                    map.get_r(dr.road).center_pts.middle(),
+                    Distance::meters(5.0),
+                )
+                .to_polygon(),
+            );
+        }
+        self.draw_route = ctx.upload(batch);
+
+        let instructions = if route.end_border.is_some() {
+            "Click roads in the order buses should drive along them. The route still starts and \
+             leaves the map where it did before."
+        } else {
+            "Click roads in the order buses should drive along them. The route still starts where \
+             it did before, and ends at its last stop."
+        };
+        self.panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line(format!("Reroute {}", route.short_name))
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from(Line(instructions))
+                .wrap_to_pct(ctx, 30)
+                .into_widget(ctx),
+            status.into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("Remove last road")
+                    .hotkey(Key::Backspace)
+                    .disabled(self.waypoints.len() == 1)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Apply")
+                    .hotkey(Key::Enter)
+                    .disabled(!can_apply)
+                    .build_def(ctx),
+            ]),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+    }
+
+    fn road_at_cursor(&self, ctx: &EventCtx, app: &App) -> Option<DirectedRoadID> {
+        let map = &app.primary.map;
+        let l = match app.mouseover_unzoomed_roads_and_intersections(ctx)? {
+            ID::Lane(l) => l,
+            _ => {
+                return None;
+            }
+        };
+        if !PathConstraints::Bus.can_use(map.get_l(l), map) {
+            return None;
+        }
+        let dr = map.get_l(l).get_directed_parent();
+        if self.waypoints.last() == Some(&dr) {
+            return None;
+        }
+        Some(dr)
+    }
+}
+
+impl State<App> for RerouteBus {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if ctx.redo_mouseover() {
+            self.hovering = self.road_at_cursor(ctx, app);
+            let mut batch = GeomBatch::new();
+            if let Some(dr) = self.hovering {
+                batch.push(
+                    app.cs.selected.alpha(0.5),
+                    app.primary.map.get_r(dr.road).get_thick_polygon(),
+                );
+            }
+            self.draw_hover = ctx.upload(batch);
+        }
+
+        if let Some(dr) = self.hovering {
+            if app.per_obj.left_click(ctx, "send buses along this road") {
+                self.waypoints.push(dr);
+                if follow_waypoints(
+                    &app.primary.map,
+                    app.primary.map.get_tr(self.route),
+                    &self.waypoints,
+                )
+                .is_err()
+                {
+                    self.waypoints.pop();
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Buses can't get there",
+                        vec!["Buses can't drive from the last road to this one"],
+                    ));
+                }
+                self.hovering = None;
+                self.draw_hover = Drawable::empty(ctx);
+                self.recalculate(ctx, app);
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Remove last road" => {
+                    self.waypoints.pop();
+                    self.recalculate(ctx, app);
+                }
+                "Apply" => {
+                    let alignment = self.alignment.clone().unwrap();
+                    return match app
+                        .primary
+                        .map
+                        .reroute_cmds(self.route, alignment)
+                        .and_then(|cmds| apply_stop_edits(ctx, app, cmds))
+                    {
+                        Ok(()) => Transition::Pop,
+                        Err(err) => Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Buses can't follow this route",
+                            vec![format!("{:#}", err)],
+                        )),
+                    };
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_route);
+        g.redraw(&self.draw_hover);
+        self.panel.draw(g);
+    }
+}
+
+/// The fastest path for buses through each waypoint in order, then to the border where the route
+/// leaves the map, if it does.
+fn follow_waypoints(
+    map: &Map,
+    route: &TransitRoute,
+    waypoints: &[DirectedRoadID],
+) -> Result<Vec<DirectedRoadID>> {
+    let mut alignment = vec![waypoints[0]];
+    let mut targets = waypoints[1..].to_vec();
+    if let Some(end) = route.end_border {
+        targets.push(map.get_l(end).get_directed_parent());
+    }
+    for to in targets {
+        let from = *alignment.last().unwrap();
+        if from == to {
+            continue;
+        }
+        let req = PathRequest::between_directed_roads(map, from, to, PathConstraints::Bus)
+            .ok_or_else(|| anyhow!("buses can't use {}", to))?;
+        let path = map.pathfind_v2(req)?;
+        // The path starts along the last road already in the alignment
+        alignment.extend(
+            path.get_steps()
+                .iter()
+                .filter_map(|step| match step {
+                    PathStepV2::Along(dr) => Some(*dr),
+                    _ => None,
+                })
+                .skip(1),
+        );
+    }
+    Ok(alignment)
+}
+
+/// The stops a route will serve after these commands
+fn new_stops(route: &TransitRoute, cmds: &[EditCmd]) -> Vec<TransitStopID> {
+    for cmd in cmds {
+        if let EditCmd::ChangeRouteStops { new, .. } = cmd {
+            return new.clone();
+        }
+    }
+    route.stops.clone()
+}
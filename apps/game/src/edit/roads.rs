@@ -1,21 +1,25 @@
 use std::collections::HashMap;
 
 use crate::ID;
-use geom::{Bounds, CornerRadii, Distance, Polygon, Pt2D, UnitFmt};
+use geom::{Bounds, Circle, CornerRadii, Distance, Polygon, Pt2D, UnitFmt};
 use map_gui::render::{Renderable, OUTLINE_THICKNESS};
 use map_model::{
-    osm, BufferType, Direction, EditCmd, EditRoad, LaneID, LaneSpec, LaneType, MapEdits, Road,
-    RoadID,
+    osm, BufferType, BusLaneEnforcement, Direction, EditCmd, EditRoad, HgvAccess, HgvRestrictions,
+    Lane, LaneExtent, LaneID, LaneSpec, LaneType, MapEdits, PathConstraints, PriceSchedule, Road,
+    RoadID, RoadPricing, SpeedEnforcement,
 };
 use widgetry::tools::PopupMsg;
 use widgetry::{
     lctrl, Choice, Color, ControlState, DragDrop, Drawable, EdgeInsets, EventCtx, GeomBatch,
     GeomBatchStack, GfxCtx, HorizontalAlignment, Image, Key, Line, Outcome, Panel, PersistentSplit,
-    Spinner, StackAxis, State, Text, TextExt, VerticalAlignment, Widget, DEFAULT_CORNER_RADIUS,
+    Spinner, StackAxis, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
+    DEFAULT_CORNER_RADIUS,
 };
 
 use crate::app::{App, Transition};
 use crate::common::Warping;
+use crate::edit::car_free::make_car_free;
+use crate::edit::kerb::KerbEditor;
 use crate::edit::zones::ZoneEditor;
 use crate::edit::{apply_map_edits, can_edit_lane, speed_limit_choices};
 
@@ -35,6 +39,9 @@ pub struct RoadEditor {
     lane_highlights: ((Option<LaneID>, Option<LaneID>), Drawable),
     // This gets updated during dragging, and is always cleared out when drag-and-drop ends.
     draw_drop_position: Drawable,
+    // Handles to drag the start and end of the selected lane along the road
+    extent_handles: Drawable,
+    dragging_extent: Option<(ExtentEnd, LaneExtent)>,
 
     // Undo/redo management
     num_edit_cmds_originally: usize,
@@ -72,6 +79,8 @@ impl RoadEditor {
             fade_irrelevant: Drawable::empty(ctx),
             lane_highlights: ((None, None), Drawable::empty(ctx)),
             draw_drop_position: Drawable::empty(ctx),
+            extent_handles: Drawable::empty(ctx),
+            dragging_extent: None,
             hovering_on_lane: None,
 
             num_edit_cmds_originally: app.primary.map.get_edits().commands.len(),
@@ -151,6 +160,79 @@ impl RoadEditor {
         self.recalc_lane_highlights(ctx, app);
 
         self.fade_irrelevant = fade_irrelevant(app, self.r).upload(ctx);
+        self.extent_handles = draw_extent_handles(app, self.selected_lane, None).upload(ctx);
+    }
+
+    /// Drags the start or end of the selected lane along the road, to make turn pockets and
+    /// similar. Returns something when the mouse was used for this, so nothing else should handle
+    /// it.
+    fn drag_lane_extent(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+        let l = self.selected_lane?;
+        let lane = app.primary.map.get_l(l);
+        if !can_shorten(lane) {
+            return None;
+        }
+
+        if let Some((end, extent)) = self.dragging_extent {
+            if ctx.input.left_mouse_button_released() {
+                self.dragging_extent = None;
+                let road = app.primary.map.get_r(self.r);
+                let road_extent = lane_to_road_extent(road, lane, extent);
+                let full_length = road_extent.is_full_length(road.center_pts.length());
+                return Some(self.modify_current_lane(ctx, app, Some(0), |new, idx| {
+                    if full_length {
+                        new.lane_extents.remove(&idx);
+                    } else {
+                        new.lane_extents.insert(idx, road_extent);
+                    }
+                }));
+            }
+
+            if ctx.redo_mouseover() {
+                if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                    let pl = &lane.lane_center_pts;
+                    if let Some((dist, _)) = pl.dist_along_of_point(pl.project_pt(pt)) {
+                        // Snap to the ends of the lane
+                        let dist = if dist < EXTENT_SNAP_DIST {
+                            Distance::ZERO
+                        } else if dist > pl.length() - EXTENT_SNAP_DIST {
+                            pl.length()
+                        } else {
+                            dist
+                        };
+                        let mut extent = extent;
+                        match end {
+                            ExtentEnd::Start => {
+                                extent.start = dist;
+                            }
+                            ExtentEnd::End => {
+                                extent.end = dist;
+                            }
+                        }
+                        let extent = extent.clamp(pl.length());
+                        self.dragging_extent = Some((end, extent));
+                        self.extent_handles =
+                            draw_extent_handles(app, self.selected_lane, Some(extent)).upload(ctx);
+                    }
+                }
+            }
+            return Some(Transition::Keep);
+        }
+
+        if ctx.input.left_mouse_button_pressed() {
+            let pt = ctx.canvas.get_cursor_in_map_space()?;
+            let extent = current_extent(lane);
+            for (end, dist) in [
+                (ExtentEnd::Start, extent.start),
+                (ExtentEnd::End, extent.end),
+            ] {
+                if extent_handle(lane, dist).contains_pt(pt) {
+                    self.dragging_extent = Some((end, extent));
+                    return Some(Transition::Keep);
+                }
+            }
+        }
+        None
     }
 
     fn recalc_lane_highlights(&mut self, ctx: &mut EventCtx, app: &App) {
@@ -194,6 +276,9 @@ impl RoadEditor {
 
 impl State<App> for RoadEditor {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(transition) = self.drag_lane_extent(ctx, app) {
+            return transition;
+        }
         ctx.canvas_movement();
 
         let mut panels_need_recalc = false;
@@ -282,12 +367,16 @@ impl State<App> for RoadEditor {
                     panels_need_recalc = true;
                 } else if x == "delete lane" {
                     return self.modify_current_lane(ctx, app, None, |new, idx| {
-                        new.lanes_ltr.remove(idx);
+                        new.remove_lane(idx);
                     });
                 } else if x == "flip direction" {
                     return self.modify_current_lane(ctx, app, Some(0), |new, idx| {
                         new.lanes_ltr[idx].dir = new.lanes_ltr[idx].dir.opposite();
                     });
+                } else if x == "full length" {
+                    return self.modify_current_lane(ctx, app, Some(0), |new, idx| {
+                        new.lane_extents.remove(&idx);
+                    });
                 } else if let Some(lt) = x.strip_prefix("change to ") {
                     let lt = if lt == "buffer" {
                         self.main_panel.persistent_split_value("change to buffer")
@@ -331,8 +420,7 @@ impl State<App> for RoadEditor {
                     let mut edits = app.primary.map.get_edits().clone();
                     let old = app.primary.map.get_r_edit(self.r);
                     let mut new = old.clone();
-                    let idx = LaneSpec::add_new_lane(
-                        &mut new.lanes_ltr,
+                    let idx = new.add_new_lane(
                         lt,
                         app.primary
                             .map
@@ -361,6 +449,18 @@ impl State<App> for RoadEditor {
                         apply_map_edits(ctx, app, edits);
                     }
                     return Transition::Replace(ZoneEditor::new_state(ctx, app, self.r));
+                } else if x == "Make car-free" {
+                    // Same as above; every lane changes, so the undo stack starts over
+                    if let Some(edits) = self.compress_edits(app) {
+                        apply_map_edits(ctx, app, edits);
+                    }
+                    return Transition::Replace(make_car_free(ctx, self.r));
+                } else if x == "Kerb uses" {
+                    // Same as above, the KerbEditor makes one edit command
+                    if let Some(edits) = self.compress_edits(app) {
+                        apply_map_edits(ctx, app, edits);
+                    }
+                    return Transition::Replace(KerbEditor::new_state(ctx, app, self.r));
                 } else {
                     unreachable!()
                 }
@@ -388,6 +488,124 @@ impl State<App> for RoadEditor {
                     self.recalc_hovering(ctx, app);
                     panels_need_recalc = true;
                 }
+                "No through traffic (except access)" => {
+                    let enabled = self
+                        .main_panel
+                        .is_checked("No through traffic (except access)");
+
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits
+                        .commands
+                        .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                            if enabled {
+                                new.access_restrictions
+                                    .destination_only
+                                    .insert(PathConstraints::Car);
+                            } else {
+                                new.access_restrictions
+                                    .destination_only
+                                    .remove(PathConstraints::Car);
+                            }
+                        }));
+                    apply_map_edits(ctx, app, edits);
+                    self.redo_stack.clear();
+
+                    self.selected_lane = self
+                        .selected_lane
+                        .map(|id| self.lane_for_idx(app, id.offset));
+                    self.recalc_hovering(ctx, app);
+                    panels_need_recalc = true;
+                }
+                "bus lane enforcement" => {
+                    let enforcement = self.main_panel.dropdown_value("bus lane enforcement");
+
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits
+                        .commands
+                        .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                            new.bus_lane_enforcement = enforcement;
+                        }));
+                    apply_map_edits(ctx, app, edits);
+                    self.redo_stack.clear();
+
+                    self.selected_lane = self
+                        .selected_lane
+                        .map(|id| self.lane_for_idx(app, id.offset));
+                    self.recalc_hovering(ctx, app);
+                    panels_need_recalc = true;
+                }
+                "speed enforcement" => {
+                    let enforcement = self.main_panel.dropdown_value("speed enforcement");
+
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits
+                        .commands
+                        .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                            new.speed_enforcement = enforcement;
+                        }));
+                    apply_map_edits(ctx, app, edits);
+                    self.redo_stack.clear();
+
+                    self.selected_lane = self
+                        .selected_lane
+                        .map(|id| self.lane_for_idx(app, id.offset));
+                    self.recalc_hovering(ctx, app);
+                    panels_need_recalc = true;
+                }
+                "HGV restrictions" => {
+                    let hgv = self.main_panel.dropdown_value("HGV restrictions");
+
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits
+                        .commands
+                        .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                            new.hgv = hgv;
+                        }));
+                    apply_map_edits(ctx, app, edits);
+                    self.redo_stack.clear();
+
+                    self.selected_lane = self
+                        .selected_lane
+                        .map(|id| self.lane_for_idx(app, id.offset));
+                    self.recalc_hovering(ctx, app);
+                    panels_need_recalc = true;
+                }
+                "pricing" => {
+                    let pricing = self.main_panel.dropdown_value("pricing");
+
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits
+                        .commands
+                        .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                            new.pricing = pricing;
+                        }));
+                    apply_map_edits(ctx, app, edits);
+                    self.redo_stack.clear();
+
+                    self.selected_lane = self
+                        .selected_lane
+                        .map(|id| self.lane_for_idx(app, id.offset));
+                    self.recalc_hovering(ctx, app);
+                    panels_need_recalc = true;
+                }
+                "HOV lanes" => {
+                    let hov_min_occupancy = self.main_panel.dropdown_value("HOV lanes");
+
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits
+                        .commands
+                        .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                            new.hov_min_occupancy = hov_min_occupancy;
+                        }));
+                    apply_map_edits(ctx, app, edits);
+                    self.redo_stack.clear();
+
+                    self.selected_lane = self
+                        .selected_lane
+                        .map(|id| self.lane_for_idx(app, id.offset));
+                    self.recalc_hovering(ctx, app);
+                    panels_need_recalc = true;
+                }
                 "width preset" => {
                     let width = self.main_panel.dropdown_value("width preset");
                     return self.modify_current_lane(ctx, app, Some(0), |new, idx| {
@@ -444,8 +662,7 @@ impl State<App> for RoadEditor {
                     edits
                         .commands
                         .push(app.primary.map.edit_road_cmd(self.r, |new| {
-                            let spec = new.lanes_ltr.remove(old_idx);
-                            new.lanes_ltr.insert(new_idx, spec);
+                            new.move_lane(old_idx, new_idx);
                         }));
                     apply_map_edits(ctx, app, edits);
                     self.redo_stack.clear();
@@ -512,6 +729,7 @@ impl State<App> for RoadEditor {
         g.redraw(&self.fade_irrelevant);
         g.redraw(&self.lane_highlights.1);
         g.redraw(&self.draw_drop_position);
+        g.redraw(&self.extent_handles);
         self.top_panel.draw(g);
         self.main_panel.draw(g);
     }
@@ -843,6 +1061,17 @@ fn make_main_panel(
                     .hotkey(Key::F)
                     .build_def(ctx)
                     .centered_vert(),
+                ctx.style()
+                    .btn_plain
+                    .text("full length")
+                    .disabled(lane.extent.is_none())
+                    .disabled_tooltip(if can_shorten(lane) {
+                        "Drag the ends of the lane along the road to make it start or end partway"
+                    } else {
+                        "Sidewalks and shoulders always run the full length of the road"
+                    })
+                    .build_def(ctx)
+                    .centered_vert(),
                 Widget::row(vec![
                     Line("Width").secondary().into_widget(ctx).centered_vert(),
                     Widget::dropdown(ctx, "width preset", lane.width, width_choices(app, l)),
@@ -902,7 +1131,7 @@ fn make_main_panel(
         Widget::col(vec![line1, line2])
     };
 
-    let road_settings = Widget::row(vec![
+    let mut road_settings = vec![
         total_width,
         Line("Speed limit")
             .secondary()
@@ -920,7 +1149,90 @@ fn make_main_panel(
             .text("Access restrictions")
             .build_def(ctx)
             .centered_vert(),
-    ]);
+        Toggle::checkbox(
+            ctx,
+            "No through traffic (except access)",
+            None,
+            road.access_restrictions
+                .destination_only
+                .contains(PathConstraints::Car),
+        )
+        .centered_vert(),
+        ctx.style()
+            .btn_outline
+            .text("Kerb uses")
+            .build_def(ctx)
+            .centered_vert(),
+        ctx.style()
+            .btn_outline
+            .text("Make car-free")
+            .disabled(road.is_light_rail())
+            .build_def(ctx)
+            .centered_vert(),
+    ];
+    if road.lanes.iter().any(|l| l.is_bus()) {
+        road_settings.push(
+            Line("Bus lanes")
+                .secondary()
+                .into_widget(ctx)
+                .centered_vert(),
+        );
+        road_settings.push(
+            Widget::dropdown(
+                ctx,
+                "bus lane enforcement",
+                road.bus_lane_enforcement,
+                bus_lane_enforcement_choices(road.bus_lane_enforcement),
+            )
+            .centered_vert(),
+        );
+    }
+    if road.lanes.iter().any(|l| l.is_driving()) {
+        road_settings.push(
+            Line("Speed enforcement")
+                .secondary()
+                .into_widget(ctx)
+                .centered_vert(),
+        );
+        road_settings.push(
+            Widget::dropdown(
+                ctx,
+                "speed enforcement",
+                road.speed_enforcement,
+                speed_enforcement_choices(road.speed_enforcement),
+            )
+            .centered_vert(),
+        );
+        road_settings.push(Line("HGVs").secondary().into_widget(ctx).centered_vert());
+        road_settings.push(
+            Widget::dropdown(
+                ctx,
+                "HGV restrictions",
+                road.hgv.clone(),
+                hgv_choices(&road.hgv),
+            )
+            .centered_vert(),
+        );
+        road_settings.push(Line("Pricing").secondary().into_widget(ctx).centered_vert());
+        road_settings.push(
+            Widget::dropdown(ctx, "pricing", road.pricing.clone(), pricing_choices(road))
+                .centered_vert(),
+        );
+    }
+    // Like priced lanes, HOV lanes open up existing bus lanes
+    if road.lanes.iter().any(|l| l.is_bus()) {
+        road_settings.push(Line("HOV").secondary().into_widget(ctx).centered_vert());
+        road_settings.push(
+            Widget::dropdown(
+                ctx,
+                "HOV lanes",
+                road.hov_min_occupancy,
+                hov_choices(road.hov_min_occupancy),
+            )
+            .centered_vert(),
+        );
+    }
+    let road_settings = Widget::row(road_settings);
 
     Panel::new_builder(
         Widget::custom_col(vec![
@@ -948,6 +1260,106 @@ fn make_main_panel(
     .build_custom(ctx)
 }
 
+/// Policies a council might try. Height limits come from physical structures, so every choice
+/// keeps the current one.
+fn hgv_choices(current: &HgvRestrictions) -> Vec<Choice<HgvRestrictions>> {
+    let mut choices = Vec::new();
+    for access in [
+        HgvAccess::Allowed,
+        HgvAccess::Designated,
+        HgvAccess::Destination,
+        HgvAccess::Banned,
+    ] {
+        choices.push(HgvRestrictions {
+            access,
+            max_weight_kg: None,
+            max_height: current.max_height,
+        });
+    }
+    for max_weight_kg in [7_500, 18_000] {
+        choices.push(HgvRestrictions {
+            access: HgvAccess::Allowed,
+            max_weight_kg: Some(max_weight_kg),
+            max_height: current.max_height,
+        });
+    }
+    if !choices.contains(current) {
+        choices.push(current.clone());
+    }
+    choices
+        .into_iter()
+        .map(|x| Choice::new(x.to_string(), x))
+        .collect()
+}
+
+fn pricing_choices(road: &Road) -> Vec<Choice<RoadPricing>> {
+    let mut choices = vec![RoadPricing::Free];
+    for cents in [100, 200, 500] {
+        choices.push(RoadPricing::Toll(PriceSchedule::flat(cents)));
+    }
+    choices.push(RoadPricing::Toll(PriceSchedule::peak(100, 400)));
+    // Priced lanes only make sense where there are bus lanes to open up
+    if road.lanes.iter().any(|l| l.is_bus()) {
+        for cents in [100, 300] {
+            choices.push(RoadPricing::PricedLanes(PriceSchedule::flat(cents)));
+        }
+        choices.push(RoadPricing::PricedLanes(PriceSchedule::peak(50, 400)));
+    }
+    if !choices.contains(&road.pricing) {
+        choices.push(road.pricing.clone());
+    }
+    choices
+        .into_iter()
+        .map(|x| Choice::new(x.to_string(), x))
+        .collect()
+}
+
+fn hov_choices(current: Option<usize>) -> Vec<Choice<Option<usize>>> {
+    let mut choices = vec![None, Some(2), Some(3)];
+    if !choices.contains(&current) {
+        choices.push(current);
+    }
+    choices
+        .into_iter()
+        .map(|x| match x {
+            Some(min) => Choice::new(format!("cars with {}+ people", min), x),
+            None => Choice::new("buses only", x),
+        })
+        .collect()
+}
+
+fn speed_enforcement_choices(current: SpeedEnforcement) -> Vec<Choice<SpeedEnforcement>> {
+    let mut choices = vec![SpeedEnforcement::None];
+    for compliance_pct in [40, 60, 80] {
+        choices.push(SpeedEnforcement::SpotCamera { compliance_pct });
+    }
+    for compliance_pct in [80, 90, 100] {
+        choices.push(SpeedEnforcement::AverageSpeed { compliance_pct });
+    }
+    if !choices.contains(&current) {
+        choices.push(current);
+    }
+    choices
+        .into_iter()
+        .map(|x| Choice::new(x.to_string(), x))
+        .collect()
+}
+
+fn bus_lane_enforcement_choices(current: BusLaneEnforcement) -> Vec<Choice<BusLaneEnforcement>> {
+    let mut choices = vec![BusLaneEnforcement::Default];
+    for pct in [0, 10, 25, 50] {
+        choices.push(BusLaneEnforcement::ViolationPct(pct));
+    }
+    choices.push(BusLaneEnforcement::Camera);
+    if !choices.contains(&current) {
+        choices.push(current);
+    }
+    choices
+        .into_iter()
+        .map(|x| Choice::new(x.to_string(), x))
+        .collect()
+}
+
 fn selected_lane_bg(ctx: &EventCtx) -> Color {
     ctx.style().btn_tab.bg_disabled
 }
@@ -1027,6 +1439,77 @@ fn width_choices(app: &App, l: LaneID) -> Vec<Choice<Distance>> {
 
 // TODO We need to automatically fix the direction of sidewalks and parking as we initially place
 // them or shift them around. Until then, allow fixing in the UI manually.
+/// While dragging the start or end of a lane, snap to the end of the road within this distance.
+const EXTENT_SNAP_DIST: Distance = Distance::const_meters(3.0);
+
+#[derive(Clone, Copy)]
+enum ExtentEnd {
+    Start,
+    End,
+}
+
+fn can_shorten(lane: &Lane) -> bool {
+    !lane.lane_type.is_walkable()
+}
+
+/// Measured along the lane
+fn current_extent(lane: &Lane) -> LaneExtent {
+    lane.extent.unwrap_or(LaneExtent {
+        start: Distance::ZERO,
+        end: lane.length(),
+    })
+}
+
+fn extent_handle(lane: &Lane, dist: Distance) -> Polygon {
+    Circle::new(
+        lane.lane_center_pts.must_dist_along(dist).0,
+        lane.width / 2.0,
+    )
+    .to_polygon()
+}
+
+/// The editor works with extents measured along the lane, but edits store them along the road.
+fn lane_to_road_extent(road: &Road, lane: &Lane, extent: LaneExtent) -> LaneExtent {
+    let road_len = road.center_pts.length();
+    let extent = extent.rescaled(lane.length(), road_len);
+    if lane.dir == Direction::Fwd {
+        extent
+    } else {
+        extent.reversed(road_len)
+    }
+}
+
+/// If the selected lane is being dragged, preview its new shape.
+fn draw_extent_handles(
+    app: &App,
+    selected_lane: Option<LaneID>,
+    dragging: Option<LaneExtent>,
+) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    let lane = match selected_lane {
+        Some(l) => app.primary.map.get_l(l),
+        None => {
+            return batch;
+        }
+    };
+    if !can_shorten(lane) {
+        return batch;
+    }
+    let extent = dragging.unwrap_or_else(|| current_extent(lane));
+    if dragging.is_some() {
+        batch.push(
+            Color::hex("#4CA7E9").alpha(0.5),
+            extent.make_polygon(&lane.lane_center_pts, lane.width),
+        );
+    }
+    for dist in [extent.start, extent.end] {
+        let handle = extent_handle(lane, dist);
+        batch.push(Color::BLACK, handle.to_outline(OUTLINE_THICKNESS));
+        batch.push(Color::hex("#4CA7E9"), handle);
+    }
+    batch
+}
+
 fn can_reverse(_: LaneType) -> bool {
     true
 }
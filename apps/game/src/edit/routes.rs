@@ -1,13 +1,15 @@
+use abstutil::prettyprint_usize;
 use geom::{Duration, Time};
-use map_model::{EditCmd, TransitRouteID};
+use map_model::{EditCmd, PathConstraints, TransitRouteID};
 use widgetry::{
-    EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner, State, TextExt,
-    VerticalAlignment, Widget,
+    Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner, State,
+    TextExt, VerticalAlignment, Widget,
 };
 
 use crate::app::App;
 use crate::app::Transition;
 use crate::edit::apply_map_edits;
+use crate::edit::reroute::RerouteBus;
 
 pub struct RouteEditor {
     panel: Panel,
@@ -19,6 +21,25 @@ impl RouteEditor {
         app.primary.current_selection = None;
 
         let route = app.primary.map.get_tr(id);
+        // Start from the route's current frequency, if it's regular enough to tell
+        let mut gaps: Vec<Duration> = route
+            .spawn_times
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect();
+        gaps.sort();
+        let current_freq = gaps
+            .get(gaps.len() / 2)
+            .cloned()
+            .unwrap_or(Duration::hours(1))
+            .max(Duration::minutes(1))
+            .min(Duration::hours(2));
+        let denied: usize = route
+            .stops
+            .iter()
+            .map(|ts| app.primary.sim.get_analytics().transit_crowding(*ts, id).0)
+            .sum();
+
         Box::new(RouteEditor {
             panel: Panel::new_builder(Widget::col(vec![
                 Widget::row(vec![
@@ -26,6 +47,17 @@ impl RouteEditor {
                     ctx.style().btn_close_widget(ctx),
                 ]),
                 Line(&route.long_name).into_widget(ctx),
+                if denied > 0 {
+                    Line(format!(
+                        "So far, {} riders were left behind by full {}",
+                        prettyprint_usize(denied),
+                        route.plural_noun()
+                    ))
+                    .fg(Color::RED)
+                    .into_widget(ctx)
+                } else {
+                    Widget::nothing()
+                },
                 // TODO This UI needs design, just something to start plumbing the edits
                 Widget::row(vec![
                     "Frequency".text_widget(ctx),
@@ -33,15 +65,43 @@ impl RouteEditor {
                         ctx,
                         "freq_mins",
                         (Duration::minutes(1), Duration::hours(2)),
-                        Duration::hours(1),
+                        current_freq,
                         Duration::minutes(1),
                     ),
                 ]),
+                Line("Only change the schedule between")
+                    .secondary()
+                    .into_widget(ctx),
+                Widget::row(vec![
+                    Spinner::widget(
+                        ctx,
+                        "start",
+                        (Duration::ZERO, Duration::hours(24)),
+                        Duration::ZERO,
+                        Duration::minutes(30),
+                    ),
+                    "and".text_widget(ctx).centered_vert(),
+                    Spinner::widget(
+                        ctx,
+                        "end",
+                        (Duration::ZERO, Duration::hours(24)),
+                        Duration::hours(24),
+                        Duration::minutes(30),
+                    ),
+                ]),
                 ctx.style()
                     .btn_solid_primary
                     .text("Apply")
                     .hotkey(Key::Enter)
                     .build_def(ctx),
+                if route.route_type == PathConstraints::Bus {
+                    ctx.style()
+                        .btn_outline
+                        .text("Reroute along different streets")
+                        .build_def(ctx)
+                } else {
+                    Widget::nothing()
+                },
             ]))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
             .build(ctx),
@@ -61,23 +121,36 @@ impl State<App> for RouteEditor {
                 }
                 "Apply" => {
                     let freq = self.panel.spinner("freq_mins");
-                    let mut now = Time::START_OF_DAY;
-                    let mut hourly_times = Vec::new();
-                    while now <= Time::START_OF_DAY + Duration::hours(24) {
-                        hourly_times.push(now);
+                    let start = Time::START_OF_DAY + self.panel.spinner::<Duration>("start");
+                    let end = Time::START_OF_DAY + self.panel.spinner::<Duration>("end");
+                    let old = app.primary.map.get_tr(self.route).spawn_times.clone();
+
+                    // Keep the existing schedule outside of the time window
+                    let mut new: Vec<Time> = old
+                        .iter()
+                        .filter(|t| **t < start || **t > end)
+                        .cloned()
+                        .collect();
+                    let mut now = start;
+                    while now <= end {
+                        new.push(now);
                         now += freq;
                     }
+                    new.sort();
 
                     let mut edits = app.primary.map.get_edits().clone();
                     edits.commands.push(EditCmd::ChangeRouteSchedule {
                         id: self.route,
-                        old: app.primary.map.get_tr(self.route).spawn_times.clone(),
-                        new: hourly_times,
+                        old,
+                        new,
                     });
                     apply_map_edits(ctx, app, edits);
 
                     return Transition::Pop;
                 }
+                "Reroute along different streets" => {
+                    return Transition::Replace(RerouteBus::new_state(ctx, app, self.route));
+                }
                 _ => unreachable!(),
             }
         }
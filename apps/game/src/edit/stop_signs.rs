@@ -5,7 +5,8 @@ use maplit::btreeset;
 use geom::Polygon;
 use map_gui::render::DrawIntersection;
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, EditIntersectionControl, IntersectionID, RoadID,
+    ApproachControl, ControlStopSign, ControlTrafficSignal, EditIntersectionControl,
+    IntersectionID, RoadID,
 };
 use widgetry::{
     EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Panel, SimpleState, State, Text,
@@ -70,6 +71,18 @@ impl StopSignEditor {
                     .text("Change crosswalks")
                     .hotkey(Key::C)
                     .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Change cycling corners")
+                    .build_def(ctx),
+            ]),
+            Widget::row(vec![
+                ctx.style().btn_outline.text("all-way stop").build_def(ctx),
+                ctx.style().btn_outline.text("two-way stop").build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("give way on minor roads")
+                    .build_def(ctx),
             ]),
             Widget::row(vec![
                 ctx.style()
@@ -81,6 +94,16 @@ impl StopSignEditor {
                     .text("convert to traffic signal")
                     .build_def(ctx),
             ]),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("Save as template")
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_outline
+                    .text("Apply template")
+                    .build_def(ctx),
+            ]),
         ]))
         .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
         .build(ctx);
@@ -95,6 +118,22 @@ impl StopSignEditor {
             }),
         )
     }
+
+    fn change_sign(&self, ctx: &mut EventCtx, app: &mut App, sign: ControlStopSign) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        edits
+            .commands
+            .push(app.primary.map.edit_intersection_cmd(self.id, |new| {
+                new.control = EditIntersectionControl::StopSign(sign);
+            }));
+        apply_map_edits(ctx, app, edits);
+        Transition::Replace(StopSignEditor::new_state(
+            ctx,
+            app,
+            self.id,
+            self.mode.clone(),
+        ))
+    }
 }
 
 impl SimpleState<App> for StopSignEditor {
@@ -108,22 +147,23 @@ impl SimpleState<App> for StopSignEditor {
         match x {
             "Finish" => Transition::Pop,
             "reset to default" => {
-                let mut edits = app.primary.map.get_edits().clone();
-                edits
-                    .commands
-                    .push(app.primary.map.edit_intersection_cmd(self.id, |new| {
-                        new.control = EditIntersectionControl::StopSign(ControlStopSign::new(
-                            &app.primary.map,
-                            self.id,
-                        ));
-                    }));
-                apply_map_edits(ctx, app, edits);
-                Transition::Replace(StopSignEditor::new_state(
-                    ctx,
-                    app,
-                    self.id,
-                    self.mode.clone(),
-                ))
+                let sign = ControlStopSign::new(&app.primary.map, self.id);
+                self.change_sign(ctx, app, sign)
+            }
+            "all-way stop" => {
+                let mut sign = app.primary.map.get_stop_sign(self.id).clone();
+                sign.set_all_way_stop(&app.primary.map);
+                self.change_sign(ctx, app, sign)
+            }
+            "two-way stop" => {
+                let mut sign = app.primary.map.get_stop_sign(self.id).clone();
+                sign.set_minor_roads(&app.primary.map, ApproachControl::Stop);
+                self.change_sign(ctx, app, sign)
+            }
+            "give way on minor roads" => {
+                let mut sign = app.primary.map.get_stop_sign(self.id).clone();
+                sign.set_minor_roads(&app.primary.map, ApproachControl::GiveWay);
+                self.change_sign(ctx, app, sign)
             }
             "close intersection for construction" => {
                 let cmd = app.primary.map.edit_intersection_cmd(self.id, |new| {
@@ -163,6 +203,15 @@ impl SimpleState<App> for StopSignEditor {
             "Change crosswalks" => Transition::Replace(
                 super::crosswalks::CrosswalkEditor::new_state(ctx, app, self.id),
             ),
+            "Change cycling corners" => Transition::Replace(
+                super::corner_design::CornerDesignEditor::new_state(ctx, app, self.id),
+            ),
+            "Save as template" => {
+                Transition::Push(super::junction_templates::save_template(ctx, self.id))
+            }
+            "Apply template" => {
+                Transition::Replace(super::junction_templates::apply_template(ctx, self.id))
+            }
             _ => unreachable!(),
         }
     }
@@ -184,27 +233,14 @@ impl SimpleState<App> for StopSignEditor {
 
         if let Some(r) = self.selected_sign {
             let mut sign = app.primary.map.get_stop_sign(self.id).clone();
-            let label = if sign.roads[&r].must_stop {
-                "remove stop sign"
-            } else {
-                "add stop sign"
+            let label = match sign.roads[&r].control {
+                ApproachControl::Priority => "add give way sign",
+                ApproachControl::GiveWay => "change to stop sign",
+                ApproachControl::Stop => "remove stop sign",
             };
             if app.per_obj.left_click(ctx, label) {
-                sign.flip_sign(r);
-
-                let mut edits = app.primary.map.get_edits().clone();
-                edits
-                    .commands
-                    .push(app.primary.map.edit_intersection_cmd(self.id, |new| {
-                        new.control = EditIntersectionControl::StopSign(sign);
-                    }));
-                apply_map_edits(ctx, app, edits);
-                return Transition::Replace(StopSignEditor::new_state(
-                    ctx,
-                    app,
-                    self.id,
-                    self.mode.clone(),
-                ));
+                sign.cycle_sign(r);
+                return self.change_sign(ctx, app, sign);
             }
         }
 
@@ -221,10 +257,10 @@ impl SimpleState<App> for StopSignEditor {
             // The intersection will already draw enabled stop signs
             if Some(*r) == self.selected_sign {
                 batch.push(app.cs.perma_selected_object, octagon.clone());
-                if !sign.roads[r].must_stop {
+                if sign.roads[r].control == ApproachControl::Priority {
                     batch.push(app.cs.stop_sign_pole.alpha(0.6), pole.clone());
                 }
-            } else if !sign.roads[r].must_stop {
+            } else if sign.roads[r].control == ApproachControl::Priority {
                 batch.push(app.cs.stop_sign.alpha(0.6), octagon.clone());
                 batch.push(app.cs.stop_sign_pole.alpha(0.6), pole.clone());
             }
@@ -235,7 +271,11 @@ impl SimpleState<App> for StopSignEditor {
         if let Some(r) = self.selected_sign {
             let mut osd = Text::new();
             osd.add_appended(vec![
-                Line("Stop sign for "),
+                Line(match sign.roads[&r].control {
+                    ApproachControl::Priority => "No sign for ",
+                    ApproachControl::GiveWay => "Give way sign for ",
+                    ApproachControl::Stop => "Stop sign for ",
+                }),
                 Line(
                     app.primary
                         .map
@@ -1,7 +1,8 @@
 use geom::Duration;
 use map_gui::tools::FilePicker;
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, EditIntersectionControl, IntersectionID, StageType,
+    Actuation, ControlStopSign, ControlTrafficSignal, EditIntersectionControl, IntersectionID,
+    StageType,
 };
 use widgetry::tools::{ChooseSomething, PopupMsg};
 use widgetry::{
@@ -176,6 +177,117 @@ impl SimpleState<App> for ChangeDuration {
     }
 }
 
+pub struct ChangeActuation;
+
+impl ChangeActuation {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        signal: &ControlTrafficSignal,
+    ) -> Box<dyn State<App>> {
+        let actuation = signal.actuation.clone().unwrap_or_default();
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("How should detected vehicles change the signal?")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Widget::col(vec![
+                Widget::row(vec![
+                    "Minimum green:".text_widget(ctx).centered_vert(),
+                    Spinner::widget(
+                        ctx,
+                        "min green",
+                        (Duration::seconds(1.0), Duration::minutes(2)),
+                        actuation.min_green,
+                        Duration::seconds(1.0),
+                    ),
+                ]),
+                Widget::row(vec![
+                    "Maximum green:".text_widget(ctx).centered_vert(),
+                    Spinner::widget(
+                        ctx,
+                        "max green",
+                        (Duration::seconds(1.0), Duration::minutes(5)),
+                        actuation.max_green,
+                        Duration::seconds(1.0),
+                    ),
+                ]),
+                Widget::row(vec![
+                    "End the green when no vehicle arrives for:"
+                        .text_widget(ctx)
+                        .centered_vert(),
+                    Spinner::widget(
+                        ctx,
+                        "gap",
+                        (Duration::seconds(1.0), Duration::seconds(30.0)),
+                        actuation.gap,
+                        Duration::seconds(1.0),
+                    ),
+                ]),
+            ])
+            .padding(10)
+            .bg(app.cs.inner_panel_bg)
+            .outline(ctx.style().section_outline),
+            Line("Stages with crosswalks always last long enough to cross")
+                .secondary()
+                .into_widget(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Apply")
+                .hotkey(Key::Enter)
+                .build_def(ctx),
+        ]))
+        .build(ctx);
+        <dyn SimpleState<_>>::new_state(panel, Box::new(ChangeActuation))
+    }
+}
+
+impl SimpleState<App> for ChangeActuation {
+    fn on_click(
+        &mut self,
+        _: &mut EventCtx,
+        _: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "close" => Transition::Pop,
+            "Apply" => {
+                let min_green: Duration = panel.spinner("min green");
+                let actuation = Actuation {
+                    min_green,
+                    max_green: panel.spinner::<Duration>("max green").max(min_green),
+                    gap: panel.spinner("gap"),
+                };
+                Transition::Multi(vec![
+                    Transition::Pop,
+                    Transition::ModifyState(Box::new(move |state, ctx, app| {
+                        let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
+                        let idx = editor.current_stage;
+                        editor.add_new_edit(ctx, app, idx, |ts| {
+                            ts.actuation = Some(actuation.clone());
+                        });
+                    })),
+                ])
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn other_event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        if ctx.normal_left_click() && ctx.canvas.get_cursor_in_screen_space().is_none() {
+            return Transition::Pop;
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
+
 pub fn edit_entire_signal(
     ctx: &mut EventCtx,
     app: &App,
@@ -240,7 +352,15 @@ pub fn edit_entire_signal(
                         Transition::ModifyState(Box::new(move |state, ctx, app| {
                             let editor = state.downcast_mut::<TrafficSignalEditor>().unwrap();
                             editor.add_new_edit(ctx, app, 0, |ts| {
+                                // Templates only cover stages; keep the transit priority,
+                                // leading pedestrian interval, and actuation
+                                let transit_priority = ts.transit_priority.take();
+                                let lpi = ts.leading_pedestrian_interval;
+                                let actuation = ts.actuation.take();
                                 *ts = new_signal.clone();
+                                ts.transit_priority = transit_priority;
+                                ts.leading_pedestrian_interval = lpi;
+                                ts.actuation = actuation;
                             });
                         })),
                     ])
@@ -7,14 +7,14 @@ use geom::{Distance, Line, Polygon, Pt2D};
 use map_gui::options::TrafficSignalStyle;
 use map_gui::render::{traffic_signal, DrawMovement, DrawOptions};
 use map_model::{
-    ControlTrafficSignal, EditIntersectionControl, IntersectionID, MovementID, Stage, StageType,
-    TurnPriority,
+    Actuation, ControlTrafficSignal, EditIntersectionControl, IntersectionID, MovementID, Stage,
+    StageType, TransitPriority, TurnPriority, DEFAULT_LEADING_PEDESTRIAN_INTERVAL,
 };
 use widgetry::tools::PopupMsg;
 use widgetry::{
     include_labeled_bytes, lctrl, Color, ControlState, DragDrop, DrawBaselayer, Drawable, EventCtx,
     GeomBatch, GeomBatchStack, GfxCtx, HorizontalAlignment, Image, Key, Line, Outcome, Panel,
-    RewriteColor, StackAxis, State, Text, TextExt, VerticalAlignment, Widget,
+    RewriteColor, StackAxis, State, Text, TextExt, Toggle, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, ShowEverything, Transition};
@@ -63,10 +63,14 @@ impl TrafficSignalEditor {
     pub fn new_state(
         ctx: &mut EventCtx,
         app: &mut App,
-        members: BTreeSet<IntersectionID>,
+        mut members: BTreeSet<IntersectionID>,
         mode: GameplayMode,
     ) -> Box<dyn State<App>> {
         app.primary.current_selection = None;
+        // Signals running as one controller are always edited together
+        for i in members.clone() {
+            members.extend(app.primary.map.get_traffic_signal(i).cluster.clone());
+        }
 
         let original = BundleEdits::get_current(app, &members);
         let synced = BundleEdits::synchronize(app, &members);
@@ -185,6 +189,9 @@ impl TrafficSignalEditor {
                 .get_traffic_signal(*i)
                 .validate(app.primary.map.get_i(*i))?;
         }
+        if is_clustered(app, &self.members) {
+            ControlTrafficSignal::validate_cluster(&app.primary.map, &self.members)?;
+        }
         Ok(())
     }
 }
@@ -234,6 +241,13 @@ impl State<App> for TrafficSignalEditor {
                     });
                     return Transition::Keep;
                 }
+                "change actuation" => {
+                    return Transition::Push(edits::ChangeActuation::new_state(
+                        ctx,
+                        app,
+                        canonical_signal,
+                    ));
+                }
                 "change duration" => {
                     return Transition::Push(edits::ChangeDuration::new_state(
                         ctx,
@@ -272,6 +286,60 @@ impl State<App> for TrafficSignalEditor {
                     ts.stages.swap(old_idx, new_idx);
                 });
             }
+            Outcome::Changed(x) if x == "leading pedestrian interval" => {
+                let lpi = if self.side_panel.is_checked("leading pedestrian interval") {
+                    Some(DEFAULT_LEADING_PEDESTRIAN_INTERVAL)
+                } else {
+                    None
+                };
+                self.add_new_edit(ctx, app, self.current_stage, |ts| {
+                    ts.leading_pedestrian_interval = lpi;
+                });
+            }
+            Outcome::Changed(x) if x == "transit signal priority" => {
+                let priority = if self.side_panel.is_checked("transit signal priority") {
+                    Some(TransitPriority::default())
+                } else {
+                    None
+                };
+                self.add_new_edit(ctx, app, self.current_stage, |ts| {
+                    ts.transit_priority = priority.clone();
+                });
+            }
+            Outcome::Changed(x) if x == "vehicle-actuated" => {
+                let actuation = if self.side_panel.is_checked("vehicle-actuated") {
+                    Some(Actuation::default())
+                } else {
+                    None
+                };
+                self.add_new_edit(ctx, app, self.current_stage, |ts| {
+                    ts.actuation = actuation.clone();
+                });
+            }
+            Outcome::Changed(x) if x == "run as one controller" => {
+                let cluster = if self.side_panel.is_checked("run as one controller") {
+                    self.members.clone()
+                } else {
+                    BTreeSet::new()
+                };
+                // Members of a cluster share one offset
+                let offset = canonical_signal.offset;
+                self.add_new_edit(ctx, app, self.current_stage, |ts| {
+                    ts.cluster = cluster.clone();
+                    ts.offset = offset;
+                });
+                if let Err(err) = self.validate_all_members(app) {
+                    self.command_stack.pop().unwrap().apply(app);
+                    self.top_panel =
+                        make_top_panel(ctx, app, !self.command_stack.is_empty(), false);
+                    self.change_stage(ctx, app, self.current_stage);
+                    return Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Error",
+                        vec![err.to_string()],
+                    ));
+                }
+            }
             _ => {}
         }
 
@@ -355,6 +423,37 @@ impl State<App> for TrafficSignalEditor {
                         );
                     }
                 }
+                "Save as template" | "Apply template" => {
+                    if self.members.len() != 1 {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec!["Junction templates only work with one signal at a time"],
+                        ));
+                    }
+                    if let Err(err) = self.validate_all_members(app) {
+                        error!("{}", err);
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec!["This signal configuration is somehow invalid; check the console logs"]
+                        ));
+                    }
+                    let i = *self.members.iter().next().unwrap();
+                    if x == "Save as template" {
+                        return Transition::Push(crate::edit::junction_templates::save_template(
+                            ctx, i,
+                        ));
+                    }
+                    // Like editing multiple signals, commit the current changes first
+                    let changes = check_for_missing_turns(app, &self.members)
+                        .unwrap_or_else(|| BundleEdits::get_current(app, &self.members));
+                    self.original.apply(app);
+                    changes.commit(ctx, app);
+                    return Transition::Replace(crate::edit::junction_templates::apply_template(
+                        ctx, i,
+                    ));
+                }
                 "Change crosswalks" => {
                     // TODO Probably need to follow everything Cancel does
                     return Transition::Replace(super::crosswalks::CrosswalkEditor::new_state(
@@ -363,6 +462,15 @@ impl State<App> for TrafficSignalEditor {
                         *self.members.iter().next().unwrap(),
                     ));
                 }
+                "Change cycling corners" => {
+                    return Transition::Replace(
+                        super::corner_design::CornerDesignEditor::new_state(
+                            ctx,
+                            app,
+                            *self.members.iter().next().unwrap(),
+                        ),
+                    );
+                }
                 "Preview" => {
                     // Might have to do this first!
                     app.primary
@@ -549,6 +657,24 @@ fn make_top_panel(ctx: &mut EventCtx, app: &App, can_undo: bool, can_redo: bool)
         .text("Change crosswalks")
         .hotkey(Key::C)
         .build_def(ctx)];
+    second_row.push(
+        ctx.style()
+            .btn_outline
+            .text("Change cycling corners")
+            .build_def(ctx),
+    );
+    second_row.push(
+        ctx.style()
+            .btn_outline
+            .text("Save as template")
+            .build_def(ctx),
+    );
+    second_row.push(
+        ctx.style()
+            .btn_outline
+            .text("Apply template")
+            .build_def(ctx),
+    );
     if app.opts.dev {
         second_row.push(
             ctx.style()
@@ -773,6 +899,9 @@ fn make_side_panel(
                 .text("Edit entire signal")
                 .hotkey(Key::E)
                 .build_def(ctx)
+        } else if is_clustered(app, members) {
+            // Every member shares one offset
+            Widget::nothing()
         } else {
             ctx.style()
                 .btn_outline
@@ -781,6 +910,106 @@ fn make_side_panel(
                 .build_def(ctx)
         },
     ]));
+    if members.len() > 1 {
+        col.push(Toggle::checkbox(
+            ctx,
+            "run as one controller",
+            None,
+            is_clustered(app, members),
+        ));
+        col.push(
+            Line(if is_clustered(app, members) {
+                "Stages change at every member at the same time, based on demand at all of them"
+            } else {
+                "Each signal decides when to change stages separately"
+            })
+            .secondary()
+            .into_widget(ctx),
+        );
+    }
+
+    // Transit priority applies to all members together, like stage durations
+    col.push(Toggle::checkbox(
+        ctx,
+        "transit signal priority",
+        None,
+        canonical_signal.transit_priority.is_some(),
+    ));
+    if let Some(ref priority) = canonical_signal.transit_priority {
+        col.push(
+            Line(format!(
+                "Buses delayed by {}+ can extend a green by {} or cut a red short by {}",
+                priority.min_lateness, priority.max_extension, priority.max_early_end
+            ))
+            .secondary()
+            .into_widget(ctx),
+        );
+    }
+
+    // Like transit priority, actuation applies to all members together
+    col.push(Widget::row(vec![
+        Toggle::checkbox(
+            ctx,
+            "vehicle-actuated",
+            None,
+            canonical_signal.actuation.is_some(),
+        ),
+        if canonical_signal.actuation.is_some() {
+            ctx.style()
+                .btn_plain
+                .icon("system/assets/tools/pencil.svg")
+                .build_widget(ctx, "change actuation")
+        } else {
+            Widget::nothing()
+        },
+    ]));
+    if let Some(ref actuation) = canonical_signal.actuation {
+        col.push(
+            Line(format!(
+                "Greens last {} to {}, ending early after {} with no vehicles arriving. \
+                 Stages nobody is waiting for are skipped.",
+                actuation.min_green, actuation.max_green, actuation.gap
+            ))
+            .secondary()
+            .into_widget(ctx),
+        );
+    }
+
+    // Like transit priority, this applies to all members together
+    let has_crossings = members.iter().any(|i| {
+        map.get_i(*i)
+            .turns
+            .iter()
+            .any(|t| t.turn_type.pedestrian_crossing())
+    });
+    if has_crossings {
+        col.push(Toggle::checkbox(
+            ctx,
+            "leading pedestrian interval",
+            None,
+            canonical_signal.leading_pedestrian_interval.is_some(),
+        ));
+        if let Some(lpi) = canonical_signal.leading_pedestrian_interval {
+            col.push(
+                Line(format!(
+                    "People walking start crossing {} before conflicting turns",
+                    lpi
+                ))
+                .secondary()
+                .into_widget(ctx),
+            );
+        }
+        if members
+            .iter()
+            .all(|i| map.get_traffic_signal(*i).has_ped_scramble(map.get_i(*i)))
+        {
+            col.push(
+                Line("Has an all-walk scramble stage")
+                    .secondary()
+                    .into_widget(ctx),
+            );
+        }
+    }
 
     Panel::new_builder(Widget::col(col))
         .aligned(HorizontalAlignment::Left, VerticalAlignment::Center)
@@ -810,6 +1039,10 @@ impl BundleEdits {
                 .push(app.primary.map.edit_intersection_cmd(signal.id, |new| {
                     new.control =
                         EditIntersectionControl::TrafficSignal(signal.export(&app.primary.map));
+                    new.transit_priority = signal.transit_priority.clone();
+                    new.leading_pedestrian_interval = signal.leading_pedestrian_interval;
+                    new.actuation = signal.actuation.clone();
+                    new.signal_cluster = signal.cluster.clone();
                 }));
         }
         apply_map_edits(ctx, app, edits);
@@ -844,6 +1077,9 @@ impl BundleEdits {
                 }
                 signal.stages[idx].stage_type = canonical_stage.stage_type.clone();
             }
+            signal.transit_priority = canonical.transit_priority.clone();
+            signal.leading_pedestrian_interval = canonical.leading_pedestrian_interval;
+            signal.actuation = canonical.actuation.clone();
             signals.push(signal);
         }
 
@@ -851,6 +1087,14 @@ impl BundleEdits {
     }
 }
 
+/// Do all of the members form one cluster, run by one controller?
+fn is_clustered(app: &App, members: &BTreeSet<IntersectionID>) -> bool {
+    members.len() > 1
+        && members
+            .iter()
+            .all(|i| &app.primary.map.get_traffic_signal(*i).cluster == members)
+}
+
 // If None, nothing missing.
 fn check_for_missing_turns(app: &App, members: &BTreeSet<IntersectionID>) -> Option<BundleEdits> {
     let mut all_missing = BTreeSet::new();
@@ -0,0 +1,430 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+
+use geom::{Circle, Distance};
+use map_model::{EditCmd, Map, PathConstraints, Position, TransitRouteID, TransitStopID};
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State,
+    Text, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::edit::apply_map_edits;
+use crate::ID;
+
+/// Stops closer than this can be merged
+const MERGE_RADIUS: Distance = Distance::const_meters(300.0);
+/// Roughly a five minute walk
+const WALK_ACCESS_RADIUS: Distance = Distance::const_meters(400.0);
+
+/// Move, remove, or merge one bus stop. Also summarizes how all bus stop edits so far affect each
+/// route.
+pub struct TransitStopEditor {
+    panel: Panel,
+    id: TransitStopID,
+    /// Stops this one could be merged into
+    nearby: Vec<TransitStopID>,
+}
+
+impl TransitStopEditor {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App, id: TransitStopID) -> Box<dyn State<App>> {
+        app.primary.current_selection = None;
+        update_pathfinding(ctx, app);
+
+        let map = &app.primary.map;
+        let stop = map.get_ts(id);
+        let pt = stop.sidewalk_pos.pt(map);
+        let mut nearby: Vec<(Distance, TransitStopID)> = map
+            .all_transit_stops()
+            .values()
+            .filter(|other| other.id != id && !other.is_train_stop)
+            .map(|other| (other.sidewalk_pos.pt(map).dist_to(pt), other.id))
+            .filter(|(dist, _)| *dist <= MERGE_RADIUS)
+            .collect();
+        nearby.sort();
+
+        let routes: Vec<String> = map
+            .get_routes_serving_stop(id)
+            .into_iter()
+            .map(|r| r.short_name.clone())
+            .collect();
+        let mut txt = Text::from(Line(&stop.name));
+        if routes.is_empty() {
+            txt.add_line(Line("No routes stop here").secondary());
+        } else {
+            txt.add_line(Line(format!("Served by {}", routes.join(", "))).secondary());
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Bus stop").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            txt.into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("Move")
+                    .hotkey(Key::M)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_solid_destructive
+                    .text("Remove")
+                    .build_def(ctx),
+            ]),
+        ];
+        if !nearby.is_empty() {
+            col.push(
+                Line(format!(
+                    "Merge into a stop within {}",
+                    MERGE_RADIUS.to_string(&app.opts.units)
+                ))
+                .small_heading()
+                .into_widget(ctx),
+            );
+            for (idx, (dist, other)) in nearby.iter().enumerate() {
+                col.push(
+                    ctx.style()
+                        .btn_outline
+                        .text(format!(
+                            "{} ({} away)",
+                            map.get_ts(*other).name,
+                            dist.to_string(&app.opts.units)
+                        ))
+                        .build_widget(ctx, format!("merge into #{}", idx)),
+                );
+            }
+        }
+        col.push(route_changes(ctx, app));
+
+        Box::new(TransitStopEditor {
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+                .build(ctx),
+            id,
+            nearby: nearby.into_iter().map(|(_, id)| id).collect(),
+        })
+    }
+}
+
+impl State<App> for TransitStopEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            let cmds = match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Move" => {
+                    return Transition::Replace(PlaceBusStop::new_state(ctx, app, Some(self.id)));
+                }
+                "Remove" => match app.primary.map.remove_bus_stop_cmds(self.id) {
+                    Ok(cmds) => cmds,
+                    Err(err) => {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Can't remove this stop",
+                            vec![err.to_string()],
+                        ));
+                    }
+                },
+                x => {
+                    let idx = x["merge into #".len()..].parse::<usize>().unwrap();
+                    app.primary
+                        .map
+                        .merge_bus_stops_cmds(self.id, self.nearby[idx])
+                }
+            };
+            return match apply_stop_edits(ctx, app, cmds) {
+                Ok(()) => Transition::Pop,
+                Err(err) => Transition::Push(PopupMsg::new_state(
+                    ctx,
+                    "Routes can't serve these stops",
+                    vec![format!("{:#}", err)],
+                )),
+            };
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+/// Click along a sidewalk to add a new bus stop, or to move an existing one along its road.
+pub struct PlaceBusStop {
+    panel: Panel,
+    moving: Option<TransitStopID>,
+    hovering: Option<Position>,
+    draw: Drawable,
+}
+
+impl PlaceBusStop {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        moving: Option<TransitStopID>,
+    ) -> Box<dyn State<App>> {
+        app.primary.current_selection = None;
+        update_pathfinding(ctx, app);
+
+        let instructions = if let Some(id) = moving {
+            format!(
+                "Click along a sidewalk of {} to move {}",
+                app.primary
+                    .map
+                    .get_parent(app.primary.map.get_ts(id).sidewalk_pos.lane())
+                    .get_name(app.opts.language.as_ref()),
+                app.primary.map.get_ts(id).name
+            )
+        } else {
+            "Click along a sidewalk to add a bus stop. Routes driving past will serve it."
+                .to_string()
+        };
+
+        Box::new(PlaceBusStop {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line(if moving.is_some() {
+                        "Move bus stop"
+                    } else {
+                        "Add bus stop"
+                    })
+                    .small_heading()
+                    .into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                Text::from(Line(instructions))
+                    .wrap_to_pct(ctx, 30)
+                    .into_widget(ctx),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            moving,
+            hovering: None,
+            draw: Drawable::empty(ctx),
+        })
+    }
+
+    fn position_at_cursor(&self, ctx: &EventCtx, app: &App) -> Option<Position> {
+        let map = &app.primary.map;
+        let l = match app.mouseover_unzoomed_roads_and_intersections(ctx)? {
+            ID::Lane(l) => l,
+            _ => {
+                return None;
+            }
+        };
+        let lane = map.get_l(l);
+        if !lane.is_walkable() {
+            return None;
+        }
+        if let Some(id) = self.moving {
+            if l.road != id.road {
+                return None;
+            }
+        }
+        let road = map.get_r(l.road);
+        // Buses have to be able to reach the stop
+        road.find_closest_lane(l, |other| PathConstraints::Bus.can_use(other, map))?;
+        let pt = ctx.canvas.get_cursor_in_map_space()?;
+        let (dist, _) = lane
+            .lane_center_pts
+            .dist_along_of_point(lane.lane_center_pts.project_pt(pt))?;
+        Some(Position::new(l, dist))
+    }
+}
+
+impl State<App> for PlaceBusStop {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if ctx.redo_mouseover() {
+            self.hovering = self.position_at_cursor(ctx, app);
+            let mut batch = GeomBatch::new();
+            if let Some(pos) = self.hovering {
+                batch.push(
+                    app.cs.selected,
+                    Circle::new(pos.pt(&app.primary.map), Distance::meters(2.0)).to_polygon(),
+                );
+            }
+            self.draw = ctx.upload(batch);
+        }
+
+        if let Some(pos) = self.hovering {
+            let label = if self.moving.is_some() {
+                "move the stop here"
+            } else {
+                "add a stop here"
+            };
+            if app.per_obj.left_click(ctx, label) {
+                let map = &app.primary.map;
+                let result = if let Some(id) = self.moving {
+                    Ok((id, vec![map.move_bus_stop_cmd(id, pos)]))
+                } else {
+                    let name = format!(
+                        "{} (new stop)",
+                        map.get_parent(pos.lane())
+                            .get_name(app.opts.language.as_ref())
+                    );
+                    map.add_bus_stop_cmds(pos, name).map(|cmds| {
+                        let id = match cmds[0] {
+                            EditCmd::ChangeTransitStop { id, .. } => id,
+                            _ => unreachable!(),
+                        };
+                        (id, cmds)
+                    })
+                };
+                return match result.and_then(|(id, cmds)| {
+                    apply_stop_edits(ctx, app, cmds)?;
+                    Ok(id)
+                }) {
+                    Ok(id) => Transition::Replace(TransitStopEditor::new_state(ctx, app, id)),
+                    Err(err) => Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Routes can't serve this stop",
+                        vec![format!("{:#}", err)],
+                    )),
+                };
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+    }
+}
+
+/// Checking routes against new stops needs vehicle pathfinding to match the current map.
+pub fn update_pathfinding(ctx: &mut EventCtx, app: &mut App) {
+    ctx.loading_screen("update pathfinding", |_, timer| {
+        app.primary.map.recalculate_pathfinding_after_edits(timer);
+    });
+}
+
+/// Bus stop edits and rerouting could leave a route unable to drive between its stops, so first
+/// try them on a copy of the map.
+pub fn apply_stop_edits(ctx: &mut EventCtx, app: &mut App, cmds: Vec<EditCmd>) -> Result<()> {
+    let mut edits = app.primary.map.get_edits().clone();
+    edits.commands.extend(cmds.clone());
+
+    ctx.loading_screen("check bus routes", |_, timer| -> Result<()> {
+        let mut map = app.primary.map.clone();
+        map.must_apply_edits(edits.clone(), timer);
+        // Only stops changed, so the pathfinding for buses is still correct
+        map.keep_pathfinder_despite_edits();
+
+        let mut routes: BTreeSet<TransitRouteID> = BTreeSet::new();
+        for cmd in &cmds {
+            match cmd {
+                EditCmd::ChangeRouteStops { id, .. } | EditCmd::ChangeRouteAlignment { id, .. } => {
+                    routes.insert(*id);
+                }
+                EditCmd::ChangeTransitStop { id, .. } => {
+                    for route in map.get_routes_serving_stop(*id) {
+                        routes.insert(route.id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        for r in routes {
+            let route = map.get_tr(r);
+            route
+                .all_paths(&map)
+                .with_context(|| format!("{} can't serve these stops", route.long_name))?;
+        }
+        Ok(())
+    })?;
+
+    apply_map_edits(ctx, app, edits);
+    update_pathfinding(ctx, app);
+    Ok(())
+}
+
+/// For every route with different stops than the basemap, compare the number of stops, the run
+/// time, and how many buildings are close to a stop.
+fn route_changes(ctx: &mut EventCtx, app: &App) -> Widget {
+    let map = &app.primary.map;
+    let unedited_map = match app
+        .primary
+        .unedited_map
+        .as_ref()
+        .or_else(|| app.secondary.as_ref().map(|x| &x.map))
+    {
+        Some(x) => x,
+        None => {
+            return Widget::nothing();
+        }
+    };
+
+    let edits = map.get_edits();
+    let mut routes: BTreeSet<TransitRouteID> = edits.original_route_stops.keys().cloned().collect();
+    for ts in edits.original_transit_stops.keys() {
+        for route in map.get_routes_serving_stop(*ts) {
+            routes.insert(route.id);
+        }
+    }
+    if routes.is_empty() {
+        return Widget::nothing();
+    }
+
+    let mut txt = Text::new();
+    for r in routes {
+        let before = unedited_map.get_tr(r);
+        let after = map.get_tr(r);
+        txt.add_line(Line(&after.long_name));
+        txt.add_line(
+            Line(format!(
+                "{} stops (was {}), taking {} end to end (was {})",
+                after.stops.len(),
+                before.stops.len(),
+                run_time(map, after.id, app),
+                run_time(unedited_map, before.id, app)
+            ))
+            .secondary(),
+        );
+        txt.add_line(
+            Line(format!(
+                "{} buildings within {} of a stop (was {})",
+                after.buildings_near_stops(map, WALK_ACCESS_RADIUS).len(),
+                WALK_ACCESS_RADIUS.to_string(&app.opts.units),
+                before
+                    .buildings_near_stops(unedited_map, WALK_ACCESS_RADIUS)
+                    .len()
+            ))
+            .secondary(),
+        );
+    }
+
+    Widget::col(vec![
+        Line("Routes affected by stop changes")
+            .small_heading()
+            .into_widget(ctx),
+        txt.into_widget(ctx),
+    ])
+}
+
+fn run_time(map: &Map, r: TransitRouteID, app: &App) -> String {
+    match map.get_tr(r).estimate_run_time(map) {
+        Ok(duration) => duration.to_string(&app.opts.units),
+        Err(_) => "???".to_string(),
+    }
+}
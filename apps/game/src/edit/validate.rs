@@ -1,11 +1,21 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
-use abstutil::Timer;
-use map_model::{connectivity, Direction, DrivingSide, EditCmd, Map, PathConstraints};
+use abstutil::{prettyprint_usize, Timer};
+use map_model::{
+    connectivity, BuildingID, Direction, DrivingSide, EditCmd, LaneID, Map, MapEdits,
+    PathConstraints,
+};
+use sim::{TripID, TripInfo};
+use synthpop::{TripEndpoint, TripMode};
 use widgetry::tools::PopupMsg;
-use widgetry::{EventCtx, State};
+use widgetry::{
+    ControlState, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, Text, TextExt, Widget,
+};
 
-use crate::app::App;
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::edit::EditMode;
+use crate::ID;
 
 // Some of these take a candidate EditCmd to do, then see if it's valid. If they return None, it's
 // fine. They always leave the map in the original state without the new EditCmd.
@@ -141,3 +151,229 @@ pub fn fix_sidewalk_direction(map: &Map) -> Vec<EditCmd> {
     }
     fixes
 }
+
+/// How many problems to list individually
+const MAX_PROBLEMS_LISTED: usize = 10;
+
+/// Something a proposal breaks, found before the simulation strands anybody
+pub struct RoutingProblem {
+    pub description: String,
+    /// Where to look
+    pub id: ID,
+}
+
+/// Which buildings each mode can't reach, and which trips can't be made at all. Only the main
+/// connected component of each mode's network counts as reachable.
+struct Routability {
+    unreachable: BTreeSet<(BuildingID, TripMode)>,
+    impossible_trips: BTreeSet<TripID>,
+}
+
+impl Routability {
+    fn new(map: &Map, trips: &[(TripID, TripInfo)]) -> Routability {
+        let mut main_component: BTreeMap<TripMode, HashSet<LaneID>> = BTreeMap::new();
+        for mode in [TripMode::Walk, TripMode::Bike, TripMode::Drive] {
+            main_component.insert(mode, connectivity::find_scc(map, mode.to_constraints()).0);
+        }
+
+        let mut unreachable = BTreeSet::new();
+        for b in map.all_buildings() {
+            for (mode, lanes) in &main_component {
+                let lane = match mode {
+                    TripMode::Walk => Some(b.sidewalk_pos.lane()),
+                    TripMode::Bike => b.biking_connection(map).map(|(pos, _)| pos.lane()),
+                    TripMode::Drive => b.driving_connection(map).map(|(pos, _)| pos.lane()),
+                    TripMode::Transit => unreachable!(),
+                };
+                if !lane.map(|l| lanes.contains(&l)).unwrap_or(false) {
+                    unreachable.insert((b.id, *mode));
+                }
+            }
+        }
+
+        let mut impossible_trips = BTreeSet::new();
+        for (id, info) in trips {
+            if info.cancellation_reason.is_some() {
+                continue;
+            }
+            // Transit riders at least need to walk to and from stops
+            let mode = if info.mode == TripMode::Transit {
+                TripMode::Walk
+            } else {
+                info.mode
+            };
+            let lanes = &main_component[&mode];
+            let possible = TripEndpoint::path_req(info.start, info.end, mode, map)
+                .map(|req| lanes.contains(&req.start.lane()) && lanes.contains(&req.end.lane()))
+                .unwrap_or(false);
+            if !possible {
+                impossible_trips.insert(*id);
+            }
+        }
+
+        Routability {
+            unreachable,
+            impossible_trips,
+        }
+    }
+}
+
+/// Compare the current edits against `orig_edits`, finding buildings that some mode can't reach
+/// anymore and trips in the suspended simulation that have become impossible. Leaves the map with
+/// the current edits.
+pub fn find_routing_problems(
+    app: &mut App,
+    orig_edits: &MapEdits,
+    timer: &mut Timer,
+) -> Vec<RoutingProblem> {
+    let trips = app
+        .primary
+        .suspended_sim
+        .as_ref()
+        .map(|sim| sim.all_trip_info())
+        .unwrap_or_default();
+    let current_edits = app.primary.map.get_edits().clone();
+
+    timer.start("check routing before edits");
+    app.primary.map.must_apply_edits(orig_edits.clone(), timer);
+    let before = Routability::new(&app.primary.map, &trips);
+    timer.stop("check routing before edits");
+
+    timer.start("check routing after edits");
+    app.primary.map.must_apply_edits(current_edits, timer);
+    let after = Routability::new(&app.primary.map, &trips);
+    timer.stop("check routing after edits");
+
+    let map = &app.primary.map;
+    let mut problems = Vec::new();
+
+    let mut newly_unreachable: BTreeMap<BuildingID, BTreeSet<String>> = BTreeMap::new();
+    for (b, mode) in after.unreachable.difference(&before.unreachable) {
+        newly_unreachable
+            .entry(*b)
+            .or_default()
+            .insert(mode.ongoing_verb().to_string());
+    }
+    for (b, modes) in newly_unreachable {
+        problems.push(RoutingProblem {
+            description: format!(
+                "{} can't be reached by {} anymore",
+                map.get_b(b).address,
+                abstutil::plain_list_names(modes)
+            ),
+            id: ID::Building(b),
+        });
+    }
+
+    let trips: BTreeMap<TripID, TripInfo> = trips.into_iter().collect();
+    for id in after.impossible_trips.difference(&before.impossible_trips) {
+        let info = &trips[id];
+        problems.push(RoutingProblem {
+            description: format!("{} ({}) is impossible now", id, info.mode.ongoing_verb()),
+            id: match info.start {
+                TripEndpoint::Building(b) => ID::Building(b),
+                TripEndpoint::Border(i) => ID::Intersection(i),
+                TripEndpoint::SuddenlyAppear(pos) => ID::Lane(pos.lane()),
+            },
+        });
+    }
+
+    problems
+}
+
+/// Warn about routing problems before leaving edit mode, with links to zoom to each one.
+pub struct RoutingWarnings {
+    panel: Panel,
+    problems: Vec<RoutingProblem>,
+}
+
+impl RoutingWarnings {
+    pub fn new_state(ctx: &mut EventCtx, problems: Vec<RoutingProblem>) -> Box<dyn State<App>> {
+        let mut col = vec![
+            Line("Your edits break some routes")
+                .small_heading()
+                .into_widget(ctx),
+            format!(
+                "{} problems found; click one to look at it",
+                prettyprint_usize(problems.len())
+            )
+            .text_widget(ctx),
+        ];
+        for (idx, problem) in problems.iter().take(MAX_PROBLEMS_LISTED).enumerate() {
+            col.push(
+                ctx.style()
+                    .btn_plain
+                    .btn()
+                    .label_styled_text(
+                        Text::from(problem.description.clone()),
+                        ControlState::Default,
+                    )
+                    .build_widget(ctx, format!("problem #{}", idx + 1)),
+            );
+        }
+        if problems.len() > MAX_PROBLEMS_LISTED {
+            col.push(
+                format!(
+                    "{} more...",
+                    prettyprint_usize(problems.len() - MAX_PROBLEMS_LISTED)
+                )
+                .text_widget(ctx),
+            );
+        }
+        col.push(Widget::row(vec![
+            ctx.style()
+                .btn_outline
+                .text("Keep editing")
+                .hotkey(Key::Escape)
+                .build_def(ctx),
+            ctx.style()
+                .btn_solid_destructive
+                .text("Finish anyway")
+                .build_def(ctx),
+        ]));
+
+        Box::new(RoutingWarnings {
+            panel: Panel::new_builder(Widget::col(col)).build(ctx),
+            problems,
+        })
+    }
+}
+
+impl State<App> for RoutingWarnings {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "Keep editing" => {
+                    return Transition::Pop;
+                }
+                "Finish anyway" => {
+                    return Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::ModifyState(Box::new(|state, _, _| {
+                            state.downcast_mut::<EditMode>().unwrap().quit_requested = true;
+                        })),
+                    ]);
+                }
+                x => {
+                    let idx = x["problem #".len()..].parse::<usize>().unwrap();
+                    let id = self.problems[idx - 1].id.clone();
+                    return Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::Push(Warping::new_state(
+                            ctx,
+                            app.primary.canonical_point(id.clone()).unwrap(),
+                            Some(10.0),
+                            Some(id),
+                            &mut app.primary,
+                        )),
+                    ]);
+                }
+            }
+        }
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
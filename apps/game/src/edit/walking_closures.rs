@@ -0,0 +1,210 @@
+use abstutil::prettyprint_usize;
+use geom::Distance;
+use map_model::{connectivity, LaneID, TurnID, WalkingClosure};
+use widgetry::mapspace::{ObjectID, World, WorldOutcome};
+use widgetry::{
+    Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    State, Text, TextExt, Toggle, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::edit::apply_map_edits;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Obj {
+    Sidewalk(LaneID),
+    Crossing(TurnID),
+}
+
+impl ObjectID for Obj {}
+
+/// Temporarily close sidewalks and crossings, like after snow or during works, and see which
+/// buildings can't be reached on foot anymore. Useful for deciding what to clear first.
+pub struct WalkingClosureEditor {
+    world: World<Obj>,
+    panel: Panel,
+    draw_cut_off: Drawable,
+    /// Newly closed sidewalks and crossings get this
+    closure: WalkingClosure,
+}
+
+impl WalkingClosureEditor {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        closure: WalkingClosure,
+    ) -> Box<dyn State<App>> {
+        app.primary.current_selection = None;
+
+        let map = &app.primary.map;
+        let mut world = World::new();
+        for l in map.all_lanes() {
+            if !l.is_walkable() {
+                continue;
+            }
+            let current = l.walking_closure(map);
+            world
+                .add(Obj::Sidewalk(l.id))
+                .hitbox(l.get_thick_polygon())
+                .draw_color(color(current))
+                .hover_alpha(0.3)
+                .tooltip(describe("Sidewalk", current))
+                .clickable()
+                .build(ctx);
+        }
+        for i in map.all_intersections() {
+            for turn in &i.turns {
+                if !turn.turn_type.pedestrian_crossing() {
+                    continue;
+                }
+                let width = Distance::meters(3.0);
+                let hitbox = if let Some(line) = turn.crosswalk_line() {
+                    line.make_polygons(width)
+                } else {
+                    turn.geom.make_polygons(width)
+                };
+                let current = turn.walking_closure(map);
+                world
+                    .add(Obj::Crossing(turn.id))
+                    .hitbox(hitbox)
+                    .zorder(1)
+                    .draw_color(color(current))
+                    .hover_alpha(0.3)
+                    .tooltip(describe("Crossing", current))
+                    .clickable()
+                    .build(ctx);
+            }
+        }
+        world.initialize_hover(ctx);
+
+        let cut_off = connectivity::find_cut_off_buildings(map, false);
+        let mut wheelchair_cut_off = connectivity::find_cut_off_buildings(map, true);
+        wheelchair_cut_off.retain(|b| !cut_off.contains(b));
+        let mut batch = GeomBatch::new();
+        for b in &cut_off {
+            batch.push(Color::RED.alpha(0.8), map.get_b(*b).polygon.clone());
+        }
+        for b in &wheelchair_cut_off {
+            batch.push(Color::PURPLE.alpha(0.8), map.get_b(*b).polygon.clone());
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Line("Close sidewalks and crossings")
+                .small_heading()
+                .into_widget(ctx),
+            "Click a sidewalk or crossing to close or reopen it".text_widget(ctx),
+            Toggle::choice(
+                ctx,
+                "closure",
+                WalkingClosure::Snow.describe(),
+                WalkingClosure::Works.describe(),
+                None,
+                closure == WalkingClosure::Snow,
+            ),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} buildings cut off on foot",
+                    prettyprint_usize(cut_off.len())
+                ))
+                .fg(Color::RED),
+                Line(format!(
+                    "{} more cut off for wheelchair users",
+                    prettyprint_usize(wheelchair_cut_off.len())
+                ))
+                .fg(Color::PURPLE),
+                Line("Simulate to see whose trips are cancelled").secondary(),
+            ])
+            .into_widget(ctx),
+            ctx.style()
+                .btn_solid_primary
+                .text("Finish")
+                .hotkey(Key::Escape)
+                .build_def(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx);
+
+        Box::new(WalkingClosureEditor {
+            world,
+            panel,
+            draw_cut_off: ctx.upload(batch),
+            closure,
+        })
+    }
+}
+
+impl State<App> for WalkingClosureEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let WorldOutcome::ClickedObject(obj) = self.world.event(ctx) {
+            let map = &app.primary.map;
+            let closure = self.closure;
+            let cmd = match obj {
+                Obj::Sidewalk(l) => {
+                    let side = map.get_l(l).get_nearest_side_of_road(map).side;
+                    map.edit_road_cmd(l.road, |new| {
+                        if new.sidewalk_closures.remove(&side).is_none() {
+                            new.sidewalk_closures.insert(side, closure);
+                        }
+                    })
+                }
+                Obj::Crossing(turn) => map.edit_intersection_cmd(turn.parent, |new| {
+                    if new.closed_crossings.remove(&turn).is_none() {
+                        new.closed_crossings.insert(turn, closure);
+                    }
+                }),
+            };
+            let mut edits = map.get_edits().clone();
+            edits.commands.push(cmd);
+            apply_map_edits(ctx, app, edits);
+            return Transition::Replace(Self::new_state(ctx, app, self.closure));
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(ref x) => match x.as_ref() {
+                "Finish" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                self.closure = if self.panel.is_checked("closure") {
+                    WalkingClosure::Snow
+                } else {
+                    WalkingClosure::Works
+                };
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_cut_off);
+        self.world.draw(g);
+        self.panel.draw(g);
+    }
+}
+
+/// How closed sidewalks and crossings are drawn, both here and in the layer
+pub fn walking_closure_color(closure: WalkingClosure) -> Color {
+    match closure {
+        WalkingClosure::Snow => Color::CYAN,
+        WalkingClosure::Works => Color::ORANGE,
+    }
+}
+
+fn color(closure: Option<WalkingClosure>) -> Color {
+    match closure {
+        Some(closure) => walking_closure_color(closure).alpha(0.8),
+        None => Color::GREEN.alpha(0.3),
+    }
+}
+
+fn describe(name: &str, closure: Option<WalkingClosure>) -> Text {
+    match closure {
+        Some(closure) => Text::from(format!("{} closed by {}", name, closure.describe())),
+        None => Text::from(format!("{} open", name)),
+    }
+}
@@ -92,7 +92,8 @@ impl State<App> for ZoneEditor {
                         edits
                             .commands
                             .push(app.primary.map.edit_road_cmd(*r, |new| {
-                                new.access_restrictions = AccessRestrictions::new();
+                                new.access_restrictions.allow_through_traffic =
+                                    AccessRestrictions::new().allow_through_traffic;
                             }));
                     }
 
@@ -104,17 +105,21 @@ impl State<App> for ZoneEditor {
                     // The original allow_through_traffic always includes this, and there's no way
                     // to exclude it, so stay consistent.
                     allow_through_traffic.insert(PathConstraints::Train);
-                    let new_access_restrictions = AccessRestrictions {
-                        allow_through_traffic,
-                    };
                     for r in &self.selector.roads {
-                        let old_access_restrictions =
-                            app.primary.map.get_r(*r).access_restrictions.clone();
-                        if old_access_restrictions != new_access_restrictions {
+                        // Leave destination-only restrictions on individual roads alone
+                        if app
+                            .primary
+                            .map
+                            .get_r(*r)
+                            .access_restrictions
+                            .allow_through_traffic
+                            != allow_through_traffic
+                        {
                             edits
                                 .commands
                                 .push(app.primary.map.edit_road_cmd(*r, |new| {
-                                    new.access_restrictions = new_access_restrictions.clone();
+                                    new.access_restrictions.allow_through_traffic =
+                                        allow_through_traffic;
                                 }));
                         }
                     }
@@ -7,8 +7,9 @@ use synthpop::TripMode;
 use widgetry::{Color, EventCtx, Line, Text, TextExt, Widget};
 
 use crate::app::App;
-use crate::info::{header_btns, make_table, make_tabs, Details, Tab};
+use crate::info::{edits_diff_rows, header_btns, make_table, make_tabs, Details, Tab};
 use crate::render::DrawPedestrian;
+use crate::ID;
 
 pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingID) -> Widget {
     Widget::custom_col(vec![
@@ -55,8 +56,20 @@ fn info_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingI
     } else {
         kv.push(("Parking", "None".to_string()));
     }
+    if b.bike_parking > 0 {
+        let occupied = app.primary.sim.get_bike_parking_occupied(b.id);
+        kv.push((
+            "Bike parking",
+            format!(
+                "{} / {} spots available",
+                b.bike_parking.saturating_sub(occupied),
+                b.bike_parking
+            ),
+        ));
+    }
 
     rows.extend(make_table(ctx, kv));
+    rows.extend(edits_diff_rows(ctx, app, ID::Building(id)));
 
     let mut txt = Text::new();
 
@@ -4,18 +4,20 @@ use abstutil::prettyprint_usize;
 use geom::{ArrowCap, Distance, Duration, PolyLine, Polygon, Tessellation, Time};
 use map_gui::options::TrafficSignalStyle;
 use map_gui::render::traffic_signal::draw_signal_stage;
-use map_model::{IntersectionControl, IntersectionID, StageType};
+use map_model::{BikeConflictType, IntersectionControl, IntersectionID, StageType};
 use sim::AgentType;
 use widgetry::{
     Color, DrawWithTooltips, EventCtx, FanChart, GeomBatch, Line, PlotOptions, ScatterPlot, Series,
-    Text, Toggle, Widget,
+    Text, TextExt, Toggle, Widget,
 };
 
 use crate::app::App;
 use crate::common::color_for_agent_type;
 use crate::info::{
-    header_btns, make_tabs, problem_count, throughput, DataOptions, Details, ProblemOptions, Tab,
+    edits_diff_rows, header_btns, make_tabs, problem_count, throughput, DataOptions, Details,
+    ProblemOptions, Tab,
 };
+use crate::ID;
 
 pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: IntersectionID) -> Widget {
     Widget::custom_col(vec![
@@ -44,6 +46,14 @@ fn info_body(ctx: &EventCtx, app: &App, id: IntersectionID) -> Widget {
     }
     rows.push(txt.into_widget(ctx));
 
+    if !i.corner_design.is_empty() {
+        rows.push(
+            format!("Cycling design: {}", i.corner_design.describe().join(", ")).text_widget(ctx),
+        );
+    }
+    rows.push(crate::edit::conflict_summary(ctx, app, id));
+    rows.extend(edits_diff_rows(ctx, app, ID::Intersection(id)));
+
     if app.opts.dev {
         rows.push(
             ctx.style()
@@ -89,6 +99,24 @@ fn traffic_body(ctx: &mut EventCtx, app: &App, id: IntersectionID, opts: &DataOp
                 .total_for(id)
         )
     ));
+    let analytics = app.primary.sim.get_analytics();
+    let mut total_conflicts = 0;
+    let mut conflicts = Vec::new();
+    for conflict in BikeConflictType::all() {
+        let cnt = analytics.bike_conflicts.get((id, conflict));
+        total_conflicts += cnt;
+        if cnt > 0 {
+            conflicts.push(format!("{} {:?}", prettyprint_usize(cnt), conflict));
+        }
+    }
+    if !conflicts.is_empty() {
+        txt.add_line(format!(
+            "Since midnight: vehicles and people cycling had to give way to each other {} times \
+             ({})",
+            prettyprint_usize(total_conflicts),
+            conflicts.join(", ")
+        ));
+    }
     rows.push(txt.into_widget(ctx));
 
     rows.push(opts.to_controls(ctx, app));
@@ -325,6 +353,12 @@ fn traffic_signal_body(ctx: &mut EventCtx, app: &App, id: IntersectionID) -> Wid
             // TODO Say "normally" or something?
             txt.add_line(format!("One cycle lasts {}", total));
         }
+        if let Some(ref actuation) = signal.actuation {
+            txt.add_line(format!(
+                "Vehicle-actuated: greens last {} to {}, with a {} gap",
+                actuation.min_green, actuation.max_green, actuation.gap
+            ));
+        }
         rows.push(txt.into_widget(ctx));
     }
 
@@ -488,6 +522,8 @@ fn header(
 
     let label = if i.is_border() {
         format!("Border #{}", id.0)
+    } else if i.is_level_crossing(&app.primary.map) && !i.is_traffic_signal() {
+        format!("{} (Level crossing)", id)
     } else {
         match i.control {
             IntersectionControl::Signed | IntersectionControl::Uncontrolled => {
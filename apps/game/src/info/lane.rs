@@ -4,9 +4,10 @@ use widgetry::{EventCtx, Line, LinePlot, PlotOptions, Series, Text, TextExt, Wid
 
 use crate::app::App;
 use crate::info::{
-    header_btns, make_table, make_tabs, problem_count, throughput, DataOptions, Details,
-    ProblemOptions, Tab,
+    edits_diff_rows, header_btns, make_table, make_tabs, problem_count, throughput, DataOptions,
+    Details, ProblemOptions, Tab,
 };
+use crate::ID;
 
 pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Widget {
     Widget::custom_col(vec![
@@ -38,6 +39,15 @@ fn info_body(ctx: &EventCtx, app: &App, id: LaneID) -> Widget {
             kv.push(("No through-traffic for", ban.join(", ")));
         }
     }
+    if !r.access_restrictions.destination_only.is_empty() {
+        let modes: Vec<String> = r
+            .access_restrictions
+            .destination_only
+            .iter()
+            .map(|p| format!("{:?}", p).to_ascii_lowercase())
+            .collect();
+        kv.push(("Access only for", modes.join(", ")));
+    }
 
     if l.is_parking() {
         kv.push((
@@ -50,11 +60,47 @@ fn info_body(ctx: &EventCtx, app: &App, id: LaneID) -> Widget {
         ));
     } else {
         kv.push(("Speed limit", r.speed_limit.to_string(&app.opts.units)));
+        if !r.traffic_calming.is_empty() {
+            kv.push((
+                "Traffic calming",
+                r.traffic_calming
+                    .iter()
+                    .map(|tc| tc.calming_type.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ));
+            kv.push((
+                "Effective speed",
+                r.effective_speed_limit().to_string(&app.opts.units),
+            ));
+        }
     }
 
     kv.push(("Length", l.length().to_string(&app.opts.units)));
+    if l.extent.is_some() {
+        kv.push((
+            "Full width for",
+            l.usable_length().to_string(&app.opts.units),
+        ));
+    }
+    let cycle_networks = map.get_cycle_networks_on_road(r.id);
+    if !cycle_networks.is_empty() {
+        kv.push((
+            "Cycle routes",
+            cycle_networks
+                .into_iter()
+                .map(|rel| match (&rel.name, &rel.network) {
+                    (Some(name), Some(network)) => format!("{} ({})", name, network),
+                    (Some(name), None) => name.clone(),
+                    (None, _) => format!("unnamed {}", rel.osm_id),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ));
+    }
 
     rows.extend(make_table(ctx, kv));
+    rows.extend(edits_diff_rows(ctx, app, ID::Lane(id)));
 
     if l.is_parking() {
         let capacity = l.number_parking_spots(app.primary.map.get_config());
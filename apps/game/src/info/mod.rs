@@ -19,7 +19,7 @@ use widgetry::{
 };
 
 use crate::app::{App, Transition};
-use crate::common::{color_for_agent_type, Warping};
+use crate::common::{color_for_agent_type, edits_diff, Warping};
 use crate::debug::path_counter::PathCounter;
 use crate::edit::{EditMode, RouteEditor};
 use crate::layer::PANEL_PLACEMENT;
@@ -712,6 +712,28 @@ fn do_time_warp(
     (false, Some(rewind_sim))
 }
 
+/// If the current proposal changed this object, show how
+fn edits_diff_rows(ctx: &EventCtx, app: &App, id: ID) -> Vec<Widget> {
+    let diffs = edits_diff(app, &id);
+    if diffs.is_empty() {
+        return Vec::new();
+    }
+    let mut rows = vec![Line(format!(
+        "Changed by \"{}\"",
+        app.primary.map.get_edits().edits_name
+    ))
+    .small_heading()
+    .into_widget(ctx)];
+    rows.extend(make_table(
+        ctx,
+        diffs
+            .into_iter()
+            .map(|diff| (diff.attribute, format!("{} -> {}", diff.before, diff.after)))
+            .collect(),
+    ));
+    rows
+}
+
 fn make_table<I: Into<String>>(ctx: &EventCtx, rows: Vec<(I, String)>) -> Vec<Widget> {
     rows.into_iter()
         .map(|(k, v)| {
@@ -554,17 +554,21 @@ fn parked_car_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: Car
     // TODO prev trips, next trips, etc
     let mut rows = vec![];
 
-    let p = app.primary.sim.get_owner_of_car(id).unwrap();
-    rows.push(
-        ctx.style()
-            .btn_outline
-            .text(format!("Owned by {}", p))
-            .build_def(ctx),
-    );
-    details.hyperlinks.insert(
-        format!("Owned by {}", p),
-        Tab::PersonTrips(p, BTreeMap::new()),
-    );
+    if let Some(p) = app.primary.sim.get_owner_of_car(id) {
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text(format!("Owned by {}", p))
+                .build_def(ctx),
+        );
+        details.hyperlinks.insert(
+            format!("Owned by {}", p),
+            Tab::PersonTrips(p, BTreeMap::new()),
+        );
+    } else {
+        // Seeded to match observed parking occupancy
+        rows.push("Not owned by anybody in the simulation".text_widget(ctx));
+    }
 
     if let Some(p) = app.primary.sim.lookup_parked_car(id) {
         match p.spot {
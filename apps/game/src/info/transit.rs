@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use crate::ID;
 use abstutil::{prettyprint_usize, Counter};
 use geom::{Circle, Distance, Time};
@@ -91,6 +93,10 @@ fn stop_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: TransitSt
             ))
             .secondary(),
         );
+        let (denied, peak_load) = app.primary.sim.get_analytics().transit_crowding(id, r.id);
+        if let Some(line) = describe_crowding(denied, peak_load) {
+            txt.add_line(Line(format!("  {}", line)).fg(crowding_color(denied, peak_load)));
+        }
     }
     rows.push(txt.into_widget(ctx));
 
@@ -129,11 +135,16 @@ fn bus_status_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: Car
         Tab::TransitRoute(route.id),
     );
 
+    let passengers = app.primary.sim.num_transit_passengers(id);
     rows.push(
-        Line(format!(
-            "Currently has {} passengers",
-            app.primary.sim.num_transit_passengers(id),
-        ))
+        Line(match app.primary.sim.transit_capacity(id) {
+            Some(cap) => format!(
+                "Currently has {} passengers, {}% full",
+                passengers,
+                100 * passengers / cap.max(1)
+            ),
+            None => format!("Currently has {} passengers", passengers),
+        })
         .into_widget(ctx),
     );
 
@@ -219,7 +230,15 @@ fn route_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: TransitR
     let mut boardings: Counter<TransitStopID> = Counter::new();
     let mut alightings: Counter<TransitStopID> = Counter::new();
     let mut waiting: Counter<TransitStopID> = Counter::new();
+    let mut denied: Counter<TransitStopID> = Counter::new();
+    let mut peak_loads: BTreeMap<TransitStopID, f64> = BTreeMap::new();
     for ts in &route.stops {
+        let (num_denied, peak_load) = app.primary.sim.get_analytics().transit_crowding(*ts, id);
+        denied.add(*ts, num_denied);
+        if let Some(load) = peak_load {
+            peak_loads.insert(*ts, load);
+        }
+
         if let Some(list) = app.primary.sim.get_analytics().passengers_boarding.get(ts) {
             for (_, r, _) in list {
                 if *r == id {
@@ -278,17 +297,23 @@ fn route_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: TransitR
                 .btn_plain
                 .icon("system/assets/tools/pin.svg")
                 .build_widget(ctx, &name),
-            Text::from_all(vec![
-                Line(&ts.name),
-                Line(format!(
-                    ": {} boardings, {} alightings, {} currently waiting",
-                    prettyprint_usize(boardings.get(ts.id)),
-                    prettyprint_usize(alightings.get(ts.id)),
-                    prettyprint_usize(waiting.get(ts.id))
-                ))
-                .secondary(),
-            ])
-            .into_widget(ctx),
+            {
+                let mut txt = Text::from_all(vec![
+                    Line(&ts.name),
+                    Line(format!(
+                        ": {} boardings, {} alightings, {} currently waiting",
+                        prettyprint_usize(boardings.get(ts.id)),
+                        prettyprint_usize(alightings.get(ts.id)),
+                        prettyprint_usize(waiting.get(ts.id))
+                    ))
+                    .secondary(),
+                ]);
+                let peak_load = peak_loads.get(&ts.id).cloned();
+                if let Some(line) = describe_crowding(denied.get(ts.id), peak_load) {
+                    txt.add_line(Line(line).fg(crowding_color(denied.get(ts.id), peak_load)));
+                }
+                txt.into_widget(ctx)
+            },
         ]));
         details.warpers.insert(name, ID::TransitStop(ts.id));
     }
@@ -308,6 +333,20 @@ fn route_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: TransitR
 
     // TODO Soon it'll be time to split into tabs
     {
+        if denied.sum() > 0 {
+            rows.push(
+                Text::from(
+                    Line(format!(
+                        "{} riders were left behind by full {}. Running more often would help.",
+                        prettyprint_usize(denied.sum()),
+                        route.plural_noun()
+                    ))
+                    .fg(Color::RED),
+                )
+                .wrap_to_pct(ctx, 20)
+                .into_widget(ctx),
+            );
+        }
         rows.push(
             ctx.style()
                 .btn_outline
@@ -362,6 +401,34 @@ fn route_body(ctx: &mut EventCtx, app: &App, details: &mut Details, id: TransitR
     Widget::col(rows)
 }
 
+/// None if there's nothing worth mentioning
+fn describe_crowding(denied: usize, peak_load: Option<f64>) -> Option<String> {
+    let peak_load = peak_load.filter(|x| *x > 0.0);
+    match (denied, peak_load) {
+        (0, None) => None,
+        (0, Some(load)) => Some(format!("peak load {}%", (100.0 * load).round())),
+        (_, None) => Some(format!(
+            "{} left behind by full vehicles",
+            prettyprint_usize(denied)
+        )),
+        (_, Some(load)) => Some(format!(
+            "{} left behind by full vehicles, peak load {}%",
+            prettyprint_usize(denied),
+            (100.0 * load).round()
+        )),
+    }
+}
+
+fn crowding_color(denied: usize, peak_load: Option<f64>) -> Color {
+    if denied > 0 {
+        Color::RED
+    } else if peak_load.unwrap_or(0.0) > 0.8 {
+        Color::ORANGE
+    } else {
+        Color::GREEN
+    }
+}
+
 // TODO Unit test
 fn describe_schedule(route: &TransitRoute) -> Text {
     let mut txt = Text::new();
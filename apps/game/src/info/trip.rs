@@ -13,7 +13,7 @@ use widgetry::{
 };
 
 use crate::app::App;
-use crate::common::color_for_trip_phase;
+use crate::common::{cmp_duration_shorter, color_for_trip_phase};
 use crate::info::{make_table, Details, Tab};
 
 #[derive(Clone)]
@@ -21,6 +21,9 @@ pub struct OpenTrip {
     pub show_after: bool,
     // (unzoomed, zoomed). Indexed by order of TripPhase.
     cached_routes: Vec<Option<(Polygon, Vec<Polygon>)>>,
+    // (unzoomed, zoomed) dashes along the route taken in the other world, before or after the
+    // proposal
+    cached_other_route: Option<(Vec<Polygon>, Vec<Polygon>)>,
 }
 
 // Ignore cached_routes
@@ -39,6 +42,7 @@ impl OpenTrip {
         OpenTrip {
             show_after: true,
             cached_routes: Vec::new(),
+            cached_other_route: None,
         }
     }
 }
@@ -253,6 +257,7 @@ pub fn finished(
             OpenTrip {
                 show_after: false,
                 cached_routes: Vec::new(),
+                cached_other_route: None,
             },
         );
         details.hyperlinks.insert(
@@ -333,6 +338,17 @@ pub fn finished(
             Line(trip.purpose.to_string()).secondary().into_widget(ctx),
         ]));
     }
+    if app.has_prebaked().is_some() {
+        col.push(compare_before_after(
+            ctx,
+            app,
+            id,
+            open_trips.get_mut(&id).unwrap(),
+            details,
+            col_width,
+        ));
+    }
+
     col.push(describe_problems(
         ctx,
         if open_trips[&id].show_after {
@@ -390,6 +406,145 @@ pub fn cancelled(
     Widget::col(col)
 }
 
+/// How long each part of the trip took before and after the proposal, side by side. The route
+/// from the other world is drawn as dashes on the map, so both can be seen at once.
+fn compare_before_after(
+    ctx: &mut EventCtx,
+    app: &App,
+    id: TripID,
+    open_trip: &mut OpenTrip,
+    details: &mut Details,
+    col_width: Percent,
+) -> Widget {
+    let unedited_map = app
+        .primary
+        .unedited_map
+        .as_ref()
+        .unwrap_or(&app.primary.map);
+    let before = app.prebaked().get_trip_phases(id, unedited_map);
+    let after = app
+        .primary
+        .sim
+        .get_analytics()
+        .get_trip_phases(id, &app.primary.map);
+    let (before_total, after_total) = match (trip_duration(&before), trip_duration(&after)) {
+        (Some(b), Some(a)) => (b, a),
+        // The trip didn't finish in one of the worlds
+        _ => {
+            return Widget::nothing();
+        }
+    };
+
+    if open_trip.cached_other_route.is_none() {
+        let (phases, map) = if open_trip.show_after {
+            (&before, unedited_map)
+        } else {
+            (&after, &app.primary.map)
+        };
+        let mut unzoomed = Vec::new();
+        let mut zoomed = Vec::new();
+        for trace in phases
+            .iter()
+            .filter_map(|p| p.path.as_ref().and_then(|path| path.trace(map)))
+        {
+            unzoomed.extend(trace.dashed_lines(
+                Distance::meters(4.0),
+                Distance::meters(10.0),
+                Distance::meters(6.0),
+            ));
+            zoomed.extend(trace.dashed_lines(
+                Distance::meters(0.5),
+                Distance::meters(2.0),
+                Distance::meters(1.0),
+            ));
+        }
+        open_trip.cached_other_route = Some((unzoomed, zoomed));
+    }
+    let (unzoomed, zoomed) = open_trip.cached_other_route.as_ref().unwrap();
+    details
+        .draw_extra
+        .unzoomed
+        .extend(Color::PURPLE, unzoomed.clone());
+    details
+        .draw_extra
+        .zoomed
+        .extend(Color::PURPLE, zoomed.clone());
+
+    let cell = |ctx: &mut EventCtx, txt: Text| {
+        Widget::custom_row(vec![txt.into_widget(ctx)]).force_width_window_pct(ctx, col_width)
+    };
+    let mut rows = vec![Widget::custom_row(vec![
+        cell(ctx, Text::new()),
+        cell(ctx, Text::from(Line("Before").secondary())),
+        cell(ctx, Text::from(Line("After").secondary())),
+        cell(ctx, Text::from(Line("Change").secondary())),
+    ])];
+    let before_phases = time_per_phase(&before);
+    let after_phases = time_per_phase(&after);
+    let mut names: Vec<&str> = before_phases.keys().cloned().collect();
+    for name in after_phases.keys() {
+        if !names.contains(name) {
+            names.push(*name);
+        }
+    }
+    names.push("Total");
+    for name in names {
+        let (b, a) = if name == "Total" {
+            (before_total, after_total)
+        } else {
+            (
+                before_phases.get(name).cloned().unwrap_or(Duration::ZERO),
+                after_phases.get(name).cloned().unwrap_or(Duration::ZERO),
+            )
+        };
+        rows.push(Widget::custom_row(vec![
+            cell(ctx, Text::from(Line(name).secondary())),
+            cell(ctx, Text::from(b.to_string(&app.opts.units))),
+            cell(ctx, Text::from(a.to_string(&app.opts.units))),
+            cell(ctx, Text::from_all(cmp_duration_shorter(app, a, b))),
+        ]));
+    }
+    rows.push(
+        Line("The route from the other world is dashed in purple")
+            .secondary()
+            .into_widget(ctx),
+    );
+    Widget::col(rows)
+}
+
+/// None if the trip didn't finish
+fn trip_duration(phases: &[TripPhase]) -> Option<Duration> {
+    let end = phases.last()?.end_time?;
+    Some(end - phases[0].start_time)
+}
+
+/// Sums the time spent in similar phases, like all walking
+fn time_per_phase(phases: &[TripPhase]) -> BTreeMap<&'static str, Duration> {
+    let mut result = BTreeMap::new();
+    for p in phases {
+        let end = match p.end_time {
+            Some(t) => t,
+            None => {
+                continue;
+            }
+        };
+        let name = match p.phase_type {
+            TripPhaseType::Driving => "Driving",
+            TripPhaseType::Walking => "Walking",
+            TripPhaseType::Biking => "Biking",
+            TripPhaseType::Parking => "Parking",
+            TripPhaseType::WaitingForBus(_, _) => "Waiting for transit",
+            TripPhaseType::RidingBus(_, _, _) => "Riding transit",
+            TripPhaseType::DelayedStart => "Delayed start",
+            TripPhaseType::Cancelled | TripPhaseType::Finished => {
+                continue;
+            }
+        };
+        *result.entry(name).or_insert(Duration::ZERO) += end - p.start_time;
+    }
+    result
+}
+
 fn describe_problems(
     ctx: &mut EventCtx,
     analytics: &Analytics,
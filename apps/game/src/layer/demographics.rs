@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, HashMap};
+
+use abstutil::prettyprint_usize;
+use blockfinding::Perimeter;
+use geom::{Bounds, Polygon, QuadTree};
+use synthpop::{AgeBand, TripEndpoint, TripPurpose};
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::ColorLegend;
+use widgetry::{Choice, Color, EventCtx, GfxCtx, Line, Outcome, Panel, Text, TextExt, Widget};
+
+use crate::app::App;
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+
+/// Summarizes the people in the loaded scenario per city block, to show where demand comes from
+/// while editing. Unlike the population map, this doesn't depend on the simulation running.
+pub struct BlockDemographics {
+    measure: Measure,
+    blocks: Vec<BlockStats>,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Measure {
+    ResidentialDensity,
+    WorkplaceDensity,
+    CarOwnership,
+    AgeMix,
+}
+
+struct BlockStats {
+    polygon: Polygon,
+    /// People whose first trip of the day starts here
+    residents: usize,
+    /// People with a work trip ending here
+    workers: usize,
+    /// Only residents with demographic data are counted below
+    with_demographics: usize,
+    car_owners: usize,
+    ages: BTreeMap<AgeBand, usize>,
+}
+
+impl Layer for BlockDemographics {
+    fn name(&self) -> Option<&'static str> {
+        Some("demographics")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                let measure = self.panel.dropdown_value("measure");
+                if measure != self.measure {
+                    let blocks = std::mem::take(&mut self.blocks);
+                    *self = BlockDemographics::from_blocks(ctx, app, blocks, measure);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl BlockDemographics {
+    pub fn new(ctx: &mut EventCtx, app: &App, measure: Measure) -> BlockDemographics {
+        let blocks = calculate_blocks(ctx, app);
+        BlockDemographics::from_blocks(ctx, app, blocks, measure)
+    }
+
+    fn from_blocks(
+        ctx: &mut EventCtx,
+        app: &App,
+        blocks: Vec<BlockStats>,
+        measure: Measure,
+    ) -> BlockDemographics {
+        let mut draw = ToggleZoomed::builder();
+        let mut col = vec![
+            header(ctx, "Demographics"),
+            Widget::row(vec![
+                "Show:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "measure",
+                    measure,
+                    vec![
+                        Choice::new("residential density", Measure::ResidentialDensity),
+                        Choice::new("workplace density", Measure::WorkplaceDensity),
+                        Choice::new("car ownership", Measure::CarOwnership),
+                        Choice::new("age mix", Measure::AgeMix),
+                    ],
+                ),
+            ]),
+        ];
+
+        if app.primary.scenario.is_none() {
+            col.push(
+                Text::from(Line("No scenario is loaded, so there's nobody to show").secondary())
+                    .wrap_to_pct(ctx, 15)
+                    .into_widget(ctx),
+            );
+        } else {
+            let (summary, legend) = match measure {
+                Measure::ResidentialDensity | Measure::WorkplaceDensity => {
+                    let count = |b: &BlockStats| {
+                        if measure == Measure::ResidentialDensity {
+                            b.residents
+                        } else {
+                            b.workers
+                        }
+                    };
+                    // People per square kilometer
+                    let density = |b: &BlockStats| count(b) as f64 / b.polygon.area() * 1e6;
+                    let max = blocks.iter().map(density).fold(0.0, f64::max);
+                    for b in &blocks {
+                        if count(b) > 0 {
+                            let color = app.cs.good_to_bad_red.eval(density(b) / max);
+                            draw.unzoomed.push(color.alpha(0.7), b.polygon.clone());
+                            draw.zoomed.push(color.alpha(0.4), b.polygon.clone());
+                        }
+                    }
+                    let total: usize = blocks.iter().map(count).sum();
+                    (
+                        format!(
+                            "{} {}",
+                            prettyprint_usize(total),
+                            if measure == Measure::ResidentialDensity {
+                                "people live in these blocks"
+                            } else {
+                                "people work in these blocks"
+                            }
+                        ),
+                        ColorLegend::gradient(
+                            ctx,
+                            &app.cs.good_to_bad_red,
+                            vec![
+                                "0".to_string(),
+                                format!("{} / km²", prettyprint_usize(max as usize)),
+                            ],
+                        ),
+                    )
+                }
+                Measure::CarOwnership => {
+                    for b in &blocks {
+                        if b.with_demographics > 0 {
+                            let pct = b.car_owners as f64 / b.with_demographics as f64;
+                            let color = app.cs.good_to_bad_red.eval(pct);
+                            draw.unzoomed.push(color.alpha(0.7), b.polygon.clone());
+                            draw.zoomed.push(color.alpha(0.4), b.polygon.clone());
+                        }
+                    }
+                    let owners: usize = blocks.iter().map(|b| b.car_owners).sum();
+                    let known: usize = blocks.iter().map(|b| b.with_demographics).sum();
+                    (
+                        if known == 0 {
+                            "This scenario doesn't say who owns a car".to_string()
+                        } else {
+                            format!(
+                                "{} of {} residents own a car",
+                                prettyprint_usize(owners),
+                                prettyprint_usize(known)
+                            )
+                        },
+                        ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0%", "100%"]),
+                    )
+                }
+                Measure::AgeMix => {
+                    let colors: Vec<(AgeBand, Color)> = AgeBand::all()
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, age)| (age, app.cs.rotating_color_plot(idx)))
+                        .collect();
+                    for b in &blocks {
+                        // Color by the most common age, fading blocks with a more even mix
+                        if let Some((age, count)) = b.ages.iter().max_by_key(|(_, count)| **count) {
+                            let share = *count as f64 / b.with_demographics as f64;
+                            let color = colors.iter().find(|(a, _)| a == age).unwrap().1;
+                            draw.unzoomed
+                                .push(color.alpha(0.8 * share), b.polygon.clone());
+                            draw.zoomed
+                                .push(color.alpha(0.5 * share), b.polygon.clone());
+                        }
+                    }
+                    let known: usize = blocks.iter().map(|b| b.with_demographics).sum();
+                    (
+                        if known == 0 {
+                            "This scenario doesn't include ages".to_string()
+                        } else {
+                            "Blocks are colored by the most common age of residents, fainter \
+                             when ages are more mixed"
+                                .to_string()
+                        },
+                        Widget::col(
+                            colors
+                                .into_iter()
+                                .map(|(age, color)| ColorLegend::row(ctx, color, age.to_string()))
+                                .collect(),
+                        ),
+                    )
+                }
+            };
+            col.push(
+                Text::from(Line(summary))
+                    .wrap_to_pct(ctx, 15)
+                    .into_widget(ctx),
+            );
+            col.push(legend);
+        }
+
+        BlockDemographics {
+            measure,
+            blocks,
+            draw: draw.build(ctx),
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned_pair(PANEL_PLACEMENT)
+                .build(ctx),
+        }
+    }
+}
+
+fn calculate_blocks(ctx: &mut EventCtx, app: &App) -> Vec<BlockStats> {
+    let scenario = match app.primary.scenario {
+        Some(ref scenario) => scenario,
+        None => {
+            return Vec::new();
+        }
+    };
+    let map = &app.primary.map;
+
+    ctx.loading_screen("summarize people per block", |_, timer| {
+        timer.start("find blocks");
+        let mut blocks = Vec::new();
+        for perimeter in Perimeter::find_all_single_blocks(map) {
+            if let Ok(block) = perimeter.to_block(map) {
+                blocks.push(BlockStats {
+                    polygon: block.polygon,
+                    residents: 0,
+                    workers: 0,
+                    with_demographics: 0,
+                    car_owners: 0,
+                    ages: BTreeMap::new(),
+                });
+            }
+        }
+        timer.stop("find blocks");
+
+        timer.start("match buildings to blocks");
+        let mut quadtree = QuadTree::builder();
+        for (idx, block) in blocks.iter().enumerate() {
+            quadtree.add_with_box(idx, block.polygon.get_bounds());
+        }
+        let quadtree = quadtree.build();
+        let mut block_per_bldg = HashMap::new();
+        for b in map.all_buildings() {
+            let pt = b.polygon.center();
+            if let Some(idx) = quadtree
+                .query_bbox(Bounds::from(&[pt]))
+                .find(|idx| blocks[*idx].polygon.contains_pt(pt))
+            {
+                block_per_bldg.insert(b.id, idx);
+            }
+        }
+        timer.stop("match buildings to blocks");
+
+        for person in &scenario.people {
+            if let Some(TripEndpoint::Building(home)) = person.trips.first().map(|t| t.origin) {
+                if let Some(idx) = block_per_bldg.get(&home) {
+                    let block = &mut blocks[*idx];
+                    block.residents += 1;
+                    if let Some(ref demographics) = person.demographics {
+                        block.with_demographics += 1;
+                        if demographics.owns_car {
+                            block.car_owners += 1;
+                        }
+                        *block.ages.entry(demographics.age).or_insert(0) += 1;
+                    }
+                }
+            }
+            // Only count each person's workplace once
+            if let Some(TripEndpoint::Building(work)) = person
+                .trips
+                .iter()
+                .find(|t| t.purpose == TripPurpose::Work)
+                .map(|t| t.destination)
+            {
+                if let Some(idx) = block_per_bldg.get(&work) {
+                    blocks[*idx].workers += 1;
+                }
+            }
+        }
+        blocks
+    })
+}
@@ -0,0 +1,287 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use instant::Instant;
+
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Circle, Distance, PolyLine, Pt2D, Time};
+use map_gui::tools::color_for_mode;
+use synthpop::TripMode;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::{
+    Color, EventCtx, GeomBatch, GfxCtx, Line, Outcome, Panel, Spinner, Text, TextExt, Toggle,
+    UpdateType, Widget,
+};
+
+use crate::app::App;
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+
+/// Trip endpoints are grouped into square cells this wide, so nearby trips share one line
+const CELL_SIZE: f64 = 300.0;
+/// Only draw this many of the busiest lines, to keep the picture readable
+const MAX_LINES: usize = 300;
+/// Particles take this many seconds of real time to travel along any line
+const ANIMATION_PERIOD: f64 = 4.0;
+/// The busiest line gets this many particles
+const MAX_PARTICLES_PER_LINE: usize = 8;
+
+/// Aggregates finished trips into curved lines between their origins and destinations, thicker
+/// for more trips. Lines curve to their right, so the two directions between a pair of places
+/// don't overlap.
+pub struct DesireLines {
+    time: Time,
+    opts: Options,
+    draw: ToggleZoomed,
+    panel: Panel,
+    /// (line, number of particles, color)
+    flows: Vec<(PolyLine, usize, Color)>,
+    started: Instant,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Options {
+    pub modes: BTreeSet<TripMode>,
+    /// Only count trips departing in this range of hours
+    pub from_hour: usize,
+    pub to_hour: usize,
+    pub animate: bool,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            modes: TripMode::all().into_iter().collect(),
+            from_hour: 0,
+            to_hour: 24,
+            animate: false,
+        }
+    }
+}
+
+impl Layer for DesireLines {
+    fn name(&self) -> Option<&'static str> {
+        Some("desire lines")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            let mut new = DesireLines::new(ctx, app, self.opts.clone());
+            new.panel.restore(ctx, &self.panel);
+            new.started = self.started;
+            *self = new;
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            _ => {
+                let new_opts = self.options();
+                if self.opts != new_opts {
+                    let started = self.started;
+                    *self = DesireLines::new(ctx, app, new_opts);
+                    self.started = started;
+                }
+            }
+        }
+
+        if self.opts.animate && !self.flows.is_empty() {
+            ctx.request_update(UpdateType::Game);
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+
+        if self.opts.animate {
+            // Keep particles the same size on screen
+            let radius = Distance::meters(4.0 / g.canvas.cam_zoom);
+            let progress = self.started.elapsed().as_secs_f64() / ANIMATION_PERIOD;
+            let mut batch = GeomBatch::new();
+            for (pl, particles, color) in &self.flows {
+                for i in 0..*particles {
+                    let pct = (progress + (i as f64) / (*particles as f64)).fract();
+                    let (pt, _) = pl.must_dist_along(pct * pl.length());
+                    batch.push(*color, Circle::new(pt, radius).to_polygon());
+                }
+            }
+            batch.draw(g);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl DesireLines {
+    pub fn new(ctx: &mut EventCtx, app: &App, opts: Options) -> DesireLines {
+        let map = &app.primary.map;
+        let sim = &app.primary.sim;
+
+        // Count trips between every pair of cells, remembering the modes used
+        let mut per_pair: BTreeMap<((i64, i64), (i64, i64)), Counter<TripMode>> = BTreeMap::new();
+        let mut total_trips = 0;
+        for (_, id, mode, _) in &sim.get_analytics().finished_trips {
+            if !opts.modes.contains(mode) {
+                continue;
+            }
+            let info = sim.trip_info(*id);
+            let hour = (info.departure.inner_seconds() / 3600.0) as usize;
+            if hour < opts.from_hour || hour >= opts.to_hour {
+                continue;
+            }
+            let from = to_cell(info.start.pt(map));
+            let to = to_cell(info.end.pt(map));
+            if from == to {
+                continue;
+            }
+            per_pair
+                .entry((from, to))
+                .or_insert_with(Counter::new)
+                .inc(*mode);
+            total_trips += 1;
+        }
+
+        // Busiest first
+        let mut lines: Vec<(usize, (i64, i64), (i64, i64), TripMode)> = per_pair
+            .into_iter()
+            .map(|((from, to), counts)| {
+                let main_mode = counts.max_key();
+                (counts.sum(), from, to, main_mode)
+            })
+            .collect();
+        lines.sort_by_key(|(cnt, _, _, _)| std::cmp::Reverse(*cnt));
+        lines.truncate(MAX_LINES);
+        let max_cnt = lines.first().map(|(cnt, _, _, _)| *cnt).unwrap_or(1) as f64;
+
+        let mut draw = ToggleZoomed::builder();
+        let mut flows = Vec::new();
+        // Draw the busiest lines last, on top
+        for (cnt, from, to, mode) in lines.into_iter().rev() {
+            let pl = match curved_line(cell_center(from), cell_center(to)) {
+                Some(pl) => pl,
+                None => continue,
+            };
+            let pct = (cnt as f64) / max_cnt;
+            let color = color_for_mode(app, mode);
+            draw.unzoomed.push(
+                color.alpha(0.7),
+                pl.make_polygons(Distance::meters(5.0 + 35.0 * pct)),
+            );
+            draw.zoomed.push(
+                color.alpha(0.5),
+                pl.make_polygons(Distance::meters(1.0 + 7.0 * pct)),
+            );
+            let particles = ((MAX_PARTICLES_PER_LINE as f64) * pct).ceil() as usize;
+            flows.push((pl, particles.max(1), color));
+        }
+
+        let panel = make_controls(ctx, app, &opts, total_trips, flows.len());
+        DesireLines {
+            time: app.primary.sim.time(),
+            opts,
+            draw: draw.build(ctx),
+            panel,
+            flows,
+            started: Instant::now(),
+        }
+    }
+
+    fn options(&self) -> Options {
+        let mut modes = BTreeSet::new();
+        for m in TripMode::all() {
+            if self.panel.is_checked(m.ongoing_verb()) {
+                modes.insert(m);
+            }
+        }
+        let from_hour = self.panel.spinner("from hour");
+        let to_hour = self.panel.spinner("to hour");
+        Options {
+            modes,
+            from_hour,
+            // Don't let the range be empty
+            to_hour: to_hour.max(from_hour + 1),
+            animate: self.panel.is_checked("animate flows"),
+        }
+    }
+}
+
+fn to_cell(pt: Pt2D) -> (i64, i64) {
+    (
+        (pt.x() / CELL_SIZE).floor() as i64,
+        (pt.y() / CELL_SIZE).floor() as i64,
+    )
+}
+
+fn cell_center((x, y): (i64, i64)) -> Pt2D {
+    Pt2D::new(
+        ((x as f64) + 0.5) * CELL_SIZE,
+        ((y as f64) + 0.5) * CELL_SIZE,
+    )
+}
+
+/// A quadratic Bezier curve from `from` to `to`, bulging to the right
+fn curved_line(from: Pt2D, to: Pt2D) -> Option<PolyLine> {
+    let bulge = 0.2 * from.dist_to(to);
+    let control = from
+        .project_away(from.dist_to(to) / 2.0, from.angle_to(to))
+        .project_away(bulge, from.angle_to(to).rotate_degs(90.0));
+    let steps = 20;
+    let pts = (0..=steps)
+        .map(|i| {
+            let t = (i as f64) / (steps as f64);
+            Pt2D::new(
+                (1.0 - t).powi(2) * from.x()
+                    + 2.0 * (1.0 - t) * t * control.x()
+                    + t.powi(2) * to.x(),
+                (1.0 - t).powi(2) * from.y()
+                    + 2.0 * (1.0 - t) * t * control.y()
+                    + t.powi(2) * to.y(),
+            )
+        })
+        .collect();
+    PolyLine::deduping_new(pts).ok()
+}
+
+fn make_controls(
+    ctx: &mut EventCtx,
+    app: &App,
+    opts: &Options,
+    total_trips: usize,
+    num_lines: usize,
+) -> Panel {
+    let mut col = vec![
+        header(ctx, "Desire lines"),
+        Text::from(
+            Line(format!(
+                "{} finished trips, drawn as the {} busiest lines",
+                prettyprint_usize(total_trips),
+                num_lines
+            ))
+            .secondary(),
+        )
+        .wrap_to_pct(ctx, 15)
+        .into_widget(ctx),
+    ];
+    for m in TripMode::all() {
+        col.push(Toggle::colored_checkbox(
+            ctx,
+            m.ongoing_verb(),
+            color_for_mode(app, m),
+            opts.modes.contains(&m),
+        ));
+    }
+    col.push(Widget::row(vec![
+        "Departing from".text_widget(ctx).centered_vert(),
+        Spinner::widget(ctx, "from hour", (0, 23), opts.from_hour, 1),
+        "to".text_widget(ctx).centered_vert(),
+        Spinner::widget(ctx, "to hour", (1, 24), opts.to_hour, 1),
+    ]));
+    col.push(Toggle::switch(ctx, "animate flows", None, opts.animate));
+
+    Panel::new_builder(Widget::col(col))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx)
+}
@@ -0,0 +1,206 @@
+use maplit::btreeset;
+
+use geom::{Circle, Distance, Duration, Time};
+use map_model::IntersectionID;
+use sim::DelaySummary;
+use widgetry::mapspace::{ObjectID, ToggleZoomed, World, WorldOutcome};
+use widgetry::tools::ColorLegend;
+use widgetry::{Color, EventCtx, GfxCtx, Line, Outcome, Panel, Text, Toggle, Widget};
+
+use crate::app::{App, Transition};
+use crate::edit::{EditMode, TrafficSignalEditor};
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+use crate::sandbox::SandboxMode;
+
+/// Circles are sized by average delay, up to this much
+const MAX_MEAN_DELAY: Duration = Duration::const_seconds(120.0);
+/// Circles are colored by 95th percentile delay, up to this much
+const MAX_P95_DELAY: Duration = Duration::const_seconds(300.0);
+const MIN_RADIUS: Distance = Distance::const_meters(5.0);
+const MAX_RADIUS: Distance = Distance::const_meters(40.0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Obj(IntersectionID);
+
+impl ObjectID for Obj {}
+
+/// How long vehicles or people walking have waited at each intersection so far today. Clicking a
+/// traffic signal opens it in the signal editor.
+pub struct IntersectionDelay {
+    time: Time,
+    pedestrians: bool,
+    world: World<Obj>,
+    panel: Panel,
+}
+
+impl Layer for IntersectionDelay {
+    fn name(&self) -> Option<&'static str> {
+        Some("intersection delay")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = IntersectionDelay::new(ctx, app, self.pedestrians);
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                *self = IntersectionDelay::new(ctx, app, !self.panel.is_checked("delay of"));
+            }
+            _ => {}
+        }
+
+        if let WorldOutcome::ClickedObject(Obj(i)) = self.world.event(ctx) {
+            return Some(LayerOutcome::Transition(Transition::ConsumeState(
+                Box::new(move |state, ctx, app| {
+                    let mode = state
+                        .downcast_ref::<SandboxMode>()
+                        .unwrap()
+                        .gameplay_mode
+                        .clone();
+                    vec![
+                        state,
+                        EditMode::new_state(ctx, app, mode.clone()),
+                        TrafficSignalEditor::new_state(ctx, app, btreeset! {i}, mode),
+                    ]
+                }),
+            )));
+        }
+
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.world.draw(g);
+    }
+    fn draw_minimap(&self, _: &mut GfxCtx) {}
+}
+
+impl IntersectionDelay {
+    pub fn new(ctx: &mut EventCtx, app: &App, pedestrians: bool) -> IntersectionDelay {
+        let map = &app.primary.map;
+        let mut world = World::new();
+        let mut draw = ToggleZoomed::builder();
+        draw.unzoomed
+            .push(app.cs.fade_map_dark, map.get_boundary_polygon().clone());
+        world.draw_master_batch(ctx, draw);
+
+        let analytics = app.primary.sim.get_analytics();
+        let summaries: Vec<(IntersectionID, DelaySummary)> = if pedestrians {
+            analytics
+                .pedestrian_delays
+                .iter()
+                .map(|(i, summary)| (*i, summary.clone()))
+                .collect()
+        } else {
+            analytics
+                .approach_delays
+                .iter()
+                .map(|(i, per_road)| {
+                    let mut summary = DelaySummary::new();
+                    for x in per_road.values() {
+                        summary.merge(x);
+                    }
+                    (*i, summary)
+                })
+                .collect()
+        };
+
+        let mut worst: Option<(IntersectionID, Duration)> = None;
+        for (i, summary) in summaries {
+            let (mean, p95) = match (summary.mean(), summary.percentile(95.0)) {
+                (Some(mean), Some(p95)) => (mean, p95),
+                _ => continue,
+            };
+            if worst.map(|(_, d)| mean > d).unwrap_or(true) {
+                worst = Some((i, mean));
+            }
+
+            let intersection = map.get_i(i);
+            let radius = MIN_RADIUS + (MAX_RADIUS - MIN_RADIUS) * (mean / MAX_MEAN_DELAY).min(1.0);
+            let color = app
+                .cs
+                .good_to_bad_red
+                .eval((p95 / MAX_P95_DELAY).min(1.0))
+                .alpha(0.8);
+
+            let mut txt = Text::from(Line(intersection.name(app.opts.language.as_ref(), map)));
+            txt.add_line(format!("Average delay: {}", mean));
+            txt.add_line(format!("95th percentile delay: {}", p95));
+            txt.add_line(
+                Line(format!(
+                    "{} {} measured",
+                    summary.count,
+                    if pedestrians { "crossings" } else { "vehicles" }
+                ))
+                .secondary(),
+            );
+            if intersection.is_traffic_signal() {
+                txt.add_line(Line("Click to edit this traffic signal").secondary());
+            }
+
+            let obj = world
+                .add(Obj(i))
+                .hitbox(Circle::new(intersection.polygon.center(), radius).to_polygon())
+                .draw_color(color)
+                .hover_outline(Color::BLACK, Distance::meters(2.0))
+                .tooltip(txt);
+            if intersection.is_traffic_signal() {
+                obj.clickable().build(ctx);
+            } else {
+                obj.build(ctx);
+            }
+        }
+        world.initialize_hover(ctx);
+
+        let mut col = vec![
+            header(ctx, "Intersection delay"),
+            Toggle::choice(
+                ctx,
+                "delay of",
+                "Vehicles",
+                "People walking",
+                None,
+                !pedestrians,
+            ),
+            Text::from(
+                Line(if pedestrians {
+                    "How long people walking have waited to cross so far today. Size shows the \
+                     average, color the 95th percentile."
+                } else {
+                    "How long vehicles have waited to turn so far today. Size shows the average, \
+                     color the 95th percentile."
+                })
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec!["0", "1", "2", "3", "4", "5+ mins"],
+            ),
+        ];
+        if let Some((i, mean)) = worst {
+            col.push(
+                Text::from(format!("Worst: {} averages {}", i, mean))
+                    .wrap_to_pct(ctx, 15)
+                    .into_widget(ctx),
+            );
+        }
+
+        IntersectionDelay {
+            time: app.primary.sim.time(),
+            pedestrians,
+            world,
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned_pair(PANEL_PLACEMENT)
+                .build(ctx),
+        }
+    }
+}
@@ -1,16 +1,22 @@
+use std::collections::BTreeSet;
+
 use maplit::btreeset;
 
 use crate::ID;
 use abstutil::{prettyprint_usize, Counter};
 use geom::{Distance, Time};
 use map_gui::tools::{ColorDiscrete, ColorNetwork};
-use map_model::{AmenityType, Direction, LaneType};
+use map_model::{
+    connectivity, AmenityType, Direction, HgvAccess, HgvProfile, LaneType, WalkingClosure,
+};
 use sim::AgentType;
+use synthpop::TripEndpoint;
 use widgetry::mapspace::ToggleZoomed;
 use widgetry::tools::ColorLegend;
-use widgetry::{Color, EventCtx, GfxCtx, Line, Panel, Text, Widget};
+use widgetry::{Color, EventCtx, GeomBatch, GfxCtx, Line, Panel, Text, Widget};
 
 use crate::app::App;
+use crate::edit::walking_closure_color;
 use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
 
 pub struct BikeActivity {
@@ -63,6 +69,9 @@ impl Layer for BikeActivity {
     fn draw_minimap(&self, g: &mut GfxCtx) {
         g.redraw(&self.draw.unzoomed);
     }
+    fn poster(&self) -> Option<(GeomBatch, Vec<(String, Color)>)> {
+        Some(self.poster.clone())
+    }
 }
 
 impl BikeActivity {
@@ -167,6 +176,7 @@ pub struct Static {
     panel: Panel,
     pub draw: ToggleZoomed,
     name: &'static str,
+    poster: (GeomBatch, Vec<(String, Color)>),
 }
 
 impl Layer for Static {
@@ -193,12 +203,18 @@ impl Static {
         title: String,
         extra: Widget,
     ) -> Static {
+        let poster = (colorer.draw.zoomed.clone(), colorer.categories.clone());
         let (draw, legend) = colorer.build(ctx);
         let panel = Panel::new_builder(Widget::col(vec![header(ctx, &title), extra, legend]))
             .aligned_pair(PANEL_PLACEMENT)
             .build(ctx);
 
-        Static { panel, draw, name }
+        Static {
+            panel,
+            draw,
+            name,
+            poster,
+        }
     }
 
     pub fn edits(ctx: &mut EventCtx, app: &App) -> Static {
@@ -273,8 +289,15 @@ impl Static {
         .aligned_pair(PANEL_PLACEMENT)
         .build(ctx);
 
+        let legend = vec![
+            (AmenityType::Food.to_string(), food),
+            (AmenityType::School.to_string(), school),
+            (AmenityType::Shopping.to_string(), shopping),
+            ("other".to_string(), other),
+        ];
         Static {
             panel,
+            poster: (draw.zoomed.clone(), legend),
             draw: draw.build(ctx),
             name: "amenities",
         }
@@ -296,6 +319,125 @@ impl Static {
         )
     }
 
+    pub fn inaccessible_crossings(ctx: &mut EventCtx, app: &App) -> Static {
+        let map = &app.primary.map;
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("raised kerb at a crossing", Color::RED),
+                ("steps", Color::PURPLE),
+                ("rough surface", Color::YELLOW),
+            ],
+        );
+        let mut num_intersections = 0;
+        for i in map.all_intersections() {
+            if i.turns.iter().any(|t| !t.usable_by_wheelchair(map)) {
+                num_intersections += 1;
+                colorer.add_i(i.id, "raised kerb at a crossing");
+            }
+        }
+        for r in map.all_roads() {
+            if !r.lanes.iter().any(|l| l.is_walkable()) {
+                continue;
+            }
+            if r.is_steps() {
+                colorer.add_r(r.id, "steps");
+            } else if r.has_rough_walking_surface() {
+                colorer.add_r(r.id, "rough surface");
+            }
+        }
+        Static::new(
+            ctx,
+            colorer,
+            "inaccessible crossings",
+            "Inaccessible to wheelchairs".to_string(),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} intersections with a crossing missing a dropped kerb",
+                    prettyprint_usize(num_intersections)
+                )),
+                Line("Only kerbs tagged in OpenStreetMap are known").secondary(),
+            ])
+            .into_widget(ctx),
+        )
+    }
+
+    /// Sidewalks and crossings closed by snow or works, and who can't get around on foot because
+    /// of them
+    pub fn walking_closures(ctx: &mut EventCtx, app: &App) -> Static {
+        let map = &app.primary.map;
+        let mut categories: Vec<(&str, Color)> = vec![
+            ("cut off", Color::RED),
+            ("cut off for wheelchairs", Color::PURPLE),
+        ];
+        for closure in WalkingClosure::all() {
+            categories.push((closure.describe(), walking_closure_color(closure)));
+        }
+        let mut colorer = ColorDiscrete::new(app, categories);
+
+        for l in map.all_lanes() {
+            if let Some(closure) = l.walking_closure(map) {
+                colorer.add_l(l.id, closure.describe());
+            }
+        }
+        for i in map.all_intersections() {
+            if let Some(closure) = i.closed_crossings.values().next() {
+                colorer.add_i(i.id, closure.describe());
+            }
+        }
+
+        let cut_off = connectivity::find_cut_off_buildings(map, false);
+        let mut wheelchair_cut_off = connectivity::find_cut_off_buildings(map, true);
+        wheelchair_cut_off.retain(|b| !cut_off.contains(b));
+        for b in &cut_off {
+            colorer.add_b(*b, "cut off");
+        }
+        for b in &wheelchair_cut_off {
+            colorer.add_b(*b, "cut off for wheelchairs");
+        }
+
+        // Who has a trip starting or ending somewhere they can't get to?
+        let sim = &app.primary.sim;
+        let mut people = BTreeSet::new();
+        let mut wheelchair_users = BTreeSet::new();
+        for (id, info) in sim.all_trip_info() {
+            let person = sim.trip_to_person(id).unwrap();
+            let wheelchair = sim.get_person(person).wheelchair;
+            for endpt in [info.start, info.end] {
+                if let TripEndpoint::Building(b) = endpt {
+                    if cut_off.contains(&b) {
+                        people.insert(person);
+                    }
+                    if wheelchair && (cut_off.contains(&b) || wheelchair_cut_off.contains(&b)) {
+                        wheelchair_users.insert(person);
+                    }
+                }
+            }
+        }
+
+        Static::new(
+            ctx,
+            colorer,
+            "walking closures",
+            "Closed sidewalks and crossings".to_string(),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} buildings cut off on foot, {} more for wheelchair users",
+                    prettyprint_usize(cut_off.len()),
+                    prettyprint_usize(wheelchair_cut_off.len())
+                )),
+                Line(format!(
+                    "{} people have trips to or from them, including {} using wheelchairs",
+                    prettyprint_usize(people.len()),
+                    prettyprint_usize(wheelchair_users.len())
+                )),
+                Line("Close sidewalks and crossings while editing the map").secondary(),
+            ])
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+        )
+    }
+
     pub fn blackholes(ctx: &mut EventCtx, app: &App) -> Static {
         let mut colorer = ColorDiscrete::new(
             app,
@@ -347,4 +489,52 @@ impl Static {
             .into_widget(ctx),
         )
     }
+
+    pub fn hgv_network(ctx: &mut EventCtx, app: &App) -> Static {
+        let profile = HgvProfile::default();
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("HGV route", Color::GREEN),
+                ("permitted", Color::BLUE),
+                ("access only", Color::YELLOW),
+                ("too heavy or tall", Color::ORANGE),
+                ("banned", Color::RED),
+            ],
+        );
+        let mut num_restricted = 0;
+        for r in app.primary.map.all_roads() {
+            if !r.lanes.iter().any(|l| l.is_driving()) {
+                continue;
+            }
+            let category = match r.hgv.access {
+                HgvAccess::Banned => "banned",
+                HgvAccess::Destination => "access only",
+                _ if !r.hgv.fits(&profile) => "too heavy or tall",
+                HgvAccess::Designated => "HGV route",
+                HgvAccess::Allowed => "permitted",
+            };
+            if !r.hgv.permits_through(&profile) {
+                num_restricted += 1;
+            }
+            colorer.add_r(r.id, category);
+        }
+
+        Static::new(
+            ctx,
+            colorer,
+            "HGV network",
+            "Roads permitted for HGVs".to_string(),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} roads restricted for a {}t, {}m lorry",
+                    prettyprint_usize(num_restricted),
+                    profile.weight_kg / 1000,
+                    profile.height.inner_meters()
+                )),
+                Line("Only restrictions tagged in OpenStreetMap or edited are known").secondary(),
+            ])
+            .into_widget(ctx),
+        )
+    }
 }
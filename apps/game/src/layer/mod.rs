@@ -1,21 +1,29 @@
+use std::collections::BTreeSet;
+
 use map_gui::tools::{grey_out_map, HeatmapOptions};
 use sim::AgentType;
 use widgetry::{
-    DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Image, Key, Line, Outcome, Panel, State,
-    TextExt, VerticalAlignment, Widget,
+    Color, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Image, Key, Line,
+    Outcome, Panel, State, TextExt, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
 use crate::sandbox::dashboards;
 
+mod demographics;
+mod desire_lines;
 pub mod elevation;
 pub mod favorites;
+mod intersection_delay;
 pub mod map;
+mod observed_traffic;
 mod pandemic;
 mod parking;
 mod population;
 mod problems;
 mod problems_diff;
+mod signals;
+mod spillback;
 pub mod traffic;
 pub mod transit;
 
@@ -29,6 +37,11 @@ pub trait Layer {
     fn draw(&self, g: &mut GfxCtx, app: &App);
     // Just draw contents and do it always
     fn draw_minimap(&self, g: &mut GfxCtx);
+    /// The layer contents to draw over a detailed map, in map-space, and a legend. Used for
+    /// exporting posters; most layers don't support this.
+    fn poster(&self) -> Option<(GeomBatch, Vec<(String, Color)>)> {
+        None
+    }
 }
 
 impl dyn Layer {
@@ -108,16 +121,23 @@ impl PickLayer {
                     btn("delay", Key::D),
                     btn("throughput", Key::T),
                     btn("traffic jams", Key::J),
+                    btn("queue spillback", Key::Num4),
                     btn("cycling activity", Key::B),
                     btn("pedestrian crowding", Key::C),
+                    btn("signal stages", Key::Q),
+                    btn("intersection delay", Key::I),
                 ]),
                 Widget::col(vec![
                     "Map".text_widget(ctx),
                     btn("map edits", Key::E),
                     btn("parking occupancy", Key::P),
+                    btn("bike parking", Key::Num1),
                     btn("transit network", Key::U),
                     btn("population map", Key::X),
+                    btn("demographics", Key::Num3),
                     btn("no sidewalks", Key::S),
+                    btn("inaccessible crossings", Key::W),
+                    btn("walking closures", Key::Num7),
                     btn("favorite buildings", Key::F),
                 ]),
             ])
@@ -136,6 +156,7 @@ impl PickLayer {
                     btn("blackholes", Key::L),
                     btn("problem map", Key::K),
                     btn("high stress", Key::H),
+                    btn("HGV network", Key::Num2),
                     if app.primary.sim.get_pandemic_model().is_some() {
                         btn("pandemic model", Key::Y)
                     } else {
@@ -146,6 +167,8 @@ impl PickLayer {
                     "Data".text_widget(ctx),
                     btn("traffic signal demand", Key::M),
                     btn("commuter patterns", Key::R),
+                    btn("desire lines", Key::Num5),
+                    btn("observed traffic", Key::Num6),
                 ]),
             ])
             .evenly_spaced(),
@@ -167,91 +190,6 @@ impl State<App> for PickLayer {
                 "None" => {
                     app.primary.layer = None;
                 }
-                "amenities" => {
-                    app.primary.layer = Some(Box::new(map::Static::amenities(ctx, app)));
-                }
-                "backpressure" => {
-                    app.primary.layer = Some(Box::new(traffic::Backpressure::new(ctx, app)));
-                }
-                "cycling activity" => {
-                    app.primary.layer = Some(Box::new(map::BikeActivity::new(ctx, app)));
-                }
-                "delay" => {
-                    app.primary.layer = Some(Box::new(traffic::Delay::new(ctx, app)));
-                }
-                "pedestrian crowding" => {
-                    app.primary.layer = Some(Box::new(traffic::PedestrianCrowding::new(ctx, app)));
-                }
-                "steep streets" => {
-                    app.primary.layer = Some(Box::new(elevation::SteepStreets::new(ctx, app)));
-                }
-                "elevation" => {
-                    app.primary.layer = Some(Box::new(elevation::ElevationContours::new(ctx, app)));
-                }
-                "map edits" => {
-                    app.primary.layer = Some(Box::new(map::Static::edits(ctx, app)));
-                }
-                "no sidewalks" => {
-                    app.primary.layer = Some(Box::new(map::Static::no_sidewalks(ctx, app)));
-                }
-                "high stress" => {
-                    app.primary.layer = Some(Box::new(map::Static::high_stress(ctx, app)));
-                }
-                "favorite buildings" => {
-                    app.primary.layer = Some(Box::new(favorites::ShowFavorites::new(ctx, app)));
-                }
-                "pandemic model" => {
-                    app.primary.layer = Some(Box::new(pandemic::Pandemic::new(
-                        ctx,
-                        app,
-                        pandemic::Options {
-                            heatmap: Some(HeatmapOptions::new()),
-                            state: pandemic::Seir::Infected,
-                        },
-                    )));
-                }
-                "blackholes" => {
-                    app.primary.layer = Some(Box::new(map::Static::blackholes(ctx, app)));
-                }
-                "parking occupancy" => {
-                    app.primary.layer = Some(Box::new(parking::Occupancy::new(
-                        ctx, app, true, true, true, false, true,
-                    )));
-                }
-                "parking efficiency" => {
-                    app.primary.layer = Some(Box::new(parking::Efficiency::new(ctx, app)));
-                }
-                "population map" => {
-                    app.primary.layer = Some(Box::new(population::PopulationMap::new(
-                        ctx,
-                        app,
-                        population::Options {
-                            heatmap: Some(HeatmapOptions::new()),
-                        },
-                    )));
-                }
-                "problem map" => {
-                    app.primary.layer = Some(Box::new(problems::ProblemMap::new(
-                        ctx,
-                        app,
-                        problems::Options::new(app),
-                    )));
-                }
-                "throughput" => {
-                    app.primary.layer = Some(Box::new(traffic::Throughput::new(
-                        ctx,
-                        app,
-                        AgentType::all().into_iter().collect(),
-                    )));
-                }
-                "traffic jams" => {
-                    app.primary.layer = Some(Box::new(traffic::TrafficJams::new(ctx, app)));
-                }
-                "transit network" => {
-                    app.primary.layer = Some(Box::new(transit::TransitNetwork::new(
-                        ctx, app, false, true, true,
-                    )));
-                }
                 "traffic signal demand" => {
                     return Transition::Replace(dashboards::TrafficSignalDemand::new_state(
                         ctx, app,
@@ -260,7 +198,9 @@ impl State<App> for PickLayer {
                 "commuter patterns" => {
                     return Transition::Replace(dashboards::CommuterPatterns::new_state(ctx, app));
                 }
-                _ => unreachable!(),
+                x => {
+                    app.primary.layer = Some(layer_by_name(ctx, app, x).unwrap());
+                }
             },
             _ => {
                 if self.panel.clicked_outside(ctx) {
@@ -282,7 +222,126 @@ impl State<App> for PickLayer {
     }
 }
 
+/// Creates a layer by the name shown in the layer picker, or as returned by `Layer::name`. Returns
+/// `None` for unknown names.
+pub fn layer_by_name(ctx: &mut EventCtx, app: &App, name: &str) -> Option<Box<dyn Layer>> {
+    match name {
+        "amenities" => Some(Box::new(map::Static::amenities(ctx, app))),
+        "backpressure" => Some(Box::new(traffic::Backpressure::new(ctx, app))),
+        "cycling activity" => Some(Box::new(map::BikeActivity::new(ctx, app))),
+        "delay" => Some(Box::new(traffic::Delay::new(ctx, app))),
+        "desire lines" => Some(Box::new(desire_lines::DesireLines::new(
+            ctx,
+            app,
+            desire_lines::Options::new(),
+        ))),
+        "demographics" => Some(Box::new(demographics::BlockDemographics::new(
+            ctx,
+            app,
+            demographics::Measure::ResidentialDensity,
+        ))),
+        "observed traffic" => Some(Box::new(observed_traffic::CompareObserved::new(ctx, app))),
+        "pedestrian crowding" => Some(Box::new(traffic::PedestrianCrowding::new(ctx, app))),
+        "intersection delay" => Some(Box::new(intersection_delay::IntersectionDelay::new(
+            ctx, app, false,
+        ))),
+        "signal stages" => Some(Box::new(signals::SignalStages::new(ctx, app))),
+        "steep streets" => Some(Box::new(elevation::SteepStreets::new(ctx, app))),
+        "elevation" => Some(Box::new(elevation::ElevationContours::new(ctx, app))),
+        "map edits" => Some(Box::new(map::Static::edits(ctx, app))),
+        "no sidewalks" => Some(Box::new(map::Static::no_sidewalks(ctx, app))),
+        "inaccessible crossings" => Some(Box::new(map::Static::inaccessible_crossings(ctx, app))),
+        "walking closures" => Some(Box::new(map::Static::walking_closures(ctx, app))),
+        "high stress" => Some(Box::new(map::Static::high_stress(ctx, app))),
+        "HGV network" => Some(Box::new(map::Static::hgv_network(ctx, app))),
+        "favorite buildings" | "favorites" => {
+            Some(Box::new(favorites::ShowFavorites::new(ctx, app)))
+        }
+        "pandemic model" => Some(Box::new(pandemic::Pandemic::new(
+            ctx,
+            app,
+            pandemic::Options {
+                heatmap: Some(HeatmapOptions::new()),
+                state: pandemic::Seir::Infected,
+            },
+        ))),
+        "blackholes" => Some(Box::new(map::Static::blackholes(ctx, app))),
+        "parking occupancy" => Some(Box::new(parking::Occupancy::new(
+            ctx, app, true, true, true, false, true,
+        ))),
+        "bike parking" => Some(Box::new(parking::BikeOccupancy::new(ctx, app))),
+        "parking efficiency" => Some(Box::new(parking::Efficiency::new(ctx, app))),
+        "population map" => Some(Box::new(population::PopulationMap::new(
+            ctx,
+            app,
+            population::Options {
+                heatmap: Some(HeatmapOptions::new()),
+            },
+        ))),
+        "problem map" => Some(Box::new(problems::ProblemMap::new(
+            ctx,
+            app,
+            problems::Options::new(app),
+        ))),
+        "throughput" => Some(Box::new(traffic::Throughput::new(
+            ctx,
+            app,
+            AgentType::all().into_iter().collect(),
+        ))),
+        "traffic jams" => Some(Box::new(traffic::TrafficJams::new(ctx, app))),
+        "queue spillback" => Some(Box::new(spillback::QueueSpillback::new(
+            ctx,
+            app,
+            false,
+            BTreeSet::new(),
+        ))),
+        "transit network" => Some(Box::new(transit::TransitNetwork::new(
+            ctx, app, false, true, true,
+        ))),
+        _ => None,
+    }
+}
+
 /// Creates the top row for any layer panel.
+/// The names of every layer that `layer_by_name` can create for the current simulation
+pub fn all_layer_names(app: &App) -> Vec<&'static str> {
+    let mut names = vec![
+        "delay",
+        "throughput",
+        "traffic jams",
+        "queue spillback",
+        "cycling activity",
+        "pedestrian crowding",
+        "signal stages",
+        "intersection delay",
+        "map edits",
+        "parking occupancy",
+        "bike parking",
+        "transit network",
+        "population map",
+        "demographics",
+        "no sidewalks",
+        "inaccessible crossings",
+        "walking closures",
+        "favorite buildings",
+        "amenities",
+        "backpressure",
+        "steep streets",
+        "elevation",
+        "parking efficiency",
+        "blackholes",
+        "problem map",
+        "high stress",
+        "HGV network",
+        "desire lines",
+        "observed traffic",
+    ];
+    if app.primary.sim.get_pandemic_model().is_some() {
+        names.push("pandemic model");
+    }
+    names
+}
+
 pub fn header(ctx: &mut EventCtx, name: &str) -> Widget {
     Widget::row(vec![
         Image::from_path("system/assets/tools/layers.svg")
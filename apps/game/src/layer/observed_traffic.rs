@@ -0,0 +1,259 @@
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Speed, Time};
+use map_gui::tools::ColorNetwork;
+use map_model::RoadID;
+use sim::AgentType;
+use synthpop::ObservedTraffic;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::DivergingScale;
+use widgetry::{
+    Color, EventCtx, GfxCtx, Line, Outcome, Panel, Spinner, Text, TextExt, Toggle, Widget,
+};
+
+use crate::app::App;
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+use crate::ID;
+
+/// A road matches its observations if the simulated value is within this ratio
+const CLOSE_ENOUGH: f64 = 0.2;
+
+/// Compares speeds or volumes observed in the real world, imported with the `cli` tool, against
+/// the simulation, road-by-road and for one hour of the day.
+pub struct CompareObserved {
+    time: Time,
+    opts: Options,
+    data: Option<ObservedTraffic>,
+    tooltip: Option<Text>,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Options {
+    pub speed: bool,
+    pub hour: usize,
+}
+
+impl Layer for CompareObserved {
+    fn name(&self) -> Option<&'static str> {
+        Some("observed traffic")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        let mut recalc_tooltip = false;
+        if app.primary.sim.time() != self.time {
+            self.recalculate(ctx, app);
+            recalc_tooltip = true;
+        }
+
+        // Show a tooltip with both values, only when unzoomed
+        if ctx.canvas.is_unzoomed() {
+            if ctx.redo_mouseover() || recalc_tooltip {
+                self.tooltip = None;
+                if let Some(ID::Road(r)) = app.mouseover_unzoomed_roads_and_intersections(ctx) {
+                    if let Some((observed, simulated)) = self.compare(app, r) {
+                        self.tooltip = Some(Text::from(format!(
+                            "{} observed, {} simulated",
+                            self.describe(app, observed),
+                            self.describe(app, simulated)
+                        )));
+                    }
+                }
+            }
+        } else {
+            self.tooltip = None;
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                let new_opts = Options {
+                    speed: self.panel.is_checked("measure"),
+                    hour: self.panel.spinner("hour"),
+                };
+                if self.opts != new_opts {
+                    self.opts = new_opts;
+                    self.recalculate(ctx, app);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+        if let Some(ref txt) = self.tooltip {
+            g.draw_mouse_tooltip(txt.clone());
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl CompareObserved {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> CompareObserved {
+        let data = abstio::maybe_read_binary::<ObservedTraffic>(
+            abstio::path_observed_traffic(app.primary.map.get_name()),
+            &mut Timer::throwaway(),
+        )
+        .ok();
+        let mut layer = CompareObserved {
+            time: app.primary.sim.time(),
+            opts: Options {
+                speed: true,
+                hour: app.primary.sim.time().get_hours().min(23),
+            },
+            data,
+            tooltip: None,
+            draw: ToggleZoomed::empty(ctx),
+            panel: Panel::empty(ctx),
+        };
+        layer.recalculate(ctx, app);
+        layer
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.time = app.primary.sim.time();
+
+        let mut col = vec![header(ctx, "Observed traffic")];
+        let data = if let Some(ref data) = self.data {
+            data
+        } else {
+            col.push(
+                Text::from(
+                    Line(
+                        "No observed traffic for this map. Import some with the cli tool's \
+                         import-observed-traffic command.",
+                    )
+                    .secondary(),
+                )
+                .wrap_to_pct(ctx, 15)
+                .into_widget(ctx),
+            );
+            self.draw = ToggleZoomed::empty(ctx);
+            self.panel = Panel::new_builder(Widget::col(col))
+                .aligned_pair(PANEL_PLACEMENT)
+                .build(ctx);
+            return;
+        };
+
+        let scale = DivergingScale::new(
+            app.cs.better_than_before,
+            Color::WHITE,
+            app.cs.worse_than_before,
+        )
+        .range(0.0, 2.0)
+        .ignore(1.0 - CLOSE_ENOUGH, 1.0 + CLOSE_ENOUGH);
+
+        let mut colorer = ColorNetwork::new(app);
+        let mut compared = 0;
+        let mut close = 0;
+        for r in data
+            .per_road
+            .keys()
+            .filter(|(_, hour)| *hour == self.opts.hour)
+            .map(|(r, _)| *r)
+        {
+            if let Some((observed, simulated)) = self.compare(app, r) {
+                if observed == 0.0 {
+                    continue;
+                }
+                let ratio = simulated / observed;
+                compared += 1;
+                if (ratio - 1.0).abs() <= CLOSE_ENOUGH {
+                    close += 1;
+                }
+                if let Some(c) = scale.eval(ratio) {
+                    colorer.add_r(r, c);
+                }
+            }
+        }
+
+        col.push(Text::from(Line(&data.description).secondary()).into_widget(ctx));
+        col.push(Toggle::choice(
+            ctx,
+            "measure",
+            "speed",
+            "volume",
+            None,
+            self.opts.speed,
+        ));
+        col.push(Widget::row(vec![
+            "Hour of the day".text_widget(ctx).centered_vert(),
+            Spinner::widget(ctx, "hour", (0, 23), self.opts.hour, 1),
+        ]));
+        col.push(
+            Text::from(
+                Line(if self.opts.hour > self.time.get_hours() {
+                    "The simulation hasn't reached this hour yet".to_string()
+                } else {
+                    format!(
+                        "Within {}% of observations on {} of {} roads",
+                        (CLOSE_ENOUGH * 100.0) as usize,
+                        prettyprint_usize(close),
+                        prettyprint_usize(compared)
+                    )
+                })
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+        );
+        col.push(scale.make_legend(ctx, vec!["simulated lower", "same", "higher"]));
+
+        self.draw = colorer.build(ctx);
+        self.panel = Panel::new_builder(Widget::col(col))
+            .aligned_pair(PANEL_PLACEMENT)
+            .build(ctx);
+    }
+
+    /// The observed and simulated speed (in meters per second) or volume on a road for the
+    /// selected hour, if both exist
+    fn compare(&self, app: &App, r: RoadID) -> Option<(f64, f64)> {
+        let observation = self.data.as_ref()?.get(r, self.opts.hour)?;
+        let analytics = app.primary.sim.get_analytics();
+        if self.opts.speed {
+            let observed = observation.speed?;
+            let simulated = analytics.average_road_speed(r, self.opts.hour)?;
+            Some((
+                observed.inner_meters_per_second(),
+                simulated.inner_meters_per_second(),
+            ))
+        } else {
+            let observed = observation.volume?;
+            let start = Time::START_OF_DAY + Duration::hours(self.opts.hour);
+            if self.time <= start {
+                return None;
+            }
+            let mut simulated = 0;
+            for agent_type in [AgentType::Car, AgentType::Bus] {
+                simulated += analytics
+                    .road_thruput
+                    .counts
+                    .get(&(r, agent_type, self.opts.hour))
+                    .cloned()
+                    .unwrap_or(0);
+            }
+            // If the hour isn't over yet, extrapolate the count so far
+            let elapsed = (self.time - start).min(Duration::hours(1));
+            Some((
+                observed as f64,
+                (simulated as f64) / (elapsed / Duration::hours(1)),
+            ))
+        }
+    }
+
+    fn describe(&self, app: &App, value: f64) -> String {
+        if self.opts.speed {
+            Speed::meters_per_second(value).to_string(&app.opts.units)
+        } else {
+            format!("{} vehicles/hour", prettyprint_usize(value as usize))
+        }
+    }
+}
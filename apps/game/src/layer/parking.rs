@@ -401,3 +401,75 @@ impl Efficiency {
         }
     }
 }
+
+pub struct BikeOccupancy {
+    time: Time,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+impl Layer for BikeOccupancy {
+    fn name(&self) -> Option<&'static str> {
+        Some("bike parking")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = BikeOccupancy::new(ctx, app);
+        }
+        <dyn Layer>::simple_event(ctx, &mut self.panel)
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl BikeOccupancy {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> BikeOccupancy {
+        let mut filled = 0;
+        let mut capacity = 0;
+        let mut full = 0;
+        let mut colorer = ColorNetwork::new(app);
+        for b in app.primary.map.all_buildings() {
+            if b.bike_parking == 0 {
+                continue;
+            }
+            // Map edits may have removed spots that're still in use
+            let occupied = app.primary.sim.get_bike_parking_occupied(b.id);
+            filled += occupied.min(b.bike_parking);
+            capacity += b.bike_parking;
+            if occupied >= b.bike_parking {
+                full += 1;
+            }
+            let percent = (occupied as f64 / b.bike_parking as f64).min(1.0);
+            colorer.add_b(b.id, app.cs.good_to_bad_red.eval(percent));
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Bike parking occupancy"),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} / {} spots filled",
+                    prettyprint_usize(filled),
+                    prettyprint_usize(capacity)
+                )),
+                Line(format!("{} buildings are full", prettyprint_usize(full))),
+                Line("Cyclists arriving at a full building park nearby and walk").secondary(),
+            ])
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+            ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0%", "100%"]),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        BikeOccupancy {
+            time: app.primary.sim.time(),
+            draw: colorer.build(ctx),
+            panel,
+        }
+    }
+}
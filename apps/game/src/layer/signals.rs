@@ -0,0 +1,127 @@
+use anyhow::Result;
+
+use geom::{Angle, Distance, Pt2D, Ring, Time};
+use map_model::{IntersectionID, Map, MovementID, RoadID};
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::ColorLegend;
+use widgetry::{Color, EventCtx, GfxCtx, Line, Panel, Text, Widget};
+
+use crate::app::App;
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+
+/// Radius of each wedge, big enough to see when zoomed out
+const WEDGE_RADIUS: Distance = Distance::const_meters(15.0);
+/// Each wedge spans twice this angle
+const WEDGE_HALF_ANGLE_DEGS: f64 = 25.0;
+
+/// When zoomed out, show which approaches to every traffic signal have a green light right now.
+pub struct SignalStages {
+    time: Time,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+impl Layer for SignalStages {
+    fn name(&self) -> Option<&'static str> {
+        Some("signal stages")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = SignalStages::new(ctx, app);
+        }
+
+        <dyn Layer>::simple_event(ctx, &mut self.panel)
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl SignalStages {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> SignalStages {
+        let map = &app.primary.map;
+        let mut draw = ToggleZoomed::builder();
+        for i in map.all_intersections() {
+            if !i.is_traffic_signal() {
+                continue;
+            }
+            let (stage_idx, _) = app.primary.sim.current_stage_and_remaining_time(i.id);
+            let stage = &map.get_traffic_signal(i.id).stages[stage_idx];
+            let center = i.polygon.center();
+
+            for r in &i.roads {
+                // Skip roads that only lead away from the intersection
+                if !i
+                    .incoming_lanes
+                    .iter()
+                    .any(|l| l.road == *r && map.get_l(*l).is_driving())
+                {
+                    continue;
+                }
+
+                // What's the best any vehicle coming from this road can do right now?
+                let from_here = |m: &&MovementID| m.from.road == *r && !m.crosswalk;
+                let color = if stage.protected_movements.iter().any(from_here) {
+                    app.cs.signal_protected_turn
+                } else if stage.yield_movements.iter().any(from_here) {
+                    app.cs.signal_permitted_turn
+                } else {
+                    app.cs.signal_banned_turn
+                };
+                if let Ok(ring) = make_wedge(center, approach_angle(map, i.id, center, *r)) {
+                    draw.unzoomed.push(color, ring.clone().into_polygon());
+                    draw.unzoomed
+                        .push(Color::BLACK, ring.to_outline(Distance::meters(1.0)));
+                }
+            }
+        }
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            header(ctx, "Signal stages"),
+            Text::from(Line("What each approach to a traffic signal can do right now").secondary())
+                .wrap_to_pct(ctx, 15)
+                .into_widget(ctx),
+            ColorLegend::row(ctx, app.cs.signal_protected_turn, "green"),
+            ColorLegend::row(ctx, app.cs.signal_permitted_turn, "yield to others"),
+            ColorLegend::row(ctx, app.cs.signal_banned_turn, "red"),
+        ]))
+        .aligned_pair(PANEL_PLACEMENT)
+        .build(ctx);
+
+        SignalStages {
+            time: app.primary.sim.time(),
+            draw: draw.build(ctx),
+            panel,
+        }
+    }
+}
+
+/// The direction from the middle of the intersection towards one of its roads
+fn approach_angle(map: &Map, i: IntersectionID, center: Pt2D, r: RoadID) -> Angle {
+    let road = map.get_r(r);
+    let pl = if road.src_i == i {
+        road.center_pts.clone()
+    } else {
+        road.center_pts.reversed()
+    };
+    let (pt, angle) = pl.must_dist_along(pl.length().min(Distance::meters(10.0)));
+    if center.dist_to(pt) < Distance::meters(0.1) {
+        angle
+    } else {
+        center.angle_to(pt)
+    }
+}
+
+fn make_wedge(center: Pt2D, facing: Angle) -> Result<Ring> {
+    let mut pts = vec![center];
+    for step in 0..=4 {
+        let degs = -WEDGE_HALF_ANGLE_DEGS + (step as f64) * WEDGE_HALF_ANGLE_DEGS / 2.0;
+        pts.push(center.project_away(WEDGE_RADIUS, facing.rotate_degs(degs)));
+    }
+    pts.push(center);
+    Ring::new(pts)
+}
@@ -0,0 +1,232 @@
+use std::collections::BTreeSet;
+
+use geom::{Bounds, Distance, Duration, Pt2D, Time};
+use map_model::IntersectionID;
+use sim::Gridlock;
+use widgetry::mapspace::ToggleZoomed;
+use widgetry::tools::ColorLegend;
+use widgetry::{Color, EventCtx, GfxCtx, Line, Outcome, Panel, Text, TextExt, Toggle, Widget};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::layer::{header, Layer, LayerOutcome, PANEL_PLACEMENT};
+use crate::sandbox::SandboxMode;
+
+/// Vehicles waiting on each other for less than this is probably just normal queueing between
+/// nearby signals
+const GRIDLOCK_THRESHOLD: Duration = Duration::const_seconds(120.0);
+/// Only explain this many cases of gridlock in the panel
+const MAX_GRIDLOCK_SHOWN: usize = 3;
+
+/// Draws how far stopped vehicles back up from the end of each lane, and watches for gridlock:
+/// groups of intersections where vehicles are all stuck waiting on each other in a cycle.
+pub struct QueueSpillback {
+    time: Time,
+    pause_on_gridlock: bool,
+    gridlock: Vec<Gridlock>,
+    /// Intersections that've been part of gridlock already, so the same problem doesn't keep
+    /// pausing the simulation
+    reported: BTreeSet<IntersectionID>,
+    draw: ToggleZoomed,
+    panel: Panel,
+}
+
+impl Layer for QueueSpillback {
+    fn name(&self) -> Option<&'static str> {
+        Some("queue spillback")
+    }
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            let mut reported = std::mem::take(&mut self.reported);
+            // The simulation was reset
+            if app.primary.sim.time() < self.time {
+                reported.clear();
+            }
+            *self = QueueSpillback::new(ctx, app, self.pause_on_gridlock, reported);
+
+            let fresh = self.gridlock.iter().position(|gridlock| {
+                gridlock
+                    .intersections
+                    .iter()
+                    .any(|i| !self.reported.contains(i))
+            });
+            for gridlock in &self.gridlock {
+                self.reported.extend(gridlock.intersections.iter().cloned());
+            }
+            if let Some(idx) = fresh {
+                if self.pause_on_gridlock {
+                    return Some(LayerOutcome::Transition(Transition::Multi(vec![
+                        Transition::ModifyState(Box::new(|state, ctx, app| {
+                            // This layer might be open outside of sandbox mode
+                            if let Some(mode) = state.downcast_mut::<SandboxMode>() {
+                                if let Some(ref mut time_panel) = mode.controls.time_panel {
+                                    time_panel.pause(ctx, app);
+                                }
+                            }
+                        })),
+                        self.warp_to(ctx, app, idx),
+                    ])));
+                }
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if x == "close" {
+                    return Some(LayerOutcome::Close);
+                }
+                if let Some(idx) = x.strip_prefix("zoom to gridlock ") {
+                    let idx = idx.parse::<usize>().unwrap() - 1;
+                    return Some(LayerOutcome::Transition(self.warp_to(ctx, app, idx)));
+                }
+                unreachable!()
+            }
+            Outcome::Changed(_) => {
+                self.pause_on_gridlock = self.panel.is_checked("pause when gridlock is found");
+            }
+            _ => {}
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.draw.draw(g);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw.unzoomed);
+    }
+}
+
+impl QueueSpillback {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        pause_on_gridlock: bool,
+        reported: BTreeSet<IntersectionID>,
+    ) -> QueueSpillback {
+        let map = &app.primary.map;
+        let mut draw = ToggleZoomed::builder();
+
+        let mut num_spilling_back = 0;
+        for (l, length) in app.primary.sim.get_queue_lengths() {
+            let lane = map.get_l(l);
+            let pct = (length / lane.length()).min(1.0);
+            if pct == 1.0 {
+                num_spilling_back += 1;
+            }
+            // The queue starts at the end of the lane and grows backwards
+            if let Ok(pl) = lane
+                .lane_center_pts
+                .maybe_exact_slice(lane.length() - length.min(lane.length()), lane.length())
+            {
+                let color = app.cs.good_to_bad_red.eval(pct);
+                draw.unzoomed.push(color, pl.make_polygons(lane.width));
+                draw.zoomed
+                    .push(color.alpha(0.7), pl.make_polygons(0.5 * lane.width));
+            }
+        }
+
+        let gridlock = app.primary.sim.find_gridlock(map, GRIDLOCK_THRESHOLD);
+        for i in gridlock.iter().flat_map(|g| g.intersections.iter()) {
+            let polygon = &map.get_i(*i).polygon;
+            draw.unzoomed.push(Color::RED.alpha(0.8), polygon.clone());
+            draw.zoomed.push(
+                Color::RED.alpha(0.5),
+                polygon.to_outline(Distance::meters(1.0)),
+            );
+        }
+
+        let mut col = vec![
+            header(ctx, "Queue spillback"),
+            Text::from(
+                Line("Bars show how far stopped vehicles back up from the end of each lane")
+                    .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .into_widget(ctx),
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec!["short queue", "fills the lane"],
+            ),
+            format!(
+                "{} lanes are backed up to the previous intersection",
+                num_spilling_back
+            )
+            .text_widget(ctx),
+            Widget::horiz_separator(ctx, 1.0),
+            Toggle::checkbox(ctx, "pause when gridlock is found", None, pause_on_gridlock),
+        ];
+        if gridlock.is_empty() {
+            col.push(Text::from(Line("No gridlock right now").secondary()).into_widget(ctx));
+        } else {
+            col.push(
+                Text::from(
+                    Line(
+                        "Gridlock: vehicles at each of these intersections are stuck behind \
+                         vehicles at another one, in a cycle, so none of them can move. This \
+                         usually starts when vehicles enter an intersection without room to \
+                         leave it, or when queues from nearby intersections reach each other.",
+                    )
+                    .secondary(),
+                )
+                .wrap_to_pct(ctx, 15)
+                .into_widget(ctx),
+            );
+            for (idx, g) in gridlock.iter().enumerate().take(MAX_GRIDLOCK_SHOWN) {
+                col.push(Widget::row(vec![
+                    format!(
+                        "{} vehicles across {} intersections, stuck for up to {}",
+                        g.agents.len(),
+                        g.intersections.len(),
+                        g.longest_wait()
+                    )
+                    .text_widget(ctx)
+                    .centered_vert(),
+                    ctx.style()
+                        .btn_plain
+                        .icon("system/assets/tools/location.svg")
+                        .build_widget(ctx, format!("zoom to gridlock {}", idx + 1)),
+                ]));
+            }
+            if gridlock.len() > MAX_GRIDLOCK_SHOWN {
+                col.push(
+                    format!("... and {} more", gridlock.len() - MAX_GRIDLOCK_SHOWN)
+                        .text_widget(ctx),
+                );
+            }
+        }
+
+        QueueSpillback {
+            time: app.primary.sim.time(),
+            pause_on_gridlock,
+            gridlock,
+            reported,
+            draw: draw.build(ctx),
+            panel: Panel::new_builder(Widget::col(col))
+                .aligned_pair(PANEL_PLACEMENT)
+                .build(ctx),
+        }
+    }
+
+    fn warp_to(&self, ctx: &mut EventCtx, app: &mut App, idx: usize) -> Transition {
+        let pts: Vec<Pt2D> = self.gridlock[idx]
+            .intersections
+            .iter()
+            .map(|i| app.primary.map.get_i(*i).polygon.center())
+            .collect();
+        // Fit every intersection involved on the screen
+        let bounds = Bounds::from(&pts);
+        let dims = ctx.canvas.get_window_dims();
+        let zoom = (0.5 * (dims.width / bounds.width().max(1.0)))
+            .min(0.5 * (dims.height / bounds.height().max(1.0)))
+            .min(10.0);
+        Transition::Push(Warping::new_state(
+            ctx,
+            Pt2D::center(&pts),
+            Some(zoom),
+            None,
+            &mut app.primary,
+        ))
+    }
+}
@@ -381,9 +381,13 @@ impl CompareThroughput {
 
         let mut colorer = ColorNetwork::new(app);
 
-        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
-            .range(0.0, 2.0)
-            .ignore(0.7, 1.3);
+        let scale = DivergingScale::new(
+            app.cs.better_than_before,
+            Color::WHITE,
+            app.cs.worse_than_before,
+        )
+        .range(0.0, 2.0)
+        .ignore(0.7, 1.3);
 
         for (r, before, after) in before_road.compare(after_road) {
             if let Some(c) = scale.eval((after as f64) / (before as f64)) {
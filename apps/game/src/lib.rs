@@ -68,6 +68,10 @@ struct Args {
     /// Override the monitor's auto-detected scale factor
     #[structopt(long)]
     scale_factor: Option<f64>,
+    /// Write timings for every frame and simulation step to this file, as one JSON object per
+    /// line. No web support.
+    #[structopt(long)]
+    profile_json: Option<String>,
 
     /// Dev mode exposes experimental tools useful for debugging, but that'd likely confuse most
     /// players.
@@ -130,6 +134,7 @@ struct Setup {
     center_camera: Option<String>,
     start_time: Option<Duration>,
     diff_map: Option<String>,
+    profile_json: Option<String>,
     mode: Mode,
 }
 
@@ -175,6 +180,7 @@ fn run(mut settings: Settings) {
         center_camera: args.cam,
         start_time: args.start_time,
         diff_map: args.diff_map,
+        profile_json: args.profile_json,
         mode: if args.tutorial_intro {
             Mode::TutorialIntro
         } else if args.challenges {
@@ -301,6 +307,8 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
         GameplayMode::PlayScenario(_, _, _)
         | GameplayMode::FixTrafficSignals
         | GameplayMode::OptimizeCommute(_, _)
+        | GameplayMode::CarbonBudget(_)
+        | GameplayMode::SchoolRun(_)
         | GameplayMode::Tutorial(_),
     ) = setup.mode
     {
@@ -344,7 +352,7 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
             ctx,
             &mut Timer::throwaway(),
         );
-        let app = App {
+        let mut app = App {
             primary,
             secondary: None,
             store_unedited_map_in_secondary: false,
@@ -353,6 +361,9 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
             per_obj: crate::app::PerObjectActions::new(),
             session: crate::app::SessionState::empty(),
         };
+        if let Some(ref path) = setup.profile_json {
+            app.session.perf.profile_to(path);
+        }
         let map_name = MapName::from_path(&app.primary.current_flags.sim_flags.load).unwrap();
         let states = vec![map_gui::load::MapLoader::new_state(
             ctx,
@@ -381,6 +392,9 @@ fn setup_app(ctx: &mut EventCtx, mut setup: Setup) -> (App, Vec<Box<dyn State<Ap
             per_obj: crate::app::PerObjectActions::new(),
             session: crate::app::SessionState::empty(),
         };
+        if let Some(ref path) = setup.profile_json {
+            app.session.perf.profile_to(path);
+        }
 
         let states = continue_app_setup(ctx, &mut app, title, setup, None);
         (app, states)
@@ -39,6 +39,7 @@ fn enter_state(ctx: &mut EventCtx, app: &mut App, args: Vec<&str>) -> Box<dyn St
             crate::ungap::ExploreMap::new_state(ctx, app, layers)
         }
         "--devtools" => crate::devtools::DevToolsMode::new_state(ctx, app),
+        "--load-session" => crate::sandbox::session::pick_session(ctx),
         _ => unreachable!(),
     }
 }
@@ -0,0 +1,152 @@
+use abstutil::prettyprint_usize;
+use map_model::RoadID;
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+/// How many drivers illegally used each road's bus lanes
+pub struct BusLaneViolations {
+    panel: Panel,
+}
+
+impl BusLaneViolations {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let now = app.primary.sim.time();
+        let analytics = app.primary.sim.get_analytics();
+
+        // Sort descending by count, then by name
+        let mut roads: Vec<(isize, String, RoadID)> = Vec::new();
+        for r in map.all_roads() {
+            if r.lanes.iter().any(|l| l.is_bus()) {
+                roads.push((
+                    -(analytics.bus_lane_violations.total_for(r.id) as isize),
+                    r.get_name(app.opts.language.as_ref()),
+                    r.id,
+                ));
+            }
+        }
+        roads.sort();
+        let total: usize = roads.iter().map(|(cnt, _, _)| -cnt as usize).sum();
+
+        let mut summary = Text::from(format!(
+            "{} cars used a bus lane illegally",
+            prettyprint_usize(total)
+        ));
+        if app.has_prebaked().is_some() {
+            let before: usize = roads
+                .iter()
+                .map(|(_, _, r)| {
+                    app.prebaked()
+                        .bus_lane_violations
+                        .total_for_by_time(*r, now)
+                })
+                .sum();
+            summary.add_line(
+                Line(format!(
+                    "{} by this time before your changes",
+                    prettyprint_usize(before)
+                ))
+                .secondary(),
+            );
+        }
+        summary.add_line(
+            Line(
+                "Drivers only break the rules if the simulation was started with \
+                 --bus-lane-violation-pct, or if a road overrides it. Place enforcement cameras \
+                 by editing a road.",
+            )
+            .secondary(),
+        );
+
+        let col = vec![
+            DashTab::BusLaneViolations.picker(ctx, app),
+            Line(format!("{} roads with bus lanes", roads.len()))
+                .small_heading()
+                .into_widget(ctx),
+            summary.wrap_to_pct(ctx, 50).into_widget(ctx),
+            Widget::col(
+                roads
+                    .into_iter()
+                    .map(|(cnt, name, r)| {
+                        Widget::row(vec![
+                            ctx.style()
+                                .btn_outline
+                                .text(name)
+                                .build_widget(ctx, r.to_string()),
+                            format!(
+                                "{} violations ({})",
+                                prettyprint_usize(-cnt as usize),
+                                map.get_r(r).bus_lane_enforcement
+                            )
+                            .text_widget(ctx)
+                            .centered_vert(),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ];
+
+        Box::new(BusLaneViolations {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for BusLaneViolations {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let r = match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if let Some(x) = x.strip_prefix("Road #") {
+                    RoadID(x.parse::<usize>().unwrap())
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::BusLaneViolations.transition(ctx, app, &self.panel) {
+                    return t;
+                } else {
+                    return Transition::Keep;
+                }
+            }
+            _ => {
+                return Transition::Keep;
+            }
+        };
+        let l = app
+            .primary
+            .map
+            .get_r(r)
+            .lanes
+            .iter()
+            .find(|l| l.is_bus())
+            .unwrap()
+            .id;
+
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                let mut actions = sandbox.contextual_actions();
+                sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                    ctx,
+                    app,
+                    Tab::LaneInfo(l),
+                    &mut actions,
+                )
+            })),
+        ])
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,185 @@
+use abstutil::{prettyprint_usize, Counter};
+use map_model::RoadID;
+use synthpop::MAX_CAR_OCCUPANCY;
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+/// How many people drive alone, and how many people HOV lanes move
+pub struct Carpools {
+    panel: Panel,
+}
+
+impl Carpools {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let now = app.primary.sim.time();
+        let analytics = app.primary.sim.get_analytics();
+
+        let mut summary = Text::new();
+        match analytics.sov_share(now) {
+            Some(share) => {
+                summary.add_line(format!(
+                    "{:.1}% of people in cars on finished trips were alone",
+                    share * 100.0
+                ));
+            }
+            None => {
+                summary.add_line("Nobody has finished driving anywhere yet");
+            }
+        }
+        if app.has_prebaked().is_some() {
+            if let Some(before) = app.prebaked().sov_share(now) {
+                summary.add_line(
+                    Line(format!(
+                        "{:.1}% by this time before your changes",
+                        before * 100.0
+                    ))
+                    .secondary(),
+                );
+            }
+        }
+        let mut per_occupancy = Counter::new();
+        for (time, occupancy) in &analytics.car_occupancy {
+            if *time > now {
+                break;
+            }
+            per_occupancy.inc((*occupancy).min(MAX_CAR_OCCUPANCY));
+        }
+        summary.add_line(
+            (1..=MAX_CAR_OCCUPANCY)
+                .map(|n| {
+                    format!(
+                        "{} trips with {} {}",
+                        prettyprint_usize(per_occupancy.get(n)),
+                        n,
+                        if n == 1 { "person" } else { "people" }
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        let counts = app.primary.sim.num_commuters_vehicles();
+        summary.add_line(format!(
+            "Driving right now: {} single-occupancy vehicles and {} carpools",
+            prettyprint_usize(counts.sov_drivers),
+            prettyprint_usize(counts.carpools)
+        ));
+        summary.add_line(
+            Line(
+                "Give cars more than one person by modifying the scenario's traffic patterns. \
+                 Open a road's bus lanes to carpools by editing the road.",
+            )
+            .secondary(),
+        );
+
+        // Sort by people moved, descending, then by name
+        let mut roads: Vec<(isize, String, RoadID)> = Vec::new();
+        for r in map.all_roads() {
+            if r.hov_min_occupancy.is_some() {
+                roads.push((
+                    -(analytics.road_person_thruput.total_for(r.id) as isize),
+                    r.get_name(app.opts.language.as_ref()),
+                    r.id,
+                ));
+            }
+        }
+        roads.sort();
+
+        let col = vec![
+            DashTab::Carpools.picker(ctx, app),
+            Line(format!("{} roads with HOV lanes", roads.len()))
+                .small_heading()
+                .into_widget(ctx),
+            summary.wrap_to_pct(ctx, 50).into_widget(ctx),
+            Widget::col(
+                roads
+                    .into_iter()
+                    .map(|(cnt, name, r)| {
+                        let mut details = format!(
+                            "{} people moved (open to cars with {}+ people)",
+                            prettyprint_usize(-cnt as usize),
+                            map.get_r(r).hov_min_occupancy.unwrap()
+                        );
+                        if app.has_prebaked().is_some() {
+                            details = format!(
+                                "{}, {} by this time before your changes",
+                                details,
+                                prettyprint_usize(
+                                    app.prebaked().road_person_thruput.total_for_by_time(r, now)
+                                )
+                            );
+                        }
+                        Widget::row(vec![
+                            ctx.style()
+                                .btn_outline
+                                .text(name)
+                                .build_widget(ctx, r.to_string()),
+                            details.text_widget(ctx).centered_vert(),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ];
+
+        Box::new(Carpools {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for Carpools {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let r = match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if let Some(x) = x.strip_prefix("Road #") {
+                    RoadID(x.parse::<usize>().unwrap())
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::Carpools.transition(ctx, app, &self.panel) {
+                    return t;
+                } else {
+                    return Transition::Keep;
+                }
+            }
+            _ => {
+                return Transition::Keep;
+            }
+        };
+        let road = app.primary.map.get_r(r);
+        let l = road
+            .lanes
+            .iter()
+            .find(|l| l.is_bus())
+            .unwrap_or(&road.lanes[0])
+            .id;
+
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                let mut actions = sandbox.contextual_actions();
+                sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                    ctx,
+                    app,
+                    Tab::LaneInfo(l),
+                    &mut actions,
+                )
+            })),
+        ])
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use abstutil::prettyprint_usize;
+use geom::{Duration, Time};
+use sim::{PersonID, TripID};
+use synthpop::{AgeBand, Demographics, IncomeBand};
+use widgetry::tools::PopupMsg;
+use widgetry::{Choice, EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget};
+
+use crate::app::{App, Transition};
+use crate::sandbox::dashboards::DashTab;
+
+/// Split the changes in travel time and access caused by the current proposal by demographic
+/// group, to see who benefits and who loses out.
+pub struct Equity {
+    panel: Panel,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Grouping {
+    Age,
+    Income,
+    CarOwnership,
+    Disability,
+}
+
+impl Grouping {
+    fn describe(self) -> &'static str {
+        match self {
+            Grouping::Age => "age",
+            Grouping::Income => "income",
+            Grouping::CarOwnership => "car ownership",
+            Grouping::Disability => "disability",
+        }
+    }
+
+    /// All of the groups, in display order. People without demographic data, or without income
+    /// data, go in a separate group.
+    fn groups(self) -> Vec<String> {
+        let mut groups = match self {
+            Grouping::Age => AgeBand::all().into_iter().map(|x| x.to_string()).collect(),
+            Grouping::Income => IncomeBand::all()
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            Grouping::CarOwnership => vec!["owns a car".to_string(), "no car".to_string()],
+            Grouping::Disability => vec![
+                "has a disability".to_string(),
+                "no disability".to_string(),
+            ],
+        };
+        groups.push("unknown".to_string());
+        groups
+    }
+
+    fn group(self, demographics: Option<&Demographics>) -> String {
+        let d = match demographics {
+            Some(d) => d,
+            None => {
+                return "unknown".to_string();
+            }
+        };
+        match self {
+            Grouping::Age => d.age.to_string(),
+            Grouping::Income => d
+                .income
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            Grouping::CarOwnership if d.owns_car => "owns a car".to_string(),
+            Grouping::CarOwnership => "no car".to_string(),
+            Grouping::Disability if d.has_disability => "has a disability".to_string(),
+            Grouping::Disability => "no disability".to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct GroupStats {
+    people: BTreeSet<PersonID>,
+    /// Trips that finished both before and after the proposal
+    trips: usize,
+    total_before: Duration,
+    total_after: Duration,
+    time_saved: Duration,
+    time_lost: Duration,
+    /// Trips that finished before the proposal, but were cancelled afterwards. This is a crude
+    /// measure of lost accessibility.
+    trips_lost: usize,
+}
+
+impl Equity {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, grouping: Grouping) -> Box<dyn State<App>> {
+        let stats = calculate(app, grouping);
+
+        let mut col = vec![
+            DashTab::Equity.picker(ctx, app),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "How do the changes from \"{}\" affect different groups?",
+                    app.primary.map.get_edits().edits_name
+                )),
+                Line(
+                    "Only trips finished both before and after the changes are compared. People \
+                     without demographic data are shown as unknown.",
+                )
+                .secondary(),
+            ])
+            .into_widget(ctx),
+            Widget::row(vec![
+                "Group by:".text_widget(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "grouping",
+                    grouping,
+                    vec![
+                        Choice::new(Grouping::Age.describe(), Grouping::Age),
+                        Choice::new(Grouping::Income.describe(), Grouping::Income),
+                        Choice::new(Grouping::CarOwnership.describe(), Grouping::CarOwnership),
+                        Choice::new(Grouping::Disability.describe(), Grouping::Disability),
+                    ],
+                ),
+            ]),
+        ];
+
+        for group in grouping.groups() {
+            let s = match stats.get(&group) {
+                Some(s) => s,
+                None => {
+                    continue;
+                }
+            };
+            let mut txt = Text::from(Line(&group).small_heading());
+            txt.add_line(format!(
+                "{} people, {} trips",
+                prettyprint_usize(s.people.len()),
+                prettyprint_usize(s.trips)
+            ));
+            if s.trips > 0 {
+                let n = s.trips as f64;
+                txt.add_line(format!(
+                    "Average trip time: {} before, {} after",
+                    s.total_before / n,
+                    s.total_after / n
+                ));
+            }
+            txt.add_line(format!(
+                "Total time saved: {}, total time lost: {}",
+                s.time_saved, s.time_lost
+            ));
+            txt.add_line(format!(
+                "Trips no longer possible: {}",
+                prettyprint_usize(s.trips_lost)
+            ));
+            col.push(txt.into_widget(ctx).section(ctx));
+        }
+
+        col.push(ctx.style().btn_plain.text("Export to CSV").build_def(ctx));
+
+        Box::new(Equity {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for Equity {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "Export to CSV" => {
+                    return Transition::Push(match export_equity(app) {
+                        Ok(path) => PopupMsg::new_state(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => {
+                            PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                        }
+                    });
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::Equity.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+
+                Transition::Replace(Equity::new_state(
+                    ctx,
+                    app,
+                    self.panel.dropdown_value("grouping"),
+                ))
+            }
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
+
+fn calculate(app: &App, grouping: Grouping) -> BTreeMap<String, GroupStats> {
+    let sim = &app.primary.sim;
+    let mut stats: BTreeMap<String, GroupStats> = BTreeMap::new();
+
+    for (id, before, after, _) in sim
+        .get_analytics()
+        .both_finished_trips(sim.time(), app.prebaked())
+    {
+        if let Some(s) = stats_for_trip(&mut stats, app, grouping, id) {
+            s.trips += 1;
+            s.total_before += before;
+            s.total_after += after;
+            if after < before {
+                s.time_saved += before - after;
+            } else {
+                s.time_lost += after - before;
+            }
+        }
+    }
+
+    for id in trips_lost(app, sim.time()) {
+        if let Some(s) = stats_for_trip(&mut stats, app, grouping, id) {
+            s.trips_lost += 1;
+        }
+    }
+
+    stats
+}
+
+fn stats_for_trip<'a>(
+    stats: &'a mut BTreeMap<String, GroupStats>,
+    app: &App,
+    grouping: Grouping,
+    id: TripID,
+) -> Option<&'a mut GroupStats> {
+    let person = app.primary.sim.trip_to_person(id)?;
+    let demographics = app.primary.sim.get_person(person).demographics.as_ref();
+    let s = stats.entry(grouping.group(demographics)).or_default();
+    s.people.insert(person);
+    Some(s)
+}
+
+/// Trips that finished in the baseline, but were cancelled with the current proposal
+fn trips_lost(app: &App, now: Time) -> Vec<TripID> {
+    let mut finished_before = BTreeSet::new();
+    for (t, id, _, maybe_dt) in &app.prebaked().finished_trips {
+        if *t > now {
+            break;
+        }
+        if maybe_dt.is_some() {
+            finished_before.insert(*id);
+        }
+    }
+
+    let mut lost = Vec::new();
+    for (t, id, _, maybe_dt) in &app.primary.sim.get_analytics().finished_trips {
+        if *t > now {
+            break;
+        }
+        if maybe_dt.is_none() && finished_before.contains(id) {
+            lost.push(*id);
+        }
+    }
+    lost
+}
+
+fn export_equity(app: &App) -> Result<String> {
+    let path = format!(
+        "equity_{}_{}.csv",
+        app.primary.map.get_name().as_filename(),
+        app.primary.sim.time().as_filename()
+    );
+    let mut out = String::new();
+    writeln!(
+        out,
+        "grouping,group,people,trips,total_seconds_before,total_seconds_after,seconds_saved,\
+         seconds_lost,trips_lost"
+    )?;
+    for grouping in [
+        Grouping::Age,
+        Grouping::Income,
+        Grouping::CarOwnership,
+        Grouping::Disability,
+    ] {
+        let stats = calculate(app, grouping);
+        for group in grouping.groups() {
+            if let Some(s) = stats.get(&group) {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{},{},{}",
+                    grouping.describe(),
+                    group,
+                    s.people.len(),
+                    s.trips,
+                    s.total_before.inner_seconds(),
+                    s.total_after.inner_seconds(),
+                    s.time_saved.inner_seconds(),
+                    s.time_lost.inner_seconds(),
+                    s.trips_lost
+                )?;
+            }
+        }
+    }
+    abstio::write_file(path, out)
+}
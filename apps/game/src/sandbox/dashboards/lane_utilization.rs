@@ -0,0 +1,181 @@
+use abstutil::prettyprint_usize;
+use geom::Percent;
+use map_model::{LaneID, PathConstraints};
+use sim::LaneChangeReason;
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+// Ignore roads with too little traffic to say anything about
+const MIN_VEHICLES: usize = 50;
+
+/// Compares how evenly vehicles spread across the lanes of each multi-lane road to the Highway
+/// Capacity Manual's default lane utilization factors.
+pub struct LaneUtilization {
+    panel: Panel,
+}
+
+impl LaneUtilization {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let analytics = app.primary.sim.get_analytics();
+
+        // Sort descending by how much busier the busiest lane is than expected
+        let mut rows: Vec<(isize, String, Vec<usize>, usize, LaneID)> = Vec::new();
+        for r in map.all_roads() {
+            for dr in r.id.both_directions() {
+                let lanes = dr.lanes(PathConstraints::Car, map);
+                if lanes.len() < 2 {
+                    continue;
+                }
+                let counts: Vec<usize> =
+                    lanes.iter().map(|l| analytics.lane_usage.get(*l)).collect();
+                let total: usize = counts.iter().sum();
+                if total < MIN_VEHICLES {
+                    continue;
+                }
+                let busiest = *counts.iter().max().unwrap() as f64 / total as f64;
+                let excess = busiest - expected_busiest_share(lanes.len());
+                rows.push((
+                    -(excess * 1000.0) as isize,
+                    format!(
+                        "{} ({} lanes)",
+                        r.get_name(app.opts.language.as_ref()),
+                        lanes.len()
+                    ),
+                    counts,
+                    total,
+                    lanes[0],
+                ));
+            }
+        }
+        rows.sort_by_key(|(excess, name, _, _, _)| (*excess, name.clone()));
+
+        let mut summary = Text::from(format!(
+            "{} roads with at least {} vehicles using 2+ lanes",
+            prettyprint_usize(rows.len()),
+            MIN_VEHICLES
+        ));
+        for (reason, label) in [
+            (
+                LaneChangeReason::Mandatory,
+                "to get around something stopped",
+            ),
+            (LaneChangeReason::Discretionary, "to use a shorter queue"),
+            (LaneChangeReason::Overtaking, "to pass a slower vehicle"),
+        ] {
+            let cnt: usize = analytics
+                .lane_changes
+                .borrow()
+                .iter()
+                .filter(|((_, r), _)| *r == reason)
+                .map(|(_, cnt)| *cnt)
+                .sum();
+            summary.add_line(format!("{} lane changes {}", prettyprint_usize(cnt), label));
+        }
+        summary.add_line(
+            Line(
+                "The expected share of the busiest lane is 52.5% for 2 lanes and 36.7% for 3 \
+                 lanes, from the HCM's default lane utilization factors. Much higher means \
+                 vehicles are piling into one lane.",
+            )
+            .secondary(),
+        );
+
+        let col = vec![
+            DashTab::LaneUtilization.picker(ctx, app),
+            Line("Lane utilization").small_heading().into_widget(ctx),
+            summary.wrap_to_pct(ctx, 50).into_widget(ctx),
+            Widget::col(
+                rows.into_iter()
+                    .map(|(_, name, counts, total, l)| {
+                        let shares: Vec<String> = counts
+                            .iter()
+                            .map(|cnt| Percent::of(*cnt, total).to_string())
+                            .collect();
+                        Widget::row(vec![
+                            ctx.style()
+                                .btn_outline
+                                .text(name)
+                                .build_widget(ctx, l.to_string()),
+                            format!(
+                                "{} vehicles: {} (expected busiest lane: {})",
+                                prettyprint_usize(total),
+                                shares.join(" / "),
+                                Percent::int(
+                                    (expected_busiest_share(counts.len()) * 100.0).round() as usize
+                                )
+                            )
+                            .text_widget(ctx)
+                            .centered_vert(),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ];
+
+        Box::new(LaneUtilization {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+/// From the HCM's default lane utilization factors: 0.952 for 2 lanes, 0.908 for 3 or more. The
+/// busiest lane carries 1 / (lanes * factor) of the traffic.
+fn expected_busiest_share(num_lanes: usize) -> f64 {
+    let factor = match num_lanes {
+        0 | 1 => 1.0,
+        2 => 0.952,
+        _ => 0.908,
+    };
+    1.0 / (num_lanes.max(1) as f64 * factor)
+}
+
+impl State<App> for LaneUtilization {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let l = match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if let Some(x) = x.strip_prefix("Lane #") {
+                    LaneID::decode_u32(x.parse::<u32>().unwrap())
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::LaneUtilization.transition(ctx, app, &self.panel) {
+                    return t;
+                } else {
+                    return Transition::Keep;
+                }
+            }
+            _ => {
+                return Transition::Keep;
+            }
+        };
+
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                let mut actions = sandbox.contextual_actions();
+                sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                    ctx,
+                    app,
+                    Tab::LaneInfo(l),
+                    &mut actions,
+                )
+            })),
+        ])
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
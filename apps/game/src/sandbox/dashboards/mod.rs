@@ -1,4 +1,5 @@
 pub use commuter::CommuterPatterns;
+pub use tournament::Tournament;
 pub use traffic_signals::TrafficSignalDemand;
 
 use widgetry::{Choice, EventCtx, Image, Line, Panel, State, TextExt, Widget};
@@ -6,17 +7,29 @@ use widgetry::{Choice, EventCtx, Image, Line, Panel, State, TextExt, Widget};
 use crate::app::App;
 use crate::app::Transition;
 
+mod bus_lanes;
+mod carpools;
 mod commuter;
+mod equity;
 mod generic_trip_table;
+mod lane_utilization;
 mod misc;
 mod mode_shift;
+mod multiple_runs;
+mod park_and_ride;
 mod parking_overhead;
 mod risks;
+mod segments;
 mod selector;
+mod speed_enforcement;
+mod tolls;
+mod tournament;
 mod traffic_signals;
+mod transit_priority;
 mod travel_times;
 mod trip_problems;
 mod trip_table;
+mod winners_losers;
 
 // Oh the dashboards melted, but we still had the radio
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -30,10 +43,34 @@ pub enum DashTab {
     CommuterPatterns,
     TrafficSignals,
     ModeShift,
+    BusLaneViolations,
+    SpeedEnforcement,
+    Tolls,
+    Carpools,
+    LaneUtilization,
+    TransitSignalPriority,
+    ParkAndRide,
+    Equity,
+    WinnersAndLosers,
+    MultipleRuns,
+    ProposalTournament,
 }
 
 impl DashTab {
     pub fn picker(self, ctx: &EventCtx, app: &App) -> Widget {
+        Widget::row(vec![
+            Image::from_path("system/assets/meters/trip_histogram.svg").into_widget(ctx),
+            Line("Data").big_heading_plain().into_widget(ctx),
+            Widget::dropdown(ctx, "tab", self, DashTab::choices(app)),
+            format!("By {}", app.primary.sim.time().ampm_tostring())
+                .text_widget(ctx)
+                .centered_vert(),
+            ctx.style().btn_close_widget(ctx),
+        ])
+    }
+
+    /// Every dashboard that makes sense for the current simulation
+    pub fn choices(app: &App) -> Vec<Choice<DashTab>> {
         let mut choices = vec![
             Choice::new("Trip Table", DashTab::TripTable),
             Choice::new("Travel Times", DashTab::TravelTimes),
@@ -44,20 +81,24 @@ impl DashTab {
             Choice::new("Commuter Patterns", DashTab::CommuterPatterns),
             Choice::new("Traffic Signal Demand", DashTab::TrafficSignals),
             Choice::new("Mode shift (experimental)", DashTab::ModeShift),
+            Choice::new("Bus Lane Violations", DashTab::BusLaneViolations),
+            Choice::new("Speed Enforcement", DashTab::SpeedEnforcement),
+            Choice::new("Tolls", DashTab::Tolls),
+            Choice::new("Carpools", DashTab::Carpools),
+            Choice::new("Lane Utilization", DashTab::LaneUtilization),
+            Choice::new("Transit Signal Priority", DashTab::TransitSignalPriority),
+            Choice::new("Park and Ride", DashTab::ParkAndRide),
+            Choice::new("Multiple Runs", DashTab::MultipleRuns),
+            Choice::new("Compare Proposals", DashTab::ProposalTournament),
         ];
         if app.has_prebaked().is_none() {
             choices.remove(1);
             choices.remove(1);
+        } else {
+            choices.push(Choice::new("Equity (experimental)", DashTab::Equity));
+            choices.push(Choice::new("Winners and Losers", DashTab::WinnersAndLosers));
         }
-        Widget::row(vec![
-            Image::from_path("system/assets/meters/trip_histogram.svg").into_widget(ctx),
-            Line("Data").big_heading_plain().into_widget(ctx),
-            Widget::dropdown(ctx, "tab", self, choices),
-            format!("By {}", app.primary.sim.time().ampm_tostring())
-                .text_widget(ctx)
-                .centered_vert(),
-            ctx.style().btn_close_widget(ctx),
-        ])
+        choices
     }
 
     pub fn launch(self, ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
@@ -73,6 +114,21 @@ impl DashTab {
             DashTab::CommuterPatterns => CommuterPatterns::new_state(ctx, app),
             DashTab::TrafficSignals => TrafficSignalDemand::new_state(ctx, app),
             DashTab::ModeShift => mode_shift::ModeShift::new_state(ctx, app),
+            DashTab::BusLaneViolations => bus_lanes::BusLaneViolations::new_state(ctx, app),
+            DashTab::SpeedEnforcement => {
+                speed_enforcement::SpeedEnforcementCompliance::new_state(ctx, app)
+            }
+            DashTab::Tolls => tolls::TollRevenue::new_state(ctx, app),
+            DashTab::Carpools => carpools::Carpools::new_state(ctx, app),
+            DashTab::LaneUtilization => lane_utilization::LaneUtilization::new_state(ctx, app),
+            DashTab::TransitSignalPriority => {
+                transit_priority::TransitSignalPriority::new_state(ctx, app)
+            }
+            DashTab::ParkAndRide => park_and_ride::ParkAndRideUsage::new_state(ctx, app),
+            DashTab::Equity => equity::Equity::new_state(ctx, app, equity::Grouping::Age),
+            DashTab::WinnersAndLosers => winners_losers::WinnersAndLosers::new_state(ctx, app),
+            DashTab::MultipleRuns => multiple_runs::MultipleRuns::new_state(ctx, app),
+            DashTab::ProposalTournament => tournament::ProposalTournament::new_state(ctx, app),
         }
     }
 
@@ -1,11 +1,11 @@
 use std::collections::HashSet;
 
 use abstutil::Counter;
-use geom::{Distance, Duration};
+use geom::{Distance, Duration, Time};
 use map_gui::tools::ColorNetwork;
 use map_model::PathStepV2;
 use sim::TripID;
-use synthpop::{TripEndpoint, TripMode};
+use synthpop::{TripEndpoint, TripMode, TripPurpose};
 use widgetry::table::{Col, Filter, Table};
 use widgetry::{
     Drawable, EventCtx, Filler, GeomBatch, GfxCtx, Line, Outcome, Panel, Spinner, State, Text,
@@ -14,6 +14,7 @@ use widgetry::{
 
 use crate::app::{App, Transition};
 use crate::sandbox::dashboards::generic_trip_table::{open_trip_transition, preview_trip};
+use crate::sandbox::dashboards::segments::TripSegment;
 use crate::sandbox::dashboards::DashTab;
 
 pub struct ModeShift {
@@ -103,6 +104,8 @@ impl State<App> for ModeShift {
 
 struct Entry {
     trip: TripID,
+    purpose: TripPurpose,
+    departure: Time,
     estimated_driving_time: Duration,
     // Only when we prebaked data?
     //actual_driving_time: Duration,
@@ -117,6 +120,7 @@ struct Filters {
     max_biking_time: Duration,
     max_distance: Distance,
     max_elevation_gain: Distance,
+    segment: TripSegment,
 }
 
 fn produce_raw_data(ctx: &mut EventCtx, app: &App) -> Vec<Entry> {
@@ -151,6 +155,8 @@ fn produce_raw_data(ctx: &mut EventCtx, app: &App) -> Vec<Entry> {
                         biking_path.get_total_elevation_change(map);
                     Some(Entry {
                         trip: id,
+                        purpose: info.purpose,
+                        departure: info.departure,
                         estimated_driving_time: driving_path.estimate_duration(map, None),
                         estimated_biking_time: biking_path
                             .estimate_duration(map, Some(map_model::MAX_BIKE_SPEED)),
@@ -180,63 +186,69 @@ fn make_table(ctx: &mut EventCtx, app: &App) -> Table<App, Entry, Filters> {
             max_biking_time: Duration::minutes(30),
             max_distance: Distance::miles(10.0),
             max_elevation_gain: Distance::feet(30.0),
+            segment: TripSegment::everything(),
         },
         to_controls: Box::new(|ctx, _, state| {
-            Widget::row(vec![
-                Widget::row(vec![
-                    "Max driving time".text_widget(ctx).centered_vert(),
-                    Spinner::widget(
-                        ctx,
-                        "max_driving_time",
-                        (Duration::ZERO, Duration::hours(12)),
-                        state.max_driving_time,
-                        Duration::minutes(1),
-                    ),
-                ]),
-                Widget::row(vec![
-                    "Max biking time".text_widget(ctx).centered_vert(),
-                    Spinner::widget(
-                        ctx,
-                        "max_biking_time",
-                        (Duration::ZERO, Duration::hours(12)),
-                        state.max_biking_time,
-                        Duration::minutes(1),
-                    ),
-                ]),
-                Widget::row(vec![
-                    "Max distance".text_widget(ctx).centered_vert(),
-                    Spinner::widget(
-                        ctx,
-                        "max_distance",
-                        (Distance::ZERO, Distance::miles(20.0)),
-                        state.max_distance,
-                        Distance::miles(0.1),
-                    ),
-                ]),
+            Widget::col(vec![
                 Widget::row(vec![
-                    "Max elevation gain".text_widget(ctx).centered_vert(),
-                    Spinner::widget(
-                        ctx,
-                        "max_elevation_gain",
-                        (Distance::ZERO, Distance::feet(500.0)),
-                        state.max_elevation_gain,
-                        Distance::feet(10.0),
-                    ),
-                ]),
+                    Widget::row(vec![
+                        "Max driving time".text_widget(ctx).centered_vert(),
+                        Spinner::widget(
+                            ctx,
+                            "max_driving_time",
+                            (Duration::ZERO, Duration::hours(12)),
+                            state.max_driving_time,
+                            Duration::minutes(1),
+                        ),
+                    ]),
+                    Widget::row(vec![
+                        "Max biking time".text_widget(ctx).centered_vert(),
+                        Spinner::widget(
+                            ctx,
+                            "max_biking_time",
+                            (Duration::ZERO, Duration::hours(12)),
+                            state.max_biking_time,
+                            Duration::minutes(1),
+                        ),
+                    ]),
+                    Widget::row(vec![
+                        "Max distance".text_widget(ctx).centered_vert(),
+                        Spinner::widget(
+                            ctx,
+                            "max_distance",
+                            (Distance::ZERO, Distance::miles(20.0)),
+                            state.max_distance,
+                            Distance::miles(0.1),
+                        ),
+                    ]),
+                    Widget::row(vec![
+                        "Max elevation gain".text_widget(ctx).centered_vert(),
+                        Spinner::widget(
+                            ctx,
+                            "max_elevation_gain",
+                            (Distance::ZERO, Distance::feet(500.0)),
+                            state.max_elevation_gain,
+                            Distance::feet(10.0),
+                        ),
+                    ]),
+                ])
+                .evenly_spaced(),
+                state.segment.to_controls(ctx),
             ])
-            .evenly_spaced()
         }),
         from_controls: Box::new(|panel| Filters {
             max_driving_time: panel.spinner("max_driving_time"),
             max_biking_time: panel.spinner("max_biking_time"),
             max_distance: panel.spinner("max_distance"),
             max_elevation_gain: panel.spinner("max_elevation_gain"),
+            segment: TripSegment::from_controls(panel),
         }),
         apply: Box::new(|state, x, _| {
             x.estimated_driving_time <= state.max_driving_time
                 && x.estimated_biking_time <= state.max_biking_time
                 && x.distance <= state.max_distance
                 && x.total_elevation_gain <= state.max_elevation_gain
+                && state.segment.matches(x.purpose, x.departure)
         }),
     };
 
@@ -248,6 +260,7 @@ fn make_table(ctx: &mut EventCtx, app: &App) -> Table<App, Entry, Filters> {
         filter,
     );
     table.static_col("Trip ID", Box::new(|x| x.trip.0.to_string()));
+    table.static_col("Purpose", Box::new(|x| x.purpose.to_string()));
     table.column(
         "Estimated driving time",
         Box::new(|ctx, app, x| {
@@ -0,0 +1,371 @@
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Circle, Distance, Duration, Polygon, Pt2D};
+use sim::{simulate_headless, Estimate, MultiRunResults, RunKPIs};
+use synthpop::TripMode;
+use widgetry::{
+    Color, EventCtx, GeomBatch, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, UpdateType,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::JobProgress;
+use crate::sandbox::dashboards::DashTab;
+
+const BAR_WIDTH: f64 = 300.0;
+const BAR_HEIGHT: f64 = 20.0;
+const JOB_NAME: &str = "Simulate many runs";
+
+/// Compares KPIs across many simulation runs with different RNG seeds, so differences within the
+/// noise between runs aren't over-interpreted.
+pub struct MultipleRuns {
+    panel: Panel,
+    /// Rebuild when a background job finishes
+    jobs_version: usize,
+}
+
+impl MultipleRuns {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let baseline = load_baseline(app);
+        let current = current_results(app);
+        let this_run = RunKPIs::new(
+            &app.primary.sim,
+            app.primary.current_flags.sim_flags.rng_seed,
+        );
+
+        let mut col = vec![DashTab::MultipleRuns.picker(ctx, app)];
+        let scenario_name = match app.primary.scenario {
+            Some(ref scenario) => scenario.scenario_name.clone(),
+            None => {
+                col.push("There's no scenario loaded to simulate".text_widget(ctx));
+                return Box::new(MultipleRuns {
+                    panel: Panel::new_builder(Widget::col(col)).build(ctx),
+                    jobs_version: app.session.jobs.version(),
+                });
+            }
+        };
+
+        let mut txt = Text::from(Line(format!("Scenario: {}", scenario_name)).small_heading());
+        txt.add_line(Line(
+            "Every run uses a different random seed. Bars show the 95% confidence interval of the \
+             average; the dot is the average, and the tick is this run so far.",
+        ));
+        txt.add_line(Line(
+            "If two bars overlap, the difference between them might just be noise.",
+        ));
+        match baseline {
+            Some(ref results) => {
+                txt.add_line(
+                    Line(format!(
+                        "Baseline: {} runs without edits",
+                        results.runs.len()
+                    ))
+                    .fg(app.cs.before_changes),
+                );
+            }
+            None => {
+                txt.add_line(
+                    Line(
+                        "No baseline runs without edits. Generate them with abcli multi-run \
+                         --scenario <path>",
+                    )
+                    .secondary(),
+                );
+            }
+        }
+        if let Some(ref results) = current {
+            txt.add_line(
+                Line(format!(
+                    "Current: {} runs with \"{}\"",
+                    results.runs.len(),
+                    results.edits_name
+                ))
+                .fg(app.cs.after_changes),
+            );
+        }
+        col.push(txt.wrap_to_pct(ctx, 60).into_widget(ctx));
+
+        let mut rows = vec![kpi_row(
+            ctx,
+            app,
+            "Finished trips",
+            baseline.as_ref().and_then(|r| r.finished_trips()),
+            current.as_ref().and_then(|r| r.finished_trips()),
+            this_run.finished_trips as f64,
+            false,
+        )];
+        rows.push(kpi_row(
+            ctx,
+            app,
+            "Cancelled trips",
+            baseline.as_ref().and_then(|r| r.cancelled_trips()),
+            current.as_ref().and_then(|r| r.cancelled_trips()),
+            this_run.cancelled_trips as f64,
+            false,
+        ));
+        rows.push(kpi_row(
+            ctx,
+            app,
+            "Average trip time",
+            baseline.as_ref().and_then(|r| r.mean_trip_duration()),
+            current.as_ref().and_then(|r| r.mean_trip_duration()),
+            this_run.mean_trip_duration_seconds(),
+            true,
+        ));
+        for mode in TripMode::all() {
+            let this_run = match this_run.mean_trip_duration_seconds.get(&mode) {
+                Some(x) => *x,
+                None => continue,
+            };
+            rows.push(kpi_row(
+                ctx,
+                app,
+                &format!("Average trip time {}", mode.ongoing_verb()),
+                baseline
+                    .as_ref()
+                    .and_then(|r| r.mean_trip_duration_for_mode(mode)),
+                current
+                    .as_ref()
+                    .and_then(|r| r.mean_trip_duration_for_mode(mode)),
+                this_run,
+                true,
+            ));
+        }
+        col.push(Widget::col(rows).section(ctx));
+
+        if app.session.jobs.is_running(JOB_NAME) {
+            col.push(
+                "Simulating in the background. You can close this dashboard and come back."
+                    .text_widget(ctx),
+            );
+        } else {
+            col.push(Widget::row(vec![
+                "Simulate the full day with the current edits:"
+                    .text_widget(ctx)
+                    .centered_vert(),
+                ctx.style().btn_outline.text("5 runs").build_def(ctx),
+                ctx.style().btn_outline.text("10 runs").build_def(ctx),
+                ctx.style().btn_outline.text("20 runs").build_def(ctx),
+            ]));
+        }
+
+        Box::new(MultipleRuns {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+            jobs_version: app.session.jobs.version(),
+        })
+    }
+}
+
+impl State<App> for MultipleRuns {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if x == "close" {
+                    return Transition::Pop;
+                }
+                let num_runs = x.strip_suffix(" runs").unwrap().parse::<u64>().unwrap();
+                start_runs(app, num_runs);
+                Transition::Replace(MultipleRuns::new_state(ctx, app))
+            }
+            Outcome::Changed(_) => DashTab::MultipleRuns
+                .transition(ctx, app, &self.panel)
+                .unwrap_or(Transition::Keep),
+            _ => {
+                if self.jobs_version != app.session.jobs.version() {
+                    return Transition::Replace(MultipleRuns::new_state(ctx, app));
+                }
+                if app.session.jobs.is_running(JOB_NAME) {
+                    // Notice when the runs finish, even if the player doesn't do anything
+                    ctx.request_update(UpdateType::Game);
+                }
+                Transition::Keep
+            }
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+/// Simulate the current scenario and edits once per seed, one after another, in the background.
+/// The results are only kept if the map and edits haven't changed since.
+fn start_runs(app: &mut App, num_runs: u64) {
+    let map = app.primary.map.clone();
+    let scenario = app.primary.scenario.clone().unwrap();
+    let map_name = map.get_name().clone();
+    let edits_key = map.get_edits_change_key();
+    app.session.jobs.spawn(
+        JOB_NAME,
+        move |progress: &JobProgress| {
+            let mut runs = Vec::new();
+            for rng_seed in 1..=num_runs {
+                progress.set(
+                    format!("Run {} of {}", rng_seed, num_runs),
+                    ((rng_seed - 1) as f64) / (num_runs as f64),
+                );
+                progress.check_cancelled()?;
+                let sim =
+                    simulate_headless(&map, &scenario, rng_seed, None, &mut Timer::throwaway());
+                runs.push(RunKPIs::new(&sim, rng_seed));
+            }
+            Ok(MultiRunResults {
+                map_name: map.get_name().clone(),
+                scenario_name: scenario.scenario_name.clone(),
+                edits_name: map.get_edits().edits_name.clone(),
+                runs,
+            })
+        },
+        move |app, results| {
+            if app.primary.map.get_name() != &map_name
+                || app.primary.map.get_edits_change_key() != edits_key
+            {
+                return format!(
+                    "Finished {} runs, but the map changed since, so the results were discarded",
+                    results.runs.len()
+                );
+            }
+            let msg = format!(
+                "Finished {} runs of {}. See the multiple runs dashboard.",
+                results.runs.len(),
+                results.scenario_name
+            );
+            app.primary.multirun = Some((edits_key, results));
+            msg
+        },
+    );
+}
+
+/// Baseline runs without edits, prebaked for the current scenario
+pub fn load_baseline(app: &App) -> Option<MultiRunResults> {
+    let scenario = app.primary.scenario.as_ref()?;
+    abstio::maybe_read_json::<MultiRunResults>(
+        abstio::path_prebaked_multirun(app.primary.map.get_name(), &scenario.scenario_name),
+        &mut Timer::throwaway(),
+    )
+    .ok()
+}
+
+/// Runs calculated in this session, if they match the current edits and scenario
+pub fn current_results(app: &App) -> Option<MultiRunResults> {
+    let (key, results) = app.primary.multirun.as_ref()?;
+    let scenario = app.primary.scenario.as_ref()?;
+    if *key == app.primary.map.get_edits_change_key()
+        && results.scenario_name == scenario.scenario_name
+    {
+        Some(results.clone())
+    } else {
+        None
+    }
+}
+
+/// If there are multiple runs both with and without edits, describe whether the change in average
+/// trip time is bigger than the noise between runs.
+pub fn describe_trip_time_change(app: &App) -> Option<Text> {
+    let before = load_baseline(app)?.mean_trip_duration()?;
+    let after = current_results(app)?.mean_trip_duration()?;
+    let mut txt = Text::from(format!(
+        "Across many runs, average trip time went from {} to {}",
+        describe_estimate(before, true),
+        describe_estimate(after, true)
+    ));
+    txt.add_line(
+        Line(if before.overlaps(&after) {
+            "The confidence intervals overlap, so this might just be noise"
+        } else {
+            "The confidence intervals don't overlap, so this is likely a real difference"
+        })
+        .secondary(),
+    );
+    Some(txt)
+}
+
+fn kpi_row(
+    ctx: &mut EventCtx,
+    app: &App,
+    label: &str,
+    baseline: Option<Estimate>,
+    current: Option<Estimate>,
+    this_run: f64,
+    is_duration: bool,
+) -> Widget {
+    // All bars in one row share a scale
+    let mut min = this_run;
+    let mut max = this_run;
+    for est in baseline.iter().chain(current.iter()) {
+        min = min.min(est.low());
+        max = max.max(est.high());
+    }
+    let padding = ((max - min) * 0.1).max(1.0);
+    let range = (min - padding, max + padding);
+
+    let mut bars = Vec::new();
+    let mut txt = Text::new();
+    if let Some(est) = baseline {
+        bars.push(error_bar(ctx, est, range, this_run, app.cs.before_changes));
+        txt.add_line(Line(describe_estimate(est, is_duration)).fg(app.cs.before_changes));
+    }
+    if let Some(est) = current {
+        bars.push(error_bar(ctx, est, range, this_run, app.cs.after_changes));
+        txt.add_line(Line(describe_estimate(est, is_duration)).fg(app.cs.after_changes));
+    }
+    txt.add_line(
+        Line(format!(
+            "This run so far: {}",
+            describe_value(this_run, is_duration)
+        ))
+        .secondary(),
+    );
+
+    Widget::row(vec![
+        Line(label).into_widget(ctx).centered_vert(),
+        Widget::col(bars).centered_vert(),
+        txt.into_widget(ctx),
+    ])
+    .evenly_spaced()
+}
+
+fn error_bar(
+    ctx: &EventCtx,
+    est: Estimate,
+    range: (f64, f64),
+    this_run: f64,
+    color: Color,
+) -> Widget {
+    let x = |value: f64| BAR_WIDTH * (value - range.0) / (range.1 - range.0);
+    let mid = BAR_HEIGHT / 2.0;
+
+    let mut batch = GeomBatch::new();
+    batch.push(Color::CLEAR, Polygon::rectangle(BAR_WIDTH, BAR_HEIGHT));
+    batch.push(
+        color.alpha(0.5),
+        Polygon::rectangle((x(est.high()) - x(est.low())).max(1.0), BAR_HEIGHT / 2.0)
+            .translate(x(est.low()), BAR_HEIGHT / 4.0),
+    );
+    batch.push(
+        color,
+        Circle::new(Pt2D::new(x(est.mean), mid), Distance::meters(4.0)).to_polygon(),
+    );
+    batch.push(
+        Color::BLACK,
+        Polygon::rectangle(2.0, BAR_HEIGHT).translate(x(this_run) - 1.0, 0.0),
+    );
+    batch.into_widget(ctx)
+}
+
+fn describe_estimate(est: Estimate, is_duration: bool) -> String {
+    format!(
+        "{} ± {}",
+        describe_value(est.mean, is_duration),
+        describe_value(est.half_width, is_duration)
+    )
+}
+
+fn describe_value(value: f64, is_duration: bool) -> String {
+    if is_duration {
+        Duration::seconds(value).to_rounded_string(1)
+    } else {
+        prettyprint_usize(value.round() as usize)
+    }
+}
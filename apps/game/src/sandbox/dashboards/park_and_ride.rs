@@ -0,0 +1,156 @@
+use abstutil::prettyprint_usize;
+use map_model::{BuildingID, OffstreetParking};
+use synthpop::TripMode;
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, Widget};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+/// How full each park-and-ride facility is, and how the people using it continue their trip
+pub struct ParkAndRideUsage {
+    panel: Panel,
+}
+
+impl ParkAndRideUsage {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let sim = &app.primary.sim;
+        let analytics = sim.get_analytics();
+        let config = sim.get_park_and_ride();
+
+        let mut total_outbound = 0;
+        let mut total_returning = 0;
+        let mut rows = Vec::new();
+        for b in &config.facilities {
+            let bldg = map.get_b(*b);
+            let name = match bldg.parking {
+                OffstreetParking::PublicGarage(ref name, _) => name.clone(),
+                OffstreetParking::Private(_, _) => b.to_string(),
+            };
+            let capacity = bldg.num_parking_spots();
+            let occupied = capacity - sim.get_free_offstreet_spots(*b).len();
+
+            let records = analytics.park_and_ride.get(b).cloned().unwrap_or_default();
+            let count = |mode: TripMode, returning: bool| {
+                records
+                    .iter()
+                    .filter(|(_, m, r)| *m == mode && *r == returning)
+                    .count()
+            };
+            let returning = records.iter().filter(|(_, _, r)| *r).count();
+            total_outbound += records.len() - returning;
+            total_returning += returning;
+
+            let mut txt = Text::from(format!(
+                "{} / {} spots filled now",
+                prettyprint_usize(occupied),
+                prettyprint_usize(capacity)
+            ));
+            txt.add_line(format!(
+                "{} people parked here and took transit, {} continued by bike",
+                prettyprint_usize(count(TripMode::Transit, false)),
+                prettyprint_usize(count(TripMode::Bike, false))
+            ));
+            txt.add_line(
+                Line(format!(
+                    "{} came back for their car",
+                    prettyprint_usize(returning)
+                ))
+                .secondary(),
+            );
+            rows.push(Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text(name)
+                    .build_widget(ctx, b.to_string()),
+                txt.into_widget(ctx).centered_vert(),
+            ]));
+        }
+
+        let mut summary = Text::new();
+        if config.is_empty() {
+            summary.add_line(
+                "This scenario has no park-and-ride facilities. Designate some by modifying the \
+                 scenario.",
+            );
+        } else {
+            summary.add_line(format!(
+                "{} trips used park-and-ride, and {} trips returned to a car left there",
+                prettyprint_usize(total_outbound),
+                prettyprint_usize(total_returning)
+            ));
+            summary.add_line(
+                Line(format!(
+                    "Drivers consider parking and riding for trips over {}, if a facility with \
+                     free spots is on the way. Otherwise they drive the whole way.",
+                    config.min_trip_distance
+                ))
+                .secondary(),
+            );
+        }
+
+        let col = vec![
+            DashTab::ParkAndRide.picker(ctx, app),
+            Line(format!(
+                "{} park-and-ride facilities",
+                config.facilities.len()
+            ))
+            .small_heading()
+            .into_widget(ctx),
+            summary.wrap_to_pct(ctx, 50).into_widget(ctx),
+            Widget::col(rows),
+        ];
+
+        Box::new(ParkAndRideUsage {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for ParkAndRideUsage {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let b = match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if let Some(x) = x.strip_prefix("Building #") {
+                    BuildingID(x.parse::<usize>().unwrap())
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::ParkAndRide.transition(ctx, app, &self.panel) {
+                    return t;
+                } else {
+                    return Transition::Keep;
+                }
+            }
+            _ => {
+                return Transition::Keep;
+            }
+        };
+
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                let mut actions = sandbox.contextual_actions();
+                sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                    ctx,
+                    app,
+                    Tab::BldgInfo(b),
+                    &mut actions,
+                )
+            })),
+        ])
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeSet;
+
+use geom::{Duration, Time};
+use sim::TripID;
+use synthpop::TripPurpose;
+use widgetry::{EventCtx, Panel, Percent, Spinner, TextExt, Toggle, Widget};
+
+use crate::app::App;
+
+/// Narrows a dashboard down to one travel market: trips made for some purposes, departing during
+/// some window of the day. Changes to the map often help one market and hurt another, so it's
+/// useful to attribute them correctly.
+#[derive(Clone)]
+pub struct TripSegment {
+    pub purposes: BTreeSet<TripPurpose>,
+    /// Hours after midnight
+    pub depart_after: usize,
+    /// Hours after midnight. 24 means there's no upper bound, to include trips departing past
+    /// midnight.
+    pub depart_before: usize,
+}
+
+impl TripSegment {
+    pub fn everything() -> TripSegment {
+        TripSegment {
+            purposes: TripPurpose::all().into_iter().collect(),
+            depart_after: 0,
+            depart_before: 24,
+        }
+    }
+
+    pub fn to_controls(&self, ctx: &EventCtx) -> Widget {
+        let mut purposes = Vec::new();
+        for p in TripPurpose::all() {
+            purposes.push(
+                Toggle::checkbox(ctx, &p.to_string(), None, self.purposes.contains(&p))
+                    .margin_right(24),
+            );
+        }
+        Widget::col(vec![
+            Widget::custom_row(purposes).flex_wrap(ctx, Percent::int(80)),
+            Widget::row(vec![
+                "Departing between hour".text_widget(ctx).centered_vert(),
+                Spinner::widget(ctx, "depart after", (0, 23), self.depart_after, 1),
+                "and".text_widget(ctx).centered_vert(),
+                Spinner::widget(ctx, "depart before", (1, 24), self.depart_before, 1),
+            ]),
+        ])
+    }
+
+    /// Only call this if `to_controls` was used to build the panel.
+    pub fn from_controls(panel: &Panel) -> TripSegment {
+        let mut purposes = BTreeSet::new();
+        for p in TripPurpose::all() {
+            if panel.is_checked(&p.to_string()) {
+                purposes.insert(p);
+            }
+        }
+        TripSegment {
+            purposes,
+            depart_after: panel.spinner("depart after"),
+            depart_before: panel.spinner("depart before"),
+        }
+    }
+
+    pub fn matches(&self, purpose: TripPurpose, departure: Time) -> bool {
+        if !self.purposes.contains(&purpose) {
+            return false;
+        }
+        if departure < Time::START_OF_DAY + Duration::hours(self.depart_after) {
+            return false;
+        }
+        if self.depart_before < 24
+            && departure >= Time::START_OF_DAY + Duration::hours(self.depart_before)
+        {
+            return false;
+        }
+        true
+    }
+
+    /// Check a trip that's finished or been cancelled in the live simulation, using what
+    /// `Analytics` recorded about it.
+    pub fn matches_finished_trip(&self, app: &App, id: TripID) -> bool {
+        let analytics = app.primary.sim.get_analytics();
+        match (
+            analytics.trip_purposes.get(&id),
+            analytics.started_trips.get(&id),
+        ) {
+            (Some(purpose), Some(departure)) => self.matches(*purpose, *departure),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use abstutil::prettyprint_usize;
+use map_model::{RoadID, SpeedEnforcement};
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+/// How well each speed enforcement device deters drivers who'd otherwise speed
+pub struct SpeedEnforcementCompliance {
+    panel: Panel,
+}
+
+impl SpeedEnforcementCompliance {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let analytics = app.primary.sim.get_analytics();
+
+        // Sort by the number of speeders passing, descending, then by name
+        let mut roads: Vec<(isize, String, RoadID, usize)> = Vec::new();
+        for r in map.all_roads() {
+            if r.speed_enforcement == SpeedEnforcement::None {
+                continue;
+            }
+            let complied = analytics.speed_enforcement.get((r.id, true));
+            let speeding = analytics.speed_enforcement.get((r.id, false));
+            roads.push((
+                -((complied + speeding) as isize),
+                r.get_name(app.opts.language.as_ref()),
+                r.id,
+                complied,
+            ));
+        }
+        roads.sort();
+        let total: usize = roads.iter().map(|(cnt, _, _, _)| -cnt as usize).sum();
+        let total_complied: usize = roads.iter().map(|(_, _, _, x)| *x).sum();
+
+        let mut summary = Text::from(format!(
+            "{} of {} speeding drivers passing enforcement obeyed the limit",
+            prettyprint_usize(total_complied),
+            prettyprint_usize(total)
+        ));
+        summary.add_line(
+            Line(
+                "Drivers only speed if the simulation was started with --speeding-pct. Place \
+                 speed cameras by editing a road, or compare with traffic calming, which slows \
+                 down everybody.",
+            )
+            .secondary(),
+        );
+
+        let col = vec![
+            DashTab::SpeedEnforcement.picker(ctx, app),
+            Line(format!("{} roads with speed enforcement", roads.len()))
+                .small_heading()
+                .into_widget(ctx),
+            summary.wrap_to_pct(ctx, 50).into_widget(ctx),
+            Widget::col(
+                roads
+                    .into_iter()
+                    .map(|(cnt, name, r, complied)| {
+                        let cnt = -cnt as usize;
+                        Widget::row(vec![
+                            ctx.style()
+                                .btn_outline
+                                .text(name)
+                                .build_widget(ctx, r.to_string()),
+                            format!(
+                                "{} of {} speeders complied{} ({})",
+                                prettyprint_usize(complied),
+                                prettyprint_usize(cnt),
+                                if cnt == 0 {
+                                    String::new()
+                                } else {
+                                    format!(", {}%", complied * 100 / cnt)
+                                },
+                                map.get_r(r).speed_enforcement
+                            )
+                            .text_widget(ctx)
+                            .centered_vert(),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ];
+
+        Box::new(SpeedEnforcementCompliance {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for SpeedEnforcementCompliance {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let r = match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if let Some(x) = x.strip_prefix("Road #") {
+                    RoadID(x.parse::<usize>().unwrap())
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::SpeedEnforcement.transition(ctx, app, &self.panel) {
+                    return t;
+                } else {
+                    return Transition::Keep;
+                }
+            }
+            _ => {
+                return Transition::Keep;
+            }
+        };
+        let road = app.primary.map.get_r(r);
+        let l = road
+            .lanes
+            .iter()
+            .find(|l| l.is_driving())
+            .unwrap_or(&road.lanes[0])
+            .id;
+
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                let mut actions = sandbox.contextual_actions();
+                sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                    ctx,
+                    app,
+                    Tab::LaneInfo(l),
+                    &mut actions,
+                )
+            })),
+        ])
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
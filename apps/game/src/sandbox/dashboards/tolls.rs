@@ -0,0 +1,151 @@
+use abstutil::prettyprint_usize;
+use map_model::{format_cents, RoadID, RoadPricing};
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+/// How much each toll road and priced lane has collected, and how many drivers went around tolls
+pub struct TollRevenue {
+    panel: Panel,
+}
+
+impl TollRevenue {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let analytics = app.primary.sim.get_analytics();
+
+        // Sort by revenue, descending, then by name
+        let mut roads: Vec<(isize, String, RoadID)> = Vec::new();
+        for r in map.all_roads() {
+            if r.pricing == RoadPricing::Free {
+                continue;
+            }
+            roads.push((
+                -(analytics.toll_revenue.get(r.id) as isize),
+                r.get_name(app.opts.language.as_ref()),
+                r.id,
+            ));
+        }
+        roads.sort();
+
+        let total_revenue = analytics.toll_revenue.sum();
+        let total_payments = analytics.toll_payments.sum();
+        let total_diversions = analytics.toll_diversions.sum();
+        let mut summary = Text::from(format!(
+            "{} collected from {} payments. {} drivers avoided a toll road.",
+            format_cents(total_revenue as u32),
+            prettyprint_usize(total_payments),
+            prettyprint_usize(total_diversions)
+        ));
+        let mut explain = "Drivers compare the best route with tolls to the best free one, \
+                           counting each toll as time by how much they value theirs. Drivers pay \
+                           for priced lanes when the queue they'd skip is long enough. Set tolls \
+                           by editing a road."
+            .to_string();
+        if let Some(ref scenario) = app.primary.scenario {
+            explain = format!(
+                "{} In this scenario, {}.",
+                explain,
+                scenario.value_of_time.describe()
+            );
+        }
+        summary.add_line(Line(explain).secondary());
+
+        let col = vec![
+            DashTab::Tolls.picker(ctx, app),
+            Line(format!("{} roads with tolls or priced lanes", roads.len()))
+                .small_heading()
+                .into_widget(ctx),
+            summary.wrap_to_pct(ctx, 50).into_widget(ctx),
+            Widget::col(
+                roads
+                    .into_iter()
+                    .map(|(_, name, r)| {
+                        let road = map.get_r(r);
+                        let mut details = format!(
+                            "{} from {} payments",
+                            format_cents(analytics.toll_revenue.get(r) as u32),
+                            prettyprint_usize(analytics.toll_payments.get(r))
+                        );
+                        if let RoadPricing::Toll(_) = road.pricing {
+                            details = format!(
+                                "{}, {} drivers went around",
+                                details,
+                                prettyprint_usize(analytics.toll_diversions.get(r))
+                            );
+                        }
+                        Widget::row(vec![
+                            ctx.style()
+                                .btn_outline
+                                .text(name)
+                                .build_widget(ctx, r.to_string()),
+                            format!("{} ({})", details, road.pricing)
+                                .text_widget(ctx)
+                                .centered_vert(),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ];
+
+        Box::new(TollRevenue {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for TollRevenue {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let r = match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if let Some(x) = x.strip_prefix("Road #") {
+                    RoadID(x.parse::<usize>().unwrap())
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::Tolls.transition(ctx, app, &self.panel) {
+                    return t;
+                } else {
+                    return Transition::Keep;
+                }
+            }
+            _ => {
+                return Transition::Keep;
+            }
+        };
+        let road = app.primary.map.get_r(r);
+        let l = road
+            .lanes
+            .iter()
+            .find(|l| l.is_driving())
+            .unwrap_or(&road.lanes[0])
+            .id;
+
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                let mut actions = sandbox.contextual_actions();
+                sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                    ctx,
+                    app,
+                    Tab::LaneInfo(l),
+                    &mut actions,
+                )
+            })),
+        ])
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
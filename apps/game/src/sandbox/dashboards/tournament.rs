@@ -0,0 +1,473 @@
+//! Rank several saved proposals against each other. Each one is simulated headless in the
+//! background, against the same scenario and RNG seed, so the only difference between the runs is
+//! the proposal itself.
+
+use std::collections::BTreeSet;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+use abstutil::Timer;
+use map_model::{EditCmd, Map, MapEdits, RoadID};
+use sim::{all_metrics, simulate_headless, Analytics, Better, BoundaryRoadVolume, Metric, Sim};
+use synthpop::Scenario;
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    EventCtx, GfxCtx, Line, Outcome, Panel, Slider, State, Text, TextExt, Toggle, UpdateType,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::dashboards::DashTab;
+
+/// Each proposal takes a full day of simulation, so don't let people queue up too many
+const MAX_PROPOSALS: usize = 5;
+
+/// Proposals simulated so far, and the weights used to rank them. Lives in `PerMap`, so the runs
+/// continue while the dashboard is closed.
+pub struct Tournament {
+    scenario_name: String,
+    /// The first entry is always the map without any edits
+    entries: Vec<String>,
+    results: Vec<Option<ProposalResults>>,
+    /// Indexed by `tournament_metrics`
+    weights: Vec<f64>,
+    receiver: Receiver<(usize, ProposalResults)>,
+    crashed: bool,
+}
+
+/// One value per `tournament_metrics`
+#[derive(Clone)]
+struct ProposalResults {
+    values: Vec<f64>,
+}
+
+/// Every proposal is scored on these. The last one measures traffic on unedited roads touching
+/// the proposal's edits; its value is the change compared to the map without edits. Just to get
+/// names and units, pass in no roads.
+fn tournament_metrics(next_to_edits: BTreeSet<RoadID>) -> Vec<Box<dyn Metric + Send>> {
+    let mut metrics = all_metrics();
+    metrics.push(Box::new(BoundaryRoadVolume {
+        roads: next_to_edits,
+    }));
+    metrics
+}
+
+impl Tournament {
+    fn start(app: &App, proposals: Vec<String>) -> Result<Tournament, String> {
+        let scenario = app
+            .primary
+            .scenario
+            .clone()
+            .ok_or_else(|| "There's no scenario loaded to simulate".to_string())?;
+        let map = &app.primary.map;
+        let mut edits = Vec::new();
+        for name in &proposals {
+            edits.push(
+                MapEdits::load_from_file(
+                    map,
+                    abstio::path_edits(map.get_name(), name),
+                    &mut Timer::throwaway(),
+                )
+                .map_err(|err| format!("Couldn't load {}: {}", name, err))?,
+            );
+        }
+
+        let (sender, receiver) = channel();
+        let map = map.clone();
+        let rng_seed = app.primary.current_flags.sim_flags.rng_seed;
+        let scenario_name = scenario.scenario_name.clone();
+        std::thread::spawn(move || simulate_all(map, scenario, rng_seed, edits, sender));
+
+        let mut entries = vec!["No edits".to_string()];
+        entries.extend(proposals);
+        Ok(Tournament {
+            scenario_name,
+            results: vec![None; entries.len()],
+            entries,
+            weights: vec![1.0; tournament_metrics(BTreeSet::new()).len()],
+            receiver,
+            crashed: false,
+        })
+    }
+
+    /// Returns true if anything changed
+    fn poll(&mut self) -> bool {
+        let mut changed = false;
+        loop {
+            match self.receiver.try_recv() {
+                Ok((idx, results)) => {
+                    self.results[idx] = Some(results);
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => {
+                    return changed;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    if !self.is_done() && !self.crashed {
+                        self.crashed = true;
+                        changed = true;
+                    }
+                    return changed;
+                }
+            }
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.crashed || self.results.iter().all(|r| r.is_some())
+    }
+
+    /// Finished entries, best first, with a score from 0 to 100. Each metric is scaled between the
+    /// best and worst entry, then weighted.
+    fn ranking(&self) -> Vec<(usize, f64)> {
+        let finished: Vec<(usize, &ProposalResults)> = self
+            .results
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, r)| r.as_ref().map(|r| (idx, r)))
+            .collect();
+        let total_weight: f64 = self.weights.iter().sum();
+
+        let mut scores = vec![0.0; finished.len()];
+        for (idx, (metric, weight)) in tournament_metrics(BTreeSet::new())
+            .into_iter()
+            .zip(self.weights.iter())
+            .enumerate()
+        {
+            let values: Vec<f64> = finished.iter().map(|(_, r)| r.values[idx]).collect();
+            let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            for (score, value) in scores.iter_mut().zip(values) {
+                // If everyone ties, everyone gets the full score
+                let scaled = if max > min {
+                    match metric.better() {
+                        Better::Lower => (max - value) / (max - min),
+                        Better::Higher => (value - min) / (max - min),
+                    }
+                } else {
+                    1.0
+                };
+                *score += weight * scaled;
+            }
+        }
+
+        let mut ranking: Vec<(usize, f64)> = finished
+            .into_iter()
+            .zip(scores)
+            .map(|((idx, _), score)| {
+                if total_weight > 0.0 {
+                    (idx, 100.0 * score / total_weight)
+                } else {
+                    (idx, 0.0)
+                }
+            })
+            .collect();
+        ranking.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        ranking
+    }
+}
+
+// Runs in a background thread. The first run is without any edits.
+fn simulate_all(
+    mut map: Map,
+    scenario: Scenario,
+    rng_seed: u64,
+    proposals: Vec<MapEdits>,
+    sender: Sender<(usize, ProposalResults)>,
+) {
+    let mut timer = Timer::throwaway();
+    let mut baseline: Option<Analytics> = None;
+    let mut all_edits = vec![map.new_edits()];
+    all_edits.extend(proposals);
+    for (idx, edits) in all_edits.into_iter().enumerate() {
+        let next_to_edits = roads_next_to_edits(&map, &edits);
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+
+        let sim = simulate_headless(&map, &scenario, rng_seed, None, &mut timer);
+        let before = baseline.get_or_insert_with(|| sim.get_analytics().clone());
+        let results = ProposalResults::new(&map, &sim, before, next_to_edits);
+        // If the dashboard started a different tournament, nobody's listening anymore
+        if sender.send((idx, results)).is_err() {
+            return;
+        }
+    }
+}
+
+impl ProposalResults {
+    fn new(
+        map: &Map,
+        sim: &Sim,
+        baseline: &Analytics,
+        next_to_edits: BTreeSet<RoadID>,
+    ) -> ProposalResults {
+        let now = sim.time();
+        let metrics = tournament_metrics(next_to_edits);
+        let mut values: Vec<f64> = metrics
+            .iter()
+            .map(|metric| metric.measure(sim.get_analytics(), map, now).unwrap_or(0.0))
+            .collect();
+        // Only the change in traffic next to the edits matters
+        *values.last_mut().unwrap() -= metrics
+            .last()
+            .unwrap()
+            .measure(baseline, map, now)
+            .unwrap_or(0.0);
+        ProposalResults { values }
+    }
+}
+
+/// Unedited roads sharing an intersection with anything the proposal changes. This is where
+/// traffic displaced by the proposal shows up first.
+fn roads_next_to_edits(map: &Map, edits: &MapEdits) -> BTreeSet<RoadID> {
+    let mut edited_roads = BTreeSet::new();
+    let mut intersections = BTreeSet::new();
+    for cmd in &edits.commands {
+        match cmd {
+            EditCmd::ChangeRoad { r, .. } => {
+                edited_roads.insert(*r);
+                intersections.insert(map.get_r(*r).src_i);
+                intersections.insert(map.get_r(*r).dst_i);
+            }
+            EditCmd::ChangeIntersection { i, .. } => {
+                intersections.insert(*i);
+            }
+            EditCmd::ChangeRouteSchedule { .. }
+            | EditCmd::ChangeBikeParking { .. }
+            | EditCmd::ChangeTransitStop { .. }
+            | EditCmd::ChangeRouteStops { .. }
+            | EditCmd::ChangeRouteAlignment { .. } => {}
+        }
+    }
+    intersections
+        .into_iter()
+        .flat_map(|i| map.get_i(i).roads.iter().cloned())
+        .filter(|r| !edited_roads.contains(r))
+        .collect()
+}
+
+pub struct ProposalTournament {
+    panel: Panel,
+    proposals: Vec<String>,
+}
+
+impl ProposalTournament {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let proposals =
+            abstio::list_all_objects(abstio::path_all_edits(app.primary.map.get_name()));
+        let mut col = vec![
+            DashTab::ProposalTournament.picker(ctx, app),
+            Line("Compare proposals").small_heading().into_widget(ctx),
+        ];
+
+        match app.primary.tournament {
+            _ if cfg!(target_arch = "wasm32") => {
+                col.push(
+                    "Comparing proposals is only supported in the desktop version".text_widget(ctx),
+                );
+            }
+            _ if app.primary.scenario.is_none() => {
+                col.push("There's no scenario loaded to simulate".text_widget(ctx));
+            }
+            Some(ref tournament)
+                if Some(&tournament.scenario_name)
+                    == app.primary.scenario.as_ref().map(|s| &s.scenario_name) =>
+            {
+                col.extend(results_widgets(ctx, app, tournament));
+            }
+            _ => {
+                col.push(
+                    Text::from(format!(
+                        "Pick up to {} of your saved proposals. Each one is simulated for a full \
+                         day in the background, along with the map without any edits, using the \
+                         same scenario and random seed. This takes a while, but you can close \
+                         this dashboard and come back.",
+                        MAX_PROPOSALS
+                    ))
+                    .wrap_to_pct(ctx, 50)
+                    .into_widget(ctx),
+                );
+                if proposals.is_empty() {
+                    col.push("You don't have any saved proposals yet".text_widget(ctx));
+                }
+                for name in &proposals {
+                    col.push(Toggle::custom_checkbox(
+                        ctx,
+                        &format!("proposal {}", name),
+                        vec![Line(name)],
+                        None,
+                        false,
+                    ));
+                }
+                col.push(
+                    ctx.style()
+                        .btn_solid_primary
+                        .text("Simulate and compare")
+                        .disabled(proposals.is_empty())
+                        .build_def(ctx),
+                );
+            }
+        }
+
+        Box::new(ProposalTournament {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+            proposals,
+        })
+    }
+}
+
+fn results_widgets(ctx: &mut EventCtx, app: &App, tournament: &Tournament) -> Vec<Widget> {
+    let mut col = Vec::new();
+    let num_done = tournament.results.iter().filter(|r| r.is_some()).count();
+    if tournament.crashed {
+        col.push(
+            Line("The simulation crashed before finishing. Check the logs for details.")
+                .fg(ctx.style().text_destructive_color)
+                .into_widget(ctx),
+        );
+    } else if num_done < tournament.entries.len() {
+        col.push(
+            format!(
+                "Simulated {} of {} so far...",
+                num_done,
+                tournament.entries.len()
+            )
+            .text_widget(ctx),
+        );
+    }
+
+    col.push("How much does each metric matter?".text_widget(ctx));
+    for (metric, weight) in tournament_metrics(BTreeSet::new())
+        .into_iter()
+        .zip(tournament.weights.iter())
+    {
+        col.push(Widget::row(vec![
+            metric.name().text_widget(ctx).centered_vert(),
+            Slider::area(
+                ctx,
+                0.15 * ctx.canvas.window_width,
+                *weight,
+                &format!("weight for {}", metric.name()),
+            )
+            .align_right(),
+        ]));
+    }
+    col.push(Widget::horiz_separator(ctx, 1.0));
+    col.push(ranking_widget(ctx, app, tournament));
+    col.push(
+        ctx.style()
+            .btn_outline
+            .text("Start over")
+            .disabled(!tournament.is_done())
+            .build_def(ctx),
+    );
+    col
+}
+
+fn ranking_widget(ctx: &mut EventCtx, app: &App, tournament: &Tournament) -> Widget {
+    let metrics = tournament_metrics(BTreeSet::new());
+    let mut header = vec![
+        Line("Rank").into_widget(ctx),
+        Line("Proposal").into_widget(ctx),
+    ];
+    for metric in &metrics {
+        header.push(Line(metric.name()).into_widget(ctx));
+    }
+    header.push(Line("Score").into_widget(ctx));
+
+    let mut rows = vec![Widget::row(header).evenly_spaced()];
+    for (rank, (idx, score)) in tournament.ranking().into_iter().enumerate() {
+        let results = tournament.results[idx].as_ref().unwrap();
+        let mut row = vec![
+            format!("{}", rank + 1).text_widget(ctx),
+            tournament.entries[idx].as_str().text_widget(ctx),
+        ];
+        for (metric_idx, (metric, value)) in metrics.iter().zip(results.values.iter()).enumerate() {
+            let mut txt = metric.unit().describe(*value, &app.opts.units);
+            // The last metric is a change from the map without edits
+            if metric_idx == metrics.len() - 1 && *value > 0.0 {
+                txt = format!("+{}", txt);
+            }
+            row.push(txt.text_widget(ctx));
+        }
+        row.push(format!("{:.0}", score).text_widget(ctx));
+        rows.push(Widget::row(row).evenly_spaced());
+    }
+    Widget::col(rows).section(ctx).named("ranking")
+}
+
+impl State<App> for ProposalTournament {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(ref mut tournament) = app.primary.tournament {
+            if tournament.poll() {
+                return Transition::Replace(ProposalTournament::new_state(ctx, app));
+            }
+            if !tournament.is_done() {
+                // Keep checking for results, even without input
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "Simulate and compare" => {
+                    let picked: Vec<String> = self
+                        .proposals
+                        .iter()
+                        .filter(|name| self.panel.is_checked(&format!("proposal {}", name)))
+                        .cloned()
+                        .collect();
+                    if picked.is_empty() || picked.len() > MAX_PROPOSALS {
+                        return Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Error",
+                            vec![format!("Pick between 1 and {} proposals", MAX_PROPOSALS)],
+                        ));
+                    }
+                    match Tournament::start(app, picked) {
+                        Ok(tournament) => {
+                            app.primary.tournament = Some(tournament);
+                            Transition::Replace(ProposalTournament::new_state(ctx, app))
+                        }
+                        Err(err) => Transition::Push(PopupMsg::new_state(ctx, "Error", vec![err])),
+                    }
+                }
+                "Start over" => {
+                    app.primary.tournament = None;
+                    Transition::Replace(ProposalTournament::new_state(ctx, app))
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed(x) => {
+                if x.starts_with("weight for ") {
+                    if let Some(ref mut tournament) = app.primary.tournament {
+                        for (metric, weight) in tournament_metrics(BTreeSet::new())
+                            .into_iter()
+                            .zip(tournament.weights.iter_mut())
+                        {
+                            *weight = self
+                                .panel
+                                .slider(&format!("weight for {}", metric.name()))
+                                .get_percent();
+                        }
+                    }
+                    if let Some(ref tournament) = app.primary.tournament {
+                        let ranking = ranking_widget(ctx, app, tournament);
+                        self.panel.replace(ctx, "ranking", ranking);
+                    }
+                    return Transition::Keep;
+                }
+                DashTab::ProposalTournament
+                    .transition(ctx, app, &self.panel)
+                    .unwrap_or(Transition::Keep)
+            }
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,200 @@
+use abstutil::prettyprint_usize;
+use geom::{Duration, Time};
+use map_model::IntersectionID;
+use sim::{AgentType, Analytics};
+use widgetry::{EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget};
+
+use crate::app::{App, Transition};
+use crate::info::Tab;
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+/// For each traffic signal with transit priority, how much it helped buses and what it cost
+/// everybody else
+pub struct TransitSignalPriority {
+    panel: Panel,
+}
+
+impl TransitSignalPriority {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let now = app.primary.sim.time();
+        let analytics = app.primary.sim.get_analytics();
+        let prebaked = app.has_prebaked().map(|_| app.prebaked());
+
+        let signals: Vec<IntersectionID> = map
+            .all_intersections()
+            .iter()
+            .filter(|i| {
+                map.maybe_get_traffic_signal(i.id)
+                    .map(|ts| ts.transit_priority.is_some())
+                    .unwrap_or(false)
+            })
+            .map(|i| i.id)
+            .collect();
+
+        let mut total_activations = 0;
+        let mut total_saved = Duration::ZERO;
+        let mut total_cost = Duration::ZERO;
+        let mut rows = Vec::new();
+        for i in &signals {
+            let records = analytics
+                .transit_signal_priority
+                .get(i)
+                .cloned()
+                .unwrap_or_default();
+            let saved: Duration = records.iter().map(|(_, _, saved, _)| *saved).sum();
+            let cost: Duration = records.iter().map(|(_, _, _, cost)| *cost).sum();
+            total_activations += records.len();
+            total_saved += saved;
+            total_cost += cost;
+
+            let mut txt = Text::from(format!(
+                "{} buses helped, saving them about {}. Other movements lost about {}.",
+                prettyprint_usize(records.len()),
+                saved,
+                cost
+            ));
+            txt.add_line(Line(describe_delays(analytics, prebaked, *i, now)).secondary());
+            rows.push(Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text(map.get_i(*i).name(app.opts.language.as_ref(), map))
+                    .build_widget(ctx, i.to_string()),
+                txt.into_widget(ctx).centered_vert(),
+            ]));
+        }
+
+        let mut summary = Text::from(format!(
+            "Late buses got priority {} times, saving about {} in total",
+            prettyprint_usize(total_activations),
+            total_saved
+        ));
+        summary.add_line(
+            Line(format!(
+                "Other movements lost about {} of green time",
+                total_cost
+            ))
+            .secondary(),
+        );
+        summary.add_line(
+            Line(
+                "Savings are estimates. Delays measured at each intersection are compared to \
+                 before your changes, when available. Turn on transit signal priority in the \
+                 traffic signal editor.",
+            )
+            .secondary(),
+        );
+
+        let col = vec![
+            DashTab::TransitSignalPriority.picker(ctx, app),
+            Line(format!(
+                "{} traffic signals with transit priority",
+                signals.len()
+            ))
+            .small_heading()
+            .into_widget(ctx),
+            summary.wrap_to_pct(ctx, 50).into_widget(ctx),
+            Widget::col(rows),
+        ];
+
+        Box::new(TransitSignalPriority {
+            panel: Panel::new_builder(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for TransitSignalPriority {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let i = match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if let Some(x) = x.strip_prefix("Intersection #") {
+                    IntersectionID(x.parse::<usize>().unwrap())
+                } else if x == "close" {
+                    return Transition::Pop;
+                } else {
+                    unreachable!()
+                }
+            }
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::TransitSignalPriority.transition(ctx, app, &self.panel) {
+                    return t;
+                } else {
+                    return Transition::Keep;
+                }
+            }
+            _ => {
+                return Transition::Keep;
+            }
+        };
+
+        Transition::Multi(vec![
+            Transition::Pop,
+            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                let mut actions = sandbox.contextual_actions();
+                sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                    ctx,
+                    app,
+                    Tab::IntersectionInfo(i),
+                    &mut actions,
+                )
+            })),
+        ])
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _app: &App) {
+        self.panel.draw(g);
+    }
+}
+
+fn describe_delays(
+    analytics: &Analytics,
+    prebaked: Option<&Analytics>,
+    i: IntersectionID,
+    now: Time,
+) -> String {
+    let describe = |analytics: &Analytics| {
+        let bus = mean_delay(analytics, i, now, |a| a == AgentType::Bus);
+        let others = mean_delay(analytics, i, now, |a| a != AgentType::Bus);
+        format!(
+            "buses wait {}, everybody else waits {}",
+            bus.map(|d| d.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            others
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        )
+    };
+    match prebaked {
+        Some(prebaked) => format!(
+            "On average, {} (before: {})",
+            describe(analytics),
+            describe(prebaked)
+        ),
+        None => format!("On average, {}", describe(analytics)),
+    }
+}
+
+fn mean_delay<F: Fn(AgentType) -> bool>(
+    analytics: &Analytics,
+    i: IntersectionID,
+    now: Time,
+    include: F,
+) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut cnt = 0;
+    for (_, t, dt, agent_type) in analytics.intersection_delays.get(&i)? {
+        if *t <= now && include(*agent_type) {
+            total += *dt;
+            cnt += 1;
+        }
+    }
+    if cnt == 0 {
+        None
+    } else {
+        Some(total / (cnt as f64))
+    }
+}
@@ -14,6 +14,7 @@ use widgetry::{
     Panel, State, Text, TextExt, Toggle, Widget,
 };
 
+use super::segments::TripSegment;
 use super::trip_problems::{problem_matrix, TripProblemFilter};
 use crate::app::{App, Transition};
 use crate::sandbox::dashboards::generic_trip_table::open_trip_transition;
@@ -54,7 +55,12 @@ impl TravelTimes {
             Widget::row(vec![
                 Widget::col(filters).section(ctx),
                 Widget::col(vec![
+                    filter.segment.to_controls(ctx).section(ctx),
                     summary_boxes(ctx, app, &filter),
+                    match super::multiple_runs::describe_trip_time_change(app) {
+                        Some(txt) => txt.into_widget(ctx),
+                        None => Widget::nothing(),
+                    },
                     Widget::col(vec![
                         Text::from(Line("Travel Times").small_heading()).into_widget(ctx),
                         Widget::row(vec![
@@ -142,6 +148,7 @@ impl State<App> for TravelTimes {
                     changes_pct: self.panel.dropdown_value("filter"),
                     modes: BTreeSet::new(),
                     include_no_changes: self.panel.is_checked("include trips without any changes"),
+                    segment: TripSegment::from_controls(&self.panel),
                 };
                 for m in TripMode::all() {
                     if self.panel.is_checked(m.ongoing_verb()) {
@@ -168,13 +175,13 @@ fn summary_boxes(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     let mut num_slower = 0;
     let mut sum_faster = Duration::ZERO;
     let mut sum_slower = Duration::ZERO;
-    for (_, b, a, mode) in app
+    for (id, b, a, mode) in app
         .primary
         .sim
         .get_analytics()
         .both_finished_trips(app.primary.sim.time(), app.prebaked())
     {
-        if !filter.modes.contains(&mode) {
+        if !filter.modes.contains(&mode) || !filter.segment.matches_finished_trip(app, id) {
             continue;
         }
         let same = if let Some(pct) = filter.changes_pct {
@@ -581,6 +588,7 @@ pub struct Filter {
     changes_pct: Option<f64>,
     modes: BTreeSet<TripMode>,
     include_no_changes: bool,
+    segment: TripSegment,
 }
 
 impl TripProblemFilter for Filter {
@@ -591,6 +599,10 @@ impl TripProblemFilter for Filter {
     fn include_no_changes(&self) -> bool {
         self.include_no_changes
     }
+
+    fn includes_trip(&self, app: &App, id: TripID) -> bool {
+        self.segment.matches_finished_trip(app, id)
+    }
 }
 
 impl Filter {
@@ -599,18 +611,20 @@ impl Filter {
             changes_pct: None,
             modes: TripMode::all().into_iter().collect(),
             include_no_changes: false,
+            segment: TripSegment::everything(),
         }
     }
 
     fn get_trips(&self, app: &App) -> Vec<(Duration, Duration)> {
         let mut points = Vec::new();
-        for (_, b, a, mode) in app
+        for (id, b, a, mode) in app
             .primary
             .sim
             .get_analytics()
             .both_finished_trips(app.primary.sim.time(), app.prebaked())
         {
             if self.modes.contains(&mode)
+                && self.segment.matches_finished_trip(app, id)
                 && self
                     .changes_pct
                     .map(|pct| pct_diff(a, b) > pct)
@@ -15,6 +15,10 @@ use crate::{App, EventCtx};
 pub trait TripProblemFilter {
     fn includes_mode(&self, mode: &TripMode) -> bool;
     fn include_no_changes(&self) -> bool;
+    /// Further restrict which trips are included, beyond their mode
+    fn includes_trip(&self, _: &App, _: TripID) -> bool {
+        true
+    }
 
     // Returns:
     // 1) trip ID
@@ -32,7 +36,7 @@ pub trait TripProblemFilter {
 
         let mut points = Vec::new();
         for (id, _, time_after, mode) in after.both_finished_trips(app.primary.sim.time(), before) {
-            if self.includes_mode(&mode) {
+            if self.includes_mode(&mode) && self.includes_trip(app, id) {
                 let count_before = problem_type
                     .count(before.problems_per_trip.get(&id).unwrap_or(&empty))
                     as isize;
@@ -52,8 +56,8 @@ pub trait TripProblemFilter {
         let after = app.primary.sim.get_analytics();
 
         let mut count = 0;
-        for (_, _, _, mode) in after.both_finished_trips(app.primary.sim.time(), before) {
-            if self.includes_mode(&mode) {
+        for (id, _, _, mode) in after.both_finished_trips(app.primary.sim.time(), before) {
+            if self.includes_mode(&mode) && self.includes_trip(app, id) {
                 count += 1;
             }
         }
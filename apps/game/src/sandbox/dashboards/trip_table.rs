@@ -4,7 +4,7 @@ use abstutil::prettyprint_usize;
 use geom::{Duration, Polygon, Time};
 use map_gui::tools::{checkbox_per_mode, color_for_mode};
 use sim::TripID;
-use synthpop::{TripEndpoint, TripMode};
+use synthpop::{TripEndpoint, TripMode, TripPurpose};
 use widgetry::table::{Col, Filter, Table};
 use widgetry::{
     Color, EventCtx, Filler, GeomBatch, GfxCtx, Line, Outcome, Panel, Stash, State, TabController,
@@ -12,6 +12,7 @@ use widgetry::{
 };
 
 use super::generic_trip_table::{open_trip_transition, preview_trip};
+use super::segments::TripSegment;
 use super::selector::RectangularSelector;
 use super::DashTab;
 use crate::app::{App, Transition};
@@ -213,6 +214,7 @@ impl State<App> for TripTable {
 struct FinishedTrip {
     id: TripID,
     mode: TripMode,
+    purpose: TripPurpose,
     modified: bool,
     start: TripEndpoint,
     end: TripEndpoint,
@@ -226,6 +228,7 @@ struct FinishedTrip {
 struct CancelledTrip {
     id: TripID,
     mode: TripMode,
+    purpose: TripPurpose,
     departure: Time,
     start: TripEndpoint,
     end: TripEndpoint,
@@ -236,6 +239,7 @@ struct CancelledTrip {
 struct UnfinishedTrip {
     id: TripID,
     mode: TripMode,
+    purpose: TripPurpose,
     departure: Time,
     duration_before: Duration,
     // TODO Estimated wait time?
@@ -249,6 +253,7 @@ struct Filters {
     ends_in: Option<Polygon>,
     unmodified_trips: bool,
     modified_trips: bool,
+    segment: TripSegment,
 }
 
 fn produce_raw_data(app: &App) -> (Vec<FinishedTrip>, Vec<CancelledTrip>) {
@@ -284,6 +289,7 @@ fn produce_raw_data(app: &App) -> (Vec<FinishedTrip>, Vec<CancelledTrip>) {
             cancelled.push(CancelledTrip {
                 id: *id,
                 mode: *mode,
+                purpose: trip.purpose,
                 departure: trip.departure,
                 start: trip.start,
                 end: trip.end,
@@ -299,6 +305,7 @@ fn produce_raw_data(app: &App) -> (Vec<FinishedTrip>, Vec<CancelledTrip>) {
         finished.push(FinishedTrip {
             id: *id,
             mode: *mode,
+            purpose: trip.purpose,
             departure: trip.departure,
             modified: trip.modified,
             start: trip.start,
@@ -324,6 +331,7 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
             ends_in: None,
             unmodified_trips: true,
             modified_trips: true,
+            segment: TripSegment::everything(),
         },
         to_controls: Box::new(move |ctx, app, state| {
             Widget::col(vec![
@@ -356,6 +364,7 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
                         Widget::nothing()
                     },
                 ]),
+                state.segment.to_controls(ctx),
             ])
         }),
         from_controls: Box::new(|panel| {
@@ -377,6 +386,7 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
                 modified_trips: panel
                     .maybe_is_checked("trips modified by experiment")
                     .unwrap_or(true),
+                segment: TripSegment::from_controls(panel),
             }
         }),
         apply: Box::new(|state, x, app| {
@@ -405,6 +415,9 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
             if !state.modified_trips && x.modified {
                 return false;
             }
+            if !state.segment.matches(x.purpose, x.departure) {
+                return false;
+            }
             true
         }),
     };
@@ -436,6 +449,7 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
         }),
         Col::Static,
     );
+    table.static_col("Purpose", Box::new(|x| x.purpose.to_string()));
     table.column(
         "Departure",
         Box::new(|ctx, _, x| Text::from(x.departure.ampm_tostring()).render(ctx)),
@@ -514,6 +528,7 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
             ends_in: None,
             unmodified_trips: true,
             modified_trips: true,
+            segment: TripSegment::everything(),
         },
         to_controls: Box::new(move |ctx, app, state| {
             Widget::col(vec![
@@ -522,6 +537,7 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
                     Toggle::switch(ctx, "starting off-map", None, state.off_map_starts),
                     Toggle::switch(ctx, "ending off-map", None, state.off_map_ends),
                 ]),
+                state.segment.to_controls(ctx),
             ])
         }),
         from_controls: Box::new(|panel| {
@@ -539,6 +555,7 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
                 ends_in: None,
                 unmodified_trips: true,
                 modified_trips: true,
+                segment: TripSegment::from_controls(panel),
             }
         }),
         apply: Box::new(|state, x, app| {
@@ -561,6 +578,9 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
                     return false;
                 }
             }
+            if !state.segment.matches(x.purpose, x.departure) {
+                return false;
+            }
             true
         }),
     };
@@ -580,6 +600,7 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
         }),
         Col::Static,
     );
+    table.static_col("Purpose", Box::new(|x| x.purpose.to_string()));
     table.column(
         "Departure",
         Box::new(|ctx, _, x| Text::from(x.departure.ampm_tostring()).render(ctx)),
@@ -623,6 +644,7 @@ fn make_table_unfinished_trips(app: &App) -> Table<App, UnfinishedTrip, Filters>
             unfinished.push(UnfinishedTrip {
                 id,
                 mode: trip.mode,
+                purpose: trip.purpose,
                 departure: trip.departure,
                 duration_before,
             });
@@ -639,8 +661,14 @@ fn make_table_unfinished_trips(app: &App) -> Table<App, UnfinishedTrip, Filters>
             ends_in: None,
             unmodified_trips: true,
             modified_trips: true,
+            segment: TripSegment::everything(),
         },
-        to_controls: Box::new(move |ctx, app, state| checkbox_per_mode(ctx, app, &state.modes)),
+        to_controls: Box::new(move |ctx, app, state| {
+            Widget::col(vec![
+                checkbox_per_mode(ctx, app, &state.modes),
+                state.segment.to_controls(ctx),
+            ])
+        }),
         from_controls: Box::new(|panel| {
             let mut modes = BTreeSet::new();
             for m in TripMode::all() {
@@ -656,12 +684,16 @@ fn make_table_unfinished_trips(app: &App) -> Table<App, UnfinishedTrip, Filters>
                 ends_in: None,
                 unmodified_trips: true,
                 modified_trips: true,
+                segment: TripSegment::from_controls(panel),
             }
         }),
         apply: Box::new(|state, x, _| {
             if !state.modes.contains(&x.mode) {
                 return false;
             }
+            if !state.segment.matches(x.purpose, x.departure) {
+                return false;
+            }
             true
         }),
     };
@@ -681,6 +713,7 @@ fn make_table_unfinished_trips(app: &App) -> Table<App, UnfinishedTrip, Filters>
         }),
         Col::Static,
     );
+    table.static_col("Purpose", Box::new(|x| x.purpose.to_string()));
     table.column(
         "Departure",
         Box::new(|ctx, _, x| Text::from(x.departure.ampm_tostring()).render(ctx)),
@@ -0,0 +1,185 @@
+//! The trips that gained or lost the most from the current proposal. Aggregate numbers hide who's
+//! actually affected; clicking one of these trips shows the route and timing before and after.
+
+use std::collections::BTreeSet;
+
+use geom::Duration;
+use map_gui::tools::color_for_mode;
+use sim::TripID;
+use synthpop::TripMode;
+use widgetry::{
+    Choice, ControlState, EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Toggle,
+    Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::cmp_duration_shorter;
+use crate::sandbox::dashboards::generic_trip_table::open_trip_transition;
+use crate::sandbox::dashboards::DashTab;
+
+/// Long lists aren't any easier to read than the aggregate numbers
+const MAX_TRIPS: usize = 30;
+
+pub struct WinnersAndLosers {
+    panel: Panel,
+}
+
+struct Filter {
+    modes: BTreeSet<TripMode>,
+    /// Show the trips that got slower, instead of faster
+    losers: bool,
+    min_change: Duration,
+}
+
+impl WinnersAndLosers {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let filter = Filter {
+            modes: TripMode::all().into_iter().collect(),
+            losers: false,
+            min_change: Duration::minutes(1),
+        };
+        Box::new(WinnersAndLosers {
+            panel: make_panel(ctx, app, &filter),
+        })
+    }
+}
+
+fn make_panel(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Panel {
+    let mut filters = vec!["Filters".text_widget(ctx)];
+    for mode in TripMode::all() {
+        filters.push(Toggle::colored_checkbox(
+            ctx,
+            mode.ongoing_verb(),
+            color_for_mode(app, mode),
+            filter.modes.contains(&mode),
+        ));
+    }
+    filters.push(Widget::dropdown(
+        ctx,
+        "losers",
+        filter.losers,
+        vec![
+            Choice::new("biggest winners", false),
+            Choice::new("biggest losers", true),
+        ],
+    ));
+    filters.push(Widget::row(vec![
+        "changed by at least".text_widget(ctx).centered_vert(),
+        Widget::dropdown(
+            ctx,
+            "min change",
+            filter.min_change,
+            vec![
+                Choice::new("any amount", Duration::ZERO),
+                Choice::new("1 minute", Duration::minutes(1)),
+                Choice::new("5 minutes", Duration::minutes(5)),
+                Choice::new("15 minutes", Duration::minutes(15)),
+            ],
+        ),
+    ]));
+
+    let trips = find_trips(app, filter);
+    let mut list = vec![Text::from(
+        Line(if filter.losers {
+            "Trips that got the most slower"
+        } else {
+            "Trips that got the most faster"
+        })
+        .small_heading(),
+    )
+    .into_widget(ctx)];
+    if trips.is_empty() {
+        list.push("No trips match".text_widget(ctx));
+    }
+    for (id, before, after, mode) in trips {
+        let purpose = app.primary.sim.trip_info(id).purpose;
+        let mut txt = Text::from(format!(
+            "Trip #{}, {} for {}: {} before, {} after, ",
+            id.0,
+            mode.ongoing_verb(),
+            purpose,
+            before.to_string(&app.opts.units),
+            after.to_string(&app.opts.units)
+        ));
+        txt.append_all(cmp_duration_shorter(app, after, before));
+        list.push(
+            ctx.style()
+                .btn_outline
+                .btn()
+                .label_styled_text(txt, ControlState::Default)
+                .build_widget(ctx, id.0.to_string()),
+        );
+    }
+
+    Panel::new_builder(Widget::col(vec![
+        DashTab::WinnersAndLosers.picker(ctx, app),
+        Widget::row(vec![
+            Widget::col(filters).section(ctx),
+            Widget::col(list).section(ctx),
+        ]),
+    ]))
+    .exact_size_percent(90, 90)
+    .build(ctx)
+}
+
+/// Trips that finished in both worlds, biggest change first. (ID, before, after, mode)
+fn find_trips(app: &App, filter: &Filter) -> Vec<(TripID, Duration, Duration, TripMode)> {
+    let mut trips: Vec<(TripID, Duration, Duration, TripMode)> = app
+        .primary
+        .sim
+        .get_analytics()
+        .both_finished_trips(app.primary.sim.time(), app.prebaked())
+        .into_iter()
+        .filter(|(_, before, after, mode)| {
+            let change = if filter.losers {
+                *after - *before
+            } else {
+                *before - *after
+            };
+            filter.modes.contains(mode) && change > Duration::ZERO && change >= filter.min_change
+        })
+        .collect();
+    if filter.losers {
+        trips.sort_by_key(|(_, before, after, _)| *before - *after);
+    } else {
+        trips.sort_by_key(|(_, before, after, _)| *after - *before);
+    }
+    trips.truncate(MAX_TRIPS);
+    trips
+}
+
+impl State<App> for WinnersAndLosers {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                x => open_trip_transition(app, x.parse::<usize>().unwrap()),
+            },
+            Outcome::Changed(_) => {
+                if let Some(t) = DashTab::WinnersAndLosers.transition(ctx, app, &self.panel) {
+                    return t;
+                }
+
+                let mut filter = Filter {
+                    modes: BTreeSet::new(),
+                    losers: self.panel.dropdown_value("losers"),
+                    min_change: self.panel.dropdown_value("min change"),
+                };
+                for m in TripMode::all() {
+                    if self.panel.is_checked(m.ongoing_verb()) {
+                        filter.modes.insert(m);
+                    }
+                }
+                let mut new_panel = make_panel(ctx, app, &filter);
+                new_panel.restore(ctx, &self.panel);
+                self.panel = new_panel;
+                Transition::Keep
+            }
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,83 @@
+use geom::{Duration, Polygon, Time};
+use widgetry::{Color, DrawWithTooltips, Drawable, EventCtx, GeomBatch, GfxCtx, Text, Widget};
+
+use crate::app::App;
+
+const NIGHT: Color = Color::rgb_f(0.04, 0.06, 0.15);
+const STREET_LIGHT: Color = Color::rgb_f(1.0, 0.82, 0.5);
+
+/// Darkens the map between sunset and sunrise, using the simulation time and the map's latitude.
+/// Roads tagged with street lights glow after dark.
+pub struct DrawDaylight {
+    draw_lit: Drawable,
+}
+
+impl DrawDaylight {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> DrawDaylight {
+        let mut batch = GeomBatch::new();
+        for r in app.primary.map.all_roads() {
+            if r.is_lit() == Some(true) {
+                batch.push(STREET_LIGHT.alpha(0.3), r.get_thick_polygon());
+            }
+        }
+        DrawDaylight {
+            draw_lit: ctx.upload(batch),
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if !app.opts.show_daylight {
+            return;
+        }
+        let daylight = app.primary.map.get_daylight(app.opts.day_of_year);
+        let time = app.primary.sim.time();
+        let darkness = daylight.darkness(time);
+        if darkness == 0.0 {
+            return;
+        }
+
+        g.fork_screenspace();
+        g.draw_polygon(
+            NIGHT.alpha(0.6 * darkness as f32),
+            Polygon::rectangle(g.canvas.window_width, g.canvas.window_height),
+        );
+        g.unfork();
+        if daylight.is_dark(time) {
+            g.redraw(&self.draw_lit);
+        }
+    }
+}
+
+/// A strip covering one day, shaded from night to day, with a cursor at the current time
+pub fn daylight_bar(ctx: &EventCtx, app: &App, now: Time, width: f64) -> Widget {
+    let daylight = app.primary.map.get_daylight(app.opts.day_of_year);
+    let height = 8.0;
+    let day_color = ctx.style().primary_fg.tint(0.6);
+
+    let mut batch = GeomBatch::new();
+    // Shade in 15 minute slices
+    let slices = 24 * 4;
+    let slice_width = width / (slices as f64);
+    for idx in 0..slices {
+        let time = Time::START_OF_DAY + Duration::minutes(15 * idx);
+        batch.push(
+            day_color.lerp(NIGHT, daylight.darkness(time)),
+            Polygon::rectangle(slice_width, height).translate(slice_width * (idx as f64), 0.0),
+        );
+    }
+    let pct = (now.inner_seconds() % (24.0 * 3600.0)) / (24.0 * 3600.0);
+    batch.push(
+        Color::WHITE,
+        Polygon::rectangle(2.0, height).translate(pct * width, 0.0),
+    );
+
+    let mut tooltip = Text::from(format!("Sunrise at {}", daylight.sunrise.ampm_tostring()));
+    tooltip.add_line(format!("Sunset at {}", daylight.sunset.ampm_tostring()));
+    tooltip.add_line("(Local solar time)");
+    DrawWithTooltips::new_widget(
+        ctx,
+        batch,
+        vec![(Polygon::rectangle(width, height), tooltip, None)],
+        Box::new(|_| GeomBatch::new()),
+    )
+}
@@ -0,0 +1,342 @@
+use geom::{Polygon, Time};
+use sim::{Co2Emissions, Metric};
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    Color, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Image, Line, Outcome, Panel, State,
+    Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::challenges::cutscene::{CutsceneBuilder, ShowMessage};
+use crate::challenges::Challenge;
+use crate::edit::EditMode;
+use crate::sandbox::gameplay::{challenge_header, FinalScore, GameplayMode, GameplayState};
+use crate::sandbox::{Actions, SandboxControls};
+
+/// Fractions of the goal that get celebrated along the way
+const MILESTONES: [f64; 3] = [0.25, 0.5, 0.75];
+
+pub struct CarbonBudget {
+    top_right: Panel,
+    mode: GameplayMode,
+    /// Percent reduction in CO2 from the baseline
+    goal: usize,
+    time: Time,
+    done: bool,
+    /// How many of the MILESTONES have been reached. Never decreases.
+    milestones_reached: usize,
+}
+
+impl CarbonBudget {
+    pub fn new_state(ctx: &mut EventCtx, goal: usize) -> Box<dyn GameplayState> {
+        Box::new(CarbonBudget {
+            top_right: Panel::empty(ctx),
+            mode: GameplayMode::CarbonBudget(goal),
+            goal,
+            time: Time::START_OF_DAY,
+            done: false,
+            milestones_reached: 0,
+        })
+    }
+
+    pub fn cutscene_pt1(ctx: &mut EventCtx, _: &App, mode: &GameplayMode) -> Box<dyn State<App>> {
+        CutsceneBuilder::new("Carbon budget")
+            .boss("The mayor just signed the climate pledge. In front of cameras, no less.")
+            .player("That's great news! What's in it?")
+            .boss("Transportation emissions have to come down. Starting with Montlake.")
+            .player("How much time do we have?")
+            .boss("One day. The press office wants numbers by tonight.")
+            .player("(Time to get people out of their cars, one edit at a time.)")
+            .build(ctx, cutscene_task(mode))
+    }
+
+    pub fn cutscene_pt2(ctx: &mut EventCtx, _: &App, mode: &GameplayMode) -> Box<dyn State<App>> {
+        CutsceneBuilder::new("Carbon budget: part 2")
+            .boss("The numbers from yesterday went over well. Too well.")
+            .player("Uh oh. What does that mean?")
+            .boss("The council wants three times the cut. Same neighbourhood, same deadline.")
+            .player("(Small tweaks won't be enough this time.)")
+            .build(ctx, cutscene_task(mode))
+    }
+}
+
+impl GameplayState for CarbonBudget {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        _: &mut SandboxControls,
+        _: &mut Actions,
+    ) -> Option<Transition> {
+        if self.time != app.primary.sim.time() && !self.done {
+            self.time = app.primary.sim.time();
+            self.recreate_panels(ctx, app);
+
+            let (before, after) = get_score(app);
+            if app.primary.sim.is_done() {
+                self.done = true;
+                return Some(Transition::Push(final_score(
+                    ctx,
+                    app,
+                    self.mode.clone(),
+                    before,
+                    after,
+                    self.goal,
+                )));
+            }
+
+            let progress = reduction_pct(before, after) / (self.goal as f64);
+            let mut newly_reached = None;
+            while self.milestones_reached < MILESTONES.len()
+                && progress >= MILESTONES[self.milestones_reached]
+            {
+                newly_reached = Some(MILESTONES[self.milestones_reached]);
+                self.milestones_reached += 1;
+            }
+            if let Some(milestone) = newly_reached {
+                self.recreate_panels(ctx, app);
+                return Some(Transition::Push(PopupMsg::new_state(
+                    ctx,
+                    "Milestone reached",
+                    vec![format!(
+                        "You're {}% of the way to cutting emissions by {}%. Keep going!",
+                        (milestone * 100.0) as usize,
+                        self.goal
+                    )],
+                )));
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.top_right.event(ctx) {
+            match x.as_ref() {
+                "edit map" => {
+                    return Some(Transition::Push(EditMode::new_state(
+                        ctx,
+                        app,
+                        self.mode.clone(),
+                    )));
+                }
+                "instructions" => {
+                    let contents = (cutscene_task(&self.mode))(ctx);
+                    return Some(Transition::Push(ShowMessage::new_state(
+                        ctx,
+                        contents,
+                        Color::WHITE,
+                    )));
+                }
+                "hint" => {
+                    let mut txt = Text::from("Hints");
+                    txt.add_line("");
+                    txt.add_line("Emissions come from every car and bus, per kilometer driven.");
+                    txt.add_line("Bike lanes and bus lanes can shift trips away from driving.");
+                    txt.add_line("Long detours for drivers can make things worse, not better.");
+                    let contents = txt.into_widget(ctx);
+                    return Some(Transition::Push(ShowMessage::new_state(
+                        ctx,
+                        contents,
+                        app.cs.panel_bg,
+                    )));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        None
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.top_right.draw(g);
+    }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &App) {
+        let (before, after) = get_score(app);
+        let reduction = reduction_pct(before, after);
+
+        let mut txt = Text::from(format!(
+            "CO2 so far: {} kg, versus {} kg before your changes",
+            after.round(),
+            before.round()
+        ));
+        txt.add_line(
+            Line(format!(
+                "{:.1}% reduction (only counting hours that are over)",
+                reduction
+            ))
+            .secondary(),
+        );
+
+        let mut milestones = Text::new();
+        for (idx, milestone) in MILESTONES.iter().enumerate() {
+            let line = Line(format!(
+                "Cut emissions by {:.1}%",
+                milestone * (self.goal as f64)
+            ));
+            milestones.add_line(if idx < self.milestones_reached {
+                line.fg(Color::GREEN)
+            } else {
+                line.secondary()
+            });
+        }
+
+        self.top_right = Panel::new_builder(Widget::col(vec![
+            challenge_header(ctx, "Carbon budget"),
+            Widget::row(vec![
+                format!("Cut CO2 emissions by {}%", self.goal)
+                    .text_widget(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_plain
+                    .icon_text("system/assets/tools/lightbulb.svg", "Hint")
+                    .build_widget(ctx, "hint")
+                    .align_right(),
+            ]),
+            gauge(ctx, app, reduction / (self.goal as f64)),
+            txt.into_widget(ctx),
+            milestones.into_widget(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx);
+    }
+}
+
+/// Returns CO2 emissions in kg (before, after)
+fn get_score(app: &App) -> (f64, f64) {
+    let map = &app.primary.map;
+    let now = app.primary.sim.time();
+    (
+        Co2Emissions.measure(app.prebaked(), map, now).unwrap(),
+        Co2Emissions
+            .measure(app.primary.sim.get_analytics(), map, now)
+            .unwrap(),
+    )
+}
+
+/// Positive means emissions went down
+fn reduction_pct(before: f64, after: f64) -> f64 {
+    Co2Emissions.better().pct_improvement(before, after)
+}
+
+/// A horizontal bar filled by progress towards the goal, with ticks at each milestone
+fn gauge(ctx: &EventCtx, app: &App, progress: f64) -> Widget {
+    let width = 300.0;
+    let height = 20.0;
+    let mut batch = GeomBatch::new();
+    batch.push(app.cs.inner_panel_bg, Polygon::rectangle(width, height));
+    if let Ok(p) = Polygon::maybe_rectangle(progress.clamp(0.0, 1.0) * width, height) {
+        batch.push(
+            if progress >= 1.0 {
+                app.cs.better_than_before
+            } else {
+                Color::hex("#4CA7E9")
+            },
+            p,
+        );
+    }
+    for milestone in MILESTONES {
+        batch.push(
+            Color::WHITE,
+            Polygon::rectangle(2.0, height).translate(milestone * width, 0.0),
+        );
+    }
+    batch.into_widget(ctx)
+}
+
+fn final_score(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    mode: GameplayMode,
+    before: f64,
+    after: f64,
+    goal: usize,
+) -> Box<dyn State<App>> {
+    let mut next_mode: Option<GameplayMode> = None;
+    let reduction = reduction_pct(before, after);
+
+    let msg = if after >= before {
+        format!(
+            "Emissions went from {} kg to {} kg. That's going in the wrong direction! The mayor \
+             is going to have my head.",
+            before.round(),
+            after.round()
+        )
+    } else if reduction < goal as f64 {
+        format!(
+            "Emissions went from {} kg to {} kg, a {:.1}% cut. It's a start, but the pledge says \
+             {}%.",
+            before.round(),
+            after.round(),
+            reduction,
+            goal
+        )
+    } else {
+        next_mode = Challenge::find(&mode).1.map(|c| c.gameplay);
+
+        format!(
+            "Emissions went from {} kg to {} kg -- a {:.1}% cut! The press office is thrilled. \
+             Don't let it go to your head.",
+            before.round(),
+            after.round(),
+            reduction
+        )
+    };
+
+    FinalScore::new_state(ctx, msg, mode, next_mode, None)
+}
+
+fn cutscene_task(mode: &GameplayMode) -> Box<dyn Fn(&mut EventCtx) -> Widget> {
+    let goal = match mode {
+        GameplayMode::CarbonBudget(goal) => *goal,
+        _ => unreachable!(),
+    };
+
+    Box::new(move |ctx| {
+        let icon_builder = Image::empty().color(Color::BLACK).dims(50.0);
+        Widget::custom_col(vec![
+            Text::from_multiline(vec![
+                Line(format!(
+                    "Cut CO2 emissions from cars and buses by {}% over the day",
+                    goal
+                ))
+                .fg(Color::BLACK),
+                Line("Any edits are allowed.").fg(Color::BLACK),
+            ])
+            .into_widget(ctx)
+            .margin_below(30),
+            Widget::row(vec![
+                Widget::col(vec![
+                    Line("Time").fg(Color::BLACK).into_widget(ctx),
+                    icon_builder
+                        .clone()
+                        .source_path("system/assets/tools/time.svg")
+                        .into_widget(ctx),
+                    Line("One full day").fg(Color::BLACK).into_widget(ctx),
+                ]),
+                Widget::col(vec![
+                    Line("Goal").fg(Color::BLACK).into_widget(ctx),
+                    icon_builder
+                        .clone()
+                        .source_path("system/assets/tools/location.svg")
+                        .into_widget(ctx),
+                    Text::from_multiline(vec![
+                        Line("Emit less CO2").fg(Color::BLACK),
+                        Line(format!("by at least {}%", goal)).fg(Color::BLACK),
+                    ])
+                    .into_widget(ctx),
+                ]),
+                Widget::col(vec![
+                    Line("Score").fg(Color::BLACK).into_widget(ctx),
+                    icon_builder
+                        .source_path("system/assets/tools/star.svg")
+                        .into_widget(ctx),
+                    Text::from_multiline(vec![
+                        Line("How much CO2").fg(Color::BLACK),
+                        Line("you cut").fg(Color::BLACK),
+                    ])
+                    .into_widget(ctx),
+                ]),
+            ])
+            .evenly_spaced(),
+        ])
+    })
+}
@@ -243,6 +243,7 @@ fn final_score(
     goal: Duration,
 ) -> Box<dyn State<App>> {
     let mut next_mode: Option<GameplayMode> = None;
+    let mut high_score = None;
 
     let msg = if before == after {
         format!(
@@ -267,12 +268,13 @@ fn final_score(
             goal
         )
     } else {
-        HighScore {
+        let score = HighScore {
             goal: format!("make VIP's commute at least {} faster", goal),
             score: before - after,
             edits_name: app.primary.map.get_edits().edits_name.clone(),
-        }
-        .record(app, mode.clone());
+        };
+        score.clone().record(app, mode.clone());
+        high_score = Some(score);
 
         next_mode = Challenge::find(&mode).1.map(|c| c.gameplay);
 
@@ -284,7 +286,7 @@ fn final_score(
         )
     };
 
-    FinalScore::new_state(ctx, msg, mode, next_mode)
+    FinalScore::new_state(ctx, msg, mode, next_mode, high_score)
 }
 
 fn cutscene_task(mode: &GameplayMode) -> Box<dyn Fn(&mut EventCtx) -> Widget> {
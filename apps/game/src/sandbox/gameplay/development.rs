@@ -0,0 +1,76 @@
+//! Test the transport impact of planned construction by dropping a proposed development onto the
+//! map. The site plan is a GeoJSON file in `player/developments/<country>/<city>/<map>/`, with
+//! one polygon per building footprint. Each feature may have these properties:
+//!
+//! - `name`
+//! - `levels`: the number of stories, defaulting to 1
+//! - `housing_units`: how many homes the building has
+//! - `floorspace`: square meters of offices, shops, and other workplaces, across all levels
+//!
+//! The buildings are added to the map until it's reloaded, and people living or working in them
+//! are added to the current scenario.
+
+use anyhow::Result;
+
+use geom::Polygon;
+use map_gui::render::DrawMap;
+use map_model::ProposedBuilding;
+use sim::ScenarioGenerator;
+use widgetry::EventCtx;
+
+use crate::app::App;
+
+/// Reads a site plan and adds its buildings to the map and its people to `app.primary.scenario`.
+/// The current simulation is reset, since it doesn't know about the new buildings. Returns the
+/// number of buildings and people added.
+pub fn add_development(ctx: &mut EventCtx, app: &mut App, path: &str) -> Result<(usize, usize)> {
+    let require_in_bounds = true;
+    let mut proposals = Vec::new();
+    for (polygon, props) in Polygon::from_geojson_bytes(
+        &abstio::slurp_file(path)?,
+        app.primary.map.get_gps_bounds(),
+        require_in_bounds,
+    )? {
+        let levels = match props.get("levels") {
+            Some(x) => x.parse::<f64>()?,
+            None => 1.0,
+        };
+        let housing_units = match props.get("housing_units") {
+            Some(x) => x.parse::<usize>()?,
+            None => 0,
+        };
+        let commercial_floorspace = match props.get("floorspace") {
+            Some(x) => x.parse::<f64>()?,
+            None => 0.0,
+        };
+        proposals.push(ProposedBuilding {
+            polygon,
+            name: props.get("name").cloned(),
+            levels,
+            housing_units,
+            commercial_floorspace,
+        });
+    }
+    if proposals.is_empty() {
+        bail!("{} has no building footprints within the map", path);
+    }
+
+    ctx.loading_screen("add a proposed development", |ctx, timer| {
+        let bldgs = app.primary.map.add_proposed_buildings(&proposals, timer);
+        if bldgs.is_empty() {
+            bail!("None of the buildings in {} are close to a sidewalk", path);
+        }
+
+        let mut rng = app.primary.current_flags.sim_flags.make_rng();
+        let people =
+            ScenarioGenerator::development_trips(&app.primary.map, &bldgs, &mut rng, timer);
+        let num_people = people.len();
+        if let Some(ref mut scenario) = app.primary.scenario {
+            scenario.people.extend(people);
+        }
+
+        app.primary.clear_sim();
+        app.primary.draw_map = DrawMap::new(ctx, &app.primary.map, &app.opts, &app.cs, timer);
+        Ok((bldgs.len(), num_people))
+    })
+}
@@ -325,7 +325,7 @@ fn final_score(
     failed: bool,
 ) -> Box<dyn State<App>> {
     let score = app.primary.sim.time() - Time::START_OF_DAY;
-    HighScore {
+    let high_score = HighScore {
         goal: format!(
             "make it {} without delay exceeding {}",
             app.primary.sim.get_end_of_day() - Time::START_OF_DAY,
@@ -333,8 +333,8 @@ fn final_score(
         ),
         score,
         edits_name: app.primary.map.get_edits().edits_name.clone(),
-    }
-    .record(app, mode.clone());
+    };
+    high_score.clone().record(app, mode.clone());
 
     let msg = if failed {
         format!(
@@ -344,7 +344,7 @@ fn final_score(
     } else {
         "Wow, you managed to fix the signals. Great job!".to_string()
     };
-    FinalScore::new_state(ctx, msg, mode, None)
+    FinalScore::new_state(ctx, msg, mode, None, Some(high_score))
 }
 
 // TODO Can we automatically transform text and SVG colors?
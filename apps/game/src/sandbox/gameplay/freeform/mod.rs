@@ -2,6 +2,7 @@ mod area_spawner;
 #[cfg(not(target_arch = "wasm32"))]
 mod importers;
 mod spawner;
+mod trip_planner;
 
 use rand::seq::SliceRandom;
 use rand::Rng;
@@ -79,6 +80,9 @@ impl GameplayState for Freeform {
                 "Start a new trip" => Some(Transition::Push(spawner::AgentSpawner::new_state(
                     ctx, app, None,
                 ))),
+                "Plan a trip" => Some(Transition::Push(trip_planner::TripPlanner::new_state(
+                    ctx, app,
+                ))),
                 "Spawn area traffic" => {
                     Some(Transition::Push(area_spawner::AreaSpawner::new_state(ctx)))
                 }
@@ -145,6 +149,7 @@ impl GameplayState for Freeform {
                     .btn_outline
                     .text("Start a new trip")
                     .build_def(ctx),
+                ctx.style().btn_outline.text("Plan a trip").build_def(ctx),
                 /*ctx.style()
                 .btn_outline
                 .text("Spawn area traffic")
@@ -380,6 +385,7 @@ pub fn spawn_agents_around(i: IntersectionID, app: &mut App) {
                         TripEndpoint::Building(map.all_buildings().choose(&mut rng).unwrap().id),
                         mode,
                     )],
+                    demographics: None,
                 });
             }
         } else if lane.is_walkable() {
@@ -396,6 +402,7 @@ pub fn spawn_agents_around(i: IntersectionID, app: &mut App) {
                         TripEndpoint::Building(map.all_buildings().choose(&mut rng).unwrap().id),
                         TripMode::Walk,
                     )],
+                    demographics: None,
                 });
             }
         }
@@ -0,0 +1,412 @@
+use geom::{Duration, Polygon, Time};
+use map_gui::tools::{InputWaypoints, WaypointID};
+use map_model::{
+    Map, Path, PathConstraints, PathRequest, PathfinderCaching, RoutingParams, MAX_BIKE_SPEED,
+    MAX_WALKING_SPEED,
+};
+use synthpop::{TripEndpoint, TripMode};
+use widgetry::mapspace::{ObjectID, World, WorldOutcome};
+use widgetry::{
+    Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner, State, Text,
+    TextExt, Toggle, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Preview how long a trip would take by each mode, under the current edits, without running a
+/// scenario. Routes come from the same pathfinding the simulation uses, and times assume no
+/// traffic, the fastest walking and cycling speeds agents can have, and transit running exactly
+/// on schedule.
+pub struct TripPlanner {
+    panel: Panel,
+    waypoints: InputWaypoints,
+    world: World<ID>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum ID {
+    Waypoint(WaypointID),
+    Itinerary(usize),
+}
+impl ObjectID for ID {}
+
+#[derive(Clone, Copy)]
+struct Preferences {
+    departure: Time,
+    cycle_avoiding_hills: bool,
+    cycle_avoiding_stress: bool,
+    cycle_avoiding_unlit: bool,
+    /// Is it dark at the departure time?
+    dark: bool,
+    wheelchair: bool,
+}
+
+impl Preferences {
+    fn bike_routing_params(self) -> RoutingParams {
+        // The same penalties as the bike trip planner
+        RoutingParams {
+            avoid_steep_incline_penalty: if self.cycle_avoiding_hills { 2.0 } else { 1.0 },
+            avoid_high_stress: if self.cycle_avoiding_stress { 2.0 } else { 1.0 },
+            avoid_unlit: if self.cycle_avoiding_unlit && self.dark {
+                2.0
+            } else {
+                1.0
+            },
+            ..Default::default()
+        }
+    }
+}
+
+struct Itinerary {
+    mode: TripMode,
+    legs: Vec<Leg>,
+}
+
+struct Leg {
+    description: String,
+    duration: Duration,
+    path: Option<Path>,
+}
+
+impl Itinerary {
+    fn total(&self) -> Duration {
+        self.legs.iter().map(|leg| leg.duration).sum()
+    }
+
+    fn color(&self, app: &App) -> Color {
+        match self.mode {
+            TripMode::Walk => app.cs.unzoomed_pedestrian,
+            TripMode::Bike => app.cs.unzoomed_bike,
+            TripMode::Transit => app.cs.unzoomed_bus,
+            TripMode::Drive => app.cs.unzoomed_car,
+        }
+    }
+
+    fn describe(&self, departure: Time) -> Text {
+        let name = match self.mode {
+            TripMode::Walk => "Walk",
+            TripMode::Bike => "Bike",
+            TripMode::Transit => "Walk + transit",
+            TripMode::Drive => "Drive",
+        };
+        let mut txt = Text::from(Line(format!(
+            "{}: arrive at {} ({})",
+            name,
+            (departure + self.total()).ampm_tostring(),
+            self.total()
+        )));
+        if self.legs.len() > 1 {
+            for leg in &self.legs {
+                txt.add_line(Line(format!("  {} ({})", leg.description, leg.duration)).secondary());
+            }
+        }
+        txt
+    }
+}
+
+impl TripPlanner {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut state = TripPlanner {
+            panel: Panel::empty(ctx),
+            waypoints: InputWaypoints::new_max_2(
+                app,
+                vec![
+                    PathConstraints::Car,
+                    PathConstraints::Bike,
+                    PathConstraints::Pedestrian,
+                ],
+            ),
+            world: World::new(),
+        };
+        let departure =
+            Time::START_OF_DAY + Duration::hours(app.primary.sim.time().get_hours() % 24);
+        let prefs = Preferences {
+            departure,
+            cycle_avoiding_hills: false,
+            cycle_avoiding_stress: false,
+            cycle_avoiding_unlit: false,
+            dark: is_dark(app, departure),
+            wheelchair: false,
+        };
+        state.recalculate(ctx, app, prefs);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App, prefs: Preferences) {
+        let map = &app.primary.map;
+
+        let mut world = World::new();
+        self.waypoints
+            .rebuild_world(ctx, &mut world, ID::Waypoint, 2);
+
+        let mut results = Vec::new();
+        let waypoints = self.waypoints.get_waypoints();
+        if waypoints.len() == 2 {
+            let mut itineraries = Vec::new();
+            let mut impossible = Vec::new();
+            for mode in TripMode::all() {
+                match plan(map, waypoints[0], waypoints[1], mode, prefs) {
+                    Some(itinerary) => itineraries.push(itinerary),
+                    None => impossible.push(mode),
+                }
+            }
+            itineraries.sort_by_key(|itinerary| itinerary.total());
+
+            for (idx, itinerary) in itineraries.iter().enumerate() {
+                results.push(itinerary.describe(prefs.departure).into_widget(ctx));
+
+                let hitboxes: Vec<Polygon> = itinerary
+                    .legs
+                    .iter()
+                    .filter_map(|leg| leg.path.as_ref())
+                    .filter_map(|path| path.trace_v2(map).ok())
+                    .collect();
+                if !hitboxes.is_empty() {
+                    world
+                        .add(ID::Itinerary(idx))
+                        .hitboxes(hitboxes)
+                        // Draw the fastest on top
+                        .zorder(itineraries.len() - idx)
+                        .draw_color(itinerary.color(app).alpha(0.8))
+                        .hover_alpha(0.5)
+                        .tooltip(itinerary.describe(prefs.departure))
+                        .build(ctx);
+                }
+            }
+            for mode in impossible {
+                results.push(
+                    Text::from(
+                        Line(format!("Can't {} between these places", mode.verb())).secondary(),
+                    )
+                    .into_widget(ctx),
+                );
+            }
+        } else {
+            results.push("Choose where the trip starts and ends".text_widget(ctx));
+        }
+
+        world.initialize_hover(ctx);
+        world.rebuilt_during_drag(ctx, &self.world);
+        self.world = world;
+
+        self.panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Plan a trip").small_heading().into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            Text::from(
+                Line(
+                    "Times assume no traffic, the fastest walking and cycling speeds, and \
+                     transit running on schedule",
+                )
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 20)
+            .into_widget(ctx),
+            self.waypoints.get_panel_widget(ctx),
+            Widget::row(vec![
+                "Depart at hour".text_widget(ctx).centered_vert(),
+                Spinner::widget(
+                    ctx,
+                    "departure hour",
+                    (0, 23),
+                    prefs.departure.get_hours(),
+                    1,
+                ),
+            ]),
+            Toggle::checkbox(ctx, "Cycle on flat roads", None, prefs.cycle_avoiding_hills),
+            Toggle::checkbox(
+                ctx,
+                "Cycle on quiet roads",
+                None,
+                prefs.cycle_avoiding_stress,
+            ),
+            Toggle::checkbox(
+                ctx,
+                "Cycle on lit roads after dark",
+                None,
+                prefs.cycle_avoiding_unlit,
+            ),
+            Toggle::checkbox(ctx, "Walk using a wheelchair", None, prefs.wheelchair),
+            Widget::horiz_separator(ctx, 1.0),
+            Widget::col(results),
+        ]))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+        .ignore_initial_events()
+        .build(ctx);
+    }
+
+    fn preferences(&self, app: &App) -> Preferences {
+        let departure = Time::START_OF_DAY + Duration::hours(self.panel.spinner("departure hour"));
+        Preferences {
+            departure,
+            cycle_avoiding_hills: self.panel.is_checked("Cycle on flat roads"),
+            cycle_avoiding_stress: self.panel.is_checked("Cycle on quiet roads"),
+            cycle_avoiding_unlit: self.panel.is_checked("Cycle on lit roads after dark"),
+            dark: is_dark(app, departure),
+            wheelchair: self.panel.is_checked("Walk using a wheelchair"),
+        }
+    }
+}
+
+impl State<App> for TripPlanner {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let panel_outcome = self.panel.event(ctx);
+        match panel_outcome {
+            Outcome::Clicked(ref x) if x == "close" => {
+                return Transition::Pop;
+            }
+            // The waypoint cards never produce this, so it must be a preference
+            Outcome::Changed(_) => {
+                let prefs = self.preferences(app);
+                self.recalculate(ctx, app, prefs);
+                return Transition::Keep;
+            }
+            _ => {}
+        }
+        if ctx.input.pressed(Key::Escape) {
+            return Transition::Pop;
+        }
+
+        let world_outcome = self.world.event(ctx);
+        let world_outcome_for_waypoints = world_outcome
+            .maybe_map_id(|id| match id {
+                ID::Waypoint(id) => Some(id),
+                _ => None,
+            })
+            .unwrap_or(WorldOutcome::Nothing);
+
+        if self
+            .waypoints
+            .event(app, panel_outcome, world_outcome_for_waypoints)
+        {
+            let prefs = self.preferences(app);
+            self.recalculate(ctx, app, prefs);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        self.world.draw(g);
+    }
+}
+
+fn is_dark(app: &App, time: Time) -> bool {
+    app.primary
+        .map
+        .get_daylight(app.opts.day_of_year)
+        .is_dark(time)
+}
+
+fn plan(
+    map: &Map,
+    from: TripEndpoint,
+    to: TripEndpoint,
+    mode: TripMode,
+    prefs: Preferences,
+) -> Option<Itinerary> {
+    let req = TripEndpoint::path_req(from, to, mode, map)?;
+    let legs = match mode {
+        TripMode::Walk => vec![walk(map, req, prefs.wheelchair, "Walk")?],
+        TripMode::Bike => {
+            let path = map
+                .pathfind_with_params(
+                    req,
+                    &prefs.bike_routing_params(),
+                    PathfinderCaching::CacheDijkstra,
+                )
+                .ok()?;
+            vec![Leg {
+                description: "Cycle".to_string(),
+                duration: path.estimate_duration(map, Some(MAX_BIKE_SPEED)),
+                path: Some(path),
+            }]
+        }
+        TripMode::Drive => {
+            let path = map.pathfind(req).ok()?;
+            vec![Leg {
+                description: "Drive".to_string(),
+                duration: path.estimate_duration(map, None),
+                path: Some(path),
+            }]
+        }
+        TripMode::Transit => transit(map, req, prefs)?,
+    };
+    Some(Itinerary { mode, legs })
+}
+
+fn walk(map: &Map, req: PathRequest, wheelchair: bool, description: &str) -> Option<Leg> {
+    let path = if wheelchair {
+        map.pathfind_wheelchair(req).ok()?
+    } else {
+        map.pathfind(req).ok()?
+    };
+    Some(Leg {
+        description: description.to_string(),
+        duration: path.estimate_duration(map, Some(MAX_WALKING_SPEED)),
+        path: Some(path),
+    })
+}
+
+/// Like the simulation, only consider one transit route, and only if it beats walking the whole
+/// way. Riders who'd stay on until the route leaves the map aren't handled.
+fn transit(map: &Map, req: PathRequest, prefs: Preferences) -> Option<Vec<Leg>> {
+    let (board, alight, route) = map.should_use_transit(req.start, req.end)?;
+    let alight = alight?;
+    let tr = map.get_tr(route);
+    let arrivals = tr.estimate_stop_arrivals(map).ok()?;
+    let board_idx = tr.stops.iter().position(|s| *s == board)?;
+    let alight_idx = tr.stops.iter().position(|s| *s == alight)?;
+    if alight_idx <= board_idx {
+        return None;
+    }
+
+    let walk_to_stop = walk(
+        map,
+        PathRequest::walking(req.start, map.get_ts(board).sidewalk_pos),
+        prefs.wheelchair,
+        &format!("Walk to {}", map.get_ts(board).name),
+    )?;
+    let at_stop = prefs.departure + walk_to_stop.duration;
+    // The first vehicle that reaches the stop after we do
+    let vehicle_arrives = tr
+        .spawn_times
+        .iter()
+        .map(|t| *t + arrivals[board_idx])
+        .find(|t| *t >= at_stop)?;
+    let ride = map
+        .pathfind(PathRequest::vehicle(
+            map.get_ts(board).driving_pos,
+            map.get_ts(alight).driving_pos,
+            tr.route_type,
+        ))
+        .ok();
+    let walk_from_stop = walk(
+        map,
+        PathRequest::walking(map.get_ts(alight).sidewalk_pos, req.end),
+        prefs.wheelchair,
+        &format!("Walk from {}", map.get_ts(alight).name),
+    )?;
+
+    Some(vec![
+        walk_to_stop,
+        Leg {
+            description: format!("Wait for {}", tr.short_name),
+            duration: vehicle_arrives - at_stop,
+            path: None,
+        },
+        Leg {
+            description: format!(
+                "Ride {} {} stops to {}",
+                tr.short_name,
+                alight_idx - board_idx,
+                map.get_ts(alight).name
+            ),
+            duration: arrivals[alight_idx] - arrivals[board_idx],
+            path: ride,
+        },
+        walk_from_stop,
+    ])
+}
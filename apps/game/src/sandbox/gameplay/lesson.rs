@@ -0,0 +1,467 @@
+//! Guided lessons loaded from data files. Unlike the hard-coded tutorial, these work on any map
+//! and can be authored in-app (see `devtools::lessons`), so educators can build their own.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::{ArrowCap, Circle, Distance, LonLat, PolyLine, Pt2D, Time};
+use widgetry::tools::PopupMsg;
+use widgetry::{
+    hotkeys, lctrl, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    ScreenPt, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::{tool_panel, Warping};
+use crate::edit::EditMode;
+use crate::sandbox::gameplay::{GameplayMode, GameplayState, TutorialPointer};
+use crate::sandbox::{maybe_exit_sandbox, Actions, SandboxControls, SandboxMode};
+
+/// If the player inspects an object this close to an `InspectObject` target, the stage is done.
+const INSPECT_RADIUS: Distance = Distance::const_meters(30.0);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Lesson {
+    pub name: String,
+    pub map_name: MapName,
+    /// Simulate this scenario during every stage. If `None`, there's no traffic.
+    pub scenario: Option<String>,
+    pub stages: Vec<LessonStage>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LessonStage {
+    pub title: String,
+    /// Shown one at a time, before the player attempts the stage
+    pub messages: Vec<String>,
+    pub camera: Option<CameraTarget>,
+    pub highlight: Option<Highlight>,
+    pub trigger: CompletionTrigger,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct CameraTarget {
+    pub center: LonLat,
+    pub zoom: f64,
+}
+
+/// Something to point at while showing messages
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Highlight {
+    MapPoint(LonLat),
+    TimeControls,
+    ToolPanel,
+}
+
+/// When is a stage finished?
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum CompletionTrigger {
+    /// As soon as the player reads all of the messages
+    ReadMessages,
+    /// When the simulation reaches some time
+    ReachTime(Time),
+    /// When the player opens the info panel for an object near this point
+    InspectObject(LonLat),
+    /// When the player makes any new edit to the map
+    EditMap,
+    /// When the simulation finishes
+    FinishSimulation,
+}
+
+impl CompletionTrigger {
+    pub fn describe(self) -> String {
+        match self {
+            CompletionTrigger::ReadMessages => "Read the instructions".to_string(),
+            CompletionTrigger::ReachTime(t) => format!("Wait until {}", t.ampm_tostring()),
+            CompletionTrigger::InspectObject(_) => "Inspect the highlighted object".to_string(),
+            CompletionTrigger::EditMap => "Edit the map".to_string(),
+            CompletionTrigger::FinishSimulation => "Finish the simulation".to_string(),
+        }
+    }
+}
+
+impl Lesson {
+    pub fn new(map_name: MapName) -> Lesson {
+        Lesson {
+            name: "new lesson".to_string(),
+            map_name,
+            scenario: None,
+            stages: Vec::new(),
+        }
+    }
+
+    pub fn load(map_name: &MapName, name: &str) -> Result<Lesson> {
+        abstio::maybe_read_json(abstio::path_lesson(map_name, name), &mut Timer::throwaway())
+    }
+
+    pub fn save(&self) {
+        abstio::write_json(abstio::path_lesson(&self.map_name, &self.name), self);
+    }
+
+    pub fn list_all(map_name: &MapName) -> Vec<String> {
+        abstio::list_all_objects(abstio::path_all_lessons(map_name))
+    }
+
+    /// A lesson describing why another one couldn't be loaded
+    fn broken(map_name: MapName, err: String) -> Lesson {
+        let mut lesson = Lesson::new(map_name);
+        lesson.stages.push(LessonStage {
+            title: "Couldn't load lesson".to_string(),
+            messages: vec![err],
+            camera: None,
+            highlight: None,
+            trigger: CompletionTrigger::ReadMessages,
+        });
+        lesson
+    }
+}
+
+pub struct LessonPlayer {
+    lesson: Lesson,
+    current: TutorialPointer,
+    top_right: Panel,
+    msg_panel: Option<Panel>,
+    warped: bool,
+    finished: bool,
+    num_edits_at_start: usize,
+
+    tool_panel_pt: ScreenPt,
+    // Screen-space, updated every event for UI elements
+    arrow_to: Option<ScreenPt>,
+}
+
+impl LessonPlayer {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        map_name: &MapName,
+        name: &str,
+        current: TutorialPointer,
+    ) -> Box<dyn GameplayState> {
+        let lesson = match Lesson::load(map_name, name) {
+            Ok(lesson) if !lesson.stages.is_empty() => lesson,
+            Ok(_) => Lesson::broken(map_name.clone(), format!("{} has no stages", name)),
+            Err(err) => Lesson::broken(map_name.clone(), err.to_string()),
+        };
+        // The lesson file might've been edited since the pointer was made
+        let current = if current.stage < lesson.stages.len() {
+            current
+        } else {
+            TutorialPointer::new(0, 0)
+        };
+        app.primary.current_selection = None;
+
+        let mut state = LessonPlayer {
+            lesson,
+            current,
+            top_right: Panel::empty(ctx),
+            msg_panel: None,
+            warped: false,
+            finished: false,
+            num_edits_at_start: app.primary.map.get_edits().commands.len(),
+
+            tool_panel_pt: tool_panel(ctx).center_of_panel(),
+            arrow_to: None,
+        };
+        state.top_right = state.make_top_right(ctx);
+        state.msg_panel = state.make_msg_panel(ctx);
+        Box::new(state)
+    }
+
+    fn stage(&self) -> &LessonStage {
+        &self.lesson.stages[self.current.stage]
+    }
+
+    fn showing_messages(&self) -> bool {
+        self.current.part < self.stage().messages.len()
+    }
+
+    fn mode(&self, current: TutorialPointer) -> GameplayMode {
+        GameplayMode::Lesson(
+            self.lesson.map_name.clone(),
+            self.lesson.name.clone(),
+            current,
+        )
+    }
+
+    fn jump_to_stage(&self, app: &mut App, stage: usize) -> Transition {
+        Transition::Replace(SandboxMode::simple_new(
+            app,
+            self.mode(TutorialPointer::new(stage, 0)),
+        ))
+    }
+
+    fn finish_stage(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if self.current.stage + 1 < self.lesson.stages.len() {
+            return self.jump_to_stage(app, self.current.stage + 1);
+        }
+        self.finished = true;
+        self.top_right = self.make_top_right(ctx);
+        Transition::Push(PopupMsg::new_state(
+            ctx,
+            "Lesson complete",
+            vec![format!("You've finished {}!", self.lesson.name)],
+        ))
+    }
+
+    fn is_stage_done(&self, app: &App, controls: &SandboxControls) -> bool {
+        match self.stage().trigger {
+            // Only called after the messages are dismissed
+            CompletionTrigger::ReadMessages => true,
+            CompletionTrigger::ReachTime(t) => app.primary.sim.time() >= t,
+            CompletionTrigger::InspectObject(gps) => {
+                let target = gps.to_pt(app.primary.map.get_gps_bounds());
+                controls
+                    .common
+                    .as_ref()
+                    .and_then(|c| c.info_panel_open(app))
+                    .and_then(|id| app.primary.canonical_point(id))
+                    .map(|pt| pt.dist_to(target) <= INSPECT_RADIUS)
+                    .unwrap_or(false)
+            }
+            CompletionTrigger::EditMap => {
+                app.primary.map.get_edits().commands.len() > self.num_edits_at_start
+            }
+            CompletionTrigger::FinishSimulation => app.primary.sim.is_done(),
+        }
+    }
+
+    fn make_top_right(&self, ctx: &mut EventCtx) -> Panel {
+        let mut col = vec![Widget::row(vec![
+            Line(&self.lesson.name).small_heading().into_widget(ctx),
+            Widget::vert_separator(ctx, 50.0),
+            ctx.style()
+                .btn_prev()
+                .disabled(self.current.stage == 0)
+                .build_widget(ctx, "previous stage"),
+            {
+                let mut txt = Text::from(format!("Stage {}", self.current.stage + 1));
+                txt.append(Line(format!("/{}", self.lesson.stages.len())).fg(Color::grey(0.7)));
+                txt.into_widget(ctx)
+            },
+            ctx.style()
+                .btn_next()
+                .disabled(self.current.stage == self.lesson.stages.len() - 1)
+                .build_widget(ctx, "next stage"),
+            ctx.style().btn_outline.text("Quit").build_def(ctx),
+        ])
+        .centered()];
+        if !self.showing_messages() {
+            col.push(Widget::row(vec![
+                Line(&self.stage().title).small_heading().into_widget(ctx),
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/info.svg")
+                    .build_widget(ctx, "instructions")
+                    .centered_vert()
+                    .align_right(),
+            ]));
+            let goal = self.stage().trigger.describe();
+            col.push(if self.finished {
+                Line(format!("[X] {}", goal))
+                    .fg(ctx.style().text_hotkey_color)
+                    .into_widget(ctx)
+            } else {
+                format!("[ ] {}", goal).text_widget(ctx)
+            });
+            col.push(
+                ctx.style()
+                    .btn_outline
+                    .icon_text("system/assets/tools/pencil.svg", "Edit map")
+                    .hotkey(lctrl(Key::E))
+                    .build_widget(ctx, "edit map"),
+            );
+        }
+
+        Panel::new_builder(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx)
+    }
+
+    fn make_msg_panel(&self, ctx: &mut EventCtx) -> Option<Panel> {
+        if !self.showing_messages() {
+            return None;
+        }
+        let num_messages = self.stage().messages.len();
+
+        let mut col = vec![{
+            let mut txt = Text::new();
+            txt.add_line(Line(&self.stage().title).small_heading());
+            txt.add_line("");
+            txt.into_widget(ctx)
+        }];
+        col.push(
+            Text::from_multiline(self.stage().messages[self.current.part].lines().collect())
+                .wrap_to_pct(ctx, 30)
+                .into_widget(ctx),
+        );
+        let mut controls = vec![Widget::row(vec![
+            ctx.style()
+                .btn_prev()
+                .disabled(self.current.part == 0)
+                .hotkey(Key::LeftArrow)
+                .build_widget(ctx, "previous message"),
+            format!("{}/{}", self.current.part + 1, num_messages)
+                .text_widget(ctx)
+                .centered_vert(),
+            ctx.style()
+                .btn_next()
+                .disabled(self.current.part == num_messages - 1)
+                .hotkey(Key::RightArrow)
+                .build_widget(ctx, "next message"),
+        ])];
+        if self.current.part == num_messages - 1 {
+            controls.push(
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Try it")
+                    .hotkey(hotkeys(vec![Key::RightArrow, Key::Space, Key::Enter]))
+                    .build_def(ctx),
+            );
+        }
+        col.push(Widget::col(controls).align_bottom());
+
+        Some(
+            Panel::new_builder(Widget::col(col).outline((5.0, Color::WHITE)))
+                .exact_size_percent(40, 40)
+                .aligned(HorizontalAlignment::Center, VerticalAlignment::Center)
+                .build(ctx),
+        )
+    }
+}
+
+impl GameplayState for LessonPlayer {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        controls: &mut SandboxControls,
+        _: &mut Actions,
+    ) -> Option<Transition> {
+        if !self.warped {
+            self.warped = true;
+            if let Some(camera) = self.stage().camera {
+                return Some(Transition::Push(Warping::new_state(
+                    ctx,
+                    camera.center.to_pt(app.primary.map.get_gps_bounds()),
+                    Some(camera.zoom),
+                    None,
+                    &mut app.primary,
+                )));
+            }
+        }
+
+        self.arrow_to = match self.stage().highlight {
+            Some(Highlight::TimeControls) => controls
+                .time_panel
+                .as_ref()
+                .map(|time| time.panel.center_of_panel()),
+            Some(Highlight::ToolPanel) => Some(self.tool_panel_pt),
+            // Calculated while drawing, since the camera might move
+            Some(Highlight::MapPoint(_)) | None => None,
+        };
+
+        if let Outcome::Clicked(x) = self.top_right.event(ctx) {
+            match x.as_ref() {
+                "Quit" => {
+                    return Some(maybe_exit_sandbox(ctx));
+                }
+                "previous stage" => {
+                    return Some(self.jump_to_stage(app, self.current.stage - 1));
+                }
+                "next stage" => {
+                    return Some(self.jump_to_stage(app, self.current.stage + 1));
+                }
+                "instructions" => {
+                    self.current.part = 0;
+                    self.top_right = self.make_top_right(ctx);
+                    self.msg_panel = self.make_msg_panel(ctx);
+                    return Some(Transition::Keep);
+                }
+                "edit map" => {
+                    return Some(Transition::Push(EditMode::new_state(
+                        ctx,
+                        app,
+                        self.mode(self.current),
+                    )));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if let Some(ref mut msg) = self.msg_panel {
+            match msg.event(ctx) {
+                Outcome::Clicked(x) => {
+                    match x.as_ref() {
+                        "previous message" => {
+                            self.current.part -= 1;
+                        }
+                        "next message" | "Try it" => {
+                            self.current.part += 1;
+                        }
+                        _ => unreachable!(),
+                    }
+                    self.top_right = self.make_top_right(ctx);
+                    self.msg_panel = self.make_msg_panel(ctx);
+                    return Some(Transition::Keep);
+                }
+                _ => {
+                    // Don't allow other interactions
+                    return Some(Transition::Keep);
+                }
+            }
+        }
+
+        if !self.finished && self.is_stage_done(app, controls) {
+            return Some(self.finish_stage(ctx, app));
+        }
+
+        None
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.top_right.draw(g);
+
+        if let Some(ref msg) = self.msg_panel {
+            let arrow_to = match self.stage().highlight {
+                Some(Highlight::MapPoint(gps)) => Some(
+                    g.canvas
+                        .map_to_screen(gps.to_pt(app.primary.map.get_gps_bounds())),
+                ),
+                _ => self.arrow_to,
+            };
+            // Arrows underneath the message panel, but on top of other panels
+            if let Some(pt) = arrow_to {
+                g.fork_screenspace();
+                if let Ok(pl) =
+                    PolyLine::new(vec![msg.center_of("next message").to_pt(), pt.to_pt()])
+                {
+                    g.draw_polygon(
+                        Color::RED,
+                        pl.make_arrow(Distance::meters(20.0), ArrowCap::Triangle),
+                    );
+                }
+                g.unfork();
+            }
+
+            msg.draw(g);
+        } else if let CompletionTrigger::InspectObject(gps) = self.stage().trigger {
+            let pt: Pt2D = gps.to_pt(app.primary.map.get_gps_bounds());
+            g.draw_polygon(
+                Color::YELLOW.alpha(0.5),
+                Circle::new(pt, INSPECT_RADIUS).to_polygon(),
+            );
+        }
+    }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, _: &App) {
+        self.top_right = self.make_top_right(ctx);
+        self.msg_panel = self.make_msg_panel(ctx);
+    }
+
+    fn can_move_canvas(&self) -> bool {
+        self.msg_panel.is_none()
+    }
+}
@@ -3,6 +3,7 @@ use core::pin::Pin;
 
 use anyhow::Result;
 use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
 use abstutil::Timer;
@@ -10,28 +11,35 @@ use geom::Duration;
 use map_model::{EditCmd, EditIntersectionControl, MapEdits};
 use sim::ScenarioGenerator;
 use synthpop::{OrigPersonID, Scenario, ScenarioModifier};
+use widgetry::tools::PopupMsg;
 use widgetry::{
     lctrl, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, State, TextExt, Widget,
 };
 
 pub use self::freeform::spawn_agents_around;
+pub use self::lesson::{CameraTarget, CompletionTrigger, Highlight, Lesson, LessonStage};
 pub use self::tutorial::{Tutorial, TutorialPointer, TutorialState};
 use crate::app::App;
 use crate::app::Transition;
-use crate::challenges::{Challenge, ChallengesPicker};
+use crate::challenges::leaderboard::{export_score_card, SubmitScore};
+use crate::challenges::{Challenge, ChallengesPicker, HighScore};
 use crate::edit::SaveEdits;
 use crate::pregame::TitleScreen;
 use crate::sandbox::{Actions, SandboxControls, SandboxMode};
 
 // TODO pub so challenges can grab cutscenes and SandboxMode can dispatch to actions. Weird?
 mod actdev;
+pub mod carbon_budget;
 pub mod commute;
+mod development;
 pub mod fix_traffic_signals;
 pub mod freeform;
+mod lesson;
 pub mod play_scenario;
+pub mod school_run;
 pub mod tutorial;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub enum GameplayMode {
     // TODO Maybe this should be "sandbox"
     Freeform(MapName),
@@ -39,11 +47,17 @@ pub enum GameplayMode {
     PlayScenario(MapName, String, Vec<ScenarioModifier>),
     FixTrafficSignals,
     OptimizeCommute(OrigPersonID, Duration),
+    // Percent reduction in CO2 emissions
+    CarbonBudget(usize),
+    // Percent of homes near a school with a safe route there
+    SchoolRun(usize),
     // Map name, scenario name, background traffic
     Actdev(MapName, String, bool),
 
     // current
     Tutorial(TutorialPointer),
+    // Map name, lesson name, current
+    Lesson(MapName, String, TutorialPointer),
 }
 
 pub trait GameplayState: downcast_rs::Downcast {
@@ -83,14 +97,18 @@ pub enum LoadScenario {
     Nothing,
     Path(String),
     Scenario(Scenario),
-    // wasm futures are not `Send`, since they all ultimately run on the browser's single threaded
-    // runloop
-    #[cfg(target_arch = "wasm32")]
-    Future(Pin<Box<dyn Future<Output = Result<Box<dyn Send + FnOnce(&App) -> Scenario>>>>>),
-    #[cfg(not(target_arch = "wasm32"))]
-    Future(Pin<Box<dyn Send + Future<Output = Result<Box<dyn Send + FnOnce(&App) -> Scenario>>>>>),
+    Future(ScenarioFuture),
 }
 
+// wasm futures are not `Send`, since they all ultimately run on the browser's single threaded
+// runloop
+#[cfg(target_arch = "wasm32")]
+pub type ScenarioFuture =
+    Pin<Box<dyn Future<Output = Result<Box<dyn Send + FnOnce(&App) -> Scenario>>>>>;
+#[cfg(not(target_arch = "wasm32"))]
+pub type ScenarioFuture =
+    Pin<Box<dyn Send + Future<Output = Result<Box<dyn Send + FnOnce(&App) -> Scenario>>>>>;
+
 impl GameplayMode {
     pub fn map_name(&self) -> MapName {
         match self {
@@ -98,8 +116,11 @@ impl GameplayMode {
             GameplayMode::PlayScenario(ref name, _, _) => name.clone(),
             GameplayMode::FixTrafficSignals => MapName::seattle("downtown"),
             GameplayMode::OptimizeCommute(_, _) => MapName::seattle("montlake"),
+            GameplayMode::CarbonBudget(_) => MapName::seattle("montlake"),
+            GameplayMode::SchoolRun(_) => MapName::seattle("montlake"),
             GameplayMode::Tutorial(_) => MapName::seattle("montlake"),
             GameplayMode::Actdev(ref name, _, _) => name.clone(),
+            GameplayMode::Lesson(ref name, _, _) => name.clone(),
         }
     }
 
@@ -120,6 +141,12 @@ impl GameplayMode {
                     None => LoadScenario::Nothing,
                 };
             }
+            GameplayMode::Lesson(ref name, ref lesson, _) => {
+                match Lesson::load(name, lesson).ok().and_then(|l| l.scenario) {
+                    Some(scenario) => scenario,
+                    None => return LoadScenario::Nothing,
+                }
+            }
             GameplayMode::Actdev(_, ref scenario, bg_traffic) => {
                 if *bg_traffic {
                     format!("{}_with_bg", scenario)
@@ -127,21 +154,34 @@ impl GameplayMode {
                     scenario.to_string()
                 }
             }
-            GameplayMode::FixTrafficSignals | GameplayMode::OptimizeCommute(_, _) => {
-                "weekday".to_string()
-            }
+            GameplayMode::FixTrafficSignals
+            | GameplayMode::OptimizeCommute(_, _)
+            | GameplayMode::CarbonBudget(_)
+            | GameplayMode::SchoolRun(_) => "weekday".to_string(),
         };
         if name == "random" {
             LoadScenario::Scenario(ScenarioGenerator::small_run(map).generate(map, &mut rng, timer))
         } else if name == "home_to_work" {
             LoadScenario::Scenario(ScenarioGenerator::proletariat_robot(map, &mut rng, timer))
         } else if name == "census" {
+            let source = match popdat::CensusSource::for_map(map.get_name()) {
+                Some(source) => source,
+                None => {
+                    warn!(
+                        "No census data covers {}; not generating people",
+                        map.get_name().describe()
+                    );
+                    return LoadScenario::Nothing;
+                }
+            };
+            info!("Using {} for the census scenario", source.describe());
             let map_area = map.get_boundary_polygon().clone();
             let map_bounds = map.get_gps_bounds().clone();
             let mut rng = sim::fork_rng(&mut rng);
 
             LoadScenario::Future(Box::pin(async move {
-                let areas = popdat::CensusArea::fetch_all_for_map(&map_area, &map_bounds).await?;
+                let areas =
+                    popdat::CensusArea::fetch_all_for_map(source, &map_area, &map_bounds).await?;
 
                 let scenario_from_app: Box<dyn Send + FnOnce(&App) -> Scenario> =
                     Box::new(move |app: &App| {
@@ -199,7 +239,14 @@ impl GameplayMode {
                         return false;
                     }
                 }
-                EditCmd::ChangeRouteSchedule { .. } => {}
+                EditCmd::ChangeTransitStop { .. }
+                | EditCmd::ChangeRouteStops { .. }
+                | EditCmd::ChangeRouteAlignment { .. } => {
+                    if !self.can_edit_roads() {
+                        return false;
+                    }
+                }
+                EditCmd::ChangeRouteSchedule { .. } | EditCmd::ChangeBikeParking { .. } => {}
             }
         }
         true
@@ -219,10 +266,15 @@ impl GameplayMode {
             GameplayMode::OptimizeCommute(p, goal) => {
                 commute::OptimizeCommute::new_state(ctx, app, *p, *goal)
             }
+            GameplayMode::CarbonBudget(goal) => carbon_budget::CarbonBudget::new_state(ctx, *goal),
+            GameplayMode::SchoolRun(goal) => school_run::SchoolRun::new_state(ctx, app, *goal),
             GameplayMode::Tutorial(current) => Tutorial::make_gameplay(ctx, app, *current),
             GameplayMode::Actdev(_, ref scenario, bg_traffic) => {
                 actdev::Actdev::new_state(ctx, scenario.clone(), *bg_traffic)
             }
+            GameplayMode::Lesson(ref name, ref lesson, current) => {
+                lesson::LessonPlayer::new_state(ctx, app, name, lesson, *current)
+            }
         }
     }
 }
@@ -250,6 +302,8 @@ pub struct FinalScore {
     panel: Panel,
     retry: GameplayMode,
     next_mode: Option<GameplayMode>,
+    /// If the player just set a high score, they can share it
+    score: Option<HighScore>,
 
     chose_next: bool,
     chose_back_to_challenges: bool,
@@ -261,6 +315,7 @@ impl FinalScore {
         msg: String,
         mode: GameplayMode,
         next_mode: Option<GameplayMode>,
+        score: Option<HighScore>,
     ) -> Box<dyn State<App>> {
         Box::new(FinalScore {
             panel: Panel::new_builder(Widget::row(vec![
@@ -290,12 +345,27 @@ impl FinalScore {
                         .btn_outline
                         .text("Back to challenges")
                         .build_def(ctx),
+                    if score.is_some() {
+                        Widget::row(vec![
+                            ctx.style()
+                                .btn_plain
+                                .text("Submit to leaderboard")
+                                .build_def(ctx),
+                            ctx.style()
+                                .btn_plain
+                                .text("Export score card")
+                                .build_def(ctx),
+                        ])
+                    } else {
+                        Widget::nothing()
+                    },
                 ])
                 .section(ctx),
             ]))
             .build(ctx),
             retry: mode,
             next_mode,
+            score,
             chose_next: false,
             chose_back_to_challenges: false,
         })
@@ -328,6 +398,29 @@ impl State<App> for FinalScore {
                         ));
                     }
                 }
+                "Submit to leaderboard" => {
+                    return Transition::Push(SubmitScore::new_state(
+                        ctx,
+                        Challenge::find(&self.retry).0,
+                        self.score.clone().unwrap(),
+                    ));
+                }
+                "Export score card" => {
+                    let challenge = Challenge::find(&self.retry).0;
+                    return Transition::Push(
+                        match export_score_card(ctx, app, &challenge, self.score.as_ref().unwrap())
+                        {
+                            Ok(path) => PopupMsg::new_state(
+                                ctx,
+                                "Score card exported",
+                                vec![format!("Wrote {}", path)],
+                            ),
+                            Err(err) => {
+                                PopupMsg::new_state(ctx, "Export failed", vec![err.to_string()])
+                            }
+                        },
+                    );
+                }
                 "Back to challenges" => {
                     self.chose_back_to_challenges = true;
                     if app.primary.map.unsaved_edits() {
@@ -2,20 +2,24 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use maplit::btreeset;
 
-use abstutil::prettyprint_usize;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Timer};
 use geom::{Duration, Time};
-use map_gui::tools::{checkbox_per_mode, grey_out_map, CityPicker};
-use sim::SlidingWindow;
-use synthpop::{ScenarioModifier, TripMode};
-use widgetry::tools::{ChooseSomething, PopupMsg, URLManager};
+use map_gui::tools::{checkbox_per_mode, color_for_mode, grey_out_map, CityPicker};
+use map_model::{BuildingID, OffstreetParking};
+use sim::{SlidingWindow, WarmStart};
+use synthpop::{OccupancyMix, ParkAndRide, PickUpDropOff, Scenario, ScenarioModifier, TripMode};
+use widgetry::tools::{ChooseSomething, PopupMsg, PromptInput, URLManager};
 use widgetry::{
-    lctrl, Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, LinePlot, Outcome,
-    Panel, PlotOptions, Series, SimpleState, Slider, Spinner, State, Text, TextExt,
-    VerticalAlignment, Widget,
+    include_labeled_bytes, lctrl, Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
+    LinePlot, Outcome, Panel, PlotOptions, Series, SimpleState, Slider, Spinner, State, Text,
+    TextExt, Toggle, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
 use crate::edit::EditMode;
+use crate::sandbox::gameplay::development;
 use crate::sandbox::gameplay::freeform::ChangeScenario;
 use crate::sandbox::gameplay::{GameplayMode, GameplayState};
 use crate::sandbox::{Actions, SandboxControls, SandboxMode, TimeWarpScreen};
@@ -108,8 +112,13 @@ impl GameplayState for PlayScenario {
                 "edit traffic patterns" => {
                     Some(Transition::Push(EditScenarioModifiers::new_state(
                         ctx,
+                        app,
                         self.scenario_name.clone(),
-                        self.modifiers.clone(),
+                        EditScenarioModifiers::load_pipeline(
+                            app,
+                            &self.scenario_name,
+                            self.modifiers.clone(),
+                        ),
                     )))
                 }
                 "save scenario" => {
@@ -128,8 +137,40 @@ impl GameplayState for PlayScenario {
                 "When do trips start?" => {
                     Some(Transition::Push(DepartureSummary::new_state(ctx, app)))
                 }
+                "save parked cars for the next day" => {
+                    let warm_start = WarmStart::new(&app.primary.sim);
+                    warm_start.save();
+                    self.recreate_panels(ctx, app);
+                    Some(Transition::Push(PopupMsg::new_state(
+                        ctx,
+                        "Saved",
+                        vec![
+                            format!("Saved {}", warm_start.describe()),
+                            "Start from the previous day to begin the next run with these cars \
+                             parked in the same places."
+                                .to_string(),
+                        ],
+                    )))
+                }
                 _ => unreachable!(),
             },
+            Outcome::Changed(_) => {
+                app.primary.current_flags.sim_flags.warm_start =
+                    if self.top_right.is_checked("start from the previous day") {
+                        Some(abstio::path_warm_start(
+                            app.primary.map.get_name(),
+                            &self.scenario_name,
+                        ))
+                    } else {
+                        None
+                    };
+                let mode = GameplayMode::PlayScenario(
+                    app.primary.map.get_name().clone(),
+                    self.scenario_name.clone(),
+                    self.modifiers.clone(),
+                );
+                Some(Transition::Replace(SandboxMode::simple_new(app, mode)))
+            }
             _ => None,
         }
     }
@@ -160,6 +201,30 @@ impl GameplayState for PlayScenario {
                     .text_widget(ctx)
                     .centered_vert(),
             ]));
+
+            // Multi-day runs can start with cars parked wherever the last run left them
+            let warm_start_path =
+                abstio::path_warm_start(app.primary.map.get_name(), &self.scenario_name);
+            extra.push(Widget::row(vec![
+                if abstio::file_exists(&warm_start_path) {
+                    Toggle::checkbox(
+                        ctx,
+                        "start from the previous day",
+                        None,
+                        app.primary.current_flags.sim_flags.warm_start.as_ref()
+                            == Some(&warm_start_path),
+                    )
+                    .centered_vert()
+                } else {
+                    Widget::nothing()
+                },
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/save.svg")
+                    .label_text("save parked cars for the next day")
+                    .build_def(ctx)
+                    .centered_vert(),
+            ]));
         }
         if !abstio::file_exists(abstio::path_scenario(
             app.primary.map.get_name(),
@@ -208,17 +273,64 @@ impl GameplayState for PlayScenario {
     }
 }
 
+/// One step in the pipeline of modifiers applied to a scenario. Disabled steps are kept around,
+/// so they can be toggled back on.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub modifier: ScenarioModifier,
+    pub enabled: bool,
+}
+
+impl PipelineStep {
+    fn active(steps: &[PipelineStep]) -> Vec<ScenarioModifier> {
+        steps
+            .iter()
+            .filter(|step| step.enabled)
+            .map(|step| step.modifier.clone())
+            .collect()
+    }
+}
+
+/// A pipeline saved to reuse later
+#[derive(Serialize, Deserialize)]
+struct ModifierPreset {
+    name: String,
+    steps: Vec<PipelineStep>,
+}
+
 struct EditScenarioModifiers {
     scenario_name: String,
-    modifiers: Vec<ScenarioModifier>,
+    steps: Vec<PipelineStep>,
     panel: Panel,
 }
 
 impl EditScenarioModifiers {
+    /// Pick up the pipeline last applied to this scenario, including disabled steps, as long as
+    /// it matches the modifiers in effect.
+    fn load_pipeline(
+        app: &App,
+        scenario_name: &str,
+        modifiers: Vec<ScenarioModifier>,
+    ) -> Vec<PipelineStep> {
+        if let Some((ref name, ref steps)) = app.session.scenario_pipeline {
+            if name == scenario_name && PipelineStep::active(steps) == modifiers {
+                return steps.clone();
+            }
+        }
+        modifiers
+            .into_iter()
+            .map(|modifier| PipelineStep {
+                modifier,
+                enabled: true,
+            })
+            .collect()
+    }
+
     pub fn new_state(
         ctx: &mut EventCtx,
+        app: &App,
         scenario_name: String,
-        modifiers: Vec<ScenarioModifier>,
+        steps: Vec<PipelineStep>,
     ) -> Box<dyn State<App>> {
         let mut rows = vec![
             Line("Modify traffic patterns")
@@ -232,18 +344,70 @@ impl EditScenarioModifiers {
             .wrap_to_pct(ctx, 50)
             .into_widget(ctx),
         ];
-        for (idx, m) in modifiers.iter().enumerate() {
-            rows.push(
+        for (idx, step) in steps.iter().enumerate() {
+            let n = idx + 1;
+            let mut row = vec![Toggle::custom_checkbox(
+                ctx,
+                &format!("enable modifier {}", n),
+                vec![if step.enabled {
+                    Line(format!("{}. {}", n, step.modifier.describe()))
+                } else {
+                    Line(format!("{}. {}", n, step.modifier.describe())).secondary()
+                }],
+                None,
+                step.enabled,
+            )
+            .centered_vert()];
+            match step.modifier {
+                ScenarioModifier::RepeatDays(days)
+                | ScenarioModifier::RepeatDaysNoise { days, .. } => {
+                    row.push(
+                        Spinner::widget(ctx, format!("days for modifier {}", n), (2, 14), days, 1)
+                            .centered_vert(),
+                    );
+                }
+                ScenarioModifier::ChangeMode { .. } | ScenarioModifier::SetParkAndRide(_) => {
+                    row.push(
+                        ctx.style()
+                            .btn_plain
+                            .icon("system/assets/tools/pencil.svg")
+                            .build_widget(ctx, format!("edit modifier {}", n))
+                            .centered_vert(),
+                    );
+                }
+                ScenarioModifier::AddExtraTrips(_)
+                | ScenarioModifier::SetVehicleMix(_)
+                | ScenarioModifier::SetPickUpDropOff(_)
+                | ScenarioModifier::AssignOccupancy(_)
+                | ScenarioModifier::FormCarpools(_) => {}
+            }
+            row.push(
                 Widget::row(vec![
-                    m.describe().text_widget(ctx).centered_vert(),
+                    ctx.style()
+                        .btn_plain
+                        .icon_bytes(include_labeled_bytes!(
+                            "../../../../../widgetry/icons/arrow_up.svg"
+                        ))
+                        .disabled(idx == 0)
+                        .build_widget(ctx, format!("move modifier {} up", n)),
+                    ctx.style()
+                        .btn_plain
+                        .icon_bytes(include_labeled_bytes!(
+                            "../../../../../widgetry/icons/arrow_down.svg"
+                        ))
+                        .disabled(idx == steps.len() - 1)
+                        .build_widget(ctx, format!("move modifier {} down", n)),
                     ctx.style()
                         .btn_solid_destructive
                         .icon("system/assets/tools/trash.svg")
-                        .build_widget(ctx, format!("delete modifier {}", idx + 1))
-                        .align_right(),
+                        .build_widget(ctx, format!("delete modifier {}", n)),
                 ])
-                .padding(10)
-                .outline(ctx.style().section_outline),
+                .align_right(),
+            );
+            rows.push(
+                Widget::row(row)
+                    .padding(10)
+                    .outline(ctx.style().section_outline),
             );
         }
         rows.push(
@@ -258,6 +422,41 @@ impl EditScenarioModifiers {
                 .text("Add extra new trips")
                 .build_def(ctx),
         );
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text("Designate park-and-ride garages")
+                .build_def(ctx),
+        );
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text("Add taxi and drop-off trips")
+                .build_def(ctx),
+        );
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text("Assign car occupancy")
+                .build_def(ctx),
+        );
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text("Form household carpools")
+                .build_def(ctx),
+        );
+        rows.push(
+            ctx.style()
+                .btn_outline
+                .text("Add a proposed development")
+                .disabled(list_developments(app).is_empty())
+                .disabled_tooltip(format!(
+                    "Put GeoJSON site plans in {}",
+                    abstio::path_all_developments(app.primary.map.get_name())
+                ))
+                .build_def(ctx),
+        );
         rows.push(Widget::row(vec![
             Spinner::widget(ctx, "repeat_days", (2, 14), 2, 1),
             ctx.style()
@@ -272,6 +471,25 @@ impl EditScenarioModifiers {
                 .text("Repeat schedule multiple days with +/- 10 minutes of noise")
                 .build_def(ctx),
         ]));
+        rows.push(Widget::row(vec![
+            ctx.style()
+                .btn_outline
+                .icon_text("system/assets/tools/save.svg", "Save as preset")
+                .disabled(steps.is_empty())
+                .build_widget(ctx, "save preset"),
+            ctx.style()
+                .btn_outline
+                .icon_text("system/assets/tools/folder.svg", "Load preset")
+                .disabled(
+                    abstio::list_all_objects(abstio::path_all_scenario_modifier_presets(
+                        app.primary.map.get_name(),
+                    ))
+                    .is_empty(),
+                )
+                .build_widget(ctx, "load preset"),
+        ]));
+        rows.push(Widget::horiz_separator(ctx, 1.0));
+        rows.push(preview(ctx, app, &steps));
         rows.push(Widget::horiz_separator(ctx, 1.0));
         rows.push(
             Widget::row(vec![
@@ -291,110 +509,283 @@ impl EditScenarioModifiers {
 
         Box::new(EditScenarioModifiers {
             scenario_name,
-            modifiers,
+            steps,
             panel: Panel::new_builder(Widget::col(rows))
                 .exact_size_percent(80, 80)
                 .build(ctx),
         })
     }
+
+    fn rebuild(&self, ctx: &mut EventCtx, app: &App) -> Transition {
+        Transition::Replace(EditScenarioModifiers::new_state(
+            ctx,
+            app,
+            self.scenario_name.clone(),
+            self.steps.clone(),
+        ))
+    }
+
+    fn push_modifier(&mut self, modifier: ScenarioModifier) {
+        self.steps.push(PipelineStep {
+            modifier,
+            enabled: true,
+        });
+    }
 }
 
 impl State<App> for EditScenarioModifiers {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
-        if let Outcome::Clicked(x) = self.panel.event(ctx) {
-            match x.as_ref() {
-                "Discard changes" => {
-                    return Transition::Pop;
-                }
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "Discard changes" => Transition::Pop,
                 "Apply" => {
+                    let modifiers = PipelineStep::active(&self.steps);
                     info!("To apply these modifiers in the future:");
                     info!(
                         "--scenario_modifiers='{}'",
-                        abstutil::to_json_terse(&self.modifiers)
+                        abstutil::to_json_terse(&modifiers)
                     );
+                    app.session.scenario_pipeline =
+                        Some((self.scenario_name.clone(), self.steps.clone()));
 
-                    return Transition::Multi(vec![
+                    Transition::Multi(vec![
                         Transition::Pop,
                         Transition::Replace(SandboxMode::simple_new(
                             app,
                             GameplayMode::PlayScenario(
                                 app.primary.map.get_name().clone(),
                                 self.scenario_name.clone(),
-                                self.modifiers.clone(),
+                                modifiers,
                             ),
                         )),
-                    ]);
+                    ])
                 }
-                "Change trip mode" => {
-                    return Transition::Push(ChangeMode::new_state(
+                "Change trip mode" => Transition::Push(ChangeMode::new_state(
+                    ctx,
+                    app,
+                    self.scenario_name.clone(),
+                    self.steps.clone(),
+                    None,
+                )),
+                "Designate park-and-ride garages" => {
+                    Transition::Push(ChooseParkAndRide::new_state(
                         ctx,
                         app,
                         self.scenario_name.clone(),
-                        self.modifiers.clone(),
-                    ));
+                        self.steps.clone(),
+                        None,
+                    ))
                 }
-                "Add extra new trips" => {
-                    return Transition::Push(ChooseSomething::new_state(
+                "Add a proposed development" => {
+                    let scenario_name = self.scenario_name.clone();
+                    let steps = self.steps.clone();
+                    Transition::Push(ChooseSomething::new_state(
                         ctx,
-                        "Which trips do you want to add in?",
-                        // TODO Exclude weekday?
-                        Choice::strings(abstio::list_all_objects(abstio::path_all_scenarios(
-                            app.primary.map.get_name(),
-                        ))),
-                        Box::new(|name, _, _| {
-                            Transition::Multi(vec![
-                                Transition::Pop,
-                                Transition::ConsumeState(Box::new(|state, ctx, _| {
-                                    let mut state =
-                                        state.downcast::<EditScenarioModifiers>().ok().unwrap();
-                                    state.modifiers.push(ScenarioModifier::AddExtraTrips(name));
-                                    vec![EditScenarioModifiers::new_state(
-                                        ctx,
-                                        state.scenario_name,
-                                        state.modifiers,
-                                    )]
-                                })),
-                            ])
+                        "Which development do you want to build?",
+                        list_developments(app)
+                            .into_iter()
+                            .map(|path| Choice::new(abstutil::basename(&path), path))
+                            .collect(),
+                        Box::new(move |path, ctx, app| {
+                            match development::add_development(ctx, app, &path) {
+                                // Like "Apply", restart the scenario. The loader reuses the
+                                // scenario with the new people added.
+                                Ok((num_bldgs, num_people)) => {
+                                    info!(
+                                        "Added {} buildings and {} people from {}",
+                                        num_bldgs, num_people, path
+                                    );
+                                    app.session.scenario_pipeline =
+                                        Some((scenario_name.clone(), steps.clone()));
+                                    Transition::Multi(vec![
+                                        Transition::Pop,
+                                        Transition::Pop,
+                                        Transition::Replace(SandboxMode::simple_new(
+                                            app,
+                                            GameplayMode::PlayScenario(
+                                                app.primary.map.get_name().clone(),
+                                                scenario_name,
+                                                PipelineStep::active(&steps),
+                                            ),
+                                        )),
+                                    ])
+                                }
+                                Err(err) => Transition::Replace(PopupMsg::new_state(
+                                    ctx,
+                                    "Error",
+                                    vec![format!("Couldn't add {}: {}", path, err)],
+                                )),
+                            }
                         }),
-                    ));
+                    ))
+                }
+                "Add extra new trips" => Transition::Push(ChooseSomething::new_state(
+                    ctx,
+                    "Which trips do you want to add in?",
+                    // TODO Exclude weekday?
+                    Choice::strings(abstio::list_all_objects(abstio::path_all_scenarios(
+                        app.primary.map.get_name(),
+                    ))),
+                    Box::new(|name, _, _| {
+                        Transition::Multi(vec![
+                            Transition::Pop,
+                            Transition::ConsumeState(Box::new(|state, ctx, app| {
+                                let mut state =
+                                    state.downcast::<EditScenarioModifiers>().ok().unwrap();
+                                state.push_modifier(ScenarioModifier::AddExtraTrips(name));
+                                vec![EditScenarioModifiers::new_state(
+                                    ctx,
+                                    app,
+                                    state.scenario_name,
+                                    state.steps,
+                                )]
+                            })),
+                        ])
+                    }),
+                )),
+                "Add taxi and drop-off trips" => {
+                    self.push_modifier(
+                        ScenarioModifier::SetPickUpDropOff(PickUpDropOff::typical()),
+                    );
+                    self.rebuild(ctx, app)
+                }
+                "Assign car occupancy" => {
+                    self.push_modifier(ScenarioModifier::AssignOccupancy(OccupancyMix::default()));
+                    self.rebuild(ctx, app)
+                }
+                "Form household carpools" => {
+                    self.push_modifier(ScenarioModifier::FormCarpools(Duration::minutes(10)));
+                    self.rebuild(ctx, app)
                 }
                 "Repeat schedule multiple days" => {
-                    self.modifiers.push(ScenarioModifier::RepeatDays(
+                    self.push_modifier(ScenarioModifier::RepeatDays(
                         self.panel.spinner("repeat_days"),
                     ));
-                    return Transition::Replace(EditScenarioModifiers::new_state(
-                        ctx,
-                        self.scenario_name.clone(),
-                        self.modifiers.clone(),
-                    ));
+                    self.rebuild(ctx, app)
                 }
                 "Repeat schedule multiple days with +/- 10 minutes of noise" => {
-                    self.modifiers.push(ScenarioModifier::RepeatDaysNoise {
+                    self.push_modifier(ScenarioModifier::RepeatDaysNoise {
                         days: self.panel.spinner("repeat_days_noise"),
                         departure_time_noise: Duration::minutes(10),
                     });
-                    return Transition::Replace(EditScenarioModifiers::new_state(
+                    self.rebuild(ctx, app)
+                }
+                "save preset" => {
+                    let steps = self.steps.clone();
+                    Transition::Push(PromptInput::new_state(
                         ctx,
-                        self.scenario_name.clone(),
-                        self.modifiers.clone(),
-                    ));
+                        "Name this preset",
+                        String::new(),
+                        Box::new(move |name, ctx, app| {
+                            let path = abstio::path_scenario_modifier_preset(
+                                app.primary.map.get_name(),
+                                &name,
+                            );
+                            abstio::write_json(path, &ModifierPreset { name, steps });
+                            Transition::Replace(PopupMsg::new_state(
+                                ctx,
+                                "Saved",
+                                vec!["Load this preset for any scenario on this map"],
+                            ))
+                        }),
+                    ))
                 }
+                "load preset" => Transition::Push(ChooseSomething::new_state(
+                    ctx,
+                    "Load which preset?",
+                    Choice::strings(abstio::list_all_objects(
+                        abstio::path_all_scenario_modifier_presets(app.primary.map.get_name()),
+                    )),
+                    Box::new(|name, _, app| {
+                        let preset = abstio::maybe_read_json::<ModifierPreset>(
+                            abstio::path_scenario_modifier_preset(
+                                app.primary.map.get_name(),
+                                &name,
+                            ),
+                            &mut Timer::throwaway(),
+                        );
+                        Transition::Multi(vec![
+                            Transition::Pop,
+                            Transition::ConsumeState(Box::new(|state, ctx, app| {
+                                let state = state.downcast::<EditScenarioModifiers>().ok().unwrap();
+                                match preset {
+                                    Ok(preset) => vec![EditScenarioModifiers::new_state(
+                                        ctx,
+                                        app,
+                                        state.scenario_name,
+                                        preset.steps,
+                                    )],
+                                    Err(err) => vec![
+                                        state,
+                                        PopupMsg::new_state(
+                                            ctx,
+                                            "Error",
+                                            vec![format!("Couldn't load preset: {}", err)],
+                                        ),
+                                    ],
+                                }
+                            })),
+                        ])
+                    }),
+                )),
                 x => {
                     if let Some(x) = x.strip_prefix("delete modifier ") {
-                        self.modifiers.remove(x.parse::<usize>().unwrap() - 1);
-                        return Transition::Replace(EditScenarioModifiers::new_state(
-                            ctx,
-                            self.scenario_name.clone(),
-                            self.modifiers.clone(),
-                        ));
+                        self.steps.remove(x.parse::<usize>().unwrap() - 1);
+                    } else if let Some(x) = x
+                        .strip_prefix("move modifier ")
+                        .and_then(|x| x.strip_suffix(" up"))
+                    {
+                        let idx = x.parse::<usize>().unwrap() - 1;
+                        self.steps.swap(idx, idx - 1);
+                    } else if let Some(x) = x
+                        .strip_prefix("move modifier ")
+                        .and_then(|x| x.strip_suffix(" down"))
+                    {
+                        let idx = x.parse::<usize>().unwrap() - 1;
+                        self.steps.swap(idx, idx + 1);
+                    } else if let Some(x) = x.strip_prefix("edit modifier ") {
+                        let idx = x.parse::<usize>().unwrap() - 1;
+                        let scenario_name = self.scenario_name.clone();
+                        let steps = self.steps.clone();
+                        return Transition::Push(match self.steps[idx].modifier {
+                            ScenarioModifier::SetParkAndRide(_) => ChooseParkAndRide::new_state(
+                                ctx,
+                                app,
+                                scenario_name,
+                                steps,
+                                Some(idx),
+                            ),
+                            _ => ChangeMode::new_state(ctx, app, scenario_name, steps, Some(idx)),
+                        });
                     } else {
                         unreachable!()
                     }
+                    self.rebuild(ctx, app)
+                }
+            },
+            Outcome::Changed(x) => {
+                if let Some(x) = x.strip_prefix("enable modifier ") {
+                    let idx = x.parse::<usize>().unwrap() - 1;
+                    self.steps[idx].enabled =
+                        self.panel.is_checked(&format!("enable modifier {}", x));
+                    self.rebuild(ctx, app)
+                } else if let Some(x) = x.strip_prefix("days for modifier ") {
+                    let idx = x.parse::<usize>().unwrap() - 1;
+                    let new_days: usize = self.panel.spinner(&format!("days for modifier {}", x));
+                    match self.steps[idx].modifier {
+                        ScenarioModifier::RepeatDays(ref mut days)
+                        | ScenarioModifier::RepeatDaysNoise { ref mut days, .. } => {
+                            *days = new_days;
+                        }
+                        _ => unreachable!(),
+                    }
+                    self.rebuild(ctx, app)
+                } else {
+                    Transition::Keep
                 }
             }
+            _ => Transition::Keep,
         }
-
-        Transition::Keep
     }
 
     fn draw(&self, g: &mut GfxCtx, app: &App) {
@@ -403,10 +794,115 @@ impl State<App> for EditScenarioModifiers {
     }
 }
 
+/// Preview the trips resulting from applying the enabled steps, compared to the original
+/// scenario.
+fn list_developments(app: &App) -> Vec<String> {
+    abstio::list_dir(abstio::path_all_developments(app.primary.map.get_name()))
+        .into_iter()
+        .filter(|path| path.ends_with(".geojson"))
+        .collect()
+}
+
+fn preview(ctx: &mut EventCtx, app: &App, steps: &[PipelineStep]) -> Widget {
+    let before = match app.primary.scenario {
+        Some(ref scenario) => scenario.clone(),
+        None => {
+            return Widget::nothing();
+        }
+    };
+    // Use the same RNG as SandboxLoader, so the preview matches what'll be simulated
+    let mut rng = app.primary.current_flags.sim_flags.make_rng();
+    let mut after = before.clone();
+    for m in PipelineStep::active(steps) {
+        after = m.apply(&app.primary.map, after, &mut rng);
+    }
+
+    let count = |scenario: &Scenario| {
+        let mut per_mode: BTreeMap<TripMode, usize> = BTreeMap::new();
+        for person in &scenario.people {
+            for trip in &person.trips {
+                *per_mode.entry(trip.mode).or_insert(0) += 1;
+            }
+        }
+        per_mode
+    };
+    let before_counts = count(&before);
+    let after_counts = count(&after);
+
+    let mut txt = Text::from(Line("Resulting trips").small_heading());
+    for mode in TripMode::all() {
+        let old = before_counts.get(&mode).cloned().unwrap_or(0);
+        let new = after_counts.get(&mode).cloned().unwrap_or(0);
+        let mut line = vec![Line(format!("{}: {}", mode.noun(), prettyprint_usize(new)))];
+        if old != new {
+            line.push(Line(format!(" (originally {})", prettyprint_usize(old))).secondary());
+        }
+        txt.add_appended(line);
+    }
+    let (old_sov, new_sov) = (before.sov_share(), after.sov_share());
+    let mut line = vec![Line(format!(
+        "Driving alone: {:.1}% of people in cars",
+        new_sov * 100.0
+    ))];
+    if old_sov != new_sov {
+        line.push(Line(format!(" (originally {:.1}%)", old_sov * 100.0)).secondary());
+    }
+    txt.add_appended(line);
+
+    // Departures per hour, for each mode
+    let mut per_hour: BTreeMap<TripMode, BTreeMap<usize, usize>> = BTreeMap::new();
+    for person in &after.people {
+        for trip in &person.trips {
+            *per_hour
+                .entry(trip.mode)
+                .or_insert_with(BTreeMap::new)
+                .entry(trip.depart.get_hours())
+                .or_insert(0) += 1;
+        }
+    }
+    let last_hour = per_hour
+        .values()
+        .filter_map(|hours| hours.keys().max())
+        .max()
+        .cloned()
+        .unwrap_or(0);
+    let series = per_hour
+        .into_iter()
+        .map(|(mode, hours)| Series {
+            label: mode.noun().to_string(),
+            color: color_for_mode(app, mode),
+            pts: (0..=last_hour)
+                .map(|hour| {
+                    (
+                        Time::START_OF_DAY + Duration::hours(hour),
+                        hours.get(&hour).cloned().unwrap_or(0),
+                    )
+                })
+                .collect(),
+        })
+        .collect();
+
+    Widget::row(vec![
+        txt.into_widget(ctx),
+        Widget::col(vec![
+            "Departures per hour".text_widget(ctx),
+            LinePlot::new_widget(
+                ctx,
+                "departures per hour",
+                series,
+                PlotOptions::fixed(),
+                app.opts.units,
+            ),
+        ]),
+    ])
+}
+
 struct ChangeMode {
     panel: Panel,
     scenario_name: String,
-    modifiers: Vec<ScenarioModifier>,
+    steps: Vec<PipelineStep>,
+    // If set, replace this step instead of adding a new one
+    editing: Option<usize>,
     count_trips: CountTrips,
 }
 
@@ -415,11 +911,41 @@ impl ChangeMode {
         ctx: &mut EventCtx,
         app: &App,
         scenario_name: String,
-        modifiers: Vec<ScenarioModifier>,
+        steps: Vec<PipelineStep>,
+        editing: Option<usize>,
     ) -> Box<dyn State<App>> {
+        // Start from the step being edited, or some defaults
+        let (pct_ppl, from_modes, (depart_from, depart_to), to_mode) =
+            match editing.map(|idx| &steps[idx].modifier) {
+                Some(ScenarioModifier::ChangeMode {
+                    pct_ppl,
+                    departure_filter,
+                    from_modes,
+                    to_mode,
+                }) => {
+                    let end_of_day = app.primary.sim.get_end_of_day();
+                    (
+                        *pct_ppl,
+                        from_modes.clone(),
+                        (
+                            departure_filter.0.to_percent(end_of_day).min(1.0),
+                            departure_filter.1.to_percent(end_of_day).min(1.0),
+                        ),
+                        *to_mode,
+                    )
+                }
+                _ => (
+                    50,
+                    btreeset! { TripMode::Drive },
+                    (0.0, 0.3),
+                    Some(TripMode::Bike),
+                ),
+            };
+
         let mut state = ChangeMode {
             scenario_name,
-            modifiers,
+            steps,
+            editing,
             count_trips: CountTrips::new(app),
             panel: Panel::new_builder(Widget::col(vec![
                 Line("Change trip mode").small_heading().into_widget(ctx),
@@ -427,23 +953,28 @@ impl ChangeMode {
                     "Percent of people to modify:"
                         .text_widget(ctx)
                         .centered_vert(),
-                    Spinner::widget(ctx, "pct_ppl", (1, 100), 50_usize, 1),
+                    Spinner::widget(ctx, "pct_ppl", (1, 100), pct_ppl, 1),
                 ]),
                 "Types of trips to convert:".text_widget(ctx),
-                checkbox_per_mode(ctx, app, &btreeset! { TripMode::Drive }),
+                checkbox_per_mode(ctx, app, &from_modes),
                 Widget::row(vec![
                     "Departing from:".text_widget(ctx),
-                    Slider::area(ctx, 0.25 * ctx.canvas.window_width, 0.0, "depart from"),
+                    Slider::area(
+                        ctx,
+                        0.25 * ctx.canvas.window_width,
+                        depart_from,
+                        "depart from",
+                    ),
                 ]),
                 Widget::row(vec![
                     "Departing until:".text_widget(ctx),
-                    Slider::area(ctx, 0.25 * ctx.canvas.window_width, 0.3, "depart to"),
+                    Slider::area(ctx, 0.25 * ctx.canvas.window_width, depart_to, "depart to"),
                 ]),
                 "Matching trips:".text_widget(ctx).named("count"),
                 Widget::horiz_separator(ctx, 1.0),
                 Widget::row(vec![
                     "Change to trip type:".text_widget(ctx),
-                    Widget::dropdown(ctx, "to_mode", Some(TripMode::Bike), {
+                    Widget::dropdown(ctx, "to_mode", to_mode, {
                         let mut choices = vec![Choice::new("cancel trip", None)];
                         for m in TripMode::all() {
                             choices.push(Choice::new(m.ongoing_verb(), Some(m)));
@@ -538,19 +1069,28 @@ impl State<App> for ChangeMode {
                         ));
                     }
 
-                    let mut mods = self.modifiers.clone();
-                    mods.push(ScenarioModifier::ChangeMode {
+                    let mut steps = self.steps.clone();
+                    let modifier = ScenarioModifier::ChangeMode {
                         to_mode,
                         pct_ppl,
                         departure_filter,
                         from_modes,
-                    });
+                    };
+                    if let Some(idx) = self.editing {
+                        steps[idx].modifier = modifier;
+                    } else {
+                        steps.push(PipelineStep {
+                            modifier,
+                            enabled: true,
+                        });
+                    }
                     Transition::Multi(vec![
                         Transition::Pop,
                         Transition::Replace(EditScenarioModifiers::new_state(
                             ctx,
+                            app,
                             self.scenario_name.clone(),
-                            mods,
+                            steps,
                         )),
                     ])
                 }
@@ -570,6 +1110,153 @@ impl State<App> for ChangeMode {
     }
 }
 
+struct ChooseParkAndRide {
+    scenario_name: String,
+    steps: Vec<PipelineStep>,
+    // If set, replace this step instead of adding a new one
+    editing: Option<usize>,
+    config: ParkAndRide,
+    candidates: Vec<BuildingID>,
+}
+
+impl ChooseParkAndRide {
+    fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        scenario_name: String,
+        steps: Vec<PipelineStep>,
+        editing: Option<usize>,
+    ) -> Box<dyn State<App>> {
+        // Start from the step being edited, or whatever the scenario already uses
+        let config = match editing.map(|idx| &steps[idx].modifier) {
+            Some(ScenarioModifier::SetParkAndRide(config)) => config.clone(),
+            _ => app
+                .primary
+                .scenario
+                .as_ref()
+                .map(|s| s.park_and_ride.clone())
+                .unwrap_or_default(),
+        };
+        let map = &app.primary.map;
+        let candidates = ParkAndRide::candidates(map);
+
+        let mut col = vec![
+            Line("Designate park-and-ride garages")
+                .small_heading()
+                .into_widget(ctx),
+            Text::from(format!(
+                "Drivers going more than {} can leave their car at one of these garages and \
+                 continue by transit or bike, as long as it's roughly on the way and has free \
+                 spots.",
+                config.min_trip_distance
+            ))
+            .wrap_to_pct(ctx, 50)
+            .into_widget(ctx),
+        ];
+        if candidates.is_empty() {
+            col.push("This map has no public parking garages".text_widget(ctx));
+        }
+        for b in &candidates {
+            let bldg = map.get_b(*b);
+            let name = match bldg.parking {
+                OffstreetParking::PublicGarage(ref name, _) => name.clone(),
+                OffstreetParking::Private(_, _) => unreachable!(),
+            };
+            col.push(Toggle::custom_checkbox(
+                ctx,
+                &b.to_string(),
+                vec![
+                    Line(name),
+                    Line(format!(
+                        " ({} spots)",
+                        prettyprint_usize(bldg.num_parking_spots())
+                    ))
+                    .secondary(),
+                ],
+                None,
+                config.facilities.contains(b),
+            ));
+        }
+        col.push(
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_primary
+                    .text("Apply")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+                ctx.style()
+                    .btn_solid_destructive
+                    .text("Discard changes")
+                    .hotkey(Key::Escape)
+                    .build_def(ctx),
+            ])
+            .centered(),
+        );
+
+        let panel = Panel::new_builder(Widget::col(col))
+            .exact_size_percent(50, 80)
+            .build(ctx);
+        <dyn SimpleState<_>>::new_state(
+            panel,
+            Box::new(ChooseParkAndRide {
+                scenario_name,
+                steps,
+                editing,
+                config,
+                candidates,
+            }),
+        )
+    }
+}
+
+impl SimpleState<App> for ChooseParkAndRide {
+    fn on_click(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        x: &str,
+        panel: &mut Panel,
+    ) -> Transition {
+        match x {
+            "Discard changes" => Transition::Pop,
+            "Apply" => {
+                let mut config = self.config.clone();
+                config.facilities = self
+                    .candidates
+                    .iter()
+                    .filter(|b| panel.is_checked(&b.to_string()))
+                    .cloned()
+                    .collect();
+
+                let mut steps = self.steps.clone();
+                let modifier = ScenarioModifier::SetParkAndRide(config);
+                if let Some(idx) = self.editing {
+                    steps[idx].modifier = modifier;
+                } else {
+                    steps.push(PipelineStep {
+                        modifier,
+                        enabled: true,
+                    });
+                }
+                Transition::Multi(vec![
+                    Transition::Pop,
+                    Transition::Replace(EditScenarioModifiers::new_state(
+                        ctx,
+                        app,
+                        self.scenario_name.clone(),
+                        steps,
+                    )),
+                ])
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+    }
+}
+
 pub struct DepartureSummary {
     first_trip: Time,
 }
@@ -0,0 +1,451 @@
+use geom::{Distance, Duration, Time};
+use map_model::{
+    AmenityType, ApproachControl, BuildingID, IntersectionID, Map, PathConstraints, PathRequest,
+    PathStep, RoadID, TurnType,
+};
+use sim::{AgentType, Analytics};
+use widgetry::{
+    Color, EventCtx, GfxCtx, HorizontalAlignment, Image, Line, Outcome, Panel, State, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::challenges::cutscene::{CutsceneBuilder, ShowMessage};
+use crate::challenges::Challenge;
+use crate::edit::EditMode;
+use crate::sandbox::gameplay::{challenge_header, FinalScore, GameplayMode, GameplayState};
+use crate::sandbox::{Actions, SandboxControls};
+
+/// Homes this close to the school are in its catchment
+const CATCHMENT_RADIUS: Distance = Distance::const_meters(1000.0);
+/// Children can cross a road with at most this level of traffic stress on their own
+const MAX_SAFE_LTS: usize = 2;
+/// Any longer waiting for the walk signal, and children are tempted to cross against it
+const MAX_CROSSING_DELAY: Duration = Duration::const_seconds(45.0);
+/// The challenge ends when school starts
+const SCHOOL_BELL: Duration = Duration::const_seconds(9.0 * 3600.0);
+
+pub struct SchoolRun {
+    top_right: Panel,
+    mode: GameplayMode,
+    /// Percent of homes in the catchment with a safe route to school
+    goal: usize,
+    school: Option<BuildingID>,
+    catchment: Vec<BuildingID>,
+    score: Score,
+    /// Recalculate the score when the map is edited
+    edits_key: usize,
+    time: Time,
+    done: bool,
+}
+
+#[derive(Default)]
+struct Score {
+    safe_walk: usize,
+    safe_bike: usize,
+    /// Homes with at least one safe way to get to school
+    safe_either: usize,
+}
+
+impl SchoolRun {
+    pub fn new_state(ctx: &mut EventCtx, app: &App, goal: usize) -> Box<dyn GameplayState> {
+        let map = &app.primary.map;
+        let school = find_school(map);
+        let catchment = school
+            .map(|school| find_catchment(map, school))
+            .unwrap_or_default();
+        if school.is_none() {
+            warn!(
+                "No school on {}; the school run challenge can't be scored",
+                map.get_name().describe()
+            );
+        }
+        Box::new(SchoolRun {
+            top_right: Panel::empty(ctx),
+            mode: GameplayMode::SchoolRun(goal),
+            goal,
+            school,
+            catchment,
+            score: Score::default(),
+            // Force the first calculation
+            edits_key: usize::MAX,
+            time: Time::START_OF_DAY,
+            done: false,
+        })
+    }
+
+    pub fn cutscene_pt1(ctx: &mut EventCtx, _: &App, mode: &GameplayMode) -> Box<dyn State<App>> {
+        CutsceneBuilder::new("School run")
+            .boss("Have you seen the drop-off line at the elementary school? It's a parking lot.")
+            .player("Parents say it's too dangerous for their kids to walk or bike.")
+            .boss("Then make it not dangerous. The PTA is meeting tomorrow.")
+            .player("Crossings, quieter streets, maybe a School Street out front...")
+            .boss("Whatever it takes. Tell me how many families could send their kids on foot.")
+            .build(ctx, cutscene_task(mode))
+    }
+
+    pub fn cutscene_pt2(ctx: &mut EventCtx, _: &App, mode: &GameplayMode) -> Box<dyn State<App>> {
+        CutsceneBuilder::new("School run: part 2")
+            .boss("The PTA loved it. Now the principal wants every kid to get there safely.")
+            .player("Every kid? Some of them live across the arterial!")
+            .boss("Then figure out how to get them across it.")
+            .player("(The easy streets are done. Time for the hard ones.)")
+            .build(ctx, cutscene_task(mode))
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let school = match self.school {
+            Some(b) => b,
+            None => {
+                return;
+            }
+        };
+        let map = &app.primary.map;
+        let analytics = app.primary.sim.get_analytics();
+        let catchment = &self.catchment;
+        self.score = ctx.loading_screen("check routes to school", |_, timer| {
+            let mut score = Score::default();
+            timer.start_iter("check homes", catchment.len());
+            for home in catchment {
+                timer.next();
+                let walk = safe_to_walk(map, analytics, *home, school);
+                let bike = safe_to_bike(map, *home, school);
+                if walk {
+                    score.safe_walk += 1;
+                }
+                if bike {
+                    score.safe_bike += 1;
+                }
+                if walk || bike {
+                    score.safe_either += 1;
+                }
+            }
+            score
+        });
+        self.edits_key = map.get_edits_change_key();
+    }
+
+    fn pct_safe(&self) -> f64 {
+        pct(self.score.safe_either, self.catchment.len())
+    }
+}
+
+impl GameplayState for SchoolRun {
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        _: &mut SandboxControls,
+        _: &mut Actions,
+    ) -> Option<Transition> {
+        if self.edits_key != app.primary.map.get_edits_change_key() {
+            self.recalculate(ctx, app);
+            self.recreate_panels(ctx, app);
+        }
+
+        if self.time != app.primary.sim.time() && !self.done {
+            self.time = app.primary.sim.time();
+            if self.time >= Time::START_OF_DAY + SCHOOL_BELL || app.primary.sim.is_done() {
+                self.done = true;
+                // Crossing delays are measured from the morning's traffic
+                self.recalculate(ctx, app);
+                self.recreate_panels(ctx, app);
+                return Some(Transition::Push(final_score(
+                    ctx,
+                    self.mode.clone(),
+                    self.pct_safe(),
+                    self.goal,
+                )));
+            }
+        }
+
+        if let Outcome::Clicked(x) = self.top_right.event(ctx) {
+            match x.as_ref() {
+                "edit map" => {
+                    return Some(Transition::Push(EditMode::new_state(
+                        ctx,
+                        app,
+                        self.mode.clone(),
+                    )));
+                }
+                "instructions" => {
+                    let contents = (cutscene_task(&self.mode))(ctx);
+                    return Some(Transition::Push(ShowMessage::new_state(
+                        ctx,
+                        contents,
+                        Color::WHITE,
+                    )));
+                }
+                "hint" => {
+                    let mut txt = Text::from("Hints");
+                    txt.add_line("");
+                    txt.add_line("Children can cross quiet local streets anywhere.");
+                    txt.add_line("Busy roads need a signal with a short wait, or an all-way stop.");
+                    txt.add_line("Modal filters and School Streets make local streets calm.");
+                    txt.add_line("Protected bike lanes make busier roads safe to cycle along.");
+                    let contents = txt.into_widget(ctx);
+                    return Some(Transition::Push(ShowMessage::new_state(
+                        ctx,
+                        contents,
+                        app.cs.panel_bg,
+                    )));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        None
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.top_right.draw(g);
+    }
+
+    fn recreate_panels(&mut self, ctx: &mut EventCtx, _: &App) {
+        let total = self.catchment.len();
+        let mut txt = Text::new();
+        if self.school.is_none() {
+            txt.add_line(Line("There's no school on this map!").fg(Color::RED));
+        } else {
+            txt.add_line(format!(
+                "{:.1}% of {} homes have a safe route to school",
+                self.pct_safe(),
+                total
+            ));
+            txt.add_line(
+                Line(format!(
+                    "{} can walk safely, {} can bike safely",
+                    self.score.safe_walk, self.score.safe_bike
+                ))
+                .secondary(),
+            );
+        }
+
+        self.top_right = Panel::new_builder(Widget::col(vec![
+            challenge_header(ctx, "School run"),
+            Widget::row(vec![
+                format!("Give {}% of homes a safe route to school by 9am", self.goal)
+                    .text_widget(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_plain
+                    .icon_text("system/assets/tools/lightbulb.svg", "Hint")
+                    .build_widget(ctx, "hint")
+                    .align_right(),
+            ]),
+            txt.into_widget(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx);
+    }
+}
+
+/// Use the first school on the map, so the challenge is deterministic
+fn find_school(map: &Map) -> Option<BuildingID> {
+    map.all_buildings()
+        .iter()
+        .find(|b| b.has_amenity(AmenityType::School))
+        .map(|b| b.id)
+}
+
+fn find_catchment(map: &Map, school: BuildingID) -> Vec<BuildingID> {
+    let center = map.get_b(school).label_center;
+    map.all_buildings()
+        .iter()
+        .filter(|b| {
+            b.id != school
+                && b.bldg_type.has_residents()
+                && b.label_center.dist_to(center) <= CATCHMENT_RADIUS
+        })
+        .map(|b| b.id)
+        .collect()
+}
+
+/// Can a child walk from home to school, only crossing roads safely?
+fn safe_to_walk(map: &Map, analytics: &Analytics, home: BuildingID, school: BuildingID) -> bool {
+    let path = match PathRequest::between_buildings(map, home, school, PathConstraints::Pedestrian)
+        .and_then(|req| map.pathfind(req).ok())
+    {
+        Some(path) => path,
+        None => {
+            return false;
+        }
+    };
+    for step in path.get_steps() {
+        if let PathStep::Turn(t) | PathStep::ContraflowTurn(t) = step {
+            let turn = map.get_t(*t);
+            if turn.turn_type.pedestrian_crossing()
+                && !safe_crossing(map, analytics, t.parent, t.src.road, turn.turn_type)
+            {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn safe_crossing(
+    map: &Map,
+    analytics: &Analytics,
+    i: IntersectionID,
+    crossed: RoadID,
+    turn_type: TurnType,
+) -> bool {
+    if map.get_r(crossed).level_of_traffic_stress(map) <= MAX_SAFE_LTS {
+        return true;
+    }
+    if let Some(signal) = map.maybe_get_traffic_signal(i) {
+        return pedestrian_delay(analytics, i)
+            .unwrap_or_else(|| signal.simple_cycle_duration() / 2.0)
+            <= MAX_CROSSING_DELAY;
+    }
+    // Drivers have to stop before a marked crosswalk, but may roll through an unmarked one
+    turn_type == TurnType::Crosswalk
+        && map
+            .maybe_get_stop_sign(i)
+            .and_then(|ss| ss.roads.get(&crossed))
+            .map(|r| r.control == ApproachControl::Stop)
+            .unwrap_or(false)
+}
+
+/// The mean time pedestrians have waited to cross here so far
+fn pedestrian_delay(analytics: &Analytics, i: IntersectionID) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut cnt = 0;
+    for (_, _, dt, agent_type) in analytics.intersection_delays.get(&i)? {
+        if *agent_type == AgentType::Pedestrian {
+            total += *dt;
+            cnt += 1;
+        }
+    }
+    if cnt == 0 {
+        None
+    } else {
+        Some(total / (cnt as f64))
+    }
+}
+
+/// Can a child bike from home to school, only using low-stress roads and intersections?
+fn safe_to_bike(map: &Map, home: BuildingID, school: BuildingID) -> bool {
+    let path = match PathRequest::between_buildings(map, home, school, PathConstraints::Bike)
+        .and_then(|req| map.pathfind(req).ok())
+    {
+        Some(path) => path,
+        None => {
+            return false;
+        }
+    };
+    for step in path.get_steps() {
+        match step {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                if map.get_r(l.road).level_of_traffic_stress(map) > MAX_SAFE_LTS {
+                    return false;
+                }
+            }
+            PathStep::Turn(t) | PathStep::ContraflowTurn(t) => {
+                let i = map.get_i(t.parent);
+                if !i.is_traffic_signal()
+                    && i.roads
+                        .iter()
+                        .any(|r| map.get_r(*r).level_of_traffic_stress(map) > MAX_SAFE_LTS)
+                {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+fn pct(x: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    100.0 * (x as f64) / (total as f64)
+}
+
+fn final_score(
+    ctx: &mut EventCtx,
+    mode: GameplayMode,
+    pct_safe: f64,
+    goal: usize,
+) -> Box<dyn State<App>> {
+    let mut next_mode: Option<GameplayMode> = None;
+
+    let msg = if pct_safe < goal as f64 {
+        format!(
+            "Only {:.1}% of families could send their kids to school safely. The PTA was hoping \
+             for {}%.",
+            pct_safe, goal
+        )
+    } else {
+        next_mode = Challenge::find(&mode).1.map(|c| c.gameplay);
+
+        format!(
+            "{:.1}% of families can send their kids to school on foot or by bike! Maybe the \
+             drop-off line will finally shrink.",
+            pct_safe
+        )
+    };
+
+    FinalScore::new_state(ctx, msg, mode, next_mode, None)
+}
+
+fn cutscene_task(mode: &GameplayMode) -> Box<dyn Fn(&mut EventCtx) -> Widget> {
+    let goal = match mode {
+        GameplayMode::SchoolRun(goal) => *goal,
+        _ => unreachable!(),
+    };
+
+    Box::new(move |ctx| {
+        let icon_builder = Image::empty().color(Color::BLACK).dims(50.0);
+        Widget::custom_col(vec![
+            Text::from_multiline(vec![
+                Line(format!(
+                    "Give {}% of homes near the school a safe way for children to walk or bike \
+                     there",
+                    goal
+                ))
+                .fg(Color::BLACK),
+                Line("Add crossings, School Streets, and traffic calming.").fg(Color::BLACK),
+            ])
+            .into_widget(ctx)
+            .margin_below(30),
+            Widget::row(vec![
+                Widget::col(vec![
+                    Line("Time").fg(Color::BLACK).into_widget(ctx),
+                    icon_builder
+                        .clone()
+                        .source_path("system/assets/tools/time.svg")
+                        .into_widget(ctx),
+                    Line("Until 9am").fg(Color::BLACK).into_widget(ctx),
+                ]),
+                Widget::col(vec![
+                    Line("Goal").fg(Color::BLACK).into_widget(ctx),
+                    icon_builder
+                        .clone()
+                        .source_path("system/assets/tools/location.svg")
+                        .into_widget(ctx),
+                    Text::from_multiline(vec![
+                        Line("Safe routes to school").fg(Color::BLACK),
+                        Line(format!("for at least {}% of homes", goal)).fg(Color::BLACK),
+                    ])
+                    .into_widget(ctx),
+                ]),
+                Widget::col(vec![
+                    Line("Score").fg(Color::BLACK).into_widget(ctx),
+                    icon_builder
+                        .source_path("system/assets/tools/star.svg")
+                        .into_widget(ctx),
+                    Text::from_multiline(vec![
+                        Line("Homes where children").fg(Color::BLACK),
+                        Line("can get to school safely").fg(Color::BLACK),
+                    ])
+                    .into_widget(ctx),
+                ]),
+            ])
+            .evenly_spaced(),
+        ])
+    })
+}
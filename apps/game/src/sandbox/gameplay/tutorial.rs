@@ -1,5 +1,7 @@
 use std::collections::BTreeSet;
 
+use serde::{Deserialize, Serialize};
+
 use crate::ID;
 use abstio::MapName;
 use abstutil::Timer;
@@ -39,7 +41,7 @@ pub struct Tutorial {
     warped: bool,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct TutorialPointer {
     pub stage: usize,
     // Index into messages. messages.len() means the actual task.
@@ -1074,6 +1076,7 @@ impl TutorialState {
                             TripEndpoint::Building(goal_bldg),
                             TripMode::Drive,
                         )],
+                        demographics: None,
                     });
                     // Will definitely get there first
                     for _ in 0..map.get_b(goal_bldg).num_parking_spots() {
@@ -1086,6 +1089,7 @@ impl TutorialState {
                                 TripEndpoint::Building(goal_bldg),
                                 TripMode::Drive,
                             )],
+                            demographics: None,
                         });
                     }
                     let mut rng = app.primary.current_flags.sim_flags.make_rng();
@@ -7,18 +7,26 @@ use map_gui::load::MapLoader;
 use map_gui::options::OptionsPanel;
 use map_gui::tools::Minimap;
 use map_gui::AppLike;
-use sim::Analytics;
+use sim::{Analytics, Breakpoint, WarmStart};
 use synthpop::Scenario;
-use widgetry::tools::{ChooseSomething, FileLoader, FutureLoader, URLManager};
+#[cfg(target_arch = "wasm32")]
+use widgetry::tools::FileLoader;
+use widgetry::tools::{Cancelled, ChooseSomething, FutureLoader, URLManager};
 use widgetry::{lctrl, Choice, EventCtx, GfxCtx, Key, Outcome, Panel, State, UpdateType};
 
-pub use self::gameplay::{spawn_agents_around, GameplayMode, TutorialPointer, TutorialState};
+use self::daylight::DrawDaylight;
+pub use self::gameplay::{
+    spawn_agents_around, CameraTarget, CompletionTrigger, GameplayMode, Highlight, Lesson,
+    LessonStage, TutorialPointer, TutorialState,
+};
 pub use self::minimap::MinimapController;
 use self::misc_tools::{RoutePreview, TrafficRecorder};
+use self::spawn_scenario::SpawnScenario;
 pub use self::speed::{SpeedSetting, TimePanel};
 pub use self::time_warp::TimeWarpScreen;
 use crate::app::{App, Transition};
-use crate::common::{tool_panel, CommonState};
+use crate::common::bug_report::BugReport;
+use crate::common::{sandbox_tool_panel, CommandPalette, CommonState, JobsPanel};
 use crate::debug::DebugMode;
 use crate::edit::{
     can_edit_lane, EditMode, RoadEditor, SaveEdits, StopSignEditor, TrafficSignalEditor,
@@ -31,9 +39,12 @@ use crate::render::{unzoomed_agent_radius, UnzoomedAgents};
 use crate::ID;
 
 pub mod dashboards;
+mod daylight;
 pub mod gameplay;
 mod minimap;
 mod misc_tools;
+pub mod session;
+mod spawn_scenario;
 mod speed;
 mod time_warp;
 mod turn_explorer;
@@ -54,6 +65,8 @@ pub struct SandboxControls {
     tool_panel: Option<Panel>,
     pub time_panel: Option<TimePanel>,
     minimap: Option<Minimap<App, MinimapController>>,
+    daylight: DrawDaylight,
+    jobs: JobsPanel,
 }
 
 impl SandboxMode {
@@ -149,6 +162,13 @@ impl State<App> for SandboxMode {
         if app.opts.dev && ctx.input.pressed(lctrl(Key::D)) {
             return Transition::Push(DebugMode::new_state(ctx, app));
         }
+        if ctx.input.pressed(lctrl(Key::K)) {
+            return Transition::Push(CommandPalette::new_state(
+                ctx,
+                app,
+                self.gameplay_mode.clone(),
+            ));
+        }
 
         if let Some(ref mut m) = self.controls.minimap {
             if let Some(t) = m.event(ctx, app) {
@@ -192,6 +212,10 @@ impl State<App> for SandboxMode {
             }
         }
 
+        if let Some(t) = self.controls.jobs.event(ctx, app) {
+            return t;
+        }
+
         if let Some(ref mut tp) = self.controls.tool_panel {
             if let Outcome::Clicked(x) = tp.event(ctx) {
                 match x.as_ref() {
@@ -201,6 +225,15 @@ impl State<App> for SandboxMode {
                     "settings" => {
                         return Transition::Push(OptionsPanel::new_state(ctx, app));
                     }
+                    "save session" => {
+                        return session::save_session(ctx, app, self.gameplay_mode.clone());
+                    }
+                    "report a problem" => {
+                        return Transition::Push(BugReport::new_state(
+                            ctx,
+                            self.gameplay_mode.clone(),
+                        ));
+                    }
                     _ => unreachable!(),
                 }
             }
@@ -221,6 +254,7 @@ impl State<App> for SandboxMode {
     }
 
     fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.controls.daylight.draw(g, app);
         if let Some(ref l) = app.primary.layer {
             l.draw(g, app);
         }
@@ -244,6 +278,7 @@ impl State<App> for SandboxMode {
         if let Some(ref r) = self.controls.route_preview {
             r.draw(g);
         }
+        self.controls.jobs.draw(g);
 
         if !app.opts.minimal_controls {
             self.gameplay.draw(g, app);
@@ -341,6 +376,32 @@ impl ContextualActions for Actions {
                 }
                 _ => {}
             }
+            if self.is_paused {
+                match id {
+                    ID::Intersection(_) => {
+                        actions.push((Key::N, "run until the next event here".to_string()));
+                    }
+                    ID::Car(_) | ID::Pedestrian(_) => {
+                        if id
+                            .agent_id()
+                            .and_then(|a| app.primary.sim.agent_to_trip(a))
+                            .is_some()
+                        {
+                            actions.push((Key::U, "run until this trip finishes".to_string()));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if app.opts.dev {
+                if let Some(bp) = crate::debug::breakpoints::breakpoint_for(&id) {
+                    if app.primary.sim.get_breakpoints().contains(&bp) {
+                        actions.push((Key::Y, "remove breakpoint".to_string()));
+                    } else {
+                        actions.push((Key::Y, "set breakpoint".to_string()));
+                    }
+                }
+            }
         }
         actions.extend(match self.gameplay {
             GameplayMode::Freeform(_) => gameplay::freeform::actions(app, id),
@@ -396,6 +457,33 @@ impl ContextualActions for Actions {
                 app.primary.layer = Some(Box::new(ShowFavorites::new(ctx, app)));
                 Transition::Keep
             }
+            (id, "set breakpoint") => {
+                let bp = crate::debug::breakpoints::breakpoint_for(&id).unwrap();
+                app.primary.sim.add_breakpoint(bp);
+                Transition::Keep
+            }
+            (id, "remove breakpoint") => {
+                let bp = crate::debug::breakpoints::breakpoint_for(&id).unwrap();
+                app.primary.sim.remove_breakpoint(bp);
+                Transition::Keep
+            }
+            (ID::Intersection(i), "run until the next event here") => {
+                app.primary
+                    .sim
+                    .add_one_time_breakpoint(Breakpoint::Intersection(i));
+                run_until_breakpoint(close_panel)
+            }
+            (id, "run until this trip finishes") => {
+                let trip = app
+                    .primary
+                    .sim
+                    .agent_to_trip(id.agent_id().unwrap())
+                    .unwrap();
+                app.primary
+                    .sim
+                    .add_one_time_breakpoint(Breakpoint::Trip(trip));
+                run_until_breakpoint(close_panel)
+            }
             (_, "follow (run the simulation)") => {
                 *close_panel = false;
                 Transition::ModifyState(Box::new(|state, ctx, app| {
@@ -429,6 +517,17 @@ impl ContextualActions for Actions {
     }
 }
 
+/// Run as quickly as possible. The time panel pauses when a breakpoint is hit.
+fn run_until_breakpoint(close_panel: &mut bool) -> Transition {
+    *close_panel = false;
+    Transition::ModifyState(Box::new(|state, ctx, app| {
+        let mode = state.downcast_mut::<SandboxMode>().unwrap();
+        if let Some(ref mut time_panel) = mode.controls.time_panel {
+            time_panel.resume(ctx, app, SpeedSetting::Fastest);
+        }
+    }))
+}
+
 // TODO Setting SandboxMode up is quite convoluted, all in order to support asynchronously loading
 // files on the web. Each LoadStage is followed in order, with some optional short-circuiting.
 //
@@ -495,27 +594,13 @@ impl State<App> for SandboxLoader {
                             continue;
                         }
                         gameplay::LoadScenario::Future(future) => {
-                            let (_, outer_progress_rx) = futures_channel::mpsc::channel(1);
-                            let (_, inner_progress_rx) = futures_channel::mpsc::channel(1);
-                            return Transition::Push(FutureLoader::<App, Scenario>::new_state(
+                            let (_, progress_rx) = futures_channel::mpsc::channel(1);
+                            let (_, fraction_rx) = futures_channel::mpsc::channel(1);
+                            return Transition::Push(load_scenario_future(
                                 ctx,
-                                Box::pin(future),
-                                outer_progress_rx,
-                                inner_progress_rx,
-                                "Loading Scenario",
-                                Box::new(|_, _, scenario| {
-                                    // TODO show error/retry alert?
-                                    let scenario =
-                                        scenario.expect("failed to load scenario from future");
-                                    Transition::Multi(vec![
-                                        Transition::Pop,
-                                        Transition::ModifyState(Box::new(|state, _, _| {
-                                            let loader =
-                                                state.downcast_mut::<SandboxLoader>().unwrap();
-                                            loader.stage = Some(LoadStage::GotScenario(scenario));
-                                        })),
-                                    ])
-                                }),
+                                future,
+                                progress_rx,
+                                fraction_rx,
                             ));
                         }
                         gameplay::LoadScenario::Path(path) => {
@@ -527,70 +612,61 @@ impl State<App> for SandboxLoader {
                                 }
                             }
 
-                            return Transition::Push(FileLoader::<App, Scenario>::new_state(
-                                ctx,
-                                path,
-                                Box::new(|_, _, _, scenario| {
-                                    // TODO Handle corrupt files
-                                    let scenario = scenario.unwrap();
-                                    Transition::Multi(vec![
-                                        Transition::Pop,
-                                        Transition::ModifyState(Box::new(|state, _, _| {
-                                            let loader =
-                                                state.downcast_mut::<SandboxLoader>().unwrap();
-                                            loader.stage = Some(LoadStage::GotScenario(scenario));
-                                        })),
-                                    ])
-                                }),
-                            ));
+                            return Transition::Push(load_scenario_file(ctx, path));
                         }
                     }
                 }
                 LoadStage::GotScenario(mut scenario) => {
                     let scenario_name = scenario.scenario_name.clone();
-                    ctx.loading_screen("instantiate scenario", |_, timer| {
+                    // Use the same RNG as we apply scenario modifiers and instantiate the
+                    // scenario. One unexpected effect will be that parked car seeding (during
+                    // scenario instantiation) may spuriously change if a scenario modifier uses
+                    // the RNG. This is at least consistent with the tests, headless mode, and
+                    // instantiating a scenario from CLI flags.
+                    let mut rng = app.primary.current_flags.sim_flags.make_rng();
+                    ctx.loading_screen("prepare scenario", |_, timer| {
                         app.primary.scenario = Some(scenario.clone());
 
-                        // Use the same RNG as we apply scenario modifiers and instantiate the
-                        // scenario. One unexpected effect will be that parked car seeding (during
-                        // scenario instantiation) may spuriously change if a scenario modifier
-                        // uses the RNG. This is at least consistent with the tests, headless mode,
-                        // and instantiating a scenario from CLI flags.
-                        let mut rng = app.primary.current_flags.sim_flags.make_rng();
-
                         if let GameplayMode::PlayScenario(_, _, ref modifiers) = self.mode {
                             for m in modifiers {
                                 scenario = m.apply(&app.primary.map, scenario, &mut rng);
                             }
                         }
 
-                        app.primary
-                            .sim
-                            .instantiate(&scenario, &app.primary.map, &mut rng, timer);
-                        app.primary
-                            .sim
-                            .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
-
+                        if let Some(ref path) = app.primary.current_flags.sim_flags.warm_start {
+                            match abstio::maybe_read_binary::<WarmStart>(path.clone(), timer) {
+                                Ok(warm_start) => app.primary.sim.set_warm_start(warm_start),
+                                Err(err) => warn!("Couldn't load warm start {}: {}", path, err),
+                            }
+                        }
                         if let Some(ref mut secondary) = app.secondary {
                             // TODO Modifiers already applied
                             secondary.scenario = Some(scenario.clone());
-
-                            secondary.sim.instantiate(
-                                &scenario,
-                                &secondary.map,
-                                // Start fresh here. This will match up with the primary sim,
-                                // unless modifiers used the RNG
-                                &mut secondary.current_flags.sim_flags.make_rng(),
-                                timer,
-                            );
-                            secondary
-                                .sim
-                                .tiny_step(&secondary.map, &mut secondary.sim_cb);
                         }
                     });
 
-                    self.stage = Some(LoadStage::LoadingPrebaked(scenario_name));
-                    continue;
+                    // Spawn people in batches, so the map stays responsive
+                    return Transition::Push(SpawnScenario::new_state(
+                        ctx,
+                        app,
+                        scenario,
+                        rng,
+                        Box::new(move |_, _, finished| {
+                            Transition::Multi(vec![
+                                Transition::Pop,
+                                Transition::ModifyState(Box::new(move |state, _, app| {
+                                    let loader = state.downcast_mut::<SandboxLoader>().unwrap();
+                                    loader.stage = Some(if finished {
+                                        LoadStage::LoadingPrebaked(scenario_name)
+                                    } else {
+                                        app.primary.scenario = None;
+                                        app.set_prebaked(None);
+                                        LoadStage::Finalizing
+                                    });
+                                })),
+                            ])
+                        }),
+                    ));
                 }
                 LoadStage::LoadingPrebaked(scenario_name) => {
                     // Maybe we've already got prebaked data for this map+scenario.
@@ -664,6 +740,78 @@ impl State<App> for SandboxLoader {
     fn draw(&self, _: &mut GfxCtx, _: &App) {}
 }
 
+/// Read a scenario file in the background, so it can be cancelled
+#[cfg(not(target_arch = "wasm32"))]
+fn load_scenario_file(ctx: &mut EventCtx, path: String) -> Box<dyn State<App>> {
+    let (mut progress_tx, progress_rx) = futures_channel::mpsc::channel(1);
+    let (fraction_tx, fraction_rx) = futures_channel::mpsc::channel(100);
+    let _ = progress_tx.try_send(format!("Reading {}", abstutil::basename(&path)));
+    let future: gameplay::ScenarioFuture = Box::pin(async move {
+        let scenario = spawn_scenario::read_scenario(path, fraction_tx)?;
+        let scenario_from_app: Box<dyn Send + FnOnce(&App) -> Scenario> =
+            Box::new(move |_: &App| scenario);
+        Ok(scenario_from_app)
+    });
+    load_scenario_future(ctx, future, progress_rx, fraction_rx)
+}
+
+/// On the web, FileLoader already fetches in the background
+#[cfg(target_arch = "wasm32")]
+fn load_scenario_file(ctx: &mut EventCtx, path: String) -> Box<dyn State<App>> {
+    FileLoader::<App, Scenario>::new_state(
+        ctx,
+        path,
+        Box::new(|_, _, _, scenario| {
+            // TODO Handle corrupt files
+            let scenario = scenario.unwrap();
+            Transition::Multi(vec![
+                Transition::Pop,
+                Transition::ModifyState(Box::new(|state, _, _| {
+                    let loader = state.downcast_mut::<SandboxLoader>().unwrap();
+                    loader.stage = Some(LoadStage::GotScenario(scenario));
+                })),
+            ])
+        }),
+    )
+}
+
+/// Load a scenario in the background. If it's cancelled or fails, start with no scenario.
+fn load_scenario_future(
+    ctx: &mut EventCtx,
+    future: gameplay::ScenarioFuture,
+    progress_rx: futures_channel::mpsc::Receiver<String>,
+    fraction_rx: futures_channel::mpsc::Receiver<f64>,
+) -> Box<dyn State<App>> {
+    FutureLoader::<App, Scenario>::new_cancellable_state(
+        ctx,
+        future,
+        progress_rx,
+        fraction_rx,
+        "Loading Scenario",
+        Box::new(|_, _, scenario| {
+            let stage = match scenario {
+                Ok(scenario) => LoadStage::GotScenario(scenario),
+                Err(err) => {
+                    if !err.is::<Cancelled>() {
+                        error!("Couldn't load scenario: {:#}", err);
+                    }
+                    LoadStage::Finalizing
+                }
+            };
+            Transition::Multi(vec![
+                Transition::Pop,
+                Transition::ModifyState(Box::new(|state, _, app| {
+                    if matches!(stage, LoadStage::Finalizing) {
+                        app.set_prebaked(None);
+                    }
+                    let loader = state.downcast_mut::<SandboxLoader>().unwrap();
+                    loader.stage = Some(stage);
+                })),
+            ])
+        }),
+    )
+}
+
 fn mouseover_unzoomed_agent_circle(ctx: &mut EventCtx, app: &mut App) {
     let cursor = if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
         pt
@@ -687,8 +835,10 @@ fn mouseover_unzoomed_agent_circle(ctx: &mut EventCtx, app: &mut App) {
 }
 
 fn is_daytime(app: &App) -> bool {
-    let hours = app.primary.sim.time().get_hours() % 24;
-    (6..18).contains(&hours)
+    !app.primary
+        .map
+        .get_daylight(app.opts.day_of_year)
+        .is_dark(app.primary.sim.time())
 }
 
 impl SandboxControls {
@@ -709,7 +859,7 @@ impl SandboxControls {
                 None
             },
             tool_panel: if gameplay.has_tool_panel() {
-                Some(tool_panel(ctx))
+                Some(sandbox_tool_panel(ctx))
             } else {
                 None
             },
@@ -723,12 +873,14 @@ impl SandboxControls {
             } else {
                 None
             },
+            daylight: DrawDaylight::new(ctx, app),
+            jobs: JobsPanel::new(ctx, app),
         }
     }
 
     fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &App) {
         if self.tool_panel.is_some() {
-            self.tool_panel = Some(tool_panel(ctx));
+            self.tool_panel = Some(sandbox_tool_panel(ctx));
         }
         if let Some(ref mut speed) = self.time_panel {
             speed.recreate_panel(ctx, app);
@@ -736,5 +888,6 @@ impl SandboxControls {
         if let Some(ref mut minimap) = self.minimap {
             minimap.recreate_panel(ctx, app);
         }
+        self.jobs.recreate_panel(ctx, app);
     }
 }
@@ -0,0 +1,175 @@
+//! A saved session captures everything needed to resume an analysis later: the map, edits,
+//! gameplay mode, simulation time, camera, and open layer.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::Time;
+use map_gui::load::MapLoader;
+use map_model::MapEdits;
+use widgetry::tools::{ChooseSomething, PopupMsg, PromptInput};
+use widgetry::{Choice, EventCtx, State};
+
+use crate::app::{App, Transition};
+use crate::common::jump_to_time_upon_startup;
+use crate::sandbox::gameplay::{CameraTarget, GameplayMode};
+use crate::sandbox::SandboxMode;
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub name: String,
+    pub map_name: MapName,
+    /// `PermanentMapEdits` as JSON, so old sessions go through the usual format upgrades. `None`
+    /// if the map wasn't edited.
+    pub edits: Option<serde_json::Value>,
+    pub gameplay: GameplayMode,
+    /// The simulation is deterministic, so it's re-run up to this time when the session is
+    /// restored, instead of storing a large savestate.
+    pub time: Time,
+    pub camera: CameraTarget,
+    /// As returned by `Layer::name`
+    pub layer: Option<String>,
+}
+
+impl Session {
+    pub fn capture(ctx: &EventCtx, app: &App, name: String, gameplay: GameplayMode) -> Session {
+        let map = &app.primary.map;
+        let edits = if map.get_edits().commands.is_empty() {
+            None
+        } else {
+            serde_json::to_value(map.get_edits().to_permanent(map)).ok()
+        };
+        Session {
+            name,
+            map_name: map.get_name().clone(),
+            edits,
+            gameplay,
+            time: app.primary.sim.time(),
+            camera: CameraTarget {
+                center: ctx.canvas.center_to_map_pt().to_gps(map.get_gps_bounds()),
+                zoom: ctx.canvas.cam_zoom,
+            },
+            layer: app
+                .primary
+                .layer
+                .as_ref()
+                .and_then(|l| l.name())
+                .map(|x| x.to_string()),
+        }
+    }
+
+    pub fn load(name: &str) -> Result<Session> {
+        abstio::maybe_read_json(abstio::path_session(name), &mut Timer::throwaway())
+    }
+
+    pub fn save(&self) {
+        abstio::write_json(abstio::path_session(&self.name), self);
+    }
+
+    pub fn list_all() -> Vec<String> {
+        abstio::list_all_objects(abstio::path_all_sessions())
+    }
+
+    /// Load the map, apply edits, start the gameplay mode, and run the simulation to the saved
+    /// time.
+    pub fn restore(self, ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        MapLoader::new_state(
+            ctx,
+            app,
+            self.map_name.clone(),
+            Box::new(move |ctx, app| {
+                let edits = match self.edits.clone() {
+                    Some(value) => match serde_json::to_vec(&value)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|bytes| MapEdits::load_from_bytes(&app.primary.map, bytes))
+                    {
+                        Ok(edits) => edits,
+                        Err(err) => {
+                            return Transition::Replace(PopupMsg::new_state(
+                                ctx,
+                                "Couldn't restore session",
+                                vec![format!("The edits are broken: {}", err)],
+                            ));
+                        }
+                    },
+                    None => app.primary.map.new_edits(),
+                };
+                ctx.loading_screen("apply session edits", |ctx, timer| {
+                    crate::edit::apply_map_edits(ctx, app, edits);
+                    app.primary.map.recalculate_pathfinding_after_edits(timer);
+                });
+
+                let camera = self.camera;
+                let layer = self.layer.clone();
+                let time = self.time;
+                Transition::Clear(vec![SandboxMode::async_new(
+                    app,
+                    self.gameplay.clone(),
+                    Box::new(move |ctx, app| {
+                        ctx.canvas.cam_zoom = camera.zoom;
+                        ctx.canvas.center_on_map_pt(
+                            camera.center.to_pt(app.primary.map.get_gps_bounds()),
+                        );
+                        if let Some(name) = layer {
+                            app.primary.layer = crate::layer::layer_by_name(ctx, app, &name);
+                        }
+                        if time > Time::START_OF_DAY {
+                            jump_to_time_upon_startup(time - Time::START_OF_DAY)(ctx, app)
+                        } else {
+                            Vec::new()
+                        }
+                    }),
+                )])
+            }),
+        )
+    }
+}
+
+/// Ask for a name, then save the current session.
+pub fn save_session(ctx: &mut EventCtx, app: &App, gameplay: GameplayMode) -> Transition {
+    let default_name = format!(
+        "{} at {}",
+        app.primary.map.get_name().as_filename(),
+        app.primary.sim.time().as_filename()
+    );
+    Transition::Push(PromptInput::new_state(
+        ctx,
+        "Name this session",
+        default_name,
+        Box::new(move |name, ctx, app| {
+            let session = Session::capture(ctx, app, name, gameplay);
+            session.save();
+            Transition::Replace(PopupMsg::new_state(
+                ctx,
+                "Session saved",
+                vec![
+                    format!("Saved {}", abstio::path_session(&session.name)),
+                    "Restore it from the title screen.".to_string(),
+                ],
+            ))
+        }),
+    ))
+}
+
+/// Pick a saved session to restore.
+pub fn pick_session(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+    let names = Session::list_all();
+    if names.is_empty() {
+        return PopupMsg::new_state(ctx, "No saved sessions", vec!["Save one from the sandbox"]);
+    }
+    ChooseSomething::new_state(
+        ctx,
+        "Restore which session?",
+        Choice::strings(names),
+        Box::new(|name, ctx, app| match Session::load(&name) {
+            Ok(session) => Transition::Replace(session.restore(ctx, app)),
+            Err(err) => Transition::Replace(PopupMsg::new_state(
+                ctx,
+                "Couldn't load session",
+                vec![err.to_string()],
+            )),
+        }),
+    )
+}
@@ -0,0 +1,266 @@
+//! Large scenarios take a while to read and instantiate. Instead of blocking the UI, read them in
+//! the background, then spawn people in batches while the map is drawn and can be moved around.
+
+use instant::Instant;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Duration, Polygon};
+use sim::ScenarioInstantiation;
+use synthpop::Scenario;
+use widgetry::{
+    EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text,
+    UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// How long to spend spawning people between frames
+const FRAME_BUDGET: Duration = Duration::const_seconds(0.05);
+/// How many people to spawn before checking the time
+const BATCH_SIZE: usize = 500;
+
+/// Instantiates a scenario in the primary simulation, and the secondary one if it exists, a batch
+/// of people at a time. Cancelling leaves both simulations empty.
+pub struct SpawnScenario {
+    panel: Panel,
+    scenario: Scenario,
+    // Both are taken when finishing
+    primary: Option<(ScenarioInstantiation, XorShiftRng)>,
+    secondary: Option<(ScenarioInstantiation, XorShiftRng)>,
+    /// Called with true if the scenario was fully spawned, false if the player cancelled
+    on_done: Option<Box<dyn FnOnce(&mut EventCtx, &mut App, bool) -> Transition>>,
+}
+
+impl SpawnScenario {
+    /// Modifiers should already be applied to the scenario. The primary simulation uses `rng`;
+    /// the secondary one starts fresh from its flags.
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        scenario: Scenario,
+        mut rng: XorShiftRng,
+        on_done: Box<dyn FnOnce(&mut EventCtx, &mut App, bool) -> Transition>,
+    ) -> Box<dyn State<App>> {
+        let primary = ScenarioInstantiation::new(
+            &mut app.primary.sim,
+            &scenario,
+            &app.primary.map,
+            &mut rng,
+            true,
+        );
+        let secondary = app.secondary.as_mut().map(|secondary| {
+            // This will match up with the primary sim, unless modifiers used the RNG
+            let mut rng = secondary.current_flags.sim_flags.make_rng();
+            let instantiation = ScenarioInstantiation::new(
+                &mut secondary.sim,
+                &scenario,
+                &secondary.map,
+                &mut rng,
+                true,
+            );
+            (instantiation, rng)
+        });
+
+        let mut state = SpawnScenario {
+            panel: Panel::empty(ctx),
+            scenario,
+            primary: Some((primary, rng)),
+            secondary,
+            on_done: Some(on_done),
+        };
+        state.panel = state.make_panel(ctx);
+        Box::new(state)
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx) -> Panel {
+        let (done, total) = self.primary.as_ref().unwrap().0.progress(&self.scenario);
+        let pct = if total == 0 {
+            1.0
+        } else {
+            (done as f64) / (total as f64)
+        };
+        // Match the time panel's progress bar
+        let width = 300.0;
+        let height = 15.0;
+        let fg = ctx.style().primary_fg;
+        let mut bar = GeomBatch::new();
+        bar.push(fg.tint(0.6).shade(0.2), Polygon::rectangle(width, height));
+        if let Ok(p) = Polygon::maybe_rectangle(pct * width, height) {
+            bar.push(fg, p);
+        }
+
+        Panel::new_builder(Widget::col(vec![
+            Line(format!("Loading {}", self.scenario.scenario_name))
+                .small_heading()
+                .into_widget(ctx),
+            Text::from(Line(format!(
+                "Spawned {} of {} people",
+                prettyprint_usize(done),
+                prettyprint_usize(total)
+            )))
+            .into_widget(ctx),
+            bar.into_widget(ctx),
+            ctx.style()
+                .btn_outline
+                .text("Cancel")
+                .hotkey(Key::Escape)
+                .build_def(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+        .build(ctx)
+    }
+
+    fn is_done(&self) -> bool {
+        self.primary.as_ref().unwrap().0.is_done(&self.scenario)
+            && self
+                .secondary
+                .as_ref()
+                .map(|(x, _)| x.is_done(&self.scenario))
+                .unwrap_or(true)
+    }
+
+    fn spawn_batch(&mut self, app: &mut App) {
+        let mut timer = Timer::throwaway();
+        let (instantiation, rng) = self.primary.as_mut().unwrap();
+        instantiation.add_people(
+            &mut app.primary.sim,
+            &self.scenario,
+            &app.primary.map,
+            rng,
+            BATCH_SIZE,
+            &mut timer,
+        );
+        if let (Some((instantiation, rng)), Some(secondary)) =
+            (self.secondary.as_mut(), app.secondary.as_mut())
+        {
+            instantiation.add_people(
+                &mut secondary.sim,
+                &self.scenario,
+                &secondary.map,
+                rng,
+                BATCH_SIZE,
+                &mut timer,
+            );
+        }
+    }
+
+    fn finish(&mut self, ctx: &mut EventCtx, app: &mut App) {
+        let scenario = &self.scenario;
+        let primary = self.primary.take().unwrap();
+        let secondary = self.secondary.take();
+        ctx.loading_screen("finish spawning scenario", |_, timer| {
+            let (instantiation, mut rng) = primary;
+            instantiation.finish(
+                &mut app.primary.sim,
+                scenario,
+                &app.primary.map,
+                &mut rng,
+                timer,
+            );
+            app.primary
+                .sim
+                .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
+
+            if let (Some((instantiation, mut rng)), Some(secondary)) =
+                (secondary, app.secondary.as_mut())
+            {
+                instantiation.finish(
+                    &mut secondary.sim,
+                    scenario,
+                    &secondary.map,
+                    &mut rng,
+                    timer,
+                );
+                secondary
+                    .sim
+                    .tiny_step(&secondary.map, &mut secondary.sim_cb);
+            }
+        });
+    }
+}
+
+impl State<App> for SpawnScenario {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "Cancel" => {
+                    // Throw away the people spawned so far
+                    app.primary.clear_sim();
+                    if let Some(ref mut secondary) = app.secondary {
+                        secondary.clear_sim();
+                    }
+                    return (self.on_done.take().unwrap())(ctx, app, false);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        let started = Instant::now();
+        while !self.is_done() && Duration::realtime_elapsed(started) < FRAME_BUDGET {
+            self.spawn_batch(app);
+        }
+        if self.is_done() {
+            self.finish(ctx, app);
+            return (self.on_done.take().unwrap())(ctx, app, true);
+        }
+
+        self.panel = self.make_panel(ctx);
+        // Keep spawning, even if the player doesn't do anything
+        ctx.request_update(UpdateType::Game);
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+/// Read a scenario file without blocking the UI, sending the fraction of the file read so far.
+/// On the web, `FileLoader` already does this.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_scenario(
+    path: String,
+    mut fraction: futures_channel::mpsc::Sender<f64>,
+) -> anyhow::Result<Scenario> {
+    use std::io::Read;
+
+    struct ProgressReader<R> {
+        inner: R,
+        read: u64,
+        total: u64,
+        fraction: futures_channel::mpsc::Sender<f64>,
+    }
+
+    impl<R: Read> Read for ProgressReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            let before = self.read * 100 / self.total.max(1);
+            self.read += n as u64;
+            // Only send whole percents, so the channel doesn't fill up
+            if self.read * 100 / self.total.max(1) != before {
+                let _ = self
+                    .fraction
+                    .try_send((self.read as f64) / (self.total as f64));
+            }
+            Ok(n)
+        }
+    }
+
+    let file = fs_err::File::open(&path)?;
+    let total = file.metadata()?.len();
+    let _ = fraction.try_send(0.0);
+    let reader = ProgressReader {
+        inner: std::io::BufReader::new(file),
+        read: 0,
+        total,
+        fraction,
+    };
+    if path.ends_with(".bin") {
+        abstutil::from_binary_reader(reader)
+    } else {
+        abstutil::from_json_reader(reader)
+    }
+}
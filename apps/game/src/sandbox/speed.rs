@@ -1,3 +1,5 @@
+use instant::Instant;
+
 use crate::ID;
 use abstutil::prettyprint_usize;
 use geom::{Circle, Distance, Duration, Polygon, Pt2D, Ring, Time};
@@ -11,6 +13,8 @@ use widgetry::{
 
 use crate::app::{App, Transition};
 use crate::common::Warping;
+use crate::debug::breakpoints::BreakpointHits;
+use crate::sandbox::daylight::daylight_bar;
 use crate::sandbox::time_warp::JumpToTime;
 use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
 
@@ -133,6 +137,18 @@ impl TimePanel {
             .margin_right(16),
         );
 
+        row.push(
+            ctx.style()
+                .btn_plain
+                .text("+1 step")
+                .hotkey(Key::Dot)
+                .tooltip(Text::from(Line(
+                    "Advance to the next time anything happens",
+                )))
+                .build_widget(ctx, "step to the next event")
+                .margin_right(16),
+        );
+
         row.push(
             ctx.style()
                 .btn_plain
@@ -317,6 +333,7 @@ impl TimePanel {
 
         Widget::col(vec![
             Text::from(Line(self.time.ampm_tostring()).big_monospaced()).into_widget(ctx),
+            daylight_bar(ctx, app, self.time, 400.0).margin_above(4),
             trips_bar.margin_above(12),
             if app.primary.dirty_from_edits {
                 ctx.style()
@@ -411,6 +428,21 @@ impl TimePanel {
                         None,
                     )));
                 }
+                "step to the next event" => {
+                    if !app
+                        .primary
+                        .sim
+                        .step_to_next_event(&app.primary.map, &mut app.primary.sim_cb)
+                    {
+                        return Some(Transition::Push(PopupMsg::new_state(
+                            ctx,
+                            "Nothing left to do",
+                            vec!["Nothing else is scheduled to happen in the simulation"],
+                        )));
+                    }
+                    app.recalculate_current_selection(ctx);
+                    // Fall through to check breakpoints
+                }
                 "see why results are tentative" => {
                     return Some(Transition::Push(PopupMsg::new_state(
                         ctx,
@@ -488,16 +520,29 @@ impl TimePanel {
                 let dt = multiplier * real_dt;
                 // TODO This should match the update frequency in widgetry. Plumb along the deadline
                 // or frequency to here.
+                let before = app.primary.sim.time();
+                let started = Instant::now();
                 app.primary.sim.time_limited_step(
                     &app.primary.map,
                     dt,
                     Duration::seconds(0.033),
                     &mut app.primary.sim_cb,
                 );
+                app.session.perf.record_sim_step(
+                    before,
+                    app.primary.sim.time(),
+                    Duration::realtime_elapsed(started),
+                );
                 app.recalculate_current_selection(ctx);
             }
         }
 
+        let hits = app.primary.sim.clear_breakpoint_hits();
+        if !hits.is_empty() {
+            self.pause(ctx, app);
+            return Some(Transition::Push(BreakpointHits::new_state(ctx, app, hits)));
+        }
+
         // TODO Need to do this anywhere that steps the sim, like TimeWarpScreen.
         let alerts = app.primary.sim.clear_alerts();
         if !alerts.is_empty() {
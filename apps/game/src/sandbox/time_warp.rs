@@ -6,6 +6,7 @@ use abstutil::prettyprint_usize;
 use geom::{Duration, Polygon, Pt2D, Ring, Time};
 use map_gui::render::DrawOptions;
 use map_gui::tools::grey_out_map;
+use sim::FastForwardAccuracy;
 use widgetry::tools::PopupMsg;
 use widgetry::{
     Choice, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, PanelDims,
@@ -14,6 +15,7 @@ use widgetry::{
 
 use crate::app::{App, FindDelayedIntersections, ShowEverything, Transition};
 use crate::common::Warping;
+use crate::debug::breakpoints::BreakpointHits;
 use crate::sandbox::{GameplayMode, SandboxMode};
 
 // TODO Text entry would be great
@@ -70,6 +72,10 @@ impl JumpToTime {
                     target.to_percent(end_of_day).min(1.0),
                     "time slider",
                 ),
+                Widget::row(vec![
+                    Line("Simulate").into_widget(ctx).centered_vert(),
+                    Widget::dropdown(ctx, "fast-forward", None, fast_forward_choices()),
+                ]),
                 build_jump_to_time_btn(ctx, target),
             ])
         };
@@ -111,7 +117,7 @@ impl JumpToTime {
                 tabs.build_widget(ctx),
             ]))
             .dims_width(PanelDims::ExactPixels(640.0))
-            .dims_height(PanelDims::ExactPixels(360.0))
+            .dims_height(PanelDims::ExactPixels(400.0))
             .build(ctx),
             tabs,
         })
@@ -126,6 +132,8 @@ impl State<App> for JumpToTime {
                     return Transition::Pop;
                 }
                 "jump to time" => {
+                    let fast_forward: Option<FastForwardAccuracy> =
+                        self.panel.dropdown_value("fast-forward");
                     if self.target < app.primary.sim.time() {
                         if let Some(mode) = self.maybe_mode.take() {
                             let target_time = self.target;
@@ -133,6 +141,9 @@ impl State<App> for JumpToTime {
                                 app,
                                 mode,
                                 Box::new(move |ctx, app| {
+                                    if let Some(accuracy) = fast_forward {
+                                        app.primary.sim.fast_forward_until(target_time, accuracy);
+                                    }
                                     vec![Transition::Push(TimeWarpScreen::new_state(
                                         ctx,
                                         app,
@@ -149,6 +160,9 @@ impl State<App> for JumpToTime {
                             ));
                         }
                     }
+                    if let Some(accuracy) = fast_forward {
+                        app.primary.sim.fast_forward_until(self.target, accuracy);
+                    }
                     return Transition::Replace(TimeWarpScreen::new_state(
                         ctx,
                         app,
@@ -278,12 +292,19 @@ impl State<App> for TimeWarpScreen {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         if ctx.input.nonblocking_is_update_event().is_some() {
             ctx.input.use_update_event();
+            let before = app.primary.sim.time();
+            let started = Instant::now();
             app.primary.sim.time_limited_step(
                 &app.primary.map,
                 self.target - app.primary.sim.time(),
                 Duration::seconds(0.033),
                 &mut app.primary.sim_cb,
             );
+            app.session.perf.record_sim_step(
+                before,
+                app.primary.sim.time(),
+                Duration::realtime_elapsed(started),
+            );
             #[allow(clippy::never_loop)]
             for (t, maybe_i, alert) in app.primary.sim.clear_alerts() {
                 // TODO Just the first :(
@@ -293,6 +314,10 @@ impl State<App> for TimeWarpScreen {
                     vec![format!("At {}, near {:?}, {}", t, maybe_i, alert)],
                 ));
             }
+            let hits = app.primary.sim.clear_breakpoint_hits();
+            if !hits.is_empty() {
+                return Transition::Replace(BreakpointHits::new_state(ctx, app, hits));
+            }
             if let Some(ref mut cb) = app.primary.sim_cb {
                 let di = cb.downcast_mut::<FindDelayedIntersections>().unwrap();
                 if let Some((i, t)) = di.currently_delayed.get(0) {
@@ -400,6 +425,7 @@ impl State<App> for TimeWarpScreen {
     }
 
     fn on_destroy(&mut self, _: &mut EventCtx, app: &mut App) {
+        app.primary.sim.stop_fast_forward();
         if self.halt_upon_delay.is_some() {
             assert!(app.primary.sim_cb.is_some());
             app.primary.sim_cb = None;
@@ -445,6 +471,17 @@ fn compare_count(after: usize, before: usize) -> String {
     }
 }
 
+fn fast_forward_choices() -> Vec<Choice<Option<FastForwardAccuracy>>> {
+    let mut choices = vec![Choice::new("every trip in detail", None)];
+    for accuracy in FastForwardAccuracy::all() {
+        choices.push(Choice::new(
+            format!("fast-forward ({})", accuracy.describe()),
+            Some(accuracy),
+        ));
+    }
+    choices
+}
+
 fn build_jump_to_time_btn(ctx: &EventCtx, target: Time) -> Widget {
     ctx.style()
         .btn_solid_primary
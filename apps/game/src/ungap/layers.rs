@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use geom::Distance;
+use geom::{Circle, Distance};
 use map_gui::tools::{DrawRoadLabels, Navigator};
 use map_model::osm::RoadRank;
 use map_model::LaneType;
@@ -15,6 +15,8 @@ use crate::app::{App, Transition};
 use crate::ungap::bike_network;
 use crate::ungap::bike_network::DrawNetworkLayer;
 
+const DRIVEWAY_COLOR: Color = Color::ORANGE;
+
 /// A bottom-right panel for managing a bunch of toggleable layers in the "ungap the map" tool.
 pub struct Layers {
     panel: Panel,
@@ -23,6 +25,7 @@ pub struct Layers {
     labels: Option<DrawRoadLabels>,
     elevation: bool,
     steep_streets: Option<Drawable>,
+    driveways: Option<Drawable>,
     // TODO Once widgetry buttons can take custom enums, that'd be perfect here
     road_types: HashMap<String, Drawable>,
     fade_map: Drawable,
@@ -40,6 +43,7 @@ impl Layers {
             labels: Some(DrawRoadLabels::only_major_roads()),
             elevation: false,
             steep_streets: None,
+            driveways: None,
             road_types: HashMap::new(),
             fade_map: GeomBatch::from(vec![(
                 Color::BLACK.alpha(0.4),
@@ -62,6 +66,9 @@ impl Layers {
                 self.bike_network = Some(DrawNetworkLayer::new(ctx, app));
             }
             self.road_types.clear();
+            if self.driveways.is_some() {
+                self.driveways = Some(draw_driveways(ctx, app));
+            }
         }
 
         if ctx.redo_mouseover() && self.elevation && !self.minimized {
@@ -174,6 +181,14 @@ impl Layers {
                     }
                     self.update_panel(ctx, app);
                 }
+                "driveways" => {
+                    if self.panel.is_checked("driveways") {
+                        self.driveways = Some(draw_driveways(ctx, app));
+                    } else {
+                        self.driveways = None;
+                    }
+                    self.update_panel(ctx, app);
+                }
                 _ => unreachable!(),
             },
             _ => {}
@@ -228,6 +243,9 @@ impl Layers {
             if let Some(ref draw) = self.steep_streets {
                 g.redraw(draw);
             }
+            if let Some(ref draw) = self.driveways {
+                g.redraw(draw);
+            }
         }
     }
 
@@ -351,6 +369,22 @@ impl Layers {
                 }
                 row
             }),
+            Widget::row({
+                let mut row = vec![Toggle::checkbox(
+                    ctx,
+                    "driveways",
+                    Key::D,
+                    self.driveways.is_some(),
+                )];
+                if self.driveways.is_some() {
+                    row.push(
+                        legend_btn(DRIVEWAY_COLOR, "many driveways")
+                            .disabled(true)
+                            .build_def(ctx),
+                    );
+                }
+                row
+            }),
             // TODO Probably a collisions layer
         ])
     }
@@ -361,6 +395,7 @@ impl Layers {
             || name == "road labels"
             || name == "elevation"
             || name == "steep streets"
+            || name == "driveways"
             || name.starts_with("about ")
         {
             return;
@@ -407,6 +442,27 @@ impl Layers {
     }
 }
 
+/// Roads where vehicles turning in and out of driveways constantly cut across the sidewalk and any
+/// bike lane, plus a dot for every curb cut
+fn draw_driveways(ctx: &mut EventCtx, app: &App) -> Drawable {
+    let map = &app.primary.map;
+    let mut batch = GeomBatch::new();
+    for r in map.all_roads() {
+        if r.has_high_driveway_density(map) {
+            batch.push(DRIVEWAY_COLOR.alpha(0.8), r.get_thick_polygon());
+        }
+    }
+    for b in map.all_buildings() {
+        if b.has_driveway() {
+            batch.push(
+                DRIVEWAY_COLOR,
+                Circle::new(b.sidewalk_pos.pt(map), Distance::meters(3.0)).to_polygon(),
+            );
+        }
+    }
+    ctx.upload(batch)
+}
+
 fn make_zoom_controls(ctx: &mut EventCtx) -> Widget {
     let builder = ctx
         .style()
@@ -45,9 +45,10 @@ pub struct PerMap {
     pub draw_all_filters: render::Toggle3Zoomed,
     pub draw_major_road_labels: DrawSimpleRoadLabels,
     pub draw_all_local_road_labels: Option<DrawSimpleRoadLabels>,
-    pub draw_poi_icons: Drawable,
-    pub draw_bus_routes: Drawable,
-    pub draw_turn_restrictions: Drawable,
+    pub draw_poi_icons: render::Toggle3Zoomed,
+    pub draw_bus_routes: render::Toggle3Zoomed,
+    pub draw_turn_restrictions: render::Toggle3Zoomed,
+    pub draw_annotations: Drawable,
 
     pub current_trip_name: Option<String>,
 }
@@ -73,7 +74,7 @@ impl PerMap {
         // Create DrawMap after transform_existing_filters, which modifies road widths
         let draw_map = DrawMap::new(ctx, &map, opts, cs, timer);
         let draw_poi_icons = render::render_poi_icons(ctx, &map);
-        let draw_bus_routes = render::render_bus_routes(ctx, &map);
+        let draw_bus_routes = render::render_bus_routes(ctx, &map, cs);
         let draw_turn_restrictions = render::render_turn_restrictions(ctx, &map);
 
         let per_map = Self {
@@ -95,6 +96,7 @@ impl PerMap {
             draw_poi_icons,
             draw_bus_routes,
             draw_turn_restrictions,
+            draw_annotations: Drawable::empty(ctx),
 
             current_trip_name: None,
         };
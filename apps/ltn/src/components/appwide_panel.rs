@@ -63,6 +63,7 @@ impl AppwidePanel {
                     app,
                     app.per_map.current_neighbourhood.unwrap(),
                 ))),
+                "Citywide" => Some(Transition::Replace(pages::Citywide::new_state(ctx, app))),
                 "Plan route" => Some(Transition::Replace(pages::RoutePlanner::new_state(
                     ctx, app,
                 ))),
@@ -72,6 +73,7 @@ impl AppwidePanel {
                     ctx, app,
                 ))),
                 "Census" => Some(Transition::Replace(pages::Census::new_state(ctx, app))),
+                "Annotate" => Some(Transition::Replace(pages::Annotate::new_state(ctx, app))),
                 _ => unreachable!(),
             };
         }
@@ -154,6 +156,16 @@ fn make_top_panel(ctx: &mut EventCtx, app: &App, mode: Mode) -> Panel {
                     .disabled_tooltip("Pick an area first")
                     .build_def(ctx)
             },
+            if mode == Mode::Citywide {
+                current_mode(ctx, "Citywide")
+            } else {
+                ctx.style()
+                    .btn_outline
+                    .text("Citywide")
+                    .disabled(app.per_map.consultation.is_some())
+                    .disabled_tooltip("This consultation is only about the current area")
+                    .build_def(ctx)
+            },
             if mode == Mode::RoutePlanner {
                 current_mode(ctx, "Plan route")
             } else {
@@ -196,6 +208,16 @@ fn make_top_panel(ctx: &mut EventCtx, app: &App, mode: Mode) -> Panel {
             } else {
                 ctx.style().btn_outline.text("Census").build_def(ctx)
             },
+            if mode == Mode::Annotate {
+                current_mode(ctx, "Annotate")
+            } else {
+                ctx.style()
+                    .btn_outline
+                    .text("Annotate")
+                    .disabled(app.per_map.consultation.is_some())
+                    .disabled_tooltip("Not supported here yet")
+                    .build_def(ctx)
+            },
         ])
         .centered_vert()
     } else {
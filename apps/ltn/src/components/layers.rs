@@ -121,10 +121,10 @@ impl Layers {
     pub fn draw(&self, g: &mut GfxCtx, app: &App) {
         self.panel.draw(g);
         if self.show_bus_routes {
-            g.redraw(&app.per_map.draw_bus_routes);
+            app.per_map.draw_bus_routes.draw(g);
         }
         if self.show_turn_restrictions {
-            g.redraw(&app.per_map.draw_turn_restrictions);
+            app.per_map.draw_turn_restrictions.draw(g);
         }
     }
 
@@ -197,7 +197,7 @@ impl Layers {
             {
                 let checkbox = Toggle::checkbox(ctx, "show bus routes", None, self.show_bus_routes);
                 if self.show_bus_routes {
-                    checkbox.outline((1.0, *colors::BUS_ROUTE))
+                    checkbox.outline((1.0, cs.bus_layer))
                 } else {
                     checkbox
                 }
@@ -312,7 +312,7 @@ impl Mode {
                         150.0,
                     ),
                 ]),
-                Widget::row(vec!["Cells:".text_widget(ctx), color_grid(ctx)]),
+                Widget::row(vec!["Cells:".text_widget(ctx), color_grid(ctx, cs)]),
                 Widget::row(vec![
                     "Modal filters:".text_widget(ctx),
                     Image::from_path(filter_svg_path(FilterType::WalkCycleOnly))
@@ -415,6 +415,8 @@ impl Mode {
                 ),
             ],
             Mode::Census => vec![],
+            Mode::Citywide => vec![],
+            Mode::Annotate => vec![],
         })
     }
 }
@@ -456,12 +458,12 @@ fn entry_tooltip(
         .build_def(ctx)
 }
 
-fn color_grid(ctx: &mut EventCtx) -> Widget {
+fn color_grid(ctx: &mut EventCtx, cs: &ColorScheme) -> Widget {
     let size = 16.0;
     let columns = 3;
     let mut batch = GeomBatch::new();
 
-    for (i, color) in colors::CELLS.iter().enumerate() {
+    for (i, color) in cs.categorical.iter().enumerate() {
         let row = (i / columns) as f64;
         let column = (i % columns) as f64;
         batch.push(
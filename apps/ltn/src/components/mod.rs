@@ -11,6 +11,7 @@ pub enum Mode {
     PickArea,
     FreehandBoundary,
     ModifyNeighbourhood,
+    Citywide,
     SelectBoundary,
     PerResidentImpact,
     RoutePlanner,
@@ -18,4 +19,5 @@ pub enum Mode {
     Impact,
     CycleNetwork,
     Census,
+    Annotate,
 }
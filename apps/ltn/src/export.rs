@@ -5,6 +5,7 @@ use geojson::{Feature, FeatureCollection, GeoJson, Value};
 use geom::{PolyLine, Pt2D};
 use osm2streets::Direction;
 
+use crate::stats::NeighbourhoodStats;
 use crate::{render, App, Neighbourhood};
 
 pub fn geojson_string(app: &App) -> Result<String> {
@@ -14,12 +15,14 @@ pub fn geojson_string(app: &App) -> Result<String> {
 
     // All neighbourhood boundaries
     for (id, info) in app.partitioning().all_neighbourhoods() {
+        let neighbourhood = Neighbourhood::new(app, *id);
         let mut feature = Feature::from(info.block.polygon.to_geojson(gps_bounds));
         feature.set_property("type", "neighbourhood");
+        NeighbourhoodStats::new(map, &neighbourhood).to_geojson_properties(&mut feature);
         features.push(feature);
 
         // Cells per neighbourhood
-        let render_cells = render::RenderCells::new(map, &Neighbourhood::new(app, *id));
+        let render_cells = render::RenderCells::new(map, &neighbourhood, &app.cs.categorical);
         for (idx, mut multipolygon) in render_cells.to_multipolygons().into_iter().enumerate() {
             // Transform to WGS84
             multipolygon.map_coords_in_place(|c| {
@@ -92,6 +95,10 @@ pub fn geojson_string(app: &App) -> Result<String> {
         }
     }
 
+    for annotation in &app.per_map.proposals.get_current().annotations {
+        features.push(annotation.to_geojson(map.get_gps_bounds())?);
+    }
+
     let gj = GeoJson::FeatureCollection(FeatureCollection {
         features,
         bbox: None,
@@ -24,6 +24,7 @@ mod neighbourhood;
 pub mod pages;
 mod render;
 pub mod save;
+pub mod stats;
 
 pub fn main() {
     let settings = Settings::new("Low traffic neighbourhoods");
@@ -226,6 +227,8 @@ pub fn run_wasm(root_dom_id: String, assets_base_url: String, assets_are_gzipped
 pub fn redraw_all_icons(ctx: &EventCtx, app: &mut App) {
     app.per_map.draw_all_filters = render::render_modal_filters(ctx, &app.per_map.map);
     app.per_map.draw_turn_restrictions = render::render_turn_restrictions(ctx, &app.per_map.map);
+    app.per_map.draw_annotations =
+        render::render_annotations(ctx, &app.per_map.proposals.get_current().annotations);
 }
 
 fn is_private(road: &Road) -> bool {
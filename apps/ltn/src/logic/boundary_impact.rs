@@ -0,0 +1,155 @@
+use std::collections::BTreeSet;
+
+use abstutil::{Counter, Timer};
+use map_model::{
+    IntersectionID, LaneID, Map, PathConstraints, PathRequest, PathStepV2, Pathfinder, Position,
+    RoadID,
+};
+
+use crate::logic::Shortcuts;
+use crate::{App, Neighbourhood};
+
+/// When new filters stop shortcuts through a neighbourhood, the drivers taking them have to go
+/// around on the boundary roads instead. This is a quick estimate of where they'd go, without
+/// running a simulation: every shortcut possible before any changes but blocked now is assigned to
+/// the fastest route around the perimeter between the same entrance and exit.
+///
+/// There's no demand model here, so the numbers are counts of shortcuts, not vehicles. They're
+/// only useful to compare boundary roads with each other.
+pub struct BoundaryImpact {
+    /// How many diverted shortcuts use each road outside the neighbourhood
+    pub diverted_per_road: Counter<RoadID>,
+    pub num_shortcuts_before: usize,
+    /// Shortcuts possible before any changes, but not anymore
+    pub num_diverted: usize,
+    /// Diverted shortcuts with no route around the perimeter
+    pub num_unassigned: usize,
+}
+
+impl BoundaryImpact {
+    pub fn new(app: &App, neighbourhood: &Neighbourhood, timer: &mut Timer) -> Self {
+        let map = &app.per_map.map;
+        let before = Shortcuts::new_with_params(
+            map,
+            neighbourhood,
+            app.per_map.routing_params_before_changes.clone(),
+            timer,
+        );
+        let still_possible: BTreeSet<(LaneID, LaneID)> = neighbourhood
+            .shortcuts
+            .paths
+            .iter()
+            .map(|path| endpoints(path.get_req()))
+            .collect();
+
+        let mut requests = Vec::new();
+        let mut num_unassigned = 0;
+        for path in &before.paths {
+            let (start, end) = endpoints(path.get_req());
+            if still_possible.contains(&(start, end)) {
+                continue;
+            }
+            match detour_request(map, neighbourhood, start, end) {
+                Some(req) => requests.push(req),
+                None => {
+                    num_unassigned += 1;
+                }
+            }
+        }
+        let num_diverted = requests.len() + num_unassigned;
+
+        let mut diverted_per_road = Counter::new();
+        if !requests.is_empty() {
+            // Stay out of the interior entirely
+            let mut params = map.routing_params_respecting_modal_filters();
+            params
+                .avoid_roads
+                .extend(neighbourhood.interior_roads.iter().cloned());
+            let pathfinder =
+                Pathfinder::new_dijkstra(map, params, vec![PathConstraints::Car], timer);
+            for maybe_path in timer.parallelize(
+                "route diverted shortcuts around the perimeter",
+                requests,
+                |req| pathfinder.pathfind_v2(req, map),
+            ) {
+                match maybe_path {
+                    Some(path) => {
+                        for step in path.get_steps() {
+                            if let PathStepV2::Along(dr) = step {
+                                diverted_per_road.inc(dr.road);
+                            }
+                        }
+                    }
+                    None => {
+                        num_unassigned += 1;
+                    }
+                }
+            }
+        }
+
+        Self {
+            diverted_per_road,
+            num_shortcuts_before: before.paths.len(),
+            num_diverted,
+            num_unassigned,
+        }
+    }
+
+    /// The perimeter roads that would absorb some diverted traffic, with the most affected first
+    pub fn affected_perimeter_roads(&self, neighbourhood: &Neighbourhood) -> Vec<(RoadID, usize)> {
+        let mut roads: Vec<(RoadID, usize)> = self
+            .diverted_per_road
+            .borrow()
+            .iter()
+            .filter(|(r, _)| neighbourhood.perimeter_roads.contains(r))
+            .map(|(r, cnt)| (*r, *cnt))
+            .collect();
+        roads.sort_by_key(|(r, cnt)| (std::cmp::Reverse(*cnt), *r));
+        roads
+    }
+}
+
+fn endpoints(req: &PathRequest) -> (LaneID, LaneID) {
+    (req.start.lane(), req.end.lane())
+}
+
+/// Shortcuts start on the first interior lane and end on the last one. The detour has to start
+/// just before entering the neighbourhood and end just after leaving it.
+fn detour_request(
+    map: &Map,
+    neighbourhood: &Neighbourhood,
+    entrance: LaneID,
+    exit: LaneID,
+) -> Option<PathRequest> {
+    let from = outside_lane(map, neighbourhood, map.get_l(entrance).src_i, true)?;
+    let to = outside_lane(map, neighbourhood, map.get_l(exit).dst_i, false)?;
+    Some(PathRequest::vehicle(
+        Position::end(from, map),
+        Position::start(to),
+        PathConstraints::Car,
+    ))
+}
+
+/// Find a driving lane at a border intersection that isn't part of the neighbourhood, preferring
+/// ones on the perimeter
+fn outside_lane(
+    map: &Map,
+    neighbourhood: &Neighbourhood,
+    i: IntersectionID,
+    incoming: bool,
+) -> Option<LaneID> {
+    let lanes = if incoming {
+        map.get_i(i).get_incoming_lanes(map, PathConstraints::Car)
+    } else {
+        map.get_i(i).get_outgoing_lanes(map, PathConstraints::Car)
+    };
+    let candidates: Vec<LaneID> = lanes
+        .into_iter()
+        .filter(|l| !neighbourhood.interior_roads.contains(&l.road))
+        .collect();
+    candidates
+        .iter()
+        .find(|l| neighbourhood.perimeter_roads.contains(&l.road))
+        .or_else(|| candidates.first())
+        .cloned()
+}
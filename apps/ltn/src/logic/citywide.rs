@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use abstutil::Timer;
+use map_model::{Map, RoadID};
+
+use crate::logic::Partitioning;
+use crate::{App, Neighbourhood, NeighbourhoodID};
+
+/// Summarizes how the current proposal changes every neighbourhood, so a scheme spanning many
+/// areas can be understood at once.
+pub struct CitywideSummary {
+    /// Only neighbourhoods with some change
+    pub neighbourhoods: Vec<NeighbourhoodChanges>,
+    /// Predicted traffic along the boundary roads of all changed neighbourhoods, before and after
+    /// the proposal. Only known when impact prediction has run for the current edits.
+    pub boundary_traffic: Option<(usize, usize)>,
+}
+
+pub struct NeighbourhoodChanges {
+    pub id: NeighbourhoodID,
+    pub new_filters: usize,
+    pub changed_directions: usize,
+    pub interior_streets: usize,
+    pub quiet_streets: usize,
+    pub shortcuts: usize,
+    pub perimeter_roads: BTreeSet<RoadID>,
+    /// Like `CitywideSummary::boundary_traffic`, but just for this neighbourhood's perimeter
+    pub boundary_traffic: Option<(usize, usize)>,
+}
+
+impl CitywideSummary {
+    pub fn new(app: &App, timer: &mut Timer) -> Self {
+        let map = &app.per_map.map;
+        let partitioning = app.partitioning();
+
+        let candidates = neighbourhoods_with_edits(map, partitioning);
+        let mut neighbourhoods: Vec<NeighbourhoodChanges> = timer
+            .parallelize(
+                "summarize changed neighbourhoods",
+                candidates.into_iter().collect(),
+                |id| {
+                    let neighbourhood = Neighbourhood::new_without_app(map, partitioning, id);
+                    NeighbourhoodChanges::new(map, &neighbourhood)
+                },
+            )
+            .into_iter()
+            .filter(|n| n.new_filters > 0 || n.changed_directions > 0)
+            .collect();
+
+        // Only use impact prediction results if they match the current edits
+        let impact = &app.per_map.impact;
+        let counts =
+            if &impact.map == map.get_name() && impact.map_edit_key == map.get_edits_change_key() {
+                Some((
+                    &impact.compare_counts.counts_a.per_road,
+                    &impact.compare_counts.counts_b.per_road,
+                ))
+            } else {
+                None
+            };
+
+        let mut all_perimeter_roads = BTreeSet::new();
+        for n in &mut neighbourhoods {
+            all_perimeter_roads.extend(n.perimeter_roads.iter().cloned());
+            if let Some((before, after)) = counts {
+                n.boundary_traffic = Some((
+                    n.perimeter_roads.iter().map(|r| before.get(*r)).sum(),
+                    n.perimeter_roads.iter().map(|r| after.get(*r)).sum(),
+                ));
+            }
+        }
+        let boundary_traffic = counts.map(|(before, after)| {
+            (
+                all_perimeter_roads.iter().map(|r| before.get(*r)).sum(),
+                all_perimeter_roads.iter().map(|r| after.get(*r)).sum(),
+            )
+        });
+
+        Self {
+            neighbourhoods,
+            boundary_traffic,
+        }
+    }
+
+    pub fn total_filters(&self) -> usize {
+        self.neighbourhoods.iter().map(|n| n.new_filters).sum()
+    }
+
+    pub fn total_interior_streets(&self) -> usize {
+        self.neighbourhoods.iter().map(|n| n.interior_streets).sum()
+    }
+
+    pub fn total_boundary_roads(&self) -> usize {
+        let mut roads = BTreeSet::new();
+        for n in &self.neighbourhoods {
+            roads.extend(n.perimeter_roads.iter().cloned());
+        }
+        roads.len()
+    }
+}
+
+impl NeighbourhoodChanges {
+    fn new(map: &Map, neighbourhood: &Neighbourhood) -> Self {
+        let edits = map.get_edits();
+        let mut new_filters = 0;
+        let mut changed_directions = 0;
+        for (r, orig) in &edits.original_roads {
+            if !neighbourhood.interior_roads.contains(r) {
+                continue;
+            }
+            let road = map.get_r(*r);
+            if road.modal_filter.is_some() && orig.modal_filter.is_none() {
+                new_filters += 1;
+            }
+            if road
+                .lanes
+                .iter()
+                .map(|l| l.dir)
+                .ne(orig.lanes_ltr.iter().map(|l| l.dir))
+            {
+                changed_directions += 1;
+            }
+        }
+        for (i, orig) in &edits.original_intersections {
+            if neighbourhood.interior_intersections.contains(i)
+                && map.get_i(*i).modal_filter.is_some()
+                && orig.modal_filter.is_none()
+            {
+                new_filters += 1;
+            }
+        }
+
+        let (quiet_streets, interior_streets) = neighbourhood
+            .shortcuts
+            .quiet_and_total_streets(neighbourhood);
+        Self {
+            id: neighbourhood.id,
+            new_filters,
+            changed_directions,
+            interior_streets,
+            quiet_streets,
+            shortcuts: neighbourhood.shortcuts.paths.len(),
+            perimeter_roads: neighbourhood.perimeter_roads.clone(),
+            boundary_traffic: None,
+        }
+    }
+}
+
+/// Cheaply finds neighbourhoods that might contain an edited road or intersection, without
+/// calculating shortcuts for every neighbourhood in the map.
+fn neighbourhoods_with_edits(map: &Map, partitioning: &Partitioning) -> BTreeSet<NeighbourhoodID> {
+    // Roads on the perimeter may belong to two neighbourhoods
+    let mut road_to_neighbourhood: BTreeMap<RoadID, BTreeSet<NeighbourhoodID>> = BTreeMap::new();
+    for (id, info) in partitioning.all_neighbourhoods() {
+        let perimeter = &info.block.perimeter;
+        for r in perimeter
+            .interior
+            .iter()
+            .chain(perimeter.roads.iter().map(|id| &id.road))
+        {
+            road_to_neighbourhood.entry(*r).or_default().insert(*id);
+        }
+    }
+    for (id, custom) in &partitioning.custom_boundaries {
+        for r in &custom.interior_roads {
+            road_to_neighbourhood.entry(*r).or_default().insert(*id);
+        }
+    }
+
+    let edits = map.get_edits();
+    let mut result = BTreeSet::new();
+    let mut add = |r: &RoadID| {
+        if let Some(ids) = road_to_neighbourhood.get(r) {
+            result.extend(ids.iter().cloned());
+        }
+    };
+    for r in edits.original_roads.keys() {
+        add(r);
+    }
+    for i in edits.original_intersections.keys() {
+        for r in &map.get_i(*i).roads {
+            add(r);
+        }
+    }
+    result
+}
@@ -1,4 +1,6 @@
 mod auto_filters;
+mod boundary_impact;
+mod citywide;
 mod existing;
 pub mod impact;
 mod partition;
@@ -6,6 +8,8 @@ mod shortcuts;
 pub mod turn_restrictions;
 
 pub use auto_filters::AutoFilterHeuristic;
+pub use boundary_impact::BoundaryImpact;
+pub use citywide::{CitywideSummary, NeighbourhoodChanges};
 pub use existing::transform_existing;
 pub use impact::Impact;
 pub use partition::{BlockID, CustomBoundary, NeighbourhoodID, Partitioning};
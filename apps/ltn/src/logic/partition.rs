@@ -4,7 +4,7 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
-use abstutil::Timer;
+use abstutil::{deserialize_btreemap, serialize_btreemap, Timer};
 use blockfinding::{Block, Perimeter};
 use geom::Polygon;
 use map_model::osm::RoadRank;
@@ -37,7 +37,12 @@ pub struct Partitioning {
     // TODO Possibly this never happens anymore and can go away
     pub broken: bool,
 
-    #[serde(skip_serializing, skip_deserializing)]
+    /// Drawn or imported by the user, instead of made from blocks
+    #[serde(
+        default,
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
     pub custom_boundaries: BTreeMap<NeighbourhoodID, CustomBoundary>,
 }
 
@@ -58,7 +63,7 @@ impl NeighbourhoodInfo {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CustomBoundary {
     pub name: String,
     pub boundary_polygon: Polygon,
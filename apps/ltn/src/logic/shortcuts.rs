@@ -4,7 +4,7 @@ use abstutil::{Counter, Timer};
 use map_gui::tools::ColorNetwork;
 use map_model::{
     DirectedRoadID, IntersectionID, LaneID, Map, PathConstraints, PathRequest, PathStepV2, PathV2,
-    Pathfinder, Position, RoadID,
+    Pathfinder, Position, RoadID, RoutingParams,
 };
 use widgetry::GeomBatch;
 
@@ -27,6 +27,21 @@ impl Shortcuts {
     }
 
     pub fn new(map: &Map, neighbourhood: &Neighbourhood, timer: &mut Timer) -> Self {
+        Self::new_with_params(
+            map,
+            neighbourhood,
+            map.routing_params_respecting_modal_filters(),
+            timer,
+        )
+    }
+
+    /// Like `new`, but respecting some other set of filters, like the ones before any changes.
+    pub fn new_with_params(
+        map: &Map,
+        neighbourhood: &Neighbourhood,
+        mut params: RoutingParams,
+        timer: &mut Timer,
+    ) -> Self {
         // The overall approach: look for all possible paths from an entrance to an exit, only if they
         // connect to different major roads.
         //
@@ -64,8 +79,6 @@ impl Shortcuts {
             return Self::empty();
         }
 
-        let mut params = map.routing_params_respecting_modal_filters();
-
         // Restrict the pathfinding to the interior of the neighbourhood only. Don't allow using
         // perimeter roads or leaving and re-entering at all.
         //
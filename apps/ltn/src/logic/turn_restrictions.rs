@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use geom::{Polygon, Pt2D};
+use geom::{Distance, Polygon, Pt2D};
 use map_model::{IntersectionID, Map, RoadID};
 use osm2streets::{Direction, RestrictionType};
 
@@ -78,7 +78,7 @@ impl FocusedTurns {
 
         let restricted_t = restricted_destination_roads(map, r, Some(i));
         let possible_t = possible_destination_roads(map, r, Some(i));
-        let hull = hull_around_focused_turns(map, r, &possible_t, &restricted_t);
+        let hull = hull_around_focused_turns(map, i, r, &possible_t, &restricted_t);
 
         FocusedTurns {
             from_r: r,
@@ -92,29 +92,32 @@ impl FocusedTurns {
 
 fn hull_around_focused_turns(
     map: &Map,
+    i: IntersectionID,
     r: RoadID,
     permitted_t: &HashSet<RoadID>,
     restricted_t: &HashSet<RoadID>,
 ) -> Polygon {
-    let mut all_pt: Vec<Pt2D> = Vec::new();
-
     let mut all_r = HashSet::from([r]);
     all_r.extend(permitted_t);
     all_r.extend(restricted_t);
 
+    // Every road meets at the intersection, so the union should be one polygon. Pad it a bit, so
+    // the outline doesn't hug the roads.
+    let mut polygons = vec![map.get_i(i).polygon.clone()];
     for other_r in all_r {
-        all_pt.extend(
-            map.get_r(other_r)
-                .get_thick_polygon()
-                .get_outer_ring()
-                .clone()
-                .into_points(),
-        );
+        polygons.push(map.get_r(other_r).get_thick_polygon());
     }
+    Polygon::union_all(polygons)
+        .ok()
+        .and_then(largest)
+        .and_then(|p| p.buffer(Distance::meters(5.0)).ok())
+        .and_then(largest)
+        .unwrap_or(Polygon::dummy())
+}
 
-    // TODO the `200` value seems to work for some cases. But it is arbitrary and there is no science
-    // behind its the value. Need to work out what is an appropriate value _and why_.
-    Polygon::concave_hull(all_pt, 200).unwrap_or(Polygon::dummy())
+fn largest(list: Vec<Polygon>) -> Option<Polygon> {
+    list.into_iter()
+        .max_by(|a, b| a.area().partial_cmp(&b.area()).unwrap())
 }
 
 /// Returns all roads that are possible destinations from the given "from_road" where the turn is currently
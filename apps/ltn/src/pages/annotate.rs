@@ -0,0 +1,239 @@
+use geom::Distance;
+use widgetry::tools::{Lasso, PolyLineLasso, PromptInput};
+use widgetry::{lctrl, EventCtx, GfxCtx, Key, Line, Outcome, Panel, State, Text, TextExt, Widget};
+
+use crate::components::{AppwidePanel, BottomPanel, Mode};
+use crate::save::Annotation;
+use crate::{App, Transition};
+
+/// Place pins, arrows, and highlighted areas with notes on the map, to explain a proposal.
+pub struct Annotate {
+    appwide_panel: AppwidePanel,
+    bottom_panel: Panel,
+    tool: Option<Tool>,
+}
+
+enum Tool {
+    Pin,
+    Arrow(PolyLineLasso),
+    Area(Lasso),
+}
+
+impl Annotate {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        Box::new(Self::new(ctx, app, None))
+    }
+
+    fn new(ctx: &mut EventCtx, app: &mut App, tool: Option<Tool>) -> Self {
+        let appwide_panel = AppwidePanel::new(ctx, app, Mode::Annotate);
+        let bottom_panel = make_bottom_panel(ctx, app, &appwide_panel, tool.as_ref());
+        app.session
+            .layers
+            .show_panel(ctx, &app.cs, Some(&bottom_panel));
+
+        Self {
+            appwide_panel,
+            bottom_panel,
+            tool,
+        }
+    }
+
+    fn set_tool(&mut self, ctx: &mut EventCtx, app: &App, tool: Option<Tool>) {
+        self.tool = tool;
+        self.bottom_panel = make_bottom_panel(ctx, app, &self.appwide_panel, self.tool.as_ref());
+    }
+}
+
+impl State<App> for Annotate {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(t) =
+            self.appwide_panel
+                .event(ctx, app, &crate::save::PreserveState::Annotate, help)
+        {
+            return t;
+        }
+        if let Some(t) =
+            app.session
+                .layers
+                .event(ctx, &app.cs, Mode::Annotate, Some(&self.bottom_panel))
+        {
+            return t;
+        }
+        if let Outcome::Clicked(x) = self.bottom_panel.event(ctx) {
+            match x.as_ref() {
+                "pin" => {
+                    self.set_tool(ctx, app, Some(Tool::Pin));
+                }
+                "arrow" => {
+                    self.set_tool(ctx, app, Some(Tool::Arrow(PolyLineLasso::new())));
+                }
+                "area" => {
+                    self.set_tool(
+                        ctx,
+                        app,
+                        Some(Tool::Area(Lasso::new(Distance::meters(1.0)))),
+                    );
+                }
+                "pan" => {
+                    self.set_tool(ctx, app, None);
+                }
+                "undo" => {
+                    app.per_map.proposals.mut_annotations().pop();
+                    crate::redraw_all_icons(ctx, app);
+                    self.set_tool(ctx, app, None);
+                }
+                _ => unreachable!(),
+            }
+            return Transition::Keep;
+        }
+
+        let placed = match self.tool {
+            None => {
+                ctx.canvas_movement();
+                None
+            }
+            Some(Tool::Pin) => {
+                ctx.canvas_movement();
+                if ctx.normal_left_click() {
+                    ctx.canvas
+                        .get_cursor_in_map_space()
+                        .map(|pt| Annotation::pin(pt, String::new()))
+                } else {
+                    None
+                }
+            }
+            Some(Tool::Arrow(ref mut lasso)) => lasso
+                .event(ctx)
+                .map(|pl| Annotation::arrow(pl, String::new())),
+            Some(Tool::Area(ref mut lasso)) => lasso
+                .event(ctx)
+                .map(|polygon| Annotation::area(polygon, String::new())),
+        };
+
+        if let Some(annotation) = placed {
+            // Reset the tool, but keep the same kind selected
+            self.tool = Some(match self.tool.take().unwrap() {
+                Tool::Pin => Tool::Pin,
+                Tool::Arrow(_) => Tool::Arrow(PolyLineLasso::new()),
+                Tool::Area(_) => Tool::Area(Lasso::new(Distance::meters(1.0))),
+            });
+            return Transition::Push(PromptInput::new_state(
+                ctx,
+                "What does this show?",
+                String::new(),
+                Box::new(move |text, ctx, app| {
+                    let mut annotation = annotation;
+                    annotation.text = text;
+                    app.per_map.proposals.mut_annotations().push(annotation);
+                    crate::redraw_all_icons(ctx, app);
+                    Transition::Multi(vec![Transition::Pop, Transition::Recreate])
+                }),
+            ));
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.appwide_panel.draw(g);
+        self.bottom_panel.draw(g);
+        app.per_map.draw_major_road_labels.draw(g);
+        app.session.layers.draw(g, app);
+        app.per_map.draw_all_filters.draw(g);
+        app.per_map.draw_poi_icons.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
+        match self.tool {
+            Some(Tool::Arrow(ref lasso)) => lasso.draw(g),
+            Some(Tool::Area(ref lasso)) => lasso.draw(g),
+            _ => {}
+        }
+    }
+
+    fn recreate(&mut self, ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        // Keep using the same tool
+        Box::new(Self::new(ctx, app, self.tool.take()))
+    }
+}
+
+fn help() -> Vec<&'static str> {
+    vec![
+        "Explain your proposal with notes on the map.",
+        "Annotations are saved with the proposal and included when exporting GeoJSON.",
+    ]
+}
+
+fn make_bottom_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    appwide_panel: &AppwidePanel,
+    tool: Option<&Tool>,
+) -> Panel {
+    let num_annotations = app.per_map.proposals.get_current().annotations.len();
+
+    let button =
+        |ctx: &mut EventCtx, icon: &str, name: &str, key: Key, selected: bool, help: &str| {
+            ctx.style()
+                .btn_solid_primary
+                .icon(icon)
+                .hotkey(key)
+                .disabled(selected)
+                .tooltip_and_disabled({
+                    let mut txt = Text::new();
+                    txt.append(Line(name));
+                    txt.add_line(Line(help));
+                    txt
+                })
+                .build_widget(ctx, name)
+        };
+
+    BottomPanel::new(
+        ctx,
+        appwide_panel,
+        Widget::row(vec![
+            button(
+                ctx,
+                "system/assets/tools/pan.svg",
+                "pan",
+                Key::Escape,
+                tool.is_none(),
+                "Move around the map",
+            ),
+            button(
+                ctx,
+                "system/assets/tools/pin.svg",
+                "pin",
+                Key::F1,
+                matches!(tool, Some(Tool::Pin)),
+                "Click to place a note",
+            ),
+            button(
+                ctx,
+                "system/assets/tools/shortcut.svg",
+                "arrow",
+                Key::F2,
+                matches!(tool, Some(Tool::Arrow(_))),
+                "Click and drag to draw an arrow",
+            ),
+            button(
+                ctx,
+                "system/assets/tools/select.svg",
+                "area",
+                Key::F3,
+                matches!(tool, Some(Tool::Area(_))),
+                "Click and drag to highlight an area",
+            ),
+            Widget::vertical_separator(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_plain
+                    .icon("system/assets/tools/undo.svg")
+                    .disabled(num_annotations == 0)
+                    .hotkey(lctrl(Key::Z))
+                    .build_widget(ctx, "undo"),
+                format!("{num_annotations} annotations")
+                    .text_widget(ctx)
+                    .centered_vert(),
+            ]),
+        ]),
+    )
+}
@@ -148,6 +148,7 @@ impl State<App> for Census {
         app.per_map.draw_major_road_labels.draw(g);
         app.session.layers.draw(g, app);
         app.per_map.draw_all_filters.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
         app.per_map.draw_poi_icons.draw(g);
         self.world.draw(g);
     }
@@ -0,0 +1,209 @@
+use abstutil::prettyprint_usize;
+use geom::Polygon;
+use widgetry::mapspace::{World, WorldOutcome};
+use widgetry::{
+    DrawBaselayer, EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, TextExt, Widget,
+};
+
+use crate::components::{AppwidePanel, LeftPanel, Mode};
+use crate::logic::{CitywideSummary, NeighbourhoodChanges};
+use crate::render::colors;
+use crate::{pages, App, NeighbourhoodID, Transition};
+
+/// Summarizes every neighbourhood changed by the current proposal, so a scheme spanning many
+/// areas can be assessed as a whole. Click a neighbourhood to keep designing it.
+pub struct Citywide {
+    appwide_panel: AppwidePanel,
+    left_panel: Panel,
+    world: World<NeighbourhoodID>,
+}
+
+impl Citywide {
+    pub fn new_state(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        let summary = ctx.loading_screen("summarize proposal", |_, timer| {
+            CitywideSummary::new(app, timer)
+        });
+
+        let appwide_panel = AppwidePanel::new(ctx, app, Mode::Citywide);
+        let left_panel =
+            LeftPanel::right_of_proposals(ctx, &appwide_panel, make_contents(ctx, app, &summary))
+                .build(ctx);
+        app.session.layers.event(ctx, &app.cs, Mode::Citywide, None);
+
+        let mut world = World::new();
+        for n in &summary.neighbourhoods {
+            let quiet = if n.interior_streets == 0 {
+                1.0
+            } else {
+                n.quiet_streets as f64 / n.interior_streets as f64
+            };
+            world
+                .add(n.id)
+                .hitbox(neighbourhood_polygon(app, n.id))
+                .draw_color(app.cs.good_to_bad_red.eval(1.0 - quiet).alpha(0.5))
+                .hover_color(colors::HOVER)
+                .clickable()
+                .tooltip(describe(app, n))
+                .build(ctx);
+        }
+        world.initialize_hover(ctx);
+
+        Box::new(Self {
+            appwide_panel,
+            left_panel,
+            world,
+        })
+    }
+}
+
+impl State<App> for Citywide {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(t) =
+            self.appwide_panel
+                .event(ctx, app, &crate::save::PreserveState::Citywide, help)
+        {
+            self.world.hack_unset_hovering();
+            return t;
+        }
+        if let Some(t) = app.session.layers.event(ctx, &app.cs, Mode::Citywide, None) {
+            return t;
+        }
+
+        if let Outcome::Clicked(x) = self.left_panel.event(ctx) {
+            if let Some(id) = x.strip_prefix("neighbourhood ") {
+                let id = NeighbourhoodID(id.parse::<usize>().unwrap());
+                return Transition::Replace(pages::DesignLTN::new_state(ctx, app, id));
+            }
+            unreachable!()
+        }
+
+        if let WorldOutcome::ClickedObject(id) = self.world.event(ctx) {
+            return Transition::Replace(pages::DesignLTN::new_state(ctx, app, id));
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        app.draw_with_layering(g, |g| self.world.draw(g));
+
+        self.appwide_panel.draw(g);
+        self.left_panel.draw(g);
+        app.per_map.draw_major_road_labels.draw(g);
+        app.session.layers.draw(g, app);
+        app.per_map.draw_all_filters.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
+        app.per_map.draw_poi_icons.draw(g);
+    }
+
+    fn recreate(&mut self, ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        Self::new_state(ctx, app)
+    }
+}
+
+fn make_contents(ctx: &mut EventCtx, app: &App, summary: &CitywideSummary) -> Widget {
+    let mut col = vec![Line("Citywide scheme").small_heading().into_widget(ctx)];
+    if summary.neighbourhoods.is_empty() {
+        col.push("No neighbourhoods have been changed yet".text_widget(ctx));
+        return Widget::col(col);
+    }
+
+    let mut txt = Text::new();
+    txt.add_line(format!(
+        "{} neighbourhoods changed",
+        summary.neighbourhoods.len()
+    ));
+    txt.add_line(format!(
+        "{} new filters",
+        prettyprint_usize(summary.total_filters())
+    ));
+    txt.add_line(format!(
+        "{} streets inside changed neighbourhoods",
+        prettyprint_usize(summary.total_interior_streets())
+    ));
+    txt.add_line(format!(
+        "{} boundary roads",
+        prettyprint_usize(summary.total_boundary_roads())
+    ));
+    match summary.boundary_traffic {
+        Some((before, after)) => {
+            txt.add_line(format!(
+                "Predicted traffic on boundary roads: {} before, {} after",
+                prettyprint_usize(before),
+                prettyprint_usize(after)
+            ));
+        }
+        None => {
+            txt.add_line(
+                Line("Use \"Predict impact\" to estimate changes on boundary roads").secondary(),
+            );
+        }
+    }
+    col.push(txt.into_widget(ctx));
+    col.push(Widget::horiz_separator(ctx, 1.0));
+
+    for n in &summary.neighbourhoods {
+        col.push(
+            ctx.style()
+                .btn_outline
+                .text(neighbourhood_name(app, n.id))
+                .build_widget(ctx, format!("neighbourhood {}", n.id.0)),
+        );
+        col.push(describe(app, n).into_widget(ctx));
+    }
+    Widget::col(col)
+}
+
+fn describe(app: &App, n: &NeighbourhoodChanges) -> Text {
+    let mut txt = Text::from(Line(neighbourhood_name(app, n.id)));
+    txt.add_line(format!(
+        "{} new filters, {} road directions changed",
+        n.new_filters, n.changed_directions
+    ));
+    txt.add_line(format!(
+        "{} of {} streets have no shortcuts",
+        n.quiet_streets, n.interior_streets
+    ));
+    if let Some((before, after)) = n.boundary_traffic {
+        txt.add_line(
+            Line(format!(
+                "Boundary road traffic: {} before, {} after",
+                prettyprint_usize(before),
+                prettyprint_usize(after)
+            ))
+            .secondary(),
+        );
+    }
+    txt
+}
+
+fn neighbourhood_name(app: &App, id: NeighbourhoodID) -> String {
+    match app.partitioning().custom_boundaries.get(&id) {
+        Some(custom) => custom.name.clone(),
+        None => format!(
+            "Neighbourhood #{} ({})",
+            id.0,
+            app.partitioning().neighbourhood_area_km2(id)
+        ),
+    }
+}
+
+fn neighbourhood_polygon(app: &App, id: NeighbourhoodID) -> Polygon {
+    match app.partitioning().custom_boundaries.get(&id) {
+        Some(custom) => custom.boundary_polygon.clone(),
+        None => app.partitioning().neighbourhood_block(id).polygon.clone(),
+    }
+}
+
+fn help() -> Vec<&'static str> {
+    vec![
+        "This summarizes every neighbourhood changed by the current proposal.",
+        "",
+        "Areas are colored by how many streets still have shortcuts.",
+        "Click an area to keep designing it.",
+    ]
+}
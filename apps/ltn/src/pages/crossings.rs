@@ -3,7 +3,7 @@ use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
 use abstutil::PriorityQueueItem;
 use geom::{Circle, Duration};
 use map_model::{osm, Crossing, CrossingType, Road, RoadID};
-use widgetry::mapspace::{DrawCustomUnzoomedShapes, ObjectID, PerZoom, World, WorldOutcome};
+use widgetry::mapspace::{ObjectID, World, WorldOutcome};
 use widgetry::{
     lctrl, Color, ControlState, Drawable, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel,
     RewriteColor, State, Text, TextExt, Widget,
@@ -195,8 +195,7 @@ fn main_roads(app: &App) -> Vec<&Road> {
 }
 
 fn draw_crossings(ctx: &EventCtx, app: &App) -> Toggle3Zoomed {
-    let mut batch = GeomBatch::new();
-    let mut low_zoom = DrawCustomUnzoomedShapes::builder();
+    let mut builder = Toggle3Zoomed::builder();
 
     let mut icons = BTreeMap::new();
     for ct in [CrossingType::Signalized, CrossingType::Unsignalized] {
@@ -216,38 +215,25 @@ fn draw_crossings(ctx: &EventCtx, app: &App) -> Toggle3Zoomed {
             let icon = &icons[&crossing.kind];
             if let Ok((pt, angle)) = road.center_pts.dist_along(crossing.dist) {
                 let angle = angle.rotate_degs(90.0);
-                batch.append(
+                builder.zoomed.append(
                     icon.clone()
                         .scale_to_fit_width(road.get_width().inner_meters())
                         .centered_on(pt)
                         .rotate_around_batch_center(angle)
                         .color(rewrite_color),
                 );
-
-                // TODO Memory intensive
-                let icon = icon.clone();
-                // TODO They can shrink a bit past their map size
-                low_zoom.add_custom(Box::new(move |batch, thickness| {
-                    batch.append(
-                        icon.clone()
-                            .scale_to_fit_width(30.0 * thickness)
-                            .centered_on(pt)
-                            .rotate_around_batch_center(angle)
-                            .color(rewrite_color),
-                    );
-                }));
+                builder.add_icon(
+                    Crossings::svg_path(crossing.kind),
+                    icon.clone().color(rewrite_color),
+                    pt,
+                    angle,
+                    30.0,
+                );
             }
         }
     }
 
-    let min_zoom_for_detail = 5.0;
-    let step_size = 0.1;
-    // TODO Ideally we get rid of Toggle3Zoomed and make DrawCustomUnzoomedShapes handle this
-    // medium-zoom case.
-    Toggle3Zoomed::new(
-        batch.build(ctx),
-        low_zoom.build(PerZoom::new(min_zoom_for_detail, step_size)),
-    )
+    builder.build(ctx, 5.0)
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -0,0 +1,120 @@
+use abstutil::{prettyprint_usize, Counter};
+use geom::Percent;
+use map_gui::tools::ColorNetwork;
+use widgetry::{
+    DrawBaselayer, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Line, Panel, SimpleState,
+    State, Text, VerticalAlignment, Widget,
+};
+
+use super::road_name;
+use crate::logic::BoundaryImpact;
+use crate::{App, Neighbourhood, Transition};
+
+// Don't list every perimeter road
+const MAX_ROADS_LISTED: usize = 10;
+
+/// Shows where the shortcuts blocked by new filters would likely go instead.
+pub struct BoundaryImpactView {
+    draw: Drawable,
+}
+
+impl BoundaryImpactView {
+    pub fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        neighbourhood: &Neighbourhood,
+    ) -> Box<dyn State<App>> {
+        let impact = ctx.loading_screen("estimate boundary road impact", |_, timer| {
+            BoundaryImpact::new(app, neighbourhood, timer)
+        });
+        let affected = impact.affected_perimeter_roads(neighbourhood);
+
+        let mut colorer = ColorNetwork::no_fading(app);
+        let mut counter = Counter::new();
+        for (r, cnt) in &affected {
+            counter.add(*r, *cnt);
+        }
+        colorer.ranked_roads(counter, &app.cs.good_to_bad_red);
+
+        let mut txt = Text::new();
+        if impact.num_diverted == 0 {
+            txt.add_line(format!(
+                "None of the {} shortcuts possible before your changes have been blocked",
+                prettyprint_usize(impact.num_shortcuts_before)
+            ));
+        } else {
+            txt.add_line(format!(
+                "{} of the {} shortcuts possible before your changes are blocked now",
+                prettyprint_usize(impact.num_diverted),
+                prettyprint_usize(impact.num_shortcuts_before)
+            ));
+            if impact.num_unassigned > 0 {
+                txt.add_line(format!(
+                    "{} of them have no way around the perimeter",
+                    prettyprint_usize(impact.num_unassigned)
+                ));
+            }
+            txt.add_line("");
+            txt.add_line("Drivers would go around on:");
+            let map = &app.per_map.map;
+            for (r, cnt) in affected.iter().take(MAX_ROADS_LISTED) {
+                txt.add_line(format!(
+                    "- {}: +{} ({} of the diverted shortcuts)",
+                    road_name(app, map.get_r(*r)),
+                    prettyprint_usize(*cnt),
+                    Percent::of(*cnt, impact.num_diverted)
+                ));
+            }
+            if affected.len() > MAX_ROADS_LISTED {
+                txt.add_line(format!(
+                    "... and {} more",
+                    affected.len() - MAX_ROADS_LISTED
+                ));
+            }
+        }
+        txt.add_line("");
+        txt.add_line(
+            Line(
+                "This is a rough estimate that sends every blocked shortcut along the fastest \
+                 route around the perimeter. It counts shortcuts, not vehicles.",
+            )
+            .secondary(),
+        );
+
+        let panel = Panel::new_builder(Widget::col(vec![
+            Widget::row(vec![
+                Line("Boundary road impact")
+                    .small_heading()
+                    .into_widget(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            txt.wrap_to_pct(ctx, 30).into_widget(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        <dyn SimpleState<_>>::new_state(
+            panel,
+            Box::new(BoundaryImpactView {
+                draw: colorer.draw.unzoomed.upload(ctx),
+            }),
+        )
+    }
+}
+
+impl SimpleState<App> for BoundaryImpactView {
+    fn on_click(&mut self, _: &mut EventCtx, _: &mut App, x: &str, _: &mut Panel) -> Transition {
+        if x == "close" {
+            return Transition::Pop;
+        }
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+}
@@ -1,3 +1,4 @@
+mod boundary_impact;
 mod filters;
 mod freehand_filters;
 mod modals;
@@ -5,9 +6,10 @@ mod one_ways;
 mod page;
 mod shortcuts;
 mod speed_limits;
+mod traffic_calming;
 pub mod turn_restrictions;
 
-use map_model::{IntersectionID, Road, RoadID};
+use map_model::{IntersectionID, Road, RoadID, TrafficCalmingType};
 use widgetry::mapspace::{ObjectID, World};
 use widgetry::tools::{PolyLineLasso, PopupMsg};
 use widgetry::{EventCtx, Panel};
@@ -25,6 +27,7 @@ pub enum EditMode {
     Shortcuts(Option<shortcuts::FocusedRoad>),
     SpeedLimits,
     TurnRestrictions(Option<FocusedTurns>),
+    TrafficCalming(TrafficCalmingType),
 }
 
 pub struct EditNeighbourhood {
@@ -76,6 +79,7 @@ impl EditNeighbourhood {
                 EditMode::TurnRestrictions(focus) => {
                     turn_restrictions::make_world(ctx, app, neighbourhood, focus)
                 }
+                EditMode::TrafficCalming(_) => traffic_calming::make_world(ctx, app, neighbourhood),
             },
         }
     }
@@ -100,6 +104,7 @@ impl EditNeighbourhood {
             EditMode::TurnRestrictions(_) => {
                 turn_restrictions::handle_world_outcome(ctx, app, outcome)
             }
+            EditMode::TrafficCalming(_) => traffic_calming::handle_world_outcome(ctx, app, outcome),
         };
         if matches!(outcome, EditOutcome::Transition(_)) {
             self.world.hack_unset_hovering();
@@ -171,6 +176,9 @@ impl EditNeighbourhood {
                 app.session.edit_mode = EditMode::Shortcuts(None);
                 EditOutcome::UpdatePanelAndWorld
             }
+            "Boundary road impact" => EditOutcome::Transition(Transition::Push(
+                boundary_impact::BoundaryImpactView::new_state(ctx, app, neighbourhood),
+            )),
             "previous shortcut" => {
                 if let EditMode::Shortcuts(Some(ref mut focus)) = app.session.edit_mode {
                     focus.current_idx -= 1;
@@ -202,7 +210,17 @@ impl EditNeighbourhood {
                 app.session.edit_mode = EditMode::TurnRestrictions(None);
                 EditOutcome::UpdatePanelAndWorld
             }
-            _ => EditOutcome::Nothing,
+            "Traffic calming" => {
+                app.session.edit_mode = EditMode::TrafficCalming(TrafficCalmingType::SpeedHump);
+                EditOutcome::UpdatePanelAndWorld
+            }
+            x => {
+                if let Some(calming_type) = traffic_calming::parse_action(x) {
+                    app.session.edit_mode = EditMode::TrafficCalming(calming_type);
+                    return EditOutcome::UpdatePanelAndWorld;
+                }
+                EditOutcome::Nothing
+            }
         }
     }
 }
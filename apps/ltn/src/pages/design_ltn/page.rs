@@ -13,6 +13,7 @@ use super::{EditMode, EditNeighbourhood, EditOutcome};
 use crate::components::{AppwidePanel, BottomPanel, Mode};
 use crate::logic::AutoFilterHeuristic;
 use crate::render::colors;
+use crate::stats::NeighbourhoodStats;
 use crate::{is_private, pages, render, App, Neighbourhood, NeighbourhoodID, Transition};
 
 pub struct DesignLTN {
@@ -142,6 +143,11 @@ impl DesignLTN {
                 )
                 .text_widget(ctx)
                 .centered_horiz(),
+                ctx.style()
+                    .btn_outline
+                    .text("Statistics")
+                    .build_def(ctx)
+                    .centered_horiz(),
                 warning1.centered_horiz(),
                 warning2.centered_horiz(),
             ])
@@ -169,6 +175,12 @@ impl State<App> for DesignLTN {
         if let Outcome::Clicked(x) = self.bottom_panel.event(ctx) {
             if x == "Advanced" {
                 return launch_advanced(ctx, app, self.neighbourhood.id);
+            } else if x == "Statistics" {
+                return Transition::Push(PopupMsg::new_state(
+                    ctx,
+                    "Neighbourhood statistics",
+                    NeighbourhoodStats::new(&app.per_map.map, &self.neighbourhood).describe(),
+                ));
             } else if x == "warning1" {
                 return Transition::Push(PopupMsg::new_state(
                     ctx,
@@ -257,6 +269,7 @@ impl State<App> for DesignLTN {
         app.per_map.draw_major_road_labels.draw(g);
         app.session.layers.draw(g, app);
         app.per_map.draw_all_filters.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
         app.per_map.draw_poi_icons.draw(g);
 
         if self.bottom_panel.currently_hovering() == Some(&"warning1".to_string()) {
@@ -297,7 +310,7 @@ fn setup_editing(
     // edit.world so that we draw it even while hovering on roads/intersections in a cell
     let mut highlight_cell = World::new();
 
-    let render_cells = render::RenderCells::new(map, neighbourhood);
+    let render_cells = render::RenderCells::new(map, neighbourhood, &app.cs.categorical);
 
     let draw_under_roads_layer = render_cells.draw_colored_areas();
     draw_top_layer.append(render_cells.draw_island_outlines());
@@ -506,6 +519,8 @@ fn make_bottom_panel(
             super::speed_limits::widget(ctx)
         } else if let EditMode::TurnRestrictions(ref focus) = app.session.edit_mode {
             super::turn_restrictions::widget(ctx, app, focus.as_ref())
+        } else if let EditMode::TrafficCalming(calming_type) = app.session.edit_mode {
+            super::traffic_calming::widget(ctx, calming_type)
         } else {
             Widget::nothing()
         }
@@ -727,5 +742,22 @@ fn edit_mode(ctx: &mut EventCtx, app: &App) -> Widget {
             })
             .build_widget(ctx, "Turn restrictions")
             .centered_vert(),
+        ctx.style()
+            .btn_solid_primary
+            .icon("system/assets/tools/uphill.svg")
+            .disabled(matches!(edit_mode, EditMode::TrafficCalming(_)))
+            .hotkey(Key::F7)
+            .tooltip_and_disabled({
+                let mut txt = Text::new();
+                txt.add_line(Line(Key::F7.describe()).fg(ctx.style().text_hotkey_color));
+                txt.append(Line(" - Traffic calming"));
+                txt.add_line(Line("Click").fg(ctx.style().text_hotkey_color));
+                txt.append(Line(
+                    " a road to add a speed hump, raised table, or chicane",
+                ));
+                txt
+            })
+            .build_widget(ctx, "Traffic calming")
+            .centered_vert(),
     ])
 }
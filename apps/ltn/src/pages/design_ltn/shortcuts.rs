@@ -43,7 +43,12 @@ pub fn widget(ctx: &mut EventCtx, app: &App, focus: Option<&FocusedRoad>) -> Wid
                     .build_widget(ctx, "next shortcut"),
             ]),
         ]),
-        None => Widget::nothing(),
+        None => ctx
+            .style()
+            .btn_outline
+            .text("Boundary road impact")
+            .build_def(ctx)
+            .centered_vert(),
     }
 }
 
@@ -45,11 +45,22 @@ pub fn make_world(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood)
                 colors::SPEED_LIMITS[3]
             })
             .hover_color(colors::HOVER)
-            .tooltip(Text::from(format!(
-                "Current speed limit is {} ({})",
-                road.speed_limit.to_string(&UnitFmt::imperial()),
-                road.speed_limit.to_string(&UnitFmt::metric()),
-            )))
+            .tooltip({
+                let mut txt = Text::from(format!(
+                    "Current speed limit is {} ({})",
+                    road.speed_limit.to_string(&UnitFmt::imperial()),
+                    road.speed_limit.to_string(&UnitFmt::metric()),
+                ));
+                if !road.traffic_calming.is_empty() {
+                    let speed = road.effective_speed_limit();
+                    txt.add_line(format!(
+                        "Traffic calming slows vehicles to an average of {} ({})",
+                        speed.to_string(&UnitFmt::imperial()),
+                        speed.to_string(&UnitFmt::metric()),
+                    ));
+                }
+                txt
+            })
             .clickable()
             .build(ctx);
     }
@@ -0,0 +1,119 @@
+use geom::{Distance, UnitFmt};
+use map_model::{TrafficCalming, TrafficCalmingType};
+use widgetry::mapspace::{World, WorldOutcome};
+use widgetry::{EventCtx, Text, Widget};
+
+use super::{road_name, EditMode, EditOutcome, Obj};
+use crate::render::colors;
+use crate::{redraw_all_icons, App, Neighbourhood};
+
+/// Clicking this close to an existing traffic calming feature removes it
+const REMOVE_THRESHOLD: Distance = Distance::const_meters(10.0);
+
+pub fn widget(ctx: &mut EventCtx, current: TrafficCalmingType) -> Widget {
+    Widget::row(
+        TrafficCalmingType::all()
+            .into_iter()
+            .map(|calming_type| {
+                ctx.style()
+                    .btn_outline
+                    .text(calming_type.to_string())
+                    .disabled(calming_type == current)
+                    .build_widget(ctx, action(calming_type))
+                    .centered_vert()
+            })
+            .collect(),
+    )
+}
+
+/// The panel action to switch to placing this type
+pub fn action(calming_type: TrafficCalmingType) -> String {
+    format!("traffic calming: {}", calming_type)
+}
+
+pub fn parse_action(action: &str) -> Option<TrafficCalmingType> {
+    TrafficCalmingType::all()
+        .into_iter()
+        .find(|calming_type| self::action(*calming_type) == action)
+}
+
+pub fn make_world(ctx: &mut EventCtx, app: &App, neighbourhood: &Neighbourhood) -> World<Obj> {
+    let map = &app.per_map.map;
+    let mut world = World::new();
+
+    for r in &neighbourhood.interior_roads {
+        let road = map.get_r(*r);
+        let mut txt = Text::from(road_name(app, road));
+        txt.add_line("Click to add traffic calming, or click an existing feature to remove it");
+        if !road.traffic_calming.is_empty() {
+            txt.add_line(format!(
+                "Vehicles average {} here, with a speed limit of {}",
+                road.effective_speed_limit().to_string(&UnitFmt::imperial()),
+                road.speed_limit.to_string(&UnitFmt::imperial())
+            ));
+        }
+
+        world
+            .add(Obj::Road(*r))
+            .hitbox(road.get_thick_polygon())
+            .drawn_in_master_batch()
+            .hover_color(colors::HOVER)
+            .tooltip(txt)
+            .clickable()
+            .build(ctx);
+    }
+
+    world.initialize_hover(ctx);
+    world
+}
+
+pub fn handle_world_outcome(
+    ctx: &mut EventCtx,
+    app: &mut App,
+    outcome: WorldOutcome<Obj>,
+) -> EditOutcome {
+    let calming_type = match app.session.edit_mode {
+        EditMode::TrafficCalming(calming_type) => calming_type,
+        _ => unreachable!(),
+    };
+    match outcome {
+        WorldOutcome::ClickedObject(Obj::Road(r)) => {
+            let cursor_pt = match ctx.canvas.get_cursor_in_map_space() {
+                Some(pt) => pt,
+                None => {
+                    return EditOutcome::Nothing;
+                }
+            };
+            let road = app.per_map.map.get_r(r);
+            let dist = match road
+                .center_pts
+                .dist_along_of_point(road.center_pts.project_pt(cursor_pt))
+            {
+                Some((dist, _)) => dist,
+                None => {
+                    return EditOutcome::Nothing;
+                }
+            };
+
+            let mut traffic_calming = road.traffic_calming.clone();
+            if let Some(idx) = traffic_calming.iter().position(|tc| {
+                tc.start - REMOVE_THRESHOLD <= dist && dist <= tc.end + REMOVE_THRESHOLD
+            }) {
+                traffic_calming.remove(idx);
+            } else {
+                traffic_calming.push(TrafficCalming::new(calming_type, dist, road));
+                traffic_calming.sort_by_key(|tc| tc.start);
+            }
+
+            let mut edits = app.per_map.map.get_edits().clone();
+            edits.commands.push(app.per_map.map.edit_road_cmd(r, |new| {
+                new.traffic_calming = traffic_calming;
+            }));
+            app.apply_edits(edits);
+            redraw_all_icons(ctx, app);
+
+            EditOutcome::UpdateAll
+        }
+        _ => EditOutcome::Nothing,
+    }
+}
@@ -1,4 +1,6 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{bail, Result};
 
 use geom::{Distance, Polygon, QuadTree};
 use map_gui::tools::EditPolygon;
@@ -110,6 +112,44 @@ impl FreehandBoundary {
         Box::new(state)
     }
 
+    /// Import boundaries from a GeoJSON file. A single polygon can be adjusted before confirming
+    /// it; if there are many, they're all added immediately.
+    pub fn import_geojson(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        bytes: &[u8],
+    ) -> Result<Box<dyn State<App>>> {
+        let mut polygons =
+            Polygon::from_geojson_bytes(bytes, app.per_map.map.get_gps_bounds(), false)?;
+        if polygons.is_empty() {
+            bail!("The file doesn't contain any polygons");
+        }
+        let name_of = |idx: usize, tags: &BTreeMap<String, String>| {
+            tags.get("name")
+                .cloned()
+                .unwrap_or_else(|| format!("Imported boundary {}", idx + 1))
+        };
+        if polygons.len() == 1 {
+            let (polygon, tags) = polygons.pop().unwrap();
+            return Ok(Self::new_from_polygon(ctx, app, name_of(0, &tags), polygon));
+        }
+
+        let quadtree = make_quadtree(app);
+        let mut first = None;
+        for (idx, (polygon, tags)) in polygons.into_iter().enumerate() {
+            let custom = polygon_to_custom_boundary(app, polygon, name_of(idx, &tags), &quadtree);
+            if custom.interior_roads.is_empty() {
+                continue;
+            }
+            let id = mut_partitioning!(app).add_custom_boundary(custom);
+            first.get_or_insert(id);
+        }
+        match first {
+            Some(id) => Ok(pages::DesignLTN::new_state(ctx, app, id)),
+            None => bail!("None of the polygons contain any roads"),
+        }
+    }
+
     fn recalculate(&mut self, ctx: &EventCtx, app: &App) {
         self.queued_recalculate = false;
         if let Ok(ring) = self.edit.get_ring() {
@@ -1,5 +1,7 @@
 mod about;
+mod annotate;
 mod census;
+mod citywide;
 mod crossings;
 mod customize_boundary;
 mod cycle_network;
@@ -12,7 +14,9 @@ mod route_planner;
 mod select_boundary;
 
 pub use about::About;
+pub use annotate::Annotate;
 pub use census::Census;
+pub use citywide::Citywide;
 pub use crossings::Crossings;
 pub use customize_boundary::CustomizeBoundary;
 pub use cycle_network::CycleNetwork;
@@ -70,7 +70,7 @@ impl PerResidentImpact {
         }
 
         // It's a subtle effect, but maybe useful to see
-        let render_cells = render::RenderCells::new(map, &neighbourhood);
+        let render_cells = render::RenderCells::new(map, &neighbourhood, &app.cs.categorical);
         let cell_outline = render_cells.draw_island_outlines();
 
         // Depending on the number of buildings_inside, Dijkstra may be faster, but this seems fast
@@ -365,6 +365,7 @@ impl State<App> for PerResidentImpact {
         app.per_map.draw_major_road_labels.draw(g);
         app.session.layers.draw(g, app);
         app.per_map.draw_all_filters.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
         self.world.draw(g);
         if let Some((_, ref draw)) = self.compare_routes {
             g.redraw(draw);
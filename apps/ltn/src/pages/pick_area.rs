@@ -1,5 +1,6 @@
+use map_gui::tools::FilePicker;
 use widgetry::mapspace::{World, WorldOutcome};
-use widgetry::tools::{ChooseSomething, PromptInput};
+use widgetry::tools::{ChooseSomething, PopupMsg, PromptInput};
 use widgetry::{Choice, Color, DrawBaselayer, EventCtx, GfxCtx, Outcome, Panel, State, Widget};
 
 use crate::components::{AppwidePanel, BottomPanel, Mode};
@@ -100,6 +101,7 @@ impl State<App> for PickArea {
         app.per_map.draw_major_road_labels.draw(g);
         app.session.layers.draw(g, app);
         app.per_map.draw_all_filters.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
         app.per_map.draw_poi_icons.draw(g);
     }
 
@@ -130,7 +132,8 @@ fn make_world(ctx: &mut EventCtx, app: &App) -> World<NeighbourhoodID> {
                 }
                 PickAreaStyle::Cells => {
                     let neighbourhood = Neighbourhood::new(app, *id);
-                    let render_cells = render::RenderCells::new(map, &neighbourhood);
+                    let render_cells =
+                        render::RenderCells::new(map, &neighbourhood, &app.cs.categorical);
                     let hovered_batch = render_cells.draw_colored_areas();
                     world
                         .add(*id)
@@ -199,29 +202,64 @@ fn change_draw_style(ctx: &mut EventCtx) -> Transition {
     ))
 }
 
+#[derive(Clone)]
+enum ManageCustomBoundary {
+    Create,
+    Import,
+    Existing(NeighbourhoodID),
+}
+
 fn manage_custom_boundary(ctx: &mut EventCtx, app: &App) -> Transition {
-    let mut choices = vec![Choice::new("Create new", None)];
+    let mut choices = vec![
+        Choice::new("Create new", ManageCustomBoundary::Create),
+        Choice::new("Import from GeoJSON", ManageCustomBoundary::Import),
+    ];
     for (id, custom) in &app.partitioning().custom_boundaries {
-        choices.push(Choice::new(&custom.name, Some(*id)));
+        choices.push(Choice::new(
+            &custom.name,
+            ManageCustomBoundary::Existing(*id),
+        ));
     }
 
     Transition::Push(ChooseSomething::new_state(
         ctx,
         "Manage custom boundaries",
         choices,
-        Box::new(move |choice, ctx, app| {
-            if let Some(id) = choice {
+        Box::new(move |choice, ctx, app| match choice {
+            ManageCustomBoundary::Existing(id) => {
                 Transition::Clear(vec![pages::DesignLTN::new_state(ctx, app, id)])
-            } else {
-                Transition::Replace(PromptInput::new_state(
-                    ctx,
-                    "Name the custom boundary",
-                    String::new(),
-                    Box::new(|name, ctx, app| {
-                        Transition::Clear(vec![pages::FreehandBoundary::blank(ctx, app, name)])
-                    }),
-                ))
             }
+            ManageCustomBoundary::Create => Transition::Replace(PromptInput::new_state(
+                ctx,
+                "Name the custom boundary",
+                String::new(),
+                Box::new(|name, ctx, app| {
+                    Transition::Clear(vec![pages::FreehandBoundary::blank(ctx, app, name)])
+                }),
+            )),
+            ManageCustomBoundary::Import => Transition::Replace(FilePicker::new_state(
+                ctx,
+                crate::save::start_dir(),
+                Box::new(|ctx, app, maybe_file| match maybe_file {
+                    Ok(Some((_, bytes))) => {
+                        match pages::FreehandBoundary::import_geojson(ctx, app, &bytes) {
+                            Ok(state) => Transition::Clear(vec![state]),
+                            Err(err) => Transition::Replace(PopupMsg::new_state(
+                                ctx,
+                                "Error",
+                                vec![err.to_string()],
+                            )),
+                        }
+                    }
+                    // No file chosen, just quit the picker
+                    Ok(None) => Transition::Pop,
+                    Err(err) => Transition::Replace(PopupMsg::new_state(
+                        ctx,
+                        "Error",
+                        vec![err.to_string()],
+                    )),
+                }),
+            )),
         }),
     ))
 }
@@ -217,6 +217,7 @@ impl State<App> for ShowImpactResults {
         g.redraw(&app.per_map.draw_map.draw_all_areas);
         app.per_map.impact.compare_counts.draw(g, app);
         app.per_map.draw_all_filters.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
 
         self.appwide_panel.draw(g);
         self.left_panel.draw(g);
@@ -415,6 +416,7 @@ impl State<App> for ChangedRoutes {
         self.panel.draw(g);
         g.redraw(&self.draw_paths);
         app.per_map.draw_all_filters.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
         app.per_map.draw_poi_icons.draw(g);
     }
 }
@@ -459,6 +459,7 @@ impl State<App> for RoutePlanner {
             .draw(g);
         app.per_map.draw_major_road_labels.draw(g);
         app.per_map.draw_all_filters.draw(g);
+        g.redraw(&app.per_map.draw_annotations);
         app.per_map.draw_poi_icons.draw(g);
 
         self.appwide_panel.draw(g);
@@ -34,8 +34,9 @@ impl RenderCells {
     /// Partition a neighbourhood's boundary polygon based on the cells. This discretizes space into
     /// a grid, and then extracts a polygon from the raster. The results don't look perfect, but
     /// it's fast.
-    pub fn new(map: &Map, neighbourhood: &Neighbourhood) -> RenderCells {
-        RenderCellsBuilder::new(map, neighbourhood).finalize()
+    /// `palette` is usually `ColorScheme::categorical`.
+    pub fn new(map: &Map, neighbourhood: &Neighbourhood, palette: &[Color]) -> RenderCells {
+        RenderCellsBuilder::new(map, neighbourhood, palette).finalize()
     }
 
     /// Draw cells as areas with different colors. The colors are meaningless, but the same color
@@ -93,7 +94,7 @@ impl RenderCells {
 }
 
 impl RenderCellsBuilder {
-    fn new(map: &Map, neighbourhood: &Neighbourhood) -> RenderCellsBuilder {
+    fn new(map: &Map, neighbourhood: &Neighbourhood, palette: &[Color]) -> RenderCellsBuilder {
         let boundary_polygon = neighbourhood.boundary_polygon.clone();
         // Make a 2D grid covering the polygon. Each tile in the grid contains a cell index, which
         // will become a color by the end. None means no cell is assigned yet.
@@ -167,7 +168,7 @@ impl RenderCellsBuilder {
         }
 
         let adjacencies = diffusion(&mut grid, boundary_marker);
-        let mut cell_colors = color_cells(neighbourhood.cells.len(), adjacencies);
+        let mut cell_colors = color_cells(neighbourhood.cells.len(), adjacencies, palette);
 
         // Color some special cells
         for (idx, cell) in neighbourhood.cells.iter().enumerate() {
@@ -302,12 +303,15 @@ fn diffusion(grid: &mut Grid<Option<usize>>, boundary_marker: usize) -> HashSet<
     adjacencies
 }
 
-fn color_cells(num_cells: usize, adjacencies: HashSet<(usize, usize)>) -> Vec<Color> {
+fn color_cells(
+    num_cells: usize,
+    adjacencies: HashSet<(usize, usize)>,
+    palette: &[Color],
+) -> Vec<Color> {
     // This is the same greedy logic as Perimeter::calculate_coloring
     let mut assigned_colors = Vec::new();
     for this_idx in 0..num_cells {
-        let mut available_colors: Vec<bool> =
-            std::iter::repeat(true).take(colors::CELLS.len()).collect();
+        let mut available_colors: Vec<bool> = std::iter::repeat(true).take(palette.len()).collect();
         // Find all neighbors
         for other_idx in 0..num_cells {
             if adjacencies.contains(&(this_idx, other_idx)) {
@@ -339,11 +343,11 @@ fn color_cells(num_cells: usize, adjacencies: HashSet<(usize, usize)>) -> Vec<Co
         assigned_colors.push(
             choice
                 .or(backup)
-                .unwrap_or_else(|| assigned_colors.len() % colors::CELLS.len()),
+                .unwrap_or_else(|| assigned_colors.len() % palette.len()),
         );
     }
     assigned_colors
         .into_iter()
-        .map(|idx| colors::CELLS[idx].alpha(0.8))
+        .map(|idx| palette[idx].alpha(0.8))
         .collect()
 }
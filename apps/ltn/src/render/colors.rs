@@ -1,21 +1,6 @@
 use widgetry::Color;
 
 lazy_static::lazy_static! {
-    // A qualitative palette from colorbrewer2.org, skipping the red hue (used for levels of
-    // shortcutting) and grey (too close to the basemap)
-    pub static ref CELLS: [Color; 10] = [
-        Color::hex("#8dd3c7"),
-        Color::hex("#ffffb3"),
-        Color::hex("#bebada"),
-        Color::hex("#80b1d3"),
-        Color::hex("#fdb462"),
-        Color::hex("#b3de69"),
-        Color::hex("#fccde5"),
-        Color::hex("#bc80bd"),
-        Color::hex("#ccebc5"),
-        Color::hex("#ffed6f"),
-    ];
-
     pub static ref PLAN_ROUTE_BEFORE: Color = Color::PURPLE;
     pub static ref PLAN_ROUTE_AFTER: Color = Color::CYAN;
     pub static ref PLAN_ROUTE_BIKE: Color = Color::GREEN;
@@ -40,6 +25,8 @@ lazy_static::lazy_static! {
     pub static ref NETWORK_QUIET_STREET: Color = Color::hex("#03AC13");
     pub static ref NETWORK_PAINTED_LANE: Color = Color::hex("#90EE90");
     pub static ref NETWORK_THROUGH_TRAFFIC_STREET: Color = Color::hex("#F3A4A4");
+
+    pub static ref ANNOTATION: Color = Color::hex("#7B2CBF");
 }
 
 pub const DISCONNECTED_CELL: Color = Color::RED.alpha(0.5);
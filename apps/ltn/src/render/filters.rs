@@ -1,15 +1,13 @@
 use std::collections::BTreeMap;
 
-use geom::Angle;
+use geom::{Angle, Circle, Distance};
 use map_model::{FilterType, Map};
-use widgetry::mapspace::{DrawCustomUnzoomedShapes, PerZoom};
 use widgetry::{EventCtx, GeomBatch, RewriteColor};
 
 use crate::render;
 
 pub fn render_modal_filters(ctx: &EventCtx, map: &Map) -> render::Toggle3Zoomed {
-    let mut batch = GeomBatch::new();
-    let mut low_zoom = DrawCustomUnzoomedShapes::builder();
+    let mut builder = render::Toggle3Zoomed::builder();
 
     let mut icons = BTreeMap::new();
     for ft in [
@@ -36,26 +34,20 @@ pub fn render_modal_filters(ctx: &EventCtx, map: &Map) -> render::Toggle3Zoomed
                 Angle::ZERO
             };
 
-            batch.append(
+            builder.zoomed.append(
                 icon.clone()
                     .scale_to_fit_width(road.get_width().inner_meters())
                     .centered_on(pt)
                     .rotate(angle)
                     .color(rewrite_color),
             );
-
-            // TODO Memory intensive
-            let icon = icon.clone();
-            // TODO They can shrink a bit past their map size
-            low_zoom.add_custom(Box::new(move |batch, thickness| {
-                batch.append(
-                    icon.clone()
-                        .scale_to_fit_width(30.0 * thickness)
-                        .centered_on(pt)
-                        .rotate(angle)
-                        .color(rewrite_color),
-                );
-            }));
+            builder.add_icon(
+                render::filter_svg_path(filter.filter_type),
+                icon.clone().color(rewrite_color),
+                pt,
+                angle,
+                30.0,
+            );
         }
     }
 
@@ -76,34 +68,40 @@ pub fn render_modal_filters(ctx: &EventCtx, map: &Map) -> render::Toggle3Zoomed
             };
             let pt = line.middle().unwrap();
 
-            batch.append(
+            builder.zoomed.append(
                 icon.clone()
                     .scale_to_fit_width(line.length().inner_meters())
                     .centered_on(pt)
                     .rotate(angle)
                     .color(rewrite_color),
             );
+            builder.add_icon(
+                render::filter_svg_path(filter.filter_type),
+                icon.clone().color(rewrite_color),
+                pt,
+                angle,
+                30.0,
+            );
+        }
+    }
 
-            let icon = icon.clone();
-            low_zoom.add_custom(Box::new(move |batch, thickness| {
-                // TODO Why is this magic value different than the one above?
-                batch.append(
-                    icon.clone()
-                        .scale(0.4 * thickness)
-                        .centered_on(pt)
-                        .rotate(angle)
-                        .color(rewrite_color),
-                );
-            }));
+    // Traffic calming is drawn the same way as in the main map, with a dot when zoomed out
+    for road in map.all_roads() {
+        for tc in &road.traffic_calming {
+            if let Ok((pt, _)) = road.center_pts.dist_along(tc.middle()) {
+                let color = map_gui::render::traffic_calming_color(tc.calming_type);
+                builder.add_unzoomed(Box::new(move |batch, thickness| {
+                    batch.push(
+                        color,
+                        Circle::new(pt, Distance::meters(5.0 * thickness)).to_polygon(),
+                    );
+                }));
+            }
+        }
+        for (color, polygon) in map_gui::render::traffic_calming_shapes(road) {
+            builder.zoomed.push(color, polygon);
         }
     }
 
-    let min_zoom_for_detail = 5.0;
-    let step_size = 0.1;
-    // TODO Ideally we get rid of Toggle3Zoomed and make DrawCustomUnzoomedShapes handle this
-    // medium-zoom case.
-    render::Toggle3Zoomed::new(
-        batch.build(ctx),
-        low_zoom.build(PerZoom::new(min_zoom_for_detail, step_size)),
-    )
+    builder.build(ctx, 5.0)
 }
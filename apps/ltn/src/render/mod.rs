@@ -1,18 +1,23 @@
 mod cells;
 pub mod colors;
 mod filters;
+mod zoomed;
 
-use geom::{Angle, Distance, Pt2D};
+use geom::{Angle, ArrowCap, Distance};
+use map_gui::colors::ColorScheme;
 use map_model::{AmenityType, ExtraPOIType, FilterType, Map, RestrictionType, Road, TurnType};
-use widgetry::mapspace::DrawCustomUnzoomedShapes;
-use widgetry::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, RewriteColor, Text};
+use widgetry::{Color, Drawable, EventCtx, GeomBatch, Line, RewriteColor, Text};
+
+use crate::save::{Annotation, AnnotationKind};
 
 pub use cells::RenderCells;
 pub use filters::render_modal_filters;
+pub use zoomed::{Toggle3Zoomed, Toggle3ZoomedBuilder};
 
-pub fn render_poi_icons(ctx: &EventCtx, map: &Map) -> Drawable {
-    let mut batch = GeomBatch::new();
-    let school = GeomBatch::load_svg(ctx, "system/assets/map/school.svg")
+pub fn render_poi_icons(ctx: &EventCtx, map: &Map) -> Toggle3Zoomed {
+    let mut builder = Toggle3Zoomed::builder();
+    let school_path = "system/assets/map/school.svg";
+    let school = GeomBatch::load_svg(ctx, school_path)
         .scale(0.2)
         .color(RewriteColor::ChangeAll(Color::WHITE));
 
@@ -21,36 +26,90 @@ pub fn render_poi_icons(ctx: &EventCtx, map: &Map) -> Drawable {
             let at = AmenityType::categorize(&a.amenity_type);
             at == Some(AmenityType::School) || at == Some(AmenityType::University)
         }) {
-            batch.append(school.clone().centered_on(b.polygon.polylabel()));
+            let pt = b.polygon.polylabel();
+            builder.zoomed.append(school.clone().centered_on(pt));
+            builder.add_icon(school_path, school.clone(), pt, Angle::ZERO, 16.0);
         }
     }
 
-    let tfl =
-        GeomBatch::load_svg(ctx, "system/assets/map/tfl_underground.svg").scale_to_fit_width(20.0);
-    let national_rail =
-        GeomBatch::load_svg(ctx, "system/assets/map/national_rail.svg").scale_to_fit_width(20.0);
+    let tfl_path = "system/assets/map/tfl_underground.svg";
+    let national_rail_path = "system/assets/map/national_rail.svg";
+    let tfl = GeomBatch::load_svg(ctx, tfl_path).scale_to_fit_width(20.0);
+    let national_rail = GeomBatch::load_svg(ctx, national_rail_path).scale_to_fit_width(20.0);
 
-    // TODO Toggle3Zoomed could be nicer; these're not terribly visible from afar
     for extra in map.all_extra_pois() {
-        let (name, icon) = match extra.kind {
-            ExtraPOIType::LondonUndergroundStation(ref name) => (name, &tfl),
-            ExtraPOIType::NationalRailStation(ref name) => (name, &national_rail),
+        let (name, kind, icon) = match extra.kind {
+            ExtraPOIType::LondonUndergroundStation(ref name) => (name, tfl_path, &tfl),
+            ExtraPOIType::NationalRailStation(ref name) => {
+                (name, national_rail_path, &national_rail)
+            }
         };
-        batch.append(icon.clone().centered_on(extra.pt));
-        batch.append(
-            Text::from(Line(name).fg(Color::WHITE))
-                .bg(Color::hex("#0019A8"))
-                .render_autocropped(ctx)
+        let label = Text::from(Line(name).fg(Color::WHITE))
+            .bg(Color::hex("#0019A8"))
+            .render_autocropped(ctx);
+
+        builder.zoomed.append(icon.clone().centered_on(extra.pt));
+        builder.zoomed.append(
+            label
+                .clone()
                 .scale_to_fit_height(10.0)
                 .centered_on(extra.pt.offset(0.0, icon.get_bounds().height())),
         );
+        builder.add_labelled_icon(kind, icon.clone(), extra.pt, 20.0, label);
     }
 
-    ctx.upload(batch)
+    // The icons are about as big on screen as in map-space at this zoom
+    builder.build(ctx, 1.0)
 }
 
-pub fn render_bus_routes(ctx: &EventCtx, map: &Map) -> Drawable {
+pub fn render_annotations(ctx: &EventCtx, annotations: &[Annotation]) -> Drawable {
     let mut batch = GeomBatch::new();
+    let pin = GeomBatch::load_svg(ctx, "system/assets/tools/pin.svg")
+        .scale_to_fit_width(15.0)
+        .color(RewriteColor::ChangeAll(*colors::ANNOTATION));
+
+    for a in annotations {
+        match a.kind {
+            AnnotationKind::Pin => {
+                // The tip of the pin marks the spot
+                let pin = pin.clone().centered_on(a.pts[0]);
+                let dy = pin.get_bounds().height() / 2.0;
+                batch.append(pin.translate(0.0, -dy));
+            }
+            AnnotationKind::Arrow => {
+                if let Ok(pl) = a.polyline() {
+                    batch.push(
+                        *colors::ANNOTATION,
+                        pl.make_arrow(Distance::meters(3.0), ArrowCap::Triangle),
+                    );
+                }
+            }
+            AnnotationKind::Area => {
+                if let Ok(polygon) = a.polygon() {
+                    batch.push(colors::ANNOTATION.alpha(0.3), polygon.clone());
+                    batch.push(
+                        *colors::ANNOTATION,
+                        polygon.to_outline(Distance::meters(2.0)),
+                    );
+                }
+            }
+        }
+        if !a.text.is_empty() {
+            batch.append(
+                Text::from(Line(&a.text).fg(Color::WHITE))
+                    .bg(*colors::ANNOTATION)
+                    .render_autocropped(ctx)
+                    .scale_to_fit_height(10.0)
+                    .centered_on(a.label_pt().offset(0.0, 10.0)),
+            );
+        }
+    }
+
+    ctx.upload(batch)
+}
+
+pub fn render_bus_routes(ctx: &EventCtx, map: &Map, cs: &ColorScheme) -> Toggle3Zoomed {
+    let mut builder = Toggle3Zoomed::builder();
     for r in map.all_roads() {
         if map.get_bus_routes_on_road(r.id).is_empty() {
             continue;
@@ -64,8 +123,8 @@ pub fn render_bus_routes(ctx: &EventCtx, map: &Map) -> Drawable {
         .into_iter()
         .flatten()
         {
-            batch.extend(
-                *colors::BUS_ROUTE,
+            builder.zoomed.extend(
+                cs.bus_layer,
                 pl.exact_dashed_polygons(
                     Distance::meters(2.0),
                     Distance::meters(5.0),
@@ -73,49 +132,66 @@ pub fn render_bus_routes(ctx: &EventCtx, map: &Map) -> Drawable {
                 ),
             );
         }
+
+        // Zoomed out, the outlines would merge together, so dash the middle of the road instead
+        let pl = r.center_pts.clone();
+        let color = cs.bus_layer;
+        builder.add_unzoomed(Box::new(move |batch, thickness| {
+            batch.extend(
+                color,
+                pl.exact_dashed_polygons(
+                    Distance::meters(3.0 * thickness),
+                    Distance::meters(5.0 * thickness),
+                    Distance::meters(3.0 * thickness),
+                ),
+            );
+        }));
     }
-    ctx.upload(batch)
+    builder.build(ctx, 2.0)
 }
 
-pub fn render_turn_restrictions(ctx: &EventCtx, map: &Map) -> Drawable {
-    let mut batch = GeomBatch::new();
+pub fn render_turn_restrictions(ctx: &EventCtx, map: &Map) -> Toggle3Zoomed {
+    let mut builder = Toggle3Zoomed::builder();
     for r1 in map.all_roads() {
         // TODO Also interpret lane-level? Maybe just check all the generated turns and see what's
         // allowed / banned in practice?
         for (restriction, r2) in &r1.turn_restrictions {
             // TODO "Invert" OnlyAllowTurns so we can just draw banned things
             if *restriction == RestrictionType::BanTurns {
-                batch.append(draw_restriction(ctx, map, r1, map.get_r(*r2)));
+                draw_restriction(ctx, &mut builder, map, r1, map.get_r(*r2));
             }
         }
         for (_via, r2) in &r1.complicated_turn_restrictions {
             // TODO Show the 'via'? Or just draw the entire shape?
-            batch.append(draw_restriction(ctx, map, r1, map.get_r(*r2)));
+            draw_restriction(ctx, &mut builder, map, r1, map.get_r(*r2));
         }
     }
-    ctx.upload(batch)
-}
-
-fn draw_restriction(ctx: &EventCtx, map: &Map, r1: &Road, r2: &Road) -> GeomBatch {
-    let mut batch = GeomBatch::new();
-    // TODO: remove/name this wrapper, which is just for debugging svg icon placement/rotation
-    let (t_type, sign_pt, r1_angle, _) = map.get_ban_turn_info(r1, r2);
-    batch.append(draw_turn_restriction_icon(
-        ctx, t_type, sign_pt, r1, r1_angle,
-    ));
-    batch
+    // Like modal filters
+    builder.build(ctx, 5.0)
 }
 
-fn draw_turn_restriction_icon(
+fn draw_restriction(
     ctx: &EventCtx,
-    t_type: TurnType,
-    sign_pt: Pt2D,
+    builder: &mut Toggle3ZoomedBuilder,
+    map: &Map,
     r1: &Road,
-    r1_angle: Angle,
-) -> GeomBatch {
-    let mut batch = GeomBatch::new();
+    r2: &Road,
+) {
+    let (t_type, sign_pt, r1_angle, _) = map.get_ban_turn_info(r1, r2);
+    let icon_path = turn_restriction_svg_path(t_type);
+    let icon = GeomBatch::load_svg(ctx, icon_path);
+    let angle = r1_angle.rotate_degs(90.0);
+
+    builder.zoomed.append(
+        icon.clone()
+            .scale_to_fit_width(r1.get_width().inner_meters())
+            .centered_on(sign_pt)
+            .rotate_around_batch_center(angle),
+    );
+    builder.add_icon(icon_path, icon, sign_pt, angle, 30.0);
+}
 
-    // Which icon do we want?
+fn turn_restriction_svg_path(t_type: TurnType) -> &'static str {
     let no_right_t = "system/assets/map/no_right_turn.svg";
     let no_left_t = "system/assets/map/no_left_turn.svg";
     let no_u_t = "system/assets/map/no_u_turn_left_to_right.svg";
@@ -123,7 +199,7 @@ fn draw_turn_restriction_icon(
     // TODO - what should we do with these?
     let other_t = "system/assets/map/thought_bubble.svg";
 
-    let icon_path = match t_type {
+    match t_type {
         TurnType::Right => no_right_t,
         TurnType::Left => no_left_t,
         TurnType::UTurn => no_u_t,
@@ -131,41 +207,6 @@ fn draw_turn_restriction_icon(
         TurnType::SharedSidewalkCorner => other_t,
         TurnType::Straight => no_straight,
         TurnType::UnmarkedCrossing => other_t,
-    };
-
-    // Draw the svg icon
-    let icon = GeomBatch::load_svg(ctx, icon_path)
-        .scale_to_fit_width(r1.get_width().inner_meters())
-        .centered_on(sign_pt)
-        .rotate_around_batch_center(r1_angle.rotate_degs(90.0));
-
-    batch.append(icon);
-    batch
-}
-
-/// Depending on the canvas zoom level, draws one of 2 things.
-// TODO Rethink filter styles and do something better than this.
-pub struct Toggle3Zoomed {
-    draw_zoomed: Drawable,
-    unzoomed: DrawCustomUnzoomedShapes,
-}
-
-impl Toggle3Zoomed {
-    pub fn new(draw_zoomed: Drawable, unzoomed: DrawCustomUnzoomedShapes) -> Self {
-        Self {
-            draw_zoomed,
-            unzoomed,
-        }
-    }
-
-    pub fn empty(ctx: &EventCtx) -> Self {
-        Self::new(Drawable::empty(ctx), DrawCustomUnzoomedShapes::empty())
-    }
-
-    pub fn draw(&self, g: &mut GfxCtx) {
-        if !self.unzoomed.maybe_draw(g) {
-            self.draw_zoomed.draw(g);
-        }
     }
 }
 
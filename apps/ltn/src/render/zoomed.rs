@@ -0,0 +1,214 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use geom::{Angle, Pt2D};
+use widgetry::mapspace::{DrawCustomUnzoomedShapes, PerZoom};
+use widgetry::{Color, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Text};
+
+// How tall labels and cluster counts appear on screen when zoomed out, in pixels
+const LABEL_HEIGHT: f64 = 14.0;
+const COUNT_HEIGHT: f64 = 16.0;
+const STEP_SIZE: f64 = 0.1;
+
+/// Depending on the canvas zoom level, draws one of 2 things. Zoomed in, everything is drawn at
+/// its size in map-space. Zoomed out, shapes scale with the zoom, and icons keep a constant size
+/// on screen. Icons of the same kind that'd overlap collapse into one, with a count.
+pub struct Toggle3Zoomed {
+    draw_zoomed: Drawable,
+    unzoomed: DrawCustomUnzoomedShapes,
+    icons: IconClusters,
+}
+
+impl Toggle3Zoomed {
+    pub fn new(draw_zoomed: Drawable, unzoomed: DrawCustomUnzoomedShapes) -> Self {
+        Self {
+            draw_zoomed,
+            unzoomed,
+            icons: IconClusters::new(Vec::new(), 1.0),
+        }
+    }
+
+    pub fn empty(ctx: &EventCtx) -> Self {
+        Self::new(Drawable::empty(ctx), DrawCustomUnzoomedShapes::empty())
+    }
+
+    pub fn builder() -> Toggle3ZoomedBuilder {
+        Toggle3ZoomedBuilder {
+            zoomed: GeomBatch::new(),
+            unzoomed: Vec::new(),
+            icons: Vec::new(),
+        }
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        if self.unzoomed.maybe_draw(g) {
+            self.icons.maybe_draw(g);
+        } else {
+            self.draw_zoomed.draw(g);
+        }
+    }
+}
+
+pub struct Toggle3ZoomedBuilder {
+    /// Drawn when zoomed in
+    pub zoomed: GeomBatch,
+    unzoomed: Vec<Box<dyn Fn(&mut GeomBatch, f64)>>,
+    icons: Vec<Icon>,
+}
+
+impl Toggle3ZoomedBuilder {
+    /// Drawn when zoomed out. The callback is passed a thickness to scale shapes by.
+    pub fn add_unzoomed(&mut self, f: Box<dyn Fn(&mut GeomBatch, f64)>) {
+        self.unzoomed.push(f);
+    }
+
+    /// When zoomed out, draw an icon `width` pixels wide on screen, centered on `pt`. Only icons
+    /// with the same `kind` collapse together. The zoomed in version must be added to `zoomed`
+    /// separately.
+    pub fn add_icon(
+        &mut self,
+        kind: &'static str,
+        batch: GeomBatch,
+        pt: Pt2D,
+        angle: Angle,
+        width: f64,
+    ) {
+        self.icons.push(Icon {
+            kind,
+            batch,
+            pt,
+            angle,
+            width,
+            label: None,
+        });
+    }
+
+    /// Like `add_icon`, but with a label underneath it whenever the icon isn't collapsed into a
+    /// cluster
+    pub fn add_labelled_icon(
+        &mut self,
+        kind: &'static str,
+        batch: GeomBatch,
+        pt: Pt2D,
+        width: f64,
+        label: GeomBatch,
+    ) {
+        self.icons.push(Icon {
+            kind,
+            batch,
+            pt,
+            angle: Angle::ZERO,
+            width,
+            label: Some(label),
+        });
+    }
+
+    /// Below `min_zoom_for_detail`, switch to the zoomed out view.
+    pub fn build(self, ctx: &EventCtx, min_zoom_for_detail: f64) -> Toggle3Zoomed {
+        let mut unzoomed = DrawCustomUnzoomedShapes::builder();
+        for f in self.unzoomed {
+            unzoomed.add_custom(f);
+        }
+        Toggle3Zoomed {
+            draw_zoomed: self.zoomed.build(ctx),
+            unzoomed: unzoomed.build(PerZoom::new(min_zoom_for_detail, STEP_SIZE)),
+            icons: IconClusters::new(self.icons, min_zoom_for_detail),
+        }
+    }
+}
+
+struct Icon {
+    kind: &'static str,
+    batch: GeomBatch,
+    pt: Pt2D,
+    angle: Angle,
+    /// In screen pixels
+    width: f64,
+    label: Option<GeomBatch>,
+}
+
+struct IconClusters {
+    icons: Vec<Icon>,
+    per_zoom: RefCell<PerZoom>,
+}
+
+impl IconClusters {
+    fn new(icons: Vec<Icon>, min_zoom_for_detail: f64) -> Self {
+        Self {
+            icons,
+            per_zoom: RefCell::new(PerZoom::new(min_zoom_for_detail, STEP_SIZE)),
+        }
+    }
+
+    fn maybe_draw(&self, g: &mut GfxCtx) {
+        if self.icons.is_empty() {
+            return;
+        }
+        let mut per_zoom = self.per_zoom.borrow_mut();
+        let (zoom, idx) = per_zoom.discretize_zoom(g.canvas.cam_zoom);
+        if idx >= per_zoom.draw_per_zoom.len() {
+            return;
+        }
+        if per_zoom.draw_per_zoom[idx].is_none() {
+            let batch = self.render(g, 1.0 / zoom);
+            per_zoom.draw_per_zoom[idx] = Some(g.upload(batch));
+        }
+        g.redraw(per_zoom.draw_per_zoom[idx].as_ref().unwrap());
+    }
+
+    fn render(&self, g: &GfxCtx, thickness: f64) -> GeomBatch {
+        // Icons in the same grid cell, roughly one icon wide on screen, would overlap
+        let mut clusters: BTreeMap<(&'static str, isize, isize), Vec<&Icon>> = BTreeMap::new();
+        for icon in &self.icons {
+            let cell = icon.width * thickness;
+            clusters
+                .entry((
+                    icon.kind,
+                    (icon.pt.x() / cell).floor() as isize,
+                    (icon.pt.y() / cell).floor() as isize,
+                ))
+                .or_insert_with(Vec::new)
+                .push(icon);
+        }
+
+        let mut batch = GeomBatch::new();
+        for icons in clusters.into_values() {
+            let first = icons[0];
+            let width = first.width * thickness;
+
+            if icons.len() == 1 {
+                let icon = first
+                    .batch
+                    .clone()
+                    .scale_to_fit_width(width)
+                    .centered_on(first.pt)
+                    .rotate_around_batch_center(first.angle);
+                let dy = icon.get_dims().height / 2.0;
+                batch.append(icon);
+                if let Some(ref label) = first.label {
+                    let label = label.clone().scale_to_fit_height(LABEL_HEIGHT * thickness);
+                    let dy = dy + label.get_dims().height / 2.0;
+                    batch.append(label.centered_on(first.pt.offset(0.0, dy)));
+                }
+                continue;
+            }
+
+            let center = Pt2D::center(&icons.iter().map(|icon| icon.pt).collect::<Vec<_>>());
+            batch.append(
+                first
+                    .batch
+                    .clone()
+                    .scale_to_fit_width(width)
+                    .centered_on(center),
+            );
+            batch.append(
+                Text::from(Line(icons.len().to_string()).fg(Color::WHITE))
+                    .bg(Color::BLACK)
+                    .render_autocropped(g)
+                    .scale_to_fit_height(COUNT_HEIGHT * thickness)
+                    .centered_on(center.offset(width / 2.0, -width / 2.0)),
+            );
+        }
+        batch
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::Result;
+use geojson::Feature;
+use serde::{Deserialize, Serialize};
+
+use geom::{GPSBounds, LonLat, PolyLine, Polygon, Pt2D, Ring};
+
+/// A note somebody places on the map to explain their proposal
+#[derive(Clone)]
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    pub text: String,
+    /// One point for a pin, a path for an arrow, or a ring for an area
+    pub pts: Vec<Pt2D>,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AnnotationKind {
+    Pin,
+    Arrow,
+    Area,
+}
+
+/// Annotations are saved in WGS84, so they stay put even if the map's bounds change
+#[derive(Serialize, Deserialize)]
+pub struct PermanentAnnotation {
+    kind: AnnotationKind,
+    text: String,
+    pts: Vec<LonLat>,
+}
+
+impl Annotation {
+    pub fn pin(pt: Pt2D, text: String) -> Self {
+        Self {
+            kind: AnnotationKind::Pin,
+            text,
+            pts: vec![pt],
+        }
+    }
+
+    pub fn arrow(pl: PolyLine, text: String) -> Self {
+        Self {
+            kind: AnnotationKind::Arrow,
+            text,
+            pts: pl.into_points(),
+        }
+    }
+
+    pub fn area(polygon: Polygon, text: String) -> Self {
+        Self {
+            kind: AnnotationKind::Area,
+            text,
+            pts: polygon.get_outer_ring().clone().into_points(),
+        }
+    }
+
+    /// Where to put the text label
+    pub fn label_pt(&self) -> Pt2D {
+        match self.kind {
+            AnnotationKind::Pin => self.pts[0],
+            // Label the start of the arrow; the end is what it points at
+            AnnotationKind::Arrow => self.pts[0],
+            AnnotationKind::Area => self.polygon().unwrap().polylabel(),
+        }
+    }
+
+    pub fn polyline(&self) -> Result<PolyLine> {
+        PolyLine::new(self.pts.clone())
+    }
+
+    pub fn polygon(&self) -> Result<Polygon> {
+        Ok(Ring::new(self.pts.clone())?.into_polygon())
+    }
+
+    pub fn to_permanent(&self, gps_bounds: &GPSBounds) -> PermanentAnnotation {
+        PermanentAnnotation {
+            kind: self.kind,
+            text: self.text.clone(),
+            pts: gps_bounds.convert_back(&self.pts),
+        }
+    }
+
+    pub fn from_permanent(perma: PermanentAnnotation, gps_bounds: &GPSBounds) -> Result<Self> {
+        let pts = gps_bounds
+            .try_convert(&perma.pts)
+            .ok_or_else(|| anyhow!("annotation \"{}\" is outside this map", perma.text))?;
+        let annotation = Self {
+            kind: perma.kind,
+            text: perma.text,
+            pts,
+        };
+        // Make sure the geometry is still valid
+        match annotation.kind {
+            AnnotationKind::Pin => {}
+            AnnotationKind::Arrow => {
+                annotation.polyline()?;
+            }
+            AnnotationKind::Area => {
+                annotation.polygon()?;
+            }
+        }
+        Ok(annotation)
+    }
+
+    pub fn to_geojson(&self, gps_bounds: &GPSBounds) -> Result<Feature> {
+        let gps_bounds = Some(gps_bounds);
+        let mut feature = Feature::from(match self.kind {
+            AnnotationKind::Pin => self.pts[0].to_geojson(gps_bounds),
+            AnnotationKind::Arrow => self.polyline()?.to_geojson(gps_bounds),
+            AnnotationKind::Area => self.polygon()?.to_geojson(gps_bounds),
+        });
+        feature.set_property("type", "annotation");
+        feature.set_property(
+            "annotation_type",
+            match self.kind {
+                AnnotationKind::Pin => "pin",
+                AnnotationKind::Arrow => "arrow",
+                AnnotationKind::Area => "area",
+            },
+        );
+        feature.set_property("text", self.text.clone());
+        Ok(feature)
+    }
+}
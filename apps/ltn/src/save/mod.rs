@@ -1,3 +1,4 @@
+mod annotations;
 mod perma;
 mod proposals_ui;
 mod save_dialog;
@@ -15,6 +16,7 @@ use widgetry::{EventCtx, State};
 use crate::logic::{BlockID, Partitioning};
 use crate::{pages, App, Transition};
 
+pub use annotations::{Annotation, AnnotationKind};
 pub use share::PROPOSAL_HOST_URL;
 
 pub struct Proposals {
@@ -31,6 +33,7 @@ pub struct Proposals {
 pub struct Proposal {
     pub partitioning: Partitioning,
     pub edits: MapEdits,
+    pub annotations: Vec<Annotation>,
 }
 
 impl Proposal {
@@ -109,6 +112,7 @@ impl Proposals {
             list: vec![Proposal {
                 partitioning: Partitioning::seed_using_heuristics(map, timer),
                 edits: map.get_edits().clone(),
+                annotations: Vec::new(),
             }],
             current: 0,
         }
@@ -135,6 +139,15 @@ impl Proposals {
         // TODO Maybe we could mark this as unsaved, depending how we decide to do autosave
         self.list[self.current].edits = edits;
     }
+
+    /// Call before changing annotations. Like map edits, the basemap can't be annotated.
+    pub fn mut_annotations(&mut self) -> &mut Vec<Annotation> {
+        if self.current == 0 {
+            self.list.insert(1, self.list[0].clone());
+            self.current = 1;
+        }
+        &mut self.list[self.current].annotations
+    }
 }
 
 // After switching proposals, we have to recreate state
@@ -152,6 +165,8 @@ pub enum PreserveState {
     PerResidentImpact(BTreeSet<BlockID>, Option<BuildingID>),
     CycleNetwork,
     Census,
+    Citywide,
+    Annotate,
 }
 
 impl PreserveState {
@@ -195,16 +210,18 @@ impl PreserveState {
                 Transition::Replace(pages::CycleNetwork::new_state(ctx, app))
             }
             PreserveState::Census => Transition::Replace(pages::Census::new_state(ctx, app)),
+            PreserveState::Citywide => Transition::Replace(pages::Citywide::new_state(ctx, app)),
+            PreserveState::Annotate => Transition::Replace(pages::Annotate::new_state(ctx, app)),
         }
     }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn start_dir() -> Option<String> {
+pub fn start_dir() -> Option<String> {
     home::home_dir().map(|x| x.display().to_string())
 }
 
 #[cfg(target_arch = "wasm32")]
-fn start_dir() -> Option<String> {
+pub fn start_dir() -> Option<String> {
     None
 }
@@ -24,8 +24,9 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use serde_json::Value;
 
-use map_model::{Map, OriginalRoad, PermanentMapEdits, RoadID};
+use map_model::{osm, IntersectionID, Map, OriginalRoad, PermanentMapEdits, RoadID};
 
+use super::annotations::{Annotation, PermanentAnnotation};
 use super::Proposal;
 use crate::save::Partitioning;
 
@@ -38,6 +39,11 @@ pub fn to_permanent(map: &Map, proposal: &Proposal) -> Result<Value> {
         if is_road_id(path) {
             let replace_with = map.get_r(RoadID(value.as_u64().unwrap() as usize)).orig_id;
             *value = serde_json::to_value(&replace_with)?;
+        } else if is_intersection_id(path) {
+            let replace_with = map
+                .get_i(IntersectionID(value.as_u64().unwrap() as usize))
+                .orig_id;
+            *value = serde_json::to_value(&replace_with)?;
         }
         Ok(())
     })?;
@@ -46,6 +52,16 @@ pub fn to_permanent(map: &Map, proposal: &Proposal) -> Result<Value> {
         .as_object_mut()
         .unwrap()
         .insert("partitioning".to_string(), partitioning_value);
+
+    let annotations: Vec<PermanentAnnotation> = proposal
+        .annotations
+        .iter()
+        .map(|a| a.to_permanent(map.get_gps_bounds()))
+        .collect();
+    proposal_value.as_object_mut().unwrap().insert(
+        "annotations".to_string(),
+        serde_json::to_value(annotations)?,
+    );
     Ok(proposal_value)
 }
 
@@ -61,11 +77,28 @@ pub fn from_permanent(map: &Map, mut proposal_value: Value) -> Result<Proposal>
             let orig_id: OriginalRoad = serde_json::from_value(value.clone())?;
             let replace_with = map.find_r_by_osm_id(orig_id)?;
             *value = serde_json::to_value(&replace_with)?;
+        } else if is_intersection_id(path) {
+            let orig_id: osm::NodeID = serde_json::from_value(value.clone())?;
+            let replace_with = map.find_i_by_osm_id(orig_id)?;
+            *value = serde_json::to_value(&replace_with)?;
         }
         Ok(())
     })?;
     let partitioning: Partitioning = serde_json::from_value(partitioning_value)?;
 
+    // Older proposals don't have annotations
+    let mut annotations = Vec::new();
+    if let Some(value) = proposal_value
+        .as_object_mut()
+        .unwrap()
+        .remove("annotations")
+    {
+        let perma: Vec<PermanentAnnotation> = serde_json::from_value(value)?;
+        for a in perma {
+            annotations.push(Annotation::from_permanent(a, map.get_gps_bounds())?);
+        }
+    }
+
     // TODO This repeats a bit of MapEdits code, because we're starting from a Value
     // TODO And it skips the compat code
     let perma_edits: PermanentMapEdits = serde_json::from_value(proposal_value)?;
@@ -74,6 +107,7 @@ pub fn from_permanent(map: &Map, mut proposal_value: Value) -> Result<Proposal>
     Ok(Proposal {
         edits,
         partitioning,
+        annotations,
     })
 }
 
@@ -86,12 +120,24 @@ fn is_road_id(path: &str) -> bool {
             // The other
             Regex::new(r"^/partitioning/neighbourhoods/\d+/0/perimeter/interior/\d+$").unwrap(),
             Regex::new(r"^/partitioning/neighbourhoods/\d+/0/perimeter/roads/\d+/road$").unwrap(),
+            // Custom boundaries. The walk starts from the partitioning itself, so there's no
+            // prefix.
+            Regex::new(r"^/custom_boundaries/\d+/1/interior_roads/\d+$").unwrap(),
         ];
     }
 
     PATTERNS.iter().any(|re| re.is_match(path))
 }
 
+fn is_intersection_id(path: &str) -> bool {
+    lazy_static! {
+        static ref PATTERNS: Vec<Regex> =
+            vec![Regex::new(r"^/custom_boundaries/\d+/1/borders/\d+$").unwrap(),];
+    }
+
+    PATTERNS.iter().any(|re| re.is_match(path))
+}
+
 // Note there's no chance to transform keys in a map. So use serialize_btreemap elsewhere to force
 // into a list of pairs
 fn walk<F: Fn(&str, &mut Value) -> Result<()>>(
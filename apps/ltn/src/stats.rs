@@ -0,0 +1,143 @@
+//! Summary statistics about one neighbourhood, so people describing a scheme don't have to count
+//! things by hand.
+
+use std::collections::BTreeMap;
+
+use geom::Distance;
+use map_model::{osm, BuildingType, Map, PathConstraints};
+
+use crate::{Neighbourhood, NeighbourhoodID};
+
+pub struct NeighbourhoodStats {
+    pub id: NeighbourhoodID,
+    /// The total length of interior streets, grouped by their OSM `highway` classification
+    pub street_length_per_classification: BTreeMap<String, Distance>,
+    /// In square meters
+    pub area: f64,
+    /// Housing units in residential buildings. Mixed-use buildings count as one household.
+    pub households: usize,
+    /// Possible shortcuts through the neighbourhood. This estimates how much through-traffic the
+    /// current filters allow.
+    pub shortcuts: usize,
+    /// Modal filters on interior streets and intersections, existing or new
+    pub filters: usize,
+    /// The fraction of the length of interior streets that cars can't use at all
+    pub car_free_share: f64,
+}
+
+impl NeighbourhoodStats {
+    pub fn new(map: &Map, neighbourhood: &Neighbourhood) -> Self {
+        let mut street_length_per_classification = BTreeMap::new();
+        let mut car_free_length = Distance::ZERO;
+        let mut filters = 0;
+        for r in &neighbourhood.interior_roads {
+            let road = map.get_r(*r);
+            let classification = road
+                .osm_tags
+                .get(osm::HIGHWAY)
+                .cloned()
+                .unwrap_or_else(|| "unknown".to_string());
+            *street_length_per_classification
+                .entry(classification)
+                .or_insert(Distance::ZERO) += road.length();
+            if !PathConstraints::Car.can_use_road(road, map) {
+                car_free_length += road.length();
+            }
+            if road.modal_filter.is_some() {
+                filters += 1;
+            }
+        }
+        for i in &neighbourhood.interior_intersections {
+            if map.get_i(*i).modal_filter.is_some() {
+                filters += 1;
+            }
+        }
+
+        let mut households = 0;
+        for b in map.all_buildings() {
+            if !neighbourhood
+                .boundary_polygon
+                .contains_pt(b.polygon.center())
+            {
+                continue;
+            }
+            households += match b.bldg_type {
+                BuildingType::Residential {
+                    num_housing_units, ..
+                } => num_housing_units,
+                BuildingType::ResidentialCommercial(_, _) => 1,
+                BuildingType::Commercial(_) | BuildingType::Empty => 0,
+            };
+        }
+
+        let total_length: Distance = street_length_per_classification.values().cloned().sum();
+        let car_free_share = if total_length == Distance::ZERO {
+            0.0
+        } else {
+            car_free_length / total_length
+        };
+
+        Self {
+            id: neighbourhood.id,
+            street_length_per_classification,
+            area: neighbourhood.boundary_polygon.area(),
+            households,
+            shortcuts: neighbourhood.shortcuts.paths.len(),
+            filters,
+            car_free_share,
+        }
+    }
+
+    pub fn total_street_length(&self) -> Distance {
+        self.street_length_per_classification
+            .values()
+            .cloned()
+            .sum()
+    }
+
+    /// One line per statistic, for showing in a panel
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Area: ~{:.2} km²", self.area / 1_000_000.0),
+            format!(
+                "Households: {}",
+                abstutil::prettyprint_usize(self.households)
+            ),
+            format!("Modal filters: {}", self.filters),
+            format!(
+                "Possible shortcuts (estimated through-traffic): {}",
+                self.shortcuts
+            ),
+            format!(
+                "Car-free streets: {:.1}% of the length",
+                self.car_free_share * 100.0
+            ),
+            format!("Streets: {}", km(self.total_street_length())),
+        ];
+        for (classification, length) in &self.street_length_per_classification {
+            lines.push(format!("- {}: {}", classification, km(*length)));
+        }
+        lines
+    }
+
+    /// Adds every statistic as a property of an exported feature
+    pub fn to_geojson_properties(&self, feature: &mut geojson::Feature) {
+        feature.set_property("area_km2", self.area / 1_000_000.0);
+        feature.set_property("households", self.households);
+        feature.set_property("modal_filters", self.filters);
+        feature.set_property("shortcuts", self.shortcuts);
+        feature.set_property("car_free_share", self.car_free_share);
+        let mut street_km = serde_json::Map::new();
+        for (classification, length) in &self.street_length_per_classification {
+            street_km.insert(
+                classification.clone(),
+                (length.inner_meters() / 1000.0).into(),
+            );
+        }
+        feature.set_property("street_km", street_km);
+    }
+}
+
+fn km(distance: Distance) -> String {
+    format!("{:.2} km", distance.inner_meters() / 1000.0)
+}
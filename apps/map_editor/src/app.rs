@@ -1,10 +1,11 @@
-use geom::{Distance, Line, Polygon, Pt2D};
+use geom::{Distance, Line, PolyLine, Polygon, Pt2D};
 use osm2streets::{IntersectionID, Transformation};
 use widgetry::mapspace::WorldOutcome;
 use widgetry::tools::{open_browser, URLManager};
 use widgetry::{
-    lctrl, Canvas, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
-    SharedAppState, State, Text, Toggle, Transition, VerticalAlignment, Widget,
+    lctrl, Canvas, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, RoundedF64, SharedAppState, Spinner, State, Text, TextExt, Toggle, Transition,
+    VerticalAlignment, Widget,
 };
 
 use crate::camera::CameraState;
@@ -35,6 +36,8 @@ impl SharedAppState for App {
 pub struct MainState {
     mode: Mode,
     panel: Panel,
+    /// Road center-lines before and after smoothing, if the preview is on
+    smoothing_preview: Option<Drawable>,
 }
 
 enum Mode {
@@ -103,6 +106,26 @@ impl MainState {
                             .btn_outline
                             .text("simplify RawMap")
                             .build_def(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("compare with another RawMap")
+                            .build_def(ctx),
+                        ctx.style()
+                            .btn_outline
+                            .text("data quality report")
+                            .build_def(ctx),
+                    ])
+                    .section(ctx),
+                    Widget::col(vec![
+                        Widget::row(vec![
+                            "Tolerance (meters)".text_widget(ctx).centered_vert(),
+                            Spinner::f64_widget(ctx, "smoothing tolerance", (0.1, 10.0), 1.0, 0.1),
+                        ]),
+                        Toggle::switch(ctx, "preview smoothing", Key::S, false),
+                        ctx.style()
+                            .btn_outline
+                            .text("smooth center-lines")
+                            .build_def(ctx),
                     ])
                     .section(ctx),
                 ]),
@@ -110,6 +133,7 @@ impl MainState {
             ]))
             .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
             .build(ctx),
+            smoothing_preview: None,
         };
         state.update_instructions(ctx, app);
         Box::new(state)
@@ -136,6 +160,18 @@ impl MainState {
         let instructions = txt.into_widget(ctx);
         self.panel.replace(ctx, "instructions", instructions);
     }
+
+    fn smoothing_tolerance(&self) -> Distance {
+        Distance::meters(self.panel.spinner::<RoundedF64>("smoothing tolerance").0)
+    }
+
+    fn update_smoothing_preview(&mut self, ctx: &EventCtx, app: &App) {
+        self.smoothing_preview = if self.panel.is_checked("preview smoothing") {
+            Some(draw_smoothing_preview(ctx, app, self.smoothing_tolerance()))
+        } else {
+            None
+        };
+    }
 }
 
 impl State<App> for MainState {
@@ -259,6 +295,18 @@ impl State<App> for MainState {
                         "adjust boundary" => {
                             self.mode = Mode::SetBoundaryPt1;
                         }
+                        "smooth center-lines" => {
+                            let tolerance = self.smoothing_tolerance();
+                            ctx.loading_screen("smooth center-lines", |ctx, timer| {
+                                raw_map::transform::smooth_centerlines(
+                                    &mut app.model.map,
+                                    tolerance,
+                                    timer,
+                                );
+                                app.model.recreate_world(ctx, timer);
+                            });
+                            self.update_smoothing_preview(ctx, app);
+                        }
                         "simplify RawMap" => {
                             ctx.loading_screen("simplify", |ctx, timer| {
                                 app.model
@@ -283,18 +331,31 @@ impl State<App> for MainState {
                                 None,
                             ));
                         }
+                        "compare with another RawMap" => {
+                            return Transition::Push(crate::load::PickMap::compare_state(ctx));
+                        }
+                        "data quality report" => {
+                            return Transition::Push(crate::quality::QualityReport::new_state(
+                                ctx, app,
+                            ));
+                        }
                         "open another RawMap" => {
                             CameraState::save(ctx.canvas, &app.model.map.name);
                             return Transition::Push(crate::load::PickMap::new_state(ctx));
                         }
                         _ => unreachable!(),
                     },
-                    Outcome::Changed(_) => {
-                        app.model.show_intersection_geometry(
-                            ctx,
-                            self.panel.is_checked("show intersection geometry"),
-                        );
-                    }
+                    Outcome::Changed(x) => match x.as_ref() {
+                        "preview smoothing" | "smoothing tolerance" => {
+                            self.update_smoothing_preview(ctx, app);
+                        }
+                        _ => {
+                            app.model.show_intersection_geometry(
+                                ctx,
+                                self.panel.is_checked("show intersection geometry"),
+                            );
+                        }
+                    },
                     _ => {}
                 }
             }
@@ -368,6 +429,9 @@ impl State<App> for MainState {
             app.model.map.streets.boundary_polygon.clone(),
         );
         app.model.world.draw(g);
+        if let Some(ref draw) = self.smoothing_preview {
+            g.redraw(draw);
+        }
 
         match self.mode {
             Mode::Neutral | Mode::SetBoundaryPt1 => {}
@@ -393,3 +457,18 @@ impl State<App> for MainState {
         self.panel.draw(g);
     }
 }
+
+/// Draw every road's current center-line in red, with the smoothed version on top in green
+fn draw_smoothing_preview(ctx: &EventCtx, app: &App, tolerance: Distance) -> Drawable {
+    let mut batch = GeomBatch::new();
+    for road in app.model.map.streets.roads.values() {
+        let before = &road.reference_line;
+        batch.push(Color::RED, before.make_polygons(Distance::meters(1.5)));
+        if let Ok(after) =
+            PolyLine::deduping_new(raw_map::transform::smooth_pts(before.points(), tolerance))
+        {
+            batch.push(Color::GREEN, after.make_polygons(Distance::meters(0.5)));
+        }
+    }
+    ctx.upload(batch)
+}
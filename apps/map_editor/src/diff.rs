@@ -0,0 +1,522 @@
+//! Compare the loaded RawMap against another version of it -- maybe imported from a newer OSM
+//! extract, or with different consolidation settings -- and show which roads and intersections
+//! were added, removed, or modified. Useful for tracking down import regressions.
+
+use std::collections::BTreeMap;
+
+use abstutil::{Tags, Timer};
+use geom::{Circle, Distance, HashablePt2D, PolyLine, Polygon, Pt2D};
+use osm2streets::{osm, IntersectionID, RoadID};
+use raw_map::RawMap;
+use widgetry::tools::{ColorLegend, FileLoader, PopupMsg};
+use widgetry::{
+    Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, Text, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+const ADDED: Color = Color::GREEN;
+const REMOVED: Color = Color::RED;
+const MODIFIED: Color = Color::YELLOW;
+
+/// Intersections that move less than this are considered unchanged.
+const MOVED_THRESHOLD: Distance = Distance::const_meters(1.0);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    fn color(self) -> Color {
+        match self {
+            ChangeKind::Added => ADDED,
+            ChangeKind::Removed => REMOVED,
+            ChangeKind::Modified => MODIFIED,
+        }
+    }
+}
+
+pub struct Change {
+    pub kind: ChangeKind,
+    pub is_road: bool,
+    pub label: String,
+    pub details: Vec<String>,
+    /// In the coordinate space of the loaded map
+    pub polygon: Polygon,
+}
+
+/// Objects are matched between the two maps by OSM IDs, since `RoadID`s and `IntersectionID`s
+/// change between imports. Objects without OSM IDs, like the ones drawn by hand in this editor,
+/// fall back to matching by position.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum MatchKey {
+    Osm(Vec<osm::WayID>, Vec<osm::NodeID>, Vec<osm::NodeID>),
+    Position(Vec<HashablePt2D>),
+}
+
+/// Returns every difference between `before` and `after`, with all geometry expressed in
+/// `before`'s coordinate space. Roads come first, then intersections.
+pub fn diff_maps(before: &RawMap, after: &RawMap, timer: &mut Timer) -> Vec<Change> {
+    let to_before = |pt: Pt2D| -> Pt2D {
+        pt.to_gps(&after.streets.gps_bounds)
+            .to_pt(&before.streets.gps_bounds)
+    };
+
+    timer.start("diff roads");
+    let before_roads = road_keys(before, &|pt| pt);
+    let after_roads = road_keys(after, &to_before);
+    let mut changes = Vec::new();
+    for (key, r1) in &before_roads {
+        let road = &before.streets.roads[r1];
+        let polygon = road.reference_line.make_polygons(road.total_width());
+        match after_roads.get(key) {
+            Some(r2) => {
+                let details = road_details(before, *r1, after, *r2, &to_before);
+                if !details.is_empty() {
+                    changes.push(Change {
+                        kind: ChangeKind::Modified,
+                        is_road: true,
+                        label: describe_road(before, *r1),
+                        details,
+                        polygon,
+                    });
+                }
+            }
+            None => {
+                changes.push(Change {
+                    kind: ChangeKind::Removed,
+                    is_road: true,
+                    label: describe_road(before, *r1),
+                    details: Vec::new(),
+                    polygon,
+                });
+            }
+        }
+    }
+    for (key, r2) in &after_roads {
+        if before_roads.contains_key(key) {
+            continue;
+        }
+        let road = &after.streets.roads[r2];
+        let pts = road
+            .reference_line
+            .points()
+            .iter()
+            .map(|pt| to_before(*pt))
+            .collect();
+        if let Ok(pl) = PolyLine::deduping_new(pts) {
+            changes.push(Change {
+                kind: ChangeKind::Added,
+                is_road: true,
+                label: describe_road(after, *r2),
+                details: Vec::new(),
+                polygon: pl.make_polygons(road.total_width()),
+            });
+        }
+    }
+    timer.stop("diff roads");
+
+    timer.start("diff intersections");
+    let before_intersections = intersection_keys(before, &|pt| pt);
+    let after_intersections = intersection_keys(after, &to_before);
+    for (key, i1) in &before_intersections {
+        let center = before.streets.intersections[i1].polygon.center();
+        let polygon = Circle::new(center, Distance::meters(5.0)).to_polygon();
+        match after_intersections.get(key) {
+            Some(i2) => {
+                let details = intersection_details(before, *i1, after, *i2, &to_before);
+                if !details.is_empty() {
+                    changes.push(Change {
+                        kind: ChangeKind::Modified,
+                        is_road: false,
+                        label: describe_intersection(before, *i1),
+                        details,
+                        polygon,
+                    });
+                }
+            }
+            None => {
+                changes.push(Change {
+                    kind: ChangeKind::Removed,
+                    is_road: false,
+                    label: describe_intersection(before, *i1),
+                    details: Vec::new(),
+                    polygon,
+                });
+            }
+        }
+    }
+    for (key, i2) in &after_intersections {
+        if before_intersections.contains_key(key) {
+            continue;
+        }
+        let center = to_before(after.streets.intersections[i2].polygon.center());
+        changes.push(Change {
+            kind: ChangeKind::Added,
+            is_road: false,
+            label: describe_intersection(after, *i2),
+            details: Vec::new(),
+            polygon: Circle::new(center, Distance::meters(5.0)).to_polygon(),
+        });
+    }
+    timer.stop("diff intersections");
+
+    changes
+}
+
+fn road_keys(map: &RawMap, transform: &dyn Fn(Pt2D) -> Pt2D) -> BTreeMap<MatchKey, RoadID> {
+    let mut keys = BTreeMap::new();
+    for road in map.streets.roads.values() {
+        let key = if road.osm_ids.is_empty() {
+            MatchKey::Position(
+                road.reference_line
+                    .points()
+                    .iter()
+                    .map(|pt| rounded(transform(*pt)))
+                    .collect(),
+            )
+        } else {
+            let mut ways = road.osm_ids.clone();
+            ways.sort();
+            MatchKey::Osm(
+                ways,
+                sorted_nodes(map, road.src_i),
+                sorted_nodes(map, road.dst_i),
+            )
+        };
+        keys.insert(key, road.id);
+    }
+    keys
+}
+
+fn intersection_keys(
+    map: &RawMap,
+    transform: &dyn Fn(Pt2D) -> Pt2D,
+) -> BTreeMap<MatchKey, IntersectionID> {
+    let mut keys = BTreeMap::new();
+    for (id, i) in &map.streets.intersections {
+        let key = if i.osm_ids.is_empty() {
+            MatchKey::Position(vec![rounded(transform(i.polygon.center()))])
+        } else {
+            MatchKey::Osm(Vec::new(), sorted_nodes(map, *id), Vec::new())
+        };
+        keys.insert(key, *id);
+    }
+    keys
+}
+
+fn sorted_nodes(map: &RawMap, i: IntersectionID) -> Vec<osm::NodeID> {
+    let mut nodes = map.streets.intersections[&i].osm_ids.clone();
+    nodes.sort();
+    nodes
+}
+
+/// Converting between GPS bounds loses a little precision, so only match positions to the nearest
+/// meter.
+fn rounded(pt: Pt2D) -> HashablePt2D {
+    Pt2D::new(pt.x().round(), pt.y().round()).to_hashable()
+}
+
+fn road_details(
+    before: &RawMap,
+    r1: RoadID,
+    after: &RawMap,
+    r2: RoadID,
+    to_before: &dyn Fn(Pt2D) -> Pt2D,
+) -> Vec<String> {
+    let road1 = &before.streets.roads[&r1];
+    let road2 = &after.streets.roads[&r2];
+    let mut details = Vec::new();
+
+    let pts1 = road1.reference_line.points();
+    let pts2 = road2.reference_line.points();
+    if pts1.len() != pts2.len()
+        || pts1
+            .iter()
+            .zip(pts2.iter())
+            .any(|(pt1, pt2)| !pt1.approx_eq(to_before(*pt2), MOVED_THRESHOLD))
+    {
+        details.push(format!(
+            "Geometry changed: {} points, {} long -> {} points, {} long",
+            pts1.len(),
+            road1.reference_line.length(),
+            pts2.len(),
+            road2.reference_line.length()
+        ));
+    }
+
+    let lanes1 = road1
+        .lane_specs_ltr
+        .iter()
+        .map(|spec| (spec.lt, spec.dir, spec.width))
+        .collect::<Vec<_>>();
+    let lanes2 = road2
+        .lane_specs_ltr
+        .iter()
+        .map(|spec| (spec.lt, spec.dir, spec.width))
+        .collect::<Vec<_>>();
+    if lanes1 != lanes2 {
+        details.push(format!(
+            "Lanes changed: {} lanes, {} wide -> {} lanes, {} wide",
+            lanes1.len(),
+            road1.total_width(),
+            lanes2.len(),
+            road2.total_width()
+        ));
+    }
+
+    let tags1 = before
+        .road_to_osm_tags(r1)
+        .cloned()
+        .unwrap_or_else(Tags::empty);
+    let tags2 = after
+        .road_to_osm_tags(r2)
+        .cloned()
+        .unwrap_or_else(Tags::empty);
+    for (k, v1, v2) in tags1.diff(&tags2) {
+        details.push(format!("{}: \"{}\" -> \"{}\"", k, v1, v2));
+    }
+
+    if road1.internal_junction_road != road2.internal_junction_road {
+        details.push(format!(
+            "Junction road: {} -> {}",
+            road1.internal_junction_road, road2.internal_junction_road
+        ));
+    }
+
+    details
+}
+
+fn intersection_details(
+    before: &RawMap,
+    i1: IntersectionID,
+    after: &RawMap,
+    i2: IntersectionID,
+    to_before: &dyn Fn(Pt2D) -> Pt2D,
+) -> Vec<String> {
+    let int1 = &before.streets.intersections[&i1];
+    let int2 = &after.streets.intersections[&i2];
+    let mut details = Vec::new();
+
+    let center1 = int1.polygon.center();
+    let center2 = to_before(int2.polygon.center());
+    if !center1.approx_eq(center2, MOVED_THRESHOLD) {
+        details.push(format!("Moved {}", center1.dist_to(center2)));
+    }
+    if int1.kind != int2.kind {
+        details.push(format!("Kind: {:?} -> {:?}", int1.kind, int2.kind));
+    }
+    if int1.control != int2.control {
+        details.push(format!("Control: {:?} -> {:?}", int1.control, int2.control));
+    }
+    if int1.roads.len() != int2.roads.len() {
+        details.push(format!(
+            "Connected roads: {} -> {}",
+            int1.roads.len(),
+            int2.roads.len()
+        ));
+    }
+
+    details
+}
+
+fn describe_road(map: &RawMap, r: RoadID) -> String {
+    let road = &map.streets.roads[&r];
+    let name = map
+        .road_to_osm_tags(r)
+        .and_then(|tags| tags.get("name").cloned())
+        .unwrap_or_else(|| "unnamed road".to_string());
+    match road.osm_ids.get(0) {
+        Some(way) => format!("{} ({})", name, way),
+        None => name,
+    }
+}
+
+fn describe_intersection(map: &RawMap, i: IntersectionID) -> String {
+    match map.streets.intersections[&i].osm_ids.get(0) {
+        Some(node) => format!("Intersection ({})", node),
+        None => "Intersection".to_string(),
+    }
+}
+
+/// Load another RawMap, then compare the current one against it.
+pub fn load_comparison(ctx: &mut EventCtx, path: String) -> Box<dyn State<App>> {
+    FileLoader::<App, RawMap>::new_state(
+        ctx,
+        path,
+        Box::new(move |ctx, app, timer, map| match map {
+            Ok(other) => Transition::Replace(CompareMaps::new_state(ctx, app, other, timer)),
+            Err(err) => Transition::Replace(PopupMsg::new_state(
+                ctx,
+                "Error",
+                vec![format!("Couldn't load the other RawMap: {}", err)],
+            )),
+        }),
+    )
+}
+
+pub struct CompareMaps {
+    panel: Panel,
+    changes: Vec<Change>,
+    current: usize,
+    draw: Drawable,
+    highlight: Drawable,
+}
+
+impl CompareMaps {
+    fn new_state(
+        ctx: &mut EventCtx,
+        app: &App,
+        other: RawMap,
+        timer: &mut Timer,
+    ) -> Box<dyn State<App>> {
+        let changes = diff_maps(&app.model.map, &other, timer);
+
+        let mut batch = GeomBatch::new();
+        for change in &changes {
+            batch.push(change.kind.color().alpha(0.5), change.polygon.clone());
+        }
+
+        let count = |kind: ChangeKind, is_road: bool| {
+            changes
+                .iter()
+                .filter(|c| c.kind == kind && c.is_road == is_road)
+                .count()
+        };
+        let mut legend = Vec::new();
+        for (kind, color, label) in [
+            (ChangeKind::Added, ADDED, "added"),
+            (ChangeKind::Removed, REMOVED, "removed"),
+            (ChangeKind::Modified, MODIFIED, "modified"),
+        ] {
+            legend.push(ColorLegend::row(
+                ctx,
+                color,
+                format!(
+                    "{} roads, {} intersections {}",
+                    count(kind, true),
+                    count(kind, false),
+                    label
+                ),
+            ));
+        }
+
+        let mut state = CompareMaps {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Comparing RawMaps").small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                format!(
+                    "{} (loaded) vs {}",
+                    app.model.map.name.describe(),
+                    other.name.describe()
+                )
+                .text_widget(ctx),
+                Widget::col(legend).section(ctx),
+                Widget::placeholder(ctx, "current change"),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            changes,
+            current: 0,
+            draw: ctx.upload(batch),
+            highlight: Drawable::empty(ctx),
+        };
+        state.update(ctx);
+        Box::new(state)
+    }
+
+    fn update(&mut self, ctx: &mut EventCtx) {
+        if self.changes.is_empty() {
+            let widget = "No differences found".text_widget(ctx);
+            self.panel.replace(ctx, "current change", widget);
+            return;
+        }
+
+        let change = &self.changes[self.current];
+        let mut txt = Text::from(Line(format!(
+            "{:?} {}",
+            change.kind,
+            if change.is_road {
+                "road"
+            } else {
+                "intersection"
+            }
+        )));
+        txt.add_line(Line(&change.label).secondary());
+        for line in &change.details {
+            txt.add_line(Line(format!("- {}", line)));
+        }
+
+        let widget = Widget::col(vec![
+            Widget::row(vec![
+                ctx.style()
+                    .btn_prev()
+                    .disabled(self.current == 0)
+                    .hotkey(Key::LeftArrow)
+                    .build_widget(ctx, "previous change"),
+                Text::from(
+                    Line(format!("{}/{}", self.current + 1, self.changes.len())).secondary(),
+                )
+                .into_widget(ctx)
+                .centered_vert(),
+                ctx.style()
+                    .btn_next()
+                    .disabled(self.current == self.changes.len() - 1)
+                    .hotkey(Key::RightArrow)
+                    .build_widget(ctx, "next change"),
+            ]),
+            txt.into_widget(ctx),
+        ])
+        .section(ctx);
+        self.panel.replace(ctx, "current change", widget);
+
+        let mut batch = GeomBatch::new();
+        batch.push(
+            Color::CYAN,
+            change.polygon.to_outline(Distance::meters(1.0)),
+        );
+        self.highlight = ctx.upload(batch);
+        ctx.canvas.center_on_map_pt(change.polygon.center());
+    }
+}
+
+impl State<App> for CompareMaps {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "previous change" => {
+                    self.current -= 1;
+                    self.update(ctx);
+                }
+                "next change" => {
+                    self.current += 1;
+                    self.update(ctx);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        g.redraw(&self.highlight);
+        self.panel.draw(g);
+    }
+}
@@ -18,9 +18,11 @@ use crate::app::App;
 
 mod app;
 mod camera;
+mod diff;
 mod edit;
 mod load;
 mod model;
+mod quality;
 
 pub fn main() {
     let settings = Settings::new("RawMap editor");
@@ -10,10 +10,34 @@ use crate::camera::CameraState;
 
 pub struct PickMap {
     panel: Panel,
+    on_pick: Box<dyn Fn(&mut EventCtx, &App, String) -> Transition<App>>,
 }
 
 impl PickMap {
     pub fn new_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        PickMap::with_action(
+            ctx,
+            "Select a map",
+            Box::new(|ctx, app, path| {
+                Transition::Push(load_map(ctx, path, app.model.include_bldgs, None))
+            }),
+        )
+    }
+
+    /// Pick another version of a RawMap to compare the current one against.
+    pub fn compare_state(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        PickMap::with_action(
+            ctx,
+            "Select a map to compare against",
+            Box::new(|ctx, _, path| Transition::Replace(crate::diff::load_comparison(ctx, path))),
+        )
+    }
+
+    fn with_action(
+        ctx: &mut EventCtx,
+        title: &str,
+        on_pick: Box<dyn Fn(&mut EventCtx, &App, String) -> Transition<App>>,
+    ) -> Box<dyn State<App>> {
         let mut entries = Vec::new();
         for name in MapName::list_all_maps_merged(&Manifest::load()) {
             entries.push((name.describe(), abstio::path_raw_map(&name)));
@@ -22,7 +46,7 @@ impl PickMap {
         Box::new(PickMap {
             panel: Panel::new_builder(Widget::col(vec![
                 Widget::row(vec![
-                    Line("Select a map").small_heading().into_widget(ctx),
+                    Line(title).small_heading().into_widget(ctx),
                     ctx.style().btn_close_widget(ctx),
                 ]),
                 Widget::row(vec![
@@ -32,6 +56,7 @@ impl PickMap {
             ]))
             .exact_size_percent(80, 80)
             .build(ctx),
+            on_pick,
         })
     }
 }
@@ -48,12 +73,7 @@ impl State<App> for PickMap {
         }
         if let Some(mut paths) = self.panel.autocomplete_done::<String>("search") {
             if !paths.is_empty() {
-                return Transition::Push(load_map(
-                    ctx,
-                    paths.remove(0),
-                    app.model.include_bldgs,
-                    None,
-                ));
+                return (self.on_pick)(ctx, app, paths.remove(0));
             }
         }
 
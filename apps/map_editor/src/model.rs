@@ -498,11 +498,16 @@ impl Model {
     }
 
     pub fn merge_r(&mut self, ctx: &EventCtx, id: RoadID) {
+        let merge = raw_map::merges::ManualMerge::new(&self.map, id);
         if let Err(err) = self.map.streets.collapse_short_road(id) {
             warn!("Can't merge this road: {}", err);
             return;
         }
         info!("Merged {id}");
+        // Remember the merge, so re-importing from OSM doesn't lose it
+        if let Some(merge) = merge {
+            raw_map::merges::record(&self.map.name, merge);
+        }
 
         // This is very blunt and slow. Multiple roads and intersections might've vanished.
         self.recreate_world(ctx, &mut Timer::throwaway());
@@ -543,6 +548,7 @@ impl Model {
                 osm_tags: Tags::empty(),
                 public_garage_name: None,
                 num_parking_spots: 0,
+                bike_parking: 0,
                 amenities: Vec::new(),
             },
         );
@@ -0,0 +1,180 @@
+//! Step through suspicious data in the loaded RawMap, like roads missing speed limits or
+//! buildings far from any sidewalk, and apply the safe fixes.
+
+use geom::Distance;
+use raw_map::quality::{find_quality_issues, IssueKind, QualityIssue};
+use widgetry::{
+    Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, Text, TextExt, Transition, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+
+pub struct QualityReport {
+    panel: Panel,
+    issues: Vec<QualityIssue>,
+    current: usize,
+    draw: Drawable,
+    highlight: Drawable,
+}
+
+impl QualityReport {
+    pub fn new_state(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let issues = ctx.loading_screen("check data quality", |_, timer| {
+            find_quality_issues(&app.model.map, timer)
+        });
+        let mut state = QualityReport {
+            panel: Panel::new_builder(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Data quality").small_heading().into_widget(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                Widget::placeholder(ctx, "summary"),
+                Widget::placeholder(ctx, "current issue"),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            issues,
+            current: 0,
+            draw: Drawable::empty(ctx),
+            highlight: Drawable::empty(ctx),
+        };
+        state.update_summary(ctx);
+        state.update(ctx);
+        Box::new(state)
+    }
+
+    fn update_summary(&mut self, ctx: &mut EventCtx) {
+        let mut txt = Text::new();
+        for kind in IssueKind::all() {
+            txt.add_line(format!(
+                "{} {}",
+                self.issues.iter().filter(|i| i.kind == kind).count(),
+                kind.plural()
+            ));
+        }
+        let widget = txt.into_widget(ctx).section(ctx);
+        self.panel.replace(ctx, "summary", widget);
+
+        let mut batch = GeomBatch::new();
+        for issue in &self.issues {
+            batch.push(Color::RED.alpha(0.5), issue.polygon.clone());
+        }
+        self.draw = ctx.upload(batch);
+    }
+
+    fn update(&mut self, ctx: &mut EventCtx) {
+        if self.issues.is_empty() {
+            let widget = "No problems found".text_widget(ctx);
+            self.panel.replace(ctx, "current issue", widget);
+            self.highlight = Drawable::empty(ctx);
+            return;
+        }
+
+        let issue = &self.issues[self.current];
+        let mut txt = Text::from(Line(format!("{:?}", issue.kind)));
+        txt.add_line(Line(&issue.details).secondary());
+        if !issue.osm_ways.is_empty() {
+            txt.add_line(Line(format!(
+                "OSM: {}",
+                issue
+                    .osm_ways
+                    .iter()
+                    .map(|w| w.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+
+        let widget = Widget::col(vec![
+            Widget::row(vec![
+                ctx.style()
+                    .btn_prev()
+                    .disabled(self.current == 0)
+                    .hotkey(Key::LeftArrow)
+                    .build_widget(ctx, "previous issue"),
+                Text::from(Line(format!("{}/{}", self.current + 1, self.issues.len())).secondary())
+                    .into_widget(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_next()
+                    .disabled(self.current == self.issues.len() - 1)
+                    .hotkey(Key::RightArrow)
+                    .build_widget(ctx, "next issue"),
+            ]),
+            txt.into_widget(ctx),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_outline
+                    .text("zoom to")
+                    .hotkey(Key::Z)
+                    .build_def(ctx),
+                match issue.fix {
+                    Some(ref fix) => ctx
+                        .style()
+                        .btn_solid_primary
+                        .text(format!("Fix: {}", fix))
+                        .build_widget(ctx, "fix"),
+                    None => Widget::nothing(),
+                },
+            ]),
+        ])
+        .section(ctx);
+        self.panel.replace(ctx, "current issue", widget);
+
+        let mut batch = GeomBatch::new();
+        batch.push(Color::CYAN, issue.polygon.to_outline(Distance::meters(1.0)));
+        self.highlight = ctx.upload(batch);
+        ctx.canvas.center_on_map_pt(issue.polygon.center());
+    }
+}
+
+impl State<App> for QualityReport {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+        ctx.canvas_movement();
+
+        if let Outcome::Clicked(x) = self.panel.event(ctx) {
+            match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "previous issue" => {
+                    self.current -= 1;
+                    self.update(ctx);
+                }
+                "next issue" => {
+                    self.current += 1;
+                    self.update(ctx);
+                }
+                "zoom to" => {
+                    let issue = &self.issues[self.current];
+                    ctx.canvas.center_on_map_pt(issue.polygon.center());
+                }
+                "fix" => {
+                    let fix = self.issues[self.current].fix.clone().unwrap();
+                    self.issues = ctx.loading_screen("fix data quality issue", |ctx, timer| {
+                        fix.apply(&mut app.model.map);
+                        app.model.recreate_world(ctx, timer);
+                        find_quality_issues(&app.model.map, timer)
+                    });
+                    self.current = self.current.min(self.issues.len().saturating_sub(1));
+                    self.update_summary(ctx);
+                    self.update(ctx);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw);
+        g.redraw(&self.highlight);
+        self.panel.draw(g);
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::Speed;
+use map_model::{osm, Map};
+use synthpop::{ExternalObservation, ObservedTraffic};
+
+pub fn run(csv_path: String, map: String, description: String) -> Result<()> {
+    let mut timer = Timer::new("import observed traffic");
+    timer.start("parse CSV");
+    let input = parse_observations(csv_path)?;
+    timer.stop("parse CSV");
+    let map = Map::load_synchronously(map, &mut timer);
+
+    let observed = ObservedTraffic::import(&map, description, input, &mut timer)?;
+    let roads: BTreeSet<_> = observed.per_road.keys().map(|(r, _)| *r).collect();
+    println!(
+        "Imported observations for {} roads",
+        prettyprint_usize(roads.len())
+    );
+    observed.save();
+
+    Ok(())
+}
+
+fn parse_observations(csv_path: String) -> Result<Vec<ExternalObservation>> {
+    let mut input = Vec::new();
+    for rec in csv::Reader::from_reader(fs_err::File::open(csv_path)?).deserialize() {
+        let rec: Record = rec?;
+        input.push(ExternalObservation {
+            osm_way_id: osm::WayID(rec.osm_way_id),
+            hour: rec.hour,
+            speed: rec.speed_kmh.map(Speed::km_per_hour),
+            volume: rec.volume,
+        });
+    }
+    Ok(input)
+}
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    osm_way_id: i64,
+    hour: usize,
+    speed_kmh: Option<f64>,
+    volume: Option<usize>,
+}
@@ -8,18 +8,22 @@ mod augment_scenario;
 mod clip_osm;
 mod generate_houses;
 mod import_grid2demand;
+mod import_observed_traffic;
 mod import_scenario;
+mod multi_run;
 mod one_step_import;
+mod run_experiment;
 
 use std::io::Write;
 
 use abstio::CityName;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use fs_err::File;
 use importer::Job;
 use structopt::StructOpt;
 
 use abstutil::Timer;
+use geom::Distance;
 
 #[derive(StructOpt)]
 #[structopt(name = "abcli", about = "The A/B Street multi-tool")]
@@ -102,6 +106,21 @@ enum Command {
         #[structopt(long)]
         skip_problems: bool,
     },
+    /// Import speeds and volumes observed per road and hour of the day, from traffic counters or
+    /// telematics. The input is a CSV file with columns `osm_way_id`, `hour` (0 to 23),
+    /// `speed_kmh`, and `volume`. Speed or volume may be blank. The result is stored alongside the
+    /// map.
+    ImportObservedTraffic {
+        /// The path to a CSV file
+        #[structopt(long)]
+        input: String,
+        /// The path to a map matching the data
+        #[structopt(long)]
+        map: String,
+        /// Describes where the data came from
+        #[structopt(long, default_value = "observed traffic")]
+        description: String,
+    },
     /// Transform a JSON map that's been manually edited into the binary format suitable for
     /// simulation.
     ImportJSONMap {
@@ -166,6 +185,13 @@ enum Command {
         /// Downgrade crosswalks not matching a `highway=crossing` OSM node into unmarked crossings.
         #[structopt(long)]
         filter_crosswalks: bool,
+        /// Merge pairs of one-way roads tagged as dual carriageways into a single two-way road.
+        #[structopt(long)]
+        merge_dual_carriageways: bool,
+        /// Simplify noisy road center-lines, dropping points within this many meters of the
+        /// simplified line.
+        #[structopt(long)]
+        smooth_centerlines: Option<f64>,
         /// Generate a simple travel demand model based on 2011 UK commuting data. This will only
         /// work if the boundary is in the UK.
         #[structopt(long)]
@@ -189,6 +215,13 @@ enum Command {
         /// Downgrade crosswalks not matching a `highway=crossing` OSM node into unmarked crossings.
         #[structopt(long)]
         filter_crosswalks: bool,
+        /// Merge pairs of one-way roads tagged as dual carriageways into a single two-way road.
+        #[structopt(long)]
+        merge_dual_carriageways: bool,
+        /// Simplify noisy road center-lines, dropping points within this many meters of the
+        /// simplified line.
+        #[structopt(long)]
+        smooth_centerlines: Option<f64>,
         /// Generate a simple travel demand model based on 2011 UK commuting data. This will only
         /// work if the boundary is in the UK.
         #[structopt(long)]
@@ -214,6 +247,20 @@ enum Command {
         #[structopt(flatten)]
         job: Job,
     },
+    /// Check if the OSM extract a city was imported from is older than the latest one from
+    /// Geofabrik. If so, download the new extract and update the city's RawMaps, patching them in
+    /// place when only buildings changed. Manual road merges from map_editor are preserved.
+    #[structopt(name = "refresh-osm")]
+    RefreshOSM {
+        #[structopt(long, parse(try_from_str = CityName::parse))]
+        city: CityName,
+        /// Only report how old the extract is
+        #[structopt(long)]
+        check_only: bool,
+        /// Only update one map. If not specified, update all maps in the city.
+        #[structopt()]
+        only_map: Option<String>,
+    },
     /// Simulate a full day of a scenario, and write the "prebaked results," so the UI can later be
     /// used for A/B testing.
     #[structopt(name = "prebake-scenario")]
@@ -222,6 +269,54 @@ enum Command {
         #[structopt()]
         scenario_path: String,
     },
+    /// Simulate every combination of proposals and parameters described by an experiment spec,
+    /// in parallel, and write a CSV file with KPIs from each run.
+    ///
+    /// The spec is a JSON file with a `scenario` path, optional `proposals` (paths to map edits),
+    /// `filter_candidates` (objects with a `road` ID and `pct_along`), `scenario_modifiers`,
+    /// `rng_seeds`, and `hours` to simulate.
+    RunExperiment {
+        /// The path to a JSON experiment spec
+        #[structopt(long)]
+        spec: String,
+        /// The path to write the CSV results
+        #[structopt(long)]
+        output: String,
+    },
+    /// Simulate a scenario many times with different RNG seeds, in parallel, and report KPIs as
+    /// means with 95% confidence intervals. Differences smaller than the intervals are likely
+    /// just noise from a single run.
+    MultiRun {
+        /// The path to a scenario file
+        #[structopt(long)]
+        scenario: String,
+        /// The path to map edits to apply. If omitted, simulate the unedited map.
+        #[structopt(long)]
+        edits: Option<String>,
+        /// How many runs to simulate
+        #[structopt(long, default_value = "10")]
+        runs: usize,
+        /// Runs use consecutive seeds starting from this one
+        #[structopt(long, default_value = "1")]
+        first_seed: u64,
+        /// How long to simulate. If omitted, run until a few hours after the end of the day.
+        #[structopt(long)]
+        hours: Option<f64>,
+        /// Where to write the results as JSON. Without edits, this defaults to where the UI
+        /// looks for multi-run results to compare against.
+        #[structopt(long)]
+        output: Option<String>,
+    },
+    /// Check that a map edits file applies cleanly to a map. Each problem found points at the
+    /// position of the offending command.
+    ValidateEdits {
+        /// The path to a map
+        #[structopt(long)]
+        map: String,
+        /// The path to a JSON map edits file
+        #[structopt(long)]
+        edits: String,
+    },
 }
 
 // See https://github.com/TeXitoi/structopt/issues/94
@@ -278,6 +373,11 @@ async fn main() -> Result<()> {
             map,
             skip_problems,
         } => import_scenario::run(input, map, skip_problems),
+        Command::ImportObservedTraffic {
+            input,
+            map,
+            description,
+        } => import_observed_traffic::run(input, map, description)?,
         Command::ImportJSONMap { input, output } => import_json_map(input, output),
         Command::MinifyMap { map } => minify_map(map),
         Command::GenerateHouses {
@@ -296,12 +396,16 @@ async fn main() -> Result<()> {
             use_osmium,
             inferred_sidewalks,
             filter_crosswalks,
+            merge_dual_carriageways,
+            smooth_centerlines,
             create_uk_travel_demand_model,
             opts,
         } => {
             let mut options = convert_osm::Options::default();
             options.map_config.inferred_sidewalks = inferred_sidewalks;
             options.filter_crosswalks = filter_crosswalks;
+            options.merge_dual_carriageways = merge_dual_carriageways;
+            options.smooth_centerlines = smooth_centerlines.map(Distance::meters);
             one_step_import::run(
                 geojson_path,
                 map_name,
@@ -318,12 +422,16 @@ async fn main() -> Result<()> {
             clip_path,
             inferred_sidewalks,
             filter_crosswalks,
+            merge_dual_carriageways,
+            smooth_centerlines,
             create_uk_travel_demand_model,
             opts,
         } => {
             let mut options = convert_osm::Options::default();
             options.map_config.inferred_sidewalks = inferred_sidewalks;
             options.filter_crosswalks = filter_crosswalks;
+            options.merge_dual_carriageways = merge_dual_carriageways;
+            options.smooth_centerlines = smooth_centerlines.map(Distance::meters);
             importer::oneshot(
                 osm_input,
                 clip_path,
@@ -339,7 +447,25 @@ async fn main() -> Result<()> {
         } => importer::regenerate_everything(shard_num, num_shards).await,
         Command::RegenerateEverythingExternally => regenerate_everything_externally()?,
         Command::Import { job } => job.run(&mut Timer::new("import one city")).await,
+        Command::RefreshOSM {
+            city,
+            check_only,
+            only_map,
+        } => {
+            importer::refresh_city(city, only_map, check_only, &mut Timer::new("refresh OSM"))
+                .await?
+        }
         Command::PrebakeScenario { scenario_path } => prebake_scenario(scenario_path),
+        Command::RunExperiment { spec, output } => run_experiment::run(spec, output)?,
+        Command::MultiRun {
+            scenario,
+            edits,
+            runs,
+            first_seed,
+            hours,
+            output,
+        } => multi_run::run(scenario, edits, runs, first_seed, hours, output)?,
+        Command::ValidateEdits { map, edits } => validate_edits(map, edits)?,
     }
     Ok(())
 }
@@ -445,3 +571,22 @@ fn prebake_scenario(path: String) {
     let map = map_model::Map::load_synchronously(scenario.map_name.path(), &mut timer);
     sim::prebake::prebake(&map, scenario, &mut timer);
 }
+
+fn validate_edits(map: String, edits: String) -> Result<()> {
+    let mut timer = Timer::new("validate edits");
+    let map = map_model::Map::load_synchronously(map, &mut timer);
+    let perma = map_model::PermanentMapEdits::load_from_file(&map, edits, &mut timer)?;
+    let problems = perma.validate(&map);
+    if problems.is_empty() {
+        println!("All {} commands are valid", perma.num_commands());
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    bail!(
+        "{} of {} commands are invalid",
+        problems.len(),
+        perma.num_commands()
+    );
+}
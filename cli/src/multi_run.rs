@@ -0,0 +1,83 @@
+//! Simulate a scenario many times with different RNG seeds, headless and in parallel, and report
+//! KPIs with confidence intervals.
+
+use anyhow::Result;
+
+use abstutil::Timer;
+use geom::Duration;
+use map_model::{Map, MapEdits};
+use sim::{Estimate, MultiRunResults};
+use synthpop::{Scenario, TripMode};
+
+pub fn run(
+    scenario_path: String,
+    edits_path: Option<String>,
+    runs: usize,
+    first_seed: u64,
+    hours: Option<f64>,
+    output: Option<String>,
+) -> Result<()> {
+    let mut timer = Timer::new("simulate many runs");
+    let scenario: Scenario = abstio::must_read_object(scenario_path, &mut timer);
+    let mut map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    if let Some(ref path) = edits_path {
+        let edits = MapEdits::load_from_file(&map, path.clone(), &mut timer)?;
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+
+    let seeds = (first_seed..first_seed + runs as u64).collect();
+    let results = MultiRunResults::simulate(
+        &map,
+        &scenario,
+        seeds,
+        hours.map(Duration::hours),
+        &mut timer,
+    );
+
+    println!(
+        "{} runs of {} on {} ({})",
+        results.runs.len(),
+        results.scenario_name,
+        results.map_name.describe(),
+        results.edits_name
+    );
+    print_estimate("Finished trips", results.finished_trips());
+    print_estimate("Cancelled trips", results.cancelled_trips());
+    print_estimate("Mean trip time (seconds)", results.mean_trip_duration());
+    for mode in TripMode::all() {
+        print_estimate(
+            &format!("Mean trip time {} (seconds)", mode.ongoing_verb()),
+            results.mean_trip_duration_for_mode(mode),
+        );
+    }
+
+    // Without edits or an explicit output, save the results for the UI to compare against
+    let output = output.or_else(|| {
+        if edits_path.is_none() {
+            Some(abstio::path_prebaked_multirun(
+                &results.map_name,
+                &results.scenario_name,
+            ))
+        } else {
+            None
+        }
+    });
+    if let Some(path) = output {
+        abstio::write_json(path.clone(), &results);
+        println!("Wrote {}", path);
+    }
+    Ok(())
+}
+
+fn print_estimate(label: &str, estimate: Option<Estimate>) {
+    if let Some(estimate) = estimate {
+        println!(
+            "- {}: {} (95% confidence interval {:.1} to {:.1})",
+            label,
+            estimate,
+            estimate.low(),
+            estimate.high()
+        );
+    }
+}
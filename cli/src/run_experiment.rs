@@ -0,0 +1,213 @@
+//! Run every combination of proposals and parameters from an experiment spec, headless and in
+//! parallel, then write one row of KPIs per run.
+
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::Deserialize;
+
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::{FilterType, Map, MapEdits, RoadFilter, RoadID};
+use sim::{AlertHandler, Sim, SimOptions};
+use synthpop::{Scenario, ScenarioModifier};
+
+#[derive(Deserialize)]
+struct ExperimentSpec {
+    /// The path to a scenario file. This determines the map too.
+    scenario: String,
+    /// Paths to MapEdits files. The baseline without any edits is always run too.
+    #[serde(default)]
+    proposals: Vec<String>,
+    /// Each run tries placing at most one of these modal filters, on top of the proposal. A run
+    /// without any extra filter always happens too.
+    #[serde(default)]
+    filter_candidates: Vec<FilterCandidate>,
+    /// Transform the scenario before every run
+    #[serde(default)]
+    scenario_modifiers: Vec<ScenarioModifier>,
+    /// Repeat every combination with each of these seeds
+    #[serde(default = "default_rng_seeds")]
+    rng_seeds: Vec<u64>,
+    /// How long to simulate. If omitted, run until a few hours after the end of the day.
+    #[serde(default)]
+    hours: Option<f64>,
+}
+
+#[derive(Deserialize, Clone)]
+struct FilterCandidate {
+    road: usize,
+    /// From 0 to 1, how far along the road to place the filter
+    #[serde(default = "default_pct_along")]
+    pct_along: f64,
+}
+
+fn default_rng_seeds() -> Vec<u64> {
+    vec![42]
+}
+
+fn default_pct_along() -> f64 {
+    0.5
+}
+
+/// One combination of parameters to simulate
+#[derive(Clone)]
+struct Run {
+    proposal: Option<String>,
+    filter: Option<FilterCandidate>,
+    rng_seed: u64,
+}
+
+struct RunResults {
+    finished_trips: usize,
+    cancelled_trips: usize,
+    total_trip_duration_seconds: f64,
+    agents_left: usize,
+}
+
+pub fn run(spec_path: String, output: String) -> Result<()> {
+    let mut timer = Timer::new("run experiment");
+    let spec: ExperimentSpec = abstio::maybe_read_json(spec_path, &mut timer)?;
+
+    let mut scenario: Scenario = abstio::must_read_object(spec.scenario.clone(), &mut timer);
+    let map = Map::load_synchronously(scenario.map_name.path(), &mut timer);
+    {
+        let mut rng = XorShiftRng::seed_from_u64(spec.rng_seeds.get(0).cloned().unwrap_or(42));
+        for m in &spec.scenario_modifiers {
+            scenario = m.apply(&map, scenario, &mut rng);
+        }
+    }
+
+    // Make sure all of the inputs are valid before spending time on simulation
+    for path in &spec.proposals {
+        MapEdits::load_from_file(&map, path.clone(), &mut timer)?;
+    }
+    for filter in &spec.filter_candidates {
+        if filter.road >= map.all_roads().len() {
+            bail!("Filter candidate on road {} doesn't exist", filter.road);
+        }
+        if !(0.0..=1.0).contains(&filter.pct_along) {
+            bail!("Filter candidate pct_along must be in [0, 1]");
+        }
+    }
+
+    let mut runs = Vec::new();
+    let proposals: Vec<Option<String>> = std::iter::once(None)
+        .chain(spec.proposals.iter().cloned().map(Some))
+        .collect();
+    let filters: Vec<Option<FilterCandidate>> = std::iter::once(None)
+        .chain(spec.filter_candidates.iter().cloned().map(Some))
+        .collect();
+    for proposal in &proposals {
+        for filter in &filters {
+            for rng_seed in &spec.rng_seeds {
+                runs.push(Run {
+                    proposal: proposal.clone(),
+                    filter: filter.clone(),
+                    rng_seed: *rng_seed,
+                });
+            }
+        }
+    }
+    info!("Running {} combinations", runs.len());
+
+    let duration = spec.hours.map(Duration::hours);
+    let map_ref = &map;
+    let scenario_ref = &scenario;
+    let results = timer.parallelize("run experiments", runs.clone(), |run| {
+        simulate(map_ref, scenario_ref, &run, duration)
+    });
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "proposal,filter_road,filter_pct_along,rng_seed,finished_trips,cancelled_trips,\
+         total_trip_duration_seconds,agents_left"
+    )?;
+    for (run, result) in runs.into_iter().zip(results) {
+        let result = match result {
+            Ok(result) => result,
+            Err(err) => {
+                error!("Run failed: {}", err);
+                continue;
+            }
+        };
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            run.proposal
+                .map(abstutil::basename)
+                .unwrap_or_else(|| "baseline".to_string()),
+            run.filter
+                .as_ref()
+                .map(|f| f.road.to_string())
+                .unwrap_or_default(),
+            run.filter
+                .as_ref()
+                .map(|f| f.pct_along.to_string())
+                .unwrap_or_default(),
+            run.rng_seed,
+            result.finished_trips,
+            result.cancelled_trips,
+            result.total_trip_duration_seconds,
+            result.agents_left
+        )?;
+    }
+    println!("Wrote {}", abstio::write_file(output, out)?);
+    Ok(())
+}
+
+fn simulate(
+    base_map: &Map,
+    scenario: &Scenario,
+    run: &Run,
+    duration: Option<Duration>,
+) -> Result<RunResults> {
+    let mut timer = Timer::throwaway();
+    let mut map = base_map.clone();
+
+    let mut edits = if let Some(ref path) = run.proposal {
+        MapEdits::load_from_file(&map, path.clone(), &mut timer)?
+    } else {
+        map.new_edits()
+    };
+    if let Some(ref filter) = run.filter {
+        let r = RoadID(filter.road);
+        let dist = filter.pct_along * map.get_r(r).length();
+        edits.commands.push(map.edit_road_cmd(r, |new| {
+            new.modal_filter = Some(RoadFilter::new(dist, FilterType::NoEntry));
+        }));
+    }
+    if !edits.commands.is_empty() {
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+
+    let mut opts = SimOptions::new("experiment");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(&map, opts);
+    let mut rng = XorShiftRng::seed_from_u64(run.rng_seed);
+    sim.instantiate(scenario, &map, &mut rng, &mut timer);
+    // By default, run until a few hours after the end of the day, like prebaking does
+    let duration =
+        duration.unwrap_or_else(|| sim.get_end_of_day() - Time::START_OF_DAY + Duration::hours(3));
+    sim.timed_step(&map, duration, &mut None, &mut timer);
+
+    let mut results = RunResults {
+        finished_trips: 0,
+        cancelled_trips: 0,
+        total_trip_duration_seconds: 0.0,
+        agents_left: sim.num_agents().sum(),
+    };
+    for (_, _, _, maybe_duration) in &sim.get_analytics().finished_trips {
+        if let Some(dt) = maybe_duration {
+            results.finished_trips += 1;
+            results.total_trip_duration_seconds += dt.inner_seconds();
+        } else {
+            results.cancelled_trips += 1;
+        }
+    }
+    Ok(results)
+}
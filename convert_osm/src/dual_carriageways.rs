@@ -0,0 +1,251 @@
+//! Dual carriageways are mapped in OSM as two parallel one-way roads. This is faithful, but it
+//! doubles the number of intersections along arterials and renders as two thin roads with a gap in
+//! between. This pass finds these pairs and optionally merges each into a single two-way road with
+//! a median.
+
+use std::collections::BTreeSet;
+
+use abstutil::{Tags, Timer};
+use geom::{Distance, PolyLine};
+use osm2streets::{
+    osm, BufferType, Direction, DrivingSide, IntersectionID, LaneSpec, LaneType, Road, RoadID,
+};
+use raw_map::{ExtraRoadData, RawMap};
+
+/// If the endpoints of two candidate carriageways are further apart than this, don't treat them
+/// as a pair.
+const MAX_ENDPOINT_DIST: Distance = Distance::const_meters(35.0);
+
+/// Find pairs of one-way roads that form a dual carriageway. The first road of each pair points
+/// the opposite direction of the second, and they start and end near each other.
+pub fn find_pairs(map: &RawMap) -> Vec<(RoadID, RoadID)> {
+    let mut pairs = Vec::new();
+    let mut used = BTreeSet::new();
+    for (id1, r1) in &map.streets.roads {
+        if used.contains(id1) {
+            continue;
+        }
+        let tags1 = match map.road_to_osm_tags(*id1) {
+            Some(tags) => tags,
+            None => continue,
+        };
+        if !tags1.is("oneway", "yes") {
+            continue;
+        }
+
+        for (id2, r2) in &map.streets.roads {
+            if id1 >= id2 || used.contains(id2) {
+                continue;
+            }
+            let tags2 = match map.road_to_osm_tags(*id2) {
+                Some(tags) => tags,
+                None => continue,
+            };
+            if !tags2.is("oneway", "yes")
+                || !(tags1.is("dual_carriageway", "yes") || tags2.is("dual_carriageway", "yes"))
+                || tags1.get(osm::HIGHWAY) != tags2.get(osm::HIGHWAY)
+                || tags1.get("name") != tags2.get("name")
+            {
+                continue;
+            }
+            // All four intersections must be distinct, or this is something like a loop
+            let endpoints: BTreeSet<IntersectionID> = vec![r1.src_i, r1.dst_i, r2.src_i, r2.dst_i]
+                .into_iter()
+                .collect();
+            if endpoints.len() != 4 {
+                continue;
+            }
+            if r1
+                .reference_line
+                .first_pt()
+                .dist_to(r2.reference_line.last_pt())
+                > MAX_ENDPOINT_DIST
+                || r1
+                    .reference_line
+                    .last_pt()
+                    .dist_to(r2.reference_line.first_pt())
+                    > MAX_ENDPOINT_DIST
+            {
+                continue;
+            }
+            if !r1
+                .reference_line
+                .overall_angle()
+                .approx_eq(r2.reference_line.overall_angle().opposite(), 30.0)
+            {
+                continue;
+            }
+
+            used.insert(*id1);
+            used.insert(*id2);
+            pairs.push((*id1, *id2));
+            break;
+        }
+    }
+    pairs
+}
+
+/// Merge every dual carriageway into one two-way road. The first road of each pair is kept and
+/// widened; roads connecting to the second are reattached to the first.
+pub fn merge(map: &mut RawMap, timer: &mut Timer) {
+    let pairs = find_pairs(map);
+    timer.start_iter("merge dual carriageways", pairs.len());
+    let mut merged = 0;
+    for (keep, remove) in pairs {
+        timer.next();
+        // An earlier merge may have touched one of these roads
+        if !map.streets.roads.contains_key(&keep) || !map.streets.roads.contains_key(&remove) {
+            continue;
+        }
+        merge_pair(map, keep, remove);
+        merged += 1;
+    }
+    info!("Merged {} dual carriageways", merged);
+}
+
+fn merge_pair(map: &mut RawMap, keep: RoadID, remove: RoadID) {
+    let driving_side = map.streets.config.driving_side;
+    let removed = map.streets.remove_road(remove);
+    map.extra_road_data.remove(&remove);
+
+    // The carriageways point in opposite directions, so the removed road's start matches the kept
+    // road's end.
+    let (keep_src, keep_dst) = {
+        let r = &map.streets.roads[&keep];
+        (r.src_i, r.dst_i)
+    };
+    let replace = |i: IntersectionID| {
+        if i == removed.dst_i {
+            keep_src
+        } else if i == removed.src_i {
+            keep_dst
+        } else {
+            i
+        }
+    };
+
+    // Reattach everything else touching the removed carriageway
+    let mut reattach = BTreeSet::new();
+    for i in [removed.src_i, removed.dst_i] {
+        reattach.extend(map.streets.intersections[&i].roads.iter().cloned());
+    }
+    for r in reattach {
+        let old = map.streets.remove_road(r);
+        let extra = map
+            .extra_road_data
+            .remove(&r)
+            .unwrap_or_else(ExtraRoadData::default);
+        let src_i = replace(old.src_i);
+        let dst_i = replace(old.dst_i);
+        // Short links between the two carriageways would become loops
+        if src_i == dst_i {
+            continue;
+        }
+
+        let mut pts = old.reference_line.clone().into_points();
+        pts[0] = map.streets.intersections[&src_i].polygon.center();
+        *pts.last_mut().unwrap() = map.streets.intersections[&dst_i].polygon.center();
+        let reference_line = match PolyLine::deduping_new(pts) {
+            Ok(pl) => pl,
+            Err(err) => {
+                warn!("Dropping {} while merging dual carriageways: {}", r, err);
+                continue;
+            }
+        };
+        let tags = old
+            .osm_ids
+            .get(0)
+            .and_then(|id| map.osm_tags.get(id))
+            .cloned()
+            .unwrap_or_else(Tags::empty);
+
+        let id = map.streets.next_road_id();
+        let mut road = Road::new(
+            id,
+            old.osm_ids.clone(),
+            src_i,
+            dst_i,
+            reference_line,
+            tags,
+            &map.streets.config,
+        );
+        road.lane_specs_ltr = old.lane_specs_ltr.clone();
+        road.update_center_line(driving_side);
+        map.streets.insert_road(road);
+        map.extra_road_data.insert(id, extra);
+    }
+
+    for i in [removed.src_i, removed.dst_i] {
+        if map.streets.intersections[&i].roads.is_empty() {
+            map.streets.remove_intersection(i);
+            map.elevation_per_intersection.remove(&i);
+        }
+    }
+
+    // Combine the lanes, looking along the kept road. The other carriageway is on the left side
+    // when driving on the right.
+    let highway = map
+        .road_to_osm_tags(keep)
+        .and_then(|tags| tags.get(osm::HIGHWAY))
+        .cloned()
+        .unwrap_or_else(|| "primary".to_string());
+    let median_lt = LaneType::Buffer(BufferType::Verge);
+    let median = LaneSpec {
+        lt: median_lt,
+        dir: Direction::Fwd,
+        width: LaneSpec::typical_lane_widths(median_lt, &highway)[0].0,
+        allowed_turns: Default::default(),
+    };
+    let mut other_side: Vec<LaneSpec> = removed
+        .lane_specs_ltr
+        .into_iter()
+        .rev()
+        .map(|mut spec| {
+            spec.dir = spec.dir.opposite();
+            spec
+        })
+        .collect();
+
+    let road = map.streets.roads.get_mut(&keep).unwrap();
+    let mut this_side = std::mem::take(&mut road.lane_specs_ltr);
+    // Sidewalks along the median aren't useful anymore
+    let inner_walkway =
+        |spec: &LaneSpec| matches!(spec.lt, LaneType::Sidewalk | LaneType::Shoulder);
+    let mut lanes = Vec::new();
+    if driving_side == DrivingSide::Right {
+        while other_side.last().map(inner_walkway).unwrap_or(false) {
+            other_side.pop();
+        }
+        while this_side.first().map(inner_walkway).unwrap_or(false) {
+            this_side.remove(0);
+        }
+        lanes.extend(other_side);
+        lanes.push(median);
+        lanes.extend(this_side);
+    } else {
+        while this_side.last().map(inner_walkway).unwrap_or(false) {
+            this_side.pop();
+        }
+        while other_side.first().map(inner_walkway).unwrap_or(false) {
+            other_side.remove(0);
+        }
+        lanes.extend(this_side);
+        lanes.push(median);
+        lanes.extend(other_side);
+    }
+    road.lane_specs_ltr = lanes;
+    road.update_center_line(driving_side);
+    // Keep the other carriageway's ways, so relations like bus routes running only one way are
+    // still found
+    for id in removed.osm_ids {
+        if !road.osm_ids.contains(&id) {
+            road.osm_ids.push(id);
+        }
+    }
+
+    map.streets.update_i(keep_src);
+    map.streets.update_i(keep_dst);
+    map.extra_road_data
+        .entry(keep)
+        .or_insert_with(ExtraRoadData::default);
+}
@@ -1,14 +1,16 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use abstutil::{MultiMap, Tags, Timer};
+use abstutil::{Tags, Timer};
 use geom::{Distance, FindClosest, GPSBounds, HashablePt2D, LonLat, Polygon, Pt2D, Ring};
 use osm2streets::osm::{OsmID, RelationID, WayID};
 use osm2streets::{osm, NamePerLanguage};
 use raw_map::{
-    Amenity, AreaType, CrossingType, ExtraPOI, ExtraPOIType, RawArea, RawBuilding, RawMap,
-    RawParkingLot,
+    Amenity, AreaType, CrossingType, ExtraPOI, ExtraPOIType, KerbType, RawArea, RawBuilding,
+    RawMap, RawParkingLot, RawRelation,
 };
 
+use crate::plazas::{is_plaza, Plaza};
+use crate::sidewalks::{infer_sidewalks, SidewalkSource};
 use crate::Options;
 use streets_reader::osm_reader::glue_multipolygon;
 use streets_reader::OsmExtract;
@@ -16,12 +18,17 @@ use streets_reader::OsmExtract;
 pub struct Extract {
     pub osm: OsmExtract,
     pub doc: streets_reader::osm_reader::Document,
-    pub bus_routes_on_roads: MultiMap<WayID, String>,
     /// Crossings located at these points, which should be on a Road's center line
     pub crossing_nodes: HashSet<(HashablePt2D, CrossingType)>,
+    /// Kerbs described at these points, which should be on a Road's center line
+    pub kerb_nodes: Vec<(HashablePt2D, KerbType)>,
     /// Some kind of barrier nodes at these points.
     pub barrier_nodes: Vec<(osm::NodeID, HashablePt2D)>,
     pub extra_pois: Vec<ExtraPOI>,
+    /// Where the sidewalks on each way came from
+    pub sidewalk_sources: HashMap<WayID, SidewalkSource>,
+    /// Areas people can walk across in any direction
+    pub plazas: Vec<Plaza>,
 }
 
 pub fn extract_osm(
@@ -57,10 +64,12 @@ pub fn extract_osm(
 
     let mut out = OsmExtract::new();
     let mut amenity_points = Vec::new();
-    let mut bus_routes_on_roads: MultiMap<WayID, String> = MultiMap::new();
+    let mut bike_parking_points = Vec::new();
     let mut crossing_nodes = HashSet::new();
+    let mut kerb_nodes = Vec::new();
     let mut barrier_nodes = Vec::new();
     let mut extra_pois = Vec::new();
+    let mut plazas = Vec::new();
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
     for (id, node) in &doc.nodes {
@@ -69,6 +78,9 @@ pub fn extract_osm(
         for amenity in get_bldg_amenities(&node.tags) {
             amenity_points.push((node.pt, amenity));
         }
+        if node.tags.is("amenity", "bicycle_parking") {
+            bike_parking_points.push((node.pt, bike_parking_capacity(&node.tags)));
+        }
         if node.tags.is(osm::HIGHWAY, "crossing") {
             // TODO Look for crossing:signals:* too.
             // https://wiki.openstreetmap.org/wiki/Tag:crossing=traffic%20signals?uselang=en
@@ -79,6 +91,11 @@ pub fn extract_osm(
             };
             crossing_nodes.insert((node.pt.to_hashable(), kind));
         }
+        if node.tags.is(osm::HIGHWAY, "crossing") || node.tags.is("barrier", "kerb") {
+            if let Some(kerb) = KerbType::from_tags(&node.tags) {
+                kerb_nodes.push((node.pt.to_hashable(), kerb));
+            }
+        }
         // TODO Any kind of barrier?
         if node.tags.is("barrier", "bollard") {
             barrier_nodes.push((*id, node.pt.to_hashable()));
@@ -105,6 +122,12 @@ pub fn extract_osm(
         }
     }
 
+    let sidewalk_sources = infer_sidewalks(
+        &mut doc,
+        &opts.sidewalk_inference,
+        opts.map_config.inferred_sidewalks,
+    );
+
     let mut coastline_groups: Vec<(WayID, Vec<Pt2D>)> = Vec::new();
     let mut memorial_areas: Vec<Polygon> = Vec::new();
     let mut amenity_areas: Vec<(Polygon, Amenity)> = Vec::new();
@@ -132,6 +155,15 @@ pub fn extract_osm(
             continue;
         };
 
+        // Plazas might be parks or have amenities too
+        if is_plaza(&way.tags) {
+            plazas.push(Plaza {
+                osm_id: OsmID::Way(id),
+                polygon: polygon.clone(),
+                tags: way.tags.clone(),
+            });
+        }
+
         if is_bldg(&way.tags) {
             map.buildings.insert(
                 OsmID::Way(id),
@@ -139,6 +171,7 @@ pub fn extract_osm(
                     polygon,
                     public_garage_name: None,
                     num_parking_spots: 0,
+                    bike_parking: 0,
                     amenities: get_bldg_amenities(&way.tags),
                     osm_tags: way.tags.clone(),
                 },
@@ -156,6 +189,8 @@ pub fn extract_osm(
                 polygon,
                 osm_tags: way.tags.clone(),
             });
+        } else if way.tags.is("amenity", "bicycle_parking") {
+            bike_parking_points.push((polygon.center(), bike_parking_capacity(&way.tags)));
         } else if way.tags.is("historic", "memorial") {
             memorial_areas.push(polygon);
         } else if way.tags.contains_key("amenity") {
@@ -175,6 +210,35 @@ pub fn extract_osm(
         timer.next();
         let id = *id;
 
+        // Remember routes and boundaries that roads belong to. Roads haven't been split from ways
+        // yet, so just record the ways.
+        if let Some(relation) = RawRelation::from_tags(id, &rel.tags) {
+            let mut any = false;
+            for (role, member) in &rel.members {
+                if let OsmID::Way(w) = member {
+                    if relation.includes_role(role) {
+                        map.relation_members.insert(*w, id);
+                        any = true;
+                    }
+                }
+            }
+            if any {
+                map.relations.insert(id, relation);
+            }
+        }
+
+        if is_plaza(&rel.tags) && rel.tags.is("type", "multipolygon") {
+            for polygon in
+                glue_multipolygon(id, doc.get_multipolygon_members(id, rel), Some(&boundary))
+            {
+                plazas.push(Plaza {
+                    osm_id: OsmID::Relation(id),
+                    polygon,
+                    tags: rel.tags.clone(),
+                });
+            }
+        }
+
         if out.handle_relation(id, rel) {
             continue;
         } else if let Some(area_type) = get_area_type(&rel.tags) {
@@ -200,6 +264,7 @@ pub fn extract_osm(
                                 polygon,
                                 public_garage_name: None,
                                 num_parking_spots: 0,
+                                bike_parking: 0,
                                 amenities: get_bldg_amenities(&rel.tags),
                                 osm_tags: rel.tags.clone(),
                             },
@@ -238,16 +303,6 @@ pub fn extract_osm(
                     }
                 }
             }
-        } else if rel.tags.is("type", "route") && rel.tags.is("route", "bus") {
-            if let Some(name) = rel.tags.get("name") {
-                for (role, member) in &rel.members {
-                    if let OsmID::Way(w) = member {
-                        if role.is_empty() {
-                            bus_routes_on_roads.insert(*w, name.to_string());
-                        }
-                    }
-                }
-            }
         }
     }
 
@@ -291,6 +346,15 @@ pub fn extract_osm(
         }
     }
 
+    // Bike racks are usually mapped outside, next to the building they serve
+    timer.start_iter("match bike parking to buildings", bike_parking_points.len());
+    for (pt, capacity) in bike_parking_points {
+        timer.next();
+        if let Some((id, _)) = closest_bldg.closest_pt(pt, Distance::meters(50.0)) {
+            map.buildings.get_mut(&id).unwrap().bike_parking += capacity;
+        }
+    }
+
     timer.start_iter("match buildings to amenity areas", amenity_areas.len());
     for (poly, amenity) in amenity_areas {
         timer.next();
@@ -321,19 +385,21 @@ pub fn extract_osm(
     Extract {
         osm: out,
         doc,
-        bus_routes_on_roads,
         crossing_nodes,
+        kerb_nodes,
         barrier_nodes,
         extra_pois,
+        sidewalk_sources,
+        plazas,
     }
 }
 
-fn is_bldg(tags: &Tags) -> bool {
+pub(crate) fn is_bldg(tags: &Tags) -> bool {
     // Sorry, the towers at Gasworks don't count. :)
     tags.contains_key("building") && !tags.contains_key("abandoned:man_made")
 }
 
-fn get_bldg_amenities(tags: &Tags) -> Vec<Amenity> {
+pub(crate) fn get_bldg_amenities(tags: &Tags) -> Vec<Amenity> {
     let mut amenities = Vec::new();
     for key in ["amenity", "shop", "craft", "office", "tourism", "leisure"] {
         if let Some(amenity) = tags.get(key) {
@@ -347,7 +413,7 @@ fn get_bldg_amenities(tags: &Tags) -> Vec<Amenity> {
     amenities
 }
 
-fn get_area_type(tags: &Tags) -> Option<AreaType> {
+pub(crate) fn get_area_type(tags: &Tags) -> Option<AreaType> {
     if tags.is_any("leisure", vec!["garden", "park", "golf_course"]) {
         return Some(AreaType::Park);
     }
@@ -381,6 +447,13 @@ fn get_area_type(tags: &Tags) -> Option<AreaType> {
     None
 }
 
+// Racks without a capacity tag are usually a single stand, which fits two bikes
+fn bike_parking_capacity(tags: &Tags) -> usize {
+    tags.get("capacity")
+        .and_then(|x| x.parse::<usize>().ok())
+        .unwrap_or(2)
+}
+
 // Look for any service roads that collide with parking lots, and treat them as parking aisles
 // instead.
 fn find_parking_aisles(map: &mut RawMap, roads: &mut Vec<(WayID, Vec<Pt2D>, Tags)>) {
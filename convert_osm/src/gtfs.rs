@@ -5,7 +5,7 @@ use fs_err::File;
 use serde::Deserialize;
 
 use abstutil::MultiMap;
-use geom::{LonLat, PolyLine, Pt2D};
+use geom::{LonLat, PolyLine, Pt2D, Time};
 use kml::{ExtraShape, ExtraShapes};
 use raw_map::{RawMap, RawTransitRoute, RawTransitStop, RawTransitType};
 
@@ -34,6 +34,7 @@ pub fn import(map: &mut RawMap) -> Result<()> {
             shape: PolyLine::dummy(),
             stops: Vec::new(),
             route_type,
+            spawn_times: Vec::new(),
         });
     }
 
@@ -41,11 +42,14 @@ pub fn import(map: &mut RawMap) -> Result<()> {
     let mut route_to_shapes = MultiMap::new();
     // Map (route_id, shape_id) to trip_id
     let mut route_and_shape_to_trips = MultiMap::new();
+    // Which days each trip runs on
+    let mut trip_to_service: HashMap<TripID, ServiceID> = HashMap::new();
     for rec in csv::Reader::from_reader(File::open(map.name.city.input_path("gtfs/trips.txt"))?)
         .deserialize()
     {
         let rec: Trip = rec?;
         route_to_shapes.insert(rec.route_id.clone(), rec.shape_id.clone());
+        trip_to_service.insert(rec.trip_id.clone(), rec.service_id);
         route_and_shape_to_trips.insert((rec.route_id, rec.shape_id), rec.trip_id);
     }
 
@@ -105,39 +109,66 @@ pub fn import(map: &mut RawMap) -> Result<()> {
     }
     map.transit_routes = transit_routes;
 
-    // For now, every route uses exactly one trip ID, and there's no schedule. Just pick an
-    // arbitrary trip per route.
-    let mut route_to_trip = HashMap::new();
+    // Every route uses the stops of one arbitrary trip. The schedule comes from all trips with the
+    // same shape, but only on one day -- whichever service runs the most trips, usually a
+    // weekday.
+    let mut route_to_trips: HashMap<RouteID, Vec<&TripID>> = HashMap::new();
     for (route_id, shape_id) in &route_to_shape {
         let trips = route_and_shape_to_trips.get((route_id.clone(), shape_id.clone()));
-        if let Some(trip_id) = trips.into_iter().next() {
-            route_to_trip.insert(route_id.clone(), trip_id);
+        let mut trips_per_service: BTreeMap<&ServiceID, Vec<&TripID>> = BTreeMap::new();
+        for trip_id in trips {
+            trips_per_service
+                .entry(&trip_to_service[trip_id])
+                .or_insert_with(Vec::new)
+                .push(trip_id);
+        }
+        if let Some(trips) = trips_per_service
+            .into_values()
+            .max_by_key(|trips| trips.len())
+        {
+            route_to_trips.insert(route_id.clone(), trips);
         }
     }
 
-    // Scrape the trip ID -> (stop ID, sequence number)
-    let mut trip_to_stops: HashMap<TripID, Vec<(StopID, usize)>> = HashMap::new();
+    // Scrape the trip ID -> (stop ID, sequence number, departure time)
+    let mut trip_to_stops: HashMap<TripID, Vec<(StopID, usize, Option<Time>)>> = HashMap::new();
     for rec in
         csv::Reader::from_reader(File::open(map.name.city.input_path("gtfs/stop_times.txt"))?)
             .deserialize()
     {
         let rec: StopTime = rec?;
+        // Only stops designated as timepoints are required to have a time
+        let departure_time = Time::parse(&rec.departure_time).ok();
         trip_to_stops
             .entry(rec.trip_id)
             .or_insert_with(Vec::new)
-            .push((rec.stop_id, rec.stop_sequence));
+            .push((rec.stop_id, rec.stop_sequence, departure_time));
     }
 
-    // Assign the stops for every route
+    // Assign the stops and schedule for every route
     let mut stop_ids = HashSet::new();
     for route in &mut map.transit_routes {
-        let trip_id = route_to_trip[&RouteID(route.gtfs_id.clone())];
-        let mut stops = trip_to_stops.remove(&trip_id).unwrap_or_else(Vec::new);
-        stops.sort_by_key(|(_, seq)| *seq);
-        for (stop_id, _) in stops {
-            route.stops.push(stop_id.0.clone());
-            stop_ids.insert(stop_id);
+        let trips = match route_to_trips.get(&RouteID(route.gtfs_id.clone())) {
+            Some(trips) => trips,
+            None => {
+                continue;
+            }
+        };
+        for (idx, trip_id) in trips.iter().enumerate() {
+            let mut stops = trip_to_stops.remove(*trip_id).unwrap_or_else(Vec::new);
+            stops.sort_by_key(|(_, seq, _)| *seq);
+            if let Some((_, _, Some(time))) = stops.get(0) {
+                route.spawn_times.push(*time);
+            }
+            if idx == 0 {
+                for (stop_id, _, _) in stops {
+                    route.stops.push(stop_id.0.clone());
+                    stop_ids.insert(stop_id);
+                }
+            }
         }
+        route.spawn_times.sort();
+        route.spawn_times.dedup();
     }
 
     // Scrape stop metadata
@@ -187,6 +218,8 @@ struct TripID(String);
 struct StopID(String);
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
 struct RouteID(String);
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+struct ServiceID(String);
 
 #[derive(Deserialize)]
 struct Route {
@@ -202,6 +235,7 @@ struct Route {
 #[derive(Deserialize)]
 struct Trip {
     route_id: RouteID,
+    service_id: ServiceID,
     shape_id: ShapeID,
     trip_id: TripID,
 }
@@ -227,6 +261,8 @@ struct StopTime {
     trip_id: TripID,
     stop_id: StopID,
     stop_sequence: usize,
+    #[serde(default)]
+    departure_time: String,
 }
 
 fn dump_kml(map: &RawMap) {
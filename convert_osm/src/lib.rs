@@ -11,12 +11,21 @@ use abstio::MapName;
 use abstutil::{Tags, Timer};
 use geom::{Distance, HashablePt2D, LonLat, PolyLine, Polygon};
 use osm2streets::{osm, MapConfig, Road, RoadID};
-use raw_map::{CrossingType, ExtraRoadData, RawMap};
+use raw_map::{CrossingType, ExtraRoadData, KerbType, RawMap};
 
+pub use self::plazas::PLAZA_TAG;
+pub use self::sidewalks::{SidewalkInference, SIDEWALK_SOURCE_TAG};
+pub use self::update::{update, Update};
+
+mod dual_carriageways;
 mod elevation;
 mod extract;
 mod gtfs;
 mod parking;
+mod plazas;
+mod sidewalks;
+mod update;
+mod z_levels;
 
 /// Configures the creation of a `RawMap` from OSM and other input data.
 pub struct Options {
@@ -25,6 +34,12 @@ pub struct Options {
     pub onstreet_parking: OnstreetParking,
     pub public_offstreet_parking: PublicOffstreetParking,
     pub private_offstreet_parking: PrivateOffstreetParking,
+    /// If provided, read the observed capacity and occupancy of on-street parking from this CSV
+    /// file. Each row has `osm_way_id`, `side` (`left`, `right`, or `both`), and optionally
+    /// `capacity` and `occupancy` (from 0 to 1). This overrides any capacity tagged in OSM.
+    pub parking_survey: Option<String>,
+    /// How to guess sidewalks on roads that don't say
+    pub sidewalk_inference: SidewalkInference,
     /// If provided, read polygons from this GeoJSON file and add them to the RawMap as buildings.
     pub extra_buildings: Option<String>,
     /// Configure public transit using this URL to a static GTFS feed in .zip format.
@@ -32,6 +47,12 @@ pub struct Options {
     pub elevation: bool,
     /// Only include crosswalks that match a `highway=crossing` OSM node.
     pub filter_crosswalks: bool,
+    /// Merge pairs of one-way roads tagged as dual carriageways into a single two-way road with a
+    /// median.
+    pub merge_dual_carriageways: bool,
+    /// Simplify road center-lines, removing points that stray less than this distance from the
+    /// simplified line, and zig-zags near intersections. Sharp corners are kept.
+    pub smooth_centerlines: Option<Distance>,
 }
 
 impl Options {
@@ -41,10 +62,14 @@ impl Options {
             onstreet_parking: OnstreetParking::JustOSM,
             public_offstreet_parking: PublicOffstreetParking::None,
             private_offstreet_parking: PrivateOffstreetParking::FixedPerBldg(1),
+            parking_survey: None,
+            sidewalk_inference: SidewalkInference::default(),
             extra_buildings: None,
             gtfs_url: None,
             elevation: false,
             filter_crosswalks: false,
+            merge_dual_carriageways: false,
+            smooth_centerlines: None,
         }
     }
 }
@@ -99,12 +124,17 @@ pub fn convert(
 
     // Cul-de-sacs aren't supported yet.
     map.streets.retain_roads(|r| r.src_i != r.dst_i);
+    sidewalks::report_sidewalk_sources(&map, &extract.sidewalk_sources);
 
-    map.bus_routes_on_roads = extract.bus_routes_on_roads;
     map.extra_pois = extract.extra_pois;
 
     clip_map(&mut map, timer);
 
+    let fixes = raw_map::repair::repair_geometry(&mut map, timer);
+    for fix in &fixes {
+        warn!("Repaired geometry of {}", fix);
+    }
+
     for i in map.streets.intersections.keys() {
         map.elevation_per_intersection.insert(*i, Distance::ZERO);
     }
@@ -132,12 +162,26 @@ pub fn convert(
     timer.start("use barrier and crossing nodes");
     use_barrier_nodes(&mut map, extract.barrier_nodes, &pt_to_road);
     use_crossing_nodes(&mut map, &extract.crossing_nodes, &pt_to_road);
+    use_kerb_nodes(&mut map, extract.kerb_nodes, &pt_to_road);
     timer.stop("use barrier and crossing nodes");
 
     if opts.filter_crosswalks {
         filter_crosswalks(&mut map, extract.crossing_nodes, pt_to_road, timer);
     }
 
+    z_levels::split_crossings(&mut map, timer);
+
+    plazas::connect_plazas(&mut map, extract.plazas, timer);
+
+    if opts.merge_dual_carriageways {
+        dual_carriageways::merge(&mut map, timer);
+    }
+
+    if let Some(tolerance) = opts.smooth_centerlines {
+        let removed = raw_map::transform::smooth_centerlines(&mut map, tolerance, timer);
+        info!("Smoothing road center-lines removed {} points", removed);
+    }
+
     if opts.elevation {
         timer.start("add elevation data");
         if let Err(err) = elevation::add_data(&mut map) {
@@ -163,11 +207,27 @@ pub fn convert(
         bristol_hack(&mut map);
     }
 
+    report_quality(&map, timer);
+
     timer.stop("create RawMap from input data");
 
     map
 }
 
+/// Log a summary of suspicious data. map_editor and the game's debug mode show the full list.
+fn report_quality(map: &RawMap, timer: &mut Timer) {
+    let issues = raw_map::quality::find_quality_issues(map, timer);
+    for kind in raw_map::quality::IssueKind::all() {
+        let count = issues.iter().filter(|issue| issue.kind == kind).count();
+        if count > 0 {
+            warn!("Data quality: {} {}", count, kind.plural());
+        }
+    }
+    for issue in &issues {
+        debug!("{}", issue);
+    }
+}
+
 fn add_extra_buildings(map: &mut RawMap, path: &str) -> Result<()> {
     let require_in_bounds = true;
     let mut id = -1;
@@ -184,6 +244,7 @@ fn add_extra_buildings(map: &mut RawMap, path: &str) -> Result<()> {
                 osm_tags: Tags::empty(),
                 public_garage_name: None,
                 num_parking_spots: 1,
+                bike_parking: 0,
                 amenities: Vec::new(),
             },
         );
@@ -346,6 +407,21 @@ fn use_crossing_nodes(
     }
 }
 
+fn use_kerb_nodes(
+    map: &mut RawMap,
+    kerb_nodes: Vec<(HashablePt2D, KerbType)>,
+    pt_to_road: &HashMap<HashablePt2D, RoadID>,
+) {
+    for (pt, kerb) in kerb_nodes {
+        if let Some(road) = pt_to_road
+            .get(&pt)
+            .and_then(|r| map.extra_road_data.get_mut(r))
+        {
+            road.kerb_nodes.push((pt.to_pt2d(), kerb));
+        }
+    }
+}
+
 fn filter_crosswalks(
     map: &mut RawMap,
     crosswalks: HashSet<(HashablePt2D, CrossingType)>,
@@ -1,8 +1,14 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use fs_err::File;
+use serde::Deserialize;
+
 use abstutil::{Tags, Timer};
 use geom::{Distance, FindClosest, PolyLine};
 use kml::ExtraShapes;
 use osm2streets::{osm, RoadID};
-use raw_map::RawMap;
+use raw_map::{RawBuilding, RawMap};
 
 use crate::{OnstreetParking, Options, PrivateOffstreetParking, PublicOffstreetParking};
 
@@ -23,6 +29,16 @@ pub fn apply_parking(map: &mut RawMap, opts: &Options, timer: &mut Timer) {
         }
     }
     apply_private_offstreet_parking(map, &opts.private_offstreet_parking);
+
+    timer.start("use observed street parking");
+    let way_to_roads = roads_per_way(map);
+    use_street_parking_tags(map, &way_to_roads);
+    if let Some(ref path) = opts.parking_survey {
+        if let Err(err) = use_parking_survey(map, &way_to_roads, path) {
+            error!("Couldn't use parking survey {}: {}", path, err);
+        }
+    }
+    timer.stop("use observed street parking");
 }
 
 fn unknown_parking(tags: &Tags) -> bool {
@@ -195,30 +211,169 @@ fn use_offstreet_parking(map: &mut RawMap, path: String, timer: &mut Timer) {
 }
 
 fn apply_private_offstreet_parking(map: &mut RawMap, policy: &PrivateOffstreetParking) {
+    for b in map.buildings.values_mut() {
+        if b.public_garage_name.is_none() {
+            assert_eq!(b.num_parking_spots, 0);
+            private_offstreet_parking(b, policy);
+        }
+    }
+}
+
+/// Set the number of private parking spots for one building.
+pub(crate) fn private_offstreet_parking(b: &mut RawBuilding, policy: &PrivateOffstreetParking) {
     match policy {
         PrivateOffstreetParking::FixedPerBldg(n) => {
-            for b in map.buildings.values_mut() {
-                if b.public_garage_name.is_none() {
-                    assert_eq!(b.num_parking_spots, 0);
-
-                    // Is it a parking garage?
-                    if b.osm_tags.is("building", "parking") || b.osm_tags.is("amenity", "parking") {
-                        let levels = b
-                            .osm_tags
-                            .get("parking:levels")
-                            .or_else(|| b.osm_tags.get("building:levels"))
-                            .and_then(|x| x.parse::<usize>().ok())
-                            .unwrap_or(1);
-                        // For multi-story garages, assume every floor has the same capacity. Guess
-                        // 1 spot per 30m^2.
-                        b.num_parking_spots = ((b.polygon.area() / 30.0) as usize) * levels;
-                        // Not useful to list this
-                        b.amenities.retain(|a| a.amenity_type != "parking");
-                    } else {
-                        b.num_parking_spots = *n;
-                    }
+            // Is it a parking garage?
+            if b.osm_tags.is("building", "parking") || b.osm_tags.is("amenity", "parking") {
+                let levels = b
+                    .osm_tags
+                    .get("parking:levels")
+                    .or_else(|| b.osm_tags.get("building:levels"))
+                    .and_then(|x| x.parse::<usize>().ok())
+                    .unwrap_or(1);
+                // For multi-story garages, assume every floor has the same capacity. Guess 1 spot
+                // per 30m^2.
+                b.num_parking_spots = ((b.polygon.area() / 30.0) as usize) * levels;
+                // Not useful to list this
+                b.amenities.retain(|a| a.amenity_type != "parking");
+            } else {
+                b.num_parking_spots = *n;
+            }
+        }
+    }
+}
+
+/// Look for capacity tagged in OSM, using both the older `parking:lane:*:capacity` and newer
+/// `parking:*:capacity` schemes.
+fn use_street_parking_tags(map: &mut RawMap, way_to_roads: &WayToRoads) {
+    let mut per_way: BTreeMap<osm::WayID, (Option<usize>, Option<usize>)> = BTreeMap::new();
+    for (id, tags) in &map.osm_tags {
+        let capacity = |side: &str| {
+            for key in [
+                format!("parking:lane:{}:capacity", side),
+                format!("parking:{}:capacity", side),
+                "parking:lane:both:capacity".to_string(),
+                "parking:both:capacity".to_string(),
+            ] {
+                if let Some(n) = tags.get(&key).and_then(|x| x.parse::<usize>().ok()) {
+                    return Some(n);
+                }
+            }
+            None
+        };
+        let (left, right) = (capacity("left"), capacity("right"));
+        if left.is_some() || right.is_some() {
+            per_way.insert(*id, (left, right));
+        }
+    }
+
+    for (way, (left, right)) in per_way {
+        for (r, fraction) in way_to_roads.get(&way).into_iter().flatten() {
+            let fraction = *fraction;
+            let extra = map.extra_road_data.get_mut(r).unwrap();
+            if let Some(n) = left {
+                extra.parking_left.capacity = Some(split_capacity(n, fraction));
+            }
+            if let Some(n) = right {
+                extra.parking_right.capacity = Some(split_capacity(n, fraction));
+            }
+        }
+    }
+}
+
+fn use_parking_survey(map: &mut RawMap, way_to_roads: &WayToRoads, path: &str) -> Result<()> {
+    let mut matched = 0;
+    let mut unmatched = 0;
+    for rec in csv::Reader::from_reader(File::open(path)?).deserialize() {
+        let rec: SurveyRecord = rec?;
+        if let Some(occupancy) = rec.occupancy {
+            if !(0.0..=1.0).contains(&occupancy) {
+                bail!(
+                    "Occupancy {} for way {} isn't between 0 and 1",
+                    occupancy,
+                    rec.osm_way_id
+                );
+            }
+        }
+        let (left, right) = match rec.side.as_ref() {
+            "left" => (true, false),
+            "right" => (false, true),
+            "both" => (true, true),
+            x => bail!("Unknown side {} for way {}", x, rec.osm_way_id),
+        };
+
+        let roads = if let Some(roads) = way_to_roads.get(&osm::WayID(rec.osm_way_id)) {
+            roads
+        } else {
+            unmatched += 1;
+            continue;
+        };
+        matched += 1;
+        for (r, fraction) in roads {
+            let fraction = *fraction;
+            let extra = map.extra_road_data.get_mut(r).unwrap();
+            for (apply, parking) in [
+                (left, &mut extra.parking_left),
+                (right, &mut extra.parking_right),
+            ] {
+                if !apply {
+                    continue;
+                }
+                // The survey is more recent and detailed than OSM
+                if let Some(n) = rec.capacity {
+                    parking.capacity = Some(split_capacity(n, fraction));
+                }
+                if rec.occupancy.is_some() {
+                    parking.occupancy = rec.occupancy;
                 }
             }
         }
     }
+    info!(
+        "Matched {} parking survey records to roads. {} are outside the map or on ways that \
+         weren't imported",
+        matched, unmatched
+    );
+    Ok(())
+}
+
+type WayToRoads = BTreeMap<osm::WayID, Vec<(RoadID, f64)>>;
+
+/// One way may be split into many roads. Returns each piece with the fraction of the way's length
+/// it covers, so that counts for the whole way can be divided up.
+fn roads_per_way(map: &RawMap) -> WayToRoads {
+    let mut lengths: BTreeMap<osm::WayID, Vec<(RoadID, Distance)>> = BTreeMap::new();
+    for r in map.streets.roads.values() {
+        for way in &r.osm_ids {
+            lengths
+                .entry(*way)
+                .or_insert_with(Vec::new)
+                .push((r.id, r.reference_line.length()));
+        }
+    }
+
+    let mut result = BTreeMap::new();
+    for (way, roads) in lengths {
+        let total: Distance = roads.iter().map(|(_, len)| *len).sum();
+        if total == Distance::ZERO {
+            continue;
+        }
+        result.insert(
+            way,
+            roads.into_iter().map(|(r, len)| (r, len / total)).collect(),
+        );
+    }
+    result
+}
+
+fn split_capacity(n: usize, fraction: f64) -> usize {
+    ((n as f64) * fraction).round() as usize
+}
+
+#[derive(Deserialize)]
+struct SurveyRecord {
+    osm_way_id: i64,
+    side: String,
+    capacity: Option<usize>,
+    occupancy: Option<f64>,
 }
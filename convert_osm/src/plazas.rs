@@ -0,0 +1,132 @@
+//! Pedestrian plazas are usually mapped as areas, not as lines. People cross them in any
+//! direction, but without any footways through them, routing sends everyone around the edge, and
+//! walking times through town squares look much worse than they are. This pass adds footways
+//! straight across each plaza between every pair of roads reaching it.
+
+use abstutil::{Tags, Timer};
+use geom::{Distance, Line, PolyLine, Polygon, Pt2D};
+use osm2streets::osm::OsmID;
+use osm2streets::{osm, IntersectionID, Road};
+use raw_map::{ExtraRoadData, RawMap};
+
+/// Every footway created across a plaza has this tag, with the plaza's OSM ID as the value
+pub const PLAZA_TAG: &str = "abst:plaza";
+
+/// Intersections this close to a plaza's edge are entrances to it
+const ENTRANCE_THRESHOLD: Distance = Distance::const_meters(3.0);
+/// How often to check that a footway stays inside the plaza
+const STEP_SIZE: Distance = Distance::const_meters(2.0);
+
+/// A plaza people can walk across freely, from `highway=pedestrian` + `area=yes` or
+/// `area:highway=pedestrian`
+pub struct Plaza {
+    pub osm_id: OsmID,
+    pub polygon: Polygon,
+    pub tags: Tags,
+}
+
+pub fn is_plaza(tags: &Tags) -> bool {
+    (tags.is_any(osm::HIGHWAY, vec!["pedestrian", "footway"]) && tags.is("area", "yes"))
+        || tags.is_any("area:highway", vec!["pedestrian", "footway"])
+}
+
+pub fn connect_plazas(map: &mut RawMap, plazas: Vec<Plaza>, timer: &mut Timer) {
+    let mut added = 0;
+    timer.start_iter("add footways across plazas", plazas.len());
+    for plaza in plazas {
+        timer.next();
+        // Entrances lie on the edge, so look a little beyond it
+        let area = match plaza.polygon.buffer(ENTRANCE_THRESHOLD) {
+            Ok(list) => list,
+            Err(err) => {
+                warn!("Can't connect plaza {}: {}", plaza.osm_id, err);
+                continue;
+            }
+        };
+        let contains = |pt: Pt2D| area.iter().any(|p| p.contains_pt(pt));
+
+        let entrances: Vec<(IntersectionID, Pt2D)> = map
+            .streets
+            .intersections
+            .values()
+            .filter(|i| {
+                let pt = i.polygon.center();
+                contains(pt)
+                    && i.roads.iter().any(|r| {
+                        map.streets.roads[r]
+                            .lane_specs_ltr
+                            .iter()
+                            .any(|spec| spec.lt.is_walkable())
+                    })
+            })
+            .map(|i| (i.id, i.polygon.center()))
+            .collect();
+        if entrances.len() < 2 {
+            continue;
+        }
+        // Don't cross anything already mapped through the plaza. Footways added here can cross
+        // each other, though; nobody needs to turn in the middle of a plaza.
+        let existing: Vec<Line> = map
+            .streets
+            .roads
+            .values()
+            .filter(|r| r.reference_line.points().iter().any(|pt| contains(*pt)))
+            .flat_map(|r| r.reference_line.lines())
+            .collect();
+
+        let mut new_roads = Vec::new();
+        for (idx, (i1, pt1)) in entrances.iter().enumerate() {
+            for (i2, pt2) in entrances.iter().skip(idx + 1) {
+                let line = match Line::new(*pt1, *pt2) {
+                    Ok(line) => line,
+                    Err(_) => continue,
+                };
+                if map.streets.roads.values().any(|r| {
+                    (r.src_i == *i1 && r.dst_i == *i2) || (r.src_i == *i2 && r.dst_i == *i1)
+                }) {
+                    continue;
+                }
+                if !stays_inside(&line, &contains) || existing.iter().any(|l| l.crosses(&line)) {
+                    continue;
+                }
+                new_roads.push((*i1, *i2, line));
+            }
+        }
+
+        for (src_i, dst_i, line) in new_roads {
+            let id = map.streets.next_road_id();
+            map.streets.insert_road(Road::new(
+                id,
+                Vec::new(),
+                src_i,
+                dst_i,
+                PolyLine::must_new(vec![line.pt1(), line.pt2()]),
+                footway_tags(&plaza),
+                &map.streets.config,
+            ));
+            map.extra_road_data.insert(id, ExtraRoadData::default());
+            added += 1;
+        }
+    }
+    info!("Added {} footways across plazas", added);
+}
+
+fn stays_inside<F: Fn(Pt2D) -> bool>(line: &Line, contains: &F) -> bool {
+    let steps = (line.length() / STEP_SIZE).ceil().max(1.0) as usize;
+    (0..=steps).all(|step| {
+        line.percent_along((step as f64) / (steps as f64))
+            .map(contains)
+            .unwrap_or(false)
+    })
+}
+
+fn footway_tags(plaza: &Plaza) -> Tags {
+    let mut tags = Tags::empty();
+    tags.insert(osm::HIGHWAY, "footway");
+    tags.insert("bicycle", "no");
+    if let Some(name) = plaza.tags.get("name") {
+        tags.insert("name", name);
+    }
+    tags.insert(PLAZA_TAG, plaza.osm_id.to_string());
+    tags
+}
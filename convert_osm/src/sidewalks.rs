@@ -0,0 +1,172 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use fs_err::File;
+use serde::Deserialize;
+
+use abstutil::{prettyprint_usize, Tags};
+use osm2streets::osm;
+use raw_map::RawMap;
+use streets_reader::osm_reader::Document;
+
+/// Marks the `sidewalk` tag on a way as something we filled in, not from OSM
+pub const SIDEWALK_SOURCE_TAG: &str = "abst:sidewalk_source";
+const SIDEWALK_KEYS: [&str; 4] = [
+    "sidewalk",
+    "sidewalk:both",
+    "sidewalk:left",
+    "sidewalk:right",
+];
+
+/// Many roads in OSM don't say whether they have sidewalks. osm2streets guesses the same way
+/// everywhere, but street design varies a lot between places. Residential streets in UK cities
+/// almost always have a pavement on both sides, while rural US roads rarely do.
+#[derive(Clone, Debug, Default)]
+pub struct SidewalkInference {
+    /// For roads without any sidewalk tags, assume this value for `sidewalk` (like "both" or
+    /// "no"), based on the `highway` type. Types not listed here are left to osm2streets.
+    pub per_highway: BTreeMap<String, String>,
+    /// A CSV file with `osm_way_id` and `sidewalk` columns. These values replace whatever's tagged
+    /// in OSM or inferred for those ways.
+    pub overrides: Option<String>,
+}
+
+impl SidewalkInference {
+    /// Streets in British cities have pavements on both sides, unless they're tagged otherwise
+    pub fn uk_city() -> Self {
+        Self::assume(
+            &[
+                "living_street",
+                "residential",
+                "unclassified",
+                "tertiary",
+                "secondary",
+                "primary",
+            ],
+            "both",
+        )
+    }
+
+    /// Minor roads outside of cities in the US usually have no sidewalks. Residential streets and
+    /// bigger roads are left to osm2streets.
+    pub fn us() -> Self {
+        Self::assume(&["unclassified", "track", "road"], "no")
+    }
+
+    fn assume(highway_types: &[&str], sidewalk: &str) -> Self {
+        Self {
+            per_highway: highway_types
+                .iter()
+                .map(|hwy| (hwy.to_string(), sidewalk.to_string()))
+                .collect(),
+            overrides: None,
+        }
+    }
+}
+
+/// Where the sidewalks on a road came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SidewalkSource {
+    Tagged,
+    Inferred,
+    Overridden,
+    /// Nothing was tagged or configured, so osm2streets guessed
+    Default,
+}
+
+/// Fill in `sidewalk` tags on ways according to the inference profile and overrides, and remember
+/// where every road's sidewalks came from. If `MapConfig::inferred_sidewalks` is off, only the
+/// overrides are used.
+pub fn infer_sidewalks(
+    doc: &mut Document,
+    inference: &SidewalkInference,
+    enabled: bool,
+) -> HashMap<osm::WayID, SidewalkSource> {
+    let overrides = match inference.overrides {
+        Some(ref path) => match read_overrides(path) {
+            Ok(overrides) => overrides,
+            Err(err) => {
+                error!("Couldn't read sidewalk overrides from {}: {}", path, err);
+                BTreeMap::new()
+            }
+        },
+        None => BTreeMap::new(),
+    };
+
+    let mut sources = HashMap::new();
+    for (id, way) in &mut doc.ways {
+        let hwy = match way.tags.get(osm::HIGHWAY) {
+            Some(hwy) => hwy.clone(),
+            None => {
+                continue;
+            }
+        };
+        let source = if let Some(sidewalk) = overrides.get(id) {
+            clear_sidewalk_tags(&mut way.tags);
+            way.tags.insert("sidewalk", sidewalk);
+            way.tags.insert(SIDEWALK_SOURCE_TAG, "override");
+            SidewalkSource::Overridden
+        } else if has_sidewalk_tags(&way.tags) {
+            SidewalkSource::Tagged
+        } else if let Some(sidewalk) = inference.per_highway.get(&hwy).filter(|_| enabled) {
+            way.tags.insert("sidewalk", sidewalk);
+            way.tags.insert(SIDEWALK_SOURCE_TAG, "inferred");
+            SidewalkSource::Inferred
+        } else {
+            SidewalkSource::Default
+        };
+        sources.insert(*id, source);
+    }
+    sources
+}
+
+/// Log how many roads got their sidewalks from each source
+pub fn report_sidewalk_sources(map: &RawMap, sources: &HashMap<osm::WayID, SidewalkSource>) {
+    let mut counts: BTreeMap<SidewalkSource, usize> = BTreeMap::new();
+    for road in map.streets.roads.values() {
+        if let Some(source) = road.osm_ids.get(0).and_then(|id| sources.get(id)) {
+            *counts.entry(*source).or_insert(0) += 1;
+        }
+    }
+    let count = |source| prettyprint_usize(counts.get(&source).cloned().unwrap_or(0));
+    info!(
+        "Sidewalks on {} roads are tagged, {} inferred from the region's profile, {} overridden, \
+         and {} guessed by osm2streets",
+        count(SidewalkSource::Tagged),
+        count(SidewalkSource::Inferred),
+        count(SidewalkSource::Overridden),
+        count(SidewalkSource::Default)
+    );
+}
+
+fn has_sidewalk_tags(tags: &Tags) -> bool {
+    SIDEWALK_KEYS.iter().any(|key| tags.contains_key(key))
+}
+
+fn clear_sidewalk_tags(tags: &mut Tags) {
+    for key in SIDEWALK_KEYS {
+        tags.remove(key);
+    }
+}
+
+#[derive(Deserialize)]
+struct OverrideRecord {
+    osm_way_id: i64,
+    sidewalk: String,
+}
+
+fn read_overrides(path: &str) -> Result<BTreeMap<osm::WayID, String>> {
+    let mut overrides = BTreeMap::new();
+    for rec in csv::Reader::from_reader(File::open(path)?).deserialize() {
+        let rec: OverrideRecord = rec?;
+        if !["both", "left", "right", "no", "none", "separate"].contains(&rec.sidewalk.as_str()) {
+            bail!(
+                "Unknown sidewalk value {} for way {}",
+                rec.sidewalk,
+                rec.osm_way_id
+            );
+        }
+        overrides.insert(osm::WayID(rec.osm_way_id), rec.sidewalk);
+    }
+    Ok(overrides)
+}
@@ -0,0 +1,267 @@
+//! Update an existing `RawMap` from a newer OSM extract, without converting everything again.
+//! Most upstream changes are to buildings and shops, which can be patched in place. Anything
+//! touching the street network needs a full conversion.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use abstutil::{Tags, Timer};
+use geom::{Pt2D, Ring};
+use osm2streets::osm::{self, OsmID, WayID};
+use raw_map::{RawBuilding, RawMap};
+use streets_reader::osm_reader::Document;
+
+use crate::extract::{get_area_type, get_bldg_amenities, is_bldg};
+use crate::Options;
+
+/// What happened when updating a `RawMap` from a newer extract
+pub enum Update {
+    /// Nothing used by the `RawMap` changed
+    Unchanged,
+    /// Buildings were updated in place
+    Patched { buildings: usize },
+    /// The change can't be patched in place; the `RawMap` must be converted from scratch
+    NeedsFullConversion(String),
+}
+
+/// Compare the old and new .osm files, both already clipped to the map's boundary. If only
+/// buildings and their amenities changed, update them in `map`. Otherwise `map` is left
+/// untouched.
+///
+/// Amenities coming from surrounding amenity areas aren't recalculated for patched buildings.
+pub fn update(
+    map: &mut RawMap,
+    old_osm_path: &str,
+    new_osm_path: &str,
+    opts: &Options,
+    timer: &mut Timer,
+) -> Result<Update> {
+    // Use the same bounds for both, so points can be compared
+    let old = read_doc(map, old_osm_path, timer)?;
+    let new = read_doc(map, new_osm_path, timer)?;
+
+    timer.start("diff OSM extracts");
+    let result = find_changed_buildings(map, &old, &new);
+    timer.stop("diff OSM extracts");
+    let ways = match result {
+        Ok(ways) => ways,
+        Err(reason) => {
+            return Ok(Update::NeedsFullConversion(reason));
+        }
+    };
+    if ways.is_empty() {
+        return Ok(Update::Unchanged);
+    }
+
+    // Amenities tagged on nodes are matched to the building containing them
+    let amenity_points: Vec<(Pt2D, &Tags)> = new
+        .nodes
+        .values()
+        .filter(|node| !get_bldg_amenities(&node.tags).is_empty())
+        .map(|node| (node.pt, &node.tags))
+        .collect();
+
+    timer.start_iter("patch buildings", ways.len());
+    for way_id in &ways {
+        timer.next();
+        let id = OsmID::Way(*way_id);
+        let old_bldg = map.buildings.remove(&id);
+        let way = match new.ways.get(way_id) {
+            Some(way) if is_bldg(&way.tags) => way,
+            _ => continue,
+        };
+        let mut deduped = way.pts.clone();
+        deduped.dedup();
+        let polygon = match Ring::new(deduped) {
+            Ok(ring) => ring.into_polygon(),
+            Err(_) => continue,
+        };
+        // Like clip_map, only keep buildings completely inside the boundary
+        if !polygon
+            .get_outer_ring()
+            .points()
+            .iter()
+            .all(|pt| map.streets.boundary_polygon.contains_pt(*pt))
+        {
+            continue;
+        }
+
+        let mut amenities = get_bldg_amenities(&way.tags);
+        for (pt, tags) in &amenity_points {
+            if polygon.contains_pt(*pt) {
+                amenities.extend(get_bldg_amenities(tags));
+            }
+        }
+        let mut bldg = RawBuilding {
+            polygon,
+            osm_tags: way.tags.clone(),
+            public_garage_name: None,
+            num_parking_spots: 0,
+            // Bike racks come from separate nodes, which aren't part of the update
+            bike_parking: old_bldg.as_ref().map(|b| b.bike_parking).unwrap_or(0),
+            amenities,
+        };
+        // Public garages come from separate data that didn't change
+        match old_bldg {
+            Some(old_bldg) if old_bldg.public_garage_name.is_some() => {
+                bldg.public_garage_name = old_bldg.public_garage_name;
+                bldg.num_parking_spots = old_bldg.num_parking_spots;
+            }
+            _ => {
+                crate::parking::private_offstreet_parking(
+                    &mut bldg,
+                    &opts.private_offstreet_parking,
+                );
+            }
+        }
+        map.buildings.insert(id, bldg);
+    }
+
+    Ok(Update::Patched {
+        buildings: ways.len(),
+    })
+}
+
+fn read_doc(map: &RawMap, path: &str, timer: &mut Timer) -> Result<Document> {
+    let osm_xml = fs_err::read_to_string(path)?;
+    Document::read(&osm_xml, Some(map.streets.gps_bounds.clone()), timer)
+}
+
+/// How a change to something in OSM affects the `RawMap`
+#[derive(PartialEq)]
+enum Impact {
+    /// Not used at all
+    Ignored,
+    Building,
+    /// The street network, areas, parking lots, transit, or anything else that needs a full
+    /// conversion
+    Other,
+}
+
+fn impact(tags: &Tags) -> Impact {
+    if is_bldg(tags) {
+        Impact::Building
+    } else if tags.has_any(vec![
+        osm::HIGHWAY,
+        "railway",
+        "public_transport",
+        "barrier",
+        "crossing",
+        "area:highway",
+        "historic",
+        "natural",
+        "type",
+    ]) || tags.is("amenity", "parking")
+        || get_area_type(tags).is_some()
+    {
+        Impact::Other
+    } else {
+        Impact::Ignored
+    }
+}
+
+/// Returns the building ways that need to be patched, or the reason a full conversion is needed.
+fn find_changed_buildings(
+    map: &RawMap,
+    old: &Document,
+    new: &Document,
+) -> Result<BTreeSet<WayID>, String> {
+    let mut buildings = BTreeSet::new();
+
+    let all_ways: BTreeSet<WayID> = old.ways.keys().chain(new.ways.keys()).cloned().collect();
+    for id in all_ways {
+        let before = old.ways.get(&id);
+        let after = new.ways.get(&id);
+        let changed = match (before, after) {
+            (Some(before), Some(after)) => before.tags != after.tags || before.pts != after.pts,
+            _ => true,
+        };
+        if !changed {
+            continue;
+        }
+        // Ways used by roads always matter, even if the tags look irrelevant
+        if map.osm_tags.contains_key(&id) {
+            return Err(format!("road {} changed", id));
+        }
+        let impacts = [before, after]
+            .into_iter()
+            .flatten()
+            .map(|way| impact(&way.tags))
+            .collect::<Vec<_>>();
+        if impacts.contains(&Impact::Other) {
+            return Err(format!("way {} changed", id));
+        }
+        if impacts.contains(&Impact::Building) {
+            buildings.insert(id);
+        }
+    }
+
+    // Moving a node changes the points of the ways using it, which was already checked. Only
+    // look for changed tags.
+    let all_nodes: BTreeSet<osm::NodeID> =
+        old.nodes.keys().chain(new.nodes.keys()).cloned().collect();
+    for id in all_nodes {
+        let before = old.nodes.get(&id);
+        let after = new.nodes.get(&id);
+        let changed = match (before, after) {
+            (Some(before), Some(after)) => before.tags != after.tags,
+            (Some(node), None) | (None, Some(node)) => !node.tags.is_empty(),
+            (None, None) => unreachable!(),
+        };
+        if !changed {
+            continue;
+        }
+        for node in [before, after].into_iter().flatten() {
+            if impact(&node.tags) == Impact::Other {
+                return Err(format!("node {} changed", id));
+            }
+            // An amenity inside a building
+            if !get_bldg_amenities(&node.tags).is_empty() {
+                for (b, bldg) in &map.buildings {
+                    if let OsmID::Way(way) = b {
+                        if bldg.polygon.contains_pt(node.pt) {
+                            buildings.insert(*way);
+                        }
+                    }
+                }
+                for (way_id, way) in &new.ways {
+                    if is_bldg(&way.tags) {
+                        if let Ok(ring) = Ring::new(way.pts.clone()) {
+                            if ring.into_polygon().contains_pt(node.pt) {
+                                buildings.insert(*way_id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let all_relations: BTreeSet<osm::RelationID> = old
+        .relations
+        .keys()
+        .chain(new.relations.keys())
+        .cloned()
+        .collect();
+    for id in all_relations {
+        let before = old.relations.get(&id);
+        let after = new.relations.get(&id);
+        let changed = match (before, after) {
+            (Some(before), Some(after)) => {
+                before.tags != after.tags || before.members != after.members
+            }
+            _ => true,
+        };
+        if changed
+            && [before, after]
+                .into_iter()
+                .flatten()
+                .any(|rel| impact(&rel.tags) != Impact::Ignored)
+        {
+            return Err(format!("relation {} changed", id));
+        }
+    }
+
+    Ok(buildings)
+}
@@ -0,0 +1,151 @@
+//! OSM ways at different z-levels (bridges, tunnels, or anything with a `layer` tag) should never
+//! share a node, but sometimes they do. When a bridge and the road underneath both pass through
+//! the same node, splitting ways creates an intersection between them, letting agents teleport
+//! between levels. This pass finds these crossings and removes the bogus connection.
+
+use std::collections::BTreeMap;
+
+use abstutil::{Tags, Timer};
+use geom::PolyLine;
+use osm2streets::{IntersectionID, Road, RoadID};
+use raw_map::{ExtraRoadData, RawMap};
+
+pub fn split_crossings(map: &mut RawMap, timer: &mut Timer) {
+    let mut crossings = Vec::new();
+    for i in map.streets.intersections.values() {
+        let mut per_layer: BTreeMap<isize, Vec<RoadID>> = BTreeMap::new();
+        for r in &i.roads {
+            per_layer
+                .entry(map.streets.roads[r].layer)
+                .or_insert_with(Vec::new)
+                .push(*r);
+        }
+        if per_layer.len() < 2 {
+            continue;
+        }
+        // Only handle the clear case, where every level just has one way passing straight through
+        // this node. Anything else might be a legitimate ramp between levels.
+        if per_layer.values().all(|roads| {
+            roads.len() == 2
+                && map.streets.roads[&roads[0]].osm_ids.get(0).is_some()
+                && map.streets.roads[&roads[0]].osm_ids.get(0)
+                    == map.streets.roads[&roads[1]].osm_ids.get(0)
+        }) {
+            // Leave the lowest level connected to the intersection
+            let pairs: Vec<(RoadID, RoadID)> = per_layer
+                .into_values()
+                .skip(1)
+                .map(|roads| (roads[0], roads[1]))
+                .collect();
+            crossings.push((i.id, pairs));
+        }
+    }
+
+    timer.start_iter("split crossings between z-levels", crossings.len());
+    let mut fixed = 0;
+    for (i, pairs) in crossings {
+        timer.next();
+        for (r1, r2) in pairs {
+            if join_roads(map, i, r1, r2) {
+                fixed += 1;
+            }
+        }
+        map.streets.update_i(i);
+    }
+    info!("Removed {} bogus connections between z-levels", fixed);
+}
+
+/// Replace two roads meeting at `i` with one road passing through without connecting. Returns
+/// false if this isn't possible.
+fn join_roads(map: &mut RawMap, i: IntersectionID, r1: RoadID, r2: RoadID) -> bool {
+    let (far1, far2) = {
+        let road1 = &map.streets.roads[&r1];
+        let road2 = &map.streets.roads[&r2];
+        let far1 = if road1.src_i == i {
+            road1.dst_i
+        } else {
+            road1.src_i
+        };
+        let far2 = if road2.src_i == i {
+            road2.dst_i
+        } else {
+            road2.src_i
+        };
+        (far1, far2)
+    };
+    // Joining these would produce a loop
+    if far1 == far2 || far1 == i || far2 == i {
+        return false;
+    }
+
+    let road1 = map.streets.remove_road(r1);
+    let road2 = map.streets.remove_road(r2);
+    let extra = map
+        .extra_road_data
+        .remove(&r1)
+        .unwrap_or_else(ExtraRoadData::default);
+    let extra2 = map.extra_road_data.remove(&r2);
+
+    // Keep the orientation of the first road, so its lanes remain valid
+    let mut pts2 = road2.reference_line.clone().into_points();
+    let (src_i, dst_i, pts) = if road1.dst_i == i {
+        if road2.src_i != i {
+            pts2.reverse();
+        }
+        let mut pts = road1.reference_line.clone().into_points();
+        pts.extend(pts2.into_iter().skip(1));
+        (road1.src_i, far2, pts)
+    } else {
+        if road2.dst_i != i {
+            pts2.reverse();
+        }
+        pts2.pop();
+        pts2.extend(road1.reference_line.clone().into_points());
+        (far2, road1.dst_i, pts2)
+    };
+
+    let reference_line = match PolyLine::deduping_new(pts) {
+        Ok(pl) => pl,
+        Err(err) => {
+            warn!("Couldn't join {} and {} across {}: {}", r1, r2, i, err);
+            // Put things back the way they were
+            map.streets.insert_road(road1);
+            map.streets.insert_road(road2);
+            map.extra_road_data.insert(r1, extra);
+            if let Some(extra2) = extra2 {
+                map.extra_road_data.insert(r2, extra2);
+            }
+            return false;
+        }
+    };
+
+    let mut osm_ids = road1.osm_ids.clone();
+    for id in &road2.osm_ids {
+        if !osm_ids.contains(id) {
+            osm_ids.push(*id);
+        }
+    }
+    let tags = road1
+        .osm_ids
+        .get(0)
+        .and_then(|id| map.osm_tags.get(id))
+        .cloned()
+        .unwrap_or_else(Tags::empty);
+
+    let id = map.streets.next_road_id();
+    let mut road = Road::new(
+        id,
+        osm_ids,
+        src_i,
+        dst_i,
+        reference_line,
+        tags,
+        &map.streets.config,
+    );
+    road.lane_specs_ltr = road1.lane_specs_ltr;
+    road.layer = road1.layer;
+    road.update_center_line(map.streets.config.driving_side);
+    map.streets.insert_road(road);
+    map.extra_road_data.insert(id, extra);
+    true
+}
@@ -0,0 +1,99 @@
+use crate::{Duration, LonLat, Time};
+
+/// The sun is this far below the horizon at sunrise and sunset, accounting for refraction and
+/// the size of the sun's disk
+const SUNRISE_ALTITUDE_DEGREES: f64 = -0.833;
+/// How long it takes to go from full darkness to full light, before sunrise or after sunset. This
+/// roughly matches civil twilight away from the poles.
+const TWILIGHT: Duration = Duration::const_seconds(30.0 * 60.0);
+
+/// When the sun rises and sets on one day of the year, at some latitude. Times are local solar
+/// time, with noon exactly at 12pm. Time zones, daylight saving time, and the equation of time
+/// aren't modelled, so these can be off from a real clock by an hour or so, but they're good
+/// enough to shade the map and decide when street lighting matters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Daylight {
+    pub sunrise: Time,
+    pub sunset: Time,
+}
+
+impl Daylight {
+    /// `day_of_year` counts from 1 on January 1st. Near the poles, the sun may never rise or set;
+    /// then sunrise and sunset are both midnight or noon.
+    pub fn new(pt: LonLat, day_of_year: usize) -> Daylight {
+        let latitude = pt.y().to_radians();
+        let declination = -23.44_f64.to_radians()
+            * (2.0 * std::f64::consts::PI / 365.0 * (day_of_year as f64 + 10.0)).cos();
+        let cos_hour_angle = (SUNRISE_ALTITUDE_DEGREES.to_radians().sin()
+            - latitude.sin() * declination.sin())
+            / (latitude.cos() * declination.cos());
+
+        // The sun moves 15 degrees per hour
+        let half_day = if cos_hour_angle <= -1.0 {
+            // Polar day
+            Duration::hours(12)
+        } else if cos_hour_angle >= 1.0 {
+            // Polar night
+            Duration::ZERO
+        } else {
+            Duration::seconds(cos_hour_angle.acos().to_degrees() / 15.0 * 3600.0)
+        };
+        let noon = Time::START_OF_DAY + Duration::hours(12);
+        Daylight {
+            sunrise: noon - half_day,
+            sunset: noon + half_day,
+        }
+    }
+
+    /// From 0 in full daylight to 1 in full darkness, with a gradual change through twilight.
+    /// Times past midnight wrap around to the next day.
+    pub fn darkness(&self, time: Time) -> f64 {
+        if self.sunrise == self.sunset {
+            return 1.0;
+        }
+        let t = Time::START_OF_DAY + Duration::seconds(time.inner_seconds() % (24.0 * 3600.0));
+        let light = if t < self.sunrise {
+            1.0 - (self.sunrise - t) / TWILIGHT
+        } else if t > self.sunset {
+            1.0 - (t - self.sunset) / TWILIGHT
+        } else {
+            1.0
+        };
+        1.0 - light.max(0.0)
+    }
+
+    /// Is it at least half dark? This is roughly when street lights switch on.
+    pub fn is_dark(&self, time: Time) -> bool {
+        self.darkness(time) >= 0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_length() {
+        // Seattle
+        let pt = LonLat::new(-122.3, 47.6);
+        let summer = Daylight::new(pt, 172);
+        let winter = Daylight::new(pt, 355);
+        let summer_hours = (summer.sunset - summer.sunrise).inner_seconds() / 3600.0;
+        let winter_hours = (winter.sunset - winter.sunrise).inner_seconds() / 3600.0;
+        assert!((summer_hours - 16.0).abs() < 0.25, "{}", summer_hours);
+        assert!((winter_hours - 8.4).abs() < 0.25, "{}", winter_hours);
+
+        let noon = Time::START_OF_DAY + Duration::hours(12);
+        let midnight = Time::START_OF_DAY;
+        assert_eq!(summer.darkness(noon), 0.0);
+        assert_eq!(summer.darkness(midnight), 1.0);
+        // The next day wraps around
+        assert_eq!(summer.darkness(noon + Duration::hours(24)), 0.0);
+        assert!(!winter.is_dark(winter.sunset));
+        assert!(winter.is_dark(winter.sunset + Duration::minutes(20)));
+
+        // Tromsø in December never sees the sun
+        let polar_night = Daylight::new(LonLat::new(18.9, 69.6), 355);
+        assert_eq!(polar_night.darkness(noon), 1.0);
+    }
+}
@@ -8,6 +8,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub use crate::angle::Angle;
 pub use crate::bounds::{Bounds, GPSBounds, QuadTree, QuadTreeBuilder};
 pub use crate::circle::Circle;
+pub use crate::daylight::Daylight;
 pub use crate::distance::Distance;
 pub use crate::duration::Duration;
 pub use crate::find_closest::FindClosest;
@@ -27,6 +28,7 @@ mod angle;
 mod bounds;
 mod circle;
 mod conversions;
+mod daylight;
 mod distance;
 mod duration;
 mod find_closest;
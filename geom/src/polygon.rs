@@ -8,8 +8,8 @@ use geo::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    Angle, Bounds, CornerRadii, Distance, GPSBounds, LonLat, PolyLine, Pt2D, Ring, Tessellation,
-    Triangle,
+    Angle, Bounds, Circle, CornerRadii, Distance, GPSBounds, Line, LonLat, PolyLine, Pt2D, Ring,
+    Tessellation, Triangle,
 };
 
 #[derive(PartialEq, Serialize, Deserialize, Clone, Debug)]
@@ -269,28 +269,40 @@ impl Polygon {
         result
     }
 
+    /// Union all of the polygons together. The result may be disjoint, and holes are kept.
+    pub fn union_all(list: Vec<Self>) -> Result<Vec<Self>> {
+        boolean_op(|| Self::union_all_into_multipolygon(list))
+    }
+
+    pub fn union(&self, other: &Self) -> Result<Vec<Self>> {
+        boolean_op(|| self.to_geo().union(&other.to_geo()))
+    }
+
     pub fn intersection(&self, other: &Self) -> Result<Vec<Self>> {
-        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            from_multi(self.to_geo().intersection(&other.to_geo()))
-        })) {
-            Ok(result) => result,
-            Err(err) => {
-                println!("BooleanOps crashed: {err:?}");
-                bail!("BooleanOps crashed: {err:?}");
-            }
-        }
+        boolean_op(|| self.to_geo().intersection(&other.to_geo()))
     }
 
     pub fn difference(&self, other: &Self) -> Result<Vec<Self>> {
-        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            from_multi(self.to_geo().difference(&other.to_geo()))
-        })) {
-            Ok(result) => result,
-            Err(err) => {
-                println!("BooleanOps crashed: {err:?}");
-                bail!("BooleanOps crashed: {err:?}");
-            }
+        boolean_op(|| self.to_geo().difference(&other.to_geo()))
+    }
+
+    /// Grows the polygon outwards by some distance, rounding corners, or shrinks it if the
+    /// distance is negative. Holes shrink as the polygon grows. Shrinking too much can make the
+    /// polygon disappear, returning nothing.
+    pub fn buffer(&self, distance: Distance) -> Result<Vec<Self>> {
+        if distance == Distance::ZERO {
+            return Ok(vec![self.clone()]);
         }
+        let sweep = sweep_around_rings(&self.rings, distance.abs());
+        boolean_op(|| {
+            let sweep = Self::union_all_into_multipolygon(sweep);
+            let poly = geo::MultiPolygon::from(self.to_geo());
+            if distance > Distance::ZERO {
+                poly.union(&sweep)
+            } else {
+                poly.difference(&sweep)
+            }
+        })
     }
 
     pub fn convex_hull(list: Vec<Self>) -> Result<Self> {
@@ -550,6 +562,42 @@ impl From<Polygon> for geo::Polygon {
     }
 }
 
+/// The geo crate's boolean operations can panic on some inputs, so turn that into an error.
+fn boolean_op<F: FnOnce() -> geo::MultiPolygon>(op: F) -> Result<Vec<Polygon>> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(op)) {
+        Ok(result) => from_multi(result),
+        Err(err) => {
+            println!("BooleanOps crashed: {err:?}");
+            bail!("BooleanOps crashed: {err:?}");
+        }
+    }
+}
+
+/// Everything within some distance of the rings' edges, as pieces that overlap. A circle swept
+/// along each edge is a rectangle, with a circle at each end.
+pub(crate) fn sweep_around_rings(rings: &[Ring], distance: Distance) -> Vec<Polygon> {
+    let mut pieces = Vec::new();
+    for ring in rings {
+        for pair in ring.points().windows(2) {
+            if let Ok(line) = Line::new(pair[0], pair[1]) {
+                let left = line.shift_left(distance);
+                let right = line.shift_right(distance);
+                if let Ok(rect) = Ring::new(vec![
+                    left.pt1(),
+                    left.pt2(),
+                    right.pt2(),
+                    right.pt1(),
+                    left.pt1(),
+                ]) {
+                    pieces.push(rect.into_polygon());
+                }
+            }
+            pieces.push(Circle::new(pair[0], distance).to_polygon());
+        }
+    }
+    pieces
+}
+
 pub(crate) fn from_multi(multi: geo::MultiPolygon) -> Result<Vec<Polygon>> {
     let mut result = Vec::new();
     for polygon in multi {
@@ -557,3 +605,46 @@ pub(crate) fn from_multi(multi: geo::MultiPolygon) -> Result<Vec<Polygon>> {
     }
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total_area(list: &[Polygon]) -> f64 {
+        list.iter().map(|p| p.area()).sum()
+    }
+
+    #[test]
+    fn test_union() {
+        let p1 = Polygon::rectangle(10.0, 10.0);
+        let p2 = p1.translate(5.0, 0.0);
+        let far = p1.translate(100.0, 0.0);
+
+        let result = p1.union(&p2).unwrap();
+        assert_eq!(result.len(), 1);
+        assert!((total_area(&result) - 150.0).abs() < 0.1);
+
+        let result = Polygon::union_all(vec![p1, p2, far]).unwrap();
+        assert_eq!(result.len(), 2);
+        assert!((total_area(&result) - 250.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_buffer() {
+        let square = Polygon::rectangle(10.0, 10.0);
+
+        // Growing adds a band along each side, plus rounded corners
+        let grown = square.buffer(Distance::meters(1.0)).unwrap();
+        assert_eq!(grown.len(), 1);
+        let expected = 100.0 + 4.0 * 10.0 + std::f64::consts::PI;
+        assert!((total_area(&grown) - expected).abs() < 0.5);
+        assert!(grown[0].contains_pt(Pt2D::new(-0.5, 5.0)));
+
+        let shrunk = square.buffer(Distance::meters(-1.0)).unwrap();
+        assert_eq!(shrunk.len(), 1);
+        assert!((total_area(&shrunk) - 64.0).abs() < 0.1);
+
+        // Shrinking too much erases the polygon
+        assert!(square.buffer(Distance::meters(-6.0)).unwrap().is_empty());
+    }
+}
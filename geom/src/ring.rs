@@ -5,6 +5,7 @@ use std::fmt::Write;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use crate::polygon::sweep_around_rings;
 use crate::{Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D, Tessellation};
 
 /// Maybe a misnomer, but like a PolyLine, but closed.
@@ -89,6 +90,18 @@ impl Ring {
         self.as_polyline().thicken_tessellation(thickness)
     }
 
+    /// Everything within some distance of the ring's outline, on both sides. Unlike `to_outline`,
+    /// corners are rounded and the result can be used in further boolean operations.
+    pub fn buffer(&self, distance: Distance) -> Result<Vec<Polygon>> {
+        if distance == Distance::ZERO {
+            return Ok(Vec::new());
+        }
+        Polygon::union_all(sweep_around_rings(
+            std::slice::from_ref(self),
+            distance.abs(),
+        ))
+    }
+
     pub fn into_polygon(self) -> Polygon {
         Polygon::with_holes(self, Vec::new())
     }
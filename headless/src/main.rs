@@ -8,6 +8,10 @@
 //! it's now 01:01:00.0
 //! > curl http://localhost:1234/data/get-road-thruput
 //! ... huge JSON blob
+//! > curl http://localhost:1234/sim/subscribe?types=trip-started,bus-arrived-at-stop
+//! 0
+//! > curl http://localhost:1234/sim/get-events?id=0
+//! ... every matching event since subscribing or the last call
 
 #[macro_use]
 extern crate anyhow;
@@ -32,7 +36,8 @@ use map_model::{
     MovementID, PermanentMapEdits, RoadID, TurnID,
 };
 use sim::{
-    AgentID, AgentType, DelayCause, PersonID, Sim, SimFlags, SimOptions, TripID, VehicleType,
+    AgentID, AgentType, DelayCause, PersonID, Sim, SimEvent, SimEventType, SimFlags, SimOptions,
+    SubscriptionID, TripID, VehicleType,
 };
 use synthpop::{ExternalPerson, Scenario, ScenarioModifier, TripMode};
 
@@ -199,6 +204,37 @@ fn handle_command(
                 sim.get_all_people().last().unwrap().id
             ))
         }
+        // Event subscriptions. Resetting or loading a new simulation drops all of them.
+        "/sim/subscribe" => {
+            let types = match params.get("types") {
+                Some(list) => list
+                    .split(',')
+                    .map(SimEventType::parse)
+                    .collect::<Result<BTreeSet<_>>>()?,
+                None => SimEventType::all().into_iter().collect(),
+            };
+            Ok(sim.subscribe(types).0.to_string())
+        }
+        "/sim/unsubscribe" => {
+            let id = SubscriptionID(get("id")?.parse::<usize>()?);
+            if sim.unsubscribe(id) {
+                Ok(format!("unsubscribed {}", id.0))
+            } else {
+                bail!("no subscription {}", id.0)
+            }
+        }
+        "/sim/get-events" => {
+            let id = SubscriptionID(get("id")?.parse::<usize>()?);
+            match sim.drain_events(id) {
+                Some(events) => Ok(abstutil::to_json(
+                    &events
+                        .into_iter()
+                        .map(|(time, event)| TimedEvent { time, event })
+                        .collect::<Vec<_>>(),
+                )),
+                None => bail!("no subscription {}", id.0),
+            }
+        }
         // Traffic signals
         "/traffic-signals/get" => {
             let i = IntersectionID(get("id")?.parse::<usize>()?);
@@ -409,6 +445,12 @@ fn handle_command(
 
 // TODO I think specifying the API with protobufs or similar will be a better idea.
 
+#[derive(Serialize)]
+struct TimedEvent {
+    time: Time,
+    event: SimEvent,
+}
+
 #[derive(Serialize)]
 struct FinishedTrip {
     id: TripID,
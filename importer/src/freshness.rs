@@ -0,0 +1,173 @@
+//! Check if the OSM extract a city was imported from is out-of-date, and if so, update the city's
+//! RawMaps from a newer extract. Re-importing a big region from scratch takes hours, but most
+//! upstream changes are small and can be patched in place.
+
+use std::process::Command;
+
+use anyhow::Result;
+
+use abstio::{CityName, MapName};
+use abstutil::Timer;
+use raw_map::RawMap;
+
+use crate::configuration::ImporterConfiguration;
+use crate::utils::{boundary_polygon, convert_clipped_osm, download, osmium};
+
+/// How old the local copy of a Geofabrik extract is. Timestamps are ISO 8601 in UTC, like
+/// `2023-05-01T20:21:22Z`, so they can be compared as strings.
+pub struct Freshness {
+    pub url: String,
+    pub local_path: String,
+    /// None if there's no local copy, or its age is unknown
+    pub local_timestamp: Option<String>,
+    pub remote_timestamp: String,
+}
+
+impl Freshness {
+    /// Find the extract covering a map, and compare its age with the latest version available.
+    pub async fn check(name: &MapName, config: &ImporterConfiguration) -> Result<Freshness> {
+        let (url, local_path) = crate::pick_geofabrik(boundary_polygon(name)).await?;
+        let local_timestamp = local_timestamp(&local_path, config);
+        let remote_timestamp = remote_timestamp(&url).await?;
+        Ok(Freshness {
+            url,
+            local_path,
+            local_timestamp,
+            remote_timestamp,
+        })
+    }
+
+    pub fn is_stale(&self) -> bool {
+        match self.local_timestamp {
+            Some(ref local) => *local < self.remote_timestamp,
+            None => true,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} is from {}, and the latest extract is from {}",
+            self.local_path,
+            self.local_timestamp.as_deref().unwrap_or("an unknown time"),
+            self.remote_timestamp
+        )
+    }
+}
+
+/// Geofabrik extracts record when they were made in their header
+fn local_timestamp(path: &str, config: &ImporterConfiguration) -> Option<String> {
+    if !abstio::file_exists(path) {
+        return None;
+    }
+    let output = Command::new(&config.osmium)
+        .arg("fileinfo")
+        .arg("-g")
+        .arg("header.option.osmosis_replication_timestamp")
+        .arg(path)
+        .output()
+        .ok()?;
+    let timestamp = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    if output.status.success() && !timestamp.is_empty() {
+        Some(timestamp)
+    } else {
+        None
+    }
+}
+
+/// Geofabrik publishes the replication state of every extract, like
+/// https://download.geofabrik.de/europe/monaco-updates/state.txt
+async fn remote_timestamp(url: &str) -> Result<String> {
+    let state_url = match url.strip_suffix("-latest.osm.pbf") {
+        Some(prefix) => format!("{}-updates/state.txt", prefix),
+        None => bail!("Unexpected Geofabrik URL {}", url),
+    };
+    let state = String::from_utf8(abstio::http_get(&state_url).await?)?;
+    for line in state.lines() {
+        if let Some(timestamp) = line.strip_prefix("timestamp=") {
+            // The file is in Java properties format, which escapes colons
+            return Ok(timestamp.replace("\\:", ":"));
+        }
+    }
+    bail!("No timestamp in {}", state_url)
+}
+
+/// If the extract covering a city is out-of-date, download the latest one and update all of the
+/// city's RawMaps (or just one). Where only buildings changed, they're patched in place;
+/// otherwise the RawMap is converted again, re-applying manual merges from map_editor. The
+/// final maps aren't regenerated.
+pub async fn refresh_city(
+    city: CityName,
+    only_map: Option<String>,
+    check_only: bool,
+    timer: &mut Timer<'_>,
+) -> Result<()> {
+    let config = ImporterConfiguration::load();
+    let names = if let Some(map) = only_map {
+        vec![MapName::from_city(&city, &map)]
+    } else {
+        city.list_all_maps_in_city_from_importer_config()
+    };
+
+    for name in names {
+        let freshness = Freshness::check(&name, &config).await?;
+        println!("- {}: {}", name.describe(), freshness.describe());
+        if check_only {
+            continue;
+        }
+        // Maps in one city usually share an extract, so only the first will download it
+        if freshness.is_stale() {
+            if abstio::file_exists(&freshness.local_path) {
+                fs_err::remove_file(&freshness.local_path)?;
+            }
+            download(&config, freshness.local_path.clone(), &freshness.url).await;
+        }
+
+        let clipped = name.city.input_path(format!("osm/{}.osm", name.map));
+        let old_clipped = format!("{}.old", clipped);
+        if !abstio::file_exists(&clipped) {
+            bail!(
+                "{} doesn't exist; import {} first",
+                clipped,
+                name.describe()
+            );
+        }
+        fs_err::rename(&clipped, &old_clipped)?;
+        osmium(
+            freshness.local_path.clone(),
+            boundary_polygon(&name),
+            clipped.clone(),
+            &config,
+        );
+
+        timer.start(format!("update {}", name.describe()));
+        let opts = crate::map_config::config_for_map(&name);
+        let mut raw: RawMap = abstio::read_binary(abstio::path_raw_map(&name), timer);
+        match convert_osm::update(&mut raw, &old_clipped, &clipped, &opts, timer)? {
+            convert_osm::Update::Unchanged => {
+                println!("- Nothing relevant changed in {}", name.describe());
+            }
+            convert_osm::Update::Patched { buildings } => {
+                println!("- Updated {} buildings in {}", buildings, name.describe());
+                raw.save();
+            }
+            convert_osm::Update::NeedsFullConversion(reason) => {
+                println!(
+                    "- Converting {} from scratch, because {}",
+                    name.describe(),
+                    reason
+                );
+                convert_clipped_osm(&name, opts, timer);
+            }
+        }
+        timer.stop(format!("update {}", name.describe()));
+        fs_err::remove_file(&old_clipped)?;
+    }
+
+    if !check_only {
+        println!(
+            "Run the importer with --map to regenerate the final maps for {}",
+            city.describe()
+        );
+    }
+    Ok(())
+}
@@ -9,6 +9,8 @@ extern crate anyhow;
 #[macro_use]
 extern crate log;
 
+use std::collections::BTreeSet;
+
 use structopt::StructOpt;
 
 use abstio::{CityName, MapName};
@@ -16,15 +18,19 @@ use abstutil::Timer;
 use map_model::RawToMapOptions;
 
 pub use self::configuration::ImporterConfiguration;
+pub use self::freshness::{refresh_city, Freshness};
 pub use self::pick_geofabrik::pick_geofabrik;
+pub use self::stages::Stage;
 pub use utils::osmium;
 
 mod berlin;
 mod configuration;
+mod freshness;
 mod map_config;
 mod pick_geofabrik;
 mod seattle;
 mod soundcast;
+mod stages;
 mod uk;
 mod utils;
 
@@ -104,6 +110,18 @@ pub struct Job {
     #[structopt()]
     pub only_map: Option<String>,
 
+    /// Just run one stage for each map: fetch-osm, raw-map, map, or scenarios. It runs even if
+    /// its inputs haven't changed. Replaces --raw, --map, and --scenario.
+    #[structopt(long)]
+    pub only_stage: Option<Stage>,
+    /// Run this stage and every one after it for each map, even if their inputs haven't changed.
+    /// Use this after changing the importer's code. Replaces --raw, --map, and --scenario.
+    #[structopt(long)]
+    pub from_stage: Option<Stage>,
+    /// Run stages even if their inputs haven't changed since they last ran.
+    #[structopt(long)]
+    pub force: bool,
+
     #[structopt(flatten)]
     pub opts: RawToMapOptions,
 }
@@ -117,10 +135,13 @@ impl Job {
             scenario: false,
             city_overview: false,
             only_map: None,
+            only_stage: None,
+            from_stage: None,
+            force: false,
             opts: RawToMapOptions::default(),
         };
         // Only some maps run extra tasks
-        if job.city == CityName::seattle() || job.city.country == "gb" {
+        if has_scenarios(&job.city) {
             job.scenario = true;
         }
         // TODO Autodetect this based on number of maps per city?
@@ -157,16 +178,72 @@ impl Job {
         if self.city_overview {
             flags.push("--city-overview".to_string());
         }
+        if let Some(stage) = self.only_stage {
+            flags.push(format!("--only-stage={}", stage.name()));
+        }
+        if let Some(stage) = self.from_stage {
+            flags.push(format!("--from-stage={}", stage.name()));
+        }
+        if self.force {
+            flags.push("--force".to_string());
+        }
         if let Some(ref name) = self.only_map {
             flags.push(name.clone());
         }
         flags
     }
 
+    /// The stages to run for each map, before checking which are up-to-date
+    fn stages(&self) -> BTreeSet<Stage> {
+        let mut stages = BTreeSet::new();
+        if let Some(stage) = self.only_stage {
+            stages.insert(stage);
+        } else if let Some(from) = self.from_stage {
+            stages.extend(Stage::all().into_iter().filter(|s| *s >= from));
+        } else {
+            if self.osm_to_raw {
+                stages.insert(Stage::FetchOSM);
+                stages.insert(Stage::RawMap);
+            }
+            if self.raw_to_map {
+                stages.insert(Stage::Map);
+            }
+            if self.scenario {
+                stages.insert(Stage::Scenarios);
+            }
+        }
+        if !has_scenarios(&self.city) {
+            stages.remove(&Stage::Scenarios);
+        }
+        stages
+    }
+
+    /// Should a stage run for one map? Unless it was asked for explicitly, skip it if its inputs
+    /// haven't changed since it last ran.
+    fn run_stage(&self, stage: Stage, name: &MapName, record: &stages::StageRecord) -> bool {
+        if !self.stages().contains(&stage) {
+            return false;
+        }
+        if self.force
+            || self.only_stage.is_some()
+            || self.from_stage.is_some()
+            || !record.is_up_to_date(stage)
+        {
+            return true;
+        }
+        println!(
+            "- {} for {} is up-to-date, skipping",
+            stage.name(),
+            name.describe()
+        );
+        false
+    }
+
     pub async fn run(self, timer: &mut Timer<'_>) {
-        if !self.osm_to_raw && !self.raw_to_map && !self.scenario && !self.city_overview {
+        if self.stages().is_empty() && !self.city_overview {
             println!(
-                "Nothing to do! Pass some combination of --raw, --map, --scenario, or --city_overview"
+                "Nothing to do! Pass some combination of --raw, --map, --scenario, or \
+                 --city_overview, or use --only-stage or --from-stage"
             );
             std::process::exit(1);
         }
@@ -186,32 +263,46 @@ impl Job {
         // to hack in a way to avoid the work.
         let mut built_raw_huge_seattle = false;
         let mut built_map_huge_seattle = false;
-        let (maybe_popdat, maybe_huge_map, maybe_zoning_parcels) = if self.scenario
-            && self.city == CityName::seattle()
-        {
-            timer.start("ensure_popdat_exists");
-            let (popdat, huge_map) = seattle::ensure_popdat_exists(
-                timer,
-                &config,
-                &mut built_raw_huge_seattle,
-                &mut built_map_huge_seattle,
-            )
-            .await;
-            // Just assume --raw has been called...
-            let shapes: kml::ExtraShapes =
-                abstio::read_binary(CityName::seattle().input_path("zoning_parcels.bin"), timer);
-            timer.stop("ensure_popdat_exists");
-            (Some(popdat), Some(huge_map), Some(shapes))
-        } else {
-            (None, None, None)
-        };
+        let (maybe_popdat, maybe_huge_map, maybe_zoning_parcels) =
+            if self.stages().contains(&Stage::Scenarios) && self.city == CityName::seattle() {
+                timer.start("ensure_popdat_exists");
+                let (popdat, huge_map) = seattle::ensure_popdat_exists(
+                    timer,
+                    &config,
+                    &mut built_raw_huge_seattle,
+                    &mut built_map_huge_seattle,
+                )
+                .await;
+                // Just assume --raw has been called...
+                let shapes: kml::ExtraShapes = abstio::read_binary(
+                    CityName::seattle().input_path("zoning_parcels.bin"),
+                    timer,
+                );
+                timer.stop("ensure_popdat_exists");
+                (Some(popdat), Some(huge_map), Some(shapes))
+            } else {
+                (None, None, None)
+            };
 
         for name in names {
             timer.start(name.describe());
-            if self.osm_to_raw
-                && (!built_raw_huge_seattle || name != MapName::seattle("huge_seattle"))
-            {
-                let raw = utils::osm_to_raw(name.clone(), timer, &config).await;
+            let mut record = stages::StageRecord::load(&name, &self.opts);
+            let already_built_raw =
+                built_raw_huge_seattle && name == MapName::seattle("huge_seattle");
+
+            if !already_built_raw && self.run_stage(Stage::FetchOSM, &name, &record) {
+                // osmium won't overwrite an old clip
+                let clipped = stages::clipped_osm(&name);
+                if abstio::file_exists(&clipped) {
+                    fs_err::remove_file(&clipped).unwrap();
+                }
+                utils::fetch_osm(&name, timer, &config).await;
+                record.finished(Stage::FetchOSM);
+            }
+
+            if !already_built_raw && self.run_stage(Stage::RawMap, &name, &record) {
+                let raw =
+                    utils::convert_clipped_osm(&name, map_config::config_for_map(&name), timer);
 
                 // The collision data will only cover one part of London, since we don't have a
                 // region-wide map there yet
@@ -222,9 +313,12 @@ impl Job {
                 } else if name == MapName::new("gb", "london", "camden") {
                     uk::import_collision_data(&raw, &config, timer).await;
                 }
+                record.finished(Stage::RawMap);
             }
 
-            let mut maybe_map = if self.raw_to_map {
+            // Decide before the map changes; see Stage::hash_inputs
+            let run_scenarios = self.run_stage(Stage::Scenarios, &name, &record);
+            let mut maybe_map = if self.run_stage(Stage::Map, &name, &record) {
                 let mut map = if built_map_huge_seattle && name == MapName::seattle("huge_seattle")
                 {
                     map_model::Map::load_synchronously(name.path(), timer)
@@ -247,14 +341,15 @@ impl Job {
                     map.save();
                 }
 
+                record.finished(Stage::Map);
                 Some(map)
-            } else if self.scenario {
+            } else if run_scenarios {
                 Some(map_model::Map::load_synchronously(name.path(), timer))
             } else {
                 None
             };
 
-            if self.scenario {
+            if run_scenarios {
                 if self.city == CityName::seattle() {
                     timer.start(format!("scenario for {}", name.describe()));
                     let scenario = soundcast::make_scenario(
@@ -311,6 +406,7 @@ impl Job {
                             .unwrap();
                     }
                 }
+                record.finished(Stage::Scenarios);
             }
             timer.stop(name.describe());
         }
@@ -336,3 +432,8 @@ impl Job {
         timer.stop(format!("import {}", self.city.describe()));
     }
 }
+
+/// Only some cities have travel demand data to make scenarios from
+fn has_scenarios(city: &CityName) -> bool {
+    *city == CityName::seattle() || city.country == "gb"
+}
@@ -35,6 +35,10 @@ pub fn config_for_map(name: &MapName) -> convert_osm::Options {
             },
         },
         filter_crosswalks: false,
+        // Still experimental; not enabled for any maps yet
+        merge_dual_carriageways: false,
+        // Still experimental; not enabled for any maps yet
+        smooth_centerlines: None,
         onstreet_parking: match name.city.city.as_ref() {
             "seattle" => {
                 convert_osm::OnstreetParking::Blockface(name.city.input_path("blockface.bin"))
@@ -61,6 +65,31 @@ pub fn config_for_map(name: &MapName) -> convert_osm::Options {
         } else {
             convert_osm::PrivateOffstreetParking::FixedPerBldg(3)
         },
+        // Surveys need to be manually placed in the city's input directory
+        parking_survey: {
+            let path = name.city.input_path("parking_survey.csv");
+            if abstio::file_exists(&path) {
+                Some(path)
+            } else {
+                None
+            }
+        },
+        sidewalk_inference: convert_osm::SidewalkInference {
+            // Like the parking survey, corrections go in the city's input directory
+            overrides: {
+                let path = name.city.input_path("sidewalk_overrides.csv");
+                if abstio::file_exists(&path) {
+                    Some(path)
+                } else {
+                    None
+                }
+            },
+            ..match name.city.country.as_ref() {
+                "gb" => convert_osm::SidewalkInference::uk_city(),
+                "us" => convert_osm::SidewalkInference::us(),
+                _ => convert_osm::SidewalkInference::default(),
+            }
+        },
         // Unused currently
         extra_buildings: None,
         // https://www.transit.land is a great place to find the static GTFS URLs
@@ -4,7 +4,8 @@ use abstutil::{prettyprint_usize, MultiMap, Timer};
 use geom::PolyLine;
 use map_model::{osm, BuildingID, Map, Path, PathConstraints, PathRequest, PathStep};
 use synthpop::{
-    IndividTrip, MapBorder, MapBorders, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode,
+    IndividTrip, MapBorder, MapBorders, OrigPersonID, ParkAndRide, PersonSpec, PickUpDropOff,
+    Scenario, TripEndpoint, TripMode, ValueOfTime, VehicleMix,
 };
 
 use crate::soundcast::popdat::{Endpoint, OrigTrip, PopDat};
@@ -280,6 +281,7 @@ pub fn make_scenario(
         people.push(PersonSpec {
             orig_id: Some(orig_id),
             trips,
+            demographics: None,
         });
     }
     for maybe_t in individ_trips {
@@ -293,6 +295,10 @@ pub fn make_scenario(
         map_name: map.get_name().clone(),
         people,
         only_seed_buses: None,
+        vehicle_mix: VehicleMix::default(),
+        park_and_ride: ParkAndRide::default(),
+        value_of_time: ValueOfTime::default(),
+        pick_up_drop_off: PickUpDropOff::default(),
     }
     .remove_weird_schedules(true)
 }
@@ -0,0 +1,174 @@
+//! Importing one map runs through a few stages, each producing the input to the next. Regenerating
+//! everything after a small change takes hours, so every stage records a hash of its inputs after
+//! it finishes. Running the importer again skips stages whose inputs haven't changed and whose
+//! output still exists.
+//!
+//! Hashes only cover input files, not the importer's code or `map_config.rs`. After changing
+//! those, pass `--force` or `--from-stage`.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::str::FromStr;
+
+use anyhow::Result;
+
+use abstio::MapName;
+use map_model::RawToMapOptions;
+
+/// One step of importing a map, in the order they run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// Download the OSM extract covering the map and other input files, then clip OSM to the
+    /// map's boundary
+    FetchOSM,
+    /// Convert the clipped OSM and other input data to a RawMap
+    RawMap,
+    /// Convert the RawMap to the final Map
+    Map,
+    /// Generate travel demand for the map, for cities that have it
+    Scenarios,
+}
+
+impl Stage {
+    pub fn all() -> Vec<Stage> {
+        vec![Stage::FetchOSM, Stage::RawMap, Stage::Map, Stage::Scenarios]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::FetchOSM => "fetch-osm",
+            Stage::RawMap => "raw-map",
+            Stage::Map => "map",
+            Stage::Scenarios => "scenarios",
+        }
+    }
+
+    /// The file this stage produces. Scenarios aren't checked, since not every map has them.
+    fn output(self, name: &MapName) -> Option<String> {
+        match self {
+            Stage::FetchOSM => Some(clipped_osm(name)),
+            Stage::RawMap => Some(abstio::path_raw_map(name)),
+            Stage::Map => Some(name.path()),
+            Stage::Scenarios => None,
+        }
+    }
+
+    /// A hash of everything this stage reads
+    fn hash_inputs(self, name: &MapName, opts: &RawToMapOptions) -> String {
+        let mut context = md5::Context::new();
+        match self {
+            Stage::FetchOSM => {
+                hash_file(&mut context, &crate::utils::boundary_polygon(name));
+            }
+            Stage::RawMap => {
+                hash_file(&mut context, &clipped_osm(name));
+                hash_file(&mut context, &raw_map::merges::path(name));
+                hash_file(&mut context, &name.city.input_path("parking_survey.csv"));
+                hash_file(
+                    &mut context,
+                    &name.city.input_path("sidewalk_overrides.csv"),
+                );
+            }
+            // Some cities modify the map after generating scenarios, so scenarios depend on what
+            // the map is built from, instead of the map itself
+            Stage::Map | Stage::Scenarios => {
+                hash_file(&mut context, &abstio::path_raw_map(name));
+                context.consume(format!(
+                    "skip_ch={} keep_bldg_tags={}",
+                    opts.skip_ch, opts.keep_bldg_tags
+                ));
+            }
+        }
+        format!("{:x}", context.compute())
+    }
+}
+
+impl FromStr for Stage {
+    type Err = anyhow::Error;
+
+    fn from_str(x: &str) -> Result<Stage> {
+        for stage in Stage::all() {
+            if stage.name() == x {
+                return Ok(stage);
+            }
+        }
+        bail!(
+            "Unknown stage {}; try one of {}",
+            x,
+            Stage::all()
+                .into_iter()
+                .map(|s| s.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// The input hashes of the stages last run for one map
+pub struct StageRecord {
+    name: MapName,
+    opts: RawToMapOptions,
+    /// Keyed by `Stage::name`
+    hashes: BTreeMap<String, String>,
+}
+
+impl StageRecord {
+    pub fn load(name: &MapName, opts: &RawToMapOptions) -> StageRecord {
+        StageRecord {
+            name: name.clone(),
+            opts: opts.clone(),
+            hashes: abstio::maybe_read_json(path(name), &mut abstutil::Timer::throwaway())
+                .unwrap_or_else(|_| BTreeMap::new()),
+        }
+    }
+
+    /// A stage is up-to-date if its output exists and its inputs are the same as the last time it
+    /// ran
+    pub fn is_up_to_date(&self, stage: Stage) -> bool {
+        if let Some(output) = stage.output(&self.name) {
+            if !abstio::file_exists(output) {
+                return false;
+            }
+        }
+        self.hashes.get(stage.name()) == Some(&stage.hash_inputs(&self.name, &self.opts))
+    }
+
+    /// Remember a stage's inputs after it runs successfully
+    pub fn finished(&mut self, stage: Stage) {
+        self.hashes.insert(
+            stage.name().to_string(),
+            stage.hash_inputs(&self.name, &self.opts),
+        );
+        abstio::write_json(path(&self.name), &self.hashes);
+    }
+}
+
+/// Where the OSM clipped to a map's boundary lives
+pub fn clipped_osm(name: &MapName) -> String {
+    name.city.input_path(format!("osm/{}.osm", name.map))
+}
+
+fn path(name: &MapName) -> String {
+    name.city
+        .input_path(format!("import_stages/{}.json", name.map))
+}
+
+/// Include the path too, so a file that's missing hashes differently than an empty one
+fn hash_file(context: &mut md5::Context, file_path: &str) {
+    context.consume(file_path);
+    let mut file = match fs_err::File::open(file_path) {
+        Ok(file) => file,
+        Err(_) => {
+            context.consume("missing");
+            return;
+        }
+    };
+    // Input files can be huge, so read in chunks
+    let mut buffer = vec![0_u8; 1 << 20];
+    while let Ok(n) = file.read(&mut buffer) {
+        if n == 0 {
+            break;
+        }
+        context.consume(&buffer[..n]);
+    }
+}
@@ -1,10 +1,8 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
-use fs_err::File;
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
-use serde::Deserialize;
 
 use abstio::path_shared_input;
 use abstutil::{prettyprint_usize, Timer};
@@ -141,51 +139,8 @@ pub async fn generate_scenario(
 }
 
 fn parse_desire_lines(path: String) -> Result<Vec<DesireLine>> {
-    let mut output = Vec::new();
-    for rec in csv::Reader::from_reader(File::open(path)?).deserialize() {
-        let rec: Record = rec?;
-        for (mode, number_commuters) in [
-            (TripMode::Drive, rec.num_drivers),
-            (TripMode::Bike, rec.num_bikers),
-            (TripMode::Walk, rec.num_pedestrians),
-            (
-                TripMode::Transit,
-                rec.num_transit1 + rec.num_transit2 + rec.num_transit3,
-            ),
-        ] {
-            if number_commuters > 0 {
-                output.push(DesireLine {
-                    home_zone: rec.home_zone.clone(),
-                    work_zone: rec.work_zone.clone(),
-                    mode,
-                    number_commuters,
-                });
-            }
-        }
-    }
-    Ok(output)
-}
-
-// An entry in wu03ew_v2.csv. For now, ignores people who work from home, take a taxi, motorcycle,
-// are a passenger in a car, or use "another method of travel".
-#[derive(Debug, Deserialize)]
-struct Record {
-    #[serde(rename = "Area of residence")]
-    home_zone: String,
-    #[serde(rename = "Area of workplace")]
-    work_zone: String,
-    #[serde(rename = "Underground, metro, light rail, tram")]
-    num_transit1: usize,
-    #[serde(rename = "Train")]
-    num_transit2: usize,
-    #[serde(rename = "Bus, minibus or coach")]
-    num_transit3: usize,
-    #[serde(rename = "Driving a car or van")]
-    num_drivers: usize,
-    #[serde(rename = "Bicycle")]
-    num_bikers: usize,
-    #[serde(rename = "On foot")]
-    num_pedestrians: usize,
+    popdat::CensusSource::UkOutputAreas
+        .parse_workplace_flows(&abstio::slurp_file(path)?, &popdat::ModeShares::default())
 }
 
 // Transforms all zones into the map's coordinate space, no matter how far out-of-bounds they are.
@@ -124,18 +124,25 @@ pub async fn osm_to_raw(
     timer: &mut abstutil::Timer<'_>,
     config: &ImporterConfiguration,
 ) -> RawMap {
+    fetch_osm(&name, timer, config).await;
+    convert_clipped_osm(&name, crate::map_config::config_for_map(&name), timer)
+}
+
+/// Downloads OSM and other input data, then clips OSM to the map's boundary.
+pub async fn fetch_osm(
+    name: &MapName,
+    timer: &mut abstutil::Timer<'_>,
+    config: &ImporterConfiguration,
+) {
     if name.city == CityName::seattle() {
         crate::seattle::input(config, timer).await;
     }
-    let opts = crate::map_config::config_for_map(&name);
+    let opts = crate::map_config::config_for_map(name);
     if let Some(ref url) = opts.gtfs_url {
         download(config, name.city.input_path("gtfs/"), url).await;
     }
 
-    let boundary_polygon = format!(
-        "importer/config/{}/{}/{}.geojson",
-        name.city.country, name.city.city, name.map
-    );
+    let boundary_polygon = boundary_polygon(name);
     let (osm_url, local_osm_file) = crate::pick_geofabrik(boundary_polygon.clone())
         .await
         .unwrap();
@@ -143,22 +150,41 @@ pub async fn osm_to_raw(
 
     osmium(
         local_osm_file,
-        boundary_polygon.clone(),
-        name.city.input_path(format!("osm/{}.osm", name.map)),
+        boundary_polygon,
+        crate::stages::clipped_osm(name),
         config,
     );
+}
 
-    let map = convert_osm::convert(
-        name.city.input_path(format!("osm/{}.osm", name.map)),
+/// Creates a RawMap from the .osm file already clipped to the map's boundary, then re-applies
+/// manual merges from map_editor.
+pub fn convert_clipped_osm(
+    name: &MapName,
+    opts: convert_osm::Options,
+    timer: &mut abstutil::Timer,
+) -> RawMap {
+    let mut map = convert_osm::convert(
+        crate::stages::clipped_osm(name),
         name.clone(),
-        Some(boundary_polygon),
+        Some(boundary_polygon(name)),
         opts,
         timer,
     );
+    for problem in raw_map::merges::apply(&mut map) {
+        warn!("Couldn't re-apply manual merge {}", problem);
+    }
     map.save();
     map
 }
 
+/// The path to the GeoJSON polygon defining a map's boundary
+pub fn boundary_polygon(name: &MapName) -> String {
+    format!(
+        "importer/config/{}/{}/{}.geojson",
+        name.city.country, name.city.city, name.map
+    )
+}
+
 /// Converts a RawMap to a Map.
 pub fn raw_to_map(name: &MapName, opts: RawToMapOptions, timer: &mut Timer) -> map_model::Map {
     timer.start(format!("Raw->Map for {}", name.describe()));
@@ -32,6 +32,9 @@ pub enum ColorSchemeChoice {
     Textured,
     ClassicDayMode,
     LTN,
+    HighContrast,
+    /// Avoids relying on red/green distinctions
+    ColorblindSafe,
 }
 
 impl ColorSchemeChoice {
@@ -42,6 +45,8 @@ impl ColorSchemeChoice {
             Choice::new("textured", ColorSchemeChoice::Textured),
             Choice::new("classic", ColorSchemeChoice::ClassicDayMode),
             Choice::new("LTN", ColorSchemeChoice::LTN),
+            Choice::new("high contrast", ColorSchemeChoice::HighContrast),
+            Choice::new("colorblind-safe", ColorSchemeChoice::ColorblindSafe),
         ]
     }
 
@@ -90,6 +95,8 @@ pub struct ColorScheme {
     pub sidewalk_lines: Color,
     pub general_road_marking: Color,
     road_center_line: Color,
+    /// Box junctions and hatching where vehicles can't stop
+    pub no_stopping_marking: Color,
     pub light_rail_track: Color,
     pub private_road: Option<Color>,
     pub unzoomed_highway: Color,
@@ -97,6 +104,8 @@ pub struct ColorScheme {
     pub unzoomed_residential: Color,
     pub unzoomed_cycleway: Color,
     pub unzoomed_footway: Color,
+    /// Drawn along both sides of bridges
+    pub bridge_casing: Color,
     footway: Color,
     shared_use: Color,
 
@@ -149,11 +158,17 @@ pub struct ColorScheme {
     pub bike_frame: Color,
     pub parked_car: Color,
 
-    // Layers
+    // Layers. These name what a color means, so each scheme can pick colors that work for it.
     pub good_to_bad_red: ColorScale,
     pub good_to_bad_green: ColorScale,
+    /// For comparing before and after a proposal
+    pub better_than_before: Color,
+    pub worse_than_before: Color,
     pub bus_layer: Color,
     pub edits_layer: Color,
+    /// For categories with no inherent meaning, like LTN cells. Adjacent entries should be easy
+    /// to tell apart.
+    pub categorical: Vec<Color>,
 
     // Misc
     pub parking_trip: Color,
@@ -171,6 +186,8 @@ impl ColorScheme {
             ColorSchemeChoice::Textured => ColorScheme::textured(),
             ColorSchemeChoice::ClassicDayMode => ColorScheme::classic(),
             ColorSchemeChoice::LTN => ColorScheme::ltn(),
+            ColorSchemeChoice::HighContrast => ColorScheme::high_contrast(),
+            ColorSchemeChoice::ColorblindSafe => ColorScheme::colorblind_safe(),
         };
         cs.scheme = scheme;
         ctx.set_style(cs.gui_style.clone());
@@ -214,6 +231,7 @@ impl ColorScheme {
             sidewalk_lines: Color::grey(0.7),
             general_road_marking: Color::WHITE,
             road_center_line: Color::YELLOW,
+            no_stopping_marking: hex("#F2C230"),
             light_rail_track: hex("#844204"),
             private_road: Some(hex("#F0B0C0")),
             unzoomed_highway: hex("#E892A2"),
@@ -221,6 +239,7 @@ impl ColorScheme {
             unzoomed_residential: Color::WHITE,
             unzoomed_cycleway: hex("#0F7D4B"),
             unzoomed_footway: hex("#DED68A"),
+            bridge_casing: Color::grey(0.3),
             // TODO Distinguish shared use and footway unzoomed or zoomed?
             footway: hex("#DED68A"),
             shared_use: hex("#DED68A"),
@@ -283,8 +302,24 @@ impl ColorScheme {
             // Layers
             good_to_bad_red: ColorScale(vec![hex("#F19A93"), hex("#A32015")]),
             good_to_bad_green: ColorScale(vec![hex("#BEDB92"), hex("#397A4C")]),
+            better_than_before: hex("#5D9630"),
+            worse_than_before: hex("#A32015"),
             bus_layer: hex("#4CA7E9"),
             edits_layer: hex("#12409D"),
+            // A qualitative palette from colorbrewer2.org, skipping the red hue (used for levels
+            // of shortcutting) and grey (too close to the basemap)
+            categorical: vec![
+                hex("#8dd3c7"),
+                hex("#ffffb3"),
+                hex("#bebada"),
+                hex("#80b1d3"),
+                hex("#fdb462"),
+                hex("#b3de69"),
+                hex("#fccde5"),
+                hex("#bc80bd"),
+                hex("#ccebc5"),
+                hex("#ffed6f"),
+            ],
 
             // Misc
             parking_trip: hex("#4E30A6"),
@@ -322,6 +357,7 @@ impl ColorScheme {
         cs.unzoomed_interesting_intersection = cs.unzoomed_highway;
         cs.stop_sign = hex("#A32015");
         cs.private_road = Some(hex("#9E757F"));
+        cs.bridge_casing = hex("#B1B1B1");
         cs.study_area = hex("#D9B002").into();
 
         cs.panel_bg = cs.gui_style.panel_bg;
@@ -392,12 +428,121 @@ impl ColorScheme {
         cs.gui_style.panel_bg = Color::WHITE;
         cs.panel_bg = cs.gui_style.panel_bg;
 
+        cs.bus_layer = hex("#0672B9");
+
+        cs
+    }
+
+    fn high_contrast() -> ColorScheme {
+        let mut cs = ColorScheme::classic();
+        cs.scheme = ColorSchemeChoice::HighContrast;
+        cs.gui_style = Style::high_contrast();
+        cs.gui_style.loading_tips = loading_tips();
+        cs.road_outlines = true;
+
+        cs.void_background = Color::BLACK;
+        cs.map_background = Color::BLACK.into();
+        cs.grass = hex("#003300").into();
+        cs.water = hex("#000066").into();
+        cs.residential_building = Color::grey(0.3);
+        cs.commercial_building = Color::grey(0.5);
+        cs.building_outline = Color::WHITE;
+
+        cs.driving_lane = Color::grey(0.15);
+        cs.parking_lane = Color::grey(0.25);
+        cs.sidewalk = Color::grey(0.7);
+        cs.general_road_marking = Color::WHITE;
+        cs.road_center_line = hex("#FFD700");
+        cs.normal_intersection = cs.driving_lane;
+        cs.unzoomed_highway = hex("#FFD700");
+        cs.unzoomed_arterial = Color::WHITE;
+        cs.unzoomed_residential = Color::grey(0.6);
+        cs.unzoomed_interesting_intersection = Color::WHITE;
+        cs.bridge_casing = Color::WHITE;
+
+        cs.selected = hex("#FFD700").alpha(0.8);
+        cs.current_object = hex("#00FFFF");
+        cs.perma_selected_object = hex("#FF00FF");
+        cs.route = hex("#00FFFF").alpha(0.7);
+
+        cs.panel_bg = cs.gui_style.panel_bg;
+        cs.inner_panel_bg = cs.gui_style.section_bg;
+        cs.minimap_cursor_border = Color::WHITE;
+
+        cs
+    }
+
+    // Based on the Okabe-Ito palette, which stays distinguishable for the common kinds of color
+    // vision deficiency
+    fn colorblind_safe() -> ColorScheme {
+        let mut cs = ColorScheme::day_mode();
+        cs.scheme = ColorSchemeChoice::ColorblindSafe;
+
+        let orange = hex("#E69F00");
+        let sky_blue = hex("#56B4E9");
+        let bluish_green = hex("#009E73");
+        let yellow = hex("#F0E442");
+        let blue = hex("#0072B2");
+        let vermillion = hex("#D55E00");
+        let reddish_purple = hex("#CC79A7");
+
+        cs.bus_lane = vermillion;
+        cs.bike_lane = blue;
+        cs.unzoomed_cycleway = blue;
+        cs.bike_trip = blue;
+        cs.bus_trip = vermillion;
+        cs.bus_layer = sky_blue;
+        cs.stop_sign = vermillion;
+
+        cs.signal_protected_turn = blue;
+        cs.signal_permitted_turn = sky_blue;
+        cs.signal_banned_turn = vermillion;
+
+        cs.slowest_intersection = vermillion;
+        cs.slower_intersection = orange;
+        cs.slow_intersection = yellow;
+
+        cs.unzoomed_car = vermillion;
+        cs.unzoomed_bike = blue;
+        cs.unzoomed_bus = yellow;
+        cs.unzoomed_pedestrian = reddish_purple;
+        cs.agent_colors = vec![
+            orange,
+            sky_blue,
+            bluish_green,
+            blue,
+            vermillion,
+            reddish_purple,
+        ];
+
+        // Sequential scales go from light yellow to dark purple, instead of relying on hue
+        cs.good_to_bad_red = ColorScale(vec![hex("#FDE725"), hex("#3B528B"), hex("#440154")]);
+        cs.good_to_bad_green = ColorScale(vec![hex("#DEEBF7"), hex("#08519C")]);
+        cs.better_than_before = blue;
+        cs.worse_than_before = orange;
+        cs.before_changes = blue;
+        cs.after_changes = orange;
+
+        cs.categorical = vec![
+            orange,
+            sky_blue,
+            bluish_green,
+            yellow,
+            blue,
+            vermillion,
+            reddish_purple,
+            Color::grey(0.6),
+        ];
+
         cs
     }
 }
 
 impl ColorScheme {
     pub fn rotating_color_plot(&self, idx: usize) -> Color {
+        if self.scheme == ColorSchemeChoice::ColorblindSafe {
+            return modulo_color(&self.categorical, idx);
+        }
         modulo_color(
             &[
                 Color::RED,
@@ -30,6 +30,10 @@ pub struct Options {
     pub color_scheme: ColorSchemeChoice,
     /// Automatically change color_scheme based on simulation time to reflect day/night
     pub toggle_day_night_colors: bool,
+    /// Darken the map between sunset and sunrise, with lit streets glowing
+    pub show_daylight: bool,
+    /// Which day of the year sets sunrise and sunset, counting from 1 on January 1st
+    pub day_of_year: usize,
     /// Draw buildings in different perspectives
     pub camera_angle: CameraAngle,
     /// Draw building driveways.
@@ -40,6 +44,10 @@ pub struct Options {
     pub show_stop_signs: bool,
     /// Draw crosswalks and unmarked crossings.
     pub show_crosswalks: bool,
+    /// Paint road markings in detail: turn arrows and stop lines on every approach, "BUS LANE"
+    /// text, box junctions, and hatching where vehicles can't stop. Makes designs easier to read
+    /// in screenshots.
+    pub show_road_markings: bool,
     /// If true, draw an icon for traffic signals both when zoomed and unzoomed. If false, color
     /// the intersection when unzoomed and render the signal's current state when zoomed.
     pub show_traffic_signal_icon: bool,
@@ -88,11 +96,15 @@ impl Options {
             traffic_signal_style: TrafficSignalStyle::Brian,
             color_scheme: ColorSchemeChoice::DayMode,
             toggle_day_night_colors: false,
+            show_daylight: false,
+            // The March equinox
+            day_of_year: 80,
             camera_angle: CameraAngle::TopDown,
             show_building_driveways: true,
             show_building_outlines: true,
             show_stop_signs: true,
             show_crosswalks: true,
+            show_road_markings: false,
             show_traffic_signal_icon: false,
             simplify_basemap: false,
 
@@ -268,6 +280,24 @@ impl OptionsPanel {
                         }
                         Widget::dropdown(ctx, "language", default, choices)
                     }]),
+                    Toggle::checkbox(
+                        ctx,
+                        "Darken the map at night",
+                        None,
+                        app.opts().show_daylight,
+                    ),
+                    Toggle::checkbox(
+                        ctx,
+                        "Paint detailed road markings",
+                        None,
+                        app.opts().show_road_markings,
+                    ),
+                    Widget::row(vec![
+                        "Day of the year for sunrise and sunset"
+                            .text_widget(ctx)
+                            .centered_vert(),
+                        Spinner::widget(ctx, "day_of_year", (1, 365), app.opts().day_of_year, 1),
+                    ]),
                     Toggle::choice(
                         ctx,
                         "metric / imperial units",
@@ -383,6 +413,19 @@ impl<A: AppLike> State<A> for OptionsPanel {
                     }
 
                     opts.units.metric = self.panel.is_checked("metric / imperial units");
+                    opts.show_daylight = self.panel.is_checked("Darken the map at night");
+                    opts.day_of_year = self.panel.spinner("day_of_year");
+
+                    let show_road_markings = self.panel.is_checked("Paint detailed road markings");
+                    if opts.show_road_markings != show_road_markings {
+                        opts.show_road_markings = show_road_markings;
+                        for r in &mut app.mut_draw_map().roads {
+                            r.clear_rendering();
+                        }
+                        for i in &mut app.mut_draw_map().intersections {
+                            i.clear_rendering();
+                        }
+                    }
 
                     let language = self.panel.dropdown_value("language");
                     if language != opts.language {
@@ -1,12 +1,13 @@
 use std::cell::RefCell;
 
 use geom::{
-    Angle, ArrowCap, Bounds, Distance, Line, PolyLine, Polygon, Pt2D, Ring, Tessellation, Time,
-    EPSILON_DIST,
+    Angle, ArrowCap, Bounds, Circle, Distance, Line, PolyLine, Polygon, Pt2D, Ring, Tessellation,
+    Time, EPSILON_DIST,
 };
 use map_model::{
-    ControlTrafficSignal, Direction, DrivingSide, Intersection, IntersectionControl,
-    IntersectionID, LaneType, Map, Road, RoadWithStopSign, Turn, TurnType, SIDEWALK_THICKNESS,
+    ApproachControl, ControlTrafficSignal, Direction, DrivingSide, Intersection,
+    IntersectionControl, IntersectionID, LaneType, Map, Road, RoadWithStopSign, Turn, TurnType,
+    SIDEWALK_THICKNESS,
 };
 use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Prerender, RewriteColor, Text};
 
@@ -70,6 +71,11 @@ impl DrawIntersection {
             }
         }
 
+        // Crosswalks go on top
+        if app.opts().show_road_markings && is_box_junction(i, map) {
+            default_geom.extend(app.cs().no_stopping_marking, calculate_box_junction(i));
+        }
+
         for turn in &i.turns {
             if !app.opts().show_crosswalks {
                 break;
@@ -79,6 +85,18 @@ impl DrawIntersection {
             }
         }
 
+        if !i.corner_design.is_empty() {
+            make_corner_design(&mut default_geom, i, map, app.cs());
+        }
+
+        if i.is_level_crossing(map) {
+            for turn in &i.turns {
+                if map.get_l(turn.id.src).is_light_rail() {
+                    make_level_crossing(&mut default_geom, turn, map, app.cs());
+                }
+            }
+        }
+
         if i.is_private(map) {
             if let Some(color) = app.cs().private_road {
                 default_geom.push(color.alpha(0.5), i.polygon.clone());
@@ -98,10 +116,16 @@ impl DrawIntersection {
                         if !app.opts().show_stop_signs {
                             break;
                         }
-                        if ss.must_stop {
-                            if let Some((octagon, pole, angle)) =
-                                DrawIntersection::stop_sign_geom(ss, map)
-                            {
+                        let (octagon, pole, angle) = match DrawIntersection::stop_sign_geom(ss, map)
+                        {
+                            Some(geom) => geom,
+                            None => {
+                                continue;
+                            }
+                        };
+                        match ss.control {
+                            ApproachControl::Priority => {}
+                            ApproachControl::Stop => {
                                 let center = octagon.center();
                                 default_geom.push(app.cs().stop_sign, octagon);
                                 default_geom.push(app.cs().stop_sign_pole, pole);
@@ -119,6 +143,19 @@ impl DrawIntersection {
                                     .rotate(angle.opposite().rotate_degs(-90.0)),
                                 );
                             }
+                            ApproachControl::GiveWay => {
+                                let triangle = make_give_way_triangle(
+                                    octagon.center(),
+                                    Distance::meters(1.0),
+                                    angle,
+                                );
+                                default_geom.push(Color::WHITE, triangle.clone().into_polygon());
+                                default_geom.push(
+                                    app.cs().stop_sign,
+                                    triangle.to_outline(Distance::meters(0.25)),
+                                );
+                                default_geom.push(app.cs().stop_sign_pole, pole);
+                            }
                         }
                     }
                 }
@@ -546,6 +583,101 @@ fn make_octagon(center: Pt2D, radius: Distance, facing: Angle) -> Polygon {
     .into_polygon()
 }
 
+/// The triangle points in the direction of travel, so the flat edge faces approaching drivers.
+fn make_give_way_triangle(center: Pt2D, radius: Distance, facing: Angle) -> Ring {
+    Ring::must_new(
+        (0..=3)
+            .map(|i| center.project_away(radius, facing.rotate_degs(f64::from(i * 120))))
+            .collect(),
+    )
+}
+
+/// Concrete islands for protected corners, green boxes where people cycling wait to turn in two
+/// stages, and give-way markings in front of setback crossings
+fn make_corner_design(batch: &mut GeomBatch, i: &Intersection, map: &Map, cs: &ColorScheme) {
+    let rank = i.get_rank(map);
+    let mut lanes: Vec<_> = i.incoming_lanes.iter().chain(&i.outgoing_lanes).collect();
+    lanes.sort();
+    lanes.dedup();
+    for l in lanes {
+        let lane = map.get_l(*l);
+        if !lane.is_biking() {
+            continue;
+        }
+        // Points from the lane into the intersection
+        let end = lane.end_line(i.id);
+        let into = end.angle();
+
+        if i.corner_design.protected_corners {
+            // Put the island between the bike lane and the rest of the road
+            let road = map.get_r(lane.id.road);
+            let road_end = if road.src_i == i.id {
+                road.center_pts.first_pt()
+            } else {
+                road.center_pts.last_pt()
+            };
+            if let Ok(towards_road) = Line::new(end.pt2(), road_end) {
+                let center = end
+                    .pt2()
+                    .project_away(lane.width, towards_road.angle())
+                    .project_away(Distance::meters(1.5), into);
+                let island = Circle::new(center, Distance::meters(1.0)).to_polygon();
+                batch.push(cs.curb(rank), island.to_outline(Distance::meters(0.2)));
+                batch.push(cs.zoomed_road_surface(LaneType::Sidewalk, rank), island);
+            }
+        }
+
+        if i.corner_design.two_stage_turn_boxes && lane.src_i == i.id {
+            // People cycling wait here, in line with the bike lane they're about to enter
+            let start = end.pt2().project_away(Distance::meters(0.5), into);
+            let stop = start.project_away(Distance::meters(2.0), into);
+            if let Ok(line) = Line::new(start, stop) {
+                let bike_box = line.make_polygons(lane.width);
+                batch.push(
+                    cs.general_road_marking,
+                    bike_box.to_outline(Distance::meters(0.15)),
+                );
+                batch.push(cs.zoomed_road_surface(LaneType::Biking, rank), bike_box);
+            }
+        }
+    }
+
+    if i.corner_design.setback_crossings {
+        let center = i.polygon.center();
+        for turn in &i.turns {
+            if !turn.turn_type.pedestrian_crossing() {
+                continue;
+            }
+            let line = match turn.crosswalk_line() {
+                Some(line) => line,
+                None => continue,
+            };
+            // A row of small triangles on the side of the crossing facing the intersection
+            let mid = line.middle().unwrap_or_else(|_| line.pt1());
+            let mut perp = line.angle().rotate_degs(90.0);
+            if mid
+                .project_away(Distance::meters(1.0), perp)
+                .dist_to(center)
+                > mid.dist_to(center)
+            {
+                perp = perp.opposite();
+            }
+            let mut dist = Distance::meters(0.5);
+            while dist < line.length() {
+                let pt = line
+                    .must_dist_along(dist)
+                    .project_away(Distance::meters(1.5), perp);
+                batch.push(
+                    cs.general_road_marking,
+                    make_give_way_triangle(pt, Distance::meters(0.3), perp.opposite())
+                        .into_polygon(),
+                );
+                dist += Distance::meters(1.0);
+            }
+        }
+    }
+}
+
 /// Draws both zebra crosswalks and unmarked crossings
 pub fn make_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &ColorScheme) {
     if turn.turn_type == TurnType::UnmarkedCrossing {
@@ -653,6 +785,22 @@ fn make_rainbow_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map) -> bool
     true
 }
 
+/// Continue the tracks through a level crossing, so it's clear that road traffic has to cross
+/// them. This matches how light rail lanes are drawn.
+fn make_level_crossing(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &ColorScheme) {
+    let width = map.get_l(turn.id.src).width;
+    let track_width = width / 4.0;
+    for pl in [
+        turn.geom.shift_right((width - track_width) / 2.5),
+        turn.geom.shift_left((width - track_width) / 2.5),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        batch.push(cs.light_rail_track, pl.make_polygons(track_width));
+    }
+}
+
 fn make_unmarked_crossing(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &ColorScheme) {
     let color = cs.general_road_marking.alpha(0.5);
     let band_width = Distance::meters(0.1);
@@ -681,3 +829,43 @@ fn perp_line(l: Line, length: Distance) -> Line {
     let pt2 = l.shift_left(length / 2.0).pt1();
     Line::must_new(pt1, pt2)
 }
+
+/// Queueing traffic shouldn't block a signalled junction where vehicles cross from several
+/// directions
+fn is_box_junction(i: &Intersection, map: &Map) -> bool {
+    i.is_traffic_signal()
+        && i.roads
+            .iter()
+            .filter(|r| map.get_r(**r).lanes.iter().any(|l| l.is_driving()))
+            .count()
+            >= 3
+}
+
+/// A yellow criss-cross across the whole intersection
+fn calculate_box_junction(i: &Intersection) -> Vec<Tessellation> {
+    let thickness = Distance::meters(0.2);
+    let spacing = Distance::meters(2.0);
+    let bounds = i.polygon.get_bounds();
+    let center = bounds.center();
+    let radius = Distance::meters(bounds.width().max(bounds.height()));
+
+    let mut result = Vec::new();
+    for angle in [Angle::degrees(45.0), Angle::degrees(-45.0)] {
+        let mut offset = -radius;
+        while offset <= radius {
+            let pt = center.project_away(offset, angle.rotate_degs(90.0));
+            if let Ok(stripe) = Line::new(
+                pt.project_away(radius, angle.opposite()),
+                pt.project_away(radius, angle),
+            ) {
+                if let Ok(pieces) = stripe.make_polygons(thickness).intersection(&i.polygon) {
+                    result.extend(pieces.into_iter().map(Tessellation::from));
+                }
+            }
+            offset += spacing;
+        }
+    }
+    // Outline the box
+    result.push(i.polygon.get_outer_ring().to_outline(thickness));
+    result
+}
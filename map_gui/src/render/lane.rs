@@ -6,8 +6,11 @@ use geom::{
     Angle, ArrowCap, Bounds, Circle, Distance, InfiniteLine, Line, PolyLine, Polygon, Pt2D,
     Tessellation,
 };
-use map_model::{BufferType, Direction, DrivingSide, Lane, LaneID, LaneType, Map, Road, TurnID};
-use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Prerender, RewriteColor};
+use map_model::{
+    ApproachControl, BufferType, Direction, DrivingSide, IntersectionControl, Lane, LaneID,
+    LaneType, Map, Road, TurnID,
+};
+use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Prerender, RewriteColor, Text};
 
 use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
 use crate::{AppLike, ID};
@@ -37,15 +40,45 @@ impl DrawLane {
         let rank = road.get_rank();
         let mut batch = GeomBatch::new();
 
-        if !lane.is_light_rail() {
+        // If the lane only covers part of the road, the neighboring lanes widen into the rest of
+        // the space, and markings only go along the full-width part.
+        let partial_lane;
+        let lane = if let Some(extent) = lane.extent {
+            batch.push(
+                app.cs().zoomed_road_surface(LaneType::Driving, rank),
+                lane.lane_center_pts.make_polygons(lane.width),
+            );
+            partial_lane = Lane {
+                lane_center_pts: lane.lane_center_pts.exact_slice(extent.start, extent.end),
+                extent: None,
+                ..lane.clone()
+            };
+            &partial_lane
+        } else {
+            lane
+        };
+
+        // Pedestrian streets are paved the same across their whole width, like a plaza
+        let pedestrian_street = road.is_pedestrian_street();
+        if pedestrian_street {
+            batch.push(
+                app.cs().zoomed_road_surface(LaneType::Footway, rank),
+                self.polygon.clone(),
+            );
+        } else if !lane.is_light_rail() {
             batch.push(
                 app.cs().zoomed_road_surface(lane.lane_type, rank),
                 self.polygon.clone(),
             );
         }
         let general_road_marking = app.cs().general_road_marking;
+        let detailed_markings = app.opts().show_road_markings;
 
         match lane.lane_type {
+            // Delivery vehicles just drive across the paving
+            LaneType::Footway | LaneType::Driving if pedestrian_street => {
+                batch.extend(app.cs().sidewalk_lines, calculate_paving_lines(lane));
+            }
             LaneType::Sidewalk | LaneType::Shoulder => {
                 // Don't draw these for shoulders
                 if lane.is_sidewalk() {
@@ -65,26 +98,64 @@ impl DrawLane {
             }
             LaneType::Parking => {
                 batch.extend(general_road_marking, calculate_parking_lines(lane, map));
+                if detailed_markings {
+                    batch.extend(
+                        app.cs().no_stopping_marking,
+                        calculate_no_stopping_hatching(lane, map),
+                    );
+                }
             }
             LaneType::Driving => {
                 batch.extend(general_road_marking, calculate_driving_lines(lane, road));
-                batch.extend(general_road_marking, calculate_turn_markings(map, lane));
+                batch.extend(
+                    general_road_marking,
+                    calculate_turn_markings(map, lane, detailed_markings),
+                );
                 batch.extend(general_road_marking, calculate_one_way_markings(lane, road));
+                if detailed_markings {
+                    if let Some(line) = calculate_stop_line(map, lane) {
+                        batch.push(general_road_marking, line);
+                    }
+                }
             }
             LaneType::Bus => {
                 batch.extend(general_road_marking, calculate_driving_lines(lane, road));
-                batch.extend(general_road_marking, calculate_turn_markings(map, lane));
+                batch.extend(
+                    general_road_marking,
+                    calculate_turn_markings(map, lane, detailed_markings),
+                );
                 batch.extend(general_road_marking, calculate_one_way_markings(lane, road));
-                for (pt, angle) in lane
-                    .lane_center_pts
-                    .step_along(Distance::meters(30.0), Distance::meters(5.0))
-                {
-                    batch.append(
-                        GeomBatch::load_svg(prerender, "system/assets/map/bus_only.svg")
-                            .scale(0.06)
-                            .centered_on(pt)
-                            .rotate(angle.shortest_rotation_towards(Angle::degrees(-90.0))),
-                    );
+                if detailed_markings {
+                    if let Some(line) = calculate_stop_line(map, lane) {
+                        batch.push(general_road_marking, line);
+                    }
+                    // Written across the lane, to be read while driving along it
+                    let txt = Text::from("BUS LANE")
+                        .render_autocropped(prerender)
+                        .color(RewriteColor::ChangeAll(general_road_marking))
+                        .scale_to_fit_width(0.8 * lane.width.inner_meters());
+                    for (pt, angle) in lane
+                        .lane_center_pts
+                        .step_along(Distance::meters(30.0), Distance::meters(5.0))
+                    {
+                        batch.append(
+                            txt.clone()
+                                .centered_on(pt)
+                                .rotate_around_batch_center(angle.rotate_degs(90.0)),
+                        );
+                    }
+                } else {
+                    for (pt, angle) in lane
+                        .lane_center_pts
+                        .step_along(Distance::meters(30.0), Distance::meters(5.0))
+                    {
+                        batch.append(
+                            GeomBatch::load_svg(prerender, "system/assets/map/bus_only.svg")
+                                .scale(0.06)
+                                .centered_on(pt)
+                                .rotate(angle.shortest_rotation_towards(Angle::degrees(-90.0))),
+                        );
+                    }
                 }
             }
             LaneType::Biking => {
@@ -266,6 +337,18 @@ fn calculate_sidewalk_lines(lane: &Lane) -> Vec<Polygon> {
         .collect()
 }
 
+/// Small paving slabs, instead of the big squares along a sidewalk
+fn calculate_paving_lines(lane: &Lane) -> Vec<Polygon> {
+    lane.lane_center_pts
+        .step_along(Distance::meters(1.5), Distance::meters(1.5))
+        .into_iter()
+        .map(|(pt, angle)| {
+            let pt2 = pt.project_away(Distance::meters(1.0), angle);
+            perp_line(Line::must_new(pt, pt2), lane.width).make_polygons(Distance::meters(0.1))
+        })
+        .collect()
+}
+
 fn calculate_parking_lines(lane: &Lane, map: &Map) -> Vec<Polygon> {
     let leg_length = Distance::meters(1.0);
 
@@ -300,6 +383,37 @@ fn calculate_parking_lines(lane: &Lane, map: &Map) -> Vec<Polygon> {
     result
 }
 
+/// Diagonal hatching next to intersections, before the first and after the last parking spot
+fn calculate_no_stopping_hatching(lane: &Lane, map: &Map) -> Vec<Polygon> {
+    let spot_length = map.get_config().street_parking_spot_length;
+    let num_spots = lane.number_parking_spots(map.get_config());
+    let ranges = if num_spots == 0 {
+        vec![(Distance::ZERO, lane.length())]
+    } else {
+        vec![
+            (Distance::ZERO, spot_length),
+            (spot_length * (1.0 + num_spots as f64), lane.length()),
+        ]
+    };
+
+    let thickness = Distance::meters(0.15);
+    // Keep the stripes inside the lane, at 45 degrees
+    let half_stripe = lane.width * 0.4 * std::f64::consts::SQRT_2;
+    let mut result = Vec::new();
+    for (start, end) in ranges {
+        let pl = match lane.lane_center_pts.maybe_exact_slice(start, end) {
+            Ok(pl) => pl,
+            Err(_) => continue,
+        };
+        for (center, angle) in pl.step_along(Distance::meters(1.0), lane.width * 0.4) {
+            let pt1 = center.project_away(half_stripe, angle.rotate_degs(45.0));
+            let pt2 = center.project_away(half_stripe, angle.rotate_degs(45.0).opposite());
+            result.push(Line::must_new(pt1, pt2).make_polygons(thickness));
+        }
+    }
+    result
+}
+
 // Because the stripe straddles two lanes, it'll be partly hidden on one side. There are a bunch of
 // ways to work around this z-order issue. The current approach is to rely on the fact that
 // quadtrees return LaneIDs in order, and lanes are always created from left->right.
@@ -327,22 +441,25 @@ fn calculate_driving_lines(lane: &Lane, road: &Road) -> Vec<Polygon> {
     )
 }
 
-fn calculate_turn_markings(map: &Map, lane: &Lane) -> Vec<Polygon> {
+/// With `always`, mark every lane's turns, not just the ones that can't go everywhere
+fn calculate_turn_markings(map: &Map, lane: &Lane, always: bool) -> Vec<Polygon> {
     // Does this lane connect to every other possible outbound lane of the same type, excluding
     // U-turns to the same road? If so, then there's nothing unexpected to communicate.
     let i = map.get_i(lane.dst_i);
-    if i.outgoing_lanes.iter().all(|l| {
-        let l = map.get_l(*l);
-        l.lane_type != lane.lane_type
-            || l.id.road == lane.id.road
-            || map
-                .maybe_get_t(TurnID {
-                    parent: i.id,
-                    src: lane.id,
-                    dst: l.id,
-                })
-                .is_some()
-    }) {
+    if !always
+        && i.outgoing_lanes.iter().all(|l| {
+            let l = map.get_l(*l);
+            l.lane_type != lane.lane_type
+                || l.id.road == lane.id.road
+                || map
+                    .maybe_get_t(TurnID {
+                        parent: i.id,
+                        src: lane.id,
+                        dst: l.id,
+                    })
+                    .is_some()
+        })
+    {
         return Vec::new();
     }
 
@@ -474,6 +591,31 @@ fn calculate_turn_markings(map: &Map, lane: &Lane) -> Vec<Polygon> {
     results
 }
 
+/// A line across the end of the lane, where vehicles wait for a traffic signal or stop sign
+fn calculate_stop_line(map: &Map, lane: &Lane) -> Option<Polygon> {
+    let i = map.get_i(lane.dst_i);
+    let must_stop = match i.control {
+        IntersectionControl::Signalled => true,
+        IntersectionControl::Signed | IntersectionControl::Uncontrolled => map
+            .get_stop_sign(i.id)
+            .roads
+            .get(&lane.id.road)
+            .map(|ss| ss.control == ApproachControl::Stop)
+            .unwrap_or(false),
+        _ => false,
+    };
+    let thickness = Distance::meters(0.4);
+    if !must_stop || lane.length() <= thickness {
+        return None;
+    }
+    let (pt, angle) = lane
+        .lane_center_pts
+        .must_dist_along(lane.length() - thickness / 2.0);
+    // Reuse perp_line. Project away an arbitrary amount
+    let pt2 = pt.project_away(Distance::meters(1.0), angle);
+    Some(perp_line(Line::must_new(pt, pt2), lane.width).make_polygons(thickness))
+}
+
 fn calculate_one_way_markings(lane: &Lane, road: &Road) -> Vec<Tessellation> {
     let mut results = Vec::new();
     if road
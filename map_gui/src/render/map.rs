@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use abstutil::Timer;
 use geom::{Bounds, Distance, QuadTree, Tessellation};
 use map_model::{
-    AreaID, BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Road, RoadID, TransitStopID,
+    AreaID, BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Road, RoadID, RoadStructure,
+    TransitStopID,
 };
 use widgetry::{Color, Drawable, EventCtx, Fill, GeomBatch};
 
@@ -13,7 +14,8 @@ use crate::render::building::DrawBuilding;
 use crate::render::intersection::DrawIntersection;
 use crate::render::lane::DrawLane;
 use crate::render::parking_lot::DrawParkingLot;
-use crate::render::road::DrawRoad;
+use crate::render::road::{bridge_casing, DrawRoad};
+use crate::render::style::MapStyle;
 use crate::render::transit_stop::DrawTransitStop;
 use crate::render::{DrawArea, Renderable};
 use crate::{AppLike, ID};
@@ -37,6 +39,7 @@ pub struct DrawMap {
     pub show_zorder: isize,
 
     quadtree: QuadTree<ID>,
+    style: MapStyle,
 }
 
 impl DrawMap {
@@ -69,7 +72,7 @@ impl DrawMap {
             DrawMap::regenerate_unzoomed_layer(ctx, map, cs, opts, timer);
 
         let (buildings, draw_all_buildings, draw_all_building_outlines) =
-            DrawMap::regenerate_buildings(ctx, map, cs, opts, timer);
+            DrawMap::regenerate_buildings(ctx, map, cs, opts, &MapStyle::default(), timer);
 
         timer.start("make DrawParkingLot");
         let (parking_lots, draw_all_unzoomed_parking_lots) =
@@ -147,14 +150,48 @@ impl DrawMap {
 
             zorder_range: (low_z, high_z),
             show_zorder: high_z,
+            style: MapStyle::default(),
         }
     }
 
+    /// Restyle some roads and buildings in the unzoomed map, replacing any previous style.
+    pub fn set_style(ctx: &EventCtx, app: &mut dyn AppLike, style: MapStyle) {
+        let mut timer = Timer::throwaway();
+        let unzoomed = DrawMap::regenerate_unzoomed_layer_styled(
+            ctx,
+            app.map(),
+            app.cs(),
+            app.opts(),
+            &style,
+            &mut timer,
+        );
+        let (_, buildings, building_outlines) =
+            DrawMap::regenerate_buildings(ctx, app.map(), app.cs(), app.opts(), &style, &mut timer);
+
+        let draw_map = app.mut_draw_map();
+        draw_map.draw_all_unzoomed_roads_and_intersections = unzoomed;
+        draw_map.draw_all_buildings = buildings;
+        draw_map.draw_all_building_outlines = building_outlines;
+        draw_map.style = style;
+    }
+
+    /// Go back to drawing everything normally.
+    pub fn clear_style(ctx: &EventCtx, app: &mut dyn AppLike) {
+        if !app.draw_map().style.is_empty() {
+            DrawMap::set_style(ctx, app, MapStyle::default());
+        }
+    }
+
+    pub fn get_style(&self) -> &MapStyle {
+        &self.style
+    }
+
     pub fn regenerate_buildings(
         ctx: &EventCtx,
         map: &Map,
         cs: &ColorScheme,
         opts: &Options,
+        style: &MapStyle,
         timer: &mut Timer,
     ) -> (Vec<DrawBuilding>, Drawable, Drawable) {
         let mut buildings: Vec<DrawBuilding> = Vec::new();
@@ -173,6 +210,10 @@ impl DrawMap {
                 &mut all_building_outlines,
             ));
         }
+        // Cover up the normal color
+        for (b, color) in &style.buildings {
+            all_buildings.push(*color, map.get_b(*b).polygon.clone());
+        }
         timer.start("upload all buildings");
         let draw_all_buildings = all_buildings.upload(ctx);
         let draw_all_building_outlines = all_building_outlines.upload(ctx);
@@ -206,6 +247,17 @@ impl DrawMap {
         cs: &ColorScheme,
         opts: &Options,
         timer: &mut Timer,
+    ) -> Drawable {
+        DrawMap::regenerate_unzoomed_layer_styled(ctx, map, cs, opts, &MapStyle::default(), timer)
+    }
+
+    pub fn regenerate_unzoomed_layer_styled(
+        ctx: &EventCtx,
+        map: &Map,
+        cs: &ColorScheme,
+        opts: &Options,
+        style: &MapStyle,
+        timer: &mut Timer,
     ) -> Drawable {
         timer.start("generate unzoomed roads and intersections");
 
@@ -218,24 +270,42 @@ impl DrawMap {
         let mut unzoomed_pieces: Vec<(isize, Fill, Tessellation)> = Vec::new();
 
         for r in map.all_roads() {
-            let width = r.get_width();
-
+            let road_style = style.road(r.id);
+            let width = road_style.width.unwrap_or_else(|| r.get_width());
+
+            let mut color = if let Some(color) = road_style.color {
+                color
+            } else if r.is_light_rail() {
+                cs.light_rail_track
+            } else if r.is_cycleway() {
+                cs.unzoomed_cycleway
+            } else if r.is_footway() {
+                cs.unzoomed_footway
+            } else if r.is_private() && cs.private_road.is_some() {
+                cs.private_road.unwrap()
+            } else {
+                cs.unzoomed_road_surface(r.get_rank())
+            };
+            // Fade out anything underground
+            if r.structure() == RoadStructure::Tunnel {
+                color = color.alpha(0.5);
+            }
             unzoomed_pieces.push((
                 10 * r.zorder,
-                Fill::Color(if r.is_light_rail() {
-                    cs.light_rail_track
-                } else if r.is_cycleway() {
-                    cs.unzoomed_cycleway
-                } else if r.is_footway() {
-                    cs.unzoomed_footway
-                } else if r.is_private() && cs.private_road.is_some() {
-                    cs.private_road.unwrap()
-                } else {
-                    cs.unzoomed_road_surface(r.get_rank())
-                }),
+                Fill::Color(color),
                 r.center_pts.make_polygons(width).into(),
             ));
 
+            if r.structure() == RoadStructure::Bridge {
+                for p in bridge_casing(r) {
+                    unzoomed_pieces.push((
+                        10 * r.zorder + outline_z_offset,
+                        cs.bridge_casing.into(),
+                        p.into(),
+                    ));
+                }
+            }
+
             if cs.road_outlines {
                 // Draw a thick outline on the left and right
                 for pl in [
@@ -514,6 +584,22 @@ impl DrawMap {
         self.roads[road.id.0] = draw;
     }
 
+    /// The stop may have been added, moved, or removed
+    pub fn recreate_transit_stop(
+        &mut self,
+        ctx: &EventCtx,
+        id: TransitStopID,
+        map: &Map,
+        cs: &ColorScheme,
+    ) {
+        if let Some(stop) = map.maybe_get_ts(id) {
+            self.bus_stops
+                .insert(id, DrawTransitStop::new(ctx, stop, map, cs));
+        } else {
+            self.bus_stops.remove(&id);
+        }
+    }
+
     pub fn free_memory(&mut self) {
         // Clear the lazily evaluated zoomed-in details
         for r in &mut self.roads {
@@ -8,6 +8,8 @@ pub use crate::render::area::DrawArea;
 pub use crate::render::building::DrawBuilding;
 pub use crate::render::intersection::{calculate_corners, DrawIntersection};
 pub use crate::render::map::DrawMap;
+pub use crate::render::road::{traffic_calming_color, traffic_calming_shapes};
+pub use crate::render::style::{MapStyle, RoadStyle};
 pub use crate::render::turn::DrawMovement;
 use crate::{AppLike, ID};
 
@@ -18,6 +20,7 @@ mod lane;
 mod map;
 mod parking_lot;
 mod road;
+mod style;
 pub mod traffic_signal;
 mod transit_stop;
 mod turn;
@@ -1,7 +1,9 @@
 use std::cell::RefCell;
 
-use geom::{Bounds, Distance, Pt2D, Tessellation};
-use map_model::{Building, LaneType, Map, Road, RoadID, NORMAL_LANE_THICKNESS};
+use geom::{Bounds, Distance, Polygon, Pt2D, Tessellation};
+use map_model::{
+    Building, LaneType, Map, Road, RoadID, RoadStructure, TrafficCalmingType, NORMAL_LANE_THICKNESS,
+};
 use widgetry::{Color, Drawable, GeomBatch, GfxCtx, Line, Prerender, Text};
 
 use crate::colors::ColorSchemeChoice;
@@ -15,6 +17,7 @@ const LABEL_SCALE_FACTOR: f64 = 0.1;
 // Making the label follow the road's curvature usually looks better, but sometimes the letters
 // squish together, so keep this experiment disabled for now.
 const DRAW_CURVEY_LABEL: bool = false;
+const BRIDGE_CASING_THICKNESS: Distance = Distance::const_meters(1.5);
 
 pub struct DrawRoad {
     pub id: RoadID,
@@ -84,6 +87,16 @@ impl DrawRoad {
             batch = render_center_line(app, r, None);
         }
 
+        if r.structure() == RoadStructure::Bridge {
+            for p in bridge_casing(r) {
+                batch.push(app.cs().bridge_casing, p);
+            }
+        }
+
+        for (color, p) in traffic_calming_shapes(r) {
+            batch.push(color, p);
+        }
+
         // Driveways of connected buildings. These are grouped by road to limit what has to be
         // recalculated when road edits cause buildings to re-snap.
         for b in app.map().road_to_buildings(self.id) {
@@ -132,6 +145,78 @@ impl Renderable for DrawRoad {
     }
 }
 
+/// Bridges get a casing along both sides, to distinguish them from roads passing underneath. The
+/// casing is outside the road, so it doesn't matter if lanes are drawn on top.
+pub fn bridge_casing(r: &Road) -> Vec<Polygon> {
+    let offset = r.get_width() / 2.0 + BRIDGE_CASING_THICKNESS / 2.0;
+    [r.center_pts.shift_left(offset), r.center_pts.shift_right(offset)]
+        .into_iter()
+        .flatten()
+        .map(|pl| pl.make_polygons(BRIDGE_CASING_THICKNESS))
+        .collect()
+}
+
+/// Distinct markings for each kind of traffic calming along a road. Speed humps and raised tables
+/// are bands across the road; chicanes are build-outs alternating between each side.
+pub fn traffic_calming_shapes(r: &Road) -> Vec<(Color, Polygon)> {
+    let mut shapes = Vec::new();
+    let width = r.get_width();
+    for tc in &r.traffic_calming {
+        let color = traffic_calming_color(tc.calming_type);
+        match tc.calming_type {
+            TrafficCalmingType::SpeedHump | TrafficCalmingType::RaisedTable => {
+                let half_length = if tc.calming_type == TrafficCalmingType::SpeedHump {
+                    Distance::meters(1.0)
+                } else {
+                    Distance::meters(3.0)
+                };
+                let start = (tc.middle() - half_length).max(Distance::ZERO);
+                let end = (tc.middle() + half_length).min(r.length());
+                if let Ok(pl) = r.center_pts.maybe_exact_slice(start, end) {
+                    shapes.push((color, pl.make_polygons(width)));
+                    // Stripes make it visible against any road color
+                    shapes.extend(
+                        pl.dashed_lines(width, Distance::meters(0.3), Distance::meters(0.3))
+                            .into_iter()
+                            .map(|p| (Color::WHITE.alpha(0.6), p)),
+                    );
+                }
+            }
+            TrafficCalmingType::Chicane => {
+                let build_out = width / 3.0;
+                let offset = width / 2.0 - build_out / 2.0;
+                let step = Distance::meters(10.0);
+                let mut dist = tc.start;
+                let mut left = true;
+                while dist < tc.end {
+                    let next = (dist + step).min(tc.end);
+                    if let Ok(pl) = r.center_pts.maybe_exact_slice(dist, next) {
+                        let shifted = if left {
+                            pl.shift_left(offset)
+                        } else {
+                            pl.shift_right(offset)
+                        };
+                        if let Ok(pl) = shifted {
+                            shapes.push((color, pl.make_polygons(build_out)));
+                        }
+                    }
+                    left = !left;
+                    dist = next;
+                }
+            }
+        }
+    }
+    shapes
+}
+
+pub fn traffic_calming_color(calming_type: TrafficCalmingType) -> Color {
+    match calming_type {
+        TrafficCalmingType::SpeedHump => Color::hex("#F2C94C"),
+        TrafficCalmingType::RaisedTable => Color::hex("#B85C38"),
+        TrafficCalmingType::Chicane => Color::hex("#6A8D3A"),
+    }
+}
+
 /// If `text_width` is defined, don't draw the center line in the middle of the road for this
 /// amount of space
 fn render_center_line(app: &dyn AppLike, r: &Road, text_width: Option<Distance>) -> GeomBatch {
@@ -143,12 +228,15 @@ fn render_center_line(app: &dyn AppLike, r: &Road, text_width: Option<Distance>)
     let mut batch = GeomBatch::new();
 
     // Draw a center line every time two driving/bike/bus lanes of opposite direction are adjacent.
+    // Delivery lanes across a pedestrian street aren't marked.
+    let pedestrian_street = r.is_pedestrian_street();
     let mut width = Distance::ZERO;
     for pair in r.lanes.windows(2) {
         width += pair[0].width;
         if pair[0].dir != pair[1].dir
             && pair[0].lane_type.is_for_moving_vehicles()
             && pair[1].lane_type.is_for_moving_vehicles()
+            && !(pedestrian_street && pair[0].is_driving())
         {
             let pl = r.shift_from_left_side(width).unwrap();
             if let Some(text_width) = text_width {
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use geom::Distance;
+use map_model::{Building, BuildingID, Map, Road, RoadID};
+use widgetry::Color;
+
+/// Overrides how individual roads and buildings look in the unzoomed map. Layers that just want
+/// to recolor some objects can set this through `DrawMap::set_style`, instead of building and
+/// drawing their own copy of the whole map on top.
+#[derive(Clone, Default)]
+pub struct MapStyle {
+    pub roads: HashMap<RoadID, RoadStyle>,
+    pub buildings: HashMap<BuildingID, Color>,
+}
+
+/// Anything left as `None` is drawn normally.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoadStyle {
+    pub color: Option<Color>,
+    pub width: Option<Distance>,
+}
+
+impl MapStyle {
+    pub fn new() -> MapStyle {
+        MapStyle::default()
+    }
+
+    /// Ask for the style of every road and building. Returning `None` leaves the object alone.
+    pub fn from_fns<R: Fn(&Road) -> Option<RoadStyle>, B: Fn(&Building) -> Option<Color>>(
+        map: &Map,
+        road_style: R,
+        building_color: B,
+    ) -> MapStyle {
+        let mut style = MapStyle::new();
+        for r in map.all_roads() {
+            if let Some(x) = road_style(r) {
+                style.roads.insert(r.id, x);
+            }
+        }
+        for b in map.all_buildings() {
+            if let Some(color) = building_color(b) {
+                style.buildings.insert(b.id, color);
+            }
+        }
+        style
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roads.is_empty() && self.buildings.is_empty()
+    }
+
+    pub fn set_road_color(&mut self, r: RoadID, color: Color) {
+        self.roads.entry(r).or_default().color = Some(color);
+    }
+
+    pub fn set_road_width(&mut self, r: RoadID, width: Distance) {
+        self.roads.entry(r).or_default().width = Some(width);
+    }
+
+    pub fn set_building_color(&mut self, b: BuildingID, color: Color) {
+        self.buildings.insert(b, color);
+    }
+
+    pub(crate) fn road(&self, r: RoadID) -> RoadStyle {
+        self.roads.get(&r).cloned().unwrap_or_default()
+    }
+}
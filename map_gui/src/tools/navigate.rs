@@ -1,15 +1,26 @@
 use std::collections::HashSet;
 
-use map_model::RoadID;
+use geom::Pt2D;
+use map_model::{osm, Map, RoadID};
 use widgetry::{
-    Autocomplete, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome,
+    Autocomplete, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, Line, Outcome,
     Panel, State, Text, Transition, Widget,
 };
 
 use crate::tools::grey_out_map;
 use crate::{AppLike, ID};
 
+/// Something the search box can find. Streets lead to picking a cross street; everything else is
+/// warped to directly.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Found {
+    Street(RoadID),
+    Object(ID),
+}
+
 // TODO Canonicalize names, handling abbreviations like east/e and street/st
+/// Search for streets by name, intersections by the streets meeting there, buildings by address,
+/// name, or amenity, and any object by its OSM ID, like "way/123" or "node/456".
 pub struct Navigator {
     panel: Panel,
     target_zoom: f64,
@@ -29,24 +40,12 @@ impl Navigator {
             target_zoom,
             panel: Panel::new_builder(Widget::col(vec![
                 Widget::row(vec![
-                    Line("Enter a street name").small_heading().into_widget(ctx),
+                    Line("Search for a street, place, or OSM ID")
+                        .small_heading()
+                        .into_widget(ctx),
                     ctx.style().btn_close_widget(ctx),
                 ]),
-                Autocomplete::new_widget(
-                    ctx,
-                    app.map()
-                        .all_roads()
-                        .iter()
-                        .map(|r| (r.get_name(app.opts().language.as_ref()), r.id))
-                        .collect(),
-                    10,
-                )
-                .named("street"),
-                ctx.style()
-                    .btn_outline
-                    .text("Search by business name or address")
-                    .hotkey(Key::Tab)
-                    .build_def(ctx),
+                Autocomplete::new_widget(ctx, search_entries(app), 10).named("search"),
             ]))
             .build(ctx),
         })
@@ -60,21 +59,44 @@ impl<A: AppLike + 'static> State<A> for Navigator {
                 "close" => {
                     return Transition::Pop;
                 }
-                "Search by business name or address" => {
-                    return Transition::Replace(SearchBuildings::new_state(
-                        ctx,
-                        app,
-                        self.target_zoom,
-                    ));
-                }
                 _ => unreachable!(),
             }
         }
-        if let Some(roads) = self.panel.autocomplete_done("street") {
-            if roads.is_empty() {
-                return Transition::Pop;
+        if let Some(found) = self.panel.autocomplete_done::<Found>("search") {
+            let mut roads = Vec::new();
+            let mut objects = Vec::new();
+            for x in found {
+                match x {
+                    Found::Street(r) => roads.push(r),
+                    Found::Object(id) => objects.push(id),
+                }
             }
-            return Transition::Replace(CrossStreet::new_state(ctx, app, roads, self.target_zoom));
+            if objects.is_empty() {
+                if roads.is_empty() {
+                    return Transition::Pop;
+                }
+                return Transition::Replace(CrossStreet::new_state(
+                    ctx,
+                    app,
+                    roads,
+                    self.target_zoom,
+                ));
+            }
+
+            // One OSM way or relation can cover many roads. Warp to the middle of all of them, but
+            // only select a single object.
+            let pts: Vec<Pt2D> = objects.iter().map(|id| center(app.map(), id)).collect();
+            let id = if objects.len() == 1 {
+                objects.pop()
+            } else {
+                None
+            };
+            return Transition::Replace(app.make_warper(
+                ctx,
+                Pt2D::center(&pts),
+                Some(self.target_zoom),
+                id,
+            ));
         }
 
         if self.panel.clicked_outside(ctx) {
@@ -94,6 +116,90 @@ impl<A: AppLike + 'static> State<A> for Navigator {
     }
 }
 
+fn search_entries<A: AppLike>(app: &A) -> Vec<(String, Found)> {
+    let map = app.map();
+    let lang = app.opts().language.as_ref();
+    let mut entries = Vec::new();
+
+    for r in map.all_roads() {
+        let name = r.get_name(lang);
+        entries.push((name.clone(), Found::Street(r.id)));
+        // Roads have no info panel of their own, so select a lane instead
+        let id = Found::Object(ID::Lane(r.lanes[0].id));
+        entries.push((
+            format!("OSM way/{} ({})", r.orig_id.osm_way_id.0, name),
+            id.clone(),
+        ));
+        for rel in &r.osm_relations {
+            let label = match map.get_osm_relation(*rel).and_then(|x| x.name.as_ref()) {
+                Some(rel_name) => format!("OSM relation/{} ({})", rel.0, rel_name),
+                None => format!("OSM relation/{}", rel.0),
+            };
+            entries.push((label, id.clone()));
+        }
+    }
+
+    for i in map.all_intersections() {
+        let id = Found::Object(ID::Intersection(i.id));
+        let name = i.name(lang, map);
+        // Dead-ends would just match the street's name
+        if i.roads.len() > 1 {
+            entries.push((name.clone(), id.clone()));
+        }
+        entries.push((format!("OSM node/{} ({})", i.orig_id.0, name), id));
+    }
+
+    for b in map.all_buildings() {
+        let id = Found::Object(ID::Building(b.id));
+        if !b.address.starts_with("???") {
+            entries.push((b.address.clone(), id.clone()));
+        }
+        if let Some(ref names) = b.name {
+            entries.push((names.get(lang).to_string(), id.clone()));
+        }
+        for a in &b.amenities {
+            entries.push((
+                format!("{} (at {})", a.names.get(lang), b.address),
+                id.clone(),
+            ));
+        }
+        entries.push((
+            format!("OSM {} ({})", describe_osm_id(b.orig_id), b.address),
+            id,
+        ));
+    }
+
+    for a in map.all_areas() {
+        if let Some(osm_id) = a.osm_id {
+            entries.push((
+                format!("OSM {} ({:?})", describe_osm_id(osm_id), a.area_type),
+                Found::Object(ID::Area(a.id)),
+            ));
+        }
+    }
+
+    entries
+}
+
+/// Like "way/123", the form used in openstreetmap.org URLs
+fn describe_osm_id(id: osm::OsmID) -> String {
+    match id {
+        osm::OsmID::Node(n) => format!("node/{}", n.0),
+        osm::OsmID::Way(w) => format!("way/{}", w.0),
+        osm::OsmID::Relation(r) => format!("relation/{}", r.0),
+    }
+}
+
+fn center(map: &Map, id: &ID) -> Pt2D {
+    match id {
+        ID::Lane(l) => map.get_l(*l).lane_center_pts.middle(),
+        ID::Intersection(i) => map.get_i(*i).polygon.center(),
+        ID::Building(b) => map.get_b(*b).label_center,
+        ID::Area(a) => map.get_a(*a).polygon.center(),
+        _ => unreachable!(),
+    }
+}
+
 struct CrossStreet {
     first: Vec<RoadID>,
     panel: Panel,
@@ -218,114 +324,3 @@ impl<A: AppLike + 'static> State<A> for CrossStreet {
         self.panel.draw(g);
     }
 }
-
-struct SearchBuildings {
-    panel: Panel,
-    target_zoom: f64,
-}
-
-impl SearchBuildings {
-    fn new_state<A: AppLike + 'static>(
-        ctx: &mut EventCtx,
-        app: &A,
-        target_zoom: f64,
-    ) -> Box<dyn State<A>> {
-        Box::new(SearchBuildings {
-            target_zoom,
-            panel: Panel::new_builder(Widget::col(vec![
-                Widget::row(vec![
-                    Line("Enter a business name or address")
-                        .small_heading()
-                        .into_widget(ctx),
-                    ctx.style().btn_close_widget(ctx),
-                ]),
-                Autocomplete::new_widget(
-                    ctx,
-                    app.map()
-                        .all_buildings()
-                        .iter()
-                        .flat_map(|b| {
-                            let mut results = Vec::new();
-                            if !b.address.starts_with("???") {
-                                results.push((b.address.clone(), b.id));
-                            }
-                            if let Some(ref names) = b.name {
-                                results.push((
-                                    names.get(app.opts().language.as_ref()).to_string(),
-                                    b.id,
-                                ));
-                            }
-                            for a in &b.amenities {
-                                results.push((
-                                    format!(
-                                        "{} (at {})",
-                                        a.names.get(app.opts().language.as_ref()),
-                                        b.address
-                                    ),
-                                    b.id,
-                                ));
-                            }
-                            results
-                        })
-                        .collect(),
-                    10,
-                )
-                .named("bldg"),
-                ctx.style()
-                    .btn_outline
-                    .text("Search for streets")
-                    .hotkey(Key::Tab)
-                    .build_def(ctx),
-            ]))
-            .build(ctx),
-        })
-    }
-}
-
-impl<A: AppLike + 'static> State<A> for SearchBuildings {
-    fn event(&mut self, ctx: &mut EventCtx, app: &mut A) -> Transition<A> {
-        if let Outcome::Clicked(x) = self.panel.event(ctx) {
-            match x.as_ref() {
-                "close" => {
-                    return Transition::Pop;
-                }
-                "Search for streets" => {
-                    return Transition::Replace(Navigator::new_state_with_target_zoom(
-                        ctx,
-                        app,
-                        self.target_zoom,
-                    ));
-                }
-                _ => unreachable!(),
-            }
-        }
-        if let Some(bldgs) = self.panel.autocomplete_done("bldg") {
-            if bldgs.is_empty() {
-                return Transition::Pop;
-            }
-            let b = app.map().get_b(bldgs[0]);
-            let pt = b.label_center;
-            return Transition::Replace(app.make_warper(
-                ctx,
-                pt,
-                Some(self.target_zoom),
-                Some(ID::Building(bldgs[0])),
-            ));
-        }
-
-        if self.panel.clicked_outside(ctx) {
-            return Transition::Pop;
-        }
-
-        Transition::Keep
-    }
-
-    fn draw_baselayer(&self) -> DrawBaselayer {
-        DrawBaselayer::PreviousState
-    }
-
-    fn draw(&self, g: &mut GfxCtx, app: &A) {
-        grey_out_map(g, app);
-        self.panel.draw(g);
-    }
-}
@@ -128,6 +128,11 @@ impl<A: AppLike + 'static> TitleScreen<A> {
                         .text("Community proposals")
                         .tooltip("Try out proposals for changing different cities")
                         .build_def(ctx),
+                    ctx.style()
+                        .btn_outline
+                        .text("Load saved session")
+                        .tooltip("Resume a simulation session saved from the sandbox")
+                        .build_def(ctx),
                     ctx.style()
                         .btn_outline
                         .text("Advanced tools")
@@ -296,6 +301,9 @@ impl<A: AppLike + 'static> SimpleState<A> for TitleScreen<A> {
                 open_browser("https://actdev.cyipt.bike");
                 Transition::Keep
             }
+            "Load saved session" => {
+                self.run(ctx, app, Executable::ABStreet, vec!["--load-session"])
+            }
             "Advanced tools" => self.run(ctx, app, Executable::ABStreet, vec!["--devtools"]),
             "About" => Transition::Push(PopupMsg::new_state(
                 ctx,
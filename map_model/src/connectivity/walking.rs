@@ -1,4 +1,6 @@
-use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+
+use petgraph::graphmap::DiGraphMap;
 
 use abstutil::{MultiMap, PriorityQueueItem};
 use geom::{Duration, Speed};
@@ -129,7 +131,9 @@ pub fn all_walking_costs_from(
         };
         let lane = map.get_l(r.must_get_sidewalk(map));
         // Cross the lane
-        if opts.allow_shoulders || lane.lane_type != LaneType::Shoulder {
+        if (opts.allow_shoulders || lane.lane_type != LaneType::Shoulder)
+            && lane.walking_closure(map).is_none()
+        {
             let sidewalk_len = lane.length();
             let step = if is_dst_i {
                 PathStep::ContraflowLane(lane.id)
@@ -168,7 +172,7 @@ pub fn all_walking_costs_from(
         }
         // All turns from the lane
         for turn in map.get_turns_for(lane.id, PathConstraints::Pedestrian) {
-            if (turn.id.parent == lane.dst_i) != is_dst_i {
+            if (turn.id.parent == lane.dst_i) != is_dst_i || turn.walking_closure(map).is_some() {
                 continue;
             }
             queue.push(PriorityQueueItem {
@@ -190,3 +194,58 @@ pub fn all_walking_costs_from(
 
     results
 }
+
+/// Finds buildings that can't reach most of the map on foot because of sidewalk or crossing
+/// closures. Buildings that're disconnected even without closures aren't included. If
+/// `wheelchair`, steps and crossings with a raised kerb are avoided too, like wheelchair routing
+/// does.
+pub fn find_cut_off_buildings(map: &Map, wheelchair: bool) -> BTreeSet<BuildingID> {
+    let before = main_walking_component(map, wheelchair, false);
+    let after = main_walking_component(map, wheelchair, true);
+    map.all_buildings()
+        .iter()
+        .filter(|b| {
+            let node = WalkingNode::closest(b.sidewalk_pos, map);
+            before.contains(&node) && !after.contains(&node)
+        })
+        .map(|b| b.id)
+        .collect()
+}
+
+/// The largest group of sidewalk endpoints connected to each other
+fn main_walking_component(map: &Map, wheelchair: bool, closures: bool) -> HashSet<WalkingNode> {
+    let mut graph = DiGraphMap::new();
+    for l in map.all_lanes() {
+        if !l.is_walkable()
+            || (wheelchair && map.get_r(l.id.road).is_steps())
+            || (closures && l.walking_closure(map).is_some())
+        {
+            continue;
+        }
+        let n1 = WalkingNode::SidewalkEndpoint(l.get_directed_parent(), false);
+        let n2 = WalkingNode::SidewalkEndpoint(l.get_directed_parent(), true);
+        graph.add_edge(n1, n2, ());
+        graph.add_edge(n2, n1, ());
+    }
+    for t in map.all_turns() {
+        if !t.between_sidewalks()
+            || (wheelchair && !t.usable_by_wheelchair(map))
+            || (closures && t.walking_closure(map).is_some())
+        {
+            continue;
+        }
+        let src = map.get_l(t.id.src);
+        let dst = map.get_l(t.id.dst);
+        let from =
+            WalkingNode::SidewalkEndpoint(src.get_directed_parent(), src.dst_i == t.id.parent);
+        let to = WalkingNode::SidewalkEndpoint(dst.get_directed_parent(), dst.dst_i == t.id.parent);
+        graph.add_edge(from, to, ());
+        graph.add_edge(to, from, ());
+    }
+    petgraph::algo::kosaraju_scc(&graph)
+        .into_iter()
+        .max_by_key(|c| c.len())
+        .unwrap_or_default()
+        .into_iter()
+        .collect()
+}
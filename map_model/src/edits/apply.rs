@@ -8,7 +8,7 @@ use crate::make::{match_points_to_lanes, snap_driveway, trim_path};
 use crate::{
     connectivity, BuildingID, ControlStopSign, ControlTrafficSignal, EditCmd, EditEffects,
     EditIntersectionControl, IntersectionControl, IntersectionID, LaneSpec, Map, MapEdits,
-    Movement, ParkingLotID, PathConstraints, Pathfinder, RoadID, Zone,
+    Movement, ParkingLotID, PathConstraints, Pathfinder, RoadID, TransitStop, Zone,
 };
 
 impl Map {
@@ -44,6 +44,7 @@ impl Map {
             added_turns: BTreeSet::new(),
             deleted_turns: BTreeSet::new(),
             changed_parking_lots: BTreeSet::new(),
+            changed_transit_stops: BTreeSet::new(),
             modified_lanes: BTreeSet::new(),
         };
 
@@ -195,8 +196,12 @@ impl EditCmd {
                     return;
                 }
 
+                // Lanes are recreated using the new extents
+                map.roads[r.0].lane_extents = new.lane_extents.clone();
                 if old_state.lanes_ltr != new.lanes_ltr {
                     modify_lanes(map, *r, new.lanes_ltr.clone(), effects);
+                } else if old_state.lane_extents != new.lane_extents {
+                    map.roads[r.0].refresh_lane_extents();
                 }
                 let road = &mut map.roads[r.0];
                 road.speed_limit = new.speed_limit;
@@ -205,6 +210,14 @@ impl EditCmd {
                 road.crossings = new.crossings.clone();
                 road.turn_restrictions = new.turn_restrictions.clone();
                 road.complicated_turn_restrictions = new.complicated_turn_restrictions.clone();
+                road.kerb_uses = new.kerb_uses.clone();
+                road.bus_lane_enforcement = new.bus_lane_enforcement;
+                road.speed_enforcement = new.speed_enforcement;
+                road.pricing = new.pricing.clone();
+                road.hov_min_occupancy = new.hov_min_occupancy;
+                road.hgv = new.hgv.clone();
+                road.traffic_calming = new.traffic_calming.clone();
+                road.sidewalk_closures = new.sidewalk_closures.clone();
 
                 effects.changed_roads.insert(road.id);
                 // TODO If lanes_ltr didn't change, can we skip some of this?
@@ -236,6 +249,8 @@ impl EditCmd {
                     return;
                 }
                 map.intersections[i.0].modal_filter = new.modal_filter.clone();
+                map.intersections[i.0].corner_design = new.corner_design.clone();
+                map.intersections[i.0].closed_crossings = new.closed_crossings.clone();
 
                 map.stop_signs.remove(i);
                 map.traffic_signals.remove(i);
@@ -250,10 +265,12 @@ impl EditCmd {
                         if old.control == EditIntersectionControl::Closed {
                             recalculate_turns(*i, map, effects);
                         }
-                        map.traffic_signals.insert(
-                            *i,
-                            ControlTrafficSignal::import(raw_ts.clone(), *i, map).unwrap(),
-                        );
+                        let mut ts = ControlTrafficSignal::import(raw_ts.clone(), *i, map).unwrap();
+                        ts.transit_priority = new.transit_priority.clone();
+                        ts.leading_pedestrian_interval = new.leading_pedestrian_interval;
+                        ts.actuation = new.actuation.clone();
+                        ts.cluster = new.signal_cluster.clone();
+                        map.traffic_signals.insert(*i, ts);
                     }
                     EditIntersectionControl::Closed => {
                         map.intersections[i.0].control = IntersectionControl::Construction;
@@ -273,6 +290,43 @@ impl EditCmd {
             EditCmd::ChangeRouteSchedule { id, new, .. } => {
                 map.transit_routes[id.0].spawn_times = new.clone();
             }
+            EditCmd::ChangeBikeParking { b, new, .. } => {
+                map.buildings[b.0].bike_parking = *new;
+            }
+            EditCmd::ChangeTransitStop { id, new, .. } => {
+                effects.changed_transit_stops.insert(*id);
+                if let Some(new) = new {
+                    // Validation guarantees the road has a lane buses can use
+                    let driving_lane = map
+                        .get_r(id.road)
+                        .find_closest_lane(new.sidewalk_pos.lane(), |l| {
+                            PathConstraints::Bus.can_use(l, map)
+                        })
+                        .unwrap();
+                    let driving_pos = new.sidewalk_pos.equiv_pos(driving_lane, map);
+                    map.mut_road(id.road).transit_stops.insert(*id);
+                    map.transit_stops.insert(
+                        *id,
+                        TransitStop {
+                            id: *id,
+                            name: new.name.clone(),
+                            gtfs_id: new.gtfs_id.clone(),
+                            driving_pos,
+                            sidewalk_pos: new.sidewalk_pos,
+                            is_train_stop: false,
+                        },
+                    );
+                } else {
+                    map.mut_road(id.road).transit_stops.remove(id);
+                    map.transit_stops.remove(id);
+                }
+            }
+            EditCmd::ChangeRouteStops { id, new, .. } => {
+                map.transit_routes[id.0].stops = new.clone();
+            }
+            EditCmd::ChangeRouteAlignment { id, new, .. } => {
+                map.transit_routes[id.0].alignment = new.clone();
+            }
         }
     }
 
@@ -293,6 +347,26 @@ impl EditCmd {
                 old: new,
                 new: old,
             },
+            EditCmd::ChangeBikeParking { b, old, new } => EditCmd::ChangeBikeParking {
+                b,
+                old: new,
+                new: old,
+            },
+            EditCmd::ChangeTransitStop { id, old, new } => EditCmd::ChangeTransitStop {
+                id,
+                old: new,
+                new: old,
+            },
+            EditCmd::ChangeRouteStops { id, old, new } => EditCmd::ChangeRouteStops {
+                id,
+                old: new,
+                new: old,
+            },
+            EditCmd::ChangeRouteAlignment { id, old, new } => EditCmd::ChangeRouteAlignment {
+                id,
+                old: new,
+                new: old,
+            },
         }
     }
 }
@@ -315,6 +389,7 @@ fn recalculate_turns(id: IntersectionID, map: &mut Map, effects: &mut EditEffect
     }
 
     if i.is_closed() {
+        i.closed_crossings.clear();
         return;
     }
 
@@ -325,6 +400,10 @@ fn recalculate_turns(id: IntersectionID, map: &mut Map, effects: &mut EditEffect
             effects.added_turns.insert(t.id);
             i.turns.push(t);
         }
+        // Crossings might've disappeared
+        let turns = &i.turns;
+        i.closed_crossings
+            .retain(|t, _| turns.iter().any(|turn| turn.id == *t));
     }
     let movements = Movement::for_i(id, map);
     let i = &mut map.intersections[id.0];
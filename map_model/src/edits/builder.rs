@@ -0,0 +1,391 @@
+//! A stable way for external tools to generate edits without going through the UI -- for example,
+//! "add cycle lanes to all of these roads."
+//!
+//! From Rust, use `EditsBuilder`. Each change is checked against the map immediately, and
+//! `build_permanent` produces the same JSON format that the UI saves.
+//!
+//! Tools in other languages can write that JSON directly. The format is:
+//!
+//! ```json
+//! {
+//!   "map_name": { "city": { "country": "us", "city": "seattle" }, "map": "montlake" },
+//!   "edits_name": "cycle lanes on 23rd",
+//!   "version": 16,
+//!   "commands": [
+//!     { "ChangeRoad": { "r": { "osm_way_id": 123, "i1": 456, "i2": 789 }, "old": ..., "new": ... } },
+//!     { "ChangeIntersection": { "i": 456, "old": ..., "new": ... } },
+//!     { "ChangeRouteSchedule": { "gtfs_id": "...", "old": [...], "new": [...] } },
+//!     { "ChangeBikeParking": { "b": { "Way": 123 }, "old": 0, "new": 10 } },
+//!     { "ChangeTransitStop": { "id": { "r": ..., "idx": 0 }, "old": ..., "new": ... } },
+//!     { "ChangeRouteStops": { "gtfs_id": "...", "old": [...], "new": [...] } },
+//!     { "ChangeRouteAlignment": { "gtfs_id": "...", "old": [], "new": [[{ "osm_way_id": ... }, "Fwd"], ...] } }
+//!   ],
+//!   "proposal_description": [],
+//!   "proposal_link": null
+//! }
+//! ```
+//!
+//! Roads are identified by an OSM way and the OSM nodes at either end; intersections by OSM node;
+//! buildings by their OSM way or relation; bus stops by their road and a number unique along it.
+//! `old` and `new` for roads are `EditRoad`s, serialized field-by-field. Distances, speeds, and
+//! other quantities are fixed-point integers: the value in meters (or meters per second) times
+//! 10,000. Easiest is to start from a file saved by the UI and modify it. Older versions are
+//! upgraded automatically when loading.
+//!
+//! Use `PermanentMapEdits::validate` (or `cli validate-edits`) to check a file. Every problem
+//! found refers to the position of the offending command.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{bail, Result};
+
+use geom::{Distance, Speed};
+
+use super::perma::PermanentMapEdits;
+use crate::{
+    BufferType, BusLaneEnforcement, ControlStopSign, EditCmd, EditIntersection,
+    EditIntersectionControl, EditRoad, EditTransitStop, IntersectionID, LaneSpec, LaneType, Map,
+    MapEdits, OriginalRoad, PathConstraints, RoadID, RoadPricing, TransitStopID,
+};
+
+/// Accumulates changes to roads and intersections, checking each one against the map.
+pub struct EditsBuilder<'a> {
+    map: &'a Map,
+    edits: MapEdits,
+    // Changes to the same object are merged, so each appears in one command
+    roads: BTreeMap<RoadID, EditRoad>,
+    intersections: BTreeMap<IntersectionID, EditIntersection>,
+}
+
+impl<'a> EditsBuilder<'a> {
+    /// Start a new set of edits on top of whatever edits the map already has applied.
+    pub fn new(map: &'a Map, edits_name: &str) -> EditsBuilder<'a> {
+        let mut edits = map.new_edits();
+        edits.edits_name = edits_name.to_string();
+        EditsBuilder {
+            map,
+            edits,
+            roads: BTreeMap::new(),
+            intersections: BTreeMap::new(),
+        }
+    }
+
+    /// Mark these edits as a proposal. The first line of the description is the title.
+    pub fn proposal(&mut self, description: Vec<String>, link: Option<String>) -> &mut Self {
+        self.edits.proposal_description = description;
+        self.edits.proposal_link = link;
+        self
+    }
+
+    /// Change anything about a road. If the result is invalid, nothing changes.
+    pub fn change_road<F: FnOnce(&mut EditRoad)>(&mut self, r: RoadID, f: F) -> Result<&mut Self> {
+        if r.0 >= self.map.all_roads().len() {
+            bail!("{} doesn't exist", r);
+        }
+        let mut new = self
+            .roads
+            .get(&r)
+            .cloned()
+            .unwrap_or_else(|| self.map.get_r_edit(r));
+        f(&mut new);
+        validate_road(self.map, r, &new)?;
+        self.roads.insert(r, new);
+        Ok(self)
+    }
+
+    /// Like `change_road`, but refer to the road by OSM IDs.
+    pub fn change_osm_road<F: FnOnce(&mut EditRoad)>(
+        &mut self,
+        r: OriginalRoad,
+        f: F,
+    ) -> Result<&mut Self> {
+        let r = self.map.find_r_by_osm_id(r)?;
+        self.change_road(r, f)
+    }
+
+    /// Add bike lanes to both sides of a road, taking space from parking or driving lanes. Returns
+    /// false if the road isn't wide enough or already has bike lanes.
+    pub fn add_bike_lanes(&mut self, r: RoadID, buffer: Option<BufferType>) -> Result<bool> {
+        let driving_side = self.map.get_config().driving_side;
+        let before = self
+            .roads
+            .get(&r)
+            .map(|x| x.lanes_ltr.clone())
+            .unwrap_or_else(|| self.map.get_r(r).lane_specs());
+        let mut after = before.clone();
+        LaneSpec::maybe_add_bike_lanes(&mut after, buffer, driving_side);
+        if before == after {
+            return Ok(false);
+        }
+        self.change_road(r, |new| {
+            new.lanes_ltr = after;
+        })?;
+        Ok(true)
+    }
+
+    pub fn set_speed_limit(&mut self, r: RoadID, speed_limit: Speed) -> Result<&mut Self> {
+        self.change_road(r, |new| {
+            new.speed_limit = speed_limit;
+        })
+    }
+
+    /// Change anything about an intersection. If the result is invalid, nothing changes.
+    pub fn change_intersection<F: FnOnce(&mut EditIntersection)>(
+        &mut self,
+        i: IntersectionID,
+        f: F,
+    ) -> Result<&mut Self> {
+        if i.0 >= self.map.all_intersections().len() {
+            bail!("{} doesn't exist", i);
+        }
+        let mut new = self
+            .intersections
+            .get(&i)
+            .cloned()
+            .unwrap_or_else(|| self.map.get_i_edit(i));
+        f(&mut new);
+        validate_intersection(self.map, i, &new)?;
+        self.intersections.insert(i, new);
+        Ok(self)
+    }
+
+    /// Close an intersection for construction.
+    pub fn close_intersection(&mut self, i: IntersectionID) -> Result<&mut Self> {
+        self.change_intersection(i, |new| {
+            new.control = EditIntersectionControl::Closed;
+        })
+    }
+
+    /// Produce the edits, ready for `Map::must_apply_edits`.
+    pub fn build(mut self) -> MapEdits {
+        for (r, new) in self.roads {
+            let old = self.map.get_r_edit(r);
+            if old != new {
+                self.edits
+                    .commands
+                    .push(EditCmd::ChangeRoad { r, old, new });
+            }
+        }
+        for (i, new) in self.intersections {
+            let old = self.map.get_i_edit(i);
+            if old != new {
+                self.edits
+                    .commands
+                    .push(EditCmd::ChangeIntersection { i, old, new });
+            }
+        }
+        self.edits
+    }
+
+    /// Produce the edits in the format saved to files.
+    pub fn build_permanent(self) -> PermanentMapEdits {
+        let map = self.map;
+        self.build().to_permanent(map)
+    }
+}
+
+/// One command in a set of edits that can't be used
+#[derive(Debug)]
+pub struct InvalidCommand {
+    /// The position in the list of commands, starting from 0
+    pub idx: usize,
+    pub error: String,
+}
+
+impl fmt::Display for InvalidCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "command {}: {}", self.idx, self.error)
+    }
+}
+
+/// Check that a command makes sense for this map, beyond just referring to objects that exist.
+pub fn validate_cmd(map: &Map, cmd: &EditCmd) -> Result<()> {
+    match cmd {
+        EditCmd::ChangeRoad { r, new, .. } => validate_road(map, *r, new),
+        EditCmd::ChangeIntersection { i, new, .. } => validate_intersection(map, *i, new),
+        EditCmd::ChangeRouteSchedule { new, .. } => {
+            if new.windows(2).any(|pair| pair[0] > pair[1]) {
+                bail!("route departure times must be sorted");
+            }
+            Ok(())
+        }
+        // Any number of spots is fine
+        EditCmd::ChangeBikeParking { .. } => Ok(()),
+        EditCmd::ChangeTransitStop { id, new, .. } => validate_transit_stop(map, *id, new.as_ref()),
+        EditCmd::ChangeRouteStops { id, new, .. } => {
+            if map.get_tr(*id).route_type != PathConstraints::Bus {
+                bail!("{} isn't a bus route; only those can change stops", id);
+            }
+            if new.is_empty() {
+                bail!("{} must serve at least one stop", id);
+            }
+            Ok(())
+        }
+        EditCmd::ChangeRouteAlignment { id, new, .. } => {
+            if map.get_tr(*id).route_type != PathConstraints::Bus {
+                bail!("{} isn't a bus route; only those can be rerouted", id);
+            }
+            for pair in new.windows(2) {
+                if pair[0].dst_i(map) != pair[1].src_i(map) {
+                    bail!("{} jumps from {} to {}", id, pair[0], pair[1]);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn validate_road(map: &Map, r: RoadID, edit: &EditRoad) -> Result<()> {
+    let length = map.get_r(r).length();
+    if edit.lanes_ltr.is_empty() {
+        bail!("{} must have at least one lane", r);
+    }
+    for (idx, spec) in edit.lanes_ltr.iter().enumerate() {
+        if spec.width <= Distance::ZERO {
+            bail!("lane {} on {} has a width of {}", idx, r, spec.width);
+        }
+    }
+    if edit.speed_limit <= Speed::ZERO {
+        bail!(
+            "{} has a speed limit of {} m/s",
+            r,
+            edit.speed_limit.inner_meters_per_second()
+        );
+    }
+    if let Some(ref filter) = edit.modal_filter {
+        if filter.dist < Distance::ZERO || filter.dist > length {
+            bail!(
+                "the modal filter on {} is {} along, but the road is only {} long",
+                r,
+                filter.dist,
+                length
+            );
+        }
+    }
+    for seg in &edit.kerb_uses {
+        if seg.start > seg.end || seg.start < Distance::ZERO || seg.end > length {
+            bail!(
+                "kerb segment from {} to {} doesn't fit along {}, which is {} long",
+                seg.start,
+                seg.end,
+                r,
+                length
+            );
+        }
+    }
+    for tc in &edit.traffic_calming {
+        if tc.start > tc.end || tc.start < Distance::ZERO || tc.end > length {
+            bail!(
+                "{} from {} to {} doesn't fit along {}, which is {} long",
+                tc.calming_type,
+                tc.start,
+                tc.end,
+                r,
+                length
+            );
+        }
+    }
+    if let BusLaneEnforcement::ViolationPct(pct) = edit.bus_lane_enforcement {
+        if pct > 100 {
+            bail!("{} has bus lane violations from {}% of drivers", r, pct);
+        }
+    }
+    if edit.speed_enforcement.compliance_pct() > 100 {
+        bail!(
+            "{} has speed enforcement with {}% compliance",
+            r,
+            edit.speed_enforcement.compliance_pct()
+        );
+    }
+    if let Some(schedule) = edit.pricing.schedule() {
+        if schedule
+            .prices
+            .windows(2)
+            .any(|pair| pair[0].0 >= pair[1].0)
+        {
+            bail!("the prices along {} aren't sorted by time", r);
+        }
+    }
+    if let RoadPricing::PricedLanes(_) = edit.pricing {
+        if !edit.lanes_ltr.iter().any(|spec| spec.lt == LaneType::Bus) {
+            bail!("{} has priced lanes, but no bus lanes to price", r);
+        }
+    }
+    if let Some(min) = edit.hov_min_occupancy {
+        if min < 2 {
+            bail!("{} has HOV lanes open to cars with {} people", r, min);
+        }
+        if !edit.lanes_ltr.iter().any(|spec| spec.lt == LaneType::Bus) {
+            bail!("{} has HOV lanes, but no bus lanes to open to them", r);
+        }
+    }
+    Ok(())
+}
+
+fn validate_transit_stop(
+    map: &Map,
+    ts: TransitStopID,
+    edit: Option<&EditTransitStop>,
+) -> Result<()> {
+    if map
+        .maybe_get_ts(ts)
+        .map(|ts| ts.is_train_stop)
+        .unwrap_or(false)
+    {
+        bail!("{} is a train stop; only bus stops can be edited", ts);
+    }
+    let edit = match edit {
+        Some(edit) => edit,
+        None => {
+            return Ok(());
+        }
+    };
+    let l = edit.sidewalk_pos.lane();
+    let road = map.get_r(ts.road);
+    if l.road != ts.road || l.offset >= road.lanes.len() {
+        bail!("{} must be along a lane of {}", ts, ts.road);
+    }
+    let lane = map.get_l(l);
+    if !lane.is_walkable() {
+        bail!("{} isn't along a sidewalk or shoulder", ts);
+    }
+    if edit.sidewalk_pos.dist_along() < Distance::ZERO
+        || edit.sidewalk_pos.dist_along() > lane.length()
+    {
+        bail!(
+            "{} is {} along {}, which is only {} long",
+            ts,
+            edit.sidewalk_pos.dist_along(),
+            l,
+            lane.length()
+        );
+    }
+    if road
+        .find_closest_lane(l, |l| PathConstraints::Bus.can_use(l, map))
+        .is_none()
+    {
+        bail!(
+            "buses can't stop at {}, because they can't use {}",
+            ts,
+            ts.road
+        );
+    }
+    Ok(())
+}
+
+fn validate_intersection(map: &Map, i: IntersectionID, edit: &EditIntersection) -> Result<()> {
+    if let EditIntersectionControl::StopSign(ref ss) = edit.control {
+        let expected = ControlStopSign::new(map, i);
+        if !ss.roads.keys().eq(expected.roads.keys()) {
+            bail!(
+                "the stop sign at {} doesn't list the roads leading into it",
+                i
+            );
+        }
+    }
+    if map.get_i(i).is_border() && edit.control != map.get_i_edit(i).control {
+        bail!("{} is a border; its control can't change", i);
+    }
+    Ok(())
+}
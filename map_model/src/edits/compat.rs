@@ -120,6 +120,111 @@ pub fn upgrade(mut value: Value, map: &Map) -> Result<PermanentMapEdits> {
     if value["version"] == Value::Number(12.into()) {
         bail!("Breaking changes happened to map edits between v12 and v13. Recreate your edits from scratch; sorry.");
     }
+    if value["version"] == Value::Number(13.into()) {
+        add_kerb_uses(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(14.into()));
+    }
+    if value["version"] == Value::Number(14.into()) {
+        fix_stop_sign_controls(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(15.into()));
+    }
+    if value["version"] == Value::Number(15.into()) {
+        add_bus_lane_enforcement(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(16.into()));
+    }
+    if value["version"] == Value::Number(16.into()) {
+        add_transit_priority(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(17.into()));
+    }
+    if value["version"] == Value::Number(17.into()) {
+        add_traffic_calming(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(18.into()));
+    }
+    if value["version"] == Value::Number(18.into()) {
+        add_leading_pedestrian_interval(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(19.into()));
+    }
+    if value["version"] == Value::Number(19.into()) {
+        add_speed_enforcement(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(20.into()));
+    }
+    if value["version"] == Value::Number(20.into()) {
+        add_hgv_restrictions(&mut value, map)?;
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(21.into()));
+    }
+    if value["version"] == Value::Number(21.into()) {
+        add_signal_cluster(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(22.into()));
+    }
+    if value["version"] == Value::Number(22.into()) {
+        add_road_pricing(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(23.into()));
+    }
+    if value["version"] == Value::Number(23.into()) {
+        add_hov_lanes(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(24.into()));
+    }
+    if value["version"] == Value::Number(24.into()) {
+        add_signal_actuation(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(25.into()));
+    }
+    if value["version"] == Value::Number(25.into()) {
+        add_corner_design(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(26.into()));
+    }
+    if value["version"] == Value::Number(26.into()) {
+        add_lane_extents(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(27.into()));
+    }
+    if value["version"] == Value::Number(27.into()) {
+        add_walking_closures(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(28.into()));
+    }
 
     abstutil::from_json(&value.to_string().into_bytes())
 }
@@ -475,6 +580,321 @@ fn fix_turn_restrictions(value: &mut Value) {
     }
 }
 
+// Kerb uses were added to EditRoad
+fn add_kerb_uses(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("kerb_uses".to_string(), Value::Array(Vec::new()));
+            }
+        }
+    }
+}
+
+// Bus lane enforcement was added to EditRoad
+fn add_bus_lane_enforcement(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key].as_object_mut().unwrap().insert(
+                    "bus_lane_enforcement".to_string(),
+                    Value::String("Default".to_string()),
+                );
+            }
+        }
+    }
+}
+
+// Transit signal priority was added to EditIntersection
+fn add_transit_priority(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeIntersection") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("transit_priority".to_string(), Value::Null);
+            }
+        }
+    }
+}
+
+// Leading pedestrian intervals were added to EditIntersection
+fn add_leading_pedestrian_interval(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeIntersection") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("leading_pedestrian_interval".to_string(), Value::Null);
+            }
+        }
+    }
+}
+
+// Vehicle-actuated signals were added to EditIntersection
+fn add_signal_actuation(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeIntersection") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("actuation".to_string(), Value::Null);
+            }
+        }
+    }
+}
+
+// Signal clusters were added to EditIntersection
+fn add_signal_cluster(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeIntersection") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("signal_cluster".to_string(), Value::Array(Vec::new()));
+            }
+        }
+    }
+}
+
+// Cycling corner design was added to EditIntersection
+fn add_corner_design(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeIntersection") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key].as_object_mut().unwrap().insert(
+                    "corner_design".to_string(),
+                    serde_json::json!({
+                        "protected_corners": false,
+                        "two_stage_turn_boxes": false,
+                        "setback_crossings": false,
+                    }),
+                );
+            }
+        }
+    }
+}
+
+// Lanes covering only part of a road were added to EditRoad
+fn add_lane_extents(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key].as_object_mut().unwrap().insert(
+                    "lane_extents".to_string(),
+                    Value::Object(Default::default()),
+                );
+            }
+        }
+    }
+}
+
+// Sidewalk closures were added to EditRoad, and crossing closures to EditIntersection
+fn add_walking_closures(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        for (cmd_type, field, empty) in [
+            (
+                "ChangeRoad",
+                "sidewalk_closures",
+                Value::Object(Default::default()),
+            ),
+            (
+                "ChangeIntersection",
+                "closed_crossings",
+                Value::Array(Vec::new()),
+            ),
+        ] {
+            if let Some(cmd) = cmd.get_mut(cmd_type) {
+                let cmd = cmd.as_object_mut().unwrap();
+                for key in ["old", "new"] {
+                    cmd[key]
+                        .as_object_mut()
+                        .unwrap()
+                        .insert(field.to_string(), empty.clone());
+                }
+            }
+        }
+    }
+}
+
+// Traffic calming was added to EditRoad
+fn add_traffic_calming(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("traffic_calming".to_string(), Value::Array(Vec::new()));
+            }
+        }
+    }
+}
+
+// Speed enforcement was added to EditRoad
+fn add_speed_enforcement(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key].as_object_mut().unwrap().insert(
+                    "speed_enforcement".to_string(),
+                    Value::String("None".to_string()),
+                );
+            }
+        }
+    }
+}
+
+// Tolls and priced lanes were added to EditRoad
+fn add_road_pricing(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("pricing".to_string(), Value::String("Free".to_string()));
+            }
+        }
+    }
+}
+
+// HOV lanes were added to EditRoad
+fn add_hov_lanes(value: &mut Value) {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("hov_min_occupancy".to_string(), Value::Null);
+            }
+        }
+    }
+}
+
+// HGV restrictions were added to EditRoad. Before, they couldn't be edited, so both sides match
+// what OSM says.
+fn add_hgv_restrictions(value: &mut Value, map: &Map) -> Result<()> {
+    for orig in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        let cmd = orig.as_object_mut().unwrap();
+        if let Some(cmd) = cmd.get_mut("ChangeRoad") {
+            let road_id: OriginalRoad = serde_json::from_value(cmd["r"].clone()).unwrap();
+            let road = map.get_r(map.find_r_by_osm_id(road_id)?);
+            let cmd = cmd.as_object_mut().unwrap();
+            for key in ["old", "new"] {
+                cmd[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("hgv".to_string(), serde_json::to_value(&road.hgv).unwrap());
+            }
+        }
+    }
+    Ok(())
+}
+
+// Stop signs changed from a must_stop bool per road to priority, give way, or stop
+fn fix_stop_sign_controls(value: &mut Value) {
+    walk(value, &|map| {
+        if map.len() == 1 && map.contains_key("StopSign") {
+            let ss = map.get_mut("StopSign").unwrap().as_object_mut().unwrap();
+            if let Some(Value::Array(must_stop)) = ss.remove("must_stop") {
+                let controls = must_stop
+                    .into_iter()
+                    .map(|pair| {
+                        let mut pair = pair.as_array().unwrap().clone();
+                        let control = if pair[1] == Value::Bool(true) {
+                            "Stop"
+                        } else {
+                            "Priority"
+                        };
+                        pair[1] = Value::String(control.to_string());
+                        Value::Array(pair)
+                    })
+                    .collect();
+                ss.insert("controls".to_string(), Value::Array(controls));
+            }
+            true
+        } else {
+            false
+        }
+    })
+}
+
 // These're old structs used in fix_old_lane_cmds.
 #[derive(Debug, Deserialize)]
 struct OriginalLane {
@@ -0,0 +1,384 @@
+//! Compare one edited object against the original map, to explain what a proposal changed about
+//! it. Unlike `MapEdits::describe`, this is detailed enough to show before and after values.
+
+use enumset::EnumSet;
+
+use geom::UnitFmt;
+
+use crate::edits::{EditIntersection, EditIntersectionControl, EditRoad};
+use crate::{
+    ApproachControl, BufferType, BuildingID, Direction, IntersectionID, LaneSpec, LaneType, Map,
+    MapEdits, PathConstraints, RoadID, TurnType,
+};
+
+/// One attribute of an edited object, before and after the edits
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeDiff {
+    pub attribute: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+impl MapEdits {
+    /// How does a road differ from the original map? Empty if it hasn't been edited, or if the
+    /// edits cancel out.
+    pub fn diff_road(&self, map: &Map, r: RoadID, fmt: &UnitFmt) -> Vec<AttributeDiff> {
+        match self.original_roads.get(&r) {
+            Some(before) => diff_roads(before, &map.get_r_edit(r), fmt),
+            None => Vec::new(),
+        }
+    }
+
+    /// How does an intersection differ from the original map? Empty if it hasn't been edited, or
+    /// if the edits cancel out.
+    pub fn diff_intersection(
+        &self,
+        map: &Map,
+        i: IntersectionID,
+        fmt: &UnitFmt,
+    ) -> Vec<AttributeDiff> {
+        match self.original_intersections.get(&i) {
+            Some(before) => diff_intersections(before, &map.get_i_edit(i), fmt),
+            None => Vec::new(),
+        }
+    }
+
+    /// How does a building differ from the original map? Only bike parking can be edited.
+    pub fn diff_building(&self, map: &Map, b: BuildingID) -> Vec<AttributeDiff> {
+        let mut diffs = Vec::new();
+        if self.changed_bike_parking.contains(&b) {
+            let bldg = map.get_b(b);
+            add(
+                &mut diffs,
+                "bike parking",
+                format!("{} spots", bldg.orig_bike_parking),
+                format!("{} spots", bldg.bike_parking),
+            );
+        }
+        diffs
+    }
+}
+
+fn add(diffs: &mut Vec<AttributeDiff>, attribute: &'static str, before: String, after: String) {
+    if before != after {
+        diffs.push(AttributeDiff {
+            attribute,
+            before,
+            after,
+        });
+    }
+}
+
+fn diff_roads(before: &EditRoad, after: &EditRoad, fmt: &UnitFmt) -> Vec<AttributeDiff> {
+    let mut diffs = Vec::new();
+    add(
+        &mut diffs,
+        "lanes",
+        describe_lanes(&before.lanes_ltr),
+        describe_lanes(&after.lanes_ltr),
+    );
+    if before.lanes_ltr.len() == after.lanes_ltr.len() {
+        add(
+            &mut diffs,
+            "lane directions",
+            describe_directions(&before.lanes_ltr),
+            describe_directions(&after.lanes_ltr),
+        );
+        add(
+            &mut diffs,
+            "lane widths",
+            describe_widths(&before.lanes_ltr, fmt),
+            describe_widths(&after.lanes_ltr, fmt),
+        );
+    }
+    add(
+        &mut diffs,
+        "speed limit",
+        before.speed_limit.to_string(fmt),
+        after.speed_limit.to_string(fmt),
+    );
+    add(
+        &mut diffs,
+        "parking",
+        describe_parking(before),
+        describe_parking(after),
+    );
+    add(
+        &mut diffs,
+        "modal filter",
+        describe_option(before.modal_filter.as_ref().map(|f| &f.filter_type)),
+        describe_option(after.modal_filter.as_ref().map(|f| &f.filter_type)),
+    );
+    add(
+        &mut diffs,
+        "through-traffic allowed for",
+        describe_modes(&before.access_restrictions.allow_through_traffic),
+        describe_modes(&after.access_restrictions.allow_through_traffic),
+    );
+    add(
+        &mut diffs,
+        "bus lane enforcement",
+        before.bus_lane_enforcement.to_string(),
+        after.bus_lane_enforcement.to_string(),
+    );
+    add(
+        &mut diffs,
+        "speed enforcement",
+        before.speed_enforcement.to_string(),
+        after.speed_enforcement.to_string(),
+    );
+    add(
+        &mut diffs,
+        "pricing",
+        before.pricing.to_string(),
+        after.pricing.to_string(),
+    );
+    add(
+        &mut diffs,
+        "HOV lanes",
+        describe_hov(before.hov_min_occupancy),
+        describe_hov(after.hov_min_occupancy),
+    );
+    add(
+        &mut diffs,
+        "HGV restrictions",
+        before.hgv.to_string(),
+        after.hgv.to_string(),
+    );
+    add(
+        &mut diffs,
+        "traffic calming",
+        describe_list(before.traffic_calming.iter().map(|tc| &tc.calming_type)),
+        describe_list(after.traffic_calming.iter().map(|tc| &tc.calming_type)),
+    );
+    add(
+        &mut diffs,
+        "crossings",
+        count(before.crossings.len(), "crossing"),
+        count(after.crossings.len(), "crossing"),
+    );
+    add(
+        &mut diffs,
+        "turn restrictions",
+        count(
+            before.turn_restrictions.len() + before.complicated_turn_restrictions.len(),
+            "restriction",
+        ),
+        count(
+            after.turn_restrictions.len() + after.complicated_turn_restrictions.len(),
+            "restriction",
+        ),
+    );
+    add(
+        &mut diffs,
+        "sidewalk closures",
+        count(before.sidewalk_closures.len(), "side"),
+        count(after.sidewalk_closures.len(), "side"),
+    );
+    diffs
+}
+
+fn diff_intersections(
+    before: &EditIntersection,
+    after: &EditIntersection,
+    fmt: &UnitFmt,
+) -> Vec<AttributeDiff> {
+    let mut diffs = Vec::new();
+    let before_control = describe_control(&before.control);
+    let mut after_control = describe_control(&after.control);
+    // Signal timing or which roads stop could change without the summary changing
+    if before_control == after_control && before.control != after.control {
+        after_control.push_str(" (modified)");
+    }
+    add(&mut diffs, "control", before_control, after_control);
+    add(
+        &mut diffs,
+        "crosswalks",
+        describe_crosswalks(before),
+        describe_crosswalks(after),
+    );
+    add(
+        &mut diffs,
+        "modal filter",
+        describe_option(before.modal_filter.as_ref().map(|f| &f.filter_type)),
+        describe_option(after.modal_filter.as_ref().map(|f| &f.filter_type)),
+    );
+    add(
+        &mut diffs,
+        "transit signal priority",
+        yes_no(before.transit_priority.is_some()),
+        yes_no(after.transit_priority.is_some()),
+    );
+    add(
+        &mut diffs,
+        "leading pedestrian interval",
+        before
+            .leading_pedestrian_interval
+            .map(|d| d.to_string(fmt))
+            .unwrap_or_else(|| "none".to_string()),
+        after
+            .leading_pedestrian_interval
+            .map(|d| d.to_string(fmt))
+            .unwrap_or_else(|| "none".to_string()),
+    );
+    add(
+        &mut diffs,
+        "signal actuation",
+        yes_no(before.actuation.is_some()),
+        yes_no(after.actuation.is_some()),
+    );
+    add(
+        &mut diffs,
+        "signal cluster",
+        count(before.signal_cluster.len(), "signal"),
+        count(after.signal_cluster.len(), "signal"),
+    );
+    add(
+        &mut diffs,
+        "corner design",
+        before.corner_design.describe().join(", "),
+        after.corner_design.describe().join(", "),
+    );
+    add(
+        &mut diffs,
+        "crossing closures",
+        count(before.closed_crossings.len(), "crossing"),
+        count(after.closed_crossings.len(), "crossing"),
+    );
+    diffs
+}
+
+/// From left to right, like "sidewalk | parking | driving | driving | sidewalk"
+fn describe_lanes(lanes: &[LaneSpec]) -> String {
+    lanes
+        .iter()
+        .map(|spec| lane_type_name(spec.lt))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn describe_directions(lanes: &[LaneSpec]) -> String {
+    lanes
+        .iter()
+        .map(|spec| match spec.dir {
+            Direction::Fwd => "forwards",
+            Direction::Back => "backwards",
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn describe_widths(lanes: &[LaneSpec], fmt: &UnitFmt) -> String {
+    lanes
+        .iter()
+        .map(|spec| spec.width.to_string(fmt))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn describe_parking(road: &EditRoad) -> String {
+    let lanes = road
+        .lanes_ltr
+        .iter()
+        .filter(|spec| spec.lt == LaneType::Parking)
+        .count();
+    let mut result = count(lanes, "parking lane");
+    if !road.kerb_uses.is_empty() {
+        result.push_str(&format!(
+            ", {}",
+            count(road.kerb_uses.len(), "kerb use zone")
+        ));
+    }
+    result
+}
+
+fn describe_control(control: &EditIntersectionControl) -> String {
+    match control {
+        EditIntersectionControl::StopSign(ss) => {
+            let stops = ss
+                .roads
+                .values()
+                .filter(|r| r.control != ApproachControl::Priority)
+                .count();
+            if stops == 0 {
+                "uncontrolled".to_string()
+            } else if stops == ss.roads.len() {
+                "all-way stop".to_string()
+            } else {
+                format!("{}-way stop", stops)
+            }
+        }
+        EditIntersectionControl::TrafficSignal(_) => "traffic signal".to_string(),
+        EditIntersectionControl::Closed => "closed".to_string(),
+    }
+}
+
+fn describe_crosswalks(i: &EditIntersection) -> String {
+    let marked = i
+        .crosswalks
+        .values()
+        .filter(|tt| **tt == TurnType::Crosswalk)
+        .count();
+    format!(
+        "{} marked, {} unmarked",
+        marked,
+        i.crosswalks.len() - marked
+    )
+}
+
+fn describe_modes(modes: &EnumSet<PathConstraints>) -> String {
+    describe_list(modes.iter().map(|c| format!("{:?}", c).to_lowercase()))
+}
+
+fn describe_hov(min_occupancy: Option<usize>) -> String {
+    match min_occupancy {
+        Some(n) => format!("{}+ people", n),
+        None => "none".to_string(),
+    }
+}
+
+fn describe_option<T: std::fmt::Debug>(x: Option<&T>) -> String {
+    match x {
+        Some(x) => format!("{:?}", x),
+        None => "none".to_string(),
+    }
+}
+
+fn describe_list<T: std::fmt::Display, I: Iterator<Item = T>>(list: I) -> String {
+    let list: Vec<String> = list.map(|x| x.to_string()).collect();
+    if list.is_empty() {
+        "none".to_string()
+    } else {
+        list.join(", ")
+    }
+}
+
+fn yes_no(x: bool) -> String {
+    if x { "yes" } else { "no" }.to_string()
+}
+
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+fn lane_type_name(lt: LaneType) -> &'static str {
+    match lt {
+        LaneType::Driving => "driving",
+        LaneType::Parking => "parking",
+        LaneType::Sidewalk => "sidewalk",
+        LaneType::Shoulder => "shoulder",
+        LaneType::Biking => "bike",
+        LaneType::Bus => "bus",
+        LaneType::SharedLeftTurn => "turn",
+        LaneType::Construction => "construction",
+        LaneType::LightRail => "rail",
+        LaneType::Buffer(BufferType::Curb) => "curb",
+        LaneType::Buffer(_) => "buffer",
+        LaneType::Footway => "footway",
+        LaneType::SharedUse => "shared-use",
+    }
+}
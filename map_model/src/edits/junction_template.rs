@@ -0,0 +1,385 @@
+//! A junction template captures the design of one intersection -- the lanes on each approach, the
+//! crossings, and how it's controlled -- independently of any map, so it can be applied to other
+//! similar intersections. Templates describe approaches by their angle, not by ID, and adapt to
+//! intersections with a different number of approaches.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use geom::Duration;
+
+use crate::{
+    Actuation, ApproachControl, ControlStopSign, ControlTrafficSignal, DirectedRoadID, EditCmd,
+    EditIntersectionControl, IntersectionControl, IntersectionID, LaneSpec, Map, MovementID,
+    RoadID, Stage, StageType, TransitPriority, TurnPriority, TurnType,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JunctionTemplate {
+    pub name: String,
+    /// Ordered clockwise
+    pub approaches: Vec<TemplateApproach>,
+    pub control: TemplateControl,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TemplateApproach {
+    /// Degrees clockwise from the first approach, in [0, 360)
+    pub angle: f64,
+    /// Left-to-right, as seen by somebody driving towards the intersection
+    pub lanes_ltr: Vec<LaneSpec>,
+    /// Only used for stop signs. None if traffic can't enter the intersection from this road.
+    pub stop_sign: Option<ApproachControl>,
+    /// Are pedestrian crossings over this road marked?
+    pub marked_crossings: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum TemplateControl {
+    StopSign,
+    TrafficSignal {
+        stages: Vec<TemplateStage>,
+        offset: Duration,
+        transit_priority: Option<TransitPriority>,
+        leading_pedestrian_interval: Option<Duration>,
+        #[serde(default)]
+        actuation: Option<Actuation>,
+    },
+    Closed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TemplateStage {
+    pub protected_movements: Vec<TemplateMovement>,
+    pub yield_movements: Vec<TemplateMovement>,
+    pub stage_type: StageType,
+}
+
+/// A movement between two approaches. Each side is an index into the approaches, and whether that
+/// direction of the road leads into the intersection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TemplateMovement {
+    pub from: (usize, bool),
+    pub to: (usize, bool),
+    pub crosswalk: bool,
+}
+
+/// How the roads of an intersection correspond to the approaches of a template
+pub struct TemplateMatch {
+    /// Each road of the intersection, and the template approach it takes its design from
+    pub approaches: BTreeMap<RoadID, usize>,
+    /// The worst difference between the angle of a road and its approach, in degrees
+    pub max_angle_error: f64,
+}
+
+impl JunctionTemplate {
+    /// Captures the current design of an intersection.
+    pub fn from_intersection(map: &Map, i: IntersectionID, name: String) -> Result<Self> {
+        let intersection = map.get_i(i);
+        if intersection.is_border() {
+            bail!("Border intersections can't be used as a template");
+        }
+        let roads = approaches(map, i);
+        let idx: BTreeMap<RoadID, usize> = roads
+            .iter()
+            .enumerate()
+            .map(|(idx, (r, _))| (*r, idx))
+            .collect();
+
+        let marked: BTreeSet<RoadID> = intersection
+            .turns
+            .iter()
+            .filter(|t| t.turn_type == TurnType::Crosswalk)
+            .map(|t| t.id.src.road)
+            .collect();
+        let stop_sign = map.maybe_get_stop_sign(i);
+        let mut template_approaches = Vec::new();
+        for (r, angle) in &roads {
+            let road = map.get_r(*r);
+            let mut lanes_ltr = road.lane_specs();
+            // Store lanes as if the road points into the intersection
+            if road.dst_i != i {
+                reverse_lanes(&mut lanes_ltr);
+            }
+            template_approaches.push(TemplateApproach {
+                angle: *angle,
+                lanes_ltr,
+                stop_sign: stop_sign.and_then(|ss| ss.roads.get(r).map(|x| x.control)),
+                marked_crossings: marked.contains(r),
+            });
+        }
+
+        let control = match intersection.control {
+            IntersectionControl::Signed | IntersectionControl::Uncontrolled => {
+                TemplateControl::StopSign
+            }
+            IntersectionControl::Construction => TemplateControl::Closed,
+            IntersectionControl::Signalled => {
+                let signal = map.get_traffic_signal(i);
+                let convert = |movements: &BTreeSet<MovementID>| {
+                    movements
+                        .iter()
+                        .map(|m| TemplateMovement {
+                            from: (idx[&m.from.road], m.from.dst_i(map) == i),
+                            to: (idx[&m.to.road], m.to.dst_i(map) == i),
+                            crosswalk: m.crosswalk,
+                        })
+                        .collect()
+                };
+                TemplateControl::TrafficSignal {
+                    stages: signal
+                        .stages
+                        .iter()
+                        .map(|stage| TemplateStage {
+                            protected_movements: convert(&stage.protected_movements),
+                            yield_movements: convert(&stage.yield_movements),
+                            stage_type: stage.stage_type.clone(),
+                        })
+                        .collect(),
+                    offset: signal.offset,
+                    transit_priority: signal.transit_priority.clone(),
+                    leading_pedestrian_interval: signal.leading_pedestrian_interval,
+                    actuation: signal.actuation.clone(),
+                }
+            }
+        };
+
+        Ok(Self {
+            name,
+            approaches: template_approaches,
+            control,
+        })
+    }
+
+    /// Decides which template approach each road of an intersection corresponds to. If the number
+    /// of approaches matches, the clockwise order is kept. Otherwise, each road uses the approach
+    /// closest in angle, so some approaches may be used twice or not at all.
+    pub fn match_intersection(&self, map: &Map, i: IntersectionID) -> Result<TemplateMatch> {
+        if self.approaches.is_empty() {
+            bail!("Template {} has no approaches", self.name);
+        }
+        if map.get_i(i).is_border() {
+            bail!("Templates can't be applied to border intersections");
+        }
+        let roads = approaches(map, i);
+
+        // Try every rotation lining up one road with one approach, and keep the best
+        let mut best: Option<(f64, Vec<usize>)> = None;
+        for (first_road, (_, road_angle)) in roads.iter().enumerate() {
+            for (first_approach, approach) in self.approaches.iter().enumerate() {
+                let rotation = road_angle - approach.angle;
+                let assignment: Vec<usize> = if roads.len() == self.approaches.len() {
+                    (0..roads.len())
+                        .map(|idx| (idx + roads.len() - first_road + first_approach) % roads.len())
+                        .collect()
+                } else {
+                    roads
+                        .iter()
+                        .map(|(_, angle)| {
+                            (0..self.approaches.len())
+                                .min_by(|a, b| {
+                                    let a = self.angle_error(*a, *angle, rotation);
+                                    let b = self.angle_error(*b, *angle, rotation);
+                                    a.partial_cmp(&b).unwrap()
+                                })
+                                .unwrap()
+                        })
+                        .collect()
+                };
+                let error = roads
+                    .iter()
+                    .zip(assignment.iter())
+                    .map(|((_, angle), idx)| self.angle_error(*idx, *angle, rotation))
+                    .fold(0.0, f64::max);
+                if best.as_ref().map(|(e, _)| error < *e).unwrap_or(true) {
+                    best = Some((error, assignment));
+                }
+            }
+        }
+        let (max_angle_error, assignment) = best.unwrap();
+        Ok(TemplateMatch {
+            approaches: roads
+                .into_iter()
+                .map(|(r, _)| r)
+                .zip(assignment.into_iter())
+                .collect(),
+            max_angle_error,
+        })
+    }
+
+    /// Changes the lanes of every road around the intersection to match its approach. This
+    /// usually changes the intersection's movements, so apply these edits before calling
+    /// `control_edit`. Note the other end of each road is affected too.
+    pub fn lane_edits(&self, map: &Map, i: IntersectionID) -> Result<Vec<EditCmd>> {
+        let matched = self.match_intersection(map, i)?;
+        let mut cmds = Vec::new();
+        for (r, idx) in matched.approaches {
+            let road = map.get_r(r);
+            // Loops would need two approaches
+            if road.src_i == road.dst_i {
+                continue;
+            }
+            let mut lanes_ltr = self.approaches[idx].lanes_ltr.clone();
+            if road.dst_i != i {
+                reverse_lanes(&mut lanes_ltr);
+            }
+            if lanes_ltr != road.lane_specs() {
+                cmds.push(map.edit_road_cmd(r, |new| {
+                    new.lanes_ltr = lanes_ltr;
+                }));
+            }
+        }
+        Ok(cmds)
+    }
+
+    /// Changes the intersection's control and crossings to match the template. Movements that
+    /// the template doesn't cover are added wherever they fit into the signal.
+    pub fn control_edit(&self, map: &Map, i: IntersectionID) -> Result<EditCmd> {
+        let matched = self.match_intersection(map, i)?;
+        let intersection = map.get_i(i);
+
+        let control = match self.control {
+            TemplateControl::StopSign => {
+                let mut ss = ControlStopSign::new(map, i);
+                for (r, sign) in ss.roads.iter_mut() {
+                    if let Some(control) = self.approaches[matched.approaches[r]].stop_sign {
+                        sign.control = control;
+                    }
+                }
+                EditIntersectionControl::StopSign(ss)
+            }
+            TemplateControl::Closed => EditIntersectionControl::Closed,
+            TemplateControl::TrafficSignal {
+                ref stages, offset, ..
+            } => {
+                let to_template = |m: &MovementID| {
+                    let side =
+                        |dr: DirectedRoadID| (matched.approaches[&dr.road], dr.dst_i(map) == i);
+                    TemplateMovement {
+                        from: side(m.from),
+                        to: side(m.to),
+                        crosswalk: m.crosswalk,
+                    }
+                };
+                let mut signal = ControlTrafficSignal {
+                    id: i,
+                    stages: Vec::new(),
+                    offset,
+                    transit_priority: None,
+                    leading_pedestrian_interval: None,
+                    actuation: None,
+                    cluster: BTreeSet::new(),
+                };
+                let mut assigned = BTreeSet::new();
+                for template_stage in stages {
+                    let protected: BTreeSet<TemplateMovement> =
+                        template_stage.protected_movements.iter().cloned().collect();
+                    let yielding: BTreeSet<TemplateMovement> =
+                        template_stage.yield_movements.iter().cloned().collect();
+                    let mut stage = Stage::new();
+                    stage.stage_type = template_stage.stage_type.clone();
+                    for (id, movement) in &intersection.movements {
+                        let key = to_template(id);
+                        // When approaches are merged, protected movements might conflict now
+                        if protected.contains(&key) && stage.could_be_protected(*id, intersection) {
+                            stage.edit_movement(movement, TurnPriority::Protected);
+                            assigned.insert(*id);
+                        } else if (protected.contains(&key) || yielding.contains(&key))
+                            && !id.crosswalk
+                        {
+                            stage.edit_movement(movement, TurnPriority::Yield);
+                            assigned.insert(*id);
+                        }
+                    }
+                    signal.stages.push(stage);
+                }
+
+                // Fit in anything the template didn't have
+                let mut all_walk = Stage::new();
+                for (id, movement) in &intersection.movements {
+                    if assigned.contains(id) {
+                        continue;
+                    }
+                    if let Some(stage) = signal
+                        .stages
+                        .iter_mut()
+                        .find(|stage| stage.could_be_protected(*id, intersection))
+                    {
+                        stage.edit_movement(movement, TurnPriority::Protected);
+                    } else if id.crosswalk {
+                        all_walk.edit_movement(movement, TurnPriority::Protected);
+                    } else {
+                        signal.stages[0].edit_movement(movement, TurnPriority::Yield);
+                    }
+                }
+                if !all_walk.protected_movements.is_empty() {
+                    signal.stages.push(all_walk);
+                }
+                if signal.stages.is_empty() {
+                    bail!("Template {} has a signal with no stages", self.name);
+                }
+                signal.validate(intersection)?;
+                EditIntersectionControl::TrafficSignal(signal.export(map))
+            }
+        };
+
+        Ok(map.edit_intersection_cmd(i, |new| {
+            new.control = control;
+            for (turn, turn_type) in new.crosswalks.iter_mut() {
+                if let Some(idx) = matched.approaches.get(&turn.src.road) {
+                    *turn_type = if self.approaches[*idx].marked_crossings {
+                        TurnType::Crosswalk
+                    } else {
+                        TurnType::UnmarkedCrossing
+                    };
+                }
+            }
+            if let TemplateControl::TrafficSignal {
+                ref transit_priority,
+                leading_pedestrian_interval,
+                ref actuation,
+                ..
+            } = self.control
+            {
+                new.transit_priority = transit_priority.clone();
+                new.leading_pedestrian_interval = leading_pedestrian_interval;
+                new.actuation = actuation.clone();
+            }
+        }))
+    }
+
+    /// How far off an approach is from a road, after rotating the template
+    fn angle_error(&self, approach: usize, road_angle: f64, rotation: f64) -> f64 {
+        let diff = (road_angle - rotation - self.approaches[approach].angle).rem_euclid(360.0);
+        diff.min(360.0 - diff)
+    }
+}
+
+/// The roads of an intersection in clockwise order, with their angle leaving the intersection,
+/// relative to the first road.
+fn approaches(map: &Map, i: IntersectionID) -> Vec<(RoadID, f64)> {
+    let mut result = Vec::new();
+    let mut first_angle = None;
+    for r in &map.get_i(i).roads {
+        let road = map.get_r(*r);
+        let pl = if road.src_i == i {
+            road.center_pts.clone()
+        } else {
+            road.center_pts.reversed()
+        };
+        let angle = pl.first_line().angle().normalized_degrees();
+        let first = *first_angle.get_or_insert(angle);
+        result.push((*r, (angle - first).rem_euclid(360.0)));
+    }
+    result
+}
+
+/// Flips lanes to describe the road pointing the other way
+fn reverse_lanes(lanes_ltr: &mut Vec<LaneSpec>) {
+    lanes_ltr.reverse();
+    for spec in lanes_ltr {
+        spec.dir = spec.dir.opposite();
+    }
+}
@@ -2,26 +2,42 @@
 //! the changes to a file (as `PermanentMapEdits`). See
 //! <https://a-b-street.github.io/docs/tech/map/edits.html>.
 
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Speed, Time};
+use geom::{Duration, Speed, Time};
 use osm2streets::{get_lane_specs_ltr, RestrictionType};
 
+pub use self::builder::{validate_cmd, EditsBuilder, InvalidCommand};
+pub use self::diff::AttributeDiff;
+pub use self::junction_template::{
+    JunctionTemplate, TemplateApproach, TemplateControl, TemplateMatch, TemplateMovement,
+    TemplateStage,
+};
 pub use self::perma::PermanentMapEdits;
 use crate::{
-    AccessRestrictions, ControlStopSign, ControlTrafficSignal, Crossing, DiagonalFilter,
-    IntersectionControl, IntersectionID, LaneID, LaneSpec, Map, MapConfig, ParkingLotID, Road,
-    RoadFilter, RoadID, TransitRouteID, TurnID, TurnType,
+    AccessRestrictions, Actuation, BuildingID, BusLaneEnforcement, ControlStopSign,
+    ControlTrafficSignal, CornerDesign, Crossing, DiagonalFilter, DirectedRoadID, DrivingSide,
+    HgvRestrictions, IntersectionControl, IntersectionID, KerbSegment, LaneExtent, LaneID,
+    LaneSpec, LaneType, Map, MapConfig, ParkingLotID, Position, Road, RoadFilter, RoadID,
+    RoadPricing, SideOfRoad, SpeedEnforcement, TrafficCalming, TransitPriority, TransitRouteID,
+    TransitStopID, TurnID, TurnType, WalkingClosure,
 };
 
 mod apply;
+mod builder;
 mod compat;
+mod diff;
+mod junction_template;
+mod pedestrianize;
 mod perma;
 pub mod perma_traffic_signal;
+mod summary;
+mod transit_stops;
 
 /// Represents changes to a map. Note this isn't serializable -- that's what `PermanentMapEdits`
 /// does.
@@ -36,6 +52,11 @@ pub struct MapEdits {
     pub original_roads: BTreeMap<RoadID, EditRoad>,
     pub original_intersections: BTreeMap<IntersectionID, EditIntersection>,
     pub changed_routes: BTreeSet<TransitRouteID>,
+    pub changed_bike_parking: BTreeSet<BuildingID>,
+    /// None means the stop didn't exist originally
+    pub original_transit_stops: BTreeMap<TransitStopID, Option<EditTransitStop>>,
+    pub original_route_stops: BTreeMap<TransitRouteID, Vec<TransitStopID>>,
+    pub original_route_alignments: BTreeMap<TransitRouteID, Vec<DirectedRoadID>>,
 
     /// Some edits are included in the game by default, in data/system/proposals, as "community
     /// proposals." They require a description and may have a link to a write-up.
@@ -60,6 +81,30 @@ pub enum EditCmd {
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    /// How many bikes can be parked at a building
+    ChangeBikeParking {
+        b: BuildingID,
+        old: usize,
+        new: usize,
+    },
+    /// Add, move, or remove a bus stop. None means the stop doesn't exist.
+    ChangeTransitStop {
+        id: TransitStopID,
+        old: Option<EditTransitStop>,
+        new: Option<EditTransitStop>,
+    },
+    /// The stops a route serves, in order
+    ChangeRouteStops {
+        id: TransitRouteID,
+        old: Vec<TransitStopID>,
+        new: Vec<TransitStopID>,
+    },
+    /// The roads a route drives along. Empty means the fastest path between stops.
+    ChangeRouteAlignment {
+        id: TransitRouteID,
+        old: Vec<DirectedRoadID>,
+        new: Vec<DirectedRoadID>,
+    },
 }
 
 pub struct EditEffects {
@@ -70,6 +115,7 @@ pub struct EditEffects {
     pub added_turns: BTreeSet<TurnID>,
     pub deleted_turns: BTreeSet<TurnID>,
     pub changed_parking_lots: BTreeSet<ParkingLotID>,
+    pub changed_transit_stops: BTreeSet<TransitStopID>,
     modified_lanes: BTreeSet<LaneID>,
 }
 
@@ -82,6 +128,16 @@ pub struct EditRoad {
     pub crossings: Vec<Crossing>,
     pub turn_restrictions: Vec<(RestrictionType, RoadID)>,
     pub complicated_turn_restrictions: Vec<(RoadID, RoadID)>,
+    pub kerb_uses: Vec<KerbSegment>,
+    pub bus_lane_enforcement: BusLaneEnforcement,
+    pub speed_enforcement: SpeedEnforcement,
+    pub pricing: RoadPricing,
+    pub hov_min_occupancy: Option<usize>,
+    pub hgv: HgvRestrictions,
+    pub traffic_calming: Vec<TrafficCalming>,
+    /// Keyed by index into `lanes_ltr`. Lanes missing here run the full length of the road.
+    pub lane_extents: BTreeMap<usize, LaneExtent>,
+    pub sidewalk_closures: BTreeMap<SideOfRoad, WalkingClosure>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -91,6 +147,27 @@ pub struct EditIntersection {
     /// This must contain all crossing turns at one intersection, each mapped either to Crosswalk
     /// or UnmarkedCrossing
     pub crosswalks: BTreeMap<TurnID, TurnType>,
+    /// Only used for traffic signals
+    pub transit_priority: Option<TransitPriority>,
+    /// Only used for traffic signals
+    pub leading_pedestrian_interval: Option<Duration>,
+    /// Only used for traffic signals
+    pub actuation: Option<Actuation>,
+    /// Only used for traffic signals. See `ControlTrafficSignal::cluster`.
+    pub signal_cluster: BTreeSet<IntersectionID>,
+    pub corner_design: CornerDesign,
+    /// Only crossing turns
+    pub closed_crossings: BTreeMap<TurnID, WalkingClosure>,
+}
+
+/// The parts of a bus stop that can be edited. Train stops can't be.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditTransitStop {
+    pub name: String,
+    pub gtfs_id: String,
+    /// Along a sidewalk or shoulder of the stop's road. The stop's position for vehicles is the
+    /// closest lane buses can use.
+    pub sidewalk_pos: Position,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -118,9 +195,65 @@ impl EditRoad {
             // See https://github.com/a-b-street/abstreet/pull/1091#discussion_r1311717165
             turn_restrictions: Vec::new(),
             complicated_turn_restrictions: Vec::new(),
+            kerb_uses: Vec::new(),
+            bus_lane_enforcement: BusLaneEnforcement::Default,
+            speed_enforcement: SpeedEnforcement::None,
+            pricing: RoadPricing::Free,
+            hov_min_occupancy: None,
+            hgv: HgvRestrictions::from_osm(&r.osm_tags),
+            traffic_calming: Vec::new(),
+            lane_extents: BTreeMap::new(),
+            sidewalk_closures: BTreeMap::new(),
+        }
+    }
+
+    /// Removes a lane, keeping `lane_extents` lined up with the remaining lanes.
+    pub fn remove_lane(&mut self, idx: usize) -> LaneSpec {
+        self.reindex_lane_extents(|i| match i.cmp(&idx) {
+            Ordering::Less => Some(i),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(i - 1),
+        });
+        self.lanes_ltr.remove(idx)
+    }
+
+    /// Inserts a lane, keeping `lane_extents` lined up. The new lane runs the full length of the
+    /// road.
+    pub fn insert_lane(&mut self, idx: usize, spec: LaneSpec) {
+        self.reindex_lane_extents(|i| Some(if i < idx { i } else { i + 1 }));
+        self.lanes_ltr.insert(idx, spec);
+    }
+
+    /// Adds a new lane of some type in a sensible position, keeping `lane_extents` lined up.
+    /// Returns the new lane's index.
+    pub fn add_new_lane(
+        &mut self,
+        lt: LaneType,
+        highway_type: &str,
+        driving_side: DrivingSide,
+    ) -> usize {
+        let idx = LaneSpec::add_new_lane(&mut self.lanes_ltr, lt, highway_type, driving_side);
+        self.reindex_lane_extents(|i| Some(if i < idx { i } else { i + 1 }));
+        idx
+    }
+
+    /// Moves a lane to a new position, along with its extent.
+    pub fn move_lane(&mut self, from: usize, to: usize) {
+        let extent = self.lane_extents.get(&from).cloned();
+        let spec = self.remove_lane(from);
+        self.insert_lane(to, spec);
+        if let Some(extent) = extent {
+            self.lane_extents.insert(to, extent);
         }
     }
 
+    fn reindex_lane_extents<F: Fn(usize) -> Option<usize>>(&mut self, f: F) {
+        self.lane_extents = std::mem::take(&mut self.lane_extents)
+            .into_iter()
+            .filter_map(|(idx, extent)| f(idx).map(|idx| (idx, extent)))
+            .collect();
+    }
+
     fn diff(&self, other: &EditRoad) -> Vec<String> {
         #![allow(clippy::comparison_chain)]
         let mut lt = 0;
@@ -166,6 +299,33 @@ impl EditRoad {
         if self.crossings != other.crossings {
             changes.push("crossings".to_string());
         }
+        if self.kerb_uses != other.kerb_uses {
+            changes.push("kerb uses".to_string());
+        }
+        if self.bus_lane_enforcement != other.bus_lane_enforcement {
+            changes.push("bus lane enforcement".to_string());
+        }
+        if self.speed_enforcement != other.speed_enforcement {
+            changes.push("speed enforcement".to_string());
+        }
+        if self.pricing != other.pricing {
+            changes.push("pricing".to_string());
+        }
+        if self.hov_min_occupancy != other.hov_min_occupancy {
+            changes.push("HOV lanes".to_string());
+        }
+        if self.hgv != other.hgv {
+            changes.push("HGV restrictions".to_string());
+        }
+        if self.traffic_calming != other.traffic_calming {
+            changes.push("traffic calming".to_string());
+        }
+        if self.lane_extents != other.lane_extents {
+            changes.push("lane extents".to_string());
+        }
+        if self.sidewalk_closures != other.sidewalk_closures {
+            changes.push("sidewalk closures".to_string());
+        }
         changes
     }
 }
@@ -183,6 +343,24 @@ impl EditIntersection {
         if self.modal_filter != other.modal_filter {
             changes.push("modal filter".to_string());
         }
+        if self.transit_priority != other.transit_priority {
+            changes.push("transit signal priority".to_string());
+        }
+        if self.leading_pedestrian_interval != other.leading_pedestrian_interval {
+            changes.push("leading pedestrian interval".to_string());
+        }
+        if self.actuation != other.actuation {
+            changes.push("signal actuation".to_string());
+        }
+        if self.signal_cluster != other.signal_cluster {
+            changes.push("signal cluster".to_string());
+        }
+        if self.corner_design != other.corner_design {
+            changes.push("cycling corner design".to_string());
+        }
+        if self.closed_crossings != other.closed_crossings {
+            changes.push("crossing closures".to_string());
+        }
         changes
     }
 }
@@ -198,6 +376,10 @@ impl MapEdits {
             original_roads: BTreeMap::new(),
             original_intersections: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            changed_bike_parking: BTreeSet::new(),
+            original_transit_stops: BTreeMap::new(),
+            original_route_stops: BTreeMap::new(),
+            original_route_alignments: BTreeMap::new(),
         }
     }
 
@@ -205,15 +387,7 @@ impl MapEdits {
     /// match the current map. If the resulting edits are totally empty, consider that a failure --
     /// the edits likely don't cover this map at all.
     pub fn load_from_file(map: &Map, path: String, timer: &mut Timer) -> Result<MapEdits> {
-        let perma = match abstio::maybe_read_json::<PermanentMapEdits>(path.clone(), timer) {
-            Ok(perma) => perma,
-            Err(_) => {
-                // The JSON format may have changed, so attempt backwards compatibility.
-                let bytes = abstio::slurp_file(path)?;
-                let value = serde_json::from_slice(&bytes)?;
-                compat::upgrade(value, map)?
-            }
-        };
+        let perma = PermanentMapEdits::load_from_file(map, path, timer)?;
 
         // Don't compare the full MapName; edits in one part of a city could apply to another. But
         // make sure at least the city matches. Otherwise, we spend time trying to match up edits,
@@ -269,6 +443,10 @@ impl MapEdits {
         self.original_roads.clear();
         self.original_intersections.clear();
         self.changed_routes.clear();
+        self.changed_bike_parking.clear();
+        self.original_transit_stops.clear();
+        self.original_route_stops.clear();
+        self.original_route_alignments.clear();
 
         for cmd in &self.commands {
             match cmd {
@@ -285,6 +463,24 @@ impl MapEdits {
                 EditCmd::ChangeRouteSchedule { id, .. } => {
                     self.changed_routes.insert(*id);
                 }
+                EditCmd::ChangeBikeParking { b, .. } => {
+                    self.changed_bike_parking.insert(*b);
+                }
+                EditCmd::ChangeTransitStop { id, ref old, .. } => {
+                    if !self.original_transit_stops.contains_key(id) {
+                        self.original_transit_stops.insert(*id, old.clone());
+                    }
+                }
+                EditCmd::ChangeRouteStops { id, ref old, .. } => {
+                    if !self.original_route_stops.contains_key(id) {
+                        self.original_route_stops.insert(*id, old.clone());
+                    }
+                }
+                EditCmd::ChangeRouteAlignment { id, ref old, .. } => {
+                    if !self.original_route_alignments.contains_key(id) {
+                        self.original_route_alignments.insert(*id, old.clone());
+                    }
+                }
             }
         }
 
@@ -296,6 +492,16 @@ impl MapEdits {
             let r = map.get_tr(*br);
             r.spawn_times != r.orig_spawn_times
         });
+        self.changed_bike_parking.retain(|b| {
+            let b = map.get_b(*b);
+            b.bike_parking != b.orig_bike_parking
+        });
+        self.original_transit_stops
+            .retain(|ts, orig| map.get_ts_edit(*ts) != *orig);
+        self.original_route_stops
+            .retain(|tr, orig| map.get_tr(*tr).stops != *orig);
+        self.original_route_alignments
+            .retain(|tr, orig| map.get_tr(*tr).alignment != *orig);
     }
 
     /// Assumes update_derived has been called.
@@ -322,6 +528,36 @@ impl MapEdits {
                 old: r.orig_spawn_times.clone(),
             });
         }
+        for b in &self.changed_bike_parking {
+            let b = map.get_b(*b);
+            self.commands.push(EditCmd::ChangeBikeParking {
+                b: b.id,
+                old: b.orig_bike_parking,
+                new: b.bike_parking,
+            });
+        }
+        // The order doesn't matter. Routes may briefly refer to stops that don't exist yet.
+        for (ts, old) in &self.original_transit_stops {
+            self.commands.push(EditCmd::ChangeTransitStop {
+                id: *ts,
+                old: old.clone(),
+                new: map.get_ts_edit(*ts),
+            });
+        }
+        for (tr, old) in &self.original_route_stops {
+            self.commands.push(EditCmd::ChangeRouteStops {
+                id: *tr,
+                old: old.clone(),
+                new: map.get_tr(*tr).stops.clone(),
+            });
+        }
+        for (tr, old) in &self.original_route_alignments {
+            self.commands.push(EditCmd::ChangeRouteAlignment {
+                id: *tr,
+                old: old.clone(),
+                new: map.get_tr(*tr).alignment.clone(),
+            });
+        }
     }
 
     /// Pick apart changed_roads and figure out if an entire road was edited, or just a few lanes.
@@ -416,6 +652,28 @@ impl EditCmd {
             EditCmd::ChangeRouteSchedule { id, .. } => {
                 format!("reschedule route {}", map.get_tr(*id).short_name)
             }
+            EditCmd::ChangeBikeParking { b, old, new } => {
+                details.push(format!("{} bike parking spots, was {}", new, old));
+                format!("bike parking at {}", b)
+            }
+            EditCmd::ChangeTransitStop { id, old, new } => match (old, new) {
+                (None, Some(new)) => format!("add bus stop {}", new.name),
+                (Some(old), None) => format!("remove bus stop {}", old.name),
+                (Some(_), Some(new)) => format!("move bus stop {}", new.name),
+                (None, None) => format!("bus stop {}", id),
+            },
+            EditCmd::ChangeRouteStops { id, old, new } => {
+                details.push(format!("{} stops, was {}", new.len(), old.len()));
+                format!("stops for route {}", map.get_tr(*id).short_name)
+            }
+            EditCmd::ChangeRouteAlignment { id, new, .. } => {
+                if new.is_empty() {
+                    details.push("back to the original streets".to_string());
+                } else {
+                    details.push(format!("along {} roads", new.len()));
+                }
+                format!("reroute route {}", map.get_tr(*id).short_name)
+            }
         };
         (summary, details)
     }
@@ -455,6 +713,15 @@ impl Map {
             crossings: r.crossings.clone(),
             turn_restrictions: r.turn_restrictions.clone(),
             complicated_turn_restrictions: r.complicated_turn_restrictions.clone(),
+            kerb_uses: r.kerb_uses.clone(),
+            bus_lane_enforcement: r.bus_lane_enforcement,
+            speed_enforcement: r.speed_enforcement,
+            pricing: r.pricing.clone(),
+            hov_min_occupancy: r.hov_min_occupancy,
+            hgv: r.hgv.clone(),
+            traffic_calming: r.traffic_calming.clone(),
+            lane_extents: r.lane_extents.clone(),
+            sidewalk_closures: r.sidewalk_closures.clone(),
         }
     }
 
@@ -465,6 +732,16 @@ impl Map {
         EditCmd::ChangeRoad { r, old, new }
     }
 
+    /// None if the stop doesn't exist
+    pub fn get_ts_edit(&self, ts: TransitStopID) -> Option<EditTransitStop> {
+        let ts = self.maybe_get_ts(ts)?;
+        Some(EditTransitStop {
+            name: ts.name.clone(),
+            gtfs_id: ts.gtfs_id.clone(),
+            sidewalk_pos: ts.sidewalk_pos,
+        })
+    }
+
     pub fn get_i_edit(&self, i: IntersectionID) -> EditIntersection {
         let i = self.get_i(i);
         let control = match i.control {
@@ -486,6 +763,21 @@ impl Map {
             control,
             modal_filter: i.modal_filter.clone(),
             crosswalks,
+            transit_priority: self
+                .maybe_get_traffic_signal(i.id)
+                .and_then(|ts| ts.transit_priority.clone()),
+            leading_pedestrian_interval: self
+                .maybe_get_traffic_signal(i.id)
+                .and_then(|ts| ts.leading_pedestrian_interval),
+            actuation: self
+                .maybe_get_traffic_signal(i.id)
+                .and_then(|ts| ts.actuation.clone()),
+            signal_cluster: self
+                .maybe_get_traffic_signal(i.id)
+                .map(|ts| ts.cluster.clone())
+                .unwrap_or_default(),
+            corner_design: i.corner_design.clone(),
+            closed_crossings: i.closed_crossings.clone(),
         }
     }
 
@@ -0,0 +1,191 @@
+//! Turn a road into a car-free street in one go. Doing this lane by lane is fiddly, and easily
+//! strands bus stops or leaves lanes that nothing can reach.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use geom::{Distance, Speed, Time};
+
+use crate::{
+    osm, Direction, DrivingSide, EditCmd, LaneSpec, LaneType, Map, PathConstraints, PathStep,
+    RoadID, TransitRouteID, TransitStopID,
+};
+
+impl Map {
+    /// Turn a road into a street for walking and cycling, paved across its whole width. With
+    /// `delivery_windows`, cars may still drive along it to load and unload during those times of
+    /// day. Bus stops along the road are removed, and bus routes using it take the fastest way
+    /// around instead. Also returns a warning for every bus route that has to detour.
+    pub fn pedestrianize_cmds(
+        &self,
+        r: RoadID,
+        delivery_windows: Vec<(Time, Time)>,
+    ) -> Result<(Vec<EditCmd>, BTreeMap<TransitRouteID, String>)> {
+        let road = self.get_r(r);
+        if road.is_light_rail() {
+            bail!("{} is a railway", r);
+        }
+        if road.is_pedestrian_street()
+            && road.access_restrictions.delivery_windows == delivery_windows
+        {
+            bail!("{} is already car-free", r);
+        }
+
+        let mut cmds = Vec::new();
+        let mut warnings = BTreeMap::new();
+        for route in self.all_transit_routes() {
+            if route.route_type != PathConstraints::Bus {
+                continue;
+            }
+            if route.start.road == r || route.end_border.map(|l| l.road) == Some(r) {
+                bail!("{} starts or ends on this road", route.long_name);
+            }
+
+            let stops: Vec<TransitStopID> = route
+                .stops
+                .iter()
+                .filter(|ts| ts.road != r)
+                .cloned()
+                .collect();
+            if stops.is_empty() {
+                bail!("{} only stops along this road", route.long_name);
+            }
+            let skipped = route.stops.len() - stops.len();
+            if skipped > 0 {
+                cmds.push(EditCmd::ChangeRouteStops {
+                    id: route.id,
+                    old: route.stops.clone(),
+                    new: stops,
+                });
+            }
+
+            // A route sent along particular roads goes back to the fastest path
+            let realigned = route.alignment.iter().any(|dr| dr.road == r);
+            if realigned {
+                cmds.push(EditCmd::ChangeRouteAlignment {
+                    id: route.id,
+                    old: route.alignment.clone(),
+                    new: Vec::new(),
+                });
+            }
+
+            let uses_road = realigned
+                || skipped > 0
+                || route
+                    .all_paths(self)
+                    .map(|paths| {
+                        paths.iter().any(|path| {
+                            path.get_steps()
+                                .iter()
+                                .any(|step| matches!(step, PathStep::Lane(l) if l.road == r))
+                        })
+                    })
+                    .unwrap_or(false);
+            if uses_road {
+                let warning = match skipped {
+                    0 => format!("{} will detour around this street", route.long_name),
+                    1 => format!(
+                        "{} will detour around this street and skip 1 stop",
+                        route.long_name
+                    ),
+                    n => format!(
+                        "{} will detour around this street and skip {} stops",
+                        route.long_name, n
+                    ),
+                };
+                warnings.insert(route.id, warning);
+            }
+        }
+        // Stops can't be left along a road buses can't use
+        for ts in &road.transit_stops {
+            cmds.push(EditCmd::ChangeTransitStop {
+                id: *ts,
+                old: self.get_ts_edit(*ts),
+                new: None,
+            });
+        }
+
+        let lanes_ltr = pedestrian_street_lanes(self, r, !delivery_windows.is_empty())?;
+        cmds.push(self.edit_road_cmd(r, |new| {
+            new.lanes_ltr = lanes_ltr;
+            new.lane_extents.clear();
+            // Vehicles share the space with people walking
+            new.speed_limit = Speed::miles_per_hour(10.0);
+            new.access_restrictions.allow_through_traffic =
+                PathConstraints::Pedestrian | PathConstraints::Bike;
+            if delivery_windows.is_empty() {
+                new.access_restrictions
+                    .destination_only
+                    .remove(PathConstraints::Car);
+            } else {
+                new.access_restrictions
+                    .destination_only
+                    .insert(PathConstraints::Car);
+            }
+            new.access_restrictions.delivery_windows = delivery_windows;
+            // None of these mean anything without traffic lanes
+            new.modal_filter = None;
+            new.crossings.clear();
+            new.kerb_uses.clear();
+            new.traffic_calming.clear();
+            new.sidewalk_closures.clear();
+            new.hov_min_occupancy = None;
+        }));
+        Ok((cmds, warnings))
+    }
+}
+
+/// Footways fill the road's width, around cycle lanes in both directions, or lanes for delivery
+/// vehicles in whichever directions cars could go before. The total width doesn't change.
+fn pedestrian_street_lanes(map: &Map, r: RoadID, deliveries: bool) -> Result<Vec<LaneSpec>> {
+    let road = map.get_r(r);
+    let highway = road.osm_tags.get(osm::HIGHWAY).unwrap();
+    let old = road.lane_specs();
+
+    let (lt, mut dirs) = if deliveries {
+        let mut dirs = Vec::new();
+        for dir in [Direction::Back, Direction::Fwd] {
+            if old
+                .iter()
+                .any(|spec| spec.dir == dir && matches!(spec.lt, LaneType::Driving | LaneType::Bus))
+            {
+                dirs.push(dir);
+            }
+        }
+        if dirs.is_empty() {
+            dirs = vec![Direction::Back, Direction::Fwd];
+        }
+        (LaneType::Driving, dirs)
+    } else {
+        (LaneType::Biking, vec![Direction::Back, Direction::Fwd])
+    };
+    if map.get_config().driving_side == DrivingSide::Left {
+        dirs.reverse();
+    }
+    let middle_width = LaneSpec::typical_lane_widths(lt, highway)[0].0;
+
+    let total_width: Distance = old.iter().map(|spec| spec.width).sum();
+    let footway_width = (total_width - middle_width * (dirs.len() as f64)) / 2.0;
+    if footway_width < Distance::meters(1.0) {
+        bail!("{} is too narrow to leave space for walking", r);
+    }
+
+    let footway = |dir| LaneSpec {
+        lt: LaneType::Footway,
+        dir,
+        width: footway_width,
+        allowed_turns: Default::default(),
+    };
+    let mut lanes = vec![footway(old[0].dir)];
+    for dir in dirs {
+        lanes.push(LaneSpec {
+            lt,
+            dir,
+            width: middle_width,
+            allowed_turns: Default::default(),
+        });
+    }
+    lanes.push(footway(old.last().unwrap().dir));
+    Ok(lanes)
+}
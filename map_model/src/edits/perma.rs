@@ -4,13 +4,18 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
-use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::Time;
+use abstutil::{deserialize_btreemap, serialize_btreemap, Timer};
+use geom::{Distance, Duration, Time};
 
-use super::perma_traffic_signal;
-use crate::edits::{EditCmd, EditIntersection, EditIntersectionControl, EditRoad, MapEdits};
+use super::builder::{validate_cmd, InvalidCommand};
+use super::{compat, perma_traffic_signal};
+use crate::edits::{
+    EditCmd, EditIntersection, EditIntersectionControl, EditRoad, EditTransitStop, MapEdits,
+};
 use crate::{
-    osm, ControlStopSign, DiagonalFilter, IntersectionID, Map, MovementID, OriginalRoad, TurnType,
+    osm, Actuation, ApproachControl, ControlStopSign, CornerDesign, DiagonalFilter, DirectedRoadID,
+    Direction, IntersectionID, LaneID, Map, MovementID, OriginalRoad, Position, RoadID,
+    TransitPriority, TransitStopID, TurnID, TurnType, WalkingClosure,
 };
 
 // Manually change this to attempt to preserve edits after major OSM updates.
@@ -41,6 +46,16 @@ pub struct PermanentEditIntersection {
         deserialize_with = "deserialize_btreemap"
     )]
     crosswalks: BTreeMap<perma_traffic_signal::Turn, TurnType>,
+    transit_priority: Option<TransitPriority>,
+    leading_pedestrian_interval: Option<Duration>,
+    actuation: Option<Actuation>,
+    signal_cluster: Vec<osm::NodeID>,
+    corner_design: CornerDesign,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    closed_crossings: BTreeMap<perma_traffic_signal::Turn, WalkingClosure>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -50,12 +65,29 @@ pub enum PermanentEditIntersectionControl {
             serialize_with = "serialize_btreemap",
             deserialize_with = "deserialize_btreemap"
         )]
-        must_stop: BTreeMap<OriginalRoad, bool>,
+        controls: BTreeMap<OriginalRoad, ApproachControl>,
     },
     TrafficSignal(perma_traffic_signal::TrafficSignal),
     Closed,
 }
 
+/// Stops are numbered along each road in the order they're imported, so this stays the same as
+/// long as the transit data does.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PermanentTransitStopID {
+    r: OriginalRoad,
+    idx: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PermanentEditTransitStop {
+    name: String,
+    gtfs_id: String,
+    /// The index of the sidewalk or shoulder among the road's lanes, from the left
+    sidewalk: usize,
+    dist_along: Distance,
+}
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Serialize, Deserialize, Clone)]
 pub enum PermanentEditCmd {
@@ -74,6 +106,26 @@ pub enum PermanentEditCmd {
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    ChangeBikeParking {
+        b: osm::OsmID,
+        old: usize,
+        new: usize,
+    },
+    ChangeTransitStop {
+        id: PermanentTransitStopID,
+        old: Option<PermanentEditTransitStop>,
+        new: Option<PermanentEditTransitStop>,
+    },
+    ChangeRouteStops {
+        gtfs_id: String,
+        old: Vec<PermanentTransitStopID>,
+        new: Vec<PermanentTransitStopID>,
+    },
+    ChangeRouteAlignment {
+        gtfs_id: String,
+        old: Vec<(OriginalRoad, Direction)>,
+        new: Vec<(OriginalRoad, Direction)>,
+    },
 }
 
 impl EditCmd {
@@ -96,6 +148,34 @@ impl EditCmd {
                     new: new.clone(),
                 }
             }
+            EditCmd::ChangeBikeParking { b, old, new } => PermanentEditCmd::ChangeBikeParking {
+                b: map.get_b(*b).orig_id,
+                old: *old,
+                new: *new,
+            },
+            EditCmd::ChangeTransitStop { id, old, new } => PermanentEditCmd::ChangeTransitStop {
+                id: id.to_permanent(map),
+                old: old.as_ref().map(|x| x.to_permanent()),
+                new: new.as_ref().map(|x| x.to_permanent()),
+            },
+            EditCmd::ChangeRouteStops { id, old, new } => PermanentEditCmd::ChangeRouteStops {
+                gtfs_id: map.get_tr(*id).gtfs_id.clone(),
+                old: old.iter().map(|ts| ts.to_permanent(map)).collect(),
+                new: new.iter().map(|ts| ts.to_permanent(map)).collect(),
+            },
+            EditCmd::ChangeRouteAlignment { id, old, new } => {
+                let to_permanent = |roads: &Vec<DirectedRoadID>| {
+                    roads
+                        .iter()
+                        .map(|dr| (map.get_r(dr.road).orig_id, dr.dir))
+                        .collect()
+                };
+                PermanentEditCmd::ChangeRouteAlignment {
+                    gtfs_id: map.get_tr(*id).gtfs_id.clone(),
+                    old: to_permanent(old),
+                    new: to_permanent(new),
+                }
+            }
         }
     }
 }
@@ -146,10 +226,127 @@ impl PermanentEditCmd {
                     .ok_or_else(|| anyhow!("can't find {}", gtfs_id))?;
                 Ok(EditCmd::ChangeRouteSchedule { id, old, new })
             }
+            PermanentEditCmd::ChangeBikeParking { b, old, new } => {
+                let id = map
+                    .find_b_by_osm_id(b)
+                    .ok_or_else(|| anyhow!("can't find building {}", b))?;
+                Ok(EditCmd::ChangeBikeParking { b: id, old, new })
+            }
+            PermanentEditCmd::ChangeTransitStop { id, old, new } => {
+                let id = id.with_permanent(map)?;
+                // Stops from the basemap should still match
+                if let (Some(old), Some(current)) = (&old, map.maybe_get_ts(id)) {
+                    if old.gtfs_id != current.gtfs_id {
+                        bail!(
+                            "{} was {} in the edits, but is {} now",
+                            id,
+                            old.gtfs_id,
+                            current.gtfs_id
+                        );
+                    }
+                }
+                Ok(EditCmd::ChangeTransitStop {
+                    id,
+                    old: old.map(|x| x.with_permanent(id.road, map)).transpose()?,
+                    new: new.map(|x| x.with_permanent(id.road, map)).transpose()?,
+                })
+            }
+            PermanentEditCmd::ChangeRouteStops { gtfs_id, old, new } => {
+                let id = map
+                    .find_tr_by_gtfs(&gtfs_id)
+                    .ok_or_else(|| anyhow!("can't find {}", gtfs_id))?;
+                Ok(EditCmd::ChangeRouteStops {
+                    id,
+                    old: old
+                        .into_iter()
+                        .map(|ts| ts.with_permanent(map))
+                        .collect::<Result<Vec<_>>>()?,
+                    new: new
+                        .into_iter()
+                        .map(|ts| ts.with_permanent(map))
+                        .collect::<Result<Vec<_>>>()?,
+                })
+            }
+            PermanentEditCmd::ChangeRouteAlignment { gtfs_id, old, new } => {
+                let id = map
+                    .find_tr_by_gtfs(&gtfs_id)
+                    .ok_or_else(|| anyhow!("can't find {}", gtfs_id))?;
+                let with_permanent = |roads: Vec<(OriginalRoad, Direction)>| {
+                    roads
+                        .into_iter()
+                        .map(|(r, dir)| {
+                            Ok(DirectedRoadID {
+                                road: map.find_r_by_osm_id(r)?,
+                                dir,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()
+                };
+                Ok(EditCmd::ChangeRouteAlignment {
+                    id,
+                    old: with_permanent(old)?,
+                    new: with_permanent(new)?,
+                })
+            }
         }
     }
 }
 
+impl TransitStopID {
+    fn to_permanent(self, map: &Map) -> PermanentTransitStopID {
+        PermanentTransitStopID {
+            r: map.get_r(self.road).orig_id,
+            idx: self.idx,
+        }
+    }
+}
+
+impl PermanentTransitStopID {
+    fn with_permanent(self, map: &Map) -> Result<TransitStopID> {
+        Ok(TransitStopID {
+            road: map.find_r_by_osm_id(self.r)?,
+            idx: self.idx,
+        })
+    }
+}
+
+impl EditTransitStop {
+    fn to_permanent(&self) -> PermanentEditTransitStop {
+        PermanentEditTransitStop {
+            name: self.name.clone(),
+            gtfs_id: self.gtfs_id.clone(),
+            sidewalk: self.sidewalk_pos.lane().offset,
+            dist_along: self.sidewalk_pos.dist_along(),
+        }
+    }
+}
+
+impl PermanentEditTransitStop {
+    fn with_permanent(self, r: RoadID, map: &Map) -> Result<EditTransitStop> {
+        let road = map.get_r(r);
+        if self.sidewalk >= road.lanes.len() {
+            bail!(
+                "stop {} is on lane {} of {}, but it only has {} lanes now",
+                self.gtfs_id,
+                self.sidewalk,
+                road.orig_id,
+                road.lanes.len()
+            );
+        }
+        Ok(EditTransitStop {
+            name: self.name,
+            gtfs_id: self.gtfs_id,
+            sidewalk_pos: Position::new(
+                LaneID {
+                    road: r,
+                    offset: self.sidewalk,
+                },
+                self.dist_along,
+            ),
+        })
+    }
+}
+
 impl MapEdits {
     /// Encode the edits in a permanent format, referring to more-stable OSM IDs.
     pub fn to_permanent(&self, map: &Map) -> PermanentMapEdits {
@@ -157,7 +354,7 @@ impl MapEdits {
             map_name: map.get_name().clone(),
             edits_name: self.edits_name.clone(),
             // Increase this every time there's a schema change
-            version: 13,
+            version: 28,
             proposal_description: self.proposal_description.clone(),
             proposal_link: self.proposal_link.clone(),
             commands: self.commands.iter().map(|cmd| cmd.to_perma(map)).collect(),
@@ -166,6 +363,20 @@ impl MapEdits {
 }
 
 impl PermanentMapEdits {
+    /// Read edits from a JSON file, upgrading older formats. Nothing is checked against the map
+    /// yet.
+    pub fn load_from_file(map: &Map, path: String, timer: &mut Timer) -> Result<PermanentMapEdits> {
+        match abstio::maybe_read_json::<PermanentMapEdits>(path.clone(), timer) {
+            Ok(perma) => Ok(perma),
+            Err(_) => {
+                // The JSON format may have changed, so attempt backwards compatibility.
+                let bytes = abstio::slurp_file(path)?;
+                let value = serde_json::from_slice(&bytes)?;
+                compat::upgrade(value, map)
+            }
+        }
+    }
+
     /// Transform permanent edits to MapEdits, looking up the map IDs by the hopefully stabler OSM
     /// IDs. Validate that the basemap hasn't changed in important ways.
     pub fn into_edits(self, map: &Map) -> Result<MapEdits> {
@@ -176,12 +387,20 @@ impl PermanentMapEdits {
             commands: self
                 .commands
                 .into_iter()
-                .map(|cmd| cmd.into_cmd(map))
+                .enumerate()
+                .map(|(idx, cmd)| {
+                    cmd.into_cmd(map)
+                        .with_context(|| format!("command {} is invalid", idx))
+                })
                 .collect::<Result<Vec<EditCmd>>>()?,
 
             original_roads: BTreeMap::new(),
             original_intersections: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            changed_bike_parking: BTreeSet::new(),
+            original_transit_stops: BTreeMap::new(),
+            original_route_stops: BTreeMap::new(),
+            original_route_alignments: BTreeMap::new(),
         };
         edits.update_derived(map);
         Ok(edits)
@@ -209,11 +428,38 @@ impl PermanentMapEdits {
             original_roads: BTreeMap::new(),
             original_intersections: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            changed_bike_parking: BTreeSet::new(),
+            original_transit_stops: BTreeMap::new(),
+            original_route_stops: BTreeMap::new(),
+            original_route_alignments: BTreeMap::new(),
         };
         edits.update_derived(map);
         edits
     }
 
+    pub fn num_commands(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Find every command that doesn't match this map or can't be applied, without stopping at
+    /// the first problem.
+    pub fn validate(&self, map: &Map) -> Vec<InvalidCommand> {
+        let mut problems = Vec::new();
+        for (idx, cmd) in self.commands.iter().enumerate() {
+            if let Err(err) = cmd
+                .clone()
+                .into_cmd(map)
+                .and_then(|cmd| validate_cmd(map, &cmd))
+            {
+                problems.push(InvalidCommand {
+                    idx,
+                    error: format!("{:#}", err),
+                });
+            }
+        }
+        problems
+    }
+
     /// Get the human-friendly of these edits. If they have a description, the first line is the
     /// title. Otherwise we use the filename.
     pub fn get_title(&self) -> &str {
@@ -231,10 +477,10 @@ impl EditIntersection {
             control: match self.control {
                 EditIntersectionControl::StopSign(ref ss) => {
                     PermanentEditIntersectionControl::StopSign {
-                        must_stop: ss
+                        controls: ss
                             .roads
                             .iter()
-                            .map(|(r, val)| (map.get_r(*r).orig_id, val.must_stop))
+                            .map(|(r, val)| (map.get_r(*r).orig_id, val.control))
                             .collect(),
                     }
                 }
@@ -251,6 +497,20 @@ impl EditIntersection {
                 .iter()
                 .map(|(id, turn_type)| (id.to_movement(map).to_permanent(map), *turn_type))
                 .collect(),
+            transit_priority: self.transit_priority.clone(),
+            leading_pedestrian_interval: self.leading_pedestrian_interval,
+            actuation: self.actuation.clone(),
+            signal_cluster: self
+                .signal_cluster
+                .iter()
+                .map(|i| map.get_i(*i).orig_id)
+                .collect(),
+            corner_design: self.corner_design.clone(),
+            closed_crossings: self
+                .closed_crossings
+                .iter()
+                .map(|(id, closure)| (id.to_movement(map).to_permanent(map), *closure))
+                .collect(),
         }
     }
 }
@@ -258,24 +518,24 @@ impl EditIntersection {
 impl PermanentEditIntersection {
     fn with_permanent(self, i: IntersectionID, map: &Map) -> Result<EditIntersection> {
         let control = match self.control {
-            PermanentEditIntersectionControl::StopSign { must_stop } => {
-                let mut translated_must_stop = BTreeMap::new();
-                for (r, stop) in must_stop {
-                    translated_must_stop.insert(map.find_r_by_osm_id(r)?, stop);
+            PermanentEditIntersectionControl::StopSign { controls } => {
+                let mut translated_controls = BTreeMap::new();
+                for (r, control) in controls {
+                    translated_controls.insert(map.find_r_by_osm_id(r)?, control);
                 }
 
                 // Make sure the roads exactly match up
                 let mut ss = ControlStopSign::new(map, i);
-                if translated_must_stop.len() != ss.roads.len() {
+                if translated_controls.len() != ss.roads.len() {
                     bail!(
                         "Stop sign has {} roads now, but {} from edits",
                         ss.roads.len(),
-                        translated_must_stop.len()
+                        translated_controls.len()
                     );
                 }
-                for (r, stop) in translated_must_stop {
+                for (r, control) in translated_controls {
                     if let Some(road) = ss.roads.get_mut(&r) {
-                        road.must_stop = stop;
+                        road.control = control;
                     } else {
                         bail!("{} doesn't connect to {}", i, r);
                     }
@@ -291,22 +551,16 @@ impl PermanentEditIntersection {
 
         let mut crosswalks = BTreeMap::new();
         for (id, turn_type) in self.crosswalks {
-            let movement = MovementID::from_permanent(id, map)?;
-            // Find all TurnIDs that map to this MovementID
-            let mut turn_ids = Vec::new();
-            for turn in &map.get_i(i).turns {
-                if turn.id.to_movement(map) == movement {
-                    turn_ids.push(turn.id);
-                }
-            }
-            if turn_ids.len() != 1 {
-                bail!(
-                    "{:?} didn't map to exactly 1 crossing turn: {:?}",
-                    movement,
-                    turn_ids
-                );
-            }
-            crosswalks.insert(turn_ids.pop().unwrap(), turn_type);
+            crosswalks.insert(crossing_from_permanent(id, i, map)?, turn_type);
+        }
+        let mut closed_crossings = BTreeMap::new();
+        for (id, closure) in self.closed_crossings {
+            closed_crossings.insert(crossing_from_permanent(id, i, map)?, closure);
+        }
+
+        let mut signal_cluster = BTreeSet::new();
+        for id in self.signal_cluster {
+            signal_cluster.insert(map.find_i_by_osm_id(id)?);
         }
 
         Ok(EditIntersection {
@@ -314,6 +568,35 @@ impl PermanentEditIntersection {
             // TODO Express as GeoJSON
             modal_filter: self.modal_filter.clone(),
             crosswalks,
+            transit_priority: self.transit_priority,
+            leading_pedestrian_interval: self.leading_pedestrian_interval,
+            actuation: self.actuation,
+            signal_cluster,
+            corner_design: self.corner_design,
+            closed_crossings,
         })
     }
 }
+
+fn crossing_from_permanent(
+    id: perma_traffic_signal::Turn,
+    i: IntersectionID,
+    map: &Map,
+) -> Result<TurnID> {
+    let movement = MovementID::from_permanent(id, map)?;
+    // Find all TurnIDs that map to this MovementID
+    let mut turn_ids = Vec::new();
+    for turn in &map.get_i(i).turns {
+        if turn.id.to_movement(map) == movement {
+            turn_ids.push(turn.id);
+        }
+    }
+    if turn_ids.len() != 1 {
+        bail!(
+            "{:?} didn't map to exactly 1 crossing turn: {:?}",
+            movement,
+            turn_ids
+        );
+    }
+    Ok(turn_ids.pop().unwrap())
+}
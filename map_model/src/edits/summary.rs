@@ -0,0 +1,401 @@
+//! Describe a whole proposal in a few sentences, like "Added 3 modal filters on Main St and Pine
+//! St" or "Changed 1.2 km of Oak Ave to 20 mph". Unlike `EditCmd::describe`, this collapses similar
+//! changes to many objects together, so a shared proposal explains itself.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use geom::{Distance, UnitFmt};
+
+use crate::edits::{EditIntersection, EditIntersectionControl, EditRoad};
+use crate::{LaneSpec, LaneType, Map, MapEdits, PathConstraints};
+
+/// How many road names to list before just counting the rest
+const MAX_NAMES: usize = 3;
+
+/// One kind of change to roads. Roads with several changes belong to several of these.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum RoadChange {
+    /// Turned into a street for walking and cycling. This explains all the other changes.
+    Pedestrianize,
+    AddModalFilter,
+    RemoveModalFilter,
+    /// The new speed limit, already formatted
+    SpeedLimit(String),
+    AddBikeLanes {
+        protected: bool,
+    },
+    RemoveBikeLanes,
+    AddBusLanes,
+    RemoveBusLanes,
+    AddParking,
+    RemoveParking,
+    OtherLanes,
+    TrafficCalming,
+    Crossings,
+    Pricing,
+    SidewalkClosures,
+    Other,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum IntersectionChange {
+    AddSignal,
+    RemoveSignal,
+    Close,
+    RetimeSignal,
+    StopSigns,
+    AddDiagonalFilter,
+    RemoveDiagonalFilter,
+    Crosswalks,
+    Other,
+}
+
+/// All the objects with the same kind of change
+#[derive(Default)]
+struct Group {
+    count: usize,
+    length: Distance,
+    names: BTreeSet<String>,
+}
+
+impl Group {
+    fn add(&mut self, name: String, length: Distance) {
+        self.count += 1;
+        self.length += length;
+        self.names.insert(name);
+    }
+
+    /// Like "Main St, Pine St, Oak Ave, and 2 others"
+    fn describe_names(&self) -> String {
+        if self.names.len() <= MAX_NAMES {
+            return abstutil::plain_list_names(self.names.clone());
+        }
+        let listed: Vec<String> = self.names.iter().take(MAX_NAMES).cloned().collect();
+        format!(
+            "{}, and {} others",
+            listed.join(", "),
+            self.names.len() - MAX_NAMES
+        )
+    }
+
+    /// Like "Main St and Pine St", or "3 intersections"
+    fn describe_intersections(&self) -> String {
+        if self.count == 1 {
+            self.names.iter().next().unwrap().clone()
+        } else {
+            format!("{} intersections", self.count)
+        }
+    }
+}
+
+impl MapEdits {
+    /// A human-readable summary of everything these edits change, one sentence per kind of change.
+    /// Empty if nothing has changed.
+    pub fn summarize(&self, map: &Map, fmt: &UnitFmt) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        let mut roads: BTreeMap<RoadChange, Group> = BTreeMap::new();
+        for (r, before) in &self.original_roads {
+            let road = map.get_r(*r);
+            let name = road.get_name(None);
+            for change in classify_road(before, &map.get_r_edit(*r), fmt) {
+                roads
+                    .entry(change)
+                    .or_default()
+                    .add(name.clone(), road.length());
+            }
+        }
+        for (change, group) in roads {
+            let names = group.describe_names();
+            let length = group.length.to_string(fmt);
+            lines.push(match change {
+                RoadChange::Pedestrianize => format!("Made {} car-free", names),
+                RoadChange::AddModalFilter => {
+                    format!("Added {} on {}", plural(group.count, "modal filter"), names)
+                }
+                RoadChange::RemoveModalFilter => format!(
+                    "Removed {} from {}",
+                    plural(group.count, "modal filter"),
+                    names
+                ),
+                RoadChange::SpeedLimit(speed) => {
+                    format!("Changed {} of {} to {}", length, names, speed)
+                }
+                RoadChange::AddBikeLanes { protected } => format!(
+                    "Added {}bike lanes along {} of {}",
+                    if protected { "protected " } else { "" },
+                    length,
+                    names
+                ),
+                RoadChange::RemoveBikeLanes => format!("Removed bike lanes from {}", names),
+                RoadChange::AddBusLanes => {
+                    format!("Added bus lanes along {} of {}", length, names)
+                }
+                RoadChange::RemoveBusLanes => format!("Removed bus lanes from {}", names),
+                RoadChange::AddParking => {
+                    format!("Added parking along {} of {}", length, names)
+                }
+                RoadChange::RemoveParking => {
+                    format!("Removed parking along {} of {}", length, names)
+                }
+                RoadChange::OtherLanes => format!("Changed lanes on {}", names),
+                RoadChange::TrafficCalming => format!("Changed traffic calming on {}", names),
+                RoadChange::Crossings => format!("Changed crossings on {}", names),
+                RoadChange::Pricing => format!("Changed road pricing on {}", names),
+                RoadChange::SidewalkClosures => format!("Changed sidewalk closures on {}", names),
+                RoadChange::Other => format!("Changed other details of {}", names),
+            });
+        }
+
+        let mut intersections: BTreeMap<IntersectionChange, Group> = BTreeMap::new();
+        for (i, before) in &self.original_intersections {
+            let name = map.get_i(*i).name(None, map);
+            for change in classify_intersection(before, &map.get_i_edit(*i)) {
+                intersections
+                    .entry(change)
+                    .or_default()
+                    .add(name.clone(), Distance::ZERO);
+            }
+        }
+        for (change, group) in intersections {
+            let at = group.describe_intersections();
+            lines.push(match change {
+                IntersectionChange::AddSignal => format!("Added traffic signals at {}", at),
+                IntersectionChange::RemoveSignal => format!("Removed traffic signals at {}", at),
+                IntersectionChange::Close => format!("Closed {}", at),
+                IntersectionChange::RetimeSignal => format!("Retimed traffic signals at {}", at),
+                IntersectionChange::StopSigns => format!("Changed stop signs at {}", at),
+                IntersectionChange::AddDiagonalFilter => {
+                    format!("Added {} at {}", plural(group.count, "diagonal filter"), at)
+                }
+                IntersectionChange::RemoveDiagonalFilter => format!(
+                    "Removed {} at {}",
+                    plural(group.count, "diagonal filter"),
+                    at
+                ),
+                IntersectionChange::Crosswalks => format!("Changed crosswalks at {}", at),
+                IntersectionChange::Other => format!("Changed other details of {}", at),
+            });
+        }
+
+        let (mut added, mut removed, mut moved) = (0, 0, 0);
+        for (ts, before) in &self.original_transit_stops {
+            match (before.is_some(), map.maybe_get_ts(*ts).is_some()) {
+                (false, true) => added += 1,
+                (true, false) => removed += 1,
+                (true, true) => moved += 1,
+                (false, false) => {}
+            }
+        }
+        for (verb, count) in [("Added", added), ("Removed", removed), ("Moved", moved)] {
+            if count > 0 {
+                lines.push(format!("{} {}", verb, plural(count, "bus stop")));
+            }
+        }
+        let rescheduled: BTreeSet<String> = self
+            .changed_routes
+            .iter()
+            .map(|r| map.get_tr(*r).short_name.clone())
+            .collect();
+        if !rescheduled.is_empty() {
+            lines.push(format!(
+                "Changed the schedule of route {}",
+                abstutil::plain_list_names(rescheduled)
+            ));
+        }
+        let restopped: BTreeSet<String> = self
+            .original_route_stops
+            .keys()
+            .map(|r| map.get_tr(*r).short_name.clone())
+            .collect();
+        if !restopped.is_empty() {
+            lines.push(format!(
+                "Changed the stops of route {}",
+                abstutil::plain_list_names(restopped)
+            ));
+        }
+        let rerouted: BTreeSet<String> = self
+            .original_route_alignments
+            .keys()
+            .map(|r| map.get_tr(*r).short_name.clone())
+            .collect();
+        if !rerouted.is_empty() {
+            lines.push(format!(
+                "Rerouted route {}",
+                abstutil::plain_list_names(rerouted)
+            ));
+        }
+        if !self.changed_bike_parking.is_empty() {
+            lines.push(format!(
+                "Changed bike parking at {}",
+                plural(self.changed_bike_parking.len(), "building")
+            ));
+        }
+
+        lines
+    }
+}
+
+fn classify_road(before: &EditRoad, after: &EditRoad, fmt: &UnitFmt) -> Vec<RoadChange> {
+    if is_pedestrian_street(after) && !is_pedestrian_street(before) {
+        return vec![RoadChange::Pedestrianize];
+    }
+
+    let mut changes = Vec::new();
+    match (before.modal_filter.is_some(), after.modal_filter.is_some()) {
+        (false, true) => changes.push(RoadChange::AddModalFilter),
+        (true, false) => changes.push(RoadChange::RemoveModalFilter),
+        _ => {}
+    }
+    if before.speed_limit != after.speed_limit {
+        changes.push(RoadChange::SpeedLimit(after.speed_limit.to_string(fmt)));
+    }
+
+    let mut lanes_explained = false;
+    for (lt, added, removed) in [
+        (
+            LaneType::Biking,
+            RoadChange::AddBikeLanes {
+                protected: is_bike_lane_protected(&after.lanes_ltr),
+            },
+            RoadChange::RemoveBikeLanes,
+        ),
+        (
+            LaneType::Bus,
+            RoadChange::AddBusLanes,
+            RoadChange::RemoveBusLanes,
+        ),
+        (
+            LaneType::Parking,
+            RoadChange::AddParking,
+            RoadChange::RemoveParking,
+        ),
+    ] {
+        let num_before = count_lanes(&before.lanes_ltr, lt);
+        let num_after = count_lanes(&after.lanes_ltr, lt);
+        if num_after > num_before {
+            changes.push(added);
+            lanes_explained = true;
+        } else if num_after < num_before {
+            changes.push(removed);
+            lanes_explained = true;
+        }
+    }
+    if !lanes_explained
+        && (before.lanes_ltr != after.lanes_ltr || before.lane_extents != after.lane_extents)
+    {
+        changes.push(RoadChange::OtherLanes);
+    }
+
+    if before.traffic_calming != after.traffic_calming {
+        changes.push(RoadChange::TrafficCalming);
+    }
+    if before.crossings != after.crossings {
+        changes.push(RoadChange::Crossings);
+    }
+    if before.pricing != after.pricing {
+        changes.push(RoadChange::Pricing);
+    }
+    if before.sidewalk_closures != after.sidewalk_closures {
+        changes.push(RoadChange::SidewalkClosures);
+    }
+
+    // Anything not covered above
+    let mut rest = after.clone();
+    rest.lanes_ltr = before.lanes_ltr.clone();
+    rest.lane_extents = before.lane_extents.clone();
+    rest.speed_limit = before.speed_limit;
+    rest.modal_filter = before.modal_filter.clone();
+    rest.traffic_calming = before.traffic_calming.clone();
+    rest.crossings = before.crossings.clone();
+    rest.pricing = before.pricing.clone();
+    rest.sidewalk_closures = before.sidewalk_closures.clone();
+    if &rest != before {
+        changes.push(RoadChange::Other);
+    }
+
+    changes
+}
+
+fn classify_intersection(
+    before: &EditIntersection,
+    after: &EditIntersection,
+) -> Vec<IntersectionChange> {
+    let mut changes = Vec::new();
+    match (&before.control, &after.control) {
+        (EditIntersectionControl::TrafficSignal(_), EditIntersectionControl::TrafficSignal(_)) => {
+            if before.control != after.control {
+                changes.push(IntersectionChange::RetimeSignal);
+            }
+        }
+        (_, EditIntersectionControl::TrafficSignal(_)) => {
+            changes.push(IntersectionChange::AddSignal)
+        }
+        (_, EditIntersectionControl::Closed) => changes.push(IntersectionChange::Close),
+        (EditIntersectionControl::TrafficSignal(_), _) => {
+            changes.push(IntersectionChange::RemoveSignal)
+        }
+        _ => {
+            if before.control != after.control {
+                changes.push(IntersectionChange::StopSigns);
+            }
+        }
+    }
+    match (before.modal_filter.is_some(), after.modal_filter.is_some()) {
+        (false, true) => changes.push(IntersectionChange::AddDiagonalFilter),
+        (true, false) => changes.push(IntersectionChange::RemoveDiagonalFilter),
+        _ => {}
+    }
+    if before.crosswalks != after.crosswalks {
+        changes.push(IntersectionChange::Crosswalks);
+    }
+
+    let mut rest = after.clone();
+    rest.control = before.control.clone();
+    rest.modal_filter = before.modal_filter.clone();
+    rest.crosswalks = before.crosswalks.clone();
+    if &rest != before {
+        changes.push(IntersectionChange::Other);
+    }
+
+    changes
+}
+
+fn count_lanes(lanes: &[LaneSpec], lt: LaneType) -> usize {
+    lanes.iter().filter(|spec| spec.lt == lt).count()
+}
+
+/// Is any bike lane separated from the rest of the road by a buffer?
+/// Like `Road::is_pedestrian_street`
+fn is_pedestrian_street(road: &EditRoad) -> bool {
+    !road
+        .access_restrictions
+        .allow_through_traffic
+        .contains(PathConstraints::Car)
+        && road
+            .lanes_ltr
+            .iter()
+            .any(|spec| spec.lt == LaneType::Footway)
+        && road.lanes_ltr.iter().all(|spec| {
+            matches!(
+                spec.lt,
+                LaneType::Footway | LaneType::Biking | LaneType::Driving
+            )
+        })
+}
+
+fn is_bike_lane_protected(lanes: &[LaneSpec]) -> bool {
+    lanes.windows(2).any(|pair| {
+        let (a, b) = (pair[0].lt, pair[1].lt);
+        (a == LaneType::Biking && matches!(b, LaneType::Buffer(_)))
+            || (b == LaneType::Biking && matches!(a, LaneType::Buffer(_)))
+    })
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
@@ -0,0 +1,244 @@
+//! Commands to add, move, remove, and merge bus stops, keeping the routes serving them in sync.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use geom::Distance;
+
+use crate::{
+    DirectedRoadID, EditCmd, EditTransitStop, Map, Path, PathConstraints, PathStep, Position,
+    TransitRouteID, TransitStopID,
+};
+
+impl Map {
+    /// Add a bus stop. Every bus route already driving past it will serve it.
+    pub fn add_bus_stop_cmds(&self, sidewalk_pos: Position, name: String) -> Result<Vec<EditCmd>> {
+        let road = self.get_r(sidewalk_pos.lane().road);
+        let driving_lane = road
+            .find_closest_lane(sidewalk_pos.lane(), |l| {
+                PathConstraints::Bus.can_use(l, self)
+            })
+            .ok_or_else(|| anyhow!("buses can't use {}", road.id))?;
+        let driving_pos = sidewalk_pos.equiv_pos(driving_lane, self);
+        // This only has to be unique along the road
+        let idx = road
+            .transit_stops
+            .iter()
+            .map(|ts| ts.idx + 1)
+            .max()
+            .unwrap_or(0);
+        let id = TransitStopID { road: road.id, idx };
+
+        let mut cmds = vec![EditCmd::ChangeTransitStop {
+            id,
+            old: None,
+            new: Some(EditTransitStop {
+                name,
+                gtfs_id: format!("new_{}_{}", road.id.0, idx),
+                sidewalk_pos,
+            }),
+        }];
+        for route in self.all_transit_routes() {
+            if route.route_type != PathConstraints::Bus {
+                continue;
+            }
+            let paths = match route.all_paths(self) {
+                Ok(paths) => paths,
+                Err(err) => {
+                    warn!("Not adding a stop to {}: {}", route.long_name, err);
+                    continue;
+                }
+            };
+            // Path i leads to stop i, so the new stop goes right before that one
+            if let Some(stop_idx) = paths
+                .iter()
+                .position(|path| passes_position(path, driving_pos, self))
+            {
+                let mut new = route.stops.clone();
+                new.insert(stop_idx, id);
+                cmds.push(EditCmd::ChangeRouteStops {
+                    id: route.id,
+                    old: route.stops.clone(),
+                    new,
+                });
+            }
+        }
+        Ok(cmds)
+    }
+
+    /// Move a bus stop somewhere else along its road. The same routes keep serving it.
+    pub fn move_bus_stop_cmd(&self, id: TransitStopID, sidewalk_pos: Position) -> EditCmd {
+        let old = self.get_ts_edit(id);
+        let mut new = old.clone().unwrap();
+        new.sidewalk_pos = sidewalk_pos;
+        EditCmd::ChangeTransitStop {
+            id,
+            old,
+            new: Some(new),
+        }
+    }
+
+    /// Remove a bus stop, and skip it along every route. Fails if a route would have no stops
+    /// left.
+    pub fn remove_bus_stop_cmds(&self, id: TransitStopID) -> Result<Vec<EditCmd>> {
+        let mut cmds = Vec::new();
+        for route in self.all_transit_routes() {
+            if !route.stops.contains(&id) {
+                continue;
+            }
+            let new: Vec<TransitStopID> = route
+                .stops
+                .iter()
+                .filter(|ts| **ts != id)
+                .cloned()
+                .collect();
+            if new.is_empty() {
+                bail!(
+                    "{} is the only stop of {}",
+                    self.get_ts(id).name,
+                    route.long_name
+                );
+            }
+            cmds.push(EditCmd::ChangeRouteStops {
+                id: route.id,
+                old: route.stops.clone(),
+                new,
+            });
+        }
+        cmds.push(EditCmd::ChangeTransitStop {
+            id,
+            old: self.get_ts_edit(id),
+            new: None,
+        });
+        Ok(cmds)
+    }
+
+    /// Remove the `from` stop. Routes serving it will serve `into` instead.
+    pub fn merge_bus_stops_cmds(&self, from: TransitStopID, into: TransitStopID) -> Vec<EditCmd> {
+        let mut cmds = Vec::new();
+        for route in self.all_transit_routes() {
+            if !route.stops.contains(&from) {
+                continue;
+            }
+            let mut new: Vec<TransitStopID> = route
+                .stops
+                .iter()
+                .map(|ts| if *ts == from { into } else { *ts })
+                .collect();
+            // Routes that served both stops one after the other now just stop once
+            new.dedup();
+            cmds.push(EditCmd::ChangeRouteStops {
+                id: route.id,
+                old: route.stops.clone(),
+                new,
+            });
+        }
+        cmds.push(EditCmd::ChangeTransitStop {
+            id: from,
+            old: self.get_ts_edit(from),
+            new: None,
+        });
+        cmds
+    }
+
+    /// Send a bus route along different roads, from its start lane to its end. Stops it served
+    /// that are still along the way are kept, and it picks up any stops on roads it didn't use
+    /// before. The schedule doesn't change.
+    pub fn reroute_cmds(
+        &self,
+        id: TransitRouteID,
+        alignment: Vec<DirectedRoadID>,
+    ) -> Result<Vec<EditCmd>> {
+        let route = self.get_tr(id);
+        if alignment.first() != Some(&self.get_l(route.start).get_directed_parent()) {
+            bail!(
+                "The new route has to begin where {} starts",
+                route.long_name
+            );
+        }
+        if let Some(end) = route.end_border {
+            if alignment.last() != Some(&self.get_l(end).get_directed_parent()) {
+                bail!("The new route has to end where {} leaves", route.long_name);
+            }
+        }
+
+        let mut old_roads: BTreeSet<DirectedRoadID> = BTreeSet::new();
+        for path in route.all_paths(self)? {
+            for step in path.get_steps() {
+                if let PathStep::Lane(l) = step {
+                    old_roads.insert(self.get_l(*l).get_directed_parent());
+                }
+            }
+        }
+
+        // Order stops by where they are along the new roads. If the route passes a road twice,
+        // only stop the first time.
+        let mut stops = Vec::new();
+        for stop in self.all_transit_stops().values() {
+            if stop.is_train_stop {
+                continue;
+            }
+            let dr = self.get_l(stop.driving_pos.lane()).get_directed_parent();
+            if !route.stops.contains(&stop.id) && old_roads.contains(&dr) {
+                continue;
+            }
+            if let Some(idx) = alignment.iter().position(|x| *x == dr) {
+                stops.push((idx, stop.driving_pos.dist_along(), stop.id));
+            }
+        }
+        stops.sort();
+        let new_stops: Vec<TransitStopID> = stops.into_iter().map(|(_, _, ts)| ts).collect();
+        if new_stops.is_empty() {
+            bail!(
+                "The new route for {} doesn't pass any stops",
+                route.long_name
+            );
+        }
+
+        let mut cmds = vec![EditCmd::ChangeRouteAlignment {
+            id,
+            old: route.alignment.clone(),
+            new: alignment,
+        }];
+        if new_stops != route.stops {
+            cmds.push(EditCmd::ChangeRouteStops {
+                id,
+                old: route.stops.clone(),
+                new: new_stops,
+            });
+        }
+        Ok(cmds)
+    }
+}
+
+/// Does a vehicle following this path drive past the position, or a position on the same side of
+/// the road in another lane?
+fn passes_position(path: &Path, pos: Position, map: &Map) -> bool {
+    let dir_road = map.get_l(pos.lane()).get_directed_parent();
+    let req = path.get_req();
+    for step in path.get_steps() {
+        if let PathStep::Lane(l) = step {
+            let lane = map.get_l(*l);
+            if lane.get_directed_parent() != dir_road {
+                continue;
+            }
+            let dist = pos.equiv_pos(*l, map).dist_along();
+            // The path may start or end partway along the lane
+            let start = if req.start.lane() == *l {
+                req.start.dist_along()
+            } else {
+                Distance::ZERO
+            };
+            let end = if req.end.lane() == *l {
+                req.end.dist_along()
+            } else {
+                lane.length()
+            };
+            if start <= dist && dist <= end {
+                return true;
+            }
+        }
+    }
+    false
+}
@@ -35,44 +35,61 @@ use popgetter::CensusZone;
 use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
-use abstutil::{
-    deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
-};
+use abstutil::{deserialize_btreemap, serialize_btreemap, MultiMap};
 use geom::{Bounds, FindClosest, GPSBounds, Polygon};
 pub use osm2streets::{
     osm, BufferType, Direction, DrivingSide, IntersectionControl, IntersectionKind, LaneSpec,
     LaneType, MapConfig, NamePerLanguage, RestrictionType, NORMAL_LANE_THICKNESS,
     SIDEWALK_THICKNESS,
 };
-pub use raw_map::{Amenity, AmenityType, AreaType, CrossingType, ExtraPOI, ExtraPOIType};
+pub use raw_map::{
+    Amenity, AmenityType, AreaType, CrossingType, ExtraPOI, ExtraPOIType, KerbType, RawRelation,
+    RelationType, StreetParking,
+};
 
 pub use crate::city::City;
 pub use crate::edits::{
-    EditCmd, EditEffects, EditIntersection, EditIntersectionControl, EditRoad, MapEdits,
-    PermanentMapEdits,
+    validate_cmd, AttributeDiff, EditCmd, EditEffects, EditIntersection, EditIntersectionControl,
+    EditRoad, EditTransitStop, EditsBuilder, InvalidCommand, JunctionTemplate, MapEdits,
+    PermanentMapEdits, TemplateMatch,
 };
 
 pub use crate::make::RawToMapOptions;
 pub use crate::objects::area::{Area, AreaID};
-pub use crate::objects::building::{Building, BuildingID, BuildingType, OffstreetParking};
+pub use crate::objects::building::{
+    Building, BuildingID, BuildingType, OffstreetParking, ProposedBuilding,
+};
+pub use crate::objects::corner_design::{far_side_turn, BikeConflictType, CornerDesign};
+pub use crate::objects::enforcement::{BusLaneEnforcement, SpeedEnforcement};
+pub use crate::objects::gtfs_export::GtfsFeed;
+pub use crate::objects::hgv::{HgvAccess, HgvProfile, HgvRestrictions};
 pub use crate::objects::intersection::{Intersection, IntersectionID};
+pub use crate::objects::kerb::{KerbSegment, KerbUseType};
 pub use crate::objects::lane::{CommonEndpoint, Lane, LaneID, PARKING_LOT_SPOT_LENGTH};
+pub use crate::objects::lane_extent::LaneExtent;
 pub use crate::objects::modal_filter::{DiagonalFilter, FilterType, RoadFilter};
 pub use crate::objects::movement::{CompressedMovementID, Movement, MovementID};
 pub use crate::objects::parking_lot::{ParkingLot, ParkingLotID};
+pub use crate::objects::pricing::{format_cents, PriceSchedule, RoadPricing};
 pub use crate::objects::road::{
-    Crossing, DirectedRoadID, OriginalRoad, Road, RoadID, RoadSideID, SideOfRoad,
+    Crossing, DirectedRoadID, OriginalRoad, Road, RoadID, RoadSideID, RoadStructure, SideOfRoad,
+};
+pub use crate::objects::stop_signs::{ApproachControl, ControlStopSign, RoadWithStopSign};
+pub use crate::objects::traffic_calming::{TrafficCalming, TrafficCalmingType};
+pub use crate::objects::traffic_signals::{
+    Actuation, ControlTrafficSignal, Stage, StageType, TransitPriority,
+    DEFAULT_LEADING_PEDESTRIAN_INTERVAL,
 };
-pub use crate::objects::stop_signs::{ControlStopSign, RoadWithStopSign};
-pub use crate::objects::traffic_signals::{ControlTrafficSignal, Stage, StageType};
 pub use crate::objects::transit::{TransitRoute, TransitRouteID, TransitStop, TransitStopID};
 pub use crate::objects::turn::{Turn, TurnID, TurnPriority, TurnType};
+pub use crate::objects::walking_closure::WalkingClosure;
 pub use crate::objects::zone::{AccessRestrictions, Zone};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn};
 pub use crate::pathfind::{
-    Path, PathConstraints, PathRequest, PathStep, PathStepV2, PathV2, Pathfinder, PathfinderCache,
-    PathfinderCaching, RoutingParams,
+    AlternativeRoutes, Path, PathConstraints, PathRequest, PathStep, PathStepV2, PathV2,
+    Pathfinder, PathfinderCache, PathfinderCaching, RoutingParams,
 };
+pub use crate::region::{Region, Stitch};
 pub use crate::traversable::{Position, Traversable, MAX_BIKE_SPEED, MAX_WALKING_SPEED};
 pub use map::turn_type_from_angles;
 
@@ -83,6 +100,7 @@ mod make;
 mod map;
 mod objects;
 mod pathfind;
+mod region;
 mod traversable;
 
 // The map used by the simulation and UI. This struct is declared here so that the rest of the
@@ -108,11 +126,12 @@ pub struct Map {
     stop_signs: BTreeMap<IntersectionID, ControlStopSign>,
     traffic_signals: BTreeMap<IntersectionID, ControlTrafficSignal>,
 
+    /// Route and boundary relations that some roads belong to
     #[serde(
-        serialize_with = "serialize_multimap",
-        deserialize_with = "deserialize_multimap"
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
     )]
-    bus_routes_on_roads: MultiMap<osm::WayID, String>,
+    osm_relations: BTreeMap<osm::RelationID, RawRelation>,
 
     gps_bounds: GPSBounds,
     bounds: Bounds,
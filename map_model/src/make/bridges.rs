@@ -1,17 +1,25 @@
 use abstutil::Timer;
 use geom::{Distance, FindClosest};
 
-use crate::{Road, RoadID};
+use crate::{Road, RoadID, RoadStructure};
 
 /// Look for roads underneath bridges, then lower their z-order. OSM tags bridges and tunnels, but
-/// not the roads that pass under bridges.
+/// not the roads that pass under bridges. Tunnels missing a `layer` tag are also lowered.
 pub fn find_bridges(roads: &mut Vec<Road>, timer: &mut Timer) {
     let mut closest: FindClosest<RoadID> = FindClosest::new();
     let mut bridges = Vec::new();
-    for r in roads.iter() {
+    for r in roads.iter_mut() {
         closest.add(r.id, r.center_pts.points());
-        if r.osm_tags.contains_key("bridge") {
-            bridges.push(r.id);
+        match r.structure() {
+            RoadStructure::Bridge => {
+                bridges.push(r.id);
+            }
+            RoadStructure::Tunnel => {
+                if r.zorder == 0 {
+                    r.zorder = -1;
+                }
+            }
+            RoadStructure::AtGrade => {}
         }
     }
 
@@ -10,9 +10,12 @@ use raw_map::RawBuilding;
 use crate::make::{match_points_to_lanes, trim_path};
 use crate::{
     osm, Amenity, Building, BuildingID, BuildingType, LaneID, Map, NamePerLanguage,
-    OffstreetParking,
+    OffstreetParking, ProposedBuilding,
 };
 
+/// How many people live in each housing unit of a proposed development
+const RESIDENTS_PER_HOUSING_UNIT: usize = 2;
+
 /// Finalize importing of buildings, mostly by matching them to the nearest sidewalk.
 pub fn make_all_buildings(
     input: &BTreeMap<osm::OsmID, RawBuilding>,
@@ -105,6 +108,8 @@ pub fn make_all_buildings(
                         b.osm_tags.is("building", "parking") || b.osm_tags.is("amenity", "parking"),
                     )
                 },
+                bike_parking: b.bike_parking,
+                orig_bike_parking: b.bike_parking,
                 osm_tags: if keep_bldg_tags {
                     b.osm_tags.clone()
                 } else {
@@ -126,6 +131,70 @@ pub fn make_all_buildings(
     results
 }
 
+/// Like `make_all_buildings`, but for buildings that only exist in a development's site plan. IDs
+/// continue after the map's existing buildings. Proposals too far from a sidewalk are skipped.
+pub fn make_proposed_buildings(
+    proposals: &[ProposedBuilding],
+    map: &Map,
+    timer: &mut Timer,
+) -> Vec<Building> {
+    // Make up OSM IDs that can't clash with real buildings or ones added by the map editor
+    let first_id = map
+        .all_buildings()
+        .iter()
+        .filter_map(|b| match b.orig_id {
+            osm::OsmID::Way(id) => Some(id.0),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(0)
+        .min(0)
+        - 1;
+
+    let mut input = BTreeMap::new();
+    let mut proposal_per_id = BTreeMap::new();
+    for (idx, proposal) in proposals.iter().enumerate() {
+        let id = osm::OsmID::Way(osm::WayID(first_id - idx as i64));
+        let mut osm_tags = Tags::empty();
+        osm_tags.insert("building:levels", proposal.levels.to_string());
+        if let Some(ref name) = proposal.name {
+            osm_tags.insert("name", name.clone());
+        }
+        input.insert(
+            id,
+            RawBuilding {
+                polygon: proposal.polygon.clone(),
+                osm_tags,
+                public_garage_name: None,
+                num_parking_spots: 0,
+                bike_parking: 0,
+                amenities: Vec::new(),
+            },
+        );
+        proposal_per_id.insert(id, proposal);
+    }
+
+    let keep_bldg_tags = true;
+    let mut results = make_all_buildings(&input, map, keep_bldg_tags, timer);
+    for (idx, b) in results.iter_mut().enumerate() {
+        b.id = BuildingID(map.all_buildings().len() + idx);
+        let proposal = proposal_per_id[&b.orig_id];
+        let residents = proposal.housing_units * RESIDENTS_PER_HOUSING_UNIT;
+        // 1 person per 10 square meters, like classify_bldg
+        let workers = (proposal.commercial_floorspace / 10.0) as usize;
+        b.bldg_type = match (residents, workers) {
+            (0, 0) => BuildingType::Empty,
+            (_, 0) => BuildingType::Residential {
+                num_residents: residents,
+                num_housing_units: proposal.housing_units,
+            },
+            (0, _) => BuildingType::Commercial(workers),
+            (_, _) => BuildingType::ResidentialCommercial(residents, workers),
+        };
+    }
+    results
+}
+
 // If the house number is missing, just omit it. (In the past, we showed "???" but this was a
 // confusing UX)
 fn get_address(tags: &Tags, sidewalk: LaneID, map: &Map) -> String {
@@ -0,0 +1,302 @@
+//! Crop a large map down to a smaller study area, to iterate faster on changes that only matter
+//! locally. The RawMap is reconstructed from the Map, so this works even when the original input
+//! data isn't available.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use abstio::MapName;
+use abstutil::{Tags, Timer};
+use geom::{Distance, GPSBounds, LonLat, PolyLine, Polygon, Pt2D};
+use raw_map::{ExtraRoadData, RawArea, RawBuilding, RawMap, RawParkingLot};
+
+use crate::{
+    osm, ExtraPOI, IntersectionControl, IntersectionID, IntersectionKind, Map, OffstreetParking,
+    RawToMapOptions,
+};
+
+/// Roads crossing the boundary are cut there. Stubs shorter than this left inside are dropped,
+/// since there's no room for the intersection geometry at both ends.
+const MIN_CLIPPED_ROAD_LENGTH: Distance = Distance::const_meters(5.0);
+
+impl Map {
+    /// Produces a new map covering only the part of this one inside `boundary`, which is in this
+    /// map's coordinate space. Intersections are kept if their center is inside. Roads crossing
+    /// the boundary are cut there and end at new border intersections. Buildings and parking lots
+    /// must be completely inside; areas and census zones are clipped to the boundary.
+    ///
+    /// Transit routes and map edits aren't carried over, and traffic signals get default timing.
+    pub fn clip_to_study_area(
+        &self,
+        boundary: &Polygon,
+        name: MapName,
+        timer: &mut Timer,
+    ) -> Result<Map> {
+        timer.start("clip map to study area");
+        let old_gps = &self.gps_bounds;
+        let gps_bounds = GPSBounds::from(
+            boundary
+                .get_outer_ring()
+                .points()
+                .iter()
+                .map(|pt| pt.to_gps(old_gps))
+                .collect::<Vec<LonLat>>(),
+        );
+        // The new map's coordinates start from its own corner
+        let translate_pt = |pt: Pt2D| pt.to_gps(old_gps).to_pt(&gps_bounds);
+        let translate_polygon =
+            |polygon: &Polygon| Polygon::from_geo_wgs84(polygon.to_geo_wgs84(old_gps), &gps_bounds);
+
+        let mut raw = RawMap::blank(name);
+        raw.streets.gps_bounds = gps_bounds.clone();
+        raw.streets.boundary_polygon = translate_polygon(boundary)?;
+        raw.streets.config = self.config.clone();
+
+        let mut intersections: BTreeMap<IntersectionID, osm2streets::IntersectionID> =
+            BTreeMap::new();
+        for i in &self.intersections {
+            let center = i.polygon.center();
+            if !boundary.contains_pt(center) {
+                continue;
+            }
+            let id = raw.streets.insert_intersection(
+                vec![i.orig_id],
+                translate_pt(center),
+                i.kind,
+                i.control,
+            );
+            raw.elevation_per_intersection.insert(id, i.elevation);
+            intersections.insert(i.id, id);
+        }
+        if intersections.is_empty() {
+            timer.stop("clip map to study area");
+            bail!("No intersections are inside the study area");
+        }
+
+        let mut roads = BTreeMap::new();
+        for r in &self.roads {
+            let src = intersections.get(&r.src_i).cloned();
+            let dst = intersections.get(&r.dst_i).cloned();
+            let (src_i, dst_i, center) = match (src, dst) {
+                (Some(src_i), Some(dst_i)) => (src_i, dst_i, r.untrimmed_center_pts.clone()),
+                (None, None) => {
+                    continue;
+                }
+                _ => {
+                    // The piece inside must still start from the endpoint that's inside. Roads
+                    // weaving in and out of the boundary are dropped.
+                    let inside_pt = if src.is_some() {
+                        r.untrimmed_center_pts.first_pt()
+                    } else {
+                        r.untrimmed_center_pts.last_pt()
+                    };
+                    let pl = match boundary
+                        .clip_polyline(&r.untrimmed_center_pts)
+                        .and_then(|pts| PolyLine::new(pts).ok())
+                    {
+                        Some(pl) => pl,
+                        None => {
+                            continue;
+                        }
+                    };
+                    let (kept_inside_pt, border_pt) = if src.is_some() {
+                        (pl.first_pt(), pl.last_pt())
+                    } else {
+                        (pl.last_pt(), pl.first_pt())
+                    };
+                    if !kept_inside_pt.approx_eq(inside_pt, Distance::meters(0.1))
+                        || pl.length() < MIN_CLIPPED_ROAD_LENGTH
+                    {
+                        continue;
+                    }
+
+                    let border = raw.streets.insert_intersection(
+                        Vec::new(),
+                        translate_pt(border_pt),
+                        IntersectionKind::MapEdge,
+                        IntersectionControl::Uncontrolled,
+                    );
+                    let outside_i = if src.is_some() { r.dst_i } else { r.src_i };
+                    raw.elevation_per_intersection
+                        .insert(border, self.get_i(outside_i).elevation);
+                    (src.unwrap_or(border), dst.unwrap_or(border), pl)
+                }
+            };
+            let center =
+                match PolyLine::new(center.points().iter().map(|pt| translate_pt(*pt)).collect()) {
+                    Ok(pl) => pl,
+                    Err(err) => {
+                        warn!("Skipping {} in the study area: {}", r.id, err);
+                        continue;
+                    }
+                };
+
+            let id = raw.streets.next_road_id();
+            let way = r.orig_id.osm_way_id;
+            raw.streets.insert_road(osm2streets::Road::new(
+                id,
+                vec![way],
+                src_i,
+                dst_i,
+                center,
+                r.osm_tags.clone(),
+                &raw.streets.config,
+            ));
+            raw.osm_tags.insert(way, r.osm_tags.clone());
+            for rel in &r.osm_relations {
+                raw.relation_members.insert(way, *rel);
+                raw.relations.insert(*rel, self.osm_relations[rel].clone());
+            }
+
+            // Nodes along the road are stored as distances, but the RawMap wants points. Ones
+            // clipped away are dropped.
+            let node_pt = |dist: Distance| {
+                let pt = r.center_pts.dist_along(dist).ok()?.0;
+                if boundary.contains_pt(pt) {
+                    Some(translate_pt(pt))
+                } else {
+                    None
+                }
+            };
+            raw.extra_road_data.insert(
+                id,
+                ExtraRoadData {
+                    percent_incline: r.percent_incline,
+                    crosswalk_forward: r.crosswalk_forward,
+                    crosswalk_backward: r.crosswalk_backward,
+                    barrier_nodes: r.barrier_nodes.iter().filter_map(|d| node_pt(*d)).collect(),
+                    crossing_nodes: r
+                        .crossing_nodes
+                        .iter()
+                        .filter_map(|(d, kind)| node_pt(*d).map(|pt| (pt, *kind)))
+                        .collect(),
+                    kerb_nodes: r
+                        .kerb_nodes
+                        .iter()
+                        .filter_map(|(d, kind)| node_pt(*d).map(|pt| (pt, *kind)))
+                        .collect(),
+                    parking_left: r.parking_left,
+                    parking_right: r.parking_right,
+                },
+            );
+            roads.insert(r.id, id);
+        }
+
+        // Now that all roads have new IDs, carry over turn restrictions between the kept ones
+        for r in &self.roads {
+            if let Some(id) = roads.get(&r.id) {
+                let raw_road = raw.streets.roads.get_mut(id).unwrap();
+                raw_road.turn_restrictions = r
+                    .turn_restrictions
+                    .iter()
+                    .filter_map(|(rt, to)| roads.get(to).map(|to| (*rt, *to)))
+                    .collect();
+                raw_road.complicated_turn_restrictions = r
+                    .complicated_turn_restrictions
+                    .iter()
+                    .filter_map(|(via, to)| Some((*roads.get(via)?, *roads.get(to)?)))
+                    .collect();
+            }
+        }
+
+        for b in &self.buildings {
+            if !b
+                .polygon
+                .get_outer_ring()
+                .points()
+                .iter()
+                .all(|pt| boundary.contains_pt(*pt))
+            {
+                continue;
+            }
+            let (public_garage_name, num_parking_spots) = match b.parking {
+                OffstreetParking::PublicGarage(ref name, spots) => (Some(name.clone()), spots),
+                OffstreetParking::Private(spots, _) => (None, spots),
+            };
+            raw.buildings.insert(
+                b.orig_id,
+                RawBuilding {
+                    polygon: translate_polygon(&b.polygon)?,
+                    osm_tags: b.osm_tags.clone(),
+                    public_garage_name,
+                    num_parking_spots,
+                    bike_parking: b.orig_bike_parking,
+                    amenities: b.amenities.clone(),
+                },
+            );
+        }
+
+        for a in &self.areas {
+            // Areas generated during import, like the ocean, will be made again
+            let osm_id = match a.osm_id {
+                Some(id) => id,
+                None => {
+                    continue;
+                }
+            };
+            // If clipping fails, giving up on some areas is fine
+            if let Ok(list) = boundary.intersection(&a.polygon) {
+                for polygon in list {
+                    raw.areas.push(RawArea {
+                        area_type: a.area_type,
+                        polygon: translate_polygon(&polygon)?,
+                        osm_tags: a.osm_tags.clone(),
+                        osm_id,
+                    });
+                }
+            }
+        }
+
+        for lot in &self.parking_lots {
+            if !lot
+                .polygon
+                .get_outer_ring()
+                .points()
+                .iter()
+                .all(|pt| boundary.contains_pt(*pt))
+            {
+                continue;
+            }
+            raw.parking_lots.push(RawParkingLot {
+                osm_id: lot.osm_id,
+                polygon: translate_polygon(&lot.polygon)?,
+                osm_tags: Tags::empty(),
+            });
+            for aisle in &lot.aisles {
+                // Aisles are matched to lots by location, so the IDs don't matter
+                let id = osm::WayID(-1 - (raw.parking_aisles.len() as i64));
+                raw.parking_aisles
+                    .push((id, aisle.iter().map(|pt| translate_pt(*pt)).collect()));
+            }
+        }
+
+        for (polygon, zone) in &self.census_zones {
+            if let Ok(list) = boundary.intersection(polygon) {
+                for polygon in list {
+                    raw.census_zones
+                        .push((translate_polygon(&polygon)?, zone.clone()));
+                }
+            }
+        }
+        for poi in &self.extra_pois {
+            if boundary.contains_pt(poi.pt) {
+                raw.extra_pois.push(ExtraPOI {
+                    pt: translate_pt(poi.pt),
+                    kind: poi.kind.clone(),
+                });
+            }
+        }
+
+        info!(
+            "Study area has {} of {} roads and {} of {} buildings",
+            raw.streets.roads.len(),
+            self.roads.len(),
+            raw.buildings.len(),
+            self.buildings.len()
+        );
+        timer.stop("clip map to study area");
+
+        Ok(Map::create_from_raw(raw, RawToMapOptions::default(), timer))
+    }
+}
@@ -16,13 +16,15 @@ use raw_map::RawMap;
 pub use self::parking_lots::snap_driveway;
 use crate::pathfind::{CreateEngine, Pathfinder};
 use crate::{
-    connectivity, osm, AccessRestrictions, Area, AreaID, ControlStopSign, ControlTrafficSignal,
-    Intersection, IntersectionControl, IntersectionID, IntersectionKind, Lane, LaneID, Map,
-    MapEdits, OriginalRoad, PathConstraints, Position, Road, RoadID, RoutingParams, Zone,
+    connectivity, osm, AccessRestrictions, Area, AreaID, BusLaneEnforcement, ControlStopSign,
+    ControlTrafficSignal, CornerDesign, HgvRestrictions, Intersection, IntersectionControl,
+    IntersectionID, IntersectionKind, Lane, LaneID, Map, MapEdits, OriginalRoad, PathConstraints,
+    Position, Road, RoadID, RoadPricing, RoutingParams, SpeedEnforcement, Zone,
 };
 
 mod bridges;
-mod buildings;
+pub(crate) mod buildings;
+mod clip;
 mod parking_lots;
 pub mod traffic_signals;
 pub mod transit;
@@ -43,6 +45,10 @@ pub struct RawToMapOptions {
 
 impl Map {
     pub fn create_from_raw(mut raw: RawMap, opts: RawToMapOptions, timer: &mut Timer) -> Map {
+        // RawMaps imported before this pass existed may still have broken geometry
+        for fix in raw_map::repair::repair_geometry(&mut raw, timer) {
+            warn!("Repaired geometry of {}", fix);
+        }
         raw.streets
             .apply_transformations(Transformation::abstreet(), timer);
 
@@ -61,7 +67,7 @@ impl Map {
             boundary_polygon: raw.streets.boundary_polygon.clone(),
             stop_signs: BTreeMap::new(),
             traffic_signals: BTreeMap::new(),
-            bus_routes_on_roads: std::mem::take(&mut raw.bus_routes_on_roads),
+            osm_relations: BTreeMap::new(),
             gps_bounds: raw.streets.gps_bounds.clone(),
             bounds: raw.streets.gps_bounds.to_bounds(),
             config: raw.streets.config.clone(),
@@ -109,6 +115,8 @@ impl Map {
                 outgoing_lanes: Vec::new(),
                 roads: i.roads.iter().map(|id| road_id_mapping[id]).collect(),
                 modal_filter: None,
+                corner_design: CornerDesign::default(),
+                closed_crossings: BTreeMap::new(),
                 merged: !raw.streets.intersections[&i.id]
                     .trim_roads_for_merging
                     .is_empty(),
@@ -116,6 +124,13 @@ impl Map {
             intersection_id_mapping.insert(i.id, id);
         }
 
+        let mut relations_per_road: BTreeMap<osm2streets::RoadID, BTreeSet<osm::RelationID>> = raw
+            .streets
+            .roads
+            .keys()
+            .map(|r| (*r, raw.relations_for_road(*r)))
+            .collect();
+
         timer.start_iter("expand roads to lanes", raw.streets.roads.len());
         for r in raw.streets.roads.values_mut() {
             timer.next();
@@ -128,6 +143,7 @@ impl Map {
             let barrier_nodes = snap_nodes_to_line(&extra.barrier_nodes, &r.center_line);
             let crossing_nodes =
                 snap_nodes_with_data_to_line(&extra.crossing_nodes, &r.center_line);
+            let kerb_nodes = snap_nodes_with_data_to_line(&extra.kerb_nodes, &r.center_line);
 
             // TODO Hack. Roads and intersections each may have ZERO or more OSM IDs.
             let orig_id = OriginalRoad {
@@ -193,10 +209,24 @@ impl Map {
                 modal_filter: None,
                 barrier_nodes,
                 crossing_nodes,
+                kerb_nodes,
                 crossings: Vec::new(),
+                kerb_uses: Vec::new(),
+                bus_lane_enforcement: BusLaneEnforcement::Default,
+                speed_enforcement: SpeedEnforcement::None,
+                pricing: RoadPricing::Free,
+                hov_min_occupancy: None,
+                hgv: HgvRestrictions::unrestricted(),
+                traffic_calming: Vec::new(),
+                lane_extents: BTreeMap::new(),
+                sidewalk_closures: BTreeMap::new(),
+                osm_relations: relations_per_road.remove(&r.id).unwrap_or_default(),
+                parking_left: extra.parking_left,
+                parking_right: extra.parking_right,
             };
             road.speed_limit = road.speed_limit_from_osm();
             road.access_restrictions = road.access_restrictions_from_osm();
+            road.hgv = HgvRestrictions::from_osm(&road.osm_tags);
 
             road.recreate_lanes(r.lane_specs_ltr.clone());
             for lane in &road.lanes {
@@ -207,6 +237,17 @@ impl Map {
             map.roads.push(road);
         }
 
+        // Only keep relations that some road belongs to
+        let used_relations: BTreeSet<osm::RelationID> = map
+            .roads
+            .iter()
+            .flat_map(|r| r.osm_relations.iter().cloned())
+            .collect();
+        map.osm_relations = std::mem::take(&mut raw.relations)
+            .into_iter()
+            .filter(|(id, _)| used_relations.contains(id))
+            .collect();
+
         for i in map.intersections.iter_mut() {
             if i.is_border() && i.roads.len() != 1 {
                 // i.orig_id may be synthetic and useless, so also print OSM links of the roads
@@ -74,6 +74,10 @@ fn new(id: IntersectionID) -> ControlTrafficSignal {
         id,
         stages: Vec::new(),
         offset: Duration::ZERO,
+        transit_priority: None,
+        leading_pedestrian_interval: None,
+        actuation: None,
+        cluster: BTreeSet::new(),
     }
 }
 
@@ -219,10 +219,16 @@ fn create_route(
         }
     };
 
-    // TODO This'll come from the RawTransitRoute eventually. For now, every 30 minutes.
-    let spawn_times: Vec<Time> = (0..48)
-        .map(|i| Time::START_OF_DAY + (i as f64) * Duration::minutes(30))
-        .collect();
+    // Use the GTFS schedule if there is one, otherwise every 30 minutes. The schedule says when
+    // vehicles depart the first stop, but they spawn at the start, which may be a border a bit
+    // before that. This is close enough.
+    let spawn_times: Vec<Time> = if route.spawn_times.is_empty() {
+        (0..48)
+            .map(|i| Time::START_OF_DAY + (i as f64) * Duration::minutes(30))
+            .collect()
+    } else {
+        route.spawn_times.clone()
+    };
 
     let result = TransitRoute {
         id: TransitRouteID(map.transit_routes.len()),
@@ -238,6 +244,7 @@ fn create_route(
         },
         spawn_times: spawn_times.clone(),
         orig_spawn_times: spawn_times,
+        alignment: Vec::new(),
     };
 
     // Check that the paths are valid
@@ -12,19 +12,20 @@ use popgetter::CensusZone;
 use abstio::{CityName, MapName};
 use abstutil::{prettyprint_usize, serialized_size_bytes, MultiMap, Tags, Timer};
 use geom::{
-    Angle, Bounds, Distance, Duration, FindClosest, GPSBounds, LonLat, PolyLine, Polygon, Pt2D,
-    Ring, Time,
+    Angle, Bounds, Daylight, Distance, Duration, FindClosest, GPSBounds, LonLat, PolyLine, Polygon,
+    Pt2D, Ring, Time,
 };
 use raw_map::{RawBuilding, RawMap};
 
 use crate::{
     osm, AmenityType, Area, AreaID, AreaType, Building, BuildingID, BuildingType, CommonEndpoint,
     CompressedMovementID, ControlStopSign, ControlTrafficSignal, DirectedRoadID, Direction,
-    DrivingSide, ExtraPOI, Intersection, IntersectionControl, IntersectionID, IntersectionKind,
-    Lane, LaneID, LaneType, Map, MapConfig, MapEdits, Movement, MovementID, OffstreetParking,
-    OriginalRoad, ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest, PathV2, Pathfinder,
-    PathfinderCaching, Position, Road, RoadFilter, RoadID, RoutingParams, TransitRoute,
-    TransitRouteID, TransitStop, TransitStopID, Turn, TurnID, TurnType, Zone,
+    DrivingSide, ExtraPOI, HgvProfile, Intersection, IntersectionControl, IntersectionID,
+    IntersectionKind, Lane, LaneID, LaneType, Map, MapConfig, MapEdits, Movement, MovementID,
+    OffstreetParking, OriginalRoad, ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest,
+    PathV2, Pathfinder, PathfinderCaching, Position, ProposedBuilding, RawRelation, RelationType,
+    Road, RoadFilter, RoadID, RoadPricing, RoutingParams, TransitRoute, TransitRouteID,
+    TransitStop, TransitStopID, Turn, TurnID, TurnType, Zone,
 };
 
 impl Map {
@@ -164,7 +165,7 @@ impl Map {
             .into_polygon(),
             stop_signs: BTreeMap::new(),
             traffic_signals: BTreeMap::new(),
-            bus_routes_on_roads: MultiMap::new(),
+            osm_relations: BTreeMap::new(),
             gps_bounds: GPSBounds::new(),
             bounds: Bounds::new(),
             config: MapConfig::default(),
@@ -234,6 +235,7 @@ impl Map {
                 osm_tags: Tags::empty(),
                 public_garage_name: None,
                 num_parking_spots: 0,
+                bike_parking: 0,
                 amenities: Vec::new(),
             },
         );
@@ -529,6 +531,14 @@ impl Map {
         &self.bounds
     }
 
+    /// When the sun rises and sets over the middle of this map, on some day of the year
+    pub fn get_daylight(&self, day_of_year: usize) -> Daylight {
+        Daylight::new(
+            LonLat::center(&self.gps_bounds.get_rectangle()),
+            day_of_year,
+        )
+    }
+
     pub fn get_city_name(&self) -> &CityName {
         &self.name.city
     }
@@ -690,6 +700,14 @@ impl Map {
             .pathfind_with_params(req.clone(), params, cache_custom, self)
             .ok_or_else(|| anyhow!("can't fulfill {}", req))
     }
+    /// Like `pathfind`, but for a pedestrian using a wheelchair.
+    pub fn pathfind_wheelchair(&self, req: PathRequest) -> Result<Path> {
+        assert!(!self.pathfinder_dirty);
+        self.pathfinder
+            .pathfind_wheelchair(req.clone(), self)
+            .ok_or_else(|| anyhow!("can't fulfill {} by wheelchair", req))?
+            .into_v1(self)
+    }
     pub fn should_use_transit(
         &self,
         start: Position,
@@ -817,6 +835,21 @@ impl Map {
         self.transit_routes[br.0].spawn_times = times;
     }
 
+    /// Adds the buildings from a proposed development, connecting them to the nearest sidewalks,
+    /// and returns their IDs. Proposals too far from any sidewalk are skipped. These aren't map
+    /// edits, so they only last until the map is reloaded.
+    pub fn add_proposed_buildings(
+        &mut self,
+        proposals: &[ProposedBuilding],
+        timer: &mut Timer,
+    ) -> Vec<BuildingID> {
+        let bldgs = crate::make::buildings::make_proposed_buildings(proposals, self, timer);
+        let ids = bldgs.iter().map(|b| b.id).collect();
+        self.buildings.extend(bldgs);
+        self.recalculate_road_to_buildings();
+        ids
+    }
+
     pub fn hack_add_area(&mut self, area_type: AreaType, polygon: Polygon, osm_tags: Tags) {
         self.areas.push(Area {
             id: AreaID(self.areas.len()),
@@ -957,6 +990,44 @@ impl Map {
         params
     }
 
+    /// Adjusts the routing params baked into the map for a heavy goods vehicle, so it avoids roads
+    /// it's banned from, only allowed to access, or too big for.
+    // TODO Destination-only roads are treated like banned ones, so HGVs delivering along them
+    // rely on how avoid_roads handles the start and end of a route.
+    pub fn hgv_routing_params(&self, profile: &HgvProfile) -> RoutingParams {
+        let mut params = self.routing_params.clone();
+        for r in &self.roads {
+            if !r.hgv.permits_through(profile) {
+                params.avoid_roads.insert(r.id);
+            }
+        }
+        params
+    }
+
+    /// Adjusts the routing params baked into the map to avoid every road with a toll, so drivers
+    /// can compare a tolled route with the best free one.
+    pub fn toll_free_routing_params(&self) -> RoutingParams {
+        let mut params = self.routing_params.clone();
+        for r in &self.roads {
+            if let RoadPricing::Toll(_) = r.pricing {
+                params.avoid_roads.insert(r.id);
+            }
+        }
+        params
+    }
+
+    /// Adjusts the routing params baked into the map to avoid every road closed to cars at this
+    /// time, because it's outside of the road's delivery windows.
+    pub fn delivery_window_routing_params(&self, time: Time) -> RoutingParams {
+        let mut params = self.routing_params.clone();
+        for r in &self.roads {
+            if !r.access_restrictions.cars_allowed_at(time) {
+                params.avoid_roads.insert(r.id);
+            }
+        }
+        params
+    }
+
     pub fn road_to_buildings(&self, r: RoadID) -> &BTreeSet<BuildingID> {
         self.road_to_buildings.get(r)
     }
@@ -1086,11 +1157,41 @@ impl Map {
         geom::geometries_with_properties_to_geojson(pairs)
     }
 
-    /// What're the names of bus routes along a road? Note this is best effort, not robust to edits
-    /// or transformations.
-    pub fn get_bus_routes_on_road(&self, r: RoadID) -> &BTreeSet<String> {
-        let way = self.get_r(r).orig_id.osm_way_id;
-        self.bus_routes_on_roads.get(way)
+    /// What're the names of bus routes along a road? These come from OSM relations, so they don't
+    /// reflect edits.
+    pub fn get_bus_routes_on_road(&self, r: RoadID) -> BTreeSet<String> {
+        self.get_relations_on_road(r, RelationType::BusRoute)
+            .filter_map(|rel| rel.name.clone())
+            .collect()
+    }
+
+    /// What signed cycle routes run along a road?
+    pub fn get_cycle_networks_on_road(&self, r: RoadID) -> Vec<&RawRelation> {
+        self.get_relations_on_road(r, RelationType::CycleRoute)
+            .collect()
+    }
+
+    /// Which administrative boundaries run along a road?
+    pub fn get_boundaries_on_road(&self, r: RoadID) -> Vec<&RawRelation> {
+        self.get_relations_on_road(r, RelationType::Boundary)
+            .collect()
+    }
+
+    /// The route or boundary relation with this OSM ID, if any road belongs to it
+    pub fn get_osm_relation(&self, id: osm::RelationID) -> Option<&RawRelation> {
+        self.osm_relations.get(&id)
+    }
+
+    fn get_relations_on_road(
+        &self,
+        r: RoadID,
+        rel_type: RelationType,
+    ) -> impl Iterator<Item = &RawRelation> {
+        self.get_r(r)
+            .osm_relations
+            .iter()
+            .filter_map(|id| self.osm_relations.get(id))
+            .filter(move |rel| rel.rel_type == rel_type)
     }
 
     /// Find all amenity types that at least 1 building contains
@@ -39,6 +39,10 @@ pub struct Building {
     pub amenities: Vec<Amenity>,
     pub bldg_type: BuildingType,
     pub parking: OffstreetParking,
+    /// How many bikes can be parked here. Map edits can change this.
+    pub bike_parking: usize,
+    /// The amount of bike parking before any map edits
+    pub orig_bike_parking: usize,
     /// Depending on options while importing, these might be empty, to save file space.
     pub osm_tags: Tags,
 
@@ -49,6 +53,18 @@ pub struct Building {
     pub driveway_geom: PolyLine,
 }
 
+/// A building from the site plan of a proposed development, which doesn't exist in OSM yet. See
+/// `Map::add_proposed_buildings`.
+#[derive(Clone, Debug)]
+pub struct ProposedBuilding {
+    pub polygon: Polygon,
+    pub name: Option<String>,
+    pub levels: f64,
+    pub housing_units: usize,
+    /// Square meters of offices, shops, and other workplaces, across all levels
+    pub commercial_floorspace: f64,
+}
+
 /// Represent no parking as Private(0, false).
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum OffstreetParking {
@@ -141,6 +157,13 @@ impl Building {
         }
     }
 
+    /// Does a driveway from this building cross the sidewalk? Only buildings with off-street
+    /// parking have one. Vehicles using it are a minor conflict point for people walking and
+    /// cycling past.
+    pub fn has_driveway(&self) -> bool {
+        self.num_parking_spots() > 0
+    }
+
     /// Does this building contain any amenity matching the category?
     pub fn has_amenity(&self, category: AmenityType) -> bool {
         for amenity in &self.amenities {
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{DrivingSide, Intersection, Map, Turn, TurnID, TurnType};
+
+/// Design elements at an intersection that keep people cycling apart from turning vehicles.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CornerDesign {
+    /// Concrete islands at the corners hold cycle tracks apart from vehicle lanes until the
+    /// crossing, so turning drivers meet people cycling nearly head-on and have to yield to them.
+    pub protected_corners: bool,
+    /// Instead of merging across traffic to turn, people cycling go straight, wait in a box at the
+    /// far corner, then go straight again on the cross street's green.
+    pub two_stage_turn_boxes: bool,
+    /// Crossings for people walking and cycling are set back from the intersection by about a car
+    /// length, giving turning drivers space to see and yield to them.
+    pub setback_crossings: bool,
+}
+
+impl CornerDesign {
+    pub fn is_empty(&self) -> bool {
+        self == &CornerDesign::default()
+    }
+
+    pub fn describe(&self) -> Vec<&'static str> {
+        let mut list = Vec::new();
+        if self.protected_corners {
+            list.push("protected corners");
+        }
+        if self.two_stage_turn_boxes {
+            list.push("two-stage turn boxes");
+        }
+        if self.setback_crossings {
+            list.push("setback crossings");
+        }
+        list
+    }
+
+    /// Must a motor vehicle yield to somebody cycling, when their turns conflict like this?
+    pub fn vehicles_yield_to_bikes(&self, conflict: BikeConflictType) -> bool {
+        conflict == BikeConflictType::Hook && (self.protected_corners || self.setback_crossings)
+    }
+}
+
+/// How the paths of a motor vehicle and somebody cycling cross at an intersection. This is a
+/// rough safety proxy; some kinds of conflicts are far more dangerous than others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum BikeConflictType {
+    /// A vehicle turns across somebody cycling alongside it, coming from the same road
+    Hook,
+    /// Somebody cycling turns across the flow of vehicle traffic
+    TurningAcross,
+    /// Paths from different roads cross, which signals and right-of-way usually handle
+    Crossing,
+}
+
+impl BikeConflictType {
+    pub fn all() -> Vec<BikeConflictType> {
+        vec![
+            BikeConflictType::Hook,
+            BikeConflictType::TurningAcross,
+            BikeConflictType::Crossing,
+        ]
+    }
+
+    /// Classifies a motor vehicle's turn and a bike's turn. Doesn't check if they actually
+    /// conflict.
+    pub fn classify(vehicle: &Turn, bike: &Turn, map: &Map) -> BikeConflictType {
+        if map.get_l(vehicle.id.src).get_directed_parent()
+            == map.get_l(bike.id.src).get_directed_parent()
+        {
+            BikeConflictType::Hook
+        } else if bike.turn_type == far_side_turn(map) {
+            BikeConflictType::TurningAcross
+        } else {
+            BikeConflictType::Crossing
+        }
+    }
+
+    /// Does some element of the design remove or defuse this kind of conflict?
+    pub fn mitigated_by(self, design: &CornerDesign) -> bool {
+        match self {
+            BikeConflictType::Hook => design.protected_corners || design.setback_crossings,
+            BikeConflictType::TurningAcross => design.two_stage_turn_boxes,
+            BikeConflictType::Crossing => false,
+        }
+    }
+}
+
+/// The turn that crosses oncoming traffic: left when driving on the right
+pub fn far_side_turn(map: &Map) -> TurnType {
+    if map.get_config().driving_side == DrivingSide::Right {
+        TurnType::Left
+    } else {
+        TurnType::Right
+    }
+}
+
+impl Intersection {
+    /// Every pair of a motor vehicle turn and a bike turn whose paths cross here. Only bike lanes
+    /// and cycle tracks count; people cycling in mixed traffic aren't considered.
+    pub fn bike_conflicts(&self, map: &Map) -> Vec<(TurnID, TurnID, BikeConflictType)> {
+        let mut conflicts = Vec::new();
+        for vehicle in &self.turns {
+            let src = map.get_l(vehicle.id.src);
+            if !(src.is_driving() || src.is_bus()) || vehicle.between_sidewalks() {
+                continue;
+            }
+            for bike in &self.turns {
+                if map.get_l(bike.id.src).is_biking() && vehicle.conflicts_with(bike) {
+                    conflicts.push((
+                        vehicle.id,
+                        bike.id,
+                        BikeConflictType::classify(vehicle, bike, map),
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// How strictly the bus lanes along a road are enforced. Some drivers will jump into a bus lane to
+/// skip a queue unless something stops them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BusLaneEnforcement {
+    /// Drivers violate as often as the simulation is configured to
+    Default,
+    /// Override the percent of drivers who'll use bus lanes here illegally
+    ViolationPct(u8),
+    /// An enforcement camera deters every driver
+    Camera,
+}
+
+impl BusLaneEnforcement {
+    /// What percent of drivers will illegally use a bus lane, given the simulation-wide default?
+    pub fn violation_pct(self, default_pct: u8) -> u8 {
+        match self {
+            BusLaneEnforcement::Default => default_pct,
+            BusLaneEnforcement::ViolationPct(pct) => pct,
+            BusLaneEnforcement::Camera => 0,
+        }
+    }
+}
+
+/// Devices catching drivers who go over the speed limit along a road. Only some drivers habitually
+/// speed; of those, some still ignore the device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpeedEnforcement {
+    None,
+    /// A camera at one spot. Many drivers only slow down right around it, so it deters fewer of
+    /// them along the whole road.
+    SpotCamera {
+        compliance_pct: u8,
+    },
+    /// Cameras timing vehicles between two points, so drivers must keep their average speed down
+    AverageSpeed {
+        compliance_pct: u8,
+    },
+}
+
+impl SpeedEnforcement {
+    /// What percent of drivers who'd otherwise speed obey the limit here?
+    pub fn compliance_pct(self) -> u8 {
+        match self {
+            SpeedEnforcement::None => 0,
+            SpeedEnforcement::SpotCamera { compliance_pct }
+            | SpeedEnforcement::AverageSpeed { compliance_pct } => compliance_pct,
+        }
+    }
+}
+
+impl fmt::Display for SpeedEnforcement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpeedEnforcement::None => write!(f, "no speed enforcement"),
+            SpeedEnforcement::SpotCamera { compliance_pct } => {
+                write!(f, "speed camera ({}% of speeders comply)", compliance_pct)
+            }
+            SpeedEnforcement::AverageSpeed { compliance_pct } => write!(
+                f,
+                "average speed cameras ({}% of speeders comply)",
+                compliance_pct
+            ),
+        }
+    }
+}
+
+impl fmt::Display for BusLaneEnforcement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BusLaneEnforcement::Default => write!(f, "no enforcement"),
+            BusLaneEnforcement::ViolationPct(pct) => write!(f, "{}% of drivers violate", pct),
+            BusLaneEnforcement::Camera => write!(f, "enforcement camera"),
+        }
+    }
+}
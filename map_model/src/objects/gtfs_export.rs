@@ -0,0 +1,169 @@
+//! Export the transit network, including any edits to schedules, as a GTFS feed. See
+//! <https://developers.google.com/transit/gtfs/reference>. The output is just the minimal set of
+//! files, good enough to load into tools like OpenTripPlanner.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use geom::Time;
+
+use crate::objects::transit::DWELL_TIME;
+use crate::{Map, PathConstraints, TransitRoute};
+
+/// Each file in a GTFS feed, as (filename, CSV contents)
+pub struct GtfsFeed {
+    pub files: Vec<(String, String)>,
+}
+
+impl GtfsFeed {
+    /// Write every file to a directory, returning the paths written.
+    pub fn write(&self, dir: &str) -> Result<Vec<String>> {
+        let mut paths = Vec::new();
+        for (filename, contents) in &self.files {
+            paths.push(abstio::write_file(
+                format!("{}/{}", dir, filename),
+                contents.clone(),
+            )?);
+        }
+        Ok(paths)
+    }
+}
+
+impl Map {
+    /// Describe all transit stops and routes, using the current (possibly edited) schedule.
+    /// Arrival times at each stop are estimated from free-flow travel time, without any traffic.
+    pub fn export_gtfs(&self) -> Result<GtfsFeed> {
+        let agency_id = self.get_name().as_filename();
+
+        let mut agency = String::new();
+        writeln!(agency, "agency_id,agency_name,agency_url,agency_timezone")?;
+        writeln!(
+            agency,
+            "{},{},https://abstreet.org,UTC",
+            escape(&agency_id),
+            escape(&self.get_name().describe())
+        )?;
+
+        let mut calendar = String::new();
+        writeln!(calendar, "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date")?;
+        writeln!(calendar, "everyday,1,1,1,1,1,1,1,20000101,20991231")?;
+
+        let mut stops = String::new();
+        writeln!(stops, "stop_id,stop_name,stop_lat,stop_lon")?;
+        for ts in self.all_transit_stops().values() {
+            let gps = ts.sidewalk_pos.pt(self).to_gps(self.get_gps_bounds());
+            writeln!(
+                stops,
+                "{},{},{},{}",
+                escape(&stop_id(ts)),
+                escape(&ts.name),
+                gps.y(),
+                gps.x()
+            )?;
+        }
+
+        let mut routes = String::new();
+        writeln!(
+            routes,
+            "route_id,agency_id,route_short_name,route_long_name,route_type"
+        )?;
+        let mut trips = String::new();
+        writeln!(trips, "route_id,service_id,trip_id")?;
+        let mut stop_times = String::new();
+        writeln!(
+            stop_times,
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence"
+        )?;
+
+        for tr in self.all_transit_routes() {
+            let route_id = route_id(tr);
+            writeln!(
+                routes,
+                "{},{},{},{},{}",
+                escape(&route_id),
+                escape(&agency_id),
+                escape(&tr.short_name),
+                escape(&tr.long_name),
+                // Bus or rail
+                if tr.route_type == PathConstraints::Bus {
+                    3
+                } else {
+                    2
+                }
+            )?;
+
+            let offsets = tr.estimate_stop_arrivals(self)?;
+
+            for (trip_idx, spawn_time) in tr.spawn_times.iter().enumerate() {
+                let trip_id = format!("{}_{}", route_id, trip_idx);
+                writeln!(
+                    trips,
+                    "{},everyday,{}",
+                    escape(&route_id),
+                    escape(&trip_id)
+                )?;
+                for (seq, (ts, offset)) in tr.stops.iter().zip(offsets.iter()).enumerate() {
+                    let arrival = *spawn_time + *offset;
+                    writeln!(
+                        stop_times,
+                        "{},{},{},{},{}",
+                        escape(&trip_id),
+                        gtfs_time(arrival),
+                        gtfs_time(arrival + DWELL_TIME),
+                        escape(&stop_id(self.get_ts(*ts))),
+                        seq + 1
+                    )?;
+                }
+            }
+        }
+
+        Ok(GtfsFeed {
+            files: vec![
+                ("agency.txt".to_string(), agency),
+                ("calendar.txt".to_string(), calendar),
+                ("stops.txt".to_string(), stops),
+                ("routes.txt".to_string(), routes),
+                ("trips.txt".to_string(), trips),
+                ("stop_times.txt".to_string(), stop_times),
+            ],
+        })
+    }
+}
+
+// Prefer the original GTFS IDs, so the output can be matched up with the agency's feed. Stops and
+// routes created in A/B Street won't have one.
+fn stop_id(ts: &crate::TransitStop) -> String {
+    if ts.gtfs_id.is_empty() {
+        format!("abst_{}_{}", ts.id.road.0, ts.id.idx)
+    } else {
+        ts.gtfs_id.clone()
+    }
+}
+
+fn route_id(tr: &TransitRoute) -> String {
+    if tr.gtfs_id.is_empty() {
+        format!("abst_{}", tr.id.0)
+    } else {
+        tr.gtfs_id.clone()
+    }
+}
+
+/// GTFS uses HH:MM:SS, with hours allowed to exceed 24 for service past midnight.
+fn gtfs_time(t: Time) -> String {
+    let total = t.inner_seconds().round() as usize;
+    format!(
+        "{:02}:{:02}:{:02}",
+        total / 3600,
+        (total % 3600) / 60,
+        total % 60
+    )
+}
+
+fn escape(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::Tags;
+use geom::Distance;
+
+/// May heavy goods vehicles (lorries) use a road at all, independent of their size?
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HgvAccess {
+    Allowed,
+    /// Part of a signed lorry route, which HGVs are encouraged to use
+    Designated,
+    /// Only HGVs loading or unloading along this road may use it, not through traffic
+    Destination,
+    Banned,
+}
+
+/// What a road permits heavy goods vehicles to do, from `hgv`, `maxweight`, and `maxheight` tags
+/// in OSM. Weight and height limits apply to every vehicle, but only matter for HGVs here.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HgvRestrictions {
+    pub access: HgvAccess,
+    /// In kilograms, for the whole laden vehicle
+    pub max_weight_kg: Option<usize>,
+    /// Usually from a low bridge or tunnel somewhere along the road
+    pub max_height: Option<Distance>,
+}
+
+/// The size of one heavy goods vehicle, to decide which roads it may use
+#[derive(Clone, Debug, PartialEq)]
+pub struct HgvProfile {
+    pub weight_kg: usize,
+    pub height: Distance,
+}
+
+impl Default for HgvProfile {
+    /// A typical rigid or articulated lorry, heavy enough to be caught by most weight limits
+    fn default() -> HgvProfile {
+        HgvProfile {
+            weight_kg: 18_000,
+            height: Distance::meters(4.0),
+        }
+    }
+}
+
+impl HgvRestrictions {
+    pub fn unrestricted() -> HgvRestrictions {
+        HgvRestrictions {
+            access: HgvAccess::Allowed,
+            max_weight_kg: None,
+            max_height: None,
+        }
+    }
+
+    pub fn from_osm(tags: &Tags) -> HgvRestrictions {
+        let access = if tags.is_any("hgv", vec!["no", "private"]) {
+            HgvAccess::Banned
+        } else if tags.is_any("hgv", vec!["destination", "delivery", "agricultural"]) {
+            HgvAccess::Destination
+        } else if tags.is("hgv", "designated") {
+            HgvAccess::Designated
+        } else {
+            HgvAccess::Allowed
+        };
+        HgvRestrictions {
+            access,
+            max_weight_kg: tags
+                .get("maxweight")
+                .or_else(|| tags.get("maxweight:hgv"))
+                .and_then(|x| parse_weight_kg(x)),
+            max_height: tags.get("maxheight").and_then(|x| parse_height(x)),
+        }
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.access == HgvAccess::Allowed
+            && self.max_weight_kg.is_none()
+            && self.max_height.is_none()
+    }
+
+    /// May a vehicle this size pass through the road? Destination-only roads don't permit it.
+    pub fn permits_through(&self, profile: &HgvProfile) -> bool {
+        matches!(self.access, HgvAccess::Allowed | HgvAccess::Designated) && self.fits(profile)
+    }
+
+    /// Is the vehicle too heavy or tall for the road?
+    pub fn fits(&self, profile: &HgvProfile) -> bool {
+        self.max_weight_kg
+            .map(|max| profile.weight_kg <= max)
+            .unwrap_or(true)
+            && self
+                .max_height
+                .map(|max| profile.height <= max)
+                .unwrap_or(true)
+    }
+}
+
+impl fmt::Display for HgvRestrictions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        match self.access {
+            HgvAccess::Allowed => {}
+            HgvAccess::Designated => parts.push("HGV route".to_string()),
+            HgvAccess::Destination => parts.push("HGVs for access only".to_string()),
+            HgvAccess::Banned => parts.push("no HGVs".to_string()),
+        }
+        if let Some(kg) = self.max_weight_kg {
+            parts.push(format!("{:.1}t weight limit", kg as f64 / 1000.0));
+        }
+        if let Some(height) = self.max_height {
+            parts.push(format!("{:.1}m height limit", height.inner_meters()));
+        }
+        if parts.is_empty() {
+            write!(f, "no HGV restrictions")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// Handles "7.5", "7.5 t", "3500 kg", and "10 st" (short tons)
+fn parse_weight_kg(value: &str) -> Option<usize> {
+    let value = value.trim();
+    let (number, kg_per_unit) = if let Some(x) = value.strip_suffix("kg") {
+        (x, 1.0)
+    } else if let Some(x) = value.strip_suffix("st") {
+        (x, 907.185)
+    } else if let Some(x) = value.strip_suffix('t') {
+        (x, 1000.0)
+    } else {
+        (value, 1000.0)
+    };
+    let number = number.trim().parse::<f64>().ok()?;
+    if number <= 0.0 {
+        return None;
+    }
+    Some((number * kg_per_unit).round() as usize)
+}
+
+/// Handles "4.2", "4.2 m", and feet and inches like "14'6\"". Values like "default" or "none"
+/// aren't limits.
+fn parse_height(value: &str) -> Option<Distance> {
+    let value = value.trim();
+    if let Some((feet, inches)) = value.split_once('\'') {
+        let feet = feet.trim().parse::<f64>().ok()?;
+        let inches = inches.trim().trim_end_matches('"').trim();
+        let inches = if inches.is_empty() {
+            0.0
+        } else {
+            inches.parse::<f64>().ok()?
+        };
+        return Some(Distance::feet(feet + inches / 12.0));
+    }
+    let meters = value
+        .strip_suffix('m')
+        .unwrap_or(value)
+        .trim()
+        .parse::<f64>()
+        .ok()?;
+    if meters <= 0.0 {
+        return None;
+    }
+    Some(Distance::meters(meters))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_weight() {
+        assert_eq!(parse_weight_kg("7.5"), Some(7500));
+        assert_eq!(parse_weight_kg("7.5 t"), Some(7500));
+        assert_eq!(parse_weight_kg("3500 kg"), Some(3500));
+        assert_eq!(parse_weight_kg("10 st"), Some(9072));
+        assert_eq!(parse_weight_kg("none"), None);
+    }
+
+    #[test]
+    fn test_parse_height() {
+        assert_eq!(parse_height("4.2"), Some(Distance::meters(4.2)));
+        assert_eq!(parse_height("4.2 m"), Some(Distance::meters(4.2)));
+        assert_eq!(parse_height("14'6\""), Some(Distance::feet(14.5)));
+        assert_eq!(parse_height("default"), None);
+    }
+}
@@ -7,9 +7,9 @@ use abstutil::{deserialize_usize, serialize_usize};
 use geom::{Distance, Polygon};
 
 use crate::{
-    osm, CompressedMovementID, DiagonalFilter, DirectedRoadID, IntersectionControl,
+    osm, CompressedMovementID, CornerDesign, DiagonalFilter, DirectedRoadID, IntersectionControl,
     IntersectionKind, LaneID, Map, Movement, MovementID, PathConstraints, Road, RoadID, RoadSideID,
-    SideOfRoad, Turn, TurnID,
+    SideOfRoad, Turn, TurnID, WalkingClosure,
 };
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -53,6 +53,10 @@ pub struct Intersection {
     pub roads: Vec<RoadID>,
 
     pub modal_filter: Option<DiagonalFilter>,
+    /// Design elements protecting people cycling from turning vehicles
+    pub corner_design: CornerDesign,
+    /// Crossings here that are temporarily unusable on foot
+    pub closed_crossings: BTreeMap<TurnID, WalkingClosure>,
 
     /// Was a short road adjacent to this intersection merged?
     pub merged: bool,
@@ -89,6 +93,20 @@ impl Intersection {
         self.roads.iter().all(|r| map.get_r(*r).is_light_rail())
     }
 
+    /// Where rail tracks cross a road at grade. Road traffic has to stop for trains here.
+    pub fn is_level_crossing(&self, map: &Map) -> bool {
+        let mut rail = false;
+        let mut other = false;
+        for r in &self.roads {
+            if map.get_r(*r).is_light_rail() {
+                rail = true;
+            } else {
+                other = true;
+            }
+        }
+        rail && other
+    }
+
     pub fn is_private(&self, map: &Map) -> bool {
         self.roads.iter().all(|r| map.get_r(*r).is_private())
     }
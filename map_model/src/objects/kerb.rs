@@ -0,0 +1,85 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use geom::Distance;
+
+use crate::{Direction, Lane, Road};
+
+/// How a stretch of kerb is used. Anything except on-street parking takes space away from parking
+/// lanes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum KerbUseType {
+    Parking,
+    /// Only delivery vehicles may stop here
+    LoadingBay,
+    BusStop,
+    BikeParking,
+    /// A small public space built over what used to be parking
+    Parklet,
+    /// Taxis and cars dropping somebody off may stop here briefly, out of the way of traffic
+    PickUpDropOff,
+}
+
+impl KerbUseType {
+    pub fn all() -> Vec<KerbUseType> {
+        vec![
+            KerbUseType::Parking,
+            KerbUseType::LoadingBay,
+            KerbUseType::BusStop,
+            KerbUseType::BikeParking,
+            KerbUseType::Parklet,
+            KerbUseType::PickUpDropOff,
+        ]
+    }
+}
+
+impl fmt::Display for KerbUseType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                KerbUseType::Parking => "parking",
+                KerbUseType::LoadingBay => "loading bay",
+                KerbUseType::BusStop => "bus stop",
+                KerbUseType::BikeParking => "bike parking",
+                KerbUseType::Parklet => "parklet",
+                KerbUseType::PickUpDropOff => "pick-up/drop-off bay",
+            }
+        )
+    }
+}
+
+/// A stretch of kerb along one side of a road with a particular use. Kerb not covered by any
+/// segment is used however the lanes say -- usually parking, if there's a parking lane.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct KerbSegment {
+    /// The side of the road, named after the direction of the lanes there
+    pub side: Direction,
+    /// Distances along the road's center line, with start <= end
+    pub start: Distance,
+    pub end: Distance,
+    pub use_type: KerbUseType,
+}
+
+impl KerbSegment {
+    pub fn length(&self) -> Distance {
+        self.end - self.start
+    }
+
+    /// Does this segment overlap the interval [start, end] along the given lane? The lane must
+    /// belong to the road.
+    pub fn overlaps_lane(&self, road: &Road, lane: &Lane, start: Distance, end: Distance) -> bool {
+        if lane.dir != self.side {
+            return false;
+        }
+        // Lanes pointing backwards measure distance from the other end of the road
+        let (start, end) = if lane.dir == Direction::Fwd {
+            (start, end)
+        } else {
+            (road.length() - end, road.length() - start)
+        };
+        start < self.end && self.start < end
+    }
+}
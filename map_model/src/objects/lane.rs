@@ -6,8 +6,8 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use geom::{Distance, Line, PolyLine, Polygon, Pt2D};
 
 use crate::{
-    DirectedRoadID, Direction, DrivingSide, IntersectionID, LaneType, Map, MapConfig, Road, RoadID,
-    RoadSideID, SideOfRoad, TurnType,
+    DirectedRoadID, Direction, DrivingSide, IntersectionID, LaneExtent, LaneType, Map, MapConfig,
+    Road, RoadID, RoadSideID, SideOfRoad, TurnType, WalkingClosure,
 };
 
 /// From some manually audited cases in Seattle, the length of parallel street parking spots is a
@@ -88,6 +88,10 @@ pub struct Lane {
     /// graph, because this is near a border.
     pub driving_blackhole: bool,
     pub biking_blackhole: bool,
+
+    /// If the lane only covers part of the road, where it has its full width, measured along
+    /// lane_center_pts. Copied from the road's `lane_extents`.
+    pub extent: Option<LaneExtent>,
 }
 
 impl Lane {
@@ -229,6 +233,20 @@ impl Lane {
         }
     }
 
+    /// Is this sidewalk or shoulder temporarily unusable on foot?
+    pub fn walking_closure(&self, map: &Map) -> Option<WalkingClosure> {
+        if !self.is_walkable() {
+            return None;
+        }
+        let road = map.get_r(self.id.road);
+        if road.sidewalk_closures.is_empty() {
+            return None;
+        }
+        road.sidewalk_closures
+            .get(&self.get_nearest_side_of_road(map).side)
+            .cloned()
+    }
+
     /// Returns the set of allowed turn types, based on individual turn lane restrictions. `None`
     /// means all turn types are allowed.
     ///
@@ -307,8 +325,19 @@ impl Lane {
     }
 
     pub fn get_thick_polygon(&self) -> Polygon {
+        if let Some(extent) = self.extent {
+            return extent.make_polygon(&self.lane_center_pts, self.width);
+        }
         self.lane_center_pts.make_polygons(self.width)
     }
+
+    /// How much of the lane has its full width. Vehicles only queue along this part.
+    pub fn usable_length(&self) -> Distance {
+        if let Some(extent) = self.extent {
+            return extent.length();
+        }
+        self.length()
+    }
 }
 
 #[derive(PartialEq)]
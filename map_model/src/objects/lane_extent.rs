@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, PolyLine, Polygon, Ring, EPSILON_DIST};
+
+/// A partial lane narrows to nothing over this distance before it starts and after it ends.
+pub const TAPER_LENGTH: Distance = Distance::const_meters(15.0);
+/// Don't let a partial lane shrink shorter than this, not counting the tapers.
+pub const MIN_EXTENT_LENGTH: Distance = Distance::const_meters(10.0);
+
+/// Most lanes run the full length of their road, but some start or end partway along it, like turn
+/// pockets opening up before an intersection, or bus lanes that stop short of one so other vehicles
+/// can merge in to turn. The lane keeps its full-length geometry for positions and pathfinding;
+/// the extent just controls where it has its full width and how many vehicles can queue in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaneExtent {
+    /// Where the lane reaches its full width
+    pub start: Distance,
+    /// Where the lane starts narrowing. Always after `start`.
+    pub end: Distance,
+}
+
+impl LaneExtent {
+    /// Keeps the extent within `length` and at least `MIN_EXTENT_LENGTH` long, if possible.
+    pub fn clamp(self, length: Distance) -> LaneExtent {
+        let min_len = MIN_EXTENT_LENGTH.min(length);
+        let end = self.end.max(min_len).min(length);
+        let start = self.start.max(Distance::ZERO).min(end - min_len);
+        LaneExtent { start, end }
+    }
+
+    /// Does this cover an entire lane of this length?
+    pub fn is_full_length(&self, length: Distance) -> bool {
+        self.start <= EPSILON_DIST && self.end >= length - EPSILON_DIST
+    }
+
+    /// Flips an extent measured along a road into the opposite direction.
+    pub fn reversed(self, length: Distance) -> LaneExtent {
+        LaneExtent {
+            start: length - self.end,
+            end: length - self.start,
+        }
+    }
+
+    /// Rescales an extent measured along one line to a parallel line of a slightly different
+    /// length, like from a road's center to one of its lanes.
+    pub fn rescaled(self, from_length: Distance, to_length: Distance) -> LaneExtent {
+        if from_length == Distance::ZERO {
+            return self;
+        }
+        let ratio = to_length / from_length;
+        LaneExtent {
+            start: self.start * ratio,
+            end: self.end * ratio,
+        }
+        .clamp(to_length)
+    }
+
+    pub fn length(&self) -> Distance {
+        self.end - self.start
+    }
+
+    /// How wide the lane is at some distance along it, as a fraction of its full width.
+    pub fn width_fraction(&self, dist: Distance) -> f64 {
+        let outside = if dist < self.start {
+            self.start - dist
+        } else if dist > self.end {
+            dist - self.end
+        } else {
+            Distance::ZERO
+        };
+        (1.0 - outside / TAPER_LENGTH).max(0.0)
+    }
+
+    /// The shape of a lane following `pl` with this extent, tapering at either end unless it
+    /// reaches the end of `pl` first.
+    pub fn make_polygon(&self, pl: &PolyLine, width: Distance) -> Polygon {
+        let len = pl.length();
+        let taper_start = (self.start - TAPER_LENGTH).max(Distance::ZERO);
+        let taper_end = (self.end + TAPER_LENGTH).min(len);
+
+        // Find the edges at every vertex of the lane, plus where the tapers begin and end
+        let mut dists = vec![taper_start, self.start, self.end, taper_end];
+        let mut so_far = Distance::ZERO;
+        for line in pl.lines() {
+            so_far += line.length();
+            dists.push(so_far);
+        }
+        dists.retain(|dist| *dist >= taper_start && *dist <= taper_end);
+        dists.sort();
+        dists.dedup();
+
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for dist in dists {
+            let half_width = width * self.width_fraction(dist) / 2.0;
+            let (pt, angle) = pl.must_dist_along(dist.min(len));
+            left.push(pt.project_away(half_width, angle.rotate_degs(-90.0)));
+            right.push(pt.project_away(half_width, angle.rotate_degs(90.0)));
+        }
+        right.reverse();
+        left.extend(right);
+        left.push(left[0]);
+        Ring::deduping_new(left)
+            .map(|ring| ring.into_polygon())
+            .unwrap_or_else(|_| pl.make_polygons(width))
+    }
+}
@@ -1,13 +1,22 @@
 pub mod area;
 pub mod building;
+pub mod corner_design;
+pub mod enforcement;
+pub mod gtfs_export;
+pub mod hgv;
 pub mod intersection;
+pub mod kerb;
 pub mod lane;
+pub mod lane_extent;
 pub mod modal_filter;
 pub mod movement;
 pub mod parking_lot;
+pub mod pricing;
 pub mod road;
 pub mod stop_signs;
+pub mod traffic_calming;
 pub mod traffic_signals;
 pub mod transit;
 pub mod turn;
+pub mod walking_closure;
 pub mod zone;
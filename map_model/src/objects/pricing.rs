@@ -0,0 +1,147 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+
+/// Charging drivers for using a road. Prices may change through the day.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoadPricing {
+    Free,
+    /// Every car driving along the road pays
+    Toll(PriceSchedule),
+    /// Cars may pay to use the road's bus lanes, like a high-occupancy toll (HOT) lane. Everybody
+    /// else uses the other lanes for free, and buses and bikes still use the bus lanes for free.
+    PricedLanes(PriceSchedule),
+}
+
+/// Prices in cents. Each one starts at a time of day and lasts until the next. Before the first,
+/// it's free.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceSchedule {
+    /// Sorted by time
+    pub prices: Vec<(Time, u32)>,
+}
+
+impl RoadPricing {
+    pub fn schedule(&self) -> Option<&PriceSchedule> {
+        match self {
+            RoadPricing::Free => None,
+            RoadPricing::Toll(schedule) | RoadPricing::PricedLanes(schedule) => Some(schedule),
+        }
+    }
+
+    /// Does every car along the road pay right now?
+    pub fn toll_at(&self, time: Time) -> u32 {
+        match self {
+            RoadPricing::Toll(schedule) => schedule.price_at(time),
+            RoadPricing::Free | RoadPricing::PricedLanes(_) => 0,
+        }
+    }
+
+    /// What would a car pay to use the bus lanes right now? None means they can't.
+    pub fn priced_lane_at(&self, time: Time) -> Option<u32> {
+        match self {
+            RoadPricing::PricedLanes(schedule) => Some(schedule.price_at(time)),
+            RoadPricing::Free | RoadPricing::Toll(_) => None,
+        }
+    }
+}
+
+impl PriceSchedule {
+    /// The same price all day
+    pub fn flat(cents: u32) -> PriceSchedule {
+        PriceSchedule {
+            prices: vec![(Time::START_OF_DAY, cents)],
+        }
+    }
+
+    /// A higher price during the morning and evening rush hours
+    pub fn peak(off_peak_cents: u32, peak_cents: u32) -> PriceSchedule {
+        let hour = |h: usize| Time::START_OF_DAY + Duration::hours(h);
+        PriceSchedule {
+            prices: vec![
+                (hour(0), off_peak_cents),
+                (hour(7), peak_cents),
+                (hour(10), off_peak_cents),
+                (hour(16), peak_cents),
+                (hour(19), off_peak_cents),
+            ],
+        }
+    }
+
+    pub fn price_at(&self, time: Time) -> u32 {
+        self.prices
+            .iter()
+            .take_while(|(start, _)| *start <= time)
+            .last()
+            .map(|(_, cents)| *cents)
+            .unwrap_or(0)
+    }
+
+    pub fn max_price(&self) -> u32 {
+        self.prices
+            .iter()
+            .map(|(_, cents)| *cents)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn min_price(&self) -> u32 {
+        if self.prices.is_empty() || self.prices[0].0 > Time::START_OF_DAY {
+            return 0;
+        }
+        self.prices.iter().map(|(_, cents)| *cents).min().unwrap()
+    }
+}
+
+impl fmt::Display for RoadPricing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoadPricing::Free => write!(f, "no pricing"),
+            RoadPricing::Toll(schedule) => write!(f, "toll ({})", schedule),
+            RoadPricing::PricedLanes(schedule) => write!(f, "priced bus lanes ({})", schedule),
+        }
+    }
+}
+
+impl fmt::Display for PriceSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (min, max) = (self.min_price(), self.max_price());
+        if min == max {
+            write!(f, "{}", format_cents(min))
+        } else {
+            write!(f, "{} to {}", format_cents(min), format_cents(max))
+        }
+    }
+}
+
+/// Prices are stored in cents, but nobody wants to read them that way
+pub fn format_cents(cents: u32) -> String {
+    format!("${}.{:02}", cents / 100, cents % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_at() {
+        let hour = |h: f64| Time::START_OF_DAY + Duration::minutes((h * 60.0) as usize);
+        let schedule = PriceSchedule::peak(100, 400);
+        assert_eq!(schedule.price_at(hour(3.0)), 100);
+        assert_eq!(schedule.price_at(hour(7.0)), 400);
+        assert_eq!(schedule.price_at(hour(9.5)), 400);
+        assert_eq!(schedule.price_at(hour(10.0)), 100);
+        assert_eq!(schedule.price_at(hour(18.0)), 400);
+        assert_eq!(schedule.price_at(hour(23.0)), 100);
+
+        // Free until the first price starts
+        let schedule = PriceSchedule {
+            prices: vec![(hour(8.0), 250)],
+        };
+        assert_eq!(schedule.price_at(hour(7.0)), 0);
+        assert_eq!(schedule.price_at(hour(12.0)), 250);
+        assert_eq!(schedule.min_price(), 0);
+    }
+}
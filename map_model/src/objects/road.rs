@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 use anyhow::Result;
@@ -9,11 +9,17 @@ use abstutil::{deserialize_usize, serialize_usize, Tags};
 use geom::{Distance, PolyLine, Polygon, Speed};
 
 use crate::{
-    osm, AccessRestrictions, CommonEndpoint, CrossingType, Direction, DrivingSide, IntersectionID,
-    Lane, LaneID, LaneSpec, LaneType, Map, PathConstraints, RestrictionType, RoadFilter,
-    TransitStopID, Zone,
+    osm, AccessRestrictions, BuildingID, BusLaneEnforcement, CommonEndpoint, CrossingType,
+    Direction, DrivingSide, HgvRestrictions, IntersectionID, KerbSegment, KerbType, Lane,
+    LaneExtent, LaneID, LaneSpec, LaneType, Map, PathConstraints, RestrictionType, RoadFilter,
+    RoadPricing, SpeedEnforcement, StreetParking, TrafficCalming, TransitStopID, WalkingClosure,
+    Zone,
 };
 
+/// Driveways per kilometer, counting both sides of a road. Above this, there's a driveway every
+/// ~30m on each side.
+const HIGH_DRIVEWAY_DENSITY: f64 = 60.0;
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct RoadID(
     #[serde(
@@ -158,6 +164,15 @@ impl RoadSideID {
     }
 }
 
+/// Is a road raised above or sunk below the ground? This is separate from `Road::zorder`, which
+/// also captures roads passing underneath bridges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoadStructure {
+    AtGrade,
+    Bridge,
+    Tunnel,
+}
+
 /// A Road represents a segment between exactly two Intersections. It contains Lanes as children.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Road {
@@ -205,8 +220,34 @@ pub struct Road {
     /// Some kind of crossing this distance along center_pts.
     // TODO Just use Crossing directly?
     pub crossing_nodes: Vec<(Distance, CrossingType)>,
+    /// Tagged kerbs this distance along center_pts, usually where a crossing meets the sidewalk.
+    pub kerb_nodes: Vec<(Distance, KerbType)>,
     /// Sorted by increasing distance
     pub crossings: Vec<Crossing>,
+    /// How the kerb is used along each side of the road. Segments don't overlap.
+    pub kerb_uses: Vec<KerbSegment>,
+    /// Only matters if the road has bus lanes
+    pub bus_lane_enforcement: BusLaneEnforcement,
+    pub speed_enforcement: SpeedEnforcement,
+    pub hgv: HgvRestrictions,
+    /// Tolls or priced lanes
+    pub pricing: RoadPricing,
+    /// Cars carrying at least this many people may use the bus lanes, making them high-occupancy
+    /// vehicle (HOV) lanes.
+    pub hov_min_occupancy: Option<usize>,
+    /// Sorted by increasing distance
+    pub traffic_calming: Vec<TrafficCalming>,
+    /// Lanes that only cover part of the road, keyed by their index in `lanes`. Measured along
+    /// center_pts.
+    pub lane_extents: BTreeMap<usize, LaneExtent>,
+    /// Sidewalks and shoulders along these sides of the road are temporarily unusable on foot
+    pub sidewalk_closures: BTreeMap<SideOfRoad, WalkingClosure>,
+    /// OSM route and boundary relations that any of the ways making up this road belong to
+    pub osm_relations: BTreeSet<osm::RelationID>,
+    /// What's known about on-street parking along each side of the road, from OSM tags or a
+    /// parking survey
+    pub parking_left: StreetParking,
+    pub parking_right: StreetParking,
 }
 
 impl Road {
@@ -239,22 +280,34 @@ impl Road {
         panic!("{} doesn't contain {}", self.id, lane);
     }
 
+    /// What's known about on-street parking along one side of the road. Use
+    /// `Lane::get_nearest_side_of_road` to find the side for a parking lane.
+    pub fn observed_parking(&self, side: SideOfRoad) -> StreetParking {
+        match side {
+            SideOfRoad::Left => self.parking_left,
+            SideOfRoad::Right => self.parking_right,
+        }
+    }
+
     pub fn parking_to_driving(&self, parking: LaneID) -> Option<LaneID> {
         self.find_closest_lane(parking, |l| l.is_driving())
     }
 
-    pub(crate) fn speed_limit_from_osm(&self) -> Speed {
-        if let Some(limit) = self.osm_tags.get("maxspeed") {
-            if let Some(speed) = if let Ok(kmph) = limit.parse::<f64>() {
-                Some(Speed::km_per_hour(kmph))
-            } else if let Some(mph) = limit
+    /// Parses an OSM `maxspeed` value, in km/h by default or with a " mph" suffix.
+    pub fn parse_speed_limit(limit: &str) -> Option<Speed> {
+        if let Ok(kmph) = limit.parse::<f64>() {
+            Some(Speed::km_per_hour(kmph))
+        } else {
+            limit
                 .strip_suffix(" mph")
                 .and_then(|x| x.parse::<f64>().ok())
-            {
-                Some(Speed::miles_per_hour(mph))
-            } else {
-                None
-            } {
+                .map(Speed::miles_per_hour)
+        }
+    }
+
+    pub(crate) fn speed_limit_from_osm(&self) -> Speed {
+        if let Some(limit) = self.osm_tags.get("maxspeed") {
+            if let Some(speed) = Road::parse_speed_limit(limit) {
                 if speed == Speed::ZERO {
                     warn!("{} has a speed limit of 0", self.orig_id.osm_way_id);
                     return Speed::miles_per_hour(1.0);
@@ -329,6 +382,25 @@ impl Road {
         }
     }
 
+    /// The speed limit, lowered to account for traffic calming slowing motor vehicles along part
+    /// of the road
+    pub fn effective_speed_limit(&self) -> Speed {
+        crate::objects::traffic_calming::effective_speed_limit(self)
+    }
+
+    /// The average speed of somebody habitually driving `factor` times the speed limit. Traffic
+    /// calming still slows them down.
+    pub fn speeding_speed(&self, factor: f64) -> Speed {
+        crate::objects::traffic_calming::average_speed(self, self.speed_limit * factor)
+    }
+
+    /// May a car carrying this many people use the bus lanes along this road?
+    pub fn allows_hov(&self, occupancy: usize) -> bool {
+        self.hov_min_occupancy
+            .map(|min| occupancy >= min)
+            .unwrap_or(false)
+    }
+
     pub fn get_half_width(&self) -> Distance {
         self.get_width() / 2.0
     }
@@ -450,10 +522,97 @@ impl Road {
             )
     }
 
+    /// A street given over to walking and cycling. Vehicles may only drive along it to make
+    /// deliveries, if at all.
+    pub fn is_pedestrian_street(&self) -> bool {
+        !self
+            .access_restrictions
+            .allow_through_traffic
+            .contains(PathConstraints::Car)
+            && self.lanes.iter().any(|l| l.lane_type == LaneType::Footway)
+            && self.lanes.iter().all(|l| {
+                matches!(
+                    l.lane_type,
+                    LaneType::Footway | LaneType::Biking | LaneType::Driving
+                )
+            })
+    }
+
     pub fn is_service(&self) -> bool {
         self.osm_tags.is(osm::HIGHWAY, "service")
     }
 
+    pub fn is_steps(&self) -> bool {
+        self.osm_tags.is(osm::HIGHWAY, "steps")
+    }
+
+    /// Does this road have street lights, according to OSM's `lit` tag? None if it's not tagged.
+    pub fn is_lit(&self) -> Option<bool> {
+        match self.osm_tags.get("lit").map(|x| x.as_str()) {
+            None => None,
+            Some("no" | "disused") => Some(false),
+            // "yes", "24/7", "automatic", "sunset-sunrise", "limited", etc
+            Some(_) => Some(true),
+        }
+    }
+
+    /// What's known about the kerb where a crossing over this road meets the sidewalk near one
+    /// intersection. If any tagged kerb there is raised, the crossing isn't usable by wheelchairs.
+    pub fn kerb_near(&self, i: IntersectionID) -> Option<KerbType> {
+        let range = Distance::meters(15.0);
+        let mut result = None;
+        for (dist, kerb) in &self.kerb_nodes {
+            let near = if i == self.src_i {
+                *dist <= range
+            } else {
+                *dist >= self.length() - range
+            };
+            if near {
+                if *kerb == KerbType::Raised {
+                    return Some(KerbType::Raised);
+                }
+                result = Some(*kerb);
+            }
+        }
+        result
+    }
+
+    /// Is the walking surface along this road, from OSM's `surface` tag for footways or
+    /// `sidewalk:surface` otherwise, hard to use in a wheelchair?
+    pub fn has_rough_walking_surface(&self) -> bool {
+        let keys = if self.is_footway() {
+            vec!["surface"]
+        } else {
+            vec![
+                "sidewalk:surface",
+                "sidewalk:both:surface",
+                "sidewalk:left:surface",
+                "sidewalk:right:surface",
+            ]
+        };
+        keys.into_iter().any(|key| {
+            matches!(
+                self.osm_tags.get(key).map(|x| x.as_str()),
+                Some(
+                    "cobblestone"
+                        | "sett"
+                        | "unhewn_cobblestone"
+                        | "gravel"
+                        | "fine_gravel"
+                        | "pebblestone"
+                        | "unpaved"
+                        | "dirt"
+                        | "earth"
+                        | "ground"
+                        | "grass"
+                        | "mud"
+                        | "sand"
+                        | "woodchips"
+                )
+            )
+        })
+    }
+
     pub fn is_cycleway(&self) -> bool {
         let mut bike = false;
         for lane in &self.lanes {
@@ -491,8 +650,20 @@ impl Road {
         }
     }
 
+    pub fn structure(&self) -> RoadStructure {
+        let tagged = |key: &str| self.osm_tags.contains_key(key) && !self.osm_tags.is(key, "no");
+        if tagged("bridge") {
+            RoadStructure::Bridge
+        } else if tagged("tunnel") && !self.osm_tags.is("tunnel", "building_passage") {
+            // Passages through buildings are still at ground level
+            RoadStructure::Tunnel
+        } else {
+            RoadStructure::AtGrade
+        }
+    }
+
     pub fn is_private(&self) -> bool {
-        self.access_restrictions != AccessRestrictions::new() && !self.is_light_rail()
+        self.access_restrictions.allow_through_traffic != EnumSet::all() && !self.is_light_rail()
     }
 
     pub(crate) fn access_restrictions_from_osm(&self) -> AccessRestrictions {
@@ -507,8 +678,37 @@ impl Road {
         } else {
             EnumSet::all()
         };
+
+        let mut destination_only = EnumSet::new();
+        if self.osm_tags.is("access", "destination") || self.osm_tags.is("vehicle", "destination") {
+            destination_only |= PathConstraints::Car | PathConstraints::Bike;
+        }
+        if self.osm_tags.is("motor_vehicle", "destination")
+            || self.osm_tags.is("motorcar", "destination")
+        {
+            destination_only |= PathConstraints::Car;
+        }
+        if self.osm_tags.is("bicycle", "destination") {
+            destination_only |= PathConstraints::Bike;
+        }
+        // Explicit permission overrides the general restriction
+        if self
+            .osm_tags
+            .is_any("motor_vehicle", vec!["yes", "permissive"])
+        {
+            destination_only.remove(PathConstraints::Car);
+        }
+        if self
+            .osm_tags
+            .is_any("bicycle", vec!["yes", "permissive", "designated"])
+        {
+            destination_only.remove(PathConstraints::Bike);
+        }
+
         AccessRestrictions {
             allow_through_traffic,
+            destination_only,
+            delivery_windows: Vec::new(),
         }
     }
 
@@ -561,7 +761,7 @@ impl Road {
         let total_width = lane_specs_ltr.iter().map(|x| x.width).sum();
 
         let mut width_so_far = Distance::ZERO;
-        for lane in lane_specs_ltr {
+        for (idx, lane) in lane_specs_ltr.into_iter().enumerate() {
             let id = LaneID {
                 road: self.id,
                 offset: self.lanes.len(),
@@ -585,6 +785,7 @@ impl Road {
             } else {
                 pl.reversed()
             };
+            let extent = self.extent_along_lane(idx, lane.dir, &lane_center_pts);
 
             self.lanes.push(Lane {
                 id,
@@ -596,10 +797,42 @@ impl Road {
                 dir: lane.dir,
                 driving_blackhole: false,
                 biking_blackhole: false,
+                extent,
             });
         }
     }
 
+    /// After changing `lane_extents` without recreating the lanes, update them.
+    pub(crate) fn refresh_lane_extents(&mut self) {
+        let extents: Vec<Option<LaneExtent>> = self
+            .lanes
+            .iter()
+            .enumerate()
+            .map(|(idx, lane)| self.extent_along_lane(idx, lane.dir, &lane.lane_center_pts))
+            .collect();
+        for (lane, extent) in self.lanes.iter_mut().zip(extents) {
+            lane.extent = extent;
+        }
+    }
+
+    /// `lane_extents` are measured along the road's center, but lanes need them measured along
+    /// their own center, in their own direction.
+    fn extent_along_lane(
+        &self,
+        idx: usize,
+        dir: Direction,
+        lane_center_pts: &PolyLine,
+    ) -> Option<LaneExtent> {
+        let extent = self.lane_extents.get(&idx)?;
+        let road_len = self.center_pts.length();
+        let extent = if dir == Direction::Fwd {
+            *extent
+        } else {
+            extent.reversed(road_len)
+        };
+        Some(extent.rescaled(road_len, lane_center_pts.length()))
+    }
+
     /// Returns all lanes located between l1 and l2, exclusive.
     pub fn get_lanes_between(&self, l1: LaneID, l2: LaneID) -> Vec<LaneID> {
         let mut results = Vec::new();
@@ -618,9 +851,10 @@ impl Road {
     }
 
     /// A simple classification of if the directed road is stressful or not for cycling. Arterial
-    /// roads without a bike lane match this. Why arterial, instead of looking at speed limits?
-    /// Even on arterial roads with official speed limits lowered, in practice vehicles still
-    /// travel at the speed suggested by the design of the road.
+    /// roads without a bike lane match this, as do painted bike lanes crossed by many driveways.
+    /// Why arterial, instead of looking at speed limits? Even on arterial roads with official
+    /// speed limits lowered, in practice vehicles still travel at the speed suggested by the
+    /// design of the road.
     // TODO Should elevation matter or not? Flat high-speed roads are still terrifying, but there's
     // something about slogging up (or flying down!) a pothole-filled road inches from cars.
     pub fn high_stress_for_bikes(&self, map: &Map, dir: Direction) -> bool {
@@ -635,10 +869,85 @@ impl Road {
                 can_use = true;
             }
         }
-        if !can_use || bike_lanes {
+        if !can_use || self.get_rank() == osm::RoadRank::Local {
             return false;
         }
-        self.get_rank() != osm::RoadRank::Local
+        if bike_lanes {
+            // Vehicles turning into driveways constantly cut across a painted bike lane
+            let buffer = self
+                .lanes
+                .iter()
+                .any(|l| matches!(l.lane_type, LaneType::Buffer(_)));
+            return !buffer && self.has_high_driveway_density(map);
+        }
+        true
+    }
+
+    /// Buildings whose driveway (curb cut) crosses this road's sidewalks, with the distance along
+    /// the sidewalk. Sorted by distance, mixing both sides of the road.
+    pub fn driveways(&self, map: &Map) -> Vec<(BuildingID, Distance)> {
+        let mut driveways: Vec<(BuildingID, Distance)> = map
+            .road_to_buildings(self.id)
+            .iter()
+            .map(|b| map.get_b(*b))
+            .filter(|b| b.has_driveway())
+            .map(|b| (b.id, b.sidewalk_pos.dist_along()))
+            .collect();
+        driveways.sort_by_key(|(_, dist)| *dist);
+        driveways
+    }
+
+    /// Driveways per kilometer along this road, counting both sides
+    pub fn driveway_density(&self, map: &Map) -> f64 {
+        let km = self.length().inner_meters() / 1000.0;
+        if km == 0.0 {
+            return 0.0;
+        }
+        (self.driveways(map).len() as f64) / km
+    }
+
+    /// Are there so many driveways that people walking or cycling along here constantly have to
+    /// watch for vehicles turning in and out?
+    pub fn has_high_driveway_density(&self, map: &Map) -> bool {
+        self.driveway_density(map) >= HIGH_DRIVEWAY_DENSITY
+    }
+
+    /// A rough "level of traffic stress" for cycling along this road, from 1 (comfortable for
+    /// children) to 4 (only tolerated by the strong and fearless). This loosely follows Mekuria,
+    /// Furth, and Nixon's classification, using only rank, speed limit, and bike lanes.
+    pub fn level_of_traffic_stress(&self, map: &Map) -> usize {
+        if !PathConstraints::Car.can_use_road(self, map) {
+            return 1;
+        }
+        let bike_lane = self.lanes.iter().any(|l| l.lane_type == LaneType::Biking);
+        let buffer = self
+            .lanes
+            .iter()
+            .any(|l| matches!(l.lane_type, LaneType::Buffer(_)));
+
+        if self.get_rank() == osm::RoadRank::Local {
+            // A modal filter removes through-traffic
+            if self.modal_filter.is_some() || self.speed_limit <= Speed::miles_per_hour(20.0) {
+                1
+            } else {
+                2
+            }
+        } else if bike_lane && buffer {
+            2
+        } else if bike_lane {
+            // Vehicles turning into driveways cut across painted bike lanes
+            if self.speed_limit <= Speed::miles_per_hour(30.0)
+                && !self.has_high_driveway_density(map)
+            {
+                2
+            } else {
+                3
+            }
+        } else if self.speed_limit <= Speed::miles_per_hour(25.0) {
+            3
+        } else {
+            4
+        }
     }
 
     pub fn oneway_for_driving(&self) -> Option<Direction> {
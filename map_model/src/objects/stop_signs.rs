@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use serde::{Deserialize, Serialize};
 
@@ -47,7 +47,29 @@ pub struct ControlStopSign {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RoadWithStopSign {
     pub lane_closest_to_edge: LaneID,
-    pub must_stop: bool,
+    pub control: ApproachControl,
+}
+
+/// What vehicles approaching an intersection from one road have to do
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApproachControl {
+    /// No sign; this road has priority over the others
+    Priority,
+    /// Slow down and only proceed when there's a gap in traffic from priority roads
+    GiveWay,
+    /// Come to a complete stop, then proceed like a give-way
+    Stop,
+}
+
+impl ApproachControl {
+    /// Priority -> give way -> stop -> priority
+    pub fn next(self) -> ApproachControl {
+        match self {
+            ApproachControl::Priority => ApproachControl::GiveWay,
+            ApproachControl::GiveWay => ApproachControl::Stop,
+            ApproachControl::Stop => ApproachControl::Priority,
+        }
+    }
 }
 
 impl ControlStopSign {
@@ -87,7 +109,7 @@ impl ControlStopSign {
                     r.id,
                     RoadWithStopSign {
                         lane_closest_to_edge,
-                        must_stop: false,
+                        control: ApproachControl::Priority,
                     },
                 );
             }
@@ -109,13 +131,49 @@ impl ControlStopSign {
             return ss;
         }
 
+        // If all roads have the same rank, all-way stop. Otherwise, everything stops except the
+        // highest-priority roads.
+        let major = ss.major_roads(map);
+        ss.apply_to_minor_roads(map, &major, ApproachControl::Stop);
+        ss
+    }
+
+    /// Every road stops.
+    pub fn set_all_way_stop(&mut self, map: &Map) {
+        self.apply_to_minor_roads(map, &BTreeSet::new(), ApproachControl::Stop);
+    }
+
+    /// The highest-ranked roads have priority, and every other road has to stop or give way. If
+    /// all roads have the same rank, then this is an all-way stop or give-way.
+    pub fn set_minor_roads(&mut self, map: &Map, control: ApproachControl) {
+        let major = self.major_roads(map);
+        self.apply_to_minor_roads(map, &major, control);
+    }
+
+    fn apply_to_minor_roads(
+        &mut self,
+        map: &Map,
+        major: &BTreeSet<RoadID>,
+        control: ApproachControl,
+    ) {
+        for (r, cfg) in self.roads.iter_mut() {
+            cfg.control = ApproachControl::Priority;
+            // Don't stop in the middle of something that's likely actually an intersection.
+            if !major.contains(r) && !map.get_r(*r).is_extremely_short() {
+                cfg.control = control;
+            }
+        }
+    }
+
+    /// Returns the highest-ranked roads, or nothing if every road has the same rank.
+    fn major_roads(&self, map: &Map) -> BTreeSet<RoadID> {
         // Rank each road based on OSM highway type, and additionally:
         // - Treat cycleways as lower priority than local roads (sad but typical reality)
         // - Prioritize roundabouts, so they clear out faster than people enter them
         // - Treat on/off ramps with less priority than the main part of the highway
         // - Lower the priority of service roads
         let mut rank: HashMap<RoadID, (osm::RoadRank, usize)> = HashMap::new();
-        for r in ss.roads.keys() {
+        for r in self.roads.keys() {
             let r = map.get_r(*r);
             // Lower number is lower priority
             let priority = if r.is_cycleway() || r.osm_tags.is(osm::HIGHWAY, "service") {
@@ -140,38 +198,46 @@ impl ControlStopSign {
         // Highest rank is first
         ranks.reverse();
 
-        // If all roads have the same rank, all-way stop. Otherwise, everything stops except the
-        // highest-priority roads.
-        for (r, cfg) in ss.roads.iter_mut() {
-            if ranks.len() == 1 || rank[r] != ranks[0] {
-                // Don't stop in the middle of something that's likely actually an intersection.
-                if !map.get_r(*r).is_extremely_short() {
-                    cfg.must_stop = true;
-                }
-            }
+        if ranks.len() == 1 {
+            return BTreeSet::new();
         }
-        ss
+        self.roads
+            .keys()
+            .filter(|r| rank[r] == ranks[0])
+            .cloned()
+            .collect()
     }
 
     /// Get the priority of a turn according to the stop sign -- either protected or yield, never
-    /// banned.
+    /// banned. Both stop and give-way signs yield.
     pub fn get_priority(&self, turn: TurnID, map: &Map) -> TurnPriority {
         match map.get_t(turn).turn_type {
             TurnType::SharedSidewalkCorner => TurnPriority::Protected,
             TurnType::Crosswalk => TurnPriority::Protected,
             TurnType::UnmarkedCrossing => TurnPriority::Yield,
             _ => {
-                if self.roads[&turn.src.road].must_stop {
-                    TurnPriority::Yield
-                } else {
+                if self.roads[&turn.src.road].control == ApproachControl::Priority {
                     TurnPriority::Protected
+                } else {
+                    TurnPriority::Yield
                 }
             }
         }
     }
 
-    pub fn flip_sign(&mut self, r: RoadID) {
+    /// Does an agent making this turn have to come to a complete stop first? Pedestrians pause
+    /// before unmarked crossings.
+    pub fn must_stop(&self, turn: TurnID, map: &Map) -> bool {
+        match map.get_t(turn).turn_type {
+            TurnType::SharedSidewalkCorner | TurnType::Crosswalk => false,
+            TurnType::UnmarkedCrossing => true,
+            _ => self.roads[&turn.src.road].control == ApproachControl::Stop,
+        }
+    }
+
+    /// Cycle the sign on one road through priority, give way, and stop.
+    pub fn cycle_sign(&mut self, r: RoadID) {
         let ss = self.roads.get_mut(&r).unwrap();
-        ss.must_stop = !ss.must_stop;
+        ss.control = ss.control.next();
     }
 }
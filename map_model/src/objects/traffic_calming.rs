@@ -0,0 +1,151 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Duration, Speed};
+
+use crate::Road;
+
+/// Physical features that slow down motor vehicles without stopping them. A lighter-weight
+/// alternative to modal filters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TrafficCalmingType {
+    SpeedHump,
+    /// A raised section of road, usually at a crossing
+    RaisedTable,
+    /// Alternating build-outs forcing vehicles to weave. Covers a stretch of road, not a point.
+    Chicane,
+}
+
+impl TrafficCalmingType {
+    pub fn all() -> Vec<TrafficCalmingType> {
+        vec![
+            TrafficCalmingType::SpeedHump,
+            TrafficCalmingType::RaisedTable,
+            TrafficCalmingType::Chicane,
+        ]
+    }
+
+    /// How fast motor vehicles will go over this
+    pub fn slow_speed(self) -> Speed {
+        match self {
+            TrafficCalmingType::SpeedHump => Speed::miles_per_hour(15.0),
+            TrafficCalmingType::RaisedTable => Speed::miles_per_hour(20.0),
+            TrafficCalmingType::Chicane => Speed::miles_per_hour(20.0),
+        }
+    }
+
+    /// Vehicles slow down this far before and after the feature
+    pub fn approach_dist(self) -> Distance {
+        match self {
+            TrafficCalmingType::SpeedHump | TrafficCalmingType::RaisedTable => {
+                Distance::meters(10.0)
+            }
+            TrafficCalmingType::Chicane => Distance::meters(5.0),
+        }
+    }
+
+    /// How much road a newly placed feature covers
+    fn default_length(self) -> Distance {
+        match self {
+            TrafficCalmingType::SpeedHump | TrafficCalmingType::RaisedTable => Distance::ZERO,
+            TrafficCalmingType::Chicane => Distance::meters(30.0),
+        }
+    }
+}
+
+impl fmt::Display for TrafficCalmingType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TrafficCalmingType::SpeedHump => "speed hump",
+                TrafficCalmingType::RaisedTable => "raised table",
+                TrafficCalmingType::Chicane => "chicane",
+            }
+        )
+    }
+}
+
+/// A traffic calming feature along a road, affecting both directions
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TrafficCalming {
+    /// Distances along the road's center line, with start <= end. Speed humps and raised tables
+    /// are points, so start == end.
+    pub start: Distance,
+    pub end: Distance,
+    pub calming_type: TrafficCalmingType,
+}
+
+impl TrafficCalming {
+    /// Place a feature centered this far along the road, clamped to fit.
+    pub fn new(calming_type: TrafficCalmingType, dist: Distance, road: &Road) -> Self {
+        let half = calming_type.default_length() / 2.0;
+        let start = (dist - half).max(Distance::ZERO);
+        let end = (dist + half).min(road.length());
+        Self {
+            start: start.min(end),
+            end,
+            calming_type,
+        }
+    }
+
+    pub fn middle(&self) -> Distance {
+        (self.start + self.end) / 2.0
+    }
+
+    /// The stretch of road where vehicles are slowed, clamped to the road
+    fn zone(&self, road: &Road) -> (Distance, Distance) {
+        let approach = self.calming_type.approach_dist();
+        (
+            (self.start - approach).max(Distance::ZERO),
+            (self.end + approach).min(road.length()),
+        )
+    }
+}
+
+/// The average speed motor vehicles can manage along the whole road, slowing down near traffic
+/// calming. Where features overlap, the slowest applies.
+pub(crate) fn effective_speed_limit(road: &Road) -> Speed {
+    average_speed(road, road.speed_limit)
+}
+
+/// The average speed along the whole road of somebody cruising at `cruising` between traffic
+/// calming features
+pub(crate) fn average_speed(road: &Road, cruising: Speed) -> Speed {
+    if road.traffic_calming.is_empty() {
+        return cruising;
+    }
+
+    let zones: Vec<(Distance, Distance, Speed)> = road
+        .traffic_calming
+        .iter()
+        .map(|tc| {
+            let (start, end) = tc.zone(road);
+            (start, end, tc.calming_type.slow_speed().min(cruising))
+        })
+        .collect();
+    let mut breakpoints = vec![Distance::ZERO, road.length()];
+    for (start, end, _) in &zones {
+        breakpoints.push(*start);
+        breakpoints.push(*end);
+    }
+    breakpoints.sort();
+    breakpoints.dedup();
+
+    let mut total_time = Duration::ZERO;
+    for pair in breakpoints.windows(2) {
+        let mid = (pair[0] + pair[1]) / 2.0;
+        let speed = zones
+            .iter()
+            .filter(|(start, end, _)| *start <= mid && mid <= *end)
+            .map(|(_, _, speed)| *speed)
+            .fold(cruising, |a, b| a.min(b));
+        total_time += (pair[1] - pair[0]) / speed;
+    }
+    if total_time == Duration::ZERO {
+        return cruising;
+    }
+    Speed::meters_per_second(road.length().inner_meters() / total_time.inner_seconds())
+}
@@ -23,6 +23,69 @@ pub struct ControlTrafficSignal {
     pub id: IntersectionID,
     pub stages: Vec<Stage>,
     pub offset: Duration,
+    /// If set, late buses can extend or cut short stages. This isn't preserved when exporting to
+    /// the traffic signal data format; map edits store it separately.
+    pub transit_priority: Option<TransitPriority>,
+    /// If set, crosswalks get a head start at the beginning of every stage: vehicle movements
+    /// conflicting with them wait this long before going. Like transit priority, map edits store
+    /// this separately.
+    pub leading_pedestrian_interval: Option<Duration>,
+    /// If set, detectors on the approaches decide how long each stage lasts, instead of the
+    /// stage's own timing. Like transit priority, map edits store this separately.
+    pub actuation: Option<Actuation>,
+    /// Big compound junctions are often several intersections run by one controller. If so, this
+    /// lists every member, including this one; otherwise it's empty. Members must have the same
+    /// number of stages, with the same timing, and the simulation changes their stages together.
+    /// Like transit priority, map edits store this separately.
+    pub cluster: BTreeSet<IntersectionID>,
+}
+
+/// A typical leading pedestrian interval, long enough for people walking to get established in
+/// the crosswalk before turning vehicles go.
+pub const DEFAULT_LEADING_PEDESTRIAN_INTERVAL: Duration = Duration::const_seconds(5.0);
+
+/// When a bus that's running late approaches, the signal can hold the green for it a little
+/// longer, or cut short the red, within these limits.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TransitPriority {
+    /// Only buses that've been delayed by at least this much over their run get priority.
+    pub min_lateness: Duration,
+    /// The longest a stage serving the bus can be extended
+    pub max_extension: Duration,
+    /// The most a conflicting stage can be cut short. Pedestrians already crossing still get
+    /// enough time to finish.
+    pub max_early_end: Duration,
+}
+
+impl Default for TransitPriority {
+    fn default() -> TransitPriority {
+        TransitPriority {
+            min_lateness: Duration::minutes(1),
+            max_extension: Duration::seconds(10.0),
+            max_early_end: Duration::seconds(10.0),
+        }
+    }
+}
+
+/// Vehicle-actuated control. A green lasts at least `min_green`, then keeps getting extended while
+/// vehicles arrive no more than `gap` apart, up to `max_green`. Stages with nobody detected
+/// waiting for them are skipped. Stages with only crosswalks keep their own duration.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Actuation {
+    pub min_green: Duration,
+    pub max_green: Duration,
+    /// A green ends early ("gaps out") when no vehicle reaches the stop line for this long.
+    pub gap: Duration,
+}
+
+impl Default for Actuation {
+    fn default() -> Actuation {
+        Actuation {
+            min_green: Duration::seconds(10.0),
+            max_green: Duration::seconds(60.0),
+            gap: Duration::seconds(3.0),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -140,6 +203,62 @@ impl ControlTrafficSignal {
         Ok(())
     }
 
+    /// Can these signals run together from one controller? They must be connected to each other
+    /// by roads, agree on who's in the cluster, and share the same offset and stage timing.
+    pub fn validate_cluster(map: &Map, members: &BTreeSet<IntersectionID>) -> Result<()> {
+        if members.len() < 2 {
+            bail!("A signal cluster needs at least 2 intersections");
+        }
+        let mut first: Option<&ControlTrafficSignal> = None;
+        for i in members {
+            let signal = match map.maybe_get_traffic_signal(*i) {
+                Some(signal) => signal,
+                None => bail!("{} in a signal cluster isn't a traffic signal", i),
+            };
+            if &signal.cluster != members {
+                bail!("{} disagrees about who's in its signal cluster", i);
+            }
+            if let Some(first) = first {
+                if signal.offset != first.offset
+                    || signal.stages.len() != first.stages.len()
+                    || signal
+                        .stages
+                        .iter()
+                        .zip(first.stages.iter())
+                        .any(|(s1, s2)| s1.stage_type != s2.stage_type)
+                {
+                    bail!(
+                        "{} and {} in a signal cluster have different stage timing",
+                        first.id,
+                        i
+                    );
+                }
+            } else {
+                first = Some(signal);
+            }
+        }
+
+        // Flood from one member along roads between members
+        let start = *members.iter().next().unwrap();
+        let mut reached = BTreeSet::new();
+        let mut queue = vec![start];
+        while let Some(i) = queue.pop() {
+            if !reached.insert(i) {
+                continue;
+            }
+            for r in &map.get_i(i).roads {
+                let other = map.get_r(*r).other_endpt(i);
+                if members.contains(&other) {
+                    queue.push(other);
+                }
+            }
+        }
+        if reached.len() != members.len() {
+            bail!("The intersections in a signal cluster must be connected by roads");
+        }
+        Ok(())
+    }
+
     /// Move crosswalks from stages, adding them to an all-walk as last stage. This may promote
     /// yields to protected. True is returned if any stages were added or modified.
     pub fn convert_to_ped_scramble(&mut self, i: &Intersection) -> bool {
@@ -201,6 +320,33 @@ impl ControlTrafficSignal {
         self != &orig
     }
 
+    /// Does some stage have every crossing protected at once, with no vehicle movements?
+    pub fn has_ped_scramble(&self, i: &Intersection) -> bool {
+        self.stages.iter().any(|stage| stage.is_ped_scramble(i))
+    }
+
+    /// Could a vehicle making this turn wait for the leading pedestrian interval in this stage?
+    /// True if the turn conflicts with a crossing that's protected in the stage.
+    pub fn held_for_leading_pedestrian_interval(
+        &self,
+        stage: &Stage,
+        turn: TurnID,
+        i: &Intersection,
+    ) -> bool {
+        if self.leading_pedestrian_interval.is_none() {
+            return false;
+        }
+        let turn = i.turns.iter().find(|t| t.id == turn).unwrap();
+        if turn.turn_type.pedestrian_crossing() {
+            return false;
+        }
+        i.turns.iter().any(|t| {
+            t.turn_type.pedestrian_crossing()
+                && stage.get_priority_of_turn(t.id, i) == TurnPriority::Protected
+                && turn.conflicts_with(t)
+        })
+    }
+
     /// Modifies the fixed timing of all stages, applying either a major or minor duration,
     /// depending on the relative rank of the roads involved in the intersection. If this
     /// transformation couldn't be applied, returns an error. Even if an error is returned, the
@@ -344,6 +490,15 @@ impl Stage {
         }
     }
 
+    /// Is this an all-walk stage, protecting every crossing at the intersection and nothing else?
+    pub fn is_ped_scramble(&self, i: &Intersection) -> bool {
+        !self.protected_movements.is_empty()
+            && self.yield_movements.is_empty()
+            && i.movements.values().all(|m| {
+                m.turn_type.pedestrian_crossing() == self.protected_movements.contains(&m.id)
+            })
+    }
+
     // A trivial function that returns max crosswalk time if the stage is just crosswalks.
     pub fn max_crosswalk_time(&self, i: &Intersection) -> Option<Duration> {
         let mut max_distance = Distance::const_meters(0.0);
@@ -461,6 +616,10 @@ impl ControlTrafficSignal {
             id,
             stages,
             offset: Duration::seconds(plan.offset_seconds as f64),
+            transit_priority: None,
+            leading_pedestrian_interval: None,
+            actuation: None,
+            cluster: BTreeSet::new(),
         };
         ts.validate(map.get_i(id))?;
         Ok(ts)
@@ -1,14 +1,21 @@
 //! Public transit stops and routes.
 
+use std::collections::BTreeSet;
 use std::fmt;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::Time;
+use geom::{Distance, Duration, Pt2D, Time};
 
-use crate::{LaneID, Map, Path, PathConstraints, PathRequest, Position, RoadID};
+use crate::{
+    BuildingID, DirectedRoadID, LaneID, Map, Path, PathConstraints, PathRequest, PathV2, Position,
+    RoadID,
+};
+
+/// How long a vehicle waits at each stop. This matches the simulation.
+pub(crate) const DWELL_TIME: Duration = Duration::const_seconds(10.0);
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct TransitStopID {
@@ -69,6 +76,9 @@ pub struct TransitRoute {
     /// Explicitly store whatever the original was, since this can't be reconstructed without side
     /// input.
     pub orig_spawn_times: Vec<Time>,
+    /// The roads a vehicle drives along, from the start lane to the end, if the route has been
+    /// rerouted. When empty, vehicles take the fastest path between stops.
+    pub alignment: Vec<DirectedRoadID>,
 }
 
 impl TransitRoute {
@@ -108,6 +118,8 @@ impl TransitRoute {
     /// to the place where the vehicle vanishes.
     pub fn all_paths(&self, map: &Map) -> Result<Vec<Path>> {
         let mut paths = Vec::new();
+        // Where along the alignment the previous path ended
+        let mut idx = 0;
         for req in self.all_path_requests(map) {
             if req.start.lane().road == req.end.lane().road
                 && req.start.dist_along() > req.end.dist_along()
@@ -118,7 +130,12 @@ impl TransitRoute {
                 );
             }
 
-            let path = map.pathfind(req)?;
+            let path = if self.alignment.is_empty() {
+                map.pathfind(req)?
+            } else {
+                let roads = self.alignment_between(&req, &mut idx, map)?;
+                PathV2::from_roads(roads, req, Duration::ZERO, Vec::new(), map).into_v1(map)?
+            };
             if path.is_empty() {
                 bail!("Empty path between stops: {}", path.get_req());
             }
@@ -138,6 +155,73 @@ impl TransitRoute {
         Ok(paths)
     }
 
+    /// The slice of the alignment covering one path request, searching from `idx` onwards, so
+    /// routes that pass the same road twice use the right visit. Updates `idx` to where the slice
+    /// ends.
+    fn alignment_between(
+        &self,
+        req: &PathRequest,
+        idx: &mut usize,
+        map: &Map,
+    ) -> Result<Vec<DirectedRoadID>> {
+        let from = map.get_l(req.start.lane()).get_directed_parent();
+        let to = map.get_l(req.end.lane()).get_directed_parent();
+        let start = *idx
+            + self.alignment[*idx..]
+                .iter()
+                .position(|dr| *dr == from)
+                .ok_or_else(|| anyhow!("{} isn't along the alignment", req.start))?;
+        let end = start
+            + self.alignment[start..]
+                .iter()
+                .position(|dr| *dr == to)
+                .ok_or_else(|| anyhow!("{} isn't along the alignment", req.end))?;
+        *idx = end;
+        Ok(self.alignment[start..=end].to_vec())
+    }
+
+    /// Entry i is the time offset from a vehicle spawning to arriving at stop i, assuming no
+    /// traffic.
+    pub fn estimate_stop_arrivals(&self, map: &Map) -> Result<Vec<Duration>> {
+        let mut offsets = Vec::new();
+        let mut total = Duration::ZERO;
+        for path in self.all_paths(map)? {
+            total += path.estimate_duration(map, None);
+            offsets.push(total);
+            total += DWELL_TIME;
+        }
+        // The last path leads to where the vehicle vanishes, not a stop
+        offsets.pop();
+        Ok(offsets)
+    }
+
+    /// How long a vehicle takes from spawning to vanishing, assuming no traffic.
+    pub fn estimate_run_time(&self, map: &Map) -> Result<Duration> {
+        let mut total = DWELL_TIME * (self.stops.len() as f64);
+        for path in self.all_paths(map)? {
+            total += path.estimate_duration(map, None);
+        }
+        Ok(total)
+    }
+
+    /// Buildings within a straight-line distance of any stop along this route, measured from
+    /// where they connect to the sidewalk.
+    pub fn buildings_near_stops(&self, map: &Map, radius: Distance) -> BTreeSet<BuildingID> {
+        let stops: Vec<Pt2D> = self
+            .stops
+            .iter()
+            .map(|ts| map.get_ts(*ts).sidewalk_pos.pt(map))
+            .collect();
+        map.all_buildings()
+            .iter()
+            .filter(|b| {
+                let pt = b.sidewalk_pos.pt(map);
+                stops.iter().any(|stop| stop.dist_to(pt) <= radius)
+            })
+            .map(|b| b.id)
+            .collect()
+    }
+
     pub fn plural_noun(&self) -> &'static str {
         if self.route_type == PathConstraints::Bus {
             "buses"
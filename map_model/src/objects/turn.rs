@@ -5,8 +5,8 @@ use serde::{Deserialize, Serialize};
 use geom::{Angle, Line, PolyLine};
 
 use crate::{
-    DirectedRoadID, Direction, Intersection, IntersectionID, LaneID, Map, MovementID,
-    PathConstraints, RestrictionType,
+    DirectedRoadID, Direction, Intersection, IntersectionID, KerbType, LaneID, Map, MovementID,
+    PathConstraints, RestrictionType, WalkingClosure,
 };
 
 /// Turns are uniquely identified by their (src, dst) lanes and their parent intersection.
@@ -122,6 +122,25 @@ impl Turn {
             || self.turn_type == TurnType::UnmarkedCrossing
     }
 
+    /// Wheelchairs can't use a crossing if there's a raised kerb tagged where it meets either
+    /// sidewalk. Untagged kerbs are optimistically assumed to be dropped.
+    pub fn usable_by_wheelchair(&self, map: &Map) -> bool {
+        if !self.turn_type.pedestrian_crossing() {
+            return true;
+        }
+        [self.id.src, self.id.dst]
+            .into_iter()
+            .all(|l| map.get_r(l.road).kerb_near(self.id.parent) != Some(KerbType::Raised))
+    }
+
+    /// Is this crossing temporarily unusable on foot?
+    pub fn walking_closure(&self, map: &Map) -> Option<WalkingClosure> {
+        map.get_i(self.id.parent)
+            .closed_crossings
+            .get(&self.id)
+            .cloned()
+    }
+
     // TODO Maybe precompute this.
     /// Penalties for (lane types, lane-changing, slow lane). The penalty may depend on the vehicle
     /// performing the turn. Lower means preferable.
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Why a sidewalk or crossing can't be used on foot for a while. Closures are ordinary map edits,
+/// so a set of them can be saved as a proposal -- a snowstorm, or a season of works -- and
+/// simulated to see who gets cut off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum WalkingClosure {
+    /// Not cleared after snow or ice
+    Snow,
+    /// Blocked by construction or maintenance
+    Works,
+}
+
+impl WalkingClosure {
+    pub fn all() -> Vec<WalkingClosure> {
+        vec![WalkingClosure::Snow, WalkingClosure::Works]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            WalkingClosure::Snow => "snow",
+            WalkingClosure::Works => "works",
+        }
+    }
+}
@@ -10,19 +10,41 @@ use std::collections::BTreeSet;
 use enumset::EnumSet;
 use serde::{Deserialize, Serialize};
 
+use geom::Time;
+
 use crate::{CommonEndpoint, IntersectionID, Map, PathConstraints, RoadID};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct AccessRestrictions {
     pub allow_through_traffic: EnumSet<PathConstraints>,
+    /// These modes may only use this one road to start or end a trip on it, like roads signed
+    /// `motor_vehicle=destination`. Unlike `allow_through_traffic`, this doesn't form a zone; a
+    /// trip ending on a neighboring road can't use this one.
+    #[serde(default)]
+    pub destination_only: EnumSet<PathConstraints>,
+    /// If this isn't empty, cars may only use this road during these times of day, like a
+    /// pedestrianized street open for deliveries in the morning. Each window is `[start, end)`.
+    #[serde(default)]
+    pub delivery_windows: Vec<(Time, Time)>,
 }
 
 impl AccessRestrictions {
     pub fn new() -> AccessRestrictions {
         AccessRestrictions {
             allow_through_traffic: EnumSet::all(),
+            destination_only: EnumSet::new(),
+            delivery_windows: Vec::new(),
         }
     }
+
+    /// May cars use this road at this time of day?
+    pub fn cars_allowed_at(&self, time: Time) -> bool {
+        self.delivery_windows.is_empty()
+            || self
+                .delivery_windows
+                .iter()
+                .any(|(start, end)| *start <= time && time < *end)
+    }
 }
 
 /// A contiguous set of roads with access restrictions. This is derived from all the map's roads and
@@ -61,7 +83,7 @@ impl Zone {
 }
 
 fn floodfill(map: &Map, start: RoadID) -> Option<Zone> {
-    let match_constraints = map.get_r(start).access_restrictions.clone();
+    let match_constraints = map.get_r(start).access_restrictions.allow_through_traffic;
     let mut queue = vec![start];
     let mut members = BTreeSet::new();
     let mut borders = BTreeSet::new();
@@ -73,7 +95,7 @@ fn floodfill(map: &Map, start: RoadID) -> Option<Zone> {
         members.insert(current);
         for r in map.get_next_roads(current) {
             let r = map.get_r(r);
-            if r.access_restrictions == match_constraints {
+            if r.access_restrictions.allow_through_traffic == match_constraints {
                 queue.push(r.id);
             } else {
                 // TODO Handle other cases
@@ -92,6 +114,29 @@ fn floodfill(map: &Map, start: RoadID) -> Option<Zone> {
     Some(Zone {
         members,
         borders,
-        restrictions: match_constraints,
+        restrictions: AccessRestrictions {
+            allow_through_traffic: match_constraints,
+            ..AccessRestrictions::new()
+        },
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::Duration;
+
+    #[test]
+    fn test_cars_allowed_at() {
+        let hour = |h: usize| Time::START_OF_DAY + Duration::hours(h);
+        let mut restrictions = AccessRestrictions::new();
+        assert!(restrictions.cars_allowed_at(hour(3)));
+
+        restrictions.delivery_windows = vec![(hour(6), hour(10)), (hour(19), hour(22))];
+        assert!(!restrictions.cars_allowed_at(hour(3)));
+        assert!(restrictions.cars_allowed_at(hour(6)));
+        assert!(!restrictions.cars_allowed_at(hour(10)));
+        assert!(restrictions.cars_allowed_at(hour(20)));
+        assert!(!restrictions.cars_allowed_at(hour(23)));
+    }
+}
@@ -0,0 +1,200 @@
+//! Find several reasonable routes between the same endpoints, not just the single best one. Uses
+//! the penalty method: after finding each route, the roads it uses become more expensive, so the
+//! next search tends to find something different. Routes too similar to or much slower than ones
+//! already found are discarded.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use petgraph::graphmap::DiGraphMap;
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Duration};
+
+use crate::pathfind::vehicle_cost;
+use crate::{
+    DirectedRoadID, Map, MovementID, PathConstraints, PathRequest, PathStepV2, PathV2, RoadID,
+    RoutingParams,
+};
+
+/// How to search for alternative routes
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct AlternativeRoutes {
+    /// The most routes to return, including the best one
+    pub count: usize,
+    /// After a route is found, the cost of every road along it is multiplied by this
+    pub overlap_penalty: f64,
+    /// Discard a route if more than this fraction of its length is shared with a route already
+    /// found
+    pub max_overlap: f64,
+    /// Discard a route taking more than this many times as long as the best one
+    pub max_detour: f64,
+    /// How many searches to try before giving up on finding `count` routes
+    pub max_attempts: usize,
+}
+
+impl Default for AlternativeRoutes {
+    fn default() -> Self {
+        Self {
+            count: 3,
+            overlap_penalty: 1.5,
+            max_overlap: 0.8,
+            max_detour: 1.5,
+            max_attempts: 10,
+        }
+    }
+}
+
+impl AlternativeRoutes {
+    /// Returns up to `count` routes, ordered by cost. The first is the best route. The cost of
+    /// each route doesn't include the penalties used to find it.
+    ///
+    /// Only vehicles are supported; pedestrians just get the best route. Routes not from the map's
+    /// own pathfinder don't treat complex intersections as uber-turns.
+    pub fn find(&self, req: PathRequest, params: &RoutingParams, map: &Map) -> Result<Vec<PathV2>> {
+        // The map's pathfinder is much faster and handles uber-turns, so use it for the best route
+        // when possible
+        if params == map.routing_params()
+            && (self.count <= 1 || req.constraints == PathConstraints::Pedestrian)
+        {
+            return Ok(vec![map.pathfind_v2(req)?]);
+        }
+        if req.constraints == PathConstraints::Pedestrian {
+            bail!("Custom routing params aren't supported for pedestrians");
+        }
+
+        let graph = RoadGraph::new(req.constraints, params, map);
+        let start = map.get_l(req.start.lane()).get_directed_parent();
+        let end = map.get_l(req.end.lane()).get_directed_parent();
+        let best = if params == map.routing_params() {
+            map.pathfind_v2(req.clone())?
+        } else {
+            let (roads, cost) = graph
+                .search(start, end, &BTreeMap::new())
+                .ok_or_else(|| anyhow!("can't fulfill {}", req))?;
+            PathV2::from_roads(roads, req.clone(), cost, Vec::new(), map)
+        };
+        if self.count <= 1 {
+            return Ok(vec![best]);
+        }
+
+        let max_cost = best.get_cost() * self.max_detour;
+
+        let mut penalties: BTreeMap<RoadID, f64> = BTreeMap::new();
+        let mut results: Vec<(PathV2, BTreeSet<RoadID>)> = Vec::new();
+        add_penalty(&mut penalties, &best, self.overlap_penalty);
+        results.push((best.clone(), roads_used(&best)));
+
+        for _ in 0..self.max_attempts {
+            if results.len() == self.count {
+                break;
+            }
+            let (roads, cost) = match graph.search(start, end, &penalties) {
+                Some(pair) => pair,
+                None => break,
+            };
+            let path = PathV2::from_roads(roads, req.clone(), cost, Vec::new(), map);
+            add_penalty(&mut penalties, &path, self.overlap_penalty);
+            if cost > max_cost {
+                continue;
+            }
+            let used = roads_used(&path);
+            if results
+                .iter()
+                .any(|(_, other)| overlap(&used, other, map) > self.max_overlap)
+            {
+                continue;
+            }
+            results.push((path, used));
+        }
+
+        let mut paths: Vec<PathV2> = results.into_iter().map(|(path, _)| path).collect();
+        paths.sort_by_key(|path| path.get_cost());
+        Ok(paths)
+    }
+}
+
+/// Every movement a vehicle can make, with the unpenalized cost of crossing the road before it
+struct RoadGraph {
+    graph: DiGraphMap<DirectedRoadID, MovementID>,
+    costs: BTreeMap<MovementID, Duration>,
+}
+
+impl RoadGraph {
+    fn new(constraints: PathConstraints, params: &RoutingParams, map: &Map) -> RoadGraph {
+        let mut graph = DiGraphMap::new();
+        let mut costs = BTreeMap::new();
+        for r in map.all_roads() {
+            for dr in r.id.both_directions() {
+                if dr.lanes(constraints, map).is_empty() {
+                    continue;
+                }
+                for mvmnt in map.get_movements_for(dr, constraints) {
+                    if let Some(cost) = vehicle_cost(dr, mvmnt, constraints, params, map) {
+                        graph.add_edge(mvmnt.from, mvmnt.to, mvmnt);
+                        costs.insert(mvmnt, cost);
+                    }
+                }
+            }
+        }
+        RoadGraph { graph, costs }
+    }
+
+    /// Returns the roads along the route and the route's unpenalized cost
+    fn search(
+        &self,
+        start: DirectedRoadID,
+        end: DirectedRoadID,
+        penalties: &BTreeMap<RoadID, f64>,
+    ) -> Option<(Vec<DirectedRoadID>, Duration)> {
+        if start == end {
+            return Some((vec![start], Duration::ZERO));
+        }
+        if !self.graph.contains_node(start) || !self.graph.contains_node(end) {
+            return None;
+        }
+        let (_, roads) = petgraph::algo::astar(
+            &self.graph,
+            start,
+            |dr| dr == end,
+            |(_, _, mvmnt)| {
+                self.costs[mvmnt] * penalties.get(&mvmnt.from.road).cloned().unwrap_or(1.0)
+            },
+            |_| Duration::ZERO,
+        )?;
+        let mut cost = Duration::ZERO;
+        for pair in roads.windows(2) {
+            cost += self.costs[self.graph.edge_weight(pair[0], pair[1]).unwrap()];
+        }
+        Some((roads, cost))
+    }
+}
+
+fn roads_used(path: &PathV2) -> BTreeSet<RoadID> {
+    path.get_steps()
+        .iter()
+        .filter_map(|step| match step {
+            PathStepV2::Along(dr) => Some(dr.road),
+            _ => None,
+        })
+        .collect()
+}
+
+fn add_penalty(penalties: &mut BTreeMap<RoadID, f64>, path: &PathV2, penalty: f64) {
+    for r in roads_used(path) {
+        *penalties.entry(r).or_insert(1.0) *= penalty;
+    }
+}
+
+/// The fraction of the length of `roads` also used by `other`
+fn overlap(roads: &BTreeSet<RoadID>, other: &BTreeSet<RoadID>, map: &Map) -> f64 {
+    let total: Distance = roads.iter().map(|r| map.get_r(*r).length()).sum();
+    if total == Distance::ZERO {
+        return 1.0;
+    }
+    let shared: Distance = roads
+        .intersection(other)
+        .map(|r| map.get_r(*r).length())
+        .sum();
+    shared / total
+}
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 
 use geom::Duration;
 
+pub use self::alternatives::AlternativeRoutes;
 pub use self::engine::CreateEngine;
 pub use self::pathfinder::{Pathfinder, PathfinderCache, PathfinderCaching};
 pub use self::v1::{Path, PathRequest, PathStep};
@@ -15,6 +16,7 @@ pub use self::vehicles::vehicle_cost;
 pub use self::walking::WalkingNode;
 use crate::{osm, Lane, LaneID, LaneType, Map, MovementID, Road, RoadID, TurnType};
 
+mod alternatives;
 mod engine;
 mod node_map;
 mod pathfinder;
@@ -161,6 +163,27 @@ pub(crate) fn zone_cost(mvmnt: MovementID, constraints: PathConstraints, map: &M
     }
 }
 
+/// Heavily penalize entering a road only open to this mode to start or end a trip there. Leaving
+/// a road is free, so trips starting on one aren't penalized, and every route to a trip ending on
+/// one pays the same penalty once.
+pub(crate) fn destination_only_cost(
+    mvmnt: MovementID,
+    constraints: PathConstraints,
+    map: &Map,
+) -> Duration {
+    if map
+        .get_r(mvmnt.to.road)
+        .access_restrictions
+        .destination_only
+        .contains(constraints)
+    {
+        // Like zone_cost, only cut through when the alternative is absurdly long
+        Duration::hours(3)
+    } else {
+        Duration::ZERO
+    }
+}
+
 /// Tuneable parameters for all types of routing.
 // These will maybe become part of the PathRequest later, but that's an extremely invasive and
 // space-expensive change right now.
@@ -183,6 +206,10 @@ pub struct RoutingParams {
     pub avoid_steep_incline_penalty: f64,
     // If the road is `high_stress_for_bikes`, multiply by the base cost.
     pub avoid_high_stress: f64,
+    /// For bike routing. If the road is tagged as having no street lights, multiply by the base
+    /// cost. Useful for routes after dark.
+    // TODO Walking routes don't use RoutingParams yet, so they can't avoid unlit roads.
+    pub avoid_unlit: f64,
 
     /// When crossing an arterial or highway road, multiply the base cost by this penalty. When
     /// greater than 1, this will encourage routes to use local roads more.
@@ -213,6 +240,7 @@ impl Default for RoutingParams {
 
             avoid_steep_incline_penalty: 1.0,
             avoid_high_stress: 1.0,
+            avoid_unlit: 1.0,
 
             main_road_penalty: 1.0,
 
@@ -23,6 +23,7 @@ pub struct Pathfinder {
     train_graph: VehiclePathfinder,
     walking_graph: SidewalkPathfinder,
     walking_with_transit_graph: SidewalkPathfinder,
+    wheelchair_graph: SidewalkPathfinder,
 
     // These params cover the main graphs
     params: RoutingParams,
@@ -56,6 +57,7 @@ impl Clone for Pathfinder {
             train_graph: self.train_graph.clone(),
             walking_graph: self.walking_graph.clone(),
             walking_with_transit_graph: self.walking_with_transit_graph.clone(),
+            wheelchair_graph: self.wheelchair_graph.clone(),
             params: self.params.clone(),
             cached_alternatives: ThreadLocal::new(),
         }
@@ -73,6 +75,7 @@ impl Pathfinder {
             train_graph: VehiclePathfinder::empty(),
             walking_graph: SidewalkPathfinder::empty(),
             walking_with_transit_graph: SidewalkPathfinder::empty(),
+            wheelchair_graph: SidewalkPathfinder::empty(),
             params: RoutingParams::default(),
             cached_alternatives: ThreadLocal::new(),
         }
@@ -118,6 +121,12 @@ impl Pathfinder {
         let walking_graph = SidewalkPathfinder::new(map, None, engine);
         timer.stop("prepare pathfinding for pedestrians");
 
+        // The nodes are the same as the normal walking graph, just with some edges missing
+        timer.start("prepare pathfinding for wheelchair users");
+        let wheelchair_graph =
+            SidewalkPathfinder::new_wheelchair(map, &walking_graph.engine.reuse_ordering());
+        timer.stop("prepare pathfinding for wheelchair users");
+
         // Transit routes haven't been created yet, so defer this step
         let walking_with_transit_graph = SidewalkPathfinder::empty();
 
@@ -128,6 +137,7 @@ impl Pathfinder {
             train_graph,
             walking_graph,
             walking_with_transit_graph,
+            wheelchair_graph,
 
             params,
             cached_alternatives: ThreadLocal::new(),
@@ -271,6 +281,13 @@ impl Pathfinder {
         result
     }
 
+    /// Finds a walking path for somebody using a wheelchair, avoiding steps and crossings without
+    /// a dropped kerb.
+    pub fn pathfind_wheelchair(&self, req: PathRequest, map: &Map) -> Option<PathV2> {
+        assert_eq!(req.constraints, PathConstraints::Pedestrian);
+        self.wheelchair_graph.pathfind(req, map)
+    }
+
     pub fn all_costs_from(
         &self,
         req: PathRequest,
@@ -318,6 +335,10 @@ impl Pathfinder {
         self.walking_graph.apply_edits(map, None);
         timer.stop("apply edits to pedestrian pathfinding");
 
+        timer.start("apply edits to wheelchair pathfinding");
+        self.wheelchair_graph.apply_edits(map, None);
+        timer.stop("apply edits to wheelchair pathfinding");
+
         timer.start("apply edits to pedestrian using transit pathfinding");
         self.walking_with_transit_graph
             .apply_edits(map, Some((&self.bus_graph, &self.train_graph)));
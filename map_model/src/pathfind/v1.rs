@@ -299,6 +299,22 @@ impl Path {
         step
     }
 
+    /// Drop every step except the last one, as if something had been teleported most of the way
+    /// along the path. The request is changed to begin at `start`, which must be on the last
+    /// lane.
+    pub fn skip_to_last_step(&mut self, map: &Map, start: Position) {
+        let last = self.steps.pop_back().unwrap();
+        assert_eq!(PathStep::Lane(start.lane()), last);
+        self.steps.clear();
+        self.steps.push_back(last);
+        self.uber_turns.clear();
+        self.currently_inside_ut = None;
+        self.blocked_starts.clear();
+        self.orig_req.start = start;
+        self.orig_req.alt_start = None;
+        self.crossed_so_far = self.total_length - self.dist_crossed_from_step(map, &last);
+    }
+
     pub fn add(&mut self, step: PathStep, map: &Map) {
         if let Some(PathStep::Lane(l)) = self.steps.back() {
             if *l == self.orig_req.end.lane() {
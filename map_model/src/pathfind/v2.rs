@@ -138,7 +138,7 @@ impl PathV2 {
                 PathStepV2::Along(dr) | PathStepV2::Contraflow(dr) => {
                     let road = map.get_r(dr.road);
                     dist = road.length();
-                    speed = road.speed_limit;
+                    speed = road.effective_speed_limit();
 
                     if let Some(penalty) = main_road_penalty {
                         if road.get_rank() != osm::RoadRank::Local {
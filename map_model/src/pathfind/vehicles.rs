@@ -11,7 +11,7 @@ use geom::Duration;
 use crate::pathfind::engine::{CreateEngine, PathfindEngine};
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::pathfind::uber_turns::{IntersectionCluster, UberTurnV2};
-use crate::pathfind::zone_cost;
+use crate::pathfind::{destination_only_cost, zone_cost};
 use crate::pathfind::{round, unround};
 use crate::{
     osm, DirectedRoadID, Direction, LaneType, Map, MovementID, PathConstraints, PathRequest,
@@ -372,7 +372,15 @@ pub fn vehicle_cost(
         multiplier *= params.avoid_high_stress;
     }
 
-    let mut extra = zone_cost(mvmnt, constraints, map);
+    if constraints == PathConstraints::Bike
+        && (params.avoid_unlit - 1.0).abs() > f64::EPSILON
+        && road.is_lit() == Some(false)
+    {
+        multiplier *= params.avoid_unlit;
+    }
+
+    let mut extra =
+        zone_cost(mvmnt, constraints, map) + destination_only_cost(mvmnt, constraints, map);
     // Penalize unprotected turns at a stop sign from smaller to larger roads.
     if map.is_unprotected_turn(dr.road, mvmnt.to.road, movement.turn_type) {
         extra += params.unprotected_turn_penalty
@@ -22,7 +22,9 @@ pub struct SidewalkPathfinder {
     #[serde(deserialize_with = "deserialize_nodemap")]
     nodes: NodeMap<WalkingNode>,
     use_transit: bool,
-    engine: PathfindEngine,
+    /// Avoid steps, crossings without a dropped kerb, and rough surfaces
+    wheelchair: bool,
+    pub(crate) engine: PathfindEngine,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Serialize, Deserialize)]
@@ -59,6 +61,7 @@ impl SidewalkPathfinder {
         SidewalkPathfinder {
             nodes: NodeMap::new(),
             use_transit: false,
+            wheelchair: false,
             engine: PathfindEngine::Empty,
         }
     }
@@ -68,33 +71,28 @@ impl SidewalkPathfinder {
         use_transit: Option<(&VehiclePathfinder, &VehiclePathfinder)>,
         engine: &CreateEngine,
     ) -> SidewalkPathfinder {
-        let mut nodes = NodeMap::new();
-        for r in map.all_roads() {
-            // Regardless of whether the road has sidewalks/shoulders on one or both sides, add
-            // both. These could change later, and we want the node IDs to match up.
-            for dr in r.id.both_directions() {
-                for endpt in [true, false] {
-                    nodes.get_or_insert(WalkingNode::SidewalkEndpoint(dr, endpt));
-                }
-            }
-        }
-        if use_transit.is_some() {
-            // Add a node for each stop.
-            for ts in map.all_transit_stops().keys() {
-                nodes.get_or_insert(WalkingNode::RideTransit(*ts));
-            }
-            for i in map.all_outgoing_borders() {
-                // We could filter for those with sidewalks, but eh
-                nodes.get_or_insert(WalkingNode::LeaveMap(i.id));
-            }
+        let nodes = make_nodes(map, use_transit.is_some());
+        let input_graph = make_input_graph(&nodes, use_transit, false, map);
+        let engine = engine.create(input_graph);
+
+        SidewalkPathfinder {
+            nodes,
+            use_transit: use_transit.is_some(),
+            wheelchair: false,
+            engine,
         }
+    }
 
-        let input_graph = make_input_graph(&nodes, use_transit, map);
+    /// Only walking, for somebody using a wheelchair.
+    pub fn new_wheelchair(map: &Map, engine: &CreateEngine) -> SidewalkPathfinder {
+        let nodes = make_nodes(map, false);
+        let input_graph = make_input_graph(&nodes, None, true, map);
         let engine = engine.create(input_graph);
 
         SidewalkPathfinder {
             nodes,
-            use_transit: use_transit.is_some(),
+            use_transit: false,
+            wheelchair: true,
             engine,
         }
     }
@@ -108,8 +106,25 @@ impl SidewalkPathfinder {
             return;
         }
 
-        let input_graph = make_input_graph(&self.nodes, use_transit, map);
-        let engine = self.engine.reuse_ordering().create(input_graph);
+        // New transit stops need new nodes, so the old node ordering can't be reused
+        let mut added_stops = false;
+        if self.use_transit {
+            for ts in map.all_transit_stops().keys() {
+                if !self.nodes.contains(WalkingNode::RideTransit(*ts)) {
+                    self.nodes.get_or_insert(WalkingNode::RideTransit(*ts));
+                    added_stops = true;
+                }
+            }
+        }
+
+        let input_graph = make_input_graph(&self.nodes, use_transit, self.wheelchair, map);
+        let engine = if !added_stops {
+            self.engine.reuse_ordering().create(input_graph)
+        } else if self.engine.is_dijkstra() {
+            CreateEngine::Dijkstra.create(input_graph)
+        } else {
+            CreateEngine::CH.create(input_graph)
+        };
         self.engine = engine;
     }
 
@@ -241,7 +256,7 @@ impl SidewalkPathfinder {
             self.engine.all_costs_from(start)
         } else {
             // The CH engine doesn't support this!
-            let input_graph = make_input_graph(&self.nodes, None, map);
+            let input_graph = make_input_graph(&self.nodes, None, self.wheelchair, map);
             CreateEngine::Dijkstra
                 .create(input_graph)
                 .all_costs_from(start)
@@ -261,9 +276,34 @@ impl SidewalkPathfinder {
     }
 }
 
+fn make_nodes(map: &Map, use_transit: bool) -> NodeMap<WalkingNode> {
+    let mut nodes = NodeMap::new();
+    for r in map.all_roads() {
+        // Regardless of whether the road has sidewalks/shoulders on one or both sides, add
+        // both. These could change later, and we want the node IDs to match up.
+        for dr in r.id.both_directions() {
+            for endpt in [true, false] {
+                nodes.get_or_insert(WalkingNode::SidewalkEndpoint(dr, endpt));
+            }
+        }
+    }
+    if use_transit {
+        // Add a node for each stop.
+        for ts in map.all_transit_stops().keys() {
+            nodes.get_or_insert(WalkingNode::RideTransit(*ts));
+        }
+        for i in map.all_outgoing_borders() {
+            // We could filter for those with sidewalks, but eh
+            nodes.get_or_insert(WalkingNode::LeaveMap(i.id));
+        }
+    }
+    nodes
+}
+
 fn make_input_graph(
     nodes: &NodeMap<WalkingNode>,
     use_transit: Option<(&VehiclePathfinder, &VehiclePathfinder)>,
+    wheelchair: bool,
     map: &Map,
 ) -> InputGraph {
     let max_speed = Some(crate::MAX_WALKING_SPEED);
@@ -271,6 +311,10 @@ fn make_input_graph(
 
     for l in map.all_lanes() {
         if l.is_walkable() {
+            let road = map.get_r(l.id.road);
+            if (wheelchair && road.is_steps()) || l.walking_closure(map).is_some() {
+                continue;
+            }
             // Sidewalks can be crossed in two directions. When there's a steep incline, of course
             // it flips.
             let n1 = nodes.get(WalkingNode::SidewalkEndpoint(
@@ -289,6 +333,15 @@ fn make_input_graph(
                 if l.is_shoulder() {
                     cost = 2.0 * cost;
                 }
+                if wheelchair {
+                    if road.has_rough_walking_surface() {
+                        cost = 3.0 * cost;
+                    }
+                    // Steep slopes are slow going either way
+                    if road.percent_incline.abs() > 0.08 {
+                        cost = 2.0 * cost;
+                    }
+                }
                 input_graph.add_edge(pair.0, pair.1, round(cost));
             }
         }
@@ -315,6 +368,12 @@ fn make_input_graph(
                 // TODO Add to RoutingParams
                 cost = 3.0 * cost;
             }
+            if wheelchair && !t.usable_by_wheelchair(map) {
+                continue;
+            }
+            if t.walking_closure(map).is_some() {
+                continue;
+            }
 
             input_graph.add_edge(from, to, round(cost));
             input_graph.add_edge(to, from, round(cost));
@@ -0,0 +1,112 @@
+//! Adjacent maps can be stitched together at the border intersections they share, so that trips
+//! can start in one map and end in another.
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::Distance;
+
+use crate::{IntersectionID, Map};
+
+/// Borders not matched by OSM node must be at least this close in the two maps.
+const MAX_STITCH_DIST: Distance = Distance::const_meters(10.0);
+/// Clipping invents new nodes whose IDs can collide between maps, so even borders with the same
+/// OSM node must be near each other.
+const MAX_SAME_NODE_DIST: Distance = Distance::const_meters(100.0);
+
+/// A border intersection in one map that's the same place as a border in another map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stitch {
+    pub map1: usize,
+    pub i1: IntersectionID,
+    pub map2: usize,
+    pub i2: IntersectionID,
+}
+
+/// Several maps that touch each other
+pub struct Region {
+    pub maps: Vec<Map>,
+    pub stitches: Vec<Stitch>,
+}
+
+impl Region {
+    pub fn new(maps: Vec<Map>, timer: &mut Timer) -> Region {
+        let mut stitches = Vec::new();
+        timer.start("stitch map borders");
+        for idx1 in 0..maps.len() {
+            for idx2 in idx1 + 1..maps.len() {
+                stitches.extend(find_stitches(&maps, idx1, idx2));
+            }
+        }
+        timer.stop("stitch map borders");
+        info!(
+            "Stitched {} maps together at {} borders",
+            maps.len(),
+            stitches.len()
+        );
+        Region { maps, stitches }
+    }
+
+    pub fn load(names: Vec<MapName>, timer: &mut Timer) -> Region {
+        let maps = names
+            .into_iter()
+            .map(|name| Map::load_synchronously(name.path(), timer))
+            .collect();
+        Region::new(maps, timer)
+    }
+
+    /// Every way to cross directly from one map into another, as pairs of the border in each map.
+    /// Not every crossing can be used by every mode, or in both directions.
+    pub fn crossings(&self, from: usize, to: usize) -> Vec<(IntersectionID, IntersectionID)> {
+        self.stitches
+            .iter()
+            .filter_map(|s| {
+                if s.map1 == from && s.map2 == to {
+                    Some((s.i1, s.i2))
+                } else if s.map1 == to && s.map2 == from {
+                    Some((s.i2, s.i1))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn find_stitches(maps: &[Map], idx1: usize, idx2: usize) -> Vec<Stitch> {
+    let map1 = &maps[idx1];
+    let map2 = &maps[idx2];
+    let mut stitches = Vec::new();
+    for i1 in map1.all_intersections() {
+        if !i1.is_border() {
+            continue;
+        }
+        // Where is this border in the other map's coordinates?
+        let pt = i1
+            .polygon
+            .center()
+            .to_gps(map1.get_gps_bounds())
+            .to_pt(map2.get_gps_bounds());
+        // Prefer matching the same OSM node, since clipping may not produce exactly the same
+        // geometry in both maps
+        let candidates = map2.all_intersections().iter().filter(|i2| i2.is_border());
+        let same_node = candidates.clone().find(|i2| {
+            i2.orig_id == i1.orig_id && i2.polygon.center().dist_to(pt) < MAX_SAME_NODE_DIST
+        });
+        let closest = || {
+            candidates
+                .map(|i2| (i2.polygon.center().dist_to(pt), i2))
+                .filter(|(dist, _)| *dist < MAX_STITCH_DIST)
+                .min_by_key(|(dist, _)| *dist)
+                .map(|(_, i2)| i2)
+        };
+        if let Some(i2) = same_node.or_else(closest) {
+            stitches.push(Stitch {
+                map1: idx1,
+                i1: i1.id,
+                map2: idx2,
+                i2: i2.id,
+            });
+        }
+    }
+    stitches
+}
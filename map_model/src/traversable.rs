@@ -217,8 +217,8 @@ impl Traversable {
             walking_speed_on_incline(max_speed_on_flat_ground.unwrap(), percent_incline)
         } else {
             debug_assert!(max_speed_on_flat_ground.is_none());
-            // Incline doesn't affect cars, buses, or trains
-            road.speed_limit
+            // Incline doesn't affect cars, buses, or trains, but traffic calming does
+            road.effective_speed_limit()
         };
 
         let speed = if let Some(s) = max_speed_on_flat_ground {
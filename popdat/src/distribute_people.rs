@@ -4,8 +4,9 @@ use rand_xorshift::XorShiftRng;
 
 use abstutil::prettyprint_usize;
 use map_model::{BuildingID, Map};
+use synthpop::IncomeBand;
 
-use crate::{CensusArea, CensusPerson, Config};
+use crate::{AreaDemographics, CensusArea, CensusPerson, Config};
 
 pub fn assign_people_to_houses(
     areas: Vec<CensusArea>,
@@ -15,16 +16,18 @@ pub fn assign_people_to_houses(
 ) -> Vec<CensusPerson> {
     let mut people = Vec::new();
     for area in areas {
+        let demographics = area.demographics;
         for (home, n) in distribute_population_to_homes(area.polygon, area.population, map, rng) {
             for _ in 0..n {
                 people.push(CensusPerson {
                     home,
+                    age: pick_age(&demographics, rng),
                     // TODO Making this up for now. We can either move this to Config or see if we
-                    // can extract it from the census. Also, not even sure which of these
-                    // attributes are useful later in the pipeline.
-                    age: rng.gen_range(5..95),
+                    // can extract it from the census.
                     employed: rng.gen_bool(0.7),
-                    owns_car: rng.gen_bool(0.5),
+                    owns_car: rng.gen_bool(demographics.pct_car_owners),
+                    income: pick_income(&demographics, rng),
+                    has_disability: rng.gen_bool(demographics.pct_disabled),
                 });
             }
         }
@@ -32,6 +35,30 @@ pub fn assign_people_to_houses(
     people
 }
 
+fn pick_age(demographics: &AreaDemographics, rng: &mut XorShiftRng) -> usize {
+    let x: f64 = rng.gen();
+    if x < demographics.pct_under_18 {
+        rng.gen_range(5..18)
+    } else if x < 1.0 - demographics.pct_over_65 {
+        rng.gen_range(18..65)
+    } else {
+        rng.gen_range(65..95)
+    }
+}
+
+fn pick_income(demographics: &AreaDemographics, rng: &mut XorShiftRng) -> Option<IncomeBand> {
+    let pct_low = demographics.pct_low_income?;
+    let pct_high = demographics.pct_high_income?;
+    let x: f64 = rng.gen();
+    Some(if x < pct_low {
+        IncomeBand::Low
+    } else if x < 1.0 - pct_high {
+        IncomeBand::Middle
+    } else {
+        IncomeBand::High
+    })
+}
+
 /// Starting from some number of total people living in a polygonal area, randomly distribute them
 /// to residential buildings within that area. Returns a list of homes with the number of residents
 /// in each.
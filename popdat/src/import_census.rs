@@ -3,10 +3,11 @@ use geo::{BoundingRect, Intersects, MapCoordsInPlace};
 
 use geom::{GPSBounds, Polygon};
 
-use crate::CensusArea;
+use crate::{CensusArea, CensusSource};
 
 impl CensusArea {
     pub async fn fetch_all_for_map(
+        source: CensusSource,
         map_area: &Polygon,
         bounds: &GPSBounds,
     ) -> Result<Vec<CensusArea>> {
@@ -23,8 +24,7 @@ impl CensusArea {
             .bounding_rect()
             .ok_or_else(|| anyhow!("missing bound rect"))?;
 
-        // See the import handbook for how to prepare this file.
-        let mut fgb = HttpFgbReader::open("https://abstreet.s3.amazonaws.com/population_areas.fgb")
+        let mut fgb = HttpFgbReader::open(source.areas_url())
             .await?
             .select_bbox(
                 bounding_rect.min().x,
@@ -39,11 +39,13 @@ impl CensusArea {
             use flatgeobuf::FeatureProperties;
             // PERF TODO: how to parse into usize directly? And avoid parsing entire props dict?
             let props = feature.properties()?;
-            if !props.contains_key("population") {
-                warn!("skipping feature with missing population");
-                continue;
-            }
-            let population: usize = props["population"].parse()?;
+            let (population, demographics) = match source.parse_area(&props) {
+                Some(pair) => pair,
+                None => {
+                    warn!("skipping feature with missing population");
+                    continue;
+                }
+            };
             let geometry = match feature.geometry() {
                 Some(g) => g,
                 None => {
@@ -79,6 +81,7 @@ impl CensusArea {
                 results.push(CensusArea {
                     polygon,
                     population,
+                    demographics,
                 });
             } else {
                 warn!("skipping unexpected geometry");
@@ -9,7 +9,7 @@
 //! These types form a pipeline:
 //!
 //! 1) For a given map, find some census data that describes how many people live in different
-//!    areas of the city. (CensusArea)
+//!    areas of the city. (CensusArea) The CensusSource depends on what country the map is in.
 //! 2) Take the CensusAreas and turn them into individual CensusPersons, by randomly choosing a
 //!    specific building on the map as their home, and assigning specific attributes based on the
 //!    census data's distribution.
@@ -27,15 +27,17 @@ use rand_xorshift::XorShiftRng;
 use abstutil::Timer;
 use geom::{Distance, Time};
 use map_model::{BuildingID, Map};
-use synthpop::Scenario;
+use synthpop::{IncomeBand, Scenario};
 
 pub use self::distribute_people::distribute_population_to_homes;
+pub use self::sources::{CensusSource, ModeShares};
 
 mod activities;
 mod distribute_people;
 mod import_census;
 mod make_person;
 pub mod od;
+mod sources;
 
 /// Represents aggregate demographic data for some part of a city. These could be census tracts or
 /// blocks, depending what data we find. All of the areas should roughly partition the map -- we
@@ -45,7 +47,33 @@ pub mod od;
 pub struct CensusArea {
     pub polygon: geo::Polygon,
     pub population: usize,
-    // TODO Not sure what goes here, whatever census data actually has that could be useful
+    pub demographics: AreaDemographics,
+}
+
+/// The share of residents in an area belonging to different groups, each from 0 to 1. When the
+/// census data is missing something, a guess is used instead.
+#[derive(Debug, PartialEq)]
+pub struct AreaDemographics {
+    pub pct_under_18: f64,
+    pub pct_over_65: f64,
+    /// If the census data doesn't cover income, then nobody is assigned an income band.
+    pub pct_low_income: Option<f64>,
+    pub pct_high_income: Option<f64>,
+    pub pct_car_owners: f64,
+    pub pct_disabled: f64,
+}
+
+impl AreaDemographics {
+    pub fn default() -> AreaDemographics {
+        AreaDemographics {
+            pct_under_18: 0.2,
+            pct_over_65: 0.15,
+            pct_low_income: None,
+            pct_high_income: None,
+            pct_car_owners: 0.5,
+            pct_disabled: 0.1,
+        }
+    }
 }
 
 /// Demographic information for a single person
@@ -54,6 +82,8 @@ pub struct CensusPerson {
     pub age: usize,
     pub employed: bool,
     pub owns_car: bool,
+    pub income: Option<IncomeBand>,
+    pub has_disability: bool,
 }
 
 /// It might be useful to classify a CensusPerson into different categories to figure out their
@@ -6,7 +6,9 @@ use rand_xorshift::XorShiftRng;
 
 use abstutil::Timer;
 use map_model::{BuildingID, IntersectionID, Map, PathConstraints, PathRequest};
-use synthpop::{IndividTrip, PersonSpec, TripEndpoint, TripMode, TripPurpose};
+use synthpop::{
+    AgeBand, Demographics, IndividTrip, PersonSpec, TripEndpoint, TripMode, TripPurpose,
+};
 
 use crate::{Activity, CensusPerson, Config};
 
@@ -184,6 +186,12 @@ impl PersonFactory {
         let mut output = PersonSpec {
             orig_id: None,
             trips: Vec::new(),
+            demographics: Some(Demographics {
+                age: AgeBand::from_years(person.age),
+                income: person.income,
+                owns_car: person.owns_car,
+                has_disability: person.has_disability,
+            }),
         };
 
         let mut current_location = TripEndpoint::Building(person.home);
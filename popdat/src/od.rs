@@ -163,6 +163,7 @@ pub fn disaggregate(
                             desire.mode,
                         ),
                     ],
+                    demographics: None,
                 });
             }
         }
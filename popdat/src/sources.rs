@@ -0,0 +1,328 @@
+//! Different countries publish population data in different shapes. Each `CensusSource` knows
+//! where to find a FlatGeobuf file of areas for its region, how to turn the raw properties of each
+//! area into a population and `AreaDemographics`, and optionally how to parse workplace flows into
+//! `DesireLine`s for `od::disaggregate`.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use abstio::MapName;
+use synthpop::TripMode;
+
+use crate::od::DesireLine;
+use crate::AreaDemographics;
+
+/// Countries reporting to Eurostat. The UK has more detailed data of its own.
+const EUROSTAT_COUNTRIES: [&str; 31] = [
+    "at", "be", "bg", "ch", "cy", "cz", "de", "dk", "ee", "es", "fi", "fr", "gr", "hr", "hu", "ie",
+    "is", "it", "li", "lt", "lu", "lv", "mt", "nl", "no", "pl", "pt", "ro", "se", "si", "sk",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CensusSource {
+    /// US census blocks, with properties already expressed as `pct_under_18` and so on
+    UsCensus,
+    /// UK 2011 census output areas, with raw counts from NOMIS tables
+    UkOutputAreas,
+    /// Eurostat NUTS 3 regions. These are large, so people are spread out assuming uniform
+    /// density.
+    Eurostat,
+}
+
+impl CensusSource {
+    /// Picks the source covering the region a map is in, if there is one.
+    pub fn for_map(name: &MapName) -> Option<CensusSource> {
+        let country = name.city.country.as_str();
+        if country == "us" {
+            Some(CensusSource::UsCensus)
+        } else if country == "gb" {
+            Some(CensusSource::UkOutputAreas)
+        } else if EUROSTAT_COUNTRIES.contains(&country) {
+            Some(CensusSource::Eurostat)
+        } else {
+            None
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            CensusSource::UsCensus => "US census blocks",
+            CensusSource::UkOutputAreas => "UK census output areas (NOMIS)",
+            CensusSource::Eurostat => "Eurostat NUTS 3 regions",
+        }
+    }
+
+    /// See the import handbook for how to prepare these files.
+    pub(crate) fn areas_url(self) -> &'static str {
+        match self {
+            CensusSource::UsCensus => "https://abstreet.s3.amazonaws.com/population_areas.fgb",
+            CensusSource::UkOutputAreas => {
+                "https://abstreet.s3.amazonaws.com/population_areas_uk.fgb"
+            }
+            CensusSource::Eurostat => {
+                "https://abstreet.s3.amazonaws.com/population_areas_eurostat.fgb"
+            }
+        }
+    }
+
+    /// Returns the population of one area and a description of its residents, or None if the area
+    /// is missing its population.
+    pub(crate) fn parse_area(
+        self,
+        props: &HashMap<String, String>,
+    ) -> Option<(usize, AreaDemographics)> {
+        match self {
+            CensusSource::UsCensus => {
+                let population = props.get("population")?.parse().ok()?;
+                Some((population, parse_pct_demographics(props)))
+            }
+            CensusSource::UkOutputAreas => parse_nomis(props),
+            CensusSource::Eurostat => parse_eurostat(props),
+        }
+    }
+
+    /// Parses a CSV file of commuters between zones.
+    ///
+    /// - For the UK, this is `wu03ew_v2.csv`, with flows between middle layer super output areas
+    ///   broken down by mode.
+    /// - For Eurostat, this is an SDMX-CSV export of commuting flows between NUTS regions, with
+    ///   `geo_residence`, `geo_work`, and `OBS_VALUE` columns. There's no breakdown by mode, so
+    ///   `mode_shares` splits the commuters.
+    pub fn parse_workplace_flows(
+        self,
+        raw: &[u8],
+        mode_shares: &ModeShares,
+    ) -> Result<Vec<DesireLine>> {
+        let mut output = Vec::new();
+        match self {
+            CensusSource::UsCensus => {
+                bail!("Workplace flows for the US aren't supported yet");
+            }
+            CensusSource::UkOutputAreas => {
+                for rec in csv::Reader::from_reader(raw).deserialize() {
+                    let rec: UkFlow = rec?;
+                    for (mode, number_commuters) in [
+                        (TripMode::Drive, rec.num_drivers),
+                        (TripMode::Bike, rec.num_bikers),
+                        (TripMode::Walk, rec.num_pedestrians),
+                        (
+                            TripMode::Transit,
+                            rec.num_transit1 + rec.num_transit2 + rec.num_transit3,
+                        ),
+                    ] {
+                        if number_commuters > 0 {
+                            output.push(DesireLine {
+                                home_zone: rec.home_zone.clone(),
+                                work_zone: rec.work_zone.clone(),
+                                mode,
+                                number_commuters,
+                            });
+                        }
+                    }
+                }
+            }
+            CensusSource::Eurostat => {
+                for rec in csv::Reader::from_reader(raw).deserialize() {
+                    let rec: EurostatFlow = rec?;
+                    // Missing observations are marked with ':'
+                    let total = match rec.value.trim().parse::<f64>() {
+                        Ok(x) => x,
+                        Err(_) => continue,
+                    };
+                    for (mode, pct) in mode_shares.split() {
+                        let number_commuters = (total * pct).round() as usize;
+                        if number_commuters > 0 {
+                            output.push(DesireLine {
+                                home_zone: rec.home_zone.clone(),
+                                work_zone: rec.work_zone.clone(),
+                                mode,
+                                number_commuters,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// When workplace flows don't say how people commute, assume this split, from 0 to 1.
+pub struct ModeShares {
+    pub drive: f64,
+    pub transit: f64,
+    pub bike: f64,
+    pub walk: f64,
+}
+
+impl ModeShares {
+    pub fn default() -> ModeShares {
+        ModeShares {
+            drive: 0.6,
+            transit: 0.2,
+            bike: 0.1,
+            walk: 0.1,
+        }
+    }
+
+    fn split(&self) -> Vec<(TripMode, f64)> {
+        let total = self.drive + self.transit + self.bike + self.walk;
+        if total <= 0.0 {
+            return Vec::new();
+        }
+        vec![
+            (TripMode::Drive, self.drive / total),
+            (TripMode::Transit, self.transit / total),
+            (TripMode::Bike, self.bike / total),
+            (TripMode::Walk, self.walk / total),
+        ]
+    }
+}
+
+// An entry in wu03ew_v2.csv. For now, ignores people who work from home, take a taxi, motorcycle,
+// are a passenger in a car, or use "another method of travel".
+#[derive(Debug, Deserialize)]
+struct UkFlow {
+    #[serde(rename = "Area of residence")]
+    home_zone: String,
+    #[serde(rename = "Area of workplace")]
+    work_zone: String,
+    #[serde(rename = "Underground, metro, light rail, tram")]
+    num_transit1: usize,
+    #[serde(rename = "Train")]
+    num_transit2: usize,
+    #[serde(rename = "Bus, minibus or coach")]
+    num_transit3: usize,
+    #[serde(rename = "Driving a car or van")]
+    num_drivers: usize,
+    #[serde(rename = "Bicycle")]
+    num_bikers: usize,
+    #[serde(rename = "On foot")]
+    num_pedestrians: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct EurostatFlow {
+    #[serde(rename = "geo_residence")]
+    home_zone: String,
+    #[serde(rename = "geo_work")]
+    work_zone: String,
+    #[serde(rename = "OBS_VALUE")]
+    value: String,
+}
+
+/// Areas may optionally describe their residents with properties like `pct_under_18`, from 0 to 1.
+/// Anything missing or malformed falls back to a default guess.
+fn parse_pct_demographics(props: &HashMap<String, String>) -> AreaDemographics {
+    let get = |key: &str| -> Option<f64> {
+        let value = props.get(key)?.parse::<f64>().ok()?;
+        if (0.0..=1.0).contains(&value) {
+            Some(value)
+        } else {
+            warn!("ignoring out-of-range {}: {}", key, value);
+            None
+        }
+    };
+
+    let default = AreaDemographics::default();
+    AreaDemographics {
+        pct_under_18: get("pct_under_18").unwrap_or(default.pct_under_18),
+        pct_over_65: get("pct_over_65").unwrap_or(default.pct_over_65),
+        pct_low_income: get("pct_low_income"),
+        pct_high_income: get("pct_high_income"),
+        pct_car_owners: get("pct_car_owners").unwrap_or(default.pct_car_owners),
+        pct_disabled: get("pct_disabled").unwrap_or(default.pct_disabled),
+    }
+}
+
+/// Output areas carry raw counts from a few NOMIS tables, keyed by their cell codes:
+///
+/// - KS101EW: usual residents
+/// - KS102EW: age bands
+/// - KS404EW: car or van availability per household
+/// - QS303EW: long-term health problem or disability
+fn parse_nomis(props: &HashMap<String, String>) -> Option<(usize, AreaDemographics)> {
+    let count = |key: &str| -> Option<f64> { props.get(key)?.parse::<f64>().ok() };
+    let sum = |keys: &[&str]| -> Option<f64> { keys.iter().map(|key| count(key)).sum() };
+    let ratio = |part: Option<f64>, total: Option<f64>| -> Option<f64> {
+        let (part, total) = (part?, total?);
+        if total > 0.0 {
+            Some((part / total).clamp(0.0, 1.0))
+        } else {
+            None
+        }
+    };
+
+    let population = count("KS101EW0001")?;
+    // 0-4, 5-7, 8-9, 10-14, 15, 16-17
+    let under_18 = sum(&[
+        "KS102EW0002",
+        "KS102EW0003",
+        "KS102EW0004",
+        "KS102EW0005",
+        "KS102EW0006",
+        "KS102EW0007",
+    ]);
+    // 65-74, 75-84, 85-89, 90+
+    let over_65 = sum(&["KS102EW0014", "KS102EW0015", "KS102EW0016", "KS102EW0017"]);
+    // Households with no cars or vans
+    let car_owners = ratio(count("KS404EW0002"), count("KS404EW0001")).map(|pct| 1.0 - pct);
+    // Day-to-day activities limited a lot or a little
+    let disabled = ratio(sum(&["QS303EW0002", "QS303EW0003"]), count("QS303EW0001"));
+
+    let default = AreaDemographics::default();
+    Some((
+        population as usize,
+        AreaDemographics {
+            pct_under_18: ratio(under_18, Some(population)).unwrap_or(default.pct_under_18),
+            pct_over_65: ratio(over_65, Some(population)).unwrap_or(default.pct_over_65),
+            pct_low_income: None,
+            pct_high_income: None,
+            pct_car_owners: car_owners.unwrap_or(default.pct_car_owners),
+            pct_disabled: disabled.unwrap_or(default.pct_disabled),
+        },
+    ))
+}
+
+/// NUTS 3 regions carry population by broad age group from `demo_r_pjanaggr3` and the number of
+/// passenger cars from `tran_r_vehst`.
+fn parse_eurostat(props: &HashMap<String, String>) -> Option<(usize, AreaDemographics)> {
+    let count = |key: &str| -> Option<f64> { props.get(key)?.parse::<f64>().ok() };
+
+    let population = count("TOTAL")?;
+    if population <= 0.0 {
+        return None;
+    }
+    let under_15 = count("Y_LT15");
+    let over_65 = count("Y_GE65");
+    let cars = count("CAR");
+
+    let default = AreaDemographics::default();
+    // Only 0-14 is reported, so stretch that to cover 15-17 too
+    let pct_under_18 = under_15
+        .map(|x| (x / population * 18.0 / 15.0).clamp(0.0, 1.0))
+        .unwrap_or(default.pct_under_18);
+    let pct_over_65 = over_65
+        .map(|x| (x / population).clamp(0.0, 1.0))
+        .unwrap_or(default.pct_over_65);
+    // Cars per adult is a rough stand-in for the share of people with access to one
+    let adults = population * (1.0 - pct_under_18);
+    let pct_car_owners = match cars {
+        Some(cars) if adults > 0.0 => (cars / adults).clamp(0.0, 1.0),
+        _ => default.pct_car_owners,
+    };
+
+    Some((
+        population as usize,
+        AreaDemographics {
+            pct_under_18,
+            pct_over_65,
+            pct_low_income: None,
+            pct_high_income: None,
+            pct_car_owners,
+            pct_disabled: default.pct_disabled,
+        },
+    ))
+}
@@ -13,10 +13,16 @@ use abstutil::{
     deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
     Tags,
 };
-use geom::{Distance, PolyLine, Polygon, Pt2D};
+use geom::{Distance, PolyLine, Polygon, Pt2D, Time};
 
+pub use self::relations::{RawRelation, RelationType};
 pub use self::types::{Amenity, AmenityType, AreaType};
 
+pub mod merges;
+pub mod quality;
+mod relations;
+pub mod repair;
+pub mod transform;
 mod types;
 
 #[derive(Serialize, Deserialize)]
@@ -38,16 +44,22 @@ pub struct RawMap {
         deserialize_with = "deserialize_btreemap"
     )]
     pub transit_stops: BTreeMap<String, RawTransitStop>,
-    /// Per road, what bus routes run along it?
+    /// Bus routes, cycle routes, and administrative boundaries that some roads belong to.
     ///
-    /// This is scraped from OSM relations for every map, unlike the more detailed `transit_routes`
-    /// above, which come from GTFS only for a few maps. This is used only to identify roads part
-    /// of bus routes. It's best-effort and not robust to edits or transformations.
+    /// Bus routes are scraped from OSM relations for every map, unlike the more detailed
+    /// `transit_routes` above, which come from GTFS only for a few maps.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub relations: BTreeMap<osm::RelationID, RawRelation>,
+    /// Which relations each OSM way belongs to. Use `relations_for_road` to look up a road, since
+    /// it may be made of many ways after merging.
     #[serde(
         serialize_with = "serialize_multimap",
         deserialize_with = "deserialize_multimap"
     )]
-    pub bus_routes_on_roads: MultiMap<osm::WayID, String>,
+    pub relation_members: MultiMap<osm::WayID, osm::RelationID>,
     #[serde(
         serialize_with = "serialize_btreemap",
         deserialize_with = "deserialize_btreemap"
@@ -79,7 +91,8 @@ impl RawMap {
             transit_routes: Vec::new(),
             census_zones: Vec::new(),
             transit_stops: BTreeMap::new(),
-            bus_routes_on_roads: MultiMap::new(),
+            relations: BTreeMap::new(),
+            relation_members: MultiMap::new(),
             osm_tags: BTreeMap::new(),
             extra_road_data: BTreeMap::new(),
             elevation_per_intersection: BTreeMap::new(),
@@ -108,6 +121,8 @@ pub struct RawBuilding {
     pub osm_tags: Tags,
     pub public_garage_name: Option<String>,
     pub num_parking_spots: usize,
+    /// How many bikes can be parked here, from nearby `amenity=bicycle_parking`
+    pub bike_parking: usize,
     pub amenities: Vec<Amenity>,
 }
 
@@ -136,7 +151,10 @@ pub struct RawTransitRoute {
     /// Entries into transit_stops
     pub stops: Vec<String>,
     pub route_type: RawTransitType,
-    // TODO Schedule
+    /// When a vehicle departs the first stop, in order, from the GTFS schedule for one typical
+    /// day. If empty, vehicles run at a fixed interval.
+    #[serde(default)]
+    pub spawn_times: Vec<Time>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -162,6 +180,32 @@ pub enum CrossingType {
     Unsignalized,
 }
 
+/// How a crossing meets the sidewalk, from OSM's `kerb` and `wheelchair` tags on crossing and
+/// `barrier=kerb` nodes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KerbType {
+    /// A dropped kerb or raised crossing, usable by wheelchairs
+    Lowered,
+    /// A full-height kerb with no ramp
+    Raised,
+}
+
+impl KerbType {
+    /// Interprets the tags of one node, if they say anything about the kerb.
+    pub fn from_tags(tags: &Tags) -> Option<KerbType> {
+        match tags.get("kerb").map(|x| x.as_str()) {
+            Some("lowered" | "flush" | "no") => return Some(KerbType::Lowered),
+            Some("raised" | "regular") => return Some(KerbType::Raised),
+            _ => {}
+        }
+        match tags.get("wheelchair").map(|x| x.as_str()) {
+            Some("yes" | "designated") => Some(KerbType::Lowered),
+            Some("no") => Some(KerbType::Raised),
+            _ => None,
+        }
+    }
+}
+
 /// Extra data associated with one Road
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExtraRoadData {
@@ -176,6 +220,12 @@ pub struct ExtraRoadData {
     pub barrier_nodes: Vec<Pt2D>,
     /// Crossing nodes along this road's original center line.
     pub crossing_nodes: Vec<(Pt2D, CrossingType)>,
+    /// Nodes along this road's original center line that describe a kerb, usually at a crossing.
+    pub kerb_nodes: Vec<(Pt2D, KerbType)>,
+    /// Observed on-street parking along the left and right side of the road, relative to the
+    /// direction of the reference line. This matches OSM's `parking:lane:left` and `right`.
+    pub parking_left: StreetParking,
+    pub parking_right: StreetParking,
 }
 
 impl ExtraRoadData {
@@ -187,10 +237,25 @@ impl ExtraRoadData {
             crosswalk_backward: true,
             barrier_nodes: Vec::new(),
             crossing_nodes: Vec::new(),
+            kerb_nodes: Vec::new(),
+            parking_left: StreetParking::default(),
+            parking_right: StreetParking::default(),
         }
     }
 }
 
+/// What's known about on-street parking along one side of a road, from OSM tags or an external
+/// parking survey. Whether there's a parking lane at all is determined by the lanes; this only
+/// refines how many cars fit and how many are usually there.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreetParking {
+    /// How many cars fit. If unknown, this is estimated from the length of the parking lane.
+    pub capacity: Option<usize>,
+    /// The fraction of spots that're usually occupied, from 0 to 1. If unknown, only cars
+    /// belonging to people in the scenario will be parked here.
+    pub occupancy: Option<f64>,
+}
+
 /// Extra point-of-interest
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExtraPOI {
@@ -0,0 +1,91 @@
+//! Short roads collapsed by hand in map_editor. A fresh import from OSM would lose these, so
+//! they're recorded separately and re-applied after every import.
+
+use anyhow::Result;
+use osm2streets::{osm, RoadID};
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::Timer;
+
+use crate::RawMap;
+
+/// Identifies the collapsed road by OSM IDs, since `RoadID`s change between imports.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ManualMerge {
+    pub way: osm::WayID,
+    pub src_node: osm::NodeID,
+    pub dst_node: osm::NodeID,
+}
+
+impl ManualMerge {
+    /// Describe a road that's about to be collapsed. Fails for roads introduced by the importer
+    /// without OSM IDs.
+    pub fn new(map: &RawMap, r: RoadID) -> Option<ManualMerge> {
+        let road = map.streets.roads.get(&r)?;
+        Some(ManualMerge {
+            way: *road.osm_ids.get(0)?,
+            src_node: *map.streets.intersections[&road.src_i].osm_ids.get(0)?,
+            dst_node: *map.streets.intersections[&road.dst_i].osm_ids.get(0)?,
+        })
+    }
+
+    /// Find the road this refers to in a possibly re-imported map.
+    fn find(&self, map: &RawMap) -> Option<RoadID> {
+        map.streets
+            .roads
+            .values()
+            .find(|road| {
+                road.osm_ids.contains(&self.way)
+                    && map.streets.intersections[&road.src_i]
+                        .osm_ids
+                        .contains(&self.src_node)
+                    && map.streets.intersections[&road.dst_i]
+                        .osm_ids
+                        .contains(&self.dst_node)
+            })
+            .map(|road| road.id)
+    }
+}
+
+/// Where the manual merges for a map are stored. They live in the importer config, next to the
+/// boundary polygon, so they're checked in and survive regenerating all input data.
+pub fn path(name: &MapName) -> String {
+    format!(
+        "importer/config/{}/{}/{}_merges.json",
+        name.city.country, name.city.city, name.map
+    )
+}
+
+pub fn load(name: &MapName) -> Vec<ManualMerge> {
+    abstio::maybe_read_json(path(name), &mut Timer::throwaway()).unwrap_or_else(|_| Vec::new())
+}
+
+/// Remember one more merge for this map.
+pub fn record(name: &MapName, merge: ManualMerge) {
+    let mut merges = load(name);
+    if !merges.contains(&merge) {
+        merges.push(merge);
+        abstio::write_json(path(name), &merges);
+    }
+}
+
+/// Re-apply all recorded merges, in the order they were made. Returns a description of each merge
+/// that couldn't be applied, because the road changed upstream or can't be collapsed anymore.
+pub fn apply(map: &mut RawMap) -> Vec<String> {
+    let mut problems = Vec::new();
+    for merge in load(&map.name) {
+        if let Err(err) = apply_one(map, &merge) {
+            problems.push(format!("{:?}: {}", merge, err));
+        }
+    }
+    problems
+}
+
+fn apply_one(map: &mut RawMap, merge: &ManualMerge) -> Result<()> {
+    let r = merge
+        .find(map)
+        .ok_or_else(|| anyhow::anyhow!("road not found"))?;
+    map.streets.collapse_short_road(r)?;
+    Ok(())
+}
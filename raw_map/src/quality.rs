@@ -0,0 +1,492 @@
+//! Find suspicious data in a `RawMap` after importing it.
+//!
+//! Nothing here stops a map from being built, but each problem quietly makes the simulation less
+//! realistic: a main road guessing its speed limit, a street nobody can walk along, a piece of the
+//! road network that no trip can reach, or a building too far from any sidewalk to connect to.
+//! Most of these are best fixed upstream in OSM, so the report points at the OSM objects involved.
+//! When a fix can't make anything worse, it's offered too.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use abstutil::Timer;
+use geom::{Distance, FindClosest, Polygon};
+use osm2streets::{osm, Direction, IntersectionKind, LaneType, Road, RoadID};
+
+use crate::RawMap;
+
+/// Buildings farther than this from a sidewalk get an unrealistically long path to it
+const MAX_BUILDING_TO_SIDEWALK: Distance = Distance::const_meters(100.0);
+/// Buildings farther than this from a sidewalk are dropped when the map is built anyway
+const DROPPED_BUILDING_TO_SIDEWALK: Distance = Distance::const_meters(1000.0);
+/// More driving lanes than this in one direction is almost certainly a tagging mistake
+const MAX_DRIVING_LANES_PER_DIRECTION: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueKind {
+    /// A main road with no `maxspeed`. Defaults are fine for local streets, so they're not checked.
+    NoSpeedLimit,
+    /// A street without any place to walk, where OSM doesn't say whether there are sidewalks
+    MissingSidewalks,
+    /// Lane tags that contradict each other or can't be right
+    ImpossibleLanes,
+    /// Roads that can't be driven to from the rest of the map, ignoring one-ways
+    DisconnectedIsland,
+    /// A building too far from any sidewalk
+    UnconnectedBuilding,
+}
+
+impl IssueKind {
+    pub fn all() -> Vec<IssueKind> {
+        vec![
+            IssueKind::NoSpeedLimit,
+            IssueKind::MissingSidewalks,
+            IssueKind::ImpossibleLanes,
+            IssueKind::DisconnectedIsland,
+            IssueKind::UnconnectedBuilding,
+        ]
+    }
+
+    /// Describes many issues of this kind
+    pub fn plural(self) -> &'static str {
+        match self {
+            IssueKind::NoSpeedLimit => "main roads without speed limits",
+            IssueKind::MissingSidewalks => "streets missing sidewalks",
+            IssueKind::ImpossibleLanes => "roads with impossible lanes",
+            IssueKind::DisconnectedIsland => "disconnected islands of roads",
+            IssueKind::UnconnectedBuilding => "buildings far from sidewalks",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QualityIssue {
+    pub kind: IssueKind,
+    /// Names the object and explains what's suspicious about it
+    pub details: String,
+    /// The affected area, to zoom to
+    pub polygon: Polygon,
+    /// The OSM ways involved, to fix upstream. Empty for buildings.
+    pub osm_ways: Vec<osm::WayID>,
+    /// A fix that can't make anything worse, if there is one
+    pub fix: Option<AutoFix>,
+}
+
+impl fmt::Display for QualityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.details)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AutoFix {
+    /// Tag every OSM way of a road with the `maxspeed` the rest of the street has
+    SetMaxSpeed(Vec<osm::WayID>, String),
+    /// Nothing outside can reach these roads at all, even on foot
+    RemoveRoads(Vec<RoadID>),
+    /// The map would drop this building anyway
+    RemoveBuilding(osm::OsmID),
+}
+
+impl fmt::Display for AutoFix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AutoFix::SetMaxSpeed(_, speed) => write!(f, "set maxspeed={}", speed),
+            AutoFix::RemoveRoads(roads) => write!(f, "remove {} roads", roads.len()),
+            AutoFix::RemoveBuilding(_) => write!(f, "remove the building"),
+        }
+    }
+}
+
+impl AutoFix {
+    pub fn apply(&self, map: &mut RawMap) {
+        match self {
+            AutoFix::SetMaxSpeed(ways, speed) => {
+                for way in ways {
+                    if let Some(tags) = map.osm_tags.get_mut(way) {
+                        tags.insert("maxspeed", speed.clone());
+                    }
+                }
+            }
+            AutoFix::RemoveRoads(roads) => {
+                let mut touched_intersections = Vec::new();
+                for r in roads {
+                    if !map.streets.roads.contains_key(r) {
+                        continue;
+                    }
+                    let road = map.streets.remove_road(*r);
+                    map.extra_road_data.remove(r);
+                    touched_intersections.push(road.src_i);
+                    touched_intersections.push(road.dst_i);
+                }
+                touched_intersections.sort();
+                touched_intersections.dedup();
+                for i in touched_intersections {
+                    let empty = match map.streets.intersections.get(&i) {
+                        Some(i) => i.roads.is_empty(),
+                        None => continue,
+                    };
+                    if empty {
+                        map.streets.remove_intersection(i);
+                        map.elevation_per_intersection.remove(&i);
+                    } else {
+                        map.streets.update_i(i);
+                    }
+                }
+            }
+            AutoFix::RemoveBuilding(id) => {
+                map.buildings.remove(id);
+            }
+        }
+    }
+}
+
+/// Returns every suspicious thing found, grouped by kind.
+pub fn find_quality_issues(map: &RawMap, timer: &mut Timer) -> Vec<QualityIssue> {
+    let mut issues = Vec::new();
+
+    timer.start_iter("check road tags", map.streets.roads.len());
+    for road in map.streets.roads.values() {
+        timer.next();
+        check_speed_limit(map, road, &mut issues);
+        check_sidewalks(map, road, &mut issues);
+        check_lanes(map, road, &mut issues);
+    }
+
+    timer.start("find disconnected islands");
+    check_islands(map, &mut issues);
+    timer.stop("find disconnected islands");
+
+    check_buildings(map, &mut issues, timer);
+
+    issues.sort_by_key(|issue| issue.kind);
+    issues
+}
+
+fn check_speed_limit(map: &RawMap, road: &Road, issues: &mut Vec<QualityIssue>) {
+    let tags = match map.road_to_osm_tags(road.id) {
+        Some(tags) => tags,
+        None => {
+            return;
+        }
+    };
+    if !road.is_driveable()
+        || tags.contains_key("maxspeed")
+        || !tags.is_any(
+            osm::HIGHWAY,
+            vec![
+                "motorway",
+                "motorway_link",
+                "trunk",
+                "trunk_link",
+                "primary",
+                "primary_link",
+                "secondary",
+                "secondary_link",
+                "tertiary",
+                "tertiary_link",
+            ],
+        )
+    {
+        return;
+    }
+
+    // If every connected piece of the same street agrees on a speed limit, it's safe to copy
+    let mut speeds = BTreeSet::new();
+    if let Some(name) = tags.get("name") {
+        for i in [road.src_i, road.dst_i] {
+            for other in &map.streets.intersections[&i].roads {
+                if *other == road.id {
+                    continue;
+                }
+                if let Some(other_tags) = map.road_to_osm_tags(*other) {
+                    if other_tags.is("name", name) {
+                        if let Some(speed) = other_tags.get("maxspeed") {
+                            speeds.insert(speed.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let fix = if speeds.len() == 1 {
+        Some(AutoFix::SetMaxSpeed(
+            road.osm_ids.clone(),
+            speeds.into_iter().next().unwrap(),
+        ))
+    } else {
+        None
+    };
+
+    issues.push(QualityIssue {
+        kind: IssueKind::NoSpeedLimit,
+        details: format!(
+            "{} is {}, but has no maxspeed",
+            describe_road(map, road.id),
+            tags.get(osm::HIGHWAY).unwrap()
+        ),
+        polygon: road_polygon(road),
+        osm_ways: road.osm_ids.clone(),
+        fix,
+    });
+}
+
+fn check_sidewalks(map: &RawMap, road: &Road, issues: &mut Vec<QualityIssue>) {
+    let tags = match map.road_to_osm_tags(road.id) {
+        Some(tags) => tags,
+        None => {
+            return;
+        }
+    };
+    if !road.is_driveable()
+        || road.is_service()
+        || tags.is_any(
+            osm::HIGHWAY,
+            vec!["motorway", "motorway_link", "trunk", "trunk_link"],
+        )
+        || tags.is("foot", "no")
+        || road
+            .lane_specs_ltr
+            .iter()
+            .any(|spec| spec.lt.is_walkable() || spec.lt == LaneType::Shoulder)
+    {
+        return;
+    }
+    // If OSM explicitly says there aren't sidewalks, believe it
+    if [
+        "sidewalk",
+        "sidewalk:both",
+        "sidewalk:left",
+        "sidewalk:right",
+    ]
+    .iter()
+    .any(|key| tags.contains_key(key))
+    {
+        return;
+    }
+
+    issues.push(QualityIssue {
+        kind: IssueKind::MissingSidewalks,
+        details: format!(
+            "Nobody can walk along {}, and it has no sidewalk tag",
+            describe_road(map, road.id)
+        ),
+        polygon: road_polygon(road),
+        osm_ways: road.osm_ids.clone(),
+        fix: None,
+    });
+}
+
+fn check_lanes(map: &RawMap, road: &Road, issues: &mut Vec<QualityIssue>) {
+    let mut problems = Vec::new();
+
+    if road.lane_specs_ltr.is_empty() {
+        problems.push("no lanes at all".to_string());
+    }
+    for dir in [Direction::Fwd, Direction::Back] {
+        let driving = road
+            .lane_specs_ltr
+            .iter()
+            .filter(|spec| spec.dir == dir && spec.lt == LaneType::Driving)
+            .count();
+        if driving > MAX_DRIVING_LANES_PER_DIRECTION {
+            problems.push(format!("{} driving lanes in one direction", driving));
+        }
+    }
+
+    if let Some(tags) = map.road_to_osm_tags(road.id) {
+        let parse = |key: &str| -> Option<Result<usize, String>> {
+            let value = tags.get(key)?;
+            Some(match value.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(format!("{}={} isn't a positive number", key, value)),
+            })
+        };
+        let mut parsed = Vec::new();
+        for key in ["lanes", "lanes:forward", "lanes:backward"] {
+            match parse(key) {
+                Some(Ok(n)) => {
+                    parsed.push(Some(n));
+                }
+                Some(Err(problem)) => {
+                    problems.push(problem);
+                    parsed.push(None);
+                }
+                None => {
+                    parsed.push(None);
+                }
+            }
+        }
+        if let (Some(total), Some(fwd), Some(back)) = (parsed[0], parsed[1], parsed[2]) {
+            if fwd + back > total {
+                problems.push(format!(
+                    "lanes:forward={} and lanes:backward={} add up to more than lanes={}",
+                    fwd, back, total
+                ));
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        issues.push(QualityIssue {
+            kind: IssueKind::ImpossibleLanes,
+            details: format!("{}: {}", describe_road(map, road.id), problems.join(", ")),
+            polygon: road_polygon(road),
+            osm_ways: road.osm_ids.clone(),
+            fix: None,
+        });
+    }
+}
+
+fn check_islands(map: &RawMap, issues: &mut Vec<QualityIssue>) {
+    let mut driving = connected_components(map, &|road| road.is_driveable());
+    // The largest piece is the real road network
+    driving.sort_by_key(|roads| roads.len());
+    driving.pop();
+
+    let reachable = connected_components(map, &|road| {
+        road.lane_specs_ltr
+            .iter()
+            .any(|spec| spec.lt != LaneType::LightRail)
+    });
+    let largest_reachable = reachable.iter().max_by_key(|roads| roads.len());
+
+    for roads in driving {
+        // Roads leaving the map probably connect to the rest of the network outside of it
+        if roads.iter().any(|r| {
+            let road = &map.streets.roads[r];
+            [road.src_i, road.dst_i]
+                .into_iter()
+                .any(|i| map.streets.intersections[&i].kind == IntersectionKind::MapEdge)
+        }) {
+            continue;
+        }
+
+        // If people can still walk there from the main network, the roads are useful. Otherwise,
+        // remove everything that can only be reached from the island.
+        let fix = reachable
+            .iter()
+            .find(|component| component.contains(&roads[0]))
+            .filter(|component| Some(*component) != largest_reachable)
+            .map(|component| AutoFix::RemoveRoads(component.clone()));
+
+        let polygons: Vec<Polygon> = roads
+            .iter()
+            .map(|r| road_polygon(&map.streets.roads[r]))
+            .collect();
+        let polygon =
+            Polygon::convex_hull(polygons.clone()).unwrap_or_else(|_| polygons[0].clone());
+        let mut osm_ways: Vec<osm::WayID> = roads
+            .iter()
+            .flat_map(|r| map.streets.roads[r].osm_ids.clone())
+            .collect();
+        osm_ways.sort();
+        osm_ways.dedup();
+
+        issues.push(QualityIssue {
+            kind: IssueKind::DisconnectedIsland,
+            details: format!(
+                "{} roads, including {}, can't be driven to from the rest of the map",
+                roads.len(),
+                describe_road(map, roads[0])
+            ),
+            polygon,
+            osm_ways,
+            fix,
+        });
+    }
+}
+
+/// Groups roads that connect to each other, ignoring direction. Every road matching `include` is
+/// in exactly one group.
+fn connected_components(map: &RawMap, include: &dyn Fn(&Road) -> bool) -> Vec<Vec<RoadID>> {
+    let mut seen = BTreeSet::new();
+    let mut components = Vec::new();
+    for start in map.streets.roads.values() {
+        if seen.contains(&start.id) || !include(start) {
+            continue;
+        }
+        seen.insert(start.id);
+        let mut component = Vec::new();
+        let mut queue = vec![start.id];
+        while let Some(r) = queue.pop() {
+            component.push(r);
+            let road = &map.streets.roads[&r];
+            for i in [road.src_i, road.dst_i] {
+                for next in &map.streets.intersections[&i].roads {
+                    if !seen.contains(next) && include(&map.streets.roads[next]) {
+                        seen.insert(*next);
+                        queue.push(*next);
+                    }
+                }
+            }
+        }
+        component.sort();
+        components.push(component);
+    }
+    components
+}
+
+fn check_buildings(map: &RawMap, issues: &mut Vec<QualityIssue>, timer: &mut Timer) {
+    let mut closest = FindClosest::new();
+    for road in map.streets.roads.values() {
+        if road.lane_specs_ltr.iter().any(|spec| spec.lt.is_walkable()) {
+            closest.add(road.id, road.reference_line.points());
+        }
+    }
+
+    timer.start_iter("find buildings far from sidewalks", map.buildings.len());
+    for (id, b) in &map.buildings {
+        timer.next();
+        let center = b.polygon.center();
+        if closest
+            .closest_pt(center, MAX_BUILDING_TO_SIDEWALK)
+            .is_some()
+        {
+            continue;
+        }
+        let (details, fix) = if closest
+            .closest_pt(center, DROPPED_BUILDING_TO_SIDEWALK)
+            .is_some()
+        {
+            (
+                format!(
+                    "{} is more than {} from any sidewalk",
+                    id, MAX_BUILDING_TO_SIDEWALK
+                ),
+                None,
+            )
+        } else {
+            (
+                format!(
+                    "{} is more than {} from any sidewalk, so it'll be dropped",
+                    id, DROPPED_BUILDING_TO_SIDEWALK
+                ),
+                Some(AutoFix::RemoveBuilding(*id)),
+            )
+        };
+        issues.push(QualityIssue {
+            kind: IssueKind::UnconnectedBuilding,
+            details,
+            polygon: b.polygon.clone(),
+            osm_ways: Vec::new(),
+            fix,
+        });
+    }
+}
+
+fn road_polygon(road: &Road) -> Polygon {
+    road.reference_line.make_polygons(road.total_width())
+}
+
+fn describe_road(map: &RawMap, r: RoadID) -> String {
+    let road = &map.streets.roads[&r];
+    let name = map
+        .road_to_osm_tags(r)
+        .and_then(|tags| tags.get("name").cloned())
+        .unwrap_or_else(|| "unnamed road".to_string());
+    match road.osm_ids.get(0) {
+        Some(way) => format!("{} ({})", name, way),
+        None => name,
+    }
+}
@@ -0,0 +1,100 @@
+//! Roads belong to OSM relations like bus routes, cycle networks, and administrative boundaries.
+//! Membership is recorded per OSM way, not per road, because roads get split, merged, and replaced
+//! many times during import. Anything merging roads must keep the ways of every road it consumes
+//! in `osm_ids`, so membership can still be found for the final roads.
+
+use std::collections::BTreeSet;
+
+use osm2streets::{osm, RoadID};
+use serde::{Deserialize, Serialize};
+
+use abstutil::Tags;
+
+use crate::RawMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum RelationType {
+    BusRoute,
+    /// A signed cycle route, part of some network
+    CycleRoute,
+    /// An administrative boundary, like a city or district
+    Boundary,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RawRelation {
+    pub osm_id: osm::RelationID,
+    pub rel_type: RelationType,
+    pub name: Option<String>,
+    /// For cycle routes, the level of the network: `lcn`, `rcn`, `ncn`, or `icn`. For boundaries,
+    /// the `admin_level`.
+    pub network: Option<String>,
+}
+
+impl RawRelation {
+    /// Only route and boundary relations are kept; everything else returns None.
+    pub fn from_tags(osm_id: osm::RelationID, tags: &Tags) -> Option<RawRelation> {
+        let (rel_type, network) =
+            if tags.is("type", "route") && tags.is_any("route", vec!["bus", "trolleybus"]) {
+                (RelationType::BusRoute, None)
+            } else if tags.is("type", "route") && tags.is("route", "bicycle") {
+                (RelationType::CycleRoute, tags.get("network").cloned())
+            } else if tags.is("type", "boundary") && tags.is("boundary", "administrative") {
+                (RelationType::Boundary, tags.get("admin_level").cloned())
+            } else {
+                return None;
+            };
+        Some(RawRelation {
+            osm_id,
+            rel_type,
+            name: tags.get("name").cloned(),
+            network,
+        })
+    }
+
+    /// Does a member way with this role belong to the relation? Routes also list their stops and
+    /// platforms as members.
+    pub fn includes_role(&self, role: &str) -> bool {
+        match self.rel_type {
+            RelationType::BusRoute | RelationType::CycleRoute => {
+                matches!(role, "" | "forward" | "backward")
+            }
+            RelationType::Boundary => matches!(role, "" | "outer" | "inner"),
+        }
+    }
+}
+
+impl RawMap {
+    /// All relations that any OSM way of this road belongs to
+    pub fn relations_for_road(&self, r: RoadID) -> BTreeSet<osm::RelationID> {
+        let mut result = BTreeSet::new();
+        for way in &self.streets.roads[&r].osm_ids {
+            result.extend(self.relation_members.get(*way).iter().cloned());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_tags() {
+        let mut tags = Tags::empty();
+        tags.insert("type", "route");
+        tags.insert("route", "bicycle");
+        tags.insert("network", "lcn");
+        tags.insert("name", "Quietway 1");
+        let rel = RawRelation::from_tags(osm::RelationID(1), &tags).unwrap();
+        assert_eq!(rel.rel_type, RelationType::CycleRoute);
+        assert_eq!(rel.network, Some("lcn".to_string()));
+        assert!(rel.includes_role("forward"));
+        assert!(!rel.includes_role("platform"));
+
+        let mut tags = Tags::empty();
+        tags.insert("type", "route");
+        tags.insert("route", "hiking");
+        assert!(RawRelation::from_tags(osm::RelationID(2), &tags).is_none());
+    }
+}
@@ -0,0 +1,253 @@
+//! Repair broken road geometry in a `RawMap` before building a map from it.
+//!
+//! OSM ways sometimes have repeated nodes, nodes stacked on top of each other, or loop back and
+//! cross themselves. Clipping and splitting ways can also leave behind roads with almost no length.
+//! `PolyLine` can't represent any of this, so without this pass, the problem only shows up as a
+//! panic somewhere deep in map building, far from the road that caused it.
+
+use std::fmt;
+
+use abstutil::Timer;
+use geom::{Distance, Line, PolyLine, Pt2D, EPSILON_DIST};
+use osm2streets::RoadID;
+
+use crate::RawMap;
+
+/// Something wrong with one road's center-line and how it was fixed
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeometryProblem {
+    /// Consecutive points at the same spot, forming zero-length segments, were removed
+    DuplicatePoints(usize),
+    /// The line crossed or touched itself, and this many loops were cut out
+    SelfIntersections(usize),
+    /// Nothing usable was left, so the road was removed
+    Degenerate,
+}
+
+#[derive(Clone, Debug)]
+pub struct GeometryFix {
+    pub road: RoadID,
+    pub problem: GeometryProblem,
+}
+
+impl fmt::Display for GeometryFix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.problem {
+            GeometryProblem::DuplicatePoints(n) => {
+                write!(f, "{}: removed {} duplicate points", self.road, n)
+            }
+            GeometryProblem::SelfIntersections(n) => {
+                write!(f, "{}: cut out {} self-intersecting loops", self.road, n)
+            }
+            GeometryProblem::Degenerate => {
+                write!(
+                    f,
+                    "{}: no usable geometry left, removed the road",
+                    self.road
+                )
+            }
+        }
+    }
+}
+
+/// Fix every road's center-line, removing roads that can't be fixed. Intersections left with no
+/// roads are removed too. Returns every fix made, so the caller can report them.
+pub fn repair_geometry(map: &mut RawMap, timer: &mut Timer) -> Vec<GeometryFix> {
+    let driving_side = map.streets.config.driving_side;
+    let mut fixes = Vec::new();
+    let mut touched_intersections = Vec::new();
+    let mut remove = Vec::new();
+
+    timer.start_iter("repair road geometry", map.streets.roads.len());
+    for road in map.streets.roads.values_mut() {
+        timer.next();
+        let (pts, problems) = repair_pts(road.reference_line.points());
+        if problems.is_empty() {
+            continue;
+        }
+        let degenerate = problems.contains(&GeometryProblem::Degenerate);
+        for problem in problems {
+            fixes.push(GeometryFix {
+                road: road.id,
+                problem,
+            });
+        }
+        if degenerate {
+            remove.push(road.id);
+            continue;
+        }
+        road.reference_line = PolyLine::must_new(pts);
+        road.update_center_line(driving_side);
+        touched_intersections.push(road.src_i);
+        touched_intersections.push(road.dst_i);
+    }
+
+    for r in remove {
+        let road = map.streets.remove_road(r);
+        map.extra_road_data.remove(&r);
+        for i in [road.src_i, road.dst_i] {
+            // Both ends might be the same intersection
+            if map
+                .streets
+                .intersections
+                .get(&i)
+                .map(|i| i.roads.is_empty())
+                .unwrap_or(false)
+            {
+                map.streets.remove_intersection(i);
+                map.elevation_per_intersection.remove(&i);
+            } else {
+                touched_intersections.push(i);
+            }
+        }
+    }
+
+    touched_intersections.sort();
+    touched_intersections.dedup();
+    for i in touched_intersections {
+        if map.streets.intersections.contains_key(&i) {
+            map.streets.update_i(i);
+        }
+    }
+
+    fixes
+}
+
+/// Returns the repaired points and what was wrong with the originals. The first and last points
+/// never move. If `GeometryProblem::Degenerate` is returned, there's no valid line left and the
+/// points should be ignored.
+pub fn repair_pts(pts: &[Pt2D]) -> (Vec<Pt2D>, Vec<GeometryProblem>) {
+    let mut problems = Vec::new();
+
+    let mut deduped: Vec<Pt2D> = Vec::with_capacity(pts.len());
+    for pt in pts {
+        // Points closer than this make zero-length segments, even if they're not exactly equal
+        if deduped
+            .last()
+            .map(|last| last.dist_to(*pt) <= EPSILON_DIST)
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        deduped.push(*pt);
+    }
+    // Always keep the original last point, so the road still reaches its intersection
+    if let (Some(last), Some(orig_last)) = (deduped.last_mut(), pts.last()) {
+        *last = *orig_last;
+    }
+    if deduped.len() < pts.len() {
+        problems.push(GeometryProblem::DuplicatePoints(pts.len() - deduped.len()));
+    }
+
+    let mut pts = deduped;
+    let mut loops = 0;
+    while let Some(fixed) = cut_first_loop(&pts) {
+        pts = fixed;
+        loops += 1;
+    }
+    if loops > 0 {
+        problems.push(GeometryProblem::SelfIntersections(loops));
+    }
+
+    let length = pts
+        .windows(2)
+        .fold(Distance::ZERO, |sum, pair| sum + pair[0].dist_to(pair[1]));
+    if pts.len() < 2 || length <= EPSILON_DIST || PolyLine::new(pts.clone()).is_err() {
+        problems.push(GeometryProblem::Degenerate);
+    }
+
+    (pts, problems)
+}
+
+// If the line revisits a point or crosses itself, remove everything between the two visits.
+fn cut_first_loop(pts: &[Pt2D]) -> Option<Vec<Pt2D>> {
+    // Repeated points, not necessarily from crossing segments
+    for i in 0..pts.len() {
+        for j in i + 2..pts.len() {
+            if pts[i] == pts[j] {
+                // If the endpoints coincide, there's nothing to cut; the caller will give up
+                if i == 0 && j == pts.len() - 1 {
+                    return None;
+                }
+                // Keep whichever copy is an endpoint, so the ends don't move
+                let (keep_until, resume_at) = if i == 0 { (i + 1, j + 1) } else { (i, j) };
+                let mut fixed = pts[..keep_until].to_vec();
+                fixed.extend_from_slice(&pts[resume_at..]);
+                return Some(fixed);
+            }
+        }
+    }
+
+    // Non-adjacent segments crossing
+    let lines: Vec<Option<Line>> = pts
+        .windows(2)
+        .map(|pair| Line::new(pair[0], pair[1]).ok())
+        .collect();
+    for i in 0..lines.len() {
+        for j in i + 2..lines.len() {
+            if let (Some(l1), Some(l2)) = (&lines[i], &lines[j]) {
+                if let Some(hit) = l1.intersection(l2) {
+                    let mut fixed = pts[..=i].to_vec();
+                    fixed.push(hit);
+                    fixed.extend_from_slice(&pts[j + 1..]);
+                    fixed.dedup();
+                    return Some(fixed);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_pts() {
+        // Stacked points are removed
+        let pts = vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(50.0, 0.0),
+            Pt2D::new(50.0, 0.001),
+            Pt2D::new(100.0, 0.0),
+        ];
+        assert_eq!(
+            repair_pts(&pts),
+            (
+                vec![
+                    Pt2D::new(0.0, 0.0),
+                    Pt2D::new(50.0, 0.0),
+                    Pt2D::new(100.0, 0.0)
+                ],
+                vec![GeometryProblem::DuplicatePoints(2)]
+            )
+        );
+
+        // A loop crossing itself is cut at the crossing
+        let pts = vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(100.0, 0.0),
+            Pt2D::new(100.0, 50.0),
+            Pt2D::new(50.0, 50.0),
+            Pt2D::new(50.0, -50.0),
+        ];
+        assert_eq!(
+            repair_pts(&pts),
+            (
+                vec![
+                    Pt2D::new(0.0, 0.0),
+                    Pt2D::new(50.0, 0.0),
+                    Pt2D::new(50.0, -50.0)
+                ],
+                vec![GeometryProblem::SelfIntersections(1)]
+            )
+        );
+
+        // Nothing left of a road with no length
+        let pts = vec![Pt2D::new(10.0, 10.0), Pt2D::new(10.0, 10.0)];
+        assert!(repair_pts(&pts).1.contains(&GeometryProblem::Degenerate));
+    }
+}
@@ -0,0 +1,188 @@
+//! Cleanup passes over the geometry of a `RawMap`, run during import.
+//!
+//! Road center-lines come straight from OSM, where ways are often digitized with far more points
+//! than the real curve needs, or with small kinks where a way was snapped to a node at a junction.
+//! These wiggles get amplified once lanes are projected left and right of the center-line and
+//! then trimmed back from intersections.
+
+use abstutil::Timer;
+use geom::{Distance, PolyLine, Pt2D};
+
+use crate::RawMap;
+
+/// A point where the road turns more sharply than this is a real corner, so simplification must
+/// keep it -- as long as the segments on both sides are longer than the tolerance.
+const CORNER_DEGS: f64 = 45.0;
+/// A point where the road nearly doubles back on itself is noise.
+const ZIGZAG_DEGS: f64 = 120.0;
+/// Only look for zig-zags this close to either end of a road, where ways get snapped to junction
+/// nodes.
+const ZIGZAG_ZONE: Distance = Distance::const_meters(15.0);
+
+/// Simplify the center-line of every road, keeping each endpoint in place. Points that stray less
+/// than `tolerance` from the simplified line are dropped. Returns the number of points removed.
+pub fn smooth_centerlines(map: &mut RawMap, tolerance: Distance, timer: &mut Timer) -> usize {
+    let driving_side = map.streets.config.driving_side;
+    let mut removed = 0;
+    let mut touched_intersections = Vec::new();
+
+    timer.start_iter("smooth road center-lines", map.streets.roads.len());
+    for road in map.streets.roads.values_mut() {
+        timer.next();
+        let before = road.reference_line.points().len();
+        let pts = smooth_pts(road.reference_line.points(), tolerance);
+        if pts.len() == before {
+            continue;
+        }
+        if let Ok(pl) = PolyLine::deduping_new(pts) {
+            removed += before - pl.points().len();
+            road.reference_line = pl;
+            road.update_center_line(driving_side);
+            touched_intersections.push(road.src_i);
+            touched_intersections.push(road.dst_i);
+        }
+    }
+
+    touched_intersections.sort();
+    touched_intersections.dedup();
+    for i in touched_intersections {
+        map.streets.update_i(i);
+    }
+
+    removed
+}
+
+/// Remove zig-zags near the ends of a line, then simplify it with Douglas-Peucker, keeping sharp
+/// corners. The first and last points never change.
+pub fn smooth_pts(pts: &[Pt2D], tolerance: Distance) -> Vec<Pt2D> {
+    let pts = remove_zigzags(pts.to_vec());
+    if pts.len() <= 2 {
+        return pts;
+    }
+
+    let mut keep = vec![false; pts.len()];
+    keep[0] = true;
+    keep[pts.len() - 1] = true;
+    // Split at real corners, so simplification never rounds them off
+    for idx in 1..pts.len() - 1 {
+        if turn_degs(pts[idx - 1], pts[idx], pts[idx + 1]) > CORNER_DEGS
+            && pts[idx - 1].dist_to(pts[idx]) > tolerance
+            && pts[idx].dist_to(pts[idx + 1]) > tolerance
+        {
+            keep[idx] = true;
+        }
+    }
+
+    let anchors: Vec<usize> = (0..pts.len()).filter(|idx| keep[*idx]).collect();
+    for pair in anchors.windows(2) {
+        douglas_peucker(&pts, pair[0], pair[1], tolerance, &mut keep);
+    }
+
+    pts.into_iter()
+        .zip(keep)
+        .filter_map(|(pt, keep)| if keep { Some(pt) } else { None })
+        .collect()
+}
+
+fn remove_zigzags(mut pts: Vec<Pt2D>) -> Vec<Pt2D> {
+    loop {
+        let first = pts[0];
+        let last = *pts.last().unwrap();
+        let found = (1..pts.len().saturating_sub(1)).find(|idx| {
+            let pt = pts[*idx];
+            (pt.dist_to(first) < ZIGZAG_ZONE || pt.dist_to(last) < ZIGZAG_ZONE)
+                && turn_degs(pts[idx - 1], pt, pts[idx + 1]) > ZIGZAG_DEGS
+        });
+        match found {
+            Some(idx) => {
+                pts.remove(idx);
+            }
+            None => {
+                return pts;
+            }
+        }
+    }
+}
+
+// Marks the points between start and end (exclusive) that must stay
+fn douglas_peucker(pts: &[Pt2D], start: usize, end: usize, tolerance: Distance, keep: &mut [bool]) {
+    if end <= start + 1 {
+        return;
+    }
+    let mut furthest = start + 1;
+    let mut max_dist = Distance::ZERO;
+    for idx in start + 1..end {
+        let dist = dist_to_segment(pts[idx], pts[start], pts[end]);
+        if dist > max_dist {
+            max_dist = dist;
+            furthest = idx;
+        }
+    }
+    if max_dist > tolerance {
+        keep[furthest] = true;
+        douglas_peucker(pts, start, furthest, tolerance, keep);
+        douglas_peucker(pts, furthest, end, tolerance, keep);
+    }
+}
+
+/// How much does the direction change at `pt`? 0 is straight ahead, 180 is a U-turn.
+fn turn_degs(prev: Pt2D, pt: Pt2D, next: Pt2D) -> f64 {
+    if prev.dist_to(pt) == Distance::ZERO || pt.dist_to(next) == Distance::ZERO {
+        return 0.0;
+    }
+    prev.angle_to(pt)
+        .simple_shortest_rotation_towards(pt.angle_to(next))
+        .abs()
+}
+
+fn dist_to_segment(pt: Pt2D, a: Pt2D, b: Pt2D) -> Distance {
+    let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+    let len_squared = dx * dx + dy * dy;
+    if len_squared == 0.0 {
+        return pt.dist_to(a);
+    }
+    let t = (((pt.x() - a.x()) * dx + (pt.y() - a.y()) * dy) / len_squared).clamp(0.0, 1.0);
+    pt.dist_to(Pt2D::new(a.x() + t * dx, a.y() + t * dy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smooth_pts() {
+        let tolerance = Distance::meters(1.0);
+
+        // Noise along a straight line disappears
+        let pts = vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(50.0, 0.3),
+            Pt2D::new(100.0, -0.2),
+            Pt2D::new(150.0, 0.0),
+        ];
+        assert_eq!(
+            smooth_pts(&pts, tolerance),
+            vec![Pt2D::new(0.0, 0.0), Pt2D::new(150.0, 0.0)]
+        );
+
+        // A real corner survives
+        let pts = vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(100.0, 0.0),
+            Pt2D::new(100.0, 100.0),
+        ];
+        assert_eq!(smooth_pts(&pts, tolerance), pts);
+
+        // A spike doubling back right by the end is removed
+        let pts = vec![
+            Pt2D::new(0.0, 0.0),
+            Pt2D::new(5.0, 0.0),
+            Pt2D::new(3.0, 0.5),
+            Pt2D::new(100.0, 0.0),
+        ];
+        assert_eq!(
+            smooth_pts(&pts, tolerance),
+            vec![Pt2D::new(0.0, 0.0), Pt2D::new(100.0, 0.0)]
+        );
+    }
+}
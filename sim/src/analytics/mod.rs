@@ -4,14 +4,28 @@ use std::fmt::Write;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Counter;
-use geom::{Duration, Pt2D, Time};
+use geom::{Distance, Duration, Pt2D, Speed, Time};
 use map_model::{
-    CompressedMovementID, IntersectionID, LaneID, Map, MovementID, ParkingLotID, Path, PathRequest,
-    RoadID, TransitRouteID, TransitStopID, Traversable, TurnID,
+    BikeConflictType, BuildingID, CompressedMovementID, IntersectionID, LaneID, Map, MovementID,
+    ParkingLotID, Path, PathConstraints, PathRequest, RoadID, TransitRouteID, TransitStopID,
+    Traversable, TurnID,
 };
-use synthpop::TripMode;
+use synthpop::{TripMode, TripPurpose};
 
-use crate::{AgentID, AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID, TripPhaseType};
+use crate::{
+    AgentID, AgentType, AlertLocation, CarID, Event, LaneChangeReason, ParkingSpot, TripID,
+    TripPhaseType, VehicleType,
+};
+
+pub use self::retention::RetentionPolicy;
+
+mod retention;
+
+// https://www.epa.gov/greenvehicles/greenhouse-gas-emissions-typical-passenger-vehicle#driving
+// says 404 grams per mile
+const CAR_CO2_GRAMS_PER_KM: f64 = 251.0;
+// A rough figure for a diesel bus. Trains are assumed to be electric.
+const BUS_CO2_GRAMS_PER_KM: f64 = 1300.0;
 
 /// As a simulation runs, different pieces emit Events. The Analytics object listens to these,
 /// organizing and storing some information from them. The UI queries Analytics to draw time-series
@@ -29,6 +43,11 @@ pub struct Analytics {
     // requires occasionally expensive or complicated summing or merging over all directions of an
     // intersection. So for now, eat the file size cost.
     pub traffic_signal_thruput: TimeSeriesCount<CompressedMovementID>,
+    /// Cars entering a bus lane they aren't allowed to use
+    pub bus_lane_violations: TimeSeriesCount<RoadID>,
+    /// How many people have moved along each road, counting everybody inside a vehicle. Split by
+    /// the type of agent they're in.
+    pub road_person_thruput: TimeSeriesCount<RoadID>,
 
     /// Most fields in Analytics are cumulative over time, but this is just for the current moment
     /// in time.
@@ -39,10 +58,19 @@ pub struct Analytics {
     /// For each passenger boarding, how long did they wait at the stop?
     pub passengers_boarding: BTreeMap<TransitStopID, Vec<(Time, TransitRouteID, Duration)>>,
     pub passengers_alighting: BTreeMap<TransitStopID, Vec<(Time, TransitRouteID)>>,
+    /// Each time somebody couldn't board a full vehicle and had to wait for the next one
+    pub denied_boardings: BTreeMap<TransitStopID, Vec<(Time, TransitRouteID)>>,
+    /// When a transit vehicle leaves a stop, how many passengers are on board, and how many fit.
+    pub transit_loads: BTreeMap<TransitStopID, Vec<(Time, TransitRouteID, usize, Option<usize>)>>,
 
     pub started_trips: BTreeMap<TripID, Time>,
     /// Finish time, ID, mode, trip duration if successful (or None if cancelled)
     pub finished_trips: Vec<(Time, TripID, TripMode, Option<Duration>)>,
+    /// Why each finished or cancelled trip was made, so results can be split by travel market
+    pub trip_purposes: BTreeMap<TripID, TripPurpose>,
+    /// When each driving trip finished, and how many people were in the car. People riding along
+    /// aren't counted separately.
+    pub car_occupancy: Vec<(Time, usize)>,
 
     /// Record different problems that each trip encounters.
     pub problems_per_trip: BTreeMap<TripID, Vec<(Time, Problem)>>,
@@ -53,6 +81,45 @@ pub struct Analytics {
     // TODO Transit riders aren't represented here yet, just the vehicle they're riding.
     /// Only for traffic signals. The u8 is the movement index from a CompressedMovementID.
     pub intersection_delays: BTreeMap<IntersectionID, Vec<(u8, Time, Duration, AgentType)>>,
+    /// Each time a traffic signal gave a late bus priority: the bus, roughly how much time it
+    /// saved, and how much other movements lost
+    pub transit_signal_priority: BTreeMap<IntersectionID, Vec<(Time, CarID, Duration, Duration)>>,
+    /// At every intersection, a summary of how long vehicles entering from each road waited
+    /// before turning. Unlike `intersection_delays`, this covers all intersection types and is
+    /// compact enough to keep for the whole day.
+    pub approach_delays: BTreeMap<IntersectionID, BTreeMap<RoadID, DelaySummary>>,
+    /// At every intersection, a summary of how long people walking waited before crossing.
+    /// Signal timing like leading pedestrian intervals trades this off against vehicle delay, so
+    /// it's measured separately.
+    pub pedestrian_delays: BTreeMap<IntersectionID, DelaySummary>,
+    /// How many vehicles used each lane, either entering it from a turn or changing into it
+    pub lane_usage: Counter<LaneID>,
+    /// Lane changes made in the middle of each road
+    pub lane_changes: Counter<(RoadID, LaneChangeReason)>,
+    /// Along each road with speed enforcement, how many habitual speeders obeyed the limit (true)
+    /// or kept speeding (false)
+    pub speed_enforcement: Counter<(RoadID, bool)>,
+    /// Per intersection, how often motor vehicles and people cycling had conflicting turns and
+    /// one waited for the other
+    pub bike_conflicts: Counter<(IntersectionID, BikeConflictType)>,
+    /// Per road with a toll or priced lanes, how many times cars paid
+    pub toll_payments: Counter<RoadID>,
+    /// Per road with a toll or priced lanes, the total paid in cents
+    pub toll_revenue: Counter<RoadID>,
+    /// Per toll road, how many drivers took a free route instead
+    pub toll_diversions: Counter<RoadID>,
+    /// Per road and hour, the total length of lanes that cars fully crossed and how long that
+    /// took, including any wait at the end. Average speeds from this are comparable to ones
+    /// measured by telematics.
+    pub road_speeds: BTreeMap<(RoadID, usize), (Distance, Duration)>,
+    /// Like `road_speeds`, but for buses
+    pub bus_road_speeds: BTreeMap<(RoadID, usize), (Distance, Duration)>,
+    /// Cars and buses that entered a lane from a turn, and when. Only used to fill out
+    /// `road_speeds` and `bus_road_speeds`.
+    cars_crossing_lanes: BTreeMap<CarID, (LaneID, Time)>,
+    /// Per park-and-ride facility, when somebody chose it, how they continued, and whether they
+    /// were heading back to their car
+    pub park_and_ride: BTreeMap<BuildingID, Vec<(Time, TripMode, bool)>>,
 
     /// Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
@@ -62,6 +129,10 @@ pub struct Analytics {
 
     /// For benchmarking, we may want to disable collecting data.
     record_anything: bool,
+    retention: RetentionPolicy,
+    /// Events before this have been downsampled or spilled to disk
+    downsampled_until: Time,
+    num_spilled_chunks: usize,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -152,24 +223,48 @@ impl ProblemType {
 }
 
 impl Analytics {
-    pub fn new(record_anything: bool) -> Analytics {
+    pub fn new(record_anything: bool, retention: RetentionPolicy) -> Analytics {
+        let max_raw = retention.analytics_raw_throughput;
         Analytics {
-            road_thruput: TimeSeriesCount::new(),
-            intersection_thruput: TimeSeriesCount::new(),
-            traffic_signal_thruput: TimeSeriesCount::new(),
+            road_thruput: TimeSeriesCount::new(max_raw),
+            intersection_thruput: TimeSeriesCount::new(max_raw),
+            traffic_signal_thruput: TimeSeriesCount::new(max_raw),
+            bus_lane_violations: TimeSeriesCount::new(0),
+            road_person_thruput: TimeSeriesCount::new(0),
             demand: BTreeMap::new(),
             bus_arrivals: Vec::new(),
             passengers_boarding: BTreeMap::new(),
             passengers_alighting: BTreeMap::new(),
+            denied_boardings: BTreeMap::new(),
+            transit_loads: BTreeMap::new(),
             started_trips: BTreeMap::new(),
             finished_trips: Vec::new(),
+            trip_purposes: BTreeMap::new(),
+            car_occupancy: Vec::new(),
             problems_per_trip: BTreeMap::new(),
             trip_log: Vec::new(),
             intersection_delays: BTreeMap::new(),
+            transit_signal_priority: BTreeMap::new(),
+            approach_delays: BTreeMap::new(),
+            pedestrian_delays: BTreeMap::new(),
+            lane_usage: Counter::new(),
+            lane_changes: Counter::new(),
+            speed_enforcement: Counter::new(),
+            bike_conflicts: Counter::new(),
+            toll_payments: Counter::new(),
+            toll_revenue: Counter::new(),
+            toll_diversions: Counter::new(),
+            road_speeds: BTreeMap::new(),
+            bus_road_speeds: BTreeMap::new(),
+            cars_crossing_lanes: BTreeMap::new(),
+            park_and_ride: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
             alerts: Vec::new(),
             record_anything,
+            retention,
+            downsampled_until: Time::START_OF_DAY,
+            num_spilled_chunks: 0,
         }
     }
 
@@ -177,21 +272,85 @@ impl Analytics {
         if !self.record_anything {
             return;
         }
+        self.enforce_retention(time);
+
+        if let Event::LaneChanged { to, reason, .. } = ev {
+            self.lane_usage.inc(to);
+            self.lane_changes.inc((to.road, reason));
+        }
+        if let Event::PassedSpeedEnforcement { road, complied, .. } = ev {
+            self.speed_enforcement.inc((road, complied));
+        }
+        if let Event::BikeConflict { i, conflict } = ev {
+            self.bike_conflicts.inc((i, conflict));
+        }
+
+        // Speeds
+        if let Event::AgentEntersTraversable(AgentID::Car(car), _, to, _) = ev {
+            if matches!(car.vehicle_type, VehicleType::Car | VehicleType::Bus) {
+                match to {
+                    Traversable::Lane(l) => {
+                        self.cars_crossing_lanes.insert(car, (l, time));
+                    }
+                    Traversable::Turn(t) => {
+                        if let Some((l, entered)) = self.cars_crossing_lanes.remove(&car) {
+                            if l == t.src {
+                                let speeds = if car.vehicle_type == VehicleType::Bus {
+                                    &mut self.bus_road_speeds
+                                } else {
+                                    &mut self.road_speeds
+                                };
+                                let sum = speeds
+                                    .entry((l.road, entered.get_hours()))
+                                    .or_insert((Distance::ZERO, Duration::ZERO));
+                                sum.0 += map.get_l(l).length();
+                                sum.1 += time - entered;
+                            }
+                        }
+                    }
+                }
+            }
+        }
 
         // Throughput
         if let Event::AgentEntersTraversable(a, _, to, passengers) = ev {
+            // Only transit passengers are counted separately in throughput
+            let transit_riders =
+                passengers.filter(|_| matches!(a.to_type(), AgentType::Bus | AgentType::Train));
             match to {
                 Traversable::Lane(l) => {
                     self.road_thruput.record(time, l.road, a.to_type(), 1);
-                    if let Some(n) = passengers {
+                    self.road_person_thruput.record(
+                        time,
+                        l.road,
+                        a.to_type(),
+                        1 + passengers.unwrap_or(0),
+                    );
+                    if matches!(a, AgentID::Car(_)) {
+                        self.lane_usage.inc(l);
+                    }
+                    if let Some(n) = transit_riders {
                         self.road_thruput
                             .record(time, l.road, AgentType::TransitRider, n);
                     }
+                    // Cars are sometimes allowed in bus lanes to make a turn, pay to use priced
+                    // ones, or carry enough people to use HOV lanes
+                    let lane = map.get_l(l);
+                    let road = map.get_r(l.road);
+                    if a.to_type() == AgentType::Car
+                        && lane.is_bus()
+                        && !PathConstraints::Car.can_use(lane, map)
+                        && road.pricing.priced_lane_at(time).is_none()
+                        && !road.allows_hov(1 + passengers.unwrap_or(0))
+                    {
+                        self.bus_lane_violations
+                            .record(time, l.road, AgentType::Car, 1);
+                    }
                 }
                 Traversable::Turn(t) => {
                     self.intersection_thruput
                         .record(time, t.parent, a.to_type(), 1);
-                    if let Some(n) = passengers {
+                    if let Some(n) = transit_riders {
                         self.intersection_thruput.record(
                             time,
                             t.parent,
@@ -204,7 +363,7 @@ impl Analytics {
                         *self.demand.entry(id).or_insert(0) -= 1;
                         self.traffic_signal_thruput
                             .record(time, compressed, a.to_type(), 1);
-                        if let Some(n) = passengers {
+                        if let Some(n) = transit_riders {
                             self.traffic_signal_thruput.record(
                                 time,
                                 compressed,
@@ -245,6 +404,18 @@ impl Analytics {
                 .or_insert_with(Vec::new)
                 .push((time, route));
         }
+        if let Event::PassengerDeniedBoarding(_, _, route, stop) = ev {
+            self.denied_boardings
+                .entry(stop)
+                .or_insert_with(Vec::new)
+                .push((time, route));
+        }
+        if let Event::TransitVehicleLoad(_, route, stop, passengers, capacity) = ev {
+            self.transit_loads
+                .entry(stop)
+                .or_insert_with(Vec::new)
+                .push((time, route, passengers, capacity));
+        }
 
         // Started trips
         if let Event::TripPhaseStarting(id, _, _, _) = ev {
@@ -255,15 +426,22 @@ impl Analytics {
         if let Event::TripFinished {
             trip,
             mode,
+            purpose,
             total_time,
+            occupancy,
             ..
         } = ev
         {
             self.finished_trips
                 .push((time, trip, mode, Some(total_time)));
-        } else if let Event::TripCancelled(id, mode) = ev {
+            self.trip_purposes.insert(trip, purpose);
+            if let Some(n) = occupancy {
+                self.car_occupancy.push((time, n));
+            }
+        } else if let Event::TripCancelled(id, mode, purpose) = ev {
             self.started_trips.entry(id).or_insert(time);
             self.finished_trips.push((time, id, mode, None));
+            self.trip_purposes.insert(id, purpose);
         }
 
         // Intersection delay
@@ -289,6 +467,24 @@ impl Analytics {
                     .or_insert_with(Vec::new)
                     .push((compressed.idx, time, delay, agent.to_type()));
             }
+
+            match agent {
+                AgentID::Pedestrian(_) => {
+                    self.pedestrian_delays
+                        .entry(turn_id.parent)
+                        .or_insert_with(DelaySummary::new)
+                        .add(delay);
+                }
+                AgentID::BusPassenger(_, _) => {}
+                AgentID::Car(_) => {
+                    self.approach_delays
+                        .entry(turn_id.parent)
+                        .or_insert_with(BTreeMap::new)
+                        .entry(turn_id.src.road)
+                        .or_insert_with(DelaySummary::new)
+                        .add(delay);
+                }
+            }
         }
 
         // Parking spot changes
@@ -347,7 +543,7 @@ impl Analytics {
             Event::TripPhaseStarting(id, _, maybe_req, phase_type) => {
                 self.trip_log.push((time, id, maybe_req, phase_type));
             }
-            Event::TripCancelled(id, _) => {
+            Event::TripCancelled(id, _, _) => {
                 self.trip_log
                     .push((time, id, None, TripPhaseType::Cancelled));
             }
@@ -361,6 +557,37 @@ impl Analytics {
             Event::Alert(loc, msg) => {
                 self.alerts.push((time, loc, msg));
             }
+            Event::TransitSignalPriority {
+                intersection,
+                bus,
+                bus_time_saved,
+                cross_traffic_delay,
+            } => {
+                self.transit_signal_priority
+                    .entry(intersection)
+                    .or_insert_with(Vec::new)
+                    .push((time, bus, bus_time_saved, cross_traffic_delay));
+            }
+            Event::ParkAndRide {
+                facility,
+                onward,
+                returning,
+                ..
+            } => {
+                self.park_and_ride
+                    .entry(facility)
+                    .or_insert_with(Vec::new)
+                    .push((time, onward, returning));
+            }
+            Event::PaidToll { road, cents, .. } => {
+                self.toll_payments.inc(road);
+                self.toll_revenue.add(road, cents as usize);
+            }
+            Event::AvoidedTolls { roads, .. } => {
+                for r in roads {
+                    self.toll_diversions.inc(r);
+                }
+            }
             Event::ProblemEncountered(trip, problem) => {
                 self.problems_per_trip
                     .entry(trip)
@@ -385,6 +612,15 @@ impl Analytics {
     // list.
 
     /// Ignores the current time. Returns None for cancelled trips.
+    /// The average speed of cars crossing a road during some hour, if any did
+    pub fn average_road_speed(&self, r: RoadID, hour: usize) -> Option<Speed> {
+        let (dist, duration) = self.road_speeds.get(&(r, hour))?;
+        if *duration == Duration::ZERO {
+            return None;
+        }
+        Some(Speed::from_dist_time(*dist, *duration))
+    }
+
     pub fn finished_trip_time(&self, trip: TripID) -> Option<Duration> {
         // TODO This is so inefficient!
         for (_, id, _, maybe_dt) in &self.finished_trips {
@@ -395,6 +631,73 @@ impl Analytics {
         None
     }
 
+    /// For one route at one stop, how many people were left behind by full vehicles, and the
+    /// most crowded any vehicle was when leaving, as a fraction of its capacity. The peak load is
+    /// None if no vehicle with limited capacity has left yet.
+    pub fn transit_crowding(
+        &self,
+        stop: TransitStopID,
+        route: TransitRouteID,
+    ) -> (usize, Option<f64>) {
+        let denied = self
+            .denied_boardings
+            .get(&stop)
+            .map(|list| list.iter().filter(|(_, r)| *r == route).count())
+            .unwrap_or(0);
+        let mut peak_load: Option<f64> = None;
+        if let Some(list) = self.transit_loads.get(&stop) {
+            for (_, r, passengers, capacity) in list {
+                if let Some(cap) = capacity {
+                    if *r == route && *cap > 0 {
+                        let load = (*passengers as f64) / (*cap as f64);
+                        peak_load = Some(peak_load.map_or(load, |x| x.max(load)));
+                    }
+                }
+            }
+        }
+        (denied, peak_load)
+    }
+
+    /// Of the people in cars on trips finished by `now`, how many were alone? None if nobody's
+    /// driven yet.
+    pub fn sov_share(&self, now: Time) -> Option<f64> {
+        let mut alone = 0;
+        let mut total = 0;
+        for (time, occupancy) in &self.car_occupancy {
+            if *time > now {
+                break;
+            }
+            if *occupancy == 1 {
+                alone += 1;
+            }
+            total += occupancy;
+        }
+        if total == 0 {
+            return None;
+        }
+        Some((alone as f64) / (total as f64))
+    }
+
+    /// Estimates the CO2 emitted by cars and buses, from the length of every road they've crossed.
+    /// Throughput is bucketed by hour, so only full hours before `now` count.
+    pub fn co2_emissions_kg(&self, map: &Map, now: Time) -> f64 {
+        let mut grams = 0.0;
+        for ((r, agent_type, hour), count) in &self.road_thruput.counts {
+            if *hour >= now.get_hours() {
+                continue;
+            }
+            let rate = match agent_type {
+                AgentType::Car => CAR_CO2_GRAMS_PER_KM,
+                AgentType::Bus => BUS_CO2_GRAMS_PER_KM,
+                _ => {
+                    continue;
+                }
+            };
+            grams += rate * (*count as f64) * map.get_r(*r).length().inner_meters() / 1000.0;
+        }
+        grams / 1000.0
+    }
+
     /// Returns pairs of trip times for finished trips in both worlds. (ID, before, after, mode)
     pub fn both_finished_trips(
         &self,
@@ -428,8 +731,16 @@ impl Analytics {
     /// If calling on prebaked Analytics, be careful to pass in an unedited map, to match how the
     /// simulation was originally run. Otherwise the paths may be nonsense.
     pub fn get_trip_phases(&self, trip: TripID, map: &Map) -> Vec<TripPhase> {
+        // Finished trips may have been spilled to disk. Their log is entirely in one place.
+        let spilled;
+        let mut log = &self.trip_log;
+        if self.num_spilled_chunks > 0 && !self.trip_log.iter().any(|(_, id, _, _)| *id == trip) {
+            spilled = self.read_spilled_trip_log();
+            log = &spilled;
+        }
+
         let mut phases: Vec<TripPhase> = Vec::new();
-        for (t, id, maybe_req, phase_type) in &self.trip_log {
+        for (t, id, maybe_req, phase_type) in log {
             if *id != trip {
                 continue;
             }
@@ -451,8 +762,9 @@ impl Analytics {
     }
 
     pub fn get_all_trip_phases(&self) -> BTreeMap<TripID, Vec<TripPhase>> {
+        let spilled = self.read_spilled_trip_log();
         let mut trips = BTreeMap::new();
-        for (t, id, maybe_req, phase_type) in &self.trip_log {
+        for (t, id, maybe_req, phase_type) in spilled.iter().chain(self.trip_log.iter()) {
             let phases: &mut Vec<TripPhase> = trips.entry(*id).or_insert_with(Vec::new);
             if let Some(ref mut last) = phases.last_mut() {
                 last.end_time = Some(*t);
@@ -691,7 +1003,7 @@ impl Analytics {
 
 impl Default for Analytics {
     fn default() -> Analytics {
-        Analytics::new(false)
+        Analytics::new(false, RetentionPolicy::default())
     }
 }
 
@@ -710,26 +1022,30 @@ pub struct TimeSeriesCount<X: Ord + Clone> {
     /// (Road or intersection, type, hour block) -> count for that hour
     pub counts: BTreeMap<(X, AgentType, usize), usize>,
 
-    /// Very expensive to store, so it's optional. But useful to flag on to experiment with
-    /// representations better than the hour count above.
-    pub raw: Vec<(Time, AgentType, X)>,
+    /// Very expensive to store, so it's optional. But useful to experiment with representations
+    /// better than the hour count above. Only the most recent `max_raw` events are kept.
+    pub raw: VecDeque<(Time, AgentType, X)>,
+    max_raw: usize,
 }
 
 impl<X: Ord + Clone> TimeSeriesCount<X> {
-    fn new() -> TimeSeriesCount<X> {
+    fn new(max_raw: usize) -> TimeSeriesCount<X> {
         TimeSeriesCount {
             counts: BTreeMap::new(),
-            raw: Vec::new(),
+            raw: VecDeque::new(),
+            max_raw,
         }
     }
 
     fn record(&mut self, time: Time, id: X, agent_type: AgentType, count: usize) {
-        // TODO Manually change flag
-        if false {
+        if self.max_raw > 0 {
             // TODO Woo, handling transit passengers is even more expensive in this already
             // expensive representation...
             for _ in 0..count {
-                self.raw.push((time, agent_type, id.clone()));
+                self.raw.push_back((time, agent_type, id.clone()));
+            }
+            while self.raw.len() > self.max_raw {
+                self.raw.pop_front();
             }
         }
 
@@ -851,6 +1167,77 @@ impl<X: Ord + Clone> TimeSeriesCount<X> {
     }
 }
 
+/// Summarizes many delay measurements in constant space, so the mean and percentiles can be
+/// estimated without keeping every sample.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DelaySummary {
+    pub count: usize,
+    pub total: Duration,
+    /// The number of delays falling in each bucket of width `DELAY_BUCKET`. The last bucket also
+    /// counts everything longer.
+    buckets: Vec<usize>,
+}
+
+const DELAY_BUCKET: Duration = Duration::const_seconds(5.0);
+const NUM_DELAY_BUCKETS: usize = 120;
+
+impl DelaySummary {
+    pub fn new() -> DelaySummary {
+        DelaySummary {
+            count: 0,
+            total: Duration::ZERO,
+            buckets: vec![0; NUM_DELAY_BUCKETS],
+        }
+    }
+
+    pub fn add(&mut self, delay: Duration) {
+        self.count += 1;
+        self.total += delay;
+        let idx = ((delay / DELAY_BUCKET).floor() as usize).min(NUM_DELAY_BUCKETS - 1);
+        self.buckets[idx] += 1;
+    }
+
+    /// Combine with another summary, like when looking at all approaches to an intersection
+    pub fn merge(&mut self, other: &DelaySummary) {
+        self.count += other.count;
+        self.total += other.total;
+        for (a, b) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *a += *b;
+        }
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.total / (self.count as f64))
+        }
+    }
+
+    /// Estimates the delay that `pct` percent of measurements don't exceed, rounded up to the
+    /// nearest bucket.
+    pub fn percentile(&self, pct: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let target = ((pct / 100.0) * (self.count as f64)).ceil().max(1.0) as usize;
+        let mut seen = 0;
+        for (idx, cnt) in self.buckets.iter().enumerate() {
+            seen += cnt;
+            if seen >= target {
+                return Some(DELAY_BUCKET * ((idx + 1) as f64));
+            }
+        }
+        Some(DELAY_BUCKET * (NUM_DELAY_BUCKETS as f64))
+    }
+}
+
+impl Default for DelaySummary {
+    fn default() -> DelaySummary {
+        DelaySummary::new()
+    }
+}
+
 /// A sliding window, used to count something over time
 pub struct SlidingWindow {
     times: VecDeque<Time>,
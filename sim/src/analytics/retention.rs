@@ -0,0 +1,162 @@
+//! Long simulations on large maps accumulate huge per-event logs in `Analytics`. A
+//! `RetentionPolicy` bounds this: old intersection delays are averaged into fixed intervals, the
+//! logs of finished trips can be spilled to disk, and raw throughput events live in a ring buffer.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::PathRequest;
+
+use super::Analytics;
+use crate::{AgentType, TripID, TripPhaseType};
+
+pub(crate) type TripLogEntry = (Time, TripID, Option<PathRequest>, TripPhaseType);
+
+/// How much detail `Analytics` keeps as a simulation runs. By default, everything is kept.
+#[derive(Clone, Debug, Serialize, Deserialize, StructOpt)]
+pub struct RetentionPolicy {
+    /// Only keep the most recent events at full detail, for this long (like "2:00:00"). Older
+    /// intersection delays are averaged per movement and agent type, and the logs of finished
+    /// trips are spilled to `--analytics-spill-dir`. By default, everything is kept.
+    #[structopt(long, parse(try_from_str = Duration::parse))]
+    pub analytics_full_detail: Option<Duration>,
+    /// When downsampling old events, average them over intervals this long.
+    #[structopt(long, parse(try_from_str = Duration::parse), default_value = "5:00")]
+    pub analytics_downsample_interval: Duration,
+    /// Write the logs of old finished trips to files in this directory, instead of keeping them in
+    /// memory. They're read back when a trip's phases are requested. Each run needs its own
+    /// directory.
+    #[structopt(long)]
+    pub analytics_spill_dir: Option<String>,
+    /// Keep this many of the most recent individual throughput events (separately for roads,
+    /// intersections, and traffic signal movements), for finer-grained throughput plots. Hourly
+    /// counts are always recorded.
+    #[structopt(long, default_value = "0")]
+    pub analytics_raw_throughput: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> RetentionPolicy {
+        RetentionPolicy {
+            analytics_full_detail: None,
+            analytics_downsample_interval: Duration::minutes(5),
+            analytics_spill_dir: None,
+            analytics_raw_throughput: 0,
+        }
+    }
+}
+
+impl Analytics {
+    /// Downsample and spill everything older than the retention policy allows. Cheap to call
+    /// often; it only does work once another full interval has expired.
+    pub(crate) fn enforce_retention(&mut self, now: Time) {
+        let full_detail = match self.retention.analytics_full_detail {
+            Some(dt) => dt,
+            None => {
+                return;
+            }
+        };
+        let interval = self.retention.analytics_downsample_interval;
+        if now - Time::START_OF_DAY < full_detail {
+            return;
+        }
+        // Align to whole intervals, so a bucket is never split between two compactions
+        let intervals = ((now - full_detail - Time::START_OF_DAY) / interval).floor();
+        let cutoff = Time::START_OF_DAY + intervals * interval;
+        if cutoff <= self.downsampled_until {
+            return;
+        }
+
+        self.downsample_intersection_delays(self.downsampled_until, cutoff, interval);
+        if self.retention.analytics_spill_dir.is_some() {
+            self.spill_trip_log(cutoff);
+        }
+        self.downsampled_until = cutoff;
+    }
+
+    /// Replace individual delays in [start, end) with the mean per interval, movement, and agent
+    /// type.
+    fn downsample_intersection_delays(&mut self, start: Time, end: Time, interval: Duration) {
+        for list in self.intersection_delays.values_mut() {
+            // The list is sorted by time
+            let from = list.partition_point(|(_, t, _, _)| *t < start);
+            let to = list.partition_point(|(_, t, _, _)| *t < end);
+            if to - from <= 1 {
+                continue;
+            }
+
+            let mut buckets: BTreeMap<(Time, u8, AgentType), (Duration, usize)> = BTreeMap::new();
+            for (idx, t, dt, agent_type) in list.drain(from..to) {
+                let bucket =
+                    Time::START_OF_DAY + ((t - Time::START_OF_DAY) / interval).floor() * interval;
+                let entry = buckets
+                    .entry((bucket, idx, agent_type))
+                    .or_insert((Duration::ZERO, 0));
+                entry.0 += dt;
+                entry.1 += 1;
+            }
+            let merged = buckets
+                .into_iter()
+                .map(|((t, idx, agent_type), (total, cnt))| {
+                    (idx, t, total / (cnt as f64), agent_type)
+                });
+            list.splice(from..from, merged);
+        }
+    }
+
+    /// Move the log of every trip that finished before `cutoff` to disk.
+    fn spill_trip_log(&mut self, cutoff: Time) {
+        let mut done = Vec::new();
+        for (t, id, _, phase_type) in &self.trip_log {
+            if *t >= cutoff {
+                break;
+            }
+            if *phase_type == TripPhaseType::Finished || *phase_type == TripPhaseType::Cancelled {
+                done.push(*id);
+            }
+        }
+        if done.is_empty() {
+            return;
+        }
+        done.sort();
+
+        let (spill, keep): (Vec<TripLogEntry>, Vec<TripLogEntry>) =
+            std::mem::take(&mut self.trip_log)
+                .into_iter()
+                .partition(|(_, id, _, _)| done.binary_search(id).is_ok());
+        self.trip_log = keep;
+        abstio::write_binary(self.spill_path(self.num_spilled_chunks), &spill);
+        self.num_spilled_chunks += 1;
+    }
+
+    fn spill_path(&self, chunk: usize) -> String {
+        format!(
+            "{}/trip_log_{}.bin",
+            self.retention.analytics_spill_dir.as_ref().unwrap(),
+            chunk
+        )
+    }
+
+    /// The log of old trips that was written to disk, in the order it was spilled.
+    pub(crate) fn read_spilled_trip_log(&self) -> Vec<TripLogEntry> {
+        let mut log = Vec::new();
+        for chunk in 0..self.num_spilled_chunks {
+            match abstio::maybe_read_binary::<Vec<TripLogEntry>>(
+                self.spill_path(chunk),
+                &mut Timer::throwaway(),
+            ) {
+                Ok(entries) => {
+                    log.extend(entries);
+                }
+                Err(err) => {
+                    error!("Couldn't read spilled trip log: {}", err);
+                }
+            }
+        }
+        log
+    }
+}
@@ -1,5 +1,5 @@
-//! A simple tool that just runs a simulation for the specified number of hours. Use for profiling
-//! and benchmarking.
+//! A simple tool that just runs a simulation for the specified number of hours, then prints some
+//! key performance indicators. Use for profiling and benchmarking.
 
 use structopt::StructOpt;
 
@@ -12,6 +12,10 @@ struct Args {
     /// How many hours to simulate.
     #[structopt(long)]
     hours: usize,
+    /// Afterwards, save where everybody's cars are parked, so the next run can start from there
+    /// with --warm-start.
+    #[structopt(long)]
+    save_warm_start: bool,
     #[structopt(flatten)]
     flags: sim::SimFlags,
 }
@@ -54,13 +58,16 @@ fn main() {
                 &mut None,
             );
             if sim.time() == goal_time {
-                return;
+                break;
             }
         }
-        println!("\n\nInterrupting at {}", sim.time());
-        sim.save();
-        for x in sim.describe_internal_stats() {
-            println!("{}", x);
+        if sim.time() != goal_time {
+            println!("\n\nInterrupting at {}", sim.time());
+            sim.save();
+            for x in sim.describe_internal_stats() {
+                println!("{}", x);
+            }
+            return;
         }
     } else {
         sim.timed_step(
@@ -70,4 +77,19 @@ fn main() {
             &mut abstutil::Timer::new("run simulation"),
         );
     }
+
+    let fmt = geom::UnitFmt::metric();
+    for metric in sim::all_metrics() {
+        println!(
+            "{} ({}): {}",
+            metric.name(),
+            metric.aggregation().describe(),
+            metric.describe(sim.get_analytics(), &map, sim.time(), &fmt)
+        );
+    }
+
+    if args.save_warm_start {
+        let warm_start = sim::WarmStart::new(&sim);
+        println!("Saved {} to {}", warm_start.describe(), warm_start.save());
+    }
 }
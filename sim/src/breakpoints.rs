@@ -0,0 +1,220 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use geom::Time;
+use map_model::{IntersectionID, Map, RoadID, Traversable};
+
+use crate::{AgentID, Event, TripID};
+
+/// Pause the simulation when something interesting happens to an agent or at a place. This is
+/// meant for debugging problems like gridlock, where it's hard to notice the moment when things
+/// start going wrong.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Breakpoint {
+    /// Whenever this agent enters a lane or turn, parks, waits at an intersection, and so on
+    Agent(AgentID),
+    /// Whenever any agent enters a lane of this road
+    Road(RoadID),
+    /// Whenever any agent starts a turn here, or enters or leaves the map here
+    Intersection(IntersectionID),
+    /// When this trip finishes or is cancelled
+    Trip(TripID),
+}
+
+/// A breakpoint that was triggered
+#[derive(Clone, Debug)]
+pub struct BreakpointHit {
+    pub time: Time,
+    pub breakpoint: Breakpoint,
+    /// The agent responsible, if known
+    pub agent: Option<AgentID>,
+    pub description: String,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Breakpoints {
+    active: BTreeSet<Breakpoint>,
+    /// A subset of `active` that's removed after being hit once
+    once: BTreeSet<Breakpoint>,
+    hits: Vec<BreakpointHit>,
+}
+
+impl Breakpoints {
+    pub fn add(&mut self, bp: Breakpoint) {
+        self.active.insert(bp);
+        self.once.remove(&bp);
+    }
+
+    /// Unless this breakpoint is already set, it'll be removed the first time it's hit.
+    pub fn add_once(&mut self, bp: Breakpoint) {
+        if self.active.insert(bp) {
+            self.once.insert(bp);
+        }
+    }
+
+    pub fn remove(&mut self, bp: Breakpoint) {
+        self.active.remove(&bp);
+        self.once.remove(&bp);
+    }
+
+    pub fn get_all(&self) -> &BTreeSet<Breakpoint> {
+        &self.active
+    }
+
+    pub fn take_hits(&mut self) -> Vec<BreakpointHit> {
+        std::mem::take(&mut self.hits)
+    }
+
+    /// Returns true if this event triggers any breakpoint, meaning the simulation should halt.
+    pub fn handle_event(&mut self, time: Time, ev: &Event, map: &Map) -> bool {
+        if self.active.is_empty() {
+            return false;
+        }
+
+        let before = self.hits.len();
+        match ev {
+            Event::AgentEntersTraversable(agent, _, on, _) => {
+                let desc = format!("{} entered {}", agent, describe_traversable(*on, map));
+                self.check(time, Breakpoint::Agent(*agent), Some(*agent), &desc);
+                match on {
+                    Traversable::Lane(l) => {
+                        self.check(time, Breakpoint::Road(l.road), Some(*agent), &desc);
+                    }
+                    Traversable::Turn(t) => {
+                        self.check(
+                            time,
+                            Breakpoint::Intersection(t.parent),
+                            Some(*agent),
+                            &desc,
+                        );
+                    }
+                }
+            }
+            Event::IntersectionDelayMeasured(_, turn, agent, delay) => {
+                self.check(
+                    time,
+                    Breakpoint::Agent(*agent),
+                    Some(*agent),
+                    &format!("{} waited {} at {}", agent, delay, turn.parent),
+                );
+            }
+            Event::CarReachedParkingSpot(car, spot) => {
+                let agent = AgentID::Car(*car);
+                self.check(
+                    time,
+                    Breakpoint::Agent(agent),
+                    Some(agent),
+                    &format!("{} parked at {:?}", car, spot),
+                );
+            }
+            Event::CarLeftParkingSpot(car, spot) => {
+                let agent = AgentID::Car(*car);
+                self.check(
+                    time,
+                    Breakpoint::Agent(agent),
+                    Some(agent),
+                    &format!("{} left {:?}", car, spot),
+                );
+            }
+            Event::BusArrivedAtStop(car, _, stop) => {
+                let agent = AgentID::Car(*car);
+                self.check(
+                    time,
+                    Breakpoint::Agent(agent),
+                    Some(agent),
+                    &format!("{} arrived at {}", car, stop),
+                );
+            }
+            Event::BusDepartedFromStop(car, _, stop) => {
+                let agent = AgentID::Car(*car);
+                self.check(
+                    time,
+                    Breakpoint::Agent(agent),
+                    Some(agent),
+                    &format!("{} departed from {}", car, stop),
+                );
+            }
+            Event::BikeStoppedAtSidewalk(car, l) => {
+                let agent = AgentID::Car(*car);
+                self.check(
+                    time,
+                    Breakpoint::Agent(agent),
+                    Some(agent),
+                    &format!("{} stopped biking at {}", car, l),
+                );
+            }
+            Event::PedReachedParkingSpot(ped, spot) => {
+                let agent = AgentID::Pedestrian(*ped);
+                self.check(
+                    time,
+                    Breakpoint::Agent(agent),
+                    Some(agent),
+                    &format!("{} reached {:?}", ped, spot),
+                );
+            }
+            Event::PersonEntersMap(person, agent, i) => {
+                let desc = format!("{} entered the map at {} as {}", person, i, agent);
+                self.check(time, Breakpoint::Agent(*agent), Some(*agent), &desc);
+                self.check(time, Breakpoint::Intersection(*i), Some(*agent), &desc);
+            }
+            Event::PersonLeavesMap(person, Some(agent), i) => {
+                let desc = format!("{} left the map at {} as {}", person, i, agent);
+                self.check(time, Breakpoint::Agent(*agent), Some(*agent), &desc);
+                self.check(time, Breakpoint::Intersection(*i), Some(*agent), &desc);
+            }
+            Event::TripFinished { trip, .. } => {
+                self.check(
+                    time,
+                    Breakpoint::Trip(*trip),
+                    None,
+                    &format!("{} finished", trip),
+                );
+            }
+            Event::TripCancelled(trip, _, _) => {
+                self.check(
+                    time,
+                    Breakpoint::Trip(*trip),
+                    None,
+                    &format!("{} was cancelled", trip),
+                );
+            }
+            Event::TransitSignalPriority {
+                intersection, bus, ..
+            } => {
+                let agent = AgentID::Car(*bus);
+                let desc = format!("{} got transit signal priority at {}", bus, intersection);
+                self.check(time, Breakpoint::Agent(agent), Some(agent), &desc);
+                self.check(
+                    time,
+                    Breakpoint::Intersection(*intersection),
+                    Some(agent),
+                    &desc,
+                );
+            }
+            _ => {}
+        }
+        self.hits.len() > before
+    }
+
+    fn check(&mut self, time: Time, bp: Breakpoint, agent: Option<AgentID>, description: &str) {
+        if self.active.contains(&bp) {
+            if self.once.remove(&bp) {
+                self.active.remove(&bp);
+            }
+            self.hits.push(BreakpointHit {
+                time,
+                breakpoint: bp,
+                agent,
+                description: description.to_string(),
+            });
+        }
+    }
+}
+
+fn describe_traversable(on: Traversable, map: &Map) -> String {
+    match on {
+        Traversable::Lane(l) => format!("{} ({:?})", l, map.get_l(l).lane_type),
+        Traversable::Turn(t) => format!("a turn through {}", t.parent),
+    }
+}
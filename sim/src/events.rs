@@ -2,12 +2,14 @@ use serde::{Deserialize, Serialize};
 
 use geom::Duration;
 use map_model::{
-    BuildingID, IntersectionID, LaneID, Map, Path, PathRequest, TransitRouteID, TransitStopID,
-    Traversable, TurnID,
+    BikeConflictType, BuildingID, IntersectionID, LaneID, Map, Path, PathRequest, RoadID,
+    TransitRouteID, TransitStopID, Traversable, TurnID,
 };
-use synthpop::TripMode;
+use synthpop::{TripMode, TripPurpose};
 
-use crate::{AgentID, CarID, ParkingSpot, PedestrianID, PersonID, Problem, TripID};
+use crate::{
+    AgentID, CarID, LaneChangeReason, ParkingSpot, PedestrianID, PersonID, Problem, TripID,
+};
 
 /// As a simulation runs, different systems emit Events. This cleanly separates the internal
 /// mechanics of the simulation from consumers that just want to know what's happening.
@@ -27,6 +29,10 @@ pub enum Event {
     /// How long waiting at the stop?
     PassengerBoardsTransit(PersonID, CarID, TransitRouteID, TransitStopID, Duration),
     PassengerAlightsTransit(PersonID, CarID, TransitRouteID, TransitStopID),
+    /// The vehicle was full, so the passenger keeps waiting for the next one
+    PassengerDeniedBoarding(PersonID, CarID, TransitRouteID, TransitStopID),
+    /// When a transit vehicle leaves a stop, how many passengers are on board, and how many fit.
+    TransitVehicleLoad(CarID, TransitRouteID, TransitStopID, usize, Option<usize>),
 
     PersonEntersBuilding(PersonID, BuildingID),
     PersonLeavesBuilding(PersonID, BuildingID),
@@ -40,25 +46,94 @@ pub enum Event {
 
     ProblemEncountered(TripID, Problem),
 
-    /// If the agent is a transit vehicle, then include a count of how many passengers are on
-    /// board.
+    /// If the agent is a transit vehicle or a car carrying passengers, then include a count of how
+    /// many passengers are on board.
     AgentEntersTraversable(AgentID, Option<TripID>, Traversable, Option<usize>),
+    /// A vehicle changed lanes in the middle of a road
+    LaneChanged {
+        car: CarID,
+        from: LaneID,
+        to: LaneID,
+        reason: LaneChangeReason,
+    },
     /// TripID, TurnID (Where the delay was encountered), Time spent waiting at that turn
     IntersectionDelayMeasured(TripID, TurnID, AgentID, Duration),
 
     TripFinished {
         trip: TripID,
         mode: TripMode,
+        purpose: TripPurpose,
         total_time: Duration,
         blocked_time: Duration,
+        /// For somebody driving a car, how many people were in it
+        occupancy: Option<usize>,
+    },
+    TripCancelled(TripID, TripMode, TripPurpose),
+    /// Also emitted for carpool passengers, when their driver departs
+    TripStarted {
+        trip: TripID,
+        person: PersonID,
+        mode: TripMode,
     },
-    TripCancelled(TripID, TripMode),
     TripPhaseStarting(TripID, PersonID, Option<PathRequest>, TripPhaseType),
 
     /// Just use for parking replanning. Not happy about copying the full path in here, but the way
     /// to plumb info into Analytics is Event.
     PathAmended(Path),
 
+    /// A driving trip is using a park-and-ride facility, continuing by transit or bike. If
+    /// `returning`, the person is heading back to their car instead.
+    ParkAndRide {
+        trip: TripID,
+        facility: BuildingID,
+        onward: TripMode,
+        returning: bool,
+    },
+
+    /// A habitual speeder entered a road with speed enforcement, and either obeyed the limit or
+    /// not
+    PassedSpeedEnforcement {
+        car: CarID,
+        road: RoadID,
+        complied: bool,
+    },
+
+    /// A motor vehicle and somebody cycling both wanted to make conflicting turns, and one went
+    /// while the other waited. A rough proxy for near misses.
+    BikeConflict {
+        i: IntersectionID,
+        conflict: BikeConflictType,
+    },
+
+    /// A car entered a road with a toll, or a priced lane
+    PaidToll {
+        car: CarID,
+        road: RoadID,
+        cents: u32,
+        priced_lane: bool,
+    },
+    /// The best route for a driving trip crossed these toll roads, but the driver decided the
+    /// tolls weren't worth the time saved and took the best free route instead
+    AvoidedTolls {
+        trip: TripID,
+        roads: Vec<RoadID>,
+    },
+
+    /// A late bus got transit signal priority. Includes roughly how much time the bus saved, and
+    /// how much green time other movements lost or had to wait longer.
+    TransitSignalPriority {
+        intersection: IntersectionID,
+        bus: CarID,
+        bus_time_saved: Duration,
+        cross_traffic_delay: Duration,
+    },
+
+    /// A traffic signal moved to a new stage. Members of a signal cluster each emit this.
+    SignalStageChanged {
+        i: IntersectionID,
+        stage: usize,
+    },
+
     Alert(AlertLocation, String),
 }
 
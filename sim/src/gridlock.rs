@@ -0,0 +1,174 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use geom::Duration;
+use map_model::IntersectionID;
+
+use crate::{AgentID, DelayCause};
+
+/// A group of intersections where vehicles waiting at each one are stuck behind vehicles waiting
+/// at another one, in a cycle. Nobody in the group can move until something outside the
+/// simulation's normal rules intervenes, like an agent being deleted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gridlock {
+    /// Sorted, so the same group is always described the same way
+    pub intersections: Vec<IntersectionID>,
+    /// Each vehicle stuck at one of the intersections behind a vehicle at another one in the
+    /// group, and how long it's been waiting
+    pub agents: Vec<(AgentID, Duration)>,
+}
+
+impl Gridlock {
+    /// How long the longest-waiting vehicle in the group has been stuck
+    pub fn longest_wait(&self) -> Duration {
+        self.agents
+            .iter()
+            .map(|(_, delay)| *delay)
+            .max()
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+/// Projects the blocked-by graph between agents onto intersections, then finds cycles in it. An
+/// agent only counts if they've been waiting at least `threshold`, so that normal queueing at
+/// adjacent signals isn't mistaken for gridlock. `intersection_of` says which intersection an
+/// agent is waiting to cross.
+pub(crate) fn find_gridlock<F: Fn(AgentID) -> Option<IntersectionID>>(
+    graph: &BTreeMap<AgentID, (Duration, DelayCause)>,
+    threshold: Duration,
+    intersection_of: F,
+) -> Vec<Gridlock> {
+    let mut edges: BTreeMap<IntersectionID, BTreeSet<IntersectionID>> = BTreeMap::new();
+    let mut stuck = Vec::new();
+    for (agent, (delay, cause)) in graph {
+        if *delay < threshold {
+            continue;
+        }
+        let blocker = match cause {
+            DelayCause::Agent(a) => *a,
+            DelayCause::Intersection(_) => continue,
+        };
+        if let (Some(from), Some(to)) = (intersection_of(*agent), intersection_of(blocker)) {
+            if from != to {
+                edges.entry(from).or_insert_with(BTreeSet::new).insert(to);
+                stuck.push((*agent, *delay, from, to));
+            }
+        }
+    }
+
+    strongly_connected(&edges)
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|intersections| {
+            let agents = stuck
+                .iter()
+                .filter(|(_, _, from, to)| {
+                    intersections.contains(from) && intersections.contains(to)
+                })
+                .map(|(agent, delay, _, _)| (*agent, *delay))
+                .collect();
+            Gridlock {
+                intersections,
+                agents,
+            }
+        })
+        .collect()
+}
+
+/// Kosaraju's algorithm. Every node appears in exactly one returned component, each sorted.
+fn strongly_connected<T: Copy + Ord>(edges: &BTreeMap<T, BTreeSet<T>>) -> Vec<Vec<T>> {
+    let mut nodes = BTreeSet::new();
+    let mut reversed: BTreeMap<T, Vec<T>> = BTreeMap::new();
+    for (from, dsts) in edges {
+        nodes.insert(*from);
+        for to in dsts {
+            nodes.insert(*to);
+            reversed.entry(*to).or_insert_with(Vec::new).push(*from);
+        }
+    }
+
+    // First order nodes by when a depth-first search finishes with them. Recursion would be
+    // simpler, but a big jam could overflow the stack.
+    let mut visited = BTreeSet::new();
+    let mut finished = Vec::new();
+    for start in &nodes {
+        if !visited.insert(*start) {
+            continue;
+        }
+        let mut stack = vec![(*start, 0)];
+        while let Some((node, idx)) = stack.pop() {
+            match edges.get(&node).and_then(|dsts| dsts.iter().nth(idx)) {
+                Some(next) => {
+                    stack.push((node, idx + 1));
+                    if visited.insert(*next) {
+                        stack.push((*next, 0));
+                    }
+                }
+                None => {
+                    finished.push(node);
+                }
+            }
+        }
+    }
+
+    // Then search backwards from the last one finished; everything reached is one component.
+    let mut assigned = BTreeSet::new();
+    let mut components = Vec::new();
+    for start in finished.into_iter().rev() {
+        if !assigned.insert(start) {
+            continue;
+        }
+        let mut component = vec![start];
+        let mut queue = vec![start];
+        while let Some(node) = queue.pop() {
+            for prev in reversed.get(&node).into_iter().flatten() {
+                if assigned.insert(*prev) {
+                    component.push(*prev);
+                    queue.push(*prev);
+                }
+            }
+        }
+        component.sort();
+        components.push(component);
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(pairs: Vec<(usize, usize)>) -> BTreeMap<usize, BTreeSet<usize>> {
+        let mut edges = BTreeMap::new();
+        for (from, to) in pairs {
+            edges.entry(from).or_insert_with(BTreeSet::new).insert(to);
+        }
+        edges
+    }
+
+    fn cycles(pairs: Vec<(usize, usize)>) -> Vec<Vec<usize>> {
+        let mut result: Vec<Vec<usize>> = strongly_connected(&graph(pairs))
+            .into_iter()
+            .filter(|c| c.len() > 1)
+            .collect();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn test_strongly_connected() {
+        // A chain has no cycles
+        assert!(cycles(vec![(1, 2), (2, 3), (3, 4)]).is_empty());
+
+        // A loop around a block, with a queue leading into it
+        assert_eq!(
+            cycles(vec![(1, 2), (2, 3), (3, 4), (4, 1), (5, 1), (4, 6)]),
+            vec![vec![1, 2, 3, 4]]
+        );
+
+        // Two separate loops, joined one way
+        assert_eq!(
+            cycles(vec![(1, 2), (2, 1), (2, 3), (3, 4), (4, 3)]),
+            vec![vec![1, 2], vec![3, 4]]
+        );
+    }
+}
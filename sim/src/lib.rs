@@ -5,6 +5,12 @@
 //! individual agents over time, and higher-level systems like TripManager and TransitSimState that
 //! glue together individual goals executed by the agents.
 //!
+//! The simulation is deterministic. The same map, scenario, options, and RNG seed produce exactly
+//! the same results, run after run and on any platform. To keep it that way, never let the order
+//! of iterating over a HashMap affect behavior, and don't use floating point functions that call
+//! the platform's math library (like `exp`, `ln`, or `sin`) for anything affecting agents. The
+//! `sim_regression_test` in the tests crate compares every event against goldenfiles.
+//!
 //! Helpful terminology:
 //! - sov = single occupancy vehicle, a car with just a driver and no passengers. (Car passengers
 //!   are not currently modelled)
@@ -22,51 +28,74 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::{Distance, Speed, Time};
+use geom::{Distance, Duration, Speed, Time};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints, Position,
     TransitRouteID, TransitStopID,
 };
-use synthpop::TripEndpoint;
+use synthpop::{TripEndpoint, VehicleClass};
 
 pub use crate::render::{
     CarStatus, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput, Intent, PedCrowdLocation,
     UnzoomedAgent,
 };
 
-pub use self::analytics::{Analytics, Problem, ProblemType, SlidingWindow, TripPhase};
+pub use self::analytics::{
+    Analytics, DelaySummary, Problem, ProblemType, RetentionPolicy, SlidingWindow, TripPhase,
+};
+pub(crate) use self::breakpoints::Breakpoints;
+pub use self::breakpoints::{Breakpoint, BreakpointHit};
 pub(crate) use self::events::Event;
 pub use self::events::{AlertLocation, TripPhaseType};
+pub use self::gridlock::Gridlock;
 pub use self::make::SimFlags;
-pub(crate) use self::make::{StartTripArgs, TripSpec};
+pub(crate) use self::make::{maybe_park_and_ride, StartTripArgs, TripSpec};
 pub(crate) use self::mechanics::{
-    DrivingSimState, IntersectionSimState, ParkingSim, ParkingSimState, WalkingSimState,
+    BikeParking, DrivingSimState, IntersectionSimState, ParkingSim, ParkingSimState,
+    WalkingSimState,
 };
+pub use self::mechanics::{LaneChangeReason, LaneChangingOpts};
+pub use self::metrics::{
+    all_metrics, ActiveTravelShare, Aggregation, AverageDelay, Better, BoundaryRoadVolume,
+    BusSpeed, CancelledTrips, Co2Emissions, DrivingShare, MeanTripTime, Metric, MetricUnit,
+};
+pub use self::multirun::{simulate_headless, Estimate, MultiRunResults, RunKPIs};
 pub(crate) use self::pandemic::PandemicModel;
 pub use self::prebake::PrebakeSummary;
 pub(crate) use self::recorder::TrafficRecorder;
+pub use self::region::{RegionalSim, RegionalTrip};
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
+pub use self::scripting::BehaviorScript;
 pub use self::sim::{
-    count_parked_cars_per_bldg, rand_dist, AgentProperties, AlertHandler, DelayCause, Sim,
-    SimCallback, SimOptions,
+    count_parked_cars_per_bldg, rand_dist, AgentProperties, AlertHandler, DelayCause,
+    FastForwardAccuracy, ScenarioInstantiation, Sim, SimCallback, SimOptions, WarmStart,
 };
+pub(crate) use self::subscriptions::Subscriptions;
+pub use self::subscriptions::{SimEvent, SimEventType, SubscriptionID};
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::{CommutersVehiclesCounts, Person, PersonState, TripInfo, TripResult};
 pub(crate) use self::trips::{TripLeg, TripManager};
 pub use synthpop::make::{fork_rng, BorderSpawnOverTime, ScenarioGenerator, SpawnOverTime};
 
 mod analytics;
+mod breakpoints;
 mod events;
+mod gridlock;
 mod make;
 mod mechanics;
+mod metrics;
+mod multirun;
 mod pandemic;
 pub mod prebake;
 mod recorder;
+mod region;
 mod render;
 mod router;
 mod scheduler;
+mod scripting;
 mod sim;
+mod subscriptions;
 mod transit;
 mod trips;
 
@@ -76,6 +105,7 @@ pub(crate) const MIN_CAR_LENGTH: Distance = Distance::const_meters(4.5);
 pub(crate) const MAX_CAR_LENGTH: Distance = Distance::const_meters(6.5);
 // Note this is more than MAX_CAR_LENGTH
 pub(crate) const BUS_LENGTH: Distance = Distance::const_meters(12.5);
+pub(crate) const ARTICULATED_BUS_LENGTH: Distance = Distance::const_meters(18.0);
 pub(crate) const LIGHT_RAIL_LENGTH: Distance = Distance::const_meters(60.0);
 
 /// At all speeds (including at rest), cars must be at least this far apart, measured from front of
@@ -317,13 +347,31 @@ pub struct Vehicle {
     pub id: CarID,
     pub owner: Option<PersonID>,
     pub vehicle_type: VehicleType,
+    pub class: VehicleClass,
     pub length: Distance,
     pub max_speed: Option<Speed>,
 }
 
+impl Vehicle {
+    /// The simulation doesn't model acceleration; vehicles instantly reach their speed. To make
+    /// heavy vehicles clear intersections more slowly, this much time is added to a turn after
+    /// waiting at the front of a queue.
+    pub(crate) fn startup_delay(&self) -> Duration {
+        match self.class {
+            VehicleClass::SmallCar | VehicleClass::Car | VehicleClass::Bus => Duration::ZERO,
+            VehicleClass::Bike | VehicleClass::Train => Duration::ZERO,
+            VehicleClass::Van => Duration::seconds(0.5),
+            VehicleClass::CargoBike => Duration::seconds(1.0),
+            VehicleClass::ArticulatedBus => Duration::seconds(1.5),
+            VehicleClass::Hgv => Duration::seconds(2.0),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct VehicleSpec {
     pub vehicle_type: VehicleType,
+    pub class: VehicleClass,
     pub length: Distance,
     pub max_speed: Option<Speed>,
 }
@@ -335,6 +383,7 @@ impl VehicleSpec {
             id,
             owner,
             vehicle_type: self.vehicle_type,
+            class: self.class,
             length: self.length,
             max_speed: self.max_speed,
         }
@@ -385,13 +434,24 @@ impl DrivingGoal {
         }
     }
 
-    pub fn make_router(&self, owner: CarID, path: Path, map: &Map) -> Router {
+    /// Delivery vehicles prefer loading bays and double-park when there are none free. Vehicles
+    /// dropping somebody off stop for `drop_off` and leave, instead of parking.
+    pub fn make_router(
+        &self,
+        owner: CarID,
+        path: Path,
+        map: &Map,
+        delivery: bool,
+        drop_off: Option<Duration>,
+    ) -> Router {
         match self {
             DrivingGoal::ParkNear(b) => {
                 if owner.vehicle_type == VehicleType::Bike {
                     Router::bike_then_stop(owner, path, SidewalkSpot::bike_rack(*b, map).unwrap())
+                } else if let Some(dwell_time) = drop_off {
+                    Router::drop_off(owner, path, dwell_time)
                 } else {
-                    Router::park_near(owner, path, *b)
+                    Router::park_near(owner, path, *b, delivery)
                 }
             }
             DrivingGoal::Border(i, last_lane) => {
@@ -574,6 +634,10 @@ pub(crate) struct CreateCar {
     /// None for buses
     pub trip_and_person: Option<(TripID, PersonID)>,
     pub maybe_route: Option<TransitRouteID>,
+    /// Cents per hour the driver would pay to save time. Zero for buses.
+    pub value_of_time: u32,
+    /// How many people are in a car, including the driver. Bus passengers aren't counted here.
+    pub occupancy: usize,
 }
 
 impl CreateCar {
@@ -582,6 +646,8 @@ impl CreateCar {
         router: Router,
         trip: TripID,
         person: PersonID,
+        value_of_time: u32,
+        occupancy: usize,
     ) -> CreateCar {
         CreateCar {
             vehicle,
@@ -589,6 +655,8 @@ impl CreateCar {
             maybe_parked_car: None,
             trip_and_person: Some((trip, person)),
             maybe_route: None,
+            value_of_time,
+            occupancy,
         }
     }
 
@@ -598,6 +666,8 @@ impl CreateCar {
         router: Router,
         trip: TripID,
         person: PersonID,
+        value_of_time: u32,
+        occupancy: usize,
     ) -> CreateCar {
         CreateCar {
             vehicle: parked_car.vehicle.clone(),
@@ -605,6 +675,8 @@ impl CreateCar {
             maybe_parked_car: Some(parked_car),
             trip_and_person: Some((trip, person)),
             maybe_route: None,
+            value_of_time,
+            occupancy,
         }
     }
 }
@@ -32,6 +32,10 @@ pub struct SimFlags {
     // TODO default_value can only handle strings, so copying SimFlags::RNG_SEED
     #[structopt(long, default_value = "42")]
     pub rng_seed: u64,
+    /// When loading a scenario, start with cars parked wherever they were at the end of a previous
+    /// run, as saved in this file.
+    #[structopt(long)]
+    pub warm_start: Option<String>,
     #[structopt(flatten)]
     pub opts: SimOptions,
 }
@@ -61,6 +65,7 @@ impl SimFlags {
             load: MapName::seattle("montlake").path(),
             scenario_modifiers: Vec::new(),
             rng_seed: SimFlags::RNG_SEED,
+            warm_start: None,
             opts: SimOptions::new(run_name),
         }
     }
@@ -115,6 +120,9 @@ impl SimFlags {
                 opts.run_name = scenario.scenario_name.clone();
             }
             let mut sim = Sim::new(&map, opts);
+            if let Some(ref path) = self.warm_start {
+                sim.set_warm_start(abstio::must_read_object(path.clone(), timer));
+            }
             sim.instantiate(&scenario, &map, &mut rng, timer);
 
             (map, sim, rng)
@@ -1,7 +1,7 @@
 //! Everything needed to setup a simulation.
 
 pub use self::load::SimFlags;
-pub(crate) use self::spawner::{StartTripArgs, TripSpec};
+pub(crate) use self::spawner::{maybe_park_and_ride, ParkAndRideLeg, StartTripArgs, TripSpec};
 
 mod load;
 mod spawner;
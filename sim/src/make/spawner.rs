@@ -5,9 +5,12 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use map_model::{BuildingID, Map, PathConstraints, Position, TransitRouteID, TransitStopID};
-use synthpop::{TripEndpoint, TripMode};
+use synthpop::{ParkAndRide, TripEndpoint, TripMode};
 
-use crate::{CarID, DrivingGoal, SidewalkSpot, TripLeg, VehicleType, SPAWN_DIST};
+use crate::{
+    CarID, DrivingGoal, ParkingSim, ParkingSimState, ParkingSpot, SidewalkSpot, TripLeg,
+    VehicleType, SPAWN_DIST,
+};
 
 /// We need to remember a few things from scenario instantiation that're used for starting the
 /// trip.
@@ -28,6 +31,14 @@ pub(crate) enum TripSpec {
         use_vehicle: CarID,
         retry_if_no_room: bool,
     },
+    /// A taxi or somebody else's car picks the person up in front of a building.
+    PickedUp {
+        start_bldg: BuildingID,
+        start_pos: Position,
+        goal: DrivingGoal,
+        /// This must be a currently off-map vehicle owned by the person.
+        use_vehicle: CarID,
+    },
     /// Something went wrong spawning the trip.
     SpawningFailure {
         use_vehicle: Option<CarID>,
@@ -55,6 +66,78 @@ pub(crate) enum TripSpec {
         stop1: TransitStopID,
         maybe_stop2: Option<TransitStopID>,
     },
+    /// Drive to a park-and-ride facility, then continue to the goal.
+    ParkAndRide {
+        /// This must be a currently parked vehicle owned by the person.
+        car: CarID,
+        start_bldg: BuildingID,
+        facility: BuildingID,
+        onward: ParkAndRideLeg,
+        goal: BuildingID,
+    },
+    /// Get back to a car left at a park-and-ride facility, then drive the rest of the way.
+    ReturnFromParkAndRide {
+        car: CarID,
+        start_bldg: BuildingID,
+        facility: BuildingID,
+        onward: ParkAndRideLeg,
+        goal: DrivingGoal,
+    },
+}
+
+/// How somebody gets between a park-and-ride facility and the other end of their trip
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) enum ParkAndRideLeg {
+    Transit {
+        route: TransitRouteID,
+        stop1: TransitStopID,
+        stop2: TransitStopID,
+    },
+    Bike(CarID),
+}
+
+impl ParkAndRideLeg {
+    pub fn mode(&self) -> TripMode {
+        match self {
+            ParkAndRideLeg::Transit { .. } => TripMode::Transit,
+            ParkAndRideLeg::Bike(_) => TripMode::Bike,
+        }
+    }
+
+    /// Find a way from one building to another, by transit if possible, otherwise by bike.
+    fn between(
+        config: &ParkAndRide,
+        from: BuildingID,
+        to: BuildingID,
+        bike: Option<CarID>,
+        map: &Map,
+    ) -> Option<ParkAndRideLeg> {
+        let start = SidewalkSpot::building(from, map);
+        let end = SidewalkSpot::building(to, map);
+        if let Some((stop1, Some(stop2), route)) =
+            map.should_use_transit(start.sidewalk_pos, end.sidewalk_pos)
+        {
+            return Some(ParkAndRideLeg::Transit {
+                route,
+                stop1,
+                stop2,
+            });
+        }
+        let bike = bike?;
+        let from_rack = SidewalkSpot::bike_rack(from, map)?;
+        let to_rack = SidewalkSpot::bike_rack(to, map)?;
+        if from_rack.sidewalk_pos.lane() == to_rack.sidewalk_pos.lane()
+            || map
+                .get_b(from)
+                .polygon
+                .center()
+                .dist_to(map.get_b(to).polygon.center())
+                > config.max_bike_distance
+        {
+            return None;
+        }
+        Some(ParkAndRideLeg::Bike(bike))
+    }
 }
 
 impl TripSpec {
@@ -101,6 +184,14 @@ impl TripSpec {
                     .into_plan(map);
                 }
             }
+            TripSpec::PickedUp {
+                goal, use_vehicle, ..
+            } => {
+                legs.push(TripLeg::Drive(*use_vehicle, goal.clone()));
+                if let DrivingGoal::ParkNear(b) = goal {
+                    legs.push(TripLeg::Walk(SidewalkSpot::building(*b, map)));
+                }
+            }
             TripSpec::SpawningFailure { .. } => {
                 // TODO The legs are a lie. Since the trip gets cancelled, this doesn't matter.
                 // I'm not going to bother doing better because I think TripLeg will get
@@ -201,19 +292,79 @@ impl TripSpec {
                     legs = vec![TripLeg::Walk(walk_to), TripLeg::RideBus(*route, None)];
                 }
             }
+            TripSpec::ParkAndRide {
+                car,
+                facility,
+                onward,
+                goal,
+                ..
+            } => {
+                legs.push(TripLeg::Walk(SidewalkSpot::deferred_parking_spot()));
+                legs.push(TripLeg::Drive(*car, DrivingGoal::ParkNear(*facility)));
+                match onward {
+                    ParkAndRideLeg::Transit {
+                        route,
+                        stop1,
+                        stop2,
+                    } => {
+                        legs.push(TripLeg::Walk(SidewalkSpot::bus_stop(*stop1, map)));
+                        legs.push(TripLeg::RideBus(*route, Some(*stop2)));
+                    }
+                    ParkAndRideLeg::Bike(bike) => {
+                        // ParkAndRideLeg::between checked this exists
+                        legs.push(TripLeg::Walk(
+                            SidewalkSpot::bike_rack(*facility, map).unwrap(),
+                        ));
+                        legs.push(TripLeg::Drive(*bike, DrivingGoal::ParkNear(*goal)));
+                    }
+                }
+                legs.push(TripLeg::Walk(SidewalkSpot::building(*goal, map)));
+            }
+            TripSpec::ReturnFromParkAndRide {
+                car,
+                start_bldg,
+                facility,
+                onward,
+                goal,
+            } => {
+                match onward {
+                    ParkAndRideLeg::Transit {
+                        route,
+                        stop1,
+                        stop2,
+                    } => {
+                        legs.push(TripLeg::Walk(SidewalkSpot::bus_stop(*stop1, map)));
+                        legs.push(TripLeg::RideBus(*route, Some(*stop2)));
+                    }
+                    ParkAndRideLeg::Bike(bike) => {
+                        legs.push(TripLeg::Walk(
+                            SidewalkSpot::bike_rack(*start_bldg, map).unwrap(),
+                        ));
+                        legs.push(TripLeg::Drive(*bike, DrivingGoal::ParkNear(*facility)));
+                    }
+                }
+                // Where exactly the car is gets looked up once the person gets close
+                legs.push(TripLeg::Walk(SidewalkSpot::deferred_parking_spot()));
+                legs.push(TripLeg::Drive(*car, goal.clone()));
+                if let DrivingGoal::ParkNear(b) = goal {
+                    legs.push(TripLeg::Walk(SidewalkSpot::building(*b, map)));
+                }
+            }
         };
 
         (self, legs)
     }
 
     /// Turn an origin/destination pair and mode into a specific plan for instantiating a trip.
-    /// Decisions like how to use public transit happen here.
+    /// Decisions like how to use public transit happen here. Driving trips that're `picked_up`
+    /// start in front of the building, not from a parked car.
     pub fn maybe_new(
         from: TripEndpoint,
         to: TripEndpoint,
         mode: TripMode,
         use_vehicle: Option<CarID>,
         retry_if_no_room: bool,
+        picked_up: bool,
         map: &Map,
     ) -> Result<TripSpec> {
         Ok(match mode {
@@ -226,7 +377,19 @@ impl TripSpec {
                 let goal = driving_goal(to, constraints, map)?;
                 match from {
                     TripEndpoint::Building(start_bldg) => {
-                        if mode == TripMode::Drive {
+                        if mode == TripMode::Drive && picked_up {
+                            let start_pos = map
+                                .get_b(start_bldg)
+                                .driving_connection(map)
+                                .ok_or_else(|| anyhow!("can't be picked up at {}", start_bldg))?
+                                .0;
+                            TripSpec::PickedUp {
+                                start_bldg,
+                                start_pos,
+                                goal,
+                                use_vehicle: use_vehicle.unwrap(),
+                            }
+                        } else if mode == TripMode::Drive {
                             TripSpec::UsingParkedCar {
                                 start_bldg,
                                 goal,
@@ -292,6 +455,83 @@ impl TripSpec {
     }
 }
 
+/// Decide if a driving trip leaving a building should use a park-and-ride facility. If the car is
+/// parked at a facility away from the start, the person first has to get back to it. Otherwise,
+/// pick the facility with free spots that's the least out of the way. Returns `None` to drive the
+/// normal way.
+pub(crate) fn maybe_park_and_ride(
+    config: &ParkAndRide,
+    start_bldg: BuildingID,
+    to: TripEndpoint,
+    car: CarID,
+    bike: Option<CarID>,
+    car_spot: Option<ParkingSpot>,
+    parking: &ParkingSimState,
+    map: &Map,
+) -> Option<TripSpec> {
+    if config.is_empty() {
+        return None;
+    }
+
+    if let Some(ParkingSpot::Offstreet(facility, _)) = car_spot {
+        if config.facilities.contains(&facility) && facility != start_bldg {
+            let onward = ParkAndRideLeg::between(config, start_bldg, facility, bike, map)?;
+            let goal = driving_goal(to, PathConstraints::Car, map).ok()?;
+            return Some(TripSpec::ReturnFromParkAndRide {
+                car,
+                start_bldg,
+                facility,
+                onward,
+                goal,
+            });
+        }
+    }
+
+    let goal = match to {
+        TripEndpoint::Building(b) => b,
+        _ => {
+            return None;
+        }
+    };
+    // Only bother when the car is at home, not parked somewhere unusual
+    if car_spot
+        .map(|spot| {
+            parking
+                .spot_to_sidewalk_pos(spot, map)
+                .pt(map)
+                .dist_to(map.get_b(start_bldg).label_center)
+                > config.min_trip_distance / 2.0
+        })
+        .unwrap_or(true)
+    {
+        return None;
+    }
+    let mut candidates: Vec<(f64, BuildingID)> = config
+        .facilities
+        .iter()
+        .filter_map(|facility| {
+            let detour = config.detour(map, start_bldg, goal, *facility)?;
+            if parking.get_free_offstreet_spots(*facility).is_empty() {
+                return None;
+            }
+            Some((detour, *facility))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    for (_, facility) in candidates {
+        if let Some(onward) = ParkAndRideLeg::between(config, facility, goal, bike, map) {
+            return Some(TripSpec::ParkAndRide {
+                car,
+                start_bldg,
+                facility,
+                onward,
+                goal,
+            });
+        }
+    }
+    None
+}
+
 fn start_sidewalk_spot(endpt: TripEndpoint, map: &Map) -> Result<SidewalkSpot> {
     match endpt {
         TripEndpoint::Building(b) => Ok(SidewalkSpot::building(b, map)),
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::{Distance, Duration};
+use map_model::{BuildingID, Map};
+
+use crate::CarID;
+
+/// How long somebody spends looking for somewhere to lock up their bike, when their destination
+/// has no free bike parking
+pub(crate) const BIKE_PARKING_SEARCH_TIME: Duration = Duration::const_seconds(90.0);
+/// When a destination's bike parking is full, cyclists will use free parking at buildings up to
+/// this far away, then walk the rest of the way
+const MAX_OVERFLOW_DIST: Distance = Distance::const_meters(300.0);
+
+/// Tracks bikes locked up at buildings, limited by `Building::bike_parking`. People keep bikes
+/// inside their homes, so buildings with residents have unlimited capacity. When a cyclist's
+/// destination is full, they park at a nearby building with free spots and walk the rest of the
+/// way, or if there's nothing nearby, spend some time locking up their bike informally.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct BikeParking {
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    parked: BTreeMap<CarID, BuildingID>,
+    occupied: BTreeMap<BuildingID, usize>,
+}
+
+impl BikeParking {
+    /// Does this building have space for one more bike?
+    pub fn has_free_spot(&self, b: BuildingID, map: &Map) -> bool {
+        let bldg = map.get_b(b);
+        bldg.bldg_type.has_residents() || self.get_occupied(b) < bldg.bike_parking
+    }
+
+    /// How many bikes currently use a building's bike parking. Doesn't count bikes kept inside
+    /// homes. Map edits may leave this higher than the current capacity.
+    pub fn get_occupied(&self, b: BuildingID) -> usize {
+        self.occupied.get(&b).cloned().unwrap_or(0)
+    }
+
+    /// The closest building to `goal` with free bike parking that bikes can reach
+    pub fn find_overflow(&self, goal: BuildingID, map: &Map) -> Option<BuildingID> {
+        let pt = map.get_b(goal).polygon.center();
+        map.all_buildings()
+            .iter()
+            .filter(|b| b.id != goal && b.bike_parking > self.get_occupied(b.id))
+            .map(|b| (b, b.polygon.center().dist_to(pt)))
+            .filter(|(b, dist)| *dist <= MAX_OVERFLOW_DIST && b.biking_connection(map).is_some())
+            .min_by_key(|(_, dist)| *dist)
+            .map(|(b, _)| b.id)
+    }
+
+    /// A bike arrived at a building. Returns how long it takes to lock up the bike, if there was
+    /// no free spot.
+    pub fn park(&mut self, bike: CarID, b: BuildingID, map: &Map) -> Duration {
+        if map.get_b(b).bldg_type.has_residents() {
+            return Duration::ZERO;
+        }
+        if !self.has_free_spot(b, map) {
+            // The bike is locked up somewhere informal, not taking one of the spots
+            return BIKE_PARKING_SEARCH_TIME;
+        }
+        self.parked.insert(bike, b);
+        *self.occupied.entry(b).or_insert(0) += 1;
+        Duration::ZERO
+    }
+
+    /// The bike is leaving. If it was parked somewhere besides `start`, but close enough to walk,
+    /// returns that building.
+    pub fn unpark(&mut self, bike: CarID, start: BuildingID, map: &Map) -> Option<BuildingID> {
+        let b = self.parked.remove(&bike)?;
+        if let Some(cnt) = self.occupied.get_mut(&b) {
+            *cnt -= 1;
+            if *cnt == 0 {
+                self.occupied.remove(&b);
+            }
+        }
+        // If the trip that left the bike here was cancelled, the person may have been teleported
+        // somewhere far away; they can just use the bike they have with them
+        if b != start
+            && map
+                .get_b(b)
+                .polygon
+                .center()
+                .dist_to(map.get_b(start).polygon.center())
+                <= 2.0 * MAX_OVERFLOW_DIST
+        {
+            Some(b)
+        } else {
+            None
+        }
+    }
+}
@@ -1,13 +1,15 @@
 use std::collections::{BTreeSet, VecDeque};
 
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
-use geom::{Distance, Duration, PolyLine, Time, EPSILON_DIST};
-use map_model::{Direction, LaneID, Map, Traversable};
+use geom::{Distance, Duration, PolyLine, Speed, Time, EPSILON_DIST};
+use map_model::{Direction, LaneID, Map, PathStep, RoadID, Traversable};
 
 use crate::{
-    CarID, CarStatus, DistanceInterval, DrawCarInput, Intent, ParkingSpot, PersonID, Router,
-    TimeInterval, TransitSimState, TripID, Vehicle, VehicleType,
+    CarID, CarStatus, DistanceInterval, DrawCarInput, Intent, LaneChangeReason, ParkingSpot,
+    PersonID, Router, TimeInterval, TransitSimState, TripID, Vehicle, VehicleType,
 };
 
 /// Represents a single vehicle. Note "car" is a misnomer; it could also be a bus or bike.
@@ -29,6 +31,13 @@ pub(crate) struct Car {
     /// Since lane over-taking isn't implemented yet, a vehicle tends to be stuck behind a slow
     /// leader for a while. Avoid duplicate events.
     pub wants_to_overtake: BTreeSet<CarID>,
+
+    /// If this driver habitually speeds, how many times the speed limit they go
+    pub speeding: Option<f64>,
+    /// Cents per hour the driver would pay to save time
+    pub value_of_time: u32,
+    /// How many people are in the car, including the driver
+    pub occupancy: usize,
 }
 
 impl Car {
@@ -56,15 +65,17 @@ impl Car {
         start_time: Time,
         map: &Map,
     ) -> CarState {
-        let (speed, percent_incline) = self
-            .router
-            .get_path()
-            .current_step()
-            .max_speed_and_incline_along(
-                self.vehicle.max_speed,
-                self.vehicle.vehicle_type.to_constraints(),
-                map,
-            );
+        let step = self.router.get_path().current_step();
+        let (mut speed, percent_incline) = step.max_speed_and_incline_along(
+            self.vehicle.max_speed,
+            self.vehicle.vehicle_type.to_constraints(),
+            map,
+        );
+        if let (Some(factor), PathStep::Lane(l)) = (self.speeding, step) {
+            if self.keeps_speeding(l.road, map) {
+                speed = map.get_r(l.road).speeding_speed(factor);
+            }
+        }
         let dt = (dist_int.end - dist_int.start) / speed;
         CarState::Crossing {
             time_int: TimeInterval::new(start_time, start_time + dt),
@@ -73,6 +84,38 @@ impl Car {
         }
     }
 
+    /// Does a habitual speeder ignore the speed enforcement along this road? The same driver always
+    /// decides the same way along one road.
+    pub fn keeps_speeding(&self, r: RoadID, map: &Map) -> bool {
+        let pct = map.get_r(r).speed_enforcement.compliance_pct();
+        if pct == 0 {
+            return true;
+        }
+        let mut rng =
+            XorShiftRng::seed_from_u64(((self.vehicle.id.id as u64) << 32) | (r.0 as u64));
+        rng.gen_range(0..100) >= pct
+    }
+
+    /// Entering this lane, does the driver owe a toll or pay for a priced lane? Returns the price
+    /// and whether it's for a priced lane. Only cars pay, and cars allowed in HOV lanes use priced
+    /// lanes for free.
+    pub fn toll_due(&self, l: LaneID, now: Time, map: &Map) -> Option<(u32, bool)> {
+        if self.vehicle.vehicle_type != VehicleType::Car {
+            return None;
+        }
+        let pricing = &map.get_r(l.road).pricing;
+        let toll = pricing.toll_at(now);
+        if toll > 0 {
+            return Some((toll, false));
+        }
+        if map.get_l(l).is_bus() && !map.get_r(l.road).allows_hov(self.occupancy) {
+            if let Some(price) = pricing.priced_lane_at(now).filter(|price| *price > 0) {
+                return Some((price, true));
+            }
+        }
+        None
+    }
+
     pub fn get_draw_car(
         &self,
         front: Distance,
@@ -337,7 +380,7 @@ pub(crate) enum CarState {
     },
     Queued {
         blocked_since: Time,
-        want_to_change_lanes: Option<LaneID>,
+        want_to_change_lanes: Option<(LaneID, LaneChangeReason)>,
     },
     WaitingToAdvance {
         blocked_since: Time,
@@ -367,6 +410,42 @@ impl CarState {
         }
     }
 
+    /// Stretch out a crossing to account for slow acceleration.
+    pub fn add_startup_delay(&mut self, delay: Duration) {
+        if let CarState::Crossing {
+            ref mut time_int, ..
+        } = self
+        {
+            if delay > Duration::ZERO {
+                *time_int = TimeInterval::new(time_int.start, time_int.end + delay);
+            }
+        }
+    }
+
+    /// How fast is the car currently moving?
+    pub fn speed(&self) -> Speed {
+        let (time_int, dist_int) = match self {
+            CarState::Crossing {
+                ref time_int,
+                ref dist_int,
+                ..
+            } => (time_int, dist_int),
+            CarState::ChangingLanes {
+                ref new_time,
+                ref new_dist,
+                ..
+            } => (new_time, new_dist),
+            _ => {
+                return Speed::ZERO;
+            }
+        };
+        let dt = time_int.end - time_int.start;
+        if dt == Duration::ZERO {
+            return Speed::ZERO;
+        }
+        Speed::from_dist_time(dist_int.end - dist_int.start, dt)
+    }
+
     pub fn time_spent_waiting(&self, now: Time) -> Duration {
         match self {
             CarState::Queued { blocked_since, .. }
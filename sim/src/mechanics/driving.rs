@@ -1,22 +1,28 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_hashmap, serialize_hashmap, FixedMap, IndexableKey};
-use geom::{Distance, Duration, PolyLine, Time};
-use map_model::{DrivingSide, IntersectionID, LaneID, Map, Path, PathStep, Position, Traversable};
+use geom::{Distance, Duration, PolyLine, Speed, Time};
+use map_model::{
+    DrivingSide, IntersectionID, LaneID, Map, Path, PathStep, Position, SpeedEnforcement,
+    Traversable,
+};
 
 use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::queue::{Queue, QueueEntry, Queued};
 use crate::sim::Ctx;
 use crate::{
     ActionAtEnd, AgentID, AgentProperties, CarID, CarStatus, Command, CreateCar, DelayCause,
-    DistanceInterval, DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim,
-    ParkingSpot, PersonID, Problem, SimOptions, TimeInterval, TransitSimState, TripID, TripManager,
-    UnzoomedAgent, Vehicle, VehicleType, WalkingSimState, FOLLOWING_DISTANCE, MAX_CAR_LENGTH,
+    DistanceInterval, DrawCarInput, Event, IntersectionSimState, LaneChangeReason,
+    LaneChangingOpts, ParkedCar, ParkingSim, ParkingSpot, PersonID, Problem, SimOptions,
+    TimeInterval, TransitSimState, TripID, TripManager, UnzoomedAgent, Vehicle, VehicleType,
+    WalkingSimState, FOLLOWING_DISTANCE, MAX_CAR_LENGTH,
 };
 
-const TIME_TO_WAIT_AT_BUS_STOP: Duration = Duration::const_seconds(10.0);
+const TIME_TO_DELIVER_WHILE_DOUBLE_PARKED: Duration = Duration::const_seconds(120.0);
 const TIME_TO_CHANGE_LANES: Duration = Duration::const_seconds(1.0);
 
 // TODO Do something else.
@@ -45,6 +51,10 @@ pub(crate) struct DrivingSimState {
 
     recalc_lanechanging: bool,
     handle_uber_turns: bool,
+    bus_lane_violation_pct: u8,
+    speeding_pct: u8,
+    speeding_over_limit_pct: u8,
+    lane_changing: LaneChangingOpts,
 
     time_to_unpark_onstreet: Duration,
     time_to_park_onstreet: Duration,
@@ -61,6 +71,10 @@ impl DrivingSimState {
             events: Vec::new(),
             recalc_lanechanging: !opts.dont_recalc_lanechanging,
             handle_uber_turns: !opts.dont_handle_uber_turns,
+            bus_lane_violation_pct: opts.bus_lane_violation_pct,
+            speeding_pct: opts.speeding_pct,
+            speeding_over_limit_pct: opts.speeding_over_limit_pct,
+            lane_changing: opts.lane_changing.clone(),
             waiting_to_spawn: BTreeMap::new(),
 
             time_to_unpark_onstreet: Duration::seconds(10.0),
@@ -174,10 +188,12 @@ impl DrivingSimState {
         if let Some(idx) = self.queues[&Traversable::Lane(first_lane)].get_idx_to_insert_car(
             start_dist,
             params.vehicle.length,
+            false,
             now,
             &self.cars,
             &self.queues,
         ) {
+            let speeding = self.pick_speeding(&params.vehicle);
             let mut car = Car {
                 vehicle: params.vehicle,
                 router: params.router,
@@ -191,6 +207,9 @@ impl DrivingSimState {
                 total_blocked_time: Duration::ZERO,
                 trip_and_person: params.trip_and_person,
                 wants_to_overtake: BTreeSet::new(),
+                speeding,
+                value_of_time: params.value_of_time,
+                occupancy: params.occupancy,
             };
             let mut start_crossing = false;
             if let Some(p) = params.maybe_parked_car {
@@ -300,7 +319,7 @@ impl DrivingSimState {
     ///
     /// Crossing -> Queued or WaitingToAdvance
     /// Unparking -> Crossing
-    /// IdlingAtStop -> Crossing
+    /// IdlingAtStop -> Crossing, Queued after double-parking, or done after a drop-off
     /// Queued -> last step handling (Parking or done)
     /// WaitingToAdvance -> try to advance to the next step of the path
     /// Parking -> done
@@ -328,7 +347,8 @@ impl DrivingSimState {
         let mut need_distances = {
             let car = &self.cars[&id];
             match car.state {
-                CarState::Queued { .. } => car.router.last_step(),
+                // Queued cars are only updated on their last step, or to retry changing lanes
+                CarState::Queued { .. } => true,
                 CarState::Parking(_, _, _) => true,
                 CarState::IdlingAtStop(_, _) => true,
                 _ => false,
@@ -399,9 +419,19 @@ impl DrivingSimState {
                             &self.queues,
                             ctx.map,
                             self.handle_uber_turns,
+                            self.bus_lane_violation_pct,
+                            now,
+                            car.value_of_time,
+                            car.occupancy,
                         );
                     }
                     ctx.scheduler.push(now, Command::UpdateCar(car.vehicle.id));
+                } else if let Some(target_lane) = self.wants_to_pass_blockage(car, ctx.map) {
+                    car.state = CarState::Queued {
+                        blocked_since: now,
+                        want_to_change_lanes: Some((target_lane, LaneChangeReason::Mandatory)),
+                    };
+                    return true;
                 } else if let Some(slow_leader) = self.wants_to_overtake(car) {
                     // TODO This entire check kicks in a little late; we only enter Queued after
                     // spending the freeflow time possibly moving very slowly.
@@ -424,10 +454,18 @@ impl DrivingSimState {
                         // update_car_with_distances.
                         car.state = CarState::Queued {
                             blocked_since: now,
-                            want_to_change_lanes: Some(target_lane),
+                            want_to_change_lanes: Some((target_lane, LaneChangeReason::Overtaking)),
                         };
                         return true;
                     }
+                } else if self.lane_changing.lc_discretionary_gain > 0
+                    && !self.lanechange_candidates(car, ctx.map).is_empty()
+                {
+                    // If we're still stuck here after a while, consider switching lanes
+                    ctx.scheduler.update(
+                        now + self.lane_changing.lc_patience,
+                        Command::UpdateCar(car.vehicle.id),
+                    );
                 }
             }
             CarState::Unparking {
@@ -533,14 +571,39 @@ impl DrivingSimState {
                 );
                 car.total_blocked_time += now - blocked_since;
                 car.state = car.crossing_state(Distance::ZERO, now, ctx.map);
+                if now > blocked_since {
+                    // Pulling away from a stop
+                    car.state.add_startup_delay(car.vehicle.startup_delay());
+                }
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                if let Traversable::Lane(l) = goto {
+                    if let Some((cents, priced_lane)) = car.toll_due(l, now, ctx.map) {
+                        self.events.push(Event::PaidToll {
+                            car: car.vehicle.id,
+                            road: l.road,
+                            cents,
+                            priced_lane,
+                        });
+                    }
+                }
+                if let (Traversable::Lane(l), Some(_)) = (goto, car.speeding) {
+                    if ctx.map.get_r(l.road).speed_enforcement != SpeedEnforcement::None {
+                        self.events.push(Event::PassedSpeedEnforcement {
+                            car: car.vehicle.id,
+                            road: l.road,
+                            complied: !car.keeps_speeding(l.road, ctx.map),
+                        });
+                    }
+                }
                 self.events.push(Event::AgentEntersTraversable(
                     AgentID::Car(car.vehicle.id),
                     car.trip_and_person.map(|(t, _)| t),
                     goto,
                     if car.vehicle.vehicle_type.is_transit() {
                         Some(transit.get_passengers(car.vehicle.id).len())
+                    } else if car.occupancy > 1 {
+                        Some(car.occupancy - 1)
                     } else {
                         None
                     },
@@ -633,8 +696,46 @@ impl DrivingSimState {
             } => {
                 // Two totally different reasons we'll wind up here: we want to lane-change, and
                 // we're on our last step.
-                if let Some(target_lane) = want_to_change_lanes {
-                    self.try_start_lc(car, our_dist, idx, target_lane, now, ctx);
+                if !car.router.last_step() {
+                    let want = want_to_change_lanes.or_else(|| {
+                        self.pick_discretionary_lane(car, our_dist, idx, now, ctx.map)
+                            .map(|l| (l, LaneChangeReason::Discretionary))
+                    });
+                    let changed = match want {
+                        Some((target_lane, reason)) => {
+                            car.state = CarState::Queued {
+                                blocked_since,
+                                want_to_change_lanes: want,
+                            };
+                            // After waiting long enough, somebody in the target lane will brake a
+                            // little to let us in
+                            let courtesy =
+                                now - blocked_since >= self.lane_changing.lc_courtesy_after;
+                            self.try_start_lc(
+                                car,
+                                our_dist,
+                                idx,
+                                target_lane,
+                                reason,
+                                courtesy,
+                                now,
+                                ctx,
+                            )
+                        }
+                        None => false,
+                    };
+                    // If we're still stuck in the queue, try again later. If our leader moves
+                    // first, this is rescheduled.
+                    if !changed
+                        && (want.is_some()
+                            || (self.lane_changing.lc_discretionary_gain > 0
+                                && !self.lanechange_candidates(car, ctx.map).is_empty()))
+                    {
+                        ctx.scheduler.update(
+                            now + self.lane_changing.lc_patience,
+                            Command::UpdateCar(car.vehicle.id),
+                        );
+                    }
                     return true;
                 }
 
@@ -700,6 +801,28 @@ impl DrivingSimState {
                         self.new_crossing_state(ctx, car);
                         true
                     }
+                    Some(ActionAtEnd::DoublePark) => {
+                        car.total_blocked_time += now - blocked_since;
+                        // Block the lane, just like a bus idling at a stop
+                        car.state = CarState::IdlingAtStop(
+                            our_dist,
+                            TimeInterval::new(now, now + TIME_TO_DELIVER_WHILE_DOUBLE_PARKED),
+                        );
+                        ctx.scheduler
+                            .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                        true
+                    }
+                    Some(ActionAtEnd::DropOffInLane(dwell_time)) => {
+                        car.total_blocked_time += now - blocked_since;
+                        // Also blocks the lane
+                        car.state = CarState::IdlingAtStop(
+                            our_dist,
+                            TimeInterval::new(now, now + dwell_time),
+                        );
+                        ctx.scheduler
+                            .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                        true
+                    }
                     Some(ActionAtEnd::StopBiking(bike_rack)) => {
                         car.total_blocked_time += now - blocked_since;
                         trips.bike_reached_end(
@@ -714,10 +837,12 @@ impl DrivingSimState {
                     }
                     Some(ActionAtEnd::BusAtStop) => {
                         car.total_blocked_time += now - blocked_since;
-                        if transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx) {
+                        if let Some(dwell) =
+                            transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx)
+                        {
                             car.state = CarState::IdlingAtStop(
                                 our_dist,
-                                TimeInterval::new(now, now + TIME_TO_WAIT_AT_BUS_STOP),
+                                TimeInterval::new(now, now + dwell),
                             );
                             ctx.scheduler
                                 .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
@@ -756,6 +881,20 @@ impl DrivingSimState {
                     }
                 }
             }
+            CarState::Parking(_, spot, _) if car.router.is_drop_off() => {
+                // Pulled into a pick-up/drop-off bay. The vehicle leaves right away, so the bay is
+                // free again.
+                ctx.parking.unreserve_spot(car.vehicle.id);
+                trips.car_dropped_off(
+                    now,
+                    car.vehicle.id,
+                    Some(spot),
+                    car.total_blocked_time,
+                    car.router.get_path().total_length(),
+                    ctx,
+                );
+                false
+            }
             CarState::Parking(_, spot, _) => {
                 ctx.parking.add_parked_car(ParkedCar {
                     vehicle: car.vehicle.clone(),
@@ -772,6 +911,26 @@ impl DrivingSimState {
                 );
                 false
             }
+            CarState::IdlingAtStop(_, _) if car.router.is_drop_off() => {
+                trips.car_dropped_off(
+                    now,
+                    car.vehicle.id,
+                    None,
+                    car.total_blocked_time,
+                    car.router.get_path().total_length(),
+                    ctx,
+                );
+                false
+            }
+            CarState::IdlingAtStop(_, _) if car.trip_and_person.is_some() => {
+                // A delivery vehicle finished double-parking. Go back to looking for parking.
+                car.state = CarState::Queued {
+                    blocked_since: now,
+                    want_to_change_lanes: None,
+                };
+                ctx.scheduler.push(now, Command::UpdateCar(car.vehicle.id));
+                true
+            }
             CarState::IdlingAtStop(dist, _) => {
                 car.router = transit.bus_departed_from_stop(car.vehicle.id, ctx.map);
                 self.events
@@ -1079,10 +1238,15 @@ impl DrivingSimState {
                                             &self.queues,
                                             ctx.map,
                                             self.handle_uber_turns,
+                                            self.bus_lane_violation_pct,
+                                            now,
+                                            follower.value_of_time,
+                                            follower.occupancy,
                                         );
                                     }
+                                    // They might have a lane-change retry scheduled
                                     ctx.scheduler
-                                        .push(now, Command::UpdateCar(follower.vehicle.id));
+                                        .update(now, Command::UpdateCar(follower.vehicle.id));
                                 }
                             }
                             CarState::WaitingToAdvance { .. } => unreachable!(),
@@ -1110,8 +1274,19 @@ impl DrivingSimState {
     /// - Prefer passing on the left (for DrivingSide::Right)
     /// For now, just pick one candidate lane, even if both might be usable.
     fn pick_overtaking_lane(&self, car: &Car, map: &Map) -> Option<LaneID> {
-        // Don't overtake in the middle of a turn!
-        let current_lane = map.get_l(car.router.head().maybe_lane()?);
+        self.lanechange_candidates(car, map).into_iter().next()
+    }
+
+    /// Neighboring lanes that we could change into without changing our route, in order of
+    /// preference
+    fn lanechange_candidates(&self, car: &Car, map: &Map) -> Vec<LaneID> {
+        // Don't change lanes in the middle of a turn!
+        let current_lane = match car.router.head() {
+            Traversable::Lane(l) => map.get_l(l),
+            Traversable::Turn(_) => {
+                return Vec::new();
+            }
+        };
         let road = map.get_parent(current_lane.id);
         let idx = current_lane.id.offset;
 
@@ -1126,6 +1301,7 @@ impl DrivingSimState {
             candidates.reverse();
         }
 
+        let mut results = Vec::new();
         for l in candidates {
             let target_lane = map.get_l(l);
             // Must be the same direction -- no crossing into oncoming traffic yet
@@ -1150,10 +1326,47 @@ impl DrivingSimState {
             {
                 continue;
             }
-            return Some(target_lane.id);
+            results.push(target_lane.id);
         }
+        results
+    }
 
-        None
+    /// Look for a neighboring lane with a much shorter queue ahead of us. Outside of the weaving
+    /// section at the end of the lane, drivers switch if it's worth it.
+    fn pick_discretionary_lane(
+        &self,
+        car: &Car,
+        our_dist: Distance,
+        idx_in_current_queue: usize,
+        now: Time,
+        map: &Map,
+    ) -> Option<LaneID> {
+        let gain = self.lane_changing.lc_discretionary_gain;
+        if gain == 0 || idx_in_current_queue < gain {
+            return None;
+        }
+        let current_lane = map.get_l(car.router.head().maybe_lane()?);
+        if our_dist >= self.lane_changing.weaving_section_starts(current_lane, map) {
+            return None;
+        }
+
+        let mut best: Option<(usize, LaneID)> = None;
+        for l in self.lanechange_candidates(car, map) {
+            let dist = Position::new(current_lane.id, our_dist)
+                .equiv_pos(l, map)
+                .dist_along();
+            let num_ahead = self.queues[&Traversable::Lane(l)]
+                .get_car_positions(now, &self.cars, &self.queues)
+                .into_iter()
+                .filter(|entry| entry.back > dist)
+                .count();
+            if num_ahead + gain <= idx_in_current_queue
+                && best.map(|(n, _)| num_ahead < n).unwrap_or(true)
+            {
+                best = Some((num_ahead, l));
+            }
+        }
+        best.map(|(_, l)| l)
     }
 
     fn try_start_lc(
@@ -1162,18 +1375,20 @@ impl DrivingSimState {
         front_current_queue: Distance,
         idx_in_current_queue: usize,
         target_lane: LaneID,
+        reason: LaneChangeReason,
+        courtesy: bool,
         now: Time,
         ctx: &mut Ctx,
-    ) {
+    ) -> bool {
         // If we are a laggy head somewhere else (our back is still sticking into another lane or
         // turn), don't start lane-changing!
         if !car.last_steps.is_empty() {
-            return;
+            return false;
         }
         // If the lanes are very different lengths and we're too close to the end at the target,
         // not going to work.
         if front_current_queue >= ctx.map.get_l(target_lane).length() {
-            return;
+            return false;
         }
         let current_lane = car.router.head().as_lane();
         let front_target_queue = Position::new(current_lane, front_current_queue)
@@ -1197,7 +1412,7 @@ impl DrivingSimState {
         // possible in the target?
         let lc_time = TimeInterval::new(now, now + TIME_TO_CHANGE_LANES);
         if lc_time.end >= new_time.end {
-            return;
+            return false;
         }
 
         // Is there room for us to sliiiide on over into that lane's DMs?
@@ -1205,6 +1420,7 @@ impl DrivingSimState {
             .get_idx_to_insert_car(
                 front_target_queue,
                 car.vehicle.length,
+                courtesy,
                 now,
                 &self.cars,
                 &self.queues,
@@ -1245,6 +1461,15 @@ impl DrivingSimState {
             };
             ctx.scheduler
                 .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+            self.events.push(Event::LaneChanged {
+                car: car.vehicle.id,
+                from: current_lane,
+                to: target_lane,
+                reason,
+            });
+            true
+        } else {
+            false
         }
     }
 
@@ -1269,7 +1494,8 @@ impl DrivingSimState {
         // Delete any old queues.
         self.queues.retain(|k, v| {
             if new_queues.remove(k) {
-                // No changes
+                // The lane might've been extended or shortened
+                v.capacity = Queue::calculate_capacity(*k, map);
                 true
             } else {
                 // Make sure it's empty!
@@ -1291,6 +1517,20 @@ impl DrivingSimState {
         }
     }
 
+    /// Does this driver habitually speed? Only people driving cars do. If so, returns how many
+    /// times the speed limit they go.
+    fn pick_speeding(&self, vehicle: &Vehicle) -> Option<f64> {
+        if vehicle.vehicle_type != VehicleType::Car || self.speeding_pct == 0 {
+            return None;
+        }
+        let mut rng = XorShiftRng::seed_from_u64(vehicle.id.id as u64);
+        if rng.gen_range(0..100) < self.speeding_pct {
+            Some(1.0 + (self.speeding_over_limit_pct as f64) / 100.0)
+        } else {
+            None
+        }
+    }
+
     fn new_crossing_state(&self, ctx: &mut Ctx, car: &Car) {
         if self.queues[&car.router.head()].is_car_at_front(car.vehicle.id) {
             if let Some(Traversable::Turn(turn)) = car.router.maybe_next() {
@@ -1299,6 +1539,19 @@ impl DrivingSimState {
                     turn,
                     car.state.get_end_time(),
                 );
+                if car.vehicle.vehicle_type == VehicleType::Bus {
+                    if let CarState::Crossing { ref time_int, .. } = car.state {
+                        ctx.intersections.maybe_grant_transit_priority(
+                            car.vehicle.id,
+                            turn,
+                            car.total_blocked_time,
+                            time_int.start,
+                            time_int.end,
+                            ctx.map,
+                            ctx.scheduler,
+                        );
+                    }
+                }
             }
         }
     }
@@ -1474,6 +1727,10 @@ impl DrivingSimState {
         }
     }
 
+    pub fn current_speed(&self, id: CarID) -> Option<Speed> {
+        Some(self.cars.get(&id)?.state.speed())
+    }
+
     pub fn agent_properties(&self, id: CarID, now: Time) -> AgentProperties {
         if let Some(car) = self.cars.get(&id) {
             let path = car.router.get_path();
@@ -1603,7 +1860,49 @@ impl DrivingSimState {
 
     pub fn debug_queue_lengths(&self, l: LaneID) -> Option<(Distance, Distance)> {
         let queue = self.queues.get(&Traversable::Lane(l))?;
-        Some((queue.reserved_length, queue.geom_len))
+        Some((queue.reserved_length, queue.capacity))
+    }
+
+    /// For every lane with vehicles stopped at the end of it, how far back from the end does the
+    /// line of stopped vehicles reach? Only vehicles stopped one behind the other from the front
+    /// count; a queue ends at the first moving vehicle.
+    pub fn get_queue_lengths(&self, now: Time) -> BTreeMap<LaneID, Distance> {
+        let mut lengths = BTreeMap::new();
+        for (on, queue) in &self.queues {
+            let l = match on {
+                Traversable::Lane(l) => *l,
+                Traversable::Turn(_) => continue,
+            };
+            let mut back_of_queue = None;
+            for entry in queue.get_car_positions(now, &self.cars, &self.queues) {
+                let stopped = match entry.member {
+                    Queued::Vehicle(id) => matches!(
+                        self.cars[&id].state,
+                        CarState::Queued { .. } | CarState::WaitingToAdvance { .. }
+                    ),
+                    Queued::StaticBlockage { .. } => true,
+                    // Somebody changing lanes is still moving
+                    Queued::DynamicBlockage { .. } => false,
+                };
+                if !stopped {
+                    break;
+                }
+                back_of_queue = Some(entry.back);
+            }
+            if let Some(back) = back_of_queue {
+                lengths.insert(l, (queue.geom_len - back).max(Distance::ZERO));
+            }
+        }
+        lengths
+    }
+
+    /// The intersection that a vehicle is waiting to cross or is crossing right now
+    pub fn get_next_intersection(&self, id: CarID, map: &Map) -> Option<IntersectionID> {
+        let car = self.cars.get(&id)?;
+        Some(match car.router.head() {
+            Traversable::Lane(l) => map.get_l(l).dst_i,
+            Traversable::Turn(t) => t.parent,
+        })
     }
 
     pub fn get_blocked_by_graph(
@@ -1656,13 +1955,25 @@ impl DrivingSimState {
     }
 
     /// Does the given car want to over-take the vehicle in front of it?
+    /// If the leader is stopped for a while -- parking, unparking, or a bus at a stop -- then
+    /// pick a lane to get around them.
+    fn wants_to_pass_blockage(&self, car: &Car, map: &Map) -> Option<LaneID> {
+        let queue = &self.queues[&car.router.head()];
+        let leader = &self.cars[&queue.get_leader(car.vehicle.id)?];
+        match leader.state {
+            CarState::Parking(_, _, _)
+            | CarState::Unparking { .. }
+            | CarState::IdlingAtStop(_, _) => self.pick_overtaking_lane(car, map),
+            _ => None,
+        }
+    }
+
     fn wants_to_overtake(&self, car: &Car) -> Option<CarID> {
         let queue = &self.queues[&car.router.head()];
         let leader = &self.cars[&queue.get_leader(car.vehicle.id)?];
 
         // Are we faster than them?
-        // TODO This shouldn't be a blocking check; we also want to pass parking cars and buses
-        // waiting at stops.
+        // Parking cars and buses waiting at stops are handled by wants_to_pass_blockage.
         let their_speed = leader.vehicle.max_speed?;
         if car
             .vehicle
@@ -1,22 +1,33 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, prettyprint_usize, serialize_btreemap, FixedMap};
 use geom::{Duration, Time};
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, LaneID, Map, StageType,
-    Traversable, TurnID, TurnPriority, TurnType, UberTurn,
+    far_side_turn, Actuation, BikeConflictType, ControlStopSign, ControlTrafficSignal,
+    Intersection, IntersectionID, LaneID, Map, Stage, StageType, Traversable, Turn, TurnID,
+    TurnPriority, TurnType, UberTurn,
 };
 
 use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::{
     AgentID, AlertLocation, CarID, Command, DelayCause, Event, Scheduler, SimOptions, Speed,
+    VehicleType,
 };
 
 const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
+/// A vehicle giving way won't pull out in front of a vehicle on a priority road that arrived at
+/// the intersection less than this long ago.
+const CRITICAL_GAP: Duration = Duration::const_seconds(3.0);
 const WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL: Duration = Duration::const_seconds(0.2);
+/// Level crossing gates close this long before a train is expected. If the train is later than
+/// this, the gates open again, since the prediction must've been wrong.
+const LEVEL_CROSSING_WARNING: Duration = Duration::const_seconds(15.0);
+/// With protected corners or setback crossings, turning vehicles yield to people cycling who've
+/// been waiting less than this long. Past that, the bike is probably stuck for some other reason.
+const MAX_YIELD_TO_BIKES: Duration = Duration::const_seconds(30.0);
 
 /// Manages conflicts at intersections. When an agent has reached the end of a lane, they call
 /// maybe_start_turn to make a Request. Based on the intersection type (stop sign, traffic signal,
@@ -38,6 +49,10 @@ pub(crate) struct IntersectionSimState {
     // structure.
     blocked_by: BTreeSet<(CarID, CarID)>,
     events: Vec<Event>,
+    // Stop signs where a behavior script overrides CRITICAL_GAP. Like the script itself, not
+    // saved.
+    #[serde(skip_serializing, skip_deserializing)]
+    critical_gaps: BTreeMap<IntersectionID, Duration>,
 
     // Count how many calls to maybe_start_turn there are aside from the initial call. Break down
     // failures by those not allowed by the current intersection state vs those blocked by a
@@ -87,8 +102,17 @@ struct SignalState {
     current_stage: usize,
     // The time when the signal is checked for advancing
     stage_ends_at: Time,
+    // When the current stage began, for leading pedestrian intervals
+    stage_started_at: Time,
     // The number of times a variable signal has been extended during the current stage.
     extensions_count: usize,
+    // Has a bus already extended or cut short the current stage?
+    transit_priority_used: bool,
+    // If this signal is part of a cluster run by one controller, the member that decides when
+    // everybody changes stage. None for the controller itself and for independent signals.
+    controller: Option<IntersectionID>,
+    // Only for the controller of a cluster, the other members
+    followers: Vec<IntersectionID>,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Debug)]
@@ -109,6 +133,7 @@ impl IntersectionSimState {
             disable_turn_conflicts: opts.disable_turn_conflicts,
             blocked_by: BTreeSet::new(),
             events: Vec::new(),
+            critical_gaps: BTreeMap::new(),
 
             total_repeat_requests: 0,
             not_allowed_requests: 0,
@@ -131,12 +156,21 @@ impl IntersectionSimState {
             if i.is_traffic_signal() {
                 state.signal = Some(SignalState::new(i.id, Time::START_OF_DAY, map, scheduler));
             }
+            if let Some(ref script) = opts.behavior_script {
+                if i.is_stop_sign() {
+                    let gap = script.critical_gap(i.id, i.roads.len(), CRITICAL_GAP);
+                    if gap != CRITICAL_GAP {
+                        sim.critical_gaps.insert(i.id, gap);
+                    }
+                }
+            }
             if let Some(mut set) = map_model::IntersectionCluster::autodetect(i.id, map) {
                 set.remove(&i.id);
                 state.uber_turn_neighbors.extend(set);
             }
             sim.state.insert(i.id, state);
         }
+        sim.setup_signal_clusters(Time::START_OF_DAY, map, scheduler);
         sim
     }
 
@@ -284,7 +318,7 @@ impl IntersectionSimState {
         }
     }
 
-    /// This is only triggered for traffic signals.
+    /// This is only triggered for traffic signals. In a cluster, only for the controller.
     pub fn update_intersection(
         &mut self,
         now: Time,
@@ -292,92 +326,302 @@ impl IntersectionSimState {
         map: &Map,
         scheduler: &mut Scheduler,
     ) {
-        let i = map.get_i(id);
-
         // trivial function that advances the signal stage and returns duration
         fn advance(
             signal_state: &mut SignalState,
             signal: &ControlTrafficSignal,
-            i: &Intersection,
-            allow_crosswalk_skip: bool,
+            can_skip: &dyn Fn(usize) -> bool,
+            now: Time,
         ) -> Duration {
             signal_state.current_stage = (signal_state.current_stage + 1) % signal.stages.len();
-            let stage = &signal.stages[signal_state.current_stage];
-            // only skip for variable all-walk crosswalk
-            if let StageType::Variable(_, _, _) = stage.stage_type {
-                if allow_crosswalk_skip && stage.max_crosswalk_time(i).is_some() {
-                    // we can skip this stage, as its all walk and we're allowed to skip (no
-                    // pedestrian waiting).
-                    signal_state.current_stage =
-                        (signal_state.current_stage + 1) % signal.stages.len();
-                }
+            signal_state.transit_priority_used = false;
+            signal_state.stage_started_at = now;
+            if can_skip(signal_state.current_stage) {
+                signal_state.current_stage = (signal_state.current_stage + 1) % signal.stages.len();
             }
             signal.stages[signal_state.current_stage]
                 .stage_type
                 .simple_duration()
         }
-        let state = self.state.get_mut(&id).unwrap();
-        let signal_state = state.signal.as_mut().unwrap();
+
+        // A cluster acts as one signal, so consider demand at every member
+        let mut members = vec![id];
+        members.extend(self.state[&id].signal.as_ref().unwrap().followers.clone());
+        let current_stage = self.state[&id].signal.as_ref().unwrap().current_stage;
+        let ped_waiting = members.iter().any(|i| {
+            self.state[i]
+                .waiting
+                .keys()
+                .any(|req| matches!(req.agent, AgentID::Pedestrian(_)))
+        });
+        // Filter out pedestrians, as they've had their chance and the delay could be short enough
+        // to keep them on the curb.
+        // Should we only allow protected to extend or any not banned? Currently only the
+        // protected demand control extended.
+        let protected_demand = members.iter().any(|i| {
+            let stage = &map.get_traffic_signal(*i).stages[current_stage];
+            self.state[i].waiting.keys().any(|req| {
+                !matches!(req.agent, AgentID::Pedestrian(_))
+                    && stage.get_priority_of_turn(req.turn, map.get_i(*i))
+                        == TurnPriority::Protected
+            })
+        });
+        // Only skip variable all-walk stages, when nobody's waiting to cross
+        let can_skip = |idx: usize| {
+            !ped_waiting
+                && members.iter().all(|i| {
+                    let stage = &map.get_traffic_signal(*i).stages[idx];
+                    matches!(stage.stage_type, StageType::Variable(_, _, _))
+                        && stage.max_crosswalk_time(map.get_i(*i)).is_some()
+                })
+        };
+
         let signal = map.get_traffic_signal(id);
-        let ped_waiting = state.waiting.keys().any(|req| {
-            if let AgentID::Pedestrian(_) = req.agent {
-                return true;
-            }
-            false
+        let actuated = signal.actuation.as_ref().map(|actuation| {
+            self.next_actuated_stage(now, &members, current_stage, actuation, map)
         });
+        let signal_state = self.state.get_mut(&id).unwrap().signal.as_mut().unwrap();
         let duration: Duration;
         // Switch to a new stage?
         assert_eq!(now, signal_state.stage_ends_at);
-        let old_stage = &signal.stages[signal_state.current_stage];
-        match old_stage.stage_type {
-            StageType::Fixed(_) => {
-                duration = advance(signal_state, signal, i, !ped_waiting);
+        if let Some(next) = actuated {
+            match next {
+                Some((stage, min_duration)) => {
+                    signal_state.current_stage = stage;
+                    signal_state.stage_started_at = now;
+                    signal_state.transit_priority_used = false;
+                    duration = min_duration;
+                }
+                None => {
+                    duration = signal.actuation.as_ref().unwrap().gap;
+                }
             }
-            StageType::Variable(min, delay, additional) => {
-                // test if anyone is waiting in current stage, and if so, extend the signal cycle.
-                // Filter out pedestrians, as they've had their chance and the delay
-                // could be short enough to keep them on the curb.
-                let delay = std::cmp::max(Duration::const_seconds(1.0), delay);
-                // Only extend for the fixed additional time
-                if signal_state.extensions_count as f64 * delay.inner_seconds()
-                    >= additional.inner_seconds()
-                {
-                    self.events.push(Event::Alert(
-                        AlertLocation::Intersection(id),
-                        format!(
-                            "exhausted a variable stage {},{},{},{}",
-                            min, delay, additional, signal_state.extensions_count
-                        ),
-                    ));
-                    duration = advance(signal_state, signal, i, !ped_waiting);
-                    signal_state.extensions_count = 0;
-                } else if state.waiting.keys().all(|req| {
-                    if let AgentID::Pedestrian(_) = req.agent {
-                        return true;
+        } else {
+            match signal.stages[current_stage].stage_type {
+                StageType::Fixed(_) => {
+                    duration = advance(signal_state, signal, &can_skip, now);
+                }
+                StageType::Variable(min, delay, additional) => {
+                    // test if anyone is waiting in current stage, and if so, extend the signal
+                    // cycle.
+                    let delay = std::cmp::max(Duration::const_seconds(1.0), delay);
+                    // Only extend for the fixed additional time
+                    if signal_state.extensions_count as f64 * delay.inner_seconds()
+                        >= additional.inner_seconds()
+                    {
+                        self.events.push(Event::Alert(
+                            AlertLocation::Intersection(id),
+                            format!(
+                                "exhausted a variable stage {},{},{},{}",
+                                min, delay, additional, signal_state.extensions_count
+                            ),
+                        ));
+                        duration = advance(signal_state, signal, &can_skip, now);
+                        signal_state.extensions_count = 0;
+                    } else if !protected_demand {
+                        signal_state.extensions_count = 0;
+                        duration = advance(signal_state, signal, &can_skip, now);
+                    } else {
+                        signal_state.extensions_count += 1;
+                        duration = delay;
+                        self.events.push(Event::Alert(
+                            AlertLocation::Intersection(id),
+                            format!(
+                                "Extending a variable stage {},{},{},{}",
+                                min, delay, additional, signal_state.extensions_count
+                            ),
+                        ));
                     }
-                    // Should we only allow protected to extend or any not banned?
-                    // currently only the protected demand control extended.
-                    old_stage.get_priority_of_turn(req.turn, i) != TurnPriority::Protected
-                }) {
-                    signal_state.extensions_count = 0;
-                    duration = advance(signal_state, signal, i, !ped_waiting);
-                } else {
-                    signal_state.extensions_count += 1;
-                    duration = delay;
-                    self.events.push(Event::Alert(
-                        AlertLocation::Intersection(id),
-                        format!(
-                            "Extending a variable stage {},{},{},{}",
-                            min, delay, additional, signal_state.extensions_count
-                        ),
-                    ));
                 }
             }
         }
 
         signal_state.stage_ends_at = now + duration;
         scheduler.push(signal_state.stage_ends_at, Command::UpdateIntersection(id));
-        self.wakeup_waiting(now, id, scheduler, map);
+        let new_stage = signal_state.current_stage;
+        self.sync_signal_followers(id);
+        if new_stage != current_stage {
+            for i in &members {
+                self.events.push(Event::SignalStageChanged {
+                    i: *i,
+                    stage: new_stage,
+                });
+            }
+        }
+        for i in members {
+            self.wakeup_waiting(now, i, scheduler, map);
+        }
+    }
+
+    /// For a vehicle-actuated signal, decide what happens when the current stage's time is up.
+    /// None means to hold the current stage for another gap, because vehicles are still
+    /// arriving, or because nobody is waiting for anything. Otherwise, begin a stage (maybe the
+    /// current one again) for its minimum duration.
+    fn next_actuated_stage(
+        &self,
+        now: Time,
+        members: &[IntersectionID],
+        current_stage: usize,
+        actuation: &Actuation,
+        map: &Map,
+    ) -> Option<(usize, Duration)> {
+        let signal = map.get_traffic_signal(members[0]);
+        let walk_only = |idx: usize| {
+            members.iter().all(|i| {
+                map.get_traffic_signal(*i).stages[idx]
+                    .max_crosswalk_time(map.get_i(*i))
+                    .is_some()
+            })
+        };
+
+        // Extend the green while vehicles keep arriving
+        let elapsed = now
+            - self.state[&members[0]]
+                .signal
+                .as_ref()
+                .unwrap()
+                .stage_started_at;
+        if !walk_only(current_stage)
+            && elapsed + actuation.gap <= actuation.max_green
+            && self.demand_detected(now, members, current_stage, actuation.gap, false, map)
+        {
+            return None;
+        }
+
+        // Skip stages nobody is waiting for. If the only demand is for the current stage, start
+        // it over.
+        let num_stages = signal.stages.len();
+        let next = (1..=num_stages)
+            .map(|offset| (current_stage + offset) % num_stages)
+            .find(|idx| self.demand_detected(now, members, *idx, actuation.gap, true, map))?;
+        let min_duration = if walk_only(next) {
+            signal.stages[next].stage_type.simple_duration()
+        } else {
+            members
+                .iter()
+                .map(|i| {
+                    map.get_traffic_signal(*i)
+                        .get_min_crossing_time(next, map.get_i(*i))
+                })
+                .fold(actuation.min_green, |a, b| a.max(b))
+        };
+        Some((next, min_duration))
+    }
+
+    /// Simulates detectors at the stop line of every approach. Vehicles are detected while they
+    /// wait to make a turn that the stage allows, or when they're about to arrive within `gap`.
+    /// Optionally, people waiting to use a crosswalk the stage protects count too, as if they
+    /// pushed a button.
+    fn demand_detected(
+        &self,
+        now: Time,
+        members: &[IntersectionID],
+        stage_idx: usize,
+        gap: Duration,
+        include_pedestrians: bool,
+        map: &Map,
+    ) -> bool {
+        members.iter().any(|id| {
+            let state = &self.state[id];
+            let i = map.get_i(*id);
+            let stage = &map.get_traffic_signal(*id).stages[stage_idx];
+            let waiting = state.waiting.keys().any(|req| {
+                if matches!(req.agent, AgentID::Pedestrian(_)) {
+                    include_pedestrians
+                        && stage.get_priority_of_turn(req.turn, i) == TurnPriority::Protected
+                } else {
+                    stage.get_priority_of_turn(req.turn, i) != TurnPriority::Banned
+                }
+            });
+            // Stale predictions for agents that never arrived eventually stop counting
+            waiting
+                || state.leader_eta.values().any(|(req, eta)| {
+                    *eta <= now + gap
+                        && *eta + gap >= now
+                        && stage.get_priority_of_turn(req.turn, i) != TurnPriority::Banned
+                })
+        })
+    }
+
+    /// Copy a cluster controller's stage timing to the other members
+    fn sync_signal_followers(&mut self, controller: IntersectionID) {
+        let signal_state = self.state[&controller].signal.clone().unwrap();
+        for i in &signal_state.followers {
+            self.state
+                .get_mut(i)
+                .unwrap()
+                .signal
+                .as_mut()
+                .unwrap()
+                .follow(&signal_state);
+        }
+    }
+
+    /// Signals in a valid cluster follow the member with the lowest ID, which alone schedules
+    /// stage changes. Invalid clusters run independently.
+    fn setup_signal_clusters(&mut self, now: Time, map: &Map, scheduler: &mut Scheduler) {
+        let mut was_following = Vec::new();
+        for state in self.state.values_mut() {
+            if let Some(ref mut signal_state) = state.signal {
+                if signal_state.controller.take().is_some() {
+                    was_following.push(state.id);
+                }
+                signal_state.followers.clear();
+            }
+        }
+
+        for i in map.all_intersections() {
+            let signal = match map.maybe_get_traffic_signal(i.id) {
+                Some(signal) => signal,
+                None => continue,
+            };
+            if signal.cluster.iter().next() != Some(&i.id) {
+                continue;
+            }
+            if let Err(err) = ControlTrafficSignal::validate_cluster(map, &signal.cluster) {
+                warn!(
+                    "Running signals clustered with {} independently: {}",
+                    i.id, err
+                );
+                continue;
+            }
+            let followers: Vec<IntersectionID> = signal
+                .cluster
+                .iter()
+                .filter(|member| **member != i.id)
+                .cloned()
+                .collect();
+            for member in &followers {
+                self.state
+                    .get_mut(member)
+                    .unwrap()
+                    .signal
+                    .as_mut()
+                    .unwrap()
+                    .controller = Some(i.id);
+                scheduler.cancel(Command::UpdateIntersection(*member));
+            }
+            self.state
+                .get_mut(&i.id)
+                .unwrap()
+                .signal
+                .as_mut()
+                .unwrap()
+                .followers = followers;
+            self.sync_signal_followers(i.id);
+        }
+
+        // Signals leaving a cluster have to schedule their own stage changes again
+        for i in was_following {
+            if let Some(signal_state) = self.state[&i].signal.as_ref() {
+                if signal_state.controller.is_none() {
+                    scheduler.update(
+                        signal_state.stage_ends_at.max(now),
+                        Command::UpdateIntersection(i),
+                    );
+                }
+            }
+        }
     }
 
     /// For cars: The head car calls this when they're at the end of the lane WaitingToAdvance. If
@@ -452,6 +696,11 @@ impl IntersectionSimState {
         {
             // It's never OK to perform a conflicting turn
             false
+        } else if self.level_crossing_closed(&req, now, map, scheduler) {
+            false
+        } else if is_train(agent) && map.get_i(turn.parent).is_level_crossing(map) {
+            // Trains don't stop for road traffic
+            true
         } else if maybe_cars_and_queues
             .as_ref()
             .map(|(car, _, _)| started_uber_turn(self, *car))
@@ -470,6 +719,8 @@ impl IntersectionSimState {
             }
 
             true
+        } else if self.must_yield_to_bikes(&req, now, map, scheduler) {
+            false
         } else if self.use_freeform_policy_everywhere {
             // If we made it this far, we don't conflict with an accepted turn
             true
@@ -581,6 +832,8 @@ impl IntersectionSimState {
             }
         }
 
+        self.record_bike_conflicts(&req, map);
+
         // TODO For now, we're only interested in signals, and there's too much raw data to store
         // for stop signs too.
         let state = self.state.get_mut(&turn.parent).unwrap();
@@ -638,6 +891,8 @@ impl IntersectionSimState {
                 state.uber_turn_neighbors.extend(set);
             }
         }
+
+        self.setup_signal_clusters(now, map, scheduler);
     }
 
     pub fn handle_live_edits(&self, map: &Map) {
@@ -668,6 +923,112 @@ impl IntersectionSimState {
         }
     }
 
+    /// A late bus is the leader approaching `turn` and will arrive at `eta`. If the intersection
+    /// is a traffic signal with transit priority, the bus might be about to just miss the end of a
+    /// green, so hold it; or it might face a red that's about to end anyway, so cut it short. Each
+    /// stage is adjusted at most once. Nothing compensates for the shift afterwards, so
+    /// coordination with neighboring signals drifts a bit.
+    pub fn maybe_grant_transit_priority(
+        &mut self,
+        bus: CarID,
+        turn: TurnID,
+        lateness: Duration,
+        now: Time,
+        eta: Time,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) {
+        let id = turn.parent;
+        let signal = match map.maybe_get_traffic_signal(id) {
+            Some(signal) => signal,
+            None => {
+                return;
+            }
+        };
+        let priority = match signal.transit_priority {
+            Some(ref priority) => priority,
+            None => {
+                return;
+            }
+        };
+        if lateness < priority.min_lateness || self.use_freeform_policy_everywhere {
+            return;
+        }
+        // In a cluster, shift the stage for every member
+        let controller = self.state[&id]
+            .signal
+            .as_ref()
+            .and_then(|s| s.controller)
+            .unwrap_or(id);
+        let signal_state = self
+            .state
+            .get_mut(&controller)
+            .unwrap()
+            .signal
+            .as_mut()
+            .unwrap();
+        if signal_state.transit_priority_used {
+            return;
+        }
+        // Actuated signals already respond to demand
+        if signal.actuation.is_some() {
+            return;
+        }
+        let current_stage = &signal.stages[signal_state.current_stage];
+        // Variable stages already respond to demand
+        let current_duration = match current_stage.stage_type {
+            StageType::Fixed(d) => d,
+            StageType::Variable(_, _, _) => {
+                return;
+            }
+        };
+
+        let i = map.get_i(id);
+        let serves_bus =
+            |stage: &Stage| stage.get_priority_of_turn(turn, i) != TurnPriority::Banned;
+        let old_end = signal_state.stage_ends_at;
+        let (new_end, bus_time_saved, cross_traffic_delay) = if serves_bus(current_stage) {
+            // Leave a moment for the bus to start the turn before the stage changes
+            let new_end = eta + Duration::seconds(1.0);
+            if new_end <= old_end || new_end - old_end > priority.max_extension {
+                return;
+            }
+            // Otherwise, the bus would wait for every other stage
+            (
+                new_end,
+                signal.simple_cycle_duration() - current_duration,
+                new_end - old_end,
+            )
+        } else {
+            let next_stage = &signal.stages[(signal_state.current_stage + 1) % signal.stages.len()];
+            if !serves_bus(next_stage) || eta >= old_end {
+                return;
+            }
+            // Let pedestrians who started crossing at the beginning of the stage finish
+            let started_at = old_end - current_duration;
+            let new_end = (started_at
+                + signal.get_min_crossing_time(signal_state.current_stage, i))
+            .max(old_end - priority.max_early_end)
+            .max(eta)
+            .max(now);
+            if new_end >= old_end {
+                return;
+            }
+            (new_end, old_end - new_end, old_end - new_end)
+        };
+
+        signal_state.stage_ends_at = new_end;
+        signal_state.transit_priority_used = true;
+        scheduler.update(new_end, Command::UpdateIntersection(controller));
+        self.sync_signal_followers(controller);
+        self.events.push(Event::TransitSignalPriority {
+            intersection: id,
+            bus,
+            bus_time_saved,
+            cross_traffic_delay,
+        });
+    }
+
     // Not calling this for pedestrians right now.
     // This is "best effort". If we get something wrong, somebody might start a turn and cut off an
     // approaching vehicle.
@@ -855,7 +1216,10 @@ impl IntersectionSimState {
         assert!(our_priority != TurnPriority::Banned);
         let (our_time, _) = self.state[&req.turn.parent].waiting[req];
 
-        if our_priority == TurnPriority::Yield && now < our_time + WAIT_AT_STOP_SIGN {
+        if our_priority == TurnPriority::Yield
+            && sign.must_stop(req.turn, map)
+            && now < our_time + WAIT_AT_STOP_SIGN
+        {
             // Since we have "ownership" of scheduling for req.agent, don't need to use
             // scheduler.update.
             scheduler.push(
@@ -878,8 +1242,39 @@ impl IntersectionSimState {
         // If a case #1 could've started by now, then they would have. Since they didn't, they must
         // be blocked.
 
+        // Vehicles at a stop or give-way sign accept a gap in priority traffic. Since we don't
+        // track vehicles approaching the intersection, a priority vehicle that just arrived is the
+        // best proxy for one about to arrive. If it's been waiting longer than the critical gap,
+        // it's probably blocked, so don't wait on it forever.
         // TODO Make sure we can optimistically finish this turn before an approaching
         // higher-priority vehicle wants to begin.
+        if our_priority == TurnPriority::Yield && !req.agent.is_pedestrian() {
+            let our_turn = map.get_t(req.turn);
+            let critical_gap = self
+                .critical_gaps
+                .get(&req.turn.parent)
+                .cloned()
+                .unwrap_or(CRITICAL_GAP);
+            let mut wait_until = None;
+            for (other_req, (other_time, _)) in &self.state[&req.turn.parent].waiting {
+                if other_req.agent.is_pedestrian()
+                    || now >= *other_time + critical_gap
+                    || sign.get_priority(other_req.turn, map) != TurnPriority::Protected
+                    || !our_turn.conflicts_with(map.get_t(other_req.turn))
+                {
+                    continue;
+                }
+                let t = *other_time + critical_gap;
+                if wait_until.map(|x| t < x).unwrap_or(true) {
+                    wait_until = Some(t);
+                }
+            }
+            if let Some(t) = wait_until {
+                // Other priority vehicles finishing their turn might wake us up first
+                scheduler.update(t, Command::update_agent(req.agent));
+                return false;
+            }
+        }
 
         // If a pedestrian is going to cut off a car, check how long the car has been waiting and
         // maybe yield (regardless of stop sign priority). This is a very rough start to more
@@ -915,6 +1310,50 @@ impl IntersectionSimState {
         true
     }
 
+    /// At a level crossing, road traffic and pedestrians can't start a turn crossing the tracks
+    /// while a train is crossing, waiting to cross, or about to arrive.
+    fn level_crossing_closed(
+        &self,
+        req: &Request,
+        now: Time,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) -> bool {
+        if is_train(req.agent) || !map.get_i(req.turn.parent).is_level_crossing(map) {
+            return false;
+        }
+        let state = &self.state[&req.turn.parent];
+        let our_turn = map.get_t(req.turn);
+        let conflicts = |other: &Request| {
+            is_train(other.agent) && our_turn.conflicts_with(map.get_t(other.turn))
+        };
+
+        // When the train finishes crossing, it'll wake us up
+        if state
+            .accepted
+            .iter()
+            .chain(state.waiting.keys())
+            .any(conflicts)
+        {
+            return true;
+        }
+
+        for (other_req, eta) in state.leader_eta.values() {
+            if conflicts(other_req)
+                && now + LEVEL_CROSSING_WARNING >= *eta
+                && now < *eta + LEVEL_CROSSING_WARNING
+            {
+                // If the train doesn't show up by then, try again
+                scheduler.update(
+                    *eta + LEVEL_CROSSING_WARNING,
+                    Command::update_agent(req.agent),
+                );
+                return true;
+            }
+        }
+        false
+    }
+
     fn traffic_signal_policy(
         &mut self,
         req: &Request,
@@ -929,16 +1368,49 @@ impl IntersectionSimState {
         let state = &self.state[&req.turn.parent];
         let signal_state = state.signal.as_ref().unwrap();
         let stage = &signal.stages[signal_state.current_stage];
-        let full_stage_duration = stage.stage_type.simple_duration();
-        let remaining_stage_time = signal_state.stage_ends_at - now;
+        let mut full_stage_duration = stage.stage_type.simple_duration();
+        let mut remaining_stage_time = signal_state.stage_ends_at - now;
+        if let Some(ref actuation) = signal.actuation {
+            if stage.max_crosswalk_time(map.get_i(state.id)).is_none() {
+                full_stage_duration = actuation.max_green;
+                // A waiting vehicle keeps extending the green, up to the max. People walking
+                // don't.
+                if !matches!(req.agent, AgentID::Pedestrian(_)) {
+                    remaining_stage_time = remaining_stage_time
+                        .max(signal_state.stage_started_at + actuation.max_green - now);
+                }
+            }
+        }
         let (our_time, _) = state.waiting[req];
 
         // Can't go at all this stage.
-        let our_priority = stage.get_priority_of_turn(req.turn, map.get_i(state.id));
+        let i = map.get_i(state.id);
+        let our_priority = if is_bike(req.agent)
+            && i.corner_design.two_stage_turn_boxes
+            && turn.turn_type == far_side_turn(map)
+        {
+            two_stage_turn_priority(stage, turn, i, map)
+        } else {
+            stage.get_priority_of_turn(req.turn, i)
+        };
         if our_priority == TurnPriority::Banned {
             return false;
         }
 
+        // Give people walking a head start
+        if let Some(lpi) = signal.leading_pedestrian_interval {
+            let lpi_ends_at = signal_state.stage_started_at + lpi;
+            if now < lpi_ends_at
+                && signal.held_for_leading_pedestrian_interval(stage, req.turn, map.get_i(state.id))
+            {
+                // Nothing else wakes up the agent when the interval ends
+                if let Some(s) = scheduler {
+                    s.push(lpi_ends_at, Command::update_agent(req.agent));
+                }
+                return false;
+            }
+        }
+
         if our_priority == TurnPriority::Yield
             && now < our_time + WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL
         {
@@ -977,6 +1449,75 @@ impl IntersectionSimState {
         true
     }
 
+    /// With protected corners or setback crossings, a motor vehicle turning across people cycling
+    /// alongside it yields to anybody cycling who's already waiting.
+    fn must_yield_to_bikes(
+        &self,
+        req: &Request,
+        now: Time,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) -> bool {
+        if !is_motor_vehicle(req.agent) {
+            return false;
+        }
+        let i = map.get_i(req.turn.parent);
+        if i.corner_design.is_empty() {
+            return false;
+        }
+        let turn = map.get_t(req.turn);
+        let mut yield_until: Option<Time> = None;
+        for (other, (waiting_since, _)) in &self.state[&i.id].waiting {
+            if !is_bike(other.agent) || now >= *waiting_since + MAX_YIELD_TO_BIKES {
+                continue;
+            }
+            let bike_turn = map.get_t(other.turn);
+            if turn.conflicts_with(bike_turn)
+                && i.corner_design
+                    .vehicles_yield_to_bikes(BikeConflictType::classify(turn, bike_turn, map))
+            {
+                let until = *waiting_since + MAX_YIELD_TO_BIKES;
+                yield_until = Some(yield_until.map(|t| t.max(until)).unwrap_or(until));
+            }
+        }
+        if let Some(until) = yield_until {
+            // Usually the bike going first wakes us up, but just in case it doesn't
+            scheduler.update(until, Command::update_agent(req.agent));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// When a motor vehicle and somebody cycling want to make conflicting turns and one of them
+    /// goes first, record a conflict.
+    fn record_bike_conflicts(&mut self, req: &Request, map: &Map) {
+        if !is_bike(req.agent) && !is_motor_vehicle(req.agent) {
+            return;
+        }
+        let turn = map.get_t(req.turn);
+        let mut conflicts = Vec::new();
+        for other in self.state[&req.turn.parent].waiting.keys() {
+            let other_turn = map.get_t(other.turn);
+            let (vehicle, bike) = if is_bike(req.agent) && is_motor_vehicle(other.agent) {
+                (other_turn, turn)
+            } else if is_motor_vehicle(req.agent) && is_bike(other.agent) {
+                (turn, other_turn)
+            } else {
+                continue;
+            };
+            if vehicle.conflicts_with(bike) {
+                conflicts.push(BikeConflictType::classify(vehicle, bike, map));
+            }
+        }
+        for conflict in conflicts {
+            self.events.push(Event::BikeConflict {
+                i: req.turn.parent,
+                conflict,
+            });
+        }
+    }
+
     // If true, the request can go.
     fn handle_accepted_conflicts(
         &mut self,
@@ -1062,11 +1603,12 @@ impl IntersectionSimState {
         &self,
         car: CarID,
         pair: (&FixedMap<CarID, Car>, &HashMap<Traversable, Queue>),
-    ) -> Option<HashSet<CarID>> {
+    ) -> Option<BTreeSet<CarID>> {
         let (cars, queues) = pair;
 
         let mut queue = vec![car];
-        let mut seen = HashSet::new();
+        // This winds up in an alert, so keep the order stable
+        let mut seen = BTreeSet::new();
         while !queue.is_empty() {
             let current = queue.pop().unwrap();
             // Might not actually be a cycle. Insist on seeing the original req.agent
@@ -1108,7 +1650,11 @@ impl SignalState {
         let mut state = SignalState {
             current_stage: 0,
             stage_ends_at: now,
+            stage_started_at: now,
             extensions_count: 0,
+            transit_priority_used: false,
+            controller: None,
+            followers: Vec::new(),
         };
 
         let signal = map.get_traffic_signal(id);
@@ -1126,12 +1672,59 @@ impl SignalState {
                 }
             } else {
                 state.stage_ends_at = now + dt - offset;
+                // The stage could've begun before the simulation did
+                state.stage_started_at = if now - Time::START_OF_DAY >= offset {
+                    now - offset
+                } else {
+                    Time::START_OF_DAY
+                };
                 break;
             }
         }
         scheduler.push(state.stage_ends_at, Command::UpdateIntersection(id));
         state
     }
+
+    fn follow(&mut self, controller: &SignalState) {
+        self.current_stage = controller.current_stage;
+        self.stage_ends_at = controller.stage_ends_at;
+        self.stage_started_at = controller.stage_started_at;
+        self.extensions_count = controller.extensions_count;
+        self.transit_priority_used = controller.transit_priority_used;
+    }
+}
+
+fn is_bike(agent: AgentID) -> bool {
+    matches!(agent, AgentID::Car(car) if car.vehicle_type == VehicleType::Bike)
+}
+
+fn is_motor_vehicle(agent: AgentID) -> bool {
+    matches!(agent, AgentID::Car(car) if car.vehicle_type != VehicleType::Bike)
+}
+
+/// With a two-stage turn box, somebody cycling across traffic goes straight to the far corner,
+/// then straight again with the cross street. Model this as making their turn only when traffic
+/// heading straight onto their destination road has a protected green.
+fn two_stage_turn_priority(
+    stage: &Stage,
+    turn: &Turn,
+    i: &Intersection,
+    map: &Map,
+) -> TurnPriority {
+    let dst = map.get_l(turn.id.dst).get_directed_parent();
+    if stage
+        .protected_movements
+        .iter()
+        .any(|m| m.to == dst && !m.crosswalk && i.movements[m].turn_type == TurnType::Straight)
+    {
+        TurnPriority::Protected
+    } else {
+        TurnPriority::Banned
+    }
+}
+
+fn is_train(agent: AgentID) -> bool {
+    matches!(agent, AgentID::Car(car) if car.vehicle_type == VehicleType::Train)
 }
 
 fn allow_block_the_box(i: &Intersection) -> bool {
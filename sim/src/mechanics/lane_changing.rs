@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use geom::{Distance, Duration};
+use map_model::{osm, Lane, Map};
+
+/// Controls how vehicles change lanes in the middle of a road. Lanes are still picked at
+/// intersections as before; this covers what happens after that.
+///
+/// - A mandatory lane change gets around something that won't move soon, like a bus at a stop or
+///   a car parking.
+/// - A discretionary lane change moves to a neighboring lane with a much shorter queue, after
+///   being stuck for a while.
+/// - Drivers stuck waiting to change lanes for long enough get let in by somebody in the target
+///   lane braking a little.
+/// - Near the end of a road, drivers stop making discretionary lane changes, leaving this weaving
+///   section for the changes they can't avoid.
+#[derive(Clone, Debug, Serialize, Deserialize, StructOpt)]
+pub struct LaneChangingOpts {
+    /// Only make a discretionary lane change if the neighboring lane has at least this many fewer
+    /// vehicles ahead. 0 disables discretionary lane changes.
+    #[structopt(long, default_value = "2")]
+    pub lc_discretionary_gain: usize,
+    /// How long to wait in a queue before considering a discretionary lane change, or trying a
+    /// blocked one again (like "0:10").
+    #[structopt(long, parse(try_from_str = Duration::parse), default_value = "0:10")]
+    pub lc_patience: Duration,
+    /// After waiting this long to change lanes, drivers in the target lane will make a little
+    /// room (like "0:30").
+    #[structopt(long, parse(try_from_str = Duration::parse), default_value = "0:30")]
+    pub lc_courtesy_after: Duration,
+    /// In meters, how long the weaving section at the end of each road is. It's three times
+    /// longer approaching a highway ramp.
+    #[structopt(long, default_value = "30")]
+    pub lc_weaving_section: f64,
+}
+
+impl Default for LaneChangingOpts {
+    fn default() -> LaneChangingOpts {
+        LaneChangingOpts {
+            lc_discretionary_gain: 2,
+            lc_patience: Duration::seconds(10.0),
+            lc_courtesy_after: Duration::seconds(30.0),
+            lc_weaving_section: 30.0,
+        }
+    }
+}
+
+impl LaneChangingOpts {
+    /// After this distance along the lane, no discretionary lane changes happen.
+    pub(crate) fn weaving_section_starts(&self, lane: &Lane, map: &Map) -> Distance {
+        let mut len = Distance::meters(self.lc_weaving_section);
+        let i = map.get_i(lane.dst_i);
+        if i.roads.iter().any(|r| {
+            map.get_r(*r)
+                .osm_tags
+                .get(osm::HIGHWAY)
+                .map(|hwy| hwy.ends_with("_link"))
+                .unwrap_or(false)
+        }) {
+            len = len * 3.0;
+        }
+        (lane.length() - len).max(Distance::ZERO)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LaneChangeReason {
+    /// Getting around something stopped in the lane
+    Mandatory,
+    /// Switching to a lane with a shorter queue
+    Discretionary,
+    /// Passing a slower vehicle
+    Overtaking,
+}
@@ -1,12 +1,16 @@
+pub(crate) use self::bike_parking::BikeParking;
 pub(crate) use self::driving::DrivingSimState;
 pub(crate) use self::intersection::IntersectionSimState;
+pub use self::lane_changing::{LaneChangeReason, LaneChangingOpts};
 pub(crate) use self::parking::{ParkingSim, ParkingSimState};
 pub(crate) use self::queue::Queue;
 pub(crate) use self::walking::WalkingSimState;
 
+mod bike_parking;
 mod car;
 mod driving;
 mod intersection;
+mod lane_changing;
 mod parking;
 mod queue;
 mod walking;
@@ -11,8 +11,8 @@ use abstutil::{
 };
 use geom::{Distance, PolyLine, Pt2D};
 use map_model::{
-    BuildingID, Lane, LaneID, LaneType, Map, OffstreetParking, ParkingLotID, PathConstraints,
-    PathStep, Position, Traversable, TurnID,
+    BuildingID, KerbUseType, Lane, LaneID, LaneType, Map, OffstreetParking, ParkingLotID,
+    PathConstraints, PathStep, Position, Traversable, TurnID,
 };
 
 use crate::{CarID, CarStatus, DrawCarInput, Event, ParkedCar, ParkingSpot, PersonID, Vehicle};
@@ -53,6 +53,16 @@ pub trait ParkingSim {
         target: BuildingID,
         map: &Map,
     ) -> Vec<(ParkingSpot, Position)>;
+    /// Like `get_all_free_spots`, but only returns bays of one kind on the current lane. Only
+    /// delivery vehicles should use loading bays, and only vehicles dropping somebody off should
+    /// use pick-up/drop-off bays.
+    fn get_free_bays(
+        &self,
+        driving_pos: Position,
+        vehicle: &Vehicle,
+        use_type: KerbUseType,
+        map: &Map,
+    ) -> Vec<(ParkingSpot, Position)>;
     fn spot_to_driving_pos(&self, spot: ParkingSpot, vehicle: &Vehicle, map: &Map) -> Position;
     fn spot_to_sidewalk_pos(&self, spot: ParkingSpot, map: &Map) -> Position;
     fn get_owner_of_car(&self, id: CarID) -> Option<PersonID>;
@@ -211,13 +221,31 @@ impl NormalParkingSimState {
 
         sim
     }
+
+    /// (Filled, available)
+    fn get_all_bays(&self) -> (Vec<ParkingSpot>, Vec<ParkingSpot>) {
+        let mut filled = Vec::new();
+        let mut available = Vec::new();
+        for lane in self.onstreet_lanes.values() {
+            for spot in lane.all_bays() {
+                if self.is_free(spot) {
+                    available.push(spot);
+                } else {
+                    filled.push(spot);
+                }
+            }
+        }
+        (filled, available)
+    }
 }
 
 impl ParkingSim for NormalParkingSimState {
     fn handle_live_edits(&mut self, map: &Map, timer: &mut Timer) -> (Vec<ParkedCar>, Vec<CarID>) {
-        let (filled_before, _) = self.get_all_parking_spots();
+        let (mut filled_before, _) = self.get_all_parking_spots();
+        filled_before.extend(self.get_all_bays().0);
         let new = NormalParkingSimState::new(map, timer);
-        let (_, avail_after) = new.get_all_parking_spots();
+        let (_, mut avail_after) = new.get_all_parking_spots();
+        avail_after.extend(new.get_all_bays().1);
         let avail_after: BTreeSet<ParkingSpot> = avail_after.into_iter().collect();
 
         // Use the new spots
@@ -337,7 +365,7 @@ impl ParkingSim for NormalParkingSimState {
     fn get_draw_cars(&self, id: LaneID, map: &Map) -> Vec<DrawCarInput> {
         let mut cars = Vec::new();
         if let Some(lane) = self.onstreet_lanes.get(&id) {
-            for spot in lane.spots() {
+            for spot in lane.all_spots() {
                 if let Some(car) = self.occupants.get(&spot) {
                     cars.push(self.get_draw_car(*car, map).unwrap());
                 }
@@ -495,6 +523,28 @@ impl ParkingSim for NormalParkingSimState {
             .collect()
     }
 
+    fn get_free_bays(
+        &self,
+        driving_pos: Position,
+        vehicle: &Vehicle,
+        use_type: KerbUseType,
+        map: &Map,
+    ) -> Vec<(ParkingSpot, Position)> {
+        let mut candidates = Vec::new();
+        for l in self.driving_to_parking_lanes.get(driving_pos.lane()) {
+            for spot in self.onstreet_lanes[l].bays(use_type) {
+                if !self.is_free(spot) {
+                    continue;
+                }
+                let pos = self.spot_to_driving_pos(spot, vehicle, map);
+                if driving_pos.dist_along() <= pos.dist_along() {
+                    candidates.push((spot, pos));
+                }
+            }
+        }
+        candidates
+    }
+
     fn spot_to_driving_pos(&self, spot: ParkingSpot, vehicle: &Vehicle, map: &Map) -> Position {
         match spot {
             ParkingSpot::Onstreet(l, idx) => {
@@ -635,12 +685,7 @@ impl ParkingSim for NormalParkingSimState {
     fn all_parked_car_positions(&self, map: &Map) -> Vec<(Position, PersonID)> {
         self.parked_cars
             .values()
-            .map(|p| {
-                (
-                    self.spot_to_sidewalk_pos(p.spot, map),
-                    p.vehicle.owner.unwrap(),
-                )
-            })
+            .filter_map(|p| Some((self.spot_to_sidewalk_pos(p.spot, map), p.vehicle.owner?)))
             .collect()
     }
 
@@ -663,6 +708,8 @@ struct ParkingLane {
     sidewalk: LaneID,
     // The front of the parking spot (farthest along the lane)
     spot_dist_along: Vec<Distance>,
+    // Indices into spot_dist_along reserved for deliveries or pick-ups/drop-offs
+    bays: BTreeMap<usize, KerbUseType>,
 }
 
 impl ParkingLane {
@@ -690,13 +737,47 @@ impl ParkingLane {
             return None;
         };
 
+        // Kerb uses other than parking take away spots. Loading bays stay, but only for
+        // deliveries, and pick-up/drop-off bays only for short stops.
+        let road = map.get_parent(lane.id);
+        let spot_length = map.get_config().street_parking_spot_length;
+        let mut spot_dist_along = Vec::new();
+        let mut bays = BTreeMap::new();
+        // A parking survey or OSM may say fewer cars fit than the length of the lane suggests,
+        // because of unmapped driveways, hydrants, and so on. More than that never fit.
+        let capacity = road
+            .observed_parking(lane.get_nearest_side_of_road(map).side)
+            .capacity
+            .unwrap_or(usize::MAX);
+        for idx in 0..lane.number_parking_spots(map.get_config()) {
+            if spot_dist_along.len() - bays.len() >= capacity {
+                break;
+            }
+            let dist = spot_length * (2.0 + idx as f64);
+            let use_type = road
+                .kerb_uses
+                .iter()
+                .find(|seg| seg.overlaps_lane(road, lane, dist - spot_length, dist))
+                .map(|seg| seg.use_type)
+                .unwrap_or(KerbUseType::Parking);
+            match use_type {
+                KerbUseType::Parking => {}
+                KerbUseType::LoadingBay | KerbUseType::PickUpDropOff => {
+                    bays.insert(spot_dist_along.len(), use_type);
+                }
+                KerbUseType::BusStop | KerbUseType::BikeParking | KerbUseType::Parklet => {
+                    continue;
+                }
+            }
+            spot_dist_along.push(dist);
+        }
+
         Some(ParkingLane {
             parking_lane: lane.id,
             driving_lane,
             sidewalk,
-            spot_dist_along: (0..lane.number_parking_spots(map.get_config()))
-                .map(|idx| map.get_config().street_parking_spot_length * (2.0 + idx as f64))
-                .collect(),
+            spot_dist_along,
+            bays,
         })
     }
 
@@ -706,13 +787,37 @@ impl ParkingLane {
             - (map.get_config().street_parking_spot_length - vehicle.length) / 2.0
     }
 
+    /// Excludes bays
     fn spots(&self) -> Vec<ParkingSpot> {
         let mut spots = Vec::new();
         for idx in 0..self.spot_dist_along.len() {
-            spots.push(ParkingSpot::Onstreet(self.parking_lane, idx));
+            if !self.bays.contains_key(&idx) {
+                spots.push(ParkingSpot::Onstreet(self.parking_lane, idx));
+            }
         }
         spots
     }
+
+    fn bays(&self, use_type: KerbUseType) -> Vec<ParkingSpot> {
+        self.bays
+            .iter()
+            .filter(|(_, bay_type)| **bay_type == use_type)
+            .map(|(idx, _)| ParkingSpot::Onstreet(self.parking_lane, *idx))
+            .collect()
+    }
+
+    fn all_bays(&self) -> Vec<ParkingSpot> {
+        self.bays
+            .keys()
+            .map(|idx| ParkingSpot::Onstreet(self.parking_lane, *idx))
+            .collect()
+    }
+
+    fn all_spots(&self) -> Vec<ParkingSpot> {
+        (0..self.spot_dist_along.len())
+            .map(|idx| ParkingSpot::Onstreet(self.parking_lane, idx))
+            .collect()
+    }
 }
 
 /// This assigns infinite private parking to all buildings and none anywhere else. This effectively
@@ -959,6 +1064,16 @@ impl ParkingSim for InfiniteParkingSimState {
         }
     }
 
+    fn get_free_bays(
+        &self,
+        _: Position,
+        _: &Vehicle,
+        _: KerbUseType,
+        _: &Map,
+    ) -> Vec<(ParkingSpot, Position)> {
+        Vec::new()
+    }
+
     fn spot_to_driving_pos(&self, spot: ParkingSpot, _: &Vehicle, map: &Map) -> Position {
         match spot {
             ParkingSpot::Offstreet(b, _) => map.get_b(b).driving_connection(map).unwrap().0,
@@ -1048,12 +1163,7 @@ impl ParkingSim for InfiniteParkingSimState {
     fn all_parked_car_positions(&self, map: &Map) -> Vec<(Position, PersonID)> {
         self.parked_cars
             .values()
-            .map(|p| {
-                (
-                    self.spot_to_sidewalk_pos(p.spot, map),
-                    p.vehicle.owner.unwrap(),
-                )
-            })
+            .filter_map(|p| Some((self.spot_to_sidewalk_pos(p.spot, map), p.vehicle.owner?)))
             .collect()
     }
 
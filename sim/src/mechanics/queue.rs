@@ -36,6 +36,9 @@ pub(crate) struct Queue {
 
     /// How long the lane or turn physically is.
     pub geom_len: Distance,
+    /// How much of the queue vehicles can wait in. Less than geom_len for lanes that only cover
+    /// part of their road, like turn pockets.
+    pub capacity: Distance,
     /// When a car's turn is accepted, reserve the vehicle length + FOLLOWING_DISTANCE for the
     /// target lane. When the car completely leaves (stops being the laggy_head), free up that
     /// space. To prevent blocking the box for possibly scary amounts of time, allocate some of
@@ -80,10 +83,18 @@ impl Queue {
             members: VecDeque::new(),
             laggy_head: None,
             geom_len: id.get_polyline(map).length(),
+            capacity: Queue::calculate_capacity(id, map),
             reserved_length: Distance::ZERO,
         }
     }
 
+    pub fn calculate_capacity(id: Traversable, map: &Map) -> Distance {
+        match id {
+            Traversable::Lane(l) => map.get_l(l).usable_length(),
+            Traversable::Turn(_) => id.get_polyline(map).length(),
+        }
+    }
+
     /// Get the front of the last car in the queue.
     pub fn get_last_car_position(
         &self,
@@ -294,10 +305,14 @@ impl Queue {
     }
 
     /// If the specified car can appear in the queue, return the position in the queue to do so.
+    ///
+    /// With `courtesy`, the follower will brake a little to make room, as long as the vehicles
+    /// wouldn't overlap.
     pub fn get_idx_to_insert_car(
         &self,
         start_dist: Distance,
         vehicle_len: Distance,
+        courtesy: bool,
         now: Time,
         cars: &FixedMap<CarID, Car>,
         queues: &HashMap<Traversable, Queue>,
@@ -337,7 +352,15 @@ impl Queue {
         }
         // Or the follower?
         if idx != dists.len() && start_dist - vehicle_len - FOLLOWING_DISTANCE < dists[idx].front {
-            return None;
+            let yields = courtesy
+                && start_dist - vehicle_len > dists[idx].front
+                && matches!(dists[idx].member, Queued::Vehicle(c) if matches!(
+                    cars[&c].state,
+                    CarState::Queued { .. } | CarState::Crossing { .. }
+                ));
+            if !yields {
+                return None;
+            }
         }
 
         Some(idx)
@@ -376,7 +399,7 @@ impl Queue {
     /// If true, there's room and the car must actually start the turn (because the space is
     /// reserved).
     pub fn try_to_reserve_entry(&mut self, car: &Car, force_entry: bool) -> bool {
-        // If self.reserved_length >= self.geom_len, then the lane is already full. Normally we
+        // If self.reserved_length >= self.capacity, then the lane is already full. Normally we
         // won't allow more cars to start a turn towards it, but if force_entry is true, then we'll
         // allow it.
 
@@ -391,16 +414,16 @@ impl Queue {
         false
     }
 
-    /// True if the reserved length exceeds the physical length. This means a vehicle is headed
-    /// towards the queue already and is expected to not fit entirely inside.
+    /// True if the reserved length exceeds the capacity. This means a vehicle is headed towards
+    /// the queue already and is expected to not fit entirely inside.
     pub fn is_overflowing(&self) -> bool {
-        self.reserved_length >= self.geom_len
+        self.reserved_length >= self.capacity
     }
 
     /// Can a car start a turn for this queue?
     pub fn room_for_car(&self, car: &Car) -> bool {
         self.reserved_length == Distance::ZERO
-            || self.reserved_length + car.vehicle.length + FOLLOWING_DISTANCE < self.geom_len
+            || self.reserved_length + car.vehicle.length + FOLLOWING_DISTANCE < self.capacity
     }
 
     /// Once a car has fully exited a queue, free up the space it was reserving.
@@ -526,7 +549,7 @@ impl Queue {
         cars: &FixedMap<CarID, Car>,
         queues: &HashMap<Traversable, Queue>,
     ) -> Option<usize> {
-        self.get_idx_to_insert_car(pos.dist_along(), vehicle_len, now, cars, queues)
+        self.get_idx_to_insert_car(pos.dist_along(), vehicle_len, false, now, cars, queues)
     }
 
     /// Get all cars in the queue, not including the laggy head or blockages.
@@ -388,6 +388,22 @@ impl WalkingSimState {
         }
     }
 
+    pub fn current_speed(&self, id: PedestrianID) -> Option<Speed> {
+        let p = self.peds.get(&id)?;
+        if let PedState::Crossing {
+            ref dist_int,
+            ref time_int,
+            ..
+        } = p.state
+        {
+            let dt = time_int.end - time_int.start;
+            if dt > Duration::ZERO {
+                return Some(Speed::from_dist_time(dist_int.end - dist_int.start, dt));
+            }
+        }
+        Some(Speed::ZERO)
+    }
+
     pub fn agent_properties(&self, map: &Map, id: PedestrianID, now: Time) -> AgentProperties {
         let p = &self.peds[&id];
 
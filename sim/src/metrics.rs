@@ -0,0 +1,382 @@
+//! Key performance indicators measured from a simulation's `Analytics`. Challenges, dashboards,
+//! and headless runs all score things with these, so the same number means the same thing
+//! everywhere. Since prebaked results are just `Analytics` too, every metric can compare the live
+//! simulation against the baseline.
+
+use std::collections::BTreeSet;
+
+use abstutil::prettyprint_usize;
+use geom::{Distance, Duration, Speed, Time, UnitFmt};
+use map_model::{Map, RoadID};
+use synthpop::TripMode;
+
+use crate::{AgentType, Analytics, DelaySummary};
+
+/// Something to measure about a simulation run
+pub trait Metric {
+    fn name(&self) -> &'static str;
+    fn unit(&self) -> MetricUnit;
+    fn better(&self) -> Better;
+    fn aggregation(&self) -> Aggregation;
+    /// Measure everything that happened before `now`. None if there's nothing to measure yet, like
+    /// no trips having finished.
+    fn measure(&self, analytics: &Analytics, map: &Map, now: Time) -> Option<f64>;
+
+    /// Measure and format the result, like "3 minutes" or "12.5%"
+    fn describe(&self, analytics: &Analytics, map: &Map, now: Time, fmt: &UnitFmt) -> String {
+        match self.measure(analytics, map, now) {
+            Some(value) => self.unit().describe(value, fmt),
+            None => "none yet".to_string(),
+        }
+    }
+}
+
+/// What a metric's raw value means
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricUnit {
+    /// In seconds
+    Duration,
+    /// In meters per second
+    Speed,
+    Kilograms,
+    /// From 0 to 100
+    Percent,
+    Count,
+}
+
+impl MetricUnit {
+    pub fn describe(self, value: f64, fmt: &UnitFmt) -> String {
+        match self {
+            MetricUnit::Duration => Duration::seconds(value).to_string(fmt),
+            MetricUnit::Speed => Speed::meters_per_second(value).to_string(fmt),
+            MetricUnit::Kilograms => format!("{} kg", prettyprint_usize(value.round() as usize)),
+            MetricUnit::Percent => format!("{:.1}%", value),
+            MetricUnit::Count => {
+                if value < 0.0 {
+                    format!("-{}", prettyprint_usize((-value).round() as usize))
+                } else {
+                    prettyprint_usize(value.round() as usize)
+                }
+            }
+        }
+    }
+}
+
+/// Which direction of a metric is good
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Better {
+    Lower,
+    Higher,
+}
+
+impl Better {
+    pub fn is_improvement(self, before: f64, after: f64) -> bool {
+        match self {
+            Better::Lower => after < before,
+            Better::Higher => after > before,
+        }
+    }
+
+    /// How much better `after` is than `before`, as a percent of `before`. Negative if it got
+    /// worse.
+    pub fn pct_improvement(self, before: f64, after: f64) -> f64 {
+        if before == 0.0 {
+            return 0.0;
+        }
+        let change = 100.0 * (after - before) / before.abs();
+        match self {
+            Better::Lower => -change,
+            Better::Higher => change,
+        }
+    }
+}
+
+/// How a metric combines individual observations
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Aggregation {
+    /// Averaged over trips or vehicles, so it doesn't grow as the simulation runs
+    Mean,
+    /// Summed, so it keeps growing as the simulation runs. Only compare values measured at the
+    /// same time.
+    Total,
+    /// A percent of all trips
+    Share,
+}
+
+impl Aggregation {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Aggregation::Mean => "average",
+            Aggregation::Total => "total",
+            Aggregation::Share => "share",
+        }
+    }
+}
+
+/// The metrics that don't need anything besides the simulation. `BoundaryRoadVolume` depends on
+/// where a proposal's edits are, so it's not included.
+pub fn all_metrics() -> Vec<Box<dyn Metric + Send>> {
+    vec![
+        Box::new(MeanTripTime),
+        Box::new(CancelledTrips),
+        Box::new(AverageDelay),
+        Box::new(BusSpeed),
+        Box::new(Co2Emissions),
+        Box::new(ActiveTravelShare),
+        Box::new(DrivingShare),
+    ]
+}
+
+/// How long finished trips took
+pub struct MeanTripTime;
+
+impl Metric for MeanTripTime {
+    fn name(&self) -> &'static str {
+        "Average trip time"
+    }
+    fn unit(&self) -> MetricUnit {
+        MetricUnit::Duration
+    }
+    fn better(&self) -> Better {
+        Better::Lower
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Mean
+    }
+    fn measure(&self, analytics: &Analytics, _: &Map, now: Time) -> Option<f64> {
+        let mut count = 0;
+        let mut total = 0.0;
+        for (t, _, _, maybe_dt) in &analytics.finished_trips {
+            if *t > now {
+                break;
+            }
+            if let Some(dt) = maybe_dt {
+                count += 1;
+                total += dt.inner_seconds();
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        Some(total / (count as f64))
+    }
+}
+
+pub struct CancelledTrips;
+
+impl Metric for CancelledTrips {
+    fn name(&self) -> &'static str {
+        "Cancelled trips"
+    }
+    fn unit(&self) -> MetricUnit {
+        MetricUnit::Count
+    }
+    fn better(&self) -> Better {
+        Better::Lower
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Total
+    }
+    fn measure(&self, analytics: &Analytics, _: &Map, now: Time) -> Option<f64> {
+        Some(
+            analytics
+                .finished_trips
+                .iter()
+                .take_while(|(t, _, _, _)| *t <= now)
+                .filter(|(_, _, _, maybe_dt)| maybe_dt.is_none())
+                .count() as f64,
+        )
+    }
+}
+
+/// How long vehicles waited at intersections before turning. Delays aren't recorded by time, so
+/// this ignores `now` and covers everything so far.
+pub struct AverageDelay;
+
+impl Metric for AverageDelay {
+    fn name(&self) -> &'static str {
+        "Average delay at intersections"
+    }
+    fn unit(&self) -> MetricUnit {
+        MetricUnit::Duration
+    }
+    fn better(&self) -> Better {
+        Better::Lower
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Mean
+    }
+    fn measure(&self, analytics: &Analytics, _: &Map, _: Time) -> Option<f64> {
+        let mut summary = DelaySummary::new();
+        for per_road in analytics.approach_delays.values() {
+            for delays in per_road.values() {
+                summary.merge(delays);
+            }
+        }
+        summary.mean().map(|dt| dt.inner_seconds())
+    }
+}
+
+/// How fast buses moved along roads, including any wait at the end of each road. Only full hours
+/// before `now` count.
+pub struct BusSpeed;
+
+impl Metric for BusSpeed {
+    fn name(&self) -> &'static str {
+        "Average bus speed"
+    }
+    fn unit(&self) -> MetricUnit {
+        MetricUnit::Speed
+    }
+    fn better(&self) -> Better {
+        Better::Higher
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Mean
+    }
+    fn measure(&self, analytics: &Analytics, _: &Map, now: Time) -> Option<f64> {
+        let mut dist = Distance::ZERO;
+        let mut duration = Duration::ZERO;
+        for ((_, hour), (d, dt)) in &analytics.bus_road_speeds {
+            if *hour < now.get_hours() {
+                dist += *d;
+                duration += *dt;
+            }
+        }
+        if duration == Duration::ZERO {
+            return None;
+        }
+        Some(Speed::from_dist_time(dist, duration).inner_meters_per_second())
+    }
+}
+
+/// See `Analytics::co2_emissions_kg`
+pub struct Co2Emissions;
+
+impl Metric for Co2Emissions {
+    fn name(&self) -> &'static str {
+        "CO2 emissions"
+    }
+    fn unit(&self) -> MetricUnit {
+        MetricUnit::Kilograms
+    }
+    fn better(&self) -> Better {
+        Better::Lower
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Total
+    }
+    fn measure(&self, analytics: &Analytics, map: &Map, now: Time) -> Option<f64> {
+        Some(analytics.co2_emissions_kg(map, now))
+    }
+}
+
+/// Finished trips made on foot or by bike
+pub struct ActiveTravelShare;
+
+impl Metric for ActiveTravelShare {
+    fn name(&self) -> &'static str {
+        "Trips walking or cycling"
+    }
+    fn unit(&self) -> MetricUnit {
+        MetricUnit::Percent
+    }
+    fn better(&self) -> Better {
+        Better::Higher
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Share
+    }
+    fn measure(&self, analytics: &Analytics, _: &Map, now: Time) -> Option<f64> {
+        mode_share(analytics, now, &[TripMode::Walk, TripMode::Bike])
+    }
+}
+
+/// Finished trips made by car
+pub struct DrivingShare;
+
+impl Metric for DrivingShare {
+    fn name(&self) -> &'static str {
+        "Trips by car"
+    }
+    fn unit(&self) -> MetricUnit {
+        MetricUnit::Percent
+    }
+    fn better(&self) -> Better {
+        Better::Lower
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Share
+    }
+    fn measure(&self, analytics: &Analytics, _: &Map, now: Time) -> Option<f64> {
+        mode_share(analytics, now, &[TripMode::Drive])
+    }
+}
+
+/// How many cars and buses crossed some roads, usually the ones surrounding an area where traffic
+/// might be displaced. Only full hours before `now` count.
+pub struct BoundaryRoadVolume {
+    pub roads: BTreeSet<RoadID>,
+}
+
+impl Metric for BoundaryRoadVolume {
+    fn name(&self) -> &'static str {
+        "Traffic on boundary roads"
+    }
+    fn unit(&self) -> MetricUnit {
+        MetricUnit::Count
+    }
+    fn better(&self) -> Better {
+        Better::Lower
+    }
+    fn aggregation(&self) -> Aggregation {
+        Aggregation::Total
+    }
+    fn measure(&self, analytics: &Analytics, _: &Map, now: Time) -> Option<f64> {
+        let mut total = 0;
+        for ((r, agent_type, hour), count) in &analytics.road_thruput.counts {
+            if *hour < now.get_hours()
+                && matches!(agent_type, AgentType::Car | AgentType::Bus)
+                && self.roads.contains(r)
+            {
+                total += count;
+            }
+        }
+        Some(total as f64)
+    }
+}
+
+/// Percent of finished trips using any of these modes
+fn mode_share(analytics: &Analytics, now: Time, modes: &[TripMode]) -> Option<f64> {
+    let mut matching = 0;
+    let mut total = 0;
+    for (t, _, mode, maybe_dt) in &analytics.finished_trips {
+        if *t > now {
+            break;
+        }
+        if maybe_dt.is_some() {
+            total += 1;
+            if modes.contains(mode) {
+                matching += 1;
+            }
+        }
+    }
+    if total == 0 {
+        return None;
+    }
+    Some(100.0 * (matching as f64) / (total as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn direction_of_good() {
+        assert!(Better::Lower.is_improvement(10.0, 8.0));
+        assert!(!Better::Higher.is_improvement(10.0, 8.0));
+        assert_eq!(Better::Lower.pct_improvement(10.0, 8.0), 20.0);
+        assert_eq!(Better::Higher.pct_improvement(10.0, 8.0), -20.0);
+        assert_eq!(Better::Higher.pct_improvement(0.0, 8.0), 0.0);
+    }
+}
@@ -0,0 +1,250 @@
+//! A single simulation run depends on its RNG seed, so small differences between two runs are
+//! often just noise. Running the same scenario many times with different seeds shows how much
+//! the results actually vary.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::Map;
+use synthpop::{Scenario, TripMode};
+
+use crate::{AlertHandler, Sim, SimOptions};
+
+/// KPIs from one simulation run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunKPIs {
+    pub rng_seed: u64,
+    pub finished_trips: usize,
+    pub cancelled_trips: usize,
+    /// Summed over all finished trips. Use f64 seconds, since a serialized Duration has a low cap.
+    pub total_trip_duration_seconds: f64,
+    /// The average duration of finished trips of each mode, in seconds
+    pub mean_trip_duration_seconds: BTreeMap<TripMode, f64>,
+}
+
+impl RunKPIs {
+    pub fn new(sim: &Sim, rng_seed: u64) -> Self {
+        let mut finished_trips = 0;
+        let mut cancelled_trips = 0;
+        let mut total_trip_duration_seconds = 0.0;
+        let mut per_mode: BTreeMap<TripMode, (usize, f64)> = BTreeMap::new();
+        for (_, _, mode, maybe_duration) in &sim.get_analytics().finished_trips {
+            if let Some(dt) = maybe_duration {
+                finished_trips += 1;
+                total_trip_duration_seconds += dt.inner_seconds();
+                let entry = per_mode.entry(*mode).or_insert((0, 0.0));
+                entry.0 += 1;
+                entry.1 += dt.inner_seconds();
+            } else {
+                cancelled_trips += 1;
+            }
+        }
+        Self {
+            rng_seed,
+            finished_trips,
+            cancelled_trips,
+            total_trip_duration_seconds,
+            mean_trip_duration_seconds: per_mode
+                .into_iter()
+                .map(|(mode, (count, total))| (mode, total / (count as f64)))
+                .collect(),
+        }
+    }
+
+    pub fn mean_trip_duration_seconds(&self) -> f64 {
+        if self.finished_trips == 0 {
+            0.0
+        } else {
+            self.total_trip_duration_seconds / (self.finished_trips as f64)
+        }
+    }
+}
+
+/// The same scenario on the same map, simulated once per RNG seed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultiRunResults {
+    pub map_name: MapName,
+    pub scenario_name: String,
+    pub edits_name: String,
+    pub runs: Vec<RunKPIs>,
+}
+
+impl MultiRunResults {
+    /// Simulate the scenario once per seed, in parallel. If `duration` is omitted, run until a
+    /// few hours after the end of the day, like prebaking does.
+    pub fn simulate(
+        map: &Map,
+        scenario: &Scenario,
+        rng_seeds: Vec<u64>,
+        duration: Option<Duration>,
+        timer: &mut Timer,
+    ) -> Self {
+        let runs = timer.parallelize_polite(
+            &format!("simulate {} runs", rng_seeds.len()),
+            rng_seeds,
+            |rng_seed| {
+                let sim =
+                    simulate_headless(map, scenario, rng_seed, duration, &mut Timer::throwaway());
+                RunKPIs::new(&sim, rng_seed)
+            },
+        );
+        Self {
+            map_name: map.get_name().clone(),
+            scenario_name: scenario.scenario_name.clone(),
+            edits_name: map.get_edits().edits_name.clone(),
+            runs,
+        }
+    }
+
+    pub fn finished_trips(&self) -> Option<Estimate> {
+        Estimate::new(self.runs.iter().map(|r| r.finished_trips as f64).collect())
+    }
+
+    pub fn cancelled_trips(&self) -> Option<Estimate> {
+        Estimate::new(self.runs.iter().map(|r| r.cancelled_trips as f64).collect())
+    }
+
+    /// In seconds
+    pub fn mean_trip_duration(&self) -> Option<Estimate> {
+        Estimate::new(
+            self.runs
+                .iter()
+                .map(|r| r.mean_trip_duration_seconds())
+                .collect(),
+        )
+    }
+
+    /// In seconds. Runs without any finished trips of this mode are skipped.
+    pub fn mean_trip_duration_for_mode(&self, mode: TripMode) -> Option<Estimate> {
+        Estimate::new(
+            self.runs
+                .iter()
+                .filter_map(|r| r.mean_trip_duration_seconds.get(&mode).cloned())
+                .collect(),
+        )
+    }
+}
+
+/// Simulate the scenario once without any UI, with alerts silenced. If `duration` is omitted, run
+/// until a few hours after the end of the day, like prebaking does.
+pub fn simulate_headless(
+    map: &Map,
+    scenario: &Scenario,
+    rng_seed: u64,
+    duration: Option<Duration>,
+    timer: &mut Timer,
+) -> Sim {
+    let mut opts = SimOptions::new("multirun");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(map, opts);
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+    sim.instantiate(scenario, map, &mut rng, timer);
+    let duration =
+        duration.unwrap_or_else(|| sim.get_end_of_day() - Time::START_OF_DAY + Duration::hours(3));
+    sim.timed_step(map, duration, &mut None, timer);
+    sim
+}
+
+/// A sample mean along with its 95% confidence interval
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    /// The confidence interval is `mean +/- half_width`. Zero with only one sample, since the
+    /// spread is unknown.
+    pub half_width: f64,
+    pub samples: usize,
+}
+
+impl Estimate {
+    /// None if there are no samples
+    pub fn new(samples: Vec<f64>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        if samples.len() == 1 {
+            return Some(Self {
+                mean,
+                half_width: 0.0,
+                samples: 1,
+            });
+        }
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        let std_error = (variance / n).sqrt();
+        Some(Self {
+            mean,
+            half_width: t_critical_value(samples.len() - 1) * std_error,
+            samples: samples.len(),
+        })
+    }
+
+    pub fn low(&self) -> f64 {
+        self.mean - self.half_width
+    }
+
+    pub fn high(&self) -> f64 {
+        self.mean + self.half_width
+    }
+
+    /// Is the value inside the confidence interval?
+    pub fn contains(&self, value: f64) -> bool {
+        self.low() <= value && value <= self.high()
+    }
+
+    /// Do the confidence intervals overlap? If so, the difference between the two might just be
+    /// noise.
+    pub fn overlaps(&self, other: &Estimate) -> bool {
+        self.low() <= other.high() && other.low() <= self.high()
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.1} ± {:.1}", self.mean, self.half_width)
+    }
+}
+
+/// The two-tailed 95% critical value of Student's t-distribution
+fn t_critical_value(degrees_of_freedom: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    if degrees_of_freedom == 0 {
+        f64::INFINITY
+    } else if degrees_of_freedom <= TABLE.len() {
+        TABLE[degrees_of_freedom - 1]
+    } else {
+        1.96
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(Estimate::new(Vec::new()), None);
+
+        let single = Estimate::new(vec![3.0]).unwrap();
+        assert_eq!(single.mean, 3.0);
+        assert_eq!(single.half_width, 0.0);
+
+        let four = Estimate::new(vec![10.0, 12.0, 14.0, 16.0]).unwrap();
+        // Sample variance is 20/3
+        let expected = 3.182 * (20.0_f64 / 3.0 / 4.0).sqrt();
+        assert!((four.half_width - expected).abs() < 1e-9);
+        assert!(four.overlaps(&Estimate::new(vec![16.0, 18.0]).unwrap()));
+        assert!(!four.overlaps(&Estimate::new(vec![100.0, 101.0]).unwrap()));
+    }
+}
@@ -2,7 +2,10 @@ use std::collections::BTreeSet;
 
 use geom::Time;
 use map_model::{IntersectionID, LaneID, Map, PathStep, Position, Traversable};
-use synthpop::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+use synthpop::{
+    IndividTrip, ParkAndRide, PersonSpec, PickUpDropOff, Scenario, TripEndpoint, TripMode,
+    TripPurpose, ValueOfTime, VehicleMix,
+};
 
 use crate::{AgentID, CarID, DrivingSimState, Event, TripID, VehicleType};
 
@@ -107,9 +110,14 @@ impl TrafficRecorder {
                 .map(|trip| PersonSpec {
                     orig_id: None,
                     trips: vec![trip],
+                    demographics: None,
                 })
                 .collect::<Vec<_>>(),
             only_seed_buses: None,
+            vehicle_mix: VehicleMix::default(),
+            park_and_ride: ParkAndRide::default(),
+            value_of_time: ValueOfTime::default(),
+            pick_up_drop_off: PickUpDropOff::default(),
         }
         .save();
     }
@@ -0,0 +1,215 @@
+//! Simulate trips that cross between adjacent maps. Each map runs its own `Sim`; a trip crossing a
+//! stitched border ends at the border in the first map, and a new person continues the trip from
+//! the matching border in the next map.
+
+use std::collections::BTreeMap;
+
+use rand_xorshift::XorShiftRng;
+
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::{IntersectionID, Map, Region};
+use synthpop::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+
+use crate::{Sim, SimOptions, TripID};
+
+/// A trip between two maps in a region. If both endpoints are in the same map, it's an ordinary
+/// trip.
+#[derive(Clone)]
+pub struct RegionalTrip {
+    pub depart: Time,
+    pub purpose: TripPurpose,
+    pub mode: TripMode,
+    /// The index of the map, and a place in it
+    pub from: (usize, TripEndpoint),
+    pub to: (usize, TripEndpoint),
+}
+
+/// When a trip reaches the border of one map, continue it in another
+struct Handoff {
+    map: usize,
+    entrance: IntersectionID,
+    destination: TripEndpoint,
+    purpose: TripPurpose,
+    mode: TripMode,
+}
+
+pub struct RegionalSim {
+    pub region: Region,
+    /// One per map, in the same order
+    pub sims: Vec<Sim>,
+    /// Keyed by the map and trip that'll reach a border
+    handoffs: BTreeMap<(usize, TripID), Handoff>,
+    /// For each map, how many finished trips have already been checked for handoffs
+    finished_trips_seen: Vec<usize>,
+    rng: XorShiftRng,
+}
+
+impl RegionalSim {
+    /// Handoffs are detected through finished trips, so analytics must be recorded.
+    pub fn new(region: Region, opts: &SimOptions, rng: XorShiftRng) -> RegionalSim {
+        assert!(
+            !opts.skip_analytics,
+            "RegionalSim needs analytics to notice when trips reach a border"
+        );
+        let sims = region
+            .maps
+            .iter()
+            .map(|map| Sim::new(map, opts.clone()))
+            .collect();
+        RegionalSim {
+            finished_trips_seen: vec![0; region.maps.len()],
+            region,
+            sims,
+            handoffs: BTreeMap::new(),
+            rng,
+        }
+    }
+
+    pub fn instantiate(&mut self, trips: Vec<RegionalTrip>, timer: &mut Timer) {
+        let mut scenarios: Vec<Scenario> = self
+            .region
+            .maps
+            .iter()
+            .map(|map| Scenario::empty(map, "regional trips"))
+            .collect();
+        // For each map, the scenario's person index, paired with the handoff for their trip
+        let mut pending: Vec<Vec<(usize, Handoff)>> =
+            self.region.maps.iter().map(|_| Vec::new()).collect();
+
+        let mut unroutable = 0;
+        timer.start_iter("plan regional trips", trips.len());
+        for trip in trips {
+            timer.next();
+            let (map1, from) = trip.from;
+            let (map2, to) = trip.to;
+            let destination = if map1 == map2 {
+                to
+            } else if let Some((exit, entrance)) =
+                self.best_crossing(map1, from, map2, to, trip.mode)
+            {
+                pending[map1].push((
+                    scenarios[map1].people.len(),
+                    Handoff {
+                        map: map2,
+                        entrance,
+                        destination: to,
+                        purpose: trip.purpose,
+                        mode: trip.mode,
+                    },
+                ));
+                TripEndpoint::Border(exit)
+            } else {
+                unroutable += 1;
+                continue;
+            };
+            scenarios[map1].people.push(PersonSpec {
+                orig_id: None,
+                trips: vec![IndividTrip::new(
+                    trip.depart,
+                    trip.purpose,
+                    from,
+                    destination,
+                    trip.mode,
+                )],
+                demographics: None,
+            });
+        }
+        if unroutable > 0 {
+            warn!(
+                "{} regional trips can't cross between their maps, skipping them",
+                unroutable
+            );
+        }
+
+        for (idx, scenario) in scenarios.into_iter().enumerate() {
+            let sim = &mut self.sims[idx];
+            let first_person = sim.get_all_people().len();
+            sim.instantiate(&scenario, &self.region.maps[idx], &mut self.rng, timer);
+            for (person, handoff) in pending[idx].drain(..) {
+                let trip = sim.get_all_people()[first_person + person].trips[0];
+                self.handoffs.insert((idx, trip), handoff);
+            }
+        }
+    }
+
+    /// Advance every map by `dt`. Trips reaching a border during this step continue in the next
+    /// map from the end of the step, so handoffs are only as precise as `dt`.
+    pub fn step(&mut self, dt: Duration, timer: &mut Timer) {
+        for (sim, map) in self.sims.iter_mut().zip(self.region.maps.iter()) {
+            sim.timed_step(map, dt, &mut None, timer);
+        }
+
+        let mut continuations: Vec<Vec<PersonSpec>> =
+            self.region.maps.iter().map(|_| Vec::new()).collect();
+        for (idx, sim) in self.sims.iter().enumerate() {
+            let finished = &sim.get_analytics().finished_trips;
+            for (_, trip, _, duration) in &finished[self.finished_trips_seen[idx]..] {
+                if let Some(handoff) = self.handoffs.remove(&(idx, *trip)) {
+                    // A cancelled trip never reached the border
+                    if duration.is_none() {
+                        continue;
+                    }
+                    continuations[handoff.map].push(PersonSpec {
+                        orig_id: None,
+                        trips: vec![IndividTrip::new(
+                            self.sims[handoff.map].time(),
+                            handoff.purpose,
+                            TripEndpoint::Border(handoff.entrance),
+                            handoff.destination,
+                            handoff.mode,
+                        )],
+                        demographics: None,
+                    });
+                }
+            }
+            self.finished_trips_seen[idx] = finished.len();
+        }
+
+        for (idx, people) in continuations.into_iter().enumerate() {
+            if people.is_empty() {
+                continue;
+            }
+            let map = &self.region.maps[idx];
+            let mut scenario = Scenario::empty(map, "regional handoffs");
+            scenario.people = people;
+            self.sims[idx].instantiate(&scenario, map, &mut self.rng, timer);
+        }
+    }
+
+    pub fn time(&self) -> Time {
+        self.sims[0].time()
+    }
+
+    /// True when every map is done and no trip is waiting to cross a border
+    pub fn is_done(&self) -> bool {
+        self.handoffs.is_empty() && self.sims.iter().all(|sim| sim.is_done())
+    }
+
+    /// Pick the border crossing that minimizes the estimated time of both legs of the trip
+    fn best_crossing(
+        &self,
+        map1: usize,
+        from: TripEndpoint,
+        map2: usize,
+        to: TripEndpoint,
+        mode: TripMode,
+    ) -> Option<(IntersectionID, IntersectionID)> {
+        let estimate = |map: &Map, from: TripEndpoint, to: TripEndpoint| {
+            let req = TripEndpoint::path_req(from, to, mode, map)?;
+            let path = map.pathfind(req).ok()?;
+            Some(path.estimate_duration(map, None))
+        };
+
+        self.region
+            .crossings(map1, map2)
+            .into_iter()
+            .filter_map(|(exit, entrance)| {
+                let leg1 = estimate(&self.region.maps[map1], from, TripEndpoint::Border(exit))?;
+                let leg2 = estimate(&self.region.maps[map2], TripEndpoint::Border(entrance), to)?;
+                Some((leg1 + leg2, (exit, entrance)))
+            })
+            .min_by_key(|(duration, _)| *duration)
+            .map(|(_, crossing)| crossing)
+    }
+}
@@ -3,13 +3,16 @@
 
 use std::collections::HashMap;
 
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
-use geom::Distance;
+use geom::{Distance, Duration, Time};
 use map_model::{
-    BuildingID, IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
-    Position, Traversable, Turn, TurnID,
+    BuildingID, IntersectionID, KerbUseType, Lane, LaneID, Map, Path, PathConstraints, PathRequest,
+    PathStep, Position, Traversable, Turn, TurnID,
 };
+use synthpop::price_as_time;
 
 use crate::mechanics::Queue;
 use crate::{
@@ -17,6 +20,12 @@ use crate::{
     TripID, TripPhaseType, Vehicle, VehicleType,
 };
 
+/// Roughly how much longer somebody waits for each vehicle ahead of them in a queue, when deciding
+/// whether a priced lane is worth it
+const QUEUED_VEHICLE_DELAY: Duration = Duration::const_seconds(2.0);
+/// Further than this from the building, a pick-up/drop-off bay isn't worth using
+const MAX_DROP_OFF_BAY_DIST: Distance = Distance::const_meters(50.0);
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub(crate) struct Router {
     /// Front is always the current step
@@ -33,6 +42,10 @@ pub(crate) enum ActionAtEnd {
     StopBiking(SidewalkSpot),
     BusAtStop,
     GiveUpOnParking,
+    /// A delivery vehicle couldn't find a free loading bay, so it stops in the lane for a while
+    DoublePark,
+    /// Stop in the lane for this long to let somebody out, then leave
+    DropOffInLane(Duration),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -44,6 +57,10 @@ enum Goal {
         /// No parking available at all!
         stuck_end_dist: Option<Distance>,
         started_looking: bool,
+        /// Look for loading bays first
+        delivery: bool,
+        /// Where a delivery vehicle stopped in the lane. This only happens once per trip.
+        double_parked: Option<Distance>,
     },
     EndAtBorder {
         end_dist: Distance,
@@ -55,6 +72,14 @@ enum Goal {
     FollowTransitRoute {
         end_dist: Distance,
     },
+    /// Stop briefly near a building to let somebody out, then leave. `end_dist` is the frontage of
+    /// the building along the last lane, until a free pick-up/drop-off bay is found.
+    DropOff {
+        end_dist: Distance,
+        bay: Option<ParkingSpot>,
+        looked_for_bay: bool,
+        dwell_time: Duration,
+    },
 }
 
 impl Router {
@@ -71,7 +96,7 @@ impl Router {
         }
     }
 
-    pub fn park_near(owner: CarID, path: Path, bldg: BuildingID) -> Router {
+    pub fn park_near(owner: CarID, path: Path, bldg: BuildingID, delivery: bool) -> Router {
         Router {
             path,
             goal: Goal::ParkNearBuilding {
@@ -79,7 +104,22 @@ impl Router {
                 spot: None,
                 stuck_end_dist: None,
                 started_looking: false,
+                delivery,
+                double_parked: None,
+            },
+            owner,
+        }
+    }
+
+    pub fn drop_off(owner: CarID, path: Path, dwell_time: Duration) -> Router {
+        Router {
+            goal: Goal::DropOff {
+                end_dist: path.get_req().end.dist_along(),
+                bay: None,
+                looked_for_bay: false,
+                dwell_time,
             },
+            path,
             owner,
         }
     }
@@ -126,10 +166,14 @@ impl Router {
             Goal::ParkNearBuilding {
                 spot,
                 stuck_end_dist,
+                double_parked,
                 ..
-            } => stuck_end_dist.unwrap_or_else(|| spot.unwrap().1),
+            } => stuck_end_dist
+                .or_else(|| spot.map(|(_, dist)| dist))
+                .unwrap_or_else(|| double_parked.unwrap()),
             Goal::BikeThenStop { ref goal } => goal.sidewalk_pos.dist_along(),
             Goal::FollowTransitRoute { end_dist } => end_dist,
+            Goal::DropOff { end_dist, .. } => end_dist,
         }
     }
 
@@ -137,6 +181,11 @@ impl Router {
         &self.path
     }
 
+    /// Skip every step except the last lane, starting from `start` there instead
+    pub fn skip_to_last_step(&mut self, map: &Map, start: Position) {
+        self.path.skip_to_last_step(map, start);
+    }
+
     /// Returns the step just finished
     pub fn advance(
         &mut self,
@@ -159,10 +208,13 @@ impl Router {
             );
         }
 
-        // Sanity check laws haven't been broken
+        // Sanity check laws haven't been broken -- except for drivers who knowingly break bus lane
+        // rules
         if let Traversable::Lane(l) = self.head() {
             let lane = map.get_l(l);
-            if !vehicle.vehicle_type.to_constraints().can_use(lane, map) {
+            if !vehicle.vehicle_type.to_constraints().can_use(lane, map)
+                && !(vehicle.vehicle_type == VehicleType::Car && lane.is_bus())
+            {
                 panic!(
                     "{} just wound up on {}, a {:?} (check the OSM tags)",
                     vehicle.id, l, lane.lane_type
@@ -200,6 +252,8 @@ impl Router {
                 ref mut stuck_end_dist,
                 target,
                 ref mut started_looking,
+                delivery,
+                ref mut double_parked,
             } => {
                 if let Some(d) = stuck_end_dist {
                     if *d == front {
@@ -216,6 +270,30 @@ impl Router {
                 if need_new_spot {
                     *started_looking = true;
                     let current_lane = self.path.current_step().as_lane();
+                    if delivery && double_parked.is_none() {
+                        let bays = parking.get_free_bays(
+                            Position::new(current_lane, front),
+                            vehicle,
+                            KerbUseType::LoadingBay,
+                            map,
+                        );
+                        if let Some((new_spot, new_pos)) =
+                            bays.into_iter().min_by_key(|(_, pos)| pos.dist_along())
+                        {
+                            *spot = Some((new_spot, new_pos.dist_along()));
+                            return if new_pos.dist_along() == front {
+                                Some(ActionAtEnd::StartParking(new_spot))
+                            } else {
+                                None
+                            };
+                        }
+                        // Deliveries happen right here, whether there's space or not. Afterwards,
+                        // look for normal parking.
+                        *spot = None;
+                        *double_parked = Some(front);
+                        return Some(ActionAtEnd::DoublePark);
+                    }
+
                     let candidates = parking.get_all_free_spots(
                         Position::new(current_lane, front),
                         vehicle,
@@ -318,6 +396,49 @@ impl Router {
                     None
                 }
             }
+            Goal::DropOff {
+                ref mut end_dist,
+                ref mut bay,
+                ref mut looked_for_bay,
+                dwell_time,
+            } => {
+                if let Some(spot) = *bay {
+                    if !parking.is_free(spot) {
+                        // Somebody else beat us to the bay. Just stop here.
+                        *bay = None;
+                        *end_dist = front;
+                        return Some(ActionAtEnd::DropOffInLane(dwell_time));
+                    }
+                } else if !*looked_for_bay {
+                    *looked_for_bay = true;
+                    let current_lane = self.path.current_step().as_lane();
+                    let target_dist = *end_dist;
+                    if let Some((spot, pos)) = parking
+                        .get_free_bays(
+                            Position::new(current_lane, front),
+                            vehicle,
+                            KerbUseType::PickUpDropOff,
+                            map,
+                        )
+                        .into_iter()
+                        .filter(|(_, pos)| {
+                            (pos.dist_along() - target_dist).abs() <= MAX_DROP_OFF_BAY_DIST
+                        })
+                        .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs())
+                    {
+                        *bay = Some(spot);
+                        *end_dist = pos.dist_along();
+                    }
+                }
+
+                if *end_dist != front {
+                    return None;
+                }
+                Some(match *bay {
+                    Some(spot) => ActionAtEnd::StartParking(spot),
+                    None => ActionAtEnd::DropOffInLane(dwell_time),
+                })
+            }
         }
     }
 
@@ -326,6 +447,10 @@ impl Router {
         queues: &HashMap<Traversable, Queue>,
         map: &Map,
         handle_uber_turns: bool,
+        bus_lane_violation_pct: u8,
+        now: Time,
+        value_of_time: u32,
+        occupancy: usize,
     ) {
         // if we're already in the uber-turn, we're committed, but if we're about to enter one, lock
         // in the best path through it now.
@@ -352,9 +477,28 @@ impl Router {
             let parent = map.get_parent(orig_target_lane);
             let next_parent = map.get_l(next_lane).src_i;
             let constraints = self.owner.vehicle_type.to_constraints();
+            let dir = map.get_l(orig_target_lane).dir;
+            // How many vehicles are ahead in the shortest queue this driver can use for free?
+            let free_queue = parent
+                .lanes
+                .iter()
+                .filter(|l| l.dir == dir && constraints.can_use(l, map))
+                .map(|l| queues[&Traversable::Lane(l.id)].target_lane_penalty().0)
+                .min()
+                .unwrap_or(0);
+            let may_use_bus_lane = |lane: &Lane| {
+                self.may_use_hov_lane(lane, map, occupancy)
+                    || self.would_violate_bus_lane(lane, map, bus_lane_violation_pct)
+                    || self.would_pay_for_lane(lane, free_queue, queues, map, now, value_of_time)
+            };
 
             let compute_cost = |turn1: &Turn, lane: LaneID| {
-                let (lt, lc, mut slow_lane) = turn1.penalty(constraints, map);
+                let (mut lt, lc, mut slow_lane) = turn1.penalty(constraints, map);
+                // Somebody carrying enough passengers, willing to break the rules, or paying for a
+                // priced lane treats a bus lane like any other
+                if may_use_bus_lane(map.get_l(lane)) {
+                    lt = 0;
+                }
                 let (vehicles, mut bike) = queues[&Traversable::Lane(lane)].target_lane_penalty();
 
                 // The magic happens here. We have different penalties:
@@ -383,11 +527,10 @@ impl Router {
 
             // Look for other candidates, and assign a cost to each.
             let mut original_cost = None;
-            let dir = map.get_l(orig_target_lane).dir;
             let best = parent
                 .lanes
                 .iter()
-                .filter(|l| l.dir == dir && constraints.can_use(l, map))
+                .filter(|l| l.dir == dir && (constraints.can_use(l, map) || may_use_bus_lane(l)))
                 .filter_map(|l| {
                     // Make sure we can go from this lane to next_lane.
 
@@ -452,6 +595,56 @@ impl Router {
         }
     }
 
+    /// Are there enough people in this car to use a bus lane open to high-occupancy vehicles?
+    fn may_use_hov_lane(&self, lane: &Lane, map: &Map, occupancy: usize) -> bool {
+        self.owner.vehicle_type == VehicleType::Car
+            && lane.is_bus()
+            && map.get_r(lane.id.road).allows_hov(occupancy)
+    }
+
+    /// Is this driver willing to illegally use a bus lane to skip a queue? The same driver always
+    /// decides the same way along one road.
+    fn would_violate_bus_lane(&self, lane: &Lane, map: &Map, default_pct: u8) -> bool {
+        if self.owner.vehicle_type != VehicleType::Car || !lane.is_bus() {
+            return false;
+        }
+        let pct = map
+            .get_r(lane.id.road)
+            .bus_lane_enforcement
+            .violation_pct(default_pct);
+        if pct == 0 {
+            return false;
+        }
+        let mut rng =
+            XorShiftRng::seed_from_u64(((self.owner.id as u64) << 32) | (lane.id.road.0 as u64));
+        rng.gen_range(0..100) < pct
+    }
+
+    /// Would this driver pay to use a priced bus lane? They guess how much time they'd save from
+    /// how many vehicles are ahead of them in the bus lane and in the shortest free queue.
+    fn would_pay_for_lane(
+        &self,
+        lane: &Lane,
+        free_queue: usize,
+        queues: &HashMap<Traversable, Queue>,
+        map: &Map,
+        now: Time,
+        value_of_time: u32,
+    ) -> bool {
+        if self.owner.vehicle_type != VehicleType::Car || !lane.is_bus() {
+            return false;
+        }
+        let price = match map.get_r(lane.id.road).pricing.priced_lane_at(now) {
+            Some(price) => price,
+            None => {
+                return false;
+            }
+        };
+        let ahead = queues[&Traversable::Lane(lane.id)].target_lane_penalty().0;
+        let saved = free_queue.saturating_sub(ahead) as f64;
+        QUEUED_VEHICLE_DELAY * saved >= price_as_time(price, value_of_time)
+    }
+
     pub fn can_lanechange(&self, from: LaneID, to: LaneID, map: &Map) -> bool {
         let steps = self.path.get_steps();
         if steps.len() < 3 {
@@ -488,6 +681,7 @@ impl Router {
             Goal::ParkNearBuilding {
                 started_looking, ..
             } => started_looking,
+            Goal::DropOff { bay, .. } => bay.is_some(),
             _ => false,
         }
     }
@@ -495,7 +689,13 @@ impl Router {
     pub fn get_parking_spot_goal(&self) -> Option<&ParkingSpot> {
         match self.goal {
             Goal::ParkNearBuilding { ref spot, .. } => spot.as_ref().map(|(s, _)| s),
+            Goal::DropOff { ref bay, .. } => bay.as_ref(),
             _ => None,
         }
     }
+
+    /// Does this vehicle leave the simulation after dropping somebody off, instead of parking?
+    pub fn is_drop_off(&self) -> bool {
+        matches!(self.goal, Goal::DropOff { .. })
+    }
 }
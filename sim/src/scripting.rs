@@ -0,0 +1,235 @@
+//! Advanced users can override a few decisions that agents make by writing a small
+//! [Rhai](https://rhai.rs) script, passed in with `--behavior_script`. This avoids recompiling the
+//! sim crate to try out a different route choice model or gap acceptance threshold.
+//!
+//! A script defines any subset of these functions. Anything it doesn't define keeps the normal
+//! behavior.
+//!
+//! - `route_cost(trip, mode, seconds, rank)`: Called for every candidate route a driver or cyclist
+//!   considers. `trip` is the trip ID as an integer, `mode` is "drive" or "bike", `seconds` is the
+//!   routing cost of the route (roughly its travel time, plus penalties), and `rank` is 0 for the
+//!   best route, 1 for the next best, and so on. Return a number; the candidate with the lowest
+//!   result is taken. When this is defined, at least 3 candidates are considered, or more if
+//!   `--route_alternatives` is higher.
+//! - `critical_gap(intersection, num_roads, seconds)`: Called once per stop sign intersection
+//!   when the simulation starts. `seconds` is the default gap that a vehicle giving way accepts in
+//!   priority traffic. Return the number of seconds to use instead.
+//! - `choose_mode(trip, purpose, mode, meters)`: Called for every trip when a scenario is
+//!   instantiated. `trip` is the ID the trip will get, matching `route_cost`. `purpose` is
+//!   something like "work" or "shopping", `mode` is one of "walk", "bike", "transit", or "drive",
+//!   and `meters` is the straight-line distance between the trip's endpoints. Return one of the
+//!   mode strings. Each trip is considered separately, so changing
+//!   the mode of only some trips in somebody's day may leave their car stranded.
+//!
+//! Scripts are sandboxed. Rhai has no access to files or the network, and each call is limited in
+//! how many operations it can run and how much memory it can allocate. If a call fails or returns
+//! something unexpected, the normal behavior is used and a warning is logged.
+//!
+//! Scripts aren't saved in savestates, so they must be passed in again after loading one.
+
+use anyhow::Result;
+
+use geom::{Distance, Duration};
+use map_model::IntersectionID;
+use synthpop::{TripMode, TripPurpose};
+
+use crate::TripID;
+
+/// A loaded behavior script. See the module docs for the functions it can define.
+pub struct BehaviorScript {
+    path: String,
+    #[cfg(feature = "scripting")]
+    engine: rhai::Engine,
+    #[cfg(feature = "scripting")]
+    ast: rhai::AST,
+}
+
+impl std::fmt::Debug for BehaviorScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "BehaviorScript({})", self.path)
+    }
+}
+
+#[cfg(feature = "scripting")]
+impl BehaviorScript {
+    pub fn load(path: &str) -> Result<BehaviorScript> {
+        let source = String::from_utf8(abstio::slurp_file(path)?)?;
+
+        let mut engine = rhai::Engine::new();
+        // Decisions are made constantly, so a runaway script would stall the whole simulation
+        engine.set_max_operations(100_000);
+        engine.set_max_call_levels(16);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(1024);
+        engine.set_max_array_size(1024);
+        engine.set_max_map_size(1024);
+        engine.disable_symbol("eval");
+
+        let ast = engine
+            .compile(source)
+            .map_err(|err| anyhow!("{} doesn't parse: {}", path, err))?;
+        let script = BehaviorScript {
+            path: path.to_string(),
+            engine,
+            ast,
+        };
+        if !["route_cost", "critical_gap", "choose_mode"]
+            .into_iter()
+            .any(|name| script.defines(name))
+        {
+            bail!(
+                "{} doesn't define route_cost, critical_gap, or choose_mode",
+                path
+            );
+        }
+        Ok(script)
+    }
+
+    pub fn has_route_cost(&self) -> bool {
+        self.defines("route_cost")
+    }
+
+    pub fn route_cost(&self, trip: TripID, mode: TripMode, cost: Duration, rank: usize) -> f64 {
+        let default = cost.inner_seconds();
+        self.call_number(
+            "route_cost",
+            (
+                trip.0 as i64,
+                mode_name(mode).to_string(),
+                default,
+                rank as i64,
+            ),
+        )
+        .unwrap_or(default)
+    }
+
+    pub fn critical_gap(&self, i: IntersectionID, num_roads: usize, default: Duration) -> Duration {
+        if !self.defines("critical_gap") {
+            return default;
+        }
+        match self.call_number(
+            "critical_gap",
+            (i.0 as i64, num_roads as i64, default.inner_seconds()),
+        ) {
+            Some(seconds) if seconds >= 0.0 => Duration::seconds(seconds),
+            Some(seconds) => {
+                warn!("{}: critical_gap returned {} for {}", self.path, seconds, i);
+                default
+            }
+            None => default,
+        }
+    }
+
+    pub fn choose_mode(
+        &self,
+        trip: usize,
+        purpose: TripPurpose,
+        mode: TripMode,
+        dist: Distance,
+    ) -> TripMode {
+        if !self.defines("choose_mode") {
+            return mode;
+        }
+        let result: Option<String> = self.call(
+            "choose_mode",
+            (
+                trip as i64,
+                purpose.to_string(),
+                mode_name(mode).to_string(),
+                dist.inner_meters(),
+            ),
+        );
+        let result = match result {
+            Some(x) => x,
+            None => {
+                return mode;
+            }
+        };
+        match TripMode::all()
+            .into_iter()
+            .find(|m| mode_name(*m) == result)
+        {
+            Some(m) => m,
+            None => {
+                warn!(
+                    "{}: choose_mode returned unknown mode {}",
+                    self.path, result
+                );
+                mode
+            }
+        }
+    }
+
+    fn defines(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+
+    /// Scripts might return an integer or a float
+    fn call_number(&self, name: &str, args: impl rhai::FuncArgs) -> Option<f64> {
+        let result: rhai::Dynamic = self.call(name, args)?;
+        match result.as_float() {
+            Ok(x) => Some(x),
+            Err(_) => match result.as_int() {
+                Ok(x) => Some(x as f64),
+                Err(type_name) => {
+                    warn!(
+                        "{}: {} returned a {}, not a number",
+                        self.path, name, type_name
+                    );
+                    None
+                }
+            },
+        }
+    }
+
+    fn call<T: rhai::Variant + Clone>(&self, name: &str, args: impl rhai::FuncArgs) -> Option<T> {
+        match self
+            .engine
+            .call_fn::<T>(&mut rhai::Scope::new(), &self.ast, name, args)
+        {
+            Ok(x) => Some(x),
+            Err(err) => {
+                warn!("{}: calling {} failed: {}", self.path, name, err);
+                None
+            }
+        }
+    }
+}
+
+/// How scripts refer to each mode
+#[cfg(feature = "scripting")]
+fn mode_name(mode: TripMode) -> &'static str {
+    match mode {
+        TripMode::Walk => "walk",
+        TripMode::Bike => "bike",
+        TripMode::Transit => "transit",
+        TripMode::Drive => "drive",
+    }
+}
+
+/// Without the scripting feature, scripts can't be loaded, so none of these are ever called.
+#[cfg(not(feature = "scripting"))]
+impl BehaviorScript {
+    pub fn load(path: &str) -> Result<BehaviorScript> {
+        bail!(
+            "Can't load {}; the sim crate was built without the scripting feature",
+            path
+        )
+    }
+
+    pub fn has_route_cost(&self) -> bool {
+        false
+    }
+
+    pub fn route_cost(&self, _: TripID, _: TripMode, cost: Duration, _: usize) -> f64 {
+        cost.inner_seconds()
+    }
+
+    pub fn critical_gap(&self, _: IntersectionID, _: usize, default: Duration) -> Duration {
+        default
+    }
+
+    pub fn choose_mode(&self, _: usize, _: TripPurpose, mode: TripMode, _: Distance) -> TripMode {
+        mode
+    }
+}
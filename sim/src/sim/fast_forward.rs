@@ -0,0 +1,148 @@
+//! A mesoscopic shortcut for jumping far ahead in time. Instead of simulating every vehicle's
+//! movement, car and bike trips starting well before the target time are resolved with a simple
+//! queueing model: free-flow travel time along each lane, inflated by how many other
+//! fast-forwarded vehicles entered the same lane that hour, plus a fixed delay at controlled
+//! intersections. The vehicle then appears at the start of the last lane of its path and finishes
+//! the trip in detail, so parking and ending the trip work as usual.
+
+use std::collections::HashMap;
+
+use geom::{Duration, Time};
+use map_model::{LaneID, Map, PathStep, Position, Traversable};
+
+use crate::CreateCar;
+
+/// How many vehicles per hour one lane handles before travel times start to climb
+const LANE_CAPACITY_PER_HOUR: f64 = 1800.0;
+/// Roughly half of a typical red light
+const SIGNAL_DELAY: Duration = Duration::const_seconds(15.0);
+const STOP_SIGN_DELAY: Duration = Duration::const_seconds(5.0);
+
+/// Trades off how closely a fast-forward matches a detailed simulation against how quickly it
+/// finishes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FastForwardAccuracy {
+    /// Simulate everything in detail for the last 45 minutes before the target time
+    High,
+    /// Simulate everything in detail for the last 15 minutes before the target time
+    Balanced,
+    /// Only trips still underway at the target time are simulated in detail
+    Fast,
+}
+
+impl FastForwardAccuracy {
+    pub fn all() -> Vec<FastForwardAccuracy> {
+        vec![
+            FastForwardAccuracy::High,
+            FastForwardAccuracy::Balanced,
+            FastForwardAccuracy::Fast,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            FastForwardAccuracy::High => "high accuracy",
+            FastForwardAccuracy::Balanced => "balanced",
+            FastForwardAccuracy::Fast => "fastest",
+        }
+    }
+
+    /// Trips that would finish their simplified part within this long of the target time are
+    /// simulated in detail instead, so traffic has time to settle into realistic queues.
+    fn detailed_window(self) -> Duration {
+        match self {
+            FastForwardAccuracy::High => Duration::minutes(45),
+            FastForwardAccuracy::Balanced => Duration::minutes(15),
+            FastForwardAccuracy::Fast => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct FastForward {
+    until: Time,
+    accuracy: FastForwardAccuracy,
+    /// How many fast-forwarded vehicles entered each lane during each hour
+    volumes: HashMap<(LaneID, usize), usize>,
+}
+
+impl FastForward {
+    pub fn new(until: Time, accuracy: FastForwardAccuracy) -> FastForward {
+        FastForward {
+            until,
+            accuracy,
+            volumes: HashMap::new(),
+        }
+    }
+
+    /// If this vehicle should skip detailed simulation for most of its path, returns when it
+    /// reaches its last lane and where it should appear there. The vehicle is counted towards
+    /// congestion for everybody fast-forwarded after it.
+    pub fn maybe_skip(
+        &mut self,
+        now: Time,
+        create_car: &CreateCar,
+        map: &Map,
+    ) -> Option<(Time, Position)> {
+        // Buses follow a schedule, so leave them alone
+        if create_car.maybe_route.is_some() || create_car.trip_and_person.is_none() {
+            return None;
+        }
+        let window = self.accuracy.detailed_window();
+        if now + window >= self.until {
+            return None;
+        }
+
+        let path = create_car.router.get_path();
+        let steps = path.get_steps();
+        if steps.len() < 2 {
+            return None;
+        }
+        let last_lane = match steps.back() {
+            Some(PathStep::Lane(l)) => *l,
+            _ => {
+                return None;
+            }
+        };
+        // Leave room for the vehicle at the start of the last lane, before its destination
+        let start_dist = create_car.vehicle.length;
+        if start_dist >= path.get_req().end.dist_along() {
+            return None;
+        }
+
+        let constraints = path.get_req().constraints;
+        let mut time = now;
+        let mut entered = Vec::new();
+        for step in steps.iter().take(steps.len() - 1) {
+            let free_flow = path.dist_crossed_from_step(map, step)
+                / step.max_speed_along(create_car.vehicle.max_speed, constraints, map);
+            match step.as_traversable() {
+                Traversable::Lane(l) => {
+                    let key = (l, (time.inner_seconds() / 3600.0) as usize);
+                    let volume = self.volumes.get(&key).cloned().unwrap_or(0) + 1;
+                    // The BPR link performance function
+                    let ratio = (volume as f64) / LANE_CAPACITY_PER_HOUR;
+                    time += free_flow * (1.0 + 0.15 * ratio.powi(4));
+                    entered.push(key);
+                }
+                Traversable::Turn(t) => {
+                    let i = map.get_i(t.parent);
+                    time += free_flow;
+                    if i.is_traffic_signal() {
+                        time += SIGNAL_DELAY;
+                    } else if i.is_stop_sign() {
+                        time += STOP_SIGN_DELAY;
+                    }
+                }
+            }
+        }
+        if time + window >= self.until {
+            return None;
+        }
+
+        for key in entered {
+            *self.volumes.entry(key).or_insert(0) += 1;
+        }
+        Some((time, Position::new(last_lane, start_dist)))
+    }
+}
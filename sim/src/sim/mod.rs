@@ -1,6 +1,7 @@
 // This file has a jumbled mess of queries, setup, and mutating methods.
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::Arc;
 
 use anyhow::Result;
 use instant::Instant;
@@ -14,23 +15,30 @@ use abstutil::{prettyprint_usize, serialized_size_bytes, Timer};
 use geom::{Distance, Duration, Speed, Time};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints, PathRequest,
-    Position, TransitRoute, Traversable,
+    Position, TransitRoute, TransitRouteID, Traversable,
 };
-use synthpop::OrigPersonID;
+use synthpop::{Demographics, OrigPersonID, VehicleClass};
 
+pub use self::fast_forward::FastForwardAccuracy;
 pub use self::queries::{AgentProperties, DelayCause};
 // TODO Super weird for both of these to wind up here
-pub use self::scenario::{count_parked_cars_per_bldg, rand_dist};
+use self::fast_forward::FastForward;
+pub use self::scenario::{count_parked_cars_per_bldg, rand_dist, ScenarioInstantiation};
+pub use self::warm_start::WarmStart;
 use crate::{
-    AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, DrivingSimState, Event,
-    IntersectionSimState, PandemicModel, ParkedCar, ParkingSim, ParkingSimState, ParkingSpot,
-    Person, PersonID, Router, Scheduler, SidewalkPOI, SidewalkSpot, StartTripArgs, TrafficRecorder,
-    TransitSimState, TripID, TripInfo, TripManager, TripPhaseType, Vehicle, VehicleSpec,
-    VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
+    AgentID, AlertLocation, Analytics, BehaviorScript, Breakpoint, BreakpointHit, Breakpoints,
+    CarID, Command, CreateCar, DrivingSimState, Event, IntersectionSimState, LaneChangingOpts,
+    PandemicModel, ParkedCar, ParkingSim, ParkingSimState, ParkingSpot, Person, PersonID,
+    RetentionPolicy, Router, Scheduler, SidewalkPOI, SidewalkSpot, SimEvent, SimEventType,
+    StartTripArgs, SubscriptionID, Subscriptions, TrafficRecorder, TransitSimState, TripID,
+    TripInfo, TripManager, TripPhaseType, Vehicle, VehicleSpec, VehicleType, WalkingSimState,
+    ARTICULATED_BUS_LENGTH, BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
 };
 
+mod fast_forward;
 mod queries;
 mod scenario;
+mod warm_start;
 
 // TODO Do something else.
 const BLIND_RETRY_TO_SPAWN: Duration = Duration::const_seconds(5.0);
@@ -48,6 +56,8 @@ pub struct Sim {
     pandemic: Option<PandemicModel>,
     scheduler: Scheduler,
     time: Time,
+    /// Set when seeding bus routes from a scenario
+    bus_classes: BTreeMap<TransitRouteID, VehicleClass>,
 
     // These're needed to load from a savestate.
     pub(crate) map_name: MapName,
@@ -62,6 +72,21 @@ pub struct Sim {
     // This is created interactively, and there's no reason to preserve one for savestates.
     #[serde(skip_serializing, skip_deserializing)]
     recorder: Option<TrafficRecorder>,
+    // Also created interactively, just for debugging
+    #[serde(skip_serializing, skip_deserializing)]
+    breakpoints: Breakpoints,
+    // Subscribers live outside the simulation, so there's no reason to preserve them either
+    #[serde(skip_serializing, skip_deserializing)]
+    subscriptions: Subscriptions,
+    // Only used while instantiating the next scenario
+    #[serde(skip_serializing, skip_deserializing)]
+    warm_start: Option<WarmStart>,
+    // Only used while jumping ahead in time
+    #[serde(skip_serializing, skip_deserializing)]
+    fast_forward: Option<FastForward>,
+    // Only used by regression tests, to compare everything that happens between two runs
+    #[serde(skip_serializing, skip_deserializing)]
+    event_log: Option<Vec<String>>,
 
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
@@ -118,6 +143,10 @@ pub struct SimOptions {
     /// Some maps always have this hardcoded on -- see the code for the list.
     #[structopt(long)]
     pub infinite_parking: bool,
+    /// Let everybody board transit vehicles, no matter how crowded they are. Otherwise, riders
+    /// left behind by a full vehicle wait for the next one.
+    #[structopt(long)]
+    pub unlimited_transit_capacity: bool,
     /// Allow all agents to immediately proceed into an intersection, even if they'd hit another
     /// agent. Obviously this destroys realism of the simulation, but can be used to debug
     /// gridlock. Also implies freeform_policy, so vehicles ignore traffic signals.
@@ -127,6 +156,33 @@ pub struct SimOptions {
     /// quickly.
     #[structopt(long)]
     pub skip_analytics: bool,
+    /// The percent of drivers who'll illegally use a bus lane to get around a queue, unless the
+    /// road overrides this. Cameras deter everybody.
+    #[structopt(long, default_value = "0")]
+    pub bus_lane_violation_pct: u8,
+    /// The percent of drivers who habitually go over the speed limit, unless speed enforcement
+    /// along a road deters them. Traffic calming slows everybody down.
+    #[structopt(long, default_value = "0")]
+    pub speeding_pct: u8,
+    /// How far over the speed limit speeding drivers go, as a percent of the limit
+    #[structopt(long, default_value = "20")]
+    pub speeding_over_limit_pct: u8,
+    /// Instead of always taking the single best route, drivers and cyclists pick among this many
+    /// reasonable alternatives, favoring faster ones. 1 means everybody takes the best route.
+    #[structopt(long, default_value = "1")]
+    pub route_alternatives: usize,
+    /// The percent of people with a disability who use a wheelchair, or of everybody if the
+    /// scenario has no demographics. They walk along routes avoiding steps and raised kerbs.
+    #[structopt(long, default_value = "0")]
+    pub wheelchair_pct: u8,
+    /// A Rhai script overriding some decisions agents make, like route choice. Requires building
+    /// with the `scripting` feature. See sim/src/scripting.rs for the functions it can define.
+    #[structopt(long, parse(try_from_str = parse_behavior_script))]
+    pub behavior_script: Option<Arc<BehaviorScript>>,
+    #[structopt(flatten)]
+    pub analytics_retention: RetentionPolicy,
+    #[structopt(flatten)]
+    pub lane_changing: LaneChangingOpts,
 }
 
 impl SimOptions {
@@ -141,8 +197,17 @@ impl SimOptions {
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             infinite_parking: false,
+            unlimited_transit_capacity: false,
             disable_turn_conflicts: false,
             skip_analytics: false,
+            bus_lane_violation_pct: 0,
+            speeding_pct: 0,
+            speeding_over_limit_pct: 20,
+            route_alternatives: 1,
+            wheelchair_pct: 0,
+            behavior_script: None,
+            analytics_retention: RetentionPolicy::default(),
+            lane_changing: LaneChangingOpts::default(),
         }
     }
 }
@@ -158,6 +223,10 @@ fn parse_rng(x: &str) -> Result<XorShiftRng> {
     Ok(XorShiftRng::seed_from_u64(seed))
 }
 
+fn parse_behavior_script(path: &str) -> Result<Arc<BehaviorScript>> {
+    Ok(Arc::new(BehaviorScript::load(path)?))
+}
+
 #[derive(Clone)]
 pub enum AlertHandler {
     /// Just print the alert to STDOUT
@@ -211,11 +280,16 @@ impl Sim {
             parking: ParkingSimState::new(map, opts.infinite_parking, &mut timer),
             walking: WalkingSimState::new(),
             intersections: IntersectionSimState::new(map, &mut scheduler, &opts),
-            transit: TransitSimState::new(map),
-            trips: TripManager::new(),
+            transit: TransitSimState::new(map, opts.unlimited_transit_capacity),
+            trips: TripManager::new(
+                opts.route_alternatives,
+                opts.wheelchair_pct,
+                opts.behavior_script.clone(),
+            ),
             pandemic: opts.enable_pandemic_model.map(PandemicModel::new),
             scheduler,
             time: Time::START_OF_DAY,
+            bus_classes: BTreeMap::new(),
 
             map_name: map.get_name().clone(),
             edits_name: map.get_edits().edits_name.clone(),
@@ -224,8 +298,13 @@ impl Sim {
             highlighted_people: None,
             alerts: opts.alerts,
 
-            analytics: Analytics::new(!opts.skip_analytics),
+            analytics: Analytics::new(!opts.skip_analytics, opts.analytics_retention.clone()),
             recorder: None,
+            breakpoints: Breakpoints::default(),
+            subscriptions: Subscriptions::default(),
+            warm_start: None,
+            fast_forward: None,
+            event_log: None,
         }
     }
 
@@ -243,6 +322,9 @@ impl Sim {
             // This might be immediately true due to ScenarioModifiers
             if let Some(msg) = info.cancellation_reason {
                 self.trips.cancel_unstarted_trip(trip, msg);
+            } else if let Some(driver) = info.carpool_driver {
+                // Passengers start when their driver does
+                self.trips.add_carpool_passenger(driver, trip);
             } else {
                 self.scheduler
                     .push(info.departure, Command::StartTrip(trip, args));
@@ -285,6 +367,7 @@ impl Sim {
             },
             owner: None,
             vehicle_type: VehicleType::Car,
+            class: VehicleClass::Car,
             length: MIN_CAR_LENGTH,
             max_speed: None,
         };
@@ -316,8 +399,18 @@ impl Sim {
         orig_id: Option<OrigPersonID>,
         ped_speed: Speed,
         vehicle_specs: Vec<VehicleSpec>,
+        demographics: Option<Demographics>,
+        wheelchair: bool,
+        value_of_time: u32,
     ) -> &Person {
-        self.trips.new_person(orig_id, ped_speed, vehicle_specs)
+        self.trips.new_person(
+            orig_id,
+            ped_speed,
+            vehicle_specs,
+            demographics,
+            wheelchair,
+            value_of_time,
+        )
     }
     pub(crate) fn seed_parked_car(&mut self, vehicle: Vehicle, spot: ParkingSpot) {
         self.parking.reserve_spot(spot, vehicle.id);
@@ -328,7 +421,10 @@ impl Sim {
         });
     }
 
-    pub(crate) fn seed_bus_route(&mut self, route: &TransitRoute) {
+    pub(crate) fn seed_bus_route(&mut self, route: &TransitRoute, class: VehicleClass) {
+        if route.route_type == PathConstraints::Bus {
+            self.bus_classes.insert(route.id, class);
+        }
         for t in &route.spawn_times {
             self.scheduler.push(*t, Command::StartBus(route.id, *t));
         }
@@ -338,15 +434,29 @@ impl Sim {
         // Spawn one bus for the first leg.
         let path = self.transit.create_empty_route(route, map);
 
-        // For now, no desire for randomness. Caller can pass in list of specs if that ever
-        // changes.
-        let (vehicle_type, length) = match route.route_type {
-            PathConstraints::Bus => (VehicleType::Bus, BUS_LENGTH),
-            PathConstraints::Train => (VehicleType::Train, LIGHT_RAIL_LENGTH),
+        // Every bus on a route is the same class, picked when the route was seeded
+        let (vehicle_type, class, length) = match route.route_type {
+            PathConstraints::Bus => {
+                match self
+                    .bus_classes
+                    .get(&route.id)
+                    .cloned()
+                    .unwrap_or(VehicleClass::Bus)
+                {
+                    VehicleClass::ArticulatedBus => (
+                        VehicleType::Bus,
+                        VehicleClass::ArticulatedBus,
+                        ARTICULATED_BUS_LENGTH,
+                    ),
+                    _ => (VehicleType::Bus, VehicleClass::Bus, BUS_LENGTH),
+                }
+            }
+            PathConstraints::Train => (VehicleType::Train, VehicleClass::Train, LIGHT_RAIL_LENGTH),
             _ => unreachable!(),
         };
         let vehicle = VehicleSpec {
             vehicle_type,
+            class,
             length,
             max_speed: None,
         }
@@ -367,6 +477,8 @@ impl Sim {
                     maybe_parked_car: None,
                     trip_and_person: None,
                     maybe_route: Some(route.id),
+                    value_of_time: 0,
+                    occupancy: 1,
                 },
                 true,
             ),
@@ -380,6 +492,26 @@ impl Sim {
     pub fn get_run_name(&self) -> &String {
         &self.run_name
     }
+
+    /// The next scenario instantiated will start with cars parked wherever they were at the end
+    /// of a previous run, instead of near the start of their owner's first trip.
+    pub fn set_warm_start(&mut self, warm_start: WarmStart) {
+        self.warm_start = Some(warm_start);
+    }
+
+    /// Until `until`, car and bike trips that would finish most of their route well before then
+    /// skip detailed simulation. They're moved along their path with a simplified queueing model,
+    /// then appear near the end to finish in detail. This speeds up jumping ahead in time, at
+    /// some cost to accuracy.
+    pub fn fast_forward_until(&mut self, until: Time, accuracy: FastForwardAccuracy) {
+        self.fast_forward = Some(FastForward::new(until, accuracy));
+    }
+
+    /// Go back to simulating every new trip in detail. Vehicles already being fast-forwarded
+    /// still appear near the end of their route when they're due.
+    pub fn stop_fast_forward(&mut self) {
+        self.fast_forward = None;
+    }
 }
 
 // Running
@@ -477,6 +609,38 @@ impl Sim {
                         Some(create_car.vehicle),
                         &mut ctx,
                     );
+                } else if let Some((arrival, start)) = self
+                    .fast_forward
+                    .as_mut()
+                    .and_then(|ff| ff.maybe_skip(self.time, &create_car, map))
+                {
+                    // Skip detailed simulation for most of the path. The trip begins now, but the
+                    // vehicle only appears on the map near the end.
+                    let mut create_car = create_car;
+                    let id = create_car.vehicle.id;
+                    let (trip, person) = create_car.trip_and_person.unwrap();
+                    self.trips.agent_starting_trip_leg(AgentID::Car(id), trip);
+                    events.push(Event::TripPhaseStarting(
+                        trip,
+                        person,
+                        Some(create_car.router.get_path().get_req().clone()),
+                        if id.vehicle_type == VehicleType::Car {
+                            TripPhaseType::Driving
+                        } else {
+                            TripPhaseType::Biking
+                        },
+                    ));
+                    if let Some(parked_car) = create_car.maybe_parked_car.take() {
+                        if let ParkingSpot::Offstreet(b, _) = parked_car.spot {
+                            events.push(Event::PersonLeavesBuilding(person, b));
+                        }
+                        self.parking.remove_parked_car(parked_car);
+                    }
+                    self.analytics
+                        .record_demand(create_car.router.get_path(), map);
+                    create_car.router.skip_to_last_step(map, start);
+                    self.scheduler
+                        .push(arrival, Command::SpawnCar(create_car, true));
                 } else {
                     // create_car contains a Path, which is expensive to clone. We need different
                     // parts of create_car after attempting start_car_on_lane.
@@ -484,6 +648,7 @@ impl Sim {
                     // clone the path.
                     let id = create_car.vehicle.id;
                     let maybe_route = create_car.maybe_route;
+                    let class = create_car.vehicle.class;
                     let trip_and_person = create_car.trip_and_person;
                     let maybe_parked_car = create_car.maybe_parked_car.clone();
                     let req = create_car.router.get_path().get_req().clone();
@@ -547,7 +712,7 @@ impl Sim {
                             self.parking.remove_parked_car(parked_car);
                         }
                         if let Some(route) = maybe_route {
-                            self.transit.bus_created(id, route);
+                            self.transit.bus_created(id, route, class);
                         }
                         self.analytics
                             .record_demand(self.driving.get_path(id).unwrap(), map);
@@ -637,18 +802,22 @@ impl Sim {
         }
 
         // Record events at precisely the time they occur.
-        self.dispatch_events(events, map);
+        if self.dispatch_events(events, map) {
+            halt = true;
+        }
 
         halt
     }
 
-    fn dispatch_events(&mut self, mut events: Vec<Event>, map: &Map) {
+    // If true, a breakpoint was triggered.
+    fn dispatch_events(&mut self, mut events: Vec<Event>, map: &Map) -> bool {
         events.extend(self.trips.collect_events());
         events.extend(self.transit.collect_events());
         events.extend(self.driving.collect_events());
         events.extend(self.walking.collect_events());
         events.extend(self.intersections.collect_events());
         events.extend(self.parking.collect_events());
+        let mut halt = false;
         for ev in events {
             if let Some(ref mut m) = self.pandemic {
                 m.handle_event(self.time, &ev, &mut self.scheduler);
@@ -656,9 +825,21 @@ impl Sim {
             if let Some(ref mut r) = self.recorder {
                 r.handle_event(self.time, &ev, map, &self.driving);
             }
+            if self.breakpoints.handle_event(self.time, &ev, map) {
+                halt = true;
+            }
+            self.subscriptions.handle_event(self.time, &ev);
+            if let Some(ref mut log) = self.event_log {
+                // PathAmended just copies a path, and would bloat the log
+                if !matches!(ev, Event::PathAmended(_)) {
+                    // Debug-formatting floats prints every bit, so even tiny differences show up
+                    log.push(format!("{:?} {:?}", self.time.inner_seconds(), ev));
+                }
+            }
 
             self.analytics.event(ev, self.time, map);
         }
+        halt
     }
 
     pub fn timed_step(
@@ -717,6 +898,23 @@ impl Sim {
         );
     }
 
+    /// Advance to the next time anything is scheduled to happen, and process everything
+    /// happening then. This is the smallest possible step. Returns false if nothing else is
+    /// scheduled.
+    pub fn step_to_next_event(
+        &mut self,
+        map: &Map,
+        maybe_cb: &mut Option<Box<dyn SimCallback>>,
+    ) -> bool {
+        match self.scheduler.peek_next_time() {
+            Some(time) => {
+                self.minimal_step(map, time - self.time, maybe_cb);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn time_limited_step(
         &mut self,
         map: &Map,
@@ -887,6 +1085,7 @@ impl Sim {
 
         self.driving.handle_live_edits(map);
         self.intersections.handle_live_edits(map);
+        self.transit.handle_live_edits(map);
 
         (num_trips_cancelled, num_parked_cars)
     }
@@ -1025,6 +1224,67 @@ impl Sim {
     }
 }
 
+// Recording events
+impl Sim {
+    /// Keep a description of every event from now on. Two runs of the same scenario on the same
+    /// map must produce exactly the same events, on any platform; the regression tests rely on
+    /// this.
+    pub fn record_events(&mut self) {
+        self.event_log = Some(Vec::new());
+    }
+
+    /// Returns all events since `record_events` was called, one per line, and stops recording.
+    pub fn take_recorded_events(&mut self) -> Vec<String> {
+        self.event_log.take().unwrap_or_default()
+    }
+}
+
+// Event subscriptions
+impl Sim {
+    /// Start buffering events of these types. Call `drain_events` regularly to get them.
+    pub fn subscribe(&mut self, types: BTreeSet<SimEventType>) -> SubscriptionID {
+        self.subscriptions.subscribe(types)
+    }
+
+    /// Returns false if the subscription doesn't exist
+    pub fn unsubscribe(&mut self, id: SubscriptionID) -> bool {
+        self.subscriptions.unsubscribe(id)
+    }
+
+    /// Returns every event since the last call, in order, or None if the subscription doesn't
+    /// exist.
+    pub fn drain_events(&mut self, id: SubscriptionID) -> Option<Vec<(Time, SimEvent)>> {
+        self.subscriptions.drain(id)
+    }
+}
+
+// Breakpoints
+impl Sim {
+    pub fn add_breakpoint(&mut self, bp: Breakpoint) {
+        self.breakpoints.add(bp);
+    }
+
+    /// Like `add_breakpoint`, but the breakpoint is removed the first time it's hit. This is
+    /// useful to run until something happens.
+    pub fn add_one_time_breakpoint(&mut self, bp: Breakpoint) {
+        self.breakpoints.add_once(bp);
+    }
+
+    pub fn remove_breakpoint(&mut self, bp: Breakpoint) {
+        self.breakpoints.remove(bp);
+    }
+
+    pub fn get_breakpoints(&self) -> &BTreeSet<Breakpoint> {
+        self.breakpoints.get_all()
+    }
+
+    /// Returns every breakpoint triggered since the last call. When a breakpoint is triggered,
+    /// stepping the simulation halts early.
+    pub fn clear_breakpoint_hits(&mut self) -> Vec<BreakpointHit> {
+        self.breakpoints.take_hits()
+    }
+}
+
 // Managing highlighted people
 impl Sim {
     pub fn set_highlighted_people(&mut self, people: BTreeSet<PersonID>) {
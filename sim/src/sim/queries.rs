@@ -5,18 +5,19 @@ use serde::Serialize;
 use std::collections::{BTreeMap, BTreeSet};
 
 use abstutil::Counter;
-use geom::{Distance, Duration, PolyLine, Pt2D, Time};
+use geom::{Distance, Duration, PolyLine, Pt2D, Speed, Time};
 use map_model::{
     BuildingID, IntersectionID, Lane, LaneID, Map, Path, Position, RoadID, TransitRouteID,
     TransitStopID, Traversable, TurnID,
 };
-use synthpop::{OrigPersonID, Scenario, TripMode};
+use synthpop::{OrigPersonID, ParkAndRide, Scenario, TripMode};
 
 use crate::analytics::SlidingWindow;
 use crate::{
     AgentID, AgentType, Analytics, CarID, CommutersVehiclesCounts, DrawCarInput, DrawPedCrowdInput,
-    DrawPedestrianInput, PandemicModel, ParkedCar, ParkingSim, PedestrianID, Person, PersonID,
-    PersonState, Sim, TripEndpoint, TripID, TripInfo, TripResult, UnzoomedAgent, VehicleType,
+    DrawPedestrianInput, Gridlock, PandemicModel, ParkedCar, ParkingSim, PedestrianID, Person,
+    PersonID, PersonState, Sim, TripEndpoint, TripID, TripInfo, TripResult, UnzoomedAgent,
+    VehicleType,
 };
 
 // TODO Many of these just delegate to an inner piece. This is unorganized and hard to maintain.
@@ -96,10 +97,24 @@ impl Sim {
         }
     }
 
+    /// How fast is an agent moving right now? None if the agent isn't active.
+    pub fn current_speed(&self, id: AgentID) -> Option<Speed> {
+        match id {
+            AgentID::Car(id) => self.driving.current_speed(id),
+            AgentID::Pedestrian(id) => self.walking.current_speed(id),
+            AgentID::BusPassenger(_, id) => self.driving.current_speed(id),
+        }
+    }
+
     pub fn num_transit_passengers(&self, car: CarID) -> usize {
         self.transit.get_passengers(car).len()
     }
 
+    /// How many passengers fit in a transit vehicle. None means unlimited.
+    pub fn transit_capacity(&self, car: CarID) -> Option<usize> {
+        self.transit.bus_capacity(car)
+    }
+
     pub fn bus_route_id(&self, maybe_bus: CarID) -> Option<TransitRouteID> {
         if maybe_bus.vehicle_type == VehicleType::Bus
             || maybe_bus.vehicle_type == VehicleType::Train
@@ -291,6 +306,18 @@ impl Sim {
             .get_blocked_by_graph(self.time, map, &self.intersections)
     }
 
+    /// Find groups of intersections where vehicles have been stuck waiting on each other in a
+    /// cycle for at least `threshold`.
+    pub fn find_gridlock(&self, map: &Map, threshold: Duration) -> Vec<Gridlock> {
+        crate::gridlock::find_gridlock(&self.get_blocked_by_graph(map), threshold, |agent| {
+            match agent {
+                AgentID::Car(car) => self.driving.get_next_intersection(car, map),
+                // Pedestrians and transit riders can't be part of a cycle of queues
+                AgentID::Pedestrian(_) | AgentID::BusPassenger(_, _) => None,
+            }
+        })
+    }
+
     /// (bus, stop index it's coming from, percent to next stop, location)
     pub fn status_of_buses(
         &self,
@@ -313,6 +340,16 @@ impl Sim {
         &self.analytics
     }
 
+    pub fn get_park_and_ride(&self) -> &ParkAndRide {
+        self.trips.get_park_and_ride()
+    }
+
+    /// How many bikes are using a building's bike parking right now. Bikes kept inside homes and
+    /// bikes locked up informally aren't counted.
+    pub fn get_bike_parking_occupied(&self, b: BuildingID) -> usize {
+        self.trips.get_bike_parking().get_occupied(b)
+    }
+
     /// For intersections with an agent waiting beyond some threshold, return when they started
     /// waiting. Sorted by earliest waiting (likely the root cause of gridlock).
     pub fn delayed_intersections(&self, threshold: Duration) -> Vec<(IntersectionID, Time)> {
@@ -412,6 +449,11 @@ impl Sim {
         self.driving.debug_queue_lengths(l)
     }
 
+    /// For every lane with vehicles stopped at the end of it, how far back the queue reaches
+    pub fn get_queue_lengths(&self) -> BTreeMap<LaneID, Distance> {
+        self.driving.get_queue_lengths(self.time)
+    }
+
     /// Returns the best-case time for a trip in a world with no traffic or intersection delays.
     /// Might fail in some cases where the real trip succeeds, but the single-mode path can't be
     /// found. Assumes the TripID exists.
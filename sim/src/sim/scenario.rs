@@ -1,18 +1,22 @@
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::Arc;
 
 use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
 use abstutil::{prettyprint_usize, Counter, Timer};
-use geom::{Distance, Speed};
-use map_model::{BuildingID, Map, OffstreetParking, RoadID};
+use geom::{Distance, Duration, Speed};
+use map_model::{BuildingID, LaneID, Map, OffstreetParking, RoadID};
 use synthpop::make::fork_rng;
-use synthpop::{PersonSpec, Scenario, TripEndpoint, TripMode};
+use synthpop::{
+    IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, VehicleClass, VehicleMix,
+};
 
 use crate::{
-    ParkingSpot, Sim, StartTripArgs, TripInfo, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH,
-    MAX_CAR_LENGTH, MIN_CAR_LENGTH,
+    BehaviorScript, CarID, ParkingSpot, PersonID, Sim, StartTripArgs, TripID, TripInfo, Vehicle,
+    VehicleSpec, VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
 };
 
 impl Sim {
@@ -36,42 +40,221 @@ impl Sim {
         retry_if_no_room: bool,
         timer: &mut Timer,
     ) {
+        timer.start(format!("Instantiating {}", scenario.scenario_name));
+        let mut instantiation =
+            ScenarioInstantiation::new(self, scenario, map, rng, retry_if_no_room);
+        instantiation.add_people(self, scenario, map, rng, scenario.people.len(), timer);
+        instantiation.finish(self, scenario, map, rng, timer);
+        timer.stop(format!("Instantiating {}", scenario.scenario_name));
+    }
+}
+
+/// Instantiates a scenario a batch of people at a time, so that a UI can keep drawing and
+/// responding to input while a large scenario spawns. Doing all of the batches, then finishing,
+/// produces exactly the same simulation as `Sim::instantiate`. Nothing is scheduled until the end,
+/// so the simulation shouldn't run in between.
+pub struct ScenarioInstantiation {
+    retry_if_no_room: bool,
+    /// Where a previous run left each person's cars
+    warm_spots: BTreeMap<(PersonID, usize), ParkingSpot>,
+    infinite_parking: bool,
+    behavior_script: Option<Arc<BehaviorScript>>,
+    first_trip_id: usize,
+    /// For each person in the scenario, the ID of their first trip
+    person_first_trip: Vec<usize>,
+    next_person: usize,
+
+    parked_cars: Vec<(Vehicle, BuildingID)>,
+    warm_cars: Vec<(Vehicle, ParkingSpot)>,
+    schedule_trips: Vec<(PersonID, TripInfo, StartTripArgs)>,
+}
+
+impl ScenarioInstantiation {
+    /// Checks the scenario and seeds buses. Panics if the scenario is invalid.
+    pub fn new(
+        sim: &mut Sim,
+        scenario: &Scenario,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        retry_if_no_room: bool,
+    ) -> ScenarioInstantiation {
         // Any case where map edits could change the calls to the RNG, we have to fork.
-        self.set_run_name(scenario.scenario_name.clone());
+        sim.set_run_name(scenario.scenario_name.clone());
 
-        timer.start(format!("Instantiating {}", scenario.scenario_name));
+        let mix = &scenario.vehicle_mix;
+        if let Err(err) = mix.check() {
+            panic!("{}", err);
+        }
+        if let Err(err) = scenario.value_of_time.check() {
+            panic!("{}", err);
+        }
+        if let Err(err) = scenario.pick_up_drop_off.check() {
+            panic!("{}", err);
+        }
+        // Extra trips added on top of a scenario shouldn't undo its facilities
+        if !scenario.park_and_ride.is_empty() {
+            sim.trips.set_park_and_ride(scenario.park_and_ride.clone());
+        }
 
         if let Some(ref routes) = scenario.only_seed_buses {
             for route in map.all_transit_routes() {
                 if routes.contains(&route.long_name) {
-                    self.seed_bus_route(route);
+                    sim.seed_bus_route(route, mix.sample_bus(rng));
                 }
             }
         } else {
             // All of them
             for route in map.all_transit_routes() {
-                self.seed_bus_route(route);
+                sim.seed_bus_route(route, mix.sample_bus(rng));
             }
         }
 
-        timer.start_iter("trips for People", scenario.people.len());
-        let mut parked_cars: Vec<(Vehicle, BuildingID)> = Vec::new();
-        let mut schedule_trips = Vec::new();
+        // People are numbered in the order of the scenario, so warm starts only work when
+        // nobody's been added yet.
+        let warm_spots = match sim.warm_start.take() {
+            Some(warm_start) if sim.trips.get_all_people().is_empty() => {
+                warm_start.spots_for(&scenario.scenario_name, scenario.people.len(), sim, map)
+            }
+            _ => BTreeMap::new(),
+        };
+        // Trips get IDs in the order they're scheduled
+        let first_trip_id = sim.trips.next_trip_id().0;
+        let mut person_first_trip = Vec::new();
+        let mut num_trips = first_trip_id;
         for p in &scenario.people {
+            person_first_trip.push(num_trips);
+            num_trips += p.trips.len();
+        }
+
+        ScenarioInstantiation {
+            retry_if_no_room,
+            warm_spots,
+            infinite_parking: sim.infinite_parking(),
+            behavior_script: sim.trips.behavior_script(),
+            first_trip_id,
+            person_first_trip,
+            next_person: 0,
+
+            parked_cars: Vec::new(),
+            warm_cars: Vec::new(),
+            schedule_trips: Vec::new(),
+        }
+    }
+
+    /// (People added so far, total people)
+    pub fn progress(&self, scenario: &Scenario) -> (usize, usize) {
+        (self.next_person, scenario.people.len())
+    }
+
+    pub fn is_done(&self, scenario: &Scenario) -> bool {
+        self.next_person == scenario.people.len()
+    }
+
+    /// Add up to `max_people` more people from the scenario. They won't start any trips until
+    /// `finish`.
+    pub fn add_people(
+        &mut self,
+        sim: &mut Sim,
+        scenario: &Scenario,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        max_people: usize,
+        timer: &mut Timer,
+    ) {
+        let end = scenario.people.len().min(self.next_person + max_people);
+        let mix = &scenario.vehicle_mix;
+        let first_trip_id = self.first_trip_id;
+
+        timer.start_iter("trips for People", end - self.next_person);
+        for p in &scenario.people[self.next_person..end] {
             timer.next();
 
             if let Err(err) = p.check_schedule() {
                 panic!("{}", err);
             }
+            let p = match self.behavior_script {
+                Some(ref script) => {
+                    let mut p = p.clone();
+                    for (idx, trip) in p.trips.iter_mut().enumerate() {
+                        trip.mode = script.choose_mode(
+                            first_trip_id + self.schedule_trips.len() + idx,
+                            trip.purpose,
+                            trip.mode,
+                            trip.origin.pt(map).dist_to(trip.destination.pt(map)),
+                        );
+                    }
+                    Cow::Owned(p)
+                }
+                None => Cow::Borrowed(p),
+            };
+            let p = p.as_ref();
 
+            let carpool_drivers: Vec<Option<TripID>> = p
+                .trips
+                .iter()
+                .map(|trip| {
+                    carpool_driver(scenario, trip)
+                        .map(|(driver, idx)| TripID(self.person_first_trip[driver] + idx))
+                })
+                .collect();
+            let drop_offs: Vec<Option<Duration>> = p
+                .trips
+                .iter()
+                .zip(&carpool_drivers)
+                .enumerate()
+                .map(|(idx, (trip, carpool_driver))| {
+                    if trip.mode == TripMode::Drive
+                        && carpool_driver.is_none()
+                        && scenario.pick_up_drop_off.applies(
+                            first_trip_id + self.schedule_trips.len() + idx,
+                            trip.purpose,
+                        )
+                    {
+                        Some(scenario.pick_up_drop_off.dwell_time)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
             let (vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
-                get_vehicles(p, rng);
-            let person = self.new_person(p.orig_id, rand_ped_speed(rng), vehicle_specs);
+                get_vehicles(p, &carpool_drivers, &drop_offs, mix, rng);
+            let wheelchair = sim.trips.pick_wheelchair_user(p.demographics.as_ref(), rng);
+            // Use a separate RNG, so adding this didn't change every other simulation
+            let value_of_time = scenario.value_of_time.sample(
+                p.demographics.as_ref(),
+                &mut XorShiftRng::seed_from_u64(sim.trips.get_all_people().len() as u64),
+            );
+            let person = sim.new_person(
+                p.orig_id,
+                rand_ped_speed(rng),
+                vehicle_specs,
+                p.demographics,
+                wheelchair,
+                value_of_time,
+            );
             for (idx, b) in cars_initially_parked_at {
-                parked_cars.push((person.vehicles[idx].clone(), b));
+                let vehicle = person.vehicles[idx].clone();
+                match self.warm_spots.remove(&(person.id, idx)) {
+                    // With infinite parking, spots aren't stable, so just use the same building
+                    Some(ParkingSpot::Offstreet(b, _)) if self.infinite_parking => {
+                        self.parked_cars.push((vehicle, b));
+                    }
+                    Some(spot) => {
+                        self.warm_cars.push((vehicle, spot));
+                    }
+                    None => {
+                        self.parked_cars.push((vehicle, b));
+                    }
+                }
             }
-            for (trip, maybe_idx) in p.trips.iter().zip(vehicle_foreach_trip) {
-                schedule_trips.push((
+            for (((trip, maybe_idx), carpool_driver), drop_off) in p
+                .trips
+                .iter()
+                .zip(vehicle_foreach_trip)
+                .zip(carpool_drivers)
+                .zip(drop_offs)
+            {
+                self.schedule_trips.push((
                     person.id,
                     TripInfo {
                         departure: trip.depart,
@@ -85,26 +268,94 @@ impl Sim {
                         } else {
                             None
                         },
+                        occupancy: trip.occupancy,
+                        carpool_driver,
+                        drop_off,
                     },
                     StartTripArgs {
-                        retry_if_no_room,
+                        retry_if_no_room: self.retry_if_no_room,
                         use_vehicle: maybe_idx.map(|idx| person.vehicles[idx].id),
                     },
                 ));
             }
         }
+        self.next_person = end;
+    }
 
+    /// Once everybody's been added, park their cars and schedule all of the trips.
+    pub fn finish(
+        mut self,
+        sim: &mut Sim,
+        scenario: &Scenario,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) {
+        assert!(self.is_done(scenario));
+        let first_trip_id = self.first_trip_id;
+        let schedule_trips = &mut self.schedule_trips;
+
+        // Nobody can ride along with a cancelled trip
+        for idx in 0..schedule_trips.len() {
+            if let Some(driver) = schedule_trips[idx].1.carpool_driver {
+                let driver = driver.0 - first_trip_id;
+                if schedule_trips[driver].1.cancellation_reason.is_some()
+                    && schedule_trips[idx].1.cancellation_reason.is_none()
+                {
+                    schedule_trips[idx].1.cancellation_reason =
+                        Some("their carpool driver's trip was cancelled".to_string());
+                } else if schedule_trips[idx].1.cancellation_reason.is_some() {
+                    schedule_trips[driver].1.occupancy -= 1;
+                }
+            }
+        }
+
+        if !self.warm_cars.is_empty() {
+            info!(
+                "Restored {} parked cars from a previous run",
+                prettyprint_usize(self.warm_cars.len())
+            );
+        }
+        // Seed these first, so everything else fills in around them
+        for (vehicle, spot) in self.warm_cars {
+            sim.seed_parked_car(vehicle, spot);
+        }
         // parked_cars is stable over map edits, so don't fork.
-        parked_cars.shuffle(rng);
-        seed_parked_cars(parked_cars, self, map, rng, timer);
+        self.parked_cars.shuffle(rng);
+        seed_parked_cars(self.parked_cars, sim, map, rng, timer);
+        seed_observed_parking(sim, map, rng, timer);
 
-        self.spawn_trips(schedule_trips, map, timer);
-        timer.stop(format!("Instantiating {}", scenario.scenario_name));
+        sim.spawn_trips(self.schedule_trips, map, timer);
+    }
+}
+
+/// A passenger can only ride along with somebody else driving the same trip. Returns the index of
+/// the driver in the scenario, and the index of their trip.
+fn carpool_driver(scenario: &Scenario, trip: &IndividTrip) -> Option<(usize, usize)> {
+    let (driver, idx) = trip.passenger_of?;
+    let driver_trip = scenario.people.get(driver)?.trips.get(idx)?;
+    if trip.mode == TripMode::Drive
+        && driver_trip.mode == TripMode::Drive
+        && driver_trip.passenger_of.is_none()
+        && driver_trip.origin == trip.origin
+        && driver_trip.destination == trip.destination
+    {
+        Some((driver, idx))
+    } else {
+        warn!(
+            "Ignoring a passenger riding along with trip {} of person {}, who isn't driving the \
+             same trip",
+            idx, driver
+        );
+        None
     }
 }
 
 fn get_vehicles(
     person: &PersonSpec,
+    carpool_drivers: &[Option<TripID>],
+    drop_offs: &[Option<Duration>],
+    mix: &VehicleMix,
     rng: &mut XorShiftRng,
 ) -> (
     Vec<VehicleSpec>,
@@ -120,13 +371,22 @@ fn get_vehicles(
     let mut car_locations: Vec<(usize, Option<BuildingID>)> = Vec::new();
 
     // TODO If the trip is cancelled, this should be affected...
-    for trip in &person.trips {
+    for ((trip, carpool_driver), drop_off) in
+        person.trips.iter().zip(carpool_drivers).zip(drop_offs)
+    {
         let use_for_trip = match trip.mode {
             TripMode::Walk | TripMode::Transit => None,
+            // Passengers don't need their own car
+            TripMode::Drive if carpool_driver.is_some() => None,
+            // A taxi or somebody else's car appears just for this trip, and leaves afterwards
+            TripMode::Drive if drop_off.is_some() => {
+                vehicle_specs.push(rand_vehicle(mix.sample_driving(rng), rng));
+                Some(vehicle_specs.len() - 1)
+            }
             TripMode::Bike => {
                 if bike_idx.is_none() {
                     bike_idx = Some(vehicle_specs.len());
-                    vehicle_specs.push(rand_bike(rng));
+                    vehicle_specs.push(rand_vehicle(mix.sample_biking(rng), rng));
                 }
                 bike_idx
             }
@@ -146,7 +406,7 @@ fn get_vehicles(
                 } else {
                     // Need a new car, starting in the right spot
                     let idx = vehicle_specs.len();
-                    vehicle_specs.push(rand_car(rng));
+                    vehicle_specs.push(rand_vehicle(mix.sample_driving(rng), rng));
                     if let Some(b) = need_parked_at {
                         cars_initially_parked_at.push((idx, b));
                     }
@@ -188,24 +448,57 @@ fn get_vehicles(
     )
 }
 
-fn rand_car(rng: &mut XorShiftRng) -> VehicleSpec {
-    let length = rand_dist(rng, MIN_CAR_LENGTH, MAX_CAR_LENGTH);
+/// Pick the dimensions and performance for a vehicle used by people. Buses and trains come from
+/// transit routes instead.
+fn rand_vehicle(class: VehicleClass, rng: &mut XorShiftRng) -> VehicleSpec {
+    let (vehicle_type, length, max_speed) = match class {
+        VehicleClass::SmallCar => (
+            VehicleType::Car,
+            rand_dist(rng, Distance::meters(3.5), MIN_CAR_LENGTH),
+            None,
+        ),
+        VehicleClass::Car => (
+            VehicleType::Car,
+            rand_dist(rng, MIN_CAR_LENGTH, MAX_CAR_LENGTH),
+            None,
+        ),
+        VehicleClass::Van => (
+            VehicleType::Car,
+            rand_dist(rng, Distance::meters(5.0), Distance::meters(7.0)),
+            Some(Speed::miles_per_hour(70.0)),
+        ),
+        // Between a rigid truck and an articulated lorry, with a speed limiter
+        VehicleClass::Hgv => (
+            VehicleType::Car,
+            rand_dist(rng, Distance::meters(10.0), Distance::meters(16.5)),
+            Some(Speed::miles_per_hour(56.0)),
+        ),
+        VehicleClass::Bike => (
+            VehicleType::Bike,
+            BIKE_LENGTH,
+            Some(rand_speed(
+                rng,
+                Speed::miles_per_hour(8.0),
+                map_model::MAX_BIKE_SPEED,
+            )),
+        ),
+        VehicleClass::CargoBike => (
+            VehicleType::Bike,
+            rand_dist(rng, Distance::meters(2.4), Distance::meters(2.8)),
+            Some(rand_speed(
+                rng,
+                Speed::miles_per_hour(6.0),
+                Speed::miles_per_hour(12.0),
+            )),
+        ),
+        VehicleClass::Bus | VehicleClass::ArticulatedBus | VehicleClass::Train => {
+            unreachable!()
+        }
+    };
     VehicleSpec {
-        vehicle_type: VehicleType::Car,
+        vehicle_type,
+        class,
         length,
-        max_speed: None,
-    }
-}
-
-fn rand_bike(rng: &mut XorShiftRng) -> VehicleSpec {
-    let max_speed = Some(rand_speed(
-        rng,
-        Speed::miles_per_hour(8.0),
-        map_model::MAX_BIKE_SPEED,
-    ));
-    VehicleSpec {
-        vehicle_type: VehicleType::Bike,
-        length: BIKE_LENGTH,
         max_speed,
     }
 }
@@ -307,6 +600,73 @@ fn seed_parked_cars(
     }
 }
 
+/// Where OSM or a parking survey says how full on-street parking usually is, fill up the spots
+/// left after seeding everybody's cars. These extra cars don't belong to anybody in the scenario,
+/// so they stay parked all day.
+fn seed_observed_parking(sim: &mut Sim, map: &Map, base_rng: &mut XorShiftRng, timer: &mut Timer) {
+    if sim.infinite_parking() {
+        return;
+    }
+
+    // (number of filled spots, free spots)
+    let mut per_lane: BTreeMap<LaneID, (usize, Vec<ParkingSpot>)> = BTreeMap::new();
+    let (filled, available) = sim.get_all_parking_spots();
+    for spot in filled {
+        if let ParkingSpot::Onstreet(l, _) = spot {
+            per_lane.entry(l).or_insert_with(|| (0, Vec::new())).0 += 1;
+        }
+    }
+    for spot in available {
+        if let ParkingSpot::Onstreet(l, _) = spot {
+            per_lane
+                .entry(l)
+                .or_insert_with(|| (0, Vec::new()))
+                .1
+                .push(spot);
+        }
+    }
+
+    let mut seeded = 0;
+    timer.start_iter("seed observed parking", map.all_roads().len());
+    for r in map.all_roads() {
+        timer.next();
+        // Changing parking on one road shouldn't affect far-off roads. Fork carefully.
+        let mut rng = fork_rng(base_rng);
+        for lane in &r.lanes {
+            let occupancy = match r
+                .observed_parking(lane.get_nearest_side_of_road(map).side)
+                .occupancy
+            {
+                Some(x) => x,
+                None => continue,
+            };
+            let (num_filled, free) = match per_lane.get_mut(&lane.id) {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let total = *num_filled + free.len();
+            let goal = ((total as f64) * occupancy).round() as usize;
+            free.shuffle(&mut rng);
+            for _ in *num_filled..goal {
+                let spot = free.pop().unwrap();
+                let id = CarID {
+                    id: sim.trips.new_car_id(),
+                    vehicle_type: VehicleType::Car,
+                };
+                let vehicle = rand_vehicle(VehicleClass::Car, &mut rng).make(id, None);
+                sim.seed_parked_car(vehicle, spot);
+                seeded += 1;
+            }
+        }
+    }
+    if seeded > 0 {
+        info!(
+            "Seeded {} extra parked cars to match observed parking occupancy",
+            prettyprint_usize(seeded)
+        );
+    }
+}
+
 // Pick a parking spot for this building. If the building's road has a free spot, use it. If not,
 // start BFSing out from the road in a deterministic way until finding a nearby road with an open
 // spot.
@@ -0,0 +1,110 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use geom::Time;
+use map_model::Map;
+
+use crate::{ParkingSim, ParkingSpot, PersonID, Sim};
+
+/// Where everybody's vehicles were parked at the end of a simulation. Running the same scenario
+/// again from this, instead of starting each car wherever its owner's first trip begins, lets
+/// multi-day analyses see where cars accumulate over time.
+///
+/// Only vehicles belonging to people are recorded. Cars still moving when the snapshot was taken
+/// and cars seeded to match observed parking are left out; they're seeded normally next time.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WarmStart {
+    pub map_name: MapName,
+    pub edits_name: String,
+    /// Recorded people only make sense in the scenario they came from
+    pub scenario_name: String,
+    /// When the snapshot was taken
+    pub time: Time,
+    pub num_people: usize,
+    /// (owner, index into the owner's vehicles, spot)
+    pub parked: Vec<(PersonID, usize, ParkingSpot)>,
+}
+
+impl WarmStart {
+    pub fn new(sim: &Sim) -> WarmStart {
+        let mut parked = Vec::new();
+        for person in sim.trips.get_all_people() {
+            for (idx, vehicle) in person.vehicles.iter().enumerate() {
+                if let Some(p) = sim.parking.lookup_parked_car(vehicle.id) {
+                    parked.push((person.id, idx, p.spot));
+                }
+            }
+        }
+        WarmStart {
+            map_name: sim.map_name.clone(),
+            edits_name: sim.edits_name.clone(),
+            scenario_name: sim.run_name.clone(),
+            time: sim.time,
+            num_people: sim.trips.get_all_people().len(),
+            parked,
+        }
+    }
+
+    pub fn save(&self) -> String {
+        let path = abstio::path_warm_start(&self.map_name, &self.scenario_name);
+        abstio::write_binary(path.clone(), self);
+        path
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} parked vehicles at {} from a run of {}",
+            abstutil::prettyprint_usize(self.parked.len()),
+            self.time.ampm_tostring(),
+            self.scenario_name
+        )
+    }
+
+    /// Checks this came from the same scenario, and returns the recorded spots that're still
+    /// valid. Spots may have disappeared because of map edits since the snapshot.
+    pub(crate) fn spots_for(
+        self,
+        scenario_name: &str,
+        num_people: usize,
+        sim: &Sim,
+        map: &Map,
+    ) -> BTreeMap<(PersonID, usize), ParkingSpot> {
+        if self.map_name != *map.get_name()
+            || self.scenario_name != scenario_name
+            || self.num_people != num_people
+        {
+            warn!(
+                "Ignoring warm start from {} on {}; it doesn't match scenario {}",
+                self.scenario_name,
+                self.map_name.describe(),
+                scenario_name
+            );
+            return BTreeMap::new();
+        }
+        if self.edits_name != map.get_edits().edits_name {
+            info!(
+                "Warm start was recorded with edits {}, but now using {}. Moved parking spots will \
+                 be skipped.",
+                self.edits_name,
+                map.get_edits().edits_name
+            );
+        }
+
+        let valid_spots: Option<HashSet<ParkingSpot>> = if sim.infinite_parking() {
+            None
+        } else {
+            Some(sim.parking.get_all_parking_spots().1.into_iter().collect())
+        };
+        let num_bldgs = map.all_buildings().len();
+        self.parked
+            .into_iter()
+            .filter(|(_, _, spot)| match valid_spots {
+                Some(ref spots) => spots.contains(spot),
+                None => matches!(spot, ParkingSpot::Offstreet(b, _) if b.0 < num_bldgs),
+            })
+            .map(|(person, idx, spot)| ((person, idx), spot))
+            .collect()
+    }
+}
@@ -0,0 +1,236 @@
+//! Consumers that want to react to what happens in a simulation -- UI layers, metrics, or external
+//! scripts using the headless API -- can subscribe to a few kinds of typed events, instead of
+//! poking into Analytics. Subscribers poll for everything that's happened since they last asked.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+use map_model::{IntersectionID, LaneID, RoadID, TransitRouteID, TransitStopID, Traversable};
+use synthpop::TripMode;
+
+use crate::{AgentID, CarID, Event, PersonID, TripID};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SubscriptionID(pub usize);
+
+/// What kinds of events a subscriber wants to hear about
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SimEventType {
+    TripStarted,
+    TripEnded,
+    VehicleEnteredRoad,
+    BusArrivedAtStop,
+    SignalChangedStage,
+}
+
+impl SimEventType {
+    pub fn all() -> Vec<SimEventType> {
+        vec![
+            SimEventType::TripStarted,
+            SimEventType::TripEnded,
+            SimEventType::VehicleEnteredRoad,
+            SimEventType::BusArrivedAtStop,
+            SimEventType::SignalChangedStage,
+        ]
+    }
+
+    /// Parses names like "trip-started" or "TripStarted"
+    pub fn parse(x: &str) -> Result<SimEventType> {
+        let normalized = x.replace(['-', '_'], "").to_lowercase();
+        for t in SimEventType::all() {
+            if format!("{:?}", t).to_lowercase() == normalized {
+                return Ok(t);
+            }
+        }
+        bail!("unknown event type {}", x)
+    }
+}
+
+/// Something that happened in the simulation. Unlike the internal events used to build Analytics,
+/// these are a stable public API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SimEvent {
+    TripStarted {
+        trip: TripID,
+        person: PersonID,
+        mode: TripMode,
+    },
+    /// `total_time` is None if the trip was cancelled
+    TripEnded {
+        trip: TripID,
+        mode: TripMode,
+        total_time: Option<Duration>,
+    },
+    /// A car, bike, or transit vehicle started along a lane. Lane changes partway along a road
+    /// don't count.
+    VehicleEnteredRoad {
+        vehicle: CarID,
+        trip: Option<TripID>,
+        road: RoadID,
+        lane: LaneID,
+    },
+    BusArrivedAtStop {
+        bus: CarID,
+        route: TransitRouteID,
+        stop: TransitStopID,
+    },
+    SignalChangedStage {
+        intersection: IntersectionID,
+        stage: usize,
+    },
+}
+
+impl SimEvent {
+    pub fn event_type(&self) -> SimEventType {
+        match self {
+            SimEvent::TripStarted { .. } => SimEventType::TripStarted,
+            SimEvent::TripEnded { .. } => SimEventType::TripEnded,
+            SimEvent::VehicleEnteredRoad { .. } => SimEventType::VehicleEnteredRoad,
+            SimEvent::BusArrivedAtStop { .. } => SimEventType::BusArrivedAtStop,
+            SimEvent::SignalChangedStage { .. } => SimEventType::SignalChangedStage,
+        }
+    }
+
+    fn from_event(ev: &Event) -> Option<SimEvent> {
+        match ev {
+            Event::TripStarted { trip, person, mode } => Some(SimEvent::TripStarted {
+                trip: *trip,
+                person: *person,
+                mode: *mode,
+            }),
+            Event::TripFinished {
+                trip,
+                mode,
+                total_time,
+                ..
+            } => Some(SimEvent::TripEnded {
+                trip: *trip,
+                mode: *mode,
+                total_time: Some(*total_time),
+            }),
+            Event::TripCancelled(trip, mode, _) => Some(SimEvent::TripEnded {
+                trip: *trip,
+                mode: *mode,
+                total_time: None,
+            }),
+            Event::AgentEntersTraversable(AgentID::Car(car), trip, Traversable::Lane(l), _) => {
+                Some(SimEvent::VehicleEnteredRoad {
+                    vehicle: *car,
+                    trip: *trip,
+                    road: l.road,
+                    lane: *l,
+                })
+            }
+            Event::BusArrivedAtStop(bus, route, stop) => Some(SimEvent::BusArrivedAtStop {
+                bus: *bus,
+                route: *route,
+                stop: *stop,
+            }),
+            Event::SignalStageChanged { i, stage } => Some(SimEvent::SignalChangedStage {
+                intersection: *i,
+                stage: *stage,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Buffers events for each subscriber until they're drained.
+#[derive(Clone, Default)]
+pub(crate) struct Subscriptions {
+    next_id: usize,
+    subscribers: BTreeMap<SubscriptionID, Subscriber>,
+}
+
+#[derive(Clone)]
+struct Subscriber {
+    types: BTreeSet<SimEventType>,
+    pending: Vec<(Time, SimEvent)>,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, types: BTreeSet<SimEventType>) -> SubscriptionID {
+        let id = SubscriptionID(self.next_id);
+        self.next_id += 1;
+        self.subscribers.insert(
+            id,
+            Subscriber {
+                types,
+                pending: Vec::new(),
+            },
+        );
+        id
+    }
+
+    /// Returns false if the subscription doesn't exist
+    pub fn unsubscribe(&mut self, id: SubscriptionID) -> bool {
+        self.subscribers.remove(&id).is_some()
+    }
+
+    pub fn drain(&mut self, id: SubscriptionID) -> Option<Vec<(Time, SimEvent)>> {
+        Some(std::mem::take(&mut self.subscribers.get_mut(&id)?.pending))
+    }
+
+    pub fn handle_event(&mut self, time: Time, ev: &Event) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        if let Some(sim_ev) = SimEvent::from_event(ev) {
+            let event_type = sim_ev.event_type();
+            for sub in self.subscribers.values_mut() {
+                if sub.types.contains(&event_type) {
+                    sub.pending.push((time, sim_ev.clone()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_event_type() {
+        assert_eq!(
+            SimEventType::parse("trip-started").unwrap(),
+            SimEventType::TripStarted
+        );
+        assert_eq!(
+            SimEventType::parse("SignalChangedStage").unwrap(),
+            SimEventType::SignalChangedStage
+        );
+        assert!(SimEventType::parse("trip").is_err());
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let mut subs = Subscriptions::default();
+        let signals = subs.subscribe(vec![SimEventType::SignalChangedStage].into_iter().collect());
+        let everything = subs.subscribe(SimEventType::all().into_iter().collect());
+
+        let time = Time::START_OF_DAY;
+        subs.handle_event(
+            time,
+            &Event::SignalStageChanged {
+                i: IntersectionID(3),
+                stage: 1,
+            },
+        );
+        subs.handle_event(
+            time,
+            &Event::TripCancelled(TripID(0), TripMode::Walk, synthpop::TripPurpose::Shopping),
+        );
+
+        assert_eq!(subs.drain(signals).unwrap().len(), 1);
+        assert_eq!(subs.drain(everything).unwrap().len(), 2);
+        // Draining clears the buffer
+        assert!(subs.drain(everything).unwrap().is_empty());
+
+        assert!(subs.unsubscribe(signals));
+        assert!(subs.drain(signals).is_none());
+    }
+}
@@ -3,8 +3,9 @@ use std::collections::{BTreeMap, BTreeSet};
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::Time;
+use geom::{Duration, Time};
 use map_model::{Map, Path, TransitRoute, TransitRouteID, TransitStopID};
+use synthpop::VehicleClass;
 
 use crate::sim::Ctx;
 use crate::{
@@ -15,6 +16,13 @@ use crate::{
 // These index stops along a route, not stops along a single sidewalk.
 type StopIdx = usize;
 
+/// Even if nobody gets on or off, transit vehicles wait this long at every stop.
+const MIN_DWELL_TIME: Duration = Duration::const_seconds(10.0);
+const TIME_TO_BOARD: Duration = Duration::const_seconds(2.0);
+const TIME_TO_ALIGHT: Duration = Duration::const_seconds(1.0);
+/// Past this fraction of capacity, people standing in the aisles slow down boarding and alighting
+const CROWDED_LOAD: f64 = 0.8;
+
 #[derive(Serialize, Deserialize, Clone)]
 struct Route {
     // Entry i is the path to drive to stop i. The very last path is to drive from the last step to
@@ -30,9 +38,19 @@ struct Bus {
     route: TransitRouteID,
     /// Where does each passenger want to deboard?
     passengers: Vec<(PersonID, Option<TransitStopID>)>,
+    /// None means unlimited
+    capacity: Option<usize>,
     state: BusState,
 }
 
+impl Bus {
+    fn is_full(&self) -> bool {
+        self.capacity
+            .map(|cap| self.passengers.len() >= cap)
+            .unwrap_or(false)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 enum BusState {
     DrivingToStop(StopIdx),
@@ -63,12 +81,13 @@ pub(crate) struct TransitSimState {
     )]
     peds_waiting:
         BTreeMap<TransitStopID, Vec<(PedestrianID, TransitRouteID, Option<TransitStopID>, Time)>>,
+    unlimited_capacity: bool,
 
     events: Vec<Event>,
 }
 
 impl TransitSimState {
-    pub fn new(map: &Map) -> TransitSimState {
+    pub fn new(map: &Map, unlimited_capacity: bool) -> TransitSimState {
         // Keep this filled out always so get_passengers can return &Vec without a hassle
         let mut peds_waiting = BTreeMap::new();
         for ts in map.all_transit_stops().keys() {
@@ -79,10 +98,21 @@ impl TransitSimState {
             buses: BTreeMap::new(),
             routes: BTreeMap::new(),
             peds_waiting,
+            unlimited_capacity,
             events: Vec::new(),
         }
     }
 
+    /// Bus stops may have been added, moved, or removed. Routes with no vehicles out right now
+    /// pick up the new stops when the next one starts; the others keep their old stops.
+    pub fn handle_live_edits(&mut self, map: &Map) {
+        for ts in map.all_transit_stops().keys() {
+            self.peds_waiting.entry(*ts).or_insert_with(Vec::new);
+        }
+        self.routes
+            .retain(|_, route| !route.active_vehicles.is_empty());
+    }
+
     /// Returns the path for the first leg.
     pub fn create_empty_route(&mut self, bus_route: &TransitRoute, map: &Map) -> Path {
         self.routes
@@ -105,7 +135,7 @@ impl TransitSimState {
         self.routes[&bus_route.id].paths[0].clone()
     }
 
-    pub fn bus_created(&mut self, bus: CarID, r: TransitRouteID) {
+    pub fn bus_created(&mut self, bus: CarID, r: TransitRouteID, class: VehicleClass) {
         let route = self.routes.get_mut(&r).unwrap();
         route.active_vehicles.insert(bus);
         self.buses.insert(
@@ -114,13 +144,18 @@ impl TransitSimState {
                 car: bus,
                 route: r,
                 passengers: Vec::new(),
+                capacity: if self.unlimited_capacity {
+                    None
+                } else {
+                    class.passenger_capacity()
+                },
                 state: BusState::DrivingToStop(0),
             },
         );
     }
 
-    /// If true, the bus is idling. If false, the bus actually arrived at a border and should now
-    /// vanish.
+    /// If Some, the bus idles at the stop for that long. If None, the bus actually arrived at a
+    /// border and should now vanish.
     ///
     /// TODO Misnomer -- callback from Router::follow_bus_route
     pub fn bus_arrived_at_stop(
@@ -130,7 +165,7 @@ impl TransitSimState {
         trips: &mut TripManager,
         walking: &mut WalkingSimState,
         ctx: &mut Ctx,
-    ) -> bool {
+    ) -> Option<Duration> {
         let bus = self.buses.get_mut(&id).unwrap();
         match bus.state {
             BusState::DrivingToStop(stop_idx) => {
@@ -140,9 +175,11 @@ impl TransitSimState {
                     .push(Event::BusArrivedAtStop(id, bus.route, stop1));
 
                 // Deboard existing passengers.
+                let mut alighted = 0;
                 let mut still_riding = Vec::new();
                 for (person, maybe_stop2) in bus.passengers.drain(..) {
                     if Some(stop1) == maybe_stop2 {
+                        alighted += 1;
                         trips.person_left_bus(now, person, bus.car, ctx);
                         self.events.push(Event::PassengerAlightsTransit(
                             person, bus.car, bus.route, stop1,
@@ -153,12 +190,25 @@ impl TransitSimState {
                 }
                 bus.passengers = still_riding;
 
-                // Board new passengers.
+                // Board new passengers, as long as there's room. Everybody else waits for the next
+                // vehicle, without losing their place in line.
+                let mut boarded = 0;
                 let mut still_waiting = Vec::new();
                 for (ped, route, maybe_stop2, started_waiting) in
                     self.peds_waiting.remove(&stop1).unwrap()
                 {
-                    if bus.route == route {
+                    if bus.route == route && bus.is_full() {
+                        if let Some(person) = trips
+                            .agent_to_trip(AgentID::Pedestrian(ped))
+                            .and_then(|trip| trips.trip_to_person(trip))
+                        {
+                            self.events.push(Event::PassengerDeniedBoarding(
+                                person, bus.car, bus.route, stop1,
+                            ));
+                        }
+                        still_waiting.push((ped, route, maybe_stop2, started_waiting));
+                    } else if bus.route == route {
+                        boarded += 1;
                         let (trip, person) = trips.ped_boarded_bus(
                             now,
                             ped,
@@ -189,7 +239,12 @@ impl TransitSimState {
                     }
                 }
                 self.peds_waiting.insert(stop1, still_waiting);
-                true
+                Some(dwell_time(
+                    boarded,
+                    alighted,
+                    bus.passengers.len(),
+                    bus.capacity,
+                ))
             }
             BusState::DrivingOffMap => {
                 self.routes
@@ -208,7 +263,7 @@ impl TransitSimState {
                     trips.transit_rider_reached_border(now, person, id, ctx);
                 }
                 bus.state = BusState::Finished;
-                false
+                None
             }
             BusState::AtStop(_) | BusState::Finished => unreachable!(),
         }
@@ -224,6 +279,13 @@ impl TransitSimState {
                     bus.route,
                     route.stops[stop_idx],
                 ));
+                self.events.push(Event::TransitVehicleLoad(
+                    id,
+                    bus.route,
+                    route.stops[stop_idx],
+                    bus.passengers.len(),
+                    bus.capacity,
+                ));
 
                 if stop_idx == route.stops.len() - 1 {
                     bus.state = BusState::DrivingOffMap;
@@ -238,7 +300,8 @@ impl TransitSimState {
         }
     }
 
-    /// Returns the bus if the pedestrian boarded immediately.
+    /// Returns the bus if the pedestrian boarded immediately. If the only vehicle at the stop is
+    /// full, they wait for the next one.
     pub fn ped_waiting_for_bus(
         &mut self,
         now: Time,
@@ -255,6 +318,12 @@ impl TransitSimState {
             for bus in &route.active_vehicles {
                 if let BusState::AtStop(idx) = self.buses[bus].state {
                     if route.stops[idx] == stop1 {
+                        if self.buses[bus].is_full() {
+                            self.events.push(Event::PassengerDeniedBoarding(
+                                person, *bus, route_id, stop1,
+                            ));
+                            continue;
+                        }
                         self.buses
                             .get_mut(bus)
                             .unwrap()
@@ -297,6 +366,11 @@ impl TransitSimState {
         self.buses[&bus].route
     }
 
+    /// None means unlimited
+    pub fn bus_capacity(&self, bus: CarID) -> Option<usize> {
+        self.buses[&bus].capacity
+    }
+
     /// also stop idx that the bus is coming from
     pub fn buses_for_route(&self, route: TransitRouteID) -> Vec<(CarID, Option<usize>)> {
         if let Some(r) = self.routes.get(&route) {
@@ -379,3 +453,35 @@ impl TransitSimState {
         results
     }
 }
+
+/// How long a transit vehicle waits at a stop. Boarding and alighting take longer when the
+/// vehicle is crowded.
+fn dwell_time(
+    boarded: usize,
+    alighted: usize,
+    passengers: usize,
+    capacity: Option<usize>,
+) -> Duration {
+    let mut moving = (boarded as f64) * TIME_TO_BOARD + (alighted as f64) * TIME_TO_ALIGHT;
+    if let Some(cap) = capacity {
+        if cap > 0 && (passengers as f64) / (cap as f64) > CROWDED_LOAD {
+            moving = 1.5 * moving;
+        }
+    }
+    moving.max(MIN_DWELL_TIME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dwell_time() {
+        assert_eq!(dwell_time(0, 0, 0, Some(70)), MIN_DWELL_TIME);
+        assert_eq!(dwell_time(3, 2, 10, Some(70)), MIN_DWELL_TIME);
+        assert_eq!(dwell_time(10, 0, 20, Some(70)), Duration::seconds(20.0));
+        // The same boardings take longer when people have to squeeze past others standing
+        assert_eq!(dwell_time(10, 0, 65, Some(70)), Duration::seconds(30.0));
+        assert_eq!(dwell_time(10, 0, 65, None), Duration::seconds(20.0));
+    }
+}
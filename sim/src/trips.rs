@@ -1,23 +1,28 @@
 use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
 
+use anyhow::Result;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap, Counter};
 use geom::{Distance, Duration, Speed, Time};
 use map_model::{
-    BuildingID, IntersectionID, Map, PathConstraints, PathRequest, Position, TransitRouteID,
-    TransitStopID,
+    AlternativeRoutes, BuildingID, HgvProfile, IntersectionID, Map, Path, PathConstraints,
+    PathRequest, PathStep, PathfinderCaching, Position, RoadID, TransitRouteID, TransitStopID,
 };
 use synthpop::{
-    IndividTrip, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose,
+    price_as_time, Demographics, IndividTrip, OrigPersonID, ParkAndRide, PersonSpec, Scenario,
+    TripEndpoint, TripMode, TripPurpose, VehicleClass,
 };
 
 use crate::sim::Ctx;
 use crate::{
-    AgentID, AgentType, AlertLocation, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal,
-    Event, ParkedCar, ParkingSim, ParkingSpot, PedestrianID, PersonID, SidewalkPOI, SidewalkSpot,
-    StartTripArgs, TransitSimState, TripID, TripPhaseType, TripSpec, Vehicle, VehicleSpec,
-    VehicleType, WalkingSimState,
+    maybe_park_and_ride, AgentID, AgentType, AlertLocation, BehaviorScript, BikeParking, CarID,
+    Command, CreateCar, CreatePedestrian, DrivingGoal, Event, ParkedCar, ParkingSim, ParkingSpot,
+    PedestrianID, PersonID, SidewalkPOI, SidewalkSpot, StartTripArgs, TransitSimState, TripID,
+    TripPhaseType, TripSpec, Vehicle, VehicleSpec, VehicleType, WalkingSimState,
 };
 
 /// Manages people, each of which executes some trips through the day. Each trip is further broken
@@ -39,29 +44,83 @@ pub(crate) struct TripManager {
     unfinished_trips: usize,
 
     car_id_counter: usize,
+    // See SimOptions. Older savestates don't have this, and 0 acts like 1.
+    #[serde(default)]
+    route_alternatives: usize,
+    // See SimOptions
+    #[serde(default)]
+    wheelchair_pct: u8,
+    // From the scenario
+    #[serde(default)]
+    park_and_ride: ParkAndRide,
+    #[serde(default)]
+    bike_parking: BikeParking,
+    // Driving trips to the trips of people riding along
+    #[serde(
+        default,
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    carpools: BTreeMap<TripID, Vec<TripID>>,
+    // See SimOptions
+    #[serde(skip_serializing, skip_deserializing)]
+    behavior_script: Option<Arc<BehaviorScript>>,
 
     events: Vec<Event>,
 }
 
 // Initialization
 impl TripManager {
-    pub fn new() -> TripManager {
+    pub fn new(
+        route_alternatives: usize,
+        wheelchair_pct: u8,
+        behavior_script: Option<Arc<BehaviorScript>>,
+    ) -> TripManager {
         TripManager {
             trips: Vec::new(),
             people: Vec::new(),
             active_trip_mode: BTreeMap::new(),
             unfinished_trips: 0,
             car_id_counter: 0,
+            route_alternatives,
+            wheelchair_pct,
+            park_and_ride: ParkAndRide::default(),
+            bike_parking: BikeParking::default(),
+            carpools: BTreeMap::new(),
+            behavior_script,
             events: Vec::new(),
         }
     }
 
+    pub fn behavior_script(&self) -> Option<Arc<BehaviorScript>> {
+        self.behavior_script.clone()
+    }
+
+    pub fn next_trip_id(&self) -> TripID {
+        TripID(self.trips.len())
+    }
+
+    pub fn set_park_and_ride(&mut self, config: ParkAndRide) {
+        self.park_and_ride = config;
+    }
+
+    pub fn get_park_and_ride(&self) -> &ParkAndRide {
+        &self.park_and_ride
+    }
+
+    pub fn get_bike_parking(&self) -> &BikeParking {
+        &self.bike_parking
+    }
+
     // TODO assert the specs are correct yo
     pub fn new_person(
         &mut self,
         orig_id: Option<OrigPersonID>,
         ped_speed: Speed,
         vehicle_specs: Vec<VehicleSpec>,
+        demographics: Option<Demographics>,
+        wheelchair: bool,
+        value_of_time: u32,
     ) -> &Person {
         let id = PersonID(self.people.len());
         let vehicles = vehicle_specs
@@ -85,10 +144,80 @@ impl TripManager {
             vehicles,
             delayed_trips: Vec::new(),
             on_bus: None,
+            demographics,
+            wheelchair,
+            value_of_time,
         });
         self.get_person(id).unwrap()
     }
 
+    /// Decides if a new person uses a wheelchair. Doesn't touch the RNG unless some people do.
+    pub fn pick_wheelchair_user(
+        &self,
+        demographics: Option<&Demographics>,
+        rng: &mut XorShiftRng,
+    ) -> bool {
+        if self.wheelchair_pct == 0 {
+            return false;
+        }
+        if demographics.map(|d| !d.has_disability).unwrap_or(false) {
+            return false;
+        }
+        rng.gen_bool(f64::from(self.wheelchair_pct.min(100)) / 100.0)
+    }
+
+    /// If a driver's route crosses toll roads, they compare it with the best free route, counting
+    /// the tolls as time according to how much they value it. HGVs stick to the roads they're
+    /// permitted on and always pay.
+    fn maybe_avoid_tolls(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        path: Path,
+        vehicle: &Vehicle,
+        map: &Map,
+    ) -> Path {
+        if vehicle.vehicle_type != VehicleType::Car || vehicle.class == VehicleClass::Hgv {
+            return path;
+        }
+        let mut tolls: Vec<(RoadID, u32)> = Vec::new();
+        for step in path.get_steps() {
+            if let PathStep::Lane(l) = step {
+                let toll = map.get_r(l.road).pricing.toll_at(now);
+                if toll > 0 && !tolls.iter().any(|(r, _)| *r == l.road) {
+                    tolls.push((l.road, toll));
+                }
+            }
+        }
+        if tolls.is_empty() {
+            return path;
+        }
+
+        let free = match map.pathfind_with_params(
+            path.get_req().clone(),
+            &map.toll_free_routing_params(),
+            PathfinderCaching::CacheDijkstra,
+        ) {
+            Ok(free) => free,
+            // There's no way around the tolls
+            Err(_) => {
+                return path;
+            }
+        };
+        let person = &self.people[self.trips[trip.0].person.0];
+        let total: u32 = tolls.iter().map(|(_, cents)| *cents).sum();
+        let tolled_time = path.estimate_duration(map, vehicle.max_speed)
+            + price_as_time(total, person.value_of_time);
+        if tolled_time <= free.estimate_duration(map, vehicle.max_speed) {
+            return path;
+        }
+        self.events.push(Event::AvoidedTolls {
+            trip,
+            roads: tolls.into_iter().map(|(r, _)| r).collect(),
+        });
+        free
+    }
+
     pub fn new_car_id(&mut self) -> usize {
         let id = self.car_id_counter;
         self.car_id_counter += 1;
@@ -133,6 +262,14 @@ impl TripManager {
         id
     }
 
+    /// Instead of starting on their own, the passenger's trip starts and finishes with the driver's.
+    pub fn add_carpool_passenger(&mut self, driver: TripID, passenger: TripID) {
+        self.carpools
+            .entry(driver)
+            .or_insert_with(Vec::new)
+            .push(passenger);
+    }
+
     pub fn start_trip(&mut self, now: Time, trip: TripID, args: StartTripArgs, ctx: &mut Ctx) {
         assert!(self.trips[trip.0].info.cancellation_reason.is_none());
 
@@ -158,21 +295,81 @@ impl TripManager {
             return;
         }
         self.trips[trip.0].started = true;
+        self.events.push(Event::TripStarted {
+            trip,
+            person: person.id,
+            mode: self.trips[trip.0].info.mode,
+        });
+        self.start_carpool_passengers(trip);
 
+        let person = &mut self.people[self.trips[trip.0].person.0];
         let info = &self.trips[trip.0].info;
-        let spec = match TripSpec::maybe_new(
-            info.start,
-            info.end,
-            info.mode,
-            args.use_vehicle,
-            args.retry_if_no_room,
-            ctx.map,
-        ) {
-            Ok(spec) => spec,
-            Err(error) => TripSpec::SpawningFailure {
-                use_vehicle: args.use_vehicle,
-                error: error.to_string(),
-            },
+        let park_and_ride = match (info.start, info.mode, args.use_vehicle) {
+            (TripEndpoint::Building(start_bldg), TripMode::Drive, Some(car))
+                if info.drop_off.is_none() =>
+            {
+                maybe_park_and_ride(
+                    &self.park_and_ride,
+                    start_bldg,
+                    info.end,
+                    car,
+                    person
+                        .vehicles
+                        .iter()
+                        .find(|v| v.vehicle_type == VehicleType::Bike)
+                        .map(|v| v.id),
+                    ctx.parking.lookup_parked_car(car).map(|p| p.spot),
+                    ctx.parking,
+                    ctx.map,
+                )
+            }
+            _ => None,
+        };
+        let spec = if let Some(spec) = park_and_ride {
+            match spec {
+                TripSpec::ParkAndRide {
+                    facility,
+                    ref onward,
+                    ..
+                } => {
+                    self.events.push(Event::ParkAndRide {
+                        trip,
+                        facility,
+                        onward: onward.mode(),
+                        returning: false,
+                    });
+                }
+                TripSpec::ReturnFromParkAndRide {
+                    facility,
+                    ref onward,
+                    ..
+                } => {
+                    self.events.push(Event::ParkAndRide {
+                        trip,
+                        facility,
+                        onward: onward.mode(),
+                        returning: true,
+                    });
+                }
+                _ => unreachable!(),
+            }
+            spec
+        } else {
+            match TripSpec::maybe_new(
+                info.start,
+                info.end,
+                info.mode,
+                args.use_vehicle,
+                args.retry_if_no_room,
+                info.drop_off.is_some(),
+                ctx.map,
+            ) {
+                Ok(spec) => spec,
+                Err(error) => TripSpec::SpawningFailure {
+                    use_vehicle: args.use_vehicle,
+                    error: error.to_string(),
+                },
+            }
         };
         // to_plan might actually change the TripSpec
         let (spec, legs) = spec.into_plan(ctx.map);
@@ -193,36 +390,27 @@ impl TripManager {
                     ctx.map.get_l(start_pos.lane()).src_i,
                 ));
                 person.state = PersonState::Trip(trip);
-
-                let vehicle = person.get_vehicle(use_vehicle);
-                assert!(ctx.parking.lookup_parked_car(vehicle.id).is_none());
-                let constraints = if use_vehicle.vehicle_type == VehicleType::Bike {
-                    PathConstraints::Bike
-                } else {
-                    PathConstraints::Car
-                };
-                let req = PathRequest::vehicle(
+                self.spawn_appearing_vehicle(
+                    now,
+                    trip,
                     start_pos,
-                    goal.goal_pos(constraints, ctx.map).unwrap(),
-                    constraints,
+                    goal,
+                    use_vehicle,
+                    retry_if_no_room,
+                    ctx,
                 );
-                let person = person.id;
-
-                match ctx.map.pathfind(req) {
-                    Ok(path) => {
-                        let router = goal.make_router(vehicle.id, path, ctx.map);
-                        ctx.scheduler.push(
-                            now,
-                            Command::SpawnCar(
-                                CreateCar::for_appearing(vehicle, router, trip, person),
-                                retry_if_no_room,
-                            ),
-                        );
-                    }
-                    Err(err) => {
-                        self.cancel_trip(now, trip, err.to_string(), Some(vehicle), ctx);
-                    }
-                }
+            }
+            TripSpec::PickedUp {
+                start_bldg,
+                start_pos,
+                goal,
+                use_vehicle,
+            } => {
+                assert_eq!(person.state, PersonState::Inside(start_bldg));
+                self.events
+                    .push(Event::PersonLeavesBuilding(person.id, start_bldg));
+                person.state = PersonState::Trip(trip);
+                self.spawn_appearing_vehicle(now, trip, start_pos, goal, use_vehicle, true, ctx);
             }
             TripSpec::SpawningFailure {
                 use_vehicle, error, ..
@@ -232,6 +420,9 @@ impl TripManager {
             }
             TripSpec::UsingParkedCar {
                 car, start_bldg, ..
+            }
+            | TripSpec::ParkAndRide {
+                car, start_bldg, ..
             } => {
                 assert_eq!(person.state, PersonState::Inside(start_bldg));
                 person.state = PersonState::Trip(trip);
@@ -241,7 +432,7 @@ impl TripManager {
                     let walking_goal =
                         SidewalkSpot::parking_spot(parked_car.spot, ctx.map, ctx.parking);
                     let req = PathRequest::walking(start.sidewalk_pos, walking_goal.sidewalk_pos);
-                    match ctx.map.pathfind(req) {
+                    match pathfind_walking(req, person.wheelchair, ctx.map) {
                         Ok(path) => {
                             ctx.scheduler.push(
                                 now,
@@ -280,6 +471,11 @@ impl TripManager {
                     );
                 }
             }
+            TripSpec::ReturnFromParkAndRide { start_bldg, .. } => {
+                assert_eq!(person.state, PersonState::Inside(start_bldg));
+                person.state = PersonState::Trip(trip);
+                self.spawn_ped(now, trip, SidewalkSpot::building(start_bldg, ctx.map), ctx);
+            }
             TripSpec::JustWalking { start, goal } => {
                 assert_eq!(
                     person.state,
@@ -309,7 +505,7 @@ impl TripManager {
                 person.state = PersonState::Trip(trip);
 
                 let req = PathRequest::walking(start.sidewalk_pos, goal.sidewalk_pos);
-                match ctx.map.pathfind(req) {
+                match pathfind_walking(req, person.wheelchair, ctx.map) {
                     Ok(path) => {
                         ctx.scheduler.push(
                             now,
@@ -329,16 +525,22 @@ impl TripManager {
                     }
                 }
             }
-            TripSpec::UsingBike { start, .. } => {
+            TripSpec::UsingBike { start, bike, .. } => {
                 assert_eq!(person.state, PersonState::Inside(start));
                 person.state = PersonState::Trip(trip);
 
-                if let Some(walk_to) = SidewalkSpot::bike_rack(start, ctx.map) {
+                // If there was no room last time, the bike may be parked at a nearby building
+                let walk_to = self
+                    .bike_parking
+                    .unpark(bike, start, ctx.map)
+                    .and_then(|b| SidewalkSpot::bike_rack(b, ctx.map))
+                    .or_else(|| SidewalkSpot::bike_rack(start, ctx.map));
+                if let Some(walk_to) = walk_to {
                     let req = PathRequest::walking(
                         SidewalkSpot::building(start, ctx.map).sidewalk_pos,
                         walk_to.sidewalk_pos,
                     );
-                    match ctx.map.pathfind(req) {
+                    match pathfind_walking(req, person.wheelchair, ctx.map) {
                         Ok(path) => {
                             // Where we start biking may have slightly changed due to live map
                             // edits!
@@ -412,7 +614,7 @@ impl TripManager {
 
                 let walk_to = SidewalkSpot::bus_stop(stop1, ctx.map);
                 let req = PathRequest::walking(start.sidewalk_pos, walk_to.sidewalk_pos);
-                match ctx.map.pathfind(req) {
+                match pathfind_walking(req, person.wheelchair, ctx.map) {
                     Ok(path) => {
                         ctx.scheduler.push(
                             now,
@@ -452,6 +654,76 @@ impl TripManager {
         self.active_trip_mode.insert(agent, t);
     }
 
+    /// Spawn a vehicle that isn't parked anywhere, like when entering the map from a border or
+    /// when somebody is picked up.
+    fn spawn_appearing_vehicle(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        start_pos: Position,
+        goal: DrivingGoal,
+        use_vehicle: CarID,
+        retry_if_no_room: bool,
+        ctx: &mut Ctx,
+    ) {
+        let person = &self.people[self.trips[trip.0].person.0];
+        let vehicle = person.get_vehicle(use_vehicle);
+        assert!(ctx.parking.lookup_parked_car(vehicle.id).is_none());
+        let constraints = if use_vehicle.vehicle_type == VehicleType::Bike {
+            PathConstraints::Bike
+        } else {
+            PathConstraints::Car
+        };
+        let req = PathRequest::vehicle(
+            start_pos,
+            goal.goal_pos(constraints, ctx.map).unwrap(),
+            constraints,
+        );
+        let person = person.id;
+        let delivery = self.trips[trip.0].info.purpose == TripPurpose::Delivery;
+        let drop_off = self.trips[trip.0].info.drop_off;
+
+        match pathfind_vehicle(
+            req,
+            trip,
+            vehicle.class,
+            self.route_alternatives,
+            self.behavior_script.as_deref(),
+            now,
+            ctx.map,
+        ) {
+            Ok(path) => {
+                let path = self.maybe_avoid_tolls(now, trip, path, &vehicle, ctx.map);
+                let value_of_time = self.people[person.0].value_of_time;
+                let occupancy = self.trips[trip.0].info.occupancy;
+                let router = goal.make_router(vehicle.id, path, ctx.map, delivery, drop_off);
+                ctx.scheduler.push(
+                    now,
+                    Command::SpawnCar(
+                        CreateCar::for_appearing(
+                            vehicle,
+                            router,
+                            trip,
+                            person,
+                            value_of_time,
+                            occupancy,
+                        ),
+                        retry_if_no_room,
+                    ),
+                );
+            }
+            Err(err) => {
+                // Nobody's car is left behind after a drop-off
+                let abandoned = if drop_off.is_some() {
+                    None
+                } else {
+                    Some(vehicle)
+                };
+                self.cancel_trip(now, trip, err.to_string(), abandoned, ctx);
+            }
+        }
+    }
+
     pub fn car_reached_parking_spot(
         &mut self,
         now: Time,
@@ -499,6 +771,47 @@ impl TripManager {
         );
     }
 
+    /// A vehicle dropped somebody off near their destination, either in a pick-up/drop-off bay or
+    /// right in the lane, and is leaving the simulation.
+    pub fn car_dropped_off(
+        &mut self,
+        now: Time,
+        car: CarID,
+        bay: Option<ParkingSpot>,
+        blocked_time: Duration,
+        distance_crossed: Distance,
+        ctx: &mut Ctx,
+    ) {
+        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        trip.total_blocked_time += blocked_time;
+        trip.total_distance += distance_crossed;
+
+        let b = match trip.legs.pop_front() {
+            Some(TripLeg::Drive(c, DrivingGoal::ParkNear(b))) => {
+                assert_eq!(car, c);
+                b
+            }
+            _ => unreachable!(),
+        };
+
+        let id = trip.id;
+        if let Some(spot) = bay {
+            self.spawn_ped(
+                now,
+                id,
+                SidewalkSpot::parking_spot(spot, ctx.map, ctx.parking),
+                ctx,
+            );
+        } else {
+            // Stopping in the lane happens right at the door
+            trip.assert_walking_leg(SidewalkSpot::building(b, ctx.map));
+            self.people[trip.person.0].state = PersonState::Inside(b);
+            self.events
+                .push(Event::PersonEntersBuilding(trip.person, b));
+            self.trip_finished(now, id, ctx);
+        }
+    }
+
     pub fn ped_reached_parking_spot(
         &mut self,
         now: Time,
@@ -546,14 +859,34 @@ impl TripManager {
         };
 
         let person = trip.person;
+        let delivery = trip.info.purpose == TripPurpose::Delivery;
+        let occupancy = trip.info.occupancy;
         let trip = trip.id;
-        match ctx.map.pathfind(req) {
+        match pathfind_vehicle(
+            req,
+            trip,
+            parked_car.vehicle.class,
+            self.route_alternatives,
+            self.behavior_script.as_deref(),
+            now,
+            ctx.map,
+        ) {
             Ok(path) => {
-                let router = drive_to.make_router(parked_car.vehicle.id, path, ctx.map);
+                let path = self.maybe_avoid_tolls(now, trip, path, &parked_car.vehicle, ctx.map);
+                let value_of_time = self.people[person.0].value_of_time;
+                let router =
+                    drive_to.make_router(parked_car.vehicle.id, path, ctx.map, delivery, None);
                 ctx.scheduler.push(
                     now,
                     Command::SpawnCar(
-                        CreateCar::for_parked_car(parked_car, router, trip, person),
+                        CreateCar::for_parked_car(
+                            parked_car,
+                            router,
+                            trip,
+                            person,
+                            value_of_time,
+                            occupancy,
+                        ),
                         true,
                     ),
                 );
@@ -584,10 +917,20 @@ impl TripManager {
         trip.total_distance += distance_crossed;
 
         trip.assert_walking_leg(spot.clone());
-        let (bike, drive_to) = match trip.legs[0] {
+        let (bike, mut drive_to) = match trip.legs[0] {
             TripLeg::Drive(bike, ref to) => (bike, to.clone()),
             _ => unreachable!(),
         };
+        // If there's no room to park at the destination, head for free parking nearby instead.
+        // The trip still ends at the original building, so the cyclist walks from there.
+        if let DrivingGoal::ParkNear(b) = drive_to {
+            if !self.bike_parking.has_free_spot(b, ctx.map) {
+                if let Some(overflow) = self.bike_parking.find_overflow(b, ctx.map) {
+                    drive_to = DrivingGoal::ParkNear(overflow);
+                    trip.legs[0] = TripLeg::Drive(bike, drive_to.clone());
+                }
+            }
+        }
         let driving_pos = match spot.connection {
             SidewalkPOI::BikeRack(p) => p,
             _ => unreachable!(),
@@ -615,9 +958,16 @@ impl TripManager {
                 req.start.lane()
             ))
         } else {
-            ctx.map
-                .pathfind(req)
-                .map(|path| drive_to.make_router(bike, path, ctx.map))
+            pathfind_vehicle(
+                req,
+                trip.id,
+                self.people[trip.person.0].get_vehicle(bike).class,
+                self.route_alternatives,
+                self.behavior_script.as_deref(),
+                now,
+                ctx.map,
+            )
+            .map(|path| drive_to.make_router(bike, path, ctx.map, false, None))
         };
         match maybe_router {
             Ok(router) => {
@@ -629,6 +979,8 @@ impl TripManager {
                             router,
                             trip.id,
                             trip.person,
+                            self.people[trip.person.0].value_of_time,
+                            1,
                         ),
                         true,
                     ),
@@ -658,15 +1010,17 @@ impl TripManager {
         trip.total_blocked_time += blocked_time;
         trip.total_distance += distance_crossed;
 
-        match trip.legs.pop_front() {
-            Some(TripLeg::Drive(c, DrivingGoal::ParkNear(_))) => {
+        let parked_at = match trip.legs.pop_front() {
+            Some(TripLeg::Drive(c, DrivingGoal::ParkNear(b))) => {
                 assert_eq!(c, bike);
+                b
             }
             _ => unreachable!(),
         };
+        let search_time = self.bike_parking.park(bike, parked_at, ctx.map);
 
         let id = trip.id;
-        self.spawn_ped(now, id, bike_rack, ctx);
+        self.spawn_ped(now + search_time, id, bike_rack, ctx);
     }
 
     pub fn ped_reached_building(
@@ -903,12 +1257,78 @@ impl TripManager {
         self.events.push(Event::TripFinished {
             trip: trip.id,
             mode: trip.info.mode,
+            purpose: trip.info.purpose,
             total_time: now - trip.info.departure,
             blocked_time: trip.total_blocked_time,
+            occupancy: if trip.info.mode == TripMode::Drive && trip.info.carpool_driver.is_none() {
+                Some(trip.info.occupancy)
+            } else {
+                None
+            },
         });
 
         let person = trip.person;
         self.start_delayed_trip(now, person, ctx);
+
+        // Everybody riding along arrives too
+        for passenger in self.carpools.remove(&id).unwrap_or_default() {
+            let person = self.trips[passenger.0].person;
+            self.people[person.0].state = match self.trips[passenger.0].info.end {
+                TripEndpoint::Building(b) => {
+                    self.events.push(Event::PersonEntersBuilding(person, b));
+                    PersonState::Inside(b)
+                }
+                TripEndpoint::Border(i) => {
+                    self.events.push(Event::PersonLeavesMap(person, None, i));
+                    PersonState::OffMap
+                }
+                TripEndpoint::SuddenlyAppear(_) => unreachable!(),
+            };
+            self.trip_finished(now, passenger, ctx);
+        }
+    }
+
+    /// When a driver starts a trip, people riding along with them start too. Anybody still busy
+    /// with a previous trip misses their ride.
+    fn start_carpool_passengers(&mut self, driver: TripID) {
+        let passengers = match self.carpools.remove(&driver) {
+            Some(passengers) => passengers,
+            None => {
+                return;
+            }
+        };
+        let mut riding = Vec::new();
+        for id in passengers {
+            let person = self.trips[id.0].person;
+            match self.people[person.0].state {
+                PersonState::Trip(_) => {
+                    self.cancel_unstarted_trip(id, "missed their carpool".to_string());
+                    self.trips[driver.0].info.occupancy -= 1;
+                    continue;
+                }
+                PersonState::Inside(b) => {
+                    self.events.push(Event::PersonLeavesBuilding(person, b));
+                }
+                PersonState::OffMap => {}
+            }
+            self.people[person.0].state = PersonState::Trip(id);
+            self.trips[id.0].started = true;
+            self.events.push(Event::TripStarted {
+                trip: id,
+                person,
+                mode: self.trips[id.0].info.mode,
+            });
+            self.events.push(Event::TripPhaseStarting(
+                id,
+                person,
+                None,
+                TripPhaseType::Driving,
+            ));
+            riding.push(id);
+        }
+        if !riding.is_empty() {
+            self.carpools.insert(driver, riding);
+        }
     }
 
     fn start_delayed_trip(&mut self, now: Time, id: PersonID, ctx: &mut Ctx) {
@@ -931,13 +1351,36 @@ impl TripManager {
 
     fn spawn_ped(&mut self, now: Time, id: TripID, start: SidewalkSpot, ctx: &mut Ctx) {
         let trip = &self.trips[id.0];
-        let walk_to = match trip.legs[0] {
+        let mut walk_to = match trip.legs[0] {
             TripLeg::Walk(ref to) => to.clone(),
             _ => unreachable!(),
         };
+        // Heading back to a car left somewhere earlier, like at a park-and-ride facility
+        if walk_to.connection == SidewalkPOI::DeferredParkingSpot {
+            let spot = match trip.legs.get(1) {
+                Some(TripLeg::Drive(car, _)) => ctx.parking.lookup_parked_car(*car).map(|p| p.spot),
+                _ => unreachable!(),
+            };
+            match spot {
+                Some(spot) => {
+                    walk_to = SidewalkSpot::parking_spot(spot, ctx.map, ctx.parking);
+                }
+                None => {
+                    self.cancel_trip(
+                        now,
+                        id,
+                        "the car left at a park-and-ride facility is unavailable".to_string(),
+                        None,
+                        ctx,
+                    );
+                    return;
+                }
+            }
+        }
 
         let req = PathRequest::walking(start.sidewalk_pos, walk_to.sidewalk_pos);
-        match ctx.map.pathfind(req) {
+        let wheelchair = self.people[trip.person.0].wheelchair;
+        match pathfind_walking(req, wheelchair, ctx.map) {
             Ok(path) => {
                 let person = &self.people[trip.person.0];
                 ctx.scheduler.push(
@@ -967,8 +1410,11 @@ impl TripManager {
         let trip = &mut self.trips[id.0];
         self.unfinished_trips -= 1;
         trip.info.cancellation_reason = Some(reason);
-        self.events
-            .push(Event::TripCancelled(trip.id, trip.info.mode));
+        self.events.push(Event::TripCancelled(
+            trip.id,
+            trip.info.mode,
+            trip.info.purpose,
+        ));
     }
 
     /// Cancel a trip after it's started. The person will be magically warped to their destination,
@@ -984,8 +1430,11 @@ impl TripManager {
         let trip = &mut self.trips[id.0];
         self.unfinished_trips -= 1;
         trip.info.cancellation_reason = Some(reason);
-        self.events
-            .push(Event::TripCancelled(trip.id, trip.info.mode));
+        self.events.push(Event::TripCancelled(
+            trip.id,
+            trip.info.mode,
+            trip.info.purpose,
+        ));
         let person = trip.person;
 
         // Maintain consistentency for anyone listening to events
@@ -1057,7 +1506,8 @@ impl TripManager {
         } else {
             // If the trip was cancelled because we'e totally out of parking, don't forget to clean
             // this up.
-            if let TripLeg::Drive(c, _) = &trip.legs[0] {
+            // People riding along in a carpool have no legs.
+            if let Some(TripLeg::Drive(c, _)) = trip.legs.front() {
                 if let Some(t) = self.active_trip_mode.remove(&AgentID::Car(*c)) {
                     assert_eq!(t, trip.id);
                 }
@@ -1065,6 +1515,16 @@ impl TripManager {
         }
 
         self.start_delayed_trip(now, person, ctx);
+
+        // Anybody riding along is warped to the destination too
+        for passenger in self.carpools.remove(&id).unwrap_or_default() {
+            let reason = "their carpool driver's trip was cancelled".to_string();
+            if self.trips[passenger.0].started {
+                self.cancel_trip(now, passenger, reason, None, ctx);
+            } else {
+                self.cancel_unstarted_trip(passenger, reason);
+            }
+        }
     }
 
     pub fn trip_abruptly_cancelled(&mut self, trip: TripID, agent: AgentID) {
@@ -1099,6 +1559,10 @@ impl TripManager {
         if !trip.started {
             return TripResult::TripNotStarted;
         }
+        // Somebody riding along is wherever the driver is
+        if let Some(driver) = trip.info.carpool_driver {
+            return self.trip_to_agent(driver);
+        }
 
         let person = &self.people[trip.person.0];
         let a = match &trip.legs[0] {
@@ -1159,6 +1623,7 @@ impl TripManager {
             cyclists: 0,
 
             sov_drivers: 0,
+            carpools: 0,
 
             buses,
             trains,
@@ -1166,11 +1631,15 @@ impl TripManager {
             train_riders: 0,
         };
 
-        for a in self.active_trip_mode.keys() {
+        for (a, trip) in &self.active_trip_mode {
             match a {
                 AgentID::Car(c) => match c.vehicle_type {
                     VehicleType::Car => {
-                        cnt.sov_drivers += 1;
+                        if self.trips[trip.0].info.occupancy > 1 {
+                            cnt.carpools += 1;
+                        } else {
+                            cnt.sov_drivers += 1;
+                        }
                     }
                     VehicleType::Bike => {
                         cnt.cyclists += 1;
@@ -1291,15 +1760,23 @@ impl TripManager {
                     .iter()
                     .map(|t| {
                         let trip = &self.trips[t.0];
-                        IndividTrip::new(
+                        let mut individ = IndividTrip::new(
                             trip.info.departure,
                             trip.info.purpose,
                             trip.info.start,
                             trip.info.end,
                             trip.info.mode,
-                        )
+                        );
+                        individ.occupancy = trip.info.occupancy;
+                        individ.passenger_of = trip.info.carpool_driver.map(|driver_trip| {
+                            let driver = &self.people[self.trips[driver_trip.0].person.0];
+                            let idx = driver.trips.iter().position(|t| *t == driver_trip).unwrap();
+                            (driver.id.0, idx)
+                        });
+                        individ
                     })
                     .collect(),
+                demographics: p.demographics,
             });
         }
         scenario
@@ -1330,6 +1807,14 @@ pub struct TripInfo {
     /// Did a ScenarioModifier apply to this?
     pub modified: bool,
     pub cancellation_reason: Option<String>,
+    /// For driving trips, how many people are in the car, including the driver
+    pub occupancy: usize,
+    /// Instead of making this trip on their own, this person rides along with somebody else's
+    /// driving trip
+    pub carpool_driver: Option<TripID>,
+    /// A taxi or somebody else's car picks this person up and drops them off, stopping at the kerb
+    /// this long instead of parking
+    pub drop_off: Option<Duration>,
 }
 
 impl Trip {
@@ -1394,6 +1879,13 @@ pub struct Person {
     pub ped_speed: Speed,
     /// Both cars and bikes
     pub vehicles: Vec<Vehicle>,
+    pub demographics: Option<Demographics>,
+    /// Walks along routes avoiding steps and raised kerbs
+    #[serde(default)]
+    pub wheelchair: bool,
+    /// Cents per hour this person would pay to save time when driving
+    #[serde(default)]
+    pub value_of_time: u32,
 
     delayed_trips: Vec<(TripID, StartTripArgs)>,
     on_bus: Option<CarID>,
@@ -1422,9 +1914,175 @@ pub struct CommutersVehiclesCounts {
     pub cyclists: usize,
 
     pub sov_drivers: usize,
+    /// Cars carrying more than one person
+    pub carpools: usize,
 
     pub buses: usize,
     pub trains: usize,
     pub bus_riders: usize,
     pub train_riders: usize,
 }
+
+/// Wheelchair users avoid steps and raised kerbs when they can. If there's no such route at all,
+/// they go anyway, rather than the trip failing.
+fn pathfind_walking(req: PathRequest, wheelchair: bool, map: &Map) -> Result<Path> {
+    if wheelchair {
+        if let Ok(path) = map.pathfind_wheelchair(req.clone()) {
+            return Ok(path);
+        }
+    }
+    map.pathfind(req)
+}
+
+// How strongly drivers prefer faster routes when picking among alternatives. A route taking 10%
+// longer than the best is e^-1 (about 37%) as likely to be picked.
+const ROUTE_CHOICE_SENSITIVITY: f64 = 10.0;
+
+/// Vehicles normally take the best route. If `alternatives` is more than 1, pick one of several
+/// reasonable routes instead, favoring faster ones. The choice only depends on the trip, so the
+/// simulation stays deterministic. A behavior script may take over the choice entirely. HGVs
+/// always take the best route they're permitted to use.
+fn pathfind_vehicle(
+    req: PathRequest,
+    trip: TripID,
+    class: VehicleClass,
+    alternatives: usize,
+    script: Option<&BehaviorScript>,
+    now: Time,
+    map: &Map,
+) -> Result<Path> {
+    let path = choose_vehicle_path(req, trip, class, alternatives, script, map)?;
+    // Car-free streets only let cars in during their delivery windows
+    let closed = |step: &PathStep| match step {
+        PathStep::Lane(l) => !map.get_r(l.road).access_restrictions.cars_allowed_at(now),
+        _ => false,
+    };
+    if path.get_req().constraints == PathConstraints::Car && path.get_steps().iter().any(closed) {
+        let mut params = map.delivery_window_routing_params(now);
+        if class == VehicleClass::Hgv {
+            params
+                .avoid_roads
+                .extend(map.hgv_routing_params(&HgvProfile::default()).avoid_roads);
+        }
+        return map.pathfind_with_params(
+            path.get_req().clone(),
+            &params,
+            PathfinderCaching::CacheDijkstra,
+        );
+    }
+    Ok(path)
+}
+
+fn choose_vehicle_path(
+    req: PathRequest,
+    trip: TripID,
+    class: VehicleClass,
+    alternatives: usize,
+    script: Option<&BehaviorScript>,
+    map: &Map,
+) -> Result<Path> {
+    if class == VehicleClass::Hgv {
+        let params = map.hgv_routing_params(&HgvProfile::default());
+        if &params != map.routing_params() {
+            return map.pathfind_with_params(req, &params, PathfinderCaching::CacheDijkstra);
+        }
+    }
+
+    if let Some(script) = script.filter(|s| s.has_route_cost()) {
+        let mode = if req.constraints == PathConstraints::Bike {
+            TripMode::Bike
+        } else {
+            TripMode::Drive
+        };
+        let opts = AlternativeRoutes {
+            count: alternatives.max(3),
+            ..Default::default()
+        };
+        let mut paths = opts.find(req, map.routing_params(), map)?;
+        let costs: Vec<f64> = paths
+            .iter()
+            .enumerate()
+            .map(|(rank, path)| script.route_cost(trip, mode, path.get_cost(), rank))
+            .collect();
+        let mut idx = 0;
+        for (rank, cost) in costs.iter().enumerate() {
+            if *cost < costs[idx] {
+                idx = rank;
+            }
+        }
+        return paths.swap_remove(idx).into_v1(map);
+    }
+
+    if alternatives <= 1 {
+        return map.pathfind(req);
+    }
+    let opts = AlternativeRoutes {
+        count: alternatives,
+        ..Default::default()
+    };
+    let mut paths = opts.find(req, map.routing_params(), map)?;
+    let best = paths[0].get_cost().inner_seconds().max(1.0);
+    let weights: Vec<f64> = paths
+        .iter()
+        .map(|path| {
+            portable_exp(-ROUTE_CHOICE_SENSITIVITY * (path.get_cost().inner_seconds() / best - 1.0))
+        })
+        .collect();
+
+    let mut rng = XorShiftRng::seed_from_u64(trip.0 as u64);
+    let mut choice = rng.gen_range(0.0..weights.iter().sum::<f64>());
+    let mut idx = 0;
+    while idx < weights.len() - 1 && choice >= weights[idx] {
+        choice -= weights[idx];
+        idx += 1;
+    }
+    paths.swap_remove(idx).into_v1(map)
+}
+
+/// `f64::exp` calls the platform's math library, which may round differently on different
+/// operating systems and CPUs. Since the result feeds into route choice, that'd make the
+/// simulation diverge between platforms. Only basic arithmetic is used here, which IEEE 754
+/// guarantees to be the same everywhere.
+fn portable_exp(x: f64) -> f64 {
+    if x.is_nan() {
+        return x;
+    }
+    if x < -708.0 {
+        return 0.0;
+    }
+    if x > 709.0 {
+        return f64::INFINITY;
+    }
+    // exp(x) = 2^k * exp(r), with |r| <= ln(2) / 2
+    let k = (x / std::f64::consts::LN_2).round();
+    let r = x - k * std::f64::consts::LN_2;
+    // The Taylor series converges quickly for small r
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for n in 1..=20 {
+        term *= r / (n as f64);
+        sum += term;
+    }
+    // Build 2^k directly from its bits
+    sum * f64::from_bits(((k as i64 + 1023) as u64) << 52)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::portable_exp;
+
+    #[test]
+    fn test_portable_exp() {
+        for x in [-100.0, -10.0, -2.5, -1.0, -0.1, 0.0, 0.1, 1.0, 3.7, 50.0] {
+            let expected: f64 = x.exp();
+            assert!(
+                (portable_exp(x) - expected).abs() <= expected * 1e-12,
+                "portable_exp({}) = {}, but exp is {}",
+                x,
+                portable_exp(x),
+                expected
+            );
+        }
+        assert_eq!(portable_exp(-1000.0), 0.0);
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+use map_model::BuildingID;
+
+use crate::{Scenario, TripEndpoint, TripMode, TripPurpose};
+
+/// Including the driver, no more than this many people share a car
+pub const MAX_CAR_OCCUPANCY: usize = 4;
+
+/// How many people ride in each car, including the driver. The relative weights don't need to sum
+/// to anything in particular.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OccupancyMix {
+    pub weights: Vec<(usize, usize)>,
+}
+
+impl Default for OccupancyMix {
+    /// Most cars carry only their driver, averaging about 1.3 people
+    fn default() -> OccupancyMix {
+        OccupancyMix {
+            weights: vec![(1, 75), (2, 18), (3, 5), (4, 2)],
+        }
+    }
+}
+
+impl OccupancyMix {
+    pub fn check(&self) -> Result<()> {
+        if self.weights.iter().all(|(_, weight)| *weight == 0) {
+            bail!("The occupancy mix needs at least one positive weight");
+        }
+        if let Some((occupancy, _)) = self
+            .weights
+            .iter()
+            .find(|(occupancy, _)| *occupancy == 0 || *occupancy > MAX_CAR_OCCUPANCY)
+        {
+            bail!(
+                "Cars carry between 1 and {} people, not {}",
+                MAX_CAR_OCCUPANCY,
+                occupancy
+            );
+        }
+        Ok(())
+    }
+
+    pub fn sample(&self, rng: &mut XorShiftRng) -> usize {
+        let total: usize = self.weights.iter().map(|(_, weight)| *weight).sum();
+        let mut pick = rng.gen_range(0..total);
+        for (occupancy, weight) in &self.weights {
+            if pick < *weight {
+                return *occupancy;
+            }
+            pick -= *weight;
+        }
+        unreachable!()
+    }
+
+    pub fn describe(&self) -> String {
+        let total: usize = self.weights.iter().map(|(_, weight)| *weight).sum();
+        let people: usize = self
+            .weights
+            .iter()
+            .map(|(occupancy, weight)| *occupancy * *weight)
+            .sum();
+        format!(
+            "{:.2} people per car on average",
+            (people as f64) / (total.max(1) as f64)
+        )
+    }
+}
+
+impl Scenario {
+    /// Sample how many people ride in each car. Trips already carrying passengers from the
+    /// scenario keep their occupancy, and somebody escorting another person always has company.
+    pub fn assign_occupancy(&mut self, mix: &OccupancyMix, rng: &mut XorShiftRng) {
+        if let Err(err) = mix.check() {
+            panic!("{}", err);
+        }
+        for person in &mut self.people {
+            for trip in &mut person.trips {
+                if trip.mode != TripMode::Drive
+                    || trip.cancelled
+                    || trip.passenger_of.is_some()
+                    || trip.occupancy > 1
+                {
+                    continue;
+                }
+                let mut occupancy = mix.sample(rng);
+                if trip.purpose == TripPurpose::Escort {
+                    occupancy = occupancy.max(2);
+                }
+                if occupancy != trip.occupancy {
+                    trip.occupancy = occupancy;
+                    trip.modified = true;
+                }
+            }
+        }
+    }
+
+    /// People whose day starts in the same building are treated as one household. When one of
+    /// them drives somewhere, other members driving the same trip within `max_departure_gap`
+    /// leave with them and ride along instead. Returns the number of trips turned into
+    /// passengers.
+    pub fn form_household_carpools(&mut self, max_departure_gap: Duration) -> usize {
+        let mut households: BTreeMap<BuildingID, Vec<usize>> = BTreeMap::new();
+        for (idx, person) in self.people.iter().enumerate() {
+            if let Some(TripEndpoint::Building(b)) = person.trips.first().map(|t| t.origin) {
+                households.entry(b).or_insert_with(Vec::new).push(idx);
+            }
+        }
+
+        let mut num_passengers = 0;
+        for members in households.into_values() {
+            if members.len() < 2 {
+                continue;
+            }
+            // (departure, person, trip)
+            let mut candidates: Vec<(Time, usize, usize)> = Vec::new();
+            for p in members {
+                for (t, trip) in self.people[p].trips.iter().enumerate() {
+                    if trip.mode == TripMode::Drive
+                        && !trip.cancelled
+                        && trip.passenger_of.is_none()
+                    {
+                        candidates.push((trip.depart, p, t));
+                    }
+                }
+            }
+            candidates.sort();
+
+            // The first to leave drives, picking up anybody heading the same way soon after
+            let mut passengers: BTreeSet<(usize, usize)> = BTreeSet::new();
+            for (idx, (depart, driver, driver_trip)) in candidates.iter().cloned().enumerate() {
+                if passengers.contains(&(driver, driver_trip)) {
+                    continue;
+                }
+                let origin = self.people[driver].trips[driver_trip].origin;
+                let destination = self.people[driver].trips[driver_trip].destination;
+                for (other_depart, p, t) in candidates[idx + 1..].iter().cloned() {
+                    if other_depart - depart > max_departure_gap
+                        || self.people[driver].trips[driver_trip].occupancy >= MAX_CAR_OCCUPANCY
+                    {
+                        break;
+                    }
+                    if p == driver || passengers.contains(&(p, t)) {
+                        continue;
+                    }
+                    let trips = &self.people[p].trips;
+                    if trips[t].origin != origin || trips[t].destination != destination {
+                        continue;
+                    }
+                    // Leaving earlier with the driver can't overlap the passenger's previous trip
+                    if t > 0 && trips[t - 1].depart >= depart {
+                        continue;
+                    }
+
+                    let trip = &mut self.people[p].trips[t];
+                    trip.depart = depart;
+                    trip.passenger_of = Some((driver, driver_trip));
+                    trip.modified = true;
+                    let trip = &mut self.people[driver].trips[driver_trip];
+                    trip.occupancy += 1;
+                    trip.modified = true;
+                    passengers.insert((p, t));
+                    num_passengers += 1;
+                }
+            }
+        }
+        num_passengers
+    }
+
+    /// Of the people driving or riding in cars, how many are alone?
+    pub fn sov_share(&self) -> f64 {
+        let mut alone = 0;
+        let mut total = 0;
+        for trip in self.all_trips() {
+            if trip.mode != TripMode::Drive || trip.cancelled || trip.passenger_of.is_some() {
+                continue;
+            }
+            if trip.occupancy == 1 {
+                alone += 1;
+            }
+            total += trip.occupancy;
+        }
+        if total == 0 {
+            0.0
+        } else {
+            (alone as f64) / (total as f64)
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet};
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{LonLat, Polygon};
+use map_model::{osm, BuildingID, IntersectionID, Map, Traversable};
+
+use crate::{IndividTrip, MapBorder, MapBorders, ParkAndRide, PersonSpec, Scenario, TripEndpoint};
+
+impl Scenario {
+    /// Adapts this scenario to a map cropped from its map with `Map::clip_to_study_area`, using
+    /// the same `boundary`. Trips starting and ending at buildings inside the study area are kept
+    /// as they are. Trips entering, leaving, or passing through the area instead start or end at
+    /// the new border closest to where their route on the original map crosses the boundary.
+    /// Trips that never touch the area are dropped, and so is anybody left without trips.
+    ///
+    /// Departure times aren't adjusted for the part of the trip happening off the new map.
+    pub fn clip_to_study_area(
+        &self,
+        orig_map: &Map,
+        boundary: &Polygon,
+        new_map: &Map,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let mut osm_id_to_bldg: HashMap<osm::OsmID, BuildingID> = HashMap::new();
+        for b in new_map.all_buildings() {
+            osm_id_to_bldg.insert(b.orig_id, b.id);
+        }
+        let inside: HashSet<IntersectionID> = orig_map
+            .all_intersections()
+            .iter()
+            .filter(|i| boundary.contains_pt(i.polygon.center()))
+            .map(|i| i.id)
+            .collect();
+        let borders = MapBorders::new(new_map);
+        let ctx = ClipContext {
+            orig_map,
+            inside,
+            osm_id_to_bldg,
+            borders,
+        };
+
+        let total_trips = self.people.iter().map(|p| p.trips.len()).sum::<usize>();
+        let people: Vec<PersonSpec> = timer
+            .parallelize(
+                "clip trips to study area",
+                self.people.iter().collect(),
+                |person| {
+                    let trips: Vec<IndividTrip> = person
+                        .trips
+                        .iter()
+                        .filter_map(|trip| ctx.clip_trip(trip))
+                        .collect();
+                    if trips.is_empty() {
+                        None
+                    } else {
+                        Some(PersonSpec {
+                            orig_id: person.orig_id,
+                            trips,
+                            demographics: person.demographics.clone(),
+                        })
+                    }
+                },
+            )
+            .into_iter()
+            .flatten()
+            .collect();
+        info!(
+            "{} trips clipped down to {}, over {} people",
+            prettyprint_usize(total_trips),
+            prettyprint_usize(people.iter().map(|p| p.trips.len()).sum::<usize>()),
+            prettyprint_usize(people.len())
+        );
+
+        Scenario {
+            scenario_name: self.scenario_name.clone(),
+            map_name: new_map.get_name().clone(),
+            people,
+            only_seed_buses: None,
+            vehicle_mix: self.vehicle_mix.clone(),
+            park_and_ride: ParkAndRide {
+                facilities: self
+                    .park_and_ride
+                    .facilities
+                    .iter()
+                    .filter_map(|b| ctx.osm_id_to_bldg.get(&orig_map.get_b(*b).orig_id).cloned())
+                    .collect(),
+                ..self.park_and_ride.clone()
+            },
+            value_of_time: self.value_of_time.clone(),
+            pick_up_drop_off: self.pick_up_drop_off.clone(),
+        }
+        .remove_weird_schedules(false)
+    }
+}
+
+struct ClipContext<'a> {
+    orig_map: &'a Map,
+    /// Intersections of the original map inside the study area
+    inside: HashSet<IntersectionID>,
+    /// Buildings in the new map, by their original ID
+    osm_id_to_bldg: HashMap<osm::OsmID, BuildingID>,
+    /// Borders of the new map
+    borders: MapBorders,
+}
+
+impl<'a> ClipContext<'a> {
+    fn clip_trip(&self, trip: &IndividTrip) -> Option<IndividTrip> {
+        let kept_endpoint = |endpt: TripEndpoint| match endpt {
+            TripEndpoint::Building(b) => self
+                .osm_id_to_bldg
+                .get(&self.orig_map.get_b(b).orig_id)
+                .map(|b| TripEndpoint::Building(*b)),
+            // Even borders of the original map inside the study area are matched up below
+            TripEndpoint::Border(_) | TripEndpoint::SuddenlyAppear(_) => None,
+        };
+        let origin = kept_endpoint(trip.origin);
+        let destination = kept_endpoint(trip.destination);
+
+        let (origin, destination) = if let (Some(origin), Some(destination)) = (origin, destination)
+        {
+            (origin, destination)
+        } else {
+            // Find where the route on the original map enters and leaves the study area
+            let req =
+                TripEndpoint::path_req(trip.origin, trip.destination, trip.mode, self.orig_map)?;
+            let path = self.orig_map.pathfind(req).ok()?;
+            let mut crossed = path.get_steps().iter().filter_map(|step| {
+                if let Traversable::Turn(t) = step.as_traversable() {
+                    if self.inside.contains(&t.parent) {
+                        return Some(t.parent);
+                    }
+                }
+                None
+            });
+            let entry = crossed.next()?;
+            let exit = crossed.last().unwrap_or(entry);
+
+            let (incoming, outgoing) = self.borders.for_mode(trip.mode);
+            let origin = match origin {
+                Some(x) => x,
+                None => self.closest_border(incoming, entry)?,
+            };
+            let destination = match destination {
+                Some(x) => x,
+                None => self.closest_border(outgoing, exit)?,
+            };
+            (origin, destination)
+        };
+        if origin == destination {
+            return None;
+        }
+
+        let mut clipped = trip.clone();
+        clipped.origin = origin;
+        clipped.destination = destination;
+        // Carpools refer to people by index, which clipping changes. Passengers drive themselves.
+        clipped.passenger_of = None;
+        Some(clipped)
+    }
+
+    fn closest_border(&self, borders: &[MapBorder], i: IntersectionID) -> Option<TripEndpoint> {
+        let gps: LonLat = self
+            .orig_map
+            .get_i(i)
+            .polygon
+            .center()
+            .to_gps(self.orig_map.get_gps_bounds());
+        borders
+            .iter()
+            .min_by_key(|border| border.gps_pos.fast_dist(gps))
+            .map(|border| TripEndpoint::Border(border.i))
+    }
+}
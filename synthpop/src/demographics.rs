@@ -0,0 +1,88 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Socio-demographic attributes of one person. These don't affect how anybody behaves in the
+/// simulation yet; they're carried along so the results of a proposal can be split by group.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Demographics {
+    pub age: AgeBand,
+    /// Not every source of census data has this
+    pub income: Option<IncomeBand>,
+    pub owns_car: bool,
+    pub has_disability: bool,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AgeBand {
+    Under18,
+    From18To34,
+    From35To64,
+    Over65,
+}
+
+impl AgeBand {
+    pub fn all() -> Vec<AgeBand> {
+        vec![
+            AgeBand::Under18,
+            AgeBand::From18To34,
+            AgeBand::From35To64,
+            AgeBand::Over65,
+        ]
+    }
+
+    pub fn from_years(age: usize) -> AgeBand {
+        if age < 18 {
+            AgeBand::Under18
+        } else if age < 35 {
+            AgeBand::From18To34
+        } else if age < 65 {
+            AgeBand::From35To64
+        } else {
+            AgeBand::Over65
+        }
+    }
+}
+
+impl fmt::Display for AgeBand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AgeBand::Under18 => "under 18",
+                AgeBand::From18To34 => "18 to 34",
+                AgeBand::From35To64 => "35 to 64",
+                AgeBand::Over65 => "65 and over",
+            }
+        )
+    }
+}
+
+/// Income relative to the rest of the region, not an absolute amount
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IncomeBand {
+    Low,
+    Middle,
+    High,
+}
+
+impl IncomeBand {
+    pub fn all() -> Vec<IncomeBand> {
+        vec![IncomeBand::Low, IncomeBand::Middle, IncomeBand::High]
+    }
+}
+
+impl fmt::Display for IncomeBand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                IncomeBand::Low => "low income",
+                IncomeBand::Middle => "middle income",
+                IncomeBand::High => "high income",
+            }
+        )
+    }
+}
@@ -75,6 +75,7 @@ impl ExternalPerson {
             let mut spec = PersonSpec {
                 orig_id: None,
                 trips: Vec::new(),
+                demographics: None,
             };
             for trip in person.trips {
                 if trip.departure < Time::START_OF_DAY {
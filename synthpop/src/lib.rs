@@ -1,5 +1,6 @@
-//! This crate describes a synthetic population that exist in a map. Currently each person's travel
-//! behavior is modelled, but in the future, demographic and health attributes may be added.
+//! This crate describes a synthetic population that exist in a map. Each person's travel behavior
+//! is modelled, and some people also have demographic attributes. In the future, health attributes
+//! may be added.
 //! There's a variety of ways to create these populations, scattered in other crates.
 //!
 //! Note that "scenario" is the term currently used to describe the population. This will be
@@ -16,19 +17,34 @@ use abstutil::{deserialize_usize, serialize_usize};
 use map_model::PathConstraints;
 
 pub use self::borders::{MapBorder, MapBorders};
+pub use self::carpool::{OccupancyMix, MAX_CAR_OCCUPANCY};
 pub use self::counts::TrafficCounts;
+pub use self::demographics::{AgeBand, Demographics, IncomeBand};
 pub use self::endpoint::TripEndpoint;
 pub use self::external::{ExternalPerson, ExternalTrip, ExternalTripEndpoint};
 pub use self::modifier::ScenarioModifier;
+pub use self::observed::{ExternalObservation, Observation, ObservedTraffic};
+pub use self::park_and_ride::ParkAndRide;
+pub use self::pick_up_drop_off::PickUpDropOff;
 pub use self::scenario::{IndividTrip, PersonSpec, Scenario, TripPurpose};
+pub use self::value_of_time::{price_as_time, ValueOfTime};
+pub use self::vehicles::{VehicleClass, VehicleMix};
 
 mod borders;
+mod carpool;
+mod clip;
 mod counts;
+mod demographics;
 mod endpoint;
 mod external;
 pub mod make;
 mod modifier;
+mod observed;
+mod park_and_ride;
+mod pick_up_drop_off;
 mod scenario;
+mod value_of_time;
+mod vehicles;
 
 /// How does a trip primarily happen?
 ///
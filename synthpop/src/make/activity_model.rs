@@ -106,18 +106,7 @@ impl ScenarioGenerator {
         let mut num_trips_passthru = 0;
         timer.start("create people");
 
-        // Only consider two-way intersections, so the agent can return the same way
-        // they came.
-        // TODO: instead, if it's not a two-way border, we should find an intersection
-        // an incoming border "near" the outgoing border, to allow a broader set of
-        // realistic options.
-        // TODO: prefer larger thoroughfares to better reflect reality.
-        let commuter_borders: Vec<TripEndpoint> = map
-            .all_outgoing_borders()
-            .into_iter()
-            .filter(|b| b.is_incoming_border())
-            .map(|b| TripEndpoint::Border(b.id))
-            .collect();
+        let commuter_borders = commuter_borders(map);
         let person_params = (0..num_trips)
             .filter_map(|_| {
                 let (is_local_resident, is_local_worker) = (
@@ -198,6 +187,100 @@ impl ScenarioGenerator {
         );
         s
     }
+
+    /// People who'd live or work in some new buildings, like a proposed development. Each
+    /// resident commutes to an existing workplace, and each worker from an existing home. If the
+    /// map has none, they commute across the map boundary instead.
+    pub fn development_trips(
+        map: &Map,
+        new_bldgs: &[BuildingID],
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Vec<PersonSpec> {
+        // Like proletariat_robot, repeat each building once per resident or worker, so bigger
+        // ones are picked more often
+        let mut homes: Vec<BuildingID> = Vec::new();
+        let mut workplaces: Vec<BuildingID> = Vec::new();
+        for b in map.all_buildings() {
+            if new_bldgs.contains(&b.id) {
+                continue;
+            }
+            let (residents, workers) = residents_and_workers(&b.bldg_type);
+            homes.extend(std::iter::repeat(b.id).take(residents));
+            workplaces.extend(std::iter::repeat(b.id).take(workers));
+        }
+        let commuter_borders = commuter_borders(map);
+
+        let mut person_params = Vec::new();
+        for b in new_bldgs {
+            let (residents, workers) = residents_and_workers(&map.get_b(*b).bldg_type);
+            for _ in 0..residents {
+                let work = match workplaces.choose(rng) {
+                    Some(workplace) => TripEndpoint::Building(*workplace),
+                    None => match commuter_borders.choose(rng) {
+                        Some(border) => *border,
+                        None => continue,
+                    },
+                };
+                person_params.push((TripEndpoint::Building(*b), work, fork_rng(rng)));
+            }
+            for _ in 0..workers {
+                let home = match homes.choose(rng) {
+                    Some(home) => TripEndpoint::Building(*home),
+                    None => match commuter_borders.choose(rng) {
+                        Some(border) => *border,
+                        None => continue,
+                    },
+                };
+                person_params.push((home, TripEndpoint::Building(*b), fork_rng(rng)));
+            }
+        }
+
+        let num_people = person_params.len();
+        let people: Vec<PersonSpec> = timer
+            .parallelize(
+                "create people living and working in new buildings",
+                person_params,
+                |(home, work, mut rng)| match create_prole(home, work, map, &mut rng) {
+                    Ok(person) => Some(person),
+                    Err(e) => {
+                        trace!("Unable to create person. error: {}", e);
+                        None
+                    }
+                },
+            )
+            .into_iter()
+            .flatten()
+            .collect();
+        info!(
+            "Created {} people for {} new buildings, skipping {}",
+            prettyprint_usize(people.len()),
+            prettyprint_usize(new_bldgs.len()),
+            prettyprint_usize(num_people - people.len())
+        );
+        people
+    }
+}
+
+fn residents_and_workers(bldg_type: &BuildingType) -> (usize, usize) {
+    match bldg_type {
+        BuildingType::Residential { num_residents, .. } => (*num_residents, 0),
+        BuildingType::ResidentialCommercial(residents, workers) => (*residents, *workers),
+        BuildingType::Commercial(workers) => (0, *workers),
+        BuildingType::Empty => (0, 0),
+    }
+}
+
+// Only consider two-way intersections, so the agent can return the same way they came.
+// TODO: instead, if it's not a two-way border, we should find an intersection an incoming border
+// "near" the outgoing border, to allow a broader set of realistic options.
+// TODO: prefer larger thoroughfares to better reflect reality.
+fn commuter_borders(map: &Map) -> Vec<TripEndpoint> {
+    map.all_outgoing_borders()
+        .into_iter()
+        .filter(|b| b.is_incoming_border())
+        .map(|b| TripEndpoint::Border(b.id))
+        .collect()
 }
 
 fn create_prole(
@@ -273,6 +356,7 @@ fn create_prole(
             IndividTrip::new(depart_am, TripPurpose::Work, home, work, mode),
             IndividTrip::new(depart_pm, TripPurpose::Home, work, home, mode),
         ],
+        demographics: None,
     })
 }
 
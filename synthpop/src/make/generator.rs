@@ -172,6 +172,7 @@ impl SpawnOverTime {
                 }),
                 mode,
             )],
+            demographics: None,
         });
     }
 }
@@ -190,6 +191,7 @@ impl BorderSpawnOverTime {
                 }),
                 mode,
             )],
+            demographics: None,
         });
     }
 }
@@ -10,7 +10,7 @@ use abstutil::Timer;
 use geom::{Duration, Time};
 use map_model::Map;
 
-use crate::{Scenario, TripMode};
+use crate::{OccupancyMix, ParkAndRide, PickUpDropOff, Scenario, TripMode, VehicleMix};
 
 /// Transforms an existing Scenario before instantiating it.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
@@ -29,6 +29,17 @@ pub enum ScenarioModifier {
     },
     /// Scenario name
     AddExtraTrips(String),
+    /// Replace the classes of vehicles used
+    SetVehicleMix(VehicleMix),
+    /// Replace the park-and-ride facilities
+    SetParkAndRide(ParkAndRide),
+    /// Replace which trips are taxis or drop-offs
+    SetPickUpDropOff(PickUpDropOff),
+    /// Sample how many people ride in each car
+    AssignOccupancy(OccupancyMix),
+    /// Household members making the same driving trip within this long of each other share one
+    /// car
+    FormCarpools(Duration),
 }
 
 impl ScenarioModifier {
@@ -96,6 +107,30 @@ impl ScenarioModifier {
                 }
                 s
             }
+            ScenarioModifier::SetVehicleMix(mix) => {
+                s.vehicle_mix = mix.clone();
+                s
+            }
+            ScenarioModifier::SetParkAndRide(config) => {
+                s.park_and_ride = config.clone();
+                s
+            }
+            ScenarioModifier::SetPickUpDropOff(config) => {
+                s.pick_up_drop_off = config.clone();
+                s
+            }
+            ScenarioModifier::AssignOccupancy(mix) => {
+                s.assign_occupancy(mix, rng);
+                s
+            }
+            ScenarioModifier::FormCarpools(max_departure_gap) => {
+                let n = s.form_household_carpools(*max_departure_gap);
+                info!(
+                    "{} trips now ride along with somebody in their household",
+                    n
+                );
+                s
+            }
         }
     }
 
@@ -124,6 +159,18 @@ impl ScenarioModifier {
                 to_mode.map(|m| m.verb())
             ),
             ScenarioModifier::AddExtraTrips(name) => format!("Add extra trips from {}", name),
+            ScenarioModifier::SetVehicleMix(mix) => {
+                format!("use a vehicle mix of {}", mix.describe())
+            }
+            ScenarioModifier::SetParkAndRide(config) => format!("use {}", config.describe()),
+            ScenarioModifier::SetPickUpDropOff(config) => format!("use {}", config.describe()),
+            ScenarioModifier::AssignOccupancy(mix) => {
+                format!("fill cars with {}", mix.describe())
+            }
+            ScenarioModifier::FormCarpools(max_departure_gap) => format!(
+                "share cars within a household when leaving within {} of each other",
+                max_departure_gap
+            ),
         }
     }
 }
@@ -143,13 +190,16 @@ fn repeat_days(
     rng: &mut XorShiftRng,
 ) -> Scenario {
     s.scenario_name = format!("{} (repeated {} days)", s.scenario_name, days);
+    // Passengers refer to the driver's trip by index, which shifts every day
+    let num_trips: Vec<usize> = s.people.iter().map(|p| p.trips.len()).collect();
     for person in &mut s.people {
         let mut trips = Vec::new();
         let mut offset = Duration::ZERO;
-        for _ in 0..days {
+        for day in 0..days {
             for trip in &person.trips {
                 let mut new = trip.clone();
                 new.depart += offset;
+                new.passenger_of = trip.passenger_of.map(|(p, t)| (p, day * num_trips[p] + t));
                 if let Some(noise_v) = noise {
                     // + or - noise_v
                     let noise_rnd = Duration::seconds(
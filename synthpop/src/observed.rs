@@ -0,0 +1,123 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::{prettyprint_usize, MultiMap, Timer};
+use geom::Speed;
+use map_model::{osm, Map, RoadID};
+
+/// Real speeds and volumes per road and hour of the day, from traffic counters, telematics, or
+/// similar sources. Unlike `TrafficCounts`, these are split by time of day, so they can be
+/// compared against a simulation as it runs.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ObservedTraffic {
+    pub map: MapName,
+    /// Where the data came from, what days it covers, etc
+    pub description: String,
+    /// Keyed by road and hour of the day, from 0 to 23
+    pub per_road: BTreeMap<(RoadID, usize), Observation>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Observation {
+    /// The average speed of vehicles
+    pub speed: Option<Speed>,
+    /// How many vehicles passed during the hour, in both directions
+    pub volume: Option<usize>,
+}
+
+/// One row of an external dataset, before it's matched to the map
+pub struct ExternalObservation {
+    pub osm_way_id: osm::WayID,
+    /// From 0 to 23
+    pub hour: usize,
+    pub speed: Option<Speed>,
+    pub volume: Option<usize>,
+}
+
+impl ObservedTraffic {
+    /// Matches observations to roads by OSM way ID. One way may be split into many roads, and each
+    /// of them gets the way's observations. Multiple observations for the same way and hour, like
+    /// from different days, are averaged. Observations for ways not in the map are skipped.
+    pub fn import(
+        map: &Map,
+        description: String,
+        input: Vec<ExternalObservation>,
+        timer: &mut Timer,
+    ) -> Result<ObservedTraffic> {
+        let mut roads_per_way: MultiMap<osm::WayID, RoadID> = MultiMap::new();
+        for r in map.all_roads() {
+            roads_per_way.insert(r.orig_id.osm_way_id, r.id);
+        }
+
+        // For each way and hour, the sum and number of speeds and volumes
+        let mut sums: HashMap<(osm::WayID, usize), (f64, usize, usize, usize)> = HashMap::new();
+        let mut skipped = 0;
+        let num_input = input.len();
+        timer.start_iter("match observations to roads", num_input);
+        for obs in input {
+            timer.next();
+            if obs.hour >= 24 {
+                bail!(
+                    "{} has an observation for hour {}",
+                    obs.osm_way_id,
+                    obs.hour
+                );
+            }
+            if roads_per_way.get(obs.osm_way_id).is_empty() {
+                skipped += 1;
+                continue;
+            }
+            let sum = sums
+                .entry((obs.osm_way_id, obs.hour))
+                .or_insert((0.0, 0, 0, 0));
+            if let Some(speed) = obs.speed {
+                sum.0 += speed.inner_meters_per_second();
+                sum.1 += 1;
+            }
+            if let Some(volume) = obs.volume {
+                sum.2 += volume;
+                sum.3 += 1;
+            }
+        }
+        info!(
+            "Skipped {} of {} observations for ways outside the map",
+            prettyprint_usize(skipped),
+            prettyprint_usize(num_input)
+        );
+
+        let mut per_road = BTreeMap::new();
+        for ((way, hour), (speed_sum, speed_n, volume_sum, volume_n)) in sums {
+            let obs = Observation {
+                speed: (speed_n > 0)
+                    .then(|| Speed::meters_per_second(speed_sum / (speed_n as f64))),
+                volume: (volume_n > 0).then(|| volume_sum / volume_n),
+            };
+            for r in roads_per_way.get(way) {
+                per_road.insert((*r, hour), obs);
+            }
+        }
+        if per_road.is_empty() {
+            bail!(
+                "None of the observations match roads in {}",
+                map.get_name().describe()
+            );
+        }
+
+        Ok(ObservedTraffic {
+            map: map.get_name().clone(),
+            description,
+            per_road,
+        })
+    }
+
+    pub fn save(&self) {
+        abstio::write_binary(abstio::path_observed_traffic(&self.map), self);
+    }
+
+    pub fn get(&self, r: RoadID, hour: usize) -> Option<Observation> {
+        self.per_road.get(&(r, hour)).cloned()
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use geom::Distance;
+use map_model::{BuildingID, Map, OffstreetParking};
+
+/// Public garages where drivers can leave their car and finish the trip by transit or bike. When a
+/// driving trip starts, the simulation picks a facility roughly on the way that still has free
+/// spots, and uses it if there's a way to continue from there. Otherwise the person drives all the
+/// way. On the way back, people return to their car the same way.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ParkAndRide {
+    /// These should be public garages
+    pub facilities: BTreeSet<BuildingID>,
+    /// Shorter trips, measured in a straight line, always drive the whole way
+    pub min_trip_distance: Distance,
+    /// How far out of the way a facility can be, as a percentage of the trip's straight-line
+    /// distance
+    pub max_detour_pct: usize,
+    /// Only people with a bike, going no more than this distance from the facility, can bike the
+    /// rest of the way
+    pub max_bike_distance: Distance,
+}
+
+impl Default for ParkAndRide {
+    fn default() -> ParkAndRide {
+        ParkAndRide {
+            facilities: BTreeSet::new(),
+            min_trip_distance: Distance::miles(3.0),
+            max_detour_pct: 30,
+            max_bike_distance: Distance::miles(2.0),
+        }
+    }
+}
+
+impl ParkAndRide {
+    pub fn is_empty(&self) -> bool {
+        self.facilities.is_empty()
+    }
+
+    /// All public garages in the map, which could be designated as facilities
+    pub fn candidates(map: &Map) -> Vec<BuildingID> {
+        map.all_buildings()
+            .iter()
+            .filter(|b| matches!(b.parking, OffstreetParking::PublicGarage(_, n) if n > 0))
+            .map(|b| b.id)
+            .collect()
+    }
+
+    /// How far out of the way driving to this facility is, compared to going straight there, or
+    /// `None` if the trip shouldn't consider it at all.
+    pub fn detour(
+        &self,
+        map: &Map,
+        from: BuildingID,
+        to: BuildingID,
+        via: BuildingID,
+    ) -> Option<f64> {
+        let start = map.get_b(from).polygon.center();
+        let end = map.get_b(to).polygon.center();
+        let facility = map.get_b(via).polygon.center();
+        let direct = start.dist_to(end);
+        if direct < self.min_trip_distance || via == from || via == to {
+            return None;
+        }
+        let detour = (start.dist_to(facility) + facility.dist_to(end) - direct) / direct;
+        if detour <= (self.max_detour_pct as f64) / 100.0 {
+            Some(detour)
+        } else {
+            None
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} park-and-ride facilities, for trips over {}",
+            self.facilities.len(),
+            self.min_trip_distance
+        )
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use geom::Duration;
+
+use crate::TripPurpose;
+
+/// Some driving trips aren't made in the person's own car. A taxi, ride-hailing vehicle, or a
+/// parent doing the school run picks them up at the door and drops them off at their destination.
+/// The vehicle stops briefly at the building frontage, in a pick-up/drop-off bay if there's a free
+/// one along the lane, or otherwise right in the lane, blocking traffic behind it. Then it leaves.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PickUpDropOff {
+    /// For each purpose, what percent of driving trips are dropped off. Missing purposes are never
+    /// dropped off.
+    pub pct_per_purpose: BTreeMap<TripPurpose, usize>,
+    /// How long the vehicle stops to let somebody in or out
+    pub dwell_time: Duration,
+}
+
+impl Default for PickUpDropOff {
+    /// Nobody is dropped off
+    fn default() -> PickUpDropOff {
+        PickUpDropOff {
+            pct_per_purpose: BTreeMap::new(),
+            dwell_time: Duration::seconds(45.0),
+        }
+    }
+}
+
+impl PickUpDropOff {
+    /// A rough guess for a city with ride-hailing and lots of school drop-offs
+    pub fn typical() -> PickUpDropOff {
+        let mut pct_per_purpose = BTreeMap::new();
+        pct_per_purpose.insert(TripPurpose::School, 40);
+        pct_per_purpose.insert(TripPurpose::Escort, 50);
+        pct_per_purpose.insert(TripPurpose::Medical, 20);
+        pct_per_purpose.insert(TripPurpose::Meal, 10);
+        pct_per_purpose.insert(TripPurpose::Social, 10);
+        pct_per_purpose.insert(TripPurpose::Work, 3);
+        PickUpDropOff {
+            pct_per_purpose,
+            ..PickUpDropOff::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pct_per_purpose.values().all(|pct| *pct == 0)
+    }
+
+    pub fn check(&self) -> Result<()> {
+        if self.pct_per_purpose.values().any(|pct| *pct > 100) {
+            bail!("Pick-up/drop-off percentages can't exceed 100");
+        }
+        if self.dwell_time <= Duration::ZERO {
+            bail!("The pick-up/drop-off dwell time must be positive");
+        }
+        Ok(())
+    }
+
+    /// Is this driving trip dropped off? `idx` identifies the trip; the choice is "stable" as
+    /// percentages increase, like `ScenarioModifier::ChangeMode`.
+    pub fn applies(&self, idx: usize, purpose: TripPurpose) -> bool {
+        match self.pct_per_purpose.get(&purpose) {
+            Some(pct) => idx % 100 < *pct,
+            None => false,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let purposes: Vec<String> = self
+            .pct_per_purpose
+            .iter()
+            .filter(|(_, pct)| **pct > 0)
+            .map(|(purpose, pct)| format!("{}% of {} trips", pct, purpose))
+            .collect();
+        if purposes.is_empty() {
+            return "no pick-ups or drop-offs".to_string();
+        }
+        format!(
+            "pick-up/drop-off for {}, stopping {}",
+            purposes.join(", "),
+            self.dwell_time
+        )
+    }
+}
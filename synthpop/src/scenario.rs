@@ -9,7 +9,10 @@ use abstutil::prettyprint_usize;
 use geom::Time;
 use map_model::Map;
 
-use crate::{OrigPersonID, TripEndpoint, TripMode};
+use crate::{
+    Demographics, OrigPersonID, ParkAndRide, PickUpDropOff, TripEndpoint, TripMode, ValueOfTime,
+    VehicleMix,
+};
 
 /// A Scenario describes all the input to a simulation. Usually a scenario covers one day.
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -20,6 +23,14 @@ pub struct Scenario {
     pub people: Vec<PersonSpec>,
     /// None means seed all buses. Otherwise the route name must be present here.
     pub only_seed_buses: Option<BTreeSet<String>>,
+    /// What kinds of vehicles people and bus routes use
+    pub vehicle_mix: VehicleMix,
+    /// Where drivers can switch to transit or a bike
+    pub park_and_ride: ParkAndRide,
+    /// How much drivers would pay to save time
+    pub value_of_time: ValueOfTime,
+    /// Which trips are taxis or drop-offs, stopping at the kerb instead of parking
+    pub pick_up_drop_off: PickUpDropOff,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -30,6 +41,8 @@ pub struct PersonSpec {
     /// trip. In the case of borders, the outbound and inbound border may be different. This means
     /// that there was some sort of "remote" trip happening outside the map that we don't simulate.
     pub trips: Vec<IndividTrip>,
+    /// Only some ways of generating a population know anything about people besides their trips
+    pub demographics: Option<Demographics>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -42,6 +55,13 @@ pub struct IndividTrip {
     pub cancelled: bool,
     /// Did a ScenarioModifier affect this?
     pub modified: bool,
+    /// For driving trips, how many people are in the car, including the driver and any
+    /// passengers in `passenger_of`
+    pub occupancy: usize,
+    /// Instead of making this trip on their own, this person rides along in somebody else's car.
+    /// The driving trip is identified by the driver's index in `Scenario::people` and the index
+    /// of the trip, so this breaks if people are removed or reordered.
+    pub passenger_of: Option<(usize, usize)>,
 }
 
 impl IndividTrip {
@@ -60,12 +80,14 @@ impl IndividTrip {
             purpose,
             cancelled: false,
             modified: false,
+            occupancy: 1,
+            passenger_of: None,
         }
     }
 }
 
 /// Lifted from Seattle's Soundcast model, but seems general enough to use anyhere.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TripPurpose {
     Home,
     Work,
@@ -78,6 +100,27 @@ pub enum TripPurpose {
     Recreation,
     Medical,
     ParkAndRideTransfer,
+    /// Delivery vehicles may use loading bays
+    Delivery,
+}
+
+impl TripPurpose {
+    pub fn all() -> Vec<TripPurpose> {
+        vec![
+            TripPurpose::Home,
+            TripPurpose::Work,
+            TripPurpose::School,
+            TripPurpose::Escort,
+            TripPurpose::PersonalBusiness,
+            TripPurpose::Shopping,
+            TripPurpose::Meal,
+            TripPurpose::Social,
+            TripPurpose::Recreation,
+            TripPurpose::Medical,
+            TripPurpose::ParkAndRideTransfer,
+            TripPurpose::Delivery,
+        ]
+    }
 }
 
 impl fmt::Display for TripPurpose {
@@ -98,6 +141,7 @@ impl fmt::Display for TripPurpose {
                 TripPurpose::Recreation => "recreation",
                 TripPurpose::Medical => "medical",
                 TripPurpose::ParkAndRideTransfer => "park-and-ride transfer",
+                TripPurpose::Delivery => "delivery",
             }
         )
     }
@@ -117,6 +161,10 @@ impl Scenario {
             map_name: map.get_name().clone(),
             people: Vec::new(),
             only_seed_buses: Some(BTreeSet::new()),
+            vehicle_mix: VehicleMix::default(),
+            park_and_ride: ParkAndRide::default(),
+            value_of_time: ValueOfTime::default(),
+            pick_up_drop_off: PickUpDropOff::default(),
         }
     }
 
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::Duration;
+
+use crate::{Demographics, IncomeBand};
+
+/// How much drivers would pay to save time, which decides whether they take a tolled road or pay
+/// for a priced lane. Each person gets their own value, sampled once.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ValueOfTime {
+    /// Cents per hour, with relative weights that don't need to sum to anything in particular
+    pub cents_per_hour: Vec<(u32, usize)>,
+}
+
+impl Default for ValueOfTime {
+    /// A rough spread for commuters, centered around half of a typical hourly wage
+    fn default() -> ValueOfTime {
+        ValueOfTime {
+            cents_per_hour: vec![(500, 1), (1000, 2), (1500, 3), (2500, 2), (4000, 1)],
+        }
+    }
+}
+
+impl ValueOfTime {
+    pub fn check(&self) -> Result<()> {
+        if self.cents_per_hour.iter().all(|(_, weight)| *weight == 0) {
+            bail!("The value of time distribution needs at least one positive weight");
+        }
+        Ok(())
+    }
+
+    /// When somebody's income is known, it shifts the sampled value.
+    pub fn sample(&self, demographics: Option<&Demographics>, rng: &mut XorShiftRng) -> u32 {
+        let total: usize = self.cents_per_hour.iter().map(|(_, weight)| *weight).sum();
+        let mut pick = rng.gen_range(0..total);
+        let mut cents = 0;
+        for (value, weight) in &self.cents_per_hour {
+            if pick < *weight {
+                cents = *value;
+                break;
+            }
+            pick -= *weight;
+        }
+        match demographics.and_then(|d| d.income) {
+            Some(IncomeBand::Low) => cents / 2,
+            Some(IncomeBand::High) => cents * 2,
+            Some(IncomeBand::Middle) | None => cents,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let total: usize = self.cents_per_hour.iter().map(|(_, weight)| *weight).sum();
+        let mean = self
+            .cents_per_hour
+            .iter()
+            .map(|(value, weight)| (*value as usize) * *weight)
+            .sum::<usize>()
+            / total.max(1);
+        format!(
+            "drivers value their time at ${:.2}/hour on average",
+            (mean as f64) / 100.0
+        )
+    }
+}
+
+/// How much time is a price worth to somebody?
+pub fn price_as_time(cents: u32, cents_per_hour: u32) -> Duration {
+    if cents == 0 {
+        return Duration::ZERO;
+    }
+    // Somebody who doesn't value their time at all won't pay anything
+    if cents_per_hour == 0 {
+        return Duration::hours(24);
+    }
+    Duration::seconds(3600.0 * (cents as f64) / (cents_per_hour as f64))
+}
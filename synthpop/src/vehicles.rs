@@ -0,0 +1,176 @@
+use std::fmt;
+
+use anyhow::Result;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use map_model::PathConstraints;
+
+/// A more specific kind of vehicle than a trip mode. The simulation gives each class its own
+/// length and performance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum VehicleClass {
+    SmallCar,
+    Car,
+    Van,
+    /// A heavy goods vehicle
+    Hgv,
+    Bus,
+    ArticulatedBus,
+    Train,
+    Bike,
+    CargoBike,
+}
+
+impl VehicleClass {
+    pub fn all() -> Vec<VehicleClass> {
+        vec![
+            VehicleClass::SmallCar,
+            VehicleClass::Car,
+            VehicleClass::Van,
+            VehicleClass::Hgv,
+            VehicleClass::Bus,
+            VehicleClass::ArticulatedBus,
+            VehicleClass::Train,
+            VehicleClass::Bike,
+            VehicleClass::CargoBike,
+        ]
+    }
+
+    /// Which lanes can this class use? Vans and HGVs drive like cars, articulated buses use bus
+    /// lanes, and cargo bikes use bike lanes.
+    pub fn to_constraints(self) -> PathConstraints {
+        match self {
+            VehicleClass::SmallCar | VehicleClass::Car | VehicleClass::Van | VehicleClass::Hgv => {
+                PathConstraints::Car
+            }
+            VehicleClass::Bus | VehicleClass::ArticulatedBus => PathConstraints::Bus,
+            VehicleClass::Train => PathConstraints::Train,
+            VehicleClass::Bike | VehicleClass::CargoBike => PathConstraints::Bike,
+        }
+    }
+
+    /// How many passengers fit in a transit vehicle, counting people standing. None for vehicles
+    /// that don't carry passengers.
+    pub fn passenger_capacity(self) -> Option<usize> {
+        match self {
+            VehicleClass::Bus => Some(70),
+            VehicleClass::ArticulatedBus => Some(110),
+            VehicleClass::Train => Some(250),
+            VehicleClass::SmallCar
+            | VehicleClass::Car
+            | VehicleClass::Van
+            | VehicleClass::Hgv
+            | VehicleClass::Bike
+            | VehicleClass::CargoBike => None,
+        }
+    }
+}
+
+impl fmt::Display for VehicleClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                VehicleClass::SmallCar => "small car",
+                VehicleClass::Car => "car",
+                VehicleClass::Van => "van",
+                VehicleClass::Hgv => "HGV",
+                VehicleClass::Bus => "bus",
+                VehicleClass::ArticulatedBus => "articulated bus",
+                VehicleClass::Train => "train",
+                VehicleClass::Bike => "bike",
+                VehicleClass::CargoBike => "cargo bike",
+            }
+        )
+    }
+}
+
+/// How often each class of vehicle appears. Each list has relative weights, which don't need to
+/// sum to anything in particular.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct VehicleMix {
+    /// Sampled whenever somebody needs a new vehicle for a driving trip
+    pub driving: Vec<(VehicleClass, usize)>,
+    /// Sampled whenever somebody needs a bike
+    pub biking: Vec<(VehicleClass, usize)>,
+    /// Sampled once per bus route
+    pub buses: Vec<(VehicleClass, usize)>,
+}
+
+impl Default for VehicleMix {
+    /// Like the simulation has always behaved: only regular cars, bikes, and buses
+    fn default() -> VehicleMix {
+        VehicleMix {
+            driving: vec![(VehicleClass::Car, 1)],
+            biking: vec![(VehicleClass::Bike, 1)],
+            buses: vec![(VehicleClass::Bus, 1)],
+        }
+    }
+}
+
+impl VehicleMix {
+    /// Make sure every class is used for the right kind of trip, and every list has something to
+    /// pick.
+    pub fn check(&self) -> Result<()> {
+        for (name, list, constraints) in [
+            ("driving", &self.driving, PathConstraints::Car),
+            ("biking", &self.biking, PathConstraints::Bike),
+            ("buses", &self.buses, PathConstraints::Bus),
+        ] {
+            if list.iter().all(|(_, weight)| *weight == 0) {
+                bail!(
+                    "The vehicle mix for {} needs at least one positive weight",
+                    name
+                );
+            }
+            for (class, _) in list {
+                if class.to_constraints() != constraints {
+                    bail!("A {} can't be used for {}", class, name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn sample_driving(&self, rng: &mut XorShiftRng) -> VehicleClass {
+        sample(&self.driving, rng)
+    }
+
+    pub fn sample_biking(&self, rng: &mut XorShiftRng) -> VehicleClass {
+        sample(&self.biking, rng)
+    }
+
+    pub fn sample_bus(&self, rng: &mut XorShiftRng) -> VehicleClass {
+        sample(&self.buses, rng)
+    }
+
+    pub fn describe(&self) -> String {
+        [&self.driving, &self.biking, &self.buses]
+            .into_iter()
+            .flatten()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(class, weight)| format!("{} {}", weight, class))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn sample(choices: &[(VehicleClass, usize)], rng: &mut XorShiftRng) -> VehicleClass {
+    // Don't touch the RNG when there's no choice to make, so scenarios without a custom mix
+    // produce the same simulation as before classes existed
+    if choices.len() == 1 {
+        return choices[0].0;
+    }
+    let total: usize = choices.iter().map(|(_, weight)| *weight).sum();
+    let mut pick = rng.gen_range(0..total);
+    for (class, weight) in choices {
+        if pick < *weight {
+            return *class;
+        }
+        pick -= *weight;
+    }
+    unreachable!()
+}
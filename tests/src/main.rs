@@ -27,6 +27,7 @@ fn main() -> Result<()> {
         "../tests/input/lane_selection.osm",
     )))?;
     test_map_importer()?;
+    sim_regression_test()?;
     check_proposals()?;
     if false {
         ab_test_spurious_diff()?;
@@ -159,6 +160,93 @@ fn smoke_test() -> Result<()> {
     Ok(())
 }
 
+/// Simulate small scenarios on the handcrafted test maps, recording every event that happens, and
+/// compare against goldenfiles. The simulation is meant to be deterministic across runs and
+/// platforms, so any diff means a change in behavior. If that's intended, regenerate the
+/// goldenfiles and check them in. Missing goldenfiles are created on the first run.
+fn sim_regression_test() -> Result<()> {
+    let regenerate_goldenfiles = false;
+
+    for name in [
+        "lane_selection",
+        "left_turn_and_bike_lane",
+        "multiple_left_turn_lanes",
+    ] {
+        let map = import_map(abstio::path(format!("../tests/input/{}.osm", name)));
+        let scenario = regression_scenario(&map);
+
+        let events = record_sim_events(&map, &scenario);
+        // Catch nondeterminism on one platform before comparing with results from others
+        if events != record_sim_events(&map, &scenario) {
+            bail!("Simulating {} twice produced different events", name);
+        }
+        let events = events.join("\n");
+
+        let path = abstio::path(format!("../tests/goldenfiles/sim_events/{}.txt", name));
+        if regenerate_goldenfiles || !abstio::file_exists(&path) {
+            println!("Producing sim event goldenfile for {}", name);
+            fs_err::create_dir_all(std::path::Path::new(&path).parent().unwrap())?;
+            let mut f = File::create(&path)?;
+            writeln!(f, "{}", events)?;
+            continue;
+        }
+        compare_with_goldenfile(events, path)?;
+    }
+
+    if regenerate_goldenfiles {
+        panic!("Automatically fail when the goldenfiles are regenerated, so this isn't accidentally left on")
+    }
+    Ok(())
+}
+
+/// Between every pair of borders, somebody drives, somebody bikes, and somebody walks. Trips
+/// start a few seconds apart, so they interact a bit.
+fn regression_scenario(map: &Map) -> Scenario {
+    let borders: Vec<IntersectionID> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.is_border())
+        .map(|i| i.id)
+        .collect();
+
+    let mut scenario = Scenario::empty(map, "regression");
+    for from in &borders {
+        for to in &borders {
+            if from == to {
+                continue;
+            }
+            for mode in [TripMode::Drive, TripMode::Bike, TripMode::Walk] {
+                let depart =
+                    Time::START_OF_DAY + Duration::seconds(3.0 * scenario.people.len() as f64);
+                scenario.people.push(PersonSpec {
+                    orig_id: None,
+                    trips: vec![IndividTrip::new(
+                        depart,
+                        TripPurpose::Shopping,
+                        TripEndpoint::Border(*from),
+                        TripEndpoint::Border(*to),
+                        mode,
+                    )],
+                    demographics: None,
+                });
+            }
+        }
+    }
+    scenario
+}
+
+fn record_sim_events(map: &Map, scenario: &Scenario) -> Vec<String> {
+    let mut opts = SimOptions::new("sim_regression_test");
+    opts.alerts = AlertHandler::Silence;
+    let mut sim = Sim::new(map, opts);
+    let mut rng = SimFlags::for_test("sim_regression_test").make_rng();
+    sim.record_events();
+    sim.instantiate(scenario, map, &mut rng, &mut Timer::throwaway());
+    // Everything should finish long before this
+    sim.timed_step(map, Duration::hours(2), &mut None, &mut Timer::throwaway());
+    sim.take_recorded_events()
+}
+
 /// Verify all edits under version control can be correctly apply to their map.
 fn check_proposals() -> Result<()> {
     let mut timer = Timer::new("check all proposals");
@@ -228,6 +316,7 @@ fn test_lane_changing(map: &Map) -> Result<()> {
                     TripMode::Bike
                 },
             )],
+            demographics: None,
         });
     }
     // Enable to manually watch the scenario
@@ -474,6 +563,7 @@ mod tests {
     use super::geometry_test;
     use super::import_map;
     use super::main;
+    use super::sim_regression_test;
     use super::test_blockfinding;
     use super::test_lane_changing;
     use super::test_map_importer;
@@ -490,6 +580,11 @@ mod tests {
         test_map_importer()
     }
 
+    #[test]
+    fn run_sim_regression_test() -> Result<(), anyhow::Error> {
+        sim_regression_test()
+    }
+
     #[test]
     #[ignore]
     fn run_geometry_test() -> Result<(), anyhow::Error> {
@@ -7,7 +7,7 @@
 
 use abstutil::CloneableAny;
 
-use crate::{Canvas, Color, EventCtx, GfxCtx, Outcome, Panel};
+use crate::{Canvas, Color, EventCtx, FrameStats, GfxCtx, Outcome, Panel};
 
 /// Any data that should last the entire lifetime of the application should be stored in the struct
 /// implementing this trait.
@@ -16,6 +16,11 @@ pub trait SharedAppState {
     fn before_event(&mut self) {}
     /// When DrawBaselayer::DefaultDraw is called, run this.
     fn draw_default(&self, _: &mut GfxCtx) {}
+    /// After the current state draws, run this. Useful for debug overlays that should appear no
+    /// matter what state is active.
+    fn draw_overlay(&self, _: &mut GfxCtx) {}
+    /// After every frame is drawn, this is called with timings for that frame.
+    fn frame_finished(&mut self, _: FrameStats) {}
 
     /// Will be called if `State::event` or `State::draw` panics.
     fn dump_before_abort(&self, _: &Canvas) {}
@@ -84,6 +89,7 @@ impl<A: 'static + SharedAppState> App<A> {
             }
         }
         state.draw(g, &self.shared_app_state);
+        self.shared_app_state.draw_overlay(g);
     }
 
     /// If true, then the top-most state on the stack needs to be "woken up" with a fake mouseover
@@ -4,7 +4,7 @@ use std::rc::Rc;
 use glow::HasContext;
 
 use crate::drawing::Uniforms;
-use crate::{Canvas, Color, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenRectangle};
+use crate::{Canvas, Color, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt, ScreenRectangle};
 
 #[cfg(feature = "native-backend")]
 pub use crate::backend_glow_native::setup;
@@ -400,6 +400,13 @@ impl PrerenderInnards {
         self.window().set_cursor_visible(visible);
     }
 
+    /// Not supported on every platform, so this may do nothing
+    pub fn set_cursor_position(&self, pt: ScreenPt, scale_factor: f64) {
+        let _ = self.window().set_cursor_position(
+            winit::dpi::LogicalPosition::new(pt.x, pt.y).to_physical::<f64>(scale_factor),
+        );
+    }
+
     pub fn draw_new_frame(&self) -> GfxCtxInnards {
         GfxCtxInnards::new(&self.gl, &self.program)
     }
@@ -140,6 +140,15 @@ impl Canvas {
                 }
             }
 
+            // Touch gestures and gamepads work the same regardless of touchpad_to_move
+            if let Some((dx, dy)) = input.get_pan() {
+                self.cam_x -= dx;
+                self.cam_y -= dy;
+            }
+            if let Some((factor, focus)) = input.get_pinch() {
+                self.set_zoom(self.cam_zoom * factor, focus);
+            }
+
             if self.settings.keys_to_pan {
                 if input.pressed(Key::LeftArrow) {
                     self.cam_x -= PAN_SPEED;
@@ -219,12 +228,18 @@ impl Canvas {
     }
 
     pub fn zoom(&mut self, delta: f64, focus: ScreenPt) {
+        self.set_zoom(
+            1.1_f64.powf(
+                self.cam_zoom.log(1.1) + delta * (self.settings.canvas_scroll_speed as f64 / 10.0),
+            ),
+            focus,
+        );
+    }
+
+    fn set_zoom(&mut self, new_zoom: f64, focus: ScreenPt) {
         let old_zoom = self.cam_zoom;
         // By popular request, some limits ;)
-        self.cam_zoom = 1.1_f64
-            .powf(old_zoom.log(1.1) + delta * (self.settings.canvas_scroll_speed as f64 / 10.0))
-            .max(self.min_zoom())
-            .min(self.max_zoom());
+        self.cam_zoom = new_zoom.max(self.min_zoom()).min(self.max_zoom());
 
         // Make screen_to_map of the focus point still point to the same thing after
         // zooming.
@@ -21,6 +21,10 @@ pub enum UpdateType {
         zoom: f64,
         dims: ScreenDims,
     },
+    /// Save exactly what's on the screen right now as a PNG file
+    ScreenCaptureCurrentShot {
+        filename: String,
+    },
 }
 
 pub struct EventCtx<'a> {
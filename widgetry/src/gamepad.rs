@@ -0,0 +1,145 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use crate::{Event, Key, ScreenDims, ScreenPt};
+
+// In logical pixels per update, with the stick pushed all the way
+const CURSOR_SPEED: f64 = 15.0;
+const PAN_SPEED: f64 = 15.0;
+// How much to zoom per update, with a trigger pulled all the way
+const ZOOM_SPEED: f64 = 0.05;
+// Sticks rarely rest exactly at 0
+const DEADZONE: f32 = 0.15;
+
+/// Lets a gamepad act like a mouse and a few keys. The left stick moves the cursor, the right
+/// stick pans, and the triggers zoom. The bottom face button is the left mouse button, the left
+/// one is the right mouse button, the right one is Escape, and the top one is Space. The D-pad
+/// acts like the arrow keys.
+pub(crate) struct Gamepads {
+    // None if the platform doesn't support gamepads
+    gilrs: Option<Gilrs>,
+}
+
+impl Gamepads {
+    pub fn new() -> Gamepads {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) => {
+                warn!("Gamepads won't work: {}", err);
+                None
+            }
+        };
+        Gamepads { gilrs }
+    }
+
+    /// Sticks are held down continuously, so while a gamepad is connected, the event loop has to
+    /// keep polling.
+    pub fn any_connected(&self) -> bool {
+        self.gilrs
+            .as_ref()
+            .map(|gilrs| gilrs.gamepads().next().is_some())
+            .unwrap_or(false)
+    }
+
+    pub fn poll(&mut self, cursor: ScreenPt, window: ScreenDims) -> Vec<Event> {
+        let gilrs = match self.gilrs {
+            Some(ref mut gilrs) => gilrs,
+            None => {
+                return Vec::new();
+            }
+        };
+
+        let mut events = Vec::new();
+        while let Some(ev) = gilrs.next_event() {
+            match ev.event {
+                EventType::ButtonPressed(button, _) => {
+                    events.extend(button_event(button, true));
+                }
+                EventType::ButtonReleased(button, _) => {
+                    events.extend(button_event(button, false));
+                }
+                _ => {}
+            }
+        }
+
+        let mut cursor_dx = 0.0;
+        let mut cursor_dy = 0.0;
+        let mut pan_dx = 0.0;
+        let mut pan_dy = 0.0;
+        let mut zoom = 0.0;
+        for (_, gamepad) in gilrs.gamepads() {
+            // Up is positive for the sticks, but down is positive on the screen
+            cursor_dx += deadzone(gamepad.value(Axis::LeftStickX));
+            cursor_dy -= deadzone(gamepad.value(Axis::LeftStickY));
+            pan_dx += deadzone(gamepad.value(Axis::RightStickX));
+            pan_dy -= deadzone(gamepad.value(Axis::RightStickY));
+            let trigger = |button| {
+                gamepad
+                    .button_data(button)
+                    .map(|data| f64::from(data.value()))
+                    .unwrap_or(0.0)
+            };
+            zoom += trigger(Button::RightTrigger2) - trigger(Button::LeftTrigger2);
+        }
+
+        if cursor_dx != 0.0 || cursor_dy != 0.0 {
+            events.push(Event::MouseMovedTo(ScreenPt::new(
+                (cursor.x + cursor_dx * CURSOR_SPEED).clamp(0.0, window.width),
+                (cursor.y + cursor_dy * CURSOR_SPEED).clamp(0.0, window.height),
+            )));
+        }
+        if pan_dx != 0.0 || pan_dy != 0.0 {
+            // Pushing the stick right looks further right, so everything moves left
+            events.push(Event::Pan(-pan_dx * PAN_SPEED, -pan_dy * PAN_SPEED));
+        }
+        if zoom.abs() > f64::from(DEADZONE) {
+            events.push(Event::Pinch(
+                1.0 + zoom * ZOOM_SPEED,
+                ScreenPt::new(window.width / 2.0, window.height / 2.0),
+            ));
+        }
+        events
+    }
+}
+
+fn deadzone(value: f32) -> f64 {
+    if value.abs() < DEADZONE {
+        0.0
+    } else {
+        f64::from(value)
+    }
+}
+
+fn button_event(button: Button, pressed: bool) -> Option<Event> {
+    let key = match button {
+        Button::South => {
+            return Some(if pressed {
+                Event::LeftMouseButtonDown
+            } else {
+                Event::LeftMouseButtonUp {
+                    is_double_click: false,
+                }
+            });
+        }
+        Button::West => {
+            return Some(if pressed {
+                Event::RightMouseButtonDown
+            } else {
+                Event::RightMouseButtonUp
+            });
+        }
+        Button::East => Key::Escape,
+        Button::North => Key::Space,
+        Button::DPadLeft => Key::LeftArrow,
+        Button::DPadRight => Key::RightArrow,
+        Button::DPadUp => Key::UpArrow,
+        Button::DPadDown => Key::DownArrow,
+        _ => {
+            return None;
+        }
+    };
+    Some(if pressed {
+        Event::KeyPress(key)
+    } else {
+        Event::KeyRelease(key)
+    })
+}
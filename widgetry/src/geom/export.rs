@@ -0,0 +1,125 @@
+//! Export a `GeomBatch` to images outside the app, like posters for printing.
+
+use anyhow::Result;
+use geom::{Bounds, Pt2D, Triangle};
+
+use crate::{Color, Fill, GeomBatch, ScreenDims};
+
+impl GeomBatch {
+    /// Renders the batch to an SVG document. The batch should already be in pixel coordinates,
+    /// with the origin at the top-left of the image. Only solid colors are exported; gradients
+    /// and textures are skipped.
+    pub fn to_svg(&self, dims: ScreenDims) -> String {
+        let mut svg = format!(
+            r#"<svg width="{0}" height="{1}" viewBox="0 0 {0} {1}" "#,
+            dims.width.ceil(),
+            dims.height.ceil()
+        );
+        svg.push_str("xmlns=\"http://www.w3.org/2000/svg\">\n");
+        for (color, triangles) in self.solid_layers(dims) {
+            // Merging all triangles of one color into one path avoids thin seams between them
+            let mut path = String::new();
+            for t in triangles {
+                path.push_str(&format!(
+                    "M{:.2} {:.2}L{:.2} {:.2}L{:.2} {:.2}Z",
+                    t.pt1.x(),
+                    t.pt1.y(),
+                    t.pt2.x(),
+                    t.pt2.y(),
+                    t.pt3.x(),
+                    t.pt3.y()
+                ));
+            }
+            svg.push_str(&format!(
+                r#"<path fill="{}" fill-opacity="{:.3}" d="{}"/>"#,
+                color.as_hex(),
+                color.a,
+                path
+            ));
+            svg.push('\n');
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Rasterizes the batch to a PNG. Like `to_svg`, the batch should be in pixel coordinates,
+    /// and only solid colors are drawn.
+    pub fn to_png(&self, dims: ScreenDims) -> Result<Vec<u8>> {
+        let mut pixmap =
+            tiny_skia::Pixmap::new(dims.width.ceil() as u32, dims.height.ceil() as u32)
+                .ok_or_else(|| anyhow!("Can't make an image of size {:?}", dims))?;
+        for (color, triangles) in self.solid_layers(dims) {
+            let mut pb = tiny_skia::PathBuilder::new();
+            for t in triangles {
+                pb.move_to(t.pt1.x() as f32, t.pt1.y() as f32);
+                pb.line_to(t.pt2.x() as f32, t.pt2.y() as f32);
+                pb.line_to(t.pt3.x() as f32, t.pt3.y() as f32);
+                pb.close();
+            }
+            let path = match pb.finish() {
+                Some(path) => path,
+                None => continue,
+            };
+            let mut paint = tiny_skia::Paint::default();
+            paint.set_color(
+                tiny_skia::Color::from_rgba(color.r, color.g, color.b, color.a)
+                    .unwrap_or(tiny_skia::Color::BLACK),
+            );
+            paint.anti_alias = true;
+            pixmap.fill_path(
+                &path,
+                &paint,
+                tiny_skia::FillRule::Winding,
+                tiny_skia::Transform::identity(),
+                None,
+            );
+        }
+        Ok(pixmap.encode_png()?)
+    }
+
+    /// Groups consecutive triangles with the same color, from bottom to top. Triangles entirely
+    /// outside the image are skipped.
+    fn solid_layers(&self, dims: ScreenDims) -> Vec<(Color, Vec<Triangle>)> {
+        let mut image_bounds = Bounds::new();
+        image_bounds.update(Pt2D::new(0.0, 0.0));
+        image_bounds.update(Pt2D::new(dims.width, dims.height));
+
+        // Lower z-values render on top. Keep the original order otherwise.
+        let mut list: Vec<&(Fill, geom::Tessellation, f64)> = self.list.iter().collect();
+        list.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
+
+        let mut layers: Vec<(Color, Vec<Triangle>)> = Vec::new();
+        for (fill, tessellation, _) in list {
+            let color = match fill {
+                Fill::Color(color) => *color,
+                _ => continue,
+            };
+            let triangles: Vec<Triangle> = tessellation
+                .triangles()
+                .into_iter()
+                .filter(|t| {
+                    let mut b = Bounds::new();
+                    b.update(t.pt1);
+                    b.update(t.pt2);
+                    b.update(t.pt3);
+                    b.max_x >= image_bounds.min_x
+                        && b.min_x <= image_bounds.max_x
+                        && b.max_y >= image_bounds.min_y
+                        && b.min_y <= image_bounds.max_y
+                })
+                .collect();
+            if triangles.is_empty() {
+                continue;
+            }
+            match layers.last_mut() {
+                Some((last_color, last)) if *last_color == color => {
+                    last.extend(triangles);
+                }
+                _ => {
+                    layers.push((color, triangles));
+                }
+            }
+        }
+        layers
+    }
+}
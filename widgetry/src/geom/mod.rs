@@ -5,6 +5,7 @@ use crate::{
     Widget,
 };
 
+mod export;
 pub mod geom_batch_stack;
 
 /// A mutable builder for a group of colored tessellated polygons.
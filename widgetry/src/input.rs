@@ -105,6 +105,20 @@ impl UserInput {
         None
     }
 
+    pub fn get_pinch(&self) -> Option<(f64, ScreenPt)> {
+        if let Event::Pinch(factor, focus) = self.event {
+            return Some((factor, focus));
+        }
+        None
+    }
+
+    pub fn get_pan(&self) -> Option<(f64, f64)> {
+        if let Event::Pan(dx, dy) = self.event {
+            return Some((dx, dy));
+        }
+        None
+    }
+
     pub fn is_window_resized(&self) -> bool {
         matches!(self.event, Event::WindowResized(_))
     }
@@ -21,7 +21,8 @@
 //! * [`Slider`] - horizontal and vertical sliders
 //! * [`Spinner`] - numeric input with up/down buttons
 //! * [`table::Table`] - rows and columns, supporting filtering and pagination
-//! * [`TextBox`] - single line text entry
+//! * [`TextArea`] - multi-line text entry
+//! * [`TextBox`] - single line text entry, optionally validated
 
 //#![warn(missing_docs)]
 #![allow(clippy::too_many_arguments, clippy::type_complexity)]
@@ -44,7 +45,7 @@ pub use crate::geom::geom_batch_stack::{
 };
 pub use crate::geom::{GeomBatch, RewriteColor};
 pub use crate::input::UserInput;
-pub use crate::runner::{run, Settings};
+pub use crate::runner::{run, FrameStats, Settings};
 pub use crate::screen_geom::{ScreenDims, ScreenPt, ScreenRectangle};
 pub use crate::style::{ButtonStyle, OutlineStyle, Style};
 pub use crate::text::{Font, Line, Text, TextExt, TextSpan};
@@ -71,7 +72,8 @@ pub use crate::widgets::spinner::{RoundedF64, Spinner};
 pub use crate::widgets::stash::Stash;
 pub use crate::widgets::table;
 pub use crate::widgets::tabs::TabController;
-pub use crate::widgets::text_box::TextBox;
+pub use crate::widgets::text_area::TextArea;
+pub use crate::widgets::text_box::{TextBox, Validator};
 pub use crate::widgets::toggle::Toggle;
 pub use crate::widgets::DEFAULT_CORNER_RADIUS;
 pub use crate::widgets::{
@@ -92,6 +94,8 @@ mod color;
 mod drawing;
 mod event;
 mod event_ctx;
+#[cfg(feature = "gamepad")]
+mod gamepad;
 mod geom;
 mod input;
 pub mod mapspace;
@@ -101,6 +105,7 @@ mod style;
 mod svg;
 mod text;
 pub mod tools;
+mod touch;
 mod widgets;
 
 mod backend {
@@ -11,6 +11,7 @@ use geom::Duration;
 use crate::app_state::App;
 use crate::assets::Assets;
 use crate::tools::screenshot::screenshot_everything;
+use crate::touch::TouchState;
 use crate::{
     Canvas, CanvasSettings, Event, EventCtx, GfxCtx, Prerender, SharedAppState, Style, Text,
     UpdateType, UserInput,
@@ -27,6 +28,39 @@ pub(crate) struct State<A: SharedAppState> {
     style: Style,
 
     focus_owned_by: Option<String>,
+    /// Timings accumulated since the last frame was drawn
+    frame: FrameStats,
+}
+
+/// How long one frame took, and how much drawing work it needed. Passed to
+/// `SharedAppState::frame_finished` after every frame.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameStats {
+    /// The number of events handled since the previous frame
+    pub num_events: usize,
+    /// The time spent handling those events
+    pub event_time: Duration,
+    pub draw_time: Duration,
+    /// Uploads to the GPU since the previous frame, from both events and drawing
+    pub num_uploads: usize,
+    pub num_draw_calls: usize,
+    pub num_forks: usize,
+    /// Everything uploaded to the GPU so far, including objects since freed
+    pub total_bytes_uploaded: usize,
+}
+
+impl FrameStats {
+    fn new() -> FrameStats {
+        FrameStats {
+            num_events: 0,
+            event_time: Duration::ZERO,
+            draw_time: Duration::ZERO,
+            num_uploads: 0,
+            num_draw_calls: 0,
+            num_forks: 0,
+            total_bytes_uploaded: 0,
+        }
+    }
 }
 
 impl<A: 'static + SharedAppState> State<A> {
@@ -119,6 +153,8 @@ impl<A: 'static + SharedAppState> State<A> {
             let started = Instant::now();
             self.app.event(&mut ctx);
             self.focus_owned_by = ctx.next_focus_owned_by.take();
+            self.frame.num_events += 1;
+            self.frame.event_time += Duration::realtime_elapsed(started);
             if DEBUG_PERFORMANCE {
                 println!("- event() took {}s", elapsed_seconds(started));
             }
@@ -169,6 +205,12 @@ impl<A: 'static + SharedAppState> State<A> {
         }
         let naming_hint = g.naming_hint.take();
 
+        self.frame.draw_time = Duration::realtime_elapsed(started);
+        self.frame.num_uploads = g.get_num_uploads();
+        self.frame.num_draw_calls = g.num_draw_calls;
+        self.frame.num_forks = g.num_forks;
+        self.frame.total_bytes_uploaded = prerender.get_total_bytes_uploaded();
+
         if DEBUG_PERFORMANCE {
             println!(
                 "----- {} uploads, {} draw calls, {} forks. draw() took {} -----",
@@ -180,6 +222,10 @@ impl<A: 'static + SharedAppState> State<A> {
         }
 
         prerender.inner.draw_finished(g.inner);
+
+        let frame = std::mem::replace(&mut self.frame, FrameStats::new());
+        self.app.shared_app_state.frame_finished(frame);
+
         naming_hint
     }
 
@@ -398,6 +444,7 @@ pub fn run<
         canvas,
         style,
         focus_owned_by: None,
+        frame: FrameStats::new(),
     };
 
     let dump_raw_events = settings.dump_raw_events;
@@ -409,11 +456,15 @@ pub fn run<
 
     // Remember the last keycode, so that we can suppress a sequence like Alt+Tab
     let mut previous_keycode = None;
+    let mut touches = TouchState::default();
+    #[cfg(feature = "gamepad")]
+    let mut gamepads = crate::gamepad::Gamepads::new();
     event_loop.run(move |event, _, control_flow| {
         if dump_raw_events {
             debug!("Event: {:?}", event);
         }
-        let ev = match event {
+        // One touch or gamepad event can turn into a few events
+        let events = match event {
             winit::event::Event::WindowEvent {
                 event: winit::event::WindowEvent::CloseRequested,
                 ..
@@ -442,10 +493,12 @@ pub fn run<
                 }
 
                 let scale_factor = prerender.get_scale_factor();
-                if let Some(ev) =
+                if let winit::event::WindowEvent::Touch(touch) = event {
+                    touches.handle(touch, scale_factor, previous_left_click_at)
+                } else if let Some(ev) =
                     Event::from_winit_event(event, scale_factor, previous_left_click_at)
                 {
-                    ev
+                    vec![ev]
                 } else {
                     // Don't touch control_flow if we got an irrelevant event
                     return;
@@ -457,69 +510,111 @@ pub fn run<
                 return;
             }
             winit::event::Event::MainEventsCleared => {
+                #[allow(unused_mut)]
+                let mut events = touches.check_long_press();
+                #[cfg(feature = "gamepad")]
+                {
+                    let gamepad_events =
+                        gamepads.poll(state.canvas.get_cursor(), prerender.window_size());
+                    for ev in &gamepad_events {
+                        if let Event::MouseMovedTo(pt) = ev {
+                            prerender
+                                .inner
+                                .set_cursor_position(*pt, prerender.get_scale_factor());
+                        }
+                    }
+                    events.extend(gamepad_events);
+                }
                 // We might've switched to InputOnly after the WaitUntil was requested.
                 if running {
-                    Event::Update(Duration::realtime_elapsed(last_update))
-                } else {
-                    return;
+                    events.push(Event::Update(Duration::realtime_elapsed(last_update)));
                 }
+                events
             }
             _ => {
                 return;
             }
         };
+        if events.is_empty() {
+            return;
+        }
 
-        // We want a max of UPDATE_FREQUENCY between updates, so measure the update time before
-        // doing the work (which takes time).
-        match ev {
-            Event::Update(_) => {
-                last_update = Instant::now();
-                *control_flow =
-                    winit::event_loop::ControlFlow::WaitUntil(Instant::now() + UPDATE_FREQUENCY);
-            }
-            Event::LeftMouseButtonUp {
-                is_double_click: false,
-            } => {
-                previous_left_click_at = Instant::now();
+        for ev in events {
+            // We want a max of UPDATE_FREQUENCY between updates, so measure the update time before
+            // doing the work (which takes time).
+            match ev {
+                Event::Update(_) => {
+                    last_update = Instant::now();
+                    *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                        Instant::now() + UPDATE_FREQUENCY,
+                    );
+                }
+                Event::LeftMouseButtonUp {
+                    is_double_click: false,
+                } => {
+                    previous_left_click_at = Instant::now();
+                }
+                _ => {}
             }
-            _ => {}
-        }
 
-        let (mut updates, input_used) = state.event(ev, &prerender);
+            let (mut updates, input_used) = state.event(ev, &prerender);
 
-        if input_used {
-            prerender.request_redraw();
-        }
+            if input_used {
+                prerender.request_redraw();
+            }
 
-        if updates.is_empty() {
-            updates.push(UpdateType::InputOnly);
-        }
-        for update in updates {
-            match update {
-                UpdateType::InputOnly => {
-                    running = false;
-                    *control_flow = winit::event_loop::ControlFlow::Wait;
-                }
-                UpdateType::Game => {
-                    // If we just unpaused, then don't act as if lots of time has passed.
-                    if !running {
-                        last_update = Instant::now();
-                        *control_flow = winit::event_loop::ControlFlow::WaitUntil(
-                            Instant::now() + UPDATE_FREQUENCY,
-                        );
+            if updates.is_empty() {
+                updates.push(UpdateType::InputOnly);
+            }
+            for update in updates {
+                match update {
+                    UpdateType::InputOnly => {
+                        running = false;
+                        *control_flow = winit::event_loop::ControlFlow::Wait;
                     }
-
-                    running = true;
-                }
-                UpdateType::Pan => {}
-                UpdateType::ScreenCaptureEverything { dir, zoom, dims } => {
-                    if let Err(err) =
-                        screenshot_everything(&mut state, &dir, &prerender, zoom, dims)
-                    {
-                        error!("Couldn't screenshot everything: {}", err);
+                    UpdateType::Game => {
+                        // If we just unpaused, then don't act as if lots of time has passed.
+                        if !running {
+                            last_update = Instant::now();
+                            *control_flow = winit::event_loop::ControlFlow::WaitUntil(
+                                Instant::now() + UPDATE_FREQUENCY,
+                            );
+                        }
+
+                        running = true;
+                    }
+                    UpdateType::Pan => {}
+                    UpdateType::ScreenCaptureEverything { dir, zoom, dims } => {
+                        if let Err(err) =
+                            screenshot_everything(&mut state, &dir, &prerender, zoom, dims)
+                        {
+                            error!("Couldn't screenshot everything: {}", err);
+                        }
+                    }
+                    UpdateType::ScreenCaptureCurrentShot { filename } => {
+                        // Not in screenshot mode; capture exactly what the user sees
+                        state.draw(&prerender, false);
+                        if let Err(err) = prerender
+                            .inner
+                            .screencap(state.canvas.get_window_dims(), filename)
+                        {
+                            error!("Couldn't take a screenshot: {}", err);
+                        }
                     }
                 }
             }
         }
+
+        // If the app is idle, still wake up to notice long presses and gamepad input
+        if *control_flow == winit::event_loop::ControlFlow::Wait {
+            if let Some(deadline) = touches.long_press_deadline() {
+                *control_flow = winit::event_loop::ControlFlow::WaitUntil(deadline);
+            }
+            #[cfg(feature = "gamepad")]
+            if gamepads.any_connected() {
+                *control_flow =
+                    winit::event_loop::ControlFlow::WaitUntil(Instant::now() + UPDATE_FREQUENCY);
+            }
+        }
     });
 }
@@ -226,6 +226,31 @@ impl Style {
     }
 }
 
+impl Style {
+    /// Pure black and white, for players who need maximum contrast
+    pub fn high_contrast() -> Style {
+        let yellow = hex("#FFD700");
+        let mut style = Style::dark_bg();
+        style.panel_bg = Color::BLACK;
+        style.field_bg = Color::BLACK;
+        style.section_bg = Color::BLACK;
+        style.section_outline = (DEFAULT_OUTLINE_THICKNESS, Color::WHITE);
+        style.text_primary_color = Color::WHITE;
+        style.text_secondary_color = Color::WHITE;
+        style.text_hotkey_color = yellow;
+        style.primary_fg = yellow;
+        style.btn_tab = ButtonStyle {
+            fg: Color::WHITE,
+            fg_disabled: yellow,
+            bg: Color::CLEAR,
+            bg_hover: Color::WHITE.alpha(0.2),
+            bg_disabled: Color::BLACK,
+            outline: (DEFAULT_OUTLINE_THICKNESS, Color::WHITE),
+        };
+        style
+    }
+}
+
 // Convenience
 fn hex(x: &str) -> Color {
     Color::hex(x)
@@ -12,9 +12,12 @@ use serde::de::DeserializeOwned;
 use tokio::runtime::Runtime;
 
 use abstutil::Timer;
-use geom::Duration;
+use geom::{Duration, Polygon};
 
-use crate::{Color, EventCtx, GfxCtx, Line, Panel, State, Text, Transition, UpdateType};
+use crate::{
+    Color, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, State, Text, Transition,
+    UpdateType, Widget,
+};
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use native_loader::FileLoader;
@@ -293,6 +296,26 @@ mod wasm_loader {
     }
 }
 
+/// Returned to a `FutureLoader`'s callback when the player cancels loading
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+// wasm futures are not `Send`, since they all ultimately run on the browser's single threaded
+// runloop
+#[cfg(target_arch = "wasm32")]
+type LoadingFuture<A, T> = Pin<Box<dyn Future<Output = Result<Box<dyn Send + FnOnce(&A) -> T>>>>>;
+#[cfg(not(target_arch = "wasm32"))]
+type LoadingFuture<A, T> =
+    Pin<Box<dyn Send + Future<Output = Result<Box<dyn Send + FnOnce(&A) -> T>>>>>;
+
 pub struct FutureLoader<A, T> {
     loading_title: String,
     started: Instant,
@@ -304,13 +327,16 @@ pub struct FutureLoader<A, T> {
     inner_progress_receiver: Option<mpsc::Receiver<String>>,
     last_outer_progress: String,
     last_inner_progress: String,
-
-    // If Runtime is dropped, any active tasks will be canceled, so we retain it here even
-    // though we never access it. It might make more sense for Runtime to live on App if we're
-    // going to be doing more background spawning.
+    /// From 0 to 1, drawn as a progress bar
+    fraction_receiver: Option<mpsc::Receiver<f64>>,
+    last_fraction: Option<f64>,
+    cancellable: bool,
+
+    // If Runtime is dropped, any active tasks will be canceled, so we retain it here. It might
+    // make more sense for Runtime to live on App if we're going to be doing more background
+    // spawning. Cancelling shuts it down early.
     #[cfg(not(target_arch = "wasm32"))]
-    #[allow(dead_code)]
-    runtime: Runtime,
+    runtime: Option<Runtime>,
 }
 
 impl<A, T> FutureLoader<A, T>
@@ -318,59 +344,114 @@ where
     A: 'static,
     T: 'static,
 {
-    #[cfg(target_arch = "wasm32")]
     pub fn new_state(
         ctx: &mut EventCtx,
-        future: Pin<Box<dyn Future<Output = Result<Box<dyn Send + FnOnce(&A) -> T>>>>>,
+        future: LoadingFuture<A, T>,
         outer_progress_receiver: mpsc::Receiver<String>,
         inner_progress_receiver: mpsc::Receiver<String>,
         loading_title: &str,
         on_load: Box<dyn FnOnce(&mut EventCtx, &mut A, Result<T>) -> Transition<A>>,
     ) -> Box<dyn State<A>> {
-        let (tx, receiver) = oneshot::channel();
-        wasm_bindgen_futures::spawn_local(async move {
-            tx.send(future.await).ok().unwrap();
-        });
-        Box::new(FutureLoader {
-            loading_title: loading_title.to_string(),
-            started: Instant::now(),
-            panel: ctx.make_loading_screen(Text::from(loading_title)),
-            receiver,
-            on_load: Some(on_load),
-            outer_progress_receiver: Some(outer_progress_receiver),
-            inner_progress_receiver: Some(inner_progress_receiver),
-            last_outer_progress: String::new(),
-            last_inner_progress: String::new(),
-        })
+        let mut loader = FutureLoader::new(ctx, future, loading_title, on_load);
+        loader.outer_progress_receiver = Some(outer_progress_receiver);
+        loader.inner_progress_receiver = Some(inner_progress_receiver);
+        Box::new(loader)
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    pub fn new_state(
+    /// Like `new_state`, but also draws a progress bar and lets the player cancel. If they do,
+    /// `on_load` gets a `Cancelled` error, and the future is dropped the next time it yields.
+    pub fn new_cancellable_state(
         ctx: &mut EventCtx,
-        future: Pin<Box<dyn Send + Future<Output = Result<Box<dyn Send + FnOnce(&A) -> T>>>>>,
-        outer_progress_receiver: mpsc::Receiver<String>,
-        inner_progress_receiver: mpsc::Receiver<String>,
+        future: LoadingFuture<A, T>,
+        progress_receiver: mpsc::Receiver<String>,
+        fraction_receiver: mpsc::Receiver<f64>,
         loading_title: &str,
         on_load: Box<dyn FnOnce(&mut EventCtx, &mut A, Result<T>) -> Transition<A>>,
     ) -> Box<dyn State<A>> {
-        let runtime = Runtime::new().unwrap();
+        let mut loader = FutureLoader::new(ctx, future, loading_title, on_load);
+        loader.outer_progress_receiver = Some(progress_receiver);
+        loader.fraction_receiver = Some(fraction_receiver);
+        loader.cancellable = true;
+        Box::new(loader)
+    }
+
+    fn new(
+        ctx: &mut EventCtx,
+        future: LoadingFuture<A, T>,
+        loading_title: &str,
+        on_load: Box<dyn FnOnce(&mut EventCtx, &mut A, Result<T>) -> Transition<A>>,
+    ) -> FutureLoader<A, T> {
         let (tx, receiver) = oneshot::channel();
-        runtime.spawn(async move {
-            tx.send(future.await).ok().unwrap();
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            // If loading was cancelled, nobody's listening anymore
+            let _ = tx.send(future.await);
         });
+        #[cfg(not(target_arch = "wasm32"))]
+        let runtime = {
+            let runtime = Runtime::new().unwrap();
+            runtime.spawn(async move {
+                // If loading was cancelled, nobody's listening anymore
+                let _ = tx.send(future.await);
+            });
+            runtime
+        };
 
-        Box::new(FutureLoader {
+        FutureLoader {
             loading_title: loading_title.to_string(),
             started: Instant::now(),
             panel: ctx.make_loading_screen(Text::from(loading_title)),
             receiver,
             on_load: Some(on_load),
-            runtime,
-            outer_progress_receiver: Some(outer_progress_receiver),
-            inner_progress_receiver: Some(inner_progress_receiver),
+            #[cfg(not(target_arch = "wasm32"))]
+            runtime: Some(runtime),
+            outer_progress_receiver: None,
+            inner_progress_receiver: None,
             last_outer_progress: String::new(),
             last_inner_progress: String::new(),
-        })
+            fraction_receiver: None,
+            last_fraction: None,
+            cancellable: false,
+        }
+    }
+
+    fn make_panel(&self, ctx: &mut EventCtx) -> Panel {
+        let txt = Text::from_multiline(vec![
+            Line(&self.loading_title),
+            Line(format!(
+                "Time spent: {}",
+                Duration::realtime_elapsed(self.started)
+            )),
+            Line(&self.last_outer_progress),
+            Line(&self.last_inner_progress),
+        ]);
+        if !self.cancellable {
+            return ctx.make_loading_screen(txt);
+        }
+
+        let width = 400.0;
+        let height = 20.0;
+        let mut bar = GeomBatch::new();
+        bar.push(Color::grey(0.3), Polygon::rectangle(width, height));
+        if let Some(pct) = self.last_fraction {
+            if pct > 0.0 {
+                bar.push(
+                    Color::hex("#F4DA22"),
+                    Polygon::rectangle(pct.min(1.0) * width, height),
+                );
+            }
+        }
+        Panel::new_builder(Widget::col(vec![
+            txt.into_widget(ctx),
+            bar.into_widget(ctx),
+            ctx.style()
+                .btn_outline
+                .text("Cancel")
+                .hotkey(Key::Escape)
+                .build_def(ctx)
+                .centered_horiz(),
+        ]))
+        .build(ctx)
     }
 }
 
@@ -387,6 +468,17 @@ where
                 on_load(ctx, app, Err(anyhow!("channel canceled")))
             }
             Ok(None) => {
+                if self.cancellable {
+                    if let Outcome::Clicked(x) = self.panel.event(ctx) {
+                        assert_eq!(x, "Cancel");
+                        // Don't wait for the future to finish; it might be blocked on a read
+                        #[cfg(not(target_arch = "wasm32"))]
+                        self.runtime.take().unwrap().shutdown_background();
+                        let on_load = self.on_load.take().unwrap();
+                        return on_load(ctx, app, Err(Cancelled.into()));
+                    }
+                }
+
                 if let Some(ref mut rx) = self.outer_progress_receiver {
                     // Read all of the progress that's happened
                     loop {
@@ -423,15 +515,13 @@ where
                     }
                 }
 
-                self.panel = ctx.make_loading_screen(Text::from_multiline(vec![
-                    Line(&self.loading_title),
-                    Line(format!(
-                        "Time spent: {}",
-                        Duration::realtime_elapsed(self.started)
-                    )),
-                    Line(&self.last_outer_progress),
-                    Line(&self.last_inner_progress),
-                ]));
+                if let Some(ref mut rx) = self.fraction_receiver {
+                    while let Ok(Some(pct)) = rx.try_next() {
+                        self.last_fraction = Some(pct);
+                    }
+                }
+
+                self.panel = self.make_panel(ctx);
 
                 // Until the response is received, just ask winit to regularly call event(), so we
                 // can keep polling the channel.
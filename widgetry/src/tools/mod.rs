@@ -13,7 +13,7 @@ use anyhow::Result;
 pub use choose_something::ChooseSomething;
 pub use colors::{ColorLegend, ColorScale, DivergingScale};
 pub use lasso::{Lasso, PolyLineLasso};
-pub use load::{FileLoader, FutureLoader, RawBytes};
+pub use load::{Cancelled, FileLoader, FutureLoader, RawBytes};
 pub use popup::PopupMsg;
 pub use prompt_input::PromptInput;
 pub use url::URLManager;
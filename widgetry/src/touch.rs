@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+
+use instant::Instant;
+use winit::event::{Touch, TouchPhase};
+
+use crate::{Event, ScreenPt};
+
+// Holding one finger still for this long acts like a right click
+const LONG_PRESS_DURATION: instant::Duration = instant::Duration::from_millis(500);
+// Touches are less precise than a mouse, so a finger has to move this far (in logical pixels)
+// before it starts dragging instead of tapping
+const TAP_THRESHOLD: f64 = 10.0;
+
+/// Translates touchscreen gestures into the events everything else already understands. One
+/// finger acts like the left mouse button: tapping clicks, and dragging moves the cursor with the
+/// button held. Two fingers pan and pinch to zoom. Holding one finger still opens a context menu,
+/// like a right click.
+#[derive(Default)]
+pub(crate) struct TouchState {
+    fingers: BTreeMap<u64, ScreenPt>,
+    gesture: Gesture,
+}
+
+#[derive(Default)]
+enum Gesture {
+    #[default]
+    Idle,
+    /// One finger is down, but it's not clear yet if this is a tap, drag, or long press
+    Pending { started: Instant, at: ScreenPt },
+    /// One finger is dragging with the left mouse button held
+    Dragging,
+    /// A long press was already sent as a right click
+    LongPressed,
+    /// Two fingers are down
+    Pinching { center: ScreenPt, distance: f64 },
+    /// A gesture with more than one finger happened. Ignore everything until all fingers lift.
+    Finished,
+}
+
+impl TouchState {
+    pub fn handle(
+        &mut self,
+        touch: Touch,
+        scale_factor: f64,
+        previous_click: Instant,
+    ) -> Vec<Event> {
+        let pt: ScreenPt = touch.location.to_logical(scale_factor).into();
+        match touch.phase {
+            TouchPhase::Started => {
+                self.fingers.insert(touch.id, pt);
+                self.finger_down(pt)
+            }
+            TouchPhase::Moved => {
+                if let Some(finger) = self.fingers.get_mut(&touch.id) {
+                    *finger = pt;
+                }
+                self.finger_moved(pt)
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if self.fingers.remove(&touch.id).is_none() {
+                    return Vec::new();
+                }
+                self.finger_up(touch.phase == TouchPhase::Cancelled, previous_click)
+            }
+        }
+    }
+
+    /// If a finger might turn into a long press, the event loop has to wake up at this time.
+    pub fn long_press_deadline(&self) -> Option<Instant> {
+        if let Gesture::Pending { started, .. } = self.gesture {
+            Some(started + LONG_PRESS_DURATION)
+        } else {
+            None
+        }
+    }
+
+    /// Call regularly to detect long presses.
+    pub fn check_long_press(&mut self) -> Vec<Event> {
+        if let Gesture::Pending { started, .. } = self.gesture {
+            if started.elapsed() >= LONG_PRESS_DURATION {
+                self.gesture = Gesture::LongPressed;
+                return vec![Event::RightMouseButtonDown, Event::RightMouseButtonUp];
+            }
+        }
+        Vec::new()
+    }
+
+    fn finger_down(&mut self, pt: ScreenPt) -> Vec<Event> {
+        match self.fingers.len() {
+            1 => {
+                self.gesture = Gesture::Pending {
+                    started: Instant::now(),
+                    at: pt,
+                };
+                vec![Event::MouseMovedTo(pt)]
+            }
+            2 => {
+                let mut events = Vec::new();
+                // A second finger cancels whatever the first was doing
+                if let Gesture::Dragging = self.gesture {
+                    events.push(Event::LeftMouseButtonUp {
+                        is_double_click: false,
+                    });
+                }
+                if matches!(
+                    self.gesture,
+                    Gesture::Pending { .. } | Gesture::Dragging | Gesture::LongPressed
+                ) {
+                    let (center, distance) = self.two_fingers();
+                    self.gesture = Gesture::Pinching { center, distance };
+                    events.push(Event::MouseMovedTo(center));
+                }
+                events
+            }
+            _ => {
+                self.gesture = Gesture::Finished;
+                Vec::new()
+            }
+        }
+    }
+
+    fn finger_moved(&mut self, pt: ScreenPt) -> Vec<Event> {
+        match self.gesture {
+            Gesture::Pending { at, .. } => {
+                if (pt.x - at.x).hypot(pt.y - at.y) < TAP_THRESHOLD {
+                    return Vec::new();
+                }
+                self.gesture = Gesture::Dragging;
+                // Press where the finger first touched, so the drag starts in the right place
+                vec![Event::LeftMouseButtonDown, Event::MouseMovedTo(pt)]
+            }
+            Gesture::Dragging => vec![Event::MouseMovedTo(pt)],
+            Gesture::Pinching {
+                center: old_center,
+                distance: old_distance,
+            } => {
+                let (center, distance) = self.two_fingers();
+                self.gesture = Gesture::Pinching { center, distance };
+                let mut events = vec![
+                    Event::MouseMovedTo(center),
+                    Event::Pan(center.x - old_center.x, center.y - old_center.y),
+                ];
+                if old_distance > 0.0 && distance > 0.0 {
+                    events.push(Event::Pinch(distance / old_distance, center));
+                }
+                events
+            }
+            Gesture::Idle | Gesture::LongPressed | Gesture::Finished => Vec::new(),
+        }
+    }
+
+    fn finger_up(&mut self, cancelled: bool, previous_click: Instant) -> Vec<Event> {
+        let mut events = Vec::new();
+        match self.gesture {
+            Gesture::Pending { .. } => {
+                if !cancelled {
+                    // Like the mouse, a double tap is the second tap arriving soon after the
+                    // first
+                    let is_double_click =
+                        previous_click.elapsed() <= crate::event::MAX_DOUBLE_CLICK_DURATION;
+                    events.push(Event::LeftMouseButtonDown);
+                    events.push(Event::LeftMouseButtonUp { is_double_click });
+                }
+            }
+            Gesture::Dragging => {
+                events.push(Event::LeftMouseButtonUp {
+                    is_double_click: false,
+                });
+            }
+            Gesture::Idle | Gesture::LongPressed | Gesture::Pinching { .. } | Gesture::Finished => {
+            }
+        }
+        // Don't let a remaining finger start dragging
+        self.gesture = if self.fingers.is_empty() {
+            Gesture::Idle
+        } else {
+            Gesture::Finished
+        };
+        events
+    }
+
+    /// The midpoint and distance between the first two fingers
+    fn two_fingers(&self) -> (ScreenPt, f64) {
+        let mut iter = self.fingers.values();
+        let pt1 = *iter.next().unwrap();
+        let pt2 = *iter.next().unwrap();
+        (
+            ScreenPt::new((pt1.x + pt2.x) / 2.0, (pt1.y + pt2.y) / 2.0),
+            (pt1.x - pt2.x).hypot(pt1.y - pt2.y),
+        )
+    }
+}
@@ -38,6 +38,7 @@ pub mod spinner;
 pub mod stash;
 pub mod table;
 pub mod tabs;
+pub mod text_area;
 pub mod text_box;
 pub mod toggle;
 
@@ -2,6 +2,7 @@ use std::cell::RefCell;
 use std::collections::HashSet;
 use std::rc::Rc;
 
+use anyhow::Result;
 use taffy::geometry::Size;
 use taffy::layout::AvailableSpace;
 use taffy::node::{Node, Taffy};
@@ -14,8 +15,8 @@ use crate::widgets::spinner::SpinnerValue;
 use crate::widgets::Container;
 use crate::{
     Autocomplete, Button, Color, Dropdown, EventCtx, GfxCtx, HorizontalAlignment, Menu, Outcome,
-    PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, Stash, TextBox,
-    Toggle, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
+    PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, Stash, TextArea,
+    TextBox, Toggle, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Panel {
@@ -421,6 +422,14 @@ impl Panel {
     pub fn text_box(&self, name: &str) -> String {
         self.find::<TextBox>(name).get_line()
     }
+    /// For a `TextBox` with a validator, returns the input only if it's valid.
+    pub fn validated_text_box(&self, name: &str) -> Result<String> {
+        self.find::<TextBox>(name).get_valid_line()
+    }
+
+    pub fn text_area(&self, name: &str) -> String {
+        self.find::<TextArea>(name).get_text()
+    }
 
     pub fn spinner<T: 'static + SpinnerValue>(&self, name: &str) -> T {
         self.find::<Spinner<T>>(name).current
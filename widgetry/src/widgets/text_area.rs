@@ -0,0 +1,255 @@
+use geom::{Distance, Polygon};
+
+use crate::{
+    EdgeInsets, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, ScreenDims, ScreenPt,
+    ScreenRectangle, Style, Text, Widget, WidgetImpl, WidgetOutput,
+};
+
+/// Multi-line text entry. Only a fixed number of rows are shown at once; the view scrolls to follow
+/// the cursor, or with the mouse wheel while hovering. Unlike `TextBox`, this only has focus while
+/// the mouse is over it, since Enter inserts a new line instead of submitting a form.
+pub struct TextArea {
+    lines: Vec<String>,
+    label: String,
+    // (row, column)
+    cursor: (usize, usize),
+    // The first visible row
+    scroll: usize,
+    visible_rows: usize,
+    has_focus: bool,
+    padding: EdgeInsets,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl TextArea {
+    /// `width` is measured in characters.
+    pub fn widget<I: Into<String>>(
+        ctx: &EventCtx,
+        label: I,
+        prefilled: String,
+        width: usize,
+        visible_rows: usize,
+    ) -> Widget {
+        let label = label.into();
+        Widget::new(Box::new(TextArea::new(
+            ctx,
+            label.clone(),
+            prefilled,
+            width,
+            visible_rows,
+        )))
+        .named(label)
+    }
+
+    fn new(
+        ctx: &EventCtx,
+        label: String,
+        prefilled: String,
+        width: usize,
+        visible_rows: usize,
+    ) -> TextArea {
+        let padding = EdgeInsets {
+            top: 6.0,
+            left: 8.0,
+            bottom: 8.0,
+            right: 8.0,
+        };
+        let max_char_width = 25.0;
+        let visible_rows = visible_rows.max(1);
+        let lines: Vec<String> = prefilled.split('\n').map(|l| l.to_string()).collect();
+        let cursor = (lines.len() - 1, lines.last().unwrap().len());
+        let mut area = TextArea {
+            lines,
+            label,
+            cursor,
+            scroll: 0,
+            visible_rows,
+            has_focus: false,
+            padding,
+            top_left: ScreenPt::new(0.0, 0.0),
+            dims: ScreenDims::new(
+                (width as f64) * max_char_width + padding.left + padding.right,
+                (visible_rows as f64) * ctx.default_line_height() + padding.top + padding.bottom,
+            ),
+        };
+        area.scroll_to_cursor();
+        area
+    }
+
+    /// All of the lines, joined by newlines
+    pub fn get_text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    fn scroll_to_cursor(&mut self) {
+        if self.cursor.0 < self.scroll {
+            self.scroll = self.cursor.0;
+        } else if self.cursor.0 >= self.scroll + self.visible_rows {
+            self.scroll = self.cursor.0 + 1 - self.visible_rows;
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.visible_rows)
+    }
+
+    fn calculate_text(&self, style: &Style) -> Text {
+        let mut txt = Text::new();
+        for (row, line) in self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(self.visible_rows)
+        {
+            if row != self.cursor.0 {
+                // An empty line would otherwise collapse
+                txt.add_line(Line(if line.is_empty() { " " } else { line }));
+                continue;
+            }
+            // TODO Same awful cursor as TextBox
+            let col = self.cursor.1;
+            txt.add_line(Line(&line[0..col]));
+            txt.append(Line("|").fg(style.text_primary_color));
+            if col < line.len() {
+                txt.append(Line(&line[col..]));
+            }
+        }
+        txt
+    }
+}
+
+impl WidgetImpl for TextArea {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
+        if ctx.redo_mouseover() {
+            if let Some(pt) = ctx.canvas.get_cursor_in_screen_space() {
+                self.has_focus = ScreenRectangle::top_left(self.top_left, self.dims).contains(pt);
+            } else {
+                self.has_focus = false;
+            }
+        }
+        if !self.has_focus {
+            return;
+        }
+
+        if let Some((_, dy)) = ctx.input.get_mouse_scroll() {
+            if dy > 0.0 {
+                self.scroll = self.scroll.saturating_sub(1);
+            } else if dy < 0.0 {
+                self.scroll = (self.scroll + 1).min(self.max_scroll());
+            }
+            return;
+        }
+
+        if let Some(key) = ctx.input.any_pressed() {
+            let (row, col) = self.cursor;
+            match key {
+                Key::LeftArrow => {
+                    if col > 0 {
+                        self.cursor.1 -= 1;
+                    } else if row > 0 {
+                        self.cursor = (row - 1, self.lines[row - 1].len());
+                    }
+                }
+                Key::RightArrow => {
+                    if col < self.lines[row].len() {
+                        self.cursor.1 += 1;
+                    } else if row + 1 < self.lines.len() {
+                        self.cursor = (row + 1, 0);
+                    }
+                }
+                Key::UpArrow => {
+                    if row > 0 {
+                        self.cursor = (row - 1, col.min(self.lines[row - 1].len()));
+                    }
+                }
+                Key::DownArrow => {
+                    if row + 1 < self.lines.len() {
+                        self.cursor = (row + 1, col.min(self.lines[row + 1].len()));
+                    }
+                }
+                Key::Enter => {
+                    output.outcome = Outcome::Changed(self.label.clone());
+                    let rest = self.lines[row].split_off(col);
+                    self.lines.insert(row + 1, rest);
+                    self.cursor = (row + 1, 0);
+                }
+                Key::Backspace => {
+                    if col > 0 {
+                        output.outcome = Outcome::Changed(self.label.clone());
+                        self.lines[row].remove(col - 1);
+                        self.cursor.1 -= 1;
+                    } else if row > 0 {
+                        // Join with the previous line
+                        output.outcome = Outcome::Changed(self.label.clone());
+                        let line = self.lines.remove(row);
+                        let prev_len = self.lines[row - 1].len();
+                        self.lines[row - 1].push_str(&line);
+                        self.cursor = (row - 1, prev_len);
+                    }
+                }
+                _ => {
+                    if let Some(c) = key.to_char(ctx.is_key_down(Key::LeftShift)) {
+                        output.outcome = Outcome::Changed(self.label.clone());
+                        self.lines[row].insert(col, c);
+                        self.cursor.1 += 1;
+                    } else {
+                        ctx.input.unconsume_event();
+                    }
+                }
+            };
+            self.scroll_to_cursor();
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx) {
+        // TODO Cache
+        let mut batch = GeomBatch::from(vec![(
+            if self.has_focus {
+                g.style().field_bg
+            } else {
+                g.style().field_bg.dull(0.5)
+            },
+            Polygon::rounded_rectangle(self.dims.width, self.dims.height, 2.0),
+        )]);
+
+        let outline_style = g.style().btn_outline.outline;
+        batch.push(
+            outline_style.1,
+            Polygon::rounded_rectangle(self.dims.width, self.dims.height, 2.0)
+                .to_outline(Distance::meters(outline_style.0)),
+        );
+
+        // When there's more text than fits, show where the visible rows are
+        if self.lines.len() > self.visible_rows {
+            let track_height = self.dims.height - self.padding.top - self.padding.bottom;
+            let thumb_height =
+                track_height * (self.visible_rows as f64) / (self.lines.len() as f64);
+            let thumb_y =
+                self.padding.top + track_height * (self.scroll as f64) / (self.lines.len() as f64);
+            batch.push(
+                outline_style.1,
+                Polygon::rounded_rectangle(3.0, thumb_height, 1.5)
+                    .translate(self.dims.width - self.padding.right / 2.0 - 1.5, thumb_y),
+            );
+        }
+
+        batch.append(
+            self.calculate_text(g.style())
+                .render_autocropped(g)
+                .translate(self.padding.left, self.padding.top),
+        );
+        let draw = g.upload(batch);
+        g.redraw_at(self.top_left, &draw);
+    }
+}
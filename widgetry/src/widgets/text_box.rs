@@ -1,3 +1,6 @@
+use anyhow::Result;
+use regex::Regex;
+
 use geom::{Distance, Polygon};
 
 use crate::{
@@ -15,9 +18,69 @@ pub struct TextBox {
     has_focus: bool,
     autofocus: bool,
     padding: EdgeInsets,
+    validator: Option<Validator>,
+    // Why the current line is invalid
+    error: Option<String>,
 
     top_left: ScreenPt,
+    // Includes space for the error below the field, if there's a validator
     dims: ScreenDims,
+    field_height: f64,
+}
+
+/// Checks what's typed into a `TextBox`. Invalid input isn't blocked; instead the problem is shown
+/// below the field as the user types.
+pub enum Validator {
+    /// A number between `min` and `max`, inclusive
+    NumberRange { min: f64, max: f64 },
+    /// A whole number between `min` and `max`, inclusive
+    IntegerRange { min: isize, max: isize },
+    /// The entire input must match the regular expression. The description says what's expected,
+    /// like "a hex color".
+    Regex { regex: Regex, description: String },
+}
+
+impl Validator {
+    /// Panics if the pattern is invalid.
+    pub fn regex<I: Into<String>>(pattern: &str, description: I) -> Validator {
+        Validator::Regex {
+            regex: Regex::new(pattern).unwrap(),
+            description: description.into(),
+        }
+    }
+
+    pub fn validate(&self, input: &str) -> Result<()> {
+        match self {
+            Validator::NumberRange { min, max } => {
+                let x: f64 = input
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("Must be a number"))?;
+                if x < *min || x > *max {
+                    bail!("Must be between {} and {}", min, max);
+                }
+            }
+            Validator::IntegerRange { min, max } => {
+                let x: isize = input
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("Must be a whole number"))?;
+                if x < *min || x > *max {
+                    bail!("Must be between {} and {}", min, max);
+                }
+            }
+            Validator::Regex { regex, description } => {
+                if !regex
+                    .find(input)
+                    .map(|m| m.start() == 0 && m.end() == input.len())
+                    .unwrap_or(false)
+                {
+                    bail!("Must be {}", description);
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl TextBox {
@@ -45,6 +108,26 @@ impl TextBox {
         .named(label)
     }
 
+    /// Like `widget`, but `validator` checks the input as the user types, showing any problem
+    /// below the field. Use `Panel::validated_text_box` to read the input.
+    pub fn validated_widget<I: Into<String>>(
+        ctx: &EventCtx,
+        label: I,
+        prefilled: String,
+        validator: Validator,
+        max_chars: usize,
+    ) -> Widget {
+        let label = label.into();
+        let mut text_box = TextBox::new(ctx, label.clone(), max_chars, prefilled, false);
+        text_box.error = validator
+            .validate(&text_box.line)
+            .err()
+            .map(|e| e.to_string());
+        text_box.validator = Some(validator);
+        text_box.dims.height += ctx.default_line_height();
+        Widget::new(Box::new(text_box)).named(label)
+    }
+
     pub(crate) fn new(
         ctx: &EventCtx,
         label: String,
@@ -59,6 +142,7 @@ impl TextBox {
             right: 8.0,
         };
         let max_char_width = 25.0;
+        let field_height = ctx.default_line_height() + (padding.top + padding.bottom) as f64;
         Self {
             label,
             cursor_x: prefilled.len(),
@@ -66,11 +150,14 @@ impl TextBox {
             has_focus: false,
             autofocus,
             padding,
+            validator: None,
+            error: None,
             top_left: ScreenPt::new(0.0, 0.0),
             dims: ScreenDims::new(
                 (max_chars as f64) * max_char_width + (padding.left + padding.right) as f64,
-                ctx.default_line_height() + (padding.top + padding.bottom) as f64,
+                field_height,
             ),
+            field_height,
         }
     }
 
@@ -92,6 +179,20 @@ impl TextBox {
     pub fn get_line(&self) -> String {
         self.line.clone()
     }
+
+    /// Returns the line if it passes validation. Text boxes without a validator always do.
+    pub fn get_valid_line(&self) -> Result<String> {
+        if let Some(ref err) = self.error {
+            bail!("{}", err);
+        }
+        Ok(self.line.clone())
+    }
+
+    fn revalidate(&mut self) {
+        if let Some(ref validator) = self.validator {
+            self.error = validator.validate(&self.line).err().map(|e| e.to_string());
+        }
+    }
 }
 
 impl WidgetImpl for TextBox {
@@ -142,6 +243,9 @@ impl WidgetImpl for TextBox {
                     }
                 }
             };
+            if let Outcome::Changed(_) = output.outcome {
+                self.revalidate();
+            }
         }
     }
 
@@ -153,16 +257,28 @@ impl WidgetImpl for TextBox {
             } else {
                 g.style().field_bg.dull(0.5)
             },
-            Polygon::rounded_rectangle(self.dims.width, self.dims.height, 2.0),
+            Polygon::rounded_rectangle(self.dims.width, self.field_height, 2.0),
         )]);
 
         let outline_style = g.style().btn_outline.outline;
         batch.push(
-            outline_style.1,
-            Polygon::rounded_rectangle(self.dims.width, self.dims.height, 2.0)
+            if self.error.is_some() {
+                g.style().text_destructive_color
+            } else {
+                outline_style.1
+            },
+            Polygon::rounded_rectangle(self.dims.width, self.field_height, 2.0)
                 .to_outline(Distance::meters(outline_style.0)),
         );
 
+        if let Some(ref err) = self.error {
+            batch.append(
+                Text::from(Line(err).small().fg(g.style().text_destructive_color))
+                    .render_autocropped(g)
+                    .translate(self.padding.left, self.field_height + 2.0),
+            );
+        }
+
         batch.append(
             self.calculate_text(g.style())
                 .render_autocropped(g)